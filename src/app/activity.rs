@@ -0,0 +1,192 @@
+//! Suspending background work while the user is away
+//!
+//! Left open all day, the interface would keep redrawing animations ten times
+//! a second and the workspace watcher would keep handling the file events of
+//! every build. The activity tracker decides when the user is away: the
+//! terminal lost focus or nothing was typed for a while, and no answer is on
+//! its way. Subsystems registered with it are suspended then, and resumed on
+//! the next key or when the terminal gets focus back.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Time without input after which background work is suspended
+pub const DEFAULT_IDLE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// A subsystem whose background work can be paused
+pub trait Suspend: Send + Sync {
+    /// Stop background work until resumed
+    fn suspend(&self);
+
+    /// Start background work again, catching up on what was missed
+    fn resume(&self);
+}
+
+#[derive(Debug)]
+struct State {
+    last_input: Instant,
+    focused: bool,
+    busy: bool,
+    suspended: bool,
+}
+
+/// Tracks whether the user is around and suspends subsystems when not
+pub struct ActivityTracker {
+    /// Time without input before suspending, none to only suspend on lost focus
+    idle_after: Option<Duration>,
+    state: Mutex<State>,
+    subsystems: Mutex<Vec<Arc<dyn Suspend>>>,
+}
+
+impl ActivityTracker {
+    pub fn new(idle_after: Option<Duration>) -> Self {
+        Self {
+            idle_after,
+            state: Mutex::new(State { last_input: Instant::now(), focused: true, busy: false, suspended: false }),
+            subsystems: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Suspend and resume `subsystem` with the rest
+    pub fn register(&self, subsystem: Arc<dyn Suspend>) {
+        if self.is_suspended() {
+            subsystem.suspend();
+        }
+        self.subsystems.lock().unwrap_or_else(|e| e.into_inner()).push(subsystem);
+    }
+
+    /// Note a key, paste or click
+    pub fn record_input(&self, now: Instant) {
+        self.change(now, |state| {
+            state.last_input = now;
+            state.focused = true;
+        });
+    }
+
+    /// Note that the terminal gained or lost focus
+    pub fn set_focused(&self, focused: bool, now: Instant) {
+        self.change(now, |state| {
+            if focused {
+                state.last_input = now;
+            }
+            state.focused = focused;
+        });
+    }
+
+    /// Note that an answer is or is no longer on its way
+    ///
+    /// Nothing is suspended while busy, and the idle time counts from when
+    /// the answer arrived.
+    pub fn set_busy(&self, busy: bool, now: Instant) {
+        self.change(now, |state| {
+            if state.busy && !busy {
+                state.last_input = now;
+            }
+            state.busy = busy;
+        });
+    }
+
+    /// Whether the terminal has focus, as far as it reports
+    pub fn is_focused(&self) -> bool {
+        self.lock().focused
+    }
+
+    /// Whether background work is suspended
+    pub fn is_suspended(&self) -> bool {
+        self.lock().suspended
+    }
+
+    /// Suspend or resume the subsystems to match the state at `now`
+    ///
+    /// Returns whether they are suspended.
+    pub fn update(&self, now: Instant) -> bool {
+        self.change(now, |_| {})
+    }
+
+    fn change(&self, now: Instant, apply: impl FnOnce(&mut State)) -> bool {
+        let suspend = {
+            let mut state = self.lock();
+            apply(&mut state);
+            let away = !state.focused
+                || self.idle_after.is_some_and(|idle_after| now.saturating_duration_since(state.last_input) >= idle_after);
+            let suspend = away && !state.busy;
+            if suspend == state.suspended {
+                return suspend;
+            }
+            state.suspended = suspend;
+            suspend
+        };
+
+        debug!("{} background work", if suspend { "Suspending" } else { "Resuming" });
+        for subsystem in self.subsystems.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            if suspend {
+                subsystem.suspend();
+            } else {
+                subsystem.resume();
+            }
+        }
+        suspend
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_IDLE_AFTER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    /// Counts suspensions minus resumptions
+    #[derive(Default)]
+    struct Depth(AtomicI32);
+
+    impl Suspend for Depth {
+        fn suspend(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn resume(&self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_subsystems_sleep_while_the_user_is_away() {
+        let idle_after = Duration::from_secs(60);
+        let tracker = ActivityTracker::new(Some(idle_after));
+        let depth = Arc::new(Depth::default());
+        tracker.register(depth.clone());
+        let start = Instant::now();
+
+        // Lost focus suspends at once, and focus resumes
+        assert!(!tracker.update(start));
+        tracker.set_focused(false, start);
+        assert!(tracker.is_suspended());
+        assert_eq!(depth.0.load(Ordering::SeqCst), 1);
+        tracker.set_focused(true, start);
+        assert_eq!(depth.0.load(Ordering::SeqCst), 0);
+
+        // A streaming answer keeps everything awake
+        tracker.set_busy(true, start);
+        assert!(!tracker.update(start + idle_after * 2));
+        tracker.set_busy(false, start + idle_after * 2);
+        assert!(!tracker.update(start + idle_after * 2 + Duration::from_secs(1)));
+
+        // Then the idle time counts from the answer
+        assert!(tracker.update(start + idle_after * 3));
+        assert!(tracker.update(start + idle_after * 4));
+        assert_eq!(depth.0.load(Ordering::SeqCst), 1);
+        tracker.record_input(start + idle_after * 4);
+        assert!(!tracker.is_suspended());
+        assert_eq!(depth.0.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,21 +1,43 @@
 //! AI agent abstraction for handling conversations
 
 use anyhow::Result;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info};
 
 use crate::{
-    llm::{LlmProvider, ChatRequest, ProviderResponse, Message, MessageRole, tools::ToolManager},
+    llm::{
+        changes::{FileChange, TurnChanges, FILE_CHANGE_KEY},
+        citations::{CitationSource, CitationTracker},
+        images::ImageLimits,
+        resilience,
+        is_context_limit, LlmProvider, ChatRequest, ProviderEvent, ContentBlock, Deadline, ProviderResponse, Message, MessageRole, TokenUsage, Tool,
+        tools::{ToolManager, IMAGE_METADATA_KEY, TABLE_METADATA_KEY},
+    },
     app::AppEvent,
 };
 
+/// Result of a task an agent worked on to completion
+#[derive(Debug, Clone)]
+pub struct TaskOutcome {
+    /// The model's final answer
+    pub answer: String,
+    /// Model round trips it took
+    pub rounds: usize,
+    pub usage: TokenUsage,
+    /// Files changed while working on the task
+    pub changes: TurnChanges,
+}
+
 /// An AI agent that manages conversations with an LLM provider
 pub struct Agent {
-    provider: Arc<dyn LlmProvider>,
+    /// Replaced when the user switches models mid-session
+    provider: RwLock<Arc<dyn LlmProvider>>,
     tool_manager: Arc<ToolManager>,
     event_tx: mpsc::UnboundedSender<AppEvent>,
     session_id: String,
+    /// Whether answers are shown as they are written
+    streaming: bool,
 }
 
 impl Agent {
@@ -27,33 +49,92 @@ impl Agent {
         session_id: String,
     ) -> Self {
         Self {
-            provider,
+            provider: RwLock::new(provider),
             tool_manager,
             event_tx,
             session_id,
+            streaming: false,
         }
     }
     
+    /// Stream the answers to messages, announcing their text as it arrives
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+    
+    /// Agent with the same provider and tools, working in another session
+    pub fn for_session(&self, session_id: String, event_tx: mpsc::UnboundedSender<AppEvent>) -> Self {
+        Self::new(self.provider(), self.tool_manager.clone(), event_tx, session_id)
+            .with_streaming(self.streaming)
+    }
+    
+    /// Provider the next request goes to
+    pub fn provider(&self) -> Arc<dyn LlmProvider> {
+        self.provider.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// Send the requests that follow to another provider or model
+    pub fn set_provider(&self, provider: Arc<dyn LlmProvider>) {
+        *self.provider.write().unwrap_or_else(|e| e.into_inner()) = provider;
+    }
+    
+    /// Attribute the file edits that follow to a turn of the session, for undoing them
+    pub fn begin_turn(&self, turn: usize) {
+        self.tool_manager.begin_turn(&self.session_id, turn);
+    }
+    
     /// Send a message to the agent and get a response
     pub async fn send_message(
         &self,
         messages: Vec<Message>,
         system_message: Option<String>,
+        deadline: &Deadline,
     ) -> Result<ProviderResponse> {
-        debug!("Agent sending message to provider: {}", self.provider.name());
+        let tools = self.tool_manager.get_tool_definitions();
+        self.chat(messages, system_message, tools, self.streaming, deadline).await
+    }
+    
+    /// Send a message the model has to answer in text, without offering it tools
+    pub async fn send_message_without_tools(
+        &self,
+        messages: Vec<Message>,
+        system_message: Option<String>,
+        deadline: &Deadline,
+    ) -> Result<ProviderResponse> {
+        self.chat(messages, system_message, Vec::new(), false, deadline).await
+    }
+    
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        system_message: Option<String>,
+        tools: Vec<Tool>,
+        stream: bool,
+        deadline: &Deadline,
+    ) -> Result<ProviderResponse> {
+        let provider = self.provider();
+        debug!("Agent sending message to provider: {}", provider.name());
         
         let request = ChatRequest {
             messages,
-            tools: self.tool_manager.get_tool_definitions(),
+            tools,
             system_message,
             max_tokens: None,
             temperature: None,
             top_p: None,
-            stream: false,
+            stream,
             metadata: std::collections::HashMap::new(),
         };
         
-        match self.provider.chat_completion(request).await {
+        let result = if stream {
+            let call = resilience::reporting_to(self.event_tx.clone(), self.receive_stream(&provider, request));
+            deadline.run("provider call", call).await
+        } else {
+            let call = resilience::reporting_to(self.event_tx.clone(), provider.chat_completion(request));
+            deadline.run("provider call", call).await
+        };
+        match result {
             Ok(response) => {
                 info!(
                     "Agent received response from provider: {} tokens",
@@ -68,26 +149,106 @@ impl Agent {
                 
                 Ok(response)
             }
+            // The conversation compacts itself and retries, so this is no error yet
+            Err(e) if is_context_limit(&e) => {
+                info!("Request exceeds the context of {}: {}", provider.model(), e);
+                Err(e)
+            }
             Err(e) => {
                 error!("Agent error: {}", e);
                 
                 // Send error event
                 let _ = self.event_tx.send(AppEvent::Error {
                     error: e.to_string(),
+                    recovery: None,
                 });
                 
-                Err(e.into())
+                Err(e)
             }
         }
     }
     
+    /// Receive an answer as a stream, announcing its text as it arrives
+    async fn receive_stream(&self, provider: &Arc<dyn LlmProvider>, request: ChatRequest) -> Result<ProviderResponse> {
+        use futures::StreamExt;
+        
+        let mut stream = provider.chat_completion_stream(request).await?;
+        let message_id = uuid::Uuid::new_v4().to_string();
+        let _ = self.event_tx.send(AppEvent::StreamStarted {
+            session_id: self.session_id.clone(),
+            message_id: message_id.clone(),
+        });
+        
+        // Providers that send no final response get one assembled from the deltas
+        let mut assembled = ProviderResponse {
+            content: String::new(),
+            tool_calls: Vec::new(),
+            usage: TokenUsage::default(),
+            finish_reason: None,
+            metadata: std::collections::HashMap::new(),
+        };
+        let mut arguments: Vec<String> = Vec::new();
+        let mut done = None;
+        let mut failure = None;
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(ProviderEvent::ContentDelta { delta }) => {
+                    assembled.content.push_str(&delta);
+                    let _ = self.event_tx.send(AppEvent::StreamChunk {
+                        session_id: self.session_id.clone(),
+                        message_id: message_id.clone(),
+                        chunk: delta,
+                    });
+                }
+                Ok(ProviderEvent::ToolUseStart { tool_call }) => {
+                    arguments.push(match &tool_call.arguments {
+                        serde_json::Value::String(fragment) => fragment.clone(),
+                        complete => complete.to_string(),
+                    });
+                    assembled.tool_calls.push(tool_call);
+                }
+                Ok(ProviderEvent::ToolUseDelta { delta }) => {
+                    if let Some(fragments) = arguments.last_mut() {
+                        fragments.push_str(&delta);
+                    }
+                }
+                Ok(ProviderEvent::Done { response }) => {
+                    done = Some(response);
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        
+        let _ = self.event_tx.send(AppEvent::StreamEnded {
+            session_id: self.session_id.clone(),
+            message_id,
+        });
+        if let Some(e) = failure {
+            return Err(e.into());
+        }
+        
+        Ok(done.unwrap_or_else(|| {
+            for (tool_call, fragments) in assembled.tool_calls.iter_mut().zip(arguments) {
+                tool_call.arguments = serde_json::from_str(&fragments).unwrap_or_else(|_| serde_json::json!({}));
+            }
+            assembled
+        }))
+    }
+    
     /// Send a message and stream the response
     pub async fn send_message_stream(
         &self,
         messages: Vec<Message>,
         system_message: Option<String>,
+        deadline: &Deadline,
     ) -> Result<mpsc::UnboundedReceiver<String>> {
-        debug!("Agent sending streaming message to provider: {}", self.provider.name());
+        let provider = self.provider();
+        debug!("Agent sending streaming message to provider: {}", provider.name());
         
         let request = ChatRequest {
             messages,
@@ -101,13 +262,15 @@ impl Agent {
         };
         
         let (tx, rx) = mpsc::unbounded_channel();
-        let provider = self.provider.clone();
         let event_tx = self.event_tx.clone();
         let session_id = self.session_id.clone();
         let message_id = uuid::Uuid::new_v4().to_string();
+        let deadline = *deadline;
         
         tokio::spawn(async move {
-            match provider.chat_completion_stream(request).await {
+            let call = resilience::reporting_to(event_tx.clone(), provider.chat_completion_stream(request));
+            let stream = deadline.run("provider call", call).await;
+            match stream {
                 Ok(mut stream) => {
                     // Send stream started event
                     let _ = event_tx.send(AppEvent::StreamStarted {
@@ -116,7 +279,22 @@ impl Agent {
                     });
                     
                     use futures::StreamExt;
-                    while let Some(event_result) = stream.next().await {
+                    loop {
+                        let next = deadline.run("response stream", async {
+                            Ok::<_, anyhow::Error>(stream.next().await)
+                        }).await;
+                        let event_result = match next {
+                            Ok(Some(event_result)) => event_result,
+                            Ok(None) => break,
+                            Err(e) => {
+                                error!("Stream cancelled: {}", e);
+                                let _ = event_tx.send(AppEvent::Error {
+                                    error: e.to_string(),
+                                    recovery: None,
+                                });
+                                break;
+                            }
+                        };
                         match event_result {
                             Ok(event) => {
                                 match event {
@@ -141,6 +319,7 @@ impl Agent {
                                 error!("Stream error: {}", e);
                                 let _ = event_tx.send(AppEvent::Error {
                                     error: e.to_string(),
+                                    recovery: None,
                                 });
                                 break;
                             }
@@ -157,6 +336,7 @@ impl Agent {
                     error!("Agent streaming error: {}", e);
                     let _ = event_tx.send(AppEvent::Error {
                         error: e.to_string(),
+                        recovery: None,
                     });
                 }
             }
@@ -166,36 +346,87 @@ impl Agent {
     }
     
     /// Handle tool calls from LLM response
-    pub async fn handle_tool_calls(&self, tool_calls: Vec<crate::llm::types::ToolCall>) -> Result<Vec<Message>> {
+    pub async fn handle_tool_calls(
+        &self,
+        tool_calls: Vec<crate::llm::types::ToolCall>,
+        deadline: &Deadline,
+        citations: &mut CitationTracker,
+        changes: &mut TurnChanges,
+    ) -> Result<Vec<Message>> {
         let mut tool_results = Vec::new();
+        let mut images = Vec::new();
         
-        for tool_call in tool_calls {
-            debug!("Executing tool: {} with id: {}", tool_call.name, tool_call.id);
+        let mut executions = Vec::with_capacity(tool_calls.len());
+        while executions.len() < tool_calls.len() {
+            let next = &tool_calls[executions.len()..];
             
-            // Convert JSON arguments to HashMap
-            let parameters = if let serde_json::Value::Object(map) = tool_call.arguments {
-                map.into_iter()
-                    .map(|(k, v)| (k, v))
-                    .collect()
-            } else {
-                std::collections::HashMap::new()
-            };
+            // Stop starting new tools once the turn is out of time
+            deadline.check(&format!("tool '{}'", next[0].name))?;
             
-            // Execute the tool
-            match self.tool_manager.execute_tool(&tool_call.name, parameters).await {
+            // Consecutive calls of tools that may run in parallel, such as
+            // sub-agent tasks, are started together
+            let batch = next
+                .iter()
+                .take_while(|call| self.tool_manager.runs_in_parallel(&call.name))
+                .count()
+                .max(1);
+            let batch = futures::future::join_all(
+                next[..batch].iter().map(|call| self.execute_tool_call(call, deadline)),
+            ).await;
+            executions.extend(batch);
+        }
+        
+        for (tool_call, execution) in tool_calls.into_iter().zip(executions) {
+            match execution {
                 Ok(response) => {
                     debug!("Tool '{}' executed successfully", tool_call.name);
                     
+                    // Keep tabular output so the chat view can show it as a table
+                    let mut metadata = std::collections::HashMap::new();
+                    if let Some(table) = response.metadata.as_ref().and_then(|m| m.get(TABLE_METADATA_KEY)) {
+                        metadata.insert(TABLE_METADATA_KEY.to_string(), table.clone());
+                    }
+                    
+                    // Images can't go in a tool result for every provider; collect them
+                    // and attach them to a user message after the results
+                    if let Some(image) = response.metadata.as_ref()
+                        .and_then(|m| m.get(IMAGE_METADATA_KEY))
+                        .and_then(|image| serde_json::from_value::<crate::llm::types::ImageContent>(image.clone()).ok())
+                    {
+                        images.push((tool_call.name.clone(), image));
+                    }
+                    
+                    // Remember which files the turn changed, and show the diff in the chat view
+                    if let Some(change) = response.metadata.as_ref()
+                        .filter(|_| response.success)
+                        .and_then(FileChange::from_metadata)
+                    {
+                        metadata.insert(FILE_CHANGE_KEY.to_string(), serde_json::to_value(&change)?);
+                        changes.record(change);
+                    }
+                    
+                    // Label retrieved context with its source so the answer can cite it
+                    let source = response.metadata.as_ref()
+                        .filter(|_| response.success && self.tool_manager.is_retrieval(&tool_call.name))
+                        .and_then(CitationSource::from_metadata);
+                    let content = match source {
+                        Some(source) => {
+                            let index = citations.record(&tool_call.name, source);
+                            citations.label(index, &response.content)
+                        }
+                        None => response.content,
+                    };
+                    
                     // Create tool result message
                     let tool_result = Message {
                         id: uuid::Uuid::new_v4().to_string(),
                         role: MessageRole::Tool,
                         content: vec![crate::llm::types::ContentBlock::ToolResult {
                             tool_call_id: tool_call.id,
-                            content: response.content,
+                            content,
                         }],
                         timestamp: chrono::Utc::now(),
-                        metadata: std::collections::HashMap::new(),
+                        metadata,
                     };
                     
                     tool_results.push(tool_result);
@@ -220,16 +451,143 @@ impl Agent {
             }
         }
         
+        if !images.is_empty() {
+            let mut content = Vec::new();
+            for (tool_name, image) in images {
+                content.push(crate::llm::types::ContentBlock::Text {
+                    text: format!("Image returned by tool '{}':", tool_name),
+                });
+                content.push(crate::llm::types::ContentBlock::Image { image });
+            }
+            tool_results.push(Message {
+                id: uuid::Uuid::new_v4().to_string(),
+                role: MessageRole::User,
+                content,
+                timestamp: chrono::Utc::now(),
+                metadata: std::collections::HashMap::new(),
+            });
+        }
+        
         Ok(tool_results)
     }
     
+    /// Execute one tool call, forwarding any progress it reports
+    async fn execute_tool_call(
+        &self,
+        tool_call: &crate::llm::types::ToolCall,
+        deadline: &Deadline,
+    ) -> Result<crate::llm::tools::ToolResponse> {
+        debug!("Executing tool: {} with id: {}", tool_call.name, tool_call.id);
+        
+        // Convert JSON arguments to HashMap
+        let parameters = match &tool_call.arguments {
+            serde_json::Value::Object(map) => map.clone().into_iter().collect(),
+            _ => std::collections::HashMap::new(),
+        };
+        
+        let _ = self.event_tx.send(AppEvent::ToolCalled {
+            session_id: self.session_id.clone(),
+            tool_name: tool_call.name.clone(),
+            tool_id: tool_call.id.clone(),
+            arguments: tool_call.arguments.clone(),
+        });
+        
+        let execution = self.tool_manager.execute_tool_call(
+            &self.session_id,
+            &tool_call.id,
+            &tool_call.name,
+            parameters,
+            &self.event_tx,
+            deadline,
+        ).await;
+        
+        let _ = self.event_tx.send(AppEvent::ToolCompleted {
+            session_id: self.session_id.clone(),
+            tool_id: tool_call.id.clone(),
+            result: match &execution {
+                Ok(response) => response.content.clone(),
+                Err(e) => e.to_string(),
+            },
+            success: execution.as_ref().is_ok_and(|response| response.success),
+            table: execution
+                .as_ref()
+                .ok()
+                .and_then(|response| response.metadata.as_ref()?.get(TABLE_METADATA_KEY).cloned()),
+        });
+        
+        execution
+    }
+    
+    /// Work on a prompt in an isolated conversation until the model answers
+    ///
+    /// Used for sub-agents: the conversation lives only for the task and the
+    /// final answer is all that is handed back.
+    pub async fn run_task(
+        &self,
+        prompt: String,
+        system_message: Option<String>,
+        deadline: &Deadline,
+        max_rounds: usize,
+    ) -> Result<TaskOutcome> {
+        let mut messages = vec![Message::new_user(prompt)];
+        let mut citations = CitationTracker::new();
+        let mut changes = TurnChanges::default();
+        let mut usage = TokenUsage::default();
+        
+        for round in 1..=max_rounds {
+            let reply = self.send_message(messages.clone(), system_message.clone(), deadline).await?;
+            usage.add(&reply.usage);
+            
+            if reply.tool_calls.is_empty() {
+                return Ok(TaskOutcome {
+                    answer: reply.content,
+                    rounds: round,
+                    usage,
+                    changes,
+                });
+            }
+            
+            let mut content = Vec::new();
+            if !reply.content.is_empty() {
+                content.push(ContentBlock::Text { text: reply.content.clone() });
+            }
+            content.extend(reply.tool_calls.iter().map(|call| ContentBlock::ToolUse {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                input: call.arguments.clone(),
+            }));
+            let mut tool_use = Message::new_assistant(String::new());
+            tool_use.content = content;
+            messages.push(tool_use);
+            
+            messages.extend(self.handle_tool_calls(reply.tool_calls, deadline, &mut citations, &mut changes).await?);
+        }
+        
+        Err(anyhow::anyhow!("Gave up after {} tool rounds without an answer", max_rounds))
+    }
+    
+    /// Send an event to whoever listens to this agent
+    pub fn notify(&self, event: AppEvent) {
+        let _ = self.event_tx.send(event);
+    }
+    
+    /// Tools offered to the model with each request
+    pub fn tool_definitions(&self) -> Vec<Tool> {
+        self.tool_manager.get_tool_definitions()
+    }
+    
     /// Get the provider name
-    pub fn provider_name(&self) -> &str {
-        self.provider.name()
+    pub fn provider_name(&self) -> String {
+        self.provider().name().to_string()
     }
     
     /// Get the model name
-    pub fn model_name(&self) -> &str {
-        self.provider.model()
+    pub fn model_name(&self) -> String {
+        self.provider().model().to_string()
+    }
+    
+    /// Largest image the provider takes
+    pub fn image_limits(&self) -> ImageLimits {
+        self.provider().image_limits()
     }
 }
\ No newline at end of file
@@ -0,0 +1,296 @@
+//! Custom slash commands
+//!
+//! Prompts used again and again can be saved as commands: a Markdown or
+//! TOML file in `.goofy/commands/` of the project, or in `goofy/commands/`
+//! of the user's config directory for every project, becomes `/<name>` in
+//! the chat, named after the file. Files in subdirectories are namespaced,
+//! so `.goofy/commands/db/migrate.md` is `/db:migrate`.
+//!
+//! A Markdown command is its prompt, optionally after a front matter block
+//! with a `description` and an `argument-hint`; a TOML command has the same
+//! keys plus `prompt`. In the prompt, `$ARGUMENTS` stands for everything
+//! typed after the command and `$1` to `$9` for its words one by one. A
+//! prompt with neither gets the arguments appended. Project commands are
+//! only loaded in trusted workspaces and take precedence over the user's.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Directory of the project's commands, relative to the workspace
+const PROJECT_COMMANDS_DIR: &str = ".goofy/commands";
+
+/// Commands the chat handles itself, which custom commands can't replace
+pub const BUILT_IN_COMMANDS: &[(&str, &str)] = &[
+    ("continue", "Finish an answer a crash cut off"),
+    ("diff", "Compare the answers of two sessions"),
+    ("edit", "Edit an earlier prompt and send it again"),
+    ("env", "Set variables of the session environment"),
+    ("model", "Switch to another model"),
+    ("recall", "Find past sessions by what they were about"),
+    ("retry", "Regenerate the latest answer"),
+    ("screenshot", "Attach a screenshot of the screen or a window"),
+];
+
+/// A prompt saved as a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCommand {
+    /// Name typed after the slash
+    pub name: String,
+    pub description: Option<String>,
+    /// What the arguments are, for instance `<issue> [priority]`
+    pub argument_hint: Option<String>,
+    pub template: String,
+    /// File the command was read from
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CommandFile {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, rename = "argument-hint", alias = "argument_hint")]
+    argument_hint: Option<String>,
+    #[serde(default)]
+    prompt: Option<String>,
+}
+
+impl CustomCommand {
+    /// Read a command file, naming the command `name`
+    pub fn from_file(name: String, path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read command {}", path.display()))?;
+        let is_toml = path.extension().is_some_and(|extension| extension == "toml");
+        let (meta, template) = if is_toml {
+            let mut meta: CommandFile =
+                toml::from_str(&content).with_context(|| format!("Invalid command {}", path.display()))?;
+            let Some(prompt) = meta.prompt.take() else {
+                bail!("Command {} has no prompt", path.display());
+            };
+            (meta, prompt)
+        } else {
+            parse_markdown(&content).with_context(|| format!("Invalid command {}", path.display()))?
+        };
+        if template.trim().is_empty() {
+            bail!("Command {} has an empty prompt", path.display());
+        }
+        Ok(Self {
+            name,
+            description: meta.description,
+            argument_hint: meta.argument_hint,
+            template: template.trim().to_string(),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// The prompt with `arguments` filled in
+    pub fn expand(&self, arguments: &str) -> String {
+        let arguments = arguments.trim();
+        let words = split_arguments(arguments);
+        let mut prompt = self.template.replace("$ARGUMENTS", arguments);
+        let mut positional = false;
+        for n in (1..=9).rev() {
+            let placeholder = format!("${}", n);
+            if prompt.contains(&placeholder) {
+                positional = true;
+                prompt = prompt.replace(&placeholder, words.get(n - 1).map_or("", String::as_str));
+            }
+        }
+        let placeholders = positional || self.template.contains("$ARGUMENTS");
+        if !placeholders && !arguments.is_empty() {
+            prompt = format!("{}\n\n{}", prompt, arguments);
+        }
+        prompt
+    }
+}
+
+/// Front matter and body of a Markdown command
+fn parse_markdown(content: &str) -> Result<(CommandFile, String)> {
+    let content = content.trim_start_matches('\u{feff}');
+    let Some(rest) = content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n")) else {
+        return Ok((CommandFile::default(), content.to_string()));
+    };
+    let Some(end) = rest.find("\n---") else {
+        bail!("Front matter is not closed with ---");
+    };
+    let meta = if rest[..end].trim().is_empty() {
+        CommandFile::default()
+    } else {
+        serde_yaml::from_str(&rest[..end])?
+    };
+    let body = rest[end + 4..].split_once('\n').map_or("", |(_, body)| body);
+    Ok((meta, body.to_string()))
+}
+
+/// Words of the arguments, keeping quoted ones together
+fn split_arguments(arguments: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    for c in arguments.chars() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (c, _) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The custom commands of a workspace, by name
+#[derive(Debug, Clone, Default)]
+pub struct CustomCommands {
+    commands: BTreeMap<String, CustomCommand>,
+}
+
+impl CustomCommands {
+    /// Commands of the user and, in a trusted workspace, of the project
+    pub fn load(config: &Config) -> Self {
+        let mut commands = Self::default();
+        if let Some(config_dir) = dirs::config_dir() {
+            commands.load_dir(&config_dir.join("goofy").join("commands"));
+        }
+        if config.workspace_trusted {
+            commands.load_dir(&config.cwd.join(PROJECT_COMMANDS_DIR));
+        }
+        commands
+    }
+
+    /// Add the commands in `dir`, replacing those of the same names
+    pub fn load_dir(&mut self, dir: &Path) {
+        for path in command_files(dir) {
+            let Some(name) = command_name(dir, &path) else {
+                continue;
+            };
+            if BUILT_IN_COMMANDS.iter().any(|(built_in, _)| *built_in == name) {
+                warn!("Command {} ignored: /{} is built in", path.display(), name);
+                continue;
+            }
+            match CustomCommand::from_file(name.clone(), &path) {
+                Ok(command) => {
+                    self.commands.insert(name, command);
+                }
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CustomCommand> {
+        self.commands.values()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomCommand> {
+        self.commands.get(name)
+    }
+
+    /// The prompt a `/<name> [arguments]` prompt stands for, or `None` when
+    /// it names no custom command
+    pub fn expand(&self, prompt: &str) -> Option<String> {
+        let rest = prompt.trim_start().strip_prefix('/')?;
+        let (name, arguments) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        self.get(name).map(|command| command.expand(arguments))
+    }
+}
+
+/// Markdown and TOML files under `dir`, in subdirectories too
+fn command_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(command_files(&path));
+        } else if path.extension().is_some_and(|extension| extension == "md" || extension == "toml") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// `review` for `review.md`, `db:migrate` for `db/migrate.toml`
+fn command_name(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?.with_extension("");
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let name = parts.join(":");
+    let valid = !name.is_empty() && !name.contains(char::is_whitespace) && !name.starts_with('.');
+    valid.then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_commands_are_read_from_markdown_and_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("db")).unwrap();
+        fs::write(
+            dir.path().join("review.md"),
+            "---\ndescription: Review the staged changes\nargument-hint: [focus]\n---\nReview `git diff --staged`.\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("db/migrate.toml"),
+            "description = \"Write a migration\"\nprompt = \"Write a migration that $ARGUMENTS.\"\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("model.md"), "Shadows a built-in command").unwrap();
+        fs::write(dir.path().join("notes.txt"), "Not a command").unwrap();
+
+        let mut commands = CustomCommands::default();
+        commands.load_dir(dir.path());
+        let names: Vec<&str> = commands.iter().map(|command| command.name.as_str()).collect();
+        assert_eq!(names, ["db:migrate", "review"]);
+        let review = commands.get("review").unwrap();
+        assert_eq!(review.description.as_deref(), Some("Review the staged changes"));
+        assert_eq!(review.argument_hint.as_deref(), Some("[focus]"));
+        assert_eq!(review.template, "Review `git diff --staged`.");
+    }
+
+    #[test]
+    fn test_arguments_fill_the_placeholders() {
+        let command = |template: &str| CustomCommand {
+            name: "fix".to_string(),
+            description: None,
+            argument_hint: None,
+            template: template.to_string(),
+            path: PathBuf::from("fix.md"),
+        };
+        assert_eq!(
+            command("Fix issue $1 with priority $2.").expand("123 \"very high\""),
+            "Fix issue 123 with priority very high."
+        );
+        assert_eq!(command("Fix: $ARGUMENTS").expand(" the login page "), "Fix: the login page");
+        assert_eq!(command("Fix the tests.").expand("in src/app"), "Fix the tests.\n\nin src/app");
+        assert_eq!(command("Fix $1 and $2.").expand("it"), "Fix it and .");
+
+        let mut commands = CustomCommands::default();
+        commands.commands.insert("fix".to_string(), command("Fix $ARGUMENTS."));
+        assert_eq!(commands.expand("/fix the build").as_deref(), Some("Fix the build."));
+        assert_eq!(commands.expand("/fixes"), None);
+        assert_eq!(commands.expand("fix it"), None);
+    }
+}
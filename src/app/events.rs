@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::llm::tools::ToolProgress;
+
 /// Events that can occur in the application
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -67,6 +69,7 @@ pub enum AppEvent {
         session_id: String,
         tool_name: String,
         tool_id: String,
+        arguments: serde_json::Value,
     },
     
     /// A tool call completed
@@ -74,11 +77,45 @@ pub enum AppEvent {
         session_id: String,
         tool_id: String,
         result: String,
+        /// Whether the tool succeeded
+        success: bool,
+        /// Rows the tool returned, shown as a table
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        table: Option<serde_json::Value>,
+    },
+    
+    /// A running tool reported progress
+    ToolProgress {
+        session_id: String,
+        progress: ToolProgress,
+    },
+    
+    /// The running cost of a session changed
+    CostUpdated {
+        session_id: String,
+        /// Cost of the session so far in US dollars
+        total_cost: f64,
+        /// Budget of the session, if one is configured
+        max_cost: Option<f64>,
+    },
+    
+    /// An external tool asked to switch to a session
+    OpenSessionRequested {
+        session_id: String,
+    },
+    
+    /// An external tool asked to insert text into the prompt editor
+    InsertTextRequested {
+        text: String,
     },
     
     /// An error occurred
     Error {
         error: String,
+        /// What is being done about it, like a retry or a fallback, when the
+        /// request goes on
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recovery: Option<String>,
     },
     
     /// Application is shutting down
@@ -100,8 +137,11 @@ impl AppEvent {
             | AppEvent::StreamChunk { session_id, .. }
             | AppEvent::StreamEnded { session_id, .. }
             | AppEvent::ToolCalled { session_id, .. }
-            | AppEvent::ToolCompleted { session_id, .. } => Some(session_id),
-            AppEvent::Error { .. } | AppEvent::Shutdown => None,
+            | AppEvent::ToolCompleted { session_id, .. }
+            | AppEvent::ToolProgress { session_id, .. }
+            | AppEvent::CostUpdated { session_id, .. }
+            | AppEvent::OpenSessionRequested { session_id } => Some(session_id),
+            AppEvent::InsertTextRequested { .. } | AppEvent::Error { .. } | AppEvent::Shutdown => None,
         }
     }
     
@@ -5,19 +5,35 @@
 
 mod agent;
 mod events;
+pub mod activity;
+pub mod switcher;
+pub mod commands;
+pub mod notifier;
 
 pub use agent::*;
 pub use events::*;
+pub use activity::{ActivityTracker, Suspend};
+pub use switcher::ModelSwitcher;
+pub use commands::CustomCommands;
+pub use notifier::Notifier;
 
 use anyhow::Result;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, info, error};
+use tracing::{debug, info, error, warn};
 
 use crate::{
-    config::Config,
-    llm::{LlmProvider, ProviderFactory, ProviderConfig, tools::{ToolManager, ToolPermissions}},
-    session::{SessionManager, Session, ConversationManager},
+    config::{Config, PostprocessConfig},
+    control::{ControlCommand, ControlMessage, ControlResponse, ControlServer, SharedEditor},
+    fswatch::{FileTracker, FileWatcher},
+    hooks::HookEngine,
+    index::ProjectIndex,
+    lsp::LspManager,
+    mcp::McpManager,
+    permission::{ApprovalMode, GrantStore, PermissionConfig, PermissionManager, PermissionRequest},
+    llm::{LlmProvider, ProviderFactory, ProviderConfig, images::ImageAttachment, moderation::Moderator, openrouter, resilience::{ResilientProvider, RetryPolicy}, tools::{DefinitionTool, DiagnosticsTool, FetchTool, HoverTool, ReferencesTool, EditGuards, ScreenshotTool, TaskTool, ToolManager, ToolPermissions, ToolTimeouts}},
+    session::{journal::Journal, snapshots::SnapshotStore, SessionManager, SemanticIndex, Session, Conversation, ConversationManager, Suggester, Titler},
 };
 
 /// Main application structure
@@ -27,6 +43,13 @@ pub struct App {
     conversation_manager: Arc<ConversationManager>,
     llm_provider: Arc<dyn LlmProvider>,
     tool_manager: Arc<ToolManager>,
+    permission_manager: Option<Arc<PermissionManager>>,
+    file_tracker: Arc<FileTracker>,
+    /// Keeps reporting file changes to the tool manager while alive
+    _file_watcher: Option<Arc<FileWatcher>>,
+    /// Suspends background work while the user is away
+    activity: Arc<ActivityTracker>,
+    editor: SharedEditor,
     event_tx: mpsc::UnboundedSender<AppEvent>,
     event_rx: RwLock<Option<mpsc::UnboundedReceiver<AppEvent>>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
@@ -35,38 +58,92 @@ pub struct App {
 impl App {
     /// Create a new application instance
     pub async fn new(config: Config) -> Result<Self> {
+        Self::build(config, None, None).await
+    }
+    
+    /// Create an application instance that asks the user about risky tool calls
+    ///
+    /// Tool calls the permission system wants confirmed are sent on the
+    /// returned channel and wait until the interface answers them.
+    pub async fn new_with_prompts(config: Config) -> Result<(Self, mpsc::UnboundedReceiver<PermissionRequest>)> {
+        let (prompt_tx, prompt_rx) = mpsc::unbounded_channel();
+        let app = Self::build(config, None, Some(prompt_tx)).await?;
+        Ok((app, prompt_rx))
+    }
+    
+    /// Create an application instance paired with an editor
+    ///
+    /// Editing tools are routed through the editor once it attaches over the
+    /// control socket, and an extra tool exposes its selection and diagnostics.
+    pub async fn new_with_editor(config: Config) -> Result<Self> {
+        Self::build(config, Some(SharedEditor::default()), None).await
+    }
+    
+    async fn build(
+        config: Config,
+        editor: Option<SharedEditor>,
+        prompter: Option<mpsc::UnboundedSender<PermissionRequest>>,
+    ) -> Result<Self> {
         debug!("Creating new App instance");
         
-        // Initialize session manager
-        let session_manager = Arc::new(SessionManager::new(&config.data_dir).await?);
+        // Create LLM provider from config
+        let provider_config = Self::provider_config(&config)?;
+        let llm_provider = Self::create_provider(&config)?;
+        if config.provider == "openrouter" {
+            // Price OpenRouter's models from its own listing
+            let base_url = config.base_url.clone();
+            tokio::spawn(async move {
+                match openrouter::register_model_pricing(base_url.as_deref()).await {
+                    Ok(count) => debug!("Priced {} OpenRouter models", count),
+                    Err(e) => warn!("Could not load OpenRouter prices: {}", e),
+                }
+            });
+        }
         
-        // Initialize conversation manager
-        let conversation_manager = Arc::new(ConversationManager::new());
+        // Follow-up suggestions may come from a cheaper model of the same provider
+        let suggester = if config.suggestions.enabled {
+            let provider: Arc<dyn LlmProvider> = match &config.suggestions.model {
+                Some(model) if *model != config.model => Arc::from(ProviderFactory::create_provider(ProviderConfig {
+                    model: model.clone(),
+                    ..provider_config.clone()
+                })?),
+                _ => llm_provider.clone(),
+            };
+            Some(Arc::new(Suggester::new(provider, config.suggestions.count)))
+        } else {
+            None
+        };
         
-        // Create LLM provider from config
-        let provider_config = ProviderConfig {
-            provider_type: config.provider.clone(),
-            api_key: config.api_key.clone(),
-            base_url: config.base_url.clone(),
-            model: config.model.clone(),
-            max_tokens: config.max_tokens,
-            temperature: config.temperature,
-            top_p: config.top_p,
-            stream: config.stream,
-            tools: Vec::new(), // TODO: Load from config
-            extra_headers: config.extra_headers.clone(),
-            extra_body: config.extra_body.clone(),
+        // Titles too; with them off, sessions are named after their first prompt
+        let titler = if config.titles.enabled {
+            let provider: Arc<dyn LlmProvider> = match &config.titles.model {
+                Some(model) if *model != config.model => Arc::from(ProviderFactory::create_provider(ProviderConfig {
+                    model: model.clone(),
+                    ..provider_config
+                })?),
+                _ => llm_provider.clone(),
+            };
+            Titler::new(provider)
+        } else {
+            Titler::from_prompts()
         };
         
-        let llm_provider = ProviderFactory::create_provider(provider_config)?;
-        llm_provider.validate_config()?;
+        // Initialize session manager, with semantic search when the provider can embed
+        let mut session_manager = SessionManager::with_storage(
+            crate::session::storage::from_config(&config).await?,
+        );
+        if llm_provider.default_embedding_model().is_some() {
+            let index = SemanticIndex::open(config.data_dir.join("sessions.db"), llm_provider.clone()).await?;
+            session_manager = session_manager.with_semantic_index(Arc::new(index));
+        }
+        let session_manager = Arc::new(session_manager);
         
         // Initialize tool manager with permissions from config
         let tool_permissions = ToolPermissions {
-            yolo_mode: config.yolo_mode.unwrap_or(false),
+            yolo_mode: config.yolo_mode.unwrap_or(false) && config.workspace_trusted,
             allow_read: true,
-            allow_write: !config.read_only.unwrap_or(false),
-            allow_execute: !config.read_only.unwrap_or(false),
+            allow_write: !config.is_read_only(),
+            allow_execute: !config.is_read_only(),
             allow_network: false,
             restricted_paths: vec![
                 "/etc".to_string(),
@@ -74,8 +151,111 @@ impl App {
                 "/proc".to_string(),
                 "/dev".to_string(),
             ],
+            allowed_paths: config.sandbox.allowed_paths
+                .iter()
+                .map(|path| config.cwd.join(path).display().to_string())
+                .collect(),
+            env_allowlist: config.sandbox.env_allowlist.clone(),
+        };
+        let yolo_mode = tool_permissions.yolo_mode;
+        let mut tool_manager = match &editor {
+            Some(editor) => ToolManager::with_editor(tool_permissions, editor.clone()),
+            None => ToolManager::new(tool_permissions),
         };
-        let tool_manager = Arc::new(ToolManager::new(tool_permissions));
+        tool_manager.set_workspace(config.workspace());
+        tool_manager.set_edit_guards(EditGuards::new(config.edits.clone()));
+        tool_manager.set_hooks(HookEngine::new(&config.hooks, config.workspace_trusted)?.with_working_dir(config.cwd.clone()));
+        tool_manager.set_timeouts(ToolTimeouts::new(&PermissionConfig::default(), &config.tool_timeouts));
+        tool_manager.register_tool(Box::new(FetchTool::with_config(config.fetch.clone()).with_cache_dir(&config.data_dir)));
+        tool_manager.session_env().define_sets(config.sandbox.env_sets.clone());
+        tool_manager.set_snapshot_store(Arc::new(SnapshotStore::new(SnapshotStore::default_path(&config.data_dir))));
+        let file_tracker = Arc::new(FileTracker::new());
+        tool_manager.set_file_tracker(file_tracker.clone());
+        let indexes = Self::index_workspace(&config);
+        tool_manager.set_indexes(indexes.clone());
+        let activity = Arc::new(ActivityTracker::new(config.tui.idle_suspend_after()));
+        let file_watcher = match Self::watch_workspace(&config, file_tracker.clone(), indexes) {
+            Ok(watcher) => {
+                let watcher = Arc::new(watcher);
+                activity.register(watcher.clone());
+                Some(watcher)
+            }
+            Err(e) => {
+                warn!("Not watching the workspace for file changes: {:#}", e);
+                None
+            }
+        };
+        let permission_manager = prompter.map(|prompter| {
+            let permission_config = PermissionConfig { yolo_mode, ..PermissionConfig::default() };
+            let mut permission_manager = PermissionManager::new(permission_config).with_prompter(prompter);
+            // A repository could ship its own grants, so only trusted projects keep them
+            if config.workspace_trusted {
+                match GrantStore::load_for(&config.workspace().primary().path) {
+                    Ok(grants) => permission_manager = permission_manager.with_project_grants(grants),
+                    Err(e) => warn!("Ignoring project permission grants: {:#}", e),
+                }
+            }
+            Arc::new(permission_manager)
+        });
+        if let Some(manager) = &permission_manager {
+            tool_manager.set_permission_manager(manager.clone());
+        }
+        if config.sub_agents.enabled {
+            tool_manager.register_tool(Box::new(
+                TaskTool::new(
+                    llm_provider.clone(),
+                    config.sub_agents.max_concurrent,
+                    config.sub_agents.max_tool_rounds,
+                )
+                .with_workspace(config.workspace())
+                .with_permission_manager(permission_manager.clone())
+                .with_edit_guards(EditGuards::new(config.edits.clone())),
+            ));
+        }
+        if config.screenshot.enabled {
+            tool_manager.register_tool(Box::new(
+                ScreenshotTool::new().allow_over_ssh(config.screenshot.allow_over_ssh),
+            ));
+        }
+        
+        // Language servers may run build scripts, so like MCP servers they
+        // are only started for trusted workspaces
+        if config.lsp.settings.enabled && config.workspace_trusted {
+            let mut lsp_manager = LspManager::with_config(config.lsp.clone()).await?;
+            lsp_manager.set_workspace_root(&config.workspace().primary().path).await?;
+            let lsp_manager = Arc::new(lsp_manager);
+            tool_manager.set_lsp_manager(lsp_manager.clone());
+            tool_manager.register_tool(Box::new(DiagnosticsTool::new(Some(lsp_manager.clone()))));
+            tool_manager.register_tool(Box::new(DefinitionTool::new(lsp_manager.clone())));
+            tool_manager.register_tool(Box::new(ReferencesTool::new(lsp_manager.clone())));
+            tool_manager.register_tool(Box::new(HoverTool::new(lsp_manager)));
+        }
+        
+        // Local and remote MCP servers contribute their tools side by side.
+        // Servers may launch commands, so an untrusted workspace gets none.
+        if config.workspace_trusted {
+            let mcp_manager = McpManager::connect(&config.mcp).await;
+            let mcp_tools = mcp_manager.register_tools(&mut tool_manager).await;
+            if mcp_tools > 0 {
+                info!("Registered {} tool(s) from {} MCP server(s)", mcp_tools, mcp_manager.clients().len());
+            }
+        }
+        let tool_manager = Arc::new(tool_manager);
+        
+        // Initialize conversation manager
+        let moderator = Moderator::from_config(&config.moderation, &config.data_dir)?.map(Arc::new);
+        let conversation_manager = Arc::new(
+            ConversationManager::new()
+                .with_session_manager(session_manager.clone())
+                .with_permission_manager(permission_manager.clone())
+                .with_turn_timeout(config.turn_timeout())
+                .with_moderator(moderator)
+                .with_budget(config.budget.clone())
+                .with_streaming(config.stream)
+                .with_suggester(suggester)
+                .with_titler(Some(Arc::new(titler)))
+                .with_journal(Some(Arc::new(Journal::new(Journal::default_path(&config.data_dir))))),
+        );
         
         // Create event channel
         let (event_tx, event_rx) = mpsc::unbounded_channel();
@@ -84,14 +264,90 @@ impl App {
             config,
             session_manager,
             conversation_manager,
-            llm_provider: Arc::from(llm_provider),
+            llm_provider,
             tool_manager,
+            permission_manager,
+            file_tracker,
+            _file_watcher: file_watcher,
+            activity,
+            editor: editor.unwrap_or_default(),
             event_tx,
             event_rx: RwLock::new(Some(event_rx)),
             shutdown_tx: None,
         })
     }
     
+    /// Provider settings for the configured provider and model
+    fn provider_config(config: &Config) -> Result<ProviderConfig> {
+        let mut provider_config = ProviderConfig {
+            provider_type: config.provider.clone(),
+            api_key: config.api_key.clone(),
+            base_url: config.base_url.clone(),
+            model: config.model.clone(),
+            max_tokens: config.max_tokens,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            stream: config.stream,
+            tools: Vec::new(), // TODO: Load from config
+            extra_headers: config.extra_headers.clone(),
+            extra_body: config.extra_body.clone(),
+            azure: config.azure.clone(),
+        };
+        if config.provider == "openrouter" {
+            openrouter::apply_settings(&mut provider_config, &config.openrouter)?;
+        }
+        Ok(provider_config)
+    }
+    
+    /// Create and validate the provider for the configured provider and model
+    ///
+    /// Failed requests are retried, and go to the configured fallback model
+    /// once the provider keeps failing.
+    pub fn create_provider(config: &Config) -> Result<Arc<dyn LlmProvider>> {
+        let primary: Arc<dyn LlmProvider> = Arc::from(ProviderFactory::create_provider(Self::provider_config(config)?)?);
+        let mut llm_provider = ResilientProvider::new(primary, RetryPolicy::new(&config.retry));
+        if let Some(fallback) = &config.retry.fallback {
+            let mut fallback_config = config.clone();
+            fallback_config.switch_model(fallback.provider.as_deref().unwrap_or(&config.provider), &fallback.model);
+            let provider = ProviderFactory::create_provider(Self::provider_config(&fallback_config)?)?;
+            llm_provider = llm_provider.with_fallback(Arc::from(provider), fallback.after_failures);
+        }
+        llm_provider.validate_config()?;
+        Ok(Arc::new(llm_provider))
+    }
+    
+    /// Index every workspace root in the background, starting from the
+    /// cache of the last run
+    fn index_workspace(config: &Config) -> Vec<Arc<ProjectIndex>> {
+        if !config.index.enabled {
+            return Vec::new();
+        }
+        let indexes: Vec<Arc<ProjectIndex>> = config
+            .workspace()
+            .roots()
+            .iter()
+            .map(|root| Arc::new(ProjectIndex::new(&root.path, config.index.clone()).with_cache_dir(&config.data_dir)))
+            .collect();
+        for index in &indexes {
+            let index = index.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = index.build() {
+                    warn!("Could not index {}: {:#}", index.root().display(), e);
+                }
+            });
+        }
+        indexes
+    }
+    
+    /// Report changes below every workspace root to the tracker and indexes
+    fn watch_workspace(config: &Config, tracker: Arc<FileTracker>, indexes: Vec<Arc<ProjectIndex>>) -> Result<FileWatcher> {
+        let mut watcher = FileWatcher::new(tracker, indexes)?;
+        for root in config.workspace().roots() {
+            watcher.watch(&root.path)?;
+        }
+        Ok(watcher)
+    }
+    
     /// Get the session manager
     pub fn session_manager(&self) -> &Arc<SessionManager> {
         &self.session_manager
@@ -112,6 +368,48 @@ impl App {
         &self.tool_manager
     }
     
+    /// Get the tracker of the files the agent read and edited
+    pub fn file_tracker(&self) -> &Arc<FileTracker> {
+        &self.file_tracker
+    }
+    
+    /// Get the tracker that suspends background work while the user is away
+    pub fn activity(&self) -> &Arc<ActivityTracker> {
+        &self.activity
+    }
+    
+    /// Switch the models of conversations started from this app
+    pub fn model_switcher(&self) -> ModelSwitcher {
+        ModelSwitcher::new(self.config.clone())
+    }
+    
+    /// Rewrites applied to answers before they are shown
+    pub fn postprocess_config(&self) -> &PostprocessConfig {
+        &self.config.tui.postprocess
+    }
+    
+    /// Whether `/screenshot` may capture while running over SSH
+    pub fn screenshots_over_ssh(&self) -> bool {
+        self.config.screenshot.allow_over_ssh
+    }
+    
+    /// Announce answers the way the configuration asks
+    pub fn notifier(&self) -> Notifier {
+        Notifier::new(self.config.notifications.clone())
+    }
+    
+    /// How tool calls are approved; untrusted workspaces are read-only even in
+    /// YOLO mode
+    pub fn approval_mode(&self) -> ApprovalMode {
+        if self.config.is_read_only() {
+            ApprovalMode::ReadOnly
+        } else if self.config.yolo_mode.unwrap_or(false) {
+            ApprovalMode::Yolo
+        } else {
+            ApprovalMode::Ask
+        }
+    }
+    
     /// Get the event sender
     pub fn event_sender(&self) -> &mpsc::UnboundedSender<AppEvent> {
         &self.event_tx
@@ -177,13 +475,31 @@ impl App {
             AppEvent::StreamEnded { session_id, message_id } => {
                 debug!("Stream ended in session {}: {}", session_id, message_id);
             }
-            AppEvent::ToolCalled { session_id, tool_name, tool_id } => {
+            AppEvent::ToolCalled { session_id, tool_name, tool_id, .. } => {
                 debug!("Tool called in session {}: {} ({})", session_id, tool_name, tool_id);
             }
-            AppEvent::ToolCompleted { session_id, tool_id, result: _ } => {
+            AppEvent::ToolCompleted { session_id, tool_id, .. } => {
                 debug!("Tool completed in session {}: {}", session_id, tool_id);
             }
-            AppEvent::Error { error } => {
+            AppEvent::ToolProgress { session_id, progress } => {
+                debug!(
+                    "Tool progress in session {}: {} ({}) {:?}%",
+                    session_id, progress.tool_name, progress.tool_call_id, progress.percent()
+                );
+            }
+            AppEvent::CostUpdated { session_id, total_cost, max_cost } => {
+                debug!("Cost of session {}: ${:.4} (budget {:?})", session_id, total_cost, max_cost);
+            }
+            AppEvent::OpenSessionRequested { session_id } => {
+                info!("Open session requested: {}", session_id);
+            }
+            AppEvent::InsertTextRequested { text } => {
+                debug!("Insert text requested ({} chars)", text.len());
+            }
+            AppEvent::Error { error, recovery: Some(recovery) } => {
+                warn!("Recovering from error: {} ({})", error, recovery);
+            }
+            AppEvent::Error { error, recovery: None } => {
                 error!("Application error: {}", error);
             }
             AppEvent::Shutdown => {
@@ -204,43 +520,136 @@ impl App {
         println!("🎉 Goofy Interactive Mode");
         println!("Provider: {}", self.config.provider);
         println!("Model: {}", self.config.model);
+        if !self.config.workspace_trusted {
+            println!("Workspace: untrusted (read-only tools, no instruction files)");
+        }
         println!();
         println!("Welcome to Goofy! The TUI is currently under development.");
         println!("For now, you can use 'goofy run \"your prompt\"' for non-interactive mode.");
         println!();
         println!("Press Ctrl+C to exit");
         
-        // For now, just wait for a signal
-        tokio::signal::ctrl_c().await?;
+        // Expose the control socket so editors and scripts can drive this instance
+        let control = if self.config.control.enabled {
+            match ControlServer::start(&self.config.control_socket_path(), self.editor.clone()).await {
+                Ok(control) => Some(control),
+                Err(e) => {
+                    error!("Control interface unavailable: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
         
+        self.serve_control(control).await?;
         println!("\nGoodbye! 👋");
         Ok(())
     }
     
+    /// Run as a headless server for an editor plugin
+    ///
+    /// The socket path is printed on stdout once the server is listening so
+    /// the plugin that spawned this process knows where to connect.
+    pub async fn run_editor_server(&mut self, socket_path: PathBuf) -> Result<()> {
+        info!("Starting editor server");
+        
+        self.start_event_loop().await?;
+        
+        let control = ControlServer::start(&socket_path, self.editor.clone()).await?;
+        println!("{}", control.0.socket_path().display());
+        
+        self.serve_control(Some(control)).await
+    }
+    
+    /// Handle control commands until Ctrl+C
+    async fn serve_control(
+        &mut self,
+        mut control: Option<(ControlServer, mpsc::UnboundedReceiver<ControlMessage>)>,
+    ) -> Result<()> {
+        loop {
+            tokio::select! {
+                result = tokio::signal::ctrl_c() => {
+                    result?;
+                    break;
+                }
+                Some(message) = async {
+                    match control.as_mut() {
+                        Some((_, rx)) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    let response = self.handle_control_command(message.command).await;
+                    let _ = message.reply.send(response);
+                }
+            }
+        }
+        
+        drop(control);
+        Ok(())
+    }
+    
+    /// Execute a command received over the control socket
+    async fn handle_control_command(&mut self, command: ControlCommand) -> ControlResponse {
+        match command {
+            ControlCommand::Ping => ControlResponse::ok(Some(serde_json::json!("pong"))),
+            ControlCommand::OpenSession { session_id } => {
+                match self.session_manager.get_session(&session_id).await {
+                    Ok(Some(session)) => {
+                        let _ = self.event_tx.send(AppEvent::OpenSessionRequested {
+                            session_id: session.id.clone(),
+                        });
+                        ControlResponse::ok(Some(serde_json::json!({
+                            "session_id": session.id,
+                            "title": session.title,
+                        })))
+                    }
+                    Ok(None) => ControlResponse::error(format!("Session not found: {}", session_id)),
+                    Err(e) => ControlResponse::error(e.to_string()),
+                }
+            }
+            ControlCommand::InsertText { text } => {
+                let _ = self.event_tx.send(AppEvent::InsertTextRequested { text });
+                ControlResponse::ok(None)
+            }
+            ControlCommand::RunPrompt { prompt } => {
+                match self.run_non_interactive(&prompt, true).await {
+                    Ok(result) => ControlResponse::ok(Some(serde_json::Value::String(result))),
+                    Err(e) => ControlResponse::error(e.to_string()),
+                }
+            }
+            // Attaching is handled by the server on the editor's own connection
+            ControlCommand::AttachEditor { .. } => {
+                ControlResponse::error("attach_editor must be sent on the editor connection")
+            }
+        }
+    }
+    
     /// Run a single prompt non-interactively
-    pub async fn run_non_interactive(&mut self, prompt: &str, quiet: bool) -> Result<String> {
+    pub async fn run_non_interactive(&self, prompt: &str, quiet: bool) -> Result<String> {
+        self.run_non_interactive_with_images(prompt, Vec::new(), quiet).await
+    }
+    
+    /// Run a single prompt with attached images non-interactively
+    pub async fn run_non_interactive_with_images(
+        &self,
+        prompt: &str,
+        images: Vec<ImageAttachment>,
+        quiet: bool,
+    ) -> Result<String> {
         info!("Running non-interactive prompt");
         debug!("Prompt: {}", prompt);
         debug!("Quiet mode: {}", quiet);
         
+        // Progress goes to stderr; stdout carries only the answer
         if !quiet {
-            println!("Processing prompt...");
+            eprintln!("Processing prompt...");
         }
         
-        // Create a new session for this interaction
-        let session = self.session_manager.create_session(
-            "Non-interactive session".to_string(),
-            None,
-        ).await?;
-        
-        // Start conversation
-        let conversation = self.conversation_manager.start_conversation(
-            session.id.clone(),
-            self.llm_provider.clone(),
-        ).await?;
+        let conversation = self.start_session_conversation("Non-interactive session").await?;
         
         // Send the prompt and get response
-        let response = conversation.send_message(prompt.to_string()).await?;
+        let response = conversation.send_message_with_images(prompt.to_string(), images).await?;
         
         // Update session with token usage
         if let Some(usage) = response.metadata.get("usage") {
@@ -248,12 +657,63 @@ impl App {
         }
         
         if !quiet {
-            println!("Response received.");
+            eprintln!("Response received.");
         }
         
         Ok(response.content)
     }
     
+    /// Create a session and start a conversation in it
+    pub async fn start_session_conversation(&self, title: &str) -> Result<Arc<Conversation>> {
+        let (conversation, _events) = self.start_session_conversation_with_events(title).await?;
+        Ok(conversation)
+    }
+    
+    /// Create a session and start a conversation in it, receiving its events
+    pub async fn start_session_conversation_with_events(
+        &self,
+        title: &str,
+    ) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
+        let session = self.session_manager.create_session(title.to_string(), None).await?;
+        self.session_manager.record_working_directory(&session.id, &self.config.cwd).await?;
+        self.conversation_manager.start_conversation_with_events(
+            session.id.clone(),
+            self.llm_provider.clone(),
+            self.tool_manager.clone(),
+            self.config.effective_system_message(),
+        ).await
+    }
+    
+    /// Continue a stored session with its message history
+    pub async fn resume_session_conversation(&self, session_id: &str) -> Result<Arc<Conversation>> {
+        let (conversation, _events) = self.resume_session_conversation_with_events(session_id).await?;
+        Ok(conversation)
+    }
+    
+    /// Continue a stored session with its message history, receiving its events
+    ///
+    /// The tools the user allowed for the whole session are allowed again. The
+    /// working directory is the caller's to restore, before the app is built.
+    pub async fn resume_session_conversation_with_events(
+        &self,
+        session_id: &str,
+    ) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
+        let session = self.session_manager.resumable_session(session_id).await?;
+        if let Some(permission_manager) = &self.permission_manager {
+            permission_manager.allow_for_session(session.allowed_tools()).await;
+        }
+        if session.working_directory().is_some_and(|dir| dir != self.config.cwd) {
+            warn!("Resuming session {} outside the directory it was started in", session.id);
+        }
+        info!("Resuming session {} ({} messages)", session.id, session.message_count);
+        self.conversation_manager.start_conversation_with_events(
+            session.id,
+            self.llm_provider.clone(),
+            self.tool_manager.clone(),
+            self.config.effective_system_message(),
+        ).await
+    }
+    
     /// Shutdown the application gracefully
     pub async fn shutdown(&mut self) -> Result<()> {
         info!("Shutting down application");
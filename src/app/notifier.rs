@@ -0,0 +1,160 @@
+//! Telling the user an answer is ready
+//!
+//! An answer is announced when it arrives while the terminal is in the
+//! background, or after a run long enough for the user to have looked away.
+//! The announcement is the terminal bell, an OSC 9 escape sequence that
+//! iTerm2, WezTerm, Windows Terminal, kitty and ghostty show as a desktop
+//! notification, or a notification of the desktop itself sent with
+//! `notify-send` or `osascript`. Left to `auto`, OSC 9 is used in terminals
+//! known to show it, outside tmux which would swallow it, and the bell
+//! everywhere else.
+
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::config::{NotificationConfig, NotificationMethod};
+
+/// Title of desktop notifications
+const TITLE: &str = "Goofy";
+
+/// Longest message sent, in characters
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// `TERM_PROGRAM` values of terminals that show OSC 9 notifications
+const OSC9_TERM_PROGRAMS: &[&str] = &["iTerm.app", "WezTerm", "ghostty"];
+
+/// Announces finished answers the way the configuration asks
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    config: NotificationConfig,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether an answer that took `elapsed` is announced
+    pub fn should_notify(&self, focused: bool, elapsed: Duration) -> bool {
+        let long_run = self.config.long_run_secs.is_some_and(|secs| elapsed >= Duration::from_secs(secs));
+        self.config.enabled && (!focused || long_run)
+    }
+
+    /// Announce `message`, writing escape sequences to the terminal `out`
+    ///
+    /// A desktop notification that cannot be sent rings the bell instead.
+    pub fn notify(&self, message: &str, out: &mut impl Write) -> Result<()> {
+        let message = sanitize(message);
+        match self.method_with(|name| std::env::var(name).ok()) {
+            NotificationMethod::Osc9 => write!(out, "\x1b]9;{}: {}\x07", TITLE, message)?,
+            NotificationMethod::Desktop => {
+                if let Err(e) = desktop_notification(&message) {
+                    debug!("Desktop notification not sent: {:#}", e);
+                    out.write_all(b"\x07")?;
+                }
+            }
+            NotificationMethod::Bell | NotificationMethod::Auto => out.write_all(b"\x07")?,
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// The configured method, `auto` resolved with environment variables
+    /// looked up by `var`
+    fn method_with(&self, var: impl Fn(&str) -> Option<String>) -> NotificationMethod {
+        if self.config.method != NotificationMethod::Auto {
+            return self.config.method;
+        }
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let term = var("TERM").unwrap_or_default();
+        if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+            return NotificationMethod::Bell;
+        }
+        let shows_osc9 = var("TERM_PROGRAM").is_some_and(|program| OSC9_TERM_PROGRAMS.contains(&program.as_str()))
+            || var("WT_SESSION").is_some()
+            || var("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term.contains("ghostty");
+        if shows_osc9 {
+            NotificationMethod::Osc9
+        } else {
+            NotificationMethod::Bell
+        }
+    }
+}
+
+/// The message on one line, without control characters that would end the
+/// escape sequence early
+fn sanitize(message: &str) -> String {
+    let line: String = message.chars().map(|c| if c.is_control() { ' ' } else { c }).take(MAX_MESSAGE_CHARS).collect();
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn desktop_notification(message: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!("display notification {} with title {}", quote(message), quote(TITLE)));
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name").arg(TITLE).arg(TITLE).arg(message);
+        command
+    } else {
+        bail!("No desktop notifications on this platform");
+    };
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+    // Reaped in the background, the interface doesn't wait for the desktop
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_method(method: NotificationMethod) -> Notifier {
+        Notifier::new(NotificationConfig {
+            method,
+            ..NotificationConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_answers_are_announced_when_away_or_after_long_runs() {
+        let notifier = with_method(NotificationMethod::Bell);
+        assert!(notifier.should_notify(false, Duration::from_secs(1)));
+        assert!(notifier.should_notify(true, Duration::from_secs(60)));
+        assert!(!notifier.should_notify(true, Duration::from_secs(5)));
+
+        let disabled = Notifier::new(NotificationConfig {
+            enabled: false,
+            ..NotificationConfig::default()
+        });
+        assert!(!disabled.should_notify(false, Duration::from_secs(60)));
+
+        let mut out = Vec::new();
+        notifier.notify("Answer ready", &mut out).unwrap();
+        assert_eq!(out, b"\x07");
+        let mut out = Vec::new();
+        with_method(NotificationMethod::Osc9).notify("Answer\x07 ready\nin 40s", &mut out).unwrap();
+        assert_eq!(out, b"\x1b]9;Goofy: Answer ready in 40s\x07");
+    }
+
+    #[test]
+    fn test_auto_uses_osc9_in_terminals_showing_it() {
+        let probe = |vars: &[(&str, &str)]| {
+            with_method(NotificationMethod::Auto).method_with(|name| {
+                vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(probe(&[("TERM_PROGRAM", "iTerm.app")]), NotificationMethod::Osc9);
+        assert_eq!(probe(&[("TERM", "xterm-kitty")]), NotificationMethod::Osc9);
+        assert_eq!(probe(&[("WT_SESSION", "1"), ("TMUX", "/tmp/tmux")]), NotificationMethod::Bell);
+        assert_eq!(probe(&[("TERM", "xterm-256color")]), NotificationMethod::Bell);
+        assert_eq!(with_method(NotificationMethod::Desktop).method_with(|_| None), NotificationMethod::Desktop);
+    }
+}
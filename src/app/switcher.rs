@@ -0,0 +1,253 @@
+//! Switching the model of a running session
+//!
+//! `/model` in the chat moves the conversation to another model, or another
+//! provider, without restarting: the provider is rebuilt from the
+//! configuration with the new model and the conversation carries on with the
+//! messages it has. Answers record the model that wrote them, so a session
+//! that switched along the way still tells which model said what.
+
+use anyhow::{bail, Result};
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::llm::models::{self, KnownModel, MessageModel};
+use crate::llm::{compatible, openrouter, ProviderFactory};
+use crate::session::Conversation;
+
+use super::App;
+
+const MODEL_COMMAND: &str = "/model";
+
+/// Providers serving the models of many vendors under their own names
+const AGGREGATORS: &[&str] = &["openrouter", "openai_compatible"];
+
+/// A model the provider's server lists besides the catalog's
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServedModel {
+    pub model: MessageModel,
+    pub name: String,
+}
+
+/// Rebuilds the provider of a conversation for another model
+pub struct ModelSwitcher {
+    config: Mutex<Config>,
+}
+
+impl ModelSwitcher {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: Mutex::new(config),
+        }
+    }
+
+    /// Provider and model new turns go to
+    pub fn current(&self) -> MessageModel {
+        let config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        MessageModel::new(config.provider.clone(), config.model.clone())
+    }
+
+    /// Catalog models able to call tools, which the agent needs
+    pub fn choices(&self) -> Vec<&'static KnownModel> {
+        models::catalog().iter().filter(|known| known.capabilities.tools).collect()
+    }
+
+    /// Whether the current provider's server lists the models it serves
+    pub fn lists_models(&self) -> bool {
+        AGGREGATORS.contains(&self.current().provider.as_str())
+    }
+
+    /// List the models the current provider's server serves, leaving out
+    /// those the catalog knows can't call tools
+    ///
+    /// Compatible servers list theirs below their base URL, and OpenRouter's
+    /// prices go to the cost tracker on the way. The listing works on a copy
+    /// of the configuration, so the picker stays usable while it runs.
+    pub fn listing(&self) -> impl Future<Output = Result<Vec<ServedModel>>> + Send + 'static {
+        let config = self.config.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        async move {
+            let served: Vec<ServedModel> = match config.provider.as_str() {
+                "openrouter" => openrouter::list_models(config.base_url.as_deref())
+                    .await?
+                    .into_iter()
+                    .map(|listed| {
+                        if let Some(pricing) = listed.pricing() {
+                            models::register_pricing(&listed.id, pricing);
+                        }
+                        ServedModel {
+                            model: MessageModel::new("openrouter", listed.id),
+                            name: listed.name,
+                        }
+                    })
+                    .collect(),
+                "openai_compatible" => compatible::list_models(
+                    config.base_url.as_deref().unwrap_or_default(),
+                    config.api_key.as_deref(),
+                    &config.extra_headers,
+                )
+                .await?
+                .into_iter()
+                .map(|listed| ServedModel {
+                    model: MessageModel::new("openai_compatible", listed.id.clone()),
+                    name: listed.id,
+                })
+                .collect(),
+                _ => Vec::new(),
+            };
+            // Models the catalog doesn't know may call tools, so they stay
+            Ok(served
+                .into_iter()
+                .filter(|served| models::lookup(&served.model.model).map_or(true, |known| known.capabilities.tools))
+                .collect())
+        }
+    }
+
+    /// Move the conversation to the model `/model` names
+    ///
+    /// On failure, for instance when the new provider has no API key, the
+    /// conversation stays with the model it had.
+    pub fn switch(&self, conversation: &Conversation, args: &str) -> Result<MessageModel> {
+        let target = self.parse(args)?;
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        config.switch_model(&target.provider, &target.model);
+        if !config.has_api_key() {
+            bail!("No API key configured for {}", target.provider);
+        }
+        let provider = App::create_provider(&config)?;
+        conversation.switch_provider(provider);
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+        Ok(target)
+    }
+
+    /// Read `<model>` or `<provider> <model>`
+    ///
+    /// A model on its own stays with the current provider, unless the catalog
+    /// knows it from another one. Aggregators keep every model, since they
+    /// serve other vendors' models under the same names.
+    fn parse(&self, args: &str) -> Result<MessageModel> {
+        let current = self.current();
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [provider, model] if ProviderFactory::available_providers().contains(provider) => {
+                Ok(MessageModel::new(*provider, *model))
+            }
+            [provider, _] => bail!(
+                "Unknown provider '{}'; use one of {}",
+                provider,
+                ProviderFactory::available_providers().join(", ")
+            ),
+            [model] => {
+                let provider = match models::lookup(model) {
+                    Some(known) if !AGGREGATORS.contains(&current.provider.as_str()) => known.provider.to_string(),
+                    _ => current.provider,
+                };
+                Ok(MessageModel::new(provider, *model))
+            }
+            _ => bail!("Usage: /model [provider] <model>"),
+        }
+    }
+}
+
+/// Whether a prompt is a `/model` command, with its arguments
+pub fn model_command(prompt: &str) -> Option<&str> {
+    let rest = prompt.trim().strip_prefix(MODEL_COMMAND)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn switcher(provider: &str, model: &str) -> ModelSwitcher {
+        ModelSwitcher::new(Config {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            ..Config::default()
+        })
+    }
+
+    /// Answer one request with `body` as JSON, returning the base URL
+    async fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+            socket.shutdown().await.ok();
+        });
+        base
+    }
+
+    #[test]
+    fn test_model_commands_are_recognized() {
+        assert_eq!(model_command("/model"), Some(""));
+        assert_eq!(model_command("  /model gpt-4o "), Some("gpt-4o"));
+        assert_eq!(model_command("/models"), None);
+        assert_eq!(model_command("use /model"), None);
+    }
+
+    #[test]
+    fn test_models_find_their_provider() {
+        let openai = switcher("openai", "gpt-4o");
+        assert_eq!(openai.parse("gpt-4o-mini").unwrap(), MessageModel::new("openai", "gpt-4o-mini"));
+        assert_eq!(
+            openai.parse("claude-3-5-sonnet-latest").unwrap(),
+            MessageModel::new("anthropic", "claude-3-5-sonnet-latest")
+        );
+        assert_eq!(openai.parse("my-finetune").unwrap(), MessageModel::new("openai", "my-finetune"));
+        assert_eq!(openai.parse("gemini-2.5-flash").unwrap(), MessageModel::new("gemini", "gemini-2.5-flash"));
+        assert_eq!(openai.parse("deepseek-chat").unwrap(), MessageModel::new("deepseek", "deepseek-chat"));
+        assert_eq!(openai.parse("ollama qwen3-coder:latest").unwrap(), MessageModel::new("ollama", "qwen3-coder:latest"));
+        assert!(openai.parse("nowhere gpt-4o").is_err());
+        assert!(openai.parse("").is_err());
+
+        // OpenRouter serves other vendors' models itself
+        let openrouter = switcher("openrouter", "openai/gpt-4o");
+        assert_eq!(
+            openrouter.parse("deepseek/deepseek-chat").unwrap(),
+            MessageModel::new("openrouter", "deepseek/deepseek-chat")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compatible_servers_list_their_models() {
+        let base_url = serve_once(r#"{"data":[{"id":"llama-3.3-70b","owned_by":"groq"}]}"#).await;
+        let server = ModelSwitcher::new(Config {
+            provider: "openai_compatible".to_string(),
+            model: "llama-3.3-70b".to_string(),
+            base_url: Some(base_url),
+            ..Config::default()
+        });
+        assert!(server.lists_models());
+        assert_eq!(
+            server.listing().await.unwrap(),
+            vec![ServedModel {
+                model: MessageModel::new("openai_compatible", "llama-3.3-70b"),
+                name: "llama-3.3-70b".to_string(),
+            }]
+        );
+        assert!(!switcher("openai", "gpt-4o").lists_models());
+    }
+
+    #[tokio::test]
+    async fn test_listed_models_that_cant_call_tools_are_left_out() {
+        let base_url = serve_once(r#"{"data":[{"id":"deepseek-reasoner"},{"id":"deepseek-chat"},{"id":"my-finetune"}]}"#).await;
+        let server = ModelSwitcher::new(Config {
+            provider: "openai_compatible".to_string(),
+            model: "deepseek-chat".to_string(),
+            base_url: Some(base_url),
+            ..Config::default()
+        });
+        let listed: Vec<String> = server.listing().await.unwrap().into_iter().map(|served| served.model.model).collect();
+        assert_eq!(listed, vec!["deepseek-chat", "my-finetune"]);
+    }
+}
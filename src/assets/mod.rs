@@ -0,0 +1,396 @@
+//! Shared team assets
+//!
+//! Teams publish prompt templates, modes and hook definitions in a registry:
+//! a git repository or an HTTPS location with a `manifest.json` at its root
+//! and a detached Ed25519 signature of it in `manifest.json.sig`. The
+//! manifest lists every asset with its SHA-256 digest. `goofy assets update`
+//! checks the signature against the keys trusted in the project config,
+//! fetches the assets, checks their digests and only then replaces the
+//! installed copy, so a workspace never ends up with a partial or unsigned set.
+
+pub mod registry;
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use crate::config::AssetsConfig;
+use self::registry::Registry;
+
+/// Manifest file at the root of a registry
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Detached base64 signature of the manifest
+pub const SIGNATURE_FILE: &str = "manifest.json.sig";
+
+/// Default install directory, relative to the workspace
+const DEFAULT_ASSETS_DIR: &str = ".goofy/assets";
+
+/// What an asset is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    /// Prompt template
+    Template,
+    /// Agent mode (system prompt and tool set)
+    Mode,
+    /// Hook definition
+    Hook,
+}
+
+impl fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Template => write!(f, "template"),
+            Self::Mode => write!(f, "mode"),
+            Self::Hook => write!(f, "hook"),
+        }
+    }
+}
+
+/// An asset listed in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetEntry {
+    pub kind: AssetKind,
+    pub name: String,
+    /// Location of the file relative to the registry root
+    pub path: String,
+    /// Hex SHA-256 digest of the file
+    pub sha256: String,
+}
+
+/// Index of the assets published by a registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetManifest {
+    /// Free-form release label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<AssetEntry>,
+}
+
+impl AssetManifest {
+    /// Parse a manifest and reject entries that could escape the install directory
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let manifest: Self = serde_json::from_slice(bytes).context("Invalid asset manifest")?;
+        let mut seen = HashSet::new();
+        for entry in &manifest.assets {
+            if !is_safe_relative_path(&entry.path) {
+                anyhow::bail!("Asset '{}' has an invalid path: {}", entry.name, entry.path);
+            }
+            if !seen.insert((entry.kind, entry.name.as_str())) {
+                anyhow::bail!("Duplicate {} '{}' in asset manifest", entry.kind, entry.name);
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// Error returned when no trusted key signed the manifest
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("The asset manifest is not signed by any trusted key")]
+pub struct UntrustedManifest;
+
+/// Check the detached signature of a manifest against the trusted keys
+pub fn verify_signature(manifest: &[u8], signature: &[u8], trusted_keys: &[String]) -> Result<()> {
+    let signature = BASE64
+        .decode(String::from_utf8_lossy(signature).trim())
+        .context("Manifest signature is not valid base64")?;
+    let signature = Signature::from_slice(&signature).context("Malformed manifest signature")?;
+
+    for key in trusted_keys {
+        let key = parse_public_key(key)?;
+        if key.verify_strict(manifest, &signature).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(UntrustedManifest.into())
+}
+
+/// Decode a base64 Ed25519 public key
+fn parse_public_key(key: &str) -> Result<VerifyingKey> {
+    let bytes = BASE64
+        .decode(key.trim())
+        .with_context(|| format!("Trusted key is not valid base64: {}", key))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Trusted key must be 32 bytes: {}", key))?;
+    VerifyingKey::from_bytes(&bytes).with_context(|| format!("Invalid trusted key: {}", key))
+}
+
+/// Hex SHA-256 digest of some content
+pub fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether a path stays inside the directory it is joined to
+fn is_safe_relative_path(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)))
+        && path != Path::new(MANIFEST_FILE)
+        && path != Path::new(SIGNATURE_FILE)
+}
+
+/// Directory the assets of a workspace are installed into
+pub fn install_dir(config: &AssetsConfig, workspace: &Path) -> PathBuf {
+    workspace.join(config.dir.as_deref().unwrap_or(Path::new(DEFAULT_ASSETS_DIR)))
+}
+
+/// Changes made by an update
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetUpdate {
+    pub version: Option<String>,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl AssetUpdate {
+    fn between(previous: &AssetManifest, current: &AssetManifest) -> Self {
+        let key = |e: &AssetEntry| format!("{} {}", e.kind, e.name);
+        let before: BTreeMap<String, &str> = previous.assets.iter().map(|e| (key(e), e.sha256.as_str())).collect();
+        let after: BTreeMap<String, &str> = current.assets.iter().map(|e| (key(e), e.sha256.as_str())).collect();
+
+        let mut update = Self {
+            version: current.version.clone(),
+            ..Self::default()
+        };
+        for (name, digest) in &after {
+            match before.get(name) {
+                None => update.added.push(name.clone()),
+                Some(old) if old != digest => update.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        update.removed = before.keys().filter(|name| !after.contains_key(*name)).cloned().collect();
+        update
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Fetch, verify and install the assets of the configured registry
+pub async fn update(config: &AssetsConfig, workspace: &Path) -> Result<AssetUpdate> {
+    let registry = config
+        .registry
+        .as_ref()
+        .context("No asset registry configured. Add an assets.registry section to goofy.json")?;
+    if config.trusted_keys.is_empty() {
+        anyhow::bail!("No trusted keys configured. Add the registry's public key to assets.trusted_keys");
+    }
+
+    let registry = Registry::open(registry).await?;
+    let manifest_bytes = registry.read(MANIFEST_FILE).await?;
+    let signature = registry.read(SIGNATURE_FILE).await?;
+    verify_signature(&manifest_bytes, &signature, &config.trusted_keys)?;
+    let manifest = AssetManifest::parse(&manifest_bytes)?;
+
+    let mut files = Vec::with_capacity(manifest.assets.len());
+    for entry in &manifest.assets {
+        let content = registry.read(&entry.path).await?;
+        if !sha256_hex(&content).eq_ignore_ascii_case(&entry.sha256) {
+            anyhow::bail!("Digest mismatch for {} '{}' ({})", entry.kind, entry.name, entry.path);
+        }
+        files.push((entry.path.as_str(), content));
+    }
+
+    let dir = install_dir(config, workspace);
+    let previous = InstalledAssets::load(&dir)?
+        .map(|installed| installed.manifest)
+        .unwrap_or_default();
+    install(&dir, &manifest_bytes, &signature, &files)?;
+    debug!("Installed {} asset(s) into {}", files.len(), dir.display());
+
+    Ok(AssetUpdate::between(&previous, &manifest))
+}
+
+/// Replace the install directory with the verified files
+///
+/// Everything is written to a sibling directory first and swapped in at the
+/// end, so a failed update leaves the previous assets in place.
+fn install(dir: &Path, manifest: &[u8], signature: &[u8], files: &[(&str, Vec<u8>)]) -> Result<()> {
+    let parent = dir.parent().context("Invalid asset directory")?;
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    let staging = tempfile::Builder::new().prefix(".assets-").tempdir_in(parent)?;
+
+    for (path, content) in files {
+        let target = staging.path().join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, content)?;
+    }
+    std::fs::write(staging.path().join(MANIFEST_FILE), manifest)?;
+    std::fs::write(staging.path().join(SIGNATURE_FILE), signature)?;
+
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)
+            .with_context(|| format!("Failed to remove old assets: {}", dir.display()))?;
+    }
+    // The emptied staging guard is dropped without complaint after the move
+    std::fs::rename(staging.path(), dir)
+        .with_context(|| format!("Failed to install assets into {}", dir.display()))?;
+    Ok(())
+}
+
+/// Assets installed in a workspace
+#[derive(Debug, Clone)]
+pub struct InstalledAssets {
+    dir: PathBuf,
+    manifest: AssetManifest,
+}
+
+impl InstalledAssets {
+    /// Load the installed assets, or `None` if nothing has been installed
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read asset manifest: {}", path.display()))?;
+        Ok(Some(Self {
+            dir: dir.to_path_buf(),
+            manifest: AssetManifest::parse(&bytes)?,
+        }))
+    }
+
+    /// Release label of the installed manifest
+    pub fn version(&self) -> Option<&str> {
+        self.manifest.version.as_deref()
+    }
+
+    /// Installed assets, optionally of one kind
+    pub fn entries(&self, kind: Option<AssetKind>) -> impl Iterator<Item = &AssetEntry> {
+        self.manifest
+            .assets
+            .iter()
+            .filter(move |entry| kind.is_none() || kind == Some(entry.kind))
+    }
+
+    /// Read an installed asset by kind and name
+    pub fn read(&self, kind: AssetKind, name: &str) -> Result<Option<String>> {
+        match self.entries(Some(kind)).find(|entry| entry.name == name) {
+            Some(entry) => Ok(Some(std::fs::read_to_string(self.dir.join(&entry.path))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether an installed file no longer matches its published digest
+    pub fn is_modified(&self, entry: &AssetEntry) -> bool {
+        std::fs::read(self.dir.join(&entry.path))
+            .map(|content| !sha256_hex(&content).eq_ignore_ascii_case(&entry.sha256))
+            .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AssetRegistry;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+    use std::process::Command;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        BASE64.encode(key.verifying_key().to_bytes())
+    }
+
+    fn sign(key: &SigningKey, manifest: &[u8]) -> Vec<u8> {
+        BASE64.encode(key.sign(manifest).to_bytes()).into_bytes()
+    }
+
+    #[test]
+    fn test_signature_must_match_a_trusted_key() {
+        let key = signing_key();
+        let manifest = br#"{"assets": []}"#;
+        let signature = sign(&key, manifest);
+
+        assert!(verify_signature(manifest, &signature, &[public_key(&key)]).is_ok());
+
+        let other = SigningKey::from_bytes(&[9; 32]);
+        let err = verify_signature(manifest, &signature, &[public_key(&other)]).unwrap_err();
+        assert!(err.downcast_ref::<UntrustedManifest>().is_some());
+
+        let tampered = br#"{"assets": [] }"#;
+        assert!(verify_signature(tampered, &signature, &[public_key(&key)]).is_err());
+    }
+
+    #[test]
+    fn test_manifest_rejects_escaping_paths() {
+        let manifest = json!({
+            "assets": [{ "kind": "hook", "name": "x", "path": "../x.sh", "sha256": "00" }]
+        });
+        assert!(AssetManifest::parse(manifest.to_string().as_bytes()).is_err());
+        assert!(!is_safe_relative_path("/etc/passwd"));
+        assert!(is_safe_relative_path("templates/review.md"));
+    }
+
+    #[tokio::test]
+    async fn test_update_installs_from_git_registry() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let key = signing_key();
+        let repo = tempfile::tempdir().unwrap();
+        let template = b"Review {{file}} for bugs.\n";
+        std::fs::create_dir_all(repo.path().join("templates")).unwrap();
+        std::fs::write(repo.path().join("templates/review.md"), template).unwrap();
+        let manifest = json!({
+            "version": "1.0",
+            "assets": [{
+                "kind": "template",
+                "name": "review",
+                "path": "templates/review.md",
+                "sha256": sha256_hex(template),
+            }]
+        })
+        .to_string();
+        std::fs::write(repo.path().join(MANIFEST_FILE), &manifest).unwrap();
+        std::fs::write(repo.path().join(SIGNATURE_FILE), sign(&key, manifest.as_bytes())).unwrap();
+        for args in [
+            &["init", "--quiet"][..],
+            &["add", "."],
+            &["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "--quiet", "-m", "assets"],
+        ] {
+            assert!(Command::new("git").args(args).current_dir(repo.path()).status().unwrap().success());
+        }
+
+        let workspace = tempfile::tempdir().unwrap();
+        let config = AssetsConfig {
+            registry: Some(AssetRegistry::Git {
+                url: repo.path().display().to_string(),
+                reference: None,
+            }),
+            trusted_keys: vec![public_key(&key)],
+            dir: None,
+        };
+
+        let first = update(&config, workspace.path()).await.unwrap();
+        assert_eq!(first.added, vec!["template review".to_string()]);
+
+        let installed = InstalledAssets::load(&install_dir(&config, workspace.path())).unwrap().unwrap();
+        assert_eq!(installed.version(), Some("1.0"));
+        assert_eq!(
+            installed.read(AssetKind::Template, "review").unwrap().as_deref(),
+            Some("Review {{file}} for bugs.\n")
+        );
+
+        let again = update(&config, workspace.path()).await.unwrap();
+        assert!(again.is_empty());
+    }
+}
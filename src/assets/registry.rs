@@ -0,0 +1,85 @@
+//! Fetching files from an asset registry
+//!
+//! Git registries are shallow-cloned into a temporary directory for the
+//! duration of an update; HTTPS registries are read file by file relative to
+//! the configured base URL. Plain HTTP is refused.
+
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::process::Command;
+
+use crate::config::AssetRegistry;
+
+/// An opened registry
+pub enum Registry {
+    Git { checkout: TempDir },
+    Https { base: reqwest::Url, client: reqwest::Client },
+}
+
+impl Registry {
+    /// Open a registry, cloning it first if it is a git repository
+    pub async fn open(config: &AssetRegistry) -> Result<Self> {
+        match config {
+            AssetRegistry::Git { url, reference } => {
+                let checkout = tempfile::Builder::new().prefix("goofy-assets-").tempdir()?;
+                let mut command = Command::new("git");
+                command.args(["clone", "--quiet", "--depth", "1"]);
+                if let Some(reference) = reference {
+                    command.args(["--branch", reference]);
+                }
+                let output = command
+                    .arg("--")
+                    .arg(url)
+                    .arg(checkout.path())
+                    .kill_on_drop(true)
+                    .output()
+                    .await
+                    .context("Failed to run git")?;
+                if !output.status.success() {
+                    anyhow::bail!(
+                        "Failed to clone asset registry {}: {}",
+                        url,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                Ok(Self::Git { checkout })
+            }
+            AssetRegistry::Https { url } => {
+                let mut base = reqwest::Url::parse(url)
+                    .with_context(|| format!("Invalid asset registry URL: {}", url))?;
+                if base.scheme() != "https" {
+                    anyhow::bail!("Asset registry URL must use https: {}", url);
+                }
+                // Make relative paths resolve below the base rather than beside it
+                if !base.path().ends_with('/') {
+                    base.set_path(&format!("{}/", base.path()));
+                }
+                let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+                Ok(Self::Https { base, client })
+            }
+        }
+    }
+
+    /// Read a file relative to the registry root
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Git { checkout } => {
+                let file = checkout.path().join(path);
+                tokio::fs::read(&file)
+                    .await
+                    .with_context(|| format!("Asset registry is missing {}", path))
+            }
+            Self::Https { base, client } => {
+                let url = base.join(path)?;
+                let response = client
+                    .get(url.clone())
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .with_context(|| format!("Failed to fetch {}", url))?;
+                Ok(response.bytes().await?.to_vec())
+            }
+        }
+    }
+}
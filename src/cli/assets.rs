@@ -0,0 +1,92 @@
+//! Assets command implementation for syncing shared team assets
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::assets::{self, AssetKind, InstalledAssets};
+use crate::config::Config;
+
+/// Manage shared prompt templates, modes and hooks
+#[derive(Debug, Args)]
+pub struct AssetsCommand {
+    #[command(subcommand)]
+    pub command: AssetsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AssetsSubcommand {
+    /// Fetch and verify assets from the configured registry
+    Update,
+    /// List installed assets
+    List {
+        /// Only list assets of this kind
+        #[arg(long, value_enum)]
+        kind: Option<KindArg>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum KindArg {
+    Template,
+    Mode,
+    Hook,
+}
+
+impl From<KindArg> for AssetKind {
+    fn from(kind: KindArg) -> Self {
+        match kind {
+            KindArg::Template => AssetKind::Template,
+            KindArg::Mode => AssetKind::Mode,
+            KindArg::Hook => AssetKind::Hook,
+        }
+    }
+}
+
+impl AssetsCommand {
+    /// Execute the assets command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        let dir = assets::install_dir(&config.assets, &config.cwd);
+
+        match &self.command {
+            AssetsSubcommand::Update => {
+                // The registry comes from project config, so only act on trusted workspaces
+                if !config.workspace_trusted {
+                    anyhow::bail!("Workspace is not trusted. Run `goofy trust add` before updating assets");
+                }
+                let update = assets::update(&config.assets, &config.cwd).await?;
+                if update.is_empty() {
+                    println!("Assets are up to date.");
+                    return Ok(());
+                }
+                for name in &update.added {
+                    println!("added    {}", name);
+                }
+                for name in &update.changed {
+                    println!("updated  {}", name);
+                }
+                for name in &update.removed {
+                    println!("removed  {}", name);
+                }
+                match &update.version {
+                    Some(version) => println!("Installed assets {} into {}", version, dir.display()),
+                    None => println!("Installed assets into {}", dir.display()),
+                }
+            }
+            AssetsSubcommand::List { kind } => {
+                let Some(installed) = InstalledAssets::load(&dir)? else {
+                    println!("No assets installed. Run `goofy assets update`.");
+                    return Ok(());
+                };
+                if let Some(version) = installed.version() {
+                    println!("Version {}", version);
+                }
+                for entry in installed.entries(kind.map(AssetKind::from)) {
+                    let marker = if installed.is_modified(entry) { "  (modified locally)" } else { "" };
+                    println!("{:<9} {:<24} {}{}", entry.kind, entry.name, entry.path, marker);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
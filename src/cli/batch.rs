@@ -0,0 +1,297 @@
+//! Batch command implementation for running many prompts from a JSONL file
+
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::app::App;
+use crate::config::Config;
+
+/// Run prompts from a JSONL file concurrently
+///
+/// Each input line is either a JSON string or an object with a `prompt` and an
+/// optional `id`. Results are written as JSONL in completion order.
+#[derive(Debug, Args)]
+pub struct BatchCommand {
+    /// Input file with one prompt per line
+    pub input: PathBuf,
+
+    /// Results file (defaults to `<input>.results.jsonl`)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Number of prompts to run concurrently
+    #[arg(short, long, default_value_t = 4)]
+    pub workers: usize,
+
+    /// Timeout per attempt in seconds
+    #[arg(short, long, default_value_t = 300)]
+    pub timeout: u64,
+
+    /// Retries after a failed or timed out attempt
+    #[arg(short, long, default_value_t = 2)]
+    pub retries: u32,
+
+    /// Suppress the progress bar
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// One prompt from the input file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum BatchInput {
+    Prompt(String),
+    Item {
+        #[serde(default)]
+        id: Option<String>,
+        prompt: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BatchItem {
+    id: String,
+    prompt: String,
+}
+
+/// Outcome of one item, written to the results file
+#[derive(Debug, Clone, Serialize)]
+struct BatchResult {
+    id: String,
+    status: BatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    attempts: u32,
+    duration_ms: u128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BatchStatus {
+    Ok,
+    Error,
+    Timeout,
+}
+
+impl BatchCommand {
+    /// Execute the batch command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        config.validate()?;
+
+        let content = tokio::fs::read_to_string(&self.input)
+            .await
+            .with_context(|| format!("Failed to read {}", self.input.display()))?;
+        let items = parse_items(&content)?;
+        if items.is_empty() {
+            anyhow::bail!("No prompts found in {}", self.input.display());
+        }
+
+        let output_path = self.output.clone().unwrap_or_else(|| default_output_path(&self.input));
+        let mut output = tokio::fs::File::create(&output_path)
+            .await
+            .with_context(|| format!("Failed to create {}", output_path.display()))?;
+
+        let app = Arc::new(App::new(config.clone()).await?);
+        let mut progress = Progress::new(items.len(), !self.quiet && std::io::stderr().is_terminal());
+
+        let mut results = stream::iter(items)
+            .map(|item| {
+                let app = app.clone();
+                async move { self.run_item(&app, item).await }
+            })
+            .buffer_unordered(self.workers.max(1));
+
+        while let Some(result) = results.next().await {
+            let mut line = serde_json::to_string(&result)?;
+            line.push('\n');
+            output.write_all(line.as_bytes()).await?;
+            progress.record(result.status);
+        }
+        output.flush().await?;
+        progress.finish();
+
+        eprintln!(
+            "{} succeeded, {} failed. Results written to {}",
+            progress.ok,
+            progress.failed,
+            output_path.display()
+        );
+        Ok(())
+    }
+
+    /// Run one item with timeout and retries
+    async fn run_item(&self, app: &App, item: BatchItem) -> BatchResult {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.timeout);
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            let (status, output, error) =
+                match tokio::time::timeout(timeout, app.run_non_interactive(&item.prompt, true)).await {
+                    Ok(Ok(output)) => (BatchStatus::Ok, Some(output), None),
+                    Ok(Err(e)) => (BatchStatus::Error, None, Some(e.to_string())),
+                    Err(_) => (BatchStatus::Timeout, None, Some(format!("Timed out after {}s", self.timeout))),
+                };
+
+            if status == BatchStatus::Ok || attempts > self.retries {
+                return BatchResult {
+                    id: item.id,
+                    status,
+                    output,
+                    error,
+                    attempts,
+                    duration_ms: started.elapsed().as_millis(),
+                };
+            }
+
+            let backoff = Duration::from_secs(1 << (attempts - 1).min(5));
+            debug!("Item {} failed (attempt {}), retrying in {:?}", item.id, attempts, backoff);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// Parse the input file, numbering items without an explicit id by line
+fn parse_items(content: &str) -> Result<Vec<BatchItem>> {
+    let mut items = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let input: BatchInput = serde_json::from_str(line)
+            .with_context(|| format!("Invalid input on line {}", index + 1))?;
+        let (id, prompt) = match input {
+            BatchInput::Prompt(prompt) => (None, prompt),
+            BatchInput::Item { id, prompt } => (id, prompt),
+        };
+        if prompt.trim().is_empty() {
+            warn!("Skipping empty prompt on line {}", index + 1);
+            continue;
+        }
+        items.push(BatchItem {
+            id: id.unwrap_or_else(|| (index + 1).to_string()),
+            prompt,
+        });
+    }
+    Ok(items)
+}
+
+fn default_output_path(input: &Path) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    input.with_file_name(format!("{}.results.jsonl", stem))
+}
+
+/// Progress bar drawn on stderr
+struct Progress {
+    total: usize,
+    ok: usize,
+    failed: usize,
+    enabled: bool,
+}
+
+impl Progress {
+    const WIDTH: usize = 30;
+
+    fn new(total: usize, enabled: bool) -> Self {
+        let progress = Self { total, ok: 0, failed: 0, enabled };
+        progress.draw();
+        progress
+    }
+
+    fn record(&mut self, status: BatchStatus) {
+        if status == BatchStatus::Ok {
+            self.ok += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.draw();
+    }
+
+    fn draw(&self) {
+        if !self.enabled {
+            return;
+        }
+        let done = self.ok + self.failed;
+        let filled = done * Self::WIDTH / self.total.max(1);
+        eprint!(
+            "\r[{}{}] {}/{} ({} failed)",
+            "█".repeat(filled),
+            "░".repeat(Self::WIDTH - filled),
+            done,
+            self.total,
+            self.failed
+        );
+        let _ = std::io::stderr().flush();
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            eprintln!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items_accepts_strings_and_objects() {
+        let content = r#""Summarize a.rs"
+
+{"id": "b", "prompt": "Summarize b.rs"}
+{"prompt": "Summarize c.rs"}
+"#;
+        let items = parse_items(content).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                BatchItem { id: "1".to_string(), prompt: "Summarize a.rs".to_string() },
+                BatchItem { id: "b".to_string(), prompt: "Summarize b.rs".to_string() },
+                BatchItem { id: "4".to_string(), prompt: "Summarize c.rs".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_items_reports_bad_line() {
+        let err = parse_items("\"ok\"\nnot json\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_default_output_path() {
+        assert_eq!(
+            default_output_path(Path::new("work/prompts.jsonl")),
+            PathBuf::from("work/prompts.results.jsonl")
+        );
+    }
+
+    #[test]
+    fn test_result_serialization() {
+        let result = BatchResult {
+            id: "1".to_string(),
+            status: BatchStatus::Timeout,
+            output: None,
+            error: Some("Timed out after 5s".to_string()),
+            attempts: 3,
+            duration_ms: 15000,
+        };
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"id":"1","status":"timeout","error":"Timed out after 5s","attempts":3,"duration_ms":15000}"#
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Config command for checking, reading and changing configuration files
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use super::schema::config_schema;
+use crate::config::credentials::{self, write_private};
+use crate::config::validate::{self, Problem, Severity};
+use crate::config::Config;
+
+/// Check, read and change the configuration
+#[derive(Debug, Args)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    pub command: ConfigSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigSubcommand {
+    /// Check a configuration file, naming the key and line of each problem
+    Validate {
+        /// File to check (defaults to the configuration file in use)
+        file: Option<PathBuf>,
+    },
+    /// Print a setting of the configuration in effect, like `tui.theme`
+    Get {
+        /// Dotted key of the setting
+        key: String,
+    },
+    /// Change a setting in a configuration file
+    Set {
+        /// Dotted key of the setting, like `tui.keybindings.quit`
+        key: String,
+
+        /// New value, as JSON or else as a string
+        value: String,
+
+        /// File to change (defaults to the configuration file in use, or the
+        /// user's)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+impl ConfigCommand {
+    /// Execute the config command
+    pub async fn execute(&self) -> Result<()> {
+        match &self.command {
+            ConfigSubcommand::Validate { file } => validate_file(file.as_deref()),
+            ConfigSubcommand::Get { key } => get(key).await,
+            ConfigSubcommand::Set { key, value, file } => set(key, value, file.as_deref()),
+        }
+    }
+}
+
+/// The configuration file goofy loads, the first of those that exist
+fn loaded_file() -> Option<PathBuf> {
+    Config::config_paths().into_iter().find(|path| path.exists())
+}
+
+fn validate_file(file: Option<&Path>) -> Result<()> {
+    let path = match file {
+        Some(path) => path.to_path_buf(),
+        None => loaded_file().context("No configuration file found; goofy runs on its defaults")?,
+    };
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let problems = validate::check(&content, &config_schema());
+    let errors = report(&path, &content, &problems);
+    if errors > 0 {
+        bail!("{} has {} error(s)", path.display(), errors);
+    }
+    println!("{} is valid", path.display());
+    Ok(())
+}
+
+async fn get(key: &str) -> Result<()> {
+    if let Some(message) = validate::unknown_key(&config_schema(), key) {
+        bail!(message);
+    }
+    let config = serde_json::to_value(Config::init().await?)?;
+    match config.pointer(&validate::pointer(key)).unwrap_or(&Value::Null) {
+        Value::String(text) => println!("{}", text),
+        value => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+fn set(key: &str, value: &str, file: Option<&Path>) -> Result<()> {
+    if key.split('.').all(str::is_empty) {
+        bail!("Name the setting to change, like tui.theme");
+    }
+    let schema = config_schema();
+    if let Some(message) = validate::unknown_key(&schema, key) {
+        bail!(message);
+    }
+    let path = match file {
+        Some(path) => path.to_path_buf(),
+        None => loaded_file()
+            .or_else(credentials::user_config_path)
+            .context("No configuration directory to write to")?,
+    };
+    let mut config = match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).with_context(|| format!("{} is not valid JSON", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Value::Object(Map::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+    set_value(&mut config, key, value).with_context(|| format!("Could not set {} in {}", key, path.display()))?;
+
+    // Only the setting's own problems stop it being written
+    let content = serde_json::to_string_pretty(&config)?;
+    let problems: Vec<Problem> = validate::check(&content, &schema)
+        .into_iter()
+        .filter(|problem| problem.key == key || problem.key.starts_with(&format!("{}.", key)))
+        .collect();
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}: {}", key, problem.message);
+        }
+        bail!("{} was not changed", path.display());
+    }
+
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    // The file may hold API keys
+    write_private(&path, &content).with_context(|| format!("Could not write {}", path.display()))?;
+    println!("Set {} in {}", key, path.display());
+    Ok(())
+}
+
+/// Set the dotted `key` of `config`, creating the objects on the way
+fn set_value(config: &mut Value, key: &str, value: Value) -> Result<()> {
+    let mut segments: Vec<&str> = key.split('.').filter(|segment| !segment.is_empty()).collect();
+    let last = segments.pop().context("No key given")?;
+    let mut current = config;
+    for segment in segments {
+        current = match current {
+            Value::Object(object) => object.entry(segment).or_insert_with(|| Value::Object(Map::new())),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get_mut(index)).with_context(
+                || format!("'{}' is not an index of the list", segment),
+            )?,
+            _ => bail!("'{}' holds a value, not settings", segment),
+        };
+    }
+    match current {
+        Value::Object(object) => {
+            object.insert(last.to_string(), value);
+        }
+        Value::Array(items) => {
+            let slot = last.parse::<usize>().ok().and_then(|index| items.get_mut(index));
+            *slot.with_context(|| format!("'{}' is not an index of the list", last))? = value;
+        }
+        _ => bail!("The parent of '{}' holds a value, not settings", last),
+    }
+    Ok(())
+}
+
+/// Print the problems of the configuration file at `path` like a compiler,
+/// with the line each is on, and return how many are errors
+pub(super) fn report(path: &Path, content: &str, problems: &[Problem]) -> usize {
+    let lines: Vec<&str> = content.lines().collect();
+    for problem in problems {
+        let severity = match problem.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let key = if problem.key.is_empty() { String::new() } else { format!("{}: ", problem.key) };
+        println!("{}:{}:{}: {}: {}{}", path.display(), problem.line, problem.column, severity, key, problem.message);
+        if let Some(line) = lines.get(problem.line.saturating_sub(1)) {
+            // Keep tabs so the caret lines up
+            let indent: String = line
+                .chars()
+                .take(problem.column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            println!("{:>5} | {}", problem.line, line);
+            println!("      | {}^", indent);
+        }
+    }
+    problems.iter().filter(|problem| problem.severity == Severity::Error).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_creates_objects_on_the_way() {
+        let mut config = json!({ "model": "gpt-4o", "roots": [{ "path": "a" }] });
+        set_value(&mut config, "tui.keybindings.quit", Value::from("ctrl+x")).unwrap();
+        set_value(&mut config, "roots.0.path", Value::from("b")).unwrap();
+        assert_eq!(config["tui"]["keybindings"]["quit"], "ctrl+x");
+        assert_eq!(config["roots"][0]["path"], "b");
+        assert_eq!(config["model"], "gpt-4o");
+
+        assert!(set_value(&mut config, "model.name", Value::from("x")).is_err());
+        assert!(set_value(&mut config, "roots.3.path", Value::from("x")).is_err());
+    }
+}
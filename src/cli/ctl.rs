@@ -0,0 +1,86 @@
+//! Ctl command implementation for driving a running instance over the control socket
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::control::{ControlClient, ControlCommand};
+
+/// Send commands to a running Goofy instance
+#[derive(Debug, Args)]
+pub struct CtlCommand {
+    /// Control socket path (defaults to the configured socket)
+    #[arg(short, long)]
+    pub socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: CtlSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CtlSubcommand {
+    /// Send a command and print the response
+    Send {
+        #[command(subcommand)]
+        action: CtlAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CtlAction {
+    /// Check that the instance is running
+    Ping,
+    /// Open an existing session
+    OpenSession {
+        /// Session ID
+        session_id: String,
+    },
+    /// Insert text into the prompt editor
+    InsertText {
+        /// Text to insert
+        text: Vec<String>,
+    },
+    /// Run a prompt and print the result
+    RunPrompt {
+        /// Prompt to run
+        prompt: Vec<String>,
+    },
+}
+
+impl From<&CtlAction> for ControlCommand {
+    fn from(action: &CtlAction) -> Self {
+        match action {
+            CtlAction::Ping => ControlCommand::Ping,
+            CtlAction::OpenSession { session_id } => ControlCommand::OpenSession {
+                session_id: session_id.clone(),
+            },
+            CtlAction::InsertText { text } => ControlCommand::InsertText { text: text.join(" ") },
+            CtlAction::RunPrompt { prompt } => ControlCommand::RunPrompt { prompt: prompt.join(" ") },
+        }
+    }
+}
+
+impl CtlCommand {
+    /// Execute the ctl command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        let socket_path = self.socket.clone().unwrap_or_else(|| config.control_socket_path());
+        let client = ControlClient::new(socket_path);
+
+        match &self.command {
+            CtlSubcommand::Send { action } => {
+                let response = client.send(action.into()).await?;
+                if !response.ok {
+                    anyhow::bail!(response.error.unwrap_or_else(|| "Command failed".to_string()));
+                }
+                match response.result {
+                    Some(serde_json::Value::String(text)) => println!("{}", text),
+                    Some(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+                    None => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! Doctor command implementation for diagnosing configuration problems
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::config::{env, trust::TrustStore, Config};
+
+/// Diagnose configuration and environment problems
+#[derive(Debug, Args)]
+pub struct DoctorCommand {}
+
+impl DoctorCommand {
+    /// Execute the doctor command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        println!("Configuration");
+        println!("  Provider:       {}", display_or_unset(&config.provider));
+        println!("  Model:          {}", display_or_unset(&config.model));
+        println!("  API key:        {}", if config.has_api_key() { "set" } else { "missing" });
+        if let Err(e) = config.validate() {
+            println!("  Problem:        {}", e);
+        }
+
+        println!();
+        println!("Workspace");
+        println!("  Path:           {}", config.cwd.display());
+        println!("  Trusted:        {}", if config.workspace_trusted { "yes" } else { "no" });
+        let workspace = config.workspace();
+        for root in workspace.roots().iter().skip(1) {
+            println!("  Extra root:     {} ({})", root.name, root.path.display());
+        }
+
+        let store = TrustStore::load_default()?;
+        println!(
+            "  Project .env:   {}",
+            if store.env_enabled(&config.cwd) { "enabled" } else { "disabled (enable with `goofy trust env`)" }
+        );
+
+        println!();
+        println!("Environment files");
+        let loaded = env::loaded_env_files();
+        if loaded.is_empty() {
+            println!("  (none loaded)");
+        }
+        for path in loaded {
+            println!("  {}", path.display());
+        }
+
+        println!();
+        println!("Storage");
+        println!("  Data directory: {}", config.data_dir.display());
+        println!("  Sessions:       {:?}", config.storage.backend);
+        if let Some(s3) = &config.storage.s3 {
+            println!("  Remote bucket:  {}", s3.bucket);
+        }
+
+        Ok(())
+    }
+}
+
+fn display_or_unset(value: &str) -> &str {
+    if value.is_empty() {
+        "(not set)"
+    } else {
+        value
+    }
+}
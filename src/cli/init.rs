@@ -0,0 +1,45 @@
+//! Init command for writing the project memory file
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::config::prompt;
+
+/// File the project memory is written to
+const MEMORY_FILE: &str = "GOOFY.md";
+
+/// Write a GOOFY.md describing the project, read into every session here
+#[derive(Debug, Args)]
+pub struct InitCommand {
+    /// Replace an existing GOOFY.md
+    #[arg(long)]
+    pub force: bool,
+
+    /// Print the file instead of writing it
+    #[arg(long, conflicts_with = "force")]
+    pub print: bool,
+}
+
+impl InitCommand {
+    /// Execute the init command
+    pub async fn execute(&self) -> Result<()> {
+        let root = std::env::current_dir()?;
+        let memory = prompt::project_memory(&root);
+        if self.print {
+            print!("{}", memory);
+            return Ok(());
+        }
+
+        let path = root.join(MEMORY_FILE);
+        if path.exists() && !self.force {
+            bail!("{} already exists; use --force to replace it", path.display());
+        }
+        std::fs::write(&path, memory)?;
+        println!("Wrote {}", path.display());
+        if root.join("CLAUDE.md").exists() {
+            println!("CLAUDE.md is read as well; move what it says into {} to keep one file.", MEMORY_FILE);
+        }
+        println!("Fill in the layout and conventions; goofy reads the file in trusted workspaces.");
+        Ok(())
+    }
+}
@@ -4,17 +4,18 @@ use clap::{Args, Subcommand};
 use anyhow::{Context, Result};
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Seek, SeekFrom},
-    path::PathBuf,
+    io::{BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
     time::Duration,
 };
 use tokio::{
     fs,
-    time::{interval, sleep},
+    time::interval,
 };
 use notify::{Watcher, RecursiveMode, recommended_watcher};
 use serde_json::Value;
 use crate::config::Config;
+use crate::utils::logging::{self, LOG_FILE};
 
 /// View and manage Goofy logs
 #[derive(Debug, Args)]
@@ -31,6 +32,10 @@ pub struct LogsCommand {
     #[arg(short, long)]
     pub level: Option<String>,
 
+    /// Show only the logs of a session, or of sessions starting with this ID
+    #[arg(short, long, value_name = "SESSION_ID")]
+    pub session: Option<String>,
+
     /// Export logs to a file
     #[arg(short, long)]
     pub export: Option<PathBuf>,
@@ -43,7 +48,7 @@ pub struct LogsCommand {
     #[arg(long)]
     pub until: Option<String>,
 
-    /// Output format (text, json)
+    /// Output format (text, pretty, json)
     #[arg(long, default_value = "text")]
     pub format: LogFormat,
 
@@ -54,7 +59,11 @@ pub struct LogsCommand {
 
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum LogFormat {
+    /// A line per entry, its fields as `key=value`
     Text,
+    /// A line per entry and one more per field
+    Pretty,
+    /// The entries as written
     Json,
 }
 
@@ -79,18 +88,18 @@ impl LogsCommand {
             return self.handle_subcommand(command, config).await;
         }
 
-        let log_file = self.get_log_file_path(config)?;
+        let log_dir = self.get_log_dir(config);
         
-        if !log_file.exists() {
-            eprintln!("No log file found at: {}", log_file.display());
+        if logging::log_files(&log_dir).is_empty() && !self.follow {
+            eprintln!("No log files found in: {}", log_dir.display());
             eprintln!("Make sure Goofy has been run at least once to generate logs.");
             return Ok(());
         }
 
         if self.follow {
-            self.follow_logs(&log_file).await
+            self.follow_logs(&log_dir).await
         } else {
-            self.show_logs(&log_file).await
+            self.show_logs(&log_dir).await
         }
     }
 
@@ -98,14 +107,23 @@ impl LogsCommand {
     async fn handle_subcommand(&self, command: &LogsSubcommand, config: &Config) -> Result<()> {
         match command {
             LogsSubcommand::Clear => {
-                let log_file = self.get_log_file_path(config)?;
-                if log_file.exists() {
-                    fs::write(&log_file, "").await
-                        .with_context(|| format!("Failed to clear log file: {}", log_file.display()))?;
-                    println!("Log file cleared: {}", log_file.display());
-                } else {
-                    println!("No log file found to clear.");
+                let log_dir = self.get_log_dir(config);
+                let log_files = logging::log_files(&log_dir);
+                if log_files.is_empty() {
+                    println!("No log files found to clear.");
+                    return Ok(());
+                }
+                for log_file in &log_files {
+                    // The current file is emptied rather than removed, since
+                    // a running instance keeps writing to it
+                    if log_file.ends_with(LOG_FILE) {
+                        fs::write(log_file, "").await
+                    } else {
+                        fs::remove_file(log_file).await
+                    }
+                    .with_context(|| format!("Failed to clear log file: {}", log_file.display()))?;
                 }
+                println!("Log files cleared: {}", log_dir.display());
                 Ok(())
             }
             LogsSubcommand::Archive { older_than_days } => {
@@ -117,19 +135,25 @@ impl LogsCommand {
         }
     }
 
-    /// Get the log file path from configuration
-    fn get_log_file_path(&self, config: &Config) -> Result<PathBuf> {
-        let log_dir = config.data_dir.join("logs");
-        Ok(log_dir.join("goofy.log"))
+    /// Get the log directory from configuration
+    fn get_log_dir(&self, config: &Config) -> PathBuf {
+        logging::log_dir(&config.data_dir)
     }
 
-    /// Show logs from file
-    async fn show_logs(&self, log_file: &PathBuf) -> Result<()> {
-        let file = File::open(log_file)
-            .with_context(|| format!("Failed to open log file: {}", log_file.display()))?;
-        
-        let lines = self.read_tail_lines(file)?;
-        let filtered_lines = self.filter_lines(lines)?;
+    /// The log files of a directory read one after the other, oldest first
+    fn open_log_files(&self, log_dir: &Path) -> Result<Box<dyn Read>> {
+        let mut reader: Box<dyn Read> = Box::new(std::io::empty());
+        for log_file in logging::log_files(log_dir) {
+            let file = File::open(&log_file)
+                .with_context(|| format!("Failed to open log file: {}", log_file.display()))?;
+            reader = Box::new(reader.chain(file));
+        }
+        Ok(reader)
+    }
+
+    /// Show logs from the log files
+    async fn show_logs(&self, log_dir: &Path) -> Result<()> {
+        let filtered_lines = self.read_tail_lines(self.open_log_files(log_dir)?)?;
 
         if let Some(ref export_path) = self.export {
             self.export_logs(&filtered_lines, export_path).await?;
@@ -147,21 +171,18 @@ impl LogsCommand {
     }
 
     /// Follow logs in real-time
-    async fn follow_logs(&self, log_file: &PathBuf) -> Result<()> {
+    async fn follow_logs(&self, log_dir: &Path) -> Result<()> {
         // First show existing tail lines
-        if log_file.exists() {
-            let file = File::open(log_file)
-                .with_context(|| format!("Failed to open log file: {}", log_file.display()))?;
-            
-            let lines = self.read_tail_lines(file)?;
-            let filtered_lines = self.filter_lines(lines)?;
-            
-            if !filtered_lines.is_empty() {
-                self.print_lines(&filtered_lines);
-                println!("\n--- Following new log entries ---\n");
-            }
+        let filtered_lines = self.read_tail_lines(self.open_log_files(log_dir)?)?;
+        if !filtered_lines.is_empty() {
+            self.print_lines(&filtered_lines);
+            println!("\n--- Following new log entries ---\n");
         }
 
+        std::fs::create_dir_all(log_dir)
+            .with_context(|| format!("Failed to create log directory: {}", log_dir.display()))?;
+        let log_file = &log_dir.join(LOG_FILE);
+
         // Set up file watcher
         let (tx, mut rx) = tokio::sync::mpsc::channel(100);
         
@@ -175,7 +196,6 @@ impl LogsCommand {
         })?;
 
         // Watch the log file directory
-        let log_dir = log_file.parent().unwrap_or_else(|| std::path::Path::new("."));
         _watcher.watch(log_dir, RecursiveMode::NonRecursive)?;
 
         let mut last_position = if log_file.exists() {
@@ -192,19 +212,13 @@ impl LogsCommand {
                 _ = rx.recv() => {
                     // File changed, check for new content
                     if let Ok(new_lines) = self.read_new_lines(log_file, &mut last_position).await {
-                        if !new_lines.is_empty() {
-                            let filtered_lines = self.filter_lines(new_lines)?;
-                            self.print_lines(&filtered_lines);
-                        }
+                        self.print_lines(&self.filter_lines(new_lines)?);
                     }
                 }
                 _ = interval.tick() => {
                     // Periodic check for new content (fallback)
                     if let Ok(new_lines) = self.read_new_lines(log_file, &mut last_position).await {
-                        if !new_lines.is_empty() {
-                            let filtered_lines = self.filter_lines(new_lines)?;
-                            self.print_lines(&filtered_lines);
-                        }
+                        self.print_lines(&self.filter_lines(new_lines)?);
                     }
                 }
                 _ = tokio::signal::ctrl_c() => {
@@ -217,13 +231,14 @@ impl LogsCommand {
         Ok(())
     }
 
-    /// Read the last N lines from a file
-    fn read_tail_lines(&self, mut file: File) -> Result<Vec<String>> {
-        let reader = BufReader::new(&mut file);
+    /// Read the last N lines passing the filters
+    fn read_tail_lines(&self, reader: impl Read) -> Result<Vec<String>> {
+        let reader = BufReader::new(reader);
         
         let all_lines: Vec<String> = reader.lines()
             .collect::<std::io::Result<Vec<_>>>()
             .context("Failed to read lines from log file")?;
+        let all_lines = self.filter_lines(all_lines)?;
 
         let lines = if all_lines.len() <= self.tail {
             all_lines
@@ -238,27 +253,33 @@ impl LogsCommand {
     }
 
     /// Read new lines from a specific position
-    async fn read_new_lines(&self, log_file: &PathBuf, last_position: &mut u64) -> Result<Vec<String>> {
+    ///
+    /// A file smaller than the position was rotated or cleared, and is read
+    /// from its start.
+    async fn read_new_lines(&self, log_file: &Path, last_position: &mut u64) -> Result<Vec<String>> {
         if !log_file.exists() {
             return Ok(Vec::new());
         }
 
         let current_size = fs::metadata(log_file).await?.len();
+        if current_size < *last_position {
+            *last_position = 0;
+        }
         
-        if current_size <= *last_position {
+        if current_size == *last_position {
             return Ok(Vec::new());
         }
 
         let mut file = File::open(log_file)?;
         file.seek(SeekFrom::Start(*last_position))?;
         
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines()
-            .collect::<std::io::Result<Vec<_>>>()?;
-
-        *last_position = current_size;
+        // A line still being written waits for its end
+        let mut content = String::new();
+        BufReader::new(file).read_to_string(&mut content)?;
+        let complete = content.rfind('\n').map_or(0, |end| end + 1);
+        *last_position += complete as u64;
         
-        Ok(lines)
+        Ok(content[..complete].lines().map(String::from).collect())
     }
 
     /// Filter lines based on criteria
@@ -267,21 +288,33 @@ impl LogsCommand {
 
         // Filter by log level
         if let Some(ref level) = self.level {
-            filtered = filtered.into_iter()
-                .filter(|line| self.line_matches_level(line, level))
-                .collect();
+            filtered.retain(|line| self.line_matches_level(line, level));
+        }
+
+        // Filter by session
+        if let Some(ref session) = self.session {
+            filtered.retain(|line| self.line_matches_session(line, session));
         }
 
         // Filter by date range
         if self.since.is_some() || self.until.is_some() {
-            filtered = filtered.into_iter()
-                .filter(|line| self.line_matches_date_range(line))
-                .collect();
+            filtered.retain(|line| self.line_matches_date_range(line));
         }
 
         Ok(filtered)
     }
 
+    /// Check if a log entry was written during a session
+    ///
+    /// Entries name their session in the `session_id` field, set by the
+    /// span of the turn they happened in.
+    fn line_matches_session(&self, line: &str, session: &str) -> bool {
+        serde_json::from_str::<Value>(line)
+            .ok()
+            .and_then(|parsed| parsed.pointer("/fields/session_id")?.as_str().map(|id| id.starts_with(session)))
+            .unwrap_or(false)
+    }
+
     /// Check if a log line matches the specified level
     fn line_matches_level(&self, line: &str, level: &str) -> bool {
         // Try to parse as JSON first
@@ -296,21 +329,26 @@ impl LogsCommand {
     }
 
     /// Check if a log line matches the date range
+    ///
+    /// Entry times are RFC 3339 in UTC, so their first ten characters compare
+    /// as dates. Lines without a time are kept.
     fn line_matches_date_range(&self, line: &str) -> bool {
-        // This is a simplified implementation
-        // A full implementation would parse timestamps and compare dates
+        let Some(date) = serde_json::from_str::<Value>(line)
+            .ok()
+            .and_then(|parsed| Some(parsed.get("time")?.as_str()?.get(..10)?.to_string()))
+        else {
+            return true;
+        };
         
         if let Some(ref since) = self.since {
-            if !line.contains(since) {
-                // Simplified check - would need proper date parsing
-                return true; // Allow for now
+            if date.as_str() < since.as_str() {
+                return false;
             }
         }
         
         if let Some(ref until) = self.until {
-            if !line.contains(until) {
-                // Simplified check - would need proper date parsing
-                return true; // Allow for now
+            if date.as_str() > until.as_str() {
+                return false;
             }
         }
         
@@ -319,10 +357,11 @@ impl LogsCommand {
 
     /// Print lines with formatting
     fn print_lines(&self, lines: &[String]) {
+        let color = std::io::stdout().is_terminal();
         for line in lines {
             match self.format {
-                LogFormat::Text => {
-                    self.print_formatted_line(line);
+                LogFormat::Text | LogFormat::Pretty => {
+                    println!("{}", self.format_line(line, color));
                 }
                 LogFormat::Json => {
                     println!("{}", line);
@@ -331,37 +370,50 @@ impl LogsCommand {
         }
     }
 
-    /// Print a formatted log line
-    fn print_formatted_line(&self, line: &str) {
+    /// A log line as text, its structured fields after the message in text
+    /// format and on lines of their own in pretty format
+    fn format_line(&self, line: &str, color: bool) -> String {
         // Try to parse as JSON and format nicely
-        if let Ok(parsed) = serde_json::from_str::<Value>(line) {
-            if let Some(timestamp) = parsed.get("time").and_then(|v| v.as_str()) {
-                if let Some(level) = parsed.get("level").and_then(|v| v.as_str()) {
-                    if let Some(msg) = parsed.get("msg").and_then(|v| v.as_str()) {
-                        // Format: [TIME] LEVEL: MESSAGE
-                        let time_part = if timestamp.len() > 19 {
-                            &timestamp[11..19] // Extract HH:MM:SS
-                        } else {
-                            timestamp
-                        };
-                        
-                        let level_colored = match level.to_uppercase().as_str() {
-                            "ERROR" => format!("\x1b[31m{}\x1b[0m", level), // Red
-                            "WARN" => format!("\x1b[33m{}\x1b[0m", level),  // Yellow
-                            "INFO" => format!("\x1b[32m{}\x1b[0m", level),  // Green
-                            "DEBUG" => format!("\x1b[36m{}\x1b[0m", level), // Cyan
-                            _ => level.to_string(),
-                        };
-                        
-                        println!("[{}] {}: {}", time_part, level_colored, msg);
-                        return;
-                    }
-                }
+        let Ok(parsed) = serde_json::from_str::<Value>(line) else {
+            // Fallback to raw line
+            return line.to_string();
+        };
+        let field = |name: &str| parsed.get(name).and_then(|v| v.as_str());
+        let (Some(timestamp), Some(level), Some(msg)) = (field("time"), field("level"), field("msg")) else {
+            return line.to_string();
+        };
+
+        // Format: [TIME] LEVEL target: MESSAGE
+        let time_part = timestamp.get(11..19).unwrap_or(timestamp); // Extract HH:MM:SS
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let level_colored = match level.to_uppercase().as_str() {
+            "ERROR" => paint("31", level), // Red
+            "WARN" => paint("33", level),  // Yellow
+            "INFO" => paint("32", level),  // Green
+            "DEBUG" => paint("36", level), // Cyan
+            _ => level.to_string(),
+        };
+        let mut formatted = match field("target") {
+            Some(target) => format!("[{}] {} {}: {}", time_part, level_colored, paint("2", target), msg),
+            None => format!("[{}] {}: {}", time_part, level_colored, msg),
+        };
+
+        let fields = parsed.get("fields").and_then(|v| v.as_object()).into_iter().flatten();
+        for (name, value) in fields {
+            // Strings read better without their quotes
+            let value = value.as_str().map_or_else(|| value.to_string(), String::from);
+            match self.format {
+                LogFormat::Pretty => formatted.push_str(&format!("\n    {}: {}", paint("2", name), value)),
+                _ => formatted.push_str(&format!(" {}", paint("2", &format!("{}={}", name, value)))),
             }
         }
-        
-        // Fallback to raw line
-        println!("{}", line);
+        formatted
     }
 
     /// Export logs to a file
@@ -374,7 +426,7 @@ impl LogsCommand {
 
     /// Archive old logs
     async fn archive_logs(&self, config: &Config, older_than_days: u32) -> Result<()> {
-        let log_file = self.get_log_file_path(config)?;
+        let log_file = self.get_log_dir(config).join(LOG_FILE);
         
         if !log_file.exists() {
             println!("No log file found to archive.");
@@ -388,7 +440,7 @@ impl LogsCommand {
             .as_secs() / (24 * 60 * 60); // Convert to days
 
         if age > older_than_days as u64 {
-            let archive_name = format!("goofy-{}.log", 
+            let archive_name = format!("goofy-{}.jsonl", 
                 chrono::Utc::now().format("%Y%m%d-%H%M%S"));
             let archive_path = log_file.with_file_name(archive_name);
             
@@ -406,14 +458,16 @@ impl LogsCommand {
 
     /// Show log statistics
     async fn show_log_stats(&self, config: &Config) -> Result<()> {
-        let log_file = self.get_log_file_path(config)?;
+        let log_dir = self.get_log_dir(config);
+        let log_files = logging::log_files(&log_dir);
         
-        if !log_file.exists() {
+        if log_files.is_empty() {
             println!("No log file found.");
             return Ok(());
         }
 
-        let content = fs::read_to_string(&log_file).await?;
+        let mut content = String::new();
+        self.open_log_files(&log_dir)?.read_to_string(&mut content)?;
         let lines: Vec<&str> = content.lines().collect();
         
         let mut stats = LogStats::default();
@@ -434,15 +488,18 @@ impl LogsCommand {
             }
         }
 
-        let metadata = fs::metadata(&log_file).await?;
-        let file_size = metadata.len();
-        let modified_time = metadata.modified()?;
+        let mut file_size = 0;
+        for log_file in &log_files {
+            file_size += fs::metadata(log_file).await?.len();
+        }
+        let current = log_files.last().map(|log_file| log_file.as_path()).unwrap_or(&log_dir);
+        let modified_time = fs::metadata(current).await?.modified()?;
 
         println!("Log Statistics");
         println!("==============");
-        println!("File: {}", log_file.display());
+        println!("Files: {} in {}", log_files.len(), log_dir.display());
         println!("Size: {} bytes ({:.2} KB)", file_size, file_size as f64 / 1024.0);
-        println!("Last modified: {}", humantime::format_rfc3339_seconds(modified_time.into()));
+        println!("Last modified: {}", humantime::format_rfc3339_seconds(modified_time));
         println!("Total lines: {}", stats.total_lines);
         println!("  DEBUG: {}", stats.debug_count);
         println!("  INFO:  {}", stats.info_count);
@@ -484,6 +541,7 @@ mod tests {
             follow: false,
             tail: 3,
             level: None,
+            session: None,
             export: None,
             since: None,
             until: None,
@@ -506,6 +564,7 @@ mod tests {
             follow: false,
             tail: 100,
             level: None,
+            session: None,
             export: None,
             since: None,
             until: None,
@@ -521,4 +580,42 @@ mod tests {
         assert!(cmd.line_matches_level(plain_line, "error"));
         assert!(!cmd.line_matches_level(plain_line, "info"));
     }
+    #[test]
+    fn test_entries_filter_by_session_and_date_and_show_their_fields() {
+        let cmd = LogsCommand {
+            follow: false,
+            tail: 100,
+            level: None,
+            session: Some("s1".to_string()),
+            export: None,
+            since: Some("2024-01-02".to_string()),
+            until: None,
+            format: LogFormat::Text,
+            command: None,
+        };
+        let entry = |time: &str, session: &str| {
+            format!(
+                r#"{{"time":"{}T12:00:00.000Z","level":"WARN","target":"goofy::llm","msg":"Retrying","fields":{{"attempt":2,"session_id":"{}"}}}}"#,
+                time, session
+            )
+        };
+        let lines = vec![
+            entry("2024-01-02", "s1-abc"),
+            entry("2024-01-02", "s2-def"),
+            entry("2024-01-01", "s1-abc"),
+            "plain text".to_string(),
+        ];
+        assert_eq!(cmd.filter_lines(lines.clone()).unwrap(), [lines[0].clone()]);
+
+        assert_eq!(
+            cmd.format_line(&lines[0], false),
+            "[12:00:00] WARN goofy::llm: Retrying attempt=2 session_id=s1-abc"
+        );
+        let pretty = LogsCommand { format: LogFormat::Pretty, ..cmd };
+        assert_eq!(
+            pretty.format_line(&lines[0], false),
+            "[12:00:00] WARN goofy::llm: Retrying\n    attempt: 2\n    session_id: s1-abc"
+        );
+        assert_eq!(pretty.format_line("plain text", false), "plain text");
+    }
 }
\ No newline at end of file
@@ -2,7 +2,39 @@ mod root;
 mod run;
 mod logs;
 mod schema;
+mod trust;
+mod doctor;
+mod ctl;
+mod nvim;
+mod sync;
+mod batch;
+mod assets;
+mod permissions;
+mod stats;
+mod sessions;
+mod undo;
+mod models;
+mod init;
+mod replay;
+mod setup;
+mod config;
 
 pub use root::Cli;
 pub use logs::LogsCommand;
-pub use schema::SchemaCommand;
\ No newline at end of file
+pub use schema::SchemaCommand;
+pub use trust::TrustCommand;
+pub use doctor::DoctorCommand;
+pub use ctl::CtlCommand;
+pub use nvim::NvimServerCommand;
+pub use sync::SyncCommand;
+pub use batch::BatchCommand;
+pub use assets::AssetsCommand;
+pub use permissions::PermissionsCommand;
+pub use stats::StatsCommand;
+pub use sessions::SessionsCommand;
+pub use undo::UndoCommand;
+pub use models::ModelsCommand;
+pub use init::InitCommand;
+pub use replay::ReplayCommand;
+pub use setup::SetupCommand;
+pub use config::ConfigCommand;
\ No newline at end of file
@@ -0,0 +1,106 @@
+//! Models command for listing the models goofy knows about
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::fmt::Write;
+
+use crate::llm::{
+    models::{self, Capabilities, KnownModel},
+    tokenizer::format_tokens,
+};
+
+/// Show known models and what they can do
+#[derive(Debug, Args)]
+pub struct ModelsCommand {
+    #[command(subcommand)]
+    pub command: ModelsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ModelsSubcommand {
+    /// List models with their context window, capabilities and price
+    List {
+        /// Only models of this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only models that can call tools
+        #[arg(long)]
+        tools: bool,
+
+        /// Only models that can read images
+        #[arg(long)]
+        vision: bool,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl ModelsCommand {
+    /// Execute the models command
+    pub async fn execute(&self) -> Result<()> {
+        match &self.command {
+            ModelsSubcommand::List { provider, tools, vision, json } => {
+                let needs = Capabilities { tools: *tools, vision: *vision };
+                let listed: Vec<&KnownModel> = models::catalog()
+                    .iter()
+                    .filter(|model| provider.as_deref().map_or(true, |provider| model.provider == provider))
+                    .filter(|model| model.capabilities.covers(needs))
+                    .collect();
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&listed)?);
+                } else if listed.is_empty() {
+                    println!("No known models match.");
+                } else {
+                    print!("{}", render_table(&listed));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plain-text table of models
+fn render_table(listed: &[&KnownModel]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<28} {:<10} {:>8} {:<5} {:<6} {:>15}",
+        "Model", "Provider", "Context", "Tools", "Vision", "$/M in / out"
+    );
+    for model in listed {
+        let price = model
+            .pricing
+            .map_or("local".to_string(), |pricing| format!("{:.2} / {:.2}", pricing.input, pricing.output));
+        let _ = writeln!(
+            out,
+            "{:<28} {:<10} {:>8} {:<5} {:<6} {:>15}",
+            model.id,
+            model.provider,
+            format_tokens(model.context_window as usize),
+            if model.capabilities.tools { "yes" } else { "no" },
+            if model.capabilities.vision { "yes" } else { "no" },
+            price
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_rows() {
+        let listed = [models::lookup("gpt-4o-mini").unwrap(), models::lookup("codellama").unwrap()];
+        let table = render_table(&listed);
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[1].starts_with("gpt-4o-mini") && rows[1].contains(" 128K ") && rows[1].ends_with("0.15 / 0.60"), "{}", rows[1]);
+        assert!(rows[2].contains(" no    no ") && rows[2].ends_with("local"), "{}", rows[2]);
+        assert_eq!(format_tokens(1_048_576), "1.0M");
+    }
+}
@@ -0,0 +1,37 @@
+//! Nvim-server command implementation for pairing with a Neovim session
+
+use anyhow::Result;
+use clap::Args;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::app::App;
+use crate::config::Config;
+
+/// Run a headless instance for a Neovim plugin to attach to
+///
+/// The plugin spawns this command, reads the socket path from the first line
+/// of stdout, and attaches with `attach_editor`. Edits are then applied to the
+/// editor's buffers rather than written to disk. Run `goofy trust add` in the
+/// project first; untrusted workspaces only get read-only tools.
+#[derive(Debug, Args)]
+pub struct NvimServerCommand {
+    /// Socket path to listen on (defaults to a per-process socket in the data directory)
+    #[arg(short, long)]
+    pub socket: Option<PathBuf>,
+}
+
+impl NvimServerCommand {
+    /// Execute the nvim-server command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        config.validate()?;
+
+        let socket_path = self.socket.clone().unwrap_or_else(|| {
+            config.data_dir.join(format!("nvim-{}.sock", std::process::id()))
+        });
+        info!("Starting Neovim server on {}", socket_path.display());
+
+        let mut app = App::new_with_editor(config.clone()).await?;
+        app.run_editor_server(socket_path).await
+    }
+}
@@ -0,0 +1,74 @@
+//! Permissions command for managing tools always allowed in a project
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use crate::permission::GrantStore;
+
+/// Manage tools that are always allowed in a project
+#[derive(Debug, Args)]
+pub struct PermissionsCommand {
+    /// Project directory (defaults to the current directory)
+    #[arg(long)]
+    pub project: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<PermissionsSubcommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PermissionsSubcommand {
+    /// List the tools always allowed in the project
+    List,
+    /// Ask again before running a tool
+    Revoke {
+        /// Tool to revoke
+        #[arg(required_unless_present = "all")]
+        tool: Option<String>,
+
+        /// Revoke every grant of the project
+        #[arg(long, conflicts_with = "tool")]
+        all: bool,
+    },
+}
+
+impl PermissionsCommand {
+    /// Execute the permissions command
+    pub async fn execute(&self) -> Result<()> {
+        let project = match &self.project {
+            Some(path) => path.clone(),
+            None => std::env::current_dir()?,
+        };
+        let mut store = GrantStore::load_for(&project)?;
+
+        match &self.command {
+            Some(PermissionsSubcommand::List) | None => {
+                if store.tools.is_empty() {
+                    println!("No tools are always allowed in {}.", project.display());
+                }
+                for (tool, granted_at) in &store.tools {
+                    println!("{:<24} granted {}", tool, granted_at.format("%Y-%m-%d %H:%M"));
+                }
+            }
+            Some(PermissionsSubcommand::Revoke { all: true, .. }) => {
+                let count = store.clear();
+                store.save()?;
+                println!("Revoked {} grant(s) in {}", count, project.display());
+            }
+            Some(PermissionsSubcommand::Revoke { tool: Some(tool), .. }) => {
+                if store.revoke(tool) {
+                    store.save()?;
+                    println!("Revoked grant for: {}", tool);
+                } else {
+                    println!("No grant recorded for: {}", tool);
+                }
+            }
+            Some(PermissionsSubcommand::Revoke { tool: None, .. }) => {
+                anyhow::bail!("Name a tool to revoke, or pass --all");
+            }
+        }
+
+        Ok(())
+    }
+}
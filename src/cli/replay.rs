@@ -0,0 +1,139 @@
+//! Replay command for playing back recorded sessions
+
+use anyhow::{bail, Result};
+use clap::Args;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::tui::recording::Cast;
+
+/// Slowest and fastest speeds the keys switch to
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 64.0;
+
+/// Play back a session recorded with `goofy --inline --record`
+///
+/// Space pauses, `+` and `-` double and halve the speed, `q` stops.
+#[derive(Debug, Args)]
+pub struct ReplayCommand {
+    /// Recording to play
+    pub file: PathBuf,
+
+    /// Playback speed; 2 plays twice as fast
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    /// Shorten pauses longer than this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub idle_limit: Option<f64>,
+
+    /// List the recorded app events instead of playing
+    #[arg(long)]
+    pub events: bool,
+}
+
+/// What a key pressed during playback asks for
+enum Control {
+    Continue,
+    Quit,
+}
+
+struct Player {
+    speed: f64,
+    paused: bool,
+}
+
+impl Player {
+    /// Wait `gap` recorded seconds at the current speed, answering keys
+    fn wait(&mut self, gap: f64) -> Result<Control> {
+        let mut remaining = gap;
+        loop {
+            if !self.paused && remaining <= 0.0 {
+                return Ok(Control::Continue);
+            }
+            let (paused, speed) = (self.paused, self.speed);
+            let timeout = if paused { Duration::from_secs(1) } else { Duration::from_secs_f64(remaining / speed) };
+            let started = Instant::now();
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Release {
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => return Ok(Control::Quit),
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                return Ok(Control::Quit)
+                            }
+                            KeyCode::Char(' ') => self.paused = !self.paused,
+                            KeyCode::Char('+' | '=') => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+                            KeyCode::Char('-') => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            if !paused {
+                remaining -= started.elapsed().as_secs_f64() * speed;
+            }
+        }
+    }
+
+    fn play(&mut self, cast: &Cast, idle_limit: Option<f64>) -> Result<()> {
+        let mut stdout = io::stdout();
+        let mut skipped = 0.0;
+        for (event, gap) in cast.events.iter().zip(cast.gaps(idle_limit)) {
+            // Resizes and markers show nothing, but their time passes
+            if event.kind != "o" {
+                skipped += gap;
+                continue;
+            }
+            if matches!(self.wait(std::mem::take(&mut skipped) + gap)?, Control::Quit) {
+                break;
+            }
+            stdout.write_all(event.data.as_bytes())?;
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl ReplayCommand {
+    /// Execute the replay command
+    pub async fn execute(&self) -> Result<()> {
+        if self.speed.is_nan() || self.speed <= 0.0 {
+            bail!("--speed must be more than 0");
+        }
+        let cast = Cast::load(&self.file)?;
+
+        if self.events {
+            for marker in cast.markers() {
+                println!("{:>9.3}s  {}", marker.time, marker.data);
+            }
+            return Ok(());
+        }
+
+        let (width, height) = terminal::size()?;
+        if width < cast.header.width || height < cast.header.height {
+            eprintln!(
+                "The recording is {}x{} and the terminal {}x{}; lines may wrap.",
+                cast.header.width, cast.header.height, width, height
+            );
+        }
+        let mut player = Player {
+            speed: self.speed,
+            paused: false,
+        };
+        let idle_limit = self.idle_limit;
+        let played = tokio::task::spawn_blocking(move || {
+            enable_raw_mode()?;
+            let played = player.play(&cast, idle_limit);
+            disable_raw_mode()?;
+            played
+        })
+        .await?;
+        // Leave the shell prompt on a line of its own
+        println!();
+        played
+    }
+}
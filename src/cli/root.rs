@@ -1,11 +1,32 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::{app::App, tui};
-use crate::config::Config;
+use crate::{app::{App, CustomCommands}, tui::{self, recording::Recorder}};
+use crate::config::{env, paths::{self, PathDisplay}, trust::{self, TrustStore}, Config};
+use crate::session::{prompt_history::PromptHistory, storage, SessionManager};
+use crate::utils::logging;
 use super::run::RunCommand;
+use super::trust::TrustCommand;
+use super::doctor::DoctorCommand;
+use super::ctl::CtlCommand;
+use super::nvim::NvimServerCommand;
+use super::sync::SyncCommand;
+use super::batch::BatchCommand;
+use super::assets::AssetsCommand;
+use super::permissions::PermissionsCommand;
+use super::stats::StatsCommand;
+use super::sessions::SessionsCommand;
+use super::undo::UndoCommand;
+use super::models::ModelsCommand;
+use super::init::InitCommand;
+use super::replay::ReplayCommand;
+use super::logs::LogsCommand;
+use super::setup::SetupCommand;
+use super::schema::SchemaCommand;
+use super::config::ConfigCommand;
 
 /// Goofy - The glamourous AI coding agent for your favourite terminal 💘
 #[derive(Parser)]
@@ -18,7 +39,18 @@ It provides intelligent code assistance, documentation generation, and various d
 
 Examples:
   goofy                           # Start interactive mode
+  goofy --inline                  # Chat below the prompt, keeping scrollback
+  goofy --inline --record s.cast  # Record the session, then `goofy replay s.cast`
   goofy run "explain this code"   # Run a single prompt
+  goofy run --resume <id> "next"  # Continue a stored session
+  goofy batch prompts.jsonl       # Run many prompts concurrently
+  goofy stats workspace           # Lines of code, test ratio and churn
+  goofy models list --tools       # Models that can call tools
+  goofy init                      # Write a GOOFY.md for the project
+  goofy setup                     # Pick a provider, its key and a model
+  goofy config validate           # Point at mistakes in the configuration
+  goofy config get tui.theme      # Print a setting in effect
+  goofy logs -f --level warn      # Follow warnings in the log files
   goofy --cwd /path/to/project    # Set working directory"#
 )]
 pub struct Cli {
@@ -34,6 +66,22 @@ pub struct Cli {
     #[arg(short = 'y', long = "yolo", global = true)]
     pub yolo: bool,
 
+    /// Append logs to this file instead of writing them to stderr
+    #[arg(long = "log-file", global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Chat in a few lines below the shell prompt instead of full screen
+    #[arg(long = "inline", value_name = "LINES", num_args = 0..=1, default_missing_value = "8")]
+    pub inline: Option<u16>,
+
+    /// Continue a stored session in inline mode
+    #[arg(long = "resume", value_name = "SESSION_ID", requires = "inline")]
+    pub resume: Option<String>,
+
+    /// Record the inline session to an asciicast file, for `goofy replay`
+    #[arg(long = "record", value_name = "FILE", requires = "inline")]
+    pub record: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -42,6 +90,60 @@ pub struct Cli {
 pub enum Commands {
     /// Run a single prompt non-interactively
     Run(RunCommand),
+    
+    /// Run prompts from a JSONL file concurrently
+    Batch(BatchCommand),
+    
+    /// Manage trusted workspaces
+    Trust(TrustCommand),
+    
+    /// Diagnose configuration and environment problems
+    Doctor(DoctorCommand),
+    
+    /// Send commands to a running instance
+    Ctl(CtlCommand),
+    
+    /// Run a headless instance for the Neovim plugin
+    NvimServer(NvimServerCommand),
+    
+    /// Sync sessions with remote storage
+    Sync(SyncCommand),
+    
+    /// Manage shared prompt templates, modes and hooks
+    Assets(AssetsCommand),
+    
+    /// List and revoke tools always allowed in this project
+    Permissions(PermissionsCommand),
+    
+    /// Report statistics about the workspace
+    Stats(StatsCommand),
+    
+    /// List and merge stored sessions
+    Sessions(SessionsCommand),
+    
+    /// Restore files changed during the last agent turn, or a range of turns
+    Undo(UndoCommand),
+    
+    /// List known models with their context windows, capabilities and prices
+    Models(ModelsCommand),
+    
+    /// Write a GOOFY.md describing the project for future sessions
+    Init(InitCommand),
+    
+    /// Play back a session recorded with `--record`
+    Replay(ReplayCommand),
+    
+    /// Show, follow and filter the log files
+    Logs(LogsCommand),
+    
+    /// Pick a provider, keep its key and choose a default model
+    Setup(SetupCommand),
+    
+    /// Generate the configuration's JSON Schema and check files against it
+    Schema(SchemaCommand),
+    
+    /// Check the configuration and read or change its settings
+    Config(ConfigCommand),
 }
 
 impl Cli {
@@ -58,8 +160,44 @@ impl Cli {
             info!("Changed working directory to: {}", cwd.display());
         }
 
+        // A resumed session continues in the directory it was started in
+        if self.cwd.is_none() {
+            if let Some(session_id) = self.resumed_session() {
+                restore_working_directory(session_id).await?;
+            }
+        }
+
+        // Commands that never act on the workspace skip the trust prompt
+        match &self.command {
+            Some(Commands::Trust(trust_cmd)) => return trust_cmd.execute().await,
+            Some(Commands::Permissions(permissions_cmd)) => return permissions_cmd.execute().await,
+            Some(Commands::Models(models_cmd)) => return models_cmd.execute().await,
+            Some(Commands::Init(init_cmd)) => return init_cmd.execute().await,
+            Some(Commands::Replay(replay_cmd)) => return replay_cmd.execute().await,
+            Some(Commands::Ctl(ctl_cmd)) => return ctl_cmd.execute(&Config::init().await?).await,
+            Some(Commands::Logs(logs_cmd)) => return logs_cmd.execute(&Config::init().await?).await,
+            Some(Commands::Setup(setup_cmd)) => return setup_cmd.execute(&Config::init().await?).await,
+            Some(Commands::Schema(schema_cmd)) => return schema_cmd.execute(&Config::init().await?).await,
+            Some(Commands::Config(config_cmd)) => return config_cmd.execute().await,
+            _ => {}
+        }
+
+        // Ask before acting on an unfamiliar workspace
+        let workspace = std::env::current_dir()?;
+        let workspace_trusted = trust::resolve_workspace_trust(&workspace)?;
+        debug!("Workspace trusted: {}", workspace_trusted);
+
+        // Project env files are opt-in per trusted workspace
+        let env_allowed = workspace_trusted && TrustStore::load_default()?.env_enabled(&workspace);
+        env::load_env_files(&workspace, env_allowed)?;
+
         // Initialize configuration
-        let config = Config::init().await?;
+        let mut config = Config::init().await?;
+        config.workspace_trusted = workspace_trusted;
+        paths::set_path_display(PathDisplay::from_config(&config));
+        if let Err(e) = logging::log_to_files(&config.data_dir, &config.logging) {
+            warn!("Log files not written: {:#}", e);
+        }
         debug!("Configuration initialized");
 
         match self.command {
@@ -67,10 +205,24 @@ impl Cli {
                 // Execute non-interactive run command
                 run_cmd.execute(&config, self.yolo).await
             }
-            None => {
-                // Start interactive mode
-                self.start_interactive_mode(&config).await
+            Some(Commands::Batch(batch_cmd)) => batch_cmd.execute(&config).await,
+            Some(Commands::Doctor(doctor_cmd)) => doctor_cmd.execute(&config).await,
+            Some(Commands::NvimServer(nvim_cmd)) => nvim_cmd.execute(&config).await,
+            Some(Commands::Sync(sync_cmd)) => sync_cmd.execute(&config).await,
+            Some(Commands::Assets(assets_cmd)) => assets_cmd.execute(&config).await,
+            Some(Commands::Stats(stats_cmd)) => stats_cmd.execute(&config).await,
+            Some(Commands::Sessions(sessions_cmd)) => sessions_cmd.execute(&config).await,
+            Some(Commands::Undo(undo_cmd)) => undo_cmd.execute(&config).await,
+            Some(Commands::Trust(_)) | Some(Commands::Ctl(_)) | Some(Commands::Permissions(_)) | Some(Commands::Models(_))
+            | Some(Commands::Init(_)) | Some(Commands::Replay(_)) | Some(Commands::Logs(_)) | Some(Commands::Setup(_))
+            | Some(Commands::Schema(_)) | Some(Commands::Config(_)) => {
+                unreachable!("handled before trust resolution")
             }
+            None => match self.inline {
+                Some(height) => self.start_inline_mode(&config, height, self.resume.as_deref()).await,
+                // Start interactive mode
+                None => self.start_interactive_mode(&config).await,
+            },
         }
     }
 
@@ -78,6 +230,7 @@ impl Cli {
         info!("Starting interactive mode");
         
         // Validate the configuration
+        let config = &set_up_provider(config).await?;
         config.validate()?;
         
         // Setup signal handling for graceful shutdown
@@ -93,6 +246,38 @@ impl Cli {
         Ok(())
     }
 
+    async fn start_inline_mode(&self, config: &Config, height: u16, resume: Option<&str>) -> Result<()> {
+        info!("Starting inline mode");
+        
+        let config = &set_up_provider(config).await?;
+        config.validate()?;
+        
+        let (app, prompts) = App::new_with_prompts(config.clone()).await?;
+        let (conversation, events) = match resume {
+            Some(session_id) => app.resume_session_conversation_with_events(session_id).await?,
+            None => app.start_session_conversation_with_events("Inline session").await?,
+        };
+        let history = PromptHistory::for_workspace(&config.data_dir, &config.cwd);
+        let recorder = match &self.record {
+            Some(path) => {
+                let (width, height) = crossterm::terminal::size()?;
+                let title = format!("goofy session {}", conversation.session_id);
+                Some(Recorder::create(path, width, height, Some(title))?)
+            }
+            None => None,
+        };
+        tui::inline::run(&app, conversation, height, history, events, prompts, CustomCommands::load(config), recorder).await
+    }
+
+    /// Session the command resumes, if any
+    fn resumed_session(&self) -> Option<&str> {
+        match &self.command {
+            Some(Commands::Run(run_cmd)) => run_cmd.resume.as_deref(),
+            None => self.resume.as_deref(),
+            _ => None,
+        }
+    }
+
     async fn setup_signal_handling(&self) {
         // Set up signal handling for graceful shutdown
         tokio::spawn(async {
@@ -131,4 +316,30 @@ impl Cli {
             }
         });
     }
-}
\ No newline at end of file
+}
+
+/// Run the first-run setup when no provider has a key and there is a terminal
+/// to show it on
+async fn set_up_provider(config: &Config) -> Result<Config> {
+    if config.has_api_key() || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(config.clone());
+    }
+    Ok(tui::setup::run(config).await?.unwrap_or_else(|| config.clone()))
+}
+
+/// Change to the directory a session was started in, before trust is resolved
+async fn restore_working_directory(session_id: &str) -> Result<()> {
+    let config = Config::init().await?;
+    let sessions = SessionManager::with_storage(storage::from_config(&config).await?);
+    let Some(dir) = sessions.resumable_session(session_id).await?.working_directory() else {
+        return Ok(());
+    };
+    if !dir.is_dir() {
+        warn!("Session {} was started in {}, which no longer exists", session_id, dir.display());
+        return Ok(());
+    }
+    std::env::set_current_dir(&dir)
+        .map_err(|e| anyhow::anyhow!("Failed to change directory to {}: {}", dir.display(), e))?;
+    info!("Resuming in {}", dir.display());
+    Ok(())
+}
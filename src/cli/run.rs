@@ -1,10 +1,14 @@
 use anyhow::{anyhow, Result};
 use clap::Args;
-use std::io::{self, Read};
-use tracing::{debug, info};
+use serde::Serialize;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use tracing::{debug, info, warn};
 
-use crate::app::App;
+use crate::app::{App, AppEvent, CustomCommands};
 use crate::config::Config;
+use crate::llm::images::ImageAttachment;
+use crate::session::prompt_history::{PromptHistory, PromptSource};
 
 /// Run a single prompt non-interactively
 #[derive(Args)]
@@ -12,17 +16,70 @@ pub struct RunCommand {
     /// The prompt to run. If not provided, will read from stdin
     pub prompt: Vec<String>,
 
-    /// Suppress spinner and other interactive elements
+    /// Print only the final answer, without progress or intermediate events
     #[arg(short = 'q', long = "quiet")]
     pub quiet: bool,
+
+    /// Attach an image to the prompt (repeatable)
+    #[arg(long = "image", value_name = "PATH")]
+    pub images: Vec<PathBuf>,
+
+    /// Write the answer as text, or every event of the run as JSON lines
+    #[arg(long = "output-format", value_enum, default_value = "text")]
+    pub output_format: OutputFormat,
+
+    /// Continue a stored session instead of starting a new one
+    #[arg(long = "resume", value_name = "SESSION_ID")]
+    pub resume: Option<String>,
+
+    /// Keep the prompt out of the project's prompt history
+    #[arg(long = "no-history")]
+    pub no_history: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// One line of JSON output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RunEvent {
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: serde_json::Value,
+    },
+    ToolResult {
+        id: String,
+        success: bool,
+        content: String,
+    },
+    Message {
+        role: &'static str,
+        content: String,
+    },
+    Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+        total_tokens: u32,
+        /// Cost of the session so far in US dollars, when the model is priced
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cost: Option<f64>,
+    },
+    Error {
+        message: String,
+    },
 }
 
 impl RunCommand {
     pub async fn execute(&self, config: &Config, yolo: bool) -> Result<()> {
         debug!("Executing run command");
 
-        // Get the prompt either from arguments or stdin
-        let prompt = self.get_prompt()?;
+        // Get the prompt from the arguments, stdin, or both
+        let prompt = self.get_prompt(&CustomCommands::load(config))?;
         
         if prompt.trim().is_empty() {
             return Err(anyhow!("No prompt provided. Use arguments or pipe input via stdin."));
@@ -33,29 +90,218 @@ impl RunCommand {
         // Validate the configuration
         config.validate()?;
 
+        let mut images = Vec::with_capacity(self.images.len());
+        for path in &self.images {
+            images.push(ImageAttachment::from_path(path).await?);
+        }
+
+        // Prompts typed as arguments can be recalled in the interface; piped
+        // input is left out
+        if !self.no_history {
+            let history = PromptHistory::for_workspace(&config.data_dir, &config.cwd);
+            if let Err(e) = history.record(&self.prompt.join(" "), PromptSource::Run) {
+                warn!("Prompt not added to the history: {:#}", e);
+            }
+        }
+
         // Initialize the application in non-interactive mode
-        let mut app = App::new(config.clone()).await?;
-        
-        // Run the prompt non-interactively
-        let result = app.run_non_interactive(&prompt, self.quiet).await?;
-        
-        // Output the result
-        println!("{}", result);
+        let app = App::new(config.clone()).await?;
+        let (conversation, mut events) = match &self.resume {
+            Some(session_id) => app.resume_session_conversation_with_events(session_id).await?,
+            None => app.start_session_conversation_with_events("Non-interactive session").await?,
+        };
+
+        // Progress goes to stderr; stdout carries only the answer or the events
+        if !self.quiet && self.output_format == OutputFormat::Text {
+            eprintln!("Processing prompt...");
+        }
+
+        let turn_conversation = conversation.clone();
+        let mut turn = tokio::spawn(async move { turn_conversation.send_message_with_images(prompt, images).await });
+        let mut cost = None;
+        let result = loop {
+            tokio::select! {
+                Some(event) = events.recv() => self.report(&event, &mut cost)?,
+                result = &mut turn => break result?,
+            }
+        };
+        while let Ok(event) = events.try_recv() {
+            self.report(&event, &mut cost)?;
+        }
+        // The session is named while the answer is written out
+        let title = result.is_ok().then(|| tokio::spawn(async move { conversation.generate_title().await }));
+
+        let mut stdout = io::stdout().lock();
+        match (result, self.output_format) {
+            (Ok(response), OutputFormat::Text) => write_answer(&mut stdout, &response.content)?,
+            (Ok(response), OutputFormat::Json) => {
+                write_event(&mut stdout, &RunEvent::Message {
+                    role: "assistant",
+                    content: response.content,
+                })?;
+                if !self.quiet {
+                    write_event(&mut stdout, &RunEvent::Usage {
+                        input_tokens: response.usage.input_tokens,
+                        output_tokens: response.usage.output_tokens,
+                        total_tokens: response.usage.total_tokens,
+                        cost,
+                    })?;
+                }
+            }
+            (Err(e), OutputFormat::Json) => {
+                write_event(&mut stdout, &RunEvent::Error { message: e.to_string() })?;
+                return Err(e);
+            }
+            (Err(e), OutputFormat::Text) => return Err(e),
+        }
         
+        if let Some(title) = title {
+            if let Err(e) = title.await? {
+                warn!("Session not named: {:#}", e);
+            }
+        }
         Ok(())
     }
 
-    fn get_prompt(&self) -> Result<String> {
-        if !self.prompt.is_empty() {
-            // Join all arguments into a single prompt
-            Ok(self.prompt.join(" "))
+    /// Pass on an event of the agent while the prompt runs
+    fn report(&self, event: &AppEvent, cost: &mut Option<f64>) -> Result<()> {
+        if let AppEvent::CostUpdated { total_cost, .. } = event {
+            *cost = Some(*total_cost);
+        }
+        if self.quiet {
+            return Ok(());
+        }
+        match (self.output_format, event) {
+            (OutputFormat::Json, _) => {
+                if let Some(event) = RunEvent::from_app_event(event) {
+                    write_event(&mut io::stdout().lock(), &event)?;
+                }
+            }
+            (OutputFormat::Text, AppEvent::ToolCalled { tool_name, .. }) => eprintln!("Running {}...", tool_name),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The prompt to send, with a custom command in the arguments expanded
+    fn get_prompt(&self, commands: &CustomCommands) -> Result<String> {
+        let stdin = io::stdin();
+        let piped = if stdin.is_terminal() {
+            None
         } else {
-            // Read from stdin
-            debug!("Reading prompt from stdin");
+            debug!("Reading prompt context from stdin");
             let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)
+            stdin.lock().read_to_string(&mut buffer)
                 .map_err(|e| anyhow!("Failed to read from stdin: {}", e))?;
-            Ok(buffer)
+            Some(buffer)
+        };
+        let typed = self.prompt.join(" ");
+        match commands.expand(&typed) {
+            Some(expanded) => Ok(compose_prompt(&[expanded], piped)),
+            None => Ok(compose_prompt(&self.prompt, piped)),
+        }
+    }
+}
+
+impl RunEvent {
+    /// The JSON event for an event of the agent, if it has one
+    fn from_app_event(event: &AppEvent) -> Option<Self> {
+        match event {
+            AppEvent::ToolCalled { tool_name, tool_id, arguments, .. } => Some(RunEvent::ToolCall {
+                id: tool_id.clone(),
+                name: tool_name.clone(),
+                arguments: arguments.clone(),
+            }),
+            AppEvent::ToolCompleted { tool_id, result, success, .. } => Some(RunEvent::ToolResult {
+                id: tool_id.clone(),
+                success: *success,
+                content: result.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The prompt from the arguments, with piped input as context
+///
+/// Without arguments the piped input is the prompt itself.
+fn compose_prompt(args: &[String], piped: Option<String>) -> String {
+    let prompt = args.join(" ");
+    match piped.filter(|input| !input.trim().is_empty()) {
+        None => prompt,
+        Some(input) if prompt.trim().is_empty() => input,
+        Some(input) => format!("{}\n\n<stdin>\n{}\n</stdin>", prompt, input.trim_end_matches('\n')),
+    }
+}
+
+/// Write the answer as the only output: its text ending in exactly one newline
+fn write_answer(out: &mut impl Write, answer: &str) -> io::Result<()> {
+    out.write_all(answer.trim_end_matches('\n').as_bytes())?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+/// Write an event as one line of JSON
+fn write_event(out: &mut impl Write, event: &RunEvent) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, event)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer_is_written_byte_exact() {
+        for (answer, expected) in [
+            ("42", "42\n"),
+            ("{\"ok\":true}\n", "{\"ok\":true}\n"),
+            ("  indented\n\nlines  \n\n", "  indented\n\nlines  \n"),
+            ("", "\n"),
+        ] {
+            let mut out = Vec::new();
+            write_answer(&mut out, answer).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_piped_input_becomes_context() {
+        let args = vec!["summarize".to_string(), "this".to_string()];
+        assert_eq!(compose_prompt(&args, None), "summarize this");
+        assert_eq!(compose_prompt(&args, Some(" \n".to_string())), "summarize this");
+        assert_eq!(
+            compose_prompt(&args, Some("line 1\nline 2\n".to_string())),
+            "summarize this\n\n<stdin>\nline 1\nline 2\n</stdin>"
+        );
+        assert_eq!(compose_prompt(&[], Some("just the input\n".to_string())), "just the input\n");
+    }
+
+    #[test]
+    fn test_events_are_written_as_json_lines() {
+        let event = RunEvent::from_app_event(&AppEvent::ToolCalled {
+            session_id: "s".to_string(),
+            tool_name: "bash".to_string(),
+            tool_id: "call_1".to_string(),
+            arguments: serde_json::json!({"command": "ls"}),
+        })
+        .unwrap();
+
+        let mut out = Vec::new();
+        write_event(&mut out, &event).unwrap();
+        write_event(&mut out, &RunEvent::Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            total_tokens: 15,
+            cost: None,
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"type\":\"tool_call\",\"id\":\"call_1\",\"name\":\"bash\",\"arguments\":{\"command\":\"ls\"}}\n\
+             {\"type\":\"usage\",\"input_tokens\":10,\"output_tokens\":5,\"total_tokens\":15}\n"
+        );
+        assert!(RunEvent::from_app_event(&AppEvent::Shutdown).is_none());
+    }
+}
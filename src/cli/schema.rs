@@ -1,14 +1,59 @@
 //! Schema command implementation for configuration validation and JSON schema generation
 
 use clap::{Args, Subcommand};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use std::{
     fs,
     path::PathBuf,
 };
-use schemars::{JsonSchema, schema_for};
-use serde_json::Value;
-use crate::config::Config;
+use schemars::schema_for;
+use serde_json::{json, Value};
+use crate::config::{validate, Config};
+use crate::tui::{self, KeyMap};
+use super::config::report;
+
+/// Title of the generated schema
+const SCHEMA_TITLE: &str = "Goofy Configuration";
+
+/// Description of the generated schema
+const SCHEMA_DESCRIPTION: &str = "Configuration schema for Goofy AI coding assistant";
+
+/// JSON Schema of goofy.json
+///
+/// Derived from [`Config`], with what the types can't tell filled in: the
+/// actions `tui.keybindings` knows with their default keys, the preset themes,
+/// and a `$schema` key so editors can be pointed at the schema.
+pub(crate) fn config_schema() -> Value {
+    let mut schema = serde_json::to_value(schema_for!(Config)).expect("the schema is serializable");
+    schema["title"] = Value::from(SCHEMA_TITLE);
+    schema["description"] = Value::from(SCHEMA_DESCRIPTION);
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert("$schema".to_string(), json!({
+            "description": "Schema of this file, for editors",
+            "type": "string"
+        }));
+    }
+
+    if let Some(tui) = schema.pointer_mut("/definitions/TuiConfig/properties") {
+        let key_map = KeyMap::default();
+        let actions: serde_json::Map<String, Value> = key_map
+            .bindings()
+            .iter()
+            .map(|(action, binding)| {
+                let description = format!("{} (default: {})", binding.description, binding.key_label());
+                (action.to_string(), json!({ "description": description, "type": "string" }))
+            })
+            .collect();
+        if let Some(keybindings) = tui.get_mut("keybindings") {
+            keybindings["properties"] = Value::Object(actions);
+            keybindings["additionalProperties"] = Value::Bool(false);
+        }
+        if let Some(theme) = tui.get_mut("theme") {
+            theme["examples"] = Value::from(tui::preset_theme_names());
+        }
+    }
+    schema
+}
 
 /// Generate and validate configuration schemas
 #[derive(Debug, Args)]
@@ -77,26 +122,16 @@ impl SchemaCommand {
             }
             None => {
                 // Default to generating schema
-                self.generate_schema(
-                    "Goofy Configuration",
-                    "Configuration schema for Goofy AI coding assistant"
-                ).await
+                self.generate_schema(SCHEMA_TITLE, SCHEMA_DESCRIPTION).await
             }
         }
     }
 
     /// Generate JSON schema for configuration
     async fn generate_schema(&self, title: &str, description: &str) -> Result<()> {
-        let schema = schema_for!(Config);
-        let mut schema_value = serde_json::to_value(schema)
-            .context("Failed to convert schema to JSON value")?;
-
-        // Add metadata
-        if let Some(obj) = schema_value.as_object_mut() {
-            obj.insert("title".to_string(), Value::String(title.to_string()));
-            obj.insert("description".to_string(), Value::String(description.to_string()));
-            obj.insert("$schema".to_string(), Value::String("https://json-schema.org/draft/2020-12/schema".to_string()));
-        }
+        let mut schema_value = config_schema();
+        schema_value["title"] = Value::from(title);
+        schema_value["description"] = Value::from(description);
 
         let output = match self.format {
             SchemaFormat::Json => {
@@ -126,59 +161,27 @@ impl SchemaCommand {
         Ok(())
     }
 
-    /// Validate a configuration file
+    /// Validate a configuration file, against the given schema or the
+    /// configuration's own
     async fn validate_config(&self, config_file: &PathBuf, schema_file: Option<&PathBuf>) -> Result<()> {
-        // Read configuration file
         let config_content = fs::read_to_string(config_file)
             .with_context(|| format!("Failed to read config file: {}", config_file.display()))?;
-
-        let config_value: Value = if config_file.extension().and_then(|ext| ext.to_str()) == Some("yaml") 
-            || config_file.extension().and_then(|ext| ext.to_str()) == Some("yml") {
-            serde_yaml::from_str(&config_content)
-                .with_context(|| format!("Failed to parse YAML config: {}", config_file.display()))?
-        } else {
-            serde_json::from_str(&config_content)
-                .with_context(|| format!("Failed to parse JSON config: {}", config_file.display()))?
-        };
-
-        // Try to deserialize as Config directly for validation
-        match serde_json::from_value::<Config>(config_value.clone()) {
-            Ok(_) => {
-                println!("✅ Configuration is valid and can be loaded successfully!");
+        let schema = match schema_file {
+            Some(schema_path) => {
+                let schema_content = fs::read_to_string(schema_path)
+                    .with_context(|| format!("Failed to read schema file: {}", schema_path.display()))?;
+                serde_json::from_str(&schema_content)
+                    .with_context(|| format!("Failed to parse schema file: {}", schema_path.display()))?
             }
-            Err(e) => {
-                println!("❌ Configuration validation failed: {}", e);
-                return Err(anyhow::anyhow!("Configuration validation failed: {}", e));
-            }
-        }
+            None => config_schema(),
+        };
 
-        // If schema file is provided, try to use jsonschema for additional validation
-        if let Some(schema_path) = schema_file {
-            let schema_content = fs::read_to_string(schema_path)
-                .with_context(|| format!("Failed to read schema file: {}", schema_path.display()))?;
-            let schema_value: Value = serde_json::from_str(&schema_content)
-                .with_context(|| format!("Failed to parse schema file: {}", schema_path.display()))?;
-            
-            match jsonschema::JSONSchema::compile(&schema_value) {
-                Ok(compiled_schema) => {
-                    match compiled_schema.validate(&config_value) {
-                        Ok(()) => {
-                            println!("✅ Configuration is also valid against provided schema!");
-                        }
-                        Err(errors) => {
-                            println!("⚠️  Configuration failed schema validation:");
-                            for error in errors {
-                                println!("  - {}: {}", error.instance_path, error);
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("⚠️  Could not compile provided schema: {}", e);
-                }
-            }
+        let problems = validate::check(&config_content, &schema);
+        let errors = report(config_file, &config_content, &problems);
+        if errors > 0 {
+            bail!("{} has {} error(s)", config_file.display(), errors);
         }
-
+        println!("✅ {} is valid", config_file.display());
         Ok(())
     }
 
@@ -198,6 +201,7 @@ impl SchemaCommand {
         println!("  GOOFY_MODEL        - Default model name");
         println!("  OPENAI_API_KEY     - OpenAI API key");
         println!("  ANTHROPIC_API_KEY  - Anthropic API key");
+        println!("  GEMINI_API_KEY     - Google Gemini API key");
         println!("  OLLAMA_HOST        - Ollama server URL");
         println!("  GOOFY_LOG_LEVEL    - Log level (debug, info, warn, error)");
         println!("  GOOFY_DATA_DIR     - Data directory for sessions and logs\n");
@@ -270,6 +274,10 @@ impl SchemaCommand {
       "model": "claude-3-sonnet-20240229",
       "api_key": "${ANTHROPIC_API_KEY}"
     },
+    "gemini": {
+      "model": "gemini-2.5-flash",
+      "api_key": "${GEMINI_API_KEY}"
+    },
     "ollama": {
       "model": "llama3.2",
       "base_url": "http://localhost:11434"
@@ -278,8 +286,8 @@ impl SchemaCommand {
   "tui": {
     "theme": "goofy_dark",
     "keybindings": {
-      "quit": "Ctrl+Q",
-      "new_session": "Ctrl+N"
+      "quit": "ctrl+q",
+      "help": "f1"
     }
   },
   "tools": {
@@ -354,6 +362,20 @@ mod tests {
         // assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_schema_knows_keybinding_actions_and_themes() {
+        let schema = config_schema();
+        let keybindings = &schema["definitions"]["TuiConfig"]["properties"]["keybindings"];
+        assert_eq!(keybindings["additionalProperties"], false);
+        assert!(keybindings["properties"]["quit"]["description"].as_str().unwrap().contains("default"));
+        let themes = schema["definitions"]["TuiConfig"]["properties"]["theme"]["examples"].as_array().unwrap();
+        assert!(themes.contains(&Value::from("goofy_dark")));
+
+        let problems = validate::check(r#"{ "tui": { "keybindings": { "quitt": "ctrl+q" } } }"#, &schema);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].key, "tui.keybindings.quitt");
+    }
+
     #[test]
     fn test_typescript_generation() {
         let cmd = SchemaCommand {
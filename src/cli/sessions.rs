@@ -0,0 +1,333 @@
+//! Sessions command for listing, merging, comparing, archiving and reporting on stored sessions
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{paths::display_path, Config};
+use crate::session::{storage, ComparedTurn, IssueDraft, MergeStrategy, SessionComparison, SessionManager, TurnText, WordChange};
+
+/// Manage stored sessions
+#[derive(Debug, Args)]
+pub struct SessionsCommand {
+    #[command(subcommand)]
+    pub command: SessionsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionsSubcommand {
+    /// List recent sessions
+    List {
+        /// Maximum number of sessions to list
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+
+        /// List archived sessions instead
+        #[arg(long, conflicts_with = "resumable")]
+        archived: bool,
+
+        /// List only sessions that `goofy run --resume` can continue
+        #[arg(long)]
+        resumable: bool,
+    },
+    /// Compare the assistant's answers in two sessions, turn by turn
+    Diff {
+        /// First session ID
+        first: String,
+
+        /// Second session ID
+        second: String,
+    },
+    /// Move a session to compressed storage
+    Archive {
+        /// Session ID
+        id: String,
+    },
+    /// Restore an archived session
+    Unarchive {
+        /// Session ID
+        id: String,
+    },
+    /// Merge two sessions into a new session
+    Merge {
+        /// First session ID
+        first: String,
+
+        /// Second session ID
+        second: String,
+
+        /// Title of the merged session
+        #[arg(long)]
+        into: String,
+
+        /// How the messages are combined
+        #[arg(long, value_enum, default_value = "append")]
+        strategy: StrategyArg,
+    },
+    /// Draft an issue from a session, to print, copy or file on GitHub
+    Issue {
+        /// Session ID
+        id: String,
+
+        /// Only these messages, counted from 1, e.g. 4-9
+        #[arg(long, value_parser = parse_message_range)]
+        messages: Option<Range<usize>>,
+
+        /// Copy the draft to the clipboard instead of printing it
+        #[arg(long, conflicts_with = "file")]
+        copy: bool,
+
+        /// File the issue with the GitHub CLI (`gh`)
+        #[arg(long)]
+        file: bool,
+
+        /// Repository to file in as owner/name, the current one by default
+        #[arg(long, requires = "file")]
+        repo: Option<String>,
+
+        /// Label for the filed issue; can be repeated
+        #[arg(long, requires = "file")]
+        label: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum StrategyArg {
+    Append,
+    Interleave,
+}
+
+impl From<StrategyArg> for MergeStrategy {
+    fn from(strategy: StrategyArg) -> Self {
+        match strategy {
+            StrategyArg::Append => MergeStrategy::Append,
+            StrategyArg::Interleave => MergeStrategy::Interleave,
+        }
+    }
+}
+
+impl SessionsCommand {
+    /// Execute the sessions command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        let manager = SessionManager::with_storage(storage::from_config(config).await?);
+
+        match &self.command {
+            SessionsSubcommand::List { limit, archived: true, .. } => {
+                let archived = manager.list_archived_sessions(Some(*limit)).await?;
+                if archived.is_empty() {
+                    println!("No archived sessions.");
+                }
+                for session in archived {
+                    println!(
+                        "{}  {}  {:>4} messages  {:>8}  {}",
+                        session.id,
+                        session.archived_at.format("%Y-%m-%d %H:%M"),
+                        session.message_count,
+                        format_size(session.compressed_size),
+                        session.title
+                    );
+                }
+            }
+            SessionsSubcommand::List { limit, archived: false, resumable } => {
+                let sessions = if *resumable {
+                    manager.list_resumable_sessions(Some(*limit)).await?
+                } else {
+                    manager.list_sessions(Some(*limit)).await?
+                };
+                if sessions.is_empty() {
+                    println!("No sessions.");
+                }
+                for session in sessions {
+                    // Sessions that can be resumed are marked, with the directory they continue in
+                    let directory = session
+                        .working_directory()
+                        .map(|dir| format!("  ({})", display_path(&dir)))
+                        .unwrap_or_default();
+                    println!(
+                        "{} {}  {}  {:>4} messages  {}{}",
+                        if session.is_resumable() { '*' } else { ' ' },
+                        session.id,
+                        session.updated_at.format("%Y-%m-%d %H:%M"),
+                        session.message_count,
+                        session.title,
+                        directory
+                    );
+                }
+            }
+            SessionsSubcommand::Merge { first, second, into, strategy } => {
+                let merged = manager.merge_sessions(first, second, into.clone(), (*strategy).into()).await?;
+                println!(
+                    "Merged {} and {} into {} ({} messages, {} tokens)",
+                    first, second, merged.id, merged.message_count, merged.token_usage.total_tokens
+                );
+            }
+            SessionsSubcommand::Diff { first, second } => {
+                let comparison = manager.compare_sessions(first, second).await?;
+                print!("{}", format_comparison(&comparison, std::io::stdout().is_terminal()));
+            }
+            SessionsSubcommand::Archive { id } => {
+                let archived = manager.archive_session(id).await?;
+                println!(
+                    "Archived {} ({} messages, {} compressed)",
+                    archived.title,
+                    archived.message_count,
+                    format_size(archived.compressed_size)
+                );
+            }
+            SessionsSubcommand::Unarchive { id } => {
+                let session = manager.unarchive_session(id).await?;
+                println!("Restored {} ({} messages)", session.title, session.message_count);
+            }
+            SessionsSubcommand::Issue { id, messages, copy, file, repo, label } => {
+                let draft = manager.issue_from_session(id, messages.clone()).await?;
+                if *file {
+                    let url = file_issue(&draft, repo.as_deref(), label).await?;
+                    println!("Filed {}", url);
+                } else if *copy {
+                    crate::tui::clipboard::copy(&draft.to_markdown())?;
+                    println!("Copied \"{}\" to the clipboard", draft.title);
+                } else {
+                    print!("{}", draft.to_markdown());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a 1-based, inclusive range of messages such as `4-9` or `7`
+fn parse_message_range(spec: &str) -> Result<Range<usize>> {
+    let (first, last) = spec.split_once('-').unwrap_or((spec, spec));
+    let first: usize = first.trim().parse().context("messages are counted from 1, e.g. 4-9")?;
+    let last: usize = last.trim().parse().context("messages are counted from 1, e.g. 4-9")?;
+    if first == 0 || last < first {
+        bail!("{} is not a range of messages; they are counted from 1, e.g. 4-9", spec);
+    }
+    Ok(first - 1..last)
+}
+
+/// File an issue with the GitHub CLI and return its URL
+async fn file_issue(draft: &IssueDraft, repo: Option<&str>, labels: &[String]) -> Result<String> {
+    let mut command = tokio::process::Command::new("gh");
+    command.args(["issue", "create", "--title", &draft.title, "--body-file", "-"]);
+    if let Some(repo) = repo {
+        command.args(["--repo", repo]);
+    }
+    for label in labels {
+        command.args(["--label", label]);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("filing needs the GitHub CLI (gh) on the PATH")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(draft.body.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!("gh issue create failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Human-readable size of an archive
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+/// A comparison as text, with colored word diffs or, without `color`, with
+/// `[-removed-]` and `{+added+}` markers
+fn format_comparison(comparison: &SessionComparison, color: bool) -> String {
+    let mut out = format!(
+        "Comparing \"{}\" ({}) with \"{}\" ({}): {} of {} turns differ\n",
+        comparison.first.title,
+        comparison.first.id,
+        comparison.second.title,
+        comparison.second.id,
+        comparison.changed_turns(),
+        comparison.turns.len()
+    );
+    for (index, turn) in comparison.turns.iter().enumerate() {
+        out.push('\n');
+        if turn.is_unchanged() {
+            out.push_str(&format!("── Turn {}: unchanged ──\n", index + 1));
+            continue;
+        }
+        out.push_str(&format!("── Turn {} ──\n", index + 1));
+        out.push_str(&format_prompts(turn));
+        for change in turn.answer_diff() {
+            out.push_str(&match (change, color) {
+                (WordChange::Same(text), _) => text,
+                (WordChange::Removed(text), true) => format!("\x1b[31;9m{}\x1b[0m", text),
+                (WordChange::Added(text), true) => format!("\x1b[32m{}\x1b[0m", text),
+                (WordChange::Removed(text), false) => format!("[-{}-]", text),
+                (WordChange::Added(text), false) => format!("{{+{}+}}", text),
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The prompt of a turn, or each session's prompt when they differ
+fn format_prompts(turn: &ComparedTurn) -> String {
+    match (&turn.first, &turn.second) {
+        (Some(first), _) if turn.same_prompt() => format!("> {}\n", first.prompt),
+        (first, second) => {
+            let prompt = |turn: Option<&TurnText>| {
+                turn.map_or("(no turn)".to_string(), |turn| turn.prompt.clone())
+            };
+            format!("A> {}\nB> {}\n", prompt(first.as_ref()), prompt(second.as_ref()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+
+    #[test]
+    fn test_comparison_marks_changed_words() {
+        let turn = |prompt: &str, answer: &str| TurnText { prompt: prompt.to_string(), answer: answer.to_string() };
+        let comparison = SessionComparison {
+            first: Session::new("Opus".to_string(), None),
+            second: Session::new("Haiku".to_string(), None),
+            turns: vec![
+                ComparedTurn { first: Some(turn("Plan", "Same")), second: Some(turn("Plan", "Same")) },
+                ComparedTurn { first: Some(turn("Fix it", "Use a vector")), second: Some(turn("Fix it", "Use a map")) },
+                ComparedTurn { first: None, second: Some(turn("Test it", "Passes")) },
+            ],
+        };
+
+        let text = format_comparison(&comparison, false);
+        let body = text.split_once('\n').unwrap().1;
+        assert!(text.starts_with("Comparing \"Opus\""), "{}", text);
+        assert!(text.contains("2 of 3 turns differ"), "{}", text);
+        assert_eq!(
+            body,
+            "\n── Turn 1: unchanged ──\n\
+             \n── Turn 2 ──\n> Fix it\nUse a [-vector-]{+map+}\n\
+             \n── Turn 3 ──\nA> (no turn)\nB> Test it\n{+Passes+}\n"
+        );
+    }
+
+    #[test]
+    fn test_message_ranges_count_from_one() {
+        assert_eq!(parse_message_range("4-9").unwrap(), 3..9);
+        assert_eq!(parse_message_range("7").unwrap(), 6..7);
+        assert!(parse_message_range("0-3").is_err());
+        assert!(parse_message_range("9-4").is_err());
+        assert!(parse_message_range("last").is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Setup command for picking a provider, its key and a default model
+
+use anyhow::{bail, Result};
+use clap::Args;
+use std::io::{self, IsTerminal};
+
+use crate::config::Config;
+use crate::tui;
+
+/// Pick a provider, keep its key and choose a default model
+#[derive(Debug, Args)]
+pub struct SetupCommand {}
+
+impl SetupCommand {
+    /// Execute the setup command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            bail!("The setup needs a terminal; set the provider's API key variable instead");
+        }
+        match tui::setup::run(config).await? {
+            Some(configured) => println!("Chatting with {} of {} from now on.", configured.model, configured.provider),
+            None => println!("Setup cancelled; nothing was changed."),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Stats command for reporting statistics about the workspace
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::config::Config;
+use crate::utils::stats::{StatsOptions, WorkspaceStats};
+
+/// Report statistics
+#[derive(Debug, Args)]
+pub struct StatsCommand {
+    #[command(subcommand)]
+    pub command: StatsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StatsSubcommand {
+    /// Lines of code by language, largest files, test ratio and recent churn
+    Workspace {
+        /// Number of largest and most changed files to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Days of git history to look at for churn
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+
+        /// Print JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl StatsCommand {
+    /// Execute the stats command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        match &self.command {
+            StatsSubcommand::Workspace { top, days, json } => {
+                let options = StatsOptions { top: *top, churn_days: *days };
+                let stats = WorkspaceStats::collect(&config.workspace().primary().path, &options).await?;
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&stats)?);
+                } else {
+                    print!("{}", stats.render());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Sync command implementation for reconciling local sessions with remote storage
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::config::{Config, StorageBackend};
+use crate::session::storage::{self, SyncState};
+
+/// Sync local sessions with the configured remote storage
+#[derive(Debug, Args)]
+pub struct SyncCommand {
+    /// Only print conflicts
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl SyncCommand {
+    /// Execute the sync command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        if config.storage.backend == StorageBackend::S3 {
+            anyhow::bail!("Sessions are already stored remotely (storage.backend is \"s3\"); nothing to sync");
+        }
+        let remote = storage::remote_storage(config)?
+            .ok_or_else(|| anyhow::anyhow!("No remote configured. Add a storage.s3 section to goofy.json"))?;
+        let local = storage::local_storage(config).await?;
+
+        let mut state = SyncState::load(SyncState::default_path(&config.data_dir))?;
+        let report = storage::sync::sync(local.as_ref(), remote.as_ref(), &mut state).await?;
+
+        for conflict in &report.conflicts {
+            println!(
+                "conflict: session {} {}: kept \"{}\", discarded \"{}\"",
+                conflict.session_id, conflict.field, conflict.kept, conflict.discarded
+            );
+        }
+
+        if !self.quiet {
+            if report.is_empty() {
+                println!("Already up to date.");
+            } else {
+                println!(
+                    "Pushed {}, pulled {}, merged {} session(s).",
+                    report.pushed.len(),
+                    report.pulled.len(),
+                    report.merged.len()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
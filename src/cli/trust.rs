@@ -0,0 +1,131 @@
+//! Trust command implementation for managing workspace trust decisions
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::path::{Path, PathBuf};
+
+use crate::config::trust::{TrustLevel, TrustStore};
+
+/// Manage trusted workspaces
+#[derive(Debug, Args)]
+pub struct TrustCommand {
+    #[command(subcommand)]
+    pub command: Option<TrustSubcommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrustSubcommand {
+    /// List all recorded trust decisions
+    List,
+    /// Show the trust status of a workspace
+    Status {
+        /// Workspace path (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+    /// Trust a workspace
+    Add {
+        /// Workspace path (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+    /// Mark a workspace as untrusted
+    Deny {
+        /// Workspace path (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+    /// Opt a trusted workspace in to (or out of) loading project .env files
+    Env {
+        /// Workspace path (defaults to the current directory)
+        path: Option<PathBuf>,
+
+        /// Stop loading project .env files for the workspace
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Forget the trust decision for a workspace
+    Remove {
+        /// Workspace path (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+}
+
+impl TrustCommand {
+    /// Execute the trust command
+    pub async fn execute(&self) -> Result<()> {
+        let mut store = TrustStore::load_default()?;
+
+        match &self.command {
+            Some(TrustSubcommand::List) => {
+                if store.workspaces.is_empty() {
+                    println!("No trust decisions recorded.");
+                }
+                for (path, level) in &store.workspaces {
+                    let env_marker = if store.env_workspaces.contains(path) { " (.env)" } else { "" };
+                    println!("{:<10} {}{}", level_label(Some(*level)), path.display(), env_marker);
+                }
+            }
+            Some(TrustSubcommand::Status { path }) => {
+                let path = resolve_path(path.as_deref())?;
+                println!("{}: {}", path.display(), level_label(store.get(&path)));
+            }
+            None => {
+                let path = resolve_path(None)?;
+                println!("{}: {}", path.display(), level_label(store.get(&path)));
+            }
+            Some(TrustSubcommand::Add { path }) => {
+                let path = resolve_path(path.as_deref())?;
+                store.set(&path, TrustLevel::Trusted);
+                store.save()?;
+                println!("Trusted workspace: {}", path.display());
+            }
+            Some(TrustSubcommand::Deny { path }) => {
+                let path = resolve_path(path.as_deref())?;
+                store.set(&path, TrustLevel::Untrusted);
+                store.save()?;
+                println!("Marked workspace as untrusted: {}", path.display());
+            }
+            Some(TrustSubcommand::Env { path, disable }) => {
+                let path = resolve_path(path.as_deref())?;
+                if !*disable && !store.is_trusted(&path) {
+                    anyhow::bail!(
+                        "Workspace {} is not trusted; run `goofy trust add` first",
+                        path.display()
+                    );
+                }
+                store.set_env_enabled(&path, !*disable);
+                store.save()?;
+                if *disable {
+                    println!("Project .env files disabled for: {}", path.display());
+                } else {
+                    println!("Project .env files enabled for: {}", path.display());
+                }
+            }
+            Some(TrustSubcommand::Remove { path }) => {
+                let path = resolve_path(path.as_deref())?;
+                if store.remove(&path) {
+                    store.save()?;
+                    println!("Removed trust decision for: {}", path.display());
+                } else {
+                    println!("No trust decision recorded for: {}", path.display());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve an optional path argument, defaulting to the current directory
+fn resolve_path(path: Option<&Path>) -> Result<PathBuf> {
+    match path {
+        Some(path) => Ok(path.to_path_buf()),
+        None => Ok(std::env::current_dir()?),
+    }
+}
+
+fn level_label(level: Option<TrustLevel>) -> &'static str {
+    match level {
+        Some(TrustLevel::Trusted) => "trusted",
+        Some(TrustLevel::Untrusted) => "untrusted",
+        None => "unknown",
+    }
+}
@@ -0,0 +1,87 @@
+//! Undo command for restoring files the agent changed
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::config::Config;
+use crate::session::{snapshots::SnapshotStore, storage, SessionManager};
+
+/// Restore files changed by the agent to how they were before a turn
+#[derive(Debug, Args)]
+pub struct UndoCommand {
+    /// Session whose changes are undone (defaults to the most recent session)
+    #[arg(long)]
+    pub session: Option<String>,
+
+    /// Undo this turn and every later one
+    #[arg(long)]
+    pub from: Option<usize>,
+
+    /// Last turn to undo, with --from
+    #[arg(long, requires = "from")]
+    pub to: Option<usize>,
+
+    /// List the turns that can be undone instead
+    #[arg(long, conflicts_with_all = ["from", "to"])]
+    pub list: bool,
+}
+
+impl UndoCommand {
+    /// Execute the undo command
+    pub async fn execute(&self, config: &Config) -> Result<()> {
+        let session_id = match &self.session {
+            Some(session_id) => session_id.clone(),
+            None => {
+                let manager = SessionManager::with_storage(storage::from_config(config).await?);
+                match manager.list_sessions(Some(1)).await?.pop() {
+                    Some(session) => session.id,
+                    None => anyhow::bail!("No sessions to undo"),
+                }
+            }
+        };
+        let store = SnapshotStore::new(SnapshotStore::default_path(&config.data_dir));
+        let turns = store.turns(&session_id)?;
+
+        if self.list {
+            if turns.is_empty() {
+                println!("Nothing to undo in session {}.", session_id);
+            }
+            for turn in turns {
+                let files = store.files(&session_id, turn)?;
+                println!("turn {:<4} {} file(s)", turn, files.len());
+                for file in files {
+                    println!("    {}", file.display());
+                }
+            }
+            return Ok(());
+        }
+
+        let Some(&last) = turns.last() else {
+            println!("Nothing to undo in session {}.", session_id);
+            return Ok(());
+        };
+        let range = match (self.from, self.to) {
+            (Some(from), Some(to)) if from > to => anyhow::bail!("--from {} is after --to {}", from, to),
+            (Some(from), Some(to)) => from..=to,
+            (Some(from), None) => from..=last,
+            (None, _) => last..=last,
+        };
+
+        let described = if range.start() == range.end() {
+            format!("turn {}", range.start())
+        } else {
+            format!("turns {}-{}", range.start(), range.end())
+        };
+        let restored = store.restore(&session_id, range)?;
+        if restored.is_empty() {
+            println!("No changes recorded in {} of session {}.", described, session_id);
+            return Ok(());
+        }
+        for file in &restored {
+            let action = if file.deleted { "deleted " } else { "restored" };
+            println!("{} {}", action, file.path.display());
+        }
+        println!("Undid {} of session {}", described, session_id);
+        Ok(())
+    }
+}
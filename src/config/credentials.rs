@@ -0,0 +1,128 @@
+//! Where the first-run setup keeps API keys
+//!
+//! A key is kept either in the OS keychain (the macOS Keychain, the Secret
+//! Service on Linux, the Credential Manager on Windows), under the `goofy`
+//! service with the provider as account, or in the user's `goofy.json` next to
+//! the chosen provider and model, which is then made readable by the user only.
+//! Keys in the environment or in a configuration file win over the keychain.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Keychain service the keys are stored under
+const KEYCHAIN_SERVICE: &str = "goofy";
+
+/// Where an API key is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStorage {
+    Keychain,
+    ConfigFile,
+}
+
+/// Key of `provider` in the OS keychain, if one is stored there
+pub fn keychain_key(provider: &str) -> Option<String> {
+    let key = keyring::Entry::new(KEYCHAIN_SERVICE, provider).and_then(|entry| entry.get_password());
+    match key {
+        Ok(key) => Some(key),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            debug!("Keychain not read for {}: {}", provider, e);
+            None
+        }
+    }
+}
+
+/// Store the key of `provider` in the OS keychain
+pub fn save_to_keychain(provider: &str, key: &str) -> Result<()> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, provider)
+        .and_then(|entry| entry.set_password(key))
+        .with_context(|| format!("Could not store the {} key in the keychain", provider))
+}
+
+/// The user's configuration file, written by the setup
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("goofy").join("goofy.json"))
+}
+
+/// Set the provider and model in the configuration file at `path`, with the
+/// key when it is kept there, leaving the rest of the file as it was
+pub fn save_setup(path: &Path, provider: &str, model: &str, api_key: Option<&str>) -> Result<()> {
+    let mut config = match std::fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content)
+            .with_context(|| format!("{} is not valid JSON", path.display()))?
+        {
+            Value::Object(config) => config,
+            _ => anyhow::bail!("{} does not hold a JSON object", path.display()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Map::new(),
+        Err(e) => return Err(e).with_context(|| format!("Could not read {}", path.display())),
+    };
+    config.insert("provider".to_string(), Value::from(provider));
+    config.insert("model".to_string(), Value::from(model));
+    match api_key {
+        Some(key) => {
+            config.insert("api_key".to_string(), Value::from(key));
+        }
+        // A key of another provider would be sent to this one
+        None => {
+            config.remove("api_key");
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let content = serde_json::to_string_pretty(&Value::Object(config))?;
+    write_private(path, &content).with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// Write `content` to a file only its owner can read
+#[cfg(unix)]
+pub(crate) fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    // The mode only applies to new files
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(content.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    std::fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_keeps_the_rest_of_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("goofy").join("goofy.json");
+        save_setup(&path, "anthropic", "claude-sonnet-4", Some("sk-ant-1")).unwrap();
+        let mut config: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config["api_key"], "sk-ant-1");
+
+        config["theme"] = Value::from("dark");
+        std::fs::write(&path, config.to_string()).unwrap();
+        save_setup(&path, "ollama", "qwen3-coder:latest", None).unwrap();
+        let config: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config["provider"], "ollama");
+        assert_eq!(config["theme"], "dark");
+        assert!(config.get("api_key").is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::write(&path, "[]").unwrap();
+        assert!(save_setup(&path, "openai", "gpt-4o", None).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Environment file loading
+//!
+//! Variables from the user's own `~/.config/goofy/.env` are always loaded.
+//! Project `.env` files are only loaded for trusted workspaces that opted in
+//! with `goofy trust env`, so cloning a repository cannot silently inject
+//! variables such as API base URLs. `.env.local` layers on top of `.env`, and
+//! variables already present in the process environment always win.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Project env files in order of precedence (highest first)
+pub const PROJECT_ENV_FILES: &[&str] = &[".env.local", ".env"];
+
+/// Env files loaded during startup, recorded for `goofy doctor`
+static LOADED_ENV_FILES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Location of the user-level env file
+pub fn user_env_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("goofy").join(".env"))
+}
+
+/// Load the user env file and, if allowed, the project env files of a workspace
+///
+/// Returns the files that were loaded. Only the first call records the list
+/// returned by [`loaded_env_files`].
+pub fn load_env_files(workspace: &Path, allow_project: bool) -> Result<Vec<PathBuf>> {
+    let mut candidates = Vec::new();
+
+    if allow_project {
+        candidates.extend(PROJECT_ENV_FILES.iter().map(|name| workspace.join(name)));
+    } else {
+        debug!("Project env files not enabled for {}", workspace.display());
+    }
+    candidates.extend(user_env_file());
+
+    let loaded = load_layered(&candidates)?;
+    let _ = LOADED_ENV_FILES.set(loaded.clone());
+    Ok(loaded)
+}
+
+/// Load env files so that earlier files take precedence over later ones
+///
+/// dotenvy never overrides variables that are already set, so loading in
+/// precedence order gives the process environment the final say.
+fn load_layered(files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut loaded = Vec::new();
+    for path in files {
+        if !path.is_file() {
+            continue;
+        }
+        dotenvy::from_path(path)
+            .map_err(|e| anyhow::anyhow!("Failed to load env file {}: {}", path.display(), e))?;
+        debug!("Loaded env file: {}", path.display());
+        loaded.push(path.clone());
+    }
+    Ok(loaded)
+}
+
+/// Env files that were loaded during startup
+pub fn loaded_env_files() -> &'static [PathBuf] {
+    LOADED_ENV_FILES.get().map(|files| files.as_slice()).unwrap_or(&[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_local_file_overrides_base_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join(".env"), "GOOFY_TEST_ENV_LAYER=base\nGOOFY_TEST_ENV_BASE_ONLY=1\n").unwrap();
+        std::fs::write(dir.path().join(".env.local"), "GOOFY_TEST_ENV_LAYER=local\n").unwrap();
+
+        let files: Vec<PathBuf> = PROJECT_ENV_FILES.iter().map(|name| dir.path().join(name)).collect();
+        let loaded = load_layered(&files).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(std::env::var("GOOFY_TEST_ENV_LAYER").unwrap(), "local");
+        assert_eq!(std::env::var("GOOFY_TEST_ENV_BASE_ONLY").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_missing_files_are_skipped() {
+        let dir = tempdir().unwrap();
+        let loaded = load_layered(&[dir.path().join(".env")]).unwrap();
+        assert!(loaded.is_empty());
+    }
+}
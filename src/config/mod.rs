@@ -6,63 +6,1228 @@ use tracing::debug;
 
 pub mod lsp;
 pub mod advanced;
+pub mod credentials;
+pub mod validate;
+pub mod trust;
+pub mod env;
+pub mod workspace;
+pub mod paths;
+pub mod prompt;
 
 use self::lsp::LspConfig;
+use self::paths::PathDisplayConfig;
+use self::prompt::{PromptConfig, PromptParts, ProjectInfo, BASE_INSTRUCTIONS};
+use self::workspace::{Workspace, WorkspaceRootConfig};
+use crate::hooks::HooksConfig;
+use crate::mcp::McpConfig;
 pub use advanced::*;
 
 /// Application configuration
 #[derive(Clone, Debug, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(default)]
 pub struct Config {
     /// Current working directory
     pub cwd: PathBuf,
     
+    /// Additional workspace roots alongside the working directory
+    #[serde(default)]
+    pub roots: Vec<WorkspaceRootConfig>,
+    
     /// Data directory for storing sessions and databases
     pub data_dir: PathBuf,
     
     /// AI provider type
     pub provider: String,
     
-    /// API key
-    pub api_key: Option<String>,
+    /// API key
+    pub api_key: Option<String>,
+    
+    /// Base URL for the API
+    pub base_url: Option<String>,
+    
+    /// Model to use
+    pub model: String,
+    
+    /// Maximum tokens for responses
+    pub max_tokens: Option<u32>,
+    
+    /// Temperature for sampling
+    pub temperature: Option<f32>,
+    
+    /// Top-p for nucleus sampling
+    pub top_p: Option<f32>,
+    
+    /// Whether to stream responses
+    pub stream: bool,
+    
+    /// Overall time budget in seconds for answering one message, covering
+    /// every provider call and tool execution in the turn (0 disables it)
+    #[serde(default)]
+    pub turn_timeout_secs: Option<u64>,
+    
+    /// Extra headers for API requests
+    pub extra_headers: HashMap<String, String>,
+    
+    /// Extra body parameters for API requests
+    pub extra_body: HashMap<String, serde_json::Value>,
+    
+    /// System message for conversations
+    pub system_message: Option<String>,
+    
+    /// How the system message is assembled from its parts
+    #[serde(default)]
+    pub prompt: PromptConfig,
+
+    /// LSP configuration
+    #[serde(default)]
+    pub lsp: LspConfig,
+    
+    /// Enable YOLO mode (disable permission checks)
+    pub yolo_mode: Option<bool>,
+    
+    /// Read-only mode (disable write/execute operations)
+    pub read_only: Option<bool>,
+    
+    /// Local control socket for external tools
+    #[serde(default)]
+    pub control: ControlConfig,
+    
+    /// Session storage backend
+    #[serde(default)]
+    pub storage: StorageConfig,
+    
+    /// Moderation of prompts and completions
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    
+    /// Reading responses aloud
+    #[serde(default)]
+    pub tts: TtsConfig,
+    
+    /// Shared team assets
+    #[serde(default)]
+    pub assets: AssetsConfig,
+    
+    /// Model Context Protocol servers
+    #[serde(default)]
+    pub mcp: McpConfig,
+    
+    /// Screenshot tool
+    #[serde(default)]
+    pub screenshot: ScreenshotConfig,
+    
+    /// Limits on the files the edit and write tools change
+    #[serde(default)]
+    pub edits: EditGuardConfig,
+    
+    /// Where tools may reach outside the workspace, and what commands see
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    
+    /// Sub-agents spawned with the task tool
+    #[serde(default)]
+    pub sub_agents: SubAgentConfig,
+    
+    /// Spending limit per session
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    
+    /// Follow-up prompts suggested after each answer
+    #[serde(default)]
+    pub suggestions: SuggestionsConfig,
+    
+    /// How sessions are named after their first exchange
+    #[serde(default)]
+    pub titles: TitlesConfig,
+    
+    /// Retries of failed provider requests and the model to fall back to
+    #[serde(default)]
+    pub retry: RetryConfig,
+    
+    /// Commands run, or refusals made, around tool calls
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    
+    /// How long tool calls may run before they are stopped
+    #[serde(default)]
+    pub tool_timeouts: ToolTimeoutsConfig,
+    
+    /// Index of the workspace's files and symbols
+    #[serde(default)]
+    pub index: IndexConfig,
+    
+    /// Limits, redirects, robots.txt and caching of the fetch tool
+    #[serde(default)]
+    pub fetch: FetchConfig,
+    
+    /// JSON lines log files in the data directory
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    
+    /// Announcing answers that arrive while the user is away
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    
+    /// OpenRouter attribution and routing
+    #[serde(default)]
+    pub openrouter: OpenRouterConfig,
+    
+    /// Azure OpenAI deployments, API version and Azure AD sign-in
+    #[serde(default)]
+    pub azure: AzureConfig,
+    
+    /// Terminal interface appearance
+    #[serde(default)]
+    pub tui: TuiConfig,
+    
+    /// How paths are shown in tool output, the interface and exports
+    #[serde(default)]
+    pub paths: PathDisplayConfig,
+    
+    /// Whether the current workspace has been trusted by the user
+    #[serde(skip)]
+    pub workspace_trusted: bool,
+}
+
+/// Control socket configuration
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ControlConfig {
+    /// Listen for control commands while running interactively
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Socket path (defaults to `control.sock` in the data directory)
+    #[serde(default)]
+    pub socket_path: Option<PathBuf>,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: None,
+        }
+    }
+}
+
+/// Turn budget used when `turn_timeout_secs` is not set
+const DEFAULT_TURN_TIMEOUT_SECS: u64 = 600;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Session storage configuration
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct StorageConfig {
+    /// Backend used for sessions
+    #[serde(default)]
+    pub backend: StorageBackend,
+    
+    /// S3-compatible bucket, used as the backend or as the `goofy sync` remote
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+}
+
+/// Available session storage backends
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Local SQLite database in the data directory
+    #[default]
+    Sqlite,
+    /// S3-compatible object storage
+    S3,
+}
+
+/// S3-compatible object storage settings
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct S3Config {
+    /// Bucket name
+    pub bucket: String,
+    
+    /// Endpoint URL for non-AWS services such as MinIO or R2
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    
+    /// Region used for request signing
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    
+    /// Key prefix, for sharing a bucket between teams
+    #[serde(default)]
+    pub prefix: Option<String>,
+    
+    /// Access key ID (defaults to AWS_ACCESS_KEY_ID)
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    
+    /// Secret access key (defaults to AWS_SECRET_ACCESS_KEY)
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            bucket: String::new(),
+            endpoint: None,
+            region: default_s3_region(),
+            prefix: None,
+            access_key_id: None,
+            secret_access_key: None,
+        }
+    }
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// Moderation stage applied to prompts and completions
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ModerationConfig {
+    /// Run the moderation stage
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Local keyword and regex policies
+    #[serde(default)]
+    pub policies: Vec<ModerationPolicy>,
+    
+    /// Also check text with the OpenAI moderation endpoint
+    #[serde(default)]
+    pub openai: Option<OpenAIModerationConfig>,
+    
+    /// Audit log file (defaults to `moderation.jsonl` in the data directory)
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+}
+
+/// A local moderation policy
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ModerationPolicy {
+    /// Name recorded in warnings and the audit log
+    pub name: String,
+    
+    /// Words or phrases to match, case-insensitively
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    
+    /// Regular expressions to match
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    
+    /// What to do when the policy matches
+    #[serde(default)]
+    pub action: ModerationAction,
+    
+    /// Which direction of traffic the policy applies to
+    #[serde(default)]
+    pub applies_to: ModerationScope,
+}
+
+/// Action taken when moderation flags text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Log a warning and let the text through
+    #[default]
+    Warn,
+    /// Replace the matched text before passing it on
+    Redact,
+    /// Refuse to send or show the text
+    Block,
+}
+
+/// Traffic a moderation policy applies to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationScope {
+    /// Prompts sent to the provider
+    Prompt,
+    /// Completions received from the provider
+    Completion,
+    /// Both directions
+    #[default]
+    Both,
+}
+
+/// OpenAI moderation endpoint settings
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OpenAIModerationConfig {
+    /// API key (defaults to OPENAI_API_KEY)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    
+    /// Base URL of the API
+    #[serde(default = "default_openai_base_url")]
+    pub base_url: String,
+    
+    /// Moderation model
+    #[serde(default = "default_moderation_model")]
+    pub model: String,
+    
+    /// Action for flagged text; `redact` replaces the whole text since the
+    /// endpoint does not report spans
+    #[serde(default = "default_moderation_block")]
+    pub action: ModerationAction,
+    
+    /// Which direction of traffic is sent to the endpoint
+    #[serde(default)]
+    pub applies_to: ModerationScope,
+}
+
+impl Default for OpenAIModerationConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            base_url: default_openai_base_url(),
+            model: default_moderation_model(),
+            action: default_moderation_block(),
+            applies_to: ModerationScope::default(),
+        }
+    }
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_moderation_model() -> String {
+    "omni-moderation-latest".to_string()
+}
+
+fn default_moderation_block() -> ModerationAction {
+    ModerationAction::Block
+}
+
+/// Screenshot tool configuration
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ScreenshotConfig {
+    /// Offer the screenshot tool to the model
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Capture even when running over SSH, where the display is the remote one
+    #[serde(default)]
+    pub allow_over_ssh: bool,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allow_over_ssh: false,
+        }
+    }
+}
+
+/// Limits on the files the agent edits
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EditGuardConfig {
+    /// Largest file, in bytes, the edit and write tools modify
+    #[serde(default = "default_max_edit_file_size")]
+    pub max_file_size: u64,
+    
+    /// Generated and lock files that are not edited, as file names or
+    /// `*.suffix` patterns
+    #[serde(default = "default_protected_files")]
+    pub protected: Vec<String>,
+    
+    /// Protected files that may be edited anyway
+    #[serde(default)]
+    pub allow: Vec<String>,
+    
+    /// Refuse to edit files that look binary
+    #[serde(default = "default_true")]
+    pub refuse_binary: bool,
+    
+    /// Warn the model when an edit changes more than this percentage of a
+    /// file's lines; 0 never warns
+    #[serde(default = "default_warn_changed_percent")]
+    pub warn_changed_percent: u8,
+}
+
+impl Default for EditGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: default_max_edit_file_size(),
+            protected: default_protected_files(),
+            allow: Vec::new(),
+            refuse_binary: true,
+            warn_changed_percent: default_warn_changed_percent(),
+        }
+    }
+}
+
+fn default_max_edit_file_size() -> u64 {
+    1024 * 1024
+}
+
+fn default_protected_files() -> Vec<String> {
+    [
+        "Cargo.lock",
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "poetry.lock",
+        "Gemfile.lock",
+        "composer.lock",
+        "go.sum",
+        "*.min.js",
+        "*.min.css",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+fn default_warn_changed_percent() -> u8 {
+    50
+}
+
+/// Limits of tools outside YOLO mode
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SandboxConfig {
+    /// Directories outside the workspace roots that tools may use, absolute
+    /// or relative to the working directory
+    #[serde(default)]
+    pub allowed_paths: Vec<PathBuf>,
+    
+    /// Environment variables passed on to commands; all of them if unset
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
+    
+    /// Named sets of variables `/env use` adds to commands for the session;
+    /// a value of `${NAME}` is read from Goofy's environment
+    #[serde(default)]
+    pub env_sets: HashMap<String, HashMap<String, String>>,
+}
+
+/// Sub-agent configuration
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SubAgentConfig {
+    /// Offer the task tool, letting the agent delegate work to sub-agents
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Sub-agents running at the same time; further tasks wait for a slot
+    #[serde(default = "default_max_concurrent_sub_agents")]
+    pub max_concurrent: usize,
+    
+    /// Model/tool round trips a sub-agent may take before giving up
+    #[serde(default = "default_sub_agent_rounds")]
+    pub max_tool_rounds: usize,
+}
+
+/// Time limits of tool calls
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ToolTimeoutsConfig {
+    /// Limit in milliseconds of tools without one of their own; 0 for none
+    #[serde(default = "default_tool_timeout_ms")]
+    pub default_ms: u64,
+    
+    /// Limits in milliseconds by tool name, replacing the built-in ones
+    /// such as two minutes for bash; 0 for none
+    #[serde(default)]
+    pub tools: HashMap<String, u64>,
+}
+
+/// Index of the workspace's files and the symbols defined in them
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IndexConfig {
+    /// Index the workspace in the background when starting
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Files indexed at most
+    #[serde(default = "default_index_max_files")]
+    pub max_files: usize,
+    
+    /// Files larger than this many bytes are listed but not read for symbols
+    #[serde(default = "default_index_max_file_bytes")]
+    pub max_file_bytes: u64,
+}
+
+/// What the fetch tool downloads and how
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FetchConfig {
+    /// Largest response read, in bytes
+    #[serde(default = "default_fetch_max_bytes")]
+    pub max_bytes: u64,
+    
+    /// Content types read, `*` standing for any characters as in `text/*`
+    #[serde(default = "default_fetch_content_types")]
+    pub content_types: Vec<String>,
+    
+    /// Which redirects are followed
+    #[serde(default)]
+    pub redirects: RedirectPolicy,
+    
+    /// Redirects followed at most
+    #[serde(default = "default_fetch_max_redirects")]
+    pub max_redirects: usize,
+    
+    /// Refuse URLs the site's robots.txt disallows
+    #[serde(default = "default_true")]
+    pub respect_robots: bool,
+    
+    /// Seconds a fetched page is reused instead of downloaded again; 0 turns
+    /// the cache off
+    #[serde(default = "default_fetch_cache_ttl")]
+    pub cache_ttl_secs: u64,
+}
+
+/// Log files written as JSON lines under `<data_dir>/logs`, for `goofy logs`
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LoggingConfig {
+    /// Write the log files
+    #[serde(default = "default_true")]
+    pub files: bool,
+    
+    /// Least severe level written, `trace` to `error`; `RUST_LOG` only
+    /// filters what is shown on standard error
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    
+    /// Size in bytes at which the log file is moved aside for a new one
+    #[serde(default = "default_log_max_file_bytes")]
+    pub max_file_bytes: u64,
+    
+    /// Files moved aside that are kept, the oldest removed first
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+}
+
+/// When and how finished answers are announced
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationConfig {
+    /// Announce answers at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// How answers are announced
+    #[serde(default)]
+    pub method: NotificationMethod,
+    
+    /// Announce answers taking at least this many seconds even when the
+    /// terminal has focus; unset to only announce while it hasn't
+    #[serde(default = "default_notification_long_run_secs")]
+    pub long_run_secs: Option<u64>,
+}
+
+/// How an answer is announced
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationMethod {
+    /// OSC 9 in terminals showing it, the bell elsewhere
+    #[default]
+    Auto,
+    /// The terminal bell
+    Bell,
+    /// An OSC 9 escape sequence the terminal shows as a notification
+    Osc9,
+    /// A notification of the desktop, through `notify-send` or `osascript`
+    Desktop,
+}
+
+/// Redirects the fetch tool follows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectPolicy {
+    /// Every redirect
+    Follow,
+    /// Redirects to the same host; others are reported for the agent to
+    /// fetch the new URL itself
+    #[default]
+    SameHost,
+    /// None
+    Never,
+}
+
+/// Spending limit of a session
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct BudgetConfig {
+    /// Cost in US dollars a session may reach; unlimited if unset
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+    
+    /// What happens once a session reaches `max_cost`
+    #[serde(default)]
+    pub action: BudgetAction,
+}
+
+/// Follow-up suggestions, asked of a separate and usually cheaper model
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SuggestionsConfig {
+    /// Suggest follow-up prompts after each answer
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Model the suggestions are asked of, the session's model if unset
+    #[serde(default)]
+    pub model: Option<String>,
+    
+    /// Number of suggestions, two or three
+    #[serde(default = "default_suggestion_count")]
+    pub count: usize,
+}
+
+impl Default for SuggestionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            count: default_suggestion_count(),
+        }
+    }
+}
+
+fn default_suggestion_count() -> usize {
+    3
+}
+
+/// Session titles, asked of a separate and usually cheaper model
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TitlesConfig {
+    /// Ask a model for titles; when off, sessions are named after their first prompt
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Model the titles are asked of, the session's model if unset
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl Default for TitlesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            model: None,
+        }
+    }
+}
+
+/// Retrying provider requests that failed for passing reasons, like rate
+/// limits and server errors
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RetryConfig {
+    /// Attempts per request including the first one (1 disables retries)
+    #[serde(default = "default_retry_attempts")]
+    pub max_attempts: u32,
+    
+    /// Delay before the first retry in milliseconds, doubled for each further one
+    #[serde(default = "default_retry_delay_ms")]
+    pub base_delay_ms: u64,
+    
+    /// Longest wait between attempts in milliseconds; a provider asking to
+    /// wait longer is not retried
+    #[serde(default = "default_max_retry_delay_ms")]
+    pub max_delay_ms: u64,
+    
+    /// Model requests go to once the configured one keeps failing
+    #[serde(default)]
+    pub fallback: Option<FallbackConfig>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_attempts(),
+            base_delay_ms: default_retry_delay_ms(),
+            max_delay_ms: default_max_retry_delay_ms(),
+            fallback: None,
+        }
+    }
+}
+
+/// A secondary provider and model
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FallbackConfig {
+    /// Provider of the fallback model, the configured provider if unset
+    #[serde(default)]
+    pub provider: Option<String>,
+    
+    pub model: String,
+    
+    /// Failed attempts of a request before it goes to the fallback
+    #[serde(default = "default_fallback_after")]
+    pub after_failures: u32,
+}
+
+fn default_retry_attempts() -> u32 {
+    4
+}
+
+fn default_retry_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_retry_delay_ms() -> u64 {
+    30_000
+}
+
+fn default_fallback_after() -> u32 {
+    2
+}
+
+/// OpenRouter settings
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct OpenRouterConfig {
+    /// Site shown for requests on openrouter.ai (`HTTP-Referer` header)
+    #[serde(default)]
+    pub site_url: Option<String>,
+    
+    /// App name shown for requests on openrouter.ai (`X-Title` header)
+    #[serde(default)]
+    pub app_name: Option<String>,
+    
+    /// Which upstream providers may serve requests, sent as `provider`
+    #[serde(default)]
+    pub routing: Option<OpenRouterRouting>,
+}
+
+/// OpenRouter provider routing preferences
+///
+/// See <https://openrouter.ai/docs/features/provider-routing>.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct OpenRouterRouting {
+    /// Providers to try first, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    
+    /// Only use these providers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    
+    /// Never use these providers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    
+    /// Fall back to other providers when the preferred ones fail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    
+    /// Prefer the cheapest, fastest or quickest to answer: "price", "throughput" or "latency"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    
+    /// "deny" to skip providers that may store or train on prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<String>,
+}
+
+/// Azure OpenAI settings
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AzureConfig {
+    /// Resource endpoint, like `https://my-resource.openai.azure.com`; the
+    /// base URL is used when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    
+    /// API version sent with every request
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+    
+    /// Deployment serving each model; a model not listed is served by the
+    /// deployment of the same name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub deployments: HashMap<String, String>,
+    
+    /// Sign in with an Azure AD application instead of an API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ad: Option<AzureAdConfig>,
+}
+
+impl Default for AzureConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            api_version: default_azure_api_version(),
+            deployments: HashMap::new(),
+            ad: None,
+        }
+    }
+}
+
+impl AzureConfig {
+    /// Deployment serving `model`
+    pub fn deployment(&self, model: &str) -> String {
+        self.deployments.get(model).cloned().unwrap_or_else(|| model.to_string())
+    }
+}
+
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
+}
+
+/// Azure AD application used to get tokens with the client credentials flow
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AzureAdConfig {
+    /// Directory (tenant) ID
+    pub tenant_id: String,
+    
+    /// Application (client) ID
+    pub client_id: String,
+    
+    /// Client secret; read from AZURE_CLIENT_SECRET when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    
+    /// Scope the tokens are for
+    #[serde(default = "default_azure_scope")]
+    pub scope: String,
+    
+    /// Sign-in host, for sovereign clouds
+    #[serde(default = "default_azure_authority")]
+    pub authority_host: String,
+}
+
+fn default_azure_scope() -> String {
+    "https://cognitiveservices.azure.com/.default".to_string()
+}
+
+fn default_azure_authority() -> String {
+    "https://login.microsoftonline.com".to_string()
+}
+
+/// Terminal interface settings
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TuiConfig {
+    /// Glyphs used for icons in the file picker, chat and status bar
+    #[serde(default)]
+    pub icons: IconMode,
     
-    /// Base URL for the API
-    pub base_url: Option<String>,
+    /// Theme by name, a preset or one from the user's themes directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
     
-    /// Model to use
-    pub model: String,
+    /// Keyboard macros by register, each a list of keys like "ctrl+s" or "enter"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub macros: HashMap<String, Vec<String>>,
+
+    /// Keys for TUI actions by action name, like `"quit": "ctrl+q"`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub keybindings: HashMap<String, String>,
+
+    /// Rewrites applied to assistant messages before they are shown
+    #[serde(default)]
+    pub postprocess: PostprocessConfig,
+
+    /// Seconds without input before background work is suspended, 0 to
+    /// suspend only when the terminal loses focus
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_suspend_secs: Option<u64>,
+}
+
+impl TuiConfig {
+    /// Time without input before background work is suspended
+    pub fn idle_suspend_after(&self) -> Option<std::time::Duration> {
+        match self.idle_suspend_secs {
+            Some(0) => None,
+            Some(secs) => Some(std::time::Duration::from_secs(secs)),
+            None => Some(crate::app::activity::DEFAULT_IDLE_AFTER),
+        }
+    }
+}
+
+/// Postprocessing of assistant messages, each stage toggled on its own
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PostprocessConfig {
+    /// Turn bare URLs into links
+    #[serde(default = "default_true")]
+    pub linkify_urls: bool,
+
+    /// Turn issue references like `#123` into links
+    #[serde(default = "default_true")]
+    pub link_issues: bool,
+
+    /// Issue URL with `{number}` for the issue number, derived from the
+    /// repository's origin remote when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_url: Option<String>,
+
+    /// Number footnote references and list them, with link targets, at the end
+    #[serde(default)]
+    pub footnotes: bool,
+
+    /// Number headings by section
+    #[serde(default)]
+    pub number_headings: bool,
+}
+
+impl Default for PostprocessConfig {
+    fn default() -> Self {
+        Self {
+            linkify_urls: true,
+            link_issues: true,
+            issue_url: None,
+            footnotes: false,
+            number_headings: false,
+        }
+    }
+}
+
+/// Icon glyphs to draw
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconMode {
+    /// Pick from what the terminal is detected to support
+    #[default]
+    Auto,
+    /// Color emoji
+    Emoji,
+    /// Glyphs of a patched Nerd Font
+    NerdFont,
+    /// Plain ASCII, for terminals and fonts without either
+    Ascii,
+}
+
+/// Reaction to a session reaching its budget
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Log a warning and keep going
+    #[default]
+    Warn,
+    /// Refuse further prompts in the session
+    Abort,
+}
+
+fn default_max_concurrent_sub_agents() -> usize {
+    3
+}
+
+fn default_sub_agent_rounds() -> usize {
+    15
+}
+
+fn default_tool_timeout_ms() -> u64 {
+    600_000
+}
+
+fn default_index_max_files() -> usize {
+    100_000
+}
+
+fn default_index_max_file_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_fetch_max_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_fetch_content_types() -> Vec<String> {
+    ["text/*", "application/json", "application/*+json", "application/xml", "application/*+xml", "application/javascript"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_fetch_max_redirects() -> usize {
+    5
+}
+
+fn default_fetch_cache_ttl() -> u64 {
+    15 * 60
+}
+
+fn default_log_level() -> String {
+    "debug".to_string()
+}
+
+fn default_log_max_file_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_log_max_files() -> usize {
+    5
+}
+
+fn default_notification_long_run_secs() -> Option<u64> {
+    Some(30)
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            method: NotificationMethod::default(),
+            long_run_secs: default_notification_long_run_secs(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            files: true,
+            level: default_log_level(),
+            max_file_bytes: default_log_max_file_bytes(),
+            max_files: default_log_max_files(),
+        }
+    }
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_fetch_max_bytes(),
+            content_types: default_fetch_content_types(),
+            redirects: RedirectPolicy::default(),
+            max_redirects: default_fetch_max_redirects(),
+            respect_robots: true,
+            cache_ttl_secs: default_fetch_cache_ttl(),
+        }
+    }
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_files: default_index_max_files(),
+            max_file_bytes: default_index_max_file_bytes(),
+        }
+    }
+}
+
+impl Default for ToolTimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            default_ms: default_tool_timeout_ms(),
+            tools: HashMap::new(),
+        }
+    }
+}
+
+impl Default for SubAgentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent: default_max_concurrent_sub_agents(),
+            max_tool_rounds: default_sub_agent_rounds(),
+        }
+    }
+}
+
+/// Text-to-speech of assistant responses
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TtsConfig {
+    /// Read responses aloud in new sessions (toggled per session with Ctrl+R)
+    #[serde(default)]
+    pub enabled: bool,
     
-    /// Maximum tokens for responses
-    pub max_tokens: Option<u32>,
+    /// Speech backend
+    #[serde(default)]
+    pub backend: TtsBackendKind,
     
-    /// Temperature for sampling
-    pub temperature: Option<f32>,
+    /// Voice name understood by the backend
+    #[serde(default)]
+    pub voice: Option<String>,
     
-    /// Top-p for nucleus sampling
-    pub top_p: Option<f32>,
+    /// Speaking rate in words per minute (system backend)
+    #[serde(default)]
+    pub rate: Option<u32>,
     
-    /// Whether to stream responses
-    pub stream: bool,
+    /// Custom speech command for the system backend; text is passed on stdin
+    #[serde(default)]
+    pub command: Option<String>,
     
-    /// Extra headers for API requests
-    pub extra_headers: HashMap<String, String>,
+    /// OpenAI speech endpoint settings
+    #[serde(default)]
+    pub openai: OpenAISpeechConfig,
     
-    /// Extra body parameters for API requests
-    pub extra_body: HashMap<String, serde_json::Value>,
+    /// Audio player for synthesized speech; `{file}` is replaced by the
+    /// audio file, which is appended otherwise
+    #[serde(default)]
+    pub player: Option<String>,
+}
+
+/// Where speech comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsBackendKind {
+    /// The platform's speech command
+    #[default]
+    System,
+    /// The OpenAI speech endpoint
+    Openai,
+}
+
+/// OpenAI speech endpoint settings
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OpenAISpeechConfig {
+    /// API key (defaults to OPENAI_API_KEY)
+    #[serde(default)]
+    pub api_key: Option<String>,
     
-    /// System message for conversations
-    pub system_message: Option<String>,
+    /// Base URL of the API
+    #[serde(default = "default_openai_base_url")]
+    pub base_url: String,
+    
+    /// Speech model
+    #[serde(default = "default_speech_model")]
+    pub model: String,
+}
 
-    /// LSP configuration
+impl Default for OpenAISpeechConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            base_url: default_openai_base_url(),
+            model: default_speech_model(),
+        }
+    }
+}
+
+fn default_speech_model() -> String {
+    "gpt-4o-mini-tts".to_string()
+}
+
+/// Shared prompt templates, modes and hooks pulled from a team registry
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AssetsConfig {
+    /// Where the assets are published
     #[serde(default)]
-    pub lsp: LspConfig,
+    pub registry: Option<AssetRegistry>,
     
-    /// Enable YOLO mode (disable permission checks)
-    pub yolo_mode: Option<bool>,
+    /// Ed25519 public keys (base64) allowed to sign the registry manifest
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
     
-    /// Read-only mode (disable write/execute operations)
-    pub read_only: Option<bool>,
+    /// Install directory, relative to the workspace (defaults to .goofy/assets)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+/// Location of an asset registry
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AssetRegistry {
+    /// A git repository with the manifest at its root
+    Git {
+        url: String,
+        /// Branch or tag to check out (defaults to the remote HEAD)
+        #[serde(default, rename = "ref")]
+        reference: Option<String>,
+    },
+    /// An HTTPS base URL serving the manifest and the asset files
+    Https {
+        url: String,
+    },
 }
 
+/// Project instruction files injected into the system prompt of trusted workspaces
+const INSTRUCTION_FILES: &[&str] = &[
+    ".cursorrules",
+    "CLAUDE.md",
+    "GOOFY.md",
+    "goofy.md",
+    ".goofy/context.md",
+];
+
 impl Config {
     /// Initialize configuration from various sources
     pub async fn init() -> Result<Self> {
@@ -96,6 +1261,11 @@ impl Config {
                 }
             }
         }
+        config.load_stored_key();
+        
+        if config.cwd.as_os_str().is_empty() {
+            config.cwd = std::env::current_dir()?;
+        }
         
         // Ensure data directory exists
         if !config.data_dir.exists() {
@@ -111,31 +1281,7 @@ impl Config {
             self.provider = provider;
         }
         
-        // Check for provider-specific API keys
-        if let Ok(key) = std::env::var("OPENAI_API_KEY") {
-            if self.provider == "openai" && self.api_key.is_none() {
-                self.api_key = Some(key);
-            }
-        }
-        
-        if let Ok(key) = std::env::var("ANTHROPIC_API_KEY") {
-            if self.provider == "anthropic" && self.api_key.is_none() {
-                self.api_key = Some(key);
-            }
-        }
-        
-        // Ollama doesn't require API keys, but we check for base URL
-        if self.provider == "ollama" {
-            if let Ok(url) = std::env::var("OLLAMA_HOST") {
-                self.base_url = Some(url);
-            } else if let Ok(url) = std::env::var("OLLAMA_BASE_URL") {
-                self.base_url = Some(url);
-            }
-            // Ollama doesn't need an API key
-            if self.api_key.is_none() {
-                self.api_key = Some("not-required".to_string());
-            }
-        }
+        self.load_provider_env();
         
         // Generic API key
         if let Ok(key) = std::env::var("GOOFY_API_KEY") {
@@ -166,6 +1312,12 @@ impl Config {
             self.stream = stream_str.to_lowercase() == "true";
         }
         
+        if let Ok(timeout_str) = std::env::var("GOOFY_TURN_TIMEOUT") {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.turn_timeout_secs = Some(timeout);
+            }
+        }
+        
         if let Ok(data_dir) = std::env::var("GOOFY_DATA_DIR") {
             self.data_dir = PathBuf::from(data_dir);
         }
@@ -183,8 +1335,94 @@ impl Config {
         }
     }
     
-    /// Load configuration from goofy.json files
-    pub async fn load_from_file() -> Result<Self> {
+    /// Pick up the key and endpoint of the configured provider from its own variables
+    fn load_provider_env(&mut self) {
+        // Check for provider-specific API keys
+        if let Ok(key) = std::env::var("OPENAI_API_KEY") {
+            if self.provider == "openai" && self.api_key.is_none() {
+                self.api_key = Some(key);
+            }
+        }
+        
+        if let Ok(key) = std::env::var("ANTHROPIC_API_KEY") {
+            if self.provider == "anthropic" && self.api_key.is_none() {
+                self.api_key = Some(key);
+            }
+        }
+        
+        if let Ok(key) = std::env::var("GEMINI_API_KEY").or_else(|_| std::env::var("GOOGLE_API_KEY")) {
+            if self.provider == "gemini" && self.api_key.is_none() {
+                self.api_key = Some(key);
+            }
+        }
+        
+        // OpenAI-compatible providers each have their own key
+        if let Some(profile) = crate::llm::compat::profile(&self.provider) {
+            if let Ok(key) = std::env::var(profile.api_key_env) {
+                if self.api_key.is_none() {
+                    self.api_key = Some(key);
+                }
+            }
+        }
+        
+        if self.provider == "openai_compatible" && self.base_url.is_none() {
+            if let Ok(url) = std::env::var("OPENAI_COMPATIBLE_BASE_URL") {
+                self.base_url = Some(url);
+            }
+        }
+        
+        // Azure takes its key and endpoint from its own variables
+        if self.provider == "azure" {
+            if let Ok(key) = std::env::var("AZURE_OPENAI_API_KEY") {
+                if self.api_key.is_none() {
+                    self.api_key = Some(key);
+                }
+            }
+            if let Ok(endpoint) = std::env::var("AZURE_OPENAI_ENDPOINT") {
+                if self.azure.endpoint.is_none() {
+                    self.azure.endpoint = Some(endpoint);
+                }
+            }
+        }
+        
+        // Ollama doesn't require API keys, but we check for base URL
+        if self.provider == "ollama" {
+            if let Ok(url) = std::env::var("OLLAMA_HOST") {
+                self.base_url = Some(url);
+            } else if let Ok(url) = std::env::var("OLLAMA_BASE_URL") {
+                self.base_url = Some(url);
+            }
+            // Ollama doesn't need an API key
+            if self.api_key.is_none() {
+                self.api_key = Some("not-required".to_string());
+            }
+        }
+    }
+    
+    /// Point the configuration at another model, possibly of another provider
+    ///
+    /// Moving to another provider forgets the key and endpoint of the
+    /// previous one and takes the new provider's from the environment.
+    pub fn switch_model(&mut self, provider: &str, model: &str) {
+        if provider != self.provider {
+            self.provider = provider.to_string();
+            self.api_key = None;
+            self.base_url = None;
+            self.load_provider_env();
+            self.load_stored_key();
+        }
+        self.model = model.to_string();
+    }
+    
+    /// Take the provider's key from the OS keychain when none is set
+    fn load_stored_key(&mut self) {
+        if self.api_key.is_none() && !self.provider.is_empty() {
+            self.api_key = credentials::keychain_key(&self.provider);
+        }
+    }
+    
+    /// Configuration files in order of priority
+    pub fn config_paths() -> Vec<PathBuf> {
         // Configuration priority (as per Goofy documentation):
         // 1. ./.goofy.json
         // 2. ./goofy.json
@@ -198,8 +1436,12 @@ impl Config {
         if let Some(config_dir) = dirs::config_dir() {
             config_paths.push(config_dir.join("goofy").join("goofy.json"));
         }
-        
-        for path in config_paths {
+        config_paths
+    }
+    
+    /// Load configuration from goofy.json files
+    pub async fn load_from_file() -> Result<Self> {
+        for path in Self::config_paths() {
             if path.exists() {
                 debug!("Loading configuration from: {}", path.display());
                 let content = tokio::fs::read_to_string(&path).await?;
@@ -249,6 +1491,9 @@ impl Config {
         if other.top_p.is_some() {
             self.top_p = other.top_p;
         }
+        if other.turn_timeout_secs.is_some() {
+            self.turn_timeout_secs = other.turn_timeout_secs;
+        }
         if !other.extra_headers.is_empty() {
             self.extra_headers.extend(other.extra_headers);
         }
@@ -258,6 +1503,34 @@ impl Config {
         if other.system_message.is_some() {
             self.system_message = other.system_message;
         }
+        if !other.roots.is_empty() {
+            self.roots = other.roots;
+        }
+        self.control = other.control;
+        self.storage = other.storage;
+        self.moderation = other.moderation;
+        self.tts = other.tts;
+        self.assets = other.assets;
+        self.mcp = other.mcp;
+        self.screenshot = other.screenshot;
+        self.edits = other.edits;
+        self.sandbox = other.sandbox;
+        self.sub_agents = other.sub_agents;
+        self.budget = other.budget;
+        self.suggestions = other.suggestions;
+        self.titles = other.titles;
+        self.retry = other.retry;
+        self.prompt = other.prompt;
+        self.hooks = other.hooks;
+        self.tool_timeouts = other.tool_timeouts;
+        self.index = other.index;
+        self.fetch = other.fetch;
+        self.logging = other.logging;
+        self.notifications = other.notifications;
+        self.openrouter = other.openrouter;
+        self.azure = other.azure;
+        self.tui = other.tui;
+        self.paths = other.paths;
     }
     
     /// Check if Ollama is available at the default URL
@@ -291,16 +1564,104 @@ impl Config {
             return true;
         }
         
+        // Local OpenAI-compatible servers take requests without a key
+        if self.provider == "openai_compatible" {
+            debug!("Provider is openai_compatible, API key optional");
+            return true;
+        }
+        
+        // Azure can sign in with Azure AD instead
+        if self.provider == "azure" && self.azure.ad.is_some() {
+            debug!("Provider is azure with Azure AD sign-in, API key not required");
+            return true;
+        }
+        
         let has_key = self.api_key.is_some() && !self.api_key.as_ref().unwrap().is_empty();
         debug!("Provider '{}' requires API key, has_key: {}", self.provider, has_key);
         has_key
     }
     
+    /// Path of the control socket for this instance
+    pub fn control_socket_path(&self) -> PathBuf {
+        self.control
+            .socket_path
+            .clone()
+            .unwrap_or_else(|| crate::control::default_socket_path(&self.data_dir))
+    }
+    
+    /// Time budget for a single turn, `None` when disabled
+    pub fn turn_timeout(&self) -> Option<std::time::Duration> {
+        match self.turn_timeout_secs.unwrap_or(DEFAULT_TURN_TIMEOUT_SECS) {
+            0 => None,
+            secs => Some(std::time::Duration::from_secs(secs)),
+        }
+    }
+    
+    /// Workspace made up of the working directory and any trusted extra roots
+    pub fn workspace(&self) -> Workspace {
+        let trust = trust::TrustStore::load_default().unwrap_or_default();
+        Workspace::new(&self.cwd, &self.roots, &trust)
+    }
+    
+    /// Whether tools should be restricted to read-only operations
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.unwrap_or(false) || !self.workspace_trusted
+    }
+    
+    /// Load project instruction files from the workspace
+    ///
+    /// Returns nothing for untrusted workspaces so a cloned repository cannot
+    /// inject instructions before the user has reviewed it.
+    pub fn load_instruction_files(&self) -> Vec<(PathBuf, String)> {
+        if !self.workspace_trusted {
+            debug!("Workspace not trusted, skipping instruction files");
+            return Vec::new();
+        }
+        
+        INSTRUCTION_FILES
+            .iter()
+            .map(|name| self.cwd.join(name))
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                debug!("Loaded instruction file: {}", path.display());
+                Some((path, content))
+            })
+            .collect()
+    }
+    
+    /// System message assembled from the base instructions, the detected
+    /// project, project instruction files and the configured system message
+    pub fn effective_system_message(&self) -> Option<String> {
+        let instructions: Vec<String> = self
+            .load_instruction_files()
+            .into_iter()
+            .map(|(path, content)| {
+                let name = path.strip_prefix(&self.cwd).unwrap_or(&path);
+                format!("# Instructions from {}\n\n{}", name.display(), content.trim())
+            })
+            .collect();
+        let workspace = self.workspace();
+        
+        let parts = PromptParts {
+            base: self.prompt.base_instructions.then(|| BASE_INSTRUCTIONS.to_string()),
+            project: self
+                .prompt
+                .project_info
+                .then(|| ProjectInfo::detect(&self.cwd))
+                .flatten()
+                .map(|info| info.summary()),
+            instructions: (!instructions.is_empty()).then(|| instructions.join("\n\n")),
+            system_message: self.system_message.clone(),
+            workspace: workspace.is_multi_root().then(|| workspace.summary()),
+        };
+        parts.render(self.prompt.template.as_deref())
+    }
+    
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
         if !self.has_api_key() {
             return Err(anyhow::anyhow!(
-                "No API key configured. Set OPENAI_API_KEY, ANTHROPIC_API_KEY, or GOOFY_API_KEY environment variable. For Ollama, no API key is required."
+                "No API key configured. Set OPENAI_API_KEY, ANTHROPIC_API_KEY, GEMINI_API_KEY, DEEPSEEK_API_KEY, DASHSCOPE_API_KEY, OPENROUTER_API_KEY, AZURE_OPENAI_API_KEY, or GOOFY_API_KEY environment variable. For Ollama, no API key is required."
             ));
         }
         
@@ -326,6 +1687,22 @@ impl Config {
             }
         }
         
+        if self.edits.warn_changed_percent > 100 {
+            return Err(anyhow::anyhow!("edits.warn_changed_percent must be at most 100"));
+        }
+        
+        if let Some(max_cost) = self.budget.max_cost {
+            if max_cost.is_nan() || max_cost <= 0.0 {
+                return Err(anyhow::anyhow!("budget.max_cost must be greater than 0"));
+            }
+        }
+        
+        if self.retry.max_attempts == 0 {
+            return Err(anyhow::anyhow!("retry.max_attempts must be at least 1"));
+        }
+        
+        crate::hooks::HookEngine::new(&self.hooks, true)?;
+        
         Ok(())
     }
 }
\ No newline at end of file
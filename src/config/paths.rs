@@ -0,0 +1,193 @@
+//! How paths are shown
+//!
+//! Tool output, the interface and exports all show paths through one
+//! [`PathDisplay`], so the same file reads the same everywhere. Paths inside
+//! the workspace are shown relative to their root (with the root's name in
+//! multi-root workspaces), paths in the home directory start with `~`, and the
+//! interface can cut long paths down to their last segments.
+//!
+//! Paths shown to the model are never cut, since it has to be able to use them
+//! again; relative and `~` paths both resolve back to the same file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::trust::TrustStore;
+use super::workspace::Workspace;
+use super::{default_true, Config};
+
+/// Path display settings
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PathDisplayConfig {
+    /// Show paths inside the workspace relative to their root
+    #[serde(default = "default_true")]
+    pub relative: bool,
+
+    /// Show paths in the home directory as `~/...`
+    #[serde(default = "default_true")]
+    pub tilde: bool,
+
+    /// Most segments of a path shown in the interface; the ones before are
+    /// replaced by `…`. Unlimited if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_segments: Option<usize>,
+}
+
+impl Default for PathDisplayConfig {
+    fn default() -> Self {
+        Self {
+            relative: true,
+            tilde: true,
+            max_segments: None,
+        }
+    }
+}
+
+/// Shows paths following the path display settings
+#[derive(Clone, Debug)]
+pub struct PathDisplay {
+    config: PathDisplayConfig,
+    workspace: Option<Workspace>,
+    home: Option<PathBuf>,
+}
+
+impl PathDisplay {
+    /// Show paths relative to `workspace`, if any
+    pub fn new(config: PathDisplayConfig, workspace: Option<Workspace>) -> Self {
+        Self {
+            config,
+            workspace,
+            home: dirs::home_dir(),
+        }
+    }
+
+    /// Show paths as configured, relative to the configured workspace
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.paths.clone(), Some(config.workspace()))
+    }
+
+    /// Use another home directory for `~`
+    pub fn with_home(mut self, home: Option<PathBuf>) -> Self {
+        self.home = home;
+        self
+    }
+
+    /// The same settings, relative to `dir` unless it is already the
+    /// workspace's primary root
+    pub fn in_directory(&self, dir: &Path) -> Self {
+        let mut display = self.clone();
+        if self.workspace.as_ref().map_or(true, |workspace| workspace.primary().path != dir) {
+            display.workspace = Some(Workspace::new(dir, &[], &TrustStore::default()));
+        }
+        display
+    }
+
+    /// A path in full, as tools and exports show it
+    pub fn show(&self, path: &Path) -> String {
+        if self.config.relative {
+            if let Some(workspace) = self.workspace.as_ref().filter(|workspace| workspace.root_for(path).is_some()) {
+                let shown = workspace.display_path(path);
+                return if shown.is_empty() { ".".to_string() } else { shown };
+            }
+        }
+        if self.config.tilde {
+            if let Some(rest) = self.home.as_ref().and_then(|home| path.strip_prefix(home).ok()) {
+                return if rest.as_os_str().is_empty() {
+                    "~".to_string()
+                } else {
+                    format!("~/{}", rest.display())
+                };
+            }
+        }
+        path.display().to_string()
+    }
+
+    /// A path as the interface shows it, cut to the configured segments
+    pub fn show_short(&self, path: &Path) -> String {
+        let shown = self.show(path);
+        match self.config.max_segments {
+            Some(max) => elide_segments(&shown, max.max(1)),
+            None => shown,
+        }
+    }
+}
+
+/// Keep the last `max` segments of a path, replacing the others by `…`
+fn elide_segments(path: &str, max: usize) -> String {
+    let segments: Vec<&str> = path.split(std::path::MAIN_SEPARATOR).filter(|s| !s.is_empty()).collect();
+    if segments.len() <= max {
+        return path.to_string();
+    }
+    let kept = segments[segments.len() - max..].join(std::path::MAIN_SEPARATOR_STR);
+    format!("…{}{}", std::path::MAIN_SEPARATOR, kept)
+}
+
+/// Path display of the running command
+static PATH_DISPLAY: OnceLock<PathDisplay> = OnceLock::new();
+
+/// Set how the running command shows paths; only the first call has an effect
+pub fn set_path_display(display: PathDisplay) {
+    let _ = PATH_DISPLAY.set(display);
+}
+
+/// How the running command shows paths, relative to the current directory with
+/// the default settings until [`set_path_display`] is called
+pub fn path_display() -> &'static PathDisplay {
+    PATH_DISPLAY.get_or_init(|| {
+        let workspace = std::env::current_dir().ok().map(|dir| Workspace::new(&dir, &[], &TrustStore::default()));
+        PathDisplay::new(PathDisplayConfig::default(), workspace)
+    })
+}
+
+/// A path in full, as tools and exports show it
+pub fn display_path(path: &Path) -> String {
+    path_display().show(path)
+}
+
+/// A path as the interface shows it
+pub fn display_path_short(path: &Path) -> String {
+    path_display().show_short(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::workspace::WorkspaceRootConfig;
+
+    #[test]
+    fn test_paths_are_shown_relative_or_from_home() {
+        let workspace = Workspace::new(Path::new("/home/ada/code/app"), &[], &TrustStore::default());
+        let display = PathDisplay::new(PathDisplayConfig::default(), Some(workspace))
+            .with_home(Some(PathBuf::from("/home/ada")));
+
+        assert_eq!(display.show(Path::new("/home/ada/code/app/src/main.rs")), "src/main.rs");
+        assert_eq!(display.show(Path::new("/home/ada/code/app")), ".");
+        assert_eq!(display.show(Path::new("/home/ada/notes.md")), "~/notes.md");
+        assert_eq!(display.show(Path::new("/etc/hosts")), "/etc/hosts");
+
+        let absolute = PathDisplay::new(
+            PathDisplayConfig { relative: false, tilde: false, max_segments: None },
+            Some(Workspace::new(Path::new("/home/ada/code/app"), &[], &TrustStore::default())),
+        );
+        assert_eq!(absolute.show(Path::new("/home/ada/code/app/src/main.rs")), "/home/ada/code/app/src/main.rs");
+    }
+
+    #[test]
+    fn test_long_paths_are_cut_in_the_interface() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("api")).unwrap();
+        let roots = [WorkspaceRootConfig { path: PathBuf::from("api"), name: None }];
+        let config = PathDisplayConfig { max_segments: Some(2), ..Default::default() };
+        let mut trust = TrustStore::default();
+        trust.set(&root, crate::config::trust::TrustLevel::Trusted);
+        let display = PathDisplay::new(config, Some(Workspace::new(&root, &roots, &trust)));
+
+        let path = root.join("api/src/routes/users.rs");
+        assert_eq!(display.show(&path), "api:src/routes/users.rs");
+        assert_eq!(display.show_short(&path), "…/routes/users.rs");
+        assert_eq!(display.show_short(&root.join("api/lib.rs")), "api:lib.rs");
+    }
+}
@@ -0,0 +1,349 @@
+//! Assembling the system message
+//!
+//! The system message is put together from parts: goofy's own instructions
+//! for working as a coding agent, what could be told about the project from
+//! its files (languages, build tool, how to build and test it), the project's
+//! instruction files such as `GOOFY.md` or `CLAUDE.md`, the `system_message`
+//! of the configuration and, in multi-root workspaces, a list of the roots.
+//! By default the parts follow each other in that order; a template in the
+//! `prompt` settings can order them differently, put text around them, or
+//! leave some out.
+//!
+//! `goofy init` writes a `GOOFY.md` for the project from what is detected
+//! here, to be filled in with what can't be detected.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::default_true;
+
+/// Instructions every session starts with, unless turned off
+pub const BASE_INSTRUCTIONS: &str = "\
+You are Goofy, a coding agent working in the user's terminal. You help with \
+software engineering tasks by reading the project, running commands and \
+editing files with the tools you are given.
+
+- Read the relevant code before changing it, and follow the conventions, \
+libraries and style the project already uses.
+- Keep changes to what was asked. Don't reformat, rename or refactor \
+unrelated code.
+- After changing code, build it and run the tests that cover it when the \
+project says how.
+- Never guess at file contents, APIs or command output; look them up with \
+your tools.
+- Be concise. Say what you changed and anything left undone, without \
+repeating code the user can read in the files.";
+
+/// How the system message is assembled
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PromptConfig {
+    /// Start with goofy's own instructions for working as a coding agent
+    #[serde(default = "default_true")]
+    pub base_instructions: bool,
+
+    /// Tell the model the project's languages, build tool and test command,
+    /// as detected from its files
+    #[serde(default = "default_true")]
+    pub project_info: bool,
+
+    /// Layout of the system message, with `{{base}}`, `{{project}}`,
+    /// `{{instructions}}`, `{{system_message}}` and `{{workspace}}` standing
+    /// for its parts. Parts the template leaves out are not sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            base_instructions: true,
+            project_info: true,
+            template: None,
+        }
+    }
+}
+
+/// The parts a system message is assembled from, each of them optional
+#[derive(Clone, Debug, Default)]
+pub struct PromptParts {
+    pub base: Option<String>,
+    pub project: Option<String>,
+    pub instructions: Option<String>,
+    pub system_message: Option<String>,
+    pub workspace: Option<String>,
+}
+
+impl PromptParts {
+    /// The system message, laid out by `template` or the parts in order
+    pub fn render(&self, template: Option<&str>) -> Option<String> {
+        let parts = [
+            ("base", &self.base),
+            ("project", &self.project),
+            ("instructions", &self.instructions),
+            ("system_message", &self.system_message),
+            ("workspace", &self.workspace),
+        ];
+        let message = match template {
+            Some(template) => {
+                let filled = parts.iter().fold(template.to_string(), |text, (name, part)| {
+                    text.replace(&format!("{{{{{}}}}}", name), part.as_deref().map_or("", str::trim))
+                });
+                collapse_blank_lines(&filled)
+            }
+            None => parts
+                .iter()
+                .filter_map(|(_, part)| part.as_deref().map(str::trim))
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        let message = message.trim();
+        (!message.is_empty()).then(|| message.to_string())
+    }
+}
+
+/// Runs of blank lines, left where a template's parts were empty, as one
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let blank = line.trim().is_empty();
+        if blank && lines.last().map_or(true, |last| last.trim().is_empty()) {
+            continue;
+        }
+        lines.push(if blank { "" } else { line });
+    }
+    lines.join("\n")
+}
+
+/// What can be told about a project from the files at its root
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectInfo {
+    /// Languages, the main one first
+    pub languages: Vec<String>,
+    pub build_tool: Option<String>,
+    pub build_command: Option<String>,
+    pub test_command: Option<String>,
+}
+
+impl ProjectInfo {
+    /// Detect the project at `root`, or `None` when nothing is recognized
+    pub fn detect(root: &Path) -> Option<Self> {
+        let has = |name: &str| root.join(name).exists();
+        let mut info = Self::default();
+
+        if has("Cargo.toml") {
+            info.add("Rust", "Cargo", "cargo build", "cargo test");
+        }
+        if has("go.mod") {
+            info.add("Go", "Go modules", "go build ./...", "go test ./...");
+        }
+        if has("package.json") {
+            let manager = if has("pnpm-lock.yaml") {
+                "pnpm"
+            } else if has("yarn.lock") {
+                "yarn"
+            } else if has("bun.lockb") || has("bun.lock") {
+                "bun"
+            } else {
+                "npm"
+            };
+            let scripts = std::fs::read_to_string(root.join("package.json"))
+                .ok()
+                .and_then(|manifest| serde_json::from_str::<serde_json::Value>(&manifest).ok())
+                .and_then(|manifest| manifest.get("scripts").cloned())
+                .unwrap_or_default();
+            let script = |name: &str| {
+                scripts.get(name).is_some().then(|| match (manager, name) {
+                    ("npm", "test") => "npm test".to_string(),
+                    ("npm", _) => format!("npm run {}", name),
+                    _ => format!("{} {}", manager, name),
+                })
+            };
+            let language = if has("tsconfig.json") { "TypeScript" } else { "JavaScript" };
+            info.languages.push(language.to_string());
+            info.build_tool.get_or_insert_with(|| manager.to_string());
+            if info.build_command.is_none() {
+                info.build_command = script("build");
+            }
+            if info.test_command.is_none() {
+                info.test_command = script("test");
+            }
+        }
+        if has("pyproject.toml") || has("setup.py") || has("requirements.txt") {
+            let (tool, test) = if has("uv.lock") {
+                ("uv", "uv run pytest")
+            } else if has("poetry.lock") {
+                ("Poetry", "poetry run pytest")
+            } else {
+                ("pip", "pytest")
+            };
+            info.add("Python", tool, "", test);
+        }
+        if has("pom.xml") {
+            info.add("Java", "Maven", "mvn package", "mvn test");
+        } else if has("build.gradle.kts") || has("build.gradle") {
+            let gradle = if has("gradlew") { "./gradlew" } else { "gradle" };
+            let language = if has("build.gradle.kts") { "Kotlin" } else { "Java" };
+            info.add(language, "Gradle", &format!("{} build", gradle), &format!("{} test", gradle));
+        }
+        if has("mix.exs") {
+            info.add("Elixir", "Mix", "mix compile", "mix test");
+        }
+        if has("Gemfile") {
+            let test = if has("spec") { "bundle exec rspec" } else { "bundle exec rake test" };
+            info.add("Ruby", "Bundler", "", test);
+        }
+        if has("composer.json") {
+            info.add("PHP", "Composer", "", "vendor/bin/phpunit");
+        }
+        if has("Package.swift") {
+            info.add("Swift", "Swift Package Manager", "swift build", "swift test");
+        }
+        if has("CMakeLists.txt") {
+            info.add("C/C++", "CMake", "cmake -B build && cmake --build build", "ctest --test-dir build");
+        } else if has("Makefile") && info.build_tool.is_none() {
+            info.add_tool("Make", "make", "make test");
+        }
+
+        (!info.languages.is_empty() || info.build_tool.is_some()).then_some(info)
+    }
+
+    /// Record a language, and its tooling unless a main language's came first
+    fn add(&mut self, language: &str, tool: &str, build: &str, test: &str) {
+        if !self.languages.iter().any(|known| known == language) {
+            self.languages.push(language.to_string());
+        }
+        self.add_tool(tool, build, test);
+    }
+
+    fn add_tool(&mut self, tool: &str, build: &str, test: &str) {
+        if self.build_tool.is_some() {
+            return;
+        }
+        self.build_tool = Some(tool.to_string());
+        self.build_command = (!build.is_empty()).then(|| build.to_string());
+        self.test_command = (!test.is_empty()).then(|| test.to_string());
+    }
+
+    /// Lines describing the project, as a Markdown list
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.languages.is_empty() {
+            lines.push(format!("- Languages: {}", self.languages.join(", ")));
+        }
+        if let Some(tool) = &self.build_tool {
+            lines.push(format!("- Build tool: {}", tool));
+        }
+        if let Some(build) = &self.build_command {
+            lines.push(format!("- Build: `{}`", build));
+        }
+        if let Some(test) = &self.test_command {
+            lines.push(format!("- Test: `{}`", test));
+        }
+        lines
+    }
+
+    /// The project part of the system message
+    pub fn summary(&self) -> String {
+        format!("# Project\n\n{}", self.lines().join("\n"))
+    }
+}
+
+/// Directories never listed in a project's layout
+const IGNORED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor", "__pycache__", "venv"];
+
+/// A `GOOFY.md` for the project at `root`, to be completed by hand
+pub fn project_memory(root: &Path) -> String {
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Project".to_string());
+    let mut memory = format!(
+        "# {}\n\n\
+         <!-- Goofy reads this file into every session started in this directory. \
+         Keep it short and true; describe what the code can't tell. -->\n\n",
+        name
+    );
+
+    memory.push_str("## Project\n\n");
+    match ProjectInfo::detect(root) {
+        Some(info) => memory.push_str(&info.lines().join("\n")),
+        None => memory.push_str("- Languages:\n- Build:\n- Test:"),
+    }
+
+    let mut dirs: Vec<String> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().map_or(false, |kind| kind.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_str()))
+        .collect();
+    dirs.sort();
+    if !dirs.is_empty() {
+        memory.push_str("\n\n## Layout\n\n");
+        let lines: Vec<String> = dirs.iter().map(|dir| format!("- `{}/`:", dir)).collect();
+        memory.push_str(&lines.join("\n"));
+    }
+
+    memory.push_str("\n\n## Conventions\n\n- \n");
+    memory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_projects_are_detected_from_their_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(ProjectInfo::detect(dir.path()), None);
+
+        fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"scripts": {"test": "vitest"}}"#).unwrap();
+        fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        let info = ProjectInfo::detect(dir.path()).unwrap();
+        assert_eq!(info.languages, ["Rust", "JavaScript"]);
+        assert_eq!(info.build_tool.as_deref(), Some("Cargo"));
+        assert_eq!(info.test_command.as_deref(), Some("cargo test"));
+
+        fs::remove_file(dir.path().join("Cargo.toml")).unwrap();
+        let info = ProjectInfo::detect(dir.path()).unwrap();
+        assert_eq!(info.build_tool.as_deref(), Some("pnpm"));
+        assert_eq!(info.build_command, None);
+        assert_eq!(info.test_command.as_deref(), Some("pnpm test"));
+        assert!(info.summary().contains("- Test: `pnpm test`"));
+    }
+
+    #[test]
+    fn test_parts_follow_the_template() {
+        let parts = PromptParts {
+            base: Some("Base".to_string()),
+            project: None,
+            instructions: Some("Use tabs.".to_string()),
+            system_message: Some("Be brief.".to_string()),
+            workspace: None,
+        };
+        assert_eq!(parts.render(None).unwrap(), "Base\n\nUse tabs.\n\nBe brief.");
+        assert_eq!(
+            parts.render(Some("{{system_message}}\n\n{{project}}\n\n{{instructions}}")).unwrap(),
+            "Be brief.\n\nUse tabs."
+        );
+        assert_eq!(parts.render(Some("{{workspace}}")), None);
+        assert_eq!(PromptParts::default().render(None), None);
+    }
+
+    #[test]
+    fn test_project_memory_lists_commands_and_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("go.mod"), "module example").unwrap();
+        fs::create_dir(dir.path().join("cmd")).unwrap();
+        fs::create_dir(dir.path().join("vendor")).unwrap();
+        let memory = project_memory(dir.path());
+        assert!(memory.contains("- Test: `go test ./...`"));
+        assert!(memory.contains("- `cmd/`:"));
+        assert!(!memory.contains("vendor"));
+    }
+}
@@ -0,0 +1,237 @@
+//! Workspace trust model
+//!
+//! Goofy asks before acting on a directory it has never seen. Untrusted
+//! workspaces run with read-only tools and without project instruction files,
+//! so a freshly cloned repository cannot steer the agent or run commands until
+//! the user explicitly trusts it. Decisions are persisted in the user config
+//! directory and can be managed with `goofy trust`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Trust decision for a workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustLevel {
+    /// Full tool access and instruction-file injection
+    Trusted,
+    /// Read-only tools, no instruction files
+    Untrusted,
+}
+
+/// Persistent store of workspace trust decisions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    /// Decisions keyed by canonical workspace path
+    #[serde(default)]
+    pub workspaces: BTreeMap<PathBuf, TrustLevel>,
+
+    /// Trusted workspaces that opted in to loading project `.env` files
+    #[serde(default)]
+    pub env_workspaces: BTreeSet<PathBuf>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl TrustStore {
+    /// Default location of the trust store in the user config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("goofy").join("trust.json"))
+    }
+
+    /// Load the trust store from the default location
+    pub fn load_default() -> Result<Self> {
+        let path = Self::default_path()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine user config directory"))?;
+        Self::load(path)
+    }
+
+    /// Load the trust store from a file, returning an empty store if it does not exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut store = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read trust store: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content)
+                .with_context(|| format!("Failed to parse trust store: {}", path.display()))?
+        } else {
+            Self::default()
+        };
+        store.path = path;
+        Ok(store)
+    }
+
+    /// Persist the trust store
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write trust store: {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Get the decision for a workspace, inheriting from the closest decided ancestor
+    pub fn get(&self, workspace: &Path) -> Option<TrustLevel> {
+        let workspace = normalize(workspace);
+        workspace
+            .ancestors()
+            .find_map(|ancestor| self.workspaces.get(ancestor).copied())
+    }
+
+    /// Check whether a workspace is trusted
+    pub fn is_trusted(&self, workspace: &Path) -> bool {
+        self.get(workspace) == Some(TrustLevel::Trusted)
+    }
+
+    /// Record a decision for a workspace
+    pub fn set(&mut self, workspace: &Path, level: TrustLevel) {
+        self.workspaces.insert(normalize(workspace), level);
+    }
+
+    /// Forget the decision for a workspace; returns whether one existed
+    pub fn remove(&mut self, workspace: &Path) -> bool {
+        let workspace = normalize(workspace);
+        self.env_workspaces.remove(&workspace);
+        self.workspaces.remove(&workspace).is_some()
+    }
+
+    /// Enable or disable loading project `.env` files for a workspace
+    pub fn set_env_enabled(&mut self, workspace: &Path, enabled: bool) {
+        let workspace = normalize(workspace);
+        if enabled {
+            self.env_workspaces.insert(workspace);
+        } else {
+            self.env_workspaces.remove(&workspace);
+        }
+    }
+
+    /// Whether project `.env` files may be loaded for a workspace
+    ///
+    /// Requires both trust and an explicit opt-in on the workspace itself.
+    pub fn env_enabled(&self, workspace: &Path) -> bool {
+        self.is_trusted(workspace) && self.env_workspaces.contains(&normalize(workspace))
+    }
+}
+
+/// Resolve whether the given workspace is trusted, prompting on first visit
+///
+/// The prompt is only shown when stdin and stderr are both terminals; in
+/// non-interactive contexts an unknown workspace is treated as untrusted
+/// without recording a decision.
+pub fn resolve_workspace_trust(workspace: &Path) -> Result<bool> {
+    let mut store = TrustStore::load_default()?;
+
+    if let Some(level) = store.get(workspace) {
+        debug!("Workspace {} trust: {:?}", workspace.display(), level);
+        return Ok(level == TrustLevel::Trusted);
+    }
+
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        debug!("Unknown workspace {} in non-interactive mode, treating as untrusted", workspace.display());
+        return Ok(false);
+    }
+
+    let level = prompt_for_trust(workspace)?;
+    store.set(workspace, level);
+    store.save()?;
+
+    Ok(level == TrustLevel::Trusted)
+}
+
+/// Ask the user whether to trust a workspace
+fn prompt_for_trust(workspace: &Path) -> Result<TrustLevel> {
+    let mut stderr = io::stderr();
+    writeln!(stderr, "Do you trust the files in this folder?")?;
+    writeln!(stderr, "  {}", workspace.display())?;
+    writeln!(stderr)?;
+    writeln!(stderr, "Untrusted folders run with read-only tools and ignore project instruction files.")?;
+    write!(stderr, "Trust this folder? [y/N] ")?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => TrustLevel::Trusted,
+        _ => TrustLevel::Untrusted,
+    })
+}
+
+/// Canonicalize a path when possible so decisions survive symlinks and `..`
+fn normalize(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_unknown_workspace_has_no_decision() {
+        let dir = tempdir().unwrap();
+        let store = TrustStore::load(dir.path().join("trust.json")).unwrap();
+
+        assert_eq!(store.get(dir.path()), None);
+        assert!(!store.is_trusted(dir.path()));
+    }
+
+    #[test]
+    fn test_trust_is_inherited_by_subdirectories() {
+        let dir = tempdir().unwrap();
+        let child = dir.path().join("child");
+        std::fs::create_dir(&child).unwrap();
+
+        let mut store = TrustStore::load(dir.path().join("trust.json")).unwrap();
+        store.set(dir.path(), TrustLevel::Trusted);
+        assert!(store.is_trusted(&child));
+
+        // A closer decision overrides the inherited one
+        store.set(&child, TrustLevel::Untrusted);
+        assert!(!store.is_trusted(&child));
+        assert!(store.is_trusted(dir.path()));
+    }
+
+    #[test]
+    fn test_env_requires_trust() {
+        let dir = tempdir().unwrap();
+        let mut store = TrustStore::load(dir.path().join("trust.json")).unwrap();
+
+        store.set_env_enabled(dir.path(), true);
+        store.set(dir.path(), TrustLevel::Untrusted);
+        assert!(!store.env_enabled(dir.path()));
+
+        store.set(dir.path(), TrustLevel::Trusted);
+        assert!(store.env_enabled(dir.path()));
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = tempdir().unwrap();
+        let store_path = dir.path().join("config").join("trust.json");
+
+        let mut store = TrustStore::load(&store_path).unwrap();
+        store.set(dir.path(), TrustLevel::Trusted);
+        store.save().unwrap();
+
+        let mut loaded = TrustStore::load(&store_path).unwrap();
+        assert!(loaded.is_trusted(dir.path()));
+
+        assert!(!loaded.env_enabled(dir.path()));
+        loaded.set_env_enabled(dir.path(), true);
+        assert!(loaded.env_enabled(dir.path()));
+
+        assert!(loaded.remove(dir.path()));
+        assert!(!loaded.env_enabled(dir.path()));
+        assert!(!loaded.remove(dir.path()));
+        assert_eq!(loaded.get(dir.path()), None);
+    }
+}
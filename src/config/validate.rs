@@ -0,0 +1,542 @@
+//! Checking configuration files against the configuration schema
+//!
+//! A file is read as JSON, checked against the JSON Schema generated from
+//! [`Config`], searched for keys the schema doesn't know, which serde would
+//! silently ignore, and finally loaded as a [`Config`]. Every problem names
+//! the key as a dotted path like `tui.keybindings.quit` with the line and
+//! column it is on, found by scanning the file again since parsed JSON keeps
+//! no positions.
+
+use jsonschema::error::ValidationErrorKind;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use super::Config;
+use crate::utils::text::string::similarity;
+
+/// Least similarity for a known key to be suggested for an unknown one
+const SUGGESTION_SIMILARITY: f64 = 0.6;
+
+/// How bad a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file is not loaded, or not as written
+    Error,
+    /// The setting is ignored, likely a typo
+    Warning,
+}
+
+/// A problem of a configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Dotted path of the key, empty for the whole file
+    pub key: String,
+    /// 1-based line and column of the key
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Problems of the configuration file `content` against `schema`, in the
+/// order of the file
+pub fn check(content: &str, schema: &Value) -> Vec<Problem> {
+    check_as::<Config>(content, schema)
+}
+
+fn check_as<T: DeserializeOwned>(content: &str, schema: &Value) -> Vec<Problem> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![Problem {
+                severity: Severity::Error,
+                key: String::new(),
+                line: e.line(),
+                column: e.column(),
+                message: strip_position(&e.to_string()),
+            }]
+        }
+    };
+    let positions = positions(content);
+    let locate = |pointer: &str, severity: Severity, message: String| {
+        // A missing key is reported where its parent is
+        let mut found = pointer;
+        let (line, column) = loop {
+            if let Some(position) = positions.get(found) {
+                break *position;
+            }
+            match found.rfind('/') {
+                Some(end) => found = &found[..end],
+                None => break (1, 1),
+            }
+        };
+        Problem { severity, key: dotted(pointer), line, column, message }
+    };
+
+    let mut errors = Vec::new();
+    schema_errors(&value, schema, schema, "", &mut errors);
+    let mut problems: Vec<Problem> =
+        errors.into_iter().map(|(pointer, message)| locate(&pointer, Severity::Error, message)).collect();
+    let mut unknown = Vec::new();
+    unknown_keys(&value, schema, schema, String::new(), &mut unknown);
+    problems.extend(unknown.into_iter().map(|(pointer, severity, message)| locate(&pointer, severity, message)));
+
+    // Anything the schema cannot tell, like a value a custom type refuses
+    if !problems.iter().any(|problem| problem.severity == Severity::Error) {
+        let deserializer = &mut serde_json::Deserializer::from_str(content);
+        if let Err(e) = serde_path_to_error::deserialize::<_, T>(deserializer) {
+            let key = e.path().to_string();
+            problems.push(Problem {
+                severity: Severity::Error,
+                key: if key == "." { String::new() } else { key },
+                line: e.inner().line(),
+                column: e.inner().column(),
+                message: strip_position(&e.inner().to_string()),
+            });
+        }
+    }
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+    problems
+}
+
+/// Where `value` breaks `schema`, as JSON pointers below `at` with what is
+/// wrong there
+///
+/// When a value matches none of the alternatives of an `anyOf` or `oneOf`,
+/// as schemars makes of every `Option`, the one alternative of the value's
+/// type is checked instead to find the key at fault.
+fn schema_errors(value: &Value, schema: &Value, root: &Value, at: &str, found: &mut Vec<(String, String)>) {
+    let mut scoped = schema.clone();
+    if let (Some(scoped), Some(definitions)) = (scoped.as_object_mut(), root.get("definitions")) {
+        scoped.entry("definitions").or_insert_with(|| definitions.clone());
+    }
+    let compiled = match jsonschema::JSONSchema::compile(&scoped) {
+        Ok(compiled) => compiled,
+        Err(e) => return found.push((at.to_string(), format!("The schema is invalid: {}", e))),
+    };
+    let Err(errors) = compiled.validate(value) else {
+        return;
+    };
+    for error in errors {
+        let pointer = error.instance_path.to_string();
+        match error.kind {
+            // Unknown keys are reported each where it is
+            ValidationErrorKind::AdditionalProperties { .. } => continue,
+            ValidationErrorKind::AnyOf | ValidationErrorKind::OneOfNotValid => {
+                let instance = value.pointer(&pointer);
+                let alternative = schema_at(schema, root, &pointer)
+                    .zip(instance)
+                    .and_then(|(schema, instance)| alternative_of_type(schema, root, instance));
+                if let Some((alternative, instance)) = alternative.zip(instance) {
+                    schema_errors(instance, alternative, root, &format!("{}{}", at, pointer), found);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        found.push((format!("{}{}", at, pointer), error.to_string()));
+    }
+}
+
+/// The schema of the value at the JSON `pointer`
+fn schema_at<'a>(schema: &'a Value, root: &'a Value, pointer: &str) -> Option<&'a Value> {
+    let mut current = schema;
+    for segment in pointer.split('/').skip(1) {
+        current = child(current, root, &segment.replace("~1", "/").replace("~0", "~")).ok()?;
+    }
+    Some(current)
+}
+
+/// The schema of `key` in values of `schema`, or the keys it knows
+fn child<'a>(schema: &'a Value, root: &'a Value, key: &str) -> Result<&'a Value, Vec<&'a String>> {
+    let mut alternatives = Vec::new();
+    expand(schema, root, &mut alternatives);
+    let properties: Vec<&Map<String, Value>> =
+        alternatives.iter().filter_map(|schema| schema.get("properties")?.as_object()).collect();
+    let items = alternatives.iter().find_map(|schema| schema.get("items").filter(|s| s.is_object()));
+    properties
+        .iter()
+        .find_map(|properties| properties.get(key))
+        .or_else(|| additional_properties(&alternatives))
+        .or_else(|| items.filter(|_| key.parse::<usize>().is_ok()))
+        .ok_or_else(|| properties.iter().flat_map(|properties| properties.keys()).collect())
+}
+
+/// The only alternative of `schema` that takes values of the type of `value`
+fn alternative_of_type<'a>(schema: &'a Value, root: &'a Value, value: &Value) -> Option<&'a Value> {
+    let type_name = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let takes = |alternative: &Value| {
+        let mut resolved = Vec::new();
+        expand(alternative, root, &mut resolved);
+        resolved.iter().filter_map(|schema| schema.get("type")).any(|types| {
+            let types = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                types => types.as_str().into_iter().collect::<Vec<_>>(),
+            };
+            types.iter().any(|name| *name == type_name || (*name == "number" && type_name == "integer"))
+        })
+    };
+    let alternatives = ["anyOf", "oneOf"].iter().find_map(|combination| schema.get(*combination)?.as_array())?;
+    let mut matching = alternatives.iter().filter(|alternative| takes(alternative));
+    match (matching.next(), matching.next()) {
+        (Some(alternative), None) => Some(alternative),
+        _ => None,
+    }
+}
+
+/// serde_json's message without the position appended to it
+fn strip_position(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(end) => message[..end].to_string(),
+        None => message.to_string(),
+    }
+}
+
+/// A JSON pointer as a dotted path
+pub fn dotted(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// A dotted path as a JSON pointer
+pub fn pointer(dotted: &str) -> String {
+    dotted
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Keys of `value` that `schema` has no place for, as JSON pointers with
+/// what to say about them
+///
+/// They are errors where the schema forbids other keys and warnings where
+/// they would be ignored.
+fn unknown_keys(value: &Value, schema: &Value, root: &Value, at: String, found: &mut Vec<(String, Severity, String)>) {
+    let mut alternatives = Vec::new();
+    expand(schema, root, &mut alternatives);
+    match value {
+        Value::Object(object) => {
+            let properties: Vec<&Map<String, Value>> =
+                alternatives.iter().filter_map(|schema| schema.get("properties")?.as_object()).collect();
+            let additional = additional_properties(&alternatives);
+            let forbidden = alternatives.iter().any(|schema| schema.get("additionalProperties") == Some(&Value::Bool(false)));
+            for (key, item) in object {
+                let path = format!("{}/{}", at, key.replace('~', "~0").replace('/', "~1"));
+                if let Some(property) = properties.iter().find_map(|properties| properties.get(key)) {
+                    unknown_keys(item, property, root, path, found);
+                } else if let Some(additional) = additional {
+                    unknown_keys(item, additional, root, path, found);
+                } else if !properties.is_empty() {
+                    let (severity, outcome) = if forbidden { (Severity::Error, "not allowed") } else { (Severity::Warning, "ignored") };
+                    let message = match suggest(key, properties.iter().flat_map(|properties| properties.keys())) {
+                        Some(known) => format!("Unknown key '{}', {}; did you mean '{}'?", key, outcome, known),
+                        None => format!("Unknown key '{}', {}", key, outcome),
+                    };
+                    found.push((path, severity, message));
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = alternatives.iter().find_map(|schema| schema.get("items").filter(|s| s.is_object())) {
+                for (index, item) in items.iter().enumerate() {
+                    unknown_keys(item, item_schema, root, format!("{}/{}", at, index), found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Why `schema` has no place for the dotted `key`, if it has none
+pub fn unknown_key(schema: &Value, key: &str) -> Option<String> {
+    let mut current = schema;
+    let mut known_path = Vec::new();
+    for segment in key.split('.').filter(|segment| !segment.is_empty()) {
+        match child(current, schema, segment) {
+            Ok(next) => current = next,
+            Err(known) => {
+                let at = |name: &str| known_path.iter().copied().chain([name]).collect::<Vec<_>>().join(".");
+                return Some(match suggest(segment, known.into_iter()) {
+                    Some(known) => format!("Unknown key '{}'; did you mean '{}'?", at(segment), at(known)),
+                    None => format!("Unknown key '{}'", at(segment)),
+                });
+            }
+        }
+        known_path.push(segment);
+    }
+    None
+}
+
+/// The schema of keys not named in the schema, when they are allowed
+fn additional_properties<'a>(alternatives: &[&'a Value]) -> Option<&'a Value> {
+    alternatives
+        .iter()
+        .find_map(|schema| schema.get("additionalProperties").filter(|s| s.is_object() || **s == Value::Bool(true)))
+}
+
+/// The known key most like `key`, if any is alike enough
+fn suggest<'a>(key: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    known
+        .map(|known| (similarity(key, known), known))
+        .filter(|(score, _)| *score >= SUGGESTION_SIMILARITY)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, known)| known.as_str())
+}
+
+/// The schema with its references resolved and its combinations spread out
+fn expand<'a>(schema: &'a Value, root: &'a Value, into: &mut Vec<&'a Value>) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if let Some(target) = reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer)) {
+            expand(target, root, into);
+        }
+    }
+    for combination in ["allOf", "anyOf", "oneOf"] {
+        for alternative in schema.get(combination).and_then(Value::as_array).into_iter().flatten() {
+            expand(alternative, root, into);
+        }
+    }
+    into.push(schema);
+}
+
+/// Line and column of every key and array item of valid JSON, by JSON pointer
+fn positions(content: &str) -> HashMap<String, (usize, usize)> {
+    let mut scanner = Scanner {
+        chars: content.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new(),
+    };
+    scanner.skip_whitespace();
+    scanner.record(String::new());
+    scanner.value(String::new());
+    scanner.positions
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn record(&mut self, pointer: String) {
+        self.positions.entry(pointer).or_insert((self.line, self.column));
+    }
+
+    fn value(&mut self, at: String) {
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some('"') => {
+                            let (line, column) = (self.line, self.column);
+                            let key = self.string();
+                            let path = format!("{}/{}", at, key.replace('~', "~0").replace('/', "~1"));
+                            self.positions.entry(path.clone()).or_insert((line, column));
+                            self.skip_whitespace();
+                            self.bump(); // ':'
+                            self.skip_whitespace();
+                            self.value(path);
+                            self.skip_whitespace();
+                            if self.peek() == Some(',') {
+                                self.bump();
+                            }
+                        }
+                        Some(_) => {
+                            self.bump(); // '}'
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(']') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(_) => {
+                            let path = format!("{}/{}", at, index);
+                            self.record(path.clone());
+                            self.value(path);
+                            self.skip_whitespace();
+                            if self.peek() == Some(',') {
+                                self.bump();
+                            }
+                            index += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => {
+                while self.peek().is_some_and(|c| !matches!(c, ',' | '}' | ']') && !c.is_whitespace()) {
+                    self.bump();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Read a string, decoding its escapes
+    fn string(&mut self) -> String {
+        self.bump(); // '"'
+        let mut text = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => match self.bump() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                        let code = u32::from_str_radix(&hex, 16).unwrap_or(0xfffd);
+                        text.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    Some(other) => text.push(other),
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Settings {
+        #[serde(default)]
+        model: String,
+        #[serde(default)]
+        tui: Option<Tui>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Tui {
+        #[serde(default)]
+        keybindings: HashMap<String, String>,
+    }
+
+    fn schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "model": { "type": "string" },
+                "tui": { "anyOf": [{ "$ref": "#/definitions/Tui" }, { "type": "null" }] }
+            },
+            "definitions": {
+                "Tui": {
+                    "type": "object",
+                    "properties": {
+                        "keybindings": { "type": "object", "additionalProperties": { "type": "string" } }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_problems_name_the_key_and_its_line() {
+        let content = "{\n  \"modle\": \"gpt-4o\",\n  \"tui\": {\n    \"keybindings\": { \"quit\": 42 }\n  }\n}\n";
+        let problems = check_as::<Settings>(content, &schema());
+        let found: Vec<(Severity, &str, usize, usize)> = problems
+            .iter()
+            .map(|problem| (problem.severity, problem.key.as_str(), problem.line, problem.column))
+            .collect();
+        assert_eq!(
+            found,
+            [(Severity::Warning, "modle", 2, 3), (Severity::Error, "tui.keybindings.quit", 4, 22)]
+        );
+        assert!(problems[0].message.contains("did you mean 'model'"));
+        assert!(problems[1].message.contains("is not of type \"string\""));
+
+        let mut strict = schema();
+        strict["definitions"]["Tui"]["additionalProperties"] = Value::Bool(false);
+        let problems = check_as::<Settings>("{ \"tui\": { \"theme\": \"dark\" } }", &strict);
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].severity, problems[0].key.as_str()), (Severity::Error, "tui.theme"));
+
+        let problems = check_as::<Settings>("{\n  \"model\": \"gpt-4o\",\n}", &schema());
+        assert_eq!((problems[0].line, problems[0].key.as_str()), (3, ""));
+        assert!(check_as::<Settings>("{ \"model\": \"gpt-4o\" }", &schema()).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_named_with_a_suggestion() {
+        assert_eq!(unknown_key(&schema(), "tui.keybindings.quit"), None);
+        assert_eq!(
+            unknown_key(&schema(), "tui.keybinds"),
+            Some("Unknown key 'tui.keybinds'; did you mean 'tui.keybindings'?".to_string())
+        );
+        assert_eq!(unknown_key(&schema(), "model.name"), Some("Unknown key 'model.name'".to_string()));
+    }
+
+    #[test]
+    fn test_dotted_paths_and_pointers_convert() {
+        assert_eq!(pointer("tui.keybindings.quit"), "/tui/keybindings/quit");
+        assert_eq!(dotted("/roots/0/path"), "roots.0.path");
+        assert_eq!(dotted(&pointer("mcp.servers.a/b")), "mcp.servers.a/b");
+        assert_eq!(dotted(""), "");
+    }
+}
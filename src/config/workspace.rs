@@ -0,0 +1,313 @@
+//! Multi-root workspaces
+//!
+//! A workspace always contains the working directory as its primary root and
+//! may list additional roots in the configuration, for example sibling
+//! repositories or packages of a monorepo. Relative paths are resolved across
+//! all roots; a path can be pinned to a root with a `name:` prefix
+//! (`api:src/main.rs`) when the same relative path exists in several roots.
+
+use super::trust::TrustStore;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// An additional workspace root as written in the configuration
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct WorkspaceRootConfig {
+    /// Root directory, relative to the working directory or absolute
+    pub path: PathBuf,
+
+    /// Short name used to address the root (defaults to the directory name)
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A resolved workspace root
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkspaceRoot {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Errors from resolving a path across roots
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ResolveError {
+    #[error("Unknown workspace root '{0}'")]
+    UnknownRoot(String),
+
+    #[error("'{path}' exists in several workspace roots; prefix it with one of: {}", candidates.join(", "))]
+    Ambiguous { path: String, candidates: Vec<String> },
+}
+
+/// The set of roots making up the current workspace
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workspace {
+    roots: Vec<WorkspaceRoot>,
+}
+
+impl Workspace {
+    /// Build a workspace from the primary directory and configured extra roots
+    ///
+    /// All roots are canonicalized so they compare equal however they were
+    /// written. The primary root's trust decides whether tools may write at
+    /// all; an extra root must be trusted in its own right and is left out
+    /// otherwise, since it is named by project configuration the user may not
+    /// have reviewed.
+    pub fn new(primary: &Path, extra: &[WorkspaceRootConfig], trust: &TrustStore) -> Self {
+        let primary = dunce::canonicalize(primary).unwrap_or_else(|_| primary.to_path_buf());
+        let mut roots = vec![WorkspaceRoot {
+            name: dir_name(&primary),
+            path: primary.clone(),
+        }];
+
+        for root in extra {
+            let path = if root.path.is_absolute() {
+                root.path.clone()
+            } else {
+                primary.join(&root.path)
+            };
+            let path = dunce::canonicalize(&path).unwrap_or(path);
+            if roots.iter().any(|existing| existing.path == path) {
+                continue;
+            }
+            if !trust.is_trusted(&path) {
+                warn!(
+                    "Skipping untrusted workspace root {} (trust it with `goofy trust add`)",
+                    path.display()
+                );
+                continue;
+            }
+
+            let mut name = root.name.clone().unwrap_or_else(|| dir_name(&path));
+            if roots.iter().any(|existing| existing.name == name) {
+                name = format!("{}-{}", name, roots.len() + 1);
+            }
+            roots.push(WorkspaceRoot { name, path });
+        }
+
+        Self { roots }
+    }
+
+    /// All roots, primary first
+    pub fn roots(&self) -> &[WorkspaceRoot] {
+        &self.roots
+    }
+
+    /// The primary root (the working directory)
+    pub fn primary(&self) -> &WorkspaceRoot {
+        &self.roots[0]
+    }
+
+    /// Whether the workspace spans more than one root
+    pub fn is_multi_root(&self) -> bool {
+        self.roots.len() > 1
+    }
+
+    /// Look up a root by name
+    pub fn root(&self, name: &str) -> Option<&WorkspaceRoot> {
+        self.roots.iter().find(|root| root.name == name)
+    }
+
+    /// Resolve a path to an absolute path inside the workspace
+    ///
+    /// Absolute paths are returned unchanged and `~/` stands for the home
+    /// directory, as paths are shown. A `name:` prefix selects a root
+    /// explicitly. Otherwise the path is looked up in every root: a unique
+    /// match wins, several matches are ambiguous, and a path that exists
+    /// nowhere (such as a file about to be created) goes to the primary root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, ResolveError> {
+        if Path::new(path).is_absolute() {
+            return Ok(PathBuf::from(path));
+        }
+        if let Some((rest, home)) = path.strip_prefix("~/").zip(dirs::home_dir()) {
+            return Ok(home.join(rest));
+        }
+
+        if let Some((name, rest)) = path.split_once(':') {
+            if let Some(root) = self.root(name) {
+                return Ok(root.path.join(rest));
+            }
+            if self.is_multi_root() && !name.is_empty() && !name.contains(['/', '\\']) {
+                return Err(ResolveError::UnknownRoot(name.to_string()));
+            }
+        }
+
+        let matches: Vec<&WorkspaceRoot> = self
+            .roots
+            .iter()
+            .filter(|root| root.path.join(path).exists())
+            .collect();
+
+        match matches.as_slice() {
+            [] => Ok(self.primary().path.join(path)),
+            [root] => Ok(root.path.join(path)),
+            _ => Err(ResolveError::Ambiguous {
+                path: path.to_string(),
+                candidates: matches
+                    .iter()
+                    .map(|root| format!("{}:{}", root.name, path))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// The root containing an absolute path
+    pub fn root_for(&self, path: &Path) -> Option<&WorkspaceRoot> {
+        // Prefer the deepest root when roots are nested
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(&root.path))
+            .max_by_key(|root| root.path.components().count())
+    }
+
+    /// Display a path relative to its root, prefixed with the root name in
+    /// multi-root workspaces
+    pub fn display_path(&self, path: &Path) -> String {
+        match self.root_for(path) {
+            Some(root) => {
+                let relative = path.strip_prefix(&root.path).unwrap_or(path);
+                if self.is_multi_root() {
+                    format!("{}:{}", root.name, relative.display())
+                } else {
+                    relative.display().to_string()
+                }
+            }
+            None => path.display().to_string(),
+        }
+    }
+
+    /// Overview of the roots for the system prompt
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            "# Workspace roots".to_string(),
+            String::new(),
+            "This workspace spans several directories. Relative paths are resolved across all of them; prefix a path with `<root>:` when it exists in more than one.".to_string(),
+            String::new(),
+        ];
+        for root in &self.roots {
+            let top_level = top_level_entries(&root.path);
+            lines.push(format!("- `{}`: {}", root.name, root.path.display()));
+            if !top_level.is_empty() {
+                lines.push(format!("  contains: {}", top_level.join(", ")));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn dir_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Visible top-level entries of a root, directories marked with a slash
+fn top_level_entries(path: &Path) -> Vec<String> {
+    const MAX_ENTRIES: usize = 20;
+
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                return None;
+            }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            Some(if is_dir { format!("{}/", name) } else { name })
+        })
+        .collect();
+    names.sort();
+    if names.len() > MAX_ENTRIES {
+        names.truncate(MAX_ENTRIES);
+        names.push("…".to_string());
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::trust::TrustLevel;
+    use tempfile::tempdir;
+
+    fn workspace_with_two_roots() -> (tempfile::TempDir, Workspace) {
+        let dir = tempdir().unwrap();
+        let app = dir.path().join("app");
+        let lib = dir.path().join("lib");
+        std::fs::create_dir_all(app.join("src")).unwrap();
+        std::fs::create_dir_all(lib.join("src")).unwrap();
+        std::fs::write(app.join("src/main.rs"), "").unwrap();
+        std::fs::write(app.join("README.md"), "").unwrap();
+        std::fs::write(lib.join("README.md"), "").unwrap();
+
+        let mut trust = TrustStore::default();
+        trust.set(dir.path(), TrustLevel::Trusted);
+        let workspace = Workspace::new(
+            &app,
+            &[WorkspaceRootConfig { path: PathBuf::from("../lib"), name: None }],
+            &trust,
+        );
+        (dir, workspace)
+    }
+
+    #[test]
+    fn test_unique_match_resolves_to_its_root() {
+        let (_dir, workspace) = workspace_with_two_roots();
+        let resolved = workspace.resolve("src/main.rs").unwrap();
+        assert_eq!(resolved, workspace.root("app").unwrap().path.join("src/main.rs"));
+    }
+
+    #[test]
+    fn test_duplicate_paths_are_ambiguous() {
+        let (_dir, workspace) = workspace_with_two_roots();
+        let err = workspace.resolve("README.md").unwrap_err();
+        assert_eq!(
+            err,
+            ResolveError::Ambiguous {
+                path: "README.md".to_string(),
+                candidates: vec!["app:README.md".to_string(), "lib:README.md".to_string()],
+            }
+        );
+
+        let resolved = workspace.resolve("lib:README.md").unwrap();
+        assert_eq!(resolved, workspace.root("lib").unwrap().path.join("README.md"));
+    }
+
+    #[test]
+    fn test_new_files_go_to_primary_root() {
+        let (_dir, workspace) = workspace_with_two_roots();
+        let resolved = workspace.resolve("src/new.rs").unwrap();
+        assert_eq!(resolved, workspace.primary().path.join("src/new.rs"));
+        assert!(matches!(workspace.resolve("nope:a.rs"), Err(ResolveError::UnknownRoot(_))));
+    }
+
+    #[test]
+    fn test_roots_are_canonical_and_untrusted_ones_skipped() {
+        let dir = tempdir().unwrap();
+        let app = dir.path().join("app");
+        let lib = dir.path().join("lib");
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::create_dir_all(&lib).unwrap();
+
+        let extra = [WorkspaceRootConfig { path: PathBuf::from("../lib"), name: None }];
+        let workspace = Workspace::new(&app.join("../app"), &extra, &TrustStore::default());
+        assert_eq!(workspace.primary().path, dunce::canonicalize(&app).unwrap());
+        assert!(!workspace.is_multi_root());
+
+        let mut trust = TrustStore::default();
+        trust.set(&lib, TrustLevel::Trusted);
+        let workspace = Workspace::new(&app.join("../app"), &extra, &trust);
+        assert_eq!(workspace.root("lib").unwrap().path, dunce::canonicalize(&lib).unwrap());
+    }
+
+    #[test]
+    fn test_display_path_uses_root_prefix() {
+        let (_dir, workspace) = workspace_with_two_roots();
+        let path = workspace.root("lib").unwrap().path.join("README.md");
+        assert_eq!(workspace.display_path(&path), "lib:README.md");
+    }
+}
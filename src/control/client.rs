@@ -0,0 +1,63 @@
+//! Control socket client used by `goofy ctl`
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::protocol::{ControlCommand, ControlRequest, ControlResponse};
+
+/// Client for sending commands to a running instance
+pub struct ControlClient {
+    socket_path: PathBuf,
+}
+
+impl ControlClient {
+    /// Create a client for the given socket path
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self { socket_path }
+    }
+
+    /// Send a single command and wait for the response
+    pub async fn send(&self, command: ControlCommand) -> Result<ControlResponse> {
+        let token_path = super::token_path(&self.socket_path);
+        let token = std::fs::read_to_string(&token_path)
+            .with_context(|| format!("Failed to read control token: {}", token_path.display()))?;
+
+        let request = ControlRequest {
+            token: token.trim().to_string(),
+            command,
+        };
+
+        #[cfg(unix)]
+        let stream = tokio::net::UnixStream::connect(&self.socket_path)
+            .await
+            .with_context(|| format!("No running instance at {}", self.socket_path.display()))?;
+
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+            .open(super::pipe_name(&self.socket_path))
+            .with_context(|| format!("No running instance at {}", self.socket_path.display()))?;
+
+        exchange(stream, &request).await
+    }
+}
+
+async fn exchange<S>(stream: S, request: &ControlRequest) -> Result<ControlResponse>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await?;
+
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+    if response.is_empty() {
+        anyhow::bail!("Connection closed without a response");
+    }
+
+    Ok(serde_json::from_str(&response)?)
+}
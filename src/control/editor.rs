@@ -0,0 +1,261 @@
+//! Editor bridge for in-editor pairing over the control socket
+//!
+//! An editor plugin attaches to a running instance with the `attach_editor`
+//! command. From then on the same connection carries requests in the other
+//! direction: Goofy asks the editor for buffer context (current selection,
+//! diagnostics) and sends edits to be applied to open buffers instead of
+//! writing files behind the editor's back.
+//!
+//! Outgoing requests are JSON lines of the form
+//! `{"id": 1, "method": "get_selection"}` and the editor answers with
+//! `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+
+/// How long to wait for the editor to answer a request
+const EDITOR_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request sent from Goofy to the attached editor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorRequest {
+    pub id: u64,
+    #[serde(flatten)]
+    pub method: EditorMethod,
+}
+
+/// Methods the editor is expected to implement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum EditorMethod {
+    /// Current visual selection, or the cursor line if nothing is selected
+    GetSelection,
+    /// Diagnostics for one buffer, or all open buffers
+    GetDiagnostics { file_path: Option<String> },
+    /// Replace text in a buffer, opening it if necessary
+    ApplyEdit {
+        file_path: String,
+        old_string: String,
+        new_string: String,
+        #[serde(default)]
+        replace_all: bool,
+    },
+    /// Apply several replacements to one buffer as a single change
+    ApplyEdits {
+        file_path: String,
+        edits: Vec<EditorEdit>,
+    },
+    /// Replace the whole contents of a buffer, creating the file if necessary
+    WriteFile {
+        file_path: String,
+        content: String,
+    },
+}
+
+/// One replacement within an [`EditorMethod::ApplyEdits`] request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorEdit {
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// The editor's answer to an [`EditorRequest`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorResponse {
+    pub id: u64,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Text selected in the editor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorSelection {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub text: String,
+    #[serde(default)]
+    pub filetype: Option<String>,
+}
+
+/// Handle for sending requests to an attached editor
+#[derive(Debug, Clone)]
+pub struct EditorBridge {
+    name: String,
+    outgoing: mpsc::UnboundedSender<String>,
+    pending: Arc<Mutex<HashMap<u64, oneshot::Sender<EditorResponse>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Slot holding the currently attached editor, if any
+pub type SharedEditor = Arc<RwLock<Option<EditorBridge>>>;
+
+impl EditorBridge {
+    /// Create a bridge that writes request lines to the given connection
+    pub fn new(name: impl Into<String>, outgoing: mpsc::UnboundedSender<String>) -> Self {
+        Self {
+            name: name.into(),
+            outgoing,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Name the editor announced when attaching
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the editor connection is still open
+    pub fn is_connected(&self) -> bool {
+        !self.outgoing.is_closed()
+    }
+
+    /// Send a request and wait for the editor's result
+    pub async fn call(&self, method: EditorMethod) -> Result<serde_json::Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+
+        let mut line = serde_json::to_string(&EditorRequest { id, method })?;
+        line.push('\n');
+        if self.outgoing.send(line).is_err() {
+            self.pending.lock().await.remove(&id);
+            anyhow::bail!("Editor '{}' is no longer connected", self.name);
+        }
+
+        let response = match tokio::time::timeout(EDITOR_TIMEOUT, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => anyhow::bail!("Editor '{}' disconnected", self.name),
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                anyhow::bail!("Editor '{}' did not respond in time", self.name);
+            }
+        };
+
+        match response.error {
+            Some(error) => Err(anyhow::anyhow!("Editor error: {}", error)),
+            None => Ok(response.result.unwrap_or(serde_json::Value::Null)),
+        }
+    }
+
+    /// Fetch the current selection
+    pub async fn selection(&self) -> Result<EditorSelection> {
+        let value = self.call(EditorMethod::GetSelection).await?;
+        serde_json::from_value(value).context("Editor returned an invalid selection")
+    }
+
+    /// Fetch diagnostics for a buffer, or for all buffers
+    pub async fn diagnostics(&self, file_path: Option<String>) -> Result<serde_json::Value> {
+        self.call(EditorMethod::GetDiagnostics { file_path }).await
+    }
+
+    /// Ask the editor to replace text in a buffer
+    pub async fn apply_edit(
+        &self,
+        file_path: String,
+        old_string: String,
+        new_string: String,
+        replace_all: bool,
+    ) -> Result<serde_json::Value> {
+        self.call(EditorMethod::ApplyEdit { file_path, old_string, new_string, replace_all })
+            .await
+    }
+
+    /// Ask the editor to apply several replacements to a buffer in order
+    pub async fn apply_edits(&self, file_path: String, edits: Vec<EditorEdit>) -> Result<serde_json::Value> {
+        self.call(EditorMethod::ApplyEdits { file_path, edits }).await
+    }
+
+    /// Ask the editor to replace a buffer's contents
+    pub async fn write_file(&self, file_path: String, content: String) -> Result<serde_json::Value> {
+        self.call(EditorMethod::WriteFile { file_path, content }).await
+    }
+
+    /// Deliver a response read from the connection to its waiting caller
+    pub async fn resolve(&self, response: EditorResponse) {
+        if let Some(tx) = self.pending.lock().await.remove(&response.id) {
+            let _ = tx.send(response);
+        }
+    }
+
+    /// Fail all outstanding requests after the connection closed
+    pub async fn close(&self) {
+        self.pending.lock().await.clear();
+    }
+
+    /// Whether two handles refer to the same editor connection
+    pub fn same_connection(&self, other: &EditorBridge) -> bool {
+        Arc::ptr_eq(&self.pending, &other.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = EditorRequest { id: 7, method: EditorMethod::GetSelection };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"id":7,"method":"get_selection"}"#
+        );
+
+        let request = EditorRequest {
+            id: 8,
+            method: EditorMethod::GetDiagnostics { file_path: Some("a.rs".to_string()) },
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"id":8,"method":"get_diagnostics","params":{"file_path":"a.rs"}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_resolves_with_response() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let bridge = EditorBridge::new("nvim", tx);
+
+        let responder = bridge.clone();
+        tokio::spawn(async move {
+            let line = rx.recv().await.unwrap();
+            let request: EditorRequest = serde_json::from_str(line.trim()).unwrap();
+            responder
+                .resolve(EditorResponse {
+                    id: request.id,
+                    result: Some(serde_json::json!({
+                        "file_path": "src/main.rs",
+                        "start_line": 3,
+                        "end_line": 4,
+                        "text": "fn main() {}",
+                    })),
+                    error: None,
+                })
+                .await;
+        });
+
+        let selection = bridge.selection().await.unwrap();
+        assert_eq!(selection.file_path, "src/main.rs");
+        assert_eq!(selection.start_line, 3);
+    }
+
+    #[tokio::test]
+    async fn test_call_fails_when_disconnected() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(rx);
+        let bridge = EditorBridge::new("nvim", tx);
+
+        assert!(!bridge.is_connected());
+        assert!(bridge.call(EditorMethod::GetSelection).await.is_err());
+    }
+}
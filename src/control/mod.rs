@@ -0,0 +1,41 @@
+//! Local control interface for external tools
+//!
+//! A running Goofy instance listens on a Unix domain socket (a named pipe on
+//! Windows) so editor plugins and scripts can drive it: open a session, insert
+//! text into the prompt, or run a prompt and get the result back. Requests are
+//! newline-delimited JSON and must carry the per-instance token that is written
+//! next to the socket with owner-only permissions.
+//!
+//! An editor can also attach to the instance (see [`editor`]), turning its
+//! connection into a two-way channel for buffer context and edits.
+
+pub mod protocol;
+pub mod server;
+pub mod client;
+pub mod editor;
+
+pub use protocol::{ControlCommand, ControlRequest, ControlResponse};
+pub use server::{ControlMessage, ControlServer};
+pub use client::ControlClient;
+pub use editor::{EditorBridge, EditorSelection, SharedEditor};
+
+use std::path::{Path, PathBuf};
+
+/// Default socket path inside the data directory
+pub fn default_socket_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("control.sock")
+}
+
+/// Path of the token file that accompanies a socket
+pub fn token_path(socket_path: &Path) -> PathBuf {
+    socket_path.with_extension("token")
+}
+
+/// Named pipe name used on Windows, derived from the socket path
+#[cfg(windows)]
+pub fn pipe_name(socket_path: &Path) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    socket_path.hash(&mut hasher);
+    format!(r"\\.\pipe\goofy-control-{:x}", hasher.finish())
+}
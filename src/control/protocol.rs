@@ -0,0 +1,83 @@
+//! Wire protocol for the control interface
+
+use serde::{Deserialize, Serialize};
+
+/// A command sent to a running instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Check that the instance is alive
+    Ping,
+    /// Switch to an existing session
+    OpenSession { session_id: String },
+    /// Insert text into the prompt editor
+    InsertText { text: String },
+    /// Run a prompt and return the assistant's reply
+    RunPrompt { prompt: String },
+    /// Register this connection as the editor for buffer context and edits
+    AttachEditor { name: String },
+}
+
+/// A request line: the auth token plus a command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlRequest {
+    pub token: String,
+    #[serde(flatten)]
+    pub command: ControlCommand,
+}
+
+/// A response line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ControlResponse {
+    /// Successful response with an optional result payload
+    pub fn ok(result: Option<serde_json::Value>) -> Self {
+        Self { ok: true, result, error: None }
+    }
+
+    /// Failed response with an error message
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { ok: false, result: None, error: Some(message.into()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = ControlRequest {
+            token: "secret".to_string(),
+            command: ControlCommand::RunPrompt { prompt: "hi".to_string() },
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        assert_eq!(line, r#"{"token":"secret","command":"run_prompt","prompt":"hi"}"#);
+
+        let parsed: ControlRequest = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.command, request.command);
+    }
+
+    #[test]
+    fn test_attach_editor_wire_format() {
+        let line = r#"{"token":"secret","command":"attach_editor","name":"nvim"}"#;
+        let parsed: ControlRequest = serde_json::from_str(line).unwrap();
+        assert_eq!(parsed.command, ControlCommand::AttachEditor { name: "nvim".to_string() });
+    }
+
+    #[test]
+    fn test_response_omits_empty_fields() {
+        let line = serde_json::to_string(&ControlResponse::ok(None)).unwrap();
+        assert_eq!(line, r#"{"ok":true}"#);
+
+        let line = serde_json::to_string(&ControlResponse::error("nope")).unwrap();
+        assert_eq!(line, r#"{"ok":false,"error":"nope"}"#);
+    }
+}
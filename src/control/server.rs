@@ -0,0 +1,433 @@
+//! Control socket server
+
+use anyhow::{Context, Result};
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use super::editor::{EditorBridge, EditorResponse, SharedEditor};
+use super::protocol::{ControlCommand, ControlRequest, ControlResponse};
+
+/// A command received over the socket, with a channel for the reply
+#[derive(Debug)]
+pub struct ControlMessage {
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<ControlResponse>,
+}
+
+/// Control socket server bound to a running instance
+pub struct ControlServer {
+    socket_path: PathBuf,
+    token_path: PathBuf,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ControlServer {
+    /// Bind the control socket and start accepting connections
+    ///
+    /// Returns the server handle and the receiver on which authenticated
+    /// commands are delivered to the application. An editor that attaches is
+    /// stored in `editor` for tools to use.
+    ///
+    /// The token file is only written once the socket is bound, so a launch
+    /// that finds another instance listening leaves that instance's token in
+    /// place.
+    pub async fn start(
+        socket_path: &Path,
+        editor: SharedEditor,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<ControlMessage>)> {
+        let token = generate_token();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = spawn_listener(socket_path, token.clone(), tx, editor).await?;
+
+        // From here on the handle owns the socket and token file; dropping it
+        // on failure removes both
+        let server = Self {
+            socket_path: socket_path.to_path_buf(),
+            token_path: super::token_path(socket_path),
+            task,
+        };
+        write_token(&server.token_path, &token)?;
+        info!("Control interface listening on {}", socket_path.display());
+
+        Ok((server, rx))
+    }
+
+    /// Path the server is listening on
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.socket_path);
+        let _ = std::fs::remove_file(&self.token_path);
+    }
+}
+
+#[cfg(unix)]
+async fn spawn_listener(
+    socket_path: &Path,
+    token: String,
+    tx: mpsc::UnboundedSender<ControlMessage>,
+    editor: SharedEditor,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::net::{UnixListener, UnixStream};
+
+    if socket_path.exists() {
+        // Refuse to steal the socket from another live instance
+        if UnixStream::connect(socket_path).await.is_ok() {
+            anyhow::bail!("Another instance is already listening on {}", socket_path.display());
+        }
+        std::fs::remove_file(socket_path)
+            .with_context(|| format!("Failed to remove stale socket: {}", socket_path.display()))?;
+    }
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to bind control socket: {}", socket_path.display()))?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(stream, token.clone(), tx.clone(), editor.clone()));
+                }
+                Err(e) => {
+                    warn!("Control socket accept failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(windows)]
+async fn spawn_listener(
+    socket_path: &Path,
+    token: String,
+    tx: mpsc::UnboundedSender<ControlMessage>,
+    editor: SharedEditor,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let pipe_name = super::pipe_name(socket_path);
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&pipe_name)
+        .with_context(|| format!("Failed to create control pipe: {}", pipe_name))?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                warn!("Control pipe connect failed: {}", e);
+                break;
+            }
+            let connected = server;
+            server = match ServerOptions::new().create(&pipe_name) {
+                Ok(next) => next,
+                Err(e) => {
+                    warn!("Failed to create next control pipe instance: {}", e);
+                    break;
+                }
+            };
+            tokio::spawn(handle_connection(connected, token.clone(), tx.clone(), editor.clone()));
+        }
+    }))
+}
+
+/// Serve newline-delimited requests on a single connection
+///
+/// Replies are written by a dedicated task so that requests can be handled
+/// concurrently and, once an editor has attached, so that editor requests can
+/// be interleaved with command responses on the same connection.
+async fn handle_connection<S>(
+    stream: S,
+    token: String,
+    tx: mpsc::UnboundedSender<ControlMessage>,
+    editor: SharedEditor,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (line_tx, mut line_rx) = mpsc::unbounded_channel::<String>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(line) = line_rx.recv().await {
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+            let _ = writer.flush().await;
+        }
+    });
+
+    let mut attached: Option<EditorBridge> = None;
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Replies to our own requests once this connection is an editor
+        if let Some(bridge) = &attached {
+            if let Ok(response) = serde_json::from_str::<EditorResponse>(&line) {
+                bridge.resolve(response).await;
+                continue;
+            }
+        }
+
+        let command = match authenticate(&line, &token) {
+            Ok(command) => command,
+            Err(response) => {
+                send_response(&line_tx, &response);
+                continue;
+            }
+        };
+
+        if let ControlCommand::AttachEditor { name } = command {
+            info!("Editor attached: {}", name);
+            let bridge = EditorBridge::new(name, line_tx.clone());
+            *editor.write().await = Some(bridge.clone());
+            attached = Some(bridge);
+            send_response(&line_tx, &ControlResponse::ok(None));
+            continue;
+        }
+
+        let tx = tx.clone();
+        let line_tx = line_tx.clone();
+        tokio::spawn(async move {
+            let response = dispatch(command, &tx).await;
+            send_response(&line_tx, &response);
+        });
+    }
+
+    if let Some(bridge) = attached {
+        bridge.close().await;
+        let mut slot = editor.write().await;
+        if slot.as_ref().is_some_and(|current| current.same_connection(&bridge)) {
+            *slot = None;
+        }
+        info!("Editor detached: {}", bridge.name());
+    }
+    writer_task.abort();
+    debug!("Control connection closed");
+}
+
+/// Queue a response line for the connection's writer
+fn send_response(line_tx: &mpsc::UnboundedSender<String>, response: &ControlResponse) {
+    match serde_json::to_string(response) {
+        Ok(mut encoded) => {
+            encoded.push('\n');
+            let _ = line_tx.send(encoded);
+        }
+        Err(e) => warn!("Failed to encode control response: {}", e),
+    }
+}
+
+/// Parse a request line and check its token
+fn authenticate(line: &str, token: &str) -> Result<ControlCommand, ControlResponse> {
+    let request: ControlRequest = serde_json::from_str(line)
+        .map_err(|e| ControlResponse::error(format!("Invalid request: {}", e)))?;
+
+    if !constant_time_eq(request.token.as_bytes(), token.as_bytes()) {
+        warn!("Rejected control request with invalid token");
+        return Err(ControlResponse::error("Invalid token"));
+    }
+
+    Ok(request.command)
+}
+
+/// Hand an authenticated command to the application and wait for its reply
+async fn dispatch(command: ControlCommand, tx: &mpsc::UnboundedSender<ControlMessage>) -> ControlResponse {
+    if command == ControlCommand::Ping {
+        return ControlResponse::ok(Some(serde_json::json!("pong")));
+    }
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    let message = ControlMessage {
+        command,
+        reply: reply_tx,
+    };
+    if tx.send(message).is_err() {
+        return ControlResponse::error("Instance is shutting down");
+    }
+
+    reply_rx
+        .await
+        .unwrap_or_else(|_| ControlResponse::error("Command was dropped"))
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write the token file readable only by the current user
+fn write_token(path: &Path, token: &str) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write control token: {}", path.display()))?;
+    // The mode only applies to new files; tighten one left from before
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(token.as_bytes())?;
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn test_generated_tokens_are_unique() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_authenticate_rejects_bad_token() {
+        let line = r#"{"token":"wrong","command":"ping"}"#;
+        let response = authenticate(line, "right").unwrap_err();
+        assert!(!response.ok);
+        assert_eq!(response.error.as_deref(), Some("Invalid token"));
+
+        let line = r#"{"token":"right","command":"ping"}"#;
+        assert_eq!(authenticate(line, "right").unwrap(), ControlCommand::Ping);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_token_file_is_private_even_when_it_existed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.token");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_token(&path, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_round_trip_over_socket() {
+        use crate::control::ControlClient;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("control.sock");
+        let (_server, mut rx) = ControlServer::start(&socket_path, SharedEditor::default()).await.unwrap();
+
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                let reply = match message.command {
+                    ControlCommand::RunPrompt { prompt } => {
+                        ControlResponse::ok(Some(serde_json::json!(prompt.to_uppercase())))
+                    }
+                    _ => ControlResponse::error("unsupported"),
+                };
+                let _ = message.reply.send(reply);
+            }
+        });
+
+        let client = ControlClient::new(socket_path);
+        let pong = client.send(ControlCommand::Ping).await.unwrap();
+        assert!(pong.ok);
+
+        let response = client
+            .send(ControlCommand::RunPrompt { prompt: "hello".to_string() })
+            .await
+            .unwrap();
+        assert_eq!(response.result, Some(serde_json::json!("HELLO")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_second_instance_keeps_first_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("control.sock");
+        let token_path = crate::control::token_path(&socket_path);
+
+        let (_first, _rx) = ControlServer::start(&socket_path, SharedEditor::default()).await.unwrap();
+        let token = std::fs::read_to_string(&token_path).unwrap();
+
+        assert!(ControlServer::start(&socket_path, SharedEditor::default()).await.is_err());
+        assert_eq!(std::fs::read_to_string(&token_path).unwrap(), token);
+        assert!(socket_path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_attached_editor_answers_requests() {
+        use tokio::net::UnixStream;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("control.sock");
+        let editor = SharedEditor::default();
+        let (_server, _rx) = ControlServer::start(&socket_path, editor.clone()).await.unwrap();
+        let token = std::fs::read_to_string(crate::control::token_path(&socket_path)).unwrap();
+
+        let stream = UnixStream::connect(&socket_path).await.unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+
+        let attach = format!(r#"{{"token":"{}","command":"attach_editor","name":"nvim"}}"#, token);
+        writer.write_all(format!("{}\n", attach).as_bytes()).await.unwrap();
+        let reply: ControlResponse = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert!(reply.ok);
+
+        let bridge = editor.read().await.clone().expect("editor attached");
+        let call = tokio::spawn(async move { bridge.diagnostics(None).await });
+
+        let request: crate::control::editor::EditorRequest =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        let answer = format!(r#"{{"id":{},"result":[]}}"#, request.id);
+        writer.write_all(format!("{}\n", answer).as_bytes()).await.unwrap();
+
+        assert_eq!(call.await.unwrap().unwrap(), serde_json::json!([]));
+
+        drop(writer);
+        drop(lines);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(editor.read().await.is_none());
+    }
+}
@@ -0,0 +1,19 @@
+//! Filesystem watching for the agent's file context
+//!
+//! The agent sees a file as it was when it last read it. When the user or
+//! another program changes the file afterwards, edits based on that content
+//! would silently undo the change. The tracker records every file the agent
+//! reads and whether it changed since; the watcher feeds it change events
+//! from the workspace roots.
+//!
+//! The tool manager uses the tracker in two ways: tool results carry a notice
+//! about files the agent read that changed on disk, and edits of such files
+//! are rejected until the agent reads them again. The tracker also keeps the
+//! files touched this session in order, which the interface's recent files
+//! switcher lists.
+
+pub mod tracker;
+pub mod watcher;
+
+pub use tracker::{FileAccess, FileTracker, TouchedFile};
+pub use watcher::FileWatcher;
@@ -0,0 +1,192 @@
+//! Record of the files the agent has read and edited
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Most files kept in the list of recently touched files
+const MAX_TOUCHED: usize = 200;
+
+/// Size and modification time of a file, `None` if it does not exist
+type Stamp = Option<(u64, Option<SystemTime>)>;
+
+fn stamp(path: &Path) -> Stamp {
+    fs::metadata(path).ok().map(|metadata| (metadata.len(), metadata.modified().ok()))
+}
+
+#[derive(Debug)]
+struct TrackedFile {
+    /// File as the agent last read it
+    read: Stamp,
+    /// Changed on disk since, and the agent has not been told yet
+    unnoticed_change: bool,
+}
+
+/// How a tool call touched a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAccess {
+    Read,
+    Edit,
+}
+
+/// A file the agent read or edited this session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TouchedFile {
+    pub path: PathBuf,
+    /// How the file was last touched
+    pub last_access: FileAccess,
+    /// Whether the agent edited the file at any point
+    pub edited: bool,
+}
+
+/// Files the agent has read, and whether they changed since
+#[derive(Debug, Default)]
+pub struct FileTracker {
+    files: Mutex<HashMap<PathBuf, TrackedFile>>,
+    /// Files read or edited, least recently touched first
+    touched: Mutex<Vec<TouchedFile>>,
+}
+
+impl FileTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the agent now knows the current content of a file
+    ///
+    /// Called after reads, and after the agent's own edits.
+    pub fn record_read(&self, path: &Path) {
+        let file = TrackedFile { read: stamp(path), unnoticed_change: false };
+        self.lock().insert(path.to_path_buf(), file);
+    }
+
+    /// Whether the agent has read the file
+    pub fn was_read(&self, path: &Path) -> bool {
+        self.lock().contains_key(path)
+    }
+
+    /// Note a change reported for a file
+    ///
+    /// Files the agent never read, and events that left the file as the
+    /// agent last read it (like its own edits), are ignored.
+    pub fn mark_changed(&self, path: &Path) {
+        if let Some(file) = self.lock().get_mut(path) {
+            if file.read != stamp(path) {
+                file.unnoticed_change = true;
+            }
+        }
+    }
+
+    /// Check every file the agent read for changes, for when events were missed
+    pub fn rescan(&self) {
+        for (path, file) in self.lock().iter_mut() {
+            if file.read != stamp(path) {
+                file.unnoticed_change = true;
+            }
+        }
+    }
+
+    /// Whether a file the agent read has changed on disk since
+    pub fn is_stale(&self, path: &Path) -> bool {
+        self.lock().get(path).is_some_and(|file| file.read != stamp(path))
+    }
+
+    /// Files that changed since the agent read them and it was not told about
+    ///
+    /// Each change is returned once.
+    pub fn take_changes(&self) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .lock()
+            .iter_mut()
+            .filter(|(_, file)| file.unnoticed_change)
+            .map(|(path, file)| {
+                file.unnoticed_change = false;
+                path.clone()
+            })
+            .collect();
+        changed.sort();
+        changed
+    }
+
+    /// Note that a tool call read or edited a file
+    pub fn record_touch(&self, path: &Path, access: FileAccess) {
+        let mut touched = self.touched.lock().unwrap_or_else(|e| e.into_inner());
+        let edited = match touched.iter().position(|file| file.path == path) {
+            Some(index) => touched.remove(index).edited,
+            None => false,
+        };
+        touched.push(TouchedFile {
+            path: path.to_path_buf(),
+            last_access: access,
+            edited: edited || access == FileAccess::Edit,
+        });
+        if touched.len() > MAX_TOUCHED {
+            touched.remove(0);
+        }
+    }
+
+    /// Files the agent read or edited this session, most recent first
+    pub fn recent(&self) -> Vec<TouchedFile> {
+        let touched = self.touched.lock().unwrap_or_else(|e| e.into_inner());
+        touched.iter().rev().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, TrackedFile>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_after_read_are_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let read = dir.path().join("read.rs");
+        let unread = dir.path().join("unread.rs");
+        fs::write(&read, "fn main() {}").unwrap();
+        fs::write(&unread, "").unwrap();
+
+        let tracker = FileTracker::new();
+        tracker.record_read(&read);
+        tracker.mark_changed(&read);
+        assert!(!tracker.is_stale(&read));
+        assert!(tracker.take_changes().is_empty());
+
+        fs::write(&read, "fn main() { println!(\"changed\"); }").unwrap();
+        fs::write(&unread, "changed").unwrap();
+        tracker.mark_changed(&read);
+        tracker.mark_changed(&unread);
+        assert!(tracker.is_stale(&read));
+        assert!(!tracker.is_stale(&unread));
+        assert_eq!(tracker.take_changes(), vec![read.clone()]);
+        assert!(tracker.take_changes().is_empty());
+
+        tracker.record_read(&read);
+        assert!(!tracker.is_stale(&read));
+        fs::remove_file(&read).unwrap();
+        assert!(tracker.is_stale(&read));
+
+        // Changes made while no events arrived are found by a rescan
+        tracker.rescan();
+        assert_eq!(tracker.take_changes(), vec![read.clone()]);
+    }
+
+    #[test]
+    fn test_recent_files_are_deduplicated_newest_first() {
+        let tracker = FileTracker::new();
+        tracker.record_touch(Path::new("/src/a.rs"), FileAccess::Edit);
+        tracker.record_touch(Path::new("/src/b.rs"), FileAccess::Read);
+        tracker.record_touch(Path::new("/src/a.rs"), FileAccess::Read);
+
+        let recent = tracker.recent();
+        let paths: Vec<&Path> = recent.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("/src/a.rs"), Path::new("/src/b.rs")]);
+        assert_eq!(recent[0].last_access, FileAccess::Read);
+        assert!(recent[0].edited);
+        assert!(!recent[1].edited);
+    }
+}
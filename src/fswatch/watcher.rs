@@ -0,0 +1,83 @@
+//! Watching workspace roots for changes
+
+use anyhow::Result;
+use notify::{recommended_watcher, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use super::FileTracker;
+use crate::app::Suspend;
+use crate::index::ProjectIndex;
+
+/// Watches directories and reports changed files to a tracker and the
+/// project indexes
+///
+/// Watching stops when the watcher is dropped. While suspended the roots are
+/// not watched; on resuming they are watched again, the tracker rescans the
+/// files the agent read and the indexes every file for changes made in
+/// between.
+pub struct FileWatcher {
+    watcher: Mutex<RecommendedWatcher>,
+    tracker: Arc<FileTracker>,
+    indexes: Vec<Arc<ProjectIndex>>,
+    roots: Vec<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Create a watcher reporting to `tracker` and `indexes`
+    pub fn new(tracker: Arc<FileTracker>, indexes: Vec<Arc<ProjectIndex>>) -> Result<Self> {
+        let reported = tracker.clone();
+        let reindexed = indexes.clone();
+        let watcher = recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                    for path in &event.paths {
+                        reported.mark_changed(path);
+                        for index in &reindexed {
+                            index.mark_changed(path);
+                        }
+                    }
+                }
+            }
+            Err(e) => debug!("File watcher error: {}", e),
+        })?;
+        Ok(Self { watcher: Mutex::new(watcher), tracker, indexes, roots: Vec::new() })
+    }
+
+    /// Watch a directory and everything below it
+    pub fn watch(&mut self, root: &Path) -> Result<()> {
+        self.lock().watch(root, RecursiveMode::Recursive)?;
+        self.roots.push(root.to_path_buf());
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecommendedWatcher> {
+        self.watcher.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Suspend for FileWatcher {
+    fn suspend(&self) {
+        let mut watcher = self.lock();
+        for root in &self.roots {
+            if let Err(e) = watcher.unwatch(root) {
+                debug!("Could not stop watching {}: {}", root.display(), e);
+            }
+        }
+    }
+
+    fn resume(&self) {
+        let mut watcher = self.lock();
+        for root in &self.roots {
+            if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+                debug!("Could not watch {} again: {}", root.display(), e);
+            }
+        }
+        drop(watcher);
+        self.tracker.rescan();
+        for index in &self.indexes {
+            index.rescan();
+        }
+    }
+}
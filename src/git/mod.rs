@@ -0,0 +1,178 @@
+//! Git integration
+//!
+//! Goofy talks to repositories through the `git` executable, like the asset
+//! registry and workspace statistics do, so it honours the user's git
+//! configuration, hooks and credentials. The agent reaches it through the
+//! `git_status`, `git_diff`, `git_log` and `git_commit` tools, and the chat
+//! sidebar shows the branch and the number of changed files.
+
+pub mod status;
+
+pub use status::{GitStatus, StatusEntry};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Most bytes of diff returned at once
+const MAX_DIFF_BYTES: usize = 100_000;
+
+/// A commit as listed by `git log`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+/// A git working tree
+#[derive(Debug, Clone)]
+pub struct GitRepo {
+    root: PathBuf,
+}
+
+impl GitRepo {
+    /// Open the repository containing `path`
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let output = Self::run(path.as_ref(), &["rev-parse", "--show-toplevel"]).await?;
+        Ok(Self { root: PathBuf::from(output.trim()) })
+    }
+
+    /// Top level directory of the working tree
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// URL of a remote, like `origin`
+    pub async fn remote_url(&self, name: &str) -> Result<String> {
+        Ok(self.git(&["remote", "get-url", name]).await?.trim().to_string())
+    }
+
+    async fn run(dir: &Path, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            anyhow::bail!("git {} failed: {}", args.first().unwrap_or(&""), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        Self::run(&self.root, args).await
+    }
+
+    /// Branch and changed files
+    pub async fn status(&self) -> Result<GitStatus> {
+        let output = self.git(&["status", "--porcelain=v2", "--branch", "--untracked-files=all"]).await?;
+        Ok(GitStatus::parse(&output))
+    }
+
+    /// Unstaged changes, or staged ones with `staged`, limited to `paths` if any
+    pub async fn diff(&self, staged: bool, paths: &[String]) -> Result<String> {
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+        if staged {
+            args.push("--cached");
+        }
+        args.push("--");
+        args.extend(paths.iter().map(String::as_str));
+        let mut diff = self.git(&args).await?;
+        if diff.len() > MAX_DIFF_BYTES {
+            let cut = (0..=MAX_DIFF_BYTES).rev().find(|&i| diff.is_char_boundary(i)).unwrap_or(0);
+            diff.truncate(cut);
+            diff.push_str("\n... diff truncated; ask for fewer paths to see the rest");
+        }
+        Ok(diff)
+    }
+
+    /// The last `count` commits, of `path` only if given
+    pub async fn log(&self, count: usize, path: Option<&str>) -> Result<Vec<Commit>> {
+        let count = format!("-n{}", count);
+        let mut args = vec!["log", count.as_str(), "--date=short", "--format=%h%x1f%an%x1f%ad%x1f%s"];
+        if let Some(path) = path {
+            args.extend(["--", path]);
+        }
+        let output = match self.git(&args).await {
+            Ok(output) => output,
+            // A repository without commits has no log
+            Err(_) if self.git(&["rev-parse", "--verify", "HEAD"]).await.is_err() => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(output.lines().filter_map(parse_log_line).collect())
+    }
+
+    /// Commit the staged changes, staging `paths` first, and return the new commit
+    ///
+    /// Hooks run as they would for the user; a failing hook fails the commit.
+    pub async fn commit(&self, message: &str, paths: &[String]) -> Result<Commit> {
+        if message.trim().is_empty() {
+            anyhow::bail!("Commit message is empty");
+        }
+        if !paths.is_empty() {
+            let mut args = vec!["add", "--"];
+            args.extend(paths.iter().map(String::as_str));
+            self.git(&args).await?;
+        }
+        if !self.status().await?.entries.iter().any(|entry| !entry.is_untracked() && entry.staged != '.') {
+            anyhow::bail!("Nothing staged to commit");
+        }
+        self.git(&["commit", "--quiet", "-m", message]).await?;
+        self.log(1, None)
+            .await?
+            .pop()
+            .context("Commit succeeded but HEAD could not be read")
+    }
+}
+
+/// Parse a line of `git log --format=%h%x1f%an%x1f%ad%x1f%s`
+fn parse_log_line(line: &str) -> Option<Commit> {
+    let mut fields = line.splitn(4, '\u{1f}');
+    Some(Commit {
+        hash: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        date: fields.next()?.to_string(),
+        subject: fields.next()?.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command as StdCommand;
+
+    fn git(dir: &Path, args: &[&str]) {
+        assert!(StdCommand::new("git").arg("-C").arg(dir).args(args).status().unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_log_in_a_repository() {
+        if StdCommand::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        git(temp_dir.path(), &["init", "--quiet"]);
+        git(temp_dir.path(), &["config", "user.name", "Test"]);
+        git(temp_dir.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(temp_dir.path().join("a.txt"), "one\n").unwrap();
+
+        let repo = GitRepo::open(temp_dir.path()).await.unwrap();
+        assert!(repo.log(5, None).await.unwrap().is_empty());
+        assert_eq!(repo.status().await.unwrap().dirty_count(), 1);
+        assert!(repo.commit("Nothing yet", &[]).await.is_err());
+
+        let commit = repo.commit("Add a", &["a.txt".to_string()]).await.unwrap();
+        assert_eq!(commit.subject, "Add a");
+        assert!(repo.status().await.unwrap().is_clean());
+
+        std::fs::write(temp_dir.path().join("a.txt"), "two\n").unwrap();
+        assert!(repo.diff(false, &[]).await.unwrap().contains("+two"));
+        assert!(repo.diff(true, &[]).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Working tree status read from `git status --porcelain=v2 --branch`
+
+use serde::Serialize;
+use std::fmt::Write;
+
+/// State of a changed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusEntry {
+    pub path: String,
+    /// Path before a rename or copy
+    pub original_path: Option<String>,
+    /// Change staged in the index, `.` for none, `?` for untracked files
+    pub staged: char,
+    /// Change in the working tree not staged yet
+    pub unstaged: char,
+}
+
+impl StatusEntry {
+    pub fn is_untracked(&self) -> bool {
+        self.staged == '?'
+    }
+
+    pub fn is_conflicted(&self) -> bool {
+        self.staged == 'U' || self.unstaged == 'U'
+    }
+}
+
+/// Branch and changed files of a working tree
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GitStatus {
+    /// Checked out branch, `None` on a detached head
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub entries: Vec<StatusEntry>,
+}
+
+impl GitStatus {
+    /// Parse the output of `git status --porcelain=v2 --branch`
+    pub fn parse(output: &str) -> Self {
+        let mut status = Self::default();
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+                match key {
+                    "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                    "branch.upstream" => status.upstream = Some(value.to_string()),
+                    "branch.ab" => {
+                        for count in value.split_whitespace() {
+                            if let Some(ahead) = count.strip_prefix('+') {
+                                status.ahead = ahead.parse().unwrap_or(0);
+                            } else if let Some(behind) = count.strip_prefix('-') {
+                                status.behind = behind.parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            let entry = match line.split_at(line.len().min(2)) {
+                ("? ", path) => Some(StatusEntry {
+                    path: path.to_string(),
+                    original_path: None,
+                    staged: '?',
+                    unstaged: '?',
+                }),
+                // Ordinary: 1 XY sub mH mI mW hH hI path
+                ("1 ", rest) => Self::parse_changed(rest, 7, false),
+                // Renamed or copied: 2 XY sub mH mI mW hH hI Xscore path<TAB>origPath
+                ("2 ", rest) => Self::parse_changed(rest, 8, true),
+                // Unmerged: u XY sub m1 m2 m3 mW h1 h2 h3 path
+                ("u ", rest) => Self::parse_changed(rest, 9, false),
+                _ => None,
+            };
+            status.entries.extend(entry);
+        }
+        status
+    }
+
+    fn parse_changed(rest: &str, fields_before_path: usize, renamed: bool) -> Option<StatusEntry> {
+        let mut fields = rest.splitn(fields_before_path + 1, ' ');
+        let mut xy = fields.next()?.chars();
+        let (staged, unstaged) = (xy.next()?, xy.next()?);
+        let path = fields.nth(fields_before_path - 1)?;
+        let (path, original_path) = match path.split_once('\t') {
+            Some((path, original)) if renamed => (path, Some(original.to_string())),
+            _ => (path, None),
+        };
+        Some(StatusEntry {
+            path: path.to_string(),
+            original_path,
+            staged,
+            unstaged,
+        })
+    }
+
+    /// Number of changed and untracked files
+    pub fn dirty_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Status as shown to the agent, grouped like `git status`
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        match &self.branch {
+            Some(branch) => {
+                let _ = write!(out, "On branch {}", branch);
+            }
+            None => out.push_str("HEAD detached"),
+        }
+        if let Some(upstream) = &self.upstream {
+            let _ = write!(out, " (tracking {}, {} ahead, {} behind)", upstream, self.ahead, self.behind);
+        }
+        out.push('\n');
+
+        if self.is_clean() {
+            out.push_str("Nothing to commit, working tree clean\n");
+            return out;
+        }
+
+        let sections: [(&str, Box<dyn Fn(&StatusEntry) -> Option<char>>); 4] = [
+            ("Conflicts", Box::new(|entry| entry.is_conflicted().then_some('U'))),
+            ("Staged", Box::new(|entry| (!entry.is_conflicted() && !entry.is_untracked() && entry.staged != '.').then_some(entry.staged))),
+            ("Not staged", Box::new(|entry| (!entry.is_conflicted() && !entry.is_untracked() && entry.unstaged != '.').then_some(entry.unstaged))),
+            ("Untracked", Box::new(|entry| entry.is_untracked().then_some('?'))),
+        ];
+        for (title, change) in sections {
+            let lines: Vec<String> = self
+                .entries
+                .iter()
+                .filter_map(|entry| {
+                    let change = change(entry)?;
+                    Some(match &entry.original_path {
+                        Some(original) => format!("  {} {} -> {}", change, original, entry.path),
+                        None => format!("  {} {}", change, entry.path),
+                    })
+                })
+                .collect();
+            if !lines.is_empty() {
+                let _ = writeln!(out, "\n{}:", title);
+                out.push_str(&lines.join("\n"));
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "# branch.oid 1f2e3d\n\
+# branch.head main\n\
+# branch.upstream origin/main\n\
+# branch.ab +2 -1\n\
+1 .M N... 100644 100644 100644 aaa bbb src/main.rs\n\
+1 A. N... 000000 100644 100644 000 ccc src/new file.rs\n\
+2 R. N... 100644 100644 100644 ddd eee R100 src/after.rs\tsrc/before.rs\n\
+u UU N... 100644 100644 100644 100644 f1 f2 f3 Cargo.lock\n\
+? notes.txt\n";
+
+    #[test]
+    fn test_parse_porcelain_v2() {
+        let status = GitStatus::parse(OUTPUT);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!(status.dirty_count(), 5);
+        assert_eq!(status.entries[1].path, "src/new file.rs");
+        assert_eq!(status.entries[2].original_path.as_deref(), Some("src/before.rs"));
+        assert!(status.entries[3].is_conflicted());
+        assert!(status.entries[4].is_untracked());
+
+        let rendered = status.render();
+        assert!(rendered.starts_with("On branch main (tracking origin/main, 2 ahead, 1 behind)"));
+        assert!(rendered.contains("Staged:\n  A src/new file.rs\n  R src/before.rs -> src/after.rs"));
+        assert!(rendered.contains("Not staged:\n  M src/main.rs"));
+    }
+
+    #[test]
+    fn test_detached_and_clean() {
+        let status = GitStatus::parse("# branch.oid 1f2e3d\n# branch.head (detached)\n");
+        assert_eq!(status.branch, None);
+        assert!(status.render().contains("working tree clean"));
+    }
+}
@@ -0,0 +1,491 @@
+//! Hooks around tool calls
+//!
+//! Hooks let a project check or follow up on what the agent does without
+//! changing goofy: `pre_tool_use` hooks run before a tool call and can
+//! refuse it, `post_tool_use` hooks run after it, for instance to format the
+//! file an edit touched. A hook applies to the calls whose tool name matches
+//! its `tool` pattern and, when it has one, whose string arguments match its
+//! `arguments` pattern.
+//!
+//! A hook either denies the call outright with a `message`, or runs a shell
+//! command in the workspace that gets the call as JSON on stdin. A pre hook
+//! exiting with status 2 refuses the call with its stderr as the reason; any
+//! other failure is reported and the call goes ahead. A command may also
+//! print `{"decision": "deny", "reason": "..."}` or `{"message": "..."}`.
+//! What hooks print is added to the tool's result, so the model sees it.
+//!
+//! Deny rules always apply. Hooks running commands come from configuration
+//! a repository can ship, so like MCP servers they only run in trusted
+//! workspaces.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Exit status of a pre hook refusing the call
+const DENY_EXIT_CODE: i32 = 2;
+
+/// Most characters of a hook's output added to a tool result
+const MAX_OUTPUT_CHARS: usize = 4_000;
+
+/// Hooks run around tool calls
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HooksConfig {
+    /// Hooks run before a tool call, which may refuse it
+    #[serde(default)]
+    pub pre_tool_use: Vec<HookRule>,
+
+    /// Hooks run after a tool call
+    #[serde(default)]
+    pub post_tool_use: Vec<HookRule>,
+
+    /// Longest a hook command may run, in seconds
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_tool_use: Vec::new(),
+            post_tool_use: Vec::new(),
+            timeout_secs: default_hook_timeout(),
+        }
+    }
+}
+
+fn default_hook_timeout() -> u64 {
+    60
+}
+
+/// When a hook applies and what it does
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HookRule {
+    /// Pattern the whole tool name must match, like `edit|write`; every
+    /// tool if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+
+    /// Pattern one of the call's string arguments must contain a match of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+
+    /// Shell command to run, with the call as JSON on stdin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Refuse matching calls without running anything (pre hooks only)
+    #[serde(default)]
+    pub deny: bool,
+
+    /// Reason given to the model for a refusal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreToolUse,
+    PostToolUse,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            Self::PreToolUse => "pre_tool_use",
+            Self::PostToolUse => "post_tool_use",
+        }
+    }
+}
+
+/// A tool call hooks are run for
+#[derive(Debug, Clone, Copy)]
+pub struct HookCall<'a> {
+    pub session_id: &'a str,
+    pub tool_name: &'a str,
+    pub arguments: &'a HashMap<String, Value>,
+}
+
+/// What the hooks of a call decided
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HookOutcome {
+    /// Why the call was refused, when a hook refused it
+    pub denied: Option<String>,
+    /// What the hooks printed, for the model
+    pub notes: Vec<String>,
+}
+
+impl HookOutcome {
+    /// The notes as text to append to a tool result
+    pub fn appendix(&self) -> String {
+        self.notes.iter().map(|note| format!("\n\n{}", note)).collect()
+    }
+}
+
+#[derive(Debug)]
+struct Hook {
+    tool: Option<Regex>,
+    arguments: Option<Regex>,
+    command: Option<String>,
+    deny: bool,
+    message: Option<String>,
+}
+
+impl Hook {
+    fn compile(rule: &HookRule) -> Result<Self> {
+        let tool = rule
+            .tool
+            .as_deref()
+            .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+            .transpose()
+            .with_context(|| format!("Invalid hook tool pattern: {:?}", rule.tool))?;
+        let arguments = rule
+            .arguments
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .with_context(|| format!("Invalid hook arguments pattern: {:?}", rule.arguments))?;
+        if !rule.deny && rule.command.is_none() {
+            anyhow::bail!("A hook needs a command or deny");
+        }
+        Ok(Self {
+            tool,
+            arguments,
+            command: rule.command.clone(),
+            deny: rule.deny,
+            message: rule.message.clone(),
+        })
+    }
+
+    fn matches(&self, call: &HookCall) -> bool {
+        if self.tool.as_ref().is_some_and(|tool| !tool.is_match(call.tool_name)) {
+            return false;
+        }
+        match &self.arguments {
+            Some(pattern) => string_arguments(call.arguments).iter().any(|value| pattern.is_match(value)),
+            None => true,
+        }
+    }
+}
+
+/// String values of the arguments, nested ones too
+fn string_arguments(arguments: &HashMap<String, Value>) -> Vec<&str> {
+    fn collect<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+        match value {
+            Value::String(text) => strings.push(text),
+            Value::Array(items) => items.iter().for_each(|item| collect(item, strings)),
+            Value::Object(fields) => fields.values().for_each(|field| collect(field, strings)),
+            _ => {}
+        }
+    }
+    let mut strings = Vec::new();
+    arguments.values().for_each(|value| collect(value, &mut strings));
+    strings
+}
+
+/// Runs the configured hooks of tool calls
+#[derive(Debug, Default)]
+pub struct HookEngine {
+    pre: Vec<Hook>,
+    post: Vec<Hook>,
+    timeout: Duration,
+    working_dir: Option<PathBuf>,
+}
+
+impl HookEngine {
+    /// Hooks of the configuration, leaving out those running commands
+    /// unless `run_commands`
+    pub fn new(config: &HooksConfig, run_commands: bool) -> Result<Self> {
+        let compile = |rules: &[HookRule]| -> Result<Vec<Hook>> {
+            let mut hooks = Vec::new();
+            for rule in rules {
+                let hook = Hook::compile(rule)?;
+                if hook.command.is_some() && !hook.deny && !run_commands {
+                    warn!(
+                        "Hook `{}` not run: the workspace is not trusted",
+                        rule.command.as_deref().unwrap_or_default()
+                    );
+                    continue;
+                }
+                hooks.push(hook);
+            }
+            Ok(hooks)
+        };
+        Ok(Self {
+            pre: compile(&config.pre_tool_use)?,
+            post: compile(&config.post_tool_use)?,
+            timeout: Duration::from_secs(config.timeout_secs),
+            working_dir: None,
+        })
+    }
+
+    /// Run hook commands in `dir`
+    pub fn with_working_dir(mut self, dir: PathBuf) -> Self {
+        self.working_dir = Some(dir);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pre.is_empty() && self.post.is_empty()
+    }
+
+    /// Run the pre hooks of a call, stopping at the first that refuses it
+    pub async fn pre_tool_use(&self, call: &HookCall<'_>) -> HookOutcome {
+        let mut outcome = HookOutcome::default();
+        for hook in self.pre.iter().filter(|hook| hook.matches(call)) {
+            if hook.deny {
+                let reason = hook
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| format!("A hook of this project refuses this {} call", call.tool_name));
+                outcome.denied = Some(reason);
+                return outcome;
+            }
+            let Some(command) = &hook.command else {
+                continue;
+            };
+            let input = self.input(HookEvent::PreToolUse, call, None);
+            match self.run(command, &input).await {
+                Ok(output) => {
+                    if let Some(reason) = output.denial() {
+                        outcome.denied = Some(hook.message.clone().unwrap_or(reason));
+                        return outcome;
+                    }
+                    outcome.notes.extend(output.note(command));
+                }
+                Err(e) => outcome.notes.push(format!("Hook `{}` failed: {:#}", command, e)),
+            }
+        }
+        outcome
+    }
+
+    /// Run the post hooks of a call that gave `result`
+    pub async fn post_tool_use(&self, call: &HookCall<'_>, result: &Value) -> HookOutcome {
+        let mut outcome = HookOutcome::default();
+        for hook in self.post.iter().filter(|hook| hook.matches(call)) {
+            let Some(command) = &hook.command else {
+                continue;
+            };
+            let input = self.input(HookEvent::PostToolUse, call, Some(result));
+            match self.run(command, &input).await {
+                Ok(output) => outcome.notes.extend(output.note(command)),
+                Err(e) => outcome.notes.push(format!("Hook `{}` failed: {:#}", command, e)),
+            }
+        }
+        outcome
+    }
+
+    fn input(&self, event: HookEvent, call: &HookCall, result: Option<&Value>) -> Value {
+        let mut input = json!({
+            "event": event.name(),
+            "session_id": call.session_id,
+            "tool_name": call.tool_name,
+            "arguments": call.arguments,
+        });
+        if let Some(result) = result {
+            input["result"] = result.clone();
+        }
+        input
+    }
+
+    async fn run(&self, command: &str, input: &Value) -> Result<HookOutput> {
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", command]);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", command]);
+            cmd
+        };
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        cmd.env("GOOFY_HOOK_EVENT", input["event"].as_str().unwrap_or_default())
+            .env("GOOFY_TOOL_NAME", input["tool_name"].as_str().unwrap_or_default());
+        if let Some(path) = input["arguments"]["file_path"].as_str() {
+            cmd.env("GOOFY_FILE_PATH", path);
+        }
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        debug!("Running hook: {}", command);
+        let mut child = cmd.spawn().context("Failed to start")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // Hooks that don't read their input close stdin early
+            let _ = stdin.write_all(input.to_string().as_bytes()).await;
+        }
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out after {}s", self.timeout.as_secs()))??;
+        Ok(HookOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
+
+/// What a hook command printed and how it exited
+#[derive(Debug)]
+struct HookOutput {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+impl HookOutput {
+    fn structured(&self) -> Option<serde_json::Map<String, Value>> {
+        match serde_json::from_str(&self.stdout) {
+            Ok(Value::Object(fields)) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Why the hook refused the call, if it did
+    fn denial(&self) -> Option<String> {
+        if self.code == Some(DENY_EXIT_CODE) {
+            let reason = if self.stderr.is_empty() { &self.stdout } else { &self.stderr };
+            return Some(if reason.is_empty() { "Refused by a hook".to_string() } else { reason.clone() });
+        }
+        let fields = self.structured()?;
+        (fields.get("decision")?.as_str()? == "deny").then(|| {
+            fields
+                .get("reason")
+                .and_then(Value::as_str)
+                .unwrap_or("Refused by a hook")
+                .to_string()
+        })
+    }
+
+    /// What to tell the model about the hook's run, if anything
+    fn note(&self, command: &str) -> Option<String> {
+        let text = match self.structured() {
+            Some(fields) => fields.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            None => self.stdout.clone(),
+        };
+        let text = if self.code == Some(0) {
+            text
+        } else {
+            let status = self.code.map_or("a signal".to_string(), |code| format!("status {}", code));
+            let details = [text.as_str(), self.stderr.as_str()]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("exited with {}\n{}", status, details).trim_end().to_string()
+        };
+        if text.is_empty() {
+            return None;
+        }
+        let text: String = if text.chars().count() > MAX_OUTPUT_CHARS {
+            let kept: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+            format!("{}\n[output truncated]", kept)
+        } else {
+            text
+        };
+        Some(format!("Hook `{}`: {}", command, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(tool: &str, arguments: Option<&str>, command: Option<&str>, deny: bool) -> HookRule {
+        HookRule {
+            tool: Some(tool.to_string()),
+            arguments: arguments.map(str::to_string),
+            command: command.map(str::to_string),
+            deny,
+            message: None,
+        }
+    }
+
+    fn bash_call(command: &str) -> HashMap<String, Value> {
+        HashMap::from([("command".to_string(), json!(command))])
+    }
+
+    #[tokio::test]
+    async fn test_deny_rules_refuse_matching_calls() {
+        let config = HooksConfig {
+            pre_tool_use: vec![HookRule {
+                message: Some("Don't delete recursively".to_string()),
+                ..rule("bash", Some(r"rm\s+-rf"), None, true)
+            }],
+            ..HooksConfig::default()
+        };
+        let engine = HookEngine::new(&config, false).unwrap();
+        let arguments = bash_call("rm -rf build");
+        let call = HookCall { session_id: "s", tool_name: "bash", arguments: &arguments };
+        let outcome = engine.pre_tool_use(&call).await;
+        assert_eq!(outcome.denied.as_deref(), Some("Don't delete recursively"));
+
+        let arguments = bash_call("ls");
+        let call = HookCall { session_id: "s", tool_name: "bash", arguments: &arguments };
+        assert_eq!(engine.pre_tool_use(&call).await, HookOutcome::default());
+        // The tool pattern matches whole names only
+        let arguments = bash_call("rm -rf build");
+        let call = HookCall { session_id: "s", tool_name: "background_bash", arguments: &arguments };
+        assert_eq!(engine.pre_tool_use(&call).await.denied, None);
+    }
+
+    #[tokio::test]
+    async fn test_commands_only_run_in_trusted_workspaces() {
+        let config = HooksConfig {
+            post_tool_use: vec![rule("edit", None, Some("echo formatted"), false)],
+            ..HooksConfig::default()
+        };
+        assert!(HookEngine::new(&config, false).unwrap().is_empty());
+        assert!(HookEngine::new(
+            &HooksConfig {
+                pre_tool_use: vec![rule("(", None, None, true)],
+                ..HooksConfig::default()
+            },
+            true
+        )
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_output_reaches_the_model() {
+        let config = HooksConfig {
+            pre_tool_use: vec![rule(
+                "bash",
+                None,
+                Some(r#"grep -q '"sudo' && { echo 'no sudo here' >&2; exit 2; }; echo '{"message": "checked"}'"#),
+                false,
+            )],
+            post_tool_use: vec![rule("edit|write", Some(r"\.rs$"), Some("cat >/dev/null; echo formatted $GOOFY_FILE_PATH"), false)],
+            ..HooksConfig::default()
+        };
+        let engine = HookEngine::new(&config, true).unwrap();
+
+        let arguments = bash_call("sudo reboot");
+        let call = HookCall { session_id: "s", tool_name: "bash", arguments: &arguments };
+        assert_eq!(engine.pre_tool_use(&call).await.denied.as_deref(), Some("no sudo here"));
+        let arguments = bash_call("cargo test");
+        let call = HookCall { session_id: "s", tool_name: "bash", arguments: &arguments };
+        let outcome = engine.pre_tool_use(&call).await;
+        assert_eq!(outcome.denied, None);
+        assert!(outcome.appendix().ends_with(": checked"));
+
+        let arguments = HashMap::from([("file_path".to_string(), json!("src/main.rs"))]);
+        let call = HookCall { session_id: "s", tool_name: "edit", arguments: &arguments };
+        let outcome = engine.post_tool_use(&call, &json!({ "success": true })).await;
+        assert_eq!(outcome.notes, ["Hook `cat >/dev/null; echo formatted $GOOFY_FILE_PATH`: formatted src/main.rs"]);
+    }
+}
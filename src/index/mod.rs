@@ -0,0 +1,457 @@
+//! Index of the workspace's files and the symbols defined in them
+//!
+//! Finding where `fn refresh` is defined by grepping every file takes
+//! seconds in a large repository; the index answers it from memory. At
+//! start the workspace's files are listed with `rg --files`, which skips
+//! what `.gitignore` ignores (or, without ripgrep, by walking the workspace
+//! with [`GitIgnore`]), and the definitions in each source file are noted
+//! as [`symbols`] finds them.
+//!
+//! The index is kept in a cache file in the data directory, with the size
+//! and modification time of each file, so only files changed since the last
+//! run are read again. While running, the file watcher reports changed paths
+//! with [`ProjectIndex::mark_changed`], and queries re-read those first.
+
+pub mod symbols;
+
+pub use symbols::{Symbol, SymbolKind};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info};
+
+use crate::config::IndexConfig;
+use crate::utils::gitignore::GitIgnore;
+
+/// Format of the cache file, changed when what it holds changes
+const CACHE_VERSION: u32 = 1;
+
+/// A file as it was indexed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct IndexedFile {
+    size: u64,
+    /// Modification time in nanoseconds since the epoch
+    modified: Option<u64>,
+    symbols: Vec<Symbol>,
+}
+
+impl IndexedFile {
+    fn is_current(&self, stamp: (u64, Option<u64>)) -> bool {
+        (self.size, self.modified) == stamp
+    }
+}
+
+/// Size and modification time of a file, `None` if it is not a file
+fn stamp(path: &Path) -> Option<(u64, Option<u64>)> {
+    let metadata = fs::metadata(path).ok().filter(|metadata| metadata.is_file())?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos() as u64);
+    Some((metadata.len(), modified))
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexCache {
+    version: u32,
+    root: PathBuf,
+    /// Files by path relative to the root
+    files: BTreeMap<PathBuf, IndexedFile>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    files: BTreeMap<PathBuf, IndexedFile>,
+    /// Whether the files have been listed
+    ready: bool,
+    /// Whether listing stopped at `max_files`
+    truncated: bool,
+}
+
+/// A symbol and the file it is defined in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolLocation {
+    pub path: PathBuf,
+    pub symbol: Symbol,
+}
+
+/// What building the index found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexStats {
+    pub files: usize,
+    pub symbols: usize,
+    /// Files read, the others coming from the cache
+    pub read: usize,
+}
+
+/// Files and symbols of a directory
+pub struct ProjectIndex {
+    root: PathBuf,
+    config: IndexConfig,
+    cache_path: Option<PathBuf>,
+    state: RwLock<IndexState>,
+    /// Paths reported changed since the index last looked
+    changed: Mutex<HashSet<PathBuf>>,
+    /// Whether every file is checked again, after change events were missed
+    rescan: AtomicBool,
+}
+
+impl ProjectIndex {
+    /// An empty index of `root`, filled by [`ProjectIndex::build`]
+    pub fn new(root: &Path, config: IndexConfig) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            config,
+            cache_path: None,
+            state: RwLock::new(IndexState::default()),
+            changed: Mutex::new(HashSet::new()),
+            rescan: AtomicBool::new(false),
+        }
+    }
+
+    /// Keep the index in `data_dir/index/`, a file per workspace root
+    pub fn with_cache_dir(mut self, data_dir: &Path) -> Self {
+        let digest = Sha256::digest(self.root.to_string_lossy().as_bytes());
+        let name: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        self.cache_path = Some(data_dir.join("index").join(format!("{}.json", name)));
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the files have been listed, so queries can be answered
+    pub fn is_ready(&self) -> bool {
+        self.read_state().ready
+    }
+
+    /// List the files and read those that changed since they were indexed
+    ///
+    /// Blocks while listing and reading; called from a blocking task.
+    pub fn build(&self) -> Result<IndexStats> {
+        let mut previous = std::mem::take(&mut self.write_state().files);
+        if previous.is_empty() {
+            previous = self.load_cache().unwrap_or_default();
+        }
+        let (listed, truncated) = self.list_files();
+        let mut files = BTreeMap::new();
+        let mut read = 0;
+        for relative in listed {
+            let path = self.root.join(&relative);
+            let Some(stamp) = stamp(&path) else {
+                continue;
+            };
+            let file = match previous.remove(&relative) {
+                Some(file) if file.is_current(stamp) => file,
+                _ => {
+                    read += 1;
+                    self.index_file(&path, stamp)
+                }
+            };
+            files.insert(relative, file);
+        }
+        let stats = IndexStats {
+            files: files.len(),
+            symbols: files.values().map(|file| file.symbols.len()).sum(),
+            read,
+        };
+        {
+            let mut state = self.write_state();
+            state.files = files;
+            state.ready = true;
+            state.truncated = truncated;
+        }
+        self.changed.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.save_cache();
+        info!(
+            "Indexed {} files with {} symbols in {} ({} read)",
+            stats.files,
+            stats.symbols,
+            self.root.display(),
+            stats.read
+        );
+        Ok(stats)
+    }
+
+    /// Note a path reported changed, created or removed
+    pub fn mark_changed(&self, path: &Path) {
+        if path.starts_with(&self.root) {
+            self.changed.lock().unwrap_or_else(|e| e.into_inner()).insert(path.to_path_buf());
+        }
+    }
+
+    /// Check every file again on the next query, for when events were missed
+    pub fn rescan(&self) {
+        self.rescan.store(true, Ordering::Relaxed);
+    }
+
+    /// Bring the index up to date with the changes reported since it last
+    /// looked
+    pub fn refresh(&self) {
+        if !self.is_ready() {
+            return;
+        }
+        if self.rescan.swap(false, Ordering::Relaxed) {
+            if let Err(e) = self.build() {
+                debug!("Could not rescan the index of {}: {:#}", self.root.display(), e);
+            }
+            return;
+        }
+        let changed: Vec<PathBuf> = self.changed.lock().unwrap_or_else(|e| e.into_inner()).drain().collect();
+        if changed.is_empty() {
+            return;
+        }
+        for path in changed {
+            let Ok(relative) = path.strip_prefix(&self.root).map(Path::to_path_buf) else {
+                continue;
+            };
+            match stamp(&path) {
+                Some(stamp) if self.is_indexed(&path, &relative) => {
+                    let current = self.read_state().files.get(&relative).is_some_and(|file| file.is_current(stamp));
+                    if !current {
+                        let file = self.index_file(&path, stamp);
+                        self.write_state().files.insert(relative, file);
+                    }
+                }
+                // A directory moved in is reported without its files
+                None if path.is_dir() => {
+                    let known = self.read_state().files.keys().any(|file| file.starts_with(&relative));
+                    if !known && self.is_indexed(&path, &relative) {
+                        self.add_dir(&path);
+                    }
+                }
+                Some(_) => {}
+                _ => self.write_state().files.retain(|file, _| !file.starts_with(&relative)),
+            }
+        }
+        self.save_cache();
+    }
+
+    /// The files indexed, in path order
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.refresh();
+        self.read_state().files.keys().map(|relative| self.root.join(relative)).collect()
+    }
+
+    /// Whether listing stopped at the configured number of files
+    pub fn is_truncated(&self) -> bool {
+        self.read_state().truncated
+    }
+
+    /// Definitions named `query`, or, when there are none, those whose names
+    /// contain it ignoring case; at most `limit`, in path order
+    pub fn find_symbols(&self, query: &str, kind: Option<SymbolKind>, limit: usize) -> Vec<SymbolLocation> {
+        self.refresh();
+        let state = self.read_state();
+        let search = |matches: &dyn Fn(&str) -> bool| -> Vec<SymbolLocation> {
+            state
+                .files
+                .iter()
+                .flat_map(|(path, file)| file.symbols.iter().map(move |symbol| (path, symbol)))
+                .filter(|(_, symbol)| kind.is_none_or(|kind| symbol.kind == kind) && matches(&symbol.name))
+                .take(limit)
+                .map(|(path, symbol)| SymbolLocation {
+                    path: self.root.join(path),
+                    symbol: symbol.clone(),
+                })
+                .collect()
+        };
+        let exact = search(&|name| name == query);
+        if !exact.is_empty() {
+            return exact;
+        }
+        let query = query.to_lowercase();
+        search(&|name| name.to_lowercase().contains(&query))
+    }
+
+    /// Index the files below a directory
+    fn add_dir(&self, dir: &Path) {
+        let mut found = Vec::new();
+        walk(&self.root, dir, &GitIgnore::for_dir(dir), self.config.max_files, &mut found);
+        for relative in found {
+            let path = self.root.join(&relative);
+            if let Some(stamp) = stamp(&path) {
+                let file = self.index_file(&path, stamp);
+                self.write_state().files.insert(relative, file);
+            }
+        }
+    }
+
+    /// Whether a file belongs in the index: not hidden nor ignored
+    fn is_indexed(&self, path: &Path, relative: &Path) -> bool {
+        let hidden = relative.components().any(|component| match component {
+            Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+            _ => false,
+        });
+        let ignored = path
+            .parent()
+            .is_some_and(|parent| GitIgnore::for_dir(parent).is_ignored(path, false));
+        !hidden && !ignored
+    }
+
+    fn index_file(&self, path: &Path, (size, modified): (u64, Option<u64>)) -> IndexedFile {
+        let symbols = if size <= self.config.max_file_bytes && symbols::has_symbols(path) {
+            fs::read_to_string(path)
+                .map(|content| symbols::extract(path, &content))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        IndexedFile { size, modified, symbols }
+    }
+
+    /// Files below the root relative to it, and whether there were more than
+    /// `max_files`
+    fn list_files(&self) -> (Vec<PathBuf>, bool) {
+        let mut files = match self.list_with_ripgrep() {
+            Some(files) => files,
+            None => {
+                let mut files = Vec::new();
+                walk(&self.root, &self.root, &GitIgnore::for_dir(&self.root), self.config.max_files + 1, &mut files);
+                files
+            }
+        };
+        files.sort();
+        let truncated = files.len() > self.config.max_files;
+        files.truncate(self.config.max_files);
+        (files, truncated)
+    }
+
+    /// Files `rg --files` lists, `None` if ripgrep isn't installed
+    fn list_with_ripgrep(&self) -> Option<Vec<PathBuf>> {
+        let output = Command::new("rg")
+            .args(["--files", "--no-messages", "--no-require-git"])
+            .current_dir(&self.root)
+            .output()
+            .ok()?;
+        // ripgrep exits with 1 when there are no files and 2 on errors,
+        // which still leaves the files it could list unless it couldn't start
+        match output.status.code() {
+            None => return None,
+            Some(2) if output.stdout.is_empty() => return None,
+            Some(_) => {}
+        }
+        Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| PathBuf::from(line.strip_prefix("./").unwrap_or(line)))
+                .collect(),
+        )
+    }
+
+    fn load_cache(&self) -> Option<BTreeMap<PathBuf, IndexedFile>> {
+        let path = self.cache_path.as_ref()?;
+        let content = fs::read(path).ok()?;
+        match serde_json::from_slice::<IndexCache>(&content) {
+            Ok(cache) if cache.version == CACHE_VERSION && cache.root == self.root => Some(cache.files),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Ignoring the index cache {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn save_cache(&self) {
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        let cache = IndexCache {
+            version: CACHE_VERSION,
+            root: self.root.clone(),
+            files: self.read_state().files.clone(),
+        };
+        let saved = (|| -> Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let temporary = path.with_extension("json.tmp");
+            fs::write(&temporary, serde_json::to_vec(&cache)?)?;
+            fs::rename(&temporary, path).with_context(|| format!("Failed to replace {}", path.display()))
+        })();
+        if let Err(e) = saved {
+            debug!("Could not save the index cache: {:#}", e);
+        }
+    }
+
+    fn read_state(&self) -> std::sync::RwLockReadGuard<'_, IndexState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_state(&self) -> std::sync::RwLockWriteGuard<'_, IndexState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Files below `dir` that are neither hidden nor ignored, relative to `root`,
+/// up to `limit`
+fn walk(root: &Path, dir: &Path, ignore: &GitIgnore, limit: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if files.len() >= limit {
+            return;
+        }
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if entry.file_name().to_string_lossy().starts_with('.') || ignore.matches(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            walk(root, &path, &ignore.enter(&path), limit, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_finds_symbols_and_follows_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn parse_rule() {}\npub struct Rule;\n").unwrap();
+        fs::write(root.join("target/gen.rs"), "fn parse_rule() {}\n").unwrap();
+
+        let index = ProjectIndex::new(&root, IndexConfig::default()).with_cache_dir(dir.path());
+        assert!(index.find_symbols("parse_rule", None, 10).is_empty());
+        let stats = index.build().unwrap();
+        assert_eq!((stats.files, stats.symbols), (1, 2));
+
+        let found = index.find_symbols("parse_rule", None, 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, root.join("src/lib.rs"));
+        assert_eq!(found[0].symbol.line, 1);
+        assert_eq!(index.find_symbols("rule", Some(SymbolKind::Struct), 10)[0].symbol.name, "Rule");
+
+        fs::write(root.join("src/main.rs"), "\n\nfn main() {}\n").unwrap();
+        fs::remove_file(root.join("src/lib.rs")).unwrap();
+        index.mark_changed(&root.join("src/main.rs"));
+        index.mark_changed(&root.join("src/lib.rs"));
+        assert_eq!(index.find_symbols("main", None, 10)[0].symbol.line, 3);
+        assert!(index.find_symbols("parse_rule", None, 10).is_empty());
+
+        // A new index reads only what changed since the cache was saved
+        let reopened = ProjectIndex::new(&root, IndexConfig::default()).with_cache_dir(dir.path());
+        assert_eq!(reopened.build().unwrap().read, 0);
+        assert_eq!(reopened.files(), index.files());
+    }
+}
@@ -0,0 +1,234 @@
+//! Finding definitions in source files, the way ctags does
+//!
+//! Definitions are recognized by the line they start on, with one set of
+//! patterns per language: good enough to know where `fn parse_rule` or
+//! `class Session` is without parsing the file.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// What a symbol is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Struct,
+    Enum,
+    Trait,
+    Interface,
+    Type,
+    Constant,
+    Module,
+    Macro,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Class => "class",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Module => "module",
+            SymbolKind::Macro => "macro",
+        }
+    }
+}
+
+/// A definition in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Line of the definition, from 1
+    pub line: usize,
+}
+
+/// Definition patterns of a language
+type Patterns = Vec<(SymbolKind, Regex)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+    Java,
+    Ruby,
+}
+
+impl Language {
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyi" => Language::Python,
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Language::JavaScript,
+            "go" => Language::Go,
+            "java" | "kt" | "kts" | "cs" | "scala" => Language::Java,
+            "rb" => Language::Ruby,
+            _ => return None,
+        })
+    }
+
+    /// Definition patterns, the first matching a line winning; each captures
+    /// the name as `name`
+    fn patterns(&self) -> &'static [(SymbolKind, Regex)] {
+        static PATTERNS: OnceLock<Vec<(Language, Patterns)>> = OnceLock::new();
+        let all = PATTERNS.get_or_init(|| {
+            let compile = |patterns: &[(SymbolKind, &str)]| {
+                patterns
+                    .iter()
+                    .map(|(kind, pattern)| (*kind, Regex::new(pattern).expect("valid symbol pattern")))
+                    .collect()
+            };
+            const RUST_VIS: &str = r"^\s*(?:pub(?:\([^)]*\))?\s+)?";
+            const JS_EXPORT: &str = r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?";
+            const JAVA_MODIFIERS: &str =
+                r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|sealed|open|data|partial)\s+)*";
+            vec![
+                (
+                    Language::Rust,
+                    compile(&[
+                        (SymbolKind::Function, &format!(r#"{}(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+(?P<name>\w+)"#, RUST_VIS)),
+                        (SymbolKind::Struct, &format!(r"{}(?:struct|union)\s+(?P<name>\w+)", RUST_VIS)),
+                        (SymbolKind::Enum, &format!(r"{}enum\s+(?P<name>\w+)", RUST_VIS)),
+                        (SymbolKind::Trait, &format!(r"{}(?:unsafe\s+)?trait\s+(?P<name>\w+)", RUST_VIS)),
+                        (SymbolKind::Type, &format!(r"{}type\s+(?P<name>\w+)", RUST_VIS)),
+                        (SymbolKind::Constant, &format!(r"{}(?:const|static)\s+(?:mut\s+)?(?P<name>\w+)\s*:", RUST_VIS)),
+                        (SymbolKind::Module, &format!(r"{}mod\s+(?P<name>\w+)", RUST_VIS)),
+                        (SymbolKind::Macro, r"^\s*macro_rules!\s*(?P<name>\w+)"),
+                    ]),
+                ),
+                (
+                    Language::Python,
+                    compile(&[
+                        (SymbolKind::Function, r"^\s*(?:async\s+)?def\s+(?P<name>\w+)"),
+                        (SymbolKind::Class, r"^\s*class\s+(?P<name>\w+)"),
+                        (SymbolKind::Constant, r"^(?P<name>[A-Z][A-Z0-9_]*)\s*(?::[^=]+)?=[^=]"),
+                    ]),
+                ),
+                (
+                    Language::JavaScript,
+                    compile(&[
+                        (SymbolKind::Function, &format!(r"{}(?:async\s+)?function\s*\*?\s*(?P<name>[\w$]+)", JS_EXPORT)),
+                        (SymbolKind::Class, &format!(r"{}(?:abstract\s+)?class\s+(?P<name>[\w$]+)", JS_EXPORT)),
+                        (SymbolKind::Interface, &format!(r"{}interface\s+(?P<name>[\w$]+)", JS_EXPORT)),
+                        (SymbolKind::Enum, &format!(r"{}(?:const\s+)?enum\s+(?P<name>[\w$]+)", JS_EXPORT)),
+                        (SymbolKind::Type, &format!(r"{}type\s+(?P<name>[\w$]+)\s*(?:<[^=]*>)?\s*=", JS_EXPORT)),
+                        (
+                            SymbolKind::Function,
+                            r"^\s*(?:export\s+)?(?:const|let|var)\s+(?P<name>[\w$]+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|(?:\([^)]*\)|[\w$]+)\s*(?::[^=]+)?=>)",
+                        ),
+                    ]),
+                ),
+                (
+                    Language::Go,
+                    compile(&[
+                        (SymbolKind::Function, r"^func\s+(?:\([^)]*\)\s*)?(?P<name>\w+)"),
+                        (SymbolKind::Struct, r"^type\s+(?P<name>\w+)(?:\[[^\]]*\])?\s+struct\b"),
+                        (SymbolKind::Interface, r"^type\s+(?P<name>\w+)(?:\[[^\]]*\])?\s+interface\b"),
+                        (SymbolKind::Type, r"^type\s+(?P<name>\w+)"),
+                    ]),
+                ),
+                (
+                    Language::Java,
+                    compile(&[
+                        (SymbolKind::Class, &format!(r"{}(?:class|object|record)\s+(?P<name>\w+)", JAVA_MODIFIERS)),
+                        (SymbolKind::Interface, &format!(r"{}(?:interface|trait)\s+(?P<name>\w+)", JAVA_MODIFIERS)),
+                        (SymbolKind::Enum, &format!(r"{}enum\s+(?:class\s+)?(?P<name>\w+)", JAVA_MODIFIERS)),
+                        (SymbolKind::Function, &format!(r"{}(?:suspend\s+)?fun\s+(?:<[^>]*>\s*)?(?:\w+\.)?(?P<name>\w+)", JAVA_MODIFIERS)),
+                    ]),
+                ),
+                (
+                    Language::Ruby,
+                    compile(&[
+                        (SymbolKind::Function, r"^\s*def\s+(?:self\.)?(?P<name>\w+[?!=]?)"),
+                        (SymbolKind::Class, r"^\s*class\s+(?P<name>[A-Z]\w*)"),
+                        (SymbolKind::Module, r"^\s*module\s+(?P<name>[A-Z]\w*)"),
+                    ]),
+                ),
+            ]
+        });
+        all.iter()
+            .find(|(language, _)| language == self)
+            .map_or(&[], |(_, patterns)| patterns.as_slice())
+    }
+}
+
+/// Whether symbols are looked for in a file
+pub fn has_symbols(path: &Path) -> bool {
+    Language::of(path).is_some()
+}
+
+/// Definitions in a file's content, in order
+pub fn extract(path: &Path, content: &str) -> Vec<Symbol> {
+    let Some(language) = Language::of(path) else {
+        return Vec::new();
+    };
+    let patterns = language.patterns();
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            patterns.iter().find_map(|(kind, regex)| {
+                let name = regex.captures(line)?.name("name")?.as_str();
+                Some(Symbol {
+                    name: name.to_string(),
+                    kind: *kind,
+                    line: index + 1,
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(file: &str, content: &str) -> Vec<(String, &'static str, usize)> {
+        extract(Path::new(file), content)
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind.as_str(), symbol.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_definitions_are_found_by_language() {
+        let rust = "pub struct Index {\n    files: usize,\n}\n\nimpl Index {\n    pub(crate) async fn refresh(&self) {}\n}\nconst LIMIT: usize = 3;\nmacro_rules! log {}\n";
+        assert_eq!(
+            names("src/index.rs", rust),
+            [
+                ("Index".to_string(), "struct", 1),
+                ("refresh".to_string(), "function", 6),
+                ("LIMIT".to_string(), "constant", 8),
+                ("log".to_string(), "macro", 9),
+            ]
+        );
+
+        let typescript = "export default class App {}\nexport const handler = async (req: Request) => {};\ntype Id = string;\nconst count = 3;\n";
+        assert_eq!(
+            names("app.ts", typescript),
+            [
+                ("App".to_string(), "class", 1),
+                ("handler".to_string(), "function", 2),
+                ("Id".to_string(), "type", 3),
+            ]
+        );
+
+        let go = "func (s *Server) Serve() error {\ntype Server struct {\ntype Handler interface {\n";
+        let kinds: Vec<&str> = names("main.go", go).into_iter().map(|(_, kind, _)| kind).collect();
+        assert_eq!(kinds, ["function", "struct", "interface"]);
+
+        assert_eq!(names("tool.py", "class Tool:\n    async def run(self):\n")[1].0, "run");
+        assert!(names("notes.txt", "fn main() {}").is_empty());
+    }
+}
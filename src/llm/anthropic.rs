@@ -14,6 +14,8 @@ use crate::llm::{
         ContentBlock, ToolCall, TokenUsage, FinishReason, Tool,
     },
     errors::{LlmError, LlmResult},
+    sse::SseBuffer,
+    images::ImageLimits,
 };
 
 /// Anthropic API provider
@@ -200,21 +202,7 @@ impl AnthropicProvider {
                             }
                         }
                     } else {
-                        let status = resp.status();
-                        let error_msg = utils::extract_error_message(resp).await;
-                        
-                        let error = match status.as_u16() {
-                            429 => LlmError::RateLimitError(error_msg),
-                            401 | 403 => LlmError::AuthError(error_msg),
-                            400 => {
-                                if error_msg.contains("context_length_exceeded") || error_msg.contains("too long") {
-                                    LlmError::ContextLimitError(error_msg)
-                                } else {
-                                    LlmError::ApiError(error_msg)
-                                }
-                            }
-                            _ => LlmError::ApiError(error_msg),
-                        };
+                        let error = utils::error_from_response(resp).await;
                         
                         if !utils::is_retryable_error(&error) || attempt == self.options.max_retries {
                             return Err(error);
@@ -302,13 +290,6 @@ impl LlmProvider for AnthropicProvider {
             }
         }
         
-        let finish_reason = match response.stop_reason.as_deref() {
-            Some("end_turn") => Some(FinishReason::Stop),
-            Some("max_tokens") => Some(FinishReason::Length),
-            Some("tool_use") => Some(FinishReason::ToolCalls),
-            _ => None,
-        };
-        
         Ok(ProviderResponse {
             content,
             tool_calls,
@@ -317,7 +298,7 @@ impl LlmProvider for AnthropicProvider {
                 output_tokens: response.usage.output_tokens,
                 total_tokens: response.usage.input_tokens + response.usage.output_tokens,
             },
-            finish_reason,
+            finish_reason: finish_reason(response.stop_reason.as_deref()),
             metadata: HashMap::new(),
         })
     }
@@ -372,57 +353,18 @@ impl LlmProvider for AnthropicProvider {
             .map_err(LlmError::HttpError)?;
         
         if !response.status().is_success() {
-            let error_msg = utils::extract_error_message(response).await;
-            return Err(LlmError::ApiError(error_msg));
+            return Err(utils::error_from_response(response).await);
         }
         
         let stream = response.bytes_stream()
-            .map(|result| {
-                result.map_err(LlmError::HttpError)
+            .scan(AnthropicStreamState::default(), |state, chunk_result| {
+                let events = match chunk_result {
+                    Ok(chunk) => state.push(&chunk),
+                    Err(e) => vec![Err(LlmError::HttpError(e))],
+                };
+                futures::future::ready(Some(stream::iter(events)))
             })
-            .filter_map(|chunk_result| async move {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        
-                        // Parse SSE format
-                        for line in chunk_str.lines() {
-                            if line.starts_with("data: ") {
-                                let data = &line[6..];
-                                
-                                match serde_json::from_str::<AnthropicStreamEvent>(data) {
-                                    Ok(event) => {
-                                        match event.event_type.as_str() {
-                                            "content_block_start" => {
-                                                return Some(Ok(ProviderEvent::ContentStart));
-                                            }
-                                            "content_block_delta" => {
-                                                if let Some(delta) = event.delta {
-                                                    if let Some(text) = delta.text {
-                                                        return Some(Ok(ProviderEvent::ContentDelta { delta: text }));
-                                                    }
-                                                }
-                                            }
-                                            "content_block_stop" => {
-                                                return Some(Ok(ProviderEvent::ContentStop));
-                                            }
-                                            "message_stop" => {
-                                                return Some(Ok(ProviderEvent::ContentStop));
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                    Err(e) => {
-                                        return Some(Err(LlmError::JsonError(e)));
-                                    }
-                                }
-                            }
-                        }
-                        None
-                    }
-                    Err(e) => Some(Err(e)),
-                }
-            });
+            .flatten();
         
         Ok(Box::pin(stream))
     }
@@ -446,6 +388,10 @@ impl LlmProvider for AnthropicProvider {
         
         Ok(())
     }
+    
+    fn image_limits(&self) -> ImageLimits {
+        ImageLimits::ANTHROPIC
+    }
 }
 
 // Anthropic API types
@@ -521,10 +467,220 @@ struct AnthropicStreamEvent {
     event_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     delta: Option<AnthropicStreamDelta>,
+    #[serde(default)]
+    content_block: Option<AnthropicStreamContentBlock>,
+    /// The message being answered, on `message_start`
+    #[serde(default)]
+    message: Option<AnthropicStreamMessage>,
+    /// Output tokens so far, on `message_delta`
+    #[serde(default)]
+    usage: Option<AnthropicStreamUsage>,
+    #[serde(default)]
+    error: Option<AnthropicStreamError>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AnthropicStreamDelta {
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<String>,
-}
\ No newline at end of file
+    #[serde(default)]
+    partial_json: Option<String>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamMessage {
+    #[serde(default)]
+    usage: Option<AnthropicStreamUsage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnthropicStreamUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// Finish reason of an Anthropic stop reason
+fn finish_reason(stop_reason: Option<&str>) -> Option<FinishReason> {
+    match stop_reason? {
+        "end_turn" | "stop_sequence" => Some(FinishReason::Stop),
+        "max_tokens" => Some(FinishReason::Length),
+        "tool_use" => Some(FinishReason::ToolCalls),
+        _ => None,
+    }
+}
+
+/// Accumulates a streamed message from its SSE events
+#[derive(Debug, Default)]
+struct AnthropicStreamState {
+    lines: SseBuffer,
+    content: String,
+    /// Calls so far as ID, name and the input JSON received
+    tool_calls: Vec<(String, String, String)>,
+    /// Whether the block being streamed is a tool call
+    in_tool_use: bool,
+    usage: TokenUsage,
+    stop_reason: Option<String>,
+}
+
+impl AnthropicStreamState {
+    /// Decode the Anthropic events completed by a chunk of the stream
+    fn push(&mut self, chunk: &[u8]) -> Vec<LlmResult<ProviderEvent>> {
+        let mut events = Vec::new();
+        for data in self.lines.push(chunk) {
+            match serde_json::from_str::<AnthropicStreamEvent>(&data) {
+                Ok(event) => events.extend(self.events_for(event)),
+                Err(e) => events.push(Err(LlmError::JsonError(e))),
+            }
+        }
+        events
+    }
+
+    /// Apply one event and describe it as provider events
+    fn events_for(&mut self, event: AnthropicStreamEvent) -> Vec<LlmResult<ProviderEvent>> {
+        match event.event_type.as_str() {
+            "message_start" => {
+                if let Some(usage) = event.message.and_then(|message| message.usage) {
+                    self.usage.input_tokens = usage.input_tokens;
+                    self.usage.output_tokens = usage.output_tokens;
+                }
+                vec![Ok(ProviderEvent::ContentStart)]
+            }
+            "content_block_start" => {
+                let Some(block) = event.content_block.filter(|block| block.block_type == "tool_use") else {
+                    self.in_tool_use = false;
+                    return Vec::new();
+                };
+                self.in_tool_use = true;
+                let tool_call = ToolCall {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    // The input arrives as JSON fragments
+                    arguments: serde_json::Value::String(String::new()),
+                };
+                self.tool_calls.push((tool_call.id.clone(), tool_call.name.clone(), String::new()));
+                vec![Ok(ProviderEvent::ToolUseStart { tool_call })]
+            }
+            "content_block_delta" => {
+                let Some(delta) = event.delta else {
+                    return Vec::new();
+                };
+                if let Some(text) = delta.text.filter(|text| !text.is_empty()) {
+                    self.content.push_str(&text);
+                    return vec![Ok(ProviderEvent::ContentDelta { delta: text })];
+                }
+                match (delta.partial_json, self.tool_calls.last_mut()) {
+                    (Some(partial_json), Some((_, _, input))) if self.in_tool_use && !partial_json.is_empty() => {
+                        input.push_str(&partial_json);
+                        vec![Ok(ProviderEvent::ToolUseDelta { delta: partial_json })]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            "content_block_stop" if self.in_tool_use => {
+                self.in_tool_use = false;
+                vec![Ok(ProviderEvent::ToolUseStop)]
+            }
+            "message_delta" => {
+                if let Some(usage) = event.usage {
+                    self.usage.output_tokens = usage.output_tokens;
+                }
+                if let Some(stop_reason) = event.delta.and_then(|delta| delta.stop_reason) {
+                    self.stop_reason = Some(stop_reason);
+                }
+                Vec::new()
+            }
+            "message_stop" => vec![
+                Ok(ProviderEvent::ContentStop),
+                Ok(ProviderEvent::Done { response: self.response() }),
+            ],
+            "error" => {
+                let message = event.error.map_or_else(|| "Stream failed".to_string(), |error| error.message);
+                vec![Err(LlmError::ApiError(message))]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The message received so far
+    fn response(&self) -> ProviderResponse {
+        ProviderResponse {
+            content: self.content.clone(),
+            tool_calls: self
+                .tool_calls
+                .iter()
+                .map(|(id, name, input)| ToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    arguments: if input.trim().is_empty() {
+                        json!({})
+                    } else {
+                        serde_json::from_str(input).unwrap_or_else(|_| serde_json::Value::String(input.clone()))
+                    },
+                })
+                .collect(),
+            usage: TokenUsage {
+                total_tokens: self.usage.input_tokens + self.usage.output_tokens,
+                ..self.usage.clone()
+            },
+            finish_reason: finish_reason(self.stop_reason.as_deref()),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_state_assembles_split_events() {
+        let mut state = AnthropicStreamState::default();
+        let first = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":7,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me look\"}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"ls\",\"input\":{}}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"pa",
+        );
+        let second = concat!(
+            "th\\\":\\\".\\\"}\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":3}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+
+        let events: Vec<_> = state.push(first.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(events[0], ProviderEvent::ContentStart));
+        assert!(matches!(&events[1], ProviderEvent::ContentDelta { delta } if delta == "Let me look"));
+        assert!(matches!(&events[2], ProviderEvent::ToolUseStart { tool_call } if tool_call.id == "toolu_1"));
+        assert_eq!(events.len(), 3);
+
+        let events: Vec<_> = state.push(second.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(&events[0], ProviderEvent::ToolUseDelta { delta } if delta == "{\"path\":\".\"}"));
+        let ProviderEvent::Done { response } = events.last().unwrap() else {
+            panic!("expected a final response");
+        };
+        assert_eq!(response.content, "Let me look");
+        assert_eq!(response.tool_calls[0].arguments, json!({ "path": "." }));
+        assert_eq!(response.usage.total_tokens, 10);
+        assert!(matches!(response.finish_reason, Some(FinishReason::ToolCalls)));
+    }
+}
@@ -1,63 +1,172 @@
-//! Azure OpenAI provider implementation (placeholder)
+//! Azure OpenAI provider implementation
+//!
+//! Azure serves OpenAI models behind the chat completions protocol, so
+//! requests go through the OpenAI provider. What differs is where they go and
+//! how they are signed: each model is served by a deployment of the resource,
+//! named in the URL together with the API version, and requests carry either
+//! the resource key in an `api-key` header or an Azure AD token. Tokens are
+//! fetched with the client credentials flow of an app registration and
+//! fetched again shortly before they expire.
 
+use async_trait::async_trait;
+use futures::Stream;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::config::{AzureAdConfig, AzureConfig};
 use crate::llm::{
-    types::*,
+    compat,
     errors::{LlmError, LlmResult},
-    provider::LlmProvider,
+    images::ImageLimits,
+    openai::{ApiAuth, OpenAIProvider, TokenSource},
+    provider::{utils, LlmProvider},
+    types::*,
 };
-use std::collections::HashMap;
-use futures::Stream;
-use std::pin::Pin;
-use tracing::info;
 
-/// Azure OpenAI provider (simplified implementation)
-pub struct AzureProvider {
-    model: String,
-    api_key: String,
-    endpoint: String,
+/// Tokens are renewed when they have less than this left
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Chat completions URL of the deployment serving `model`
+pub fn chat_url(endpoint: &str, settings: &AzureConfig, model: &str) -> String {
+    format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        endpoint.trim_end_matches('/'),
+        settings.deployment(model),
+        settings.api_version
+    )
 }
 
-impl AzureProvider {
-    /// Create provider from ProviderConfig
-    pub fn from_config(config: ProviderConfig) -> LlmResult<Self> {
-        let api_key = config.api_key
-            .ok_or_else(|| LlmError::ConfigError("Azure API key is required".to_string()))?;
+/// Azure AD tokens of an app registration, from the client credentials flow
+#[derive(Debug)]
+pub struct AzureAdCredential {
+    client: reqwest::Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scope: String,
+    cached: Mutex<Option<(String, Instant)>>,
+}
 
-        let endpoint = config.base_url
-            .ok_or_else(|| LlmError::ConfigError("Azure endpoint is required".to_string()))?;
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
 
+impl AzureAdCredential {
+    pub fn new(settings: &AzureAdConfig) -> LlmResult<Self> {
+        let client_secret = settings
+            .client_secret
+            .clone()
+            .or_else(|| std::env::var("AZURE_CLIENT_SECRET").ok())
+            .ok_or_else(|| {
+                LlmError::ConfigError("Azure AD sign-in needs a client secret; set azure.ad.client_secret or AZURE_CLIENT_SECRET".to_string())
+            })?;
         Ok(Self {
-            model: config.model,
-            api_key,
-            endpoint,
+            client: reqwest::Client::new(),
+            token_url: format!(
+                "{}/{}/oauth2/v2.0/token",
+                settings.authority_host.trim_end_matches('/'),
+                settings.tenant_id
+            ),
+            client_id: settings.client_id.clone(),
+            client_secret,
+            scope: settings.scope.clone(),
+            cached: Mutex::new(None),
         })
     }
+
+    async fn fetch(&self) -> LlmResult<(String, Instant)> {
+        let response = self
+            .client
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("scope", self.scope.as_str()),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(LlmError::AuthError(format!(
+                "Azure AD sign-in failed: {}",
+                utils::extract_error_message(response).await
+            )));
+        }
+        let token: TokenResponse = response.json().await?;
+        debug!("Got an Azure AD token valid for {}s", token.expires_in);
+        Ok((token.access_token, Instant::now() + Duration::from_secs(token.expires_in)))
+    }
+}
+
+#[async_trait]
+impl TokenSource for AzureAdCredential {
+    async fn token(&self) -> LlmResult<String> {
+        // Held while fetching, so concurrent requests wait for one sign-in
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires)) = cached.as_ref() {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let (token, expires) = self.fetch().await?;
+        *cached = Some((token.clone(), expires));
+        Ok(token)
+    }
 }
 
-#[async_trait::async_trait]
+/// Azure OpenAI provider
+pub struct AzureProvider {
+    inner: OpenAIProvider,
+}
+
+impl AzureProvider {
+    /// Create provider from ProviderConfig
+    ///
+    /// The endpoint comes from the Azure settings or else the base URL, and
+    /// Azure AD sign-in, when configured, is used instead of the API key.
+    pub fn from_config(config: ProviderConfig) -> LlmResult<Self> {
+        let settings = config.azure.clone();
+        let endpoint = settings
+            .endpoint
+            .clone()
+            .or_else(|| config.base_url.clone())
+            .filter(|endpoint| !endpoint.is_empty())
+            .ok_or_else(|| {
+                LlmError::ConfigError("Azure endpoint is required; set azure.endpoint or AZURE_OPENAI_ENDPOINT".to_string())
+            })?;
+        let auth = match (&settings.ad, &config.api_key) {
+            (Some(ad), _) => ApiAuth::Tokens(Arc::new(AzureAdCredential::new(ad)?)),
+            (None, Some(api_key)) if !api_key.is_empty() => ApiAuth::Header("api-key", api_key.clone()),
+            (None, _) => {
+                return Err(LlmError::ConfigError(
+                    "Azure needs an API key or Azure AD credentials under azure.ad".to_string(),
+                ))
+            }
+        };
+        let url = chat_url(&endpoint, &settings, &config.model);
+        info!("Azure OpenAI requests for {} go to {}", config.model, url);
+        let inner = OpenAIProvider::with_auth(config, compat::AZURE, auth)?.with_chat_url(url);
+        Ok(Self { inner })
+    }
+}
+
+#[async_trait]
 impl LlmProvider for AzureProvider {
-    async fn chat_completion(&self, _request: ChatRequest) -> LlmResult<ProviderResponse> {
-        // TODO: Implement actual Azure OpenAI API calls
-        // This is a placeholder implementation
-        Ok(ProviderResponse {
-            content: "Azure provider placeholder response".to_string(),
-            tool_calls: vec![],
-            usage: TokenUsage {
-                input_tokens: 0,
-                output_tokens: 25,
-                total_tokens: 25,
-            },
-            finish_reason: Some(FinishReason::Stop),
-            metadata: std::collections::HashMap::new(),
-        })
+    async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+        self.inner.chat_completion(request).await
     }
 
     async fn chat_completion_stream(
         &self,
-        _request: ChatRequest,
+        request: ChatRequest,
     ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
-        // TODO: Implement streaming
-        Err(LlmError::StreamError("Azure streaming not yet implemented".to_string()))
+        self.inner.chat_completion_stream(request).await
     }
 
     fn name(&self) -> &str {
@@ -65,19 +174,58 @@ impl LlmProvider for AzureProvider {
     }
 
     fn model(&self) -> &str {
-        &self.model
+        self.inner.model()
     }
 
     fn validate_config(&self) -> LlmResult<()> {
-        if self.api_key.is_empty() {
-            return Err(LlmError::ConfigError("Azure API key is required".to_string()));
-        }
+        self.inner.validate_config()
+    }
 
-        if self.endpoint.is_empty() {
-            return Err(LlmError::ConfigError("Azure endpoint is required".to_string()));
-        }
+    fn image_limits(&self) -> ImageLimits {
+        ImageLimits::OPENAI
+    }
+}
 
-        info!("Azure OpenAI provider configuration validated successfully");
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_models_are_routed_to_their_deployments() {
+        let settings = AzureConfig {
+            deployments: HashMap::from([("gpt-4o".to_string(), "prod-gpt4o".to_string())]),
+            ..AzureConfig::default()
+        };
+        assert_eq!(
+            chat_url("https://contoso.openai.azure.com/", &settings, "gpt-4o"),
+            "https://contoso.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-10-21"
+        );
+        assert!(chat_url("https://contoso.openai.azure.com", &settings, "gpt-4o-mini").contains("/deployments/gpt-4o-mini/"));
+
+        let config = ProviderConfig {
+            provider_type: "azure".to_string(),
+            api_key: None,
+            base_url: Some("https://contoso.openai.azure.com".to_string()),
+            ..ProviderConfig::default()
+        };
+        assert!(AzureProvider::from_config(config.clone()).is_err());
+
+        let with_ad = ProviderConfig {
+            azure: AzureConfig {
+                ad: Some(AzureAdConfig {
+                    tenant_id: "tenant".to_string(),
+                    client_id: "client".to_string(),
+                    client_secret: Some("secret".to_string()),
+                    scope: "https://cognitiveservices.azure.com/.default".to_string(),
+                    authority_host: "https://login.microsoftonline.com".to_string(),
+                }),
+                ..AzureConfig::default()
+            },
+            ..config
+        };
+        let provider = AzureProvider::from_config(with_ad).unwrap();
+        assert!(provider.validate_config().is_ok());
+        assert_eq!(provider.name(), "azure");
     }
-}
\ No newline at end of file
+}
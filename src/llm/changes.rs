@@ -0,0 +1,169 @@
+//! Tracking of files changed during a turn
+//!
+//! Editing tools report the unified diff of what they wrote under the `diff`
+//! metadata key. The diffs of one turn are collected per file, and once the
+//! turn has finished a summary listing each file with its added and removed
+//! line counts is appended to the conversation. The same record is kept in
+//! the session's `change_history` metadata so exports and other views can
+//! show which turn changed what.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::paths::display_path;
+
+/// Metadata key holding the changes a summary message describes
+pub const TURN_CHANGES_KEY: &str = "turn_changes";
+
+/// Session metadata key holding the changes of every turn
+pub const CHANGE_HISTORY_KEY: &str = "change_history";
+
+/// Tool result metadata key holding the change the tool made, shown as a diff
+pub const FILE_CHANGE_KEY: &str = "file_change";
+
+/// Unified diff between two versions of a file
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(path, path)
+        .to_string()
+}
+
+/// Link under which a file's diff of the given turn is shown
+pub fn diff_link(turn: usize, path: &str) -> String {
+    format!("diff:{}/{}", turn, path)
+}
+
+/// Changes to one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub additions: usize,
+    pub removals: usize,
+    /// Unified diffs of the edits, in the order they were made
+    pub diff: String,
+}
+
+impl FileChange {
+    /// Derive the change from a tool response's metadata
+    ///
+    /// Tools report the `file_path` they wrote and its `diff`.
+    pub fn from_metadata(metadata: &serde_json::Value) -> Option<Self> {
+        let path = metadata.get("file_path").and_then(|v| v.as_str())?;
+        let diff = metadata.get("diff").and_then(|v| v.as_str())?;
+        let (additions, removals) = count_lines(diff);
+        if additions == 0 && removals == 0 {
+            return None;
+        }
+        Some(Self {
+            path: path.to_string(),
+            additions,
+            removals,
+            diff: diff.to_string(),
+        })
+    }
+}
+
+/// Added and removed lines of a unified diff, not counting the file headers
+fn count_lines(diff: &str) -> (usize, usize) {
+    diff.lines()
+        .filter(|line| !line.starts_with("+++") && !line.starts_with("---"))
+        .fold((0, 0), |(added, removed), line| match line.as_bytes().first() {
+            Some(b'+') => (added + 1, removed),
+            Some(b'-') => (added, removed + 1),
+            _ => (added, removed),
+        })
+}
+
+/// Files changed during one turn
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TurnChanges {
+    /// Turn number within the session, counting user prompts from 1
+    pub turn: usize,
+    /// Message holding the summary
+    #[serde(default)]
+    pub message_id: String,
+    pub files: Vec<FileChange>,
+}
+
+impl TurnChanges {
+    pub fn new(turn: usize) -> Self {
+        Self { turn, ..Self::default() }
+    }
+
+    /// Record a change, merging it with earlier changes to the same file
+    pub fn record(&mut self, change: FileChange) {
+        match self.files.iter_mut().find(|file| file.path == change.path) {
+            Some(file) => {
+                file.additions += change.additions;
+                file.removals += change.removals;
+                file.diff.push_str(&change.diff);
+            }
+            None => self.files.push(change),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Markdown summary listing each file with its line counts and diff link
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "**{} file{} changed this turn**\n",
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" }
+        );
+        for file in &self.files {
+            summary.push_str(&format!(
+                "\n- [`{}`]({}) +{} -{}",
+                display_path(Path::new(&file.path)),
+                diff_link(self.turn, &file.path),
+                file.additions,
+                file.removals
+            ));
+        }
+        summary
+    }
+
+    /// Read the changes a summary message describes
+    pub fn from_message_metadata(metadata: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        serde_json::from_value(metadata.get(TURN_CHANGES_KEY)?.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changes_are_merged_per_file() {
+        let first = unified_diff("src/main.rs", "a\nb\n", "a\nc\nd\n");
+        let second = unified_diff("src/main.rs", "a\nc\nd\n", "a\nd\n");
+        let other = unified_diff("README.md", "", "# Title\n");
+
+        let mut changes = TurnChanges::new(3);
+        for diff in [&first, &other, &second] {
+            let path = if diff == &other { "README.md" } else { "src/main.rs" };
+            changes.record(FileChange::from_metadata(&json!({ "file_path": path, "diff": diff })).unwrap());
+        }
+
+        assert_eq!(changes.files.len(), 2);
+        assert_eq!((changes.files[0].additions, changes.files[0].removals), (2, 2));
+        assert_eq!((changes.files[1].additions, changes.files[1].removals), (1, 0));
+        assert_eq!(
+            changes.summary(),
+            "**2 files changed this turn**\n\n- [`src/main.rs`](diff:3/src/main.rs) +2 -2\n- [`README.md`](diff:3/README.md) +1 -0"
+        );
+    }
+
+    #[test]
+    fn test_unchanged_files_are_not_recorded() {
+        let diff = unified_diff("a.txt", "same\n", "same\n");
+        assert!(FileChange::from_metadata(&json!({ "file_path": "a.txt", "diff": diff })).is_none());
+        assert!(FileChange::from_metadata(&json!({ "file_path": "a.txt" })).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+//! Provenance tracking for retrieved context
+//!
+//! Results of retrieval tools (file views, searches, fetched pages) are
+//! numbered as they come in during a turn and labelled with their source, and
+//! the model is asked to cite them as `[N]`. Markers found in the final answer
+//! are resolved back to the recorded sources and attached to the assistant
+//! message under the `citations` metadata key.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Metadata key holding the citations of a message
+pub const CITATIONS_KEY: &str = "citations";
+
+/// Instructions appended to the system prompt so the model cites its sources
+pub const CITATION_INSTRUCTIONS: &str = "Some tool results start with a source label such as `[1] src/main.rs:10-40`. \
+When your answer relies on such a result, cite it inline with its marker, e.g. `[1]`. \
+Only cite markers that appeared in tool results.";
+
+/// Where a piece of retrieved context came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CitationSource {
+    File {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_line: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_line: Option<u64>,
+    },
+    Url {
+        url: String,
+    },
+}
+
+impl CitationSource {
+    /// Derive the source from a tool response's metadata
+    ///
+    /// Tools report a `url`, or a `file_path` with optional `start_line` and
+    /// `end_line`.
+    pub fn from_metadata(metadata: &serde_json::Value) -> Option<Self> {
+        if let Some(url) = metadata.get("url").and_then(|v| v.as_str()) {
+            return Some(Self::Url { url: url.to_string() });
+        }
+        let path = metadata.get("file_path").and_then(|v| v.as_str())?;
+        Some(Self::File {
+            path: path.to_string(),
+            start_line: metadata.get("start_line").and_then(|v| v.as_u64()),
+            end_line: metadata.get("end_line").and_then(|v| v.as_u64()),
+        })
+    }
+}
+
+impl fmt::Display for CitationSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File { path, start_line: Some(start), end_line: Some(end) } => {
+                write!(f, "{}:{}-{}", path, start, end)
+            }
+            Self::File { path, start_line: Some(start), end_line: None } => write!(f, "{}:{}", path, start),
+            Self::File { path, .. } => write!(f, "{}", path),
+            Self::Url { url } => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A numbered source that an answer can cite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// Marker number, as in `[1]`
+    pub index: usize,
+    pub source: CitationSource,
+    /// Tool that retrieved the source
+    pub tool: String,
+}
+
+impl Citation {
+    /// Read the citations stored in a message's metadata
+    pub fn from_message_metadata(metadata: &HashMap<String, serde_json::Value>) -> Vec<Citation> {
+        metadata
+            .get(CITATIONS_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Sources retrieved during a single turn
+#[derive(Debug, Default)]
+pub struct CitationTracker {
+    citations: Vec<Citation>,
+}
+
+impl CitationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a source, returning its marker number
+    ///
+    /// The same source retrieved twice keeps its first number.
+    pub fn record(&mut self, tool: &str, source: CitationSource) -> usize {
+        if let Some(existing) = self.citations.iter().find(|c| c.source == source) {
+            return existing.index;
+        }
+        let index = self.citations.len() + 1;
+        self.citations.push(Citation {
+            index,
+            source,
+            tool: tool.to_string(),
+        });
+        index
+    }
+
+    /// Prefix a tool result with its source label
+    pub fn label(&self, index: usize, content: &str) -> String {
+        match self.citations.get(index - 1) {
+            Some(citation) => format!("[{}] {}\n{}", index, citation.source, content),
+            None => content.to_string(),
+        }
+    }
+
+    /// All sources recorded this turn
+    pub fn citations(&self) -> &[Citation] {
+        &self.citations
+    }
+
+    /// Sources whose markers appear in the given text, in marker order
+    pub fn cited_in(&self, text: &str) -> Vec<Citation> {
+        let mut indices: Vec<usize> = citation_markers(text);
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .filter_map(|index| self.citations.get(index.checked_sub(1)?).cloned())
+            .collect()
+    }
+}
+
+/// Pattern matching a citation marker such as `[3]`
+pub fn marker_regex() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| Regex::new(r"\[(\d{1,3})\]").unwrap())
+}
+
+/// Marker numbers in the order they appear in the text
+pub fn citation_markers(text: &str) -> Vec<usize> {
+    marker_regex()
+        .captures_iter(text)
+        .filter_map(|caps| caps[1].parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sources_are_numbered_once() {
+        let mut tracker = CitationTracker::new();
+        let view = CitationSource::from_metadata(&json!({
+            "file_path": "src/main.rs", "start_line": 1, "end_line": 40
+        }))
+        .unwrap();
+        let page = CitationSource::from_metadata(&json!({ "url": "https://docs.rs" })).unwrap();
+
+        assert_eq!(tracker.record("view", view.clone()), 1);
+        assert_eq!(tracker.record("fetch", page), 2);
+        assert_eq!(tracker.record("view", view), 1);
+        assert_eq!(tracker.label(1, "fn main() {}"), "[1] src/main.rs:1-40\nfn main() {}");
+    }
+
+    #[test]
+    fn test_cited_in_resolves_known_markers() {
+        let mut tracker = CitationTracker::new();
+        tracker.record("grep", CitationSource::File { path: "a.rs".into(), start_line: None, end_line: None });
+        tracker.record("fetch", CitationSource::Url { url: "https://example.com".into() });
+
+        let cited = tracker.cited_in("See [2], and also [1] and [2]. Not [7].");
+        assert_eq!(cited.iter().map(|c| c.index).collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
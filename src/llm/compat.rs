@@ -0,0 +1,162 @@
+//! Profiles for OpenAI-compatible providers
+//!
+//! DeepSeek, Qwen (DashScope) and OpenRouter speak the OpenAI chat completions
+//! protocol with a few differences, so they run on the OpenAI provider with a
+//! profile instead of a provider of their own. Any other server speaking the
+//! protocol, such as Groq, Together, LM Studio or vLLM, is reached through the
+//! generic `openai_compatible` profile. The differences handled here:
+//!
+//! - Endpoints: the path below the base URL is not always `/v1/...`
+//! - Keys: local servers take requests without one
+//! - Reasoning: models return their chain of thought in `reasoning_content`
+//!   (DeepSeek, Qwen) or `reasoning` (OpenRouter) next to the answer
+//! - Finish reasons beyond OpenAI's, like DeepSeek's
+//!   `insufficient_system_resource` or Qwen's legacy `function_call`
+//! - Tool call arguments sent as a JSON object instead of a JSON string
+
+use serde_json::Value;
+
+use crate::llm::types::FinishReason;
+
+/// An OpenAI-compatible API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompatProfile {
+    /// Provider name as used in the configuration
+    pub name: &'static str,
+    /// Base URL used when none is configured
+    pub default_base_url: &'static str,
+    /// Path of the chat completions endpoint below the base URL
+    pub chat_path: &'static str,
+    /// Environment variable holding the API key
+    pub api_key_env: &'static str,
+    /// Whether requests without an API key are refused
+    pub requires_api_key: bool,
+}
+
+pub const OPENAI: CompatProfile = CompatProfile {
+    name: "openai",
+    default_base_url: "https://api.openai.com",
+    chat_path: "/v1/chat/completions",
+    api_key_env: "OPENAI_API_KEY",
+    requires_api_key: true,
+};
+
+pub const DEEPSEEK: CompatProfile = CompatProfile {
+    name: "deepseek",
+    default_base_url: "https://api.deepseek.com",
+    chat_path: "/v1/chat/completions",
+    api_key_env: "DEEPSEEK_API_KEY",
+    requires_api_key: true,
+};
+
+pub const QWEN: CompatProfile = CompatProfile {
+    name: "qwen",
+    default_base_url: "https://dashscope-intl.aliyuncs.com/compatible-mode/v1",
+    chat_path: "/chat/completions",
+    api_key_env: "DASHSCOPE_API_KEY",
+    requires_api_key: true,
+};
+
+pub const OPENROUTER: CompatProfile = CompatProfile {
+    name: "openrouter",
+    default_base_url: "https://openrouter.ai/api/v1",
+    chat_path: "/chat/completions",
+    api_key_env: "OPENROUTER_API_KEY",
+    requires_api_key: true,
+};
+
+/// Azure OpenAI, whose chat URL depends on the deployment; it is not in
+/// [`PROFILES`] because it cannot be reached through the base URL alone
+pub const AZURE: CompatProfile = CompatProfile {
+    name: "azure",
+    default_base_url: "",
+    chat_path: "/chat/completions",
+    api_key_env: "AZURE_OPENAI_API_KEY",
+    requires_api_key: true,
+};
+
+/// Any server speaking the protocol, at the configured base URL; the key is
+/// optional because local servers like LM Studio and vLLM take none
+pub const OPENAI_COMPATIBLE: CompatProfile = CompatProfile {
+    name: "openai_compatible",
+    default_base_url: "",
+    chat_path: "/chat/completions",
+    api_key_env: "OPENAI_COMPATIBLE_API_KEY",
+    requires_api_key: false,
+};
+
+/// All profiles, OpenAI first
+pub const PROFILES: &[CompatProfile] = &[OPENAI, DEEPSEEK, QWEN, OPENROUTER, OPENAI_COMPATIBLE];
+
+/// The profile of a provider name
+pub fn profile(name: &str) -> Option<CompatProfile> {
+    PROFILES.iter().copied().find(|profile| profile.name == name)
+}
+
+impl CompatProfile {
+    /// Chat completions URL below the given base URL, or the default one
+    pub fn chat_url(&self, base_url: Option<&str>) -> String {
+        let base_url = base_url.unwrap_or(self.default_base_url).trim_end_matches('/');
+        format!("{}{}", base_url, self.chat_path)
+    }
+}
+
+/// Map a finish reason of any of the profiles
+///
+/// Qwen sends the string `"null"` on chunks that did not finish.
+pub fn finish_reason(reason: Option<&str>) -> Option<FinishReason> {
+    match reason? {
+        "stop" | "eos" => Some(FinishReason::Stop),
+        "length" | "max_tokens" => Some(FinishReason::Length),
+        "content_filter" | "sensitive" => Some(FinishReason::ContentFilter),
+        "tool_calls" | "function_call" => Some(FinishReason::ToolCalls),
+        "insufficient_system_resource" | "error" => Some(FinishReason::Error),
+        _ => None,
+    }
+}
+
+/// Tool call arguments as a JSON object
+///
+/// OpenAI sends the arguments as a string holding JSON; some compatible APIs
+/// send the object itself. A string that is not JSON is kept as it is.
+pub fn tool_arguments(arguments: Value) -> Value {
+    match arguments {
+        Value::String(text) if text.trim().is_empty() => Value::Object(Default::default()),
+        Value::String(text) => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_profiles_build_chat_urls() {
+        assert_eq!(profile("deepseek").unwrap().chat_url(None), "https://api.deepseek.com/v1/chat/completions");
+        assert_eq!(
+            profile("qwen").unwrap().chat_url(Some("https://dashscope.aliyuncs.com/compatible-mode/v1/")),
+            "https://dashscope.aliyuncs.com/compatible-mode/v1/chat/completions"
+        );
+        assert_eq!(OPENAI.chat_url(None), "https://api.openai.com/v1/chat/completions");
+        assert!(profile("anthropic").is_none());
+    }
+
+    #[test]
+    fn test_finish_reasons_of_all_profiles() {
+        assert!(matches!(finish_reason(Some("function_call")), Some(FinishReason::ToolCalls)));
+        assert!(matches!(finish_reason(Some("insufficient_system_resource")), Some(FinishReason::Error)));
+        assert!(matches!(finish_reason(Some("stop")), Some(FinishReason::Stop)));
+        assert!(finish_reason(Some("null")).is_none());
+        assert!(finish_reason(None).is_none());
+    }
+
+    #[test]
+    fn test_tool_arguments_are_objects() {
+        assert_eq!(tool_arguments(json!("{\"path\": \"/tmp\"}")), json!({ "path": "/tmp" }));
+        assert_eq!(tool_arguments(json!({ "path": "/tmp" })), json!({ "path": "/tmp" }));
+        assert_eq!(tool_arguments(json!("")), json!({}));
+        assert_eq!(tool_arguments(json!("not json")), json!("not json"));
+    }
+}
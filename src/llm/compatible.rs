@@ -0,0 +1,167 @@
+//! Generic OpenAI-compatible provider
+//!
+//! Groq, Together, LM Studio, vLLM and OpenRouter all serve the OpenAI chat
+//! completions protocol below a base URL of their own, so one provider covers
+//! them: requests go through the OpenAI provider with the `openai_compatible`
+//! profile, at the configured `base_url`. The API key is optional, since local
+//! servers take none, and headers a server wants are set with `extra_headers`.
+//! The models a server offers are read from its `/models` listing, which
+//! comes either wrapped in `data` or as a bare array.
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use crate::llm::{
+    compat,
+    errors::{LlmError, LlmResult},
+    images::ImageLimits,
+    openai::OpenAIProvider,
+    provider::{utils, LlmProvider},
+    types::{ChatRequest, ProviderConfig, ProviderEvent, ProviderResponse},
+};
+
+/// Provider for any OpenAI-compatible endpoint
+pub struct OpenAICompatibleProvider {
+    inner: OpenAIProvider,
+}
+
+impl OpenAICompatibleProvider {
+    /// Create a provider for the endpoint at the configured base URL
+    pub fn new(config: ProviderConfig) -> LlmResult<Self> {
+        if config.base_url.as_deref().map_or(true, |url| url.trim().is_empty()) {
+            return Err(LlmError::ConfigError(
+                "base_url is required for openai_compatible, e.g. http://localhost:1234/v1".to_string(),
+            ));
+        }
+        Ok(Self {
+            inner: OpenAIProvider::with_profile(config, compat::OPENAI_COMPATIBLE)?,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAICompatibleProvider {
+    async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+        self.inner.chat_completion(request).await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: ChatRequest,
+    ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+        self.inner.chat_completion_stream(request).await
+    }
+
+    fn name(&self) -> &str {
+        "openai_compatible"
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn validate_config(&self) -> LlmResult<()> {
+        self.inner.validate_config()
+    }
+
+    fn image_limits(&self) -> ImageLimits {
+        ImageLimits::OPENAI
+    }
+}
+
+/// A model listed by a compatible server
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListedModel {
+    pub id: String,
+    #[serde(default)]
+    pub owned_by: Option<String>,
+    /// Context window, under the name each server uses for it
+    #[serde(default, alias = "context_window", alias = "max_model_len")]
+    pub context_length: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ModelList {
+    Wrapped { data: Vec<ListedModel> },
+    Bare(Vec<ListedModel>),
+}
+
+/// List the models served below `base_url`
+pub async fn list_models(
+    base_url: &str,
+    api_key: Option<&str>,
+    extra_headers: &HashMap<String, String>,
+) -> LlmResult<Vec<ListedModel>> {
+    let mut request = reqwest::Client::new().get(format!("{}/models", base_url.trim_end_matches('/')));
+    if let Some(api_key) = api_key.filter(|key| !key.is_empty()) {
+        request = request.bearer_auth(api_key);
+    }
+    for (name, value) in extra_headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(LlmError::ApiError(utils::extract_error_message(response).await));
+    }
+    Ok(match response.json::<ModelList>().await? {
+        ModelList::Wrapped { data } | ModelList::Bare(data) => data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_base_url_is_required_and_key_optional() {
+        let config = ProviderConfig {
+            provider_type: "openai_compatible".to_string(),
+            api_key: None,
+            model: "qwen2.5-coder-7b-instruct".to_string(),
+            ..ProviderConfig::default()
+        };
+        assert!(OpenAICompatibleProvider::new(config.clone()).is_err());
+
+        let local = ProviderConfig {
+            base_url: Some("http://localhost:1234/v1".to_string()),
+            ..config
+        };
+        let provider = OpenAICompatibleProvider::new(local).unwrap();
+        assert!(provider.validate_config().is_ok());
+        assert_eq!(provider.name(), "openai_compatible");
+    }
+
+    #[test]
+    fn test_model_listings_of_different_servers() {
+        let groq: ModelList = serde_json::from_value(json!({
+            "object": "list",
+            "data": [{ "id": "llama-3.3-70b-versatile", "owned_by": "Meta", "context_window": 131072 }]
+        }))
+        .unwrap();
+        let together: ModelList = serde_json::from_value(json!([
+            { "id": "mistralai/Mixtral-8x7B-Instruct-v0.1", "type": "chat", "context_length": 32768 }
+        ]))
+        .unwrap();
+        let vllm: ModelList = serde_json::from_value(json!({
+            "data": [{ "id": "Qwen/Qwen2.5-Coder-32B-Instruct", "max_model_len": 32768 }]
+        }))
+        .unwrap();
+
+        let models: Vec<ListedModel> = [groq, together, vllm]
+            .into_iter()
+            .flat_map(|list| match list {
+                ModelList::Wrapped { data } | ModelList::Bare(data) => data,
+            })
+            .collect();
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].context_length, Some(131072));
+        assert_eq!(models[0].owned_by.as_deref(), Some("Meta"));
+        assert_eq!(models[1].context_length, Some(32768));
+        assert_eq!(models[2].id, "Qwen/Qwen2.5-Coder-32B-Instruct");
+    }
+}
@@ -0,0 +1,156 @@
+//! Per-turn deadlines
+//!
+//! A [`Deadline`] is created when the user sends a message and is passed down
+//! to every provider call and tool execution made while answering it, so the
+//! whole turn shares one time budget instead of each step having its own
+//! independent timeout. Tools with their own timeouts clamp them to the
+//! remaining budget with [`Deadline::clamp`].
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Error returned when an operation runs past the turn's deadline
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Deadline exceeded during {operation} after {}s", elapsed.as_secs())]
+pub struct DeadlineExceeded {
+    pub operation: String,
+    pub elapsed: Duration,
+}
+
+/// Overall time budget for a user turn
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    started: Instant,
+    expires_at: Option<Instant>,
+}
+
+impl Deadline {
+    /// Deadline that expires after the given budget
+    pub fn after(budget: Duration) -> Self {
+        let started = Instant::now();
+        Self {
+            started,
+            expires_at: Some(started + budget),
+        }
+    }
+
+    /// Deadline that never expires
+    pub fn unbounded() -> Self {
+        Self {
+            started: Instant::now(),
+            expires_at: None,
+        }
+    }
+
+    /// Deadline from an optional budget, unbounded when `None`
+    pub fn from_budget(budget: Option<Duration>) -> Self {
+        budget.map(Self::after).unwrap_or_else(Self::unbounded)
+    }
+
+    /// Time left, or `None` if unbounded
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires_at.map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Time spent since the deadline was created
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the budget is used up
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Limit a step's own timeout to the remaining budget
+    pub fn clamp(&self, timeout: Duration) -> Duration {
+        match self.remaining() {
+            Some(remaining) => timeout.min(remaining),
+            None => timeout,
+        }
+    }
+
+    /// This deadline, brought forward to expire after `limit` at the latest
+    pub fn shortened_to(&self, limit: Duration) -> Self {
+        let at = Instant::now() + limit;
+        Self {
+            started: self.started,
+            expires_at: Some(self.expires_at.map_or(at, |expires_at| expires_at.min(at))),
+        }
+    }
+
+    /// Fail if the deadline has already passed
+    pub fn check(&self, operation: &str) -> Result<(), DeadlineExceeded> {
+        if self.is_expired() {
+            Err(self.exceeded(operation))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run a fallible future, cancelling it when the deadline passes
+    pub async fn run<T, E, F>(&self, operation: &str, future: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        self.check(operation)?;
+
+        match self.remaining() {
+            Some(remaining) => {
+                debug!("{}: {}ms of turn budget remaining", operation, remaining.as_millis());
+                match tokio::time::timeout(remaining, future).await {
+                    Ok(result) => result.map_err(Into::into),
+                    Err(_) => Err(self.exceeded(operation).into()),
+                }
+            }
+            None => future.await.map_err(Into::into),
+        }
+    }
+
+    fn exceeded(&self, operation: &str) -> DeadlineExceeded {
+        DeadlineExceeded {
+            operation: operation.to_string(),
+            elapsed: self.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_limits_to_remaining() {
+        let deadline = Deadline::after(Duration::from_secs(10));
+        assert!(deadline.clamp(Duration::from_secs(60)) <= Duration::from_secs(10));
+        assert_eq!(deadline.clamp(Duration::from_secs(1)), Duration::from_secs(1));
+        assert_eq!(Deadline::unbounded().clamp(Duration::from_secs(60)), Duration::from_secs(60));
+        assert!(deadline.shortened_to(Duration::from_secs(60)).remaining().unwrap() <= Duration::from_secs(10));
+        assert!(Deadline::unbounded().shortened_to(Duration::from_secs(1)).remaining().unwrap() <= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_run_cancels_when_budget_is_exceeded() {
+        let deadline = Deadline::after(Duration::from_millis(20));
+        let result = deadline
+            .run("slow step", async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok::<_, anyhow::Error>(())
+            })
+            .await;
+
+        let err = result.unwrap_err();
+        assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+        assert!(deadline.is_expired());
+        assert!(deadline.check("next step").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_passes_through_results() {
+        let deadline = Deadline::unbounded();
+        let value = deadline.run("fast step", async { Ok::<_, anyhow::Error>(42) }).await.unwrap();
+        assert_eq!(value, 42);
+    }
+}
@@ -0,0 +1,257 @@
+//! Documents attached to prompts as text
+//!
+//! Models take PDFs as context only through their text: the text is
+//! extracted, tidied up and split into parts on paragraph boundaries, and the
+//! parts are put before the prompt, each in a `<document>` block naming the
+//! file. Files over [`DocumentLimits::max_file_bytes`] are refused, and text
+//! beyond [`DocumentLimits::max_chars`] is left out, which the last part
+//! notes, so a long manual can't fill the context window on its own.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// How much of a document is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentLimits {
+    /// Largest file read, in bytes
+    pub max_file_bytes: u64,
+    /// Characters of text sent at most
+    pub max_chars: usize,
+    /// Characters of a part
+    pub chunk_chars: usize,
+}
+
+impl Default for DocumentLimits {
+    /// About 50k tokens of text from a file of up to 32 MB
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 32 * 1024 * 1024,
+            max_chars: 200_000,
+            chunk_chars: 8_000,
+        }
+    }
+}
+
+/// A document's text, as sent along with a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentAttachment {
+    /// File name shown to the model
+    pub name: String,
+    /// Parts of the text sent, in order
+    pub chunks: Vec<String>,
+    /// Characters of the whole text, including those left out
+    pub total_chars: usize,
+    /// Whether text was left out for being over the limit
+    pub truncated: bool,
+}
+
+impl DocumentAttachment {
+    /// Read a PDF file
+    pub async fn from_path(path: &Path, limits: DocumentLimits) -> Result<Self> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        if size > limits.max_file_bytes {
+            bail!(
+                "{} is {} MB, over the {} MB limit for documents",
+                path.display(),
+                size / (1024 * 1024),
+                limits.max_file_bytes / (1024 * 1024)
+            );
+        }
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        // Extraction takes a while on long documents
+        tokio::task::spawn_blocking(move || Self::from_pdf(name, &data, limits)).await?
+    }
+
+    /// Extract the text of a PDF
+    pub fn from_pdf(name: impl Into<String>, data: &[u8], limits: DocumentLimits) -> Result<Self> {
+        let name = name.into();
+        if data.len() as u64 > limits.max_file_bytes {
+            bail!("{} is over the {} MB limit for documents", name, limits.max_file_bytes / (1024 * 1024));
+        }
+        let text = pdf_extract::extract_text_from_mem(data)
+            .map_err(|e| anyhow::anyhow!("Failed to extract the text of {}: {}", name, e))?;
+        let document = Self::from_text(name, &text, limits);
+        if document.chunks.is_empty() {
+            bail!("{} has no text to extract; scanned pages need OCR first", document.name);
+        }
+        Ok(document)
+    }
+
+    /// Split text into parts, leaving out what is over the limit
+    pub fn from_text(name: impl Into<String>, text: &str, limits: DocumentLimits) -> Self {
+        let text = tidy(text);
+        let total_chars = text.chars().count();
+        let mut chunks = Vec::new();
+        let mut chunk = String::new();
+        let mut chunk_len = 0;
+        let mut sent = 0;
+        let mut truncated = false;
+        for paragraph in text.split("\n\n").flat_map(|paragraph| split_long(paragraph, limits.chunk_chars)) {
+            let len = paragraph.chars().count();
+            if sent + len > limits.max_chars {
+                truncated = true;
+                break;
+            }
+            if chunk_len > 0 && chunk_len + len + 2 > limits.chunk_chars {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_len = 0;
+            }
+            if chunk_len > 0 {
+                chunk.push_str("\n\n");
+                chunk_len += 2;
+            }
+            chunk.push_str(paragraph);
+            chunk_len += len;
+            sent += len;
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        Self {
+            name: name.into(),
+            chunks,
+            total_chars,
+            truncated,
+        }
+    }
+
+    /// Characters of text sent
+    pub fn sent_chars(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.chars().count()).sum()
+    }
+
+    /// The document as the model gets it
+    pub fn context(&self) -> String {
+        let parts = self.chunks.len();
+        let mut blocks: Vec<String> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                format!(
+                    "<document name=\"{}\" part=\"{}\" of=\"{}\">\n{}\n</document>",
+                    self.name.replace('"', "'"),
+                    index + 1,
+                    parts,
+                    chunk
+                )
+            })
+            .collect();
+        if self.truncated {
+            blocks.push(format!(
+                "(Only the first {} of {} characters of {} are attached.)",
+                self.sent_chars(),
+                self.total_chars,
+                self.name
+            ));
+        }
+        blocks.join("\n\n")
+    }
+}
+
+/// Whether a file is a document attached as its text
+pub fn is_document(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+}
+
+/// A prompt with the documents attached to it put before it
+pub fn with_documents(prompt: &str, documents: &[DocumentAttachment]) -> String {
+    if documents.is_empty() {
+        return prompt.to_string();
+    }
+    let mut blocks: Vec<String> = documents.iter().map(DocumentAttachment::context).collect();
+    blocks.push(prompt.to_string());
+    blocks.join("\n\n")
+}
+
+/// Text without trailing spaces, control characters or runs of blank lines
+fn tidy(text: &str) -> String {
+    let mut tidied = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines() {
+        let line: String = line.chars().filter(|c| !c.is_control() || *c == '\t').collect();
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !tidied.is_empty() {
+            tidied.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        tidied.push_str(line);
+        blank_lines = 0;
+    }
+    tidied
+}
+
+/// A paragraph in pieces of at most `max` characters, cut at spaces where
+/// there are any
+fn split_long(paragraph: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = paragraph;
+    while rest.chars().count() > max {
+        let end = rest.char_indices().nth(max).map_or(rest.len(), |(index, _)| index);
+        let cut = rest[..end].rfind(char::is_whitespace).filter(|cut| *cut > 0).unwrap_or(end);
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: DocumentLimits = DocumentLimits {
+        max_file_bytes: 1024,
+        max_chars: 60,
+        chunk_chars: 25,
+    };
+
+    #[test]
+    fn test_text_is_split_on_paragraphs_up_to_the_limit() {
+        let text = "First paragraph.  \n\n\n\nSecond\x07 one.\n\nThird paragraph here.\n\nFourth paragraph is too many.";
+        let document = DocumentAttachment::from_text("manual.pdf", text, LIMITS);
+
+        assert_eq!(document.chunks, ["First paragraph.", "Second one.", "Third paragraph here."]);
+        assert!(document.truncated);
+        let context = document.context();
+        assert!(context.starts_with("<document name=\"manual.pdf\" part=\"1\" of=\"3\">\nFirst paragraph.\n</document>"));
+        assert!(context.ends_with("(Only the first 48 of 83 characters of manual.pdf are attached.)"));
+
+        let short = DocumentAttachment::from_text("notes.pdf", "One.\n\nTwo.", LIMITS);
+        assert_eq!(short.chunks, ["One.\n\nTwo."]);
+        assert!(!short.truncated);
+        assert_eq!(
+            with_documents("Summarize it", &[short]),
+            "<document name=\"notes.pdf\" part=\"1\" of=\"1\">\nOne.\n\nTwo.\n</document>\n\nSummarize it"
+        );
+    }
+
+    #[test]
+    fn test_long_paragraphs_are_cut_at_spaces() {
+        assert_eq!(split_long("aaaa bbbb cccc", 10), ["aaaa bbbb", "cccc"]);
+        assert_eq!(split_long("aaaaaaaaaaaa", 5), ["aaaaa", "aaaaa", "aa"]);
+    }
+
+    #[test]
+    fn test_oversized_and_broken_pdfs_are_refused() {
+        assert!(is_document(Path::new("Report.PDF")));
+        assert!(!is_document(Path::new("report.txt")));
+
+        let error = DocumentAttachment::from_pdf("big.pdf", &[0; 2048], LIMITS).unwrap_err();
+        assert!(error.to_string().contains("over the"));
+        assert!(DocumentAttachment::from_pdf("broken.pdf", b"not a pdf", LIMITS).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for LLM providers
 
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,8 +14,20 @@ pub enum LlmError {
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
     
-    #[error("Rate limit exceeded: {0}")]
-    RateLimitError(String),
+    #[error("Rate limit exceeded: {message}")]
+    RateLimitError {
+        message: String,
+        /// How long the provider asked to wait, from its `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+    
+    /// The provider failed or was overloaded, and may answer a later request
+    #[error("Provider unavailable ({status}): {message}")]
+    Unavailable {
+        status: u16,
+        message: String,
+        retry_after: Option<Duration>,
+    },
     
     #[error("Authentication failed: {0}")]
     AuthError(String),
@@ -38,4 +51,55 @@ pub enum LlmError {
     IoError(#[from] std::io::Error),
 }
 
-pub type LlmResult<T> = Result<T, LlmError>;
\ No newline at end of file
+pub type LlmResult<T> = Result<T, LlmError>;
+
+impl LlmError {
+    /// How long the provider asked to wait before trying again, if it said
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::RateLimitError { retry_after, .. } | LlmError::Unavailable { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Phrases providers use when a request does not fit the model's context
+const CONTEXT_LIMIT_PHRASES: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "prompt is too long",
+    "input is too long",
+    "exceeds the maximum number of tokens",
+    "too many tokens",
+];
+
+/// Whether a provider's error message says the request exceeds the model's context
+pub fn is_context_limit_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    CONTEXT_LIMIT_PHRASES.iter().any(|phrase| message.contains(phrase))
+}
+
+/// Whether an error, possibly wrapped, is a provider rejecting a request as
+/// too long for the model's context
+pub fn is_context_limit(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| matches!(cause.downcast_ref::<LlmError>(), Some(LlmError::ContextLimitError(_))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_limit_errors_are_recognized() {
+        assert!(is_context_limit_message("400 Bad Request: prompt is too long: 210000 tokens > 200000 maximum"));
+        assert!(is_context_limit_message("This model's maximum context length is 128000 tokens"));
+        assert!(!is_context_limit_message("400 Bad Request: messages: text content blocks must be non-empty"));
+
+        let error = anyhow::Error::from(LlmError::ContextLimitError("too long".to_string())).context("provider call");
+        assert!(is_context_limit(&error));
+        assert!(!is_context_limit(&anyhow::Error::from(LlmError::ApiError("bad".to_string()))));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,697 @@
+//! Google Gemini provider implementation
+
+use async_trait::async_trait;
+use std::{pin::Pin, time::Duration, collections::HashMap};
+use futures::{Stream, StreamExt, stream};
+use reqwest::{Client, header::{HeaderMap, HeaderValue, CONTENT_TYPE}};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::llm::{
+    provider::{LlmProvider, ProviderClientOptions, utils},
+    types::{
+        ChatRequest, ProviderResponse, ProviderEvent, ProviderConfig, Message, MessageRole,
+        ContentBlock, ToolCall, TokenUsage, FinishReason, Tool,
+    },
+    errors::{LlmError, LlmResult},
+    sse::SseBuffer,
+};
+
+/// Default Gemini API endpoint
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// JSON schema keywords understood by Gemini function declarations
+const SUPPORTED_SCHEMA_KEYS: &[&str] = &[
+    "type", "format", "title", "description", "nullable", "enum", "properties", "required",
+    "items", "minItems", "maxItems", "minimum", "maximum", "minLength", "maxLength",
+    "pattern", "anyOf", "propertyOrdering",
+];
+
+/// Google Gemini API provider
+#[derive(Debug, Clone)]
+pub struct GeminiProvider {
+    client: Client,
+    config: ProviderConfig,
+    options: ProviderClientOptions,
+}
+
+impl GeminiProvider {
+    /// Create a new Gemini provider
+    pub fn new(config: ProviderConfig) -> LlmResult<Self> {
+        let mut headers = HeaderMap::new();
+
+        // Set API key
+        if let Some(api_key) = &config.api_key {
+            let auth_value = HeaderValue::from_str(api_key)
+                .map_err(|e| LlmError::ConfigError(format!("Invalid API key: {}", e)))?;
+            headers.insert("x-goog-api-key", auth_value);
+        } else {
+            return Err(LlmError::ConfigError("API key is required".to_string()));
+        }
+
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        // Add extra headers
+        for (key, value) in &config.extra_headers {
+            let header_name: reqwest::header::HeaderName = key.parse()
+                .map_err(|e| LlmError::ConfigError(format!("Invalid header name '{}': {}", key, e)))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|e| LlmError::ConfigError(format!("Invalid header value for '{}': {}", key, e)))?;
+            headers.insert(header_name, header_value);
+        }
+
+        let options = ProviderClientOptions::default();
+        let client = Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_secs(options.timeout_seconds))
+            .user_agent(&options.user_agent)
+            .build()
+            .map_err(|e| LlmError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            config,
+            options,
+        })
+    }
+
+    /// Convert messages to Gemini contents, returning the system instruction separately
+    fn convert_messages(messages: &[Message]) -> (Option<String>, Vec<GeminiContent>) {
+        let mut system_message = None;
+        let mut contents: Vec<GeminiContent> = Vec::new();
+        // Gemini matches function responses by name rather than call id
+        let mut tool_names: HashMap<String, String> = HashMap::new();
+
+        for msg in messages {
+            let role = match msg.role {
+                MessageRole::System => {
+                    system_message = msg.get_text_content();
+                    continue;
+                }
+                MessageRole::Assistant => "model",
+                MessageRole::User | MessageRole::Tool => "user",
+            };
+
+            let parts: Vec<GeminiPart> = msg.content.iter().map(|block| match block {
+                ContentBlock::Text { text } => GeminiPart::text(text.clone()),
+                ContentBlock::Image { image } => GeminiPart {
+                    inline_data: Some(GeminiInlineData {
+                        mime_type: image.media_type.clone(),
+                        data: image.data.clone(),
+                    }),
+                    ..Default::default()
+                },
+                ContentBlock::ToolUse { id, name, input } => {
+                    tool_names.insert(id.clone(), name.clone());
+                    GeminiPart {
+                        function_call: Some(GeminiFunctionCall {
+                            name: name.clone(),
+                            args: input.clone(),
+                        }),
+                        ..Default::default()
+                    }
+                }
+                ContentBlock::ToolResult { tool_call_id, content } => GeminiPart {
+                    function_response: Some(GeminiFunctionResponse {
+                        name: tool_names.get(tool_call_id).cloned().unwrap_or_else(|| tool_call_id.clone()),
+                        response: json!({ "content": content }),
+                    }),
+                    ..Default::default()
+                },
+            }).collect();
+
+            if parts.is_empty() {
+                continue;
+            }
+
+            // Consecutive turns of the same role are merged, as Gemini expects alternation
+            match contents.last_mut() {
+                Some(last) if last.role == role => last.parts.extend(parts),
+                _ => contents.push(GeminiContent { role: role.to_string(), parts }),
+            }
+        }
+
+        (system_message, contents)
+    }
+
+    /// Convert tools to Gemini function declarations
+    fn convert_tools(tools: &[Tool]) -> Vec<GeminiTool> {
+        vec![GeminiTool {
+            function_declarations: tools.iter().map(|tool| {
+                GeminiFunctionDeclaration {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: convert_schema(&tool.input_schema),
+                }
+            }).collect(),
+        }]
+    }
+
+    /// Build the request body shared by streaming and non-streaming calls
+    fn build_request_body(&self, request: &ChatRequest) -> serde_json::Value {
+        let (system_message, contents) = Self::convert_messages(&request.messages);
+
+        let mut request_body = json!({
+            "contents": contents,
+        });
+
+        // Add system instruction if present
+        if let Some(system) = system_message.or_else(|| request.system_message.clone()) {
+            request_body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+
+        // Add optional parameters
+        let mut generation_config = serde_json::Map::new();
+        if let Some(max_tokens) = request.max_tokens.or(self.config.max_tokens) {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if let Some(temperature) = request.temperature.or(self.config.temperature) {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = request.top_p.or(self.config.top_p) {
+            generation_config.insert("topP".to_string(), json!(top_p));
+        }
+        if !generation_config.is_empty() {
+            request_body["generationConfig"] = serde_json::Value::Object(generation_config);
+        }
+
+        if !request.tools.is_empty() {
+            request_body["tools"] = json!(Self::convert_tools(&request.tools));
+        }
+
+        // Add extra body parameters
+        for (key, value) in &self.config.extra_body {
+            request_body[key] = value.clone();
+        }
+
+        request_body
+    }
+
+    /// Get the API endpoint URL for a method
+    fn get_endpoint(&self, method: &str) -> String {
+        let base_url = self.config.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let model = self.config.model.trim_start_matches("models/");
+        format!("{}/models/{}:{}", base_url.trim_end_matches('/'), model, method)
+    }
+
+    /// Execute request with retries
+    async fn execute_request<T>(&self, request_body: serde_json::Value) -> LlmResult<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut last_error = None;
+
+        for attempt in 0..=self.options.max_retries {
+            if attempt > 0 {
+                utils::exponential_backoff_with_jitter(attempt, self.options.retry_delay_ms).await;
+            }
+
+            let response = self.client
+                .post(self.get_endpoint("generateContent"))
+                .json(&request_body)
+                .send()
+                .await;
+
+            match response {
+                Ok(resp) => {
+                    if resp.status().is_success() {
+                        match resp.json::<T>().await {
+                            Ok(result) => return Ok(result),
+                            Err(e) => {
+                                last_error = Some(LlmError::HttpError(e));
+                                continue;
+                            }
+                        }
+                    } else {
+                        let error = utils::error_from_response(resp).await;
+
+                        if !utils::is_retryable_error(&error) || attempt == self.options.max_retries {
+                            return Err(error);
+                        }
+
+                        last_error = Some(error);
+                    }
+                }
+                Err(e) => {
+                    let error = LlmError::HttpError(e);
+                    if !utils::is_retryable_error(&error) || attempt == self.options.max_retries {
+                        return Err(error);
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| LlmError::ApiError("Unknown error".to_string())))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+        let request_body = self.build_request_body(&request);
+        let response: GeminiResponse = self.execute_request(request_body).await?;
+
+        let mut state = GeminiStreamState::default();
+        state.apply(response)?;
+        Ok(state.response())
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: ChatRequest,
+    ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+        let request_body = self.build_request_body(&request);
+
+        let response = self.client
+            .post(format!("{}?alt=sse", self.get_endpoint("streamGenerateContent")))
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(LlmError::HttpError)?;
+
+        if !response.status().is_success() {
+            return Err(utils::error_from_response(response).await);
+        }
+
+        let stream = response.bytes_stream()
+            .scan(GeminiStreamState::default(), |state, chunk_result| {
+                let events = match chunk_result {
+                    Ok(chunk) => state.push(&chunk),
+                    Err(e) => vec![Err(LlmError::HttpError(e))],
+                };
+                futures::future::ready(Some(stream::iter(events)))
+            })
+            .flatten();
+
+        Ok(Box::pin(stream))
+    }
+
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn validate_config(&self) -> LlmResult<()> {
+        if self.config.api_key.is_none() {
+            return Err(LlmError::ConfigError("API key is required".to_string()));
+        }
+
+        if self.config.model.is_empty() {
+            return Err(LlmError::ConfigError("Model is required".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn default_embedding_model(&self) -> Option<&str> {
+        Some("text-embedding-004")
+    }
+
+    async fn embed(&self, model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+        let base_url = self.config.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let model = format!("models/{}", model.trim_start_matches("models/"));
+        let requests: Vec<_> = inputs
+            .iter()
+            .map(|text| json!({ "model": model, "content": { "parts": [{ "text": text }] } }))
+            .collect();
+
+        let response = self.client
+            .post(format!("{}/{}:batchEmbedContents", base_url.trim_end_matches('/'), model))
+            .json(&json!({ "requests": requests }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(LlmError::ApiError(utils::extract_error_message(response).await));
+        }
+
+        let body: GeminiEmbedResponse = response.json().await?;
+        Ok(body.embeddings.into_iter().map(|embedding| embedding.values).collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiEmbedResponse {
+    embeddings: Vec<GeminiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiEmbedding {
+    values: Vec<f32>,
+}
+
+/// Translate a tool's JSON schema into the OpenAPI subset Gemini accepts
+///
+/// Unsupported keywords such as `$schema`, `additionalProperties` or `default`
+/// are dropped, and nullable type unions like `["string", "null"]` become a
+/// single type with `nullable` set.
+fn convert_schema(schema: &serde_json::Value) -> serde_json::Value {
+    let Some(object) = schema.as_object() else {
+        return schema.clone();
+    };
+
+    let mut converted = serde_json::Map::new();
+    for (key, value) in object {
+        if !SUPPORTED_SCHEMA_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let value = match key.as_str() {
+            "type" => match value.as_array() {
+                Some(types) => {
+                    if types.iter().any(|t| t == "null") {
+                        converted.insert("nullable".to_string(), json!(true));
+                    }
+                    types.iter().find(|t| *t != "null").cloned().unwrap_or(json!("string"))
+                }
+                None => value.clone(),
+            },
+            "properties" => serde_json::Value::Object(
+                value.as_object()
+                    .map(|properties| {
+                        properties.iter()
+                            .map(|(name, property)| (name.clone(), convert_schema(property)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            "items" => convert_schema(value),
+            "anyOf" => json!(value.as_array().map(|schemas| {
+                schemas.iter().map(convert_schema).collect::<Vec<_>>()
+            })),
+            _ => value.clone(),
+        };
+        converted.insert(key.clone(), value);
+    }
+
+    serde_json::Value::Object(converted)
+}
+
+/// Accumulates a Gemini response, either whole or from SSE chunks
+#[derive(Debug, Default)]
+struct GeminiStreamState {
+    lines: SseBuffer,
+    started: bool,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    usage: TokenUsage,
+    finish_reason: Option<FinishReason>,
+}
+
+impl GeminiStreamState {
+    /// Decode the Gemini response chunks completed by a chunk of the stream
+    fn push(&mut self, chunk: &[u8]) -> Vec<LlmResult<ProviderEvent>> {
+        let mut events = Vec::new();
+        for data in self.lines.push(chunk) {
+            match serde_json::from_str::<GeminiResponse>(&data) {
+                Ok(response) => events.extend(self.events_for(response)),
+                Err(e) => events.push(Err(LlmError::JsonError(e))),
+            }
+        }
+        events
+    }
+
+    /// Apply one response chunk and describe it as provider events
+    fn events_for(&mut self, response: GeminiResponse) -> Vec<LlmResult<ProviderEvent>> {
+        let mut events = Vec::new();
+        if !self.started {
+            self.started = true;
+            events.push(Ok(ProviderEvent::ContentStart));
+        }
+
+        let content_len = self.content.len();
+        let tool_calls_len = self.tool_calls.len();
+        if let Err(e) = self.apply(response) {
+            events.push(Err(e));
+            return events;
+        }
+
+        if self.content.len() > content_len {
+            events.push(Ok(ProviderEvent::ContentDelta {
+                delta: self.content[content_len..].to_string(),
+            }));
+        }
+        for tool_call in &self.tool_calls[tool_calls_len..] {
+            events.push(Ok(ProviderEvent::ToolUseStart { tool_call: tool_call.clone() }));
+            events.push(Ok(ProviderEvent::ToolUseStop));
+        }
+
+        // The final chunk carries the finish reason and the usage totals
+        if self.finish_reason.is_some() {
+            events.push(Ok(ProviderEvent::ContentStop));
+            events.push(Ok(ProviderEvent::Done { response: self.response() }));
+        }
+
+        events
+    }
+
+    /// Merge a response (or response chunk) into the accumulated state
+    fn apply(&mut self, response: GeminiResponse) -> LlmResult<()> {
+        if let Some(usage) = response.usage_metadata {
+            self.usage = TokenUsage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: usage.candidates_token_count,
+                total_tokens: usage.total_token_count,
+            };
+        }
+
+        let Some(candidate) = response.candidates.into_iter().next() else {
+            if let Some(reason) = response.prompt_feedback.and_then(|f| f.block_reason) {
+                return Err(LlmError::ApiError(format!("Prompt blocked by Gemini: {}", reason)));
+            }
+            return Ok(());
+        };
+
+        for part in candidate.content.map(|c| c.parts).unwrap_or_default() {
+            if let Some(text) = part.text {
+                // Thought summaries are not part of the answer
+                if !part.thought {
+                    self.content.push_str(&text);
+                }
+            }
+            if let Some(call) = part.function_call {
+                self.tool_calls.push(ToolCall {
+                    id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                    name: call.name,
+                    arguments: call.args,
+                });
+            }
+        }
+
+        if let Some(reason) = candidate.finish_reason {
+            self.finish_reason = Some(match reason.as_str() {
+                "STOP" if !self.tool_calls.is_empty() => FinishReason::ToolCalls,
+                "STOP" => FinishReason::Stop,
+                "MAX_TOKENS" => FinishReason::Length,
+                "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => FinishReason::ContentFilter,
+                _ => FinishReason::Error,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The response accumulated so far
+    fn response(&self) -> ProviderResponse {
+        ProviderResponse {
+            content: self.content.clone(),
+            tool_calls: self.tool_calls.clone(),
+            usage: self.usage.clone(),
+            finish_reason: self.finish_reason.clone(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+// Gemini API types
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inline_data: Option<GeminiInlineData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<GeminiFunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_response: Option<GeminiFunctionResponse>,
+    #[serde(default, skip_serializing)]
+    thought: bool,
+}
+
+impl GeminiPart {
+    fn text(text: String) -> Self {
+        Self {
+            text: Some(text),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiInlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionCall {
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiTool {
+    function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiFunctionDeclaration {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    usage_metadata: Option<GeminiUsage>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    total_token_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_messages_maps_roles_and_tool_results() {
+        let mut assistant = Message::new_assistant("Let me look.".to_string());
+        assistant.content.push(ContentBlock::ToolUse {
+            id: "call_1".to_string(),
+            name: "view".to_string(),
+            input: json!({ "file_path": "src/main.rs" }),
+        });
+        let mut tool = Message::new_text(MessageRole::Tool, String::new());
+        tool.content = vec![ContentBlock::ToolResult {
+            tool_call_id: "call_1".to_string(),
+            content: "fn main() {}".to_string(),
+        }];
+        let messages = vec![
+            Message::new_system("Be brief.".to_string()),
+            Message::new_user("What is in main.rs?".to_string()),
+            assistant,
+            tool,
+        ];
+
+        let (system, contents) = GeminiProvider::convert_messages(&messages);
+
+        assert_eq!(system.as_deref(), Some("Be brief."));
+        assert_eq!(contents.iter().map(|c| c.role.as_str()).collect::<Vec<_>>(), vec!["user", "model", "user"]);
+        let response = contents[2].parts[0].function_response.as_ref().unwrap();
+        assert_eq!(response.name, "view");
+        assert_eq!(response.response, json!({ "content": "fn main() {}" }));
+    }
+
+    #[test]
+    fn test_convert_schema_drops_unsupported_keywords() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "path": { "type": ["string", "null"], "default": "." },
+                "tags": { "type": "array", "items": { "type": "string", "examples": ["a"] } }
+            },
+            "required": ["tags"]
+        });
+
+        assert_eq!(
+            convert_schema(&schema),
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "nullable": true },
+                    "tags": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["tags"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_stream_state_handles_split_chunks() {
+        let mut state = GeminiStreamState::default();
+        let first = "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}]}\n\ndata: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"lo\"},{\"functionCall\":{\"name\":\"ls\",\"args\":{\"path\":\".\"}}}]},";
+        let second = "\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":7,\"candidatesTokenCount\":3,\"totalTokenCount\":10}}\n\n";
+
+        let events: Vec<_> = state.push(first.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(events[0], ProviderEvent::ContentStart));
+        assert!(matches!(&events[1], ProviderEvent::ContentDelta { delta } if delta == "Hel"));
+
+        let events: Vec<_> = state.push(second.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(&events[0], ProviderEvent::ContentDelta { delta } if delta == "lo"));
+        assert!(matches!(&events[1], ProviderEvent::ToolUseStart { tool_call } if tool_call.name == "ls"));
+        let ProviderEvent::Done { response } = events.last().unwrap() else {
+            panic!("expected a final response");
+        };
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.usage.total_tokens, 10);
+        assert!(matches!(response.finish_reason, Some(FinishReason::ToolCalls)));
+    }
+
+    #[test]
+    fn test_blocked_prompt_is_an_error() {
+        let mut state = GeminiStreamState::default();
+        let response: GeminiResponse = serde_json::from_value(json!({
+            "promptFeedback": { "blockReason": "SAFETY" }
+        }))
+        .unwrap();
+        assert!(state.apply(response).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Preparing images for multimodal requests
+//!
+//! Attached images are sent base64 encoded with their media type. Providers
+//! accept a few formats up to a size: images within the limits are sent as
+//! they are, larger ones are scaled down and re-encoded first, and other
+//! formats are converted to PNG.
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use std::io::Cursor;
+
+use super::types::{ContentBlock, ImageContent};
+
+/// Quality of the JPEG an image is re-encoded as when a PNG is too large
+const JPEG_QUALITY: u8 = 85;
+
+/// Largest image a provider takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Longest side in pixels
+    pub max_dimension: u32,
+    /// Size of the base64 encoded image in bytes
+    pub max_bytes: usize,
+}
+
+impl ImageLimits {
+    /// Anthropic scales down anything over 1568 pixels and rejects images over 5 MB
+    pub const ANTHROPIC: Self = Self {
+        max_dimension: 1568,
+        max_bytes: 5 * 1024 * 1024,
+    };
+
+    /// OpenAI scales images to fit 2048 pixels and rejects images over 20 MB
+    pub const OPENAI: Self = Self {
+        max_dimension: 2048,
+        max_bytes: 20 * 1024 * 1024,
+    };
+}
+
+impl Default for ImageLimits {
+    /// Limits every multimodal provider accepts
+    fn default() -> Self {
+        Self::ANTHROPIC
+    }
+}
+
+/// An image to send, as read from a file or the clipboard
+#[derive(Debug, Clone)]
+pub struct ImageAttachment {
+    /// File name, used in error messages
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl ImageAttachment {
+    pub fn new(name: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            data,
+        }
+    }
+
+    /// Read an image file
+    pub async fn from_path(path: &std::path::Path) -> Result<Self> {
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read image {}", path.display()))?;
+        Ok(Self::new(path.display().to_string(), data))
+    }
+}
+
+/// Media type of the formats providers accept
+fn media_type(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Size of `bytes` once base64 encoded
+fn encoded_len(bytes: usize) -> usize {
+    bytes.div_ceil(3) * 4
+}
+
+/// Encode an image for a request, scaling it down to fit `limits`
+pub fn encode_image(data: &[u8], limits: ImageLimits) -> Result<ImageContent> {
+    let format = image::guess_format(data).context("Not a supported image")?;
+    let image = image::load_from_memory_with_format(data, format).context("Failed to decode image")?;
+    let fits = image.width().max(image.height()) <= limits.max_dimension
+        && encoded_len(data.len()) <= limits.max_bytes;
+
+    if let (true, Some(media_type)) = (fits, media_type(format)) {
+        return Ok(ImageContent {
+            data: BASE64.encode(data),
+            media_type: media_type.to_string(),
+        });
+    }
+
+    let image = if image.width().max(image.height()) > limits.max_dimension {
+        image.resize(limits.max_dimension, limits.max_dimension, FilterType::Triangle)
+    } else {
+        image
+    };
+    let (bytes, media_type) = match encode(&image, ImageFormat::Png)? {
+        png if encoded_len(png.len()) <= limits.max_bytes => (png, "image/png"),
+        // Photos compress far better as JPEG
+        _ => (encode(&DynamicImage::ImageRgb8(image.to_rgb8()), ImageFormat::Jpeg)?, "image/jpeg"),
+    };
+    if encoded_len(bytes.len()) > limits.max_bytes {
+        anyhow::bail!("Image is larger than {} MB even when scaled down", limits.max_bytes / (1024 * 1024));
+    }
+    Ok(ImageContent {
+        data: BASE64.encode(bytes),
+        media_type: media_type.to_string(),
+    })
+}
+
+/// Content block carrying an attached image
+pub fn image_block(attachment: &ImageAttachment, limits: ImageLimits) -> Result<ContentBlock> {
+    let image = encode_image(&attachment.data, limits).with_context(|| format!("Can't attach {}", attachment.name))?;
+    Ok(ContentBlock::Image { image })
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY);
+            image.write_with_encoder(encoder)?;
+        }
+        format => image.write_to(&mut Cursor::new(&mut bytes), format)?,
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn image_bytes(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, image::Rgba([200, 30, 30, 255])));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    fn decode(image: &ImageContent) -> DynamicImage {
+        image::load_from_memory(&BASE64.decode(&image.data).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_small_images_are_sent_as_they_are() {
+        let png = image_bytes(64, 32, ImageFormat::Png);
+        let image = encode_image(&png, ImageLimits::default()).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!(BASE64.decode(&image.data).unwrap(), png);
+
+        // Formats providers don't take are converted
+        let bmp = image_bytes(64, 32, ImageFormat::Bmp);
+        let image = encode_image(&bmp, ImageLimits::default()).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!(decode(&image).width(), 64);
+    }
+
+    #[test]
+    fn test_large_images_are_scaled_to_the_provider_limits() {
+        let png = image_bytes(3000, 1500, ImageFormat::Png);
+
+        let image = encode_image(&png, ImageLimits::ANTHROPIC).unwrap();
+        let scaled = decode(&image);
+        assert_eq!((scaled.width(), scaled.height()), (1568, 784));
+
+        let image = encode_image(&png, ImageLimits::OPENAI).unwrap();
+        assert_eq!(decode(&image).width(), 2048);
+
+        assert!(encode_image(b"not an image", ImageLimits::default()).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 //! LLM provider abstractions and implementations
 //! 
 //! This module provides a unified interface for interacting with different
-//! language model providers (OpenAI, Anthropic, etc.) with support for
+//! language model providers (OpenAI, Anthropic, Gemini, etc.) with support for
 //! streaming responses, conversation management, and error handling.
 
 pub mod provider;
@@ -10,9 +10,25 @@ pub mod openai;
 pub mod anthropic;
 pub mod azure;
 pub mod ollama;
+pub mod gemini;
+pub mod openrouter;
+pub mod compatible;
 pub mod errors;
 pub mod tools;
+pub mod deadline;
+pub mod citations;
+pub mod changes;
+pub mod compat;
+pub mod documents;
+pub mod images;
+pub mod models;
+pub mod moderation;
+pub mod partial_json;
+pub mod resilience;
+pub mod sse;
+pub mod tokenizer;
 
 pub use provider::*;
 pub use types::*;
+pub use deadline::{Deadline, DeadlineExceeded};
 pub use errors::*;
\ No newline at end of file
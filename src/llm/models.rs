@@ -0,0 +1,491 @@
+//! Catalog of known models
+//!
+//! What the agent needs to know about a model before using it: how much
+//! context it takes, whether it can call tools and read images, and what it
+//! costs. Prices are in US dollars per million tokens at the providers' list
+//! prices without cache discounts. A model is found by its family's name, or
+//! that name followed by a release such as a date, `-latest` or an Ollama tag,
+//! so `claude-3-5-sonnet-20241022` needs no entry of its own while `gpt-4.5`
+//! is not taken for `gpt-4`. Models served through OpenRouter are found under
+//! their own name, so `deepseek/deepseek-chat` is priced like `deepseek-chat`,
+//! unless a price was registered for the full name from OpenRouter's model
+//! list.
+//!
+//! Assistant messages record the provider and model that wrote them under the
+//! `model` metadata key, since a session may switch models along the way.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::llm::types::{Message, TokenUsage};
+
+/// Metadata key holding the provider and model that wrote a message
+pub const MESSAGE_MODEL_KEY: &str = "model";
+
+/// Prices registered at runtime, taking precedence over the catalog
+static REGISTERED: OnceLock<RwLock<HashMap<String, ModelPricing>>> = OnceLock::new();
+
+fn registered() -> &'static RwLock<HashMap<String, ModelPricing>> {
+    REGISTERED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Price a model as reported by its provider
+pub fn register_pricing(model: &str, pricing: ModelPricing) {
+    registered().write().unwrap_or_else(|e| e.into_inner()).insert(model.to_string(), pricing);
+}
+
+/// Price of a model per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPricing {
+    /// Cost of the given usage in US dollars
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input + usage.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// What a model can do besides chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Calls tools, which the agent needs to act on the workspace
+    pub tools: bool,
+    /// Reads images attached to messages
+    pub vision: bool,
+}
+
+impl Capabilities {
+    /// Check if a model with these capabilities has everything `needs` asks for
+    pub fn covers(&self, needs: Capabilities) -> bool {
+        (self.tools || !needs.tools) && (self.vision || !needs.vision)
+    }
+}
+
+/// A model in the catalog
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KnownModel {
+    pub provider: &'static str,
+    /// Name of the family, whose releases share the entry
+    #[serde(skip)]
+    pub family: &'static str,
+    /// Name to request the model by
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Context window in tokens
+    pub context_window: u32,
+    pub capabilities: Capabilities,
+    /// List price, none for models run locally
+    pub pricing: Option<ModelPricing>,
+}
+
+const CATALOG: &[KnownModel] = &[
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4o",
+        id: "gpt-4o",
+        name: "GPT-4o",
+        description: "Fast multimodal GPT-4 class model",
+        context_window: 128_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 2.50, output: 10.00 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4o-mini",
+        id: "gpt-4o-mini",
+        name: "GPT-4o mini",
+        description: "Small, cheap multimodal model",
+        context_window: 128_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.15, output: 0.60 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4.1",
+        id: "gpt-4.1",
+        name: "GPT-4.1",
+        description: "Long-context coding model",
+        context_window: 1_047_576,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 2.00, output: 8.00 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4.1-mini",
+        id: "gpt-4.1-mini",
+        name: "GPT-4.1 mini",
+        description: "Small long-context model",
+        context_window: 1_047_576,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.40, output: 1.60 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4-turbo",
+        id: "gpt-4-turbo",
+        name: "GPT-4 Turbo",
+        description: "GPT-4 with a larger context",
+        context_window: 128_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 10.00, output: 30.00 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-4",
+        id: "gpt-4",
+        name: "GPT-4",
+        description: "Original GPT-4",
+        context_window: 8_192,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 30.00, output: 60.00 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "gpt-3.5-turbo",
+        id: "gpt-3.5-turbo",
+        name: "GPT-3.5 Turbo",
+        description: "Fast and inexpensive",
+        context_window: 16_385,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 0.50, output: 1.50 }),
+    },
+    KnownModel {
+        provider: "openai",
+        family: "o3-mini",
+        id: "o3-mini",
+        name: "o3-mini",
+        description: "Small reasoning model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 1.10, output: 4.40 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-opus-4",
+        id: "claude-opus-4-20250514",
+        name: "Claude Opus 4",
+        description: "Most capable Claude model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 15.00, output: 75.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-sonnet-4",
+        id: "claude-sonnet-4-20250514",
+        name: "Claude Sonnet 4",
+        description: "Balanced Claude model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 3.00, output: 15.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-7-sonnet",
+        id: "claude-3-7-sonnet-20250219",
+        name: "Claude 3.7 Sonnet",
+        description: "Claude with extended thinking",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 3.00, output: 15.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-5-sonnet",
+        id: "claude-3-5-sonnet-20241022",
+        name: "Claude 3.5 Sonnet",
+        description: "Strong at coding",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 3.00, output: 15.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-5-haiku",
+        id: "claude-3-5-haiku-20241022",
+        name: "Claude 3.5 Haiku",
+        description: "Fast Claude model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.80, output: 4.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-opus",
+        id: "claude-3-opus-20240229",
+        name: "Claude 3 Opus",
+        description: "Previous most capable Claude model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 15.00, output: 75.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-sonnet",
+        id: "claude-3-sonnet-20240229",
+        name: "Claude 3 Sonnet",
+        description: "Previous balanced Claude model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 3.00, output: 15.00 }),
+    },
+    KnownModel {
+        provider: "anthropic",
+        family: "claude-3-haiku",
+        id: "claude-3-haiku-20240307",
+        name: "Claude 3 Haiku",
+        description: "Fastest Claude 3 model",
+        context_window: 200_000,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.25, output: 1.25 }),
+    },
+    KnownModel {
+        provider: "gemini",
+        family: "gemini-2.5-pro",
+        id: "gemini-2.5-pro",
+        name: "Gemini 2.5 Pro",
+        description: "Most capable Gemini model",
+        context_window: 1_048_576,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 1.25, output: 10.00 }),
+    },
+    KnownModel {
+        provider: "gemini",
+        family: "gemini-2.5-flash",
+        id: "gemini-2.5-flash",
+        name: "Gemini 2.5 Flash",
+        description: "Fast and efficient Gemini model",
+        context_window: 1_048_576,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.30, output: 2.50 }),
+    },
+    KnownModel {
+        provider: "gemini",
+        family: "gemini-2.0-flash",
+        id: "gemini-2.0-flash",
+        name: "Gemini 2.0 Flash",
+        description: "Previous fast Gemini model",
+        context_window: 1_048_576,
+        capabilities: Capabilities { tools: true, vision: true },
+        pricing: Some(ModelPricing { input: 0.10, output: 0.40 }),
+    },
+    KnownModel {
+        provider: "deepseek",
+        family: "deepseek-chat",
+        id: "deepseek-chat",
+        name: "DeepSeek V3",
+        description: "General DeepSeek chat model",
+        context_window: 65_536,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 0.27, output: 1.10 }),
+    },
+    KnownModel {
+        provider: "deepseek",
+        family: "deepseek-reasoner",
+        id: "deepseek-reasoner",
+        name: "DeepSeek R1",
+        description: "DeepSeek reasoning model",
+        context_window: 65_536,
+        capabilities: Capabilities { tools: false, vision: false },
+        pricing: Some(ModelPricing { input: 0.55, output: 2.19 }),
+    },
+    KnownModel {
+        provider: "qwen",
+        family: "qwen-max",
+        id: "qwen-max",
+        name: "Qwen Max",
+        description: "Most capable Qwen model",
+        context_window: 32_768,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 1.60, output: 6.40 }),
+    },
+    KnownModel {
+        provider: "qwen",
+        family: "qwen-plus",
+        id: "qwen-plus",
+        name: "Qwen Plus",
+        description: "Balanced Qwen model",
+        context_window: 131_072,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 0.40, output: 1.20 }),
+    },
+    KnownModel {
+        provider: "qwen",
+        family: "qwen-turbo",
+        id: "qwen-turbo",
+        name: "Qwen Turbo",
+        description: "Fast Qwen model",
+        context_window: 1_000_000,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 0.05, output: 0.20 }),
+    },
+    KnownModel {
+        provider: "qwen",
+        family: "qwen3-coder-plus",
+        id: "qwen3-coder-plus",
+        name: "Qwen3 Coder Plus",
+        description: "Qwen coding model",
+        context_window: 1_048_576,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: Some(ModelPricing { input: 1.00, output: 5.00 }),
+    },
+    KnownModel {
+        provider: "ollama",
+        family: "llama3.2",
+        id: "llama3.2",
+        name: "Llama 3.2",
+        description: "Meta's Llama 3.2 model",
+        context_window: 131_072,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: None,
+    },
+    KnownModel {
+        provider: "ollama",
+        family: "codellama",
+        id: "codellama",
+        name: "Code Llama",
+        description: "Specialized coding model",
+        context_window: 16_384,
+        capabilities: Capabilities { tools: false, vision: false },
+        pricing: None,
+    },
+    KnownModel {
+        provider: "ollama",
+        family: "mistral",
+        id: "mistral",
+        name: "Mistral",
+        description: "Mistral AI model",
+        context_window: 32_768,
+        capabilities: Capabilities { tools: true, vision: false },
+        pricing: None,
+    },
+];
+
+/// Every model in the catalog
+pub fn catalog() -> &'static [KnownModel] {
+    CATALOG
+}
+
+/// The catalog entry for a model, if known
+pub fn lookup(model: &str) -> Option<&'static KnownModel> {
+    // OpenRouter names models "<vendor>/<model>"
+    let model = model.rsplit('/').next().unwrap_or(model);
+    CATALOG.iter().find(|known| model.strip_prefix(known.family).is_some_and(is_release))
+}
+
+/// Whether what follows a family's name in a model's name only names a
+/// release of it, like `-20241022`, `-2024-07-18`, `-latest` or `:7b`
+fn is_release(suffix: &str) -> bool {
+    if suffix.is_empty() || suffix == "-latest" || suffix.starts_with(':') {
+        return true;
+    }
+    suffix.strip_prefix('-').is_some_and(|release| {
+        release.chars().any(|c| c.is_ascii_digit()) && release.chars().all(|c| c.is_ascii_digit() || c == '-')
+    })
+}
+
+/// Price of a model, if known
+pub fn pricing(model: &str) -> Option<ModelPricing> {
+    if let Some(pricing) = registered().read().unwrap_or_else(|e| e.into_inner()).get(model) {
+        return Some(*pricing);
+    }
+    lookup(model).and_then(|known| known.pricing)
+}
+
+/// Cost of the given usage of a model, or zero if its price is unknown
+pub fn cost(model: &str, usage: &TokenUsage) -> f64 {
+    pricing(model).map_or(0.0, |pricing| pricing.cost(usage))
+}
+
+/// Provider and model that wrote a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageModel {
+    pub provider: String,
+    pub model: String,
+}
+
+impl MessageModel {
+    pub fn new(provider: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+        }
+    }
+    
+    /// The model recorded on a message, none for messages from before switching existed
+    pub fn of(message: &Message) -> Option<Self> {
+        let value = message.metadata.get(MESSAGE_MODEL_KEY)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+    
+    /// Record this model on a message
+    pub fn mark(&self, message: &mut Message) {
+        if let Ok(value) = serde_json::to_value(self) {
+            message.metadata.insert(MESSAGE_MODEL_KEY.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_of_known_and_unknown_models() {
+        let usage = TokenUsage { input_tokens: 1_000_000, output_tokens: 500_000, total_tokens: 1_500_000 };
+        assert!((cost("deepseek-chat", &usage) - 0.82).abs() < 1e-9);
+        assert_eq!(pricing("qwen/qwen-max"), pricing("qwen-max"));
+        assert_eq!(pricing("gpt-4o-mini-2024-07-18"), pricing("gpt-4o-mini"));
+        assert_ne!(pricing("gpt-4o-mini"), pricing("gpt-4o"));
+        assert_eq!(cost("llama3.2", &usage), 0.0);
+    }
+
+    #[test]
+    fn test_catalog_knows_capabilities() {
+        let sonnet = lookup("claude-3-5-sonnet-latest").unwrap();
+        assert_eq!(sonnet.context_window, 200_000);
+        assert!(sonnet.capabilities.tools && sonnet.capabilities.vision);
+        assert_eq!(lookup("llama3.2:latest").map(|known| known.provider), Some("ollama"));
+        assert_eq!(lookup("gpt-4-0613").unwrap().context_window, 8_192);
+
+        let agent = Capabilities { tools: true, vision: false };
+        assert!(!lookup("deepseek-reasoner").unwrap().capabilities.covers(agent));
+        assert!(lookup("deepseek-chat").unwrap().capabilities.covers(agent));
+        assert!(!lookup("qwen-max").unwrap().capabilities.covers(Capabilities { tools: true, vision: true }));
+        assert!(catalog().iter().all(|known| known.id.starts_with(known.family)));
+    }
+
+    #[test]
+    fn test_registered_prices_take_precedence() {
+        let pricing_of_listing = ModelPricing { input: 0.14, output: 0.28 };
+        register_pricing("deepseek/deepseek-chat-v3", pricing_of_listing);
+        assert_eq!(pricing("deepseek/deepseek-chat-v3"), Some(pricing_of_listing));
+        assert_eq!(pricing("deepseek-chat-v3"), None);
+    }
+
+    #[test]
+    fn test_only_releases_share_a_family() {
+        assert_eq!(lookup("claude-opus-4-20250514").map(|known| known.family), Some("claude-opus-4"));
+        assert_eq!(lookup("gpt-4-turbo-2024-04-09").map(|known| known.family), Some("gpt-4-turbo"));
+        assert_eq!(lookup("mistral:7b").map(|known| known.family), Some("mistral"));
+        assert_eq!(lookup("gpt-4o-latest").map(|known| known.family), Some("gpt-4o"));
+        assert_eq!(pricing("gpt-4.5"), None);
+        assert_eq!(pricing("gpt-4o-audio-preview"), None);
+        assert_eq!(pricing("o3-mini-high"), None);
+    }
+
+    #[test]
+    fn test_messages_remember_their_model() {
+        let mut message = Message::new_assistant("Done".to_string());
+        assert_eq!(MessageModel::of(&message), None);
+
+        let model = MessageModel::new("anthropic", "claude-3-5-sonnet-latest");
+        model.mark(&mut message);
+        assert_eq!(MessageModel::of(&message), Some(model));
+    }
+}
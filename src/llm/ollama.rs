@@ -361,6 +361,41 @@ impl LlmProvider for OllamaProvider {
         // This is done asynchronously in health_check, so we'll just return Ok here
         Ok(())
     }
+
+    fn default_embedding_model(&self) -> Option<&str> {
+        Some("nomic-embed-text")
+    }
+
+    async fn embed(&self, model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+        let url = format!("{}/api/embed", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "model": model, "input": inputs }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(LlmError::ApiError(format!(
+                "Ollama API error {}: {}",
+                status, error_text
+            )));
+        }
+
+        let body: OllamaEmbedResponse = response.json().await?;
+        Ok(body.embeddings)
+    }
+}
+
+/// Ollama embedding response format
+#[derive(Debug, Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
 }
 
 #[cfg(test)]
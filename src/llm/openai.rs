@@ -1,41 +1,93 @@
 //! OpenAI provider implementation
+//!
+//! Also serves OpenAI-compatible APIs through the profiles in `compat`, and
+//! Azure OpenAI with its own chat URL and authentication.
 
 use async_trait::async_trait;
-use std::{pin::Pin, time::Duration, collections::HashMap};
+use std::{pin::Pin, time::Duration, collections::HashMap, sync::Arc};
 use futures::{Stream, StreamExt, stream};
 use reqwest::{Client, header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE}};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::llm::{
+    compat::{self, CompatProfile},
+    images::ImageLimits,
     provider::{LlmProvider, ProviderClientOptions, utils},
     types::{
         ChatRequest, ProviderResponse, ProviderEvent, ProviderConfig, Message, MessageRole,
-        ContentBlock, ToolCall, TokenUsage, FinishReason, Tool,
+        ContentBlock, ToolCall, TokenUsage, Tool,
     },
     errors::{LlmError, LlmResult},
+    sse::SseBuffer,
 };
 
+/// Source of short-lived bearer tokens, such as Azure AD access tokens
+#[async_trait]
+pub trait TokenSource: std::fmt::Debug + Send + Sync {
+    /// A token valid for the next request, fetched again when it runs out
+    async fn token(&self) -> LlmResult<String>;
+}
+
+/// How requests are authenticated
+#[derive(Debug, Clone)]
+pub enum ApiAuth {
+    /// `Authorization: Bearer <key>`, as OpenAI expects
+    Bearer(String),
+    /// The key in a header of its own, like Azure's `api-key`
+    Header(&'static str, String),
+    /// A bearer token fetched from the source for each request
+    Tokens(Arc<dyn TokenSource>),
+    /// No credentials, for local servers
+    None,
+}
+
 /// OpenAI API provider
 #[derive(Debug, Clone)]
 pub struct OpenAIProvider {
     client: Client,
     config: ProviderConfig,
     options: ProviderClientOptions,
+    profile: CompatProfile,
+    tokens: Option<Arc<dyn TokenSource>>,
+    /// Chat completions URL used instead of the profile's
+    chat_url: Option<String>,
 }
 
 impl OpenAIProvider {
     /// Create a new OpenAI provider
     pub fn new(config: ProviderConfig) -> LlmResult<Self> {
+        Self::with_profile(config, compat::OPENAI)
+    }
+    
+    /// Create a provider for an OpenAI-compatible API
+    pub fn with_profile(config: ProviderConfig, profile: CompatProfile) -> LlmResult<Self> {
+        let auth = match config.api_key.clone().filter(|key| !key.is_empty()) {
+            Some(api_key) => ApiAuth::Bearer(api_key),
+            None if !profile.requires_api_key => ApiAuth::None,
+            None => return Err(LlmError::ConfigError("API key is required".to_string())),
+        };
+        Self::with_auth(config, profile, auth)
+    }
+    
+    /// Create a provider authenticating its requests with `auth`
+    pub fn with_auth(config: ProviderConfig, profile: CompatProfile, auth: ApiAuth) -> LlmResult<Self> {
         let mut headers = HeaderMap::new();
         
-        // Set API key
-        if let Some(api_key) = &config.api_key {
-            let auth_value = HeaderValue::from_str(&format!("Bearer {}", api_key))
-                .map_err(|e| LlmError::ConfigError(format!("Invalid API key: {}", e)))?;
-            headers.insert(AUTHORIZATION, auth_value);
-        } else {
-            return Err(LlmError::ConfigError("API key is required".to_string()));
+        let mut tokens = None;
+        match auth {
+            ApiAuth::Bearer(api_key) => {
+                let auth_value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+                    .map_err(|e| LlmError::ConfigError(format!("Invalid API key: {}", e)))?;
+                headers.insert(AUTHORIZATION, auth_value);
+            }
+            ApiAuth::Header(name, api_key) => {
+                let auth_value = HeaderValue::from_str(&api_key)
+                    .map_err(|e| LlmError::ConfigError(format!("Invalid API key: {}", e)))?;
+                headers.insert(name, auth_value);
+            }
+            ApiAuth::Tokens(source) => tokens = Some(source),
+            ApiAuth::None => {}
         }
         
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
@@ -61,9 +113,18 @@ impl OpenAIProvider {
             client,
             config,
             options,
+            profile,
+            tokens,
+            chat_url: None,
         })
     }
     
+    /// Send chat completions to `url` instead of the profile's endpoint
+    pub fn with_chat_url(mut self, url: String) -> Self {
+        self.chat_url = Some(url);
+        self
+    }
+    
     /// Convert messages to OpenAI format
     fn convert_messages(&self, messages: &[Message]) -> Vec<OpenAIMessage> {
         messages.iter().map(|msg| {
@@ -131,8 +192,19 @@ impl OpenAIProvider {
     
     /// Get the API endpoint URL
     fn get_endpoint(&self) -> String {
-        let base_url = self.config.base_url.as_deref().unwrap_or("https://api.openai.com");
-        format!("{}/v1/chat/completions", base_url)
+        match &self.chat_url {
+            Some(url) => url.clone(),
+            None => self.profile.chat_url(self.config.base_url.as_deref()),
+        }
+    }
+    
+    /// Start a POST request, with a current token when tokens are used
+    async fn post(&self, url: &str) -> LlmResult<reqwest::RequestBuilder> {
+        let request = self.client.post(url);
+        match &self.tokens {
+            Some(tokens) => Ok(request.bearer_auth(tokens.token().await?)),
+            None => Ok(request),
+        }
     }
     
     /// Execute request with retries
@@ -147,8 +219,7 @@ impl OpenAIProvider {
                 utils::exponential_backoff_with_jitter(attempt, self.options.retry_delay_ms).await;
             }
             
-            let response = self.client
-                .post(&self.get_endpoint())
+            let response = self.post(&self.get_endpoint()).await?
                 .json(&request_body)
                 .send()
                 .await;
@@ -164,21 +235,7 @@ impl OpenAIProvider {
                             }
                         }
                     } else {
-                        let status = resp.status();
-                        let error_msg = utils::extract_error_message(resp).await;
-                        
-                        let error = match status.as_u16() {
-                            429 => LlmError::RateLimitError(error_msg),
-                            401 | 403 => LlmError::AuthError(error_msg),
-                            400 => {
-                                if error_msg.contains("context_length_exceeded") {
-                                    LlmError::ContextLimitError(error_msg)
-                                } else {
-                                    LlmError::ApiError(error_msg)
-                                }
-                            }
-                            _ => LlmError::ApiError(error_msg),
-                        };
+                        let error = utils::error_from_response(resp).await;
                         
                         if !utils::is_retryable_error(&error) || attempt == self.options.max_retries {
                             return Err(error);
@@ -243,28 +300,24 @@ impl LlmProvider for OpenAIProvider {
             .map(|tc| ToolCall {
                 id: tc.id,
                 name: tc.function.name,
-                arguments: tc.function.arguments,
+                arguments: compat::tool_arguments(tc.function.arguments),
             })
             .collect();
         
-        let finish_reason = match choice.finish_reason.as_deref() {
-            Some("stop") => Some(FinishReason::Stop),
-            Some("length") => Some(FinishReason::Length),
-            Some("content_filter") => Some(FinishReason::ContentFilter),
-            Some("tool_calls") => Some(FinishReason::ToolCalls),
-            _ => None,
-        };
+        let finish_reason = compat::finish_reason(choice.finish_reason.as_deref());
+        
+        // Reasoning models return their chain of thought next to the answer
+        let mut metadata = HashMap::new();
+        if let Some(reasoning) = choice.message.reasoning_content.or(choice.message.reasoning) {
+            metadata.insert("reasoning".to_string(), json!(reasoning));
+        }
         
         Ok(ProviderResponse {
             content,
             tool_calls,
-            usage: TokenUsage {
-                input_tokens: response.usage.prompt_tokens,
-                output_tokens: response.usage.completion_tokens,
-                total_tokens: response.usage.total_tokens,
-            },
+            usage: response.usage.map(OpenAIUsage::into_token_usage).unwrap_or_default(),
             finish_reason,
-            metadata: HashMap::new(),
+            metadata,
         })
     }
     
@@ -276,6 +329,8 @@ impl LlmProvider for OpenAIProvider {
             "model": self.config.model,
             "messages": self.convert_messages(&request.messages),
             "stream": true,
+            // The usage comes in a last chunk of its own
+            "stream_options": { "include_usage": true },
         });
         
         // Add optional parameters
@@ -300,80 +355,31 @@ impl LlmProvider for OpenAIProvider {
             request_body[key] = value.clone();
         }
         
-        let response = self.client
-            .post(&self.get_endpoint())
+        let response = self.post(&self.get_endpoint()).await?
             .json(&request_body)
             .send()
             .await
             .map_err(LlmError::HttpError)?;
         
         if !response.status().is_success() {
-            let error_msg = utils::extract_error_message(response).await;
-            return Err(LlmError::ApiError(error_msg));
+            return Err(utils::error_from_response(response).await);
         }
         
         let stream = response.bytes_stream()
-            .map(|result| {
-                result.map_err(LlmError::HttpError)
+            .scan(OpenAIStreamState::default(), |state, chunk_result| {
+                let events = match chunk_result {
+                    Ok(chunk) => state.push(&chunk),
+                    Err(e) => vec![Err(LlmError::HttpError(e))],
+                };
+                futures::future::ready(Some(stream::iter(events)))
             })
-            .filter_map(|chunk_result| async move {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        
-                        // Parse SSE format
-                        for line in chunk_str.lines() {
-                            if line.starts_with("data: ") {
-                                let data = &line[6..];
-                                if data == "[DONE]" {
-                                    return Some(Ok(ProviderEvent::ContentStop));
-                                }
-                                
-                                match serde_json::from_str::<OpenAIStreamResponse>(data) {
-                                    Ok(stream_response) => {
-                                        if let Some(choice) = stream_response.choices.first() {
-                                            if let Some(delta) = &choice.delta {
-                                                if let Some(content) = &delta.content {
-                                                    return Some(Ok(ProviderEvent::ContentDelta {
-                                                        delta: content.clone(),
-                                                    }));
-                                                }
-                                                
-                                                if let Some(tool_calls) = &delta.tool_calls {
-                                                    for tool_call in tool_calls {
-                                                        if let (Some(id), Some(function)) = (&tool_call.id, &tool_call.function) {
-                                                            if let Some(name) = &function.name {
-                                                                return Some(Ok(ProviderEvent::ToolUseStart {
-                                                                    tool_call: ToolCall {
-                                                                        id: id.clone(),
-                                                                        name: name.clone(),
-                                                                        arguments: function.arguments.clone().unwrap_or_default(),
-                                                                    },
-                                                                }));
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        return Some(Err(LlmError::JsonError(e)));
-                                    }
-                                }
-                            }
-                        }
-                        None
-                    }
-                    Err(e) => Some(Err(e)),
-                }
-            });
+            .flatten();
         
         Ok(Box::pin(stream))
     }
     
     fn name(&self) -> &str {
-        "openai"
+        self.profile.name
     }
     
     fn model(&self) -> &str {
@@ -381,7 +387,7 @@ impl LlmProvider for OpenAIProvider {
     }
     
     fn validate_config(&self) -> LlmResult<()> {
-        if self.config.api_key.is_none() {
+        if self.profile.requires_api_key && self.config.api_key.is_none() && self.tokens.is_none() {
             return Err(LlmError::ConfigError("API key is required".to_string()));
         }
         
@@ -391,6 +397,41 @@ impl LlmProvider for OpenAIProvider {
         
         Ok(())
     }
+    
+    fn image_limits(&self) -> ImageLimits {
+        ImageLimits::OPENAI
+    }
+    
+    fn default_embedding_model(&self) -> Option<&str> {
+        (self.profile == compat::OPENAI).then_some("text-embedding-3-small")
+    }
+    
+    async fn embed(&self, model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+        let base_url = self.config.base_url.as_deref().unwrap_or("https://api.openai.com");
+        let response = self.post(&format!("{}/v1/embeddings", base_url)).await?
+            .json(&json!({ "model": model, "input": inputs }))
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(LlmError::ApiError(utils::extract_error_message(response).await));
+        }
+        
+        let mut body: OpenAIEmbeddingResponse = response.json().await?;
+        body.data.sort_by_key(|item| item.index);
+        Ok(body.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
 }
 
 // OpenAI API types
@@ -443,11 +484,16 @@ struct OpenAIFunction {
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIToolCall {
     id: String,
-    #[serde(rename = "type")]
+    // DeepSeek leaves out the type in responses
+    #[serde(rename = "type", default = "default_tool_type")]
     tool_type: String,
     function: OpenAIFunctionCall,
 }
 
+fn default_tool_type() -> String {
+    "function".to_string()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIFunctionCall {
     name: String,
@@ -457,7 +503,9 @@ struct OpenAIFunctionCall {
 #[derive(Debug, Deserialize)]
 struct OpenAIResponse {
     choices: Vec<OpenAIChoice>,
-    usage: OpenAIUsage,
+    /// Some compatible servers leave usage out
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -470,33 +518,65 @@ struct OpenAIChoice {
 struct OpenAIResponseMessage {
     content: Option<String>,
     tool_calls: Option<Vec<OpenAIToolCall>>,
+    /// Chain of thought of DeepSeek and Qwen reasoning models
+    reasoning_content: Option<String>,
+    /// Chain of thought as OpenRouter reports it
+    reasoning: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
 struct OpenAIUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
 }
 
+impl OpenAIUsage {
+    /// Token usage, with the total worked out when a server leaves it out
+    fn into_token_usage(self) -> TokenUsage {
+        let total_tokens = match self.total_tokens {
+            0 => self.prompt_tokens + self.completion_tokens,
+            total => total,
+        };
+        TokenUsage {
+            input_tokens: self.prompt_tokens,
+            output_tokens: self.completion_tokens,
+            total_tokens,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamResponse {
+    #[serde(default)]
     choices: Vec<OpenAIStreamChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamChoice {
     delta: Option<OpenAIStreamDelta>,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamDelta {
     content: Option<String>,
     tool_calls: Option<Vec<OpenAIStreamToolCall>>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    reasoning: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamToolCall {
+    /// Position of the call in the answer, missing on some compatible servers
+    #[serde(default)]
+    index: Option<usize>,
     id: Option<String>,
     function: Option<OpenAIStreamFunction>,
 }
@@ -505,4 +585,161 @@ struct OpenAIStreamToolCall {
 struct OpenAIStreamFunction {
     name: Option<String>,
     arguments: Option<serde_json::Value>,
-}
\ No newline at end of file
+}
+
+/// Accumulates a streamed chat completion from its SSE chunks
+#[derive(Debug, Default)]
+struct OpenAIStreamState {
+    lines: SseBuffer,
+    started: bool,
+    content: String,
+    reasoning: String,
+    /// Calls so far as ID, name and the arguments received
+    tool_calls: Vec<(String, String, String)>,
+    usage: Option<OpenAIUsage>,
+    finish_reason: Option<String>,
+}
+
+impl OpenAIStreamState {
+    /// Decode the chat completion chunks completed by a chunk of the stream
+    fn push(&mut self, chunk: &[u8]) -> Vec<LlmResult<ProviderEvent>> {
+        let mut events = Vec::new();
+        for data in self.lines.push(chunk) {
+            if data == "[DONE]" {
+                events.push(Ok(ProviderEvent::ContentStop));
+                events.push(Ok(ProviderEvent::Done { response: self.response() }));
+                continue;
+            }
+            match serde_json::from_str::<OpenAIStreamResponse>(&data) {
+                Ok(response) => events.extend(self.events_for(response)),
+                Err(e) => events.push(Err(LlmError::JsonError(e))),
+            }
+        }
+        events
+    }
+
+    /// Merge one chunk into the answer and describe it as provider events
+    fn events_for(&mut self, response: OpenAIStreamResponse) -> Vec<LlmResult<ProviderEvent>> {
+        let mut events = Vec::new();
+        if !self.started {
+            self.started = true;
+            events.push(Ok(ProviderEvent::ContentStart));
+        }
+        if let Some(usage) = response.usage {
+            self.usage = Some(usage);
+        }
+
+        let Some(choice) = response.choices.into_iter().next() else {
+            return events;
+        };
+        if let Some(reason) = choice.finish_reason {
+            self.finish_reason = Some(reason);
+        }
+        let Some(delta) = choice.delta else {
+            return events;
+        };
+
+        if let Some(reasoning) = delta.reasoning_content.or(delta.reasoning) {
+            self.reasoning.push_str(&reasoning);
+        }
+        if let Some(content) = delta.content.filter(|content| !content.is_empty()) {
+            self.content.push_str(&content);
+            events.push(Ok(ProviderEvent::ContentDelta { delta: content }));
+        }
+        for tool_call in delta.tool_calls.unwrap_or_default() {
+            let (name, arguments) = match tool_call.function {
+                Some(function) => (function.name.unwrap_or_default(), function.arguments),
+                None => (String::new(), None),
+            };
+            // Some servers send the arguments as an object instead of text
+            let fragment = match arguments {
+                Some(serde_json::Value::String(text)) => text,
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            };
+
+            // A call starts with its name; Qwen repeats the ID with an empty name later
+            let index = tool_call.index.unwrap_or_else(|| match &tool_call.id {
+                Some(id) if !name.is_empty() && self.tool_calls.iter().all(|(known, _, _)| known != id) => self.tool_calls.len(),
+                _ => self.tool_calls.len().saturating_sub(1),
+            });
+            if index >= self.tool_calls.len() && !name.is_empty() {
+                let id = tool_call.id.unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+                events.push(Ok(ProviderEvent::ToolUseStart {
+                    tool_call: ToolCall {
+                        id: id.clone(),
+                        name: name.clone(),
+                        arguments: serde_json::Value::String(fragment.clone()),
+                    },
+                }));
+                self.tool_calls.push((id, name, fragment));
+            } else if let Some((_, _, arguments)) = self.tool_calls.get_mut(index).filter(|_| !fragment.is_empty()) {
+                arguments.push_str(&fragment);
+                events.push(Ok(ProviderEvent::ToolUseDelta { delta: fragment }));
+            }
+        }
+
+        events
+    }
+
+    /// The answer received so far
+    fn response(&self) -> ProviderResponse {
+        let mut metadata = HashMap::new();
+        if !self.reasoning.is_empty() {
+            metadata.insert("reasoning".to_string(), json!(self.reasoning));
+        }
+        ProviderResponse {
+            content: self.content.clone(),
+            tool_calls: self
+                .tool_calls
+                .iter()
+                .map(|(id, name, arguments)| ToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    arguments: compat::tool_arguments(serde_json::Value::String(arguments.clone())),
+                })
+                .collect(),
+            usage: self.usage.map(OpenAIUsage::into_token_usage).unwrap_or_default(),
+            finish_reason: compat::finish_reason(self.finish_reason.as_deref()),
+            metadata,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::FinishReason;
+
+    #[test]
+    fn test_stream_state_assembles_split_chunks() {
+        let mut state = OpenAIStreamState::default();
+        let first = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Let me \"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"look\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"ls\",\"arguments\":\"{\\\"pa\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"argu",
+        );
+        let second = concat!(
+            "ments\":\"th\\\":\\\".\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":7,\"completion_tokens\":3,\"total_tokens\":10}}\n\n",
+            "data: [DONE]\n\n",
+        );
+
+        let events: Vec<_> = state.push(first.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(events[0], ProviderEvent::ContentStart));
+        assert!(matches!(&events[1], ProviderEvent::ContentDelta { delta } if delta == "Let me "));
+        assert!(matches!(&events[3], ProviderEvent::ToolUseStart { tool_call } if tool_call.name == "ls"));
+        assert_eq!(events.len(), 4);
+
+        let events: Vec<_> = state.push(second.as_bytes()).into_iter().map(Result::unwrap).collect();
+        assert!(matches!(&events[0], ProviderEvent::ToolUseDelta { delta } if delta == "th\":\".\"}"));
+        let ProviderEvent::Done { response } = events.last().unwrap() else {
+            panic!("expected a final response");
+        };
+        assert_eq!(response.content, "Let me look");
+        assert_eq!(response.tool_calls[0].arguments, json!({ "path": "." }));
+        assert_eq!(response.usage.total_tokens, 10);
+        assert!(matches!(response.finish_reason, Some(FinishReason::ToolCalls)));
+    }
+}
@@ -0,0 +1,190 @@
+//! OpenRouter provider implementation
+//!
+//! OpenRouter serves models of many vendors behind the OpenAI chat completions
+//! protocol, so requests go through the OpenAI provider with the OpenRouter
+//! profile. On top of that this module sends the attribution headers
+//! OpenRouter shows on its leaderboards, passes provider routing preferences
+//! in the request body, and reads the model list with prices for the model
+//! picker and the cost tracker.
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde::Deserialize;
+use std::pin::Pin;
+
+use crate::config::OpenRouterConfig;
+use crate::llm::{
+    compat,
+    errors::{LlmError, LlmResult},
+    models::{self, ModelPricing},
+    openai::OpenAIProvider,
+    provider::{utils, LlmProvider},
+    types::{ChatRequest, ProviderConfig, ProviderEvent, ProviderResponse},
+};
+
+/// Referer sent when no site is configured
+const DEFAULT_SITE_URL: &str = "https://github.com/charlenopires/ClaudeContextTerminal";
+
+/// App name sent when none is configured
+const DEFAULT_APP_NAME: &str = "Goofy";
+
+/// Add OpenRouter's headers and routing preferences to a provider config
+///
+/// Headers and a `provider` body entry already in the config are kept.
+pub fn apply_settings(config: &mut ProviderConfig, settings: &OpenRouterConfig) -> LlmResult<()> {
+    let site_url = settings.site_url.as_deref().unwrap_or(DEFAULT_SITE_URL);
+    let app_name = settings.app_name.as_deref().unwrap_or(DEFAULT_APP_NAME);
+    config.extra_headers.entry("HTTP-Referer".to_string()).or_insert_with(|| site_url.to_string());
+    config.extra_headers.entry("X-Title".to_string()).or_insert_with(|| app_name.to_string());
+
+    if let Some(routing) = &settings.routing {
+        if !config.extra_body.contains_key("provider") {
+            let routing = serde_json::to_value(routing)?;
+            config.extra_body.insert("provider".to_string(), routing);
+        }
+    }
+    Ok(())
+}
+
+/// OpenRouter provider
+pub struct OpenRouterProvider {
+    inner: OpenAIProvider,
+}
+
+impl OpenRouterProvider {
+    /// Create a new OpenRouter provider
+    pub fn new(mut config: ProviderConfig) -> LlmResult<Self> {
+        apply_settings(&mut config, &OpenRouterConfig::default())?;
+        Ok(Self {
+            inner: OpenAIProvider::with_profile(config, compat::OPENROUTER)?,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+        self.inner.chat_completion(request).await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: ChatRequest,
+    ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+        self.inner.chat_completion_stream(request).await
+    }
+
+    fn name(&self) -> &str {
+        "openrouter"
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn validate_config(&self) -> LlmResult<()> {
+        self.inner.validate_config()
+    }
+}
+
+/// A model listed by OpenRouter
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenRouterModel {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u32>,
+    #[serde(default)]
+    pub pricing: Option<OpenRouterPricing>,
+}
+
+/// Prices of a listed model, in US dollars per token
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenRouterPricing {
+    pub prompt: String,
+    pub completion: String,
+}
+
+impl OpenRouterModel {
+    /// Price per million tokens, if the listing has a valid one
+    pub fn pricing(&self) -> Option<ModelPricing> {
+        let pricing = self.pricing.as_ref()?;
+        Some(ModelPricing {
+            input: pricing.prompt.parse::<f64>().ok()? * 1_000_000.0,
+            output: pricing.completion.parse::<f64>().ok()? * 1_000_000.0,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<OpenRouterModel>,
+}
+
+/// List the models OpenRouter serves
+pub async fn list_models(base_url: Option<&str>) -> LlmResult<Vec<OpenRouterModel>> {
+    let base_url = base_url.unwrap_or(compat::OPENROUTER.default_base_url).trim_end_matches('/');
+    let response = reqwest::Client::new().get(format!("{}/models", base_url)).send().await?;
+    if !response.status().is_success() {
+        return Err(LlmError::ApiError(utils::extract_error_message(response).await));
+    }
+    Ok(response.json::<ModelList>().await?.data)
+}
+
+/// Price every listed model in the cost tracker, returning how many were priced
+pub async fn register_model_pricing(base_url: Option<&str>) -> LlmResult<usize> {
+    let mut count = 0;
+    for model in list_models(base_url).await? {
+        if let Some(pricing) = model.pricing() {
+            models::register_pricing(&model.id, pricing);
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OpenRouterRouting;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_add_headers_and_routing() {
+        let mut config = ProviderConfig {
+            provider_type: "openrouter".to_string(),
+            ..ProviderConfig::default()
+        };
+        config.extra_headers.insert("X-Title".to_string(), "Custom".to_string());
+        let settings = OpenRouterConfig {
+            site_url: Some("https://example.com".to_string()),
+            app_name: None,
+            routing: Some(OpenRouterRouting {
+                order: vec!["anthropic".to_string()],
+                allow_fallbacks: Some(false),
+                ..OpenRouterRouting::default()
+            }),
+        };
+
+        apply_settings(&mut config, &settings).unwrap();
+        assert_eq!(config.extra_headers["HTTP-Referer"], "https://example.com");
+        assert_eq!(config.extra_headers["X-Title"], "Custom");
+        assert_eq!(config.extra_body["provider"], json!({ "order": ["anthropic"], "allow_fallbacks": false }));
+    }
+
+    #[test]
+    fn test_listed_prices_are_per_million_tokens() {
+        let model: OpenRouterModel = serde_json::from_value(json!({
+            "id": "anthropic/claude-3.5-sonnet",
+            "name": "Anthropic: Claude 3.5 Sonnet",
+            "context_length": 200000,
+            "pricing": { "prompt": "0.000003", "completion": "0.000015", "image": "0.0048" }
+        }))
+        .unwrap();
+        let pricing = model.pricing().unwrap();
+        assert!((pricing.input - 3.0).abs() < 1e-9);
+        assert!((pricing.output - 15.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,330 @@
+//! Incremental parsing of streamed tool-call arguments
+//!
+//! Providers stream tool arguments as fragments of a JSON document. The
+//! [`PartialJson`] parser consumes the fragments as they arrive, tracks
+//! whether the text so far is still a valid prefix, and can produce the value
+//! described so far at any point: open strings, arrays and objects are closed,
+//! and a key still waiting for its value is left out. That is enough to show
+//! e.g. the file a pending `edit` call targets before the call completes.
+
+use serde_json::Value;
+
+/// Where the parser is inside a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// Right after `{` or `[`: a key/value or the closing bracket
+    FirstOrEnd,
+    /// After a comma
+    Item,
+    /// After an object key
+    Colon,
+    /// After a colon
+    Value,
+    /// After a complete item
+    CommaOrEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+impl Container {
+    fn closer(self) -> char {
+        match self {
+            Container::Object => '}',
+            Container::Array => ']',
+        }
+    }
+}
+
+/// Incremental, tolerant JSON parser
+#[derive(Debug, Clone, Default)]
+pub struct PartialJson {
+    buffer: String,
+    /// Open containers, innermost last, with what each expects next
+    stack: Vec<(Container, Expect)>,
+    /// Whether a complete top-level value has been read
+    done: bool,
+    /// Inside a string; `true` when the string is an object key
+    string: Option<bool>,
+    escape: bool,
+    /// Unterminated number or literal
+    token: String,
+    /// Prefix length and closing brackets of the last point where the text
+    /// could be cut and closed into a valid document
+    safe: Option<(usize, String)>,
+    error: Option<String>,
+}
+
+impl PartialJson {
+    /// Create an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next fragment
+    pub fn push(&mut self, fragment: &str) {
+        for c in fragment.chars() {
+            if self.error.is_some() {
+                break;
+            }
+            self.step(c);
+            self.buffer.push(c);
+        }
+    }
+
+    /// The raw text received so far
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Whether the text so far can still become valid JSON
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Why the text can no longer become valid JSON
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Whether a complete JSON value has been received
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+            && (self.done || (self.stack.is_empty() && !self.token.is_empty() && parses(&self.token)))
+    }
+
+    /// The value received so far, with open structures closed
+    ///
+    /// Returns `None` until there is something to show or once the text is
+    /// invalid.
+    pub fn value(&self) -> Option<Value> {
+        if self.error.is_some() {
+            return None;
+        }
+        let closers = self.closers();
+
+        // A string value still being received is shown as far as it goes
+        if self.string == Some(false) {
+            let text = without_partial_escape(&self.buffer);
+            if let Ok(value) = serde_json::from_str(&format!("{}\"{}", text, closers)) {
+                return Some(value);
+            }
+        }
+        // A number or literal that may already be whole
+        if !self.token.is_empty() {
+            if let Ok(value) = serde_json::from_str(&format!("{}{}", self.buffer, closers)) {
+                return Some(value);
+            }
+        }
+        if self.string.is_none() && self.token.is_empty() {
+            if let Ok(value) = serde_json::from_str(&format!("{}{}", self.buffer, closers)) {
+                return Some(value);
+            }
+        }
+        let (len, closers) = self.safe.as_ref()?;
+        serde_json::from_str(&format!("{}{}", &self.buffer[..*len], closers)).ok()
+    }
+
+    /// A top-level string field of an object, even while it is still arriving
+    pub fn string_field(&self, key: &str) -> Option<String> {
+        self.value()?.get(key)?.as_str().map(str::to_string)
+    }
+
+    /// Brackets closing every open container, innermost first
+    fn closers(&self) -> String {
+        self.stack.iter().rev().map(|(container, _)| container.closer()).collect()
+    }
+
+    fn fail(&mut self, c: char) {
+        self.error = Some(format!("Unexpected '{}' at offset {}", c, self.buffer.len()));
+    }
+
+    /// Remember that the text up to and including `c` can be closed off
+    fn mark_safe(&mut self, c: char) {
+        self.safe = Some((self.buffer.len() + c.len_utf8(), self.closers()));
+    }
+
+    /// Record that a value ended with `c`
+    fn value_complete(&mut self, c: char) {
+        match self.stack.last_mut() {
+            Some((_, expect)) => *expect = Expect::CommaOrEnd,
+            None => self.done = true,
+        }
+        self.mark_safe(c);
+    }
+
+    /// Whether the current position accepts a value
+    fn expects_value(&self) -> bool {
+        match self.stack.last() {
+            None => !self.done,
+            Some((Container::Array, Expect::FirstOrEnd | Expect::Item)) => true,
+            Some((Container::Object, Expect::Value)) => true,
+            _ => false,
+        }
+    }
+
+    fn step(&mut self, c: char) {
+        if let Some(is_key) = self.string {
+            if self.escape {
+                self.escape = false;
+            } else if c == '\\' {
+                self.escape = true;
+            } else if c == '"' {
+                self.string = None;
+                if is_key {
+                    if let Some((_, expect)) = self.stack.last_mut() {
+                        *expect = Expect::Colon;
+                    }
+                } else {
+                    self.value_complete(c);
+                }
+            } else if c.is_control() {
+                self.fail(c);
+            }
+            return;
+        }
+
+        if !self.token.is_empty() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+') {
+                self.token.push(c);
+                return;
+            }
+            // The token ends at this delimiter
+            let token = std::mem::take(&mut self.token);
+            if !parses(&token) {
+                self.error = Some(format!("Invalid value '{}'", token));
+                return;
+            }
+            // Safe point is right before the delimiter
+            match self.stack.last_mut() {
+                Some((_, expect)) => *expect = Expect::CommaOrEnd,
+                None => self.done = true,
+            }
+            self.safe = Some((self.buffer.len(), self.closers()));
+        }
+
+        if c.is_whitespace() {
+            return;
+        }
+
+        match c {
+            '{' | '[' if self.expects_value() => {
+                let container = if c == '{' { Container::Object } else { Container::Array };
+                self.stack.push((container, Expect::FirstOrEnd));
+                self.mark_safe(c);
+            }
+            '}' | ']' => match self.stack.last() {
+                Some((container, Expect::FirstOrEnd | Expect::CommaOrEnd)) if container.closer() == c => {
+                    self.stack.pop();
+                    self.value_complete(c);
+                }
+                _ => self.fail(c),
+            },
+            ',' => match self.stack.last_mut() {
+                Some((_, expect @ Expect::CommaOrEnd)) => *expect = Expect::Item,
+                _ => self.fail(c),
+            },
+            ':' => match self.stack.last_mut() {
+                Some((Container::Object, expect @ Expect::Colon)) => *expect = Expect::Value,
+                _ => self.fail(c),
+            },
+            '"' => match self.stack.last() {
+                Some((Container::Object, Expect::FirstOrEnd | Expect::Item)) => self.string = Some(true),
+                _ if self.expects_value() => self.string = Some(false),
+                _ => self.fail(c),
+            },
+            c if (c.is_ascii_alphanumeric() || c == '-') && self.expects_value() => self.token.push(c),
+            c => self.fail(c),
+        }
+    }
+}
+
+/// Whether a complete number or literal is valid JSON
+fn parses(token: &str) -> bool {
+    serde_json::from_str::<Value>(token).is_ok()
+}
+
+/// Drop a trailing escape sequence that has not fully arrived
+fn without_partial_escape(text: &str) -> &str {
+    // A backslash starts an escape only if it is not itself escaped
+    let trailing = text.len() - text.trim_end_matches('\\').len();
+    if trailing % 2 == 1 {
+        return &text[..text.len() - 1];
+    }
+    // `\u` followed by fewer than four hex digits
+    if let Some(pos) = text.rfind("\\u") {
+        let backslashes = text[..pos].len() - text[..pos].trim_end_matches('\\').len();
+        let digits = &text[pos + 2..];
+        if backslashes % 2 == 0 && digits.len() < 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return &text[..pos];
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_in_chunks(text: &str, size: usize) -> Vec<Option<Value>> {
+        let mut parser = PartialJson::new();
+        let chars: Vec<char> = text.chars().collect();
+        chars
+            .chunks(size)
+            .map(|chunk| {
+                parser.push(&chunk.iter().collect::<String>());
+                assert!(parser.is_valid(), "{:?} after {:?}", parser.error(), parser.text());
+                parser.value()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fields_appear_progressively() {
+        let mut parser = PartialJson::new();
+        parser.push(r#"{"file_path": "src/ma"#);
+        assert_eq!(parser.string_field("file_path").as_deref(), Some("src/ma"));
+
+        parser.push(r#"in.rs", "old_str"#);
+        // The dangling key is left out
+        assert_eq!(parser.value(), Some(json!({"file_path": "src/main.rs"})));
+
+        parser.push(r#"ing": "fn main() {\"#);
+        assert_eq!(parser.value(), Some(json!({"file_path": "src/main.rs", "old_string": "fn main() {"})));
+
+        parser.push(r#"n}", "count": 1"#);
+        assert_eq!(parser.value().unwrap()["count"], json!(1));
+        assert!(!parser.is_complete());
+
+        parser.push("2}");
+        assert!(parser.is_complete());
+        assert_eq!(parser.value().unwrap()["count"], json!(12));
+        assert_eq!(parser.value().unwrap()["old_string"], json!("fn main() {\n}"));
+    }
+
+    #[test]
+    fn test_every_prefix_is_tolerated() {
+        let text = r#"{"edits": [{"old": "aé", "new": null}, {"old": true}], "n": -1.5e3, "s": "ok"}"#;
+        let values = parse_in_chunks(text, 1);
+        assert_eq!(values.last().unwrap().as_ref().unwrap(), &serde_json::from_str::<Value>(text).unwrap());
+        // Once something is shown, a later prefix never shows nothing
+        let first = values.iter().position(Option::is_some).unwrap();
+        assert!(values[first..].iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn test_invalid_text_is_reported() {
+        let mut parser = PartialJson::new();
+        parser.push(r#"{"a": 1 "b""#);
+        assert!(!parser.is_valid());
+        assert_eq!(parser.value(), None);
+
+        let mut parser = PartialJson::new();
+        parser.push(r#"[tru "#);
+        assert!(parser.error().unwrap().contains("tru"));
+    }
+}
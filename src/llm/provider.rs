@@ -6,10 +6,15 @@ use futures::Stream;
 use crate::llm::{
     types::{ChatRequest, ProviderResponse, ProviderEvent, ProviderConfig},
     errors::{LlmError, LlmResult},
+    images::ImageLimits,
+    compat,
     openai::OpenAIProvider,
     anthropic::AnthropicProvider,
     ollama::OllamaProvider,
     azure::AzureProvider,
+    gemini::GeminiProvider,
+    openrouter::OpenRouterProvider,
+    compatible::OpenAICompatibleProvider,
 };
 
 /// Trait for LLM providers
@@ -32,6 +37,22 @@ pub trait LlmProvider: Send + Sync {
     
     /// Validate the configuration
     fn validate_config(&self) -> LlmResult<()>;
+    
+    /// Largest image the provider takes in a request
+    fn image_limits(&self) -> ImageLimits {
+        ImageLimits::default()
+    }
+    
+    /// Embedding model used when none is configured, if the provider has one
+    fn default_embedding_model(&self) -> Option<&str> {
+        None
+    }
+    
+    /// Embed texts with the given model, one vector per input
+    async fn embed(&self, model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+        let _ = (model, inputs);
+        Err(LlmError::ConfigError(format!("Provider '{}' does not support embeddings", self.name())))
+    }
 }
 
 /// Factory for creating LLM providers
@@ -57,6 +78,24 @@ impl ProviderFactory {
                 let provider = AzureProvider::from_config(config)?;
                 Ok(Box::new(provider))
             }
+            "gemini" => {
+                let provider = GeminiProvider::new(config)?;
+                Ok(Box::new(provider))
+            }
+            "openrouter" => {
+                let provider = OpenRouterProvider::new(config)?;
+                Ok(Box::new(provider))
+            }
+            "openai_compatible" => {
+                let provider = OpenAICompatibleProvider::new(config)?;
+                Ok(Box::new(provider))
+            }
+            "deepseek" | "qwen" => {
+                let profile = compat::profile(&config.provider_type)
+                    .expect("every OpenAI-compatible provider has a profile");
+                let provider = OpenAIProvider::with_profile(config, profile)?;
+                Ok(Box::new(provider))
+            }
             _ => Err(LlmError::ConfigError(format!(
                 "Unsupported provider type: {}",
                 config.provider_type
@@ -66,7 +105,7 @@ impl ProviderFactory {
     
     /// Get available provider types
     pub fn available_providers() -> Vec<&'static str> {
-        vec!["openai", "anthropic", "ollama", "azure"]
+        vec!["openai", "anthropic", "ollama", "azure", "gemini", "deepseek", "qwen", "openrouter", "openai_compatible"]
     }
 }
 
@@ -82,7 +121,8 @@ pub struct ProviderClientOptions {
 impl Default for ProviderClientOptions {
     fn default() -> Self {
         Self {
-            max_retries: 3,
+            // Requests are retried by the resilience layer around every provider
+            max_retries: 0,
             retry_delay_ms: 1000,
             timeout_seconds: 300,
             user_agent: "ClaudeContextTerminal/1.0".to_string(),
@@ -112,9 +152,9 @@ pub mod utils {
     /// Check if an error is retryable
     pub fn is_retryable_error(error: &LlmError) -> bool {
         match error {
-            LlmError::RateLimitError(_) => true,
+            LlmError::RateLimitError { .. } | LlmError::Unavailable { .. } => true,
             LlmError::HttpError(e) => {
-                e.status().map_or(false, |status| {
+                e.is_timeout() || e.is_connect() || e.status().map_or(false, |status| {
                     status.is_server_error() || status == 429 || status == 408
                 })
             }
@@ -123,6 +163,39 @@ pub mod utils {
         }
     }
     
+    /// Turn an unsuccessful HTTP response into the error it stands for
+    ///
+    /// Rate limits and server failures keep the response's `Retry-After`
+    /// so the request can be retried when the provider asked.
+    pub async fn error_from_response(response: reqwest::Response) -> LlmError {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let message = extract_error_message(response).await;
+        match status {
+            429 => LlmError::RateLimitError { message, retry_after },
+            401 | 403 => LlmError::AuthError(message),
+            400 if crate::llm::errors::is_context_limit_message(&message) => LlmError::ContextLimitError(message),
+            400 if message.contains("API key not valid") => LlmError::AuthError(message),
+            408 | 500..=599 => LlmError::Unavailable { status, message, retry_after },
+            _ => LlmError::ApiError(message),
+        }
+    }
+    
+    /// Read a `Retry-After` value, either seconds or an HTTP date
+    pub fn parse_retry_after(value: &str) -> Option<Duration> {
+        let value = value.trim();
+        if let Ok(seconds) = value.parse::<f64>() {
+            return (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds));
+        }
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+        Some(wait.to_std().unwrap_or_default())
+    }
+    
     /// Sanitize content for safe display
     pub fn sanitize_content(content: &str) -> String {
         content
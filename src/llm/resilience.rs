@@ -0,0 +1,346 @@
+//! Retrying failed provider requests and falling back to another model
+//!
+//! Every provider the app creates is wrapped in a [`ResilientProvider`].
+//! Requests that fail for passing reasons, like rate limits, overloaded
+//! servers or dropped connections, are sent again after an exponential
+//! backoff with jitter, or after the wait the provider asked for in its
+//! `Retry-After` header. When a fallback model is configured, requests go to
+//! it once the configured model has failed often enough.
+//!
+//! Retries and fallbacks are reported as [`AppEvent::Error`] with a
+//! `recovery`, to whoever awaits the request inside [`reporting_to`], so the
+//! interface can say why an answer is taking longer. A streamed answer is
+//! only retried until its stream opens; failures after that end it.
+
+use async_trait::async_trait;
+use futures::Stream;
+use rand::Rng;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::app::AppEvent;
+use crate::config::RetryConfig;
+use crate::llm::{
+    errors::{LlmError, LlmResult},
+    images::ImageLimits,
+    provider::{utils, LlmProvider},
+    types::{ChatRequest, ProviderEvent, ProviderResponse},
+};
+
+tokio::task_local! {
+    static RECOVERIES: mpsc::UnboundedSender<AppEvent>;
+}
+
+/// Run `future`, reporting the retries and fallbacks of the provider
+/// requests it makes to `events`
+pub async fn reporting_to<F: Future>(events: mpsc::UnboundedSender<AppEvent>, future: F) -> F::Output {
+    RECOVERIES.scope(events, future).await
+}
+
+fn report(error: &LlmError, recovery: String) {
+    warn!("{}; {}", error, recovery);
+    let _ = RECOVERIES.try_with(|events| {
+        events.send(AppEvent::Error {
+            error: error.to_string(),
+            recovery: Some(recovery),
+        })
+    });
+}
+
+/// When to try a failed request again
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+        }
+    }
+
+    /// Wait before the next attempt of a request that failed `failures`
+    /// times, the last time with `error`, or `None` when it should not be
+    /// tried again
+    ///
+    /// A provider asking to wait longer than the longest delay is taken at
+    /// its word and not retried.
+    pub fn delay(&self, failures: u32, error: &LlmError) -> Option<Duration> {
+        if failures >= self.max_attempts || !utils::is_retryable_error(error) {
+            return None;
+        }
+        if let Some(wait) = error.retry_after() {
+            return (wait <= self.max_delay).then_some(wait);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+            .min(self.max_delay);
+        // Jitter over the upper half keeps clients that failed together apart
+        let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+        Some(backoff.mul_f64(jitter))
+    }
+}
+
+/// A provider whose failed requests are retried, then sent to a fallback
+pub struct ResilientProvider {
+    primary: Arc<dyn LlmProvider>,
+    fallback: Option<Arc<dyn LlmProvider>>,
+    /// Failed attempts of the primary before requests go to the fallback
+    fallback_after: u32,
+    policy: RetryPolicy,
+}
+
+impl ResilientProvider {
+    pub fn new(primary: Arc<dyn LlmProvider>, policy: RetryPolicy) -> Self {
+        Self {
+            primary,
+            fallback: None,
+            fallback_after: u32::MAX,
+            policy,
+        }
+    }
+
+    /// Send requests to `fallback` once the primary failed `after_failures` times
+    pub fn with_fallback(mut self, fallback: Arc<dyn LlmProvider>, after_failures: u32) -> Self {
+        self.fallback = Some(fallback);
+        self.fallback_after = after_failures.max(1);
+        self
+    }
+
+    async fn attempt<T, F, Fut>(&self, request: ChatRequest, call: F) -> LlmResult<T>
+    where
+        F: Fn(Arc<dyn LlmProvider>, ChatRequest) -> Fut,
+        Fut: Future<Output = LlmResult<T>>,
+    {
+        let mut provider = self.primary.clone();
+        let mut on_fallback = false;
+        let mut failures = 0;
+        loop {
+            let error = match call(provider.clone(), request.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            failures += 1;
+            let delay = self.policy.delay(failures, &error);
+
+            if let Some(fallback) = self.fallback.as_ref().filter(|_| !on_fallback) {
+                if utils::is_retryable_error(&error) && (delay.is_none() || failures >= self.fallback_after) {
+                    report(&error, format!("falling back to {} ({})", fallback.model(), fallback.name()));
+                    provider = fallback.clone();
+                    on_fallback = true;
+                    failures = 0;
+                    continue;
+                }
+            }
+
+            let Some(delay) = delay else {
+                return Err(error);
+            };
+            report(
+                &error,
+                format!(
+                    "retrying {} in {}s (attempt {} of {})",
+                    provider.model(),
+                    delay.as_secs_f64().ceil(),
+                    failures + 1,
+                    self.policy.max_attempts
+                ),
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ResilientProvider {
+    async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+        self.attempt(request, |provider, request| async move { provider.chat_completion(request).await })
+            .await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: ChatRequest,
+    ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+        self.attempt(request, |provider, request| async move {
+            provider.chat_completion_stream(request).await
+        })
+        .await
+    }
+
+    fn name(&self) -> &str {
+        self.primary.name()
+    }
+
+    fn model(&self) -> &str {
+        self.primary.model()
+    }
+
+    fn validate_config(&self) -> LlmResult<()> {
+        self.primary.validate_config()?;
+        match &self.fallback {
+            Some(fallback) => fallback.validate_config(),
+            None => Ok(()),
+        }
+    }
+
+    fn image_limits(&self) -> ImageLimits {
+        self.primary.image_limits()
+    }
+
+    fn default_embedding_model(&self) -> Option<&str> {
+        self.primary.default_embedding_model()
+    }
+
+    async fn embed(&self, model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+        self.primary.embed(model, inputs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(&RetryConfig {
+            max_attempts,
+            base_delay_ms: 0,
+            max_delay_ms: 30_000,
+            fallback: None,
+        })
+    }
+
+    fn rate_limited(retry_after: Option<Duration>) -> LlmError {
+        LlmError::RateLimitError {
+            message: "slow down".to_string(),
+            retry_after,
+        }
+    }
+
+    /// Fails the first `failures` requests with a rate limit
+    struct Flaky {
+        model: &'static str,
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(model: &'static str, failures: u32) -> Arc<Self> {
+            Arc::new(Self {
+                model,
+                failures,
+                calls: AtomicU32::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for Flaky {
+        async fn chat_completion(&self, _request: ChatRequest) -> LlmResult<ProviderResponse> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(rate_limited(None));
+            }
+            Ok(ProviderResponse {
+                content: self.model.to_string(),
+                tool_calls: Vec::new(),
+                usage: Default::default(),
+                finish_reason: None,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: ChatRequest,
+        ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+            Err(LlmError::ApiError("no streaming".to_string()))
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            self.model
+        }
+
+        fn validate_config(&self) -> LlmResult<()> {
+            Ok(())
+        }
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            messages: Vec::new(),
+            tools: Vec::new(),
+            system_message: None,
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            stream: false,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_delays_grow_up_to_the_limit() {
+        let policy = RetryPolicy::new(&RetryConfig {
+            max_attempts: 10,
+            base_delay_ms: 1000,
+            max_delay_ms: 5000,
+            fallback: None,
+        });
+        let error = rate_limited(None);
+        let first = policy.delay(1, &error).unwrap();
+        assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+        let third = policy.delay(3, &error).unwrap();
+        assert!(third >= Duration::from_secs(2) && third <= Duration::from_secs(4));
+        assert!(policy.delay(9, &error).unwrap() <= Duration::from_secs(5));
+        assert_eq!(policy.delay(10, &error), None);
+    }
+
+    #[test]
+    fn test_retry_after_is_respected() {
+        let policy = policy(4);
+        assert_eq!(policy.delay(1, &rate_limited(Some(Duration::from_secs(7)))), Some(Duration::from_secs(7)));
+        assert_eq!(policy.delay(1, &rate_limited(Some(Duration::from_secs(60)))), None);
+        assert_eq!(policy.delay(1, &LlmError::AuthError("bad key".to_string())), None);
+        assert_eq!(utils::parse_retry_after(" 12 "), Some(Duration::from_secs(12)));
+        assert_eq!(utils::parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(utils::parse_retry_after("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn test_requests_retry_then_fall_back() {
+        let primary = Flaky::new("primary", 2);
+        let provider = ResilientProvider::new(primary.clone(), policy(4));
+        let response = provider.chat_completion(request()).await.unwrap();
+        assert_eq!(response.content, "primary");
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 3);
+
+        let (events, mut recoveries) = mpsc::unbounded_channel();
+        let provider = ResilientProvider::new(Flaky::new("primary", u32::MAX), policy(4))
+            .with_fallback(Flaky::new("fallback", 0), 2);
+        let response = reporting_to(events, provider.chat_completion(request())).await.unwrap();
+        assert_eq!(response.content, "fallback");
+
+        let mut notes = Vec::new();
+        while let Ok(AppEvent::Error { recovery: Some(recovery), .. }) = recoveries.try_recv() {
+            notes.push(recovery);
+        }
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].starts_with("retrying primary"));
+        assert_eq!(notes[1], "falling back to fallback (flaky)");
+    }
+}
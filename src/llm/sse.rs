@@ -0,0 +1,55 @@
+//! Line buffering for server-sent event streams
+//!
+//! Streaming responses arrive in chunks that can end anywhere, including in
+//! the middle of a line or a multi-byte character. [`SseBuffer`] holds on to
+//! the unterminated tail and hands back the `data:` payload of each complete
+//! line; decoding those payloads is left to the provider.
+
+/// Collects stream bytes until whole SSE lines are available
+#[derive(Debug, Default)]
+pub struct SseBuffer {
+    /// Bytes of a line not yet terminated
+    pending: Vec<u8>,
+}
+
+impl SseBuffer {
+    /// Feed a chunk, returning the `data:` payloads of the lines it completes
+    ///
+    /// Payloads are trimmed; other fields (`event:`, `id:`, comments) and
+    /// blank lines are skipped.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+
+        while let Some(newline) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                payloads.push(data.trim().to_string());
+            }
+        }
+
+        payloads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_split_across_chunks() {
+        let mut buffer = SseBuffer::default();
+        assert!(buffer.push(b"event: ping\ndata: {\"a\"").is_empty());
+        assert_eq!(buffer.push(b":1}\n\ndata: [DONE]\n"), vec!["{\"a\":1}", "[DONE]"]);
+    }
+
+    #[test]
+    fn test_multibyte_character_split_across_chunks() {
+        let mut buffer = SseBuffer::default();
+        let line = "data: \"é\"\n".as_bytes();
+        let split = line.iter().position(|&b| b == 0xc3).unwrap() + 1;
+        assert!(buffer.push(&line[..split]).is_empty());
+        assert_eq!(buffer.push(&line[split..]), vec!["\"é\""]);
+    }
+}
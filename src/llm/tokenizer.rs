@@ -0,0 +1,157 @@
+//! Counting tokens before a request is sent
+//!
+//! OpenAI's models are counted exactly with their byte pair encodings. Other
+//! vendors don't publish their tokenizers, so their counts are estimates: the
+//! text is counted with `cl100k_base`, scaled by how much more finely the
+//! vendor's tokenizer tends to split the same text. Counts show how large the
+//! next request is while a prompt is typed, and let a conversation be
+//! compacted before it outgrows the model's context rather than after the
+//! provider rejected it.
+
+use tiktoken_rs::{
+    tokenizer::{get_tokenizer, Tokenizer as Encoding},
+    CoreBPE,
+};
+
+use super::{models, ContentBlock, Message, Tool};
+
+/// Tokens a message costs besides its content, for its role and delimiters
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Tokens counted for an image, about what one scaled to the providers'
+/// limits costs
+const IMAGE_TOKENS: usize = 1_500;
+
+/// Counts tokens the way a model does, exactly or close to it
+#[derive(Clone, Copy)]
+pub struct Tokenizer {
+    bpe: &'static CoreBPE,
+    /// Tokens of the model's own tokenizer per token of `bpe`
+    scale: f64,
+    exact: bool,
+}
+
+impl Tokenizer {
+    /// Tokenizer for a model, exact for OpenAI's and estimated for others
+    pub fn for_model(provider: &str, model: &str) -> Self {
+        // OpenRouter names models "<vendor>/<model>"
+        let name = model.rsplit('/').next().unwrap_or(model);
+        if let Some(encoding) = get_tokenizer(name) {
+            return Self {
+                bpe: encoding_bpe(encoding),
+                scale: 1.0,
+                exact: true,
+            };
+        }
+        let vendor = models::lookup(model).map_or(provider, |known| known.provider);
+        let scale = match vendor {
+            // Claude's tokenizer splits text into noticeably more tokens
+            "anthropic" => 1.15,
+            _ => 1.0,
+        };
+        Self {
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+            scale,
+            exact: false,
+        }
+    }
+
+    /// Whether counts are the model's own rather than estimates
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Tokens of a text
+    pub fn count(&self, text: &str) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+        let tokens = self.bpe.encode_ordinary(text).len();
+        (tokens as f64 * self.scale).ceil() as usize
+    }
+
+    /// Tokens of a message, with what its role and delimiters take
+    pub fn count_message(&self, message: &Message) -> usize {
+        let content: usize = message
+            .content
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text { text } => self.count(text),
+                ContentBlock::Image { .. } => IMAGE_TOKENS,
+                ContentBlock::ToolUse { name, input, .. } => self.count(name) + self.count(&input.to_string()),
+                ContentBlock::ToolResult { content, .. } => self.count(content),
+            })
+            .sum();
+        MESSAGE_OVERHEAD + content
+    }
+
+    /// Tokens of a request with these messages, system message and tools
+    pub fn count_request(&self, messages: &[Message], system_message: Option<&str>, tools: &[Tool]) -> usize {
+        let messages: usize = messages.iter().map(|message| self.count_message(message)).sum();
+        let system = system_message.map_or(0, |system| MESSAGE_OVERHEAD + self.count(system));
+        let tools = if tools.is_empty() {
+            0
+        } else {
+            self.count(&serde_json::to_string(tools).unwrap_or_default())
+        };
+        messages + system + tools
+    }
+}
+
+fn encoding_bpe(encoding: Encoding) -> &'static CoreBPE {
+    match encoding {
+        Encoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Encoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        Encoding::P50kBase => tiktoken_rs::p50k_base_singleton(),
+        Encoding::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        Encoding::R50kBase | Encoding::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    }
+}
+
+/// Token count in K or M, as context windows are usually quoted
+pub fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        t if t >= 1_000_000 => format!("{:.1}M", t as f64 / 1_000_000.0),
+        t if t >= 1_000 => format!("{}K", t / 1_000),
+        t => t.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_models_are_counted_exactly() {
+        let tokenizer = Tokenizer::for_model("openai", "gpt-4o");
+        assert!(tokenizer.is_exact());
+        assert_eq!(tokenizer.count("hello world"), 2);
+        assert_eq!(tokenizer.count(""), 0);
+        assert!(Tokenizer::for_model("openrouter", "openai/gpt-4o-mini").is_exact());
+    }
+
+    #[test]
+    fn test_other_models_are_estimated() {
+        let claude = Tokenizer::for_model("anthropic", "claude-3-5-sonnet-latest");
+        let llama = Tokenizer::for_model("ollama", "llama3.1");
+        assert!(!claude.is_exact() && !llama.is_exact());
+        let text = "fn main() { println!(\"Hello, world!\"); }";
+        assert!(claude.count(text) > llama.count(text));
+    }
+
+    #[test]
+    fn test_requests_count_every_part() {
+        let tokenizer = Tokenizer::for_model("openai", "gpt-4o");
+        let message = Message::new_user("hello world".to_string());
+        assert_eq!(tokenizer.count_message(&message), MESSAGE_OVERHEAD + 2);
+        let tool = Tool {
+            name: "bash".to_string(),
+            description: "Run a command".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+        };
+        let bare = tokenizer.count_request(&[message.clone()], None, &[]);
+        assert!(tokenizer.count_request(&[message], Some("Be brief."), &[tool]) > bare + MESSAGE_OVERHEAD);
+        assert_eq!(format_tokens(1_048_576), "1.0M");
+        assert_eq!(format_tokens(12_345), "12K");
+    }
+}
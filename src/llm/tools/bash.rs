@@ -1,24 +1,102 @@
 //! Bash command execution tool
+//!
+//! Output is read line by line while the command runs and reported through
+//! the request's progress reporter, so the chat can show it as it arrives.
+//! A [`CommandCancel`] stops the running commands with everything they
+//! started; the model is told the command was cancelled, with the output
+//! printed until then. A command running past its timeout is stopped the
+//! same way.
+//!
+//! Commands come as a shell command line, whose `{{name}}` placeholders are
+//! filled with quoted `values`, or as an `argv` list run without a shell; see
+//! [`super::shell`].
 
-use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use super::{
+    jobs::kill_group, session_env::SessionEnv, shell, BaseTool, ProgressReporter, ToolRequest, ToolResponse, ToolResult,
+};
 use async_trait::async_trait;
 use serde_json::json;
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
-use tokio::time::timeout;
+use tokio::sync::{mpsc, Notify};
+
+/// How long output still in the pipes is read once a command has ended
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Cancels the bash commands running at the moment
+#[derive(Debug, Clone, Default)]
+pub struct CommandCancel {
+    notify: Arc<Notify>,
+}
+
+impl CommandCancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop every command running now
+    pub fn cancel(&self) {
+        self.notify.notify_waiters();
+    }
+}
+
+/// What a command printed and how it ended
+struct CommandOutput {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
+    cancelled: bool,
+    timed_out: bool,
+}
 
 /// Tool for executing bash commands
-pub struct BashTool;
+pub struct BashTool {
+    env: SessionEnv,
+    cancel: CommandCancel,
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self {
+            env: SessionEnv::new(),
+            cancel: CommandCancel::new(),
+        }
     }
 
-    /// Execute a command with timeout and safety checks
-    async fn execute_command(&self, command: &str, working_dir: Option<&str>, timeout_ms: u64) -> ToolResult<(String, String, i32)> {
-        let mut cmd = if cfg!(target_os = "windows") {
+    /// Run commands with the variables of the session environment
+    pub fn with_session_env(mut self, env: SessionEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Stop running commands when `cancel` is triggered
+    pub fn with_cancel(mut self, cancel: CommandCancel) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Execute a command with timeout and safety checks, streaming its output
+    /// to `progress`
+    ///
+    /// With an `argv` the program runs directly and `command` is only its
+    /// display form.
+    async fn execute_command(
+        &self,
+        command: &str,
+        argv: Option<&[String]>,
+        working_dir: Option<&str>,
+        env_allowlist: Option<&[String]>,
+        timeout_ms: u64,
+        progress: Option<&ProgressReporter>,
+    ) -> ToolResult<CommandOutput> {
+        let mut cmd = if let Some([program, args @ ..]) = argv {
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            cmd
+        } else if cfg!(target_os = "windows") {
             let mut cmd = Command::new("cmd");
             cmd.args(["/C", command]);
             cmd
@@ -32,30 +110,98 @@ impl BashTool {
         if let Some(dir) = working_dir {
             cmd.current_dir(dir);
         }
+        
+        // Pass on only the allowed environment variables
+        if let Some(allowed) = env_allowlist {
+            cmd.env_clear();
+            cmd.envs(std::env::vars_os().filter(|(name, _)| allowed.iter().any(|allowed| name == allowed.as_str())));
+        }
+        cmd.envs(self.env.vars());
 
+        // A group of its own, so that cancelling reaches what it started
+        #[cfg(unix)]
+        cmd.process_group(0);
         cmd.stdout(Stdio::piped())
            .stderr(Stdio::piped())
-           .stdin(Stdio::null());
+           .stdin(Stdio::null())
+           .kill_on_drop(true);
 
-        let child = cmd.spawn()
+        // Listen for cancellation before the command can print anything
+        let cancelled = self.cancel.notify.notified();
+        tokio::pin!(cancelled);
+        let mut child = cmd.spawn()
             .map_err(|e| anyhow::anyhow!("Failed to spawn command: {}", e))?;
 
-        let timeout_duration = Duration::from_millis(timeout_ms);
-        
-        match timeout(timeout_duration, child.wait_with_output()).await {
-            Ok(Ok(output)) => {
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                let exit_code = output.status.code().unwrap_or(-1);
-                Ok((stdout, stderr, exit_code))
+        let (line_tx, mut lines) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            spawn_reader(stdout, false, line_tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_reader(stderr, true, line_tx);
+        }
+
+        let mut output = CommandOutput {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: -1,
+            cancelled: false,
+            timed_out: false,
+        };
+        let mut push = |(is_stderr, line): (bool, String)| {
+            if let Some(progress) = progress {
+                progress.output(line.clone());
             }
-            Ok(Err(e)) => Err(anyhow::anyhow!("Command execution failed: {}", e)),
-            Err(_) => Err(anyhow::anyhow!("Command timed out after {}ms", timeout_ms)),
+            let text = if is_stderr { &mut output.stderr } else { &mut output.stdout };
+            text.push_str(&line);
+            text.push('\n');
+        };
+
+        let expired = tokio::time::sleep(Duration::from_millis(timeout_ms));
+        tokio::pin!(expired);
+        let mut timed_out = false;
+        let mut streaming = true;
+        let status = loop {
+            tokio::select! {
+                line = lines.recv(), if streaming => match line {
+                    Some(line) => push(line),
+                    None => streaming = false,
+                },
+                status = child.wait() => {
+                    let status = status.map_err(|e| anyhow::anyhow!("Command execution failed: {}", e))?;
+                    break Some(status.code().unwrap_or(-1));
+                }
+                _ = &mut cancelled => {
+                    kill_group(child.id());
+                    let _ = child.kill().await;
+                    break None;
+                }
+                _ = &mut expired => {
+                    kill_group(child.id());
+                    let _ = child.kill().await;
+                    timed_out = true;
+                    break None;
+                }
+            }
+        };
+
+        // Lines still in the pipes, unless something the command started
+        // keeps them open
+        let _ = tokio::time::timeout(DRAIN_TIMEOUT, async {
+            while let Some(line) = lines.recv().await {
+                push(line);
+            }
+        })
+        .await;
+        match status {
+            Some(exit_code) => output.exit_code = exit_code,
+            None if timed_out => output.timed_out = true,
+            None => output.cancelled = true,
         }
+        Ok(output)
     }
 
     /// Check if command is potentially dangerous
-    fn is_dangerous_command(&self, command: &str) -> bool {
+    pub(crate) fn is_dangerous_command(&self, command: &str) -> bool {
         let dangerous_commands = [
             "rm -rf /", "rm -rf /*", ":(){ :|:& };:", // Fork bomb and destructive commands
             "dd if=/dev/zero", "mkfs", "fdisk", // Disk operations
@@ -72,13 +218,30 @@ impl BashTool {
 #[async_trait]
 impl BaseTool for BashTool {
     async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
-        let command = request.parameters.get("command")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: command"))?;
+        let argv = shell::argv_of(&request.parameters)?;
+        let command = match &argv {
+            Some(argv) => shell::join(argv),
+            None => {
+                let command = request.parameters.get("command")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing required parameter: command or argv"))?;
+                match request.parameters.get("values").and_then(|v| v.as_object()) {
+                    Some(values) => shell::fill_placeholders(command, values)?,
+                    None => command.to_string(),
+                }
+            }
+        };
+        let command = command.as_str();
 
         let timeout_ms = request.parameters.get("timeout")
             .and_then(|v| v.as_u64())
             .unwrap_or(120000); // Default 2 minutes
+        
+        // Never outlive the turn's deadline
+        let timeout_ms = match &request.deadline {
+            Some(deadline) => deadline.clamp(Duration::from_millis(timeout_ms)).as_millis() as u64,
+            None => timeout_ms,
+        };
 
         let description = request.parameters.get("description")
             .and_then(|v| v.as_str())
@@ -103,9 +266,17 @@ impl BaseTool for BashTool {
             });
         }
 
+        // Run in the requested directory, which the tool manager keeps inside
+        // the workspace, or at the workspace root
+        let working_dir = request.parameters.get("cwd")
+            .and_then(|v| v.as_str())
+            .or(request.working_directory.as_deref());
+        let env_allowlist = request.permissions.env_allowlist.as_deref();
+        
         // Execute command
-        match self.execute_command(command, request.working_directory.as_deref(), timeout_ms).await {
-            Ok((stdout, stderr, exit_code)) => {
+        let progress = request.progress.as_ref();
+        match self.execute_command(command, argv.as_deref(), working_dir, env_allowlist, timeout_ms, progress).await {
+            Ok(CommandOutput { stdout, stderr, exit_code, cancelled, timed_out }) => {
                 let mut output = String::new();
                 
                 if !stdout.is_empty() {
@@ -125,22 +296,30 @@ impl BaseTool for BashTool {
 
                 let metadata = json!({
                     "command": command,
+                    "shell": argv.is_none(),
                     "description": description,
                     "exit_code": exit_code,
                     "timeout_ms": timeout_ms,
                     "stdout_length": stdout.len(),
                     "stderr_length": stderr.len(),
+                    "cancelled": cancelled,
+                    "timed_out": timed_out,
                 });
 
+                let error = if cancelled {
+                    Some("Command cancelled by the user".to_string())
+                } else if timed_out {
+                    Some(format!("Command timed out after {}ms", timeout_ms))
+                } else if exit_code != 0 {
+                    Some(format!("Command exited with code {}", exit_code))
+                } else {
+                    None
+                };
                 Ok(ToolResponse {
                     content: output,
-                    success: exit_code == 0,
+                    success: error.is_none(),
                     metadata: Some(metadata),
-                    error: if exit_code != 0 {
-                        Some(format!("Command exited with code {}", exit_code))
-                    } else {
-                        None
-                    },
+                    error,
                 })
             }
             Err(e) => Ok(ToolResponse {
@@ -169,7 +348,17 @@ impl BaseTool for BashTool {
             "properties": {
                 "command": {
                     "type": "string",
-                    "description": "The command to execute"
+                    "description": "The shell command to execute. Put strings you did not write yourself, such as file names or text from the user, in `values` and refer to them as {{name}} instead of quoting them by hand"
+                },
+                "values": {
+                    "type": "object",
+                    "description": "Values for the {{name}} placeholders of `command`; each is quoted for the shell before it is put in",
+                    "additionalProperties": { "type": ["string", "number", "boolean"] }
+                },
+                "argv": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Program and arguments to run directly, without a shell, instead of `command`. Pipes, redirects and variables are not available"
                 },
                 "description": {
                     "type": "string",
@@ -178,9 +367,13 @@ impl BaseTool for BashTool {
                 "timeout": {
                     "type": "integer",
                     "description": "Optional timeout in milliseconds (max 600000, default 120000)"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to run the command in, inside the workspace (defaults to the workspace root)"
                 }
             },
-            "required": ["command"]
+            "required": []
         })
     }
 
@@ -195,6 +388,11 @@ impl BaseTool for BashTool {
         }
         
         // Additional bash-specific validation
+        match (request.parameters.contains_key("command"), shell::argv_of(&request.parameters)?) {
+            (true, Some(_)) => return Err(anyhow::anyhow!("Give either command or argv, not both")),
+            (false, None) => return Err(anyhow::anyhow!("Missing required parameter: command or argv")),
+            _ => {}
+        }
         let timeout = request.parameters.get("timeout")
             .and_then(|v| v.as_u64())
             .unwrap_or(120000);
@@ -207,6 +405,22 @@ impl BaseTool for BashTool {
     }
 }
 
+/// Send the lines of a command's stdout or stderr as they are printed
+fn spawn_reader(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    is_stderr: bool,
+    lines: mpsc::UnboundedSender<(bool, String)>,
+) {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            if lines.send((is_stderr, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +441,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -247,6 +463,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -278,9 +496,132 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
         assert!(response.success);
     }
-}
\ No newline at end of file
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_commands_run_in_cwd_with_allowed_environment() {
+        let env = SessionEnv::new();
+        env.set("DEPLOY_TOKEN", "secret").unwrap();
+        let tool = BashTool::new().with_session_env(env);
+        let dir = tempfile::tempdir().unwrap();
+        let mut params = HashMap::new();
+        params.insert("command".to_string(), json!("pwd; echo \"home=${HOME:-unset} token=$DEPLOY_TOKEN\""));
+        params.insert("cwd".to_string(), json!(dir.path().display().to_string()));
+        
+        let permissions = ToolPermissions {
+            allow_execute: true,
+            env_allowlist: Some(vec!["PATH".to_string()]),
+            ..ToolPermissions::default()
+        };
+        let request = ToolRequest {
+            tool_name: "bash".to_string(),
+            parameters: params,
+            working_directory: None,
+            permissions,
+            progress: None,
+            deadline: None,
+        };
+        
+        let response = tool.execute(request).await.unwrap();
+        assert!(response.success);
+        let dir_name = dir.path().file_name().unwrap().to_str().unwrap();
+        assert!(response.content.contains(dir_name));
+        assert!(response.content.contains("home=unset token=secret"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_streams_until_the_command_is_cancelled() {
+        let cancel = CommandCancel::new();
+        let tool = BashTool::new().with_cancel(cancel.clone());
+        let (reporter, mut progress) = ProgressReporter::channel("call-1", "bash");
+        let mut params = HashMap::new();
+        params.insert("command".to_string(), json!("echo started; sleep 30; echo finished"));
+        let request = ToolRequest {
+            tool_name: "bash".to_string(),
+            parameters: params,
+            working_directory: None,
+            permissions: ToolPermissions {
+                allow_execute: true,
+                ..ToolPermissions::default()
+            },
+            progress: Some(reporter),
+            deadline: None,
+        };
+
+        let running = tokio::spawn(async move { tool.execute(request).await });
+        let first = progress.recv().await.unwrap();
+        assert_eq!(first.output.as_deref(), Some("started"));
+        cancel.cancel();
+
+        let response = tokio::time::timeout(Duration::from_secs(5), running).await.unwrap().unwrap().unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("Command cancelled by the user"));
+        assert_eq!(response.content, "started\n");
+        assert_eq!(response.metadata.unwrap()["cancelled"], true);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timed_out_commands_keep_their_output() {
+        let tool = BashTool::new();
+        let mut params = HashMap::new();
+        params.insert("command".to_string(), json!("echo started; sleep 30; echo finished"));
+        params.insert("timeout".to_string(), json!(300));
+        let request = ToolRequest {
+            tool_name: "bash".to_string(),
+            parameters: params,
+            working_directory: None,
+            permissions: ToolPermissions {
+                allow_execute: true,
+                ..ToolPermissions::default()
+            },
+            progress: None,
+            deadline: None,
+        };
+
+        let response = tokio::time::timeout(Duration::from_secs(5), tool.execute(request)).await.unwrap().unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("Command timed out after 300ms"));
+        assert_eq!(response.content, "started\n");
+        assert_eq!(response.metadata.unwrap()["timed_out"], true);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_values_and_argv_are_not_interpreted_by_the_shell() {
+        let tool = BashTool::new();
+        let request_for = |parameters: serde_json::Value| {
+            let request = ToolRequest {
+                tool_name: "bash".to_string(),
+                parameters: serde_json::from_value(parameters).unwrap(),
+                working_directory: None,
+                permissions: ToolPermissions {
+                    allow_execute: true,
+                    ..ToolPermissions::default()
+                },
+                progress: None,
+                deadline: None,
+            };
+            tool.validate_request(&request).map(|_| request)
+        };
+
+        let request = request_for(json!({ "command": "printf '%s\\n' {{text}}", "values": { "text": "a; echo injected" } })).unwrap();
+        assert_eq!(tool.execute(request).await.unwrap().content, "a; echo injected\n");
+
+        let request = request_for(json!({ "argv": ["echo", "$HOME && echo injected"] })).unwrap();
+        let response = tool.execute(request).await.unwrap();
+        assert_eq!(response.content, "$HOME && echo injected\n");
+        assert_eq!(response.metadata.unwrap()["shell"], false);
+
+        assert!(request_for(json!({ "command": "ls", "argv": ["ls"] })).is_err());
+        assert!(request_for(json!({ "description": "Nothing to run" })).is_err());
+    }
+}
@@ -4,19 +4,27 @@ use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
 use async_trait::async_trait;
 use serde_json::json;
 use std::collections::HashMap;
-use tokio::time::{timeout, Duration};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::time::Duration;
 
-use crate::lsp::{LspClient, LspManager};
+use crate::config::paths::display_path;
+use crate::lsp::{Diagnostic, DiagnosticSeverity, LspManager};
 
+/// How long to wait for a language server to check a file
+pub const DIAGNOSTICS_WAIT: Duration = Duration::from_secs(5);
+
+/// Most diagnostics listed per section
+const MAX_LISTED: usize = 10;
 
 /// LSP diagnostics tool
 pub struct DiagnosticsTool {
-    lsp_manager: Option<LspManager>,
+    lsp_manager: Option<Arc<LspManager>>,
 }
 
 impl DiagnosticsTool {
     /// Create a new diagnostics tool
-    pub fn new(lsp_manager: Option<LspManager>) -> Self {
+    pub fn new(lsp_manager: Option<Arc<LspManager>>) -> Self {
         Self { lsp_manager }
     }
 }
@@ -25,7 +33,8 @@ impl DiagnosticsTool {
 impl BaseTool for DiagnosticsTool {
     async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
         let file_path = request.parameters.get("file_path")
-            .and_then(|v| v.as_str());
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
 
         let lsp_manager = match &self.lsp_manager {
             Some(manager) => manager,
@@ -39,27 +48,27 @@ impl BaseTool for DiagnosticsTool {
             }
         };
 
-        // If a specific file path is provided, ensure it's opened in LSP
-        if let Some(file_path) = file_path {
-            if let Err(e) = self.ensure_file_opened(lsp_manager, file_path).await {
-                return Ok(ToolResponse {
-                    content: String::new(),
-                    success: false,
-                    metadata: None,
-                    error: Some(format!("Failed to open file in LSP: {}", e)),
-                });
-            }
+        let mut all_diagnostics = lsp_manager.all_diagnostics().await;
 
-            // Wait for diagnostics to be updated
-            if let Err(_) = timeout(Duration::from_secs(5), self.wait_for_diagnostics(lsp_manager, file_path)).await {
-                // Continue even if timeout - we'll show what we have
+        // Check the file afresh, showing what we have if the server is slow
+        if let Some(file_path) = &file_path {
+            match lsp_manager.fresh_diagnostics(file_path, DIAGNOSTICS_WAIT).await {
+                Ok(diagnostics) => {
+                    all_diagnostics.insert(file_path.clone(), diagnostics);
+                }
+                Err(e) => {
+                    return Ok(ToolResponse {
+                        content: String::new(),
+                        success: false,
+                        metadata: None,
+                        error: Some(format!("Failed to open file in LSP: {}", e)),
+                    });
+                }
             }
         }
 
-        let diagnostics_output = self.get_diagnostics_output(lsp_manager, file_path).await;
-        
         Ok(ToolResponse {
-            content: diagnostics_output,
+            content: diagnostics_output(&all_diagnostics, file_path.as_deref()),
             success: true,
             metadata: None,
             error: None,
@@ -107,242 +116,152 @@ TIPS:
     }
 }
 
-impl DiagnosticsTool {
-    /// Ensure a file is opened in all relevant LSP clients
-    async fn ensure_file_opened(&self, _lsp_manager: &LspManager, _file_path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // TODO: Implement when LSP manager has get_clients_for_file method
-        Ok(())
-    }
-
-    /// Wait for diagnostics to be updated (simplified implementation)
-    async fn wait_for_diagnostics(&self, _lsp_manager: &LspManager, _file_path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // In a full implementation, this would wait for LSP diagnostic notifications
-        // For now, we'll just wait a short period
-        tokio::time::sleep(Duration::from_millis(500)).await;
-        Ok(())
-    }
-
-    /// Get formatted diagnostics output
-    async fn get_diagnostics_output(&self, lsp_manager: &LspManager, target_file: Option<&str>) -> String {
-        let mut file_diagnostics = Vec::new();
-        let mut project_diagnostics = Vec::new();
-
-        // Get diagnostics from all LSP clients
-        let all_diagnostics: HashMap<String, Vec<LspDiagnostic>> = HashMap::new(); // Placeholder - LSP manager integration needed
-
-        for (file_path, diagnostics) in all_diagnostics {
-            let is_target_file = target_file.map_or(false, |target| file_path == target);
-
-            for diagnostic in diagnostics {
-                let formatted = self.format_diagnostic(&file_path, &diagnostic);
-                
-                if is_target_file {
-                    file_diagnostics.push(formatted);
-                } else {
-                    project_diagnostics.push(formatted);
-                }
+/// Format the diagnostics of a file and of the rest of the project
+fn diagnostics_output(all_diagnostics: &HashMap<PathBuf, Vec<Diagnostic>>, target_file: Option<&Path>) -> String {
+    let mut file_diagnostics = Vec::new();
+    let mut project_diagnostics = Vec::new();
+
+    for (file_path, diagnostics) in all_diagnostics {
+        let is_target_file = target_file == Some(file_path.as_path());
+        for diagnostic in diagnostics {
+            let formatted = format_diagnostic(&display_path(file_path), diagnostic);
+            if is_target_file {
+                file_diagnostics.push(formatted);
+            } else {
+                project_diagnostics.push(formatted);
             }
         }
-
-        // Sort diagnostics by severity (errors first)
-        file_diagnostics.sort_by(|a, b| {
-            let a_is_error = a.starts_with("Error");
-            let b_is_error = b.starts_with("Error");
-            match (a_is_error, b_is_error) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.cmp(b),
-            }
-        });
-
-        project_diagnostics.sort_by(|a, b| {
-            let a_is_error = a.starts_with("Error");
-            let b_is_error = b.starts_with("Error");
-            match (a_is_error, b_is_error) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.cmp(b),
-            }
-        });
-
-        self.format_output(&file_diagnostics, &project_diagnostics)
     }
 
-    /// Format a single diagnostic
-    fn format_diagnostic(&self, file_path: &str, diagnostic: &LspDiagnostic) -> String {
-        let severity = match diagnostic.severity {
-            DiagnosticSeverity::Error => "Error",
-            DiagnosticSeverity::Warning => "Warn",
-            DiagnosticSeverity::Information => "Info",
-            DiagnosticSeverity::Hint => "Hint",
-        };
+    // Errors first
+    let by_severity = |a: &String, b: &String| b.starts_with("Error").cmp(&a.starts_with("Error")).then_with(|| a.cmp(b));
+    file_diagnostics.sort_by(by_severity);
+    project_diagnostics.sort_by(by_severity);
 
-        let location = format!(
-            "{}:{}:{}",
-            file_path,
-            diagnostic.range.start.line + 1,
-            diagnostic.range.start.character + 1
-        );
-
-        let source_info = diagnostic.source.as_deref().unwrap_or("unknown");
-
-        let code_info = diagnostic.code.as_ref()
-            .map(|code| format!("[{}]", code))
-            .unwrap_or_default();
-
-        let tags_info = if !diagnostic.tags.is_empty() {
-            let tags: Vec<&str> = diagnostic.tags.iter()
-                .filter_map(|tag| match tag {
-                    DiagnosticTag::Unnecessary => Some("unnecessary"),
-                    DiagnosticTag::Deprecated => Some("deprecated"),
-                })
-                .collect();
-            if !tags.is_empty() {
-                format!(" ({})", tags.join(", "))
-            } else {
-                String::new()
-            }
-        } else {
-            String::new()
-        };
+    format_output(&file_diagnostics, &project_diagnostics)
+}
 
-        format!(
-            "{}: {} [{}]{}{} {}",
-            severity,
-            location,
-            source_info,
-            code_info,
-            tags_info,
-            diagnostic.message
-        )
+/// Errors and warnings a modification introduced, to append to the edit tool's result
+///
+/// Returns nothing when the file is clean, so clean edits stay quiet.
+pub fn edit_diagnostics_note(file_path: &Path, diagnostics: &[Diagnostic]) -> Option<String> {
+    let mut problems: Vec<String> = diagnostics
+        .iter()
+        .filter(|diagnostic| matches!(diagnostic.severity, Some(DiagnosticSeverity::Error | DiagnosticSeverity::Warning)))
+        .map(|diagnostic| format_diagnostic(&display_path(file_path), diagnostic))
+        .collect();
+    if problems.is_empty() {
+        return None;
+    }
+    problems.sort_by(|a, b| b.starts_with("Error").cmp(&a.starts_with("Error")));
+    let mut note = format!(
+        "\n\nThe language server reports {} errors and {} warnings in {} after this change:\n<file_diagnostics>\n",
+        count_severity(&problems, "Error"),
+        count_severity(&problems, "Warn"),
+        display_path(file_path)
+    );
+    note.push_str(&problems.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join("\n"));
+    if problems.len() > MAX_LISTED {
+        note.push_str(&format!("\n... and {} more diagnostics", problems.len() - MAX_LISTED));
     }
+    note.push_str("\n</file_diagnostics>");
+    Some(note)
+}
 
-    /// Format the final output
-    fn format_output(&self, file_diagnostics: &[String], project_diagnostics: &[String]) -> String {
-        let mut output = String::new();
-
-        if !file_diagnostics.is_empty() {
-            output.push_str("\n<file_diagnostics>\n");
-            
-            let _to_show = if file_diagnostics.len() > 10 {
-                output.push_str(&file_diagnostics[..10].join("\n"));
-                output.push_str(&format!("\n... and {} more diagnostics", file_diagnostics.len() - 10));
-                10
-            } else {
-                output.push_str(&file_diagnostics.join("\n"));
-                file_diagnostics.len()
-            };
-            
-            output.push_str("\n</file_diagnostics>\n");
-        }
+/// Format a single diagnostic
+fn format_diagnostic(file_path: &str, diagnostic: &Diagnostic) -> String {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::Error) => "Error",
+        Some(DiagnosticSeverity::Warning) => "Warn",
+        Some(DiagnosticSeverity::Information) => "Info",
+        Some(DiagnosticSeverity::Hint) | None => "Hint",
+    };
+
+    let location = format!(
+        "{}:{}:{}",
+        file_path,
+        diagnostic.line + 1,
+        diagnostic.character + 1
+    );
+
+    let source_info = diagnostic.source.as_deref().unwrap_or("unknown");
+
+    let code_info = diagnostic.code.as_ref()
+        .map(|code| format!("[{}]", code))
+        .unwrap_or_default();
+
+    format!(
+        "{}: {} [{}]{} {}",
+        severity,
+        location,
+        source_info,
+        code_info,
+        diagnostic.message
+    )
+}
 
-        if !project_diagnostics.is_empty() {
-            output.push_str("\n<project_diagnostics>\n");
-            
-            if project_diagnostics.len() > 10 {
-                output.push_str(&project_diagnostics[..10].join("\n"));
-                output.push_str(&format!("\n... and {} more diagnostics", project_diagnostics.len() - 10));
-            } else {
-                output.push_str(&project_diagnostics.join("\n"));
-            }
-            
-            output.push_str("\n</project_diagnostics>\n");
-        }
+/// Format the final output
+fn format_output(file_diagnostics: &[String], project_diagnostics: &[String]) -> String {
+    let mut output = String::new();
 
-        if !file_diagnostics.is_empty() || !project_diagnostics.is_empty() {
-            let file_errors = self.count_severity(file_diagnostics, "Error");
-            let file_warnings = self.count_severity(file_diagnostics, "Warn");
-            let project_errors = self.count_severity(project_diagnostics, "Error");
-            let project_warnings = self.count_severity(project_diagnostics, "Warn");
-
-            output.push_str("\n<diagnostic_summary>\n");
-            output.push_str(&format!("Current file: {} errors, {} warnings\n", file_errors, file_warnings));
-            output.push_str(&format!("Project: {} errors, {} warnings\n", project_errors, project_warnings));
-            output.push_str("</diagnostic_summary>\n");
-        } else {
-            output.push_str("No diagnostics found.\n");
+    for (tag, diagnostics) in [("file_diagnostics", file_diagnostics), ("project_diagnostics", project_diagnostics)] {
+        if diagnostics.is_empty() {
+            continue;
         }
-
-        output
+        output.push_str(&format!("\n<{}>\n", tag));
+        output.push_str(&diagnostics.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join("\n"));
+        if diagnostics.len() > MAX_LISTED {
+            output.push_str(&format!("\n... and {} more diagnostics", diagnostics.len() - MAX_LISTED));
+        }
+        output.push_str(&format!("\n</{}>\n", tag));
     }
 
-    /// Count diagnostics of a specific severity
-    fn count_severity(&self, diagnostics: &[String], severity: &str) -> usize {
-        diagnostics.iter()
-            .filter(|diag| diag.starts_with(severity))
-            .count()
+    if !file_diagnostics.is_empty() || !project_diagnostics.is_empty() {
+        let file_errors = count_severity(file_diagnostics, "Error");
+        let file_warnings = count_severity(file_diagnostics, "Warn");
+        let project_errors = count_severity(project_diagnostics, "Error");
+        let project_warnings = count_severity(project_diagnostics, "Warn");
+
+        output.push_str("\n<diagnostic_summary>\n");
+        output.push_str(&format!("Current file: {} errors, {} warnings\n", file_errors, file_warnings));
+        output.push_str(&format!("Project: {} errors, {} warnings\n", project_errors, project_warnings));
+        output.push_str("</diagnostic_summary>\n");
+    } else {
+        output.push_str("No diagnostics found.\n");
     }
-}
-
-/// Simplified LSP diagnostic types (these would normally come from the LSP module)
-#[derive(Debug, Clone)]
-pub struct LspDiagnostic {
-    pub range: LspRange,
-    pub severity: DiagnosticSeverity,
-    pub message: String,
-    pub source: Option<String>,
-    pub code: Option<String>,
-    pub tags: Vec<DiagnosticTag>,
-}
-
-#[derive(Debug, Clone)]
-pub struct LspRange {
-    pub start: LspPosition,
-    pub end: LspPosition,
-}
-
-#[derive(Debug, Clone)]
-pub struct LspPosition {
-    pub line: u32,
-    pub character: u32,
-}
 
-#[derive(Debug, Clone)]
-pub enum DiagnosticSeverity {
-    Error = 1,
-    Warning = 2,
-    Information = 3,
-    Hint = 4,
+    output
 }
 
-#[derive(Debug, Clone)]
-pub enum DiagnosticTag {
-    Unnecessary = 1,
-    Deprecated = 2,
+/// Count diagnostics of a specific severity
+fn count_severity(diagnostics: &[String], severity: &str) -> usize {
+    diagnostics.iter()
+        .filter(|diag| diag.starts_with(severity))
+        .count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[tokio::test]
-    async fn test_diagnostics_tool_info() {
-        let tool = DiagnosticsTool::new(None);
-        let info = tool.info();
-        
-        assert_eq!(info.name, "diagnostics");
-        assert!(info.description.contains("diagnostics"));
-        assert!(info.description.contains("errors"));
-    }
-
-    #[tokio::test]
-    async fn test_format_diagnostic() {
-        let tool = DiagnosticsTool::new(None);
-        
-        let diagnostic = LspDiagnostic {
-            range: LspRange {
-                start: LspPosition { line: 10, character: 5 },
-                end: LspPosition { line: 10, character: 15 },
-            },
-            severity: DiagnosticSeverity::Error,
-            message: "Undefined variable".to_string(),
+    use crate::llm::tools::ToolPermissions;
+
+    fn diagnostic(severity: DiagnosticSeverity, line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            message: message.to_string(),
+            severity: Some(severity),
+            line,
+            character: 5,
+            end_line: None,
+            end_character: None,
             source: Some("rust-analyzer".to_string()),
             code: Some("E0425".to_string()),
-            tags: vec![],
-        };
+        }
+    }
+
+    #[test]
+    fn test_format_diagnostic() {
+        let formatted = format_diagnostic("src/main.rs", &diagnostic(DiagnosticSeverity::Error, 10, "Undefined variable"));
 
-        let formatted = tool.format_diagnostic("src/main.rs", &diagnostic);
-        
         assert!(formatted.contains("Error"));
         assert!(formatted.contains("src/main.rs:11:6"));
         assert!(formatted.contains("rust-analyzer"));
@@ -352,8 +271,6 @@ mod tests {
 
     #[test]
     fn test_count_severity() {
-        let tool = DiagnosticsTool::new(None);
-        
         let diagnostics = vec![
             "Error: test:1:1 [rust] message".to_string(),
             "Warn: test:2:1 [rust] message".to_string(),
@@ -361,20 +278,40 @@ mod tests {
             "Info: test:4:1 [rust] message".to_string(),
         ];
 
-        assert_eq!(tool.count_severity(&diagnostics, "Error"), 2);
-        assert_eq!(tool.count_severity(&diagnostics, "Warn"), 1);
-        assert_eq!(tool.count_severity(&diagnostics, "Info"), 1);
-        assert_eq!(tool.count_severity(&diagnostics, "Hint"), 0);
+        assert_eq!(count_severity(&diagnostics, "Error"), 2);
+        assert_eq!(count_severity(&diagnostics, "Warn"), 1);
+        assert_eq!(count_severity(&diagnostics, "Info"), 1);
+        assert_eq!(count_severity(&diagnostics, "Hint"), 0);
+    }
+
+    #[test]
+    fn test_edit_note_lists_errors_before_warnings_only() {
+        let path = Path::new("src/lib.rs");
+        assert_eq!(edit_diagnostics_note(path, &[diagnostic(DiagnosticSeverity::Hint, 1, "consider this")]), None);
+
+        let note = edit_diagnostics_note(path, &[
+            diagnostic(DiagnosticSeverity::Warning, 2, "unused variable"),
+            diagnostic(DiagnosticSeverity::Information, 3, "note"),
+            diagnostic(DiagnosticSeverity::Error, 7, "mismatched types"),
+        ]).unwrap();
+        assert!(note.contains("1 errors and 1 warnings in src/lib.rs"));
+        assert!(note.find("mismatched types").unwrap() < note.find("unused variable").unwrap());
+        assert!(!note.contains("note"));
     }
 
     #[tokio::test]
     async fn test_no_lsp_manager() {
         let tool = DiagnosticsTool::new(None);
         let request = ToolRequest {
-            parameters: serde_json::json!({}),
+            tool_name: "diagnostics".to_string(),
+            parameters: HashMap::new(),
+            working_directory: None,
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
 
         let response = tool.execute(request).await.unwrap();
         assert!(response.content.contains("No LSP clients available"));
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 //! Download tool implementation for downloading files from URLs
 
-use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use super::{BaseTool, ProgressReporter, ToolRequest, ToolResponse, ToolResult};
 use async_trait::async_trait;
 use serde_json::json;
 use std::{
@@ -75,8 +75,11 @@ impl BaseTool for DownloadTool {
         }
 
         // Perform the download with timeout
-        let download_timeout = Duration::from_secs(timeout_secs);
-        match timeout(download_timeout, self.download_file(url, file_path)).await {
+        let download_timeout = match &request.deadline {
+            Some(deadline) => deadline.clamp(Duration::from_secs(timeout_secs)),
+            None => Duration::from_secs(timeout_secs),
+        };
+        match timeout(download_timeout, self.download_file(url, file_path, request.progress.as_ref())).await {
             Ok(Ok(response)) => Ok(response),
             Ok(Err(e)) => Ok(ToolResponse {
                 content: String::new(),
@@ -153,11 +156,16 @@ TIPS:
 
 impl DownloadTool {
     /// Download a file from URL to local path
-    async fn download_file(&self, url: &str, file_path: &str) -> Result<ToolResponse, Box<dyn std::error::Error + Send + Sync>> {
+    async fn download_file(
+        &self,
+        url: &str,
+        file_path: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> Result<ToolResponse, Box<dyn std::error::Error + Send + Sync>> {
         let path = Path::new(file_path);
         
         // Make the request
-        let response = self.client.get(url).send().await?;
+        let mut response = self.client.get(url).send().await?;
 
         if !response.status().is_success() {
             return Ok(ToolResponse {
@@ -194,25 +202,39 @@ impl DownloadTool {
             fs::create_dir_all(parent).await?;
         }
 
-        // Read all bytes at once for simplicity
-        let bytes = response.bytes().await?;
+        // Stream the body to disk, reporting progress as chunks arrive
+        let total_size = response.content_length();
+        let mut file = fs::File::create(path).await?;
+        let mut bytes_written: u64 = 0;
         
-        // Check size limit
-        if bytes.len() as u64 > MAX_SIZE {
-            return Ok(ToolResponse {
-                content: String::new(),
-                success: false,
-                metadata: None,
-                error: Some(format!("File too large: {} bytes (max {} bytes)", bytes.len(), MAX_SIZE)),
-            });
+        while let Some(chunk) = response.chunk().await? {
+            bytes_written += chunk.len() as u64;
+            
+            // Check size limit
+            if bytes_written > MAX_SIZE {
+                drop(file);
+                let _ = fs::remove_file(path).await;
+                return Ok(ToolResponse {
+                    content: String::new(),
+                    success: false,
+                    metadata: None,
+                    error: Some(format!("File too large: more than {} bytes", MAX_SIZE)),
+                });
+            }
+            
+            file.write_all(&chunk).await?;
+            
+            if let Some(progress) = progress {
+                match total_size {
+                    Some(total) if total > 0 => progress.fraction(
+                        bytes_written as f64 / total as f64,
+                        Some(format!("{} / {} bytes", bytes_written, total)),
+                    ),
+                    _ => progress.message(format!("{} bytes", bytes_written)),
+                }
+            }
         }
-
-        // Create the output file and write content
-        let mut file = fs::File::create(path).await?;
-        file.write_all(&bytes).await?;
         file.flush().await?;
-        
-        let bytes_written = bytes.len() as u64;
 
         let response_msg = if content_type != "unknown" {
             format!(
@@ -279,6 +301,8 @@ mod tests {
                 allow_write: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -304,6 +328,8 @@ mod tests {
                 yolo_mode: false,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let result = tool.execute(request).await;
@@ -329,6 +355,8 @@ mod tests {
                 yolo_mode: false,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let result = tool.execute(request).await;
@@ -1,6 +1,8 @@
 //! File editing tool for making precise changes to files
 
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::config::paths::display_path;
+use crate::llm::changes::unified_diff;
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::Path;
@@ -118,12 +120,13 @@ impl BaseTool for EditTool {
                             "replacements_made": replacement_count,
                             "original_size": current_content.len(),
                             "new_size": new_content.len(),
+                            "diff": unified_diff(&display_path(path), &current_content, &new_content),
                         });
 
                         Ok(ToolResponse {
                             content: format!(
                                 "Successfully edited file '{}'. Made {} replacement(s).",
-                                file_path, replacement_count
+                                display_path(path), replacement_count
                             ),
                             success: true,
                             metadata: Some(metadata),
@@ -219,6 +222,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -252,6 +257,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -299,6 +306,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions,
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -0,0 +1,479 @@
+//! Tools backed by an attached editor
+//!
+//! When an editor is attached over the control socket, these tools read
+//! buffer context from it and route edits through it, so changes land in the
+//! open buffers (with undo history) instead of being written to disk behind
+//! the editor's back. Without an attached editor the edit, multi-edit and
+//! write tools fall back to the regular file-based tools.
+
+use super::{BaseTool, EditTool, MultiEditTool, ToolRequest, ToolResponse, ToolResult, WriteTool};
+use crate::control::editor::EditorEdit;
+use crate::control::{EditorBridge, SharedEditor};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::Path;
+
+/// Tool for reading the current selection and diagnostics from the editor
+pub struct EditorContextTool {
+    editor: SharedEditor,
+}
+
+impl EditorContextTool {
+    pub fn new(editor: SharedEditor) -> Self {
+        Self { editor }
+    }
+}
+
+#[async_trait]
+impl BaseTool for EditorContextTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let kind = request.parameters.get("kind")
+            .and_then(|v| v.as_str())
+            .unwrap_or("selection");
+
+        let Some(editor) = self.editor.read().await.clone() else {
+            return Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some("No editor is attached".to_string()),
+            });
+        };
+
+        let result = match kind {
+            "selection" => editor.selection().await.and_then(|selection| {
+                let content = format!(
+                    "{}:{}-{}\n```{}\n{}\n```",
+                    selection.file_path,
+                    selection.start_line,
+                    selection.end_line,
+                    selection.filetype.clone().unwrap_or_default(),
+                    selection.text
+                );
+                Ok((content, serde_json::to_value(&selection)?))
+            }),
+            "diagnostics" => {
+                let file_path = request.parameters.get("file_path")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                editor.diagnostics(file_path).await.and_then(|diagnostics| {
+                    Ok((serde_json::to_string_pretty(&diagnostics)?, diagnostics))
+                })
+            }
+            other => Err(anyhow::anyhow!("Unknown context kind: {}", other)),
+        };
+
+        Ok(match result {
+            Ok((content, metadata)) => ToolResponse {
+                content,
+                success: true,
+                metadata: Some(json!({ "editor": editor.name(), "kind": kind, "data": metadata })),
+                error: None,
+            },
+            Err(e) => ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(e.to_string()),
+            },
+        })
+    }
+
+    fn name(&self) -> &str {
+        "editor_context"
+    }
+
+    fn description(&self) -> &str {
+        "Read context from the user's editor: the current selection (or cursor line) or the diagnostics reported for open buffers."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {
+                    "type": "string",
+                    "enum": ["selection", "diagnostics"],
+                    "description": "What to read from the editor",
+                    "default": "selection"
+                },
+                "file_path": {
+                    "type": "string",
+                    "description": "Limit diagnostics to this file (defaults to all open buffers)"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+}
+
+/// The attached editor, if its connection is still open
+async fn connected_editor(editor: &SharedEditor) -> Option<EditorBridge> {
+    editor.read().await.clone().filter(|editor| editor.is_connected())
+}
+
+/// Run the path and permission checks the file-based tools apply before
+/// anything is sent to the editor
+///
+/// Returns a failed response when write permission is missing, matching
+/// [`EditTool`].
+fn check_write_access(request: &ToolRequest, file_path: &str) -> ToolResult<Option<ToolResponse>> {
+    if !Path::new(file_path).is_absolute() {
+        return Err(anyhow::anyhow!("File path must be absolute"));
+    }
+
+    for restricted in &request.permissions.restricted_paths {
+        if file_path.starts_with(restricted) && !request.permissions.yolo_mode {
+            return Err(anyhow::anyhow!("Access to path '{}' is restricted", file_path));
+        }
+    }
+
+    if !request.permissions.allow_write && !request.permissions.yolo_mode {
+        return Ok(Some(ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: None,
+            error: Some("Write permission required for file editing".to_string()),
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Turn the editor's answer into a tool response
+fn editor_response(
+    editor: &EditorBridge,
+    file_path: &str,
+    result: anyhow::Result<serde_json::Value>,
+    content: String,
+    mut metadata: serde_json::Value,
+) -> ToolResponse {
+    match result {
+        Ok(result) => {
+            metadata["file_path"] = json!(file_path);
+            metadata["editor"] = json!(editor.name());
+            metadata["result"] = result;
+            ToolResponse {
+                content,
+                success: true,
+                metadata: Some(metadata),
+                error: None,
+            }
+        }
+        Err(e) => ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: None,
+            error: Some(format!("Editor rejected edit to '{}': {}", file_path, e)),
+        },
+    }
+}
+
+/// Reject the replacements [`EditTool`] would also reject
+fn check_replacement(old_string: &str, new_string: &str) -> ToolResult<()> {
+    if old_string == new_string {
+        return Err(anyhow::anyhow!("old_string and new_string cannot be the same"));
+    }
+    if old_string.is_empty() {
+        return Err(anyhow::anyhow!("old_string cannot be empty"));
+    }
+    Ok(())
+}
+
+/// Edit tool that applies replacements through the attached editor
+pub struct EditorEditTool {
+    editor: SharedEditor,
+    fallback: EditTool,
+}
+
+impl EditorEditTool {
+    pub fn new(editor: SharedEditor) -> Self {
+        Self {
+            editor,
+            fallback: EditTool::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl BaseTool for EditorEditTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let Some(editor) = connected_editor(&self.editor).await else {
+            return self.fallback.execute(request).await;
+        };
+
+        let file_path = request.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: file_path"))?;
+
+        let old_string = request.parameters.get("old_string")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: old_string"))?;
+
+        let new_string = request.parameters.get("new_string")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: new_string"))?;
+
+        let replace_all = request.parameters.get("replace_all")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if let Some(denied) = check_write_access(&request, file_path)? {
+            return Ok(denied);
+        }
+        check_replacement(old_string, new_string)?;
+
+        let result = editor
+            .apply_edit(file_path.to_string(), old_string.to_string(), new_string.to_string(), replace_all)
+            .await;
+        Ok(editor_response(
+            &editor,
+            file_path,
+            result,
+            format!("Applied edit to {} in {}", file_path, editor.name()),
+            json!({ "replace_all": replace_all }),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "edit"
+    }
+
+    fn description(&self) -> &str {
+        self.fallback.description()
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        self.fallback.parameters()
+    }
+}
+
+/// Multi-edit tool that applies all replacements through the attached editor
+pub struct EditorMultiEditTool {
+    editor: SharedEditor,
+    fallback: MultiEditTool,
+}
+
+impl EditorMultiEditTool {
+    pub fn new(editor: SharedEditor) -> Self {
+        Self {
+            editor,
+            fallback: MultiEditTool::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl BaseTool for EditorMultiEditTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let Some(editor) = connected_editor(&self.editor).await else {
+            return self.fallback.execute(request).await;
+        };
+
+        let file_path = request.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: file_path"))?;
+
+        let edits: Vec<EditorEdit> = request.parameters.get("edits")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: edits"))?;
+
+        if let Some(denied) = check_write_access(&request, file_path)? {
+            return Ok(denied);
+        }
+        if edits.is_empty() {
+            return Err(anyhow::anyhow!("edits cannot be empty"));
+        }
+        for edit in &edits {
+            check_replacement(&edit.old_string, &edit.new_string)?;
+        }
+
+        let count = edits.len();
+        let result = editor.apply_edits(file_path.to_string(), edits).await;
+        Ok(editor_response(
+            &editor,
+            file_path,
+            result,
+            format!("Applied {} edit(s) to {} in {}", count, file_path, editor.name()),
+            json!({ "edits": count }),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "multiedit"
+    }
+
+    fn description(&self) -> &str {
+        self.fallback.description()
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        self.fallback.parameters()
+    }
+}
+
+/// Write tool that replaces buffer contents through the attached editor
+pub struct EditorWriteTool {
+    editor: SharedEditor,
+    fallback: WriteTool,
+}
+
+impl EditorWriteTool {
+    pub fn new(editor: SharedEditor) -> Self {
+        Self {
+            editor,
+            fallback: WriteTool::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl BaseTool for EditorWriteTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let Some(editor) = connected_editor(&self.editor).await else {
+            return self.fallback.execute(request).await;
+        };
+
+        let file_path = request.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: file_path"))?;
+
+        let content = request.parameters.get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: content"))?;
+
+        if let Some(denied) = check_write_access(&request, file_path)? {
+            return Ok(denied);
+        }
+
+        let result = editor.write_file(file_path.to_string(), content.to_string()).await;
+        Ok(editor_response(
+            &editor,
+            file_path,
+            result,
+            format!("Wrote {} in {}", file_path, editor.name()),
+            json!({ "file_size": content.len() }),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "write"
+    }
+
+    fn description(&self) -> &str {
+        self.fallback.description()
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        self.fallback.parameters()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::editor::{EditorBridge, EditorRequest, EditorResponse};
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::sync::{mpsc, RwLock};
+
+    fn request(parameters: serde_json::Value) -> ToolRequest {
+        ToolRequest {
+            tool_name: "edit".to_string(),
+            parameters: serde_json::from_value::<HashMap<_, _>>(parameters).unwrap(),
+            working_directory: None,
+            permissions: ToolPermissions { allow_write: true, ..Default::default() },
+            progress: None,
+            deadline: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_edit_is_sent_to_editor() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let bridge = EditorBridge::new("nvim", tx);
+        let editor: SharedEditor = Arc::new(RwLock::new(Some(bridge.clone())));
+
+        tokio::spawn(async move {
+            let line = rx.recv().await.unwrap();
+            let request: EditorRequest = serde_json::from_str(line.trim()).unwrap();
+            assert!(line.contains("apply_edit"));
+            bridge
+                .resolve(EditorResponse { id: request.id, result: None, error: None })
+                .await;
+        });
+
+        let tool = EditorEditTool::new(editor);
+        let response = tool
+            .execute(request(json!({
+                "file_path": "/tmp/not-written.rs",
+                "old_string": "a",
+                "new_string": "b",
+            })))
+            .await
+            .unwrap();
+
+        assert!(response.success);
+        assert!(!std::path::Path::new("/tmp/not-written.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_is_sent_to_editor() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let bridge = EditorBridge::new("nvim", tx);
+        let editor: SharedEditor = Arc::new(RwLock::new(Some(bridge.clone())));
+
+        tokio::spawn(async move {
+            let line = rx.recv().await.unwrap();
+            let request: EditorRequest = serde_json::from_str(line.trim()).unwrap();
+            assert!(line.contains("write_file"));
+            bridge
+                .resolve(EditorResponse { id: request.id, result: None, error: None })
+                .await;
+        });
+
+        let tool = EditorWriteTool::new(editor);
+        let response = tool
+            .execute(request(json!({
+                "file_path": "/tmp/not-written-either.rs",
+                "content": "fn main() {}",
+            })))
+            .await
+            .unwrap();
+
+        assert!(response.success);
+        assert!(!std::path::Path::new("/tmp/not-written-either.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_restricted_path_is_not_sent_to_editor() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let editor: SharedEditor = Arc::new(RwLock::new(Some(EditorBridge::new("nvim", tx))));
+
+        let mut restricted = request(json!({
+            "file_path": "/etc/passwd",
+            "edits": [{ "old_string": "a", "new_string": "b" }],
+        }));
+        restricted.permissions.restricted_paths = vec!["/etc".to_string()];
+        assert!(EditorMultiEditTool::new(editor.clone()).execute(restricted).await.is_err());
+
+        let relative = request(json!({ "file_path": "src/main.rs", "content": "" }));
+        assert!(EditorWriteTool::new(editor).execute(relative).await.is_err());
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_context_without_editor() {
+        let tool = EditorContextTool::new(SharedEditor::default());
+        let response = tool.execute(request(json!({ "kind": "selection" }))).await.unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("No editor is attached"));
+    }
+}
@@ -1,27 +1,71 @@
 //! Fetch tool implementation for downloading web content
-
+//!
+//! HTML pages are reduced to their main content and returned as Markdown or
+//! text. Responses are limited in size and to text content types, redirects
+//! are followed as [`RedirectPolicy`] says, and URLs a site's robots.txt
+//! disallows are refused. Pages are cached on disk for a while, see
+//! [`FetchCache`].
+
+use super::fetch_cache::{self, FetchCache, FetchedPage};
+use super::robots::Robots;
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::config::{FetchConfig, RedirectPolicy};
+use crate::utils::html;
+use anyhow::Result;
 use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use reqwest::{Client, Url};
+use scraper::{Html, Selector};
 use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::timeout;
-use reqwest::Client;
+use tracing::debug;
+
+/// Name robots.txt rules are looked up for
+const ROBOTS_AGENT: &str = "goofy";
 
 /// Fetch tool for downloading content from URLs
 pub struct FetchTool {
     client: Client,
+    config: FetchConfig,
+    cache: Option<FetchCache>,
+    /// robots.txt rules by origin
+    robots: Mutex<HashMap<String, Arc<Robots>>>,
 }
 
 impl FetchTool {
     /// Create a new fetch tool
     pub fn new() -> Self {
+        Self::with_config(FetchConfig::default())
+    }
+
+    /// Create a fetch tool with the given limits and policies
+    pub fn with_config(config: FetchConfig) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .user_agent("goofy/1.0")
+            // Redirects are followed by hand, to apply the policy to each
+            .redirect(reqwest::redirect::Policy::none())
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client }
+        Self {
+            client,
+            config,
+            cache: None,
+            robots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache pages in the data directory for the configured time
+    pub fn with_cache_dir(mut self, data_dir: &Path) -> Self {
+        self.cache = (self.config.cache_ttl_secs > 0).then(|| {
+            FetchCache::new(FetchCache::default_dir(data_dir), Duration::from_secs(self.config.cache_ttl_secs))
+        });
+        self
     }
 }
 
@@ -42,6 +86,10 @@ impl BaseTool for FetchTool {
             .unwrap_or(30)
             .min(120); // Max 2 minutes
 
+        let refresh = request.parameters.get("refresh")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
         if !url.starts_with("http://") && !url.starts_with("https://") {
             return Ok(ToolResponse {
                 content: String::new(),
@@ -66,14 +114,17 @@ impl BaseTool for FetchTool {
         }
 
         // Perform the fetch with timeout
-        let fetch_timeout = Duration::from_secs(timeout_secs);
-        match timeout(fetch_timeout, self.fetch_content(url, &format)).await {
+        let fetch_timeout = match &request.deadline {
+            Some(deadline) => deadline.clamp(Duration::from_secs(timeout_secs)),
+            None => Duration::from_secs(timeout_secs),
+        };
+        match timeout(fetch_timeout, self.fetch_content(url, &format, refresh)).await {
             Ok(Ok(response)) => Ok(response),
             Ok(Err(e)) => Ok(ToolResponse {
                 content: String::new(),
                 success: false,
                 metadata: None,
-                error: Some(e.to_string()),
+                error: Some(format!("{:#}", e)),
             }),
             Err(_) => Ok(ToolResponse {
                 content: String::new(),
@@ -88,6 +139,10 @@ impl BaseTool for FetchTool {
         "fetch"
     }
 
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         r#"Fetches content from a URL and returns it in the specified format.
 
@@ -103,19 +158,22 @@ HOW TO USE:
 
 FEATURES:
 - Supports three output formats: text, markdown, and html
-- Automatically handles HTTP redirects
+- Web pages are reduced to their main content, without scripts, menus or footers
+- Follows redirects on the same site; redirects to another host are reported
+  so you can fetch the new URL
+- Recently fetched pages come from a cache; set refresh to download again
 - Sets reasonable timeouts to prevent hanging
-- Validates input parameters before making requests
 
 LIMITATIONS:
 - Maximum response size is 5MB
+- Only reads text, HTML, JSON and XML; use the download tool for other files
 - Only supports HTTP and HTTPS protocols
+- Pages the site's robots.txt disallows are refused
 - Cannot handle authentication or cookies
-- Some websites may block automated requests
 
 TIPS:
+- Use markdown format for web pages, keeping headings, links and code blocks
 - Use text format for plain text content or simple API responses
-- Use markdown format for content that should be rendered with formatting
 - Use html format when you need the raw HTML structure
 - Set appropriate timeouts for potentially slow websites"#
     }
@@ -136,6 +194,10 @@ TIPS:
                 "timeout": {
                     "type": "number",
                     "description": "Optional timeout in seconds (max 120)"
+                },
+                "refresh": {
+                    "type": "boolean",
+                    "description": "Download the page again instead of using the cached copy"
                 }
             },
             "required": ["url", "format"]
@@ -144,106 +206,78 @@ TIPS:
 }
 
 impl FetchTool {
-    /// Fetch content from URL and format it
-    async fn fetch_content(&self, url: &str, format: &str) -> Result<ToolResponse, Box<dyn std::error::Error + Send + Sync>> {
-        // Make the request
-        let response = self.client.get(url).send().await?;
-
-        if !response.status().is_success() {
-            return Ok(ToolResponse {
-                content: String::new(),
-                success: false,
-                metadata: None,
-                error: Some(format!("Request failed with status code: {}", response.status())),
-            });
-        }
-
-        // Check content length
-        const MAX_SIZE: u64 = 5 * 1024 * 1024; // 5MB
-        if let Some(content_length) = response.content_length() {
-            if content_length > MAX_SIZE {
-                return Ok(ToolResponse {
-                    content: String::new(),
-                    success: false,
-                    metadata: None,
-                    error: Some(format!("Response too large: {} bytes (max {} bytes)", content_length, MAX_SIZE)),
-                });
-            }
-        }
-
-        // Get content type before taking bytes
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("unknown")
-            .to_string();
-
-        // Read the response body
-        let bytes = response.bytes().await?;
-        
-        // Check size limit
-        if bytes.len() as u64 > MAX_SIZE {
-            return Ok(ToolResponse {
-                content: String::new(),
-                success: false,
-                metadata: None,
-                error: Some(format!("Response too large: {} bytes (max {} bytes)", bytes.len(), MAX_SIZE)),
-            });
-        }
-
-        let content = String::from_utf8(bytes.to_vec()).map_err(|_| {
-            "Response content is not valid UTF-8"
-        })?;
-
-        let original_content_len = content.len();
-
-        // Format the content based on the requested format
-        let formatted_content = match format {
-            "text" => {
-                if content_type.contains("text/html") {
-                    self.extract_text_from_html(&content)?
-                } else {
-                    content.clone()
+    /// The page at `url`, from the cache when it was fetched recently
+    async fn fetch_content(&self, url: &str, format: &str, refresh: bool) -> Result<ToolResponse> {
+        let cached = if refresh { None } else { self.cache.as_ref().and_then(|cache| cache.get(url)) };
+        let from_cache = cached.is_some();
+        let page = match cached {
+            Some(page) => page,
+            None => match self.download(url).await? {
+                Ok(page) => {
+                    if let Some(cache) = &self.cache {
+                        if let Err(e) = cache.put(&page) {
+                            debug!("Could not cache {}: {:#}", url, e);
+                        }
+                    }
+                    page
                 }
-            }
-            "markdown" => {
-                if content_type.contains("text/html") {
-                    let markdown = self.convert_html_to_markdown(&content)?;
-                    format!("```\n{}\n```", markdown)
-                } else {
-                    format!("```\n{}\n```", content)
+                Err(refusal) => {
+                    return Ok(ToolResponse {
+                        content: String::new(),
+                        success: false,
+                        metadata: Some(json!({ "url": url })),
+                        error: Some(refusal),
+                    })
                 }
-            }
-            "html" => {
-                if content_type.contains("text/html") {
-                    // Extract body content from HTML
-                    self.extract_body_from_html(&content)?
-                } else {
-                    content.clone()
+            },
+        };
+
+        let is_html = content_type_essence(&page.content_type) == "text/html"
+            || content_type_essence(&page.content_type) == "application/xhtml+xml";
+        let base = Url::parse(&page.final_url).ok();
+        let mut title = None;
+        let formatted_content = match (format, is_html) {
+            ("markdown", true) => {
+                let readable = html::readable(&page.body, base.as_ref());
+                let markdown = readable.markdown();
+                title = readable.title.clone();
+                match &readable.title {
+                    Some(title) if !markdown.starts_with("# ") => format!("# {}\n\n{}", title, markdown),
+                    _ => markdown,
                 }
             }
-            _ => content.clone(),
+            ("text", true) => {
+                let readable = html::readable(&page.body, base.as_ref());
+                title = readable.title.clone();
+                readable.text()
+            }
+            ("html", true) => self.extract_body_from_html(&page.body),
+            _ => page.body.clone(),
         };
 
         // Truncate if too large for display
         const MAX_DISPLAY_SIZE: usize = 100_000; // 100KB for display
-        let final_content = if formatted_content.len() > MAX_DISPLAY_SIZE {
-            format!(
-                "{}\n\n[Content truncated to {} bytes]",
-                &formatted_content[..MAX_DISPLAY_SIZE],
-                MAX_DISPLAY_SIZE
-            )
+        let truncated = formatted_content.len() > MAX_DISPLAY_SIZE;
+        let final_content = if truncated {
+            let mut end = MAX_DISPLAY_SIZE;
+            while !formatted_content.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}\n\n[Content truncated to {} bytes]", &formatted_content[..end], end)
         } else {
             formatted_content
         };
 
         let metadata = json!({
             "url": url,
+            "final_url": page.final_url,
             "format": format,
-            "content_type": content_type,
-            "content_length": bytes.len(),
-            "truncated": final_content.len() != original_content_len,
+            "content_type": page.content_type,
+            "content_length": page.body.len(),
+            "title": title,
+            "cached": from_cache,
+            "fetched_at": page.fetched_at,
+            "truncated": truncated,
         });
 
         Ok(ToolResponse {
@@ -254,45 +288,253 @@ impl FetchTool {
         })
     }
 
-    /// Extract text content from HTML
-    fn extract_text_from_html(&self, html: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        // Simple HTML text extraction using html2md
-        let text = html2md::parse_html(html);
-        Ok(text.chars().filter(|c| !c.is_control() || c.is_whitespace()).collect::<String>()
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" "))
+    /// Download a page, following redirects the policy allows; `Err` holds
+    /// why the page was not downloaded
+    async fn download(&self, url: &str) -> Result<Result<FetchedPage, String>> {
+        let mut current = Url::parse(url)?;
+        for _ in 0..=self.config.max_redirects {
+            if let Some(refusal) = self.robots_refusal(&current).await {
+                return Ok(Err(refusal));
+            }
+            let mut response = self.client.get(current.clone()).send().await?;
+            let status = response.status();
+
+            if status.is_redirection() {
+                let Some(location) = response.headers().get(LOCATION).and_then(|v| v.to_str().ok()) else {
+                    return Ok(Err(format!("{} redirects without saying where to", current)));
+                };
+                let next = current.join(location)?;
+                if !matches!(next.scheme(), "http" | "https") {
+                    return Ok(Err(format!("{} redirects to {}, which is not a web page", current, next)));
+                }
+                let same_host = next.host_str() == current.host_str();
+                match self.config.redirects {
+                    RedirectPolicy::Never => {
+                        return Ok(Err(format!("{} redirects to {}; fetch that URL instead", current, next)));
+                    }
+                    RedirectPolicy::SameHost if !same_host => {
+                        return Ok(Err(format!(
+                            "{} redirects to {} on another host; fetch that URL to follow the redirect",
+                            current, next
+                        )));
+                    }
+                    _ => {}
+                }
+                current = next;
+                continue;
+            }
+            if !status.is_success() {
+                return Ok(Err(format!("Request failed with status code: {}", status)));
+            }
+
+            let content_type = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            if !content_type.is_empty() && !self.reads_content_type(&content_type) {
+                return Ok(Err(format!(
+                    "{} is {}, which the fetch tool doesn't read; use the download tool for files",
+                    current,
+                    content_type_essence(&content_type)
+                )));
+            }
+            let max_bytes = self.config.max_bytes;
+            if let Some(content_length) = response.content_length().filter(|length| *length > max_bytes) {
+                return Ok(Err(format!("Response too large: {} bytes (max {} bytes)", content_length, max_bytes)));
+            }
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                body.extend_from_slice(&chunk);
+                if body.len() as u64 > max_bytes {
+                    return Ok(Err(format!("Response too large: over {} bytes", max_bytes)));
+                }
+            }
+
+            return Ok(Ok(FetchedPage {
+                url: url.to_string(),
+                final_url: current.to_string(),
+                content_type,
+                body: String::from_utf8_lossy(&body).into_owned(),
+                fetched_at: fetch_cache::now(),
+            }));
+        }
+        Ok(Err(format!("Stopped after {} redirects", self.config.max_redirects)))
+    }
+
+    /// Why robots.txt keeps `url` from being fetched, if it does
+    async fn robots_refusal(&self, url: &Url) -> Option<String> {
+        if !self.config.respect_robots {
+            return None;
+        }
+        let origin = url.origin().ascii_serialization();
+        let cached = self.robots.lock().unwrap_or_else(|e| e.into_inner()).get(&origin).cloned();
+        let robots = match cached {
+            Some(robots) => robots,
+            None => {
+                let robots = Arc::new(self.fetch_robots(&origin).await);
+                self.robots.lock().unwrap_or_else(|e| e.into_inner()).insert(origin, robots.clone());
+                robots
+            }
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        (!robots.is_allowed(&path)).then(|| {
+            format!(
+                "The robots.txt of {} disallows fetching {}",
+                url.host_str().unwrap_or_default(),
+                path
+            )
+        })
+    }
+
+    /// The rules of a site; sites without a readable robots.txt allow
+    /// everything
+    async fn fetch_robots(&self, origin: &str) -> Robots {
+        let response = self
+            .client
+            .get(format!("{}/robots.txt", origin))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(content) => Robots::parse(&content, ROBOTS_AGENT),
+                Err(_) => Robots::allow_all(),
+            },
+            _ => Robots::allow_all(),
+        }
     }
 
-    /// Convert HTML to Markdown
-    fn convert_html_to_markdown(&self, html: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        // Use html2md for HTML to Markdown conversion
-        let markdown = html2md::parse_html(html);
-        Ok(markdown)
+    /// Whether a content type is one of those configured
+    fn reads_content_type(&self, content_type: &str) -> bool {
+        let essence = content_type_essence(content_type);
+        self.config.content_types.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            match allowed.split_once('*') {
+                Some((prefix, suffix)) => {
+                    essence.len() >= prefix.len() + suffix.len() && essence.starts_with(prefix) && essence.ends_with(suffix)
+                }
+                None => essence == allowed,
+            }
+        })
     }
 
     /// Extract body content from HTML
-    fn extract_body_from_html(&self, html: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        // Simple body extraction using scraper
-        use scraper::{Html, Selector};
-        
+    fn extract_body_from_html(&self, html: &str) -> String {
         let document = Html::parse_document(html);
-        let body_selector = Selector::parse("body").map_err(|_| "Failed to create body selector")?;
-        
+        let body_selector = Selector::parse("body").expect("valid selector");
+
         if let Some(body) = document.select(&body_selector).next() {
-            Ok(format!("<html>\n<body>\n{}\n</body>\n</html>", body.inner_html()))
+            format!("<html>\n<body>\n{}\n</body>\n</html>", body.inner_html())
         } else {
             // If no body tag found, return the full HTML
-            Ok(html.to_string())
+            html.to_string()
         }
     }
 }
 
+/// `text/html` for `text/html; charset=utf-8`
+fn content_type_essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::llm::tools::ToolPermissions;
     use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    /// Serve canned responses by path, counting the requests
+    async fn serve(routes: Vec<(&'static str, String)>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counted = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                counted.fetch_add(1, Ordering::SeqCst);
+                let reply = routes
+                    .iter()
+                    .find(|(route, _)| *route == path)
+                    .map_or_else(|| response("404 Not Found", "", ""), |(_, reply)| reply.clone());
+                socket.write_all(reply.as_bytes()).await.unwrap();
+                socket.shutdown().await.ok();
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn test_redirects_robots_content_types_and_cache() {
+        let page = "<html><head><title>Docs</title></head><body><nav>Menu</nav><main><p>Hello <a href=\"/x\">x</a></p></main></body></html>";
+        let (base, requests) = serve(vec![
+            ("/robots.txt", response("200 OK", "content-type: text/plain\r\n", "User-agent: *\nDisallow: /private\n")),
+            ("/old", response("301 Moved Permanently", "location: /docs\r\n", "")),
+            ("/away", response("302 Found", "location: https://example.org/\r\n", "")),
+            ("/docs", response("200 OK", "content-type: text/html; charset=utf-8\r\n", page)),
+            ("/logo.png", response("200 OK", "content-type: image/png\r\n", "PNG")),
+        ])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let tool = FetchTool::new().with_cache_dir(dir.path());
+        let fetch = |path: &str| {
+            let mut params = HashMap::new();
+            params.insert("url".to_string(), json!(format!("{}{}", base, path)));
+            params.insert("format".to_string(), json!("markdown"));
+            let request = ToolRequest {
+                tool_name: "fetch".to_string(),
+                parameters: params,
+                working_directory: None,
+                permissions: ToolPermissions {
+                    allow_network: true,
+                    ..Default::default()
+                },
+                progress: None,
+                deadline: None,
+            };
+            let tool = &tool;
+            async move { tool.execute(request).await.unwrap() }
+        };
+
+        let docs = fetch("/old").await;
+        assert!(docs.success, "{:?}", docs.error);
+        assert!(docs.content.starts_with("# Docs\n\nHello [x]("));
+        assert!(docs.content.contains(&format!("{}/x", base)));
+        assert!(!docs.content.contains("Menu"));
+        assert_eq!(docs.metadata.unwrap()["final_url"], format!("{}/docs", base));
+
+        // Fetched again from the cache
+        let served = requests.load(Ordering::SeqCst);
+        let again = fetch("/old").await;
+        assert_eq!(again.metadata.unwrap()["cached"], true);
+        assert_eq!(requests.load(Ordering::SeqCst), served);
+
+        assert!(fetch("/private/notes").await.error.unwrap().contains("robots.txt"));
+        assert!(fetch("/away").await.error.unwrap().contains("on another host"));
+        assert!(fetch("/logo.png").await.error.unwrap().contains("image/png"));
+    }
 
     #[tokio::test]
     async fn test_fetch_tool_info() {
@@ -322,6 +564,8 @@ mod tests {
                 allow_network: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -345,6 +589,8 @@ mod tests {
                 allow_network: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -369,6 +615,8 @@ mod tests {
                 yolo_mode: false,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let result = tool.execute(request).await;
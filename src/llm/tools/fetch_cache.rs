@@ -0,0 +1,116 @@
+//! Pages the fetch tool downloaded, kept on disk for a while
+//!
+//! An agent reading documentation fetches the same pages again and again
+//! within a task. Each response body is kept in a file named after the URL
+//! for `ttl`, and fetching the URL again in that time reads the file instead.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// A downloaded page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchedPage {
+    /// URL asked for
+    pub url: String,
+    /// URL the page was found at after redirects
+    pub final_url: String,
+    pub content_type: String,
+    pub body: String,
+    /// Seconds since the epoch
+    pub fetched_at: u64,
+}
+
+/// Seconds since the epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Downloaded pages by URL
+#[derive(Debug, Clone)]
+pub struct FetchCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl FetchCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Directory of the cache in the data directory
+    pub fn default_dir(data_dir: &Path) -> PathBuf {
+        data_dir.join("fetch_cache")
+    }
+
+    /// The page fetched from `url` within the time to live, if any
+    pub fn get(&self, url: &str) -> Option<FetchedPage> {
+        let path = self.path(url);
+        let page: FetchedPage = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if page.url != url || now().saturating_sub(page.fetched_at) >= self.ttl.as_secs() {
+            return None;
+        }
+        Some(page)
+    }
+
+    /// Keep a page, and remove the pages kept too long
+    pub fn put(&self, page: &FetchedPage) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(&page.url), serde_json::to_vec(page)?)?;
+        self.prune();
+        Ok(())
+    }
+
+    fn prune(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let expired = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= self.ttl);
+            if expired {
+                if let Err(e) = fs::remove_file(entry.path()) {
+                    debug!("Could not remove {}: {}", entry.path().display(), e);
+                }
+            }
+        }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        let name: String = digest.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_are_kept_for_their_time_to_live() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FetchCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+        let page = FetchedPage {
+            url: "https://example.com/docs".to_string(),
+            final_url: "https://example.com/docs/".to_string(),
+            content_type: "text/html".to_string(),
+            body: "<p>Docs</p>".to_string(),
+            fetched_at: now(),
+        };
+        cache.put(&page).unwrap();
+        assert_eq!(cache.get("https://example.com/docs"), Some(page.clone()));
+        assert_eq!(cache.get("https://example.com/other"), None);
+
+        cache.put(&FetchedPage { fetched_at: now() - 61, ..page }).unwrap();
+        assert_eq!(cache.get("https://example.com/docs"), None);
+    }
+}
@@ -145,6 +145,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -171,6 +173,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -192,6 +196,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
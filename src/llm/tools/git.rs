@@ -0,0 +1,353 @@
+//! Git tools giving the agent the state and history of the repository
+
+use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::git::GitRepo;
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Default number of commits listed by `git_log`
+const DEFAULT_LOG_COUNT: usize = 20;
+
+/// Repository of the request's `path`, or of its working directory
+async fn open_repo(request: &ToolRequest) -> ToolResult<GitRepo> {
+    let dir = match request.parameters.get("path").and_then(|v| v.as_str()) {
+        Some(path) => PathBuf::from(path),
+        None => match &request.working_directory {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::current_dir()?,
+        },
+    };
+    GitRepo::open(&dir).await
+}
+
+/// String array parameter, empty when missing
+fn string_list(request: &ToolRequest, key: &str) -> Vec<String> {
+    request.parameters.get(key)
+        .and_then(|v| v.as_array())
+        .map(|values| values.iter().filter_map(|v| v.as_str()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn failure(error: String) -> ToolResponse {
+    ToolResponse {
+        content: String::new(),
+        success: false,
+        metadata: None,
+        error: Some(error),
+    }
+}
+
+/// Tool showing the branch and changed files
+pub struct GitStatusTool;
+
+impl GitStatusTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for GitStatusTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let status = match open_repo(&request).await {
+            Ok(repo) => repo.status().await,
+            Err(e) => Err(e),
+        };
+        match status {
+            Ok(status) => Ok(ToolResponse {
+                content: status.render(),
+                success: true,
+                metadata: Some(serde_json::to_value(&status)?),
+                error: None,
+            }),
+            Err(e) => Ok(failure(format!("{:#}", e))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "git_status"
+    }
+
+    fn description(&self) -> &str {
+        r#"Shows the current branch, how far it is ahead of or behind its upstream, and the staged, unstaged, untracked and conflicted files.
+
+WHEN TO USE THIS TOOL:
+- Use before committing to see what would be included
+- Use to find out which files you or the user changed"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "A directory in the repository (defaults to the workspace)"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+}
+
+/// Tool showing uncommitted changes
+pub struct GitDiffTool;
+
+impl GitDiffTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for GitDiffTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let staged = request.parameters.get("staged").and_then(|v| v.as_bool()).unwrap_or(false);
+        let paths = string_list(&request, "paths");
+        let diff = match open_repo(&request).await {
+            Ok(repo) => repo.diff(staged, &paths).await,
+            Err(e) => Err(e),
+        };
+        match diff {
+            Ok(diff) if diff.is_empty() => Ok(ToolResponse {
+                content: if staged { "No staged changes" } else { "No unstaged changes" }.to_string(),
+                success: true,
+                metadata: None,
+                error: None,
+            }),
+            Ok(diff) => Ok(ToolResponse {
+                content: diff,
+                success: true,
+                metadata: None,
+                error: None,
+            }),
+            Err(e) => Ok(failure(format!("{:#}", e))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "git_diff"
+    }
+
+    fn description(&self) -> &str {
+        r#"Shows uncommitted changes as a unified diff: unstaged changes by default, or the changes staged for the next commit.
+
+WHEN TO USE THIS TOOL:
+- Use to review your changes before committing
+- Use to see what the user changed since the last commit
+
+LIMITATIONS:
+- Untracked files are not part of the diff; git_status lists them
+- Very large diffs are cut off; pass paths to see them in parts"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "staged": {
+                    "type": "boolean",
+                    "description": "Show the changes staged for commit instead of the unstaged ones"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only show changes of these files or directories"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "A directory in the repository (defaults to the workspace)"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+}
+
+/// Tool listing recent commits
+pub struct GitLogTool;
+
+impl GitLogTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for GitLogTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let count = request.parameters.get("count")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_LOG_COUNT, |count| count.clamp(1, 200) as usize);
+        let file = request.parameters.get("file").and_then(|v| v.as_str());
+        let commits = match open_repo(&request).await {
+            Ok(repo) => repo.log(count, file).await,
+            Err(e) => Err(e),
+        };
+        match commits {
+            Ok(commits) if commits.is_empty() => Ok(ToolResponse {
+                content: "No commits".to_string(),
+                success: true,
+                metadata: None,
+                error: None,
+            }),
+            Ok(commits) => Ok(ToolResponse {
+                content: commits
+                    .iter()
+                    .map(|commit| format!("{} {} {} {}", commit.hash, commit.date, commit.author, commit.subject))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                success: true,
+                metadata: Some(serde_json::to_value(&commits)?),
+                error: None,
+            }),
+            Err(e) => Ok(failure(format!("{:#}", e))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "git_log"
+    }
+
+    fn description(&self) -> &str {
+        r#"Lists recent commits, newest first, as hash, date, author and subject.
+
+WHEN TO USE THIS TOOL:
+- Use to learn how commit messages are written in this repository before committing
+- Use to find when and why a file was changed"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "count": {
+                    "type": "integer",
+                    "description": "Number of commits to list (default 20, at most 200)"
+                },
+                "file": {
+                    "type": "string",
+                    "description": "Only list commits changing this file"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "A directory in the repository (defaults to the workspace)"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+}
+
+/// Tool committing staged changes
+pub struct GitCommitTool;
+
+impl GitCommitTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for GitCommitTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let message = request.parameters.get("message")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("message parameter is required"))?;
+        let paths = string_list(&request, "paths");
+        let commit = match open_repo(&request).await {
+            Ok(repo) => repo.commit(message, &paths).await,
+            Err(e) => Err(e),
+        };
+        match commit {
+            Ok(commit) => Ok(ToolResponse {
+                content: format!("Committed {}: {}", commit.hash, commit.subject),
+                success: true,
+                metadata: Some(serde_json::to_value(&commit)?),
+                error: None,
+            }),
+            Err(e) => Ok(failure(format!("{:#}", e))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "git_commit"
+    }
+
+    fn description(&self) -> &str {
+        r#"Commits staged changes, after staging the given paths.
+
+WHEN TO USE THIS TOOL:
+- Use only when the user asked you to commit
+
+HOW TO USE:
+- Check git_status and git_diff first so the commit holds what you expect
+- Pass the files to commit in paths; files staged before are committed too
+- Write the message in the style of git_log
+
+LIMITATIONS:
+- Never pushes, amends or rewrites history
+- Commit hooks run, and the commit fails when one does"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "message": {
+                    "type": "string",
+                    "description": "The commit message"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files or directories to stage before committing"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "A directory in the repository (defaults to the workspace)"
+                }
+            },
+            "required": ["message"]
+        })
+    }
+
+    fn validate_request(&self, request: &ToolRequest) -> ToolResult<()> {
+        if !request.permissions.yolo_mode && !request.permissions.allow_write {
+            return Err(anyhow::anyhow!("Tool '{}' requires write permission", self.name()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_commit_requires_write_permission() {
+        let mut request = ToolRequest {
+            tool_name: "git_commit".to_string(),
+            parameters: HashMap::new(),
+            working_directory: None,
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
+        };
+        assert!(GitCommitTool::new().validate_request(&request).is_err());
+        request.permissions.allow_write = true;
+        assert!(GitCommitTool::new().validate_request(&request).is_ok());
+    }
+}
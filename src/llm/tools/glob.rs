@@ -1,15 +1,77 @@
 //! Glob pattern matching tool
+//!
+//! Patterns use the wildcards of `.gitignore` files (see
+//! [`crate::utils::gitignore`]) and are matched against paths relative to the
+//! directory searched; a pattern without a `/` matches file names at any
+//! depth. What `.gitignore` ignores is skipped, and so are hidden files. Once
+//! the project index has listed the workspace, searches in it match against
+//! that list instead of walking the directories.
 
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::index::ProjectIndex;
+use crate::utils::gitignore::{glob_regex, GitIgnore};
 use async_trait::async_trait;
+use regex::Regex;
 use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Paths returned at most
+const MAX_MATCHES: usize = 1_000;
 
 /// Tool for finding files using glob patterns
-pub struct GlobTool;
+pub struct GlobTool {
+    indexes: Vec<Arc<ProjectIndex>>,
+}
 
 impl GlobTool {
     pub fn new() -> Self {
-        Self
+        Self { indexes: Vec::new() }
+    }
+
+    /// Match against the files of these indexes once they are built
+    pub fn with_indexes(mut self, indexes: Vec<Arc<ProjectIndex>>) -> Self {
+        self.indexes = indexes;
+        self
+    }
+
+    /// The built index covering `root`
+    fn index_for(&self, root: &Path) -> Option<Arc<ProjectIndex>> {
+        self.indexes
+            .iter()
+            .find(|index| root.starts_with(index.root()) && index.is_ready() && !index.is_truncated())
+            .cloned()
+    }
+}
+
+/// Whether a file's path relative to the directory searched matches
+fn is_match(regex: &Regex, by_name: bool, relative: &Path) -> bool {
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let subject = if by_name { relative.rsplit('/').next().unwrap_or_default() } else { relative.as_str() };
+    regex.is_match(subject)
+}
+
+/// Files under `dir` whose paths relative to `root` match, in path order
+fn find(root: &Path, dir: &Path, ignore: &GitIgnore, regex: &Regex, by_name: bool, found: &mut Vec<PathBuf>) {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = read.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if found.len() > MAX_MATCHES {
+            return;
+        }
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if entry.file_name().to_string_lossy().starts_with('.') || ignore.matches(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            find(root, &path, &ignore.enter(&path), regex, by_name, found);
+        } else if is_match(regex, by_name, path.strip_prefix(root).unwrap_or(&path)) {
+            found.push(path);
+        }
     }
 }
 
@@ -19,13 +81,65 @@ impl BaseTool for GlobTool {
         let pattern = request.parameters.get("pattern")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing required parameter: pattern"))?;
+        let root = match request.parameters.get("path").and_then(|v| v.as_str()) {
+            Some(path) => PathBuf::from(path),
+            None => match &request.working_directory {
+                Some(dir) => PathBuf::from(dir),
+                None => std::env::current_dir()?,
+            },
+        };
+        let include_ignored = request.parameters.get("include_ignored").and_then(|v| v.as_bool()).unwrap_or(false);
+        if !root.is_dir() {
+            return Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("Not a directory: {}", root.display())),
+            });
+        }
+        let pattern = pattern.trim_start_matches("./");
+        let by_name = !pattern.contains('/');
+        let regex = glob_regex(pattern).map_err(|e| anyhow::anyhow!("Invalid pattern '{}': {}", pattern, e))?;
 
-        // Basic glob implementation using walkdir
-        // In a full implementation, this would use proper glob crate
+        let searched = root.clone();
+        let index = if include_ignored { None } else { self.index_for(&root) };
+        let indexed = index.is_some();
+        let mut found = tokio::task::spawn_blocking(move || match index {
+            Some(index) => index
+                .files()
+                .into_iter()
+                .filter(|path| path.strip_prefix(&searched).is_ok_and(|relative| is_match(&regex, by_name, relative)))
+                .take(MAX_MATCHES + 1)
+                .collect(),
+            None => {
+                let ignore = if include_ignored { GitIgnore::default() } else { GitIgnore::for_dir(&searched) };
+                let mut found = Vec::new();
+                find(&searched, &searched, &ignore, &regex, by_name, &mut found);
+                found
+            }
+        })
+        .await?;
+        let truncated = found.len() > MAX_MATCHES;
+        found.truncate(MAX_MATCHES);
+
+        let mut content = if found.is_empty() {
+            format!("No files match '{}' in {}", pattern, root.display())
+        } else {
+            found.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join("\n")
+        };
+        if truncated {
+            content.push_str(&format!("\n(Only the first {} matches are listed; use a narrower pattern.)", MAX_MATCHES));
+        }
         Ok(ToolResponse {
-            content: format!("Glob pattern matching for '{}' - Not fully implemented yet", pattern),
+            content,
             success: true,
-            metadata: Some(json!({"pattern": pattern})),
+            metadata: Some(json!({
+                "pattern": pattern,
+                "path": root.display().to_string(),
+                "matches": found.len(),
+                "truncated": truncated,
+                "indexed": indexed,
+            })),
             error: None,
         })
     }
@@ -35,7 +149,7 @@ impl BaseTool for GlobTool {
     }
 
     fn description(&self) -> &str {
-        "Find files matching glob patterns. Currently a placeholder implementation."
+        "Find files whose paths match a glob pattern such as **/*.rs or src/**/mod.rs; a pattern without a slash matches file names at any depth. Files .gitignore ignores are skipped."
     }
 
     fn parameters(&self) -> serde_json::Value {
@@ -45,9 +159,76 @@ impl BaseTool for GlobTool {
                 "pattern": {
                     "type": "string",
                     "description": "The glob pattern to match files against"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory to search (defaults to the workspace root)"
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "Search what .gitignore ignores as well"
                 }
             },
             "required": ["pattern"]
         })
     }
-}
\ No newline at end of file
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn runs_in_parallel(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IndexConfig;
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_glob_matches_paths_and_skips_ignored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/tui")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        for file in ["src/main.rs", "src/tui/mod.rs", "target/gen.rs", "README.md"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let index = Arc::new(ProjectIndex::new(root, IndexConfig::default()));
+        index.build().unwrap();
+        let indexed = GlobTool::new().with_indexes(vec![index]);
+        let walked = GlobTool::new();
+        let glob = |tool: &'static str, pattern: &str| {
+            let parameters = HashMap::from([
+                ("pattern".to_string(), json!(pattern)),
+                ("path".to_string(), json!(root.display().to_string())),
+            ]);
+            let request = ToolRequest {
+                tool_name: "glob".to_string(),
+                parameters,
+                working_directory: None,
+                permissions: ToolPermissions::default(),
+                progress: None,
+                deadline: None,
+            };
+            let tool = if tool == "indexed" { &indexed } else { &walked };
+            async move {
+                let response = tool.execute(request).await.unwrap();
+                response.content.lines().map(|line| line.replace(&root.display().to_string(), "")).collect::<Vec<_>>()
+            }
+        };
+
+        for tool in ["walked", "indexed"] {
+            assert_eq!(glob(tool, "*.rs").await, ["/src/main.rs", "/src/tui/mod.rs"]);
+            assert_eq!(glob(tool, "src/*.rs").await, ["/src/main.rs"]);
+            assert_eq!(glob(tool, "**/mod.rs").await, ["/src/tui/mod.rs"]);
+            assert!(glob(tool, "*.py").await[0].starts_with("No files match"));
+        }
+    }
+}
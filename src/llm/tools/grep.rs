@@ -1,18 +1,96 @@
 //! Text search tool using grep-like functionality
+//!
+//! Besides searching text, the tool answers "where is X defined" from the
+//! project index when given a `symbol` instead of a pattern.
 
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::config::paths::display_path;
+use crate::index::{ProjectIndex, SymbolKind};
 use async_trait::async_trait;
 use regex::Regex;
 use serde_json::json;
 use std::path::Path;
+use std::sync::Arc;
 use tokio::fs;
 
+/// Definitions listed at most for a symbol lookup
+const MAX_DEFINITIONS: usize = 50;
+
 /// Tool for searching text in files
-pub struct GrepTool;
+pub struct GrepTool {
+    indexes: Vec<Arc<ProjectIndex>>,
+}
 
 impl GrepTool {
     pub fn new() -> Self {
-        Self
+        Self { indexes: Vec::new() }
+    }
+
+    /// Look symbols up in these indexes
+    pub fn with_indexes(mut self, indexes: Vec<Arc<ProjectIndex>>) -> Self {
+        self.indexes = indexes;
+        self
+    }
+
+    /// Where a symbol is defined, according to the indexes
+    async fn find_definitions(&self, symbol: &str, kind: Option<&str>) -> ToolResult<ToolResponse> {
+        let failed = |error: String| ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: None,
+            error: Some(error),
+        };
+        let kind = match kind {
+            Some(kind) => match serde_json::from_value::<SymbolKind>(json!(kind)) {
+                Ok(kind) => Some(kind),
+                Err(_) => return Ok(failed(format!("Unknown symbol kind '{}'", kind))),
+            },
+            None => None,
+        };
+        if self.indexes.is_empty() {
+            return Ok(failed("Symbol lookups need the project index, which is turned off; search with a pattern instead".to_string()));
+        }
+        let indexes: Vec<Arc<ProjectIndex>> = self.indexes.iter().filter(|index| index.is_ready()).cloned().collect();
+        if indexes.is_empty() {
+            return Ok(failed("The project index is still being built; search with a pattern for now".to_string()));
+        }
+
+        let query = symbol.to_string();
+        let found = tokio::task::spawn_blocking(move || {
+            indexes
+                .iter()
+                .flat_map(|index| index.find_symbols(&query, kind, MAX_DEFINITIONS))
+                .take(MAX_DEFINITIONS)
+                .collect::<Vec<_>>()
+        })
+        .await?;
+        let content = if found.is_empty() {
+            format!("No definitions of '{}' in the project index.", symbol)
+        } else {
+            found
+                .iter()
+                .map(|location| {
+                    format!(
+                        "{}:{}: {} {}",
+                        display_path(&location.path),
+                        location.symbol.line,
+                        location.symbol.kind.as_str(),
+                        location.symbol.name
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Ok(ToolResponse {
+            content,
+            success: true,
+            metadata: Some(json!({
+                "symbol": symbol,
+                "kind": kind.map(|kind| kind.as_str()),
+                "matches_found": found.len(),
+            })),
+            error: None,
+        })
     }
 
     /// Search for pattern in content
@@ -71,6 +149,11 @@ impl GrepTool {
 #[async_trait]
 impl BaseTool for GrepTool {
     async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        if let Some(symbol) = request.parameters.get("symbol").and_then(|v| v.as_str()) {
+            let kind = request.parameters.get("kind").and_then(|v| v.as_str());
+            return self.find_definitions(symbol, kind).await;
+        }
+
         let pattern = request.parameters.get("pattern")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing required parameter: pattern"))?;
@@ -169,8 +252,12 @@ impl BaseTool for GrepTool {
         "grep"
     }
 
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
-        "Search for text patterns in files or content using regular expressions. Supports context lines and case-insensitive search."
+        "Search for text patterns in files or content using regular expressions. Supports context lines and case-insensitive search. To find where a function, type or other symbol is defined in the project, pass its name as symbol instead of a pattern; this is answered from the project index in milliseconds."
     }
 
     fn parameters(&self) -> serde_json::Value {
@@ -181,6 +268,15 @@ impl BaseTool for GrepTool {
                     "type": "string",
                     "description": "The regular expression pattern to search for"
                 },
+                "symbol": {
+                    "type": "string",
+                    "description": "Name of a symbol whose definitions to look up in the project index, instead of searching for a pattern"
+                },
+                "kind": {
+                    "type": "string",
+                    "enum": ["function", "class", "struct", "enum", "trait", "interface", "type", "constant", "module", "macro"],
+                    "description": "Only definitions of this kind, with symbol"
+                },
                 "path": {
                     "type": "string",
                     "description": "The absolute path to the file to search (optional if content is provided)"
@@ -209,8 +305,7 @@ impl BaseTool for GrepTool {
                     "description": "Number of lines to show after each match",
                     "default": 0
                 }
-            },
-            "required": ["pattern"]
+            }
         })
     }
 
@@ -244,6 +339,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -265,6 +362,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -299,6 +398,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -306,6 +407,33 @@ mod tests {
         assert!(response.content.contains("No matches found"));
     }
 
+    #[tokio::test]
+    async fn test_symbols_are_looked_up_in_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "struct Config;\n\npub fn load_config() {}\n").unwrap();
+        let index = Arc::new(ProjectIndex::new(dir.path(), crate::config::IndexConfig::default()));
+        index.build().unwrap();
+        let lookup = |tool: GrepTool, symbol: &str| {
+            let mut params = HashMap::new();
+            params.insert("symbol".to_string(), json!(symbol));
+            let request = ToolRequest {
+                tool_name: "grep".to_string(),
+                parameters: params,
+                working_directory: None,
+                permissions: ToolPermissions::default(),
+                progress: None,
+                deadline: None,
+            };
+            async move { tool.execute(request).await.unwrap() }
+        };
+
+        let response = lookup(GrepTool::new().with_indexes(vec![index.clone()]), "load_config").await;
+        assert!(response.content.ends_with("lib.rs:3: function load_config"));
+        let response = lookup(GrepTool::new().with_indexes(vec![index]), "config").await;
+        assert_eq!(response.metadata.unwrap()["matches_found"], 2);
+        assert!(!lookup(GrepTool::new(), "Config").await.success);
+    }
+
     #[tokio::test]
     async fn test_invalid_regex() {
         let tool = GrepTool::new();
@@ -0,0 +1,228 @@
+//! Guard rails for the edit and write tools
+//!
+//! Before an edit runs, the file is checked against the configured limits:
+//! files above the size limit, lock and generated files and binary files are
+//! refused with an error that says which limit was hit, in the response
+//! metadata under [`GUARD_METADATA_KEY`] as well as in words, so the model can
+//! take another way. After an edit that rewrote much of a file, the model is
+//! warned so it can check it did not drop content by accident.
+
+use serde_json::json;
+use similar::{ChangeTag, TextDiff};
+use std::io::Read;
+use std::path::Path;
+
+use super::ToolResponse;
+use crate::config::{paths::display_path, EditGuardConfig};
+use crate::utils::fs::matches_glob_pattern;
+
+/// Metadata key under which refusals and warnings are reported
+pub const GUARD_METADATA_KEY: &str = "guard";
+
+/// Bytes looked at to tell whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// An edit the guards refuse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardViolation {
+    /// The file, or the content written, is over the size limit
+    TooLarge { size: u64, limit: u64 },
+    /// The file matches a protected pattern
+    Protected { pattern: String },
+    /// The file looks binary
+    Binary,
+}
+
+impl GuardViolation {
+    /// Stable name of the limit, for the response metadata
+    pub fn code(&self) -> &'static str {
+        match self {
+            GuardViolation::TooLarge { .. } => "file_too_large",
+            GuardViolation::Protected { .. } => "protected_file",
+            GuardViolation::Binary => "binary_file",
+        }
+    }
+
+    /// The failed tool response for an edit of `path`
+    pub fn response(&self, path: &Path) -> ToolResponse {
+        let shown = display_path(path);
+        let (error, details) = match self {
+            GuardViolation::TooLarge { size, limit } => (
+                format!(
+                    "{} is {} bytes, over the {} byte limit for edits. Change it with a command instead, or ask the user to.",
+                    shown, size, limit
+                ),
+                json!({ "size": size, "limit": limit }),
+            ),
+            GuardViolation::Protected { pattern } => (
+                format!(
+                    "{} is a generated or lock file (matches `{}`) and is not edited by hand. Run the tool that generates it instead.",
+                    shown, pattern
+                ),
+                json!({ "pattern": pattern }),
+            ),
+            GuardViolation::Binary => (
+                format!("{} looks like a binary file and cannot be edited as text.", shown),
+                json!({}),
+            ),
+        };
+        let mut guard = json!({ "code": self.code(), "file_path": path.display().to_string() });
+        if let (Some(guard), serde_json::Value::Object(details)) = (guard.as_object_mut(), details) {
+            guard.extend(details);
+        }
+        ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: Some(json!({ GUARD_METADATA_KEY: guard })),
+            error: Some(error),
+        }
+    }
+}
+
+/// Checks edits against the configured limits
+#[derive(Debug, Clone, Default)]
+pub struct EditGuards {
+    config: EditGuardConfig,
+}
+
+impl EditGuards {
+    pub fn new(config: EditGuardConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether an edit of `path`, writing `new_size` bytes if known, is refused
+    pub fn check(&self, path: &Path, new_size: Option<u64>) -> Option<GuardViolation> {
+        if let Some(pattern) = self.protected_pattern(path) {
+            return Some(GuardViolation::Protected { pattern });
+        }
+        let limit = self.config.max_file_size;
+        let size = std::fs::metadata(path).ok().filter(|metadata| metadata.is_file()).map(|metadata| metadata.len());
+        if let Some(size) = size.into_iter().chain(new_size).find(|size| *size > limit) {
+            return Some(GuardViolation::TooLarge { size, limit });
+        }
+        if self.config.refuse_binary && size.is_some() && looks_binary(path) {
+            return Some(GuardViolation::Binary);
+        }
+        None
+    }
+
+    /// The protected pattern the file name matches, unless it is allowed
+    fn protected_pattern(&self, path: &Path) -> Option<String> {
+        let name = path.file_name()?.to_str()?;
+        let matches = |pattern: &String| matches_glob_pattern(name, pattern);
+        if self.config.allow.iter().any(matches) {
+            return None;
+        }
+        self.config.protected.iter().find(|pattern| matches(pattern)).cloned()
+    }
+
+    /// Percentage of the file's lines an edit changed, if over the warning
+    /// threshold
+    pub fn changed_percent(&self, old: &str, new: &str) -> Option<u8> {
+        let threshold = self.config.warn_changed_percent;
+        if threshold == 0 || old.is_empty() {
+            return None;
+        }
+        let (mut removed, mut added) = (0usize, 0usize);
+        for change in TextDiff::from_lines(old, new).iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Delete => removed += 1,
+                ChangeTag::Insert => added += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+        let lines = old.lines().count().max(1);
+        let percent = (removed.max(added) * 100 / lines).min(100) as u8;
+        (percent > threshold).then_some(percent)
+    }
+
+    /// Warn the model in a successful response that the edit rewrote much of
+    /// the file
+    pub fn warn_changed(&self, path: &Path, old: &str, new: &str, response: &mut ToolResponse) {
+        let Some(percent) = self.changed_percent(old, new) else {
+            return;
+        };
+        response.content.push_str(&format!(
+            "\n\nWarning: this edit changed {}% of the lines of {}. Check that no content was dropped by accident.",
+            percent,
+            display_path(path)
+        ));
+        let warning = json!({ "code": "large_change", "changed_percent": percent });
+        match &mut response.metadata {
+            Some(serde_json::Value::Object(metadata)) => {
+                metadata.insert(GUARD_METADATA_KEY.to_string(), warning);
+            }
+            metadata @ None => *metadata = Some(json!({ GUARD_METADATA_KEY: warning })),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Whether the start of the file holds a NUL byte, as text files do not
+fn looks_binary(path: &Path) -> bool {
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut start = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    if file.take(BINARY_SNIFF_BYTES as u64).read_to_end(&mut start).is_err() {
+        return false;
+    }
+    start.contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edits_are_checked_against_the_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let guards = EditGuards::new(EditGuardConfig {
+            max_file_size: 16,
+            allow: vec!["yarn.lock".to_string()],
+            ..EditGuardConfig::default()
+        });
+
+        let lock = dir.path().join("Cargo.lock");
+        assert_eq!(guards.check(&lock, None), Some(GuardViolation::Protected { pattern: "Cargo.lock".to_string() }));
+        assert_eq!(guards.check(&dir.path().join("yarn.lock"), None), None);
+        let bundle = dir.path().join("app.min.js");
+        assert_eq!(guards.check(&bundle, None).unwrap().code(), "protected_file");
+
+        let big = dir.path().join("big.txt");
+        std::fs::write(&big, "x".repeat(32)).unwrap();
+        assert_eq!(guards.check(&big, None), Some(GuardViolation::TooLarge { size: 32, limit: 16 }));
+        let new = dir.path().join("new.txt");
+        assert_eq!(guards.check(&new, Some(17)), Some(GuardViolation::TooLarge { size: 17, limit: 16 }));
+        assert_eq!(guards.check(&new, Some(3)), None);
+
+        let binary = dir.path().join("logo.dat");
+        std::fs::write(&binary, b"\x89PNG\0\0").unwrap();
+        assert_eq!(guards.check(&binary, None), Some(GuardViolation::Binary));
+
+        let response = GuardViolation::Binary.response(&binary);
+        assert!(!response.success);
+        assert_eq!(response.metadata.unwrap()[GUARD_METADATA_KEY]["code"], "binary_file");
+    }
+
+    #[test]
+    fn test_large_changes_are_warned_about() {
+        let guards = EditGuards::default();
+        let old = "a\nb\nc\nd\n";
+        assert_eq!(guards.changed_percent(old, "a\nb\nc\nD\n"), None);
+        assert_eq!(guards.changed_percent(old, "w\nx\ny\nd\n"), Some(75));
+        assert_eq!(guards.changed_percent("", "new\n"), None);
+
+        let mut response = ToolResponse {
+            content: "done".to_string(),
+            success: true,
+            metadata: Some(json!({ "additions": 3 })),
+            error: None,
+        };
+        guards.warn_changed(Path::new("/tmp/notes.txt"), old, "", &mut response);
+        assert!(response.content.contains("changed 100% of the lines"));
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata[GUARD_METADATA_KEY]["code"], "large_change");
+        assert_eq!(metadata["additions"], 3);
+    }
+}
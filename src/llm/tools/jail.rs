@@ -0,0 +1,154 @@
+//! Keeps tools inside the workspace
+//!
+//! Every path a tool is given is resolved to the file it really names, with
+//! symlinks followed and `..` applied, and refused unless that file is inside
+//! a workspace root or a directory the configuration allows. A symlink in the
+//! project pointing elsewhere therefore does not take a tool out of it. Paths
+//! that do not exist yet are checked through their closest existing parent.
+//! YOLO mode skips the check.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::workspace::Workspace;
+
+/// A path outside the directories tools may use
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("'{}' is outside the workspace{}; tools may only use files under {}", .path.display(), escape_note(.path, .resolved), allowed_list(.allowed))]
+pub struct JailError {
+    pub path: PathBuf,
+    /// Where the path leads once symlinks are followed
+    pub resolved: PathBuf,
+    pub allowed: Vec<PathBuf>,
+}
+
+fn escape_note(path: &Path, resolved: &Path) -> String {
+    if path == resolved {
+        String::new()
+    } else {
+        format!(" (it leads to '{}')", resolved.display())
+    }
+}
+
+fn allowed_list(allowed: &[PathBuf]) -> String {
+    allowed.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// The directories tools may use
+#[derive(Debug, Clone)]
+pub struct PathJail {
+    dirs: Vec<PathBuf>,
+}
+
+impl PathJail {
+    /// Jail to the roots of `workspace` and the `allowed` directories
+    pub fn new(workspace: &Workspace, allowed: &[String]) -> Self {
+        let dirs = workspace
+            .roots()
+            .iter()
+            .map(|root| root.path.clone())
+            .chain(allowed.iter().map(PathBuf::from))
+            .map(|dir| real_path(&dir))
+            .collect();
+        Self { dirs }
+    }
+
+    /// Fail unless the file `path` really names is inside the jail
+    pub fn check(&self, path: &Path) -> Result<(), JailError> {
+        let resolved = real_path(path);
+        if self.dirs.iter().any(|dir| resolved.starts_with(dir)) {
+            return Ok(());
+        }
+        Err(JailError {
+            path: path.to_path_buf(),
+            resolved,
+            allowed: self.dirs.clone(),
+        })
+    }
+}
+
+/// The absolute path a file would have with every symlink followed, for
+/// files that may not exist yet
+fn real_path(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    let base = loop {
+        if let Ok(real) = dunce::canonicalize(existing) {
+            break real;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            // `..` at the end or nothing exists: only clean the path up
+            _ => return normalize(path),
+        }
+    };
+    let mut real = base;
+    real.extend(missing.iter().rev());
+    normalize(&real)
+}
+
+/// Apply `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::trust::TrustStore;
+
+    #[test]
+    fn test_paths_outside_the_workspace_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(dir.path()).unwrap().join("project");
+        let shared = root.parent().unwrap().join("shared");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        let jail = PathJail::new(&Workspace::new(&root, &[], &TrustStore::default()), &[]);
+
+        assert!(jail.check(&root.join("src/main.rs")).is_ok());
+        assert!(jail.check(&root.join("src/new/dir/file.rs")).is_ok());
+        assert!(jail.check(Path::new("/etc/passwd")).is_err());
+        let escape = jail.check(&root.join("src/../../shared/notes.md")).unwrap_err();
+        assert_eq!(escape.resolved, shared.join("notes.md"));
+
+        let allowed = PathJail::new(&Workspace::new(&root, &[], &TrustStore::default()), &[shared.display().to_string()]);
+        assert!(allowed.check(&shared.join("notes.md")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_the_workspace_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dunce::canonicalize(dir.path()).unwrap();
+        let (root, outside) = (base.join("project"), base.join("outside"));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("secret.txt")).unwrap();
+        std::fs::write(root.join("inside.txt"), "").unwrap();
+        std::os::unix::fs::symlink(root.join("inside.txt"), root.join("alias.txt")).unwrap();
+        let jail = PathJail::new(&Workspace::new(&root, &[], &TrustStore::default()), &[]);
+
+        let error = jail.check(&root.join("link/secret.txt")).unwrap_err();
+        assert_eq!(error.resolved, outside.join("secret.txt"));
+        assert!(error.to_string().contains("it leads to"));
+        assert!(jail.check(&root.join("secret.txt")).is_err());
+        // Files about to be created through the link escape as well
+        assert!(jail.check(&root.join("link/new/file.txt")).is_err());
+        assert!(jail.check(&root.join("alias.txt")).is_ok());
+    }
+}
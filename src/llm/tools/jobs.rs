@@ -0,0 +1,518 @@
+//! Background commands
+//!
+//! The `bash_background` tool starts commands that keep running after the call
+//! returns, such as dev servers and watchers, and hands the agent a job id.
+//! Output of a job is kept in a bounded buffer; each poll returns what was
+//! printed since the previous one, and a note when older output was dropped
+//! before it could be read. Jobs are killed with their whole process group
+//! when asked to, and when the registry goes away with the session.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{oneshot, watch};
+
+use super::{session_env::SessionEnv, BaseTool, BashTool, ToolRequest, ToolResponse, ToolResult};
+
+/// Bytes of output kept per job
+pub const OUTPUT_LIMIT: usize = 256 * 1024;
+
+/// How long killing a job waits for it to end
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// State of a background job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Exited(i32),
+    Killed,
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn is_running(&self) -> bool {
+        *self == JobStatus::Running
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Exited(code) => write!(f, "exited with code {}", code),
+            JobStatus::Killed => write!(f, "killed"),
+            JobStatus::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// The newest output of a job, within [`OUTPUT_LIMIT`] bytes
+#[derive(Debug, Default)]
+struct OutputBuffer {
+    text: String,
+    /// Bytes dropped from the front so far
+    dropped: usize,
+}
+
+impl OutputBuffer {
+    fn push_line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+        if self.text.len() > OUTPUT_LIMIT {
+            let mut cut = self.text.len() - OUTPUT_LIMIT;
+            while !self.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.text.drain(..cut);
+            self.dropped += cut;
+        }
+    }
+
+    /// Output from byte `offset` of everything printed, whether some of it was
+    /// dropped, and the offset to read from next
+    fn read_from(&self, offset: usize) -> (&str, bool, usize) {
+        let start = offset.max(self.dropped) - self.dropped;
+        (&self.text[start..], offset < self.dropped, self.dropped + self.text.len())
+    }
+}
+
+/// A running or finished background job
+struct Job {
+    command: String,
+    started: Instant,
+    output: Arc<Mutex<OutputBuffer>>,
+    status: watch::Receiver<JobStatus>,
+    kill: Option<oneshot::Sender<()>>,
+    /// Offset the agent has read the output up to
+    read: usize,
+}
+
+/// New output of a job
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobOutput {
+    pub text: String,
+    /// Output printed before `text` was dropped without being read
+    pub truncated: bool,
+    pub status: JobStatus,
+}
+
+/// A job as listed
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSummary {
+    pub id: u32,
+    pub command: String,
+    pub status: JobStatus,
+    pub running_for: Duration,
+}
+
+/// Background jobs of a session
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<BTreeMap<u32, Job>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `command` in the background and return its job id
+    ///
+    /// Only the `env_allowlist` variables are passed on when it is set, and
+    /// `extra_env` is added to them.
+    pub fn start(
+        &self,
+        command: &str,
+        cwd: Option<&Path>,
+        env_allowlist: Option<&[String]>,
+        extra_env: &[(String, String)],
+    ) -> Result<u32> {
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", command]);
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", command]);
+            cmd
+        };
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+        if let Some(allowed) = env_allowlist {
+            cmd.env_clear();
+            cmd.envs(std::env::vars_os().filter(|(name, _)| allowed.iter().any(|allowed| name == allowed.as_str())));
+        }
+        cmd.envs(extra_env.iter().map(|(key, value)| (key, value)));
+        // A group of its own, so that killing the job reaches what it started
+        #[cfg(unix)]
+        cmd.process_group(0);
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = cmd.spawn().map_err(|e| anyhow!("Failed to start '{}': {}", command, e))?;
+
+        let output = Arc::new(Mutex::new(OutputBuffer::default()));
+        let readers = [
+            child.stdout.take().map(|stdout| spawn_reader(stdout, output.clone())),
+            child.stderr.take().map(|stderr| spawn_reader(stderr, output.clone())),
+        ];
+        let (status_tx, status_rx) = watch::channel(JobStatus::Running);
+        let (kill_tx, kill_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let status = tokio::select! {
+                result = child.wait() => match result {
+                    Ok(status) => JobStatus::Exited(status.code().unwrap_or(-1)),
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                },
+                _ = kill_rx => {
+                    kill_group(child.id());
+                    let _ = child.kill().await;
+                    JobStatus::Killed
+                }
+            };
+            // Let the readers catch the last lines before the job is done
+            for reader in readers.into_iter().flatten() {
+                let _ = tokio::time::timeout(Duration::from_secs(1), reader).await;
+            }
+            let _ = status_tx.send(status);
+        });
+
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = jobs.keys().next_back().map_or(1, |id| id + 1);
+        jobs.insert(id, Job {
+            command: command.to_string(),
+            started: Instant::now(),
+            output,
+            status: status_rx,
+            kill: Some(kill_tx),
+            read: 0,
+        });
+        Ok(id)
+    }
+
+    /// Output of a job printed since it was last read
+    pub fn take_output(&self, id: u32) -> Result<JobOutput> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).ok_or_else(|| unknown_job(id))?;
+        // Read the status first so that output printed before the end is complete
+        let status = job.status.borrow().clone();
+        let output = job.output.lock().unwrap();
+        let (text, truncated, next) = output.read_from(job.read);
+        let text = text.to_string();
+        drop(output);
+        job.read = next;
+        Ok(JobOutput { text, truncated, status })
+    }
+
+    /// Kill a job and wait for it to end
+    pub async fn kill(&self, id: u32) -> Result<JobStatus> {
+        let mut status = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.get_mut(&id).ok_or_else(|| unknown_job(id))?;
+            if let Some(kill) = job.kill.take() {
+                let _ = kill.send(());
+            }
+            job.status.clone()
+        };
+        let _ = tokio::time::timeout(KILL_TIMEOUT, status.wait_for(|status| !status.is_running())).await;
+        let status = status.borrow().clone();
+        Ok(status)
+    }
+
+    /// Every job, oldest first
+    pub fn list(&self) -> Vec<JobSummary> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter()
+            .map(|(id, job)| JobSummary {
+                id: *id,
+                command: job.command.clone(),
+                status: job.status.borrow().clone(),
+                running_for: job.started.elapsed(),
+            })
+            .collect()
+    }
+
+    /// Number of jobs still running
+    pub fn running(&self) -> usize {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.values().filter(|job| job.status.borrow().is_running()).count()
+    }
+}
+
+impl Drop for JobRegistry {
+    fn drop(&mut self) {
+        let jobs = self.jobs.get_mut().unwrap_or_else(|e| e.into_inner());
+        for job in jobs.values_mut() {
+            if let Some(kill) = job.kill.take() {
+                let _ = kill.send(());
+            }
+        }
+    }
+}
+
+fn unknown_job(id: u32) -> anyhow::Error {
+    anyhow!("No background job {}; list the jobs to see their ids", id)
+}
+
+/// Copy the lines of a job's output stream into its buffer
+fn spawn_reader(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    output: Arc<Mutex<OutputBuffer>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            output.lock().unwrap().push_line(&line);
+        }
+    })
+}
+
+/// Ask every process of a job's group to stop
+pub(super) fn kill_group(pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        let _ = std::process::Command::new("kill")
+            .args(["-TERM", "--", &format!("-{}", pid)])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Tool starting, polling and killing background jobs
+pub struct BackgroundBashTool {
+    jobs: Arc<JobRegistry>,
+    env: SessionEnv,
+}
+
+impl BackgroundBashTool {
+    pub fn new(jobs: Arc<JobRegistry>) -> Self {
+        Self { jobs, env: SessionEnv::new() }
+    }
+
+    /// Start jobs with the variables of the session environment
+    pub fn with_session_env(mut self, env: SessionEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    fn failure(error: String) -> ToolResponse {
+        ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: None,
+            error: Some(error),
+        }
+    }
+
+    fn job_id(request: &ToolRequest) -> ToolResult<u32> {
+        request.parameters.get("job_id")
+            .and_then(|v| v.as_u64())
+            .map(|id| id as u32)
+            .ok_or_else(|| anyhow!("Missing required parameter: job_id"))
+    }
+}
+
+#[async_trait]
+impl BaseTool for BackgroundBashTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let action = request.parameters.get("action")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing required parameter: action"))?;
+
+        match action {
+            "start" => {
+                let command = request.parameters.get("command")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("Missing required parameter: command"))?;
+                if !request.permissions.allow_execute && !request.permissions.yolo_mode {
+                    return Ok(Self::failure("Command execution not permitted. Use --yolo flag or grant execute permissions.".to_string()));
+                }
+                if BashTool::new().is_dangerous_command(command) && !request.permissions.yolo_mode {
+                    return Ok(Self::failure(format!("Potentially dangerous command detected: '{}'. Use --yolo mode to override.", command)));
+                }
+                let cwd = request.parameters.get("cwd")
+                    .and_then(|v| v.as_str())
+                    .or(request.working_directory.as_deref())
+                    .map(Path::new);
+                let env_allowlist = request.permissions.env_allowlist.as_deref();
+                match self.jobs.start(command, cwd, env_allowlist, &self.env.vars()) {
+                    Ok(id) => Ok(ToolResponse {
+                        content: format!(
+                            "Started job {}: {}\nRead its output with action \"output\" and stop it with action \"kill\".",
+                            id, command
+                        ),
+                        success: true,
+                        metadata: Some(json!({ "job_id": id, "command": command })),
+                        error: None,
+                    }),
+                    Err(e) => Ok(Self::failure(e.to_string())),
+                }
+            }
+            "output" => {
+                let id = Self::job_id(&request)?;
+                let output = match self.jobs.take_output(id) {
+                    Ok(output) => output,
+                    Err(e) => return Ok(Self::failure(e.to_string())),
+                };
+                let mut content = format!("Job {} is {}.\n", id, output.status);
+                if output.truncated {
+                    content.push_str("(earlier output was dropped)\n");
+                }
+                if output.text.is_empty() {
+                    content.push_str("(no new output)");
+                } else {
+                    content.push_str(&output.text);
+                }
+                Ok(ToolResponse {
+                    content,
+                    success: true,
+                    metadata: Some(json!({
+                        "job_id": id,
+                        "running": output.status.is_running(),
+                        "truncated": output.truncated,
+                    })),
+                    error: None,
+                })
+            }
+            "kill" => {
+                let id = Self::job_id(&request)?;
+                match self.jobs.kill(id).await {
+                    Ok(status) => Ok(ToolResponse {
+                        content: format!("Job {} is {}.", id, status),
+                        success: true,
+                        metadata: Some(json!({ "job_id": id })),
+                        error: None,
+                    }),
+                    Err(e) => Ok(Self::failure(e.to_string())),
+                }
+            }
+            "list" => {
+                let jobs = self.jobs.list();
+                let content = if jobs.is_empty() {
+                    "No background jobs.".to_string()
+                } else {
+                    jobs.iter()
+                        .map(|job| format!("{}  {} ({}s)  {}", job.id, job.status, job.running_for.as_secs(), job.command))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+                Ok(ToolResponse {
+                    content,
+                    success: true,
+                    metadata: Some(json!({ "running": self.jobs.running() })),
+                    error: None,
+                })
+            }
+            other => Err(anyhow!("Unknown action '{}'; use start, output, kill or list", other)),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "bash_background"
+    }
+
+    fn description(&self) -> &str {
+        "Run long-lived commands such as dev servers or file watchers in the background. \
+         `start` runs a command and returns a job id; `output` returns what the job printed since the last call; \
+         `kill` stops the job and everything it started; `list` shows all jobs. \
+         Use the bash tool for commands that finish on their own."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["start", "output", "kill", "list"],
+                    "description": "What to do"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Command to start (for start)"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to start the command in, inside the workspace (for start)"
+                },
+                "job_id": {
+                    "type": "integer",
+                    "description": "Job to read or kill (for output and kill)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_read_incrementally_within_the_limit() {
+        let mut buffer = OutputBuffer::default();
+        buffer.push_line("first");
+        let (text, truncated, next) = buffer.read_from(0);
+        assert_eq!((text, truncated), ("first\n", false));
+
+        buffer.push_line("second");
+        assert_eq!(buffer.read_from(next).0, "second\n");
+
+        let long = "x".repeat(OUTPUT_LIMIT);
+        buffer.push_line(&long);
+        let (text, truncated, _) = buffer.read_from(next);
+        assert!(truncated);
+        assert_eq!(text.len(), OUTPUT_LIMIT);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_jobs_are_polled_and_killed() {
+        let jobs = JobRegistry::new();
+        let id = jobs.start("echo ready; sleep 30", None, None, &[]).unwrap();
+        assert_eq!(jobs.running(), 1);
+
+        let mut output = String::new();
+        for _ in 0..50 {
+            output.push_str(&jobs.take_output(id).unwrap().text);
+            if output.contains("ready") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(output, "ready\n");
+        assert_eq!(jobs.take_output(id).unwrap().text, "");
+
+        assert_eq!(jobs.kill(id).await.unwrap(), JobStatus::Killed);
+        assert_eq!(jobs.running(), 0);
+        assert!(jobs.take_output(id + 1).is_err());
+
+        let done = jobs.start("exit 3", None, None, &[]).unwrap();
+        let mut status = JobStatus::Running;
+        for _ in 0..50 {
+            status = jobs.take_output(done).unwrap().status;
+            if !status.is_running() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status, JobStatus::Exited(3));
+    }
+}
@@ -1,6 +1,7 @@
 //! Directory listing tool
 
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::utils::gitignore::GitIgnore;
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::Path;
@@ -46,6 +47,9 @@ impl BaseTool for LsTool {
                     .collect()
             })
             .unwrap_or_default();
+        let include_ignored = request.parameters.get("include_ignored")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
         // Security check - validate path
         let path = Path::new(path_str);
@@ -65,6 +69,8 @@ impl BaseTool for LsTool {
                 let mut items = Vec::new();
                 let mut directories = Vec::new();
                 let mut files = Vec::new();
+                let gitignore = if include_ignored { GitIgnore::default() } else { GitIgnore::for_dir(path) };
+                let mut ignored = 0;
 
                 while let Some(entry) = entries.next_entry().await.map_err(|e| {
                     anyhow::anyhow!("Error reading directory entry: {}", e)
@@ -84,6 +90,12 @@ impl BaseTool for LsTool {
                         anyhow::anyhow!("Error reading metadata for '{}': {}", name, e)
                     })?;
 
+                    // Skip what .gitignore ignores
+                    if gitignore.matches(&entry_path, metadata.is_dir()) {
+                        ignored += 1;
+                        continue;
+                    }
+
                     if metadata.is_dir() {
                         directories.push(format!("    {}/", name));
                     } else {
@@ -104,13 +116,17 @@ impl BaseTool for LsTool {
                 items.extend(directories);
                 items.extend(files);
 
-                let content = items.join("\n");
                 let total_items = items.len() - 1; // Subtract 1 for the header
+                if ignored > 0 {
+                    items.push(format!("({} ignored by .gitignore)", ignored));
+                }
+                let content = items.join("\n");
 
                 let metadata = json!({
                     "path": path_str,
                     "total_items": total_items,
                     "ignore_patterns": ignore_patterns,
+                    "ignored": ignored,
                 });
 
                 Ok(ToolResponse {
@@ -134,7 +150,7 @@ impl BaseTool for LsTool {
     }
 
     fn description(&self) -> &str {
-        "List files and directories in a given path. Supports ignore patterns for filtering."
+        "List files and directories in a given path. Supports ignore patterns for filtering; files .gitignore ignores are left out."
     }
 
     fn parameters(&self) -> serde_json::Value {
@@ -151,6 +167,10 @@ impl BaseTool for LsTool {
                     "items": {
                         "type": "string"
                     }
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "List what .gitignore ignores as well"
                 }
             },
             "required": ["path"]
@@ -188,6 +208,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -217,6 +239,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -226,6 +250,40 @@ mod tests {
         assert!(!response.content.contains("ignore_me.log"));
     }
 
+    #[tokio::test]
+    async fn test_ls_skips_gitignored_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        tokio::fs::create_dir(temp_path.join("target")).await.unwrap();
+        tokio::fs::write(temp_path.join(".gitignore"), "target/\n*.log\n").await.unwrap();
+        tokio::fs::write(temp_path.join("main.rs"), "").await.unwrap();
+        tokio::fs::write(temp_path.join("debug.log"), "").await.unwrap();
+
+        let ls = |include_ignored: bool| {
+            let mut params = HashMap::new();
+            params.insert("path".to_string(), json!(temp_path.to_str().unwrap()));
+            params.insert("include_ignored".to_string(), json!(include_ignored));
+            ToolRequest {
+                tool_name: "ls".to_string(),
+                parameters: params,
+                working_directory: None,
+                permissions: ToolPermissions::default(),
+                progress: None,
+                deadline: None,
+            }
+        };
+
+        let response = LsTool::new().execute(ls(false)).await.unwrap();
+        assert!(response.content.contains("main.rs"));
+        assert!(!response.content.contains("target/"));
+        assert!(!response.content.contains("debug.log"));
+        assert!(response.content.ends_with("(2 ignored by .gitignore)"));
+
+        let response = LsTool::new().execute(ls(true)).await.unwrap();
+        assert!(response.content.contains("target/"));
+        assert!(response.content.contains("debug.log"));
+    }
+
     #[tokio::test]
     async fn test_ls_nonexistent_directory() {
         let tool = LsTool::new();
@@ -237,6 +295,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -0,0 +1,379 @@
+//! Code navigation tools backed by language servers
+//!
+//! `definition`, `references` and `hover` ask the language server of a file
+//! about the symbol at a position, which answers exactly where grepping for a
+//! name would also find unrelated matches. Servers are started on first use
+//! for the language of the file.
+
+use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::config::paths::path_display;
+use crate::lsp::{Location, LspManager};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Most locations listed in one result
+const MAX_LOCATIONS: usize = 100;
+
+/// A position in a file, zero-based as in the protocol
+#[derive(Debug, PartialEq)]
+struct Position {
+    path: PathBuf,
+    line: u32,
+    character: u32,
+}
+
+impl Position {
+    /// Read the position from the request's one-based `line` and either
+    /// `character` or the `symbol` on that line
+    fn from_request(request: &ToolRequest) -> ToolResult<Self> {
+        let file_path = request.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("file_path parameter is required"))?;
+        let path = match &request.working_directory {
+            Some(dir) if Path::new(file_path).is_relative() => Path::new(dir).join(file_path),
+            _ => PathBuf::from(file_path),
+        };
+        let line = request.parameters.get("line")
+            .and_then(|v| v.as_u64())
+            .filter(|line| *line >= 1)
+            .ok_or_else(|| anyhow::anyhow!("line parameter is required and starts at 1"))?;
+
+        let character = match request.parameters.get("character").and_then(|v| v.as_u64()) {
+            Some(character) => character.saturating_sub(1),
+            None => {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                let text = content.lines().nth(line as usize - 1)
+                    .ok_or_else(|| anyhow::anyhow!("{} has fewer than {} lines", path.display(), line))?;
+                let symbol = request.parameters.get("symbol").and_then(|v| v.as_str());
+                let offset = match symbol {
+                    Some(symbol) => text.find(symbol)
+                        .ok_or_else(|| anyhow::anyhow!("'{}' does not occur on line {}", symbol, line))?,
+                    None => text.len() - text.trim_start().len(),
+                };
+                // Positions count UTF-16 code units
+                text[..offset].encode_utf16().count() as u64
+            }
+        };
+
+        Ok(Self {
+            path,
+            line: line as u32 - 1,
+            character: character as u32,
+        })
+    }
+}
+
+/// Parameters shared by the navigation tools
+fn position_parameters() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "file_path": {
+                "type": "string",
+                "description": "The file containing the symbol"
+            },
+            "line": {
+                "type": "integer",
+                "description": "The line of the symbol, starting at 1"
+            },
+            "symbol": {
+                "type": "string",
+                "description": "The symbol on that line; its first occurrence is looked up"
+            },
+            "character": {
+                "type": "integer",
+                "description": "The column of the symbol, starting at 1, when the symbol occurs more than once on the line"
+            }
+        },
+        "required": ["file_path", "line"]
+    })
+}
+
+/// List locations as `path:line:column: text of the line`
+fn render_locations(locations: &[Location], working_directory: Option<&str>) -> String {
+    let paths = match working_directory {
+        Some(dir) => path_display().in_directory(Path::new(dir)),
+        None => path_display().clone(),
+    };
+    let mut files: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut lines = Vec::new();
+    for location in locations.iter().take(MAX_LOCATIONS) {
+        let Some(path) = location.path() else {
+            lines.push(format!("{}:{}:{}", location.uri, location.line + 1, location.character + 1));
+            continue;
+        };
+        let content = files.entry(path.clone()).or_insert_with(|| std::fs::read_to_string(&path).ok());
+        let text = content.as_deref()
+            .and_then(|content| content.lines().nth(location.line as usize))
+            .map(str::trim)
+            .unwrap_or_default();
+        lines.push(format!("{}:{}:{}: {}", paths.show(&path), location.line + 1, location.character + 1, text));
+    }
+    if locations.len() > MAX_LOCATIONS {
+        lines.push(format!("... and {} more", locations.len() - MAX_LOCATIONS));
+    }
+    lines.join("\n")
+}
+
+fn failure(error: String) -> ToolResponse {
+    ToolResponse {
+        content: String::new(),
+        success: false,
+        metadata: None,
+        error: Some(error),
+    }
+}
+
+fn locations_metadata(locations: &[Location]) -> serde_json::Value {
+    json!({
+        "locations": locations.iter().map(|location| json!({
+            "uri": location.uri,
+            "line": location.line + 1,
+            "character": location.character + 1,
+        })).collect::<Vec<_>>()
+    })
+}
+
+/// Tool finding where a symbol is defined
+pub struct DefinitionTool {
+    lsp: Arc<LspManager>,
+}
+
+impl DefinitionTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[async_trait]
+impl BaseTool for DefinitionTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let position = Position::from_request(&request)?;
+        let locations = match self.lsp.definition(&position.path, position.line, position.character).await {
+            Ok(locations) => locations,
+            Err(e) => return Ok(failure(format!("Definition lookup failed: {}", e))),
+        };
+        if locations.is_empty() {
+            return Ok(failure("No definition found at this position".to_string()));
+        }
+        Ok(ToolResponse {
+            content: render_locations(&locations, request.working_directory.as_deref()),
+            success: true,
+            metadata: Some(locations_metadata(&locations)),
+            error: None,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "definition"
+    }
+
+    fn description(&self) -> &str {
+        r#"Finds where the symbol at a position is defined, using the language server of the file.
+
+WHEN TO USE THIS TOOL:
+- Use to jump from a call, type or import to its definition
+- Prefer it over grep for names that are common or defined more than once
+
+HOW TO USE:
+- Give the file, the line (starting at 1) and the symbol on that line
+- Results list path:line:column and the text of the defining line
+
+LIMITATIONS:
+- Needs a language server for the language installed (rust-analyzer, gopls, pyright, typescript-language-server, clangd, or one configured under lsp.servers)
+- The first lookup in a project may be slow while the server indexes it"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        position_parameters()
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+}
+
+/// Tool finding the references to a symbol
+pub struct ReferencesTool {
+    lsp: Arc<LspManager>,
+}
+
+impl ReferencesTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[async_trait]
+impl BaseTool for ReferencesTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let position = Position::from_request(&request)?;
+        let locations = match self.lsp.references(&position.path, position.line, position.character).await {
+            Ok(locations) => locations,
+            Err(e) => return Ok(failure(format!("Reference lookup failed: {}", e))),
+        };
+        if locations.is_empty() {
+            return Ok(failure("No references found at this position".to_string()));
+        }
+        Ok(ToolResponse {
+            content: format!(
+                "{} references:\n{}",
+                locations.len(),
+                render_locations(&locations, request.working_directory.as_deref())
+            ),
+            success: true,
+            metadata: Some(locations_metadata(&locations)),
+            error: None,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "references"
+    }
+
+    fn description(&self) -> &str {
+        r#"Finds every reference to the symbol at a position, including its declaration, using the language server of the file.
+
+WHEN TO USE THIS TOOL:
+- Use before renaming or changing the signature of a function, type or field
+- Prefer it over grep, which also finds unrelated symbols of the same name
+
+HOW TO USE:
+- Give the file, the line (starting at 1) and the symbol on that line
+- Results list path:line:column and the text of each referencing line
+
+LIMITATIONS:
+- Needs a language server for the language installed
+- At most 100 references are listed"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        position_parameters()
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+}
+
+/// Tool showing the signature and documentation of a symbol
+pub struct HoverTool {
+    lsp: Arc<LspManager>,
+}
+
+impl HoverTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[async_trait]
+impl BaseTool for HoverTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let position = Position::from_request(&request)?;
+        match self.lsp.hover(&position.path, position.line, position.character).await {
+            Ok(Some(text)) => Ok(ToolResponse {
+                content: text,
+                success: true,
+                metadata: None,
+                error: None,
+            }),
+            Ok(None) => Ok(failure("No information about the symbol at this position".to_string())),
+            Err(e) => Ok(failure(format!("Hover lookup failed: {}", e))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "hover"
+    }
+
+    fn description(&self) -> &str {
+        r#"Shows the type, signature and documentation of the symbol at a position, using the language server of the file.
+
+WHEN TO USE THIS TOOL:
+- Use to learn the type of a variable or the signature of a function without opening its definition
+- Use to read the docs of library functions
+
+HOW TO USE:
+- Give the file, the line (starting at 1) and the symbol on that line
+
+LIMITATIONS:
+- Needs a language server for the language installed"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        position_parameters()
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::ToolPermissions;
+
+    fn request(temp_dir: &tempfile::TempDir, parameters: serde_json::Value) -> ToolRequest {
+        ToolRequest {
+            tool_name: "definition".to_string(),
+            parameters: serde_json::from_value(parameters).unwrap(),
+            working_directory: Some(temp_dir.path().display().to_string()),
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn test_position_from_symbol_or_character() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn main() {\n    let café = helper(1);\n}\n").unwrap();
+
+        let by_symbol = Position::from_request(&request(&temp_dir, json!({ "file_path": "lib.rs", "line": 2, "symbol": "helper" }))).unwrap();
+        assert_eq!(by_symbol.path, temp_dir.path().join("lib.rs"));
+        assert_eq!((by_symbol.line, by_symbol.character), (1, 15));
+
+        let by_character = Position::from_request(&request(&temp_dir, json!({ "file_path": "lib.rs", "line": 1, "character": 4 }))).unwrap();
+        assert_eq!((by_character.line, by_character.character), (0, 3));
+
+        let first_word = Position::from_request(&request(&temp_dir, json!({ "file_path": "lib.rs", "line": 2 }))).unwrap();
+        assert_eq!(first_word.character, 4);
+
+        assert!(Position::from_request(&request(&temp_dir, json!({ "file_path": "lib.rs", "line": 2, "symbol": "nope" }))).is_err());
+        assert!(Position::from_request(&request(&temp_dir, json!({ "file_path": "lib.rs", "line": 0 }))).is_err());
+    }
+
+    #[test]
+    fn test_locations_show_relative_path_and_line() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        std::fs::write(&path, "use a;\n    pub fn helper() {}\n").unwrap();
+        let location = Location {
+            uri: format!("file://{}", path.display()),
+            line: 1,
+            character: 11,
+            end_line: 1,
+            end_character: 17,
+        };
+        let rendered = render_locations(&[location], Some(&temp_dir.path().display().to_string()));
+        assert_eq!(rendered, "lib.rs:2:12: pub fn helper() {}");
+    }
+}
@@ -8,6 +8,28 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use anyhow::Result;
+use tokio::sync::mpsc;
+
+use crate::app::AppEvent;
+use crate::llm::deadline::Deadline;
+use crate::config::{paths::display_path, workspace::Workspace};
+use crate::fswatch::{FileAccess, FileTracker};
+use crate::hooks::{HookCall, HookEngine};
+use crate::index::ProjectIndex;
+use crate::lsp::LspManager;
+use crate::permission::{PermissionContext, PermissionManager};
+use crate::session::snapshots::SnapshotStore;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Parameters holding paths that are resolved against the workspace roots
+const PATH_PARAMETERS: &[&str] = &["file_path", "path", "cwd"];
+
+/// Tools whose successful calls give the agent a file's content
+const READ_TOOLS: &[&str] = &["view", "file"];
+
+/// Tools that change a file based on what the agent read of it
+const EDIT_TOOLS: &[&str] = &["edit", "multiedit", "write"];
 
 pub mod bash;
 pub mod file;
@@ -20,11 +42,27 @@ pub mod ls;
 pub mod safe;
 pub mod download;
 pub mod diagnostics;
+pub mod lsp;
 pub mod fetch;
+pub mod fetch_cache;
+pub mod git;
 pub mod view;
 pub mod write;
+pub mod progress;
+pub mod robots;
+pub mod editor;
+pub mod guards;
+pub mod jail;
+pub mod jobs;
+pub mod session_env;
+pub mod shell;
+pub mod screenshot;
+pub mod task;
+pub mod timeouts;
+pub mod tree;
+pub mod workspace_stats;
 
-pub use bash::BashTool;
+pub use bash::{BashTool, CommandCancel};
 pub use file::FileTool;
 pub use edit::EditTool;
 pub use multiedit::MultiEditTool;
@@ -35,9 +73,22 @@ pub use ls::LsTool;
 pub use safe::SafeValidator;
 pub use download::DownloadTool;
 pub use diagnostics::DiagnosticsTool;
+pub use lsp::{DefinitionTool, HoverTool, ReferencesTool};
 pub use fetch::FetchTool;
+pub use git::{GitCommitTool, GitDiffTool, GitLogTool, GitStatusTool};
 pub use view::ViewTool;
 pub use write::WriteTool;
+pub use progress::{ProgressReporter, ToolProgress};
+pub use editor::{EditorContextTool, EditorEditTool, EditorMultiEditTool, EditorWriteTool};
+pub use guards::{EditGuards, GuardViolation, GUARD_METADATA_KEY};
+pub use jail::{JailError, PathJail};
+pub use jobs::{BackgroundBashTool, JobRegistry, JobStatus};
+pub use session_env::SessionEnv;
+pub use timeouts::ToolTimeouts;
+pub use tree::TreeTool;
+pub use screenshot::ScreenshotTool;
+pub use task::TaskTool;
+pub use workspace_stats::WorkspaceStatsTool;
 
 // Re-export for easier access in tests (types defined below)
 
@@ -48,8 +99,26 @@ pub struct ToolRequest {
     pub parameters: HashMap<String, serde_json::Value>,
     pub working_directory: Option<String>,
     pub permissions: ToolPermissions,
+    /// Optional channel for reporting incremental progress
+    #[serde(skip)]
+    pub progress: Option<ProgressReporter>,
+    /// Deadline of the turn this call belongs to
+    #[serde(skip)]
+    pub deadline: Option<Deadline>,
 }
 
+/// Metadata key under which tools report tabular output
+///
+/// The value is `{"columns": [...], "rows": [[...], ...]}`; the chat view
+/// shows it as an interactive table instead of the raw content.
+pub const TABLE_METADATA_KEY: &str = "table";
+
+/// Metadata key under which tools return an image for the model to see
+///
+/// The value is `{"media_type": "image/png", "data": "<base64>"}`; the agent
+/// attaches it to the conversation after the tool results.
+pub const IMAGE_METADATA_KEY: &str = "image";
+
 /// Tool execution response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResponse {
@@ -68,6 +137,12 @@ pub struct ToolPermissions {
     pub allow_network: bool,
     pub restricted_paths: Vec<String>,
     pub yolo_mode: bool,
+    /// Directories outside the workspace roots that tools may use
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// Environment variables commands run with; all of them if unset
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
 }
 
 impl Default for ToolPermissions {
@@ -84,6 +159,8 @@ impl Default for ToolPermissions {
                 "/dev".to_string(),
             ],
             yolo_mode: false,
+            allowed_paths: Vec::new(),
+            env_allowlist: None,
         }
     }
 }
@@ -111,6 +188,16 @@ pub trait BaseTool: Send + Sync {
         true
     }
     
+    /// Whether results of this tool are retrieved context that answers can cite
+    fn is_retrieval(&self) -> bool {
+        false
+    }
+    
+    /// Whether calls of this tool may run at the same time as each other
+    fn runs_in_parallel(&self) -> bool {
+        false
+    }
+    
     /// Validate the tool request before execution
     fn validate_request(&self, request: &ToolRequest) -> ToolResult<()> {
         // Basic validation - can be overridden by specific tools
@@ -130,6 +217,17 @@ pub trait BaseTool: Send + Sync {
 pub struct ToolManager {
     tools: HashMap<String, Box<dyn BaseTool>>,
     permissions: ToolPermissions,
+    workspace: Option<Workspace>,
+    permission_manager: Option<Arc<PermissionManager>>,
+    file_tracker: Option<Arc<FileTracker>>,
+    lsp_manager: Option<Arc<LspManager>>,
+    snapshots: Option<Arc<SnapshotStore>>,
+    edit_guards: EditGuards,
+    jobs: Arc<JobRegistry>,
+    session_env: SessionEnv,
+    command_cancel: CommandCancel,
+    hooks: HookEngine,
+    timeouts: ToolTimeouts,
 }
 
 impl ToolManager {
@@ -138,6 +236,17 @@ impl ToolManager {
         let mut manager = Self {
             tools: HashMap::new(),
             permissions,
+            workspace: None,
+            permission_manager: None,
+            file_tracker: None,
+            lsp_manager: None,
+            snapshots: None,
+            edit_guards: EditGuards::default(),
+            jobs: Arc::new(JobRegistry::new()),
+            session_env: SessionEnv::new(),
+            command_cancel: CommandCancel::new(),
+            hooks: HookEngine::default(),
+            timeouts: ToolTimeouts::default(),
         };
         
         // Register default tools
@@ -145,21 +254,44 @@ impl ToolManager {
         manager
     }
     
+    /// Create a tool manager that reads context from and edits through an attached editor
+    pub fn with_editor(permissions: ToolPermissions, editor: crate::control::SharedEditor) -> Self {
+        let mut manager = Self::new(permissions);
+        manager.register_tool(Box::new(EditorContextTool::new(editor.clone())));
+        manager.register_tool(Box::new(EditorEditTool::new(editor.clone())));
+        manager.register_tool(Box::new(EditorMultiEditTool::new(editor.clone())));
+        manager.register_tool(Box::new(EditorWriteTool::new(editor)));
+        manager
+    }
+    
     /// Register all default tools
     fn register_default_tools(&mut self) {
         self.register_tool(Box::new(FileTool::new()));
         self.register_tool(Box::new(EditTool::new()));
         self.register_tool(Box::new(MultiEditTool::new()));
-        self.register_tool(Box::new(BashTool::new()));
+        self.register_tool(Box::new(
+            BashTool::new()
+                .with_session_env(self.session_env.clone())
+                .with_cancel(self.command_cancel.clone()),
+        ));
+        self.register_tool(Box::new(
+            BackgroundBashTool::new(self.jobs.clone()).with_session_env(self.session_env.clone()),
+        ));
         self.register_tool(Box::new(GrepTool::new()));
         self.register_tool(Box::new(RgTool::new()));
         self.register_tool(Box::new(GlobTool::new()));
         self.register_tool(Box::new(LsTool::new()));
+        self.register_tool(Box::new(TreeTool::new()));
         self.register_tool(Box::new(DownloadTool::new()));
         self.register_tool(Box::new(DiagnosticsTool::new(None))); // No LSP manager by default
         self.register_tool(Box::new(FetchTool::new()));
         self.register_tool(Box::new(ViewTool::new()));
         self.register_tool(Box::new(WriteTool::new()));
+        self.register_tool(Box::new(WorkspaceStatsTool::new()));
+        self.register_tool(Box::new(GitStatusTool::new()));
+        self.register_tool(Box::new(GitDiffTool::new()));
+        self.register_tool(Box::new(GitLogTool::new()));
+        self.register_tool(Box::new(GitCommitTool::new()));
     }
     
     /// Register a tool
@@ -172,18 +304,129 @@ impl ToolManager {
         let tool = self.tools.get(tool_name)
             .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", tool_name))?;
         
-        let request = ToolRequest {
+        let mut request = ToolRequest {
             tool_name: tool_name.to_string(),
-            parameters,
-            working_directory: None, // Could be set from context
+            parameters: self.resolve_paths(parameters)?,
+            working_directory: self.working_directory(),
             permissions: self.permissions.clone(),
+            progress: None,
+            deadline: None,
         };
         
         // Validate request
         tool.validate_request(&request)?;
         
         // Execute tool
-        tool.execute(request).await
+        let limit = self.timeouts.limit(tool_name, &request.parameters);
+        request.deadline = limit.map(Deadline::after);
+        let response = Self::execute_within(tool.as_ref(), request, limit).await?;
+        Ok(response.unwrap_or_else(|| timeouts::timed_out(tool_name, limit.unwrap_or_default(), String::new())))
+    }
+    
+    /// Run a tool, giving up on it once it overruns `limit`; `None` when it did
+    async fn execute_within(
+        tool: &dyn BaseTool,
+        request: ToolRequest,
+        limit: Option<Duration>,
+    ) -> ToolResult<Option<ToolResponse>> {
+        let Some(limit) = limit else {
+            return tool.execute(request).await.map(Some);
+        };
+        match tokio::time::timeout(limit + timeouts::TIMEOUT_GRACE, tool.execute(request)).await {
+            Ok(result) => result.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+    
+    /// Execute a tool call, forwarding its progress updates as application events
+    pub async fn execute_tool_call(
+        &self,
+        session_id: &str,
+        tool_call_id: &str,
+        tool_name: &str,
+        parameters: HashMap<String, serde_json::Value>,
+        event_tx: &mpsc::UnboundedSender<AppEvent>,
+        deadline: &Deadline,
+    ) -> ToolResult<ToolResponse> {
+        let tool = self.tools.get(tool_name)
+            .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", tool_name))?;
+        
+        let (reporter, mut progress_rx) = ProgressReporter::channel(tool_call_id, tool_name);
+        let mut request = ToolRequest {
+            tool_name: tool_name.to_string(),
+            parameters: self.resolve_paths(parameters)?,
+            working_directory: self.working_directory(),
+            permissions: self.permissions.clone(),
+            progress: Some(reporter),
+            deadline: Some(*deadline),
+        };
+        
+        tool.validate_request(&request)?;
+        // Refused calls are not worth asking the user about
+        let call = HookCall {
+            session_id,
+            tool_name,
+            arguments: &request.parameters,
+        };
+        let pre_hooks = self.hooks.pre_tool_use(&call).await;
+        if let Some(reason) = &pre_hooks.denied {
+            return Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("{}{}", reason, pre_hooks.appendix())),
+            });
+        }
+        let parameters = request.parameters.clone();
+        self.check_permission(tool_name, &request).await?;
+        let tracked_path = self.tracked_path(tool_name, &request);
+        let request_path = request.parameters.get("file_path").and_then(|v| v.as_str()).map(std::path::PathBuf::from);
+        if let Some(response) = self.reject_stale_edit(tool_name, tracked_path.as_deref()) {
+            return Ok(response);
+        }
+        if let Some(response) = self.reject_guarded_edit(tool_name, request_path.as_deref(), &request) {
+            return Ok(response);
+        }
+        self.snapshot_before_edit(session_id, tool_name, request_path.as_deref());
+        let content_before = self.content_before_edit(tool_name, request_path.as_deref());
+        
+        // Forward progress until the tool drops its reporter, keeping what it
+        // printed for when it overruns its limit
+        let forward_tx = event_tx.clone();
+        let forward_session = session_id.to_string();
+        let forwarder = tokio::spawn(async move {
+            let mut printed = String::new();
+            while let Some(progress) = progress_rx.recv().await {
+                if let Some(line) = &progress.output {
+                    printed.push_str(line);
+                    printed.push('\n');
+                }
+                let _ = forward_tx.send(AppEvent::ToolProgress {
+                    session_id: forward_session.clone(),
+                    progress,
+                });
+            }
+            printed
+        });
+        
+        let limit = self.timeouts.limit(tool_name, &request.parameters);
+        if let Some(limit) = limit {
+            request.deadline = Some(deadline.shortened_to(limit));
+        }
+        let execution = Self::execute_within(tool.as_ref(), request, limit);
+        let result = deadline.run(&format!("tool '{}'", tool_name), execution).await;
+        let printed = forwarder.await.unwrap_or_default();
+        let mut result = result.map(|response| {
+            response.unwrap_or_else(|| timeouts::timed_out(tool_name, limit.unwrap_or_default(), printed))
+        });
+        if let Ok(response) = &mut result {
+            self.track_files(tool_name, tracked_path.as_deref(), response);
+            self.warn_large_change(request_path.as_deref(), content_before.as_deref(), response);
+            self.report_diagnostics(tool_name, request_path.as_deref(), response).await;
+            self.run_post_hooks(session_id, tool_name, &parameters, response).await;
+            response.content.push_str(&pre_hooks.appendix());
+        }
+        result
     }
     
     /// Get list of available tools
@@ -191,6 +434,16 @@ impl ToolManager {
         self.tools.keys().cloned().collect()
     }
     
+    /// Whether the named tool retrieves citable context
+    pub fn is_retrieval(&self, tool_name: &str) -> bool {
+        self.tools.get(tool_name).is_some_and(|tool| tool.is_retrieval())
+    }
+    
+    /// Whether calls of the named tool may run at the same time
+    pub fn runs_in_parallel(&self, tool_name: &str) -> bool {
+        self.tools.get(tool_name).is_some_and(|tool| tool.runs_in_parallel())
+    }
+    
     /// Get tool definitions for LLM providers
     pub fn get_tool_definitions(&self) -> Vec<crate::llm::types::Tool> {
         self.tools.values().map(|tool| {
@@ -202,15 +455,249 @@ impl ToolManager {
         }).collect()
     }
     
+    /// Background jobs started by the agent
+    pub fn jobs(&self) -> &Arc<JobRegistry> {
+        &self.jobs
+    }
+    
+    /// Variables the session adds to the environment of commands
+    pub fn session_env(&self) -> &SessionEnv {
+        &self.session_env
+    }
+    
+    /// Stop the bash commands running now, leaving background jobs alone
+    pub fn cancel_commands(&self) {
+        self.command_cancel.cancel();
+    }
+    
     /// Update permissions
     pub fn update_permissions(&mut self, permissions: ToolPermissions) {
         self.permissions = permissions;
     }
+    
+    /// Check agent tool calls with the permission manager before running them
+    pub fn set_permission_manager(&mut self, manager: Arc<PermissionManager>) {
+        self.permission_manager = Some(manager);
+    }
+    
+    /// Fail unless the permission manager, possibly by asking the user, allows the call
+    async fn check_permission(&self, tool_name: &str, request: &ToolRequest) -> ToolResult<()> {
+        let Some(manager) = &self.permission_manager else {
+            return Ok(());
+        };
+        let context = PermissionContext::for_tool_call(tool_name, &request.parameters);
+        if !manager.check_permission(context).await? {
+            return Err(anyhow::anyhow!("Permission to run tool '{}' was denied", tool_name));
+        }
+        Ok(())
+    }
+    
+    /// Track the files the agent reads and warn it when they change on disk
+    pub fn set_file_tracker(&mut self, tracker: Arc<FileTracker>) {
+        self.file_tracker = Some(tracker);
+    }
+    
+    /// Answer symbol lookups and glob searches from the workspace's indexes
+    pub fn set_indexes(&mut self, indexes: Vec<Arc<ProjectIndex>>) {
+        self.register_tool(Box::new(GrepTool::new().with_indexes(indexes.clone())));
+        self.register_tool(Box::new(GlobTool::new().with_indexes(indexes)));
+    }
+    
+    /// File a call reads or edits, if it is one the tracker cares about
+    fn tracked_path(&self, tool_name: &str, request: &ToolRequest) -> Option<std::path::PathBuf> {
+        self.file_tracker.as_ref()?;
+        if !READ_TOOLS.contains(&tool_name) && !EDIT_TOOLS.contains(&tool_name) {
+            return None;
+        }
+        request.parameters.get("file_path")?.as_str().map(std::path::PathBuf::from)
+    }
+    
+    /// Refuse to edit a file that changed on disk since the agent read it
+    fn reject_stale_edit(&self, tool_name: &str, path: Option<&std::path::Path>) -> Option<ToolResponse> {
+        let (tracker, path) = (self.file_tracker.as_ref()?, path?);
+        if !EDIT_TOOLS.contains(&tool_name) || !tracker.is_stale(path) {
+            return None;
+        }
+        Some(ToolResponse {
+            content: String::new(),
+            success: false,
+            metadata: None,
+            error: Some(format!(
+                "{} changed on disk since you last read it. Read it again before editing it.",
+                display_path(path)
+            )),
+        })
+    }
+    
+    /// Record what the agent now knows and tell it about files changed on disk
+    fn track_files(&self, tool_name: &str, path: Option<&std::path::Path>, response: &mut ToolResponse) {
+        let Some(tracker) = &self.file_tracker else {
+            return;
+        };
+        if let Some(path) = path.filter(|_| response.success) {
+            tracker.record_read(path);
+            let access = if EDIT_TOOLS.contains(&tool_name) { FileAccess::Edit } else { FileAccess::Read };
+            tracker.record_touch(path, access);
+        }
+        for changed in tracker.take_changes() {
+            response.content.push_str(&format!(
+                "\n\nNote: {} changed on disk since you last read it.",
+                display_path(&changed)
+            ));
+        }
+    }
+    
+    /// Limit the files edit tools may change
+    pub fn set_edit_guards(&mut self, guards: EditGuards) {
+        self.edit_guards = guards;
+    }
+    
+    /// Refuse an edit that breaks one of the edit guards
+    fn reject_guarded_edit(&self, tool_name: &str, path: Option<&std::path::Path>, request: &ToolRequest) -> Option<ToolResponse> {
+        let path = path.filter(|_| EDIT_TOOLS.contains(&tool_name))?;
+        let new_size = request.parameters.get("content").and_then(|v| v.as_str()).map(|content| content.len() as u64);
+        self.edit_guards.check(path, new_size).map(|violation| violation.response(path))
+    }
+    
+    /// Text of the file an edit tool is about to change, to measure the change by
+    fn content_before_edit(&self, tool_name: &str, path: Option<&std::path::Path>) -> Option<String> {
+        let path = path.filter(|_| EDIT_TOOLS.contains(&tool_name))?;
+        std::fs::read_to_string(path).ok()
+    }
+    
+    /// Warn the agent when its edit rewrote much of the file
+    fn warn_large_change(&self, path: Option<&std::path::Path>, before: Option<&str>, response: &mut ToolResponse) {
+        let (Some(path), Some(before)) = (path, before) else {
+            return;
+        };
+        if !response.success {
+            return;
+        }
+        if let Ok(after) = std::fs::read_to_string(path) {
+            self.edit_guards.warn_changed(path, before, &after, response);
+        }
+    }
+    
+    /// Keep the content of files before the agent edits them so turns can be undone
+    pub fn set_snapshot_store(&mut self, store: Arc<SnapshotStore>) {
+        self.snapshots = Some(store);
+    }
+    
+    /// Attribute the session's following edits to `turn` when undoing
+    pub fn begin_turn(&self, session_id: &str, turn: usize) {
+        if let Some(store) = &self.snapshots {
+            store.begin_turn(session_id, turn);
+        }
+    }
+    
+    /// Snapshot the file an edit tool is about to change
+    fn snapshot_before_edit(&self, session_id: &str, tool_name: &str, path: Option<&std::path::Path>) {
+        let (Some(store), Some(path)) = (&self.snapshots, path) else {
+            return;
+        };
+        if !EDIT_TOOLS.contains(&tool_name) {
+            return;
+        }
+        if let Err(e) = store.snapshot(session_id, path) {
+            tracing::warn!("Edit of {} cannot be undone: {:#}", path.display(), e);
+        }
+    }
+    
+    /// Check files the agent modifies with their language servers
+    pub fn set_lsp_manager(&mut self, manager: Arc<LspManager>) {
+        self.lsp_manager = Some(manager);
+    }
+    
+    /// Append the errors and warnings of a file an edit tool just modified
+    async fn report_diagnostics(&self, tool_name: &str, path: Option<&std::path::Path>, response: &mut ToolResponse) {
+        let (Some(lsp_manager), Some(path)) = (&self.lsp_manager, path) else {
+            return;
+        };
+        if !EDIT_TOOLS.contains(&tool_name) || !response.success {
+            return;
+        }
+        match lsp_manager.fresh_diagnostics(path, diagnostics::DIAGNOSTICS_WAIT).await {
+            Ok(diagnostics) => {
+                if let Some(note) = diagnostics::edit_diagnostics_note(path, &diagnostics) {
+                    response.content.push_str(&note);
+                }
+            }
+            // Most files have no language server
+            Err(e) => tracing::debug!("No diagnostics for {}: {}", path.display(), e),
+        }
+    }
+    
+    /// Run hooks before and after tool calls
+    pub fn set_hooks(&mut self, hooks: HookEngine) {
+        self.hooks = hooks;
+    }
+    
+    /// Stop tool calls that run past their limits
+    pub fn set_timeouts(&mut self, timeouts: ToolTimeouts) {
+        self.timeouts = timeouts;
+    }
+    
+    /// Add what the post hooks of a finished call print to its result
+    async fn run_post_hooks(
+        &self,
+        session_id: &str,
+        tool_name: &str,
+        parameters: &HashMap<String, serde_json::Value>,
+        response: &mut ToolResponse,
+    ) {
+        let call = HookCall {
+            session_id,
+            tool_name,
+            arguments: parameters,
+        };
+        let result = serde_json::json!({
+            "success": response.success,
+            "content": response.content,
+            "error": response.error,
+        });
+        let outcome = self.hooks.post_tool_use(&call, &result).await;
+        response.content.push_str(&outcome.appendix());
+    }
+    
+    /// Resolve relative path parameters across the given workspace roots
+    pub fn set_workspace(&mut self, workspace: Workspace) {
+        self.workspace = Some(workspace);
+    }
+    
+    fn working_directory(&self) -> Option<String> {
+        self.workspace.as_ref().map(|workspace| workspace.primary().path.display().to_string())
+    }
+    
+    /// Turn relative path parameters into absolute paths inside the workspace
+    ///
+    /// Outside YOLO mode, paths leading out of the workspace roots and the
+    /// allowed directories are refused.
+    fn resolve_paths(
+        &self,
+        mut parameters: HashMap<String, serde_json::Value>,
+    ) -> ToolResult<HashMap<String, serde_json::Value>> {
+        let Some(workspace) = &self.workspace else {
+            return Ok(parameters);
+        };
+        let jail = (!self.permissions.yolo_mode).then(|| PathJail::new(workspace, &self.permissions.allowed_paths));
+        
+        for key in PATH_PARAMETERS {
+            if let Some(serde_json::Value::String(path)) = parameters.get(*key) {
+                let resolved = workspace.resolve(path)?;
+                if let Some(jail) = &jail {
+                    jail.check(&resolved)?;
+                }
+                parameters.insert(key.to_string(), serde_json::Value::String(resolved.display().to_string()));
+            }
+        }
+        Ok(parameters)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::trust::TrustStore;
     
     #[tokio::test]
     async fn test_tool_manager_creation() {
@@ -232,4 +719,141 @@ mod tests {
         assert!(!definitions.is_empty());
         assert!(definitions.iter().any(|t| t.name == "file"));
     }
+    
+    #[test]
+    fn test_relative_paths_resolve_against_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = ToolManager::new(ToolPermissions::default());
+        manager.set_workspace(Workspace::new(dir.path(), &[], &TrustStore::default()));
+        
+        let mut parameters = HashMap::new();
+        parameters.insert("file_path".to_string(), serde_json::json!("src/lib.rs"));
+        let resolved = manager.resolve_paths(parameters).unwrap();
+        
+        let expected = dir.path().join("src/lib.rs").display().to_string();
+        assert_eq!(resolved["file_path"], serde_json::json!(expected));
+    }
+    
+    #[test]
+    fn test_paths_are_jailed_to_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(dir.path()).unwrap();
+        let mut manager = ToolManager::new(ToolPermissions::default());
+        manager.set_workspace(Workspace::new(&root.join("project"), &[], &TrustStore::default()));
+        let parameters = |path: &str| HashMap::from([("path".to_string(), serde_json::json!(path))]);
+        
+        assert!(manager.resolve_paths(parameters("src")).is_ok());
+        let error = manager.resolve_paths(parameters("../other")).unwrap_err();
+        assert!(error.to_string().contains("outside the workspace"));
+        assert!(manager.resolve_paths(parameters("/etc")).is_err());
+        
+        manager.update_permissions(ToolPermissions {
+            allowed_paths: vec![root.join("other").display().to_string()],
+            ..ToolPermissions::default()
+        });
+        assert!(manager.resolve_paths(parameters("../other")).is_ok());
+        manager.update_permissions(ToolPermissions { yolo_mode: true, ..ToolPermissions::default() });
+        assert!(manager.resolve_paths(parameters("/etc")).is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_edits_of_files_changed_since_read_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "first\n").unwrap();
+        let tracker = Arc::new(FileTracker::new());
+        let mut manager = ToolManager::new(ToolPermissions { allow_write: true, ..ToolPermissions::default() });
+        manager.set_file_tracker(tracker.clone());
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let (manager, event_tx) = (&manager, &event_tx);
+        let call = move |tool: &'static str, parameters: serde_json::Value| async move {
+            let parameters = serde_json::from_value(parameters).unwrap();
+            manager
+                .execute_tool_call("session", "call", tool, parameters, event_tx, &Deadline::unbounded())
+                .await
+                .unwrap()
+        };
+        let file_path = path.display().to_string();
+        let edit = serde_json::json!({ "file_path": file_path, "old_string": "first", "new_string": "second" });
+        
+        assert!(call("view", serde_json::json!({ "file_path": file_path })).await.success);
+        std::fs::write(&path, "changed by the user\n").unwrap();
+        tracker.mark_changed(&path);
+        
+        let listing = call("ls", serde_json::json!({ "path": dir.path().display().to_string() })).await;
+        assert!(listing.content.contains("changed on disk since you last read it"));
+        let rejected = call("edit", edit).await;
+        assert!(!rejected.success);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "changed by the user\n");
+        
+        assert!(call("view", serde_json::json!({ "file_path": file_path })).await.success);
+        let edit = serde_json::json!({ "file_path": file_path, "old_string": "user", "new_string": "agent" });
+        assert!(call("edit", edit).await.success);
+        let recent = tracker.recent();
+        assert_eq!(recent.len(), 1);
+        assert!(recent[0].edited);
+    }
+    
+    /// Reports a line, then never finishes
+    struct HungTool;
+    
+    #[async_trait]
+    impl BaseTool for HungTool {
+        async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+            if let Some(progress) = &request.progress {
+                progress.output("waiting for the lock");
+            }
+            std::future::pending().await
+        }
+        
+        fn name(&self) -> &str {
+            "hung"
+        }
+        
+        fn description(&self) -> &str {
+            "Never finishes"
+        }
+        
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({ "type": "object" })
+        }
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_calls_past_their_limit_fail_with_partial_output() {
+        let mut manager = ToolManager::new(ToolPermissions { allow_execute: true, ..ToolPermissions::default() });
+        manager.register_tool(Box::new(HungTool));
+        let config = crate::config::ToolTimeoutsConfig {
+            default_ms: 200,
+            tools: HashMap::from([("bash".to_string(), 30_000)]),
+        };
+        manager.set_timeouts(ToolTimeouts::new(&crate::permission::PermissionConfig::default(), &config));
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let (manager, event_tx) = (&manager, &event_tx);
+        let call = move |tool: &'static str, parameters: serde_json::Value| async move {
+            let parameters = serde_json::from_value(parameters).unwrap();
+            let deadline = Deadline::unbounded();
+            let running = manager.execute_tool_call("session", "call", tool, parameters, event_tx, &deadline);
+            tokio::time::timeout(Duration::from_secs(10), running).await.unwrap().unwrap()
+        };
+        
+        // A tool ignoring its deadline is dropped
+        let hung = call("hung", serde_json::json!({})).await;
+        assert!(!hung.success);
+        assert_eq!(hung.error.as_deref(), Some("Tool 'hung' timed out after 200ms"));
+        assert_eq!(hung.content, "waiting for the lock\n");
+        assert_eq!(hung.metadata.unwrap()["timed_out"], true);
+        
+        // A hung command is killed at the limit the call asks for
+        let started = std::time::Instant::now();
+        let command = serde_json::json!({ "command": "echo building; sleep 30", "timeout": 300 });
+        let hung = call("bash", command).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(hung.error.as_deref(), Some("Command timed out after 300ms"));
+        assert!(hung.content.starts_with("building\n"));
+        
+        let quick = manager.execute_tool("bash", serde_json::from_value(serde_json::json!({ "command": "echo done" })).unwrap());
+        assert!(quick.await.unwrap().success);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,162 @@
+//! Progress reporting for long-running tools
+//!
+//! Tools such as downloads, test runners, or indexers can report incremental
+//! progress through the [`ProgressReporter`] carried on a [`super::ToolRequest`].
+//! The tool manager forwards these updates to the application event bus so the
+//! chat can render a live progress bar inside the tool block.
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// A single progress update emitted by a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolProgress {
+    /// ID of the tool call this update belongs to
+    pub tool_call_id: String,
+    /// Name of the tool reporting progress
+    pub tool_name: String,
+    /// Completion ratio in the range 0.0..=1.0, if known
+    pub fraction: Option<f64>,
+    /// Current step number, for step-based tools
+    pub step: Option<u64>,
+    /// Total number of steps, for step-based tools
+    pub total_steps: Option<u64>,
+    /// Short human-readable description of the current activity
+    pub message: Option<String>,
+    /// A line the tool printed, for tools streaming command output
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+impl ToolProgress {
+    /// Percentage of completion (0-100), derived from the fraction or steps
+    pub fn percent(&self) -> Option<u16> {
+        let fraction = self.fraction.or_else(|| match (self.step, self.total_steps) {
+            (Some(step), Some(total)) if total > 0 => Some(step as f64 / total as f64),
+            _ => None,
+        })?;
+        Some((fraction.clamp(0.0, 1.0) * 100.0).round() as u16)
+    }
+
+    /// Whether the update indicates an indeterminate amount of work
+    pub fn is_indeterminate(&self) -> bool {
+        self.percent().is_none()
+    }
+}
+
+/// Handle used by tools to emit progress updates
+///
+/// Reporting never fails: if nobody is listening the update is dropped.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    tool_call_id: String,
+    tool_name: String,
+    sender: mpsc::UnboundedSender<ToolProgress>,
+}
+
+impl ProgressReporter {
+    /// Create a reporter and the receiver its updates are delivered to
+    pub fn channel(
+        tool_call_id: impl Into<String>,
+        tool_name: impl Into<String>,
+    ) -> (Self, mpsc::UnboundedReceiver<ToolProgress>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let reporter = Self {
+            tool_call_id: tool_call_id.into(),
+            tool_name: tool_name.into(),
+            sender,
+        };
+        (reporter, receiver)
+    }
+
+    /// Report completion as a fraction in the range 0.0..=1.0
+    pub fn fraction(&self, fraction: f64, message: Option<String>) {
+        self.send(Some(fraction.clamp(0.0, 1.0)), None, None, message);
+    }
+
+    /// Report progress as a step out of a known total
+    pub fn step(&self, step: u64, total_steps: u64, message: Option<String>) {
+        self.send(None, Some(step), Some(total_steps), message);
+    }
+
+    /// Report activity without a known completion ratio
+    pub fn message(&self, message: impl Into<String>) {
+        self.send(None, None, None, Some(message.into()));
+    }
+
+    /// Report a line of output as it is printed
+    pub fn output(&self, line: impl Into<String>) {
+        let _ = self.sender.send(ToolProgress {
+            output: Some(line.into()),
+            ..self.update(None, None, None, None)
+        });
+    }
+
+    fn send(&self, fraction: Option<f64>, step: Option<u64>, total_steps: Option<u64>, message: Option<String>) {
+        let _ = self.sender.send(self.update(fraction, step, total_steps, message));
+    }
+
+    fn update(&self, fraction: Option<f64>, step: Option<u64>, total_steps: Option<u64>, message: Option<String>) -> ToolProgress {
+        ToolProgress {
+            tool_call_id: self.tool_call_id.clone(),
+            tool_name: self.tool_name.clone(),
+            fraction,
+            step,
+            total_steps,
+            message,
+            output: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reporter_delivers_updates() {
+        let (reporter, mut rx) = ProgressReporter::channel("call-1", "download");
+
+        reporter.fraction(0.5, Some("halfway".to_string()));
+        reporter.step(3, 4, None);
+        reporter.message("finishing");
+
+        let first = rx.recv().await.unwrap();
+        assert_eq!(first.tool_call_id, "call-1");
+        assert_eq!(first.tool_name, "download");
+        assert_eq!(first.percent(), Some(50));
+        assert_eq!(first.message.as_deref(), Some("halfway"));
+
+        let second = rx.recv().await.unwrap();
+        assert_eq!(second.percent(), Some(75));
+
+        let third = rx.recv().await.unwrap();
+        assert!(third.is_indeterminate());
+
+        reporter.output("Compiling goofy");
+        assert_eq!(rx.recv().await.unwrap().output.as_deref(), Some("Compiling goofy"));
+    }
+
+    #[test]
+    fn test_reporter_without_listener() {
+        let (reporter, rx) = ProgressReporter::channel("call-2", "bash");
+        drop(rx);
+
+        // Must not panic when the receiving side is gone
+        reporter.fraction(1.5, None);
+    }
+
+    #[test]
+    fn test_percent_is_clamped() {
+        let progress = ToolProgress {
+            tool_call_id: "id".to_string(),
+            tool_name: "tool".to_string(),
+            fraction: Some(1.7),
+            step: None,
+            total_steps: None,
+            message: None,
+            output: None,
+        };
+        assert_eq!(progress.percent(), Some(100));
+    }
+}
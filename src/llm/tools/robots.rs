@@ -0,0 +1,132 @@
+//! robots.txt rules of a site
+//!
+//! The rules of the groups naming the agent apply, or those of the `*`
+//! groups when none does. Of the `Allow` and `Disallow` rules matching a
+//! path the longest wins, `Allow` on a tie; patterns take `*` for any
+//! characters and a trailing `$` for the end of the path, as Google's
+//! crawler reads them.
+
+use regex::Regex;
+
+/// User agents of a group and its rules, whether each allows and its pattern
+type Group = (Vec<String>, Vec<(bool, String)>);
+
+/// The rules of a site for one agent
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    /// Whether each rule allows, its pattern's length and the pattern
+    rules: Vec<(bool, usize, Regex)>,
+}
+
+impl Robots {
+    /// Rules of a site without a robots.txt: everything is allowed
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// The rules of a robots.txt applying to `agent`
+    pub fn parse(content: &str, agent: &str) -> Self {
+        let agent = agent.to_ascii_lowercase();
+        // A group starts at the first user agent line after a rule
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_rules = true;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_rules = false;
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                rule @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    if let Some((_, rules)) = groups.last_mut() {
+                        rules.push((rule == "allow", value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let named = |name: &str| -> Vec<(bool, String)> {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|group_agent| group_agent == name))
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect()
+        };
+        let mut rules = named(&agent);
+        if !groups.iter().any(|(agents, _)| agents.contains(&agent)) {
+            rules = named("*");
+        }
+        Self {
+            rules: rules
+                .into_iter()
+                // An empty Disallow allows everything
+                .filter(|(_, pattern)| !pattern.is_empty())
+                .filter_map(|(allow, pattern)| Some((allow, pattern.len(), pattern_regex(&pattern)?)))
+                .collect(),
+        }
+    }
+
+    /// Whether a path, with its query, may be fetched
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let winner = self
+            .rules
+            .iter()
+            .filter(|(_, _, regex)| regex.is_match(path))
+            .max_by_key(|(allow, length, _)| (*length, *allow));
+        winner.is_none_or(|(allow, _, _)| *allow)
+    }
+}
+
+/// A robots.txt pattern as a regex anchored at the start of the path
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<String> = pattern.split('*').map(regex::escape).collect();
+    let mut regex = format!("^{}", parts.join(".*"));
+    if anchored {
+        regex.push('$');
+    }
+    Regex::new(&regex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_matching_rule_wins() {
+        let robots = Robots::parse(
+            "# Crawlers\nUser-agent: *\nDisallow: /private/\nAllow: /private/public-*\nDisallow: /*.pdf$\n\nUser-agent: badbot\nDisallow: /\n",
+            "goofy",
+        );
+        assert!(robots.is_allowed("/docs/intro"));
+        assert!(!robots.is_allowed("/private/keys"));
+        assert!(robots.is_allowed("/private/public-notes"));
+        assert!(!robots.is_allowed("/manual.pdf"));
+        assert!(robots.is_allowed("/manual.pdf?page=2"));
+
+        assert!(!Robots::parse("User-agent: *\nDisallow: /\n", "goofy").is_allowed("/"));
+    }
+
+    #[test]
+    fn test_groups_naming_the_agent_replace_the_default() {
+        let content = "User-agent: *\nDisallow: /\n\nUser-agent: Other\nUser-agent: Goofy\nDisallow: /admin\n";
+        let robots = Robots::parse(content, "goofy");
+        assert!(robots.is_allowed("/docs"));
+        assert!(!robots.is_allowed("/admin/users"));
+        assert!(Robots::parse("User-agent: *\nDisallow:\n", "goofy").is_allowed("/anything"));
+    }
+}
@@ -0,0 +1,274 @@
+//! Screenshot tool for capturing the screen or a single window
+//!
+//! The capture is saved as a PNG in the workspace and returned as an image
+//! attachment, so multimodal models can look at what is on screen.
+
+use super::{BaseTool, ToolRequest, ToolResponse, ToolResult, IMAGE_METADATA_KEY};
+use crate::config::paths::path_display;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
+use serde_json::json;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Directory, relative to the workspace, where screenshots are saved
+pub const SCREENSHOT_DIR: &str = ".goofy/screenshots";
+
+/// Longest side of the image sent to the model; the saved file keeps full size
+const MAX_ATTACHED_DIMENSION: u32 = 1568;
+
+/// What to capture
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureTarget {
+    /// A monitor by index, or the primary monitor
+    Screen { monitor: Option<usize> },
+    /// The first visible window whose title or application name contains the text
+    Window { title: String },
+}
+
+/// A saved screenshot
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// PNG bytes, downscaled for attaching to a message
+    pub attachment: Vec<u8>,
+}
+
+/// Whether this process runs inside an SSH session
+///
+/// Capturing there would grab the remote machine's display, which is rarely
+/// what the user sitting at the terminal expects.
+pub fn is_ssh_session() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some())
+}
+
+/// Capture the target and save it under `workspace`
+pub async fn take_screenshot(target: CaptureTarget, workspace: &Path) -> Result<Screenshot> {
+    let image = tokio::task::spawn_blocking(move || capture(&target)).await??;
+    let path = workspace
+        .join(SCREENSHOT_DIR)
+        .join(format!("screenshot-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")));
+    save(image, path).await
+}
+
+/// Grab the pixels of the target
+fn capture(target: &CaptureTarget) -> Result<RgbaImage> {
+    match target {
+        CaptureTarget::Screen { monitor } => {
+            let monitors = xcap::Monitor::all().context("Failed to list monitors")?;
+            let monitor = match monitor {
+                Some(index) => monitors
+                    .get(*index)
+                    .ok_or_else(|| anyhow!("There is no monitor {} ({} found)", index, monitors.len()))?,
+                None => monitors
+                    .iter()
+                    .find(|m| m.is_primary())
+                    .or_else(|| monitors.first())
+                    .context("No monitor found")?,
+            };
+            monitor.capture_image().context("Failed to capture the screen")
+        }
+        CaptureTarget::Window { title } => {
+            let needle = title.to_lowercase();
+            let windows = xcap::Window::all().context("Failed to list windows")?;
+            let window = windows
+                .iter()
+                .filter(|w| !w.is_minimized())
+                .find(|w| {
+                    w.title().to_lowercase().contains(&needle) || w.app_name().to_lowercase().contains(&needle)
+                })
+                .ok_or_else(|| anyhow!("No visible window matches '{}'", title))?;
+            window.capture_image().context("Failed to capture the window")
+        }
+    }
+}
+
+/// Write the capture to disk and prepare the attachment
+async fn save(image: RgbaImage, path: PathBuf) -> Result<Screenshot> {
+    let (width, height) = image.dimensions();
+    let image = DynamicImage::ImageRgba8(image);
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, encode_png(&image)?)
+        .await
+        .with_context(|| format!("Failed to save screenshot to {}", path.display()))?;
+
+    let attachment = if width.max(height) > MAX_ATTACHED_DIMENSION {
+        encode_png(&image.resize(MAX_ATTACHED_DIMENSION, MAX_ATTACHED_DIMENSION, FilterType::Triangle))?
+    } else {
+        encode_png(&image)?
+    };
+
+    Ok(Screenshot { path, width, height, attachment })
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// Screenshot tool
+pub struct ScreenshotTool {
+    allow_over_ssh: bool,
+}
+
+impl ScreenshotTool {
+    /// Create a new screenshot tool
+    pub fn new() -> Self {
+        Self { allow_over_ssh: false }
+    }
+
+    /// Allow capturing even when running over SSH
+    pub fn allow_over_ssh(mut self, allow: bool) -> Self {
+        self.allow_over_ssh = allow;
+        self
+    }
+}
+
+impl Default for ScreenshotTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl BaseTool for ScreenshotTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let target = match request.parameters.get("window").and_then(|v| v.as_str()) {
+            Some(title) if !title.trim().is_empty() => CaptureTarget::Window { title: title.trim().to_string() },
+            _ => CaptureTarget::Screen {
+                monitor: request.parameters.get("monitor").and_then(|v| v.as_u64()).map(|i| i as usize),
+            },
+        };
+        let workspace = request
+            .working_directory
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+
+        match take_screenshot(target, &workspace).await {
+            Ok(screenshot) => {
+                let shown = path_display().in_directory(&workspace).show(&screenshot.path);
+                let mut metadata = json!({
+                    "file_path": screenshot.path.to_string_lossy(),
+                    "width": screenshot.width,
+                    "height": screenshot.height,
+                });
+                metadata[IMAGE_METADATA_KEY] = json!({
+                    "media_type": "image/png",
+                    "data": BASE64.encode(&screenshot.attachment),
+                });
+                Ok(ToolResponse {
+                    content: format!(
+                        "Captured a {}x{} screenshot, saved to {}. The image is attached.",
+                        screenshot.width,
+                        screenshot.height,
+                        shown
+                    ),
+                    success: true,
+                    metadata: Some(metadata),
+                    error: None,
+                })
+            }
+            Err(e) => Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("{:#}", e)),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "screenshot"
+    }
+
+    fn description(&self) -> &str {
+        r#"Captures the screen or a single window and attaches the image to the conversation.
+
+WHEN TO USE THIS TOOL:
+- Use to check what a running GUI application, web page or terminal looks like
+- Helpful for verifying visual changes such as layout or styling fixes
+
+HOW TO USE:
+- Without parameters, the primary monitor is captured
+- Set 'window' to capture the first visible window whose title or application name contains the text
+- Set 'monitor' to capture another monitor by index
+
+LIMITATIONS:
+- Requires a graphical session; unavailable over SSH unless enabled in the config
+- Screenshots are saved in .goofy/screenshots in the workspace"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "window": {
+                    "type": "string",
+                    "description": "Part of the title or application name of the window to capture"
+                },
+                "monitor": {
+                    "type": "integer",
+                    "description": "Index of the monitor to capture (defaults to the primary monitor)"
+                }
+            }
+        })
+    }
+
+    fn validate_request(&self, request: &ToolRequest) -> ToolResult<()> {
+        if !self.allow_over_ssh && is_ssh_session() {
+            return Err(anyhow!("Screenshots are disabled over SSH"));
+        }
+        // The capture is written into the workspace
+        if !request.permissions.allow_write && !request.permissions.yolo_mode {
+            return Err(anyhow!("Tool '{}' requires write permission", self.name()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_save_downscales_attachment_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCREENSHOT_DIR).join("shot.png");
+        let screenshot = save(RgbaImage::new(3000, 1000), path.clone()).await.unwrap();
+
+        assert_eq!((screenshot.width, screenshot.height), (3000, 1000));
+        let saved = image::open(&path).unwrap();
+        assert_eq!(saved.width(), 3000);
+        let attached = image::load_from_memory(&screenshot.attachment).unwrap();
+        assert_eq!(attached.width(), MAX_ATTACHED_DIMENSION);
+    }
+
+    #[test]
+    fn test_validate_requires_write_permission() {
+        let tool = ScreenshotTool::new().allow_over_ssh(true);
+        let mut request = ToolRequest {
+            tool_name: "screenshot".to_string(),
+            parameters: HashMap::new(),
+            working_directory: None,
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
+        };
+        assert!(tool.validate_request(&request).is_err());
+
+        request.permissions.allow_write = true;
+        assert!(tool.validate_request(&request).is_ok());
+    }
+}
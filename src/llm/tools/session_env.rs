@@ -0,0 +1,187 @@
+//! Environment variables of the current session
+//!
+//! `/env set KEY=value` adds a variable to the commands the agent runs with
+//! the bash and background job tools, for as long as the session runs. The
+//! variables live in memory only: they are never written to the session store
+//! or the prompt history, and `/env list` shows them masked. Named sets of
+//! variables can be defined under `env_sets` in the configuration and loaded
+//! with `/env use <name>`; a value of `${NAME}` there is read from Goofy's own
+//! environment, so a token does not have to be written into the file.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// Prefix of the commands managing the session environment
+pub const ENV_COMMAND: &str = "/env";
+
+#[derive(Debug, Default)]
+struct Inner {
+    vars: BTreeMap<String, String>,
+    sets: HashMap<String, HashMap<String, String>>,
+}
+
+/// Variables added to the environment of the session's commands
+#[derive(Debug, Clone, Default)]
+pub struct SessionEnv {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl SessionEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the sets from the configuration available to `/env use`
+    pub fn define_sets(&self, sets: HashMap<String, HashMap<String, String>>) {
+        self.inner.write().unwrap().sets = sets;
+    }
+
+    /// Set a variable for the rest of the session
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        validate_key(key)?;
+        self.inner.write().unwrap().vars.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Remove a variable, returning whether it was set
+    pub fn unset(&self, key: &str) -> bool {
+        self.inner.write().unwrap().vars.remove(key).is_some()
+    }
+
+    /// Load the variables of a configured set
+    pub fn use_set(&self, name: &str) -> Result<Vec<String>> {
+        let mut inner = self.inner.write().unwrap();
+        let set = inner.sets.get(name).cloned().ok_or_else(|| {
+            let mut names: Vec<&String> = inner.sets.keys().collect();
+            names.sort();
+            if names.is_empty() {
+                anyhow!("No env set '{}'; define sets under env_sets in the configuration", name)
+            } else {
+                anyhow!(
+                    "No env set '{}'; configured sets: {}",
+                    name,
+                    names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+                )
+            }
+        })?;
+        let mut keys = Vec::new();
+        for (key, value) in set {
+            validate_key(&key)?;
+            inner.vars.insert(key.clone(), expand(&value)?);
+            keys.push(key);
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Variables to add to a command's environment
+    pub fn vars(&self) -> Vec<(String, String)> {
+        let inner = self.inner.read().unwrap();
+        inner.vars.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+
+    /// The variables with their values masked, one per line
+    pub fn listing(&self) -> String {
+        let inner = self.inner.read().unwrap();
+        if inner.vars.is_empty() {
+            return "No session environment variables.".to_string();
+        }
+        inner.vars.iter().map(|(key, value)| format!("{}={}", key, mask(value))).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Run an `/env` command, returning what to show
+    pub fn run_command(&self, args: &str) -> Result<String> {
+        let args = args.trim();
+        let (subcommand, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let rest = rest.trim();
+        match subcommand {
+            "" | "list" => Ok(self.listing()),
+            "set" => {
+                let (key, value) = rest.split_once('=').ok_or_else(|| anyhow!("Usage: /env set KEY=value"))?;
+                self.set(key.trim(), value)?;
+                Ok(format!("{} is set for this session.", key.trim()))
+            }
+            "unset" if !rest.is_empty() => {
+                if self.unset(rest) {
+                    Ok(format!("{} is no longer set.", rest))
+                } else {
+                    Ok(format!("{} was not set.", rest))
+                }
+            }
+            "use" if !rest.is_empty() => {
+                let keys = self.use_set(rest)?;
+                Ok(format!("Set {} from '{}'.", keys.join(", "), rest))
+            }
+            "clear" => {
+                self.inner.write().unwrap().vars.clear();
+                Ok("Session environment cleared.".to_string())
+            }
+            _ => bail!("Usage: /env [list | set KEY=value | unset KEY | use SET | clear]"),
+        }
+    }
+}
+
+/// Whether a prompt is an `/env` command, with its arguments
+pub fn env_command(prompt: &str) -> Option<&str> {
+    let rest = prompt.trim().strip_prefix(ENV_COMMAND)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    let valid = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("'{}' is not a valid variable name", key);
+    }
+    Ok(())
+}
+
+/// A value with `${NAME}` read from the environment
+fn expand(value: &str) -> Result<String> {
+    match value.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')) {
+        Some(name) => std::env::var(name).map_err(|_| anyhow!("{} is not set in the environment", name)),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// A value with all but its ends hidden
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 8 {
+        return "••••".to_string();
+    }
+    let start: String = chars[..2].iter().collect();
+    let end: String = chars[chars.len() - 2..].iter().collect();
+    format!("{}••••{}", start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_commands_manage_masked_variables() {
+        let env = SessionEnv::new();
+        assert_eq!(env_command("/env set TOKEN=ghp_secretvalue"), Some(" set TOKEN=ghp_secretvalue"));
+        assert_eq!(env_command("/environment"), None);
+
+        env.run_command("set TOKEN=ghp_secretvalue").unwrap();
+        env.run_command("set SHORT=a=b").unwrap();
+        assert!(env.run_command("set 1BAD=x").is_err());
+        assert_eq!(env.run_command("list").unwrap(), "SHORT=••••\nTOKEN=gh••••ue");
+        assert_eq!(env.vars()[0], ("SHORT".to_string(), "a=b".to_string()));
+
+        env.define_sets(HashMap::from([(
+            "staging".to_string(),
+            HashMap::from([("API_URL".to_string(), "https://staging.example.com".to_string())]),
+        )]));
+        assert_eq!(env.use_set("staging").unwrap(), ["API_URL"]);
+        assert!(env.run_command("use prod").unwrap_err().to_string().contains("staging"));
+
+        env.run_command("unset TOKEN").unwrap();
+        assert_eq!(env.vars().len(), 2);
+        env.run_command("clear").unwrap();
+        assert!(env.vars().is_empty());
+    }
+}
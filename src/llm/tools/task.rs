@@ -0,0 +1,295 @@
+//! Task tool for delegating work to sub-agents
+//!
+//! The primary agent hands a self-contained prompt to a sub-agent, which
+//! works on it in its own conversation with its own tools and returns only
+//! its final summary. This keeps long searches and investigations out of the
+//! primary conversation. Sub-agents cannot spawn further sub-agents, and the
+//! number running at once is bounded by the configuration.
+
+use super::{BaseTool, EditGuards, ToolManager, ToolPermissions, ToolRequest, ToolResponse, ToolResult};
+use crate::app::Agent;
+use crate::config::workspace::Workspace;
+use crate::llm::{Deadline, LlmProvider};
+use crate::permission::PermissionManager;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+/// System prompt given to every sub-agent
+const SUB_AGENT_SYSTEM_PROMPT: &str = "You are a sub-agent working on a single task delegated by another agent. \
+Work autonomously with the tools available; you cannot ask questions. \
+When you are done, reply with a concise summary of what you found or changed, naming the relevant files. \
+That reply is the only thing the other agent will see.";
+
+/// Task tool
+pub struct TaskTool {
+    provider: Arc<dyn LlmProvider>,
+    workspace: Option<Workspace>,
+    permission_manager: Option<Arc<PermissionManager>>,
+    edit_guards: EditGuards,
+    slots: Semaphore,
+    max_rounds: usize,
+}
+
+impl TaskTool {
+    /// Create a task tool running at most `max_concurrent` sub-agents at once
+    pub fn new(provider: Arc<dyn LlmProvider>, max_concurrent: usize, max_rounds: usize) -> Self {
+        Self {
+            provider,
+            workspace: None,
+            permission_manager: None,
+            edit_guards: EditGuards::default(),
+            slots: Semaphore::new(max_concurrent.max(1)),
+            max_rounds,
+        }
+    }
+
+    /// Resolve the sub-agents' paths across the given workspace roots
+    pub fn with_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Check the sub-agents' tool calls like the primary agent's
+    pub fn with_permission_manager(mut self, manager: Option<Arc<PermissionManager>>) -> Self {
+        self.permission_manager = manager;
+        self
+    }
+
+    /// Hold the sub-agents' edits to the same limits as the primary agent's
+    pub fn with_edit_guards(mut self, guards: EditGuards) -> Self {
+        self.edit_guards = guards;
+        self
+    }
+
+    /// Tools of a sub-agent; the task tool itself is not among them
+    fn sub_agent_tools(&self, permissions: ToolPermissions) -> ToolManager {
+        let mut tools = ToolManager::new(permissions);
+        if let Some(workspace) = &self.workspace {
+            tools.set_workspace(workspace.clone());
+        }
+        if let Some(manager) = &self.permission_manager {
+            tools.set_permission_manager(manager.clone());
+        }
+        tools.set_edit_guards(self.edit_guards.clone());
+        tools
+    }
+}
+
+/// Permissions of a sub-agent for the requested access
+///
+/// A sub-agent never gets more than the agent that spawned it.
+fn scoped_permissions(access: &str, parent: &ToolPermissions) -> ToolResult<ToolPermissions> {
+    match access {
+        "read_only" => Ok(ToolPermissions {
+            allow_write: false,
+            allow_execute: false,
+            yolo_mode: false,
+            ..parent.clone()
+        }),
+        "inherit" => Ok(parent.clone()),
+        other => Err(anyhow!("Unknown access '{}'; use 'read_only' or 'inherit'", other)),
+    }
+}
+
+#[async_trait]
+impl BaseTool for TaskTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let prompt = request.parameters.get("prompt")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing required parameter: prompt"))?
+            .to_string();
+        let access = request.parameters.get("access").and_then(|v| v.as_str()).unwrap_or("read_only");
+        let permissions = scoped_permissions(access, &request.permissions)?;
+        let deadline = request.deadline.unwrap_or_else(Deadline::unbounded);
+
+        // Wait for a free slot, but not past the end of the turn
+        let _slot = deadline.run("waiting for a sub-agent slot", self.slots.acquire()).await?;
+
+        // The sub-agent's events stay within the task
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let agent = Agent::new(
+            self.provider.clone(),
+            Arc::new(self.sub_agent_tools(permissions)),
+            event_tx,
+            format!("task-{}", uuid::Uuid::new_v4()),
+        );
+
+        match agent.run_task(prompt, Some(SUB_AGENT_SYSTEM_PROMPT.to_string()), &deadline, self.max_rounds).await {
+            Ok(outcome) => {
+                let changed_files: Vec<&str> = outcome.changes.files.iter().map(|f| f.path.as_str()).collect();
+                Ok(ToolResponse {
+                    content: outcome.answer.clone(),
+                    success: true,
+                    metadata: Some(json!({
+                        "rounds": outcome.rounds,
+                        "total_tokens": outcome.usage.total_tokens,
+                        "changed_files": changed_files,
+                    })),
+                    error: None,
+                })
+            }
+            Err(e) => Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("Sub-agent failed: {:#}", e)),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "task"
+    }
+
+    fn description(&self) -> &str {
+        r#"Launches a sub-agent to work on a task on its own and returns its summary.
+
+WHEN TO USE THIS TOOL:
+- Use for open-ended searches or investigations that would take many tool calls
+- Use to work on several independent tasks at once by calling it more than once in a single reply
+
+HOW TO USE:
+- Write a self-contained 'prompt': the sub-agent sees nothing of this conversation
+- Say exactly what information the summary should contain
+- 'access' defaults to 'read_only'; use 'inherit' only when the sub-agent has to edit files or run commands
+
+LIMITATIONS:
+- The sub-agent cannot ask questions or spawn further sub-agents
+- Only its final summary is returned, not the individual tool results"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "description": {
+                    "type": "string",
+                    "description": "Short (3-5 word) description of the task"
+                },
+                "prompt": {
+                    "type": "string",
+                    "description": "The task for the sub-agent, with all the context it needs"
+                },
+                "access": {
+                    "type": "string",
+                    "enum": ["read_only", "inherit"],
+                    "description": "Tool access of the sub-agent (defaults to read_only)"
+                }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn runs_in_parallel(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ChatRequest, LlmError, LlmResult, ProviderEvent, ProviderResponse, TokenUsage, ToolCall};
+    use futures::Stream;
+    use std::collections::HashMap;
+    use std::pin::Pin;
+    use std::sync::Mutex;
+
+    /// Provider that lists a directory first and then answers
+    #[derive(Default)]
+    struct ScriptedProvider {
+        requests: Mutex<Vec<ChatRequest>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn chat_completion(&self, request: ChatRequest) -> LlmResult<ProviderResponse> {
+            let mut requests = self.requests.lock().unwrap();
+            let first = requests.is_empty();
+            let path = request.messages[0].content.iter().find_map(|block| match block {
+                crate::llm::ContentBlock::Text { text } => Some(text.clone()),
+                _ => None,
+            });
+            requests.push(request);
+
+            let tool_calls = if first {
+                vec![ToolCall {
+                    id: "call-1".to_string(),
+                    name: "ls".to_string(),
+                    arguments: json!({ "path": path.unwrap_or_default() }),
+                }]
+            } else {
+                Vec::new()
+            };
+            Ok(ProviderResponse {
+                content: if first { String::new() } else { "The directory holds notes.txt".to_string() },
+                tool_calls,
+                usage: TokenUsage::default(),
+                finish_reason: None,
+                metadata: HashMap::new(),
+            })
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: ChatRequest,
+        ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+            Err(LlmError::StreamError("The scripted provider does not stream".to_string()))
+        }
+
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn model(&self) -> &str {
+            "scripted"
+        }
+
+        fn validate_config(&self) -> LlmResult<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sub_agent_works_in_isolation_and_returns_its_answer() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let provider = Arc::new(ScriptedProvider::default());
+        let tool = TaskTool::new(provider.clone(), 2, 5);
+
+        let request = ToolRequest {
+            tool_name: "task".to_string(),
+            parameters: HashMap::from([("prompt".to_string(), json!(dir.path().display().to_string()))]),
+            working_directory: None,
+            permissions: ToolPermissions { allow_write: true, ..ToolPermissions::default() },
+            progress: None,
+            deadline: None,
+        };
+        let response = tool.execute(request).await.unwrap();
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(response.content, "The directory holds notes.txt");
+        assert_eq!(response.metadata.unwrap()["rounds"], 2);
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].system_message.as_deref(), Some(SUB_AGENT_SYSTEM_PROMPT));
+        assert!(requests[0].tools.iter().all(|t| t.name != "task"));
+        // The second round sees the listing produced by the first
+        assert_eq!(requests[1].messages.len(), 3);
+    }
+
+    #[test]
+    fn test_read_only_access_drops_write_and_execute() {
+        let parent = ToolPermissions { allow_write: true, allow_execute: true, ..ToolPermissions::default() };
+        let scoped = scoped_permissions("read_only", &parent).unwrap();
+        assert!(!scoped.allow_write && !scoped.allow_execute);
+        assert!(scoped_permissions("inherit", &parent).unwrap().allow_write);
+        assert!(scoped_permissions("admin", &parent).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Time limits of tool calls
+//!
+//! A tool call runs within the limit configured for its tool under
+//! `tool_timeouts`, else the one its permission settings carry (two minutes
+//! for bash, thirty seconds for file, ls, grep and edit), else the default. A
+//! call can ask for a limit of its own with a `timeout` parameter in
+//! milliseconds, as bash commands do. The turn's deadline applies on top.
+//!
+//! The limit becomes the deadline of the call, so tools with timeouts of
+//! their own, like bash, stop by then and kill what they started. A call
+//! still running a moment later is dropped, and fails with the output it had
+//! reported so far.
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::ToolResponse;
+use crate::config::ToolTimeoutsConfig;
+use crate::permission::PermissionConfig;
+
+/// Parameter a call asks for its own limit with, in milliseconds
+pub const TIMEOUT_PARAMETER: &str = "timeout";
+
+/// Time tools get past their limit to stop on their own
+pub const TIMEOUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Limits of the calls of each tool
+#[derive(Debug, Clone, Default)]
+pub struct ToolTimeouts {
+    /// Limit of tools without one of their own; `None` when unlimited
+    default: Option<Duration>,
+    tools: HashMap<String, Option<Duration>>,
+}
+
+impl ToolTimeouts {
+    /// Limits from the permission settings, replaced by the configured ones
+    pub fn new(permissions: &PermissionConfig, config: &ToolTimeoutsConfig) -> Self {
+        let mut tools: HashMap<String, Option<Duration>> = permissions
+            .tool_permissions
+            .iter()
+            .filter_map(|(name, permission)| Some((name.clone(), limit_of(permission.timeout_ms?))))
+            .collect();
+        for (name, ms) in &config.tools {
+            tools.insert(name.clone(), limit_of(*ms));
+        }
+        Self {
+            default: limit_of(config.default_ms),
+            tools,
+        }
+    }
+
+    /// Limit of a call, the one it asks for or else its tool's; `None` when
+    /// unlimited
+    pub fn limit(&self, tool_name: &str, parameters: &HashMap<String, serde_json::Value>) -> Option<Duration> {
+        let requested = parameters.get(TIMEOUT_PARAMETER).and_then(|value| value.as_u64()).filter(|ms| *ms > 0);
+        match requested {
+            Some(ms) => Some(Duration::from_millis(ms)),
+            None => self.tools.get(tool_name).copied().unwrap_or(self.default),
+        }
+    }
+}
+
+/// A limit in milliseconds, 0 meaning none
+fn limit_of(ms: u64) -> Option<Duration> {
+    (ms > 0).then(|| Duration::from_millis(ms))
+}
+
+/// Result of a call stopped at its limit, holding the output it reported
+pub fn timed_out(tool_name: &str, limit: Duration, output: String) -> ToolResponse {
+    ToolResponse {
+        content: output,
+        success: false,
+        metadata: Some(json!({
+            "timed_out": true,
+            "timeout_ms": limit.as_millis() as u64,
+        })),
+        error: Some(format!("Tool '{}' timed out after {}ms", tool_name, limit.as_millis())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_and_requested_limits_take_precedence() {
+        let config = ToolTimeoutsConfig {
+            default_ms: 60_000,
+            tools: HashMap::from([("grep".to_string(), 5_000), ("task".to_string(), 0)]),
+        };
+        let timeouts = ToolTimeouts::new(&PermissionConfig::default(), &config);
+        let none = HashMap::new();
+
+        assert_eq!(timeouts.limit("bash", &none), Some(Duration::from_secs(120)));
+        assert_eq!(timeouts.limit("grep", &none), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.limit("task", &none), None);
+        assert_eq!(timeouts.limit("fetch", &none), Some(Duration::from_secs(60)));
+
+        let requested = HashMap::from([(TIMEOUT_PARAMETER.to_string(), json!(250))]);
+        assert_eq!(timeouts.limit("bash", &requested), Some(Duration::from_millis(250)));
+        let zero = HashMap::from([(TIMEOUT_PARAMETER.to_string(), json!(0))]);
+        assert_eq!(timeouts.limit("bash", &zero), Some(Duration::from_secs(120)));
+        assert_eq!(ToolTimeouts::default().limit("bash", &none), None);
+    }
+}
@@ -0,0 +1,342 @@
+//! Directory tree tool giving the agent the layout of a project
+//!
+//! One call lists a directory and those below it, down to a depth, as an
+//! indented tree, so the agent can find its way around a new repository
+//! without an `ls` per directory. Files `.gitignore` ignores and hidden files
+//! are left out unless asked for, directories below the depth are summed up
+//! by what they hold, and the listing stops at a number of entries.
+
+use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::utils::gitignore::GitIgnore;
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_DEPTH: usize = 3;
+const MAX_DEPTH: usize = 10;
+const DEFAULT_ENTRIES: usize = 300;
+const MAX_ENTRIES: usize = 2_000;
+
+/// Files listed per directory before the rest are counted instead
+const FILES_PER_DIR: usize = 50;
+
+/// What to list
+#[derive(Debug, Clone, Copy)]
+struct TreeOptions {
+    max_depth: usize,
+    max_entries: usize,
+    include_hidden: bool,
+    include_ignored: bool,
+}
+
+/// An entry of a directory
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+/// A listing being built
+struct Tree {
+    options: TreeOptions,
+    lines: Vec<String>,
+    dirs: usize,
+    files: usize,
+    ignored: usize,
+    /// Whether the listing stopped at `max_entries`
+    cut: bool,
+}
+
+impl Tree {
+    fn new(options: TreeOptions) -> Self {
+        Self {
+            options,
+            lines: Vec::new(),
+            dirs: 0,
+            files: 0,
+            ignored: 0,
+            cut: false,
+        }
+    }
+
+    /// Entries of a directory worth listing, directories first
+    fn entries(&mut self, dir: &Path, ignore: &GitIgnore) -> Vec<Entry> {
+        let Ok(read) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<Entry> = Vec::new();
+        for entry in read.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Symbolic links are listed, not followed
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if name == ".git" || (name.starts_with('.') && !self.options.include_hidden) {
+                continue;
+            }
+            if !self.options.include_ignored && ignore.matches(&entry.path(), is_dir) {
+                self.ignored += 1;
+                continue;
+            }
+            entries.push(Entry {
+                name,
+                path: entry.path(),
+                is_dir,
+            });
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+
+    fn walk(&mut self, dir: &Path, ignore: &GitIgnore, prefix: &str, depth: usize) {
+        let entries = self.entries(dir, ignore);
+        let dirs = entries.iter().filter(|entry| entry.is_dir).count();
+        let files_hidden = (entries.len() - dirs).saturating_sub(FILES_PER_DIR);
+        let listed = entries.len() - files_hidden;
+        for (index, entry) in entries.iter().take(listed).enumerate() {
+            if self.lines.len() >= self.options.max_entries {
+                self.cut = true;
+                return;
+            }
+            let last = index + 1 == listed && files_hidden == 0;
+            let connector = if last { "└── " } else { "├── " };
+            if !entry.is_dir {
+                self.files += 1;
+                self.lines.push(format!("{}{}{}", prefix, connector, entry.name));
+                continue;
+            }
+            self.dirs += 1;
+            let inner = ignore.enter(&entry.path);
+            if depth >= self.options.max_depth {
+                let summary = self.summary(&entry.path, &inner);
+                self.lines.push(format!("{}{}{}/ {}", prefix, connector, entry.name, summary));
+                continue;
+            }
+            self.lines.push(format!("{}{}{}/", prefix, connector, entry.name));
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            self.walk(&entry.path, &inner, &prefix, depth + 1);
+            if self.cut {
+                return;
+            }
+        }
+        if files_hidden > 0 && self.lines.len() < self.options.max_entries {
+            self.lines.push(format!("{}└── … {} more files", prefix, files_hidden));
+        }
+    }
+
+    /// What a directory below the depth holds, as `(3 dirs, 12 files)`
+    fn summary(&self, dir: &Path, ignore: &GitIgnore) -> String {
+        let mut counter = Tree::new(self.options);
+        let entries = counter.entries(dir, ignore);
+        let dirs = entries.iter().filter(|entry| entry.is_dir).count();
+        let files = entries.len() - dirs;
+        match (dirs, files) {
+            (0, 0) => "(empty)".to_string(),
+            (0, files) => format!("({} file{})", files, plural(files)),
+            (dirs, 0) => format!("({} dir{})", dirs, plural(dirs)),
+            (dirs, files) => format!("({} dir{}, {} file{})", dirs, plural(dirs), files, plural(files)),
+        }
+    }
+
+    fn render(self, root: &Path) -> String {
+        let mut output = format!("{}/\n", root.display());
+        for line in &self.lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "\n{} director{}, {} file{}",
+            self.dirs,
+            if self.dirs == 1 { "y" } else { "ies" },
+            self.files,
+            plural(self.files)
+        ));
+        if self.ignored > 0 {
+            output.push_str(&format!(", {} ignored by .gitignore", self.ignored));
+        }
+        if self.cut {
+            output.push_str(&format!(
+                "\nStopped at {} entries; list a subdirectory or lower max_depth to see the rest.",
+                self.options.max_entries
+            ));
+        }
+        output
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Tool listing a directory tree
+pub struct TreeTool;
+
+impl TreeTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for TreeTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let root = match request.parameters.get("path").and_then(|v| v.as_str()) {
+            Some(path) => PathBuf::from(path),
+            None => match &request.working_directory {
+                Some(dir) => PathBuf::from(dir),
+                None => std::env::current_dir()?,
+            },
+        };
+        if !root.is_dir() {
+            return Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("Not a directory: {}", root.display())),
+            });
+        }
+        let parameter = |key: &str| request.parameters.get(key).and_then(|v| v.as_u64()).map(|value| value as usize);
+        let flag = |key: &str| request.parameters.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        let options = TreeOptions {
+            max_depth: parameter("max_depth").unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH),
+            max_entries: parameter("max_entries").unwrap_or(DEFAULT_ENTRIES).clamp(1, MAX_ENTRIES),
+            include_hidden: flag("include_hidden"),
+            include_ignored: flag("include_ignored"),
+        };
+
+        let listed = root.clone();
+        let tree = tokio::task::spawn_blocking(move || {
+            let mut tree = Tree::new(options);
+            let ignore = GitIgnore::for_dir(&listed);
+            tree.walk(&listed, &ignore, "", 1);
+            tree
+        })
+        .await?;
+        let metadata = json!({
+            "path": root.display().to_string(),
+            "directories": tree.dirs,
+            "files": tree.files,
+            "ignored": tree.ignored,
+            "truncated": tree.cut,
+            "max_depth": options.max_depth,
+        });
+        Ok(ToolResponse {
+            content: tree.render(&root),
+            success: true,
+            metadata: Some(metadata),
+            error: None,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "tree"
+    }
+
+    fn description(&self) -> &str {
+        "Show the directory structure of a project as a tree, leaving out files .gitignore ignores and hidden files. Use it first in an unfamiliar repository instead of many ls calls; directories below max_depth are summed up by what they hold."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to list (defaults to the workspace root)"
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Levels of directories to descend (default 3, max 10)"
+                },
+                "max_entries": {
+                    "type": "integer",
+                    "description": "Entries listed at most (default 300, max 2000)"
+                },
+                "include_hidden": {
+                    "type": "boolean",
+                    "description": "List files and directories starting with a dot"
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "List what .gitignore ignores as well"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+
+    fn runs_in_parallel(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+    use std::fs;
+
+    async fn tree(root: &Path, parameters: serde_json::Value) -> ToolResponse {
+        let mut parameters: HashMap<String, serde_json::Value> = serde_json::from_value(parameters).unwrap();
+        parameters.insert("path".to_string(), json!(root.display().to_string()));
+        let request = ToolRequest {
+            tool_name: "tree".to_string(),
+            parameters,
+            working_directory: None,
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
+        };
+        TreeTool::new().execute(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tree_honors_gitignore_and_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/app/deep")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::write(root.join("build.log"), "").unwrap();
+        fs::write(root.join(".env"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("src/app/mod.rs"), "").unwrap();
+        fs::write(root.join("src/app/deep/a.rs"), "").unwrap();
+
+        let response = tree(root, json!({ "max_depth": 2 })).await;
+        assert!(response.success);
+        let listing: Vec<&str> = response.content.lines().skip(1).take(5).collect();
+        assert_eq!(
+            listing,
+            ["├── src/", "│   ├── app/ (1 dir, 1 file)", "│   └── main.rs", "└── Cargo.toml", ""]
+        );
+        assert!(response.content.ends_with("2 directories, 2 files, 2 ignored by .gitignore"));
+
+        let everything = tree(root, json!({ "include_ignored": true, "include_hidden": true })).await;
+        for name in ["target/", "build.log", ".env", ".gitignore", "deep/ (1 file)"] {
+            assert!(everything.content.contains(name), "{} missing", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tree_stops_at_the_entry_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        for n in 0..(FILES_PER_DIR + 5) {
+            fs::write(dir.path().join(format!("file{:03}.txt", n)), "").unwrap();
+        }
+        let response = tree(dir.path(), json!({})).await;
+        assert!(response.content.contains("└── … 5 more files"));
+
+        let response = tree(dir.path(), json!({ "max_entries": 10 })).await;
+        assert_eq!(response.content.lines().filter(|line| line.starts_with("├── file")).count(), 10);
+        assert!(response.content.contains("Stopped at 10 entries"));
+        assert_eq!(response.metadata.unwrap()["truncated"], true);
+    }
+}
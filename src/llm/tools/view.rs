@@ -140,6 +140,10 @@ impl BaseTool for ViewTool {
         "view"
     }
 
+    fn is_retrieval(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         r#"File viewing tool that reads and displays the contents of files with line numbers, allowing you to examine code, logs, or text data.
 
@@ -330,6 +334,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -359,6 +365,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -380,6 +388,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -400,6 +410,8 @@ mod tests {
             parameters: params,
             working_directory: None,
             permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -0,0 +1,119 @@
+//! Workspace statistics tool giving the agent an overview of a project
+
+use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::utils::stats::{StatsOptions, WorkspaceStats};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Tool reporting lines of code by language, largest files, test ratio and churn
+pub struct WorkspaceStatsTool;
+
+impl WorkspaceStatsTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl BaseTool for WorkspaceStatsTool {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let root = match request.parameters.get("path").and_then(|v| v.as_str()) {
+            Some(path) => PathBuf::from(path),
+            None => match &request.working_directory {
+                Some(dir) => PathBuf::from(dir),
+                None => std::env::current_dir()?,
+            },
+        };
+        let mut options = StatsOptions::default();
+        if let Some(top) = request.parameters.get("top").and_then(|v| v.as_u64()) {
+            options.top = top as usize;
+        }
+        if let Some(days) = request.parameters.get("churn_days").and_then(|v| v.as_u64()) {
+            options.churn_days = days as u32;
+        }
+
+        if !root.is_dir() {
+            return Ok(ToolResponse {
+                content: String::new(),
+                success: false,
+                metadata: None,
+                error: Some(format!("Not a directory: {}", root.display())),
+            });
+        }
+
+        let stats = WorkspaceStats::collect(&root, &options).await?;
+        Ok(ToolResponse {
+            content: stats.render(),
+            success: true,
+            metadata: Some(serde_json::to_value(&stats)?),
+            error: None,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "workspace_stats"
+    }
+
+    fn description(&self) -> &str {
+        r#"Reports statistics of a project: lines of code by language, the largest files, the ratio of test code to other code, and the files changed most in recent git history.
+
+WHEN TO USE THIS TOOL:
+- Use at the start of a larger task to get a feel for an unfamiliar project
+- Use when planning where tests are missing or which files change often
+
+LIMITATIONS:
+- Hidden directories and build output (target, node_modules, dist, build) are skipped
+- Block comments count as code, and tests inline in other files count as code"#
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The project directory (defaults to the workspace)"
+                },
+                "top": {
+                    "type": "integer",
+                    "description": "Number of largest and most changed files to list (default 10)"
+                },
+                "churn_days": {
+                    "type": "integer",
+                    "description": "Days of git history to look at for churn (default 30)"
+                }
+            }
+        })
+    }
+
+    fn requires_permission(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::ToolPermissions;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_workspace_stats_of_working_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "pub fn a() {}\n").unwrap();
+
+        let request = ToolRequest {
+            tool_name: "workspace_stats".to_string(),
+            parameters: HashMap::new(),
+            working_directory: Some(temp_dir.path().display().to_string()),
+            permissions: ToolPermissions::default(),
+            progress: None,
+            deadline: None,
+        };
+        let response = WorkspaceStatsTool::new().execute(request).await.unwrap();
+        assert!(response.success);
+        assert!(response.content.contains("Rust"));
+        assert_eq!(response.metadata.unwrap()["languages"][0]["code"], 1);
+    }
+}
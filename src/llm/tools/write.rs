@@ -1,6 +1,8 @@
 //! Write tool implementation for creating and updating files
 
 use super::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+use crate::config::paths::display_path;
+use crate::llm::changes::unified_diff;
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::Path;
@@ -108,7 +110,7 @@ impl BaseTool for WriteTool {
                     format!(" (new file, {} lines)", content.lines().count())
                 };
 
-                let result_msg = format!("File successfully written: {}{}", file_path, diff_info);
+                let result_msg = format!("File successfully written: {}{}", display_path(path), diff_info);
 
                 let response_metadata = json!({
                     "file_path": file_path,
@@ -117,6 +119,7 @@ impl BaseTool for WriteTool {
                     "additions": additions,
                     "removals": removals,
                     "was_new_file": old_content.is_empty(),
+                    "diff": unified_diff(&display_path(path), &old_content, content),
                 });
 
                 Ok(ToolResponse {
@@ -266,6 +269,8 @@ mod tests {
                 allow_write: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -298,6 +303,8 @@ mod tests {
                 allow_write: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -330,6 +337,8 @@ mod tests {
                 allow_write: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -356,6 +365,8 @@ mod tests {
                 yolo_mode: false,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let result = tool.execute(request).await;
@@ -380,6 +391,8 @@ mod tests {
                 allow_write: true,
                 ..Default::default()
             },
+            progress: None,
+            deadline: None,
         };
         
         let response = tool.execute(request).await.unwrap();
@@ -157,6 +157,9 @@ pub struct ProviderConfig {
     pub tools: Vec<Tool>,
     pub extra_headers: HashMap<String, String>,
     pub extra_body: HashMap<String, serde_json::Value>,
+    /// Deployments and sign-in of the Azure provider
+    #[serde(default)]
+    pub azure: crate::config::AzureConfig,
 }
 
 impl Default for ProviderConfig {
@@ -173,6 +176,7 @@ impl Default for ProviderConfig {
             tools: Vec::new(),
             extra_headers: HashMap::new(),
             extra_body: HashMap::new(),
+            azure: crate::config::AzureConfig::default(),
         }
     }
 }
@@ -91,7 +91,9 @@ impl LspClient {
             cmd.current_dir(root);
         }
         
-        cmd.stdin(std::process::Stdio::piped())
+        // Servers started on demand must not outlive Goofy
+        cmd.kill_on_drop(true)
+            .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
@@ -178,6 +180,8 @@ impl LspClient {
 
         // Store file info
         self.open_files.write().await.insert(uri.clone(), file_info);
+        // Diagnostics of the old content no longer apply
+        self.diagnostics.write().await.remove(&uri);
 
         // Send did open notification
         let message = LspProtocol::create_did_open_notification(&uri, &language_id, version, &content);
@@ -200,6 +204,33 @@ impl LspClient {
         Ok(())
     }
 
+    /// Whether a file is open in the language server
+    pub async fn is_file_open(&self, uri: &str) -> bool {
+        self.open_files.read().await.contains_key(uri)
+    }
+
+    /// Find where the symbol at a position is defined
+    pub async fn definition(&self, uri: &str, line: u32, character: u32) -> Result<Vec<Location>> {
+        let params = LspProtocol::create_position_params(uri, line, character);
+        let result = self.send_request(methods::TEXT_DOCUMENT_DEFINITION.to_string(), Some(params)).await?;
+        Ok(LspProtocol::parse_locations(&result))
+    }
+
+    /// Find the references to the symbol at a position, including its declaration
+    pub async fn references(&self, uri: &str, line: u32, character: u32) -> Result<Vec<Location>> {
+        let mut params = LspProtocol::create_position_params(uri, line, character);
+        params["context"] = json!({ "includeDeclaration": true });
+        let result = self.send_request(methods::TEXT_DOCUMENT_REFERENCES.to_string(), Some(params)).await?;
+        Ok(LspProtocol::parse_locations(&result))
+    }
+
+    /// Get the hover text, usually signature and docs, of the symbol at a position
+    pub async fn hover(&self, uri: &str, line: u32, character: u32) -> Result<Option<String>> {
+        let params = LspProtocol::create_position_params(uri, line, character);
+        let result = self.send_request(methods::TEXT_DOCUMENT_HOVER.to_string(), Some(params)).await?;
+        Ok(LspProtocol::parse_hover(&result))
+    }
+
     /// Get diagnostics for a file
     pub async fn get_diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
         self.diagnostics.read().await
@@ -208,6 +239,16 @@ impl LspClient {
             .unwrap_or_default()
     }
 
+    /// Whether the server published diagnostics for a file since it was opened
+    pub async fn has_diagnostics(&self, uri: &str) -> bool {
+        self.diagnostics.read().await.contains_key(uri)
+    }
+
+    /// Get the diagnostics of every file, by URI
+    pub async fn all_diagnostics(&self) -> HashMap<String, Vec<Diagnostic>> {
+        self.diagnostics.read().await.clone()
+    }
+
     /// Send a request and wait for response
     async fn send_request(&self, method: String, params: Option<Value>) -> Result<Value> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
@@ -258,6 +299,10 @@ impl LspClient {
                 "hover": {
                     "contentFormat": ["markdown", "plaintext"]
                 },
+                "definition": {
+                    "linkSupport": true
+                },
+                "references": {},
                 "completion": {
                     "completionItem": {
                         "snippetSupport": true
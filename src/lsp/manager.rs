@@ -9,6 +9,7 @@ use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
@@ -75,9 +76,11 @@ impl LspManager {
         }
 
         let server_config = self.config.servers.get(language_id)
+            .cloned()
+            .or_else(|| LspClientConfig::default_for(language_id))
             .ok_or_else(|| anyhow!("No LSP server configured for language: {}", language_id))?;
 
-        let mut client = LspClient::new(language_id.to_string(), server_config.clone());
+        let mut client = LspClient::new(language_id.to_string(), server_config);
         
         // Start the client
         client.start(self.workspace_root.clone()).await?;
@@ -167,6 +170,85 @@ impl LspManager {
         all_diagnostics
     }
 
+    /// Open a file afresh and wait up to `wait` for its diagnostics
+    ///
+    /// Returns what the server published by then, which is nothing if it
+    /// was not done checking the file.
+    pub async fn fresh_diagnostics<P: AsRef<Path>>(&self, file_path: P, wait: Duration) -> Result<Vec<Diagnostic>> {
+        let (language_id, uri) = self.prepare_file(file_path.as_ref()).await?;
+        let published = async {
+            loop {
+                if let Some(client) = self.clients.read().await.get(&language_id) {
+                    if client.has_diagnostics(&uri).await {
+                        return client.get_diagnostics(&uri).await;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        Ok(tokio::time::timeout(wait, published).await.unwrap_or_default())
+    }
+
+    /// Get the diagnostics of every file of every language server, by path
+    pub async fn all_diagnostics(&self) -> HashMap<PathBuf, Vec<Diagnostic>> {
+        let mut all_diagnostics: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+        for client in self.clients.read().await.values() {
+            for (uri, diagnostics) in client.all_diagnostics().await {
+                let path = uri.strip_prefix("file://").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(&uri));
+                all_diagnostics.entry(path).or_default().extend(diagnostics);
+            }
+        }
+        all_diagnostics
+    }
+
+    /// Find where the symbol at a zero-based position in a file is defined
+    pub async fn definition<P: AsRef<Path>>(&self, file_path: P, line: u32, character: u32) -> Result<Vec<Location>> {
+        let (language_id, uri) = self.prepare_file(file_path.as_ref()).await?;
+        let clients = self.clients.read().await;
+        let client = clients.get(&language_id)
+            .ok_or_else(|| anyhow!("LSP server for {} is not running", language_id))?;
+        client.definition(&uri, line, character).await
+    }
+
+    /// Find the references to the symbol at a zero-based position in a file
+    pub async fn references<P: AsRef<Path>>(&self, file_path: P, line: u32, character: u32) -> Result<Vec<Location>> {
+        let (language_id, uri) = self.prepare_file(file_path.as_ref()).await?;
+        let clients = self.clients.read().await;
+        let client = clients.get(&language_id)
+            .ok_or_else(|| anyhow!("LSP server for {} is not running", language_id))?;
+        client.references(&uri, line, character).await
+    }
+
+    /// Get the hover text of the symbol at a zero-based position in a file
+    pub async fn hover<P: AsRef<Path>>(&self, file_path: P, line: u32, character: u32) -> Result<Option<String>> {
+        let (language_id, uri) = self.prepare_file(file_path.as_ref()).await?;
+        let clients = self.clients.read().await;
+        let client = clients.get(&language_id)
+            .ok_or_else(|| anyhow!("LSP server for {} is not running", language_id))?;
+        client.hover(&uri, line, character).await
+    }
+
+    /// Start the server for a file and open the file in it
+    ///
+    /// Files are opened again on every lookup, as the agent may have edited
+    /// them since the last one.
+    async fn prepare_file(&self, file_path: &Path) -> Result<(String, String)> {
+        let language_id = self.get_or_start_server_for_file(file_path).await?
+            .ok_or_else(|| anyhow!("No language server available for {}", file_path.display()))?;
+        let uri = Self::path_to_uri(file_path);
+
+        let clients = self.clients.read().await;
+        let client = clients.get(&language_id)
+            .ok_or_else(|| anyhow!("LSP server for {} is not running", language_id))?;
+        let content = tokio::fs::read_to_string(file_path).await
+            .map_err(|e| anyhow!("Failed to read {}: {}", file_path.display(), e))?;
+        if client.is_file_open(&uri).await {
+            client.close_file(&uri).await?;
+        }
+        client.open_file(uri.clone(), language_id.clone(), content).await?;
+        Ok((language_id, uri))
+    }
+
     /// Get all active language servers
     pub async fn get_active_servers(&self) -> Vec<String> {
         self.clients.read().await.keys().cloned().collect()
@@ -174,7 +256,7 @@ impl LspManager {
 
     /// Check if LSP is available for a language
     pub fn has_language_server(&self, language_id: &str) -> bool {
-        self.config.servers.contains_key(language_id)
+        self.config.servers.contains_key(language_id) || LspClientConfig::default_for(language_id).is_some()
     }
 
     /// Get LSP configuration
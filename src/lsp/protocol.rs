@@ -225,6 +225,80 @@ impl LspProtocol {
         }
     }
 
+    /// Parameters naming a position in a document
+    pub fn create_position_params(uri: &str, line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        })
+    }
+
+    /// Parse the result of a definition or references request
+    ///
+    /// Servers answer with a single `Location`, a list of them, a list of
+    /// `LocationLink`s or null.
+    pub fn parse_locations(result: &Value) -> Vec<Location> {
+        let items = match result {
+            Value::Array(items) => items.iter().collect(),
+            Value::Null => Vec::new(),
+            item => vec![item],
+        };
+        items.into_iter().filter_map(Self::parse_location).collect()
+    }
+
+    fn parse_location(item: &Value) -> Option<Location> {
+        // LocationLinks name the target instead of the location itself
+        let uri = item.get("uri").or_else(|| item.get("targetUri"))?.as_str()?;
+        let range = item
+            .get("range")
+            .or_else(|| item.get("targetSelectionRange"))
+            .or_else(|| item.get("targetRange"))?;
+        let position = |key: &str| -> Option<(u32, u32)> {
+            let position = range.get(key)?;
+            Some((
+                position.get("line")?.as_u64()? as u32,
+                position.get("character")?.as_u64()? as u32,
+            ))
+        };
+        let (line, character) = position("start")?;
+        let (end_line, end_character) = position("end").unwrap_or((line, character));
+        Some(Location {
+            uri: uri.to_string(),
+            line,
+            character,
+            end_line,
+            end_character,
+        })
+    }
+
+    /// Parse the text of a hover result
+    ///
+    /// The contents are `MarkupContent`, a `MarkedString` or a list of
+    /// `MarkedString`s, which are plain strings or code in a language.
+    pub fn parse_hover(result: &Value) -> Option<String> {
+        fn marked_string(value: &Value) -> Option<String> {
+            match value {
+                Value::String(text) => Some(text.clone()),
+                Value::Object(object) => {
+                    let text = object.get("value")?.as_str()?;
+                    match object.get("language").and_then(Value::as_str) {
+                        Some(language) => Some(format!("```{}\n{}\n```", language, text)),
+                        None => Some(text.to_string()),
+                    }
+                }
+                _ => None,
+            }
+        }
+
+        let contents = result.get("contents")?;
+        let text = match contents {
+            Value::Array(items) => items.iter().filter_map(marked_string).collect::<Vec<_>>().join("\n\n"),
+            contents => marked_string(contents)?,
+        };
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
     /// Create a text document did close notification
     pub fn create_did_close_notification(uri: &str) -> LspMessage {
         LspMessage::Notification {
@@ -273,6 +347,31 @@ mod tests {
         assert!(serialized.contains("processId"));
     }
 
+    #[test]
+    fn test_parse_locations_and_links() {
+        let range = json!({ "start": { "line": 4, "character": 7 }, "end": { "line": 4, "character": 12 } });
+        let single = json!({ "uri": "file:///src/lib.rs", "range": range });
+        let locations = LspProtocol::parse_locations(&single);
+        assert_eq!(locations.len(), 1);
+        assert_eq!((locations[0].line, locations[0].end_character), (4, 12));
+        assert_eq!(locations[0].path(), Some(std::path::PathBuf::from("/src/lib.rs")));
+
+        let links = json!([{ "targetUri": "file:///src/main.rs", "targetRange": range, "targetSelectionRange": range }]);
+        assert_eq!(LspProtocol::parse_locations(&links)[0].uri, "file:///src/main.rs");
+        assert!(LspProtocol::parse_locations(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_parse_hover_contents() {
+        let markup = json!({ "contents": { "kind": "markdown", "value": "```rust\nfn main()\n```" } });
+        assert_eq!(LspProtocol::parse_hover(&markup).unwrap(), "```rust\nfn main()\n```");
+
+        let marked = json!({ "contents": [{ "language": "python", "value": "def f()" }, "Does things."] });
+        assert_eq!(LspProtocol::parse_hover(&marked).unwrap(), "```python\ndef f()\n```\n\nDoes things.");
+        assert_eq!(LspProtocol::parse_hover(&json!({ "contents": "" })), None);
+        assert_eq!(LspProtocol::parse_hover(&Value::Null), None);
+    }
+
     #[test]
     fn test_extract_content_length() {
         let header = "Content-Length: 123\\r\\nContent-Type: application/vscode-jsonrpc; charset=utf-8\\r\\n";
@@ -27,6 +27,31 @@ fn default_workspace() -> bool {
     true
 }
 
+impl LspClientConfig {
+    /// Server to start for a language no server is configured for
+    ///
+    /// Covers the usual servers of common languages, which are started when
+    /// they are installed on the `PATH`.
+    pub fn default_for(language_id: &str) -> Option<Self> {
+        let (command, args, extensions): (&str, &[&str], &[&str]) = match language_id {
+            "rust" => ("rust-analyzer", &[], &["rs"]),
+            "go" => ("gopls", &[], &["go"]),
+            "python" => ("pyright-langserver", &["--stdio"], &["py"]),
+            "typescript" => ("typescript-language-server", &["--stdio"], &["ts", "tsx"]),
+            "javascript" => ("typescript-language-server", &["--stdio"], &["js", "jsx"]),
+            "c" | "cpp" => ("clangd", &[], &["c", "h", "cpp", "cc", "cxx", "hpp"]),
+            _ => return None,
+        };
+        Some(Self {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            working_dir: None,
+            workspace: true,
+            file_extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        })
+    }
+}
+
 /// LSP configuration for all languages
 #[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct LspConfig {
@@ -105,6 +130,25 @@ pub struct Diagnostic {
     pub code: Option<String>,
 }
 
+/// A range in a file, as returned by definition and reference lookups
+///
+/// Lines and characters are zero-based, as in the protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub uri: String,
+    pub line: u32,
+    pub character: u32,
+    pub end_line: u32,
+    pub end_character: u32,
+}
+
+impl Location {
+    /// Path of the file, if the URI is a `file://` one
+    pub fn path(&self) -> Option<PathBuf> {
+        self.uri.strip_prefix("file://").map(PathBuf::from)
+    }
+}
+
 /// LSP server capabilities
 #[derive(Debug, Clone, Default)]
 pub struct ServerCapabilities {
@@ -1,8 +1,6 @@
-use anyhow::Result;
 use clap::Parser;
 use std::env;
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod cli;
 mod config;
@@ -15,9 +13,21 @@ mod mcp;
 mod utils;
 mod permission;
 mod version;
+mod control;
+mod tts;
+mod assets;
+mod fswatch;
+mod git;
+mod hooks;
+mod index;
 
 use cli::Cli;
 
+/// Lets the render profiler count allocations
+#[cfg(feature = "alloc-profiler")]
+#[global_allocator]
+static GLOBAL: tui::profiler::CountingAllocator = tui::profiler::CountingAllocator;
+
 #[tokio::main]
 async fn main() {
     // Set up panic hook for graceful error recovery
@@ -26,16 +36,12 @@ async fn main() {
         std::process::exit(1);
     }));
 
-    // Load environment variables from .env file
-    if let Err(e) = dotenvy::dotenv() {
-        // Don't error if .env file doesn't exist, just log it
-        tracing::debug!("No .env file found or error loading it: {}", e);
-    }
-
-    // Initialize logging/tracing
-    let result = init_logging();
-    if let Err(e) = result {
-        eprintln!("Failed to initialize logging: {}", e);
+    let cli = Cli::parse();
+    
+    // Logs go to stderr or the log file, never to stdout
+    let log_file = cli.log_file.clone();
+    if let Err(e) = utils::logging::init(log_file.as_deref()) {
+        eprintln!("Failed to initialize logging: {:#}", e);
         std::process::exit(1);
     }
 
@@ -45,25 +51,16 @@ async fn main() {
     }
 
     // Execute CLI command
-    if let Err(e) = execute().await {
+    if let Err(e) = cli.execute().await {
         error!("Application error: {}", e);
+        // The user still sees why the command failed when logs go to a file
+        if log_file.is_some() {
+            eprintln!("Error: {:#}", e);
+        }
         std::process::exit(1);
     }
 }
 
-fn init_logging() -> Result<()> {
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| "goofy=info".into());
-
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
-        .try_init()
-        .map_err(|e| anyhow::anyhow!("Failed to initialize tracing: {}", e))?;
-
-    Ok(())
-}
-
 async fn start_profiling_server(port: &str) {
     let addr = format!("127.0.0.1:{}", port);
     info!("Starting profiling server on http://{}", addr);
@@ -74,8 +71,3 @@ async fn start_profiling_server(port: &str) {
         info!("Profiling server would be running on {}", addr);
     });
 }
-
-async fn execute() -> Result<()> {
-    let cli = Cli::parse();
-    cli.execute().await
-}
\ No newline at end of file
@@ -0,0 +1,221 @@
+//! MCP client and the set of configured servers
+//!
+//! A [`McpClient`] speaks the protocol over any [`McpTransport`], so local
+//! (stdio) and remote (HTTP, SSE) servers are handled alike and can be mixed
+//! freely in one session.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::tool::McpToolAdapter;
+use super::transport::{self, McpTransport};
+use super::types::{methods, McpClientCapabilities, McpConfig, McpServerConfig, McpSettings, McpTool, McpToolResult};
+use crate::llm::tools::ToolManager;
+
+/// Protocol revision announced during initialization
+pub const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Connection to one initialized MCP server
+pub struct McpClient {
+    name: String,
+    transport: Box<dyn McpTransport>,
+}
+
+impl McpClient {
+    /// Connect to a server and perform the initialization handshake
+    pub async fn connect(config: &McpServerConfig, settings: &McpSettings) -> Result<Self> {
+        let handshake = async {
+            let transport = transport::connect(&config.transport, Duration::from_millis(settings.timeout_ms)).await?;
+            let client = Self::new(config.name.clone(), transport);
+            client.initialize().await?;
+            Ok::<_, anyhow::Error>(client)
+        };
+        tokio::time::timeout(Duration::from_millis(config.init_timeout_ms), handshake)
+            .await
+            .with_context(|| format!("MCP server '{}' did not initialize in time", config.name))?
+            .with_context(|| format!("Failed to initialize MCP server '{}'", config.name))
+    }
+
+    /// Wrap an already open transport
+    pub fn new(name: String, transport: Box<dyn McpTransport>) -> Self {
+        Self { name, transport }
+    }
+
+    /// Server name from the configuration
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": McpClientCapabilities::default(),
+            "clientInfo": { "name": "goofy", "version": env!("CARGO_PKG_VERSION") },
+        });
+        self.transport.request(methods::INITIALIZE, Some(params)).await?;
+        self.transport.notify(methods::INITIALIZED, None).await
+    }
+
+    /// List the server's tools, following pagination
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = cursor.as_ref().map(|c| json!({ "cursor": c }));
+            let page = self.transport.request(methods::LIST_TOOLS, params).await?;
+            let page_tools: Vec<McpTool> = serde_json::from_value(page.get("tools").cloned().unwrap_or(json!([])))
+                .with_context(|| format!("Invalid tool list from MCP server '{}'", self.name))?;
+            tools.extend(page_tools);
+
+            cursor = page.get("nextCursor").and_then(Value::as_str).map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Call a tool on the server
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<McpToolResult> {
+        let result = self
+            .transport
+            .request(methods::CALL_TOOL, Some(json!({ "name": name, "arguments": arguments })))
+            .await?;
+        serde_json::from_value(result)
+            .with_context(|| format!("Invalid result for tool '{}' from MCP server '{}'", name, self.name))
+    }
+
+    /// Close the connection
+    pub async fn close(&self) -> Result<()> {
+        self.transport.close().await
+    }
+}
+
+/// All connected MCP servers
+#[derive(Default)]
+pub struct McpManager {
+    clients: Vec<Arc<McpClient>>,
+}
+
+impl McpManager {
+    /// Connect to every enabled server concurrently
+    ///
+    /// A server that fails to start is logged and skipped so one broken
+    /// entry does not keep the others from working.
+    pub async fn connect(config: &McpConfig) -> Self {
+        if !config.settings.enabled {
+            return Self::default();
+        }
+
+        let connections = config
+            .servers
+            .iter()
+            .filter(|(_, server)| server.enabled)
+            .map(|(key, server)| {
+                let mut server = server.clone();
+                if server.name.is_empty() {
+                    server.name = key.clone();
+                }
+                async move { (McpClient::connect(&server, &config.settings).await, server.name) }
+            });
+
+        let mut clients = Vec::new();
+        for (result, name) in futures::future::join_all(connections).await {
+            match result {
+                Ok(client) => {
+                    info!("Connected to MCP server '{}'", name);
+                    clients.push(Arc::new(client));
+                }
+                Err(e) => warn!("Skipping MCP server '{}': {:#}", name, e),
+            }
+        }
+        Self { clients }
+    }
+
+    /// Connected servers
+    pub fn clients(&self) -> &[Arc<McpClient>] {
+        &self.clients
+    }
+
+    /// Register every server's tools with the tool manager
+    ///
+    /// Returns the number of tools added.
+    pub async fn register_tools(&self, tool_manager: &mut ToolManager) -> usize {
+        let mut count = 0;
+        for client in &self.clients {
+            match client.list_tools().await {
+                Ok(tools) => {
+                    for tool in tools {
+                        tool_manager.register_tool(Box::new(McpToolAdapter::new(client.clone(), tool)));
+                        count += 1;
+                    }
+                }
+                Err(e) => warn!("Failed to list tools of MCP server '{}': {:#}", client.name(), e),
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Transport answering from a script and recording what was sent
+    #[derive(Default)]
+    struct ScriptedTransport {
+        sent: Arc<Mutex<Vec<(String, Option<Value>)>>>,
+    }
+
+    #[async_trait]
+    impl McpTransport for ScriptedTransport {
+        async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+            self.sent.lock().unwrap().push((method.to_string(), params.clone()));
+            let cursor = params.as_ref().and_then(|p| p.get("cursor")).and_then(Value::as_str);
+            Ok(match (method, cursor) {
+                (methods::LIST_TOOLS, None) => json!({
+                    "tools": [{ "name": "search", "inputSchema": { "type": "object" } }],
+                    "nextCursor": "2",
+                }),
+                (methods::LIST_TOOLS, Some(_)) => json!({
+                    "tools": [{ "name": "fetch", "description": "Fetch a page", "inputSchema": { "type": "object" } }],
+                }),
+                (methods::CALL_TOOL, _) => json!({ "content": [{ "type": "text", "text": "done" }], "isError": false }),
+                _ => json!({}),
+            })
+        }
+
+        async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+            self.sent.lock().unwrap().push((method.to_string(), params));
+            Ok(())
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_lists_paginated_tools_and_calls_them() {
+        let transport = ScriptedTransport::default();
+        let sent = transport.sent.clone();
+        let client = McpClient::new("docs".to_string(), Box::new(transport));
+        client.initialize().await.unwrap();
+
+        let names: Vec<String> = client.list_tools().await.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["search", "fetch"]);
+
+        let result = client.call_tool("search", json!({ "q": "mcp" })).await.unwrap();
+        assert_eq!(result.is_error, Some(false));
+
+        let methods_sent: Vec<String> = sent.lock().unwrap().iter().map(|(m, _)| m.clone()).collect();
+        assert_eq!(
+            methods_sent,
+            [methods::INITIALIZE, methods::INITIALIZED, methods::LIST_TOOLS, methods::LIST_TOOLS, methods::CALL_TOOL]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! MCP over streamable HTTP
+//!
+//! Each message is POSTed to the server URL. The server answers a request
+//! either with a JSON body or with an event stream carrying the response.
+//! A session id handed out on initialization is echoed on later requests.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, Response, Url};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use super::sse::SseParser;
+use super::transport::{into_result, request_message, McpTransport};
+use super::types::McpMessage;
+
+/// Header carrying the session id assigned by the server
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Transport for servers speaking streamable HTTP
+pub struct HttpTransport {
+    client: Client,
+    url: Url,
+    session_id: RwLock<Option<String>>,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    /// Create a transport for the server at `url`
+    pub fn new(url: &str, headers: &HashMap<String, String>, timeout: Duration) -> Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("Invalid MCP server URL '{}'", url))?;
+        let client = Client::builder()
+            .default_headers(header_map(headers)?)
+            .build()?;
+        Ok(Self {
+            client,
+            url,
+            session_id: RwLock::new(None),
+            next_id: AtomicU64::new(1),
+            timeout,
+        })
+    }
+
+    async fn post(&self, message: &McpMessage) -> Result<Response> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .timeout(self.timeout)
+            .json(message);
+        if let Some(session_id) = self.session_id.read().await.as_deref() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach MCP server at {}", self.url))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("MCP server at {} returned {}", self.url, response.status()));
+        }
+        if let Some(session_id) = response.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            *self.session_id.write().await = Some(session_id.to_string());
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl McpTransport for HttpTransport {
+    async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let response = self.post(&request_message(Some(id), method, params)).await?;
+
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if !is_stream {
+            return into_result(response.json::<McpMessage>().await?);
+        }
+
+        // Read events until the one answering this request
+        let mut parser = SseParser::default();
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            for event in parser.feed(&chunk?) {
+                let Ok(message) = serde_json::from_str::<McpMessage>(&event.data) else {
+                    continue;
+                };
+                let McpMessage::JsonRpc { id: response_id, method: response_method, .. } = &message;
+                if response_method.is_none() && response_id.as_ref().and_then(Value::as_u64) == Some(id) {
+                    return into_result(message);
+                }
+            }
+        }
+        Err(anyhow!("MCP server closed the stream before answering '{}'", method))
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        self.post(&request_message(None, method, params)).await?;
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<()> {
+        // Let the server drop the session; servers without sessions ignore this
+        if let Some(session_id) = self.session_id.write().await.take() {
+            let _ = self
+                .client
+                .delete(self.url.clone())
+                .header(SESSION_HEADER, session_id)
+                .timeout(self.timeout)
+                .send()
+                .await;
+        }
+        Ok(())
+    }
+}
+
+/// Convert configured headers into a header map
+pub(crate) fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name '{}'", name))?;
+        let mut value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header '{}'", name))?;
+        value.set_sensitive(true);
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one canned HTTP response and return the raw request
+    async fn serve_once(listener: TcpListener, response: String) -> String {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let length = text[..header_end]
+                    .lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + length {
+                    break;
+                }
+            }
+        }
+        socket.write_all(response.as_bytes()).await.unwrap();
+        socket.shutdown().await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_streamed_response_and_session_header() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":true}}\n\n";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nmcp-session-id: abc\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let server = tokio::spawn(serve_once(listener, response));
+
+        let headers = HashMap::from([("Authorization".to_string(), "Bearer token".to_string())]);
+        let transport = HttpTransport::new(&url, &headers, Duration::from_secs(5)).unwrap();
+        let result = transport.request("initialize", None).await.unwrap();
+        assert_eq!(result, serde_json::json!({"ok": true}));
+        assert_eq!(transport.session_id.read().await.as_deref(), Some("abc"));
+
+        let request = server.await.unwrap().to_ascii_lowercase();
+        assert!(request.contains("authorization: bearer token"));
+        assert!(request.contains("accept: application/json, text/event-stream"));
+    }
+}
@@ -1,11 +1,19 @@
 //! MCP (Model Context Protocol) integration for Goofy
 //! 
 //! This module provides integration with Model Context Protocol to enable
-//! connection to external tools and services.
+//! connection to external tools and services. Servers are reached over
+//! stdio, streamable HTTP or Server-Sent Events behind a common transport
+//! trait, and their tools are registered with the agent's tool manager.
 
 pub mod types;
+pub mod transport;
+pub mod stdio;
+pub mod http;
+pub mod sse;
+pub mod client;
+pub mod tool;
 
 pub use types::*;
-
-// TODO: Complete MCP implementation in future phases
-// This is a placeholder for the MCP framework
\ No newline at end of file
+pub use transport::McpTransport;
+pub use client::{McpClient, McpManager};
+pub use tool::McpToolAdapter;
@@ -0,0 +1,207 @@
+//! Server-Sent Events support for remote MCP servers
+//!
+//! [`SseParser`] decodes event streams for both remote transports. The
+//! [`SseTransport`] implements the older HTTP+SSE transport: a long-lived GET
+//! stream first announces an `endpoint` URL, requests are POSTed there, and
+//! responses arrive as `message` events on the stream.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::header::ACCEPT;
+use reqwest::{Client, Url};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::http::header_map;
+use super::transport::{request_message, McpTransport, PendingRequests};
+use super::types::McpMessage;
+
+/// One decoded server-sent event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    /// Event type, `message` unless the server named it
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental decoder for `text/event-stream` bodies
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes of the current incomplete line
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the stream and return the events it completed
+    ///
+    /// Chunks may end anywhere, even inside a UTF-8 sequence.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: self.event.take().unwrap_or_else(|| "message".to_string()),
+                        data: self.data.join("\n"),
+                    });
+                }
+                self.event = None;
+                self.data.clear();
+                continue;
+            }
+            if line.starts_with(':') {
+                continue; // comment / keep-alive
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Transport for servers speaking the HTTP+SSE protocol
+pub struct SseTransport {
+    client: Client,
+    endpoint: Url,
+    pending: PendingRequests,
+    timeout: Duration,
+    reader: JoinHandle<()>,
+}
+
+impl SseTransport {
+    /// Open the event stream and wait for the server to announce its endpoint
+    pub async fn connect(url: &str, headers: &HashMap<String, String>, timeout: Duration) -> Result<Self> {
+        let base = Url::parse(url).with_context(|| format!("Invalid MCP server URL '{}'", url))?;
+        let client = Client::builder().default_headers(header_map(headers)?).build()?;
+
+        let response = client
+            .get(base.clone())
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .with_context(|| format!("Failed to connect to MCP server at {}", url))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("MCP server at {} returned {}", url, response.status()));
+        }
+
+        let pending = PendingRequests::default();
+        let reader_pending = pending.clone();
+        let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+        let reader = tokio::spawn(async move {
+            let mut endpoint_tx = Some(endpoint_tx);
+            let mut parser = SseParser::default();
+            let mut body = response.bytes_stream();
+            while let Some(Ok(chunk)) = body.next().await {
+                for event in parser.feed(&chunk) {
+                    match event.event.as_str() {
+                        "endpoint" => {
+                            if let Some(tx) = endpoint_tx.take() {
+                                let _ = tx.send(event.data);
+                            }
+                        }
+                        "message" => match serde_json::from_str::<McpMessage>(&event.data) {
+                            Ok(message) => reader_pending.dispatch(message).await,
+                            Err(e) => debug!("Skipping malformed MCP event: {}", e),
+                        },
+                        other => debug!("Ignoring MCP event '{}'", other),
+                    }
+                }
+            }
+            reader_pending.fail_all().await;
+        });
+
+        let endpoint = match tokio::time::timeout(timeout, endpoint_rx).await {
+            Ok(Ok(endpoint)) => base
+                .join(&endpoint)
+                .with_context(|| format!("Invalid MCP endpoint '{}'", endpoint))?,
+            Ok(Err(_)) => {
+                reader.abort();
+                return Err(anyhow!("MCP server at {} closed the stream before announcing an endpoint", url));
+            }
+            Err(_) => {
+                reader.abort();
+                return Err(anyhow!("MCP server at {} did not announce an endpoint", url));
+            }
+        };
+
+        Ok(Self {
+            client,
+            endpoint,
+            pending,
+            timeout,
+            reader,
+        })
+    }
+
+    async fn post(&self, message: &McpMessage) -> Result<()> {
+        let response = self.client.post(self.endpoint.clone()).json(message).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("MCP server returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let (id, rx) = self.pending.register().await;
+        if let Err(e) = self.post(&request_message(Some(id), method, params)).await {
+            self.pending.cancel(id).await;
+            return Err(e);
+        }
+        self.pending.wait(id, rx, method, self.timeout).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        self.post(&request_message(None, method, params)).await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.reader.abort();
+        Ok(())
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b"event: endpoint\r\ndata: /messages?session").is_empty());
+        let events = parser.feed(b"=1\r\n\r\n: keep-alive\n\ndata: {\"a\":\ndata: 1}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent { event: "endpoint".to_string(), data: "/messages?session=1".to_string() },
+                SseEvent { event: "message".to_string(), data: "{\"a\":\n1}".to_string() },
+            ]
+        );
+    }
+}
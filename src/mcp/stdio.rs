@@ -0,0 +1,126 @@
+//! MCP over a local process's standard streams
+//!
+//! Messages are newline-delimited JSON: requests go to the child's stdin and
+//! responses are read from its stdout. The child is killed when the
+//! transport is dropped.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use super::transport::{request_message, McpTransport, PendingRequests};
+use super::types::McpMessage;
+
+/// Transport to a server running as a child process
+pub struct StdioTransport {
+    child: Mutex<Child>,
+    stdin: Mutex<ChildStdin>,
+    pending: PendingRequests,
+    timeout: Duration,
+}
+
+impl StdioTransport {
+    /// Launch the server process
+    pub fn spawn(command: &str, args: &[String], env: &HashMap<String, String>, timeout: Duration) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Server logs would draw over the TUI
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start MCP server '{}'", command))?;
+
+        let stdin = child.stdin.take().context("MCP server stdin is not available")?;
+        let stdout = child.stdout.take().context("MCP server stdout is not available")?;
+
+        let pending = PendingRequests::default();
+        let reader_pending = pending.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) if line.trim().is_empty() => continue,
+                    Ok(Some(line)) => match serde_json::from_str::<McpMessage>(&line) {
+                        Ok(message) => reader_pending.dispatch(message).await,
+                        Err(e) => debug!("Skipping non-JSON-RPC output from MCP server: {}", e),
+                    },
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Failed to read from MCP server: {}", e);
+                        break;
+                    }
+                }
+            }
+            reader_pending.fail_all().await;
+        });
+
+        Ok(Self {
+            child: Mutex::new(child),
+            stdin: Mutex::new(stdin),
+            pending,
+            timeout,
+        })
+    }
+
+    async fn send(&self, message: &McpMessage) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(line.as_bytes()).await.context("Failed to write to MCP server")?;
+        stdin.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let (id, rx) = self.pending.register().await;
+        if let Err(e) = self.send(&request_message(Some(id), method, params)).await {
+            self.pending.cancel(id).await;
+            return Err(e);
+        }
+        self.pending.wait(id, rx, method, self.timeout).await
+    }
+
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        self.send(&request_message(None, method, params)).await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.child.lock().await.kill().await.ok();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stdio_round_trip() {
+        // Answer the first request with its own id
+        let script = r#"read line; id=$(echo "$line" | sed 's/.*"id":\([0-9]*\).*/\1/'); echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[]}}""#;
+        let transport = StdioTransport::spawn(
+            "sh",
+            &["-c".to_string(), script.to_string()],
+            &HashMap::new(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let result = transport.request("tools/list", None).await.unwrap();
+        assert_eq!(result, serde_json::json!({"tools": []}));
+        transport.close().await.unwrap();
+    }
+}
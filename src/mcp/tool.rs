@@ -0,0 +1,94 @@
+//! Exposing MCP server tools to the agent
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::client::McpClient;
+use super::types::{McpContent, McpTool};
+use crate::llm::tools::{BaseTool, ToolRequest, ToolResponse, ToolResult};
+
+/// A tool of an MCP server, registered alongside the built-in tools
+///
+/// Names are prefixed with the server name (`docs_search`) so servers
+/// offering tools with the same name do not clash.
+pub struct McpToolAdapter {
+    client: Arc<McpClient>,
+    tool: McpTool,
+    name: String,
+}
+
+impl McpToolAdapter {
+    pub fn new(client: Arc<McpClient>, tool: McpTool) -> Self {
+        let name = tool_name(client.name(), &tool.name);
+        Self { client, tool, name }
+    }
+}
+
+#[async_trait]
+impl BaseTool for McpToolAdapter {
+    async fn execute(&self, request: ToolRequest) -> ToolResult<ToolResponse> {
+        let arguments = Value::Object(request.parameters.into_iter().collect());
+        let result = self.client.call_tool(&self.tool.name, arguments).await?;
+        let content = result
+            .content
+            .unwrap_or_default()
+            .iter()
+            .map(content_text)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let success = !result.is_error.unwrap_or(false);
+
+        Ok(ToolResponse {
+            error: (!success).then(|| content.clone()),
+            content,
+            success,
+            metadata: Some(serde_json::json!({ "mcp_server": self.client.name() })),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.tool.description
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::to_value(&self.tool.input_schema).unwrap_or_else(|_| serde_json::json!({ "type": "object" }))
+    }
+}
+
+/// Name a server's tool is registered under
+///
+/// Characters providers reject in tool names are replaced with `_`.
+fn tool_name(server: &str, tool: &str) -> String {
+    format!("{}_{}", server, tool)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Text shown to the model for a content block
+fn content_text(content: &McpContent) -> String {
+    match content {
+        McpContent::Text { text } => text.clone(),
+        McpContent::Image { mime_type, .. } => format!("[image: {}]", mime_type),
+        McpContent::Resource { uri, text, .. } => match text {
+            Some(text) => format!("{}\n{}", uri, text),
+            None => format!("[resource: {}]", uri),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_name_is_prefixed_and_sanitized() {
+        assert_eq!(tool_name("docs", "search"), "docs_search");
+        assert_eq!(tool_name("my server", "read.file"), "my_server_read_file");
+    }
+}
@@ -0,0 +1,183 @@
+//! Transports carrying MCP JSON-RPC messages
+//!
+//! Every transport exposes the same request/notify interface, so the client
+//! does not care whether a server runs as a local process or behind a URL.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tracing::debug;
+
+use super::http::HttpTransport;
+use super::sse::SseTransport;
+use super::stdio::StdioTransport;
+use super::types::{McpMessage, McpTransportConfig};
+
+/// Connection to an MCP server
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    /// Send a request and wait for its result
+    async fn request(&self, method: &str, params: Option<Value>) -> Result<Value>;
+
+    /// Send a notification, which has no response
+    async fn notify(&self, method: &str, params: Option<Value>) -> Result<()>;
+
+    /// Shut the connection down
+    async fn close(&self) -> Result<()>;
+}
+
+/// Open a transport for a server
+///
+/// `default_timeout` bounds each request unless the server config sets its
+/// own `timeout_ms`.
+pub async fn connect(config: &McpTransportConfig, default_timeout: Duration) -> Result<Box<dyn McpTransport>> {
+    let timeout_for = |timeout_ms: &Option<u64>| timeout_ms.map(Duration::from_millis).unwrap_or(default_timeout);
+    Ok(match config {
+        McpTransportConfig::Stdio { command, args, env } => {
+            Box::new(StdioTransport::spawn(command, args, env, default_timeout)?)
+        }
+        McpTransportConfig::Http { url, headers, timeout_ms } => {
+            Box::new(HttpTransport::new(url, &expand_headers(headers)?, timeout_for(timeout_ms))?)
+        }
+        McpTransportConfig::Sse { url, headers, timeout_ms } => {
+            Box::new(SseTransport::connect(url, &expand_headers(headers)?, timeout_for(timeout_ms)).await?)
+        }
+    })
+}
+
+/// Substitute `$VAR` and `${VAR}` in header values from the environment
+pub fn expand_headers(headers: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = shellexpand::env(value)
+                .with_context(|| format!("Failed to expand header '{}'", name))?;
+            Ok((name.clone(), value.into_owned()))
+        })
+        .collect()
+}
+
+/// Build a JSON-RPC request, or a notification when `id` is `None`
+pub(crate) fn request_message(id: Option<u64>, method: &str, params: Option<Value>) -> McpMessage {
+    McpMessage::JsonRpc {
+        id: id.map(Value::from),
+        method: Some(method.to_string()),
+        params,
+        result: None,
+        error: None,
+    }
+}
+
+/// Turn a response into its result, or an error if the server reported one
+pub(crate) fn into_result(message: McpMessage) -> Result<Value> {
+    let McpMessage::JsonRpc { result, error, .. } = message;
+    match error {
+        Some(error) => Err(anyhow!("MCP error {}: {}", error.code, error.message)),
+        None => Ok(result.unwrap_or(Value::Null)),
+    }
+}
+
+/// Requests waiting for a response that arrives on a separate channel
+///
+/// Used by transports where responses come back on a shared stream (a child
+/// process's stdout or an SSE connection) and must be matched up by id.
+#[derive(Default, Clone)]
+pub(crate) struct PendingRequests {
+    next_id: Arc<AtomicU64>,
+    waiting: Arc<Mutex<HashMap<u64, oneshot::Sender<McpMessage>>>>,
+}
+
+impl PendingRequests {
+    /// Allocate an id and a receiver for its response
+    pub async fn register(&self) -> (u64, oneshot::Receiver<McpMessage>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (tx, rx) = oneshot::channel();
+        self.waiting.lock().await.insert(id, tx);
+        (id, rx)
+    }
+
+    /// Stop waiting for a request, e.g. after it timed out
+    pub async fn cancel(&self, id: u64) {
+        self.waiting.lock().await.remove(&id);
+    }
+
+    /// Route an incoming message to the request it answers
+    ///
+    /// Server-initiated requests and notifications are not supported and are
+    /// dropped.
+    pub async fn dispatch(&self, message: McpMessage) {
+        let McpMessage::JsonRpc { id, method, .. } = &message;
+        if method.is_some() {
+            debug!("Ignoring MCP server message '{}'", method.as_deref().unwrap_or_default());
+            return;
+        }
+        let Some(id) = id.as_ref().and_then(Value::as_u64) else {
+            debug!("Ignoring MCP response without a numeric id");
+            return;
+        };
+        if let Some(tx) = self.waiting.lock().await.remove(&id) {
+            let _ = tx.send(message);
+        }
+    }
+
+    /// Fail every waiting request, once the connection is gone
+    pub async fn fail_all(&self) {
+        self.waiting.lock().await.clear();
+    }
+
+    /// Wait for the response to a registered request
+    pub async fn wait(&self, id: u64, rx: oneshot::Receiver<McpMessage>, method: &str, timeout: Duration) -> Result<Value> {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(message)) => into_result(message),
+            Ok(Err(_)) => Err(anyhow!("MCP connection closed before '{}' completed", method)),
+            Err(_) => {
+                self.cancel(id).await;
+                Err(anyhow!("MCP request '{}' timed out after {:?}", method, timeout))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_pending_requests_match_responses_by_id() {
+        let pending = PendingRequests::default();
+        let (first, first_rx) = pending.register().await;
+        let (second, second_rx) = pending.register().await;
+        assert_ne!(first, second);
+
+        let response: McpMessage =
+            serde_json::from_value(json!({"jsonrpc": "2.0", "id": second, "result": {"ok": true}})).unwrap();
+        pending.dispatch(response).await;
+        let result = pending.wait(second, second_rx, "ping", Duration::from_secs(1)).await.unwrap();
+        assert_eq!(result, json!({"ok": true}));
+
+        let error: McpMessage = serde_json::from_value(
+            json!({"jsonrpc": "2.0", "id": first, "error": {"code": -32601, "message": "no such method"}}),
+        )
+        .unwrap();
+        pending.dispatch(error).await;
+        let err = pending.wait(first, first_rx, "nope", Duration::from_secs(1)).await.unwrap_err();
+        assert!(err.to_string().contains("no such method"));
+    }
+
+    #[test]
+    fn test_expand_headers() {
+        std::env::set_var("GOOFY_TEST_MCP_TOKEN", "s3cret");
+        let headers = HashMap::from([("Authorization".to_string(), "Bearer ${GOOFY_TEST_MCP_TOKEN}".to_string())]);
+        let expanded = expand_headers(&headers).unwrap();
+        assert_eq!(expanded["Authorization"], "Bearer s3cret");
+
+        let missing = HashMap::from([("X-Key".to_string(), "$GOOFY_TEST_MCP_UNSET".to_string())]);
+        assert!(expand_headers(&missing).is_err());
+    }
+}
@@ -1,15 +1,22 @@
 //! MCP types and data structures
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// MCP transport configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `stdio` launches a local server process. `http` talks to a remote server
+/// over streamable HTTP and `sse` over the older Server-Sent Events transport.
+/// Header values may reference environment variables (`"Bearer ${TOKEN}"`)
+/// so credentials stay out of the config file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum McpTransportConfig {
     #[serde(rename = "stdio")]
     Stdio {
         command: String,
+        #[serde(default)]
         args: Vec<String>,
         #[serde(default)]
         env: HashMap<String, String>,
@@ -33,13 +40,15 @@ pub enum McpTransportConfig {
 }
 
 /// MCP server configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerConfig {
-    /// Server name
+    /// Server name (defaults to its key in `servers`)
+    #[serde(default)]
     pub name: String,
     /// Transport configuration
     pub transport: McpTransportConfig,
     /// Server description
+    #[serde(default)]
     pub description: Option<String>,
     /// Whether server is enabled
     #[serde(default = "default_enabled")]
@@ -58,7 +67,7 @@ fn default_init_timeout() -> u64 {
 }
 
 /// MCP configuration for all servers
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct McpConfig {
     /// MCP servers by name
     #[serde(default)]
@@ -69,7 +78,7 @@ pub struct McpConfig {
 }
 
 /// Global MCP settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct McpSettings {
     /// Whether MCP is enabled globally
     #[serde(default = "default_enabled")]
@@ -132,6 +141,7 @@ pub struct McpError {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpTool {
     pub name: String,
+    #[serde(default)]
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: McpToolSchema,
@@ -161,7 +171,7 @@ pub struct McpToolCall {
 pub struct McpToolResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<Vec<McpContent>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
 }
 
@@ -268,6 +278,7 @@ pub struct McpClientCapabilities {
 /// Common MCP method names
 pub mod methods {
     pub const INITIALIZE: &str = "initialize";
+    pub const INITIALIZED: &str = "notifications/initialized";
     pub const PING: &str = "ping";
     pub const LIST_TOOLS: &str = "tools/list";
     pub const CALL_TOOL: &str = "tools/call";
@@ -0,0 +1,111 @@
+//! Per-project permission grants
+//!
+//! Tools the user chose to always allow in a project are recorded in the
+//! project's `.goofy/permissions.json`, so the agent does not ask again the
+//! next time it runs there. Grants are only honored in trusted workspaces;
+//! otherwise a cloned repository could ship its own grants. Stored grants can
+//! be listed and revoked with `goofy permissions`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Location of the grants file, relative to the project root
+pub const GRANTS_FILE: &str = ".goofy/permissions.json";
+
+/// Tools always allowed in one project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GrantStore {
+    /// When each tool was granted, keyed by tool name
+    #[serde(default)]
+    pub tools: BTreeMap<String, DateTime<Utc>>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl GrantStore {
+    /// Load the grants of the project rooted at `project`
+    pub fn load_for(project: &Path) -> Result<Self> {
+        Self::load(project.join(GRANTS_FILE))
+    }
+
+    /// Load grants from a file, returning an empty store if it does not exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut store = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read permission grants: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content)
+                .with_context(|| format!("Failed to parse permission grants: {}", path.display()))?
+        } else {
+            Self::default()
+        };
+        store.path = path;
+        Ok(store)
+    }
+
+    /// Persist the grants
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write permission grants: {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// File the grants are stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the tool is always allowed
+    pub fn contains(&self, tool_name: &str) -> bool {
+        self.tools.contains_key(tool_name)
+    }
+
+    /// Always allow the tool from now on
+    pub fn grant(&mut self, tool_name: &str) {
+        self.tools.entry(tool_name.to_string()).or_insert_with(Utc::now);
+    }
+
+    /// Revoke the grant for a tool, returning whether there was one
+    pub fn revoke(&mut self, tool_name: &str) -> bool {
+        self.tools.remove(tool_name).is_some()
+    }
+
+    /// Revoke every grant, returning how many there were
+    pub fn clear(&mut self) -> usize {
+        let count = self.tools.len();
+        self.tools.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grants_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = GrantStore::load_for(dir.path()).unwrap();
+        assert!(!store.contains("bash"));
+
+        store.grant("bash");
+        store.grant("edit");
+        store.save().unwrap();
+        assert!(dir.path().join(GRANTS_FILE).exists());
+
+        let mut store = GrantStore::load_for(dir.path()).unwrap();
+        assert!(store.contains("bash") && store.contains("edit"));
+        assert!(store.revoke("bash"));
+        assert!(!store.revoke("bash"));
+        assert_eq!(store.clear(), 1);
+    }
+}
@@ -1,15 +1,21 @@
 //! Permission manager for coordinating permission decisions
 
-use super::{PermissionConfig, PermissionContext, PermissionResult, PermissionValidator};
-use std::collections::HashMap;
+use super::{
+    GrantStore, PermissionConfig, PermissionContext, PermissionDecision, PermissionLevel, PermissionRequest,
+    PermissionResult, PermissionValidator,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use tracing::{info, warn};
 
 /// Manages permission decisions and user interactions
 pub struct PermissionManager {
     validator: Arc<RwLock<PermissionValidator>>,
     session_grants: Arc<RwLock<HashMap<String, bool>>>, // Cache for session-based decisions
+    session_tools: Arc<RwLock<HashSet<String>>>, // Tools the user allowed for the whole session
+    prompter: Option<mpsc::UnboundedSender<PermissionRequest>>,
+    project_grants: Option<Arc<RwLock<GrantStore>>>, // Tools always allowed in the project
 }
 
 impl PermissionManager {
@@ -18,9 +24,27 @@ impl PermissionManager {
         Self {
             validator: Arc::new(RwLock::new(PermissionValidator::new(config))),
             session_grants: Arc::new(RwLock::new(HashMap::new())),
+            session_tools: Arc::new(RwLock::new(HashSet::new())),
+            prompter: None,
+            project_grants: None,
         }
     }
 
+    /// Honor and record tools the user always allows in the project
+    pub fn with_project_grants(mut self, grants: GrantStore) -> Self {
+        self.project_grants = Some(Arc::new(RwLock::new(grants)));
+        self
+    }
+
+    /// Ask the user through `prompter` instead of deciding automatically
+    ///
+    /// Prompts for read-only operations are still decided automatically so
+    /// the user is only asked about changes, commands and network access.
+    pub fn with_prompter(mut self, prompter: mpsc::UnboundedSender<PermissionRequest>) -> Self {
+        self.prompter = Some(prompter);
+        self
+    }
+
     /// Check and potentially prompt for permission
     pub async fn check_permission(&self, context: PermissionContext) -> anyhow::Result<bool> {
         let (result, log_decisions) = {
@@ -59,7 +83,30 @@ impl PermissionManager {
                 .unwrap_or_else(|| context.command.as_ref().map(|c| c.as_str()).unwrap_or("").into())
         );
 
+        // Flagged calls are asked about every time, and an answer for the
+        // session or project does not carry over to other calls
+        if !context.flags.is_empty() {
+            let tool_name = context.tool_name.clone();
+            let Some(prompter) = &self.prompter else {
+                warn!("Flagged call of tool '{}' denied without asking: {}", tool_name, message);
+                return Ok(false);
+            };
+            let granted = Self::ask(prompter, context, message.clone()).await != PermissionDecision::Deny;
+            if !granted {
+                warn!("Flagged call of tool '{}' denied by user: {}", tool_name, message);
+            }
+            return Ok(granted);
+        }
+
         // Check if we already have a decision for this session
+        if self.session_tools.read().await.contains(&context.tool_name) {
+            return Ok(true);
+        }
+        if let Some(grants) = &self.project_grants {
+            if grants.read().await.contains(&context.tool_name) {
+                return Ok(true);
+            }
+        }
         {
             let session_grants = self.session_grants.read().await;
             if let Some(&granted) = session_grants.get(&permission_key) {
@@ -67,9 +114,28 @@ impl PermissionManager {
             }
         }
 
-        // For now, we'll implement a simple auto-deny for non-interactive scenarios
-        // In a full implementation, this would show a TUI prompt or use a callback
-        let granted = self.auto_decide_permission(&context, &message).await;
+        let granted = match &self.prompter {
+            Some(prompter) if context.risk_level != PermissionLevel::Read => {
+                // Answers are not cached: the user decides about each call
+                // unless they allowed the tool for the whole session
+                let tool_name = context.tool_name.clone();
+                let decision = Self::ask(prompter, context, message.clone()).await;
+                if matches!(decision, PermissionDecision::AllowForSession | PermissionDecision::AlwaysAllow) {
+                    self.session_tools.write().await.insert(tool_name.clone());
+                }
+                if decision == PermissionDecision::AlwaysAllow {
+                    self.grant_for_project(&tool_name).await;
+                }
+                let granted = decision != PermissionDecision::Deny;
+                if granted {
+                    info!("Permission granted by user for tool '{}': {}", tool_name, message);
+                } else {
+                    warn!("Permission denied by user for tool '{}': {}", tool_name, message);
+                }
+                return Ok(granted);
+            }
+            _ => self.auto_decide_permission(&context, &message).await,
+        };
 
         // Cache the decision for this session
         {
@@ -86,13 +152,39 @@ impl PermissionManager {
         Ok(granted)
     }
 
+    /// Ask the user and wait for the answer; no answer denies
+    async fn ask(
+        prompter: &mpsc::UnboundedSender<PermissionRequest>,
+        context: PermissionContext,
+        message: String,
+    ) -> PermissionDecision {
+        let (request, response) = PermissionRequest::new(context, message);
+        match prompter.send(request) {
+            Ok(()) => response.await.unwrap_or(PermissionDecision::Deny),
+            Err(_) => PermissionDecision::Deny,
+        }
+    }
+
+    /// Remember that the tool is always allowed in the project
+    async fn grant_for_project(&self, tool_name: &str) {
+        let Some(grants) = &self.project_grants else {
+            return;
+        };
+        let mut grants = grants.write().await;
+        grants.grant(tool_name);
+        match grants.save() {
+            Ok(()) => info!("Tool '{}' is now always allowed in this project", tool_name),
+            Err(e) => warn!("Failed to save permission grant for '{}': {:#}", tool_name, e),
+        }
+    }
+
     /// Auto-decide permission based on risk assessment
     async fn auto_decide_permission(&self, context: &PermissionContext, _message: &str) -> bool {
         // For now, implement conservative auto-decisions
         // In a full implementation, this would be more sophisticated
 
         // Always allow read operations on non-restricted paths
-        if matches!(context.risk_level, super::PermissionLevel::Read) {
+        if matches!(context.risk_level, PermissionLevel::Read) {
             return true;
         }
 
@@ -135,10 +227,23 @@ impl PermissionManager {
         validator.get_config().clone()
     }
 
+    /// Tools the user allowed for the whole session, sorted by name
+    pub async fn session_tools(&self) -> Vec<String> {
+        let mut tools: Vec<String> = self.session_tools.read().await.iter().cloned().collect();
+        tools.sort();
+        tools
+    }
+
+    /// Allow tools for the rest of the session, as when resuming one
+    pub async fn allow_for_session(&self, tools: impl IntoIterator<Item = String>) {
+        self.session_tools.write().await.extend(tools);
+    }
+
     /// Clear session-based permission cache
     pub async fn clear_session_cache(&self) {
         let mut session_grants = self.session_grants.write().await;
         session_grants.clear();
+        self.session_tools.write().await.clear();
         info!("Permission session cache cleared");
     }
 
@@ -187,7 +292,6 @@ impl PermissionManager {
 mod tests {
     use super::*;
     use std::path::PathBuf;
-    use crate::permission::PermissionLevel;
 
     #[tokio::test]
     async fn test_permission_manager_creation() {
@@ -269,4 +373,107 @@ mod tests {
         // The result depends on the auto-decision logic, but it should not use cached grant
         assert!(result.is_ok()); // /tmp is generally safe
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_prompter_decides_and_allows_for_session() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = Arc::new(PermissionManager::new(PermissionConfig::default()).with_prompter(tx));
+        let edit = || {
+            PermissionContext::new("edit".to_string(), "write".to_string())
+                .with_file_path(PathBuf::from("src/main.rs"))
+                .with_risk_level(PermissionLevel::Write)
+        };
+
+        // The check blocks until the user answers
+        let check = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.check_permission(edit()).await }
+        });
+        let request = rx.recv().await.unwrap();
+        assert_eq!(request.context.tool_name, "edit");
+        request.decide(PermissionDecision::Deny);
+        assert!(!check.await.unwrap().unwrap());
+
+        let check = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.check_permission(edit()).await }
+        });
+        rx.recv().await.unwrap().decide(PermissionDecision::AllowForSession);
+        assert!(check.await.unwrap().unwrap());
+
+        // Later calls of the tool are not asked about again
+        assert!(manager.check_permission(edit()).await.unwrap());
+        assert!(rx.try_recv().is_err());
+
+        // Reads are decided without asking
+        let read = PermissionContext::new("view".to_string(), "read".to_string());
+        assert!(manager.check_permission(read).await.unwrap());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(manager.session_tools().await, ["edit"]);
+
+        // A resumed session starts with the tools allowed before
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let resumed = PermissionManager::new(PermissionConfig::default()).with_prompter(tx);
+        resumed.allow_for_session(manager.session_tools().await).await;
+        assert!(resumed.check_permission(edit()).await.unwrap());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_flagged_commands_are_asked_about_every_time() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = Arc::new(PermissionManager::new(PermissionConfig::default()).with_prompter(tx));
+        manager.allow_for_session(vec!["bash".to_string()]).await;
+        let bash = |command: &str| {
+            let arguments = HashMap::from([("command".to_string(), serde_json::Value::from(command))]);
+            PermissionContext::for_tool_call("bash", &arguments)
+        };
+        assert!(manager.check_permission(bash("cargo build")).await.unwrap());
+        assert!(rx.try_recv().is_err());
+
+        for decision in [PermissionDecision::AllowForSession, PermissionDecision::Deny] {
+            let check = tokio::spawn({
+                let manager = manager.clone();
+                async move { manager.check_permission(bash("sudo make install")).await }
+            });
+            let request = rx.recv().await.unwrap();
+            assert!(request.message.contains("root privileges"));
+            request.decide(decision);
+            assert_eq!(check.await.unwrap().unwrap(), decision != PermissionDecision::Deny);
+        }
+
+        // Without anyone to ask, flagged calls are denied
+        let unattended = PermissionManager::new(PermissionConfig::default());
+        assert!(!unattended.check_permission(bash("sudo make install")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_always_allow_is_persisted_for_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let bash = || {
+            PermissionContext::new("bash".to_string(), "execute".to_string())
+                .with_command("cargo build".to_string())
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = Arc::new(
+            PermissionManager::new(PermissionConfig::default())
+                .with_prompter(tx)
+                .with_project_grants(GrantStore::load_for(dir.path()).unwrap()),
+        );
+        let check = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.check_permission(bash()).await }
+        });
+        rx.recv().await.unwrap().decide(PermissionDecision::AlwaysAllow);
+        assert!(check.await.unwrap().unwrap());
+
+        // A new run in the same project does not ask again
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = PermissionManager::new(PermissionConfig::default())
+            .with_prompter(tx)
+            .with_project_grants(GrantStore::load_for(dir.path()).unwrap());
+        assert!(manager.check_permission(bash()).await.unwrap());
+        assert!(rx.try_recv().is_err());
+    }
+}
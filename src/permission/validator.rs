@@ -142,6 +142,13 @@ impl PermissionValidator {
 
     /// Check command-based permissions
     fn check_command_permissions(&self, command: &str, context: &PermissionContext) -> Option<PermissionResult> {
+        if !context.flags.is_empty() {
+            return Some(PermissionResult::Prompt(format!(
+                "The command was flagged: it {}. Allow execution?",
+                context.flags.join("; it ")
+            )));
+        }
+
         // Check for dangerous command patterns
         let dangerous_patterns = [
             ("rm -rf", "Recursive file deletion"),
@@ -0,0 +1,149 @@
+//! Compressed archive of inactive sessions
+//!
+//! Archiving moves a session and its messages out of the live tables into a
+//! single zstd-compressed row, which keeps the active database small for heavy
+//! users. Archived sessions are left out of session listings, but their message
+//! embeddings stay behind so semantic search still finds them, and they can be
+//! restored whenever they are needed again.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{Session, SessionManager};
+use crate::llm::Message;
+
+/// Archives are written once and rarely read, so favour size over speed
+const COMPRESSION_LEVEL: i32 = 9;
+
+/// A session in the archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedSession {
+    pub id: String,
+    pub title: String,
+    /// When the session was last active
+    pub updated_at: DateTime<Utc>,
+    pub archived_at: DateTime<Utc>,
+    pub message_count: u32,
+    /// Size of the compressed session and messages in bytes
+    pub compressed_size: u64,
+}
+
+/// What an archive row holds once decompressed
+#[derive(Serialize, Deserialize)]
+struct ArchiveContents {
+    session: Session,
+    messages: Vec<Message>,
+}
+
+/// Compress a session and its messages into an archive blob
+pub fn compress(session: &Session, messages: &[Message]) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(&serde_json::json!({
+        "session": session,
+        "messages": messages,
+    }))?;
+    Ok(zstd::encode_all(json.as_slice(), COMPRESSION_LEVEL)?)
+}
+
+/// Restore a session and its messages from an archive blob
+pub fn decompress(data: &[u8]) -> Result<(Session, Vec<Message>)> {
+    let json = zstd::decode_all(data).context("Corrupt session archive")?;
+    let contents: ArchiveContents = serde_json::from_slice(&json).context("Corrupt session archive")?;
+    Ok((contents.session, contents.messages))
+}
+
+impl SessionManager {
+    /// Move a session to the compressed archive
+    ///
+    /// With semantic search enabled the session is indexed first, so it can
+    /// still be found by meaning once archived.
+    pub async fn archive_session(&self, id: &str) -> Result<ArchivedSession> {
+        if let Some(index) = self.semantic_index() {
+            if let Some(session) = self.get_session(id).await? {
+                if index.is_stale(&session).await? {
+                    let messages = self.get_messages(id, None).await?;
+                    if let Err(e) = index.index_session(&session, &messages).await {
+                        warn!("Archiving session {} without indexing it: {}", id, e);
+                    }
+                }
+            }
+        }
+
+        let archived = self.storage().archive_session(id).await?;
+        self.forget_cached(id).await;
+        Ok(archived)
+    }
+
+    /// Bring an archived session back into the active sessions
+    pub async fn unarchive_session(&self, id: &str) -> Result<Session> {
+        let session = self.storage().unarchive_session(id).await?;
+        self.cache(&session).await;
+        Ok(session)
+    }
+
+    /// Archived sessions, most recently archived first
+    pub async fn list_archived_sessions(&self, limit: Option<u32>) -> Result<Vec<ArchivedSession>> {
+        self.storage().list_archived_sessions(limit).await
+    }
+
+    /// An archived session by ID
+    pub async fn get_archived_session(&self, id: &str) -> Result<Option<ArchivedSession>> {
+        self.storage().get_archived_session(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MessageRole;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_archived_sessions_leave_the_listing_and_come_back() {
+        let dir = tempdir().unwrap();
+        let manager = SessionManager::new(dir.path()).await.unwrap();
+
+        let kept = manager.create_session("Kept".to_string(), None).await.unwrap();
+        let old = manager.create_session("Old".to_string(), None).await.unwrap();
+        for text in ["first", "second"] {
+            manager
+                .add_message(&old.id, &Message::new_text(MessageRole::User, text.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let archived = manager.archive_session(&old.id).await.unwrap();
+        assert_eq!(archived.title, "Old");
+        assert_eq!(archived.message_count, 2);
+        let listed: Vec<String> = manager.list_sessions(None).await.unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![kept.id.clone()]);
+        assert!(manager.get_session(&old.id).await.unwrap().is_none());
+        assert!(manager.get_messages(&old.id, None).await.unwrap().is_empty());
+        assert_eq!(manager.list_archived_sessions(None).await.unwrap().len(), 1);
+        assert!(manager.archive_session(&old.id).await.is_err());
+
+        let restored = manager.unarchive_session(&old.id).await.unwrap();
+        assert_eq!(restored.title, "Old");
+        let messages = manager.get_messages(&old.id, None).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].get_text_content().as_deref(), Some("first"));
+        assert!(manager.get_archived_session(&old.id).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_archives_round_trip_and_compress() {
+        let session = Session::new("Long".to_string(), None);
+        let messages: Vec<Message> = (0..50)
+            .map(|_| Message::new_text(MessageRole::Assistant, "the same answer again ".repeat(20)))
+            .collect();
+
+        let data = compress(&session, &messages).unwrap();
+        assert!(data.len() < serde_json::to_vec(&messages).unwrap().len() / 10);
+
+        let (restored, restored_messages) = decompress(&data).unwrap();
+        assert_eq!(restored.id, session.id);
+        assert_eq!(restored_messages.len(), 50);
+        assert!(decompress(b"not zstd").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Compacting a conversation that outgrew the model's context
+//!
+//! When the provider rejects a request as too long, every turn before the
+//! current one is summarized and later requests carry the summary instead of
+//! those messages. The current turn is kept whole, so tool calls stay next to
+//! their results and the model picks up where it stopped.
+//!
+//! Before a request goes out, its tokens are counted as well: one that fills
+//! most of a known context window compacts the conversation ahead of time.
+//!
+//! The session keeps every message. A compaction message appended to it
+//! records why the conversation was compacted, the summary and the first
+//! message still sent as it is; it is shown to the user but never sent to the
+//! model itself.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::llm::{ContentBlock, Message, MessageRole};
+
+/// Message metadata key marking a compaction
+pub const COMPACTION_KEY: &str = "compaction";
+
+/// Instructions for summarizing the compacted messages
+pub const SUMMARY_INSTRUCTIONS: &str = "The conversation below no longer fits your context window. \
+Summarize it so you can continue the work from the summary alone: the user's requests and \
+preferences, decisions made, files read or changed with what matters about them, commands \
+run with their outcome, and what is left to do. Be concise, but keep names, paths and \
+numbers exact. Reply with the summary only.";
+
+/// Summary used when summarizing fails
+pub const FALLBACK_SUMMARY: &str = "The earlier conversation was dropped to fit the context window. \
+Ask the user if you need anything from it.";
+
+/// Most characters of the transcript sent to be summarized
+const MAX_TRANSCRIPT_CHARS: usize = 60_000;
+
+/// Most characters of a single tool call or result in the transcript
+const MAX_BLOCK_CHARS: usize = 2_000;
+
+/// Share of the model's context window a request may fill before the
+/// conversation is compacted ahead of sending it, leaving room for the answer
+/// and for estimated counts to be off
+const COMPACT_AT: f64 = 0.85;
+
+/// A compaction recorded in the session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compaction {
+    /// Why the conversation was compacted: the error the provider rejected
+    /// the request with, or how close it came to the context window
+    pub reason: String,
+    pub summary: String,
+    /// Number of messages the summary replaces
+    pub compacted_messages: usize,
+    /// First message sent as it is after the summary
+    pub first_kept_id: String,
+}
+
+impl Compaction {
+    /// Message recording the compaction in the session
+    pub fn message(&self) -> Message {
+        let mut message = Message::new_assistant(format!(
+            "── Compacted {} earlier messages to fit the model's context ──\n\n{}",
+            self.compacted_messages, self.summary
+        ));
+        message.metadata.insert(COMPACTION_KEY.to_string(), json!(self));
+        message
+    }
+}
+
+/// Whether a request of this many tokens should be compacted before it is sent
+pub fn needs_compaction(request_tokens: usize, context_window: u32) -> bool {
+    request_tokens as f64 > context_window as f64 * COMPACT_AT
+}
+
+/// Index of the first message kept when compacting: the prompt of the current turn
+///
+/// `None` when the current turn is all there is.
+pub fn first_kept(messages: &[Message]) -> Option<usize> {
+    messages
+        .iter()
+        .rposition(|m| m.role == MessageRole::User)
+        .filter(|&index| index > 0)
+}
+
+/// Messages to send the model, with the latest compaction applied
+///
+/// Messages before the first kept one are replaced by the summary, which
+/// precedes the kept prompt. Compaction messages themselves are left out.
+pub fn apply(messages: &[Message]) -> Vec<Message> {
+    let compaction = messages
+        .iter()
+        .rev()
+        .find_map(|m| m.metadata.get(COMPACTION_KEY))
+        .and_then(|value| serde_json::from_value::<Compaction>(value.clone()).ok());
+    let start = compaction
+        .as_ref()
+        .and_then(|compaction| messages.iter().position(|m| m.id == compaction.first_kept_id))
+        .unwrap_or(0);
+
+    let mut kept: Vec<Message> = messages[start..]
+        .iter()
+        .filter(|m| !m.metadata.contains_key(COMPACTION_KEY))
+        .cloned()
+        .collect();
+    if let (Some(compaction), Some(first)) = (compaction, kept.first_mut()) {
+        if start > 0 {
+            first.content.insert(0, ContentBlock::Text {
+                text: format!("<earlier_conversation_summary>\n{}\n</earlier_conversation_summary>", compaction.summary),
+            });
+        }
+    }
+    kept
+}
+
+/// Plain text transcript of messages to be summarized
+///
+/// Long tool calls and results are cut, and when the transcript is still too
+/// long its middle is left out, keeping how the conversation started and
+/// where it stands.
+pub fn transcript(messages: &[Message]) -> String {
+    let mut lines = Vec::new();
+    for message in messages {
+        let role = match message.role {
+            MessageRole::System => "System",
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::Tool => "Tool",
+        };
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text } if !text.trim().is_empty() => lines.push(format!("{}: {}", role, text.trim())),
+                ContentBlock::Text { .. } => {}
+                ContentBlock::Image { .. } => lines.push(format!("{}: [image]", role)),
+                ContentBlock::ToolUse { name, input, .. } => {
+                    lines.push(format!("{} called {}: {}", role, name, cut(&input.to_string(), MAX_BLOCK_CHARS)))
+                }
+                ContentBlock::ToolResult { content, .. } => {
+                    lines.push(format!("Tool result: {}", cut(content, MAX_BLOCK_CHARS)))
+                }
+            }
+        }
+    }
+    let transcript = lines.join("\n\n");
+
+    let count = transcript.chars().count();
+    if count <= MAX_TRANSCRIPT_CHARS {
+        return transcript;
+    }
+    let head: String = transcript.chars().take(MAX_TRANSCRIPT_CHARS / 4).collect();
+    let tail: String = transcript.chars().skip(count - MAX_TRANSCRIPT_CHARS * 3 / 4).collect();
+    format!("{}\n\n[... {} characters left out ...]\n\n{}", head, count - MAX_TRANSCRIPT_CHARS, tail)
+}
+
+/// Text cut to at most `max` characters
+fn cut(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{} [...]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        let mut tool_use = Message::new_assistant(String::new());
+        tool_use.content = vec![ContentBlock::ToolUse {
+            id: "call-1".to_string(),
+            name: "view".to_string(),
+            input: json!({ "file_path": "src/main.rs" }),
+        }];
+        let mut tool_result = Message::new_text(MessageRole::Tool, String::new());
+        tool_result.content = vec![ContentBlock::ToolResult {
+            tool_call_id: "call-1".to_string(),
+            content: "x".repeat(5_000),
+        }];
+        vec![
+            Message::new_user("Read main.rs".to_string()),
+            tool_use,
+            tool_result,
+            Message::new_assistant("It prints hello".to_string()),
+            Message::new_user("Now make it print goodbye".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_compaction_keeps_the_current_turn() {
+        let mut messages = conversation();
+        let first = first_kept(&messages).unwrap();
+        assert_eq!(first, 4);
+        assert!(first_kept(&messages[4..]).is_none());
+
+        let text = transcript(&messages[..first]);
+        assert!(text.starts_with("User: Read main.rs"));
+        assert!(text.contains("Assistant called view: {\"file_path\":\"src/main.rs\"}"));
+        assert!(text.contains("xxxx [...]"));
+
+        let compaction = Compaction {
+            reason: "prompt is too long".to_string(),
+            summary: "main.rs prints hello".to_string(),
+            compacted_messages: first,
+            first_kept_id: messages[first].id.clone(),
+        };
+        messages.push(compaction.message());
+        messages.push(Message::new_assistant("Done".to_string()));
+
+        let sent = apply(&messages);
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].id, messages[4].id);
+        let prompt: Vec<String> = sent[0]
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(prompt[0].contains("main.rs prints hello"));
+        assert_eq!(prompt[1], "Now make it print goodbye");
+    }
+
+    #[test]
+    fn test_without_compaction_all_messages_are_sent() {
+        let messages = conversation();
+        assert_eq!(apply(&messages).len(), messages.len());
+    }
+}
@@ -0,0 +1,230 @@
+//! Comparing the transcripts of two sessions
+//!
+//! After re-running a workflow with another model or prompt, two sessions can
+//! be compared turn by turn. Turns are aligned by their user prompts, so a
+//! prompt added or dropped in one run doesn't shift every later turn, and the
+//! assistant's answers in aligned turns are diffed word by word. Tool calls and
+//! their results are left out; only what the assistant said is compared.
+
+use anyhow::Result;
+use similar::{capture_diff_slices, Algorithm, ChangeTag, DiffOp, TextDiff};
+
+use super::merge::split_turns;
+use super::{Session, SessionManager, MERGE_DIVIDER_KEY};
+use crate::llm::{Message, MessageRole};
+
+/// What was asked and answered in one turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnText {
+    pub prompt: String,
+    pub answer: String,
+}
+
+/// A run of words in a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordChange {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A turn of the first session next to the turn of the second aligned with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparedTurn {
+    /// The turn in the first session, none if only the second has it
+    pub first: Option<TurnText>,
+    /// The turn in the second session, none if only the first has it
+    pub second: Option<TurnText>,
+}
+
+impl ComparedTurn {
+    /// Check if both sessions asked the same thing
+    pub fn same_prompt(&self) -> bool {
+        match (&self.first, &self.second) {
+            (Some(first), Some(second)) => normalize(&first.prompt) == normalize(&second.prompt),
+            _ => false,
+        }
+    }
+
+    /// Check if both sessions asked and answered the same
+    pub fn is_unchanged(&self) -> bool {
+        self.same_prompt() && self.first.as_ref().map(|t| &t.answer) == self.second.as_ref().map(|t| &t.answer)
+    }
+
+    /// Word diff from the first session's answer to the second's
+    pub fn answer_diff(&self) -> Vec<WordChange> {
+        let answer = |turn: &Option<TurnText>| turn.as_ref().map_or("", |turn| turn.answer.as_str()).to_string();
+        word_diff(&answer(&self.first), &answer(&self.second))
+    }
+}
+
+/// Two sessions compared turn by turn
+#[derive(Debug, Clone)]
+pub struct SessionComparison {
+    pub first: Session,
+    pub second: Session,
+    pub turns: Vec<ComparedTurn>,
+}
+
+impl SessionComparison {
+    /// Number of turns that differ
+    pub fn changed_turns(&self) -> usize {
+        self.turns.iter().filter(|turn| !turn.is_unchanged()).count()
+    }
+}
+
+/// The prompt and answer of each turn of a transcript
+pub fn transcript_turns(messages: &[Message]) -> Vec<TurnText> {
+    split_turns(messages)
+        .into_iter()
+        .map(|turn| {
+            let prompt = match turn.first() {
+                Some(message) if message.role == MessageRole::User => message.get_text_content().unwrap_or_default(),
+                _ => String::new(),
+            };
+            let answer = turn
+                .iter()
+                .filter(|message| message.role == MessageRole::Assistant)
+                .filter(|message| !message.metadata.contains_key(MERGE_DIVIDER_KEY))
+                .filter_map(|message| message.get_text_content())
+                .filter(|text| !text.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            TurnText { prompt, answer }
+        })
+        .collect()
+}
+
+/// Pair up the turns of two transcripts by their prompts
+///
+/// Turns whose prompts match are paired. Between matches, changed prompts are
+/// paired in order and the rest stand alone.
+pub fn align_turns(first: Vec<TurnText>, second: Vec<TurnText>) -> Vec<ComparedTurn> {
+    let first_prompts: Vec<String> = first.iter().map(|turn| normalize(&turn.prompt)).collect();
+    let second_prompts: Vec<String> = second.iter().map(|turn| normalize(&turn.prompt)).collect();
+    let mut first = first.into_iter().map(Some).collect::<Vec<_>>();
+    let mut second = second.into_iter().map(Some).collect::<Vec<_>>();
+
+    let mut turns = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &first_prompts, &second_prompts) {
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        let paired = match op {
+            DiffOp::Equal { len, .. } => len,
+            DiffOp::Replace { old_len, new_len, .. } => old_len.min(new_len),
+            DiffOp::Delete { .. } | DiffOp::Insert { .. } => 0,
+        };
+        for offset in 0..paired {
+            turns.push(ComparedTurn {
+                first: first[old_range.start + offset].take(),
+                second: second[new_range.start + offset].take(),
+            });
+        }
+        for index in old_range.skip(paired) {
+            turns.push(ComparedTurn { first: first[index].take(), second: None });
+        }
+        for index in new_range.skip(paired) {
+            turns.push(ComparedTurn { first: None, second: second[index].take() });
+        }
+    }
+    turns
+}
+
+/// Diff two texts word by word, joining neighbouring words of the same kind
+pub fn word_diff(old: &str, new: &str) -> Vec<WordChange> {
+    let diff = TextDiff::from_words(old, new);
+    let mut changes: Vec<WordChange> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        match (change.tag(), changes.last_mut()) {
+            (ChangeTag::Equal, Some(WordChange::Same(text)))
+            | (ChangeTag::Delete, Some(WordChange::Removed(text)))
+            | (ChangeTag::Insert, Some(WordChange::Added(text))) => text.push_str(value),
+            (ChangeTag::Equal, _) => changes.push(WordChange::Same(value.to_string())),
+            (ChangeTag::Delete, _) => changes.push(WordChange::Removed(value.to_string())),
+            (ChangeTag::Insert, _) => changes.push(WordChange::Added(value.to_string())),
+        }
+    }
+    changes
+}
+
+/// A prompt with its whitespace collapsed, so reflowed prompts still match
+fn normalize(prompt: &str) -> String {
+    prompt.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl SessionManager {
+    /// Compare the transcripts of two sessions
+    pub async fn compare_sessions(&self, first_id: &str, second_id: &str) -> Result<SessionComparison> {
+        let first = self.get_session(first_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", first_id))?;
+        let second = self.get_session(second_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", second_id))?;
+        let turns = align_turns(
+            transcript_turns(&self.get_messages(first_id, None).await?),
+            transcript_turns(&self.get_messages(second_id, None).await?),
+        );
+        Ok(SessionComparison { first, second, turns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(prompt: &str, answer: &str) -> TurnText {
+        TurnText { prompt: prompt.to_string(), answer: answer.to_string() }
+    }
+
+    #[test]
+    fn test_turns_are_aligned_by_prompt() {
+        let mut tool_use = Message::new_assistant(String::new());
+        tool_use.content.clear();
+        let messages = vec![
+            Message::new_user("Plan it".to_string()),
+            tool_use,
+            Message::new_assistant("First plan".to_string()),
+            Message::new_user("Write it".to_string()),
+            Message::new_assistant("Done".to_string()),
+        ];
+        let first = transcript_turns(&messages);
+        assert_eq!(first, [turn("Plan it", "First plan"), turn("Write it", "Done")]);
+
+        let second = vec![
+            turn("Plan  it", "Second plan"),
+            turn("Check the tests", "They pass"),
+            turn("Write it", "Done"),
+        ];
+        let turns = align_turns(first, second);
+        assert_eq!(turns.len(), 3);
+        assert!(turns[0].same_prompt() && !turns[0].is_unchanged());
+        assert_eq!(turns[1], ComparedTurn { first: None, second: Some(turn("Check the tests", "They pass")) });
+        assert!(turns[2].is_unchanged());
+    }
+
+    #[test]
+    fn test_changed_prompts_are_paired_in_order() {
+        let turns = align_turns(
+            vec![turn("a", "1"), turn("b", "2"), turn("c", "3")],
+            vec![turn("a", "1"), turn("x", "2"), turn("c", "3")],
+        );
+        assert_eq!(turns.len(), 3);
+        assert!(!turns[1].same_prompt());
+        assert_eq!(turns[1].first.as_ref().unwrap().prompt, "b");
+        assert_eq!(turns[1].second.as_ref().unwrap().prompt, "x");
+    }
+
+    #[test]
+    fn test_answers_are_diffed_by_word() {
+        assert_eq!(
+            word_diff("use a vector here", "use a map here"),
+            [
+                WordChange::Same("use a ".to_string()),
+                WordChange::Removed("vector".to_string()),
+                WordChange::Added("map".to_string()),
+                WordChange::Same(" here".to_string()),
+            ]
+        );
+        let only_first = ComparedTurn { first: Some(turn("q", "gone")), second: None };
+        assert_eq!(only_first.answer_diff(), [WordChange::Removed("gone".to_string())]);
+    }
+}
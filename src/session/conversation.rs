@@ -1,23 +1,50 @@
 //! Conversation management and message handling
 
 use anyhow::Result;
-use std::{sync::Arc, collections::HashMap};
+use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc}, collections::HashMap, time::Duration};
 use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, info, error};
+use tracing::{debug, info, error, warn};
 
 use crate::{
-    llm::{LlmProvider, Message, MessageRole, ProviderResponse},
-    app::Agent,
-    session::SessionManager,
+    llm::{
+        changes::{TurnChanges, CHANGE_HISTORY_KEY, TURN_CHANGES_KEY},
+        citations::{CitationTracker, CITATIONS_KEY, CITATION_INSTRUCTIONS},
+        images::{self, ImageAttachment},
+        models::{self, MessageModel},
+        moderation::{ModerationDirection, Moderator},
+        tokenizer::Tokenizer,
+        is_context_limit, ContentBlock, Deadline, LlmProvider, Message, MessageRole, ProviderResponse, TokenUsage,
+    },
+    app::{Agent, AppEvent},
+    config::{BudgetAction, BudgetConfig},
+    permission::PermissionManager,
+    session::{
+        compaction::{self, Compaction, FALLBACK_SUMMARY, SUMMARY_INSTRUCTIONS},
+        journal::{self, Journal, JournalWriter, CONTINUE_PROMPT},
+        rewind::{self, Rewind, BRANCHED_FROM_KEY},
+        SessionManager, Suggester, Titler, MERGE_DIVIDER_KEY, TITLED_KEY,
+    },
 };
 
+/// Upper bound on model/tool round trips within one turn
+const MAX_TOOL_ROUNDS: usize = 25;
+
 /// A conversation instance that manages messages and AI interactions
 pub struct Conversation {
     pub session_id: String,
     messages: Arc<RwLock<Vec<Message>>>,
+    /// Prompts dropped by edits, which keep their turn numbers
+    dropped_prompts: AtomicUsize,
     agent: Agent,
     session_manager: Arc<SessionManager>,
     system_message: Option<String>,
+    turn_timeout: Option<Duration>,
+    moderator: Option<Arc<Moderator>>,
+    budget: BudgetConfig,
+    permission_manager: Option<Arc<PermissionManager>>,
+    suggester: Option<Arc<Suggester>>,
+    titler: Option<Arc<Titler>>,
+    journal: Option<Arc<Journal>>,
 }
 
 impl Conversation {
@@ -31,47 +58,272 @@ impl Conversation {
         Self {
             session_id,
             messages: Arc::new(RwLock::new(Vec::new())),
+            dropped_prompts: AtomicUsize::new(0),
             agent,
             session_manager,
             system_message,
+            turn_timeout: None,
+            moderator: None,
+            budget: BudgetConfig::default(),
+            permission_manager: None,
+            suggester: None,
+            titler: None,
+            journal: None,
+        }
+    }
+    
+    /// Suggest follow-up prompts after answers with it
+    pub fn with_suggester(mut self, suggester: Option<Arc<Suggester>>) -> Self {
+        self.suggester = suggester;
+        self
+    }
+    
+    /// Name the session with it after the first answer
+    pub fn with_titler(mut self, titler: Option<Arc<Titler>>) -> Self {
+        self.titler = titler;
+        self
+    }
+    
+    /// Journal answers in it until they are stored, and recover those a crash
+    /// cut off
+    pub fn with_journal(mut self, journal: Option<Arc<Journal>>) -> Self {
+        self.journal = journal;
+        self
+    }
+    
+    /// Remember the tools the user allows for the whole session with it
+    pub fn with_permission_manager(mut self, permission_manager: Option<Arc<PermissionManager>>) -> Self {
+        self.permission_manager = permission_manager;
+        self
+    }
+    
+    /// Run prompts and completions through a moderation stage
+    pub fn with_moderator(mut self, moderator: Option<Arc<Moderator>>) -> Self {
+        self.moderator = moderator;
+        self
+    }
+    
+    /// Limit what the session may spend
+    pub fn with_budget(mut self, budget: BudgetConfig) -> Self {
+        self.budget = budget;
+        self
+    }
+    
+    /// Refuse a new turn once an aborting budget has been reached
+    async fn check_budget(&self) -> Result<()> {
+        let Some(max_cost) = self.budget.max_cost else {
+            return Ok(());
+        };
+        let total_cost = self.session_manager.session_cost(&self.session_id).await?;
+        if self.budget.action == BudgetAction::Abort && total_cost >= max_cost {
+            anyhow::bail!(
+                "Session has spent ${:.2} of its ${:.2} budget; start a new session or raise budget.max_cost",
+                total_cost,
+                max_cost
+            );
+        }
+        Ok(())
+    }
+    
+    /// Moderate text in the given direction, passing it through when disabled
+    async fn moderate(&self, direction: ModerationDirection, text: String) -> Result<String> {
+        match &self.moderator {
+            Some(moderator) => moderator.check(direction, &self.session_id, &text).await,
+            None => Ok(text),
         }
     }
     
-    /// Load existing messages from the session
+    /// Limit each turn to the given overall time budget
+    pub fn with_turn_timeout(mut self, turn_timeout: Option<Duration>) -> Self {
+        self.turn_timeout = turn_timeout;
+        self
+    }
+    
+    /// Provider and model answering in this conversation
+    pub fn model(&self) -> MessageModel {
+        MessageModel::new(self.agent.provider_name(), self.agent.model_name())
+    }
+    
+    /// Answer the next turns with another provider or model
+    ///
+    /// The messages so far are kept and sent to the new model with the next
+    /// prompt. Answers already given keep the model that wrote them.
+    pub fn switch_provider(&self, provider: Arc<dyn LlmProvider>) {
+        info!(
+            "Conversation {} switching from {} to {} ({})",
+            self.session_id,
+            self.agent.model_name(),
+            provider.model(),
+            provider.name()
+        );
+        self.agent.set_provider(provider);
+    }
+    
+    /// System message for a turn, with instructions for citing sources
+    fn turn_system_message(&self) -> Option<String> {
+        match &self.system_message {
+            Some(system_message) => Some(format!("{}\n\n{}", system_message, CITATION_INSTRUCTIONS)),
+            None => Some(CITATION_INSTRUCTIONS.to_string()),
+        }
+    }
+    
+    /// Deadline for a turn starting now
+    pub fn start_turn(&self) -> Deadline {
+        Deadline::from_budget(self.turn_timeout)
+    }
+    
+    /// Load existing messages from the session, leaving out those edits dropped
+    ///
+    /// An answer a crash cut off while it streamed is added to the session
+    /// first, marked interrupted.
     pub async fn load_messages(&self) -> Result<()> {
-        let messages = self.session_manager.get_messages(&self.session_id, None).await?;
+        let recovered = match &self.journal {
+            Some(journal) => journal.recover(&self.session_id).await?,
+            None => None,
+        };
+        if let Some(message) = recovered {
+            warn!("Recovered an interrupted answer of session {}", self.session_id);
+            self.session_manager.add_message(&self.session_id, &message).await?;
+        }
+        let stored = self.session_manager.get_messages(&self.session_id, None).await?;
+        let prompts = count_prompts(&stored);
+        let messages = rewind::apply(stored);
+        self.dropped_prompts.store(prompts - count_prompts(&messages), Ordering::Relaxed);
         *self.messages.write().await = messages;
         Ok(())
     }
     
     /// Send a message and get a response
     pub async fn send_message(&self, content: String) -> Result<ProviderResponse> {
+        self.send_message_with_images(content, Vec::new()).await
+    }
+    
+    /// Send a message with attached images and get a response
+    ///
+    /// The images are scaled down to what the provider takes. The answer is
+    /// journaled from when it arrives until it is stored.
+    #[tracing::instrument(skip_all, fields(session_id = %self.session_id))]
+    pub async fn send_message_with_images(&self, content: String, images: Vec<ImageAttachment>) -> Result<ProviderResponse> {
         debug!("Sending message in conversation: {}", self.session_id);
         
-        // Create user message
-        let user_message = Message::new_user(content);
+        self.check_budget().await?;
+        
+        let content = self.moderate(ModerationDirection::Prompt, content).await?;
+        
+        // Create user message; decoding and scaling images is CPU bound
+        let mut user_message = Message::new_user(content);
+        if !images.is_empty() {
+            let limits = self.agent.image_limits();
+            let blocks = tokio::task::spawn_blocking(move || {
+                images.iter().map(|image| images::image_block(image, limits)).collect::<Result<Vec<_>>>()
+            }).await??;
+            user_message.content.extend(blocks);
+        }
         
         // Add to conversation
         self.add_message(user_message.clone()).await?;
         
-        // Get current messages for context
-        let messages = self.messages.read().await.clone();
+        let deadline = self.start_turn();
+        let system_message = self.turn_system_message();
+        let model = self.model();
+        let mut citations = CitationTracker::new();
+        let mut changes = TurnChanges::new(self.turn_number().await);
+        self.agent.begin_turn(changes.turn);
+        let mut usage = TokenUsage::default();
+        let mut compacted = false;
         
-        // Send to agent
-        let response = self.agent.send_message(messages, self.system_message.clone()).await?;
+        // Let the model call tools until it answers in text
+        let mut response = None;
+        for _ in 0..MAX_TOOL_ROUNDS {
+            let mut messages = self.request_messages().await;
+            if !compacted {
+                if let Some(reason) = self.over_context(&messages, system_message.as_deref()) {
+                    compacted = true;
+                    self.compact(reason, &deadline, &mut usage).await?;
+                    messages = self.request_messages().await;
+                }
+            }
+            let reply = match self.agent.send_message(messages, system_message.clone(), &deadline).await {
+                // Compact once per turn and try again
+                Err(e) if is_context_limit(&e) && !compacted => {
+                    compacted = true;
+                    self.compact(e.to_string(), &deadline, &mut usage).await?;
+                    let messages = self.request_messages().await;
+                    self.agent.send_message(messages, system_message.clone(), &deadline).await?
+                }
+                result => result?,
+            };
+            usage.add(&reply.usage);
+            
+            if reply.tool_calls.is_empty() {
+                response = Some(reply);
+                break;
+            }
+            
+            let mut content = Vec::new();
+            if !reply.content.is_empty() {
+                content.push(ContentBlock::Text { text: reply.content.clone() });
+            }
+            content.extend(reply.tool_calls.iter().map(|call| ContentBlock::ToolUse {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                input: call.arguments.clone(),
+            }));
+            let mut tool_use = Message::new_assistant(String::new());
+            tool_use.content = content;
+            model.mark(&mut tool_use);
+            self.add_message(tool_use).await?;
+            
+            for result in self.agent.handle_tool_calls(reply.tool_calls, &deadline, &mut citations, &mut changes).await? {
+                self.add_message(result).await?;
+            }
+        }
+        let mut response = response
+            .ok_or_else(|| anyhow::anyhow!("Gave up after {} tool rounds without an answer", MAX_TOOL_ROUNDS))?;
+        response.usage = usage;
+        response.content = self.moderate(ModerationDirection::Completion, response.content).await?;
         
-        // Create assistant message
-        let assistant_message = Message::new_assistant(response.content.clone());
+        // Create assistant message, journaled until it is stored, keeping the
+        // sources it cites
+        let mut assistant_message = Message::new_assistant(response.content.clone());
+        model.mark(&mut assistant_message);
+        let writer = start_journal(self.journal.as_deref(), &self.session_id, &assistant_message, &response.content).await;
+        let cited = citations.cited_in(&response.content);
+        if !cited.is_empty() {
+            let value = serde_json::to_value(&cited)?;
+            assistant_message.metadata.insert(CITATIONS_KEY.to_string(), value.clone());
+            response.metadata.insert(CITATIONS_KEY.to_string(), value);
+        }
         
-        // Add response to conversation
+        // Add response to conversation; the journal stays when it isn't stored
         self.add_message(assistant_message).await?;
+        finish_journal(writer).await;
+        
+        if !changes.is_empty() {
+            self.record_changes(changes).await?;
+        }
+        if let Some(permission_manager) = &self.permission_manager {
+            let tools = permission_manager.session_tools().await;
+            self.session_manager.record_allowed_tools(&self.session_id, &tools).await?;
+        }
         
-        // Update session usage
-        self.session_manager.update_session_usage(
-            &self.session_id,
-            &response.usage,
-            0.0, // TODO: Calculate cost
-        ).await?;
+        // Update session usage and cost
+        let total_cost = self.session_manager
+            .record_usage(&self.session_id, &model.model, &response.usage)
+            .await?;
+        if let Some(max_cost) = self.budget.max_cost {
+            if total_cost >= max_cost {
+                warn!(
+                    "Session {} has reached its budget: ${:.4} of ${:.2}",
+                    self.session_id, total_cost, max_cost
+                );
+            }
+        }
+        self.agent.notify(AppEvent::CostUpdated {
+            session_id: self.session_id.clone(),
+            total_cost,
+            max_cost: self.budget.max_cost,
+        });
         
         info!(
             "Conversation {} - Message exchange completed. Tokens: {}",
@@ -81,10 +333,86 @@ impl Conversation {
         Ok(response)
     }
     
+    /// Follow-up prompts for the conversation so far, none when suggestions are off
+    ///
+    /// The suggestions are not added to the conversation; what asking for
+    /// them cost is added to the session.
+    pub async fn suggest_follow_ups(&self) -> Result<Vec<String>> {
+        let Some(suggester) = &self.suggester else {
+            return Ok(Vec::new());
+        };
+        let (suggestions, usage) = suggester.suggest(&self.get_messages().await).await?;
+        if usage.total_tokens > 0 {
+            let total_cost = self.session_manager
+                .record_usage(&self.session_id, suggester.model(), &usage)
+                .await?;
+            self.agent.notify(AppEvent::CostUpdated {
+                session_id: self.session_id.clone(),
+                total_cost,
+                max_cost: self.budget.max_cost,
+            });
+        }
+        Ok(suggestions)
+    }
+    
+    /// Name the session after its first exchange, unless it was named already
+    ///
+    /// Returns the new title. What asking for it cost is added to the session.
+    pub async fn generate_title(&self) -> Result<Option<String>> {
+        let Some(titler) = &self.titler else {
+            return Ok(None);
+        };
+        let titled = self
+            .session_manager
+            .get_session(&self.session_id)
+            .await?
+            .map_or(true, |session| session.get_metadata(TITLED_KEY).is_some());
+        if titled {
+            return Ok(None);
+        }
+        let Some((title, usage)) = titler.title(&self.get_messages().await).await? else {
+            return Ok(None);
+        };
+        if let (Some(model), true) = (titler.model(), usage.total_tokens > 0) {
+            let total_cost = self.session_manager.record_usage(&self.session_id, model, &usage).await?;
+            self.agent.notify(AppEvent::CostUpdated {
+                session_id: self.session_id.clone(),
+                total_cost,
+                max_cost: self.budget.max_cost,
+            });
+        }
+        self.session_manager.rename_session(&self.session_id, &title).await?;
+        self.agent.notify(AppEvent::SessionUpdated {
+            session_id: self.session_id.clone(),
+        });
+        Ok(Some(title))
+    }
+    
+    /// Check if the latest answer was cut off by a crash
+    pub async fn is_interrupted(&self) -> bool {
+        self.messages.read().await.last().is_some_and(journal::is_interrupted)
+    }
+    
+    /// Ask the model to finish the latest answer, which a crash cut off
+    pub async fn continue_interrupted(&self) -> Result<ProviderResponse> {
+        if !self.is_interrupted().await {
+            anyhow::bail!("No interrupted answer to continue");
+        }
+        self.send_message(CONTINUE_PROMPT.to_string()).await
+    }
+    
     /// Send a message and stream the response
+    ///
+    /// The answer is added to the conversation once the stream ends, and
+    /// journaled until then.
+    #[tracing::instrument(skip_all, fields(session_id = %self.session_id))]
     pub async fn send_message_stream(&self, content: String) -> Result<mpsc::UnboundedReceiver<String>> {
         debug!("Sending streaming message in conversation: {}", self.session_id);
         
+        self.check_budget().await?;
+        
+        let content = self.moderate(ModerationDirection::Prompt, content).await?;
+        
         // Create user message
         let user_message = Message::new_user(content);
         
@@ -92,12 +420,273 @@ impl Conversation {
         self.add_message(user_message.clone()).await?;
         
         // Get current messages for context
-        let messages = self.messages.read().await.clone();
+        let messages = self.request_messages().await;
         
         // Send to agent for streaming
-        let stream_rx = self.agent.send_message_stream(messages, self.system_message.clone()).await?;
+        let deadline = self.start_turn();
+        let stream_rx = self.agent.send_message_stream(messages, self.turn_system_message(), &deadline).await?;
         
-        Ok(stream_rx)
+        let stream_rx = match &self.moderator {
+            // Completions have to be checked as a whole, so hold the stream
+            // back and release it once moderation has passed
+            Some(moderator) if moderator.covers(ModerationDirection::Completion) => {
+                Self::moderate_stream(moderator.clone(), self.session_id.clone(), stream_rx)
+            }
+            _ => stream_rx,
+        };
+        Ok(self.store_stream(stream_rx))
+    }
+    
+    /// Forward a streamed answer, journaling it as it arrives and adding it
+    /// to the conversation once it ends
+    ///
+    /// The answer is stored even when the receiver is dropped before the end.
+    fn store_stream(&self, mut stream_rx: mpsc::UnboundedReceiver<String>) -> mpsc::UnboundedReceiver<String> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut answer = Message::new_assistant(String::new());
+        self.model().mark(&mut answer);
+        let journal = self.journal.clone();
+        let messages = self.messages.clone();
+        let session_manager = self.session_manager.clone();
+        let session_id = self.session_id.clone();
+        tokio::spawn(async move {
+            let mut writer = start_journal(journal.as_deref(), &session_id, &answer, "").await;
+            let mut text = String::new();
+            while let Some(chunk) = stream_rx.recv().await {
+                if let Some(journal) = writer.as_mut() {
+                    if let Err(e) = journal.append(&chunk).await {
+                        warn!("Streamed answer no longer journaled: {:#}", e);
+                        writer = None;
+                    }
+                }
+                text.push_str(&chunk);
+                let _ = tx.send(chunk);
+            }
+            if text.is_empty() {
+                finish_journal(writer).await;
+                return;
+            }
+            answer.content = vec![ContentBlock::Text { text }];
+            messages.write().await.push(answer.clone());
+            match session_manager.add_message(&session_id, &answer).await {
+                Ok(()) => finish_journal(writer).await,
+                // The journal stays, to recover the answer from
+                Err(e) => error!("Streamed answer of session {} not stored: {}", session_id, e),
+            }
+        });
+        rx
+    }
+    
+    /// Buffer a streamed completion and forward it after moderation
+    fn moderate_stream(
+        moderator: Arc<Moderator>,
+        session_id: String,
+        mut stream_rx: mpsc::UnboundedReceiver<String>,
+    ) -> mpsc::UnboundedReceiver<String> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut completion = String::new();
+            while let Some(chunk) = stream_rx.recv().await {
+                completion.push_str(&chunk);
+            }
+            let text = match moderator.check(ModerationDirection::Completion, &session_id, &completion).await {
+                Ok(text) => text,
+                Err(e) => {
+                    error!("Completion withheld: {}", e);
+                    e.to_string()
+                }
+            };
+            let _ = tx.send(text);
+        });
+        rx
+    }
+    
+    /// Summarize the turns before the current one once the conversation got
+    /// too long for the model, and record the compaction in the session
+    async fn compact(&self, reason: String, deadline: &Deadline, usage: &mut TokenUsage) -> Result<()> {
+        let messages = self.request_messages().await;
+        let Some(first_kept) = compaction::first_kept(&messages) else {
+            anyhow::bail!("The current turn alone does not fit the model's context: {}", reason);
+        };
+        
+        let request = vec![Message::new_user(compaction::transcript(&messages[..first_kept]))];
+        let summary = match self
+            .agent
+            .send_message_without_tools(request, Some(SUMMARY_INSTRUCTIONS.to_string()), deadline)
+            .await
+        {
+            Ok(reply) => {
+                usage.add(&reply.usage);
+                if reply.content.trim().is_empty() {
+                    FALLBACK_SUMMARY.to_string()
+                } else {
+                    reply.content.trim().to_string()
+                }
+            }
+            Err(e) => {
+                warn!("Could not summarize session {} for compaction: {}", self.session_id, e);
+                FALLBACK_SUMMARY.to_string()
+            }
+        };
+        
+        info!("Compacting {} messages of session {}: {}", first_kept, self.session_id, reason);
+        let compaction = Compaction {
+            reason,
+            summary,
+            compacted_messages: first_kept,
+            first_kept_id: messages[first_kept].id.clone(),
+        };
+        self.add_message(compaction.message()).await
+    }
+    
+    /// Why a request with these messages should be compacted before it is
+    /// sent, if it nearly fills the model's context window
+    ///
+    /// Models of unknown context are left to the provider to reject.
+    fn over_context(&self, messages: &[Message], system_message: Option<&str>) -> Option<String> {
+        let context_window = self.context_window()?;
+        compaction::first_kept(messages)?;
+        let tokens = self.tokenizer().count_request(messages, system_message, &self.agent.tool_definitions());
+        compaction::needs_compaction(tokens, context_window).then(|| {
+            format!(
+                "request of about {} tokens nearly fills the {}-token context of {}",
+                tokens,
+                context_window,
+                self.agent.model_name()
+            )
+        })
+    }
+    
+    /// Tokenizer of the model answering in this conversation
+    pub fn tokenizer(&self) -> Tokenizer {
+        Tokenizer::for_model(&self.agent.provider_name(), &self.agent.model_name())
+    }
+    
+    /// Context window of the model answering, if the catalog knows it
+    pub fn context_window(&self) -> Option<u32> {
+        models::lookup(&self.agent.model_name()).map(|known| known.context_window)
+    }
+    
+    /// Tokens the next request takes before its prompt is added: the
+    /// messages so far, the system message and the tools
+    pub async fn context_tokens(&self) -> usize {
+        let messages = self.request_messages().await;
+        let system_message = self.turn_system_message();
+        self.tokenizer().count_request(&messages, system_message.as_deref(), &self.agent.tool_definitions())
+    }
+    
+    /// Number of the turn a new prompt starts
+    ///
+    /// Turns dropped by edits keep their numbers, so the file changes of
+    /// every turn can still be undone apart.
+    async fn turn_number(&self) -> usize {
+        count_prompts(&self.messages.read().await) + self.dropped_prompts.load(Ordering::Relaxed)
+    }
+    
+    /// Prompts of the conversation, oldest first, as message IDs with their text
+    pub async fn prompts(&self) -> Vec<(String, String)> {
+        self.messages
+            .read()
+            .await
+            .iter()
+            .filter(|m| m.role == MessageRole::User)
+            .filter_map(|m| Some((m.id.clone(), m.get_text_content()?)))
+            .filter(|(_, text)| !text.trim().is_empty())
+            .collect()
+    }
+    
+    /// Drop the prompt `message_id` and everything after it, to send it again
+    /// edited or have its answer regenerated
+    ///
+    /// Returns how many messages were dropped. The session keeps them behind
+    /// a rewind message.
+    pub async fn rewind(&self, message_id: &str) -> Result<usize> {
+        let mut messages = self.messages.write().await;
+        let index = rewind::prompt_index(&messages, message_id)?;
+        let rewind = Rewind {
+            first_dropped_id: message_id.to_string(),
+            dropped_messages: messages.len() - index,
+        };
+        self.session_manager.add_message(&self.session_id, &rewind.message()).await?;
+        self.dropped_prompts.fetch_add(count_prompts(&messages[index..]), Ordering::Relaxed);
+        messages.truncate(index);
+        info!("Conversation {} dropped {} messages to edit a prompt", self.session_id, rewind.dropped_messages);
+        Ok(rewind.dropped_messages)
+    }
+    
+    /// Continue the conversation in a new session branching off before the
+    /// prompt `message_id`, receiving the events of its agent
+    ///
+    /// The branch is a child session with copies of the earlier messages and
+    /// the same model and settings; this conversation is left as it is.
+    pub async fn branch(&self, message_id: &str) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
+        let messages = self.get_messages().await;
+        let index = rewind::prompt_index(&messages, message_id)?;
+        
+        let parent = self.session_manager.get_session(&self.session_id).await?;
+        let title = match &parent {
+            Some(parent) => format!("{} (branch)", parent.title),
+            None => "Branch".to_string(),
+        };
+        let session = self.session_manager.create_session(title, Some(self.session_id.clone())).await?;
+        if let Some(dir) = parent.as_ref().and_then(|parent| parent.working_directory()) {
+            self.session_manager.record_working_directory(&session.id, &dir).await?;
+        }
+        let branched_from = serde_json::json!({ "session_id": self.session_id, "message_id": message_id });
+        self.session_manager
+            .set_session_metadata(&session.id, BRANCHED_FROM_KEY.to_string(), branched_from)
+            .await?;
+        
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let branch = Conversation {
+            session_id: session.id.clone(),
+            messages: Arc::new(RwLock::new(Vec::new())),
+            dropped_prompts: AtomicUsize::new(0),
+            agent: self.agent.for_session(session.id.clone(), event_tx),
+            session_manager: self.session_manager.clone(),
+            system_message: self.system_message.clone(),
+            turn_timeout: self.turn_timeout,
+            moderator: self.moderator.clone(),
+            budget: self.budget.clone(),
+            permission_manager: self.permission_manager.clone(),
+            suggester: self.suggester.clone(),
+            titler: self.titler.clone(),
+            journal: self.journal.clone(),
+        };
+        for message in rewind::branch_copies(&messages[..index]) {
+            branch.add_message(message).await?;
+        }
+        info!("Conversation {} branched into session {}", self.session_id, session.id);
+        Ok((Arc::new(branch), event_rx))
+    }
+    
+    /// Messages sent to the model; change summaries and merge dividers are for the user only
+    ///
+    /// After a compaction, the summary stands in for the messages it replaced.
+    async fn request_messages(&self) -> Vec<Message> {
+        compaction::apply(&self.messages.read().await)
+            .into_iter()
+            .filter(|m| !m.metadata.contains_key(TURN_CHANGES_KEY) && !m.metadata.contains_key(MERGE_DIVIDER_KEY))
+            .collect()
+    }
+    
+    /// Append the summary of the files a turn changed and keep it in the session history
+    async fn record_changes(&self, mut changes: TurnChanges) -> Result<()> {
+        let mut summary = Message::new_assistant(changes.summary());
+        changes.message_id = summary.id.clone();
+        summary.metadata.insert(TURN_CHANGES_KEY.to_string(), serde_json::to_value(&changes)?);
+        self.add_message(summary).await?;
+        
+        let mut history = self
+            .session_manager
+            .get_session(&self.session_id)
+            .await?
+            .map(|session| session.change_history())
+            .unwrap_or_default();
+        history.push(changes);
+        self.session_manager
+            .set_session_metadata(&self.session_id, CHANGE_HISTORY_KEY.to_string(), serde_json::to_value(history)?)
+            .await
     }
     
     /// Add a message to the conversation
@@ -154,6 +743,10 @@ impl Conversation {
     }
 }
 
+fn count_prompts(messages: &[Message]) -> usize {
+    messages.iter().filter(|m| m.role == MessageRole::User).count()
+}
+
 /// Conversation statistics
 #[derive(Debug, Clone)]
 pub struct ConversationStats {
@@ -164,9 +757,41 @@ pub struct ConversationStats {
     pub last_activity: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Start journaling an answer of a session with the text that has arrived
+/// so far; the answer goes on unjournaled when the journal can't be written
+async fn start_journal(journal: Option<&Journal>, session_id: &str, answer: &Message, text: &str) -> Option<JournalWriter> {
+    let (journal, model) = (journal?, MessageModel::of(answer)?);
+    let started = async {
+        let mut writer = journal.start(session_id, &answer.id, &model).await?;
+        if !text.is_empty() {
+            writer.append(text).await?;
+        }
+        anyhow::Ok(writer)
+    };
+    started.await.map_err(|e| warn!("Answer not journaled: {:#}", e)).ok()
+}
+
+/// Remove the journal of an answer once it is stored
+async fn finish_journal(writer: Option<JournalWriter>) {
+    if let Some(writer) = writer {
+        if let Err(e) = writer.finish().await {
+            warn!("{:#}", e);
+        }
+    }
+}
+
 /// Conversation manager for handling multiple conversations
 pub struct ConversationManager {
     conversations: Arc<RwLock<HashMap<String, Arc<Conversation>>>>,
+    turn_timeout: Option<Duration>,
+    moderator: Option<Arc<Moderator>>,
+    budget: BudgetConfig,
+    session_manager: Option<Arc<SessionManager>>,
+    permission_manager: Option<Arc<PermissionManager>>,
+    suggester: Option<Arc<Suggester>>,
+    titler: Option<Arc<Titler>>,
+    journal: Option<Arc<Journal>>,
+    streaming: bool,
 }
 
 impl ConversationManager {
@@ -174,38 +799,125 @@ impl ConversationManager {
     pub fn new() -> Self {
         Self {
             conversations: Arc::new(RwLock::new(HashMap::new())),
+            turn_timeout: None,
+            moderator: None,
+            budget: BudgetConfig::default(),
+            session_manager: None,
+            permission_manager: None,
+            suggester: None,
+            titler: None,
+            journal: None,
+            streaming: false,
         }
     }
     
+    /// Store the messages of conversations in the given sessions
+    pub fn with_session_manager(mut self, session_manager: Arc<SessionManager>) -> Self {
+        self.session_manager = Some(session_manager);
+        self
+    }
+    
+    /// Remember the tools allowed for the whole session in each conversation's session
+    pub fn with_permission_manager(mut self, permission_manager: Option<Arc<PermissionManager>>) -> Self {
+        self.permission_manager = permission_manager;
+        self
+    }
+    
+    /// Suggest follow-up prompts in conversations started from now on
+    pub fn with_suggester(mut self, suggester: Option<Arc<Suggester>>) -> Self {
+        self.suggester = suggester;
+        self
+    }
+    
+    /// Name the sessions of conversations started from now on after their first answer
+    pub fn with_titler(mut self, titler: Option<Arc<Titler>>) -> Self {
+        self.titler = titler;
+        self
+    }
+    
+    /// Journal the streamed answers of conversations started from now on
+    pub fn with_journal(mut self, journal: Option<Arc<Journal>>) -> Self {
+        self.journal = journal;
+        self
+    }
+    
+    /// Limit what each conversation started from now on may spend
+    pub fn with_budget(mut self, budget: BudgetConfig) -> Self {
+        self.budget = budget;
+        self
+    }
+    
+    /// Show the answers of conversations started from now on as they are written
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+    
+    /// Moderate prompts and completions of conversations started from now on
+    pub fn with_moderator(mut self, moderator: Option<Arc<Moderator>>) -> Self {
+        self.moderator = moderator;
+        self
+    }
+    
+    /// Apply a per-turn time budget to conversations started from now on
+    pub fn with_turn_timeout(mut self, turn_timeout: Option<Duration>) -> Self {
+        self.turn_timeout = turn_timeout;
+        self
+    }
+    
     /// Start a new conversation
     pub async fn start_conversation(
         &self,
         session_id: String,
         llm_provider: Arc<dyn LlmProvider>,
+        tool_manager: Arc<crate::llm::tools::ToolManager>,
+        system_message: Option<String>,
     ) -> Result<Arc<Conversation>> {
+        let (conversation, _events) = self
+            .start_conversation_with_events(session_id, llm_provider, tool_manager, system_message)
+            .await?;
+        Ok(conversation)
+    }
+    
+    /// Start a new conversation, receiving the events of its agent
+    ///
+    /// Tool calls, their results and cost updates arrive on the returned
+    /// channel as the conversation runs.
+    pub async fn start_conversation_with_events(
+        &self,
+        session_id: String,
+        llm_provider: Arc<dyn LlmProvider>,
+        tool_manager: Arc<crate::llm::tools::ToolManager>,
+        system_message: Option<String>,
+    ) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
         // Create event channel for the agent
-        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
         
-        // Create a temporary tool manager (TODO: Pass from app)
-        let tool_permissions = crate::llm::tools::ToolPermissions::default();
-        let tool_manager = Arc::new(crate::llm::tools::ToolManager::new(tool_permissions));
+        // Create agent; completions that are moderated have to be checked
+        // as a whole, so they are not shown before
+        let moderated = self.moderator.as_ref().is_some_and(|moderator| moderator.covers(ModerationDirection::Completion));
+        let agent = Agent::new(llm_provider, tool_manager, event_tx, session_id.clone())
+            .with_streaming(self.streaming && !moderated);
         
-        // Create agent
-        let agent = Agent::new(llm_provider, tool_manager, event_tx, session_id.clone());
-        
-        // Create session manager (this should be passed in, but for now create a new one)
-        // TODO: Pass session manager from app
-        let session_manager = Arc::new(
-            crate::session::SessionManager::new(std::path::Path::new("./data")).await?
-        );
+        let session_manager = match &self.session_manager {
+            Some(session_manager) => session_manager.clone(),
+            None => Arc::new(SessionManager::new(std::path::Path::new("./data")).await?),
+        };
         
         // Create conversation
         let conversation = Arc::new(Conversation::new(
             session_id.clone(),
             agent,
             session_manager,
-            None, // TODO: Load system message from config
-        ));
+            system_message,
+        )
+        .with_turn_timeout(self.turn_timeout)
+        .with_moderator(self.moderator.clone())
+        .with_budget(self.budget.clone())
+        .with_permission_manager(self.permission_manager.clone())
+        .with_suggester(self.suggester.clone())
+        .with_titler(self.titler.clone())
+        .with_journal(self.journal.clone()));
         
         // Load existing messages
         conversation.load_messages().await?;
@@ -213,7 +925,7 @@ impl ConversationManager {
         // Store conversation
         self.conversations.write().await.insert(session_id, conversation.clone());
         
-        Ok(conversation)
+        Ok((conversation, event_rx))
     }
     
     /// Get an existing conversation
@@ -221,6 +933,22 @@ impl ConversationManager {
         self.conversations.read().await.get(session_id).cloned()
     }
     
+    /// Continue a conversation in a new session branching off before one of
+    /// its prompts, receiving the events of the branch's agent
+    pub async fn branch_conversation(
+        &self,
+        session_id: &str,
+        message_id: &str,
+    ) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
+        let conversation = self
+            .get_conversation(session_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No conversation for session {}", session_id))?;
+        let (branch, events) = conversation.branch(message_id).await?;
+        self.conversations.write().await.insert(branch.session_id.clone(), branch.clone());
+        Ok((branch, events))
+    }
+    
     /// End a conversation
     pub async fn end_conversation(&self, session_id: &str) -> Result<()> {
         if let Some(conversation) = self.conversations.write().await.remove(session_id) {
@@ -258,4 +986,242 @@ impl ConversationManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{tools::{ToolManager, ToolPermissions}, ChatRequest, LlmResult, ProviderEvent};
+    use crate::session::storage::{SqliteStorage, Storage};
+    use crate::session::Session;
+    use async_trait::async_trait;
+    use futures::Stream;
+    use std::path::Path;
+    use std::pin::Pin;
+    use std::sync::atomic::AtomicBool;
+
+    const ANSWER: &str = "Each value has a single owner";
+
+    /// Provider answering every request with the same text
+    struct Answering {
+        usage: TokenUsage,
+    }
+
+    #[async_trait]
+    impl LlmProvider for Answering {
+        async fn chat_completion(&self, _request: ChatRequest) -> LlmResult<ProviderResponse> {
+            Ok(ProviderResponse {
+                content: ANSWER.to_string(),
+                tool_calls: Vec::new(),
+                usage: self.usage.clone(),
+                finish_reason: None,
+                metadata: HashMap::new(),
+            })
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: ChatRequest,
+        ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+            // Word by word, without a final response
+            let words = ANSWER.split_inclusive(' ').map(|word| Ok(ProviderEvent::ContentDelta { delta: word.to_string() }));
+            Ok(Box::pin(futures::stream::iter(words.collect::<Vec<_>>())))
+        }
+
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        fn model(&self) -> &str {
+            "gpt-4o"
+        }
+
+        fn validate_config(&self) -> LlmResult<()> {
+            Ok(())
+        }
+    }
+
+    /// SQLite storage that fails to store answers while `failing` is set
+    struct FlakyStorage {
+        inner: SqliteStorage,
+        failing: AtomicBool,
+    }
+
+    #[async_trait]
+    impl Storage for FlakyStorage {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn put_session(&self, session: &Session) -> Result<()> {
+            self.inner.put_session(session).await
+        }
+
+        async fn get_session(&self, id: &str) -> Result<Option<Session>> {
+            self.inner.get_session(id).await
+        }
+
+        async fn list_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+            self.inner.list_sessions(limit).await
+        }
+
+        async fn delete_session(&self, id: &str) -> Result<()> {
+            self.inner.delete_session(id).await
+        }
+
+        async fn put_message(&self, session_id: &str, message: &Message) -> Result<()> {
+            if message.role == MessageRole::Assistant && self.failing.load(Ordering::Relaxed) {
+                anyhow::bail!("disk full");
+            }
+            self.inner.put_message(session_id, message).await
+        }
+
+        async fn get_messages(&self, session_id: &str, limit: Option<u32>) -> Result<Vec<Message>> {
+            self.inner.get_messages(session_id, limit).await
+        }
+    }
+
+    /// A conversation in a new session stored in `dir`, answered by `provider`,
+    /// with the events it sends
+    async fn conversation(
+        dir: &Path,
+        storage: Arc<FlakyStorage>,
+        provider: Answering,
+        budget: BudgetConfig,
+    ) -> (Conversation, mpsc::UnboundedReceiver<AppEvent>) {
+        let session_manager = Arc::new(SessionManager::with_storage(storage));
+        let session = session_manager.create_session("Test".to_string(), None).await.unwrap();
+        resumed(dir, session_manager, &session.id, provider, budget).await
+    }
+
+    /// A conversation continuing a stored session
+    async fn resumed(
+        dir: &Path,
+        session_manager: Arc<SessionManager>,
+        session_id: &str,
+        provider: Answering,
+        budget: BudgetConfig,
+    ) -> (Conversation, mpsc::UnboundedReceiver<AppEvent>) {
+        let (event_tx, events) = mpsc::unbounded_channel();
+        let tools = Arc::new(ToolManager::new(ToolPermissions::default()));
+        let agent = Agent::new(Arc::new(provider), tools, event_tx, session_id.to_string());
+        let conversation = Conversation::new(session_id.to_string(), agent, session_manager, None)
+            .with_budget(budget)
+            .with_journal(Some(Arc::new(Journal::new(dir.join("journal")))));
+        conversation.load_messages().await.unwrap();
+        (conversation, events)
+    }
+
+    async fn flaky_storage(dir: &Path) -> Arc<FlakyStorage> {
+        Arc::new(FlakyStorage {
+            inner: SqliteStorage::open(dir.join("sessions.db")).await.unwrap(),
+            failing: AtomicBool::new(false),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_answers_are_journaled_until_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = flaky_storage(dir.path()).await;
+        let provider = Answering { usage: TokenUsage::default() };
+        let (conversation, _) = conversation(dir.path(), storage.clone(), provider, BudgetConfig::default()).await;
+        let journal = dir.path().join(format!("journal/{}.jsonl", conversation.session_id));
+
+        // A stored answer leaves no journal behind
+        let response = conversation.send_message("What is ownership?".to_string()).await.unwrap();
+        assert_eq!(response.content, ANSWER);
+        assert!(!journal.exists());
+
+        // One that could not be stored is recovered when the session is loaded
+        storage.failing.store(true, Ordering::Relaxed);
+        assert!(conversation.send_message("And borrowing?".to_string()).await.is_err());
+        assert!(journal.exists());
+        storage.failing.store(false, Ordering::Relaxed);
+
+        let session_manager = Arc::new(SessionManager::with_storage(storage));
+        let provider = Answering { usage: TokenUsage::default() };
+        let (resumed, _) =
+            resumed(dir.path(), session_manager, &conversation.session_id, provider, BudgetConfig::default()).await;
+        let messages = resumed.get_messages().await;
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3].get_text_content().as_deref(), Some(ANSWER));
+        assert!(resumed.is_interrupted().await);
+        assert!(!journal.exists());
+    }
+
+    /// Costs of the turns a conversation announced
+    fn announced_costs(events: &mut mpsc::UnboundedReceiver<AppEvent>) -> Vec<(f64, Option<f64>)> {
+        std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                AppEvent::CostUpdated { total_cost, max_cost, .. } => Some((total_cost, max_cost)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A provider whose every answer costs $2.50 at gpt-4o's price
+    fn priced() -> Answering {
+        Answering { usage: TokenUsage { input_tokens: 1_000_000, output_tokens: 0, total_tokens: 1_000_000 } }
+    }
+
+    #[tokio::test]
+    async fn test_aborting_budget_refuses_the_next_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let budget = BudgetConfig { max_cost: Some(2.5), action: BudgetAction::Abort };
+        let (conversation, mut events) = conversation(dir.path(), flaky_storage(dir.path()).await, priced(), budget).await;
+
+        // The turn reaching the budget is still answered
+        conversation.send_message("What is ownership?".to_string()).await.unwrap();
+        assert_eq!(announced_costs(&mut events), vec![(2.5, Some(2.5))]);
+
+        let error = conversation.send_message("And borrowing?".to_string()).await.unwrap_err();
+        assert!(error.to_string().contains("$2.50 of its $2.50 budget"));
+        assert_eq!(conversation.get_messages().await.len(), 2);
+        assert_eq!(conversation.session_manager.session_cost(&conversation.session_id).await.unwrap(), 2.5);
+    }
+
+    #[tokio::test]
+    async fn test_warning_budget_keeps_going() {
+        let dir = tempfile::tempdir().unwrap();
+        let budget = BudgetConfig { max_cost: Some(2.5), action: BudgetAction::Warn };
+        let (conversation, mut events) = conversation(dir.path(), flaky_storage(dir.path()).await, priced(), budget).await;
+
+        conversation.send_message("What is ownership?".to_string()).await.unwrap();
+        conversation.send_message("And borrowing?".to_string()).await.unwrap();
+        assert_eq!(announced_costs(&mut events), vec![(2.5, Some(2.5)), (5.0, Some(2.5))]);
+
+        let total = conversation
+            .session_manager
+            .record_usage(&conversation.session_id, "gpt-4o-2024-08-06", &priced().usage)
+            .await
+            .unwrap();
+        assert_eq!(total, 7.5);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_answers_are_announced_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_manager = Arc::new(SessionManager::with_storage(flaky_storage(dir.path()).await));
+        let session = session_manager.create_session("Test".to_string(), None).await.unwrap();
+        let (event_tx, mut events) = mpsc::unbounded_channel();
+        let tools = Arc::new(ToolManager::new(ToolPermissions::default()));
+        let provider = Answering { usage: TokenUsage::default() };
+        let agent = Agent::new(Arc::new(provider), tools, event_tx, session.id.clone()).with_streaming(true);
+        let conversation = Conversation::new(session.id.clone(), agent, session_manager, None);
+
+        let response = conversation.send_message("What is ownership?".to_string()).await.unwrap();
+        assert_eq!(response.content, ANSWER);
+
+        let mut chunks = Vec::new();
+        let mut ended = false;
+        while let Ok(event) = events.try_recv() {
+            match event {
+                AppEvent::StreamChunk { chunk, .. } => chunks.push(chunk),
+                AppEvent::StreamEnded { .. } => ended = true,
+                _ => {}
+            }
+        }
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), ANSWER);
+        assert!(ended);
+    }
+}
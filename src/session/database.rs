@@ -6,7 +6,9 @@ use std::path::Path;
 use chrono::{DateTime, Utc};
 use serde_json;
 
-use crate::llm::{Message, TokenUsage};
+use crate::llm::{ContentBlock, Message, TokenUsage};
+use super::archive::ArchivedSession;
+use super::search::{fts_query, MessageHit};
 // use super::queries::{SessionQueries, MessageQueries}; // Complex type system needs reconciliation
 
 /// Database manager for session persistence
@@ -80,6 +82,77 @@ impl Database {
             [],
         )?;
         
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_embeddings (
+                message_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                snippet TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                PRIMARY KEY (message_id, model)
+            )",
+            [],
+        )?;
+        
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS embedding_watermarks (
+                session_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (session_id, model)
+            )",
+            [],
+        )?;
+        
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS archived_sessions (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                archived_at TEXT NOT NULL,
+                message_count INTEGER NOT NULL,
+                data BLOB NOT NULL
+            )",
+            [],
+        )?;
+        
+        self.conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5 (
+                text,
+                message_id UNINDEXED,
+                session_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            )",
+            [],
+        )?;
+        self.index_unindexed_messages()?;
+        
+        Ok(())
+    }
+    
+    /// Add messages stored before the search index existed to it
+    fn index_unindexed_messages(&self) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, content FROM messages
+             WHERE id NOT IN (SELECT message_id FROM messages_fts)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (id, session_id, content) in rows {
+            let Ok(content) = serde_json::from_str::<Vec<ContentBlock>>(&content) else {
+                continue;
+            };
+            self.index_message(&id, &session_id, &searchable_text(&content))?;
+        }
+        Ok(())
+    }
+    
+    fn index_message(&self, message_id: &str, session_id: &str, text: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO messages_fts (text, message_id, session_id) VALUES (?1, ?2, ?3)",
+            params![text, message_id, session_id],
+        )?;
         Ok(())
     }
     
@@ -169,6 +242,41 @@ impl Database {
         Ok(())
     }
     
+    /// Insert or replace a session, keeping its timestamps as given
+    pub async fn upsert_session(&self, row: &SessionRow) -> Result<()> {
+        let metadata_str = row.metadata.as_ref().map(serde_json::to_string).transpose()?;
+        
+        self.conn.execute(
+            "INSERT INTO sessions (
+                id, title, parent_session_id, created_at, updated_at, message_count,
+                total_input_tokens, total_output_tokens, total_cost, metadata
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                parent_session_id = excluded.parent_session_id,
+                updated_at = excluded.updated_at,
+                message_count = excluded.message_count,
+                total_input_tokens = excluded.total_input_tokens,
+                total_output_tokens = excluded.total_output_tokens,
+                total_cost = excluded.total_cost,
+                metadata = excluded.metadata",
+            params![
+                row.id,
+                row.title,
+                row.parent_session_id,
+                row.created_at.to_rfc3339(),
+                row.updated_at.to_rfc3339(),
+                row.message_count,
+                row.total_input_tokens,
+                row.total_output_tokens,
+                row.total_cost,
+                metadata_str
+            ],
+        )?;
+        
+        Ok(())
+    }
+    
     /// Get a session by ID
     pub async fn get_session(&self, id: &str) -> Result<Option<SessionRow>> {
         let mut stmt = self.conn.prepare(
@@ -246,10 +354,18 @@ impl Database {
                 metadata_str
             ],
         )?;
+        self.index_message(&message.id, session_id, &searchable_text(&message.content))?;
         
         Ok(())
     }
     
+    /// Insert a message, replacing any existing message with the same ID
+    pub async fn upsert_message(&self, message: &Message, session_id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM messages WHERE id = ?1", [&message.id])?;
+        self.conn.execute("DELETE FROM messages_fts WHERE message_id = ?1", [&message.id])?;
+        self.insert_message(message, session_id).await
+    }
+    
     /// Get messages for a session
     pub async fn get_messages(&self, session_id: &str, limit: Option<i32>) -> Result<Vec<Message>> {
         let query = if let Some(limit) = limit {
@@ -307,9 +423,173 @@ impl Database {
     /// Delete messages for a session
     pub async fn delete_messages(&self, session_id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM messages WHERE session_id = ?1", [session_id])?;
+        self.conn.execute("DELETE FROM messages_fts WHERE session_id = ?1", [session_id])?;
+        self.conn.execute("DELETE FROM message_embeddings WHERE session_id = ?1", [session_id])?;
+        self.conn.execute("DELETE FROM embedding_watermarks WHERE session_id = ?1", [session_id])?;
         Ok(())
     }
     
+    /// Messages whose text matches a query, best matches first, within one
+    /// session or across all of them
+    pub async fn search_messages(&self, query: &str, session_id: Option<&str>, limit: usize) -> Result<Vec<MessageHit>> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT f.session_id, f.message_id, m.role, snippet(messages_fts, 0, '', '', '…', 12)
+             FROM messages_fts f JOIN messages m ON m.id = f.message_id
+             WHERE messages_fts MATCH ?1 AND (?2 IS NULL OR f.session_id = ?2)
+             ORDER BY bm25(messages_fts) LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![fts_query, session_id, limit as i64], |row| {
+            let role: String = row.get(2)?;
+            let snippet: String = row.get(3)?;
+            Ok(MessageHit {
+                session_id: row.get(0)?,
+                message_id: row.get(1)?,
+                role: serde_json::from_str(&role)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(2, "role".to_string(), rusqlite::types::Type::Text))?,
+                snippet: snippet.split_whitespace().collect::<Vec<_>>().join(" "),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+    
+    /// Store the embedding of a message, replacing any previous one for the model
+    pub async fn upsert_embedding(&self, row: &EmbeddingRow) -> Result<()> {
+        let vector: Vec<u8> = row.vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO message_embeddings (message_id, session_id, model, snippet, vector)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![row.message_id, row.session_id, row.model, row.snippet, vector],
+        )?;
+        Ok(())
+    }
+    
+    /// All embeddings made with a model
+    pub async fn get_embeddings(&self, model: &str) -> Result<Vec<EmbeddingRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, session_id, model, snippet, vector
+             FROM message_embeddings WHERE model = ?1",
+        )?;
+        let rows = stmt.query_map([model], |row| {
+            let vector: Vec<u8> = row.get(4)?;
+            Ok(EmbeddingRow {
+                message_id: row.get(0)?,
+                session_id: row.get(1)?,
+                model: row.get(2)?,
+                snippet: row.get(3)?,
+                vector: vector
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect(),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+    
+    /// IDs of the messages of a session embedded with a model
+    pub async fn embedded_message_ids(&self, session_id: &str, model: &str) -> Result<std::collections::HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id FROM message_embeddings WHERE session_id = ?1 AND model = ?2",
+        )?;
+        let ids = stmt.query_map(params![session_id, model], |row| row.get(0))?;
+        Ok(ids.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    /// Session update time up to which messages have been embedded with a model
+    pub async fn get_embedding_watermark(&self, session_id: &str, model: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT updated_at FROM embedding_watermarks WHERE session_id = ?1 AND model = ?2",
+        )?;
+        let mut rows = stmt.query(params![session_id, model])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Record that a session has been embedded up to the given update time
+    pub async fn set_embedding_watermark(&self, session_id: &str, model: &str, updated_at: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO embedding_watermarks (session_id, model, updated_at) VALUES (?1, ?2, ?3)",
+            params![session_id, model, updated_at],
+        )?;
+        Ok(())
+    }
+    
+    /// Replace a session and its messages with an archive row
+    ///
+    /// Embeddings are kept so archived sessions stay searchable.
+    pub async fn archive_session(&self, archived: &ArchivedSession, data: &[u8]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO archived_sessions (id, title, updated_at, archived_at, message_count, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                archived.id,
+                archived.title,
+                archived.updated_at.to_rfc3339(),
+                archived.archived_at.to_rfc3339(),
+                archived.message_count,
+                data
+            ],
+        )?;
+        tx.execute("DELETE FROM messages WHERE session_id = ?1", [&archived.id])?;
+        tx.execute("DELETE FROM messages_fts WHERE session_id = ?1", [&archived.id])?;
+        tx.execute("DELETE FROM sessions WHERE id = ?1", [&archived.id])?;
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Put an archived session and its messages back, dropping the archive row
+    pub async fn restore_archive(&self, session: &SessionRow, messages: &[Message]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.upsert_session(session).await?;
+        for message in messages {
+            self.upsert_message(message, &session.id).await?;
+        }
+        tx.execute("DELETE FROM archived_sessions WHERE id = ?1", [&session.id])?;
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Compressed contents of an archived session
+    pub async fn get_archive_data(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let mut stmt = self.conn.prepare("SELECT data FROM archived_sessions WHERE id = ?1")?;
+        let mut rows = stmt.query([id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Archived sessions, most recently archived first, optionally only one
+    pub async fn list_archives(&self, id: Option<&str>, limit: Option<i32>) -> Result<Vec<ArchivedSession>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, updated_at, archived_at, message_count, length(data)
+             FROM archived_sessions WHERE ?1 IS NULL OR id = ?1
+             ORDER BY archived_at DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![id, limit.unwrap_or(-1)], |row| {
+            let timestamp = |index: usize| -> rusqlite::Result<DateTime<Utc>> {
+                let value: String = row.get(index)?;
+                Ok(DateTime::parse_from_rfc3339(&value)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(index, "timestamp".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc))
+            };
+            Ok(ArchivedSession {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                updated_at: timestamp(2)?,
+                archived_at: timestamp(3)?,
+                message_count: row.get(4)?,
+                compressed_size: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+    
     /// Get message count for a session
     pub async fn get_message_count(&self, session_id: &str) -> Result<i32> {
         let count: i32 = self.conn.query_row(
@@ -322,6 +602,29 @@ impl Database {
     }
 }
 
+/// The text of a message the search index holds
+fn searchable_text(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Stored embedding of a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingRow {
+    pub message_id: String,
+    pub session_id: String,
+    pub model: String,
+    /// Start of the message text, shown in search results
+    pub snippet: String,
+    pub vector: Vec<f32>,
+}
+
 /// Database row representation of a session
 #[derive(Debug, Clone)]
 pub struct SessionRow {
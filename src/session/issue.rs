@@ -0,0 +1,260 @@
+//! Drafting an issue from a conversation
+//!
+//! When the assistant has tracked down a bug, the conversation already holds
+//! what a report needs. The draft takes the first prompt as the summary, the
+//! commands that were run and the error they printed as the reproduction, the
+//! last answer as the proposed fix, and the diffs of the files the tools
+//! changed. Only the messages handed in are read, so a range of a session can
+//! be reported on its own.
+
+use anyhow::Result;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use super::{SessionManager, MERGE_DIVIDER_KEY};
+use crate::llm::changes::{FileChange, FILE_CHANGE_KEY, TURN_CHANGES_KEY};
+use crate::llm::tools::shell;
+use crate::llm::{ContentBlock, Message, MessageRole};
+
+/// Longest title taken from a prompt
+const MAX_TITLE_CHARS: usize = 72;
+
+/// Lines of error output kept in the reproduction
+const MAX_ERROR_LINES: usize = 40;
+
+/// An issue ready to be filed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueDraft {
+    pub title: String,
+    /// Markdown body with summary, reproduction, proposed fix and diffs
+    pub body: String,
+}
+
+impl IssueDraft {
+    /// Title and body as one markdown document
+    pub fn to_markdown(&self) -> String {
+        format!("# {}\n\n{}", self.title, self.body)
+    }
+}
+
+/// Draft an issue from `messages`, titled after the first prompt or else `fallback_title`
+pub fn draft_issue(fallback_title: &str, messages: &[Message]) -> IssueDraft {
+    let prompt = messages
+        .iter()
+        .find(|message| message.role == MessageRole::User)
+        .and_then(Message::get_text_content)
+        .filter(|text| !text.trim().is_empty());
+    let title = prompt.as_deref().and_then(title_of).unwrap_or_else(|| fallback_title.to_string());
+
+    let mut body = String::from("## Summary\n\n");
+    match &prompt {
+        Some(prompt) => body.push_str(&quote(prompt)),
+        None => body.push_str("_No prompt in the selected messages._"),
+    }
+
+    body.push_str("\n\n## Reproduction\n\n");
+    body.push_str(&reproduction(messages));
+
+    body.push_str("\n\n## Proposed fix\n\n");
+    body.push_str(&last_answer(messages).unwrap_or_else(|| "_The assistant did not answer._".to_string()));
+
+    let diffs = relevant_diffs(messages);
+    if !diffs.is_empty() {
+        body.push_str("\n\n## Relevant diffs\n");
+        for change in diffs {
+            body.push_str(&format!(
+                "\n<details>\n<summary>{} (+{} -{})</summary>\n\n```diff\n{}\n```\n\n</details>\n",
+                change.path,
+                change.additions,
+                change.removals,
+                change.diff.trim_end()
+            ));
+        }
+    }
+
+    IssueDraft { title, body: format!("{}\n", body.trim_end()) }
+}
+
+/// First line of a prompt, cut at a word boundary when long
+pub(super) fn title_of(prompt: &str) -> Option<String> {
+    let line = prompt.lines().map(str::trim).find(|line| !line.is_empty())?;
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// Text as a markdown block quote
+fn quote(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Commands the tools ran, and the last error one of them printed
+fn reproduction(messages: &[Message]) -> String {
+    let mut commands: HashMap<&str, String> = HashMap::new();
+    let mut ran = Vec::new();
+    let mut error = None;
+    for message in messages {
+        for block in &message.content {
+            match block {
+                ContentBlock::ToolUse { id, name, input } if name == "bash" => {
+                    let arguments: HashMap<String, Value> = serde_json::from_value(input.clone()).unwrap_or_default();
+                    if let Some(command) = shell::command_of(&arguments) {
+                        commands.insert(id.as_str(), command.clone());
+                        ran.push(command);
+                    }
+                }
+                ContentBlock::ToolResult { tool_call_id, content } => {
+                    if let Some(command) = commands.get(tool_call_id.as_str()).filter(|_| looks_like_error(content)) {
+                        error = Some((command.clone(), content.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if ran.is_empty() {
+        return "_No commands were run._".to_string();
+    }
+    let mut text = format!("```sh\n{}\n```", ran.join("\n"));
+    if let Some((command, output)) = error {
+        let lines: Vec<&str> = output.trim_end().lines().collect();
+        let kept = &lines[lines.len().saturating_sub(MAX_ERROR_LINES)..];
+        text.push_str(&format!("\n\n`{}` failed with:\n\n```text\n{}\n```", command, kept.join("\n")));
+    }
+    text
+}
+
+/// Whether a command's output reports a failure
+fn looks_like_error(output: &str) -> bool {
+    static ERROR: OnceLock<Regex> = OnceLock::new();
+    ERROR
+        .get_or_init(|| Regex::new(r"(?im)^error\b|\berror(\[\w+\])?:|panicked at|^Traceback|exit (code|status):? [1-9]|\bFAILED\b").unwrap())
+        .is_match(output)
+}
+
+/// The assistant's last answer, leaving out dividers and change summaries
+fn last_answer(messages: &[Message]) -> Option<String> {
+    messages
+        .iter()
+        .rev()
+        .filter(|message| message.role == MessageRole::Assistant)
+        .filter(|message| !message.metadata.contains_key(MERGE_DIVIDER_KEY) && !message.metadata.contains_key(TURN_CHANGES_KEY))
+        .filter_map(Message::get_text_content)
+        .map(|text| text.trim().to_string())
+        .find(|text| !text.is_empty())
+}
+
+/// Changes the tools made, one per file in the order files were first changed
+fn relevant_diffs(messages: &[Message]) -> Vec<FileChange> {
+    let mut files: Vec<FileChange> = Vec::new();
+    let changes = messages
+        .iter()
+        .filter_map(|message| message.metadata.get(FILE_CHANGE_KEY))
+        .filter_map(|change| serde_json::from_value::<FileChange>(change.clone()).ok());
+    for change in changes {
+        match files.iter_mut().find(|file| file.path == change.path) {
+            Some(file) => {
+                file.additions += change.additions;
+                file.removals += change.removals;
+                file.diff = format!("{}\n{}", file.diff.trim_end(), change.diff);
+            }
+            None => files.push(change),
+        }
+    }
+    files
+}
+
+impl SessionManager {
+    /// Draft an issue from a session, or from the messages in `range` of it
+    pub async fn issue_from_session(&self, session_id: &str, range: Option<Range<usize>>) -> Result<IssueDraft> {
+        let session = self.get_session(session_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_id))?;
+        let messages = self.get_messages(session_id, None).await?;
+        let messages = match range {
+            Some(range) if range.start >= range.end || range.end > messages.len() => anyhow::bail!(
+                "Messages {}-{} are not in the session, which has {}",
+                range.start + 1,
+                range.end,
+                messages.len()
+            ),
+            Some(range) => &messages[range],
+            None => &messages[..],
+        };
+        Ok(draft_issue(&session.title, messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool_call(id: &str, command: &str) -> Message {
+        let mut message = Message::new_assistant(String::new());
+        message.content = vec![ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "bash".to_string(),
+            input: json!({ "command": command }),
+        }];
+        message
+    }
+
+    fn tool_result(id: &str, output: &str, change: Option<FileChange>) -> Message {
+        let mut message = Message::new_assistant(String::new());
+        message.role = MessageRole::Tool;
+        message.content = vec![ContentBlock::ToolResult { tool_call_id: id.to_string(), content: output.to_string() }];
+        if let Some(change) = change {
+            message.metadata.insert(FILE_CHANGE_KEY.to_string(), serde_json::to_value(change).unwrap());
+        }
+        message
+    }
+
+    #[test]
+    fn test_issue_is_drafted_from_the_conversation() {
+        let edit = FileChange {
+            path: "src/parse.rs".to_string(),
+            additions: 1,
+            removals: 1,
+            diff: "--- a/src/parse.rs\n+++ b/src/parse.rs\n@@ -3 +3 @@\n-    &line[1..]\n+    line.get(1..).unwrap_or_default()\n".to_string(),
+        };
+        let messages = vec![
+            Message::new_user("Parsing an empty line crashes the importer\nIt happens on every CSV with a trailing newline.".to_string()),
+            tool_call("call_1", "cargo test parse"),
+            tool_result("call_1", "running 3 tests\nthread 'parse::empty' panicked at src/parse.rs:3:6:\nbyte index 1 is out of range", None),
+            tool_result("call_2", "edited", Some(edit)),
+            Message::new_assistant("Slicing `&line[1..]` panics on empty lines; use `get` instead.".to_string()),
+        ];
+
+        let draft = draft_issue("Importer", &messages);
+        assert_eq!(draft.title, "Parsing an empty line crashes the importer");
+        assert!(draft.body.starts_with("## Summary\n\n> Parsing an empty line crashes the importer\n> It happens"), "{}", draft.body);
+        assert!(draft.body.contains("## Reproduction\n\n```sh\ncargo test parse\n```\n\n`cargo test parse` failed with:"), "{}", draft.body);
+        assert!(draft.body.contains("panicked at src/parse.rs:3:6"));
+        assert!(draft.body.contains("## Proposed fix\n\nSlicing `&line[1..]` panics"));
+        assert!(draft.body.contains("<summary>src/parse.rs (+1 -1)</summary>\n\n```diff\n--- a/src/parse.rs"));
+        assert!(draft.to_markdown().starts_with("# Parsing an empty line"));
+
+        let answer_only = draft_issue("Importer", &messages[4..]);
+        assert_eq!(answer_only.title, "Importer");
+        assert!(answer_only.body.contains("_No commands were run._"));
+        assert!(!answer_only.body.contains("## Relevant diffs"));
+    }
+
+    #[test]
+    fn test_long_titles_are_cut_at_a_word() {
+        let title = title_of(&"word ".repeat(30)).unwrap();
+        assert!(title.chars().count() <= MAX_TITLE_CHARS + 1);
+        assert!(title.ends_with("word…"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Recovery journal for answers being streamed
+//!
+//! An answer is added to its session once it is complete, so a crash before
+//! then would lose what had arrived. Until it is stored, each chunk is
+//! appended to a journal of the session, a JSON lines file under
+//! `<data dir>/journal` that is removed once the answer is stored. Streamed
+//! answers are journaled chunk by chunk, and those of a turn with tools from
+//! when the turn starts. When a session is loaded and its journal is still
+//! there, the text it holds is added to the session as an answer marked
+//! interrupted, which `/continue` asks the model to finish.
+//!
+//! Chunks are flushed to the file as they are written, so they survive the
+//! process dying; only a crash of the machine can lose the last of them. A
+//! line cut short by the crash is skipped.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+
+use crate::llm::{models::MessageModel, Message, MessageRole};
+
+/// Message metadata key marking an answer cut off by a crash
+pub const INTERRUPTED_KEY: &str = "interrupted";
+
+/// Prompt asking the model to finish an interrupted answer
+pub const CONTINUE_PROMPT: &str = "Your previous answer was cut off. Continue it from exactly where it \
+stopped, without repeating what you already wrote.";
+
+/// A line of the journal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Entry {
+    /// First line, naming the answer
+    Started {
+        message_id: String,
+        model: MessageModel,
+        at: DateTime<Utc>,
+    },
+    Delta {
+        text: String,
+    },
+}
+
+/// Journals of the answers being streamed, one file per session
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Journal directory under the data directory
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("journal")
+    }
+
+    fn path(&self, session_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", session_id))
+    }
+
+    /// Start journaling an answer of a session, replacing what its journal held
+    pub async fn start(&self, session_id: &str, message_id: &str, model: &MessageModel) -> Result<JournalWriter> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create journal directory {}", self.dir.display()))?;
+        let path = self.path(session_id);
+        let file = File::create(&path).await.with_context(|| format!("Failed to create journal {}", path.display()))?;
+        let mut writer = JournalWriter { file, path };
+        writer
+            .write(&Entry::Started {
+                message_id: message_id.to_string(),
+                model: model.clone(),
+                at: Utc::now(),
+            })
+            .await?;
+        Ok(writer)
+    }
+
+    /// The answer left in a session's journal, marked interrupted; the
+    /// journal is removed
+    ///
+    /// `None` when nothing was being streamed, or nothing had arrived yet.
+    pub async fn recover(&self, session_id: &str) -> Result<Option<Message>> {
+        let path = self.path(session_id);
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read journal {}", path.display())),
+        };
+
+        let mut started = None;
+        let mut text = String::new();
+        for line in content.lines() {
+            match serde_json::from_str(line) {
+                Ok(Entry::Started { message_id, model, .. }) => started = Some((message_id, model)),
+                Ok(Entry::Delta { text: delta }) => text.push_str(&delta),
+                // Cut short by the crash
+                Err(_) => {}
+            }
+        }
+        fs::remove_file(&path).await.with_context(|| format!("Failed to remove journal {}", path.display()))?;
+
+        let Some((message_id, model)) = started.filter(|_| !text.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let mut message = Message::new_assistant(text);
+        message.id = message_id;
+        model.mark(&mut message);
+        message.metadata.insert(INTERRUPTED_KEY.to_string(), json!(true));
+        Ok(Some(message))
+    }
+}
+
+/// Journal of one answer as it streams
+///
+/// Dropping the writer without finishing leaves the journal to recover.
+#[derive(Debug)]
+pub struct JournalWriter {
+    file: File,
+    path: PathBuf,
+}
+
+impl JournalWriter {
+    /// Record a chunk of the answer
+    pub async fn append(&mut self, text: &str) -> Result<()> {
+        self.write(&Entry::Delta { text: text.to_string() }).await
+    }
+
+    /// Remove the journal once the answer is stored
+    pub async fn finish(self) -> Result<()> {
+        fs::remove_file(&self.path)
+            .await
+            .with_context(|| format!("Failed to remove journal {}", self.path.display()))
+    }
+
+    async fn write(&mut self, entry: &Entry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // Flushed so the line reaches the file before the next chunk arrives
+        async {
+            self.file.write_all(line.as_bytes()).await?;
+            self.file.flush().await
+        }
+        .await
+        .with_context(|| format!("Failed to write journal {}", self.path.display()))
+    }
+}
+
+/// Check if a message is an answer cut off by a crash
+pub fn is_interrupted(message: &Message) -> bool {
+    message.role == MessageRole::Assistant && message.metadata.get(INTERRUPTED_KEY).and_then(|value| value.as_bool()) == Some(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interrupted_answers_are_recovered_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal"));
+        let model = MessageModel::new("openai", "gpt-4o");
+
+        let mut writer = journal.start("s1", "m1", &model).await.unwrap();
+        writer.append("Ownership means ").await.unwrap();
+        writer.append("each value has one owner").await.unwrap();
+        // The process dies mid-write
+        drop(writer);
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("journal/s1.jsonl"))
+            .and_then(|mut file| std::io::Write::write_all(&mut file, b"{\"type\":\"delta\",\"te"))
+            .unwrap();
+
+        let message = journal.recover("s1").await.unwrap().unwrap();
+        assert_eq!(message.id, "m1");
+        assert_eq!(message.get_text_content().as_deref(), Some("Ownership means each value has one owner"));
+        assert_eq!(MessageModel::of(&message), Some(model.clone()));
+        assert!(is_interrupted(&message));
+        assert!(journal.recover("s1").await.unwrap().is_none());
+
+        // Finished answers leave nothing behind, and nor do empty ones
+        let mut writer = journal.start("s2", "m2", &model).await.unwrap();
+        writer.append("Done").await.unwrap();
+        writer.finish().await.unwrap();
+        assert!(journal.recover("s2").await.unwrap().is_none());
+        drop(journal.start("s3", "m3", &model).await.unwrap());
+        assert!(journal.recover("s3").await.unwrap().is_none());
+        assert!(!dir.path().join("journal/s3.jsonl").exists());
+    }
+}
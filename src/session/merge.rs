@@ -0,0 +1,236 @@
+//! Merging two sessions into a new one
+//!
+//! Messages of both sessions are copied into a new session, either one
+//! session after the other or interleaved turn by turn in the order the turns
+//! started. A turn is a user prompt with everything that followed it, so tool
+//! calls always stay next to their results. A divider message marks every
+//! point where the merged conversation switches from one source to the other;
+//! dividers are shown to the user but never sent to the model.
+//!
+//! The source sessions are left untouched. The merged session records them
+//! under `merged_from`, and every copied message records the session and
+//! message it came from, along with its original timestamp.
+
+use anyhow::{bail, Result};
+use chrono::{Duration, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Session, SessionManager};
+use crate::llm::{Message, MessageRole};
+
+/// Session metadata key listing the sessions a session was merged from
+pub const MERGED_FROM_KEY: &str = "merged_from";
+
+/// Message metadata key recording where a merged message came from
+pub const MERGE_SOURCE_KEY: &str = "merge_source";
+
+/// Message metadata key marking a divider between merged sessions
+pub const MERGE_DIVIDER_KEY: &str = "merge_divider";
+
+/// How the messages of the two sessions are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// All of the first session, then all of the second
+    #[default]
+    Append,
+    /// Turns of both sessions in the order they started
+    Interleave,
+}
+
+/// Combine the messages of two sessions, with dividers where the source changes
+pub fn merge_messages(
+    first: (&Session, &[Message]),
+    second: (&Session, &[Message]),
+    strategy: MergeStrategy,
+) -> Vec<Message> {
+    let mut turns: Vec<(&Session, &[Message])> = Vec::new();
+    for (session, messages) in [first, second] {
+        turns.extend(split_turns(messages).into_iter().map(|turn| (session, turn)));
+    }
+    if strategy == MergeStrategy::Interleave {
+        // Stable, so turns starting at the same time keep the first session first
+        turns.sort_by_key(|(_, turn)| turn[0].timestamp);
+    }
+
+    let mut merged = Vec::new();
+    let mut current: Option<&str> = None;
+    for (session, turn) in turns {
+        if current != Some(session.id.as_str()) {
+            merged.push(divider(session));
+            current = Some(&session.id);
+        }
+        for message in turn {
+            let mut copy = message.clone();
+            copy.id = uuid::Uuid::new_v4().to_string();
+            copy.metadata.insert(
+                MERGE_SOURCE_KEY.to_string(),
+                json!({
+                    "session_id": session.id,
+                    "message_id": message.id,
+                    "timestamp": message.timestamp,
+                }),
+            );
+            merged.push(copy);
+        }
+    }
+
+    // Storage orders messages by timestamp, so restamp them in merged order.
+    // Whole seconds keep the stored timestamps sortable as text.
+    let start = Utc::now().trunc_subsecs(0) - Duration::seconds(merged.len() as i64);
+    for (i, message) in merged.iter_mut().enumerate() {
+        message.timestamp = start + Duration::seconds(i as i64);
+    }
+    merged
+}
+
+/// Split messages into turns, each starting at a user prompt
+pub(super) fn split_turns(messages: &[Message]) -> Vec<&[Message]> {
+    let mut turns = Vec::new();
+    let mut start = 0;
+    for (i, message) in messages.iter().enumerate() {
+        if i > start && message.role == MessageRole::User {
+            turns.push(&messages[start..i]);
+            start = i;
+        }
+    }
+    if start < messages.len() {
+        turns.push(&messages[start..]);
+    }
+    turns
+}
+
+fn divider(session: &Session) -> Message {
+    let mut message = Message::new_assistant(format!("── Merged from \"{}\" ({}) ──", session.title, session.id));
+    message.metadata.insert(MERGE_DIVIDER_KEY.to_string(), json!(session.id));
+    message
+}
+
+impl SessionManager {
+    /// Merge two sessions into a new session with the given title
+    pub async fn merge_sessions(
+        &self,
+        first_id: &str,
+        second_id: &str,
+        title: String,
+        strategy: MergeStrategy,
+    ) -> Result<Session> {
+        if first_id == second_id {
+            bail!("Cannot merge session {} with itself", first_id);
+        }
+        let first = self.get_session(first_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", first_id))?;
+        let second = self.get_session(second_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", second_id))?;
+        let first_messages = self.get_messages(first_id, None).await?;
+        let second_messages = self.get_messages(second_id, None).await?;
+
+        let messages = merge_messages((&first, &first_messages), (&second, &second_messages), strategy);
+
+        let mut merged = Session::new(title, None);
+        merged.message_count = messages.len() as u32;
+        merged.update_usage(&first.token_usage, first.total_cost);
+        merged.update_usage(&second.token_usage, second.total_cost);
+        merged.set_metadata(
+            MERGED_FROM_KEY.to_string(),
+            json!([
+                { "id": first.id, "title": first.title },
+                { "id": second.id, "title": second.title },
+            ]),
+        );
+        merged.set_metadata("merge_strategy".to_string(), serde_json::to_value(strategy)?);
+
+        self.storage().put_messages(&merged.id, &messages).await?;
+        self.update_session(&merged).await?;
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::TokenUsage;
+    use crate::session::storage::SqliteStorage;
+    use std::sync::Arc;
+
+    fn message(role: MessageRole, text: &str, minute: i64) -> Message {
+        let mut message = Message::new_text(role, text.to_string());
+        message.timestamp = Utc::now() - Duration::hours(1) + Duration::minutes(minute);
+        message
+    }
+
+    fn texts(messages: &[Message]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|m| {
+                if m.metadata.contains_key(MERGE_DIVIDER_KEY) {
+                    "--".to_string()
+                } else {
+                    m.get_text_content().unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_interleave_keeps_turns_together() {
+        let main = Session::new("Main".to_string(), None);
+        let experiment = Session::new("Experiment".to_string(), None);
+        let main_messages = vec![
+            message(MessageRole::User, "a1", 0),
+            message(MessageRole::Assistant, "a2", 1),
+            message(MessageRole::User, "a3", 10),
+        ];
+        // The experiment's turn started between the main session's turns but
+        // ended after the main session's second prompt
+        let experiment_messages = vec![
+            message(MessageRole::User, "b1", 5),
+            message(MessageRole::Tool, "b2", 11),
+        ];
+
+        let append = merge_messages(
+            (&main, &main_messages),
+            (&experiment, &experiment_messages),
+            MergeStrategy::Append,
+        );
+        assert_eq!(texts(&append), ["--", "a1", "a2", "a3", "--", "b1", "b2"]);
+
+        let interleave = merge_messages(
+            (&main, &main_messages),
+            (&experiment, &experiment_messages),
+            MergeStrategy::Interleave,
+        );
+        assert_eq!(texts(&interleave), ["--", "a1", "a2", "--", "b1", "b2", "--", "a3"]);
+        assert!(interleave.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(interleave[4].metadata[MERGE_SOURCE_KEY]["message_id"], experiment_messages[0].id);
+    }
+
+    #[tokio::test]
+    async fn test_merge_sessions_sums_usage_and_keeps_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("sessions.db")).await.unwrap();
+        let manager = SessionManager::with_storage(Arc::new(storage));
+
+        let main = manager.create_session("Main".to_string(), None).await.unwrap();
+        let experiment = manager.create_session("Experiment".to_string(), Some(main.id.clone())).await.unwrap();
+        for (session, text) in [(&main, "a"), (&experiment, "b")] {
+            manager.add_message(&session.id, &Message::new_user(text.to_string())).await.unwrap();
+            let usage = TokenUsage { input_tokens: 10, output_tokens: 5, total_tokens: 15 };
+            manager.update_session_usage(&session.id, &usage, 0.5).await.unwrap();
+        }
+
+        let merged = manager
+            .merge_sessions(&main.id, &experiment.id, "Merged".to_string(), MergeStrategy::Append)
+            .await
+            .unwrap();
+        assert_eq!(merged.token_usage.total_tokens, 30);
+        assert_eq!(merged.total_cost, 1.0);
+        assert_eq!(merged.get_metadata(MERGED_FROM_KEY).unwrap()[1]["id"], experiment.id);
+
+        let messages = manager.get_messages(&merged.id, None).await.unwrap();
+        assert_eq!(texts(&messages), ["--", "a", "--", "b"]);
+        assert_eq!(manager.get_messages(&main.id, None).await.unwrap().len(), 1);
+        assert!(manager.merge_sessions(&main.id, &main.id, "x".to_string(), MergeStrategy::Append).await.is_err());
+    }
+}
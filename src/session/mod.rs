@@ -6,7 +6,31 @@
 mod session;
 mod conversation;
 mod database;
+mod merge;
+mod compare;
+mod resume;
+mod suggestions;
+mod titles;
+mod search;
+mod issue;
+pub mod archive;
+pub mod compaction;
+pub mod journal;
+pub mod rewind;
+pub mod prompt_history;
+pub mod snapshots;
+pub mod storage;
+pub mod semantic;
 
 pub use session::*;
 pub use conversation::*;
-pub use database::*;
\ No newline at end of file
+pub use database::*;
+pub use merge::*;
+pub use compare::*;
+pub use resume::*;
+pub use suggestions::*;
+pub use titles::*;
+pub use search::*;
+pub use issue::*;
+pub use archive::ArchivedSession;
+pub use semantic::{SemanticIndex, SemanticMatch};
\ No newline at end of file
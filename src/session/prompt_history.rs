@@ -0,0 +1,148 @@
+//! Prompt history of a project
+//!
+//! Prompts sent from inline chat and from `goofy run` are kept in one history
+//! per project, so a prompt first tried from a script can be recalled in the
+//! interface with Up or Ctrl+R. Each project's history is a JSON lines file
+//! under `<data dir>/history`, named after a hash of the workspace path.
+//! Appending a line keeps concurrent writers from losing each other's
+//! prompts; the file is cut back to the newest prompts once it grows well
+//! past [`MAX_ENTRIES`].
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::assets::sha256_hex;
+
+/// Prompts kept per project
+pub const MAX_ENTRIES: usize = 1000;
+
+/// Where a prompt was sent from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSource {
+    Tui,
+    Run,
+}
+
+/// A prompt in the history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub prompt: String,
+    pub source: PromptSource,
+    pub at: DateTime<Utc>,
+}
+
+/// Prompt history of one project
+#[derive(Debug, Clone)]
+pub struct PromptHistory {
+    path: PathBuf,
+}
+
+impl PromptHistory {
+    /// History kept in the file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// History of the project in `workspace`, within the data directory
+    pub fn for_workspace(data_dir: &Path, workspace: &Path) -> Self {
+        let key = sha256_hex(workspace.to_string_lossy().as_bytes());
+        Self::new(data_dir.join("history").join(format!("{}.jsonl", &key[..16])))
+    }
+
+    /// Every prompt in the history, oldest first
+    ///
+    /// Lines that cannot be read are skipped.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read prompt history {}", self.path.display())),
+        };
+        Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+
+    /// Prompts to recall, oldest first, each only at its latest use
+    pub fn prompts(&self) -> Result<Vec<String>> {
+        let mut prompts: Vec<String> = Vec::new();
+        for entry in self.entries()? {
+            prompts.retain(|prompt| *prompt != entry.prompt);
+            prompts.push(entry.prompt);
+        }
+        Ok(prompts)
+    }
+
+    /// Add a prompt to the history
+    ///
+    /// Blank prompts are not kept.
+    pub fn record(&self, prompt: &str, source: PromptSource) -> Result<()> {
+        if prompt.trim().is_empty() {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let entry = HistoryEntry {
+            prompt: prompt.to_string(),
+            source,
+            at: Utc::now(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open prompt history {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        drop(file);
+
+        if fs::metadata(&self.path).map_or(false, |metadata| metadata.len() > (MAX_ENTRIES as u64) * 512) {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Keep only the newest [`MAX_ENTRIES`] prompts
+    fn compact(&self) -> Result<()> {
+        let entries = self.entries()?;
+        if entries.len() <= MAX_ENTRIES * 2 {
+            return Ok(());
+        }
+        let kept = &entries[entries.len() - MAX_ENTRIES..];
+        let mut text = String::new();
+        for entry in kept {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, text)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_are_shared_per_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = PromptHistory::for_workspace(dir.path(), Path::new("/code/app"));
+        history.record("explain the build", PromptSource::Run).unwrap();
+        history.record("   ", PromptSource::Tui).unwrap();
+
+        // Another handle on the same project sees the prompt from the script
+        let tui = PromptHistory::for_workspace(dir.path(), Path::new("/code/app"));
+        tui.record("add a test", PromptSource::Tui).unwrap();
+        tui.record("explain the build", PromptSource::Tui).unwrap();
+        assert_eq!(tui.prompts().unwrap(), ["add a test", "explain the build"]);
+        assert_eq!(tui.entries().unwrap()[0].source, PromptSource::Run);
+
+        let other = PromptHistory::for_workspace(dir.path(), Path::new("/code/other"));
+        assert!(other.prompts().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Resuming earlier sessions
+//!
+//! A session remembers the directory it was started in and the tools the
+//! user allowed for the whole session. Resuming it loads its messages from
+//! storage, goes back to that directory and allows those tools again, so the
+//! conversation continues where it stopped without asking the same questions.
+
+use anyhow::{bail, Result};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use super::{Session, SessionManager};
+
+/// Session metadata key with the directory the session was started in
+pub const WORKING_DIRECTORY_KEY: &str = "working_directory";
+
+/// Session metadata key listing the tools allowed for the whole session
+pub const ALLOWED_TOOLS_KEY: &str = "allowed_tools";
+
+impl Session {
+    /// Directory the session was started in, if recorded
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.get_metadata(WORKING_DIRECTORY_KEY)
+            .and_then(|value| value.as_str())
+            .map(PathBuf::from)
+    }
+
+    /// Tools the user allowed for the whole session
+    pub fn allowed_tools(&self) -> Vec<String> {
+        self.get_metadata(ALLOWED_TOOLS_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Check if the session has a conversation to continue, in a directory
+    /// that still exists
+    pub fn is_resumable(&self) -> bool {
+        self.message_count > 0 && self.working_directory().map_or(true, |dir| dir.is_dir())
+    }
+}
+
+impl SessionManager {
+    /// The session to resume, failing with a hint when it can't be
+    pub async fn resumable_session(&self, id: &str) -> Result<Session> {
+        if let Some(session) = self.get_session(id).await? {
+            return Ok(session);
+        }
+        if self.get_archived_session(id).await?.is_some() {
+            bail!("Session {} is archived; restore it with `goofy sessions unarchive {}`", id, id);
+        }
+        bail!("Session not found: {}; `goofy sessions list --resumable` lists the sessions to resume", id)
+    }
+
+    /// Remember the directory a session works in
+    pub async fn record_working_directory(&self, session_id: &str, dir: &Path) -> Result<()> {
+        self.set_session_metadata(session_id, WORKING_DIRECTORY_KEY.to_string(), json!(dir.to_string_lossy()))
+            .await
+    }
+
+    /// Remember the tools allowed for the whole session, if they changed
+    pub async fn record_allowed_tools(&self, session_id: &str, tools: &[String]) -> Result<()> {
+        let Some(session) = self.get_session(session_id).await? else {
+            return Ok(());
+        };
+        if session.allowed_tools() == tools {
+            return Ok(());
+        }
+        self.set_session_metadata(session_id, ALLOWED_TOOLS_KEY.to_string(), json!(tools))
+            .await
+    }
+
+    /// Sessions that can be resumed, most recent first
+    pub async fn list_resumable_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+        let mut sessions = self.list_sessions(None).await?;
+        sessions.retain(Session::is_resumable);
+        if let Some(limit) = limit {
+            sessions.truncate(limit as usize);
+        }
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::Message;
+    use crate::session::storage::SqliteStorage;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sessions_remember_what_resuming_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("sessions.db")).await.unwrap();
+        let manager = SessionManager::with_storage(Arc::new(storage));
+
+        let session = manager.create_session("Work".to_string(), None).await.unwrap();
+        manager.record_working_directory(&session.id, dir.path()).await.unwrap();
+        manager.record_allowed_tools(&session.id, &["bash".to_string(), "edit".to_string()]).await.unwrap();
+        assert!(manager.list_resumable_sessions(None).await.unwrap().is_empty());
+
+        manager.add_message(&session.id, &Message::new_user("hello".to_string())).await.unwrap();
+        let resumed = manager.resumable_session(&session.id).await.unwrap();
+        assert_eq!(resumed.working_directory().as_deref(), Some(dir.path()));
+        assert_eq!(resumed.allowed_tools(), ["bash", "edit"]);
+        assert!(resumed.is_resumable());
+        assert_eq!(manager.list_resumable_sessions(Some(5)).await.unwrap().len(), 1);
+
+        // A session whose directory is gone can't continue there
+        let mut moved = resumed.clone();
+        moved.set_metadata(WORKING_DIRECTORY_KEY.to_string(), json!(dir.path().join("gone")));
+        assert!(!moved.is_resumable());
+
+        let error = manager.resumable_session("missing").await.unwrap_err().to_string();
+        assert!(error.contains("sessions list --resumable"), "{}", error);
+    }
+}
@@ -0,0 +1,214 @@
+//! Editing an earlier prompt of a conversation
+//!
+//! Editing a prompt, or regenerating the answer to it, sends the prompt again
+//! in place of the original. What followed the original is either dropped
+//! from the conversation, or left in its session while the conversation goes
+//! on in a new branch.
+//!
+//! Dropping is recorded rather than done: a rewind message appended to the
+//! session names the first message dropped, and the messages from there up
+//! to the rewind are left out when the session is loaded. The session keeps
+//! every message, so dropped turns can still be searched and synced. A
+//! branch is a child session holding copies of the messages before the
+//! edited prompt, and records where it branched off under `branched_from`.
+//! A fork is a branch of the whole session, made from the sessions list.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::compaction::{Compaction, COMPACTION_KEY};
+use super::{Session, SessionManager, TITLED_KEY};
+use crate::llm::{Message, MessageRole};
+
+/// Message metadata key marking a rewind
+pub const REWIND_KEY: &str = "rewind";
+
+/// Session metadata key recording the session and prompt a branch started from
+pub const BRANCHED_FROM_KEY: &str = "branched_from";
+
+/// Messages dropped from a conversation to edit a prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rewind {
+    /// The prompt that was edited, the first message dropped
+    pub first_dropped_id: String,
+    pub dropped_messages: usize,
+}
+
+impl Rewind {
+    /// Message recording the rewind in the session
+    pub fn message(&self) -> Message {
+        let mut message = Message::new_assistant(format!(
+            "── Dropped {} messages to edit an earlier prompt ──",
+            self.dropped_messages
+        ));
+        message.metadata.insert(REWIND_KEY.to_string(), json!(self));
+        message
+    }
+}
+
+/// Index of the prompt with the given ID
+pub fn prompt_index(messages: &[Message], message_id: &str) -> Result<usize> {
+    match messages.iter().position(|m| m.id == message_id) {
+        Some(index) if messages[index].role == MessageRole::User => Ok(index),
+        Some(_) => bail!("Only prompts can be edited"),
+        None => bail!("No message {} in the conversation", message_id),
+    }
+}
+
+/// Messages of a session with its rewinds applied
+///
+/// Rewind messages themselves are left out.
+pub fn apply(messages: Vec<Message>) -> Vec<Message> {
+    let mut kept: Vec<Message> = Vec::with_capacity(messages.len());
+    for message in messages {
+        let rewind = message
+            .metadata
+            .get(REWIND_KEY)
+            .and_then(|value| serde_json::from_value::<Rewind>(value.clone()).ok());
+        match rewind {
+            Some(rewind) => {
+                if let Some(index) = kept.iter().position(|m| m.id == rewind.first_dropped_id) {
+                    kept.truncate(index);
+                }
+            }
+            None => kept.push(message),
+        }
+    }
+    kept
+}
+
+/// Copies of messages to start a branch with, under new IDs
+///
+/// Compactions among them point at the copies of the messages they kept.
+pub fn branch_copies(messages: &[Message]) -> Vec<Message> {
+    let ids: HashMap<&str, String> = messages
+        .iter()
+        .map(|message| (message.id.as_str(), uuid::Uuid::new_v4().to_string()))
+        .collect();
+    messages
+        .iter()
+        .map(|message| {
+            let mut copy = message.clone();
+            copy.id = ids[message.id.as_str()].clone();
+            if let Some(value) = copy.metadata.get_mut(COMPACTION_KEY) {
+                if let Ok(mut compaction) = serde_json::from_value::<Compaction>(value.clone()) {
+                    if let Some(id) = ids.get(compaction.first_kept_id.as_str()) {
+                        compaction.first_kept_id = id.clone();
+                        *value = json!(compaction);
+                    }
+                }
+            }
+            copy
+        })
+        .collect()
+}
+
+impl SessionManager {
+    /// Copy a session's conversation, as it loads, into a new child session
+    pub async fn fork_session(&self, id: &str) -> Result<Session> {
+        let Some(parent) = self.get_session(id).await? else {
+            bail!("Session not found: {}", id);
+        };
+        let messages = apply(self.get_messages(id, None).await?);
+        let fork = self.create_session(format!("{} (fork)", parent.title), Some(parent.id.clone())).await?;
+        if let Some(dir) = parent.working_directory() {
+            self.record_working_directory(&fork.id, &dir).await?;
+        }
+        self.set_session_metadata(&fork.id, BRANCHED_FROM_KEY.to_string(), json!({ "session_id": parent.id }))
+            .await?;
+        // The fork keeps its parent's title instead of being named again
+        self.set_session_metadata(&fork.id, TITLED_KEY.to_string(), json!(true)).await?;
+        for message in branch_copies(&messages) {
+            self.add_message(&fork.id, &message).await?;
+        }
+        self.resumable_session(&fork.id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::new_user("Write a haiku".to_string()),
+            Message::new_assistant("Autumn moonlight".to_string()),
+            Message::new_user("Make it rhyme".to_string()),
+            Message::new_assistant("Moon in June".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_rewinds_drop_what_followed_the_prompt() {
+        let mut messages = conversation();
+        assert!(prompt_index(&messages, &messages[1].id).is_err());
+        let index = prompt_index(&messages, &messages[2].id).unwrap();
+        assert_eq!(index, 2);
+
+        let rewind = Rewind {
+            first_dropped_id: messages[2].id.clone(),
+            dropped_messages: 2,
+        };
+        messages.push(rewind.message());
+        messages.push(Message::new_user("Make it longer".to_string()));
+        let kept = apply(messages.clone());
+        let texts: Vec<String> = kept.iter().filter_map(|m| m.get_text_content()).collect();
+        assert_eq!(texts, ["Write a haiku", "Autumn moonlight", "Make it longer"]);
+
+        // Rewinding again drops the edited prompt as well
+        let again = Rewind {
+            first_dropped_id: kept[2].id.clone(),
+            dropped_messages: 1,
+        };
+        messages.push(again.message());
+        assert_eq!(apply(messages).len(), 2);
+    }
+
+    #[test]
+    fn test_branch_copies_keep_compactions_pointing_at_their_messages() {
+        let mut messages = conversation();
+        let compaction = Compaction {
+            reason: "prompt is too long".to_string(),
+            summary: "A haiku was written".to_string(),
+            compacted_messages: 2,
+            first_kept_id: messages[2].id.clone(),
+        };
+        messages.push(compaction.message());
+
+        let copies = branch_copies(&messages);
+        assert_eq!(copies.len(), messages.len());
+        assert!(copies.iter().zip(&messages).all(|(copy, message)| copy.id != message.id));
+        let copied: Compaction = serde_json::from_value(copies[4].metadata[COMPACTION_KEY].clone()).unwrap();
+        assert_eq!(copied.first_kept_id, copies[2].id);
+    }
+
+    #[tokio::test]
+    async fn test_forks_copy_the_conversation_as_it_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = crate::session::storage::SqliteStorage::open(dir.path().join("sessions.db")).await.unwrap();
+        let manager = SessionManager::with_storage(std::sync::Arc::new(storage));
+
+        let session = manager.create_session("Haiku".to_string(), None).await.unwrap();
+        let mut messages = conversation();
+        messages.push(
+            Rewind {
+                first_dropped_id: messages[2].id.clone(),
+                dropped_messages: 2,
+            }
+            .message(),
+        );
+        for message in &messages {
+            manager.add_message(&session.id, message).await.unwrap();
+        }
+
+        let fork = manager.fork_session(&session.id).await.unwrap();
+        assert_eq!(fork.title, "Haiku (fork)");
+        assert_eq!(fork.parent_session_id.as_deref(), Some(session.id.as_str()));
+        let copied = manager.get_messages(&fork.id, None).await.unwrap();
+        let texts: Vec<String> = copied.iter().filter_map(|m| m.get_text_content()).collect();
+        assert_eq!(texts, ["Write a haiku", "Autumn moonlight"]);
+        assert!(manager.fork_session("missing").await.is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Full-text search across messages
+//!
+//! The SQLite backend keeps an FTS5 index of the text of every message, kept
+//! up to date as messages are stored; other backends fall back to scanning the
+//! messages. A query is a list of words that all have to appear in a message,
+//! the last one also matching as the start of a longer word, so results show
+//! up while the query is still being typed.
+
+use std::ops::Range;
+
+use crate::llm::{Message, MessageRole};
+
+/// Most messages a search returns
+pub const SEARCH_LIMIT: usize = 200;
+
+/// Characters of context around the first match in a snippet
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A message matching a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageHit {
+    pub session_id: String,
+    pub message_id: String,
+    pub role: MessageRole,
+    /// The text around the first match, on one line
+    pub snippet: String,
+}
+
+impl MessageHit {
+    /// The hit for a message, if its text contains every term
+    pub fn from_message(session_id: &str, message: &Message, terms: &[String]) -> Option<Self> {
+        let text = message.get_text_content().unwrap_or_default();
+        let contains = |term: &String| !match_ranges(&text, std::slice::from_ref(term)).is_empty();
+        if terms.is_empty() || !terms.iter().all(contains) {
+            return None;
+        }
+        Some(Self {
+            session_id: session_id.to_string(),
+            message_id: message.id.clone(),
+            role: message.role.clone(),
+            snippet: snippet(&text, terms),
+        })
+    }
+}
+
+/// The words of a query
+pub fn search_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_string).collect()
+}
+
+/// An FTS5 query for the words of a query, with every word quoted so that
+/// operators and punctuation are taken literally
+pub fn fts_query(query: &str) -> Option<String> {
+    let terms = search_terms(query);
+    let (last, rest) = terms.split_last()?;
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    let mut parts: Vec<String> = rest.iter().map(|term| quote(term)).collect();
+    parts.push(format!("{}*", quote(last)));
+    Some(parts.join(" "))
+}
+
+/// Byte ranges of `text` matching any of the terms, ignoring case, in order
+pub fn match_ranges(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut offsets = text.char_indices().map(|(offset, _)| offset).peekable();
+    while let Some(start) = offsets.next() {
+        let found = terms.iter().filter_map(|term| match_at(&text[start..], term)).max();
+        if let Some(length) = found {
+            ranges.push(start..start + length);
+            while offsets.peek().is_some_and(|offset| *offset < start + length) {
+                offsets.next();
+            }
+        }
+    }
+    ranges
+}
+
+/// Length in bytes of `term` at the start of `text`, ignoring case
+fn match_at(text: &str, term: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for expected in term.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(offset, _)| offset))
+}
+
+/// The text around the first match, on one line
+fn snippet(text: &str, terms: &[String]) -> String {
+    let start = match_ranges(text, terms).first().map_or(0, |range| range.start);
+    let before: Vec<char> = text[..start].chars().rev().take(SNIPPET_CONTEXT + 1).collect();
+    let cut = before.len() > SNIPPET_CONTEXT;
+    let mut snippet: String = before.into_iter().take(SNIPPET_CONTEXT).rev().collect();
+    snippet.extend(text[start..].chars().take(SNIPPET_CONTEXT * 2));
+    let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cut {
+        format!("…{}", snippet)
+    } else {
+        snippet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_are_quoted_for_fts() {
+        assert_eq!(fts_query("lifetime er").unwrap(), "\"lifetime\" \"er\"*");
+        assert_eq!(fts_query("say \"hi\" OR").unwrap(), "\"say\" \"\"\"hi\"\"\" \"OR\"*");
+        assert!(fts_query("   ").is_none());
+    }
+
+    #[test]
+    fn test_matches_ignore_case() {
+        let terms = search_terms("rust Ownership");
+        assert_eq!(match_ranges("Rust ownership and RUST", &terms), [0..4, 5..14, 19..23]);
+        assert_eq!(match_ranges("Ärger über ärger", &search_terms("ärger")), [0..6, 13..19]);
+
+        let message = Message::new_user("Explain ownership in Rust".to_string());
+        let hit = MessageHit::from_message("s1", &message, &terms).unwrap();
+        assert_eq!(hit.snippet, "Explain ownership in Rust");
+        assert!(MessageHit::from_message("s1", &message, &search_terms("borrow")).is_none());
+    }
+}
@@ -0,0 +1,344 @@
+//! Semantic search over past sessions
+//!
+//! Message text is embedded with the configured provider's embedding endpoint
+//! and stored in the `message_embeddings` table of the local SQLite database,
+//! tagged with the embedding model so vectors from different models are never
+//! compared. Indexing is incremental: each session records the update time it
+//! was last embedded at, and only sessions that changed since are revisited.
+
+use anyhow::{anyhow, Context, Result};
+use futures::executor::block_on;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use super::database::{Database, EmbeddingRow};
+use super::{Session, SessionManager};
+use crate::llm::{LlmProvider, Message, MessageRole};
+
+/// Messages embedded per provider request
+const EMBEDDING_BATCH_SIZE: usize = 32;
+
+/// Longest text sent for a single message, in characters
+const MAX_EMBEDDED_CHARS: usize = 8000;
+
+/// Length of the snippet kept for display, in characters
+const SNIPPET_CHARS: usize = 200;
+
+/// A message that matched a semantic search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub session_id: String,
+    pub session_title: String,
+    pub message_id: String,
+    pub snippet: String,
+    /// Cosine similarity to the query
+    pub score: f32,
+    /// Whether the session is archived and has to be restored to open
+    pub archived: bool,
+}
+
+/// Embedding index of session messages
+pub struct SemanticIndex {
+    db: Arc<Mutex<Database>>,
+    provider: Arc<dyn LlmProvider>,
+    model: String,
+}
+
+impl SemanticIndex {
+    /// Open the index in the given database, using the provider's embedding model
+    pub async fn open<P: AsRef<Path>>(path: P, provider: Arc<dyn LlmProvider>) -> Result<Self> {
+        let model = provider
+            .default_embedding_model()
+            .with_context(|| format!("Provider '{}' does not support embeddings", provider.name()))?
+            .to_string();
+        Ok(Self {
+            db: Arc::new(Mutex::new(Database::new(path).await?)),
+            provider,
+            model,
+        })
+    }
+
+    /// Embedding model in use
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Run `query` on the database on a blocking thread, like the session
+    /// storage does, so the connection is never held across an `.await`
+    async fn with_db<T, F>(&self, query: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let db = Arc::clone(&self.db);
+        tokio::task::spawn_blocking(move || {
+            let db = db.lock().map_err(|_| anyhow!("The embedding database is poisoned"))?;
+            query(&db)
+        })
+        .await?
+    }
+
+    /// Embed the messages of a session that are not indexed yet
+    pub async fn index_session(&self, session: &Session, messages: &[Message]) -> Result<()> {
+        let (session_id, model) = (session.id.clone(), self.model.clone());
+        let indexed = self.with_db(move |db| block_on(db.embedded_message_ids(&session_id, &model))).await?;
+        let pending: Vec<(&Message, String)> = messages
+            .iter()
+            .filter(|message| !indexed.contains(&message.id))
+            .filter_map(|message| Some((message, searchable_text(message)?)))
+            .collect();
+
+        for batch in pending.chunks(EMBEDDING_BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+            let vectors = self.provider.embed(&self.model, &texts).await?;
+            if vectors.len() != texts.len() {
+                anyhow::bail!("Expected {} embeddings, got {}", texts.len(), vectors.len());
+            }
+
+            let rows: Vec<EmbeddingRow> = batch
+                .iter()
+                .zip(vectors)
+                .map(|((message, text), vector)| EmbeddingRow {
+                    message_id: message.id.clone(),
+                    session_id: session.id.clone(),
+                    model: self.model.clone(),
+                    snippet: text.chars().take(SNIPPET_CHARS).collect(),
+                    vector,
+                })
+                .collect();
+            self.with_db(move |db| rows.iter().try_for_each(|row| block_on(db.upsert_embedding(row)))).await?;
+        }
+
+        let (session_id, model, updated_at) = (session.id.clone(), self.model.clone(), session.updated_at.to_rfc3339());
+        self.with_db(move |db| block_on(db.set_embedding_watermark(&session_id, &model, &updated_at))).await?;
+        debug!("Indexed {} message(s) of session {}", pending.len(), session.id);
+        Ok(())
+    }
+
+    /// Whether a session changed since it was last indexed
+    pub async fn is_stale(&self, session: &Session) -> Result<bool> {
+        let (session_id, model) = (session.id.clone(), self.model.clone());
+        let watermark = self.with_db(move |db| block_on(db.get_embedding_watermark(&session_id, &model))).await?;
+        Ok(watermark.as_deref() != Some(session.updated_at.to_rfc3339().as_str()))
+    }
+
+    /// Indexed messages ranked by similarity to the query, best first
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<EmbeddingMatch>> {
+        let query_vector = self
+            .provider
+            .embed(&self.model, &[query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .context("The provider returned no embedding for the query")?;
+
+        let model = self.model.clone();
+        let rows = self.with_db(move |db| block_on(db.get_embeddings(&model))).await?;
+        let mut matches: Vec<EmbeddingMatch> = rows
+            .into_iter()
+            .map(|row| EmbeddingMatch {
+                score: cosine_similarity(&query_vector, &row.vector),
+                row,
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+}
+
+/// An indexed message with its similarity to a query
+#[derive(Debug, Clone)]
+pub struct EmbeddingMatch {
+    pub row: EmbeddingRow,
+    pub score: f32,
+}
+
+impl SessionManager {
+    /// Find messages of past sessions by meaning
+    ///
+    /// Sessions that changed since the last search are embedded first, so the
+    /// first search after a long time may take a while. Results are grouped
+    /// per session: only the best-matching message of each is returned.
+    /// Archived sessions are searched too, through the embeddings they had
+    /// when they were archived.
+    pub async fn semantic_search(&self, query: &str, limit: usize) -> Result<Vec<SemanticMatch>> {
+        let index = self
+            .semantic_index()
+            .context("Semantic search is not available: the provider has no embedding endpoint")?;
+
+        for session in self.list_sessions(None).await? {
+            if index.is_stale(&session).await? {
+                let messages = self.get_messages(&session.id, None).await?;
+                index.index_session(&session, &messages).await?;
+            }
+        }
+
+        // Fetch extra candidates so grouping by session still fills the limit
+        let candidates = index.search(query, limit.saturating_mul(5)).await?;
+        let mut results: Vec<SemanticMatch> = Vec::new();
+        for candidate in candidates {
+            if results.len() == limit {
+                break;
+            }
+            if results.iter().any(|r| r.session_id == candidate.row.session_id) {
+                continue;
+            }
+            let (session_title, archived) = match self.get_session(&candidate.row.session_id).await? {
+                Some(session) => (session.title, false),
+                None => match self.get_archived_session(&candidate.row.session_id).await? {
+                    Some(archived) => (archived.title, true),
+                    None => continue,
+                },
+            };
+            results.push(SemanticMatch {
+                session_id: candidate.row.session_id,
+                session_title,
+                message_id: candidate.row.message_id,
+                snippet: candidate.row.snippet,
+                score: candidate.score,
+                archived,
+            });
+        }
+        Ok(results)
+    }
+}
+
+/// Text of a message worth searching, if any
+fn searchable_text(message: &Message) -> Option<String> {
+    if !matches!(message.role, MessageRole::User | MessageRole::Assistant) {
+        return None;
+    }
+    let text = message.get_text_content()?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(text.chars().take(MAX_EMBEDDED_CHARS).collect())
+}
+
+/// Cosine similarity of two vectors, zero when either is empty or of another size
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ChatRequest, LlmError, LlmResult, ProviderEvent, ProviderResponse};
+    use crate::session::storage::SqliteStorage;
+    use async_trait::async_trait;
+    use futures::Stream;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    /// Bag-of-words embeddings: texts sharing words point the same way
+    #[derive(Default)]
+    struct WordEmbedder {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for WordEmbedder {
+        async fn chat_completion(&self, _request: ChatRequest) -> LlmResult<ProviderResponse> {
+            Err(LlmError::ApiError("The word embedder only embeds".to_string()))
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: ChatRequest,
+        ) -> LlmResult<Pin<Box<dyn Stream<Item = LlmResult<ProviderEvent>> + Send>>> {
+            Err(LlmError::StreamError("The word embedder only embeds".to_string()))
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+
+        fn model(&self) -> &str {
+            "words"
+        }
+
+        fn validate_config(&self) -> LlmResult<()> {
+            Ok(())
+        }
+
+        fn default_embedding_model(&self) -> Option<&str> {
+            Some("words-64")
+        }
+
+        async fn embed(&self, _model: &str, inputs: &[String]) -> LlmResult<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    let mut vector = vec![0.0; 64];
+                    for word in text.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| w.len() > 3) {
+                        let bucket = word.bytes().fold(7usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+                        vector[bucket % 64] += 1.0;
+                    }
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_ranks_sessions_by_meaning() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("sessions.db");
+        let embedder = Arc::new(WordEmbedder::default());
+        let index = SemanticIndex::open(&db_path, embedder.clone()).await.unwrap();
+        let manager = SessionManager::with_storage(Arc::new(SqliteStorage::open(&db_path).await.unwrap()))
+            .with_semantic_index(Arc::new(index));
+
+        let rust = manager.create_session("Rust".to_string(), None).await.unwrap();
+        manager
+            .add_message(&rust.id, &Message::new_user("Why does the borrow checker reject this closure?".to_string()))
+            .await
+            .unwrap();
+        let baking = manager.create_session("Baking".to_string(), None).await.unwrap();
+        manager
+            .add_message(&baking.id, &Message::new_user("Suggest a recipe for banana bread".to_string()))
+            .await
+            .unwrap();
+        manager
+            .add_message(&baking.id, &Message::new_assistant("Mash three ripe bananas with butter".to_string()))
+            .await
+            .unwrap();
+
+        let results = manager.semantic_search("banana bread recipe", 5).await.unwrap();
+        assert_eq!(results[0].session_id, baking.id);
+        assert_eq!(results.iter().filter(|r| r.session_id == baking.id).count(), 1);
+
+        // Unchanged sessions are not embedded again
+        let calls = embedder.calls.load(Ordering::SeqCst);
+        manager.semantic_search("closure", 5).await.unwrap();
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), calls + 1);
+
+        // Archived sessions are still found
+        manager.archive_session(&baking.id).await.unwrap();
+        let results = manager.semantic_search("banana bread recipe", 5).await.unwrap();
+        assert_eq!(results[0].session_id, baking.id);
+        assert!(results[0].archived);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+    }
+}
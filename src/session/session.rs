@@ -9,7 +9,10 @@ use uuid::Uuid;
 
 use crate::{
     llm::{Message, TokenUsage},
-    session::database::{Database, SessionRow},
+    session::database::SessionRow,
+    session::search::{MessageHit, SEARCH_LIMIT},
+    session::semantic::SemanticIndex,
+    session::storage::{SqliteStorage, Storage},
 };
 
 /// A conversation session
@@ -67,6 +70,13 @@ impl Session {
     pub fn get_metadata(&self, key: &str) -> Option<&serde_json::Value> {
         self.metadata.get(key)
     }
+    
+    /// Files changed by each turn that changed any, oldest first
+    pub fn change_history(&self) -> Vec<crate::llm::changes::TurnChanges> {
+        self.get_metadata(crate::llm::changes::CHANGE_HISTORY_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
 }
 
 impl From<SessionRow> for Session {
@@ -97,20 +107,64 @@ impl From<SessionRow> for Session {
     }
 }
 
+impl From<&Session> for SessionRow {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.clone(),
+            title: session.title.clone(),
+            parent_session_id: session.parent_session_id.clone(),
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            message_count: session.message_count as i32,
+            total_input_tokens: session.token_usage.input_tokens as i32,
+            total_output_tokens: session.token_usage.output_tokens as i32,
+            total_cost: session.total_cost,
+            metadata: if session.metadata.is_empty() {
+                None
+            } else {
+                serde_json::to_value(&session.metadata).ok()
+            },
+        }
+    }
+}
+
 /// Session manager for handling session persistence and operations
 pub struct SessionManager {
-    db: Arc<Database>,
+    storage: Arc<dyn Storage>,
     sessions: Arc<RwLock<HashMap<String, Session>>>,
+    semantic_index: Option<Arc<SemanticIndex>>,
 }
 
 impl SessionManager {
-    /// Create a new session manager
+    /// Create a session manager backed by SQLite in the data directory
     pub async fn new<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
-        let db_path = data_dir.as_ref().join("sessions.db");
-        let db = Arc::new(Database::new(db_path).await?);
-        let sessions = Arc::new(RwLock::new(HashMap::new()));
-        
-        Ok(Self { db, sessions })
+        let storage = SqliteStorage::open(data_dir.as_ref().join("sessions.db")).await?;
+        Ok(Self::with_storage(Arc::new(storage)))
+    }
+    
+    /// Create a session manager on top of the given storage backend
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            semantic_index: None,
+        }
+    }
+    
+    /// Enable semantic search with the given embedding index
+    pub fn with_semantic_index(mut self, index: Arc<SemanticIndex>) -> Self {
+        self.semantic_index = Some(index);
+        self
+    }
+    
+    /// The embedding index, if semantic search is enabled
+    pub fn semantic_index(&self) -> Option<&Arc<SemanticIndex>> {
+        self.semantic_index.as_ref()
+    }
+    
+    /// The storage backend in use
+    pub fn storage(&self) -> &Arc<dyn Storage> {
+        &self.storage
     }
     
     /// Create a new session
@@ -121,13 +175,8 @@ impl SessionManager {
     ) -> Result<Session> {
         let session = Session::new(title, parent_session_id);
         
-        // Insert into database
-        self.db.insert_session(
-            &session.id,
-            &session.title,
-            session.parent_session_id.as_deref(),
-            Some(&serde_json::to_value(&session.metadata)?),
-        ).await?;
+        // Persist
+        self.storage.put_session(&session).await?;
         
         // Cache in memory
         self.sessions.write().await.insert(session.id.clone(), session.clone());
@@ -142,9 +191,8 @@ impl SessionManager {
             return Ok(Some(session.clone()));
         }
         
-        // Load from database
-        if let Some(row) = self.db.get_session(id).await? {
-            let session = Session::from(row);
+        // Load from storage
+        if let Some(session) = self.storage.get_session(id).await? {
             self.sessions.write().await.insert(id.to_string(), session.clone());
             Ok(Some(session))
         } else {
@@ -154,27 +202,21 @@ impl SessionManager {
     
     /// Update a session
     pub async fn update_session(&self, session: &Session) -> Result<()> {
-        // Update database
-        self.db.update_session(
-            &session.id,
-            Some(&session.title),
-            Some(session.message_count as i32),
-            Some(session.token_usage.input_tokens as i32),
-            Some(session.token_usage.output_tokens as i32),
-            Some(session.total_cost),
-            Some(&serde_json::to_value(&session.metadata)?),
-        ).await?;
+        let mut session = session.clone();
+        session.updated_at = Utc::now();
+        
+        // Persist
+        self.storage.put_session(&session).await?;
         
         // Update cache
-        self.sessions.write().await.insert(session.id.clone(), session.clone());
+        self.sessions.write().await.insert(session.id.clone(), session);
         
         Ok(())
     }
     
     /// List sessions
     pub async fn list_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
-        let rows = self.db.list_sessions(limit.map(|l| l as i32)).await?;
-        let sessions: Vec<Session> = rows.into_iter().map(Session::from).collect();
+        let sessions = self.storage.list_sessions(limit).await?;
         
         // Update cache
         {
@@ -187,10 +229,20 @@ impl SessionManager {
         Ok(sessions)
     }
     
+    /// Give a session a title of its own, in place of the one it started with
+    pub async fn rename_session(&self, id: &str, title: &str) -> Result<()> {
+        if let Some(mut session) = self.get_session(id).await? {
+            session.title = title.to_string();
+            session.set_metadata(crate::session::TITLED_KEY.to_string(), serde_json::Value::Bool(true));
+            self.update_session(&session).await?;
+        }
+        Ok(())
+    }
+    
     /// Delete a session
     pub async fn delete_session(&self, id: &str) -> Result<()> {
-        // Delete from database
-        self.db.delete_session(id).await?;
+        // Delete from storage
+        self.storage.delete_session(id).await?;
         
         // Remove from cache
         self.sessions.write().await.remove(id);
@@ -198,10 +250,20 @@ impl SessionManager {
         Ok(())
     }
     
+    /// Put a session in the in-memory cache
+    pub(super) async fn cache(&self, session: &Session) {
+        self.sessions.write().await.insert(session.id.clone(), session.clone());
+    }
+    
+    /// Drop a session from the in-memory cache
+    pub(super) async fn forget_cached(&self, id: &str) {
+        self.sessions.write().await.remove(id);
+    }
+    
     /// Add a message to a session
     pub async fn add_message(&self, session_id: &str, message: &Message) -> Result<()> {
-        // Insert message into database
-        self.db.insert_message(message, session_id).await?;
+        // Persist the message
+        self.storage.put_message(session_id, message).await?;
         
         // Update session message count
         if let Some(mut session) = self.get_session(session_id).await? {
@@ -214,7 +276,13 @@ impl SessionManager {
     
     /// Get messages for a session
     pub async fn get_messages(&self, session_id: &str, limit: Option<u32>) -> Result<Vec<Message>> {
-        self.db.get_messages(session_id, limit.map(|l| l as i32)).await
+        self.storage.get_messages(session_id, limit).await
+    }
+    
+    /// Messages matching a query in one session, or in all sessions when
+    /// `session_id` is `None`
+    pub async fn search_messages(&self, query: &str, session_id: Option<&str>) -> Result<Vec<MessageHit>> {
+        self.storage.search_messages(query, session_id, SEARCH_LIMIT).await
     }
     
     /// Update session usage
@@ -232,6 +300,26 @@ impl SessionManager {
         Ok(())
     }
     
+    /// Add the usage of one exchange with a model, priced by the model's rate
+    ///
+    /// Returns the session's total cost afterwards.
+    pub async fn record_usage(&self, session_id: &str, model: &str, usage: &TokenUsage) -> Result<f64> {
+        let cost = crate::llm::models::cost(model, usage);
+        match self.get_session(session_id).await? {
+            Some(mut session) => {
+                session.update_usage(usage, cost);
+                self.update_session(&session).await?;
+                Ok(session.total_cost)
+            }
+            None => Ok(cost),
+        }
+    }
+    
+    /// Total cost of a session so far
+    pub async fn session_cost(&self, session_id: &str) -> Result<f64> {
+        Ok(self.get_session(session_id).await?.map_or(0.0, |session| session.total_cost))
+    }
+    
     /// Set session metadata
     pub async fn set_session_metadata(
         &self,
@@ -250,7 +338,7 @@ impl SessionManager {
     /// Get session statistics
     pub async fn get_session_stats(&self, session_id: &str) -> Result<Option<SessionStats>> {
         if let Some(session) = self.get_session(session_id).await? {
-            let message_count = self.db.get_message_count(session_id).await? as u32;
+            let message_count = self.storage.message_count(session_id).await?;
             
             Ok(Some(SessionStats {
                 session_id: session.id,
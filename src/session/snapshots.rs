@@ -0,0 +1,221 @@
+//! Snapshots of files taken before the agent edits them
+//!
+//! Before an editing tool changes a file, its content is copied into the
+//! session's snapshot directory under the turn the edit belongs to; only the
+//! first edit of a file in a turn is snapshotted, so the copy is the file as
+//! it was before the turn. Undoing turns writes those copies back, latest
+//! turn first, and deletes files the agent created. Undone turns are dropped
+//! from the store, so undoing again goes further back.
+//!
+//! Layout: `<root>/<session id>/turn-<n>/manifest.json` lists the files of a
+//! turn, next to one numbered copy per file that existed.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    files: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotEntry {
+    path: PathBuf,
+    /// Copy of the original content, `None` when the file did not exist
+    copy: Option<String>,
+}
+
+/// A file written back by an undo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoredFile {
+    pub path: PathBuf,
+    /// The agent created the file, so undoing deleted it
+    pub deleted: bool,
+}
+
+/// Session-scoped store of file contents from before the agent's edits
+#[derive(Debug)]
+pub struct SnapshotStore {
+    root: PathBuf,
+    /// Turn currently running in each session
+    turns: Mutex<HashMap<String, usize>>,
+}
+
+impl SnapshotStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            turns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Snapshot directory within the data directory
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("snapshots")
+    }
+
+    /// Attribute the session's following snapshots to `turn`
+    pub fn begin_turn(&self, session_id: &str, turn: usize) {
+        self.turns.lock().unwrap_or_else(|e| e.into_inner()).insert(session_id.to_string(), turn);
+    }
+
+    /// Keep the content of a file an edit of the current turn is about to change
+    ///
+    /// Edits outside a turn belong to turn 0.
+    pub fn snapshot(&self, session_id: &str, path: &Path) -> Result<()> {
+        let turn = self.turns.lock().unwrap_or_else(|e| e.into_inner()).get(session_id).copied().unwrap_or(0);
+        self.snapshot_in_turn(session_id, turn, path)
+    }
+
+    /// Keep the content of a file before its first change in `turn`
+    pub fn snapshot_in_turn(&self, session_id: &str, turn: usize, path: &Path) -> Result<()> {
+        let dir = self.turn_dir(session_id, turn)?;
+        let mut manifest = read_manifest(&dir)?;
+        if manifest.files.iter().any(|entry| entry.path == path) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let copy = if path.is_file() {
+            let name = manifest.files.len().to_string();
+            fs::copy(path, dir.join(&name)).with_context(|| format!("Failed to snapshot {}", path.display()))?;
+            Some(name)
+        } else {
+            None
+        };
+        manifest.files.push(SnapshotEntry { path: path.to_path_buf(), copy });
+        fs::write(dir.join(MANIFEST), serde_json::to_vec_pretty(&manifest)?)?;
+        Ok(())
+    }
+
+    /// Turns of the session with snapshots, oldest first
+    pub fn turns(&self, session_id: &str) -> Result<Vec<usize>> {
+        let dir = self.session_dir(session_id)?;
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+        };
+        let mut turns: Vec<usize> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.strip_prefix("turn-")?.parse().ok())
+            .collect();
+        turns.sort_unstable();
+        Ok(turns)
+    }
+
+    /// Files snapshotted in a turn
+    pub fn files(&self, session_id: &str, turn: usize) -> Result<Vec<PathBuf>> {
+        let manifest = read_manifest(&self.turn_dir(session_id, turn)?)?;
+        Ok(manifest.files.into_iter().map(|entry| entry.path).collect())
+    }
+
+    /// Restore the files changed in the given turns to how they were before the first of them
+    pub fn restore(&self, session_id: &str, range: RangeInclusive<usize>) -> Result<Vec<RestoredFile>> {
+        let mut restored: Vec<RestoredFile> = Vec::new();
+        for turn in self.turns(session_id)?.into_iter().rev().filter(|turn| range.contains(turn)) {
+            let dir = self.turn_dir(session_id, turn)?;
+            for entry in read_manifest(&dir)?.files {
+                let deleted = match &entry.copy {
+                    Some(copy) => {
+                        if let Some(parent) = entry.path.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::copy(dir.join(copy), &entry.path)
+                            .with_context(|| format!("Failed to restore {}", entry.path.display()))?;
+                        false
+                    }
+                    None => {
+                        match fs::remove_file(&entry.path) {
+                            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                                return Err(e).with_context(|| format!("Failed to remove {}", entry.path.display()));
+                            }
+                            _ => {}
+                        }
+                        true
+                    }
+                };
+                // Earlier turns are restored last, so their state wins
+                restored.retain(|file| file.path != entry.path);
+                restored.push(RestoredFile { path: entry.path, deleted });
+            }
+            fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+        }
+        restored.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(restored)
+    }
+
+    /// Undo the latest turn of the session with snapshots
+    ///
+    /// Returns the turn and its restored files, or `None` when nothing is left to undo.
+    pub fn undo_last_turn(&self, session_id: &str) -> Result<Option<(usize, Vec<RestoredFile>)>> {
+        let Some(turn) = self.turns(session_id)?.pop() else {
+            return Ok(None);
+        };
+        Ok(Some((turn, self.restore(session_id, turn..=turn)?)))
+    }
+
+    fn session_dir(&self, session_id: &str) -> Result<PathBuf> {
+        if session_id.is_empty() || session_id.contains(['/', '\\']) || session_id.starts_with('.') {
+            anyhow::bail!("Invalid session ID: {}", session_id);
+        }
+        Ok(self.root.join(session_id))
+    }
+
+    fn turn_dir(&self, session_id: &str, turn: usize) -> Result<PathBuf> {
+        Ok(self.session_dir(session_id)?.join(format!("turn-{}", turn)))
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest> {
+    match fs::read(dir.join(MANIFEST)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).with_context(|| format!("Corrupt snapshot manifest in {}", dir.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read snapshots in {}", dir.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_restores_state_before_the_turns() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(dir.path().join("snapshots"));
+        let edited = dir.path().join("main.rs");
+        let created = dir.path().join("new.rs");
+        fs::write(&edited, "v1").unwrap();
+
+        store.begin_turn("s1", 1);
+        store.snapshot("s1", &edited).unwrap();
+        fs::write(&edited, "v2").unwrap();
+        store.snapshot("s1", &edited).unwrap();
+        fs::write(&edited, "v3").unwrap();
+
+        store.begin_turn("s1", 2);
+        store.snapshot("s1", &edited).unwrap();
+        fs::write(&edited, "v4").unwrap();
+        store.snapshot("s1", &created).unwrap();
+        fs::write(&created, "new").unwrap();
+        assert_eq!(store.turns("s1").unwrap(), [1, 2]);
+
+        let (turn, restored) = store.undo_last_turn("s1").unwrap().unwrap();
+        assert_eq!(turn, 2);
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "v3");
+        assert!(!created.exists());
+        assert_eq!(restored.len(), 2);
+
+        let restored = store.restore("s1", 1..=1).unwrap();
+        assert_eq!(restored, [RestoredFile { path: edited.clone(), deleted: false }]);
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "v1");
+        assert!(store.undo_last_turn("s1").unwrap().is_none());
+        assert!(store.turns("other").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+//! Pluggable session storage backends
+//!
+//! Sessions and their messages are persisted through the [`Storage`] trait.
+//! Local SQLite is the default; S3-compatible object storage lets a team share
+//! sessions, either as the primary backend or as a remote that `goofy sync`
+//! reconciles with the local database.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::config::{Config, StorageBackend};
+use crate::llm::Message;
+use super::{search_terms, ArchivedSession, MessageHit, Session};
+
+pub mod sqlite;
+pub mod s3;
+pub mod sync;
+
+pub use sqlite::SqliteStorage;
+pub use s3::S3Storage;
+pub use sync::{SyncConflict, SyncReport, SyncState};
+
+/// Persistence for sessions and their messages
+///
+/// Writes are upserts keyed by session and message ID, so replaying the same
+/// data (as sync does) is harmless.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Short backend name for diagnostics
+    fn name(&self) -> &str;
+
+    /// Insert or replace a session, storing its timestamps as given
+    async fn put_session(&self, session: &Session) -> Result<()>;
+
+    /// Get a session by ID
+    async fn get_session(&self, id: &str) -> Result<Option<Session>>;
+
+    /// List sessions, most recently updated first
+    async fn list_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>>;
+
+    /// Delete a session and its messages
+    async fn delete_session(&self, id: &str) -> Result<()>;
+
+    /// Insert or replace a message
+    async fn put_message(&self, session_id: &str, message: &Message) -> Result<()>;
+
+    /// Insert or replace several messages at once
+    async fn put_messages(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        for message in messages {
+            self.put_message(session_id, message).await?;
+        }
+        Ok(())
+    }
+
+    /// Messages of a session in chronological order
+    async fn get_messages(&self, session_id: &str, limit: Option<u32>) -> Result<Vec<Message>>;
+
+    /// Number of messages stored for a session
+    async fn message_count(&self, session_id: &str) -> Result<u32> {
+        Ok(self.get_messages(session_id, None).await?.len() as u32)
+    }
+
+    /// Messages whose text contains every word of a query, within one session
+    /// or across all of them
+    ///
+    /// Backends without a search index scan the messages, newest sessions first.
+    async fn search_messages(&self, query: &str, session_id: Option<&str>, limit: usize) -> Result<Vec<MessageHit>> {
+        let terms = search_terms(query);
+        let sessions = match session_id {
+            Some(id) => vec![id.to_string()],
+            None => self.list_sessions(None).await?.into_iter().map(|session| session.id).collect(),
+        };
+        let mut hits = Vec::new();
+        for id in sessions {
+            for message in self.get_messages(&id, None).await? {
+                hits.extend(MessageHit::from_message(&id, &message, &terms));
+                if hits.len() == limit {
+                    return Ok(hits);
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Move a session and its messages to compressed storage
+    async fn archive_session(&self, _id: &str) -> Result<ArchivedSession> {
+        anyhow::bail!("The {} storage backend does not support archiving", self.name())
+    }
+
+    /// Restore an archived session and its messages
+    async fn unarchive_session(&self, _id: &str) -> Result<Session> {
+        anyhow::bail!("The {} storage backend does not support archiving", self.name())
+    }
+
+    /// Archived sessions, most recently archived first
+    async fn list_archived_sessions(&self, _limit: Option<u32>) -> Result<Vec<ArchivedSession>> {
+        Ok(Vec::new())
+    }
+
+    /// An archived session by ID
+    async fn get_archived_session(&self, _id: &str) -> Result<Option<ArchivedSession>> {
+        Ok(None)
+    }
+}
+
+/// Local SQLite storage in the data directory
+pub async fn local_storage(config: &Config) -> Result<Arc<dyn Storage>> {
+    Ok(Arc::new(SqliteStorage::open(config.data_dir.join("sessions.db")).await?))
+}
+
+/// Remote object storage, if configured
+pub fn remote_storage(config: &Config) -> Result<Option<Arc<dyn Storage>>> {
+    match &config.storage.s3 {
+        Some(s3) => Ok(Some(Arc::new(S3Storage::new(s3.clone())?))),
+        None => Ok(None),
+    }
+}
+
+/// The storage backend selected in the configuration
+pub async fn from_config(config: &Config) -> Result<Arc<dyn Storage>> {
+    match config.storage.backend {
+        StorageBackend::Sqlite => local_storage(config).await,
+        StorageBackend::S3 => remote_storage(config)?
+            .ok_or_else(|| anyhow::anyhow!("storage.backend is \"s3\" but storage.s3 is not configured")),
+    }
+}
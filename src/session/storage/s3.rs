@@ -0,0 +1,379 @@
+//! S3-compatible object storage backend
+//!
+//! Each session is stored as two JSON objects under the configured prefix:
+//! `sessions/<id>.json` for the session record and `messages/<id>.json` for
+//! its messages. Requests are signed with AWS Signature Version 4 and use
+//! path-style URLs, which AWS, MinIO, R2 and most other implementations accept.
+//! Message writes are read-modify-write cycles guarded by `If-Match`, so two
+//! clients appending to the same session retry instead of overwriting each other.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use super::Storage;
+use crate::config::S3Config;
+use crate::llm::Message;
+use crate::session::Session;
+
+/// Attempts for a conditional read-modify-write before giving up
+const MAX_WRITE_ATTEMPTS: usize = 5;
+
+/// Sessions stored in an S3-compatible bucket
+pub struct S3Storage {
+    config: S3Config,
+    endpoint: String,
+    access_key_id: String,
+    secret_access_key: String,
+    client: reqwest::Client,
+}
+
+/// Response of a GET, with the ETag for conditional writes
+struct Object {
+    body: Vec<u8>,
+    etag: Option<String>,
+}
+
+impl S3Storage {
+    /// Create a client for the configured bucket
+    ///
+    /// Credentials fall back to `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+    pub fn new(config: S3Config) -> Result<Self> {
+        let access_key_id = config.access_key_id.clone()
+            .or_else(|| std::env::var("AWS_ACCESS_KEY_ID").ok())
+            .context("No S3 access key configured (storage.s3.access_key_id or AWS_ACCESS_KEY_ID)")?;
+        let secret_access_key = config.secret_access_key.clone()
+            .or_else(|| std::env::var("AWS_SECRET_ACCESS_KEY").ok())
+            .context("No S3 secret key configured (storage.s3.secret_access_key or AWS_SECRET_ACCESS_KEY)")?;
+        let endpoint = config.endpoint.clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.region))
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Self {
+            config,
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn key(&self, kind: &str, id: &str) -> String {
+        format!("{}{}/{}.json", self.prefix(), kind, id)
+    }
+
+    fn prefix(&self) -> String {
+        match self.config.prefix.as_deref().map(|p| p.trim_matches('/')) {
+            Some(prefix) if !prefix.is_empty() => format!("{}/", prefix),
+            _ => String::new(),
+        }
+    }
+
+    /// Send a signed request
+    async fn request(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        if_match: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let path = format!("/{}/{}", self.config.bucket, key);
+        let canonical_uri = uri_encode(&path, false);
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let url = if canonical_query.is_empty() {
+            format!("{}{}", self.endpoint, canonical_uri)
+        } else {
+            format!("{}{}?{}", self.endpoint, canonical_uri, canonical_query)
+        };
+        let parsed = reqwest::Url::parse(&url)?;
+        let host = match parsed.port() {
+            Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+            None => parsed.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(etag) = if_match {
+            headers.push(("if-match".to_string(), etag.to_string()));
+        }
+        headers.sort();
+
+        let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+        let signed_headers = headers.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method.as_str(), canonical_uri, canonical_query, canonical_headers, signed_headers, payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signature = hex(&signing_key(&self.secret_access_key, &date, &self.config.region, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        let mut request = self.client.request(method, parsed).header("authorization", authorization);
+        for (name, value) in headers.into_iter().filter(|(name, _)| name != "host") {
+            request = request.header(name, value);
+        }
+        Ok(request.body(body).send().await?)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Option<Object>> {
+        let response = self.request(Method::GET, key, &[], Vec::new(), None).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response, key).await?;
+        let etag = response.headers().get("etag").and_then(|v| v.to_str().ok()).map(str::to_string);
+        Ok(Some(Object {
+            body: response.bytes().await?.to_vec(),
+            etag,
+        }))
+    }
+
+    /// Write an object; returns false if the `If-Match` precondition failed
+    async fn put_object(&self, key: &str, body: Vec<u8>, if_match: Option<&str>) -> Result<bool> {
+        let response = self.request(Method::PUT, key, &[], body, if_match).await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        check(response, key).await?;
+        Ok(true)
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        let response = self.request(Method::DELETE, key, &[], Vec::new(), None).await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, key).await?;
+        }
+        Ok(())
+    }
+
+    /// List object keys under a prefix, following continuation tokens
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let key_pattern = regex::Regex::new(r"<Key>([^<]+)</Key>")?;
+        let token_pattern = regex::Regex::new(r"<NextContinuationToken>([^<]+)</NextContinuationToken>")?;
+
+        let mut keys = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = continuation.as_deref() {
+                query.push(("continuation-token", token));
+            }
+            let response = self.request(Method::GET, "", &query, Vec::new(), None).await?;
+            let body = check(response, prefix).await?.text().await?;
+
+            keys.extend(key_pattern.captures_iter(&body).map(|c| xml_unescape(&c[1])));
+            continuation = token_pattern.captures(&body).map(|c| xml_unescape(&c[1]));
+            if continuation.is_none() {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Merge messages into the stored list, retrying on concurrent writes
+    async fn merge_messages(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        let key = self.key("messages", session_id);
+        for attempt in 1..=MAX_WRITE_ATTEMPTS {
+            let existing = self.get_object(&key).await?;
+            let (mut stored, etag) = match existing {
+                Some(object) => (serde_json::from_slice::<Vec<Message>>(&object.body)?, object.etag),
+                None => (Vec::new(), None),
+            };
+
+            for message in messages {
+                match stored.iter_mut().find(|m| m.id == message.id) {
+                    Some(slot) => *slot = message.clone(),
+                    None => stored.push(message.clone()),
+                }
+            }
+            stored.sort_by_key(|m| m.timestamp);
+
+            if self.put_object(&key, serde_json::to_vec(&stored)?, etag.as_deref()).await? {
+                return Ok(());
+            }
+            debug!("Concurrent write to {}, retrying ({}/{})", key, attempt, MAX_WRITE_ATTEMPTS);
+        }
+        anyhow::bail!("Gave up writing {} after {} concurrent modifications", key, MAX_WRITE_ATTEMPTS)
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    fn name(&self) -> &str {
+        "s3"
+    }
+
+    async fn put_session(&self, session: &Session) -> Result<()> {
+        self.put_object(&self.key("sessions", &session.id), serde_json::to_vec(session)?, None).await?;
+        Ok(())
+    }
+
+    async fn get_session(&self, id: &str) -> Result<Option<Session>> {
+        match self.get_object(&self.key("sessions", id)).await? {
+            Some(object) => Ok(Some(serde_json::from_slice(&object.body)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+        let prefix = format!("{}sessions/", self.prefix());
+        let mut sessions = Vec::new();
+        for key in self.list_keys(&prefix).await? {
+            if let Some(object) = self.get_object(&key).await? {
+                sessions.push(serde_json::from_slice::<Session>(&object.body)
+                    .with_context(|| format!("Invalid session object: {}", key))?);
+            }
+        }
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        if let Some(limit) = limit {
+            sessions.truncate(limit as usize);
+        }
+        Ok(sessions)
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<()> {
+        self.delete_object(&self.key("messages", id)).await?;
+        self.delete_object(&self.key("sessions", id)).await
+    }
+
+    async fn put_message(&self, session_id: &str, message: &Message) -> Result<()> {
+        self.merge_messages(session_id, std::slice::from_ref(message)).await
+    }
+
+    async fn put_messages(&self, session_id: &str, messages: &[Message]) -> Result<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.merge_messages(session_id, messages).await
+    }
+
+    async fn get_messages(&self, session_id: &str, limit: Option<u32>) -> Result<Vec<Message>> {
+        let mut messages = match self.get_object(&self.key("messages", session_id)).await? {
+            Some(object) => serde_json::from_slice::<Vec<Message>>(&object.body)?,
+            None => Vec::new(),
+        };
+        if let Some(limit) = limit {
+            messages.truncate(limit as usize);
+        }
+        Ok(messages)
+    }
+}
+
+async fn check(response: reqwest::Response, key: &str) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    anyhow::bail!("S3 request for '{}' failed with {}: {}", key, status, body.trim())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Derive the SigV4 signing key and sign the string
+fn signing_key(secret: &str, date: &str, region: &str, string_to_sign: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, b"s3");
+    let key = hmac_sha256(&key, b"aws4_request");
+    hmac_sha256(&key, string_to_sign.as_bytes())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode per SigV4 rules; slashes are kept unless encoding a query component
+fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("/bucket/sessions/a b.json", false), "/bucket/sessions/a%20b.json");
+        assert_eq!(uri_encode("team/sessions/", true), "team%2Fsessions%2F");
+    }
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // Example from the AWS Signature Version 4 documentation
+        let string_to_sign = "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/iam/aws4_request\nf536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59";
+        let key = hmac_sha256(b"AWS4wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", b"20150830");
+        let key = hmac_sha256(&key, b"us-east-1");
+        let key = hmac_sha256(&key, b"iam");
+        let key = hmac_sha256(&key, b"aws4_request");
+        assert_eq!(
+            hex(&hmac_sha256(&key, string_to_sign.as_bytes())),
+            "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn test_keys_use_prefix() {
+        let storage = S3Storage::new(S3Config {
+            bucket: "goofy".to_string(),
+            prefix: Some("/team/".to_string()),
+            access_key_id: Some("key".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(storage.key("sessions", "abc"), "team/sessions/abc.json");
+    }
+}
@@ -0,0 +1,196 @@
+//! SQLite storage backend
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::executor::block_on;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::Storage;
+use crate::llm::Message;
+use crate::session::database::{Database, SessionRow};
+use crate::session::{archive, ArchivedSession, MessageHit, Session};
+
+/// Sessions stored in a local SQLite database
+pub struct SqliteStorage {
+    db: Arc<Mutex<Database>>,
+}
+
+impl SqliteStorage {
+    /// Open or create the database at the given path
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            db: Arc::new(Mutex::new(Database::new(path).await?)),
+        })
+    }
+
+    /// Run `query` on the database on a blocking thread
+    ///
+    /// The connection can't be shared between threads, so it is never held
+    /// across an `.await`: the database's methods don't suspend and are run to
+    /// completion on the spot.
+    async fn with_db<T, F>(&self, query: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T> + Send + 'static,
+    {
+        let db = Arc::clone(&self.db);
+        tokio::task::spawn_blocking(move || {
+            let db = db.lock().map_err(|_| anyhow!("The session database is poisoned"))?;
+            query(&db)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn put_session(&self, session: &Session) -> Result<()> {
+        let row = SessionRow::from(session);
+        self.with_db(move |db| block_on(db.upsert_session(&row))).await
+    }
+
+    async fn get_session(&self, id: &str) -> Result<Option<Session>> {
+        let id = id.to_string();
+        let row = self.with_db(move |db| block_on(db.get_session(&id))).await?;
+        Ok(row.map(Session::from))
+    }
+
+    async fn list_sessions(&self, limit: Option<u32>) -> Result<Vec<Session>> {
+        let rows = self.with_db(move |db| block_on(db.list_sessions(limit.map(|l| l as i32)))).await?;
+        Ok(rows.into_iter().map(Session::from).collect())
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+        self.with_db(move |db| {
+            block_on(db.delete_messages(&id))?;
+            block_on(db.delete_session(&id))
+        })
+        .await
+    }
+
+    async fn put_message(&self, session_id: &str, message: &Message) -> Result<()> {
+        let (session_id, message) = (session_id.to_string(), message.clone());
+        self.with_db(move |db| block_on(db.upsert_message(&message, &session_id))).await
+    }
+
+    async fn get_messages(&self, session_id: &str, limit: Option<u32>) -> Result<Vec<Message>> {
+        let session_id = session_id.to_string();
+        self.with_db(move |db| block_on(db.get_messages(&session_id, limit.map(|l| l as i32)))).await
+    }
+
+    async fn search_messages(&self, query: &str, session_id: Option<&str>, limit: usize) -> Result<Vec<MessageHit>> {
+        let (query, session_id) = (query.to_string(), session_id.map(str::to_string));
+        self.with_db(move |db| block_on(db.search_messages(&query, session_id.as_deref(), limit))).await
+    }
+
+    async fn message_count(&self, session_id: &str) -> Result<u32> {
+        let session_id = session_id.to_string();
+        let count = self.with_db(move |db| block_on(db.get_message_count(&session_id))).await?;
+        Ok(count as u32)
+    }
+
+    async fn archive_session(&self, id: &str) -> Result<ArchivedSession> {
+        let id = id.to_string();
+        self.with_db(move |db| {
+            let session = Session::from(block_on(db.get_session(&id))?.with_context(|| format!("Session {} not found", id))?);
+            let messages = block_on(db.get_messages(&id, None))?;
+
+            let data = archive::compress(&session, &messages)?;
+            let archived = ArchivedSession {
+                id: session.id.clone(),
+                title: session.title.clone(),
+                updated_at: session.updated_at,
+                archived_at: Utc::now(),
+                message_count: messages.len() as u32,
+                compressed_size: data.len() as u64,
+            };
+            block_on(db.archive_session(&archived, &data))?;
+            Ok(archived)
+        })
+        .await
+    }
+
+    async fn unarchive_session(&self, id: &str) -> Result<Session> {
+        let id = id.to_string();
+        self.with_db(move |db| {
+            let data = block_on(db.get_archive_data(&id))?.with_context(|| format!("Session {} is not archived", id))?;
+            let (session, messages) = archive::decompress(&data)?;
+            block_on(db.restore_archive(&SessionRow::from(&session), &messages))?;
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn list_archived_sessions(&self, limit: Option<u32>) -> Result<Vec<ArchivedSession>> {
+        self.with_db(move |db| block_on(db.list_archives(None, limit.map(|l| l as i32)))).await
+    }
+
+    async fn get_archived_session(&self, id: &str) -> Result<Option<ArchivedSession>> {
+        let id = id.to_string();
+        let mut archives = self.with_db(move |db| block_on(db.list_archives(Some(&id), None))).await?;
+        Ok(archives.pop())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_put_session_is_an_upsert() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("sessions.db")).await.unwrap();
+
+        let mut session = Session::new("First".to_string(), None);
+        storage.put_session(&session).await.unwrap();
+
+        session.title = "Renamed".to_string();
+        storage.put_session(&session).await.unwrap();
+
+        let loaded = storage.get_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(loaded.title, "Renamed");
+        assert_eq!(loaded.created_at, session.created_at);
+        assert_eq!(storage.list_sessions(None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_searched_by_session_or_everywhere() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path().join("sessions.db")).await.unwrap();
+        let first = Session::new("First".to_string(), None);
+        let second = Session::new("Second".to_string(), None);
+        storage.put_session(&first).await.unwrap();
+        storage.put_session(&second).await.unwrap();
+
+        let mut edited = Message::new_user("Why does the borrow checker complain?".to_string());
+        storage.put_message(&first.id, &edited).await.unwrap();
+        storage.put_message(&second.id, &Message::new_assistant("The borrowed value \"outlives\" it".to_string())).await.unwrap();
+
+        let everywhere = storage.search_messages("borrow", None, 10).await.unwrap();
+        assert_eq!(everywhere.len(), 2);
+        let here = storage.search_messages("BORROW check", Some(&first.id), 10).await.unwrap();
+        assert_eq!(here.len(), 1);
+        assert_eq!(here[0].message_id, edited.id);
+        // Quotes and operators in the query are plain text
+        assert_eq!(storage.search_messages("\"outlives\" OR", None, 10).await.unwrap().len(), 0);
+        assert_eq!(storage.search_messages("\"outlives", None, 10).await.unwrap().len(), 1);
+
+        // Replaced and deleted messages leave the index
+        edited.content = vec![crate::llm::ContentBlock::Text { text: "Explain lifetimes".to_string() }];
+        storage.put_message(&first.id, &edited).await.unwrap();
+        assert!(storage.search_messages("borrow", Some(&first.id), 10).await.unwrap().is_empty());
+        storage.delete_session(&second.id).await.unwrap();
+        assert!(storage.search_messages("borrow", None, 10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+//! Conflict-aware session sync between two storage backends
+//!
+//! Sync is a three-way comparison per session: the local copy, the remote copy
+//! and the `updated_at` both sides had after the last successful sync, kept in
+//! a small state file. A side that did not change since then simply receives
+//! the other side's version. When both changed, messages are merged by ID
+//! (messages are append-only, so nothing is lost) and session fields are taken
+//! from the more recently updated copy; differing titles are reported as
+//! conflicts so the user can see what was overwritten.
+//!
+//! Sessions archived on either side are left alone: their absence from the
+//! active sessions is not a deletion to propagate.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::Storage;
+use crate::llm::Message;
+use crate::session::Session;
+
+/// Per-session `updated_at` recorded after the last successful sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    sessions: BTreeMap<String, DateTime<Utc>>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl SyncState {
+    /// Load the sync state, starting empty if the file does not exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut state: Self = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Self::default()
+        };
+        state.path = path;
+        Ok(state)
+    }
+
+    /// Default location in the data directory
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("sync-state.json")
+    }
+
+    /// Persist the sync state
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn last_synced(&self, id: &str) -> Option<DateTime<Utc>> {
+        self.sessions.get(id).copied()
+    }
+
+    fn record(&mut self, id: &str, updated_at: DateTime<Utc>) {
+        self.sessions.insert(id.to_string(), updated_at);
+    }
+
+    fn forget(&mut self, id: &str) {
+        self.sessions.remove(id);
+    }
+}
+
+/// A field whose concurrent edits could not both be kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConflict {
+    pub session_id: String,
+    pub field: String,
+    pub kept: String,
+    pub discarded: String,
+}
+
+/// What a sync run did
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub pushed: Vec<String>,
+    pub pulled: Vec<String>,
+    pub merged: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+impl SyncReport {
+    /// Whether nothing needed to be transferred
+    pub fn is_empty(&self) -> bool {
+        self.pushed.is_empty() && self.pulled.is_empty() && self.merged.is_empty()
+    }
+}
+
+/// Reconcile all sessions between a local and a remote backend
+pub async fn sync(local: &dyn Storage, remote: &dyn Storage, state: &mut SyncState) -> Result<SyncReport> {
+    let local_sessions: BTreeMap<String, Session> = local
+        .list_sessions(None)
+        .await?
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect();
+    let remote_sessions: BTreeMap<String, Session> = remote
+        .list_sessions(None)
+        .await?
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect();
+
+    let mut archived = BTreeSet::new();
+    for side in [local, remote] {
+        archived.extend(side.list_archived_sessions(None).await?.into_iter().map(|a| a.id));
+    }
+
+    let ids: BTreeSet<&String> = local_sessions
+        .keys()
+        .chain(remote_sessions.keys())
+        .filter(|id| !archived.contains(*id))
+        .collect();
+    let mut report = SyncReport::default();
+
+    for id in ids {
+        let base = state.last_synced(id);
+        match (local_sessions.get(id), remote_sessions.get(id)) {
+            (Some(session), None) => {
+                if base.is_some() && base == Some(session.updated_at) {
+                    // Deleted remotely and unchanged here: propagate the deletion
+                    debug!("Session {} deleted remotely", id);
+                    local.delete_session(id).await?;
+                    state.forget(id);
+                    continue;
+                }
+                copy_session(local, remote, session).await?;
+                state.record(id, session.updated_at);
+                report.pushed.push(id.clone());
+            }
+            (None, Some(session)) => {
+                if base.is_some() && base == Some(session.updated_at) {
+                    debug!("Session {} deleted locally", id);
+                    remote.delete_session(id).await?;
+                    state.forget(id);
+                    continue;
+                }
+                copy_session(remote, local, session).await?;
+                state.record(id, session.updated_at);
+                report.pulled.push(id.clone());
+            }
+            (Some(ours), Some(theirs)) => {
+                let ours_changed = base.map_or(true, |b| ours.updated_at > b);
+                let theirs_changed = base.map_or(true, |b| theirs.updated_at > b);
+
+                match (ours_changed, theirs_changed) {
+                    (false, false) => {}
+                    _ if ours.updated_at == theirs.updated_at => {
+                        state.record(id, ours.updated_at);
+                    }
+                    (true, false) => {
+                        copy_session(local, remote, ours).await?;
+                        state.record(id, ours.updated_at);
+                        report.pushed.push(id.clone());
+                    }
+                    (false, true) => {
+                        copy_session(remote, local, theirs).await?;
+                        state.record(id, theirs.updated_at);
+                        report.pulled.push(id.clone());
+                    }
+                    (true, true) => {
+                        let merged = merge_session(local, remote, ours, theirs, &mut report).await?;
+                        state.record(id, merged.updated_at);
+                        report.merged.push(id.clone());
+                    }
+                }
+            }
+            (None, None) => unreachable!("id comes from one of the maps"),
+        }
+    }
+
+    state.save()?;
+    Ok(report)
+}
+
+/// Copy a session and its messages from one backend to another
+async fn copy_session(from: &dyn Storage, to: &dyn Storage, session: &Session) -> Result<()> {
+    let messages = from.get_messages(&session.id, None).await?;
+    to.put_messages(&session.id, &messages).await?;
+    to.put_session(session).await
+}
+
+/// Merge two concurrently modified copies and write the result to both sides
+async fn merge_session(
+    local: &dyn Storage,
+    remote: &dyn Storage,
+    ours: &Session,
+    theirs: &Session,
+    report: &mut SyncReport,
+) -> Result<Session> {
+    let (newer, older) = if ours.updated_at >= theirs.updated_at { (ours, theirs) } else { (theirs, ours) };
+
+    if newer.title != older.title {
+        report.conflicts.push(SyncConflict {
+            session_id: ours.id.clone(),
+            field: "title".to_string(),
+            kept: newer.title.clone(),
+            discarded: older.title.clone(),
+        });
+    }
+
+    let local_messages = local.get_messages(&ours.id, None).await?;
+    let remote_messages = remote.get_messages(&ours.id, None).await?;
+    let messages = merge_messages(local_messages, remote_messages);
+
+    let mut merged = newer.clone();
+    merged.message_count = messages.len() as u32;
+    for (key, value) in &older.metadata {
+        merged.metadata.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    merged.updated_at = Utc::now();
+
+    for side in [local, remote] {
+        side.put_messages(&merged.id, &messages).await?;
+        side.put_session(&merged).await?;
+    }
+    Ok(merged)
+}
+
+/// Union of two message lists by ID, in chronological order
+fn merge_messages(ours: Vec<Message>, theirs: Vec<Message>) -> Vec<Message> {
+    let mut by_id: BTreeMap<String, Message> = BTreeMap::new();
+    for message in theirs.into_iter().chain(ours) {
+        by_id.insert(message.id.clone(), message);
+    }
+    let mut messages: Vec<Message> = by_id.into_values().collect();
+    messages.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MessageRole;
+    use crate::session::storage::SqliteStorage;
+    use tempfile::tempdir;
+
+    async fn stores(dir: &Path) -> (SqliteStorage, SqliteStorage, SyncState) {
+        let local = SqliteStorage::open(dir.join("local.db")).await.unwrap();
+        let remote = SqliteStorage::open(dir.join("remote.db")).await.unwrap();
+        let state = SyncState::load(dir.join("sync-state.json")).unwrap();
+        (local, remote, state)
+    }
+
+    #[tokio::test]
+    async fn test_new_sessions_are_pushed_and_pulled() {
+        let dir = tempdir().unwrap();
+        let (local, remote, mut state) = stores(dir.path()).await;
+
+        let ours = Session::new("Ours".to_string(), None);
+        let theirs = Session::new("Theirs".to_string(), None);
+        local.put_session(&ours).await.unwrap();
+        remote.put_session(&theirs).await.unwrap();
+
+        let report = sync(&local, &remote, &mut state).await.unwrap();
+        assert_eq!(report.pushed, vec![ours.id.clone()]);
+        assert_eq!(report.pulled, vec![theirs.id.clone()]);
+        assert!(local.get_session(&theirs.id).await.unwrap().is_some());
+
+        let report = sync(&local, &remote, &mut state).await.unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_archived_sessions_are_not_deleted_remotely() {
+        let dir = tempdir().unwrap();
+        let (local, remote, mut state) = stores(dir.path()).await;
+
+        let session = Session::new("Old".to_string(), None);
+        local.put_session(&session).await.unwrap();
+        sync(&local, &remote, &mut state).await.unwrap();
+
+        local.archive_session(&session.id).await.unwrap();
+        let report = sync(&local, &remote, &mut state).await.unwrap();
+        assert!(report.is_empty());
+        assert!(remote.get_session(&session.id).await.unwrap().is_some());
+        assert!(local.get_session(&session.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_edits_merge_messages_and_report_title_conflict() {
+        let dir = tempdir().unwrap();
+        let (local, remote, mut state) = stores(dir.path()).await;
+
+        let session = Session::new("Start".to_string(), None);
+        local.put_session(&session).await.unwrap();
+        sync(&local, &remote, &mut state).await.unwrap();
+
+        let mut ours = session.clone();
+        ours.title = "Local title".to_string();
+        ours.updated_at = session.updated_at + chrono::Duration::seconds(1);
+        local.put_session(&ours).await.unwrap();
+        local
+            .put_message(&session.id, &Message::new_text(MessageRole::User, "from local".to_string()))
+            .await
+            .unwrap();
+
+        let mut theirs = session.clone();
+        theirs.title = "Remote title".to_string();
+        theirs.updated_at = session.updated_at + chrono::Duration::seconds(2);
+        remote.put_session(&theirs).await.unwrap();
+        remote
+            .put_message(&session.id, &Message::new_text(MessageRole::User, "from remote".to_string()))
+            .await
+            .unwrap();
+
+        let report = sync(&local, &remote, &mut state).await.unwrap();
+        assert_eq!(report.merged, vec![session.id.clone()]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kept, "Remote title");
+
+        assert_eq!(local.get_messages(&session.id, None).await.unwrap().len(), 2);
+        assert_eq!(remote.get_messages(&session.id, None).await.unwrap().len(), 2);
+        assert_eq!(local.get_session(&session.id).await.unwrap().unwrap().title, "Remote title");
+    }
+}
@@ -0,0 +1,145 @@
+//! Follow-up suggestions
+//!
+//! After an answer, a separate and usually cheaper model can be asked for a
+//! few short prompts the user might send next. Suggestions are shown next to
+//! the answer and never become part of the conversation: they are not stored
+//! with the session's messages nor sent in later requests. Only their cost is
+//! added to the session, and nothing is asked at all unless suggestions are
+//! enabled.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::transcript_turns;
+use crate::llm::{ChatRequest, Deadline, LlmProvider, Message, TokenUsage};
+
+/// Instructions for suggesting follow-ups; `{count}` is the number wanted
+const SUGGESTION_INSTRUCTIONS: &str = "Below is the end of a conversation between a user and a \
+coding assistant. Suggest {count} short follow-up requests the user is likely to send next, \
+written as the user would type them. Reply with one suggestion per line and nothing else.";
+
+/// Turns of the conversation the suggestions are based on
+const CONTEXT_TURNS: usize = 2;
+
+/// Most characters of an answer sent along
+const MAX_ANSWER_CHARS: usize = 4_000;
+
+/// Longest suggestion kept, in characters
+const MAX_SUGGESTION_CHARS: usize = 120;
+
+/// Time given to the suggestion model
+const SUGGESTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Asks a model for follow-up prompts
+pub struct Suggester {
+    provider: Arc<dyn LlmProvider>,
+    count: usize,
+}
+
+impl Suggester {
+    /// Ask `provider` for `count` suggestions, kept between two and three
+    pub fn new(provider: Arc<dyn LlmProvider>, count: usize) -> Self {
+        Self {
+            provider,
+            count: count.clamp(2, 3),
+        }
+    }
+
+    /// Model the suggestions are asked of
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    /// Follow-up prompts for a conversation, and what asking for them used
+    pub async fn suggest(&self, messages: &[Message]) -> Result<(Vec<String>, TokenUsage)> {
+        let Some(context) = suggestion_context(messages) else {
+            return Ok((Vec::new(), TokenUsage::default()));
+        };
+        let request = ChatRequest {
+            messages: vec![Message::new_user(context)],
+            tools: Vec::new(),
+            system_message: Some(SUGGESTION_INSTRUCTIONS.replace("{count}", &self.count.to_string())),
+            max_tokens: Some(200),
+            temperature: None,
+            top_p: None,
+            stream: false,
+            metadata: HashMap::new(),
+        };
+        let deadline = Deadline::after(SUGGESTION_TIMEOUT);
+        let response = deadline.run("follow-up suggestions", self.provider.chat_completion(request)).await?;
+        Ok((parse_suggestions(&response.content, self.count), response.usage))
+    }
+}
+
+/// The last turns of a conversation as the suggestion model sees them, none
+/// before the first answer
+fn suggestion_context(messages: &[Message]) -> Option<String> {
+    let turns = transcript_turns(messages);
+    if turns.last().map_or(true, |turn| turn.answer.trim().is_empty()) {
+        return None;
+    }
+    let context = turns[turns.len().saturating_sub(CONTEXT_TURNS)..]
+        .iter()
+        .map(|turn| {
+            let answer: String = turn.answer.trim().chars().take(MAX_ANSWER_CHARS).collect();
+            format!("User: {}\n\nAssistant: {}", turn.prompt.trim(), answer)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    Some(context)
+}
+
+/// Read suggestions from a reply, dropping list markers, quotes and repeats
+pub fn parse_suggestions(reply: &str, count: usize) -> Vec<String> {
+    let mut suggestions: Vec<String> = Vec::new();
+    for line in reply.lines() {
+        let line = line.trim().trim_start_matches(|c: char| c.is_ascii_digit());
+        let line = line
+            .trim_start_matches(['-', '*', '•', '.', ')'])
+            .trim()
+            .trim_matches(['"', '“', '”', '`'])
+            .trim();
+        if line.is_empty() || line.chars().count() > MAX_SUGGESTION_CHARS {
+            continue;
+        }
+        if !suggestions.iter().any(|known| known.eq_ignore_ascii_case(line)) {
+            suggestions.push(line.to_string());
+        }
+        if suggestions.len() == count {
+            break;
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions_are_parsed_from_lists() {
+        let reply = "1. Add tests for the parser\n\n- \"Explain the lifetime error\"\n* add tests for the parser\n3) Run clippy\n4. One too many";
+        assert_eq!(
+            parse_suggestions(reply, 3),
+            ["Add tests for the parser", "Explain the lifetime error", "Run clippy"]
+        );
+    }
+
+    #[test]
+    fn test_nothing_is_asked_before_an_answer() {
+        assert!(suggestion_context(&[Message::new_user("hello".to_string())]).is_none());
+        let messages = vec![
+            Message::new_user("first".to_string()),
+            Message::new_assistant("one".to_string()),
+            Message::new_user("second".to_string()),
+            Message::new_assistant("two".to_string()),
+            Message::new_user("third".to_string()),
+            Message::new_assistant("three".to_string()),
+        ];
+        let context = suggestion_context(&messages).unwrap();
+        assert!(!context.contains("first"));
+        assert!(context.starts_with("User: second") && context.ends_with("Assistant: three"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Session titles
+//!
+//! Sessions start out with a placeholder title such as "Inline session".
+//! Once the first answer is in, a model is asked for a few words naming what
+//! the session is about, and the session is renamed to them. Without a model
+//! to ask, or when asking fails, the first line of the first prompt names it
+//! instead. A session is named once: its metadata records that it was, so
+//! titles set later by hand are kept.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{issue::title_of, transcript_turns};
+use crate::llm::{ChatRequest, Deadline, LlmProvider, Message, TokenUsage};
+
+/// Session metadata key recording that the session was named
+pub const TITLED_KEY: &str = "titled";
+
+const TITLE_INSTRUCTIONS: &str = "Below is the start of a conversation between a user and a \
+coding assistant. Reply with a title for it of at most six words, naming the task or topic, \
+without quotes or a trailing period.";
+
+/// Most characters of the first answer sent along
+const MAX_ANSWER_CHARS: usize = 2_000;
+
+/// Longest title kept, in characters
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Time given to the title model
+const TITLE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Names sessions after their first exchange
+pub struct Titler {
+    /// Model asked for titles; without one, titles come from the first prompt
+    provider: Option<Arc<dyn LlmProvider>>,
+}
+
+impl Titler {
+    /// Ask `provider` for titles
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            provider: Some(provider),
+        }
+    }
+
+    /// Take titles from first prompts, without asking a model
+    pub fn from_prompts() -> Self {
+        Self { provider: None }
+    }
+
+    /// Model the titles are asked of, if any
+    pub fn model(&self) -> Option<&str> {
+        self.provider.as_deref().map(|provider| provider.model())
+    }
+
+    /// Title for a conversation after its first exchange, and what asking
+    /// for it used; `None` before there is an answer
+    pub async fn title(&self, messages: &[Message]) -> Result<Option<(String, TokenUsage)>> {
+        let turns = transcript_turns(messages);
+        let Some(first) = turns.first().filter(|turn| !turn.answer.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let fallback = title_of(&first.prompt);
+        let Some(provider) = &self.provider else {
+            return Ok(fallback.map(|title| (title, TokenUsage::default())));
+        };
+
+        let answer: String = first.answer.trim().chars().take(MAX_ANSWER_CHARS).collect();
+        let request = ChatRequest {
+            messages: vec![Message::new_user(format!("User: {}\n\nAssistant: {}", first.prompt.trim(), answer))],
+            tools: Vec::new(),
+            system_message: Some(TITLE_INSTRUCTIONS.to_string()),
+            max_tokens: Some(30),
+            temperature: None,
+            top_p: None,
+            stream: false,
+            metadata: HashMap::new(),
+        };
+        let deadline = Deadline::after(TITLE_TIMEOUT);
+        match deadline.run("session title", provider.chat_completion(request)).await {
+            Ok(response) => {
+                let title = parse_title(&response.content).or(fallback);
+                Ok(title.map(|title| (title, response.usage)))
+            }
+            Err(e) => {
+                tracing::debug!("No title from {}: {}", provider.model(), e);
+                Ok(fallback.map(|title| (title, TokenUsage::default())))
+            }
+        }
+    }
+}
+
+/// Read a title from a reply, dropping labels, quotes and trailing periods
+pub fn parse_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line)
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(['"', '“', '”', '\'', '`', '*'])
+        .trim_end_matches('.')
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_titles_are_cleaned_up() {
+        assert_eq!(parse_title("Title: \"Fix the flaky login test.\"\n").as_deref(), Some("Fix the flaky login test"));
+        assert_eq!(parse_title("\n## Parser refactor").as_deref(), Some("Parser refactor"));
+        assert_eq!(parse_title("  \n"), None);
+        let long = parse_title(&"word ".repeat(20)).unwrap();
+        assert!(long.chars().count() <= MAX_TITLE_CHARS + 1 && long.ends_with("word…"));
+    }
+
+    #[tokio::test]
+    async fn test_titles_wait_for_an_answer_and_fall_back_to_the_prompt() {
+        let titler = Titler::from_prompts();
+        let mut messages = vec![Message::new_user("Why does the build fail on Windows?\nLog below".to_string())];
+        assert!(titler.title(&messages).await.unwrap().is_none());
+        messages.push(Message::new_assistant("The path separator.".to_string()));
+        let (title, usage) = titler.title(&messages).await.unwrap().unwrap();
+        assert_eq!(title, "Why does the build fail on Windows?");
+        assert_eq!(usage.total_tokens, 0);
+    }
+}
@@ -0,0 +1,278 @@
+//! Speech backends
+//!
+//! [`SystemSpeech`] uses the platform's speech command (`say` on macOS,
+//! System.Speech through PowerShell on Windows, `spd-say` or `espeak` on
+//! Linux) or a user-supplied command. [`OpenAISpeech`] synthesizes audio with
+//! the OpenAI speech endpoint and plays it with a local audio player.
+
+use crate::config::TtsConfig;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Something that can speak a sentence
+#[async_trait]
+pub trait SpeechBackend: Send + Sync {
+    /// Speak the text, returning once it has been spoken
+    ///
+    /// Dropping the returned future must stop playback.
+    async fn speak(&self, text: &str) -> Result<()>;
+}
+
+/// How the system backend hands text to its command
+#[derive(Debug, Clone)]
+enum TextInput {
+    /// Appended as the last argument
+    Argument,
+    /// Written to stdin
+    Stdin,
+}
+
+/// Speech through a local command
+#[derive(Debug, Clone)]
+pub struct SystemSpeech {
+    program: String,
+    args: Vec<String>,
+    input: TextInput,
+}
+
+impl SystemSpeech {
+    /// Pick the command from the configuration or the platform
+    pub fn from_config(config: &TtsConfig) -> Result<Self> {
+        if let Some(command) = &config.command {
+            return Ok(Self {
+                program: shell().to_string(),
+                args: vec![shell_flag().to_string(), command.clone()],
+                input: TextInput::Stdin,
+            });
+        }
+
+        let voice = config.voice.clone();
+        let rate = config.rate.map(|rate| rate.to_string());
+
+        if cfg!(target_os = "macos") {
+            let mut args = Vec::new();
+            if let Some(voice) = voice {
+                args.extend(["-v".to_string(), voice]);
+            }
+            if let Some(rate) = rate {
+                args.extend(["-r".to_string(), rate]);
+            }
+            return Ok(Self { program: "say".to_string(), args, input: TextInput::Argument });
+        }
+
+        if cfg!(windows) {
+            let mut script = String::from(
+                "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; ",
+            );
+            if let Some(voice) = voice {
+                script.push_str(&format!("$s.SelectVoice('{}'); ", voice.replace('\'', "''")));
+            }
+            script.push_str("$s.Speak([Console]::In.ReadToEnd())");
+            return Ok(Self {
+                program: "powershell".to_string(),
+                args: vec!["-NoProfile".to_string(), "-Command".to_string(), script],
+                input: TextInput::Stdin,
+            });
+        }
+
+        if find_program("spd-say") {
+            // -w waits until the text has been spoken
+            let mut args = vec!["-w".to_string()];
+            if let Some(voice) = voice {
+                args.extend(["-y".to_string(), voice]);
+            }
+            return Ok(Self { program: "spd-say".to_string(), args, input: TextInput::Argument });
+        }
+
+        for program in ["espeak-ng", "espeak"] {
+            if find_program(program) {
+                let mut args = Vec::new();
+                if let Some(voice) = voice {
+                    args.extend(["-v".to_string(), voice]);
+                }
+                if let Some(rate) = rate {
+                    args.extend(["-s".to_string(), rate]);
+                }
+                return Ok(Self { program: program.to_string(), args, input: TextInput::Argument });
+            }
+        }
+
+        bail!("No speech command found; install espeak-ng or speech-dispatcher, or set tts.command")
+    }
+}
+
+#[async_trait]
+impl SpeechBackend for SystemSpeech {
+    async fn speak(&self, text: &str) -> Result<()> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let status = match self.input {
+            TextInput::Argument => command.arg(text).stdin(Stdio::null()).status().await,
+            TextInput::Stdin => {
+                let mut child = command.stdin(Stdio::piped()).spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(text.as_bytes()).await?;
+                }
+                child.wait().await
+            }
+        }
+        .with_context(|| format!("Failed to run speech command '{}'", self.program))?;
+
+        if !status.success() {
+            bail!("Speech command '{}' exited with {}", self.program, status);
+        }
+        Ok(())
+    }
+}
+
+/// Speech synthesized by the OpenAI speech endpoint
+pub struct OpenAISpeech {
+    client: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    voice: String,
+    player: Vec<String>,
+}
+
+impl OpenAISpeech {
+    pub fn from_config(config: &TtsConfig) -> Result<Self> {
+        let api_key = config
+            .openai
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+            .context("No API key for the OpenAI speech endpoint")?;
+
+        let player = match &config.player {
+            Some(player) => player.split_whitespace().map(str::to_string).collect(),
+            None => default_player().context("No audio player found; set tts.player")?,
+        };
+
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(Duration::from_secs(60)).build()?,
+            api_key,
+            base_url: config.openai.base_url.trim_end_matches('/').to_string(),
+            model: config.openai.model.clone(),
+            voice: config.voice.clone().unwrap_or_else(|| "alloy".to_string()),
+            player,
+        })
+    }
+}
+
+#[async_trait]
+impl SpeechBackend for OpenAISpeech {
+    async fn speak(&self, text: &str) -> Result<()> {
+        let audio = self
+            .client
+            .post(format!("{}/audio/speech", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "voice": self.voice,
+                "input": text,
+                "response_format": "wav",
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        let file = tempfile::Builder::new().prefix("goofy-tts-").suffix(".wav").tempfile()?;
+        tokio::fs::write(file.path(), &audio).await?;
+
+        // The file replaces a `{file}` placeholder, or is appended
+        let path = file.path().display().to_string();
+        let mut args: Vec<String> = self.player.iter().map(|arg| arg.replace("{file}", &path)).collect();
+        if !self.player.iter().any(|arg| arg.contains("{file}")) {
+            args.push(path);
+        }
+        let (program, args) = args.split_first().context("Empty audio player command")?;
+        let status = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await
+            .with_context(|| format!("Failed to run audio player '{}'", program))?;
+
+        if !status.success() {
+            bail!("Audio player '{}' exited with {}", program, status);
+        }
+        Ok(())
+    }
+}
+
+/// First available player for WAV files
+fn default_player() -> Option<Vec<String>> {
+    if cfg!(target_os = "macos") {
+        return Some(vec!["afplay".to_string()]);
+    }
+    if cfg!(windows) {
+        return Some(vec![
+            "powershell".to_string(),
+            "-NoProfile".to_string(),
+            "-Command".to_string(),
+            "(New-Object Media.SoundPlayer '{file}').PlaySync()".to_string(),
+        ]);
+    }
+
+    let candidates: &[&[&str]] = &[
+        &["paplay"],
+        &["aplay", "-q"],
+        &["mpv", "--really-quiet", "--no-video"],
+        &["ffplay", "-nodisp", "-autoexit", "-loglevel", "quiet"],
+    ];
+    candidates
+        .iter()
+        .find(|command| find_program(command[0]))
+        .map(|command| command.iter().map(|s| s.to_string()).collect())
+}
+
+/// Whether a program is on the PATH
+fn find_program(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+fn shell() -> &'static str {
+    if cfg!(windows) { "cmd" } else { "sh" }
+}
+
+fn shell_flag() -> &'static str {
+    if cfg!(windows) { "/C" } else { "-c" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_command_receives_text_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("spoken.txt");
+        let config = TtsConfig {
+            command: Some(format!("cat > '{}'", out.display())),
+            ..Default::default()
+        };
+
+        let speech = SystemSpeech::from_config(&config).unwrap();
+        speech.speak("Hello there.").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "Hello there.");
+    }
+}
@@ -0,0 +1,258 @@
+//! Read-aloud of assistant responses
+//!
+//! Responses are split into sentences as they stream in and queued on a
+//! [`Speaker`], which hands them one at a time to a speech backend: the
+//! platform's speech command or the OpenAI speech endpoint. Playback can be
+//! paused between or during sentences; a paused sentence starts over when
+//! playback resumes.
+
+pub mod backend;
+
+pub use backend::{OpenAISpeech, SpeechBackend, SystemSpeech};
+
+use crate::config::{TtsBackendKind, TtsConfig};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use tracing::warn;
+
+/// Splits streamed markdown into sentences worth speaking
+///
+/// Code blocks are skipped and markdown markup is stripped, so only prose is
+/// read out.
+#[derive(Debug, Default)]
+pub struct SentenceChunker {
+    buffer: String,
+    in_code_block: bool,
+}
+
+impl SentenceChunker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add streamed text, returning the sentences it completes
+    pub fn push(&mut self, delta: &str) -> Vec<String> {
+        self.buffer.push_str(delta);
+        let mut sentences = Vec::new();
+
+        while let Some(newline) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=newline).collect();
+            if line.trim_start().starts_with("```") {
+                self.in_code_block = !self.in_code_block;
+            } else if !self.in_code_block {
+                split_sentences(&line, &mut sentences);
+            }
+        }
+
+        // A line that may still turn into a code fence is held back
+        if !self.in_code_block && !self.buffer.trim_start().starts_with('`') {
+            while let Some(end) = sentence_end(&self.buffer) {
+                let sentence: String = self.buffer.drain(..end).collect();
+                sentences.extend(speakable(&sentence));
+            }
+        }
+
+        sentences
+    }
+
+    /// Flush what is left at the end of a response
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let in_code_block = std::mem::take(&mut self.in_code_block);
+        if in_code_block || rest.trim_start().starts_with("```") {
+            return None;
+        }
+        speakable(&rest)
+    }
+}
+
+/// Split a complete line into speakable sentences
+fn split_sentences(line: &str, sentences: &mut Vec<String>) {
+    let mut rest = line;
+    while let Some(end) = sentence_end(rest) {
+        sentences.extend(speakable(&rest[..end]));
+        rest = &rest[end..];
+    }
+    sentences.extend(speakable(rest));
+}
+
+/// Byte offset just past the first sentence-ending punctuation followed by whitespace
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?') {
+            if let Some((_, next)) = chars.peek() {
+                if next.is_whitespace() {
+                    return Some(index + c.len_utf8());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Strip markdown markup, returning `None` if nothing worth saying is left
+fn speakable(text: &str) -> Option<String> {
+    let text = text
+        .trim()
+        .trim_start_matches(|c: char| matches!(c, '#' | '>' | '-' | '*' | '+') || c.is_whitespace())
+        .replace("**", "")
+        .replace('`', "");
+    let text = text.trim();
+    if text.chars().any(char::is_alphanumeric) {
+        Some(text.to_string())
+    } else {
+        None
+    }
+}
+
+enum SpeakerCommand {
+    Say(String),
+    Stop,
+}
+
+/// Queue of sentences spoken in the background
+#[derive(Clone)]
+pub struct Speaker {
+    commands: mpsc::UnboundedSender<SpeakerCommand>,
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Speaker {
+    /// Start a speaker on the given backend
+    pub fn new(backend: Arc<dyn SpeechBackend>) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (paused, paused_rx) = watch::channel(false);
+        tokio::spawn(run_speaker(backend, command_rx, paused_rx));
+        Self {
+            commands,
+            paused: Arc::new(paused),
+        }
+    }
+
+    /// Start a speaker with the backend chosen in the configuration
+    pub fn from_config(config: &TtsConfig) -> Result<Self> {
+        let backend: Arc<dyn SpeechBackend> = match config.backend {
+            TtsBackendKind::System => Arc::new(SystemSpeech::from_config(config)?),
+            TtsBackendKind::Openai => Arc::new(OpenAISpeech::from_config(config)?),
+        };
+        Ok(Self::new(backend))
+    }
+
+    /// Queue a sentence
+    pub fn say(&self, sentence: impl Into<String>) {
+        let _ = self.commands.send(SpeakerCommand::Say(sentence.into()));
+    }
+
+    /// Pause or resume playback, returning whether it is now paused
+    pub fn toggle_pause(&self) -> bool {
+        let paused = !*self.paused.borrow();
+        self.paused.send_replace(paused);
+        paused
+    }
+
+    /// Whether playback is paused
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Stop speaking and drop everything queued
+    pub fn stop(&self) {
+        let _ = self.commands.send(SpeakerCommand::Stop);
+        self.paused.send_replace(false);
+    }
+}
+
+/// Speak queued sentences until the speaker is dropped
+async fn run_speaker(
+    backend: Arc<dyn SpeechBackend>,
+    mut commands: mpsc::UnboundedReceiver<SpeakerCommand>,
+    mut paused: watch::Receiver<bool>,
+) {
+    let mut queue: VecDeque<String> = VecDeque::new();
+
+    loop {
+        if queue.is_empty() || *paused.borrow() {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(SpeakerCommand::Say(sentence)) => queue.push_back(sentence),
+                    Some(SpeakerCommand::Stop) => queue.clear(),
+                    None => return,
+                },
+                changed = paused.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let Some(sentence) = queue.pop_front() else {
+            continue;
+        };
+        // Dropping the future stops playback of the current sentence
+        let speaking = backend.speak(&sentence);
+        tokio::pin!(speaking);
+
+        loop {
+            tokio::select! {
+                result = &mut speaking => {
+                    if let Err(e) = result {
+                        warn!("Text-to-speech failed: {}", e);
+                    }
+                    break;
+                }
+                command = commands.recv() => match command {
+                    Some(SpeakerCommand::Say(next)) => queue.push_back(next),
+                    Some(SpeakerCommand::Stop) => {
+                        queue.clear();
+                        break;
+                    }
+                    None => return,
+                },
+                changed = paused.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    if *paused.borrow() {
+                        queue.push_front(sentence.clone());
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_all(deltas: &[&str]) -> Vec<String> {
+        let mut chunker = SentenceChunker::new();
+        let mut sentences: Vec<String> = deltas.iter().flat_map(|delta| chunker.push(delta)).collect();
+        sentences.extend(chunker.finish());
+        sentences
+    }
+
+    #[test]
+    fn test_sentences_are_emitted_as_they_complete() {
+        let mut chunker = SentenceChunker::new();
+        assert!(chunker.push("Rust has own").is_empty());
+        assert_eq!(chunker.push("ership. It is "), vec!["Rust has ownership."]);
+        assert_eq!(chunker.push("checked at compile time! Next"), vec!["It is checked at compile time!"]);
+        assert_eq!(chunker.finish().as_deref(), Some("Next"));
+    }
+
+    #[test]
+    fn test_code_blocks_and_markup_are_skipped() {
+        let sentences = chunk_all(&[
+            "## Example\n\nUse **borrowing** here.\n```r",
+            "ust\nlet x = 1.0; // not. read\n```\n- Call `clone()` sparingly.\n",
+        ]);
+        assert_eq!(sentences, vec!["Example", "Use borrowing here.", "Call clone() sparingly."]);
+    }
+}
@@ -1,10 +1,20 @@
-use crate::tui::{events::Event, keys::KeyMap, pages::{Page, PageId, PageManager, /* chat::ChatPage, home::HomePage, settings::SettingsPage */}, themes::{Theme, presets}, Frame};
+use crate::config::IconMode;
+use crate::fswatch::FileTracker;
+use crate::llm::tools::JobRegistry;
+use crate::permission::PermissionRequest;
+use crate::session::{snapshots::SnapshotStore, SessionManager};
+use crate::tui::components::image::protocol as image_protocol;
+use crate::tui::components::{dialogs::{permission::PermissionDialog, recent_files::{RecentFileAction, RecentFilesDialog}, session_diff::SessionDiffDialog, Dialog}, Component};
+use crate::tui::{events::Event, keys::KeyMap, profiler, responsive::{self, MinSize}, macros::{self, MacroOutcome, MacroRecorder}, pages::{Page, PageId, PageManager, /* chat::ChatPage, home::HomePage, settings::SettingsPage */}, themes::{self, user::{self as user_themes, ThemeWatcher}, IconSet, SharedThemes, Theme}, capabilities::TerminalCapabilities, Frame};
 use anyhow::Result;
+use crossterm::event::KeyEvent;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::style::{Color, Style};
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
 
 /// Main application state and controller
 pub struct App {
@@ -23,6 +33,12 @@ pub struct App {
     /// Current theme for styling
     pub theme: Theme,
     
+    /// Themes shared with the components
+    themes: SharedThemes,
+    
+    /// Changes to the shared themes not yet applied to `theme`
+    theme_changes: watch::Receiver<u64>,
+    
     /// Status message to display
     pub status_message: Option<String>,
     
@@ -34,6 +50,45 @@ pub struct App {
     
     /// Event receiver for internal communication
     pub event_receiver: mpsc::UnboundedReceiver<Event>,
+    
+    /// Set when the draft should be opened in an external editor
+    external_edit_requested: bool,
+    
+    /// Tool calls waiting for the user's approval
+    permission_requests: Option<mpsc::UnboundedReceiver<PermissionRequest>>,
+    
+    /// Approval dialog of the tool call being asked about
+    permission_dialog: Option<PermissionDialog>,
+    
+    /// Running cost of the current session and its budget, in US dollars
+    session_cost: Option<(f64, Option<f64>)>,
+    
+    /// Keyboard macros
+    macros: MacroRecorder,
+    
+    /// Configuration file recorded macros are saved to
+    macro_store: Option<PathBuf>,
+    
+    /// Files the agent read and edited this session
+    file_tracker: Option<Arc<FileTracker>>,
+    
+    /// Directory recent files are shown relative to
+    workspace_root: Option<PathBuf>,
+    
+    /// Switcher over the files the agent touched
+    recent_files_dialog: Option<RecentFilesDialog>,
+    
+    /// Snapshots of the files the agent edited, and the session they belong to
+    snapshots: Option<(Arc<SnapshotStore>, String)>,
+    
+    /// Stored sessions, for comparing them
+    session_manager: Option<Arc<SessionManager>>,
+    
+    /// Picker and view comparing two sessions
+    session_diff_dialog: Option<SessionDiffDialog>,
+    
+    /// Background jobs the agent started
+    jobs: Option<Arc<JobRegistry>>,
 }
 
 /// Application configuration
@@ -80,37 +135,288 @@ impl App {
         // TODO: Fix when pages are available
         // page_manager.navigate_to("home".to_string())?;
         
-        Ok(Self {
+        let mut app = Self {
             should_quit: false,
             size: Rect::default(),
             key_map: KeyMap::default(),
             page_manager,
-            theme: presets::goofy_dark(),
+            theme: (*themes::current_theme()).clone(),
+            themes: themes::shared().clone(),
+            theme_changes: themes::shared().subscribe(),
             status_message: None,
             config: AppConfig::default(),
             event_sender,
             event_receiver,
-        })
+            external_edit_requested: false,
+            permission_requests: None,
+            permission_dialog: None,
+            session_cost: None,
+            macros: MacroRecorder::new(),
+            macro_store: None,
+            file_tracker: None,
+            workspace_root: None,
+            recent_files_dialog: None,
+            snapshots: None,
+            session_manager: None,
+            session_diff_dialog: None,
+            jobs: None,
+        };
+        app.set_icon_mode(IconMode::Auto);
+        Ok(app)
+    }
+    
+    /// Pick the icon glyphs for the configured mode and this terminal
+    pub fn set_icon_mode(&mut self, mode: IconMode) {
+        let style = TerminalCapabilities::probe().icon_style(mode);
+        self.theme.icons = IconSet::for_style(style);
+        self.themes.update(|themes| themes.set_icon_style(style));
+    }
+    
+    /// Share themes with the components other than the global ones
+    pub fn set_themes(&mut self, themes: SharedThemes) {
+        self.theme_changes = themes.subscribe();
+        self.themes = themes;
+        self.apply_shared_theme();
+    }
+    
+    /// Take up the current shared theme if it changed
+    fn refresh_theme(&mut self) {
+        if self.theme_changes.has_changed().unwrap_or(false) {
+            self.theme_changes.borrow_and_update();
+            self.apply_shared_theme();
+        }
+    }
+    
+    /// Use the current shared theme, keeping the icons picked for this terminal
+    fn apply_shared_theme(&mut self) {
+        let icons = self.theme.icons.clone();
+        self.theme = (*self.themes.current()).clone();
+        self.theme.icons = icons;
+    }
+    
+    /// Ask the user about tool calls arriving on `requests`
+    ///
+    /// Each request opens a modal approval dialog; the tool call waits until
+    /// the user answers it.
+    pub fn set_permission_requests(&mut self, requests: mpsc::UnboundedReceiver<PermissionRequest>) {
+        self.permission_requests = Some(requests);
+    }
+    
+    /// Show the running cost of the current session in the status bar
+    pub fn set_session_cost(&mut self, total_cost: f64, max_cost: Option<f64>) {
+        self.session_cost = Some((total_cost, max_cost));
+    }
+    
+    /// Register the themes in the user's themes directory and watch it for changes
+    ///
+    /// Changed theme files arrive as events on `sender`; the returned watcher
+    /// stops watching when dropped.
+    pub fn load_user_themes(&mut self, dir: &Path, sender: mpsc::UnboundedSender<Event>) -> Option<ThemeWatcher> {
+        let problems = self.themes.update(|themes| themes.load_user_themes(dir));
+        for problem in &problems {
+            tracing::warn!("{}", problem);
+        }
+        if let Some(problem) = problems.first() {
+            self.status_message = Some(problem.clone());
+        }
+        if !dir.is_dir() {
+            return None;
+        }
+        match ThemeWatcher::new(dir, sender) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::warn!("Not watching {} for theme changes: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+    
+    /// Switch to a preset or user theme by name
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        self.themes.set_theme(name)?;
+        self.refresh_theme();
+        Ok(())
+    }
+    
+    /// Load a theme file again after it changed, applying it if it is in use
+    fn reload_theme_file(&mut self, path: &Path) {
+        match self.themes.update(|themes| themes.load_theme_file(path)) {
+            Ok(name) if name == self.theme.name => {
+                self.refresh_theme();
+                self.status_message = Some(format!("Reloaded theme {}", name));
+            }
+            Ok(_) => {}
+            // Keep the last good version while the file is being edited
+            Err(e) => self.status_message = Some(format!("{:#}", e)),
+        }
+    }
+    
+    /// Load the macros saved in a configuration file and save new ones there
+    pub fn set_macro_store(&mut self, path: PathBuf) {
+        match macros::load_stored(&path) {
+            Ok(stored) => {
+                for problem in self.macros.load(&stored) {
+                    tracing::warn!("Ignoring {}", problem);
+                }
+            }
+            Err(e) => tracing::warn!("Could not load macros from {}: {}", path.display(), e),
+        }
+        self.macro_store = Some(path);
+    }
+    
+    /// Show how many background jobs of the agent are running
+    pub fn set_jobs(&mut self, jobs: Arc<JobRegistry>) {
+        self.jobs = Some(jobs);
+    }
+    
+    /// List the files the agent touched, relative to `workspace_root`, in the recent files switcher
+    pub fn set_file_tracker(&mut self, tracker: Arc<FileTracker>, workspace_root: Option<PathBuf>) {
+        self.file_tracker = Some(tracker);
+        self.workspace_root = workspace_root;
+    }
+    
+    /// Open the recent files switcher
+    fn open_recent_files(&mut self) {
+        let Some(tracker) = &self.file_tracker else {
+            self.status_message = Some("No agent is attached, so there are no recent files".to_string());
+            return;
+        };
+        let mut dialog = RecentFilesDialog::new(tracker.recent(), self.workspace_root.as_deref());
+        dialog.set_event_sender(self.event_sender.clone());
+        self.recent_files_dialog = Some(dialog);
+    }
+    
+    /// Open or attach the file chosen in the switcher on the current page
+    fn apply_recent_file_action(&mut self, action: RecentFileAction) {
+        let Some(page) = self.page_manager.current_page_mut() else {
+            self.status_message = Some("No page to open the file in".to_string());
+            return;
+        };
+        let (result, path, done) = match &action {
+            RecentFileAction::Open(path) => (page.open_file(path), path, "Opened"),
+            RecentFileAction::Attach(path) => (page.attach_file(path), path, "Attached"),
+        };
+        self.status_message = Some(match result {
+            Ok(()) => format!("{} {}", done, path.display()),
+            Err(e) => e.to_string(),
+        });
+    }
+    
+    /// Undo the agent's file changes in the given session from snapshots in `store`
+    pub fn set_snapshot_store(&mut self, store: Arc<SnapshotStore>, session_id: String) {
+        self.snapshots = Some((store, session_id));
+    }
+    
+    /// Restore the files the last agent turn with changes modified
+    fn undo_last_turn(&mut self) {
+        let Some((store, session_id)) = &self.snapshots else {
+            self.status_message = Some("No agent is attached, so there is nothing to undo".to_string());
+            return;
+        };
+        self.status_message = Some(match store.undo_last_turn(session_id) {
+            Ok(Some((turn, restored))) => format!("Undid turn {}: restored {} file(s)", turn, restored.len()),
+            Ok(None) => "Nothing to undo".to_string(),
+            Err(e) => format!("Undo failed: {:#}", e),
+        });
+    }
+    
+    /// Compare stored sessions from `manager`
+    pub fn set_session_manager(&mut self, manager: Arc<SessionManager>) {
+        self.session_manager = Some(manager);
+    }
+    
+    /// Open the session comparison over the most recent sessions
+    async fn open_session_diff(&mut self) {
+        let Some(manager) = &self.session_manager else {
+            self.status_message = Some("No sessions are stored to compare".to_string());
+            return;
+        };
+        match manager.list_sessions(Some(100)).await {
+            Ok(sessions) => {
+                let mut dialog = SessionDiffDialog::new(sessions);
+                dialog.set_event_sender(self.event_sender.clone());
+                self.session_diff_dialog = Some(dialog);
+            }
+            Err(e) => self.status_message = Some(format!("Could not list sessions: {:#}", e)),
+        }
+    }
+    
+    /// Load the comparison of the sessions picked in the dialog
+    async fn load_session_diff(&mut self, first: &str, second: &str) {
+        let (Some(manager), Some(dialog)) = (&self.session_manager, self.session_diff_dialog.as_mut()) else {
+            return;
+        };
+        match manager.compare_sessions(first, second).await {
+            Ok(comparison) => dialog.show(comparison),
+            Err(e) => dialog.show_error(format!("{:#}", e)),
+        }
+    }
+    
+    /// Keep a recorded macro for later sessions
+    fn save_macro(&mut self, register: char, keys: &[KeyEvent]) {
+        let saved = match &self.macro_store {
+            Some(path) => macros::save(path, register, keys).map_err(|e| e.to_string()),
+            None => Err("no configuration file".to_string()),
+        };
+        self.status_message = Some(match saved {
+            Ok(()) => format!("Recorded macro {} ({} keys)", register, keys.len()),
+            Err(e) => format!("Recorded macro {} ({} keys) for this session only: {}", register, keys.len(), e),
+        });
+    }
+    
+    /// Open the approval dialog for the next waiting tool call
+    fn poll_permission_requests(&mut self) {
+        if self.permission_dialog.as_ref().is_some_and(|dialog| !dialog.is_answered()) {
+            return;
+        }
+        self.permission_dialog = None;
+        if let Some(request) = self.permission_requests.as_mut().and_then(|rx| rx.try_recv().ok()) {
+            let mut dialog = PermissionDialog::new(request);
+            dialog.set_event_sender(self.event_sender.clone());
+            self.permission_dialog = Some(dialog);
+        }
     }
     
     /// Handle incoming events
     pub async fn handle_event(&mut self, event: Event) -> Result<bool> {
         match event {
             Event::Key(key_event) => {
-                if self.key_map.should_quit(&key_event) {
+                // Ctrl+C copies while text is selected
+                let has_selection = self.page_manager.current_page().is_some_and(|page| page.has_selection());
+                if self.key_map.should_quit(&key_event) && !has_selection {
                     self.should_quit = true;
                     return Ok(true);
                 }
                 
-                if self.key_map.should_show_help(&key_event) {
-                    self.config.show_help = !self.config.show_help;
-                    return Ok(false);
+                // Approvals are answered by hand, never by a macro
+                if self.permission_dialog.is_none() {
+                    let record = self.key_map.should_record_macro(&key_event);
+                    let play = self.key_map.should_play_macro(&key_event);
+                    match self.macros.handle_key(key_event, record, play) {
+                        MacroOutcome::Pass => {}
+                        MacroOutcome::Consumed(message) => {
+                            if message.is_some() {
+                                self.status_message = message;
+                            }
+                            return Ok(false);
+                        }
+                        MacroOutcome::Recorded(register, keys) => {
+                            self.save_macro(register, &keys);
+                            return Ok(false);
+                        }
+                        MacroOutcome::Replay(keys) => {
+                            for key in keys {
+                                if self.should_quit || self.permission_dialog.is_some() {
+                                    break;
+                                }
+                                self.handle_key(key).await?;
+                            }
+                            return Ok(self.should_quit);
+                        }
+                    }
                 }
                 
-                // Forward key events to current page
-                if let Some(current_page) = self.page_manager.current_page_mut() {
-                    current_page.handle_key_event(key_event).await?;
-                }
+                self.handle_key(key_event).await?;
             }
             
             Event::Mouse(mouse_event) => {
@@ -123,16 +429,29 @@ impl App {
             
             Event::Resize(width, height) => {
                 self.size = Rect::new(0, 0, width, height);
+                image_protocol::invalidate();
                 self.page_manager.resize(self.size);
             }
             
             Event::Tick => {
                 // Handle periodic updates
+                self.refresh_theme();
+                self.poll_permission_requests();
                 if let Some(current_page) = self.page_manager.current_page_mut() {
                     current_page.tick().await?;
                 }
             },
             
+            Event::Custom(kind, serde_json::Value::String(text)) if kind == "paste" => {
+                if let Some(current_page) = self.page_manager.current_page_mut() {
+                    current_page.paste(&text);
+                }
+            }
+            
+            Event::Custom(kind, serde_json::Value::String(path)) if kind == user_themes::THEME_CHANGED_EVENT => {
+                self.reload_theme_file(Path::new(&path));
+            }
+            
             Event::Custom(_, _) => {
                 // Handle custom events
             },
@@ -158,10 +477,89 @@ impl App {
         Ok(self.should_quit)
     }
     
+    /// Handle a key typed or replayed from a macro
+    async fn handle_key(&mut self, key_event: KeyEvent) -> Result<()> {
+        if self.key_map.should_show_help(&key_event) {
+            self.config.show_help = !self.config.show_help;
+            return Ok(());
+        }
+        
+        if self.key_map.should_toggle_profiler(&key_event) {
+            profiler::set_enabled(!profiler::is_enabled());
+            return Ok(());
+        }
+        
+        // A pending approval takes every other key
+        if let Some(dialog) = self.permission_dialog.as_mut() {
+            dialog.handle_key_event(key_event).await?;
+            self.poll_permission_requests();
+            return Ok(());
+        }
+        
+        if let Some(dialog) = self.recent_files_dialog.as_mut() {
+            dialog.handle_key_event(key_event).await?;
+            let action = dialog.take_action();
+            if dialog.is_closed() {
+                self.recent_files_dialog = None;
+            }
+            if let Some(action) = action {
+                self.apply_recent_file_action(action);
+            }
+            return Ok(());
+        }
+        
+        if let Some(dialog) = self.session_diff_dialog.as_mut() {
+            dialog.handle_key_event(key_event).await?;
+            let request = dialog.take_request();
+            if dialog.is_closed() {
+                self.session_diff_dialog = None;
+            } else if let Some((first, second)) = request {
+                self.load_session_diff(&first, &second).await;
+            }
+            return Ok(());
+        }
+        
+        if self.key_map.should_compare_sessions(&key_event) {
+            self.open_session_diff().await;
+            return Ok(());
+        }
+        
+        if self.key_map.should_open_recent_files(&key_event) {
+            self.open_recent_files();
+            return Ok(());
+        }
+        
+        if self.key_map.should_undo_turn(&key_event) {
+            self.undo_last_turn();
+            return Ok(());
+        }
+        
+        // The editor needs the terminal, so the main loop runs it
+        if self.key_map.should_open_external_editor(&key_event) {
+            self.external_edit_requested = self.draft().is_some();
+            return Ok(());
+        }
+        
+        // Forward key events to current page
+        if let Some(current_page) = self.page_manager.current_page_mut() {
+            current_page.handle_key_event(key_event).await?;
+        }
+        Ok(())
+    }
+    
     /// Render the application UI
     pub fn render(&mut self, frame: &mut Frame) {
         self.size = frame.size();
         
+        // Past every compact layout there is nothing sensible left to draw
+        let min_size = self.page_manager.current_page().map_or(MinSize::DEFAULT, |page| page.min_size());
+        let needed = MinSize::new(min_size.width, min_size.height + 1);
+        if !needed.fits(self.size) {
+            image_protocol::hide(self.size);
+            responsive::render_too_small(frame, self.size, needed, &self.theme);
+            return;
+        }
+        
         // Create main layout
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -173,45 +571,168 @@ impl App {
         
         // Render current page
         if let Some(current_page) = self.page_manager.current_page_mut() {
+            let _span = profiler::span("page");
             current_page.render(frame, chunks[0], &self.theme);
         } else {
             // Render empty state
             let empty_block = Block::default()
                 .borders(Borders::ALL)
                 .title("Crush Terminal")
-                .style(self.theme.styles.base);
+                .style(Style::default().fg(self.theme.fg_base));
             
             let empty_text = Paragraph::new("No active page")
                 .block(empty_block)
-                .style(self.theme.styles.text);
+                .style(Style::default().fg(self.theme.fg_base));
                 
             frame.render_widget(empty_text, chunks[0]);
         }
         
         // Render status bar
-        self.render_status_bar(frame, chunks[1]);
+        {
+            let _span = profiler::span("status bar");
+            self.render_status_bar(frame, chunks[1]);
+        }
         
         // Render help overlay if enabled
         if self.config.show_help {
             self.render_help_overlay(frame);
         }
+        
+        if self.recent_files_dialog.is_some() {
+            let _span = profiler::span("recent files");
+            self.render_recent_files_dialog(frame);
+        }
+        
+        if self.session_diff_dialog.is_some() {
+            let _span = profiler::span("session diff");
+            self.render_session_diff_dialog(frame);
+        }
+        
+        if self.permission_dialog.is_some() {
+            let _span = profiler::span("permission dialog");
+            self.render_permission_dialog(frame);
+        }
+        
+        if profiler::is_enabled() {
+            self.render_profiler_overlay(frame);
+        }
+    }
+    
+    /// Render the profiler's timings in the top right corner
+    fn render_profiler_overlay(&self, frame: &mut Frame) {
+        let lines = profiler::overlay_lines(&self.theme);
+        let width = lines.iter().map(|line| line.width() as u16).max().unwrap_or(0) + 2;
+        let height = lines.len() as u16 + 2;
+        let screen = frame.size();
+        let area = Rect::new(
+            screen.x + screen.width.saturating_sub(width),
+            screen.y,
+            width.min(screen.width),
+            height.min(screen.height.saturating_sub(1)),
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Profiler (F12)")
+            .style(Style::default().fg(self.theme.fg_base));
+        
+        image_protocol::hide(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+    
+    /// Render the recent files switcher above the page
+    fn render_recent_files_dialog(&mut self, frame: &mut Frame) {
+        let Some(dialog) = self.recent_files_dialog.as_mut() else {
+            return;
+        };
+        let area = centered_rect(60, 60, frame.size());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(dialog.config().title.clone().unwrap_or_default())
+            .style(Style::default().fg(self.theme.fg_base));
+        let inner = block.inner(area);
+        
+        image_protocol::hide(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        dialog.render_content(frame, inner, &self.theme);
+    }
+    
+    /// Render the session comparison above the page
+    fn render_session_diff_dialog(&mut self, frame: &mut Frame) {
+        let Some(dialog) = self.session_diff_dialog.as_mut() else {
+            return;
+        };
+        let area = centered_rect(80, 80, frame.size());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(dialog.config().title.clone().unwrap_or_default())
+            .style(Style::default().fg(self.theme.fg_base));
+        let inner = block.inner(area);
+        
+        image_protocol::hide(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        dialog.render_content(frame, inner, &self.theme);
+    }
+    
+    /// Render the tool approval dialog above everything else
+    fn render_permission_dialog(&mut self, frame: &mut Frame) {
+        let Some(dialog) = self.permission_dialog.as_mut() else {
+            return;
+        };
+        let area = centered_rect(70, 70, frame.size());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(dialog.config().title.clone().unwrap_or_default())
+            .style(Style::default().fg(self.theme.fg_base));
+        let inner = block.inner(area);
+        
+        image_protocol::hide(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        dialog.render_content(frame, inner, &self.theme);
     }
     
     /// Render the status bar
     fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
-        let status_text = if let Some(ref message) = self.status_message {
+        let mut status_text = if let Some(ref message) = self.status_message {
             message.clone()
         } else {
             format!(
-                "Page: {} | Press Ctrl+G for help | Ctrl+C to quit",
+                "Page: {} | F1 help | Ctrl+P recent files | Ctrl+G edit in $EDITOR | Ctrl+C to quit",
                 self.page_manager.current_page_id().map_or("None", |v| v)
             )
         };
+        if let Some(register) = self.macros.recording() {
+            status_text = format!("{} REC {} | {}", self.theme.icons.loading, register, status_text);
+        }
+        let running_jobs = self.jobs.as_ref().map_or(0, |jobs| jobs.running());
+        if running_jobs > 0 {
+            let plural = if running_jobs == 1 { "" } else { "s" };
+            status_text = format!("{} {} job{} | {}", self.theme.icons.loading, running_jobs, plural, status_text);
+        }
         
         let status_paragraph = Paragraph::new(status_text)
-            .style(self.theme.styles.status_bar);
+            .style(Style::default().fg(self.theme.fg_muted).bg(self.theme.bg_base_lighter));
             
         frame.render_widget(status_paragraph, area);
+        
+        if let Some((total_cost, max_cost)) = self.session_cost {
+            let cost_text = match max_cost {
+                Some(max_cost) => format!(" ${:.4} / ${:.2} ", total_cost, max_cost),
+                None => format!(" ${:.4} ", total_cost),
+            };
+            let style = if max_cost.is_some_and(|max_cost| total_cost >= max_cost) {
+                Style::default().fg(self.theme.warning)
+            } else {
+                Style::default().fg(self.theme.fg_muted).bg(self.theme.bg_base_lighter)
+            };
+            let width = (cost_text.len() as u16).min(area.width);
+            let cost_area = Rect::new(area.x + area.width - width, area.y, width, area.height);
+            frame.render_widget(Clear, cost_area);
+            frame.render_widget(Paragraph::new(cost_text).style(style), cost_area);
+        }
     }
     
     /// Render help overlay
@@ -222,15 +743,33 @@ impl App {
         let help_block = Block::default()
             .borders(Borders::ALL)
             .title("Help")
-            .style(self.theme.styles.base);
+            .style(Style::default().fg(self.theme.fg_base));
             
         let help_paragraph = Paragraph::new(help_text)
             .block(help_block)
-            .style(self.theme.styles.text);
+            .style(Style::default().fg(self.theme.fg_base));
             
+        image_protocol::hide(help_area);
         frame.render_widget(help_paragraph, help_area);
     }
     
+    /// Take a pending request to edit the draft externally
+    pub fn take_external_edit_request(&mut self) -> bool {
+        std::mem::take(&mut self.external_edit_requested)
+    }
+    
+    /// Draft of the current page, if it has an input
+    pub fn draft(&self) -> Option<String> {
+        self.page_manager.current_page().and_then(|page| page.draft())
+    }
+    
+    /// Replace the draft of the current page
+    pub fn set_draft(&mut self, content: String) {
+        if let Some(page) = self.page_manager.current_page_mut() {
+            page.set_draft(content);
+        }
+    }
+    
     /// Get a sender for internal events
     pub fn event_sender(&self) -> mpsc::UnboundedSender<Event> {
         self.event_sender.clone()
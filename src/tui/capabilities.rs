@@ -0,0 +1,156 @@
+//! Terminal capability probing
+//!
+//! Terminals cannot be asked which glyphs they draw, so the prober guesses
+//! from the environment. The locale tells whether the terminal expects UTF-8
+//! at all, and `TERM`, `TERM_PROGRAM` and variables set by specific emulators
+//! tell whether it is one known to draw color emoji. Nerd Font glyphs depend
+//! on the font rather than the terminal, so they are only used when asked for
+//! in the configuration or with `GOOFY_NERD_FONT=1`.
+//!
+//! Image protocols are guessed the same way. Inside tmux or screen the
+//! escape sequences would need wrapping, so character cells are used there
+//! unless `GOOFY_GRAPHICS` names a protocol.
+
+use crate::config::IconMode;
+
+use super::themes::IconStyle;
+
+/// `TERM_PROGRAM` values of terminals that draw color emoji
+const EMOJI_TERM_PROGRAMS: &[&str] = &["iTerm.app", "Apple_Terminal", "WezTerm", "vscode", "ghostty", "Hyper"];
+
+/// Variables set by terminals that draw color emoji
+const EMOJI_TERM_VARS: &[&str] = &["WT_SESSION", "KITTY_WINDOW_ID", "WEZTERM_EXECUTABLE", "KONSOLE_VERSION", "VTE_VERSION"];
+
+/// `TERM` values of terminals that draw Sixel images
+const SIXEL_TERMS: &[&str] = &["foot", "foot-extra", "mlterm", "contour", "yaft-256color"];
+
+/// Native image protocol a terminal understands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphicsSupport {
+    /// Only character cells
+    #[default]
+    None,
+    /// DEC Sixel graphics
+    Sixel,
+    /// Kitty graphics protocol
+    Kitty,
+    /// iTerm2 inline images
+    ITerm2,
+}
+
+/// What the terminal is able to draw
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// Characters beyond ASCII, like box drawing
+    pub unicode: bool,
+    /// Color emoji
+    pub emoji: bool,
+    /// Glyphs of a patched Nerd Font
+    pub nerd_font: bool,
+    /// Native image protocol
+    pub graphics: GraphicsSupport,
+}
+
+impl TerminalCapabilities {
+    /// Probe the terminal Goofy runs in
+    pub fn probe() -> Self {
+        Self::probe_with(|name| std::env::var(name).ok())
+    }
+
+    /// Probe with environment variables looked up by `var`
+    pub fn probe_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let term = var("TERM").unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| var(name))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        // Windows Terminal is UTF-8 whatever the locale says
+        let unicode = var("WT_SESSION").is_some() || locale.contains("utf-8") || locale.contains("utf8");
+
+        let emoji_terminal = var("TERM_PROGRAM").is_some_and(|program| EMOJI_TERM_PROGRAMS.contains(&program.as_str()))
+            || EMOJI_TERM_VARS.iter().any(|name| var(name).is_some())
+            || term == "xterm-kitty"
+            || term == "alacritty"
+            || term.contains("ghostty");
+        // The Linux console only has a 512 glyph font
+        let emoji = unicode && emoji_terminal && term != "linux" && term != "dumb";
+
+        let nerd_font = unicode && var("GOOFY_NERD_FONT").is_some_and(|value| value == "1" || value == "true");
+
+        let graphics = match var("GOOFY_GRAPHICS").map(|value| value.to_ascii_lowercase()).as_deref() {
+            Some("sixel") => GraphicsSupport::Sixel,
+            Some("kitty") => GraphicsSupport::Kitty,
+            Some("iterm2") => GraphicsSupport::ITerm2,
+            Some(_) => GraphicsSupport::None,
+            None if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") => GraphicsSupport::None,
+            None if term == "xterm-kitty" || term.contains("ghostty") || var("KITTY_WINDOW_ID").is_some() => {
+                GraphicsSupport::Kitty
+            }
+            None if matches!(var("TERM_PROGRAM").as_deref(), Some("iTerm.app" | "WezTerm")) => GraphicsSupport::ITerm2,
+            None if SIXEL_TERMS.contains(&term.as_str()) || var("KONSOLE_VERSION").is_some() => GraphicsSupport::Sixel,
+            None => GraphicsSupport::None,
+        };
+
+        Self { unicode, emoji, nerd_font, graphics }
+    }
+
+    /// Icon glyphs to draw for the configured mode
+    pub fn icon_style(&self, mode: IconMode) -> IconStyle {
+        match mode {
+            IconMode::Emoji => IconStyle::Emoji,
+            IconMode::NerdFont => IconStyle::NerdFont,
+            IconMode::Ascii => IconStyle::Ascii,
+            IconMode::Auto if self.nerd_font => IconStyle::NerdFont,
+            IconMode::Auto if self.emoji => IconStyle::Emoji,
+            IconMode::Auto => IconStyle::Ascii,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn probe(vars: &[(&str, &str)]) -> TerminalCapabilities {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        TerminalCapabilities::probe_with(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_probe_detects_emoji_terminals() {
+        let iterm = probe(&[("LANG", "en_US.UTF-8"), ("TERM_PROGRAM", "iTerm.app")]);
+        assert!(iterm.unicode && iterm.emoji && !iterm.nerd_font);
+        assert_eq!(iterm.icon_style(IconMode::Auto), IconStyle::Emoji);
+
+        let console = probe(&[("LANG", "en_US.UTF-8"), ("TERM", "linux"), ("VTE_VERSION", "7200")]);
+        assert!(console.unicode && !console.emoji);
+        assert_eq!(console.icon_style(IconMode::Auto), IconStyle::Ascii);
+
+        let latin1 = probe(&[("LANG", "de_DE.ISO-8859-1"), ("TERM", "xterm-kitty")]);
+        assert!(!latin1.unicode && !latin1.emoji);
+    }
+
+    #[test]
+    fn test_config_overrides_probe() {
+        let nerd = probe(&[("LC_ALL", "C.UTF-8"), ("GOOFY_NERD_FONT", "1")]);
+        assert_eq!(nerd.icon_style(IconMode::Auto), IconStyle::NerdFont);
+        assert_eq!(nerd.icon_style(IconMode::Ascii), IconStyle::Ascii);
+        assert_eq!(probe(&[]).icon_style(IconMode::Emoji), IconStyle::Emoji);
+    }
+
+    #[test]
+    fn test_probe_detects_image_protocols() {
+        assert_eq!(probe(&[("TERM", "xterm-kitty")]).graphics, GraphicsSupport::Kitty);
+        assert_eq!(probe(&[("TERM_PROGRAM", "iTerm.app")]).graphics, GraphicsSupport::ITerm2);
+        assert_eq!(probe(&[("TERM", "foot")]).graphics, GraphicsSupport::Sixel);
+        assert_eq!(probe(&[("TERM", "xterm-256color")]).graphics, GraphicsSupport::None);
+
+        // Multiplexers swallow the sequences unless told otherwise
+        let tmux = [("TERM", "tmux-256color"), ("KITTY_WINDOW_ID", "1")];
+        assert_eq!(probe(&tmux).graphics, GraphicsSupport::None);
+        assert_eq!(probe(&[("TMUX", "/tmp/tmux"), ("GOOFY_GRAPHICS", "sixel")]).graphics, GraphicsSupport::Sixel);
+    }
+}
@@ -0,0 +1,67 @@
+//! System clipboard access for the TUI
+//!
+//! Locally the clipboard is reached through arboard. Over SSH that would be the
+//! remote machine's clipboard, so copies are sent to the user's terminal with
+//! an OSC 52 escape sequence instead, wrapped for tmux when needed. Terminals
+//! rarely allow reading the clipboard that way, so pasting over SSH relies on
+//! the terminal's own paste, which arrives as a bracketed paste.
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::io::Write;
+
+use crate::llm::tools::screenshot::is_ssh_session;
+
+/// Copy text to the clipboard
+pub fn copy(text: &str) -> Result<()> {
+    if is_ssh_session() {
+        return copy_osc52(text);
+    }
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            // Headless sessions have no clipboard server, but the terminal may take OSC 52
+            tracing::debug!("Clipboard unavailable ({}), copying through the terminal", e);
+            copy_osc52(text)
+        }
+    }
+}
+
+/// Text on the clipboard
+pub fn paste() -> Result<String> {
+    if is_ssh_session() {
+        anyhow::bail!("The clipboard can't be read over SSH; use your terminal's paste");
+    }
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("Failed to read the clipboard")
+}
+
+fn copy_osc52(text: &str) -> Result<()> {
+    let sequence = osc52_sequence(text, std::env::var_os("TMUX").is_some());
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// OSC 52 sequence setting the clipboard, passed through tmux if asked
+fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", BASE64.encode(text));
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hi\n", false), "\x1b]52;c;aGkK\x07");
+        assert_eq!(osc52_sequence("hi\n", true), "\x1bPtmux;\x1b\x1b]52;c;aGkK\x07\x1b\\");
+    }
+}
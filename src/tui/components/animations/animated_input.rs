@@ -8,6 +8,7 @@ use super::pulse::{PulseAnimation, PulseConfig, PulseStyle};
 use super::fade::{FadeAnimation, FadeConfig, FadeDirection};
 use super::interpolation::RgbColor;
 use crate::tui::themes::Theme;
+use crate::utils::width::{display_width, truncate_with};
 use anyhow::Result;
 use ratatui::{
     layout::Rect,
@@ -15,6 +16,7 @@ use ratatui::{
     text::{Line, Span},
 };
 use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Input field animation styles
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -626,8 +628,9 @@ impl Animation for AnimatedInput {
 
         // Content
         let available_width = self.area.width.saturating_sub(4) as usize; // Account for borders and padding
-        let visible_text = if display_text.len() > available_width {
-            display_text.chars().skip(self.scroll_offset).take(available_width).collect()
+        let visible_text = if display_width(&display_text) > available_width {
+            let scrolled: String = display_text.graphemes(true).skip(self.scroll_offset).collect();
+            truncate_with(&scrolled, available_width, "")
         } else {
             display_text
         };
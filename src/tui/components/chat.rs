@@ -12,11 +12,26 @@ pub mod sidebar;
 pub mod formatting;
 
 
-use super::{Component, ComponentState};
+use super::{
+    dialogs::{ConfirmDialog, Dialog},
+    files::diff_viewer::DiffAction,
+    Component, ComponentState,
+};
 use crate::{
-    llm::types::{ProviderEvent, MessageRole},
-    session::{Session, SessionManager},
+    llm::{
+        changes::FILE_CHANGE_KEY,
+        citations::Citation,
+        tools::{
+            screenshot::{is_ssh_session, take_screenshot, CaptureTarget},
+            ToolProgress,
+        },
+        types::{ContentBlock, ProviderEvent, MessageRole},
+    },
+    session::{MessageHit, Session, SessionManager},
+    tts::{SentenceChunker, Speaker},
     tui::{
+        profiler,
+        responsive::LayoutTier,
         themes::{Theme, ThemeManager},
         Frame,
     },
@@ -30,11 +45,11 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
 };
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
     time::Instant,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
 // Re-export the new types for backward compatibility
 pub use message_types::{
@@ -42,14 +57,30 @@ pub use message_types::{
     ToolResult, ToolArtifact,
 };
 pub use message_renderer::{MessageRenderer, RenderedMessage};
+pub use super::postprocess::Postprocessor;
+pub use super::search::{self, MessageSearch, SearchAction, SearchScope};
+pub use super::fences::{self, FenceChoice};
 pub use editor::{ChatEditor, EditorMode, CompletionItem, CompletionKind, CursorDirection};
 pub use streaming::{
     StreamingManager, StreamingUpdate, StreamingSubscription, StreamingStats, TypingIndicator,
 };
 pub use header::{ChatHeader, HeaderConfig};
 pub use sidebar::{ChatSidebar, SidebarMode, SidebarConfig, SidebarAction};
+pub use super::scroll::StickyScroll;
+pub use super::data_table::{DataTable, TableAction, TableData, TABLE_METADATA_KEY};
 pub use formatting::{MessageFormatter, FormatOptions, FormattedText};
 
+/// Draft command that attaches a screenshot, optionally of a named window
+const SCREENSHOT_COMMAND: &str = "/screenshot";
+
+/// Draft held back while the user decides whether to fence its code
+struct PendingSend {
+    dialog: ConfirmDialog<FenceChoice>,
+    response: oneshot::Receiver<FenceChoice>,
+    unfenced: fences::Unfenced,
+    attachments: Vec<MessageAttachment>,
+}
+
 /// Enhanced chat interface component
 pub struct EnhancedChatInterface {
     state: ComponentState,
@@ -63,6 +94,7 @@ pub struct EnhancedChatInterface {
     // Message management
     messages: VecDeque<ChatMessage>,
     max_messages: usize,
+    scroll: StickyScroll,
     
     // Streaming support
     streaming_manager: Arc<Mutex<StreamingManager>>,
@@ -70,11 +102,16 @@ pub struct EnhancedChatInterface {
     
     // Session management
     current_session: Option<Session>,
-    // TODO: Re-enable when SessionManager is Send+Sync
-    // session_manager: Option<Arc<Mutex<SessionManager>>>,
+    session_manager: Option<Arc<SessionManager>>,
+    
+    // Message search (Ctrl+F), and the message to scroll to once laid out
+    search: Option<MessageSearch>,
+    scroll_target: Option<String>,
     
     // Layout configuration
     layout_config: ChatLayoutConfig,
+    /// How much of the layout fits, from the last size
+    tier: LayoutTier,
     
     // Event handling
     event_sender: Option<mpsc::UnboundedSender<ChatEvent>>,
@@ -89,6 +126,18 @@ pub struct EnhancedChatInterface {
     
     // Focus management
     focused_component: FocusedComponent,
+    
+    // Read-aloud
+    speaker: Option<Speaker>,
+    read_aloud_by_default: bool,
+    read_aloud_overrides: HashMap<String, bool>,
+    sentence_chunker: SentenceChunker,
+    
+    // `/screenshot` may capture while running over SSH
+    screenshots_over_ssh: bool,
+    
+    // Draft waiting on the offer to fence its code
+    pending_send: Option<PendingSend>,
 }
 
 /// Chat layout configuration
@@ -153,6 +202,7 @@ pub enum ChatEvent {
     
     // Tool events
     ToolCallStarted { message_id: String, tool_name: String },
+    ToolCallProgress(ToolProgress),
     ToolCallCompleted { message_id: String, result: String },
     ToolCallFailed { message_id: String, error: String },
 }
@@ -179,17 +229,27 @@ impl EnhancedChatInterface {
             sidebar: ChatSidebar::new(),
             messages: VecDeque::new(),
             max_messages: 1000,
+            scroll: StickyScroll::new(),
             streaming_manager,
             streaming_subscription: None,
             current_session: None,
-            // session_manager: None,
+            session_manager: None,
+            search: None,
+            scroll_target: None,
             layout_config: ChatLayoutConfig::default(),
+            tier: LayoutTier::Full,
             event_sender: Some(event_sender),
             event_receiver: Some(event_receiver),
             last_render: Instant::now(),
             render_cache: RenderCache::default(),
             display_options: MessageDisplayOptions::default(),
             focused_component: FocusedComponent::Editor,
+            speaker: None,
+            read_aloud_by_default: false,
+            read_aloud_overrides: HashMap::new(),
+            sentence_chunker: SentenceChunker::new(),
+            screenshots_over_ssh: false,
+            pending_send: None,
         }
     }
 
@@ -201,14 +261,37 @@ impl EnhancedChatInterface {
         interface
     }
 
+    /// Read assistant responses aloud with the given speaker
+    pub fn with_speaker(mut self, speaker: Speaker, enabled_by_default: bool) -> Self {
+        self.speaker = Some(speaker);
+        self.read_aloud_by_default = enabled_by_default;
+        self
+    }
+
+    /// Rewrite finished assistant messages before they are shown
+    pub fn with_postprocessor(mut self, postprocessor: Postprocessor) -> Self {
+        self.message_renderer.set_postprocessor(postprocessor);
+        self
+    }
+
+    /// Allow `/screenshot` while running over SSH
+    pub fn allow_screenshots_over_ssh(mut self, allow: bool) -> Self {
+        self.screenshots_over_ssh = allow;
+        self
+    }
+
     /// Set session manager
-    // TODO: Re-enable when SessionManager is Send+Sync
-    // pub fn set_session_manager(&mut self, session_manager: Arc<Mutex<SessionManager>>) {
-    //     self.session_manager = Some(session_manager);
-    // }
+    pub fn set_session_manager(&mut self, session_manager: Arc<SessionManager>) {
+        self.session_manager = Some(session_manager);
+    }
 
     /// Set current session
     pub async fn set_session(&mut self, session: Session) -> Result<()> {
+        // Don't keep reading the previous session's answer
+        if let Some(speaker) = &self.speaker {
+            speaker.stop();
+        }
+        self.sentence_chunker = SentenceChunker::new();
         self.current_session = Some(session.clone());
         self.header.set_session(Some(session.clone()));
         
@@ -231,6 +314,20 @@ impl EnhancedChatInterface {
 
     /// Add a message to the interface
     pub async fn add_message(&mut self, message: ChatMessage) -> Result<()> {
+        // The citations panel follows the latest answer
+        if message.role == MessageRole::Assistant {
+            self.sidebar.set_citations(Citation::from_message_metadata(&message.metadata));
+            // Streamed messages are read as their deltas arrive
+            if !message.is_streaming() {
+                self.read_aloud(&message.get_text_content(), true);
+            }
+        }
+        
+        // Tool output is shown under the call that produced it
+        if message.role == MessageRole::Tool {
+            self.attach_tool_results(&message);
+        }
+        
         self.messages.push_back(message.clone());
         
         // Maintain maximum message limit
@@ -255,6 +352,13 @@ impl EnhancedChatInterface {
             return Ok(());
         }
 
+        // `/screenshot [window]` attaches a capture to the draft instead of sending
+        if let Some(window) = content.trim().strip_prefix(SCREENSHOT_COMMAND) {
+            if window.is_empty() || window.starts_with(' ') {
+                return self.attach_screenshot(window.trim()).await;
+            }
+        }
+
         // Create user message
         let mut message = ChatMessage::new_user_text(content.clone());
         for attachment in &attachments {
@@ -278,9 +382,105 @@ impl EnhancedChatInterface {
         Ok(())
     }
 
+    /// Send the draft, first offering to fence code pasted without one
+    pub async fn submit_draft(&mut self, content: String, attachments: Vec<MessageAttachment>) -> Result<()> {
+        let filenames: Vec<&str> = attachments.iter().map(|attachment| attachment.filename.as_str()).collect();
+        let Some(unfenced) = fences::find_unfenced(&content, &filenames) else {
+            return self.send_message(content, attachments).await;
+        };
+        let (dialog, response) = unfenced.offer();
+        self.pending_send = Some(PendingSend { dialog, response, unfenced, attachments });
+        Ok(())
+    }
+
+    /// Handle a key while the offer to fence code is open
+    async fn handle_pending_send_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(pending) = &mut self.pending_send else {
+            return Ok(());
+        };
+        pending.dialog.handle_key(event);
+        let Ok(choice) = pending.response.try_recv() else {
+            return Ok(());
+        };
+        let Some(pending) = self.pending_send.take() else {
+            return Ok(());
+        };
+
+        let content = self.editor.get_content().to_string();
+        match choice {
+            FenceChoice::Wrap => {
+                let wrapped = fences::wrap(&content, &pending.unfenced);
+                self.send_message(wrapped, pending.attachments).await
+            }
+            FenceChoice::SendAsIs => self.send_message(content, pending.attachments).await,
+            FenceChoice::KeepEditing => Ok(()),
+        }
+    }
+
+    /// Render the offer to fence code over the middle of `area`
+    fn render_pending_send(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(pending) = &mut self.pending_send else {
+            return;
+        };
+        let width = area.width.min(64);
+        let height = area.height.min(9);
+        let dialog_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, dialog_area);
+        let block = Block::default()
+            .title(pending.dialog.config().title.clone().unwrap_or_default())
+            .borders(Borders::ALL)
+            .border_style(theme.styles.dialog_border);
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+        pending.dialog.render_content(frame, inner, theme);
+    }
+
+    /// Capture the screen, or the window matching `window`, into the draft
+    async fn attach_screenshot(&mut self, window: &str) -> Result<()> {
+        self.editor.clear();
+        if is_ssh_session() && !self.screenshots_over_ssh {
+            return self
+                .add_message(ChatMessage::new_system_text("Screenshots are disabled over SSH.".to_string()))
+                .await;
+        }
+
+        let target = if window.is_empty() {
+            CaptureTarget::Screen { monitor: None }
+        } else {
+            CaptureTarget::Window { title: window.to_string() }
+        };
+        let workspace = std::env::current_dir()?;
+        match take_screenshot(target, &workspace).await {
+            Ok(screenshot) => {
+                let filename = screenshot
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "screenshot.png".to_string());
+                let mut attachment = MessageAttachment::new(filename, "image/png".to_string(), screenshot.attachment);
+                attachment.url = Some(screenshot.path.to_string_lossy().into_owned());
+                self.editor.add_attachment(attachment)
+            }
+            Err(e) => {
+                self.add_message(ChatMessage::new_system_text(format!("Screenshot failed: {:#}", e)))
+                    .await
+            }
+        }
+    }
+
     /// Start streaming for a message
     pub async fn start_streaming(&mut self, message_id: String, role: crate::llm::types::MessageRole) -> Result<()> {
         if let Ok(manager) = self.streaming_manager.lock() {
+            // The manager's processing task is started with the first stream
+            if self.streaming_subscription.is_none() {
+                manager.start().await?;
+                self.streaming_subscription = Some(manager.subscribe());
+            }
             manager.start_stream(message_id.clone(), role).await?;
         }
         
@@ -299,6 +499,270 @@ impl EnhancedChatInterface {
         Ok(())
     }
 
+    /// Apply the updates broadcast by the streaming manager
+    ///
+    /// Content deltas are appended to the message as they arrive, so the
+    /// response is rendered incrementally instead of once it is complete.
+    fn drain_streaming_updates(&mut self) {
+        let mut updates = Vec::new();
+        if let Some(subscription) = self.streaming_subscription.as_mut() {
+            loop {
+                match subscription.try_recv() {
+                    Ok(Some(update)) => updates.push(update),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("Dropped streaming updates: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for update in updates {
+            match update {
+                StreamingUpdate::StreamStarted { message_id, role, .. } => {
+                    if !self.messages.iter().any(|m| m.id == message_id) {
+                        let mut message = ChatMessage::new(role, Vec::new());
+                        message.id = message_id;
+                        message.streaming_state = StreamingState::Streaming;
+                        self.messages.push_back(message);
+                        self.render_cache.cache_valid = false;
+                    }
+                }
+                StreamingUpdate::ContentDelta { message_id, delta, .. } => {
+                    self.append_to_message(&message_id, &delta);
+                }
+                StreamingUpdate::ThinkingDelta { message_id, accumulated_thinking, .. } => {
+                    if let Some(message) = self.messages.iter_mut().rev().find(|m| m.id == message_id) {
+                        message.set_thinking_content(accumulated_thinking);
+                        self.render_cache.cache_valid = false;
+                    }
+                }
+                StreamingUpdate::StreamCompleted { message_id, final_message, finish_reason } => {
+                    if let Some(message) = self.messages.iter_mut().rev().find(|m| m.id == message_id) {
+                        if !final_message.content.is_empty() {
+                            message.content = final_message.content;
+                        }
+                        message.tool_calls = final_message.tool_calls;
+                        message.set_finish_reason(finish_reason);
+                        message.streaming_state = StreamingState::Complete;
+                        self.render_cache.cache_valid = false;
+                    }
+                    self.read_aloud("", true);
+                }
+                StreamingUpdate::ToolUseStarted { message_id, tool_call_id, tool_name } => {
+                    if let Some(message) = self.messages.iter_mut().rev().find(|m| m.id == message_id) {
+                        if !message.tool_calls.iter().any(|call| call.id == tool_call_id) {
+                            message.add_tool_call(crate::llm::types::ToolCall {
+                                id: tool_call_id,
+                                name: tool_name,
+                                arguments: serde_json::Value::Null,
+                            });
+                            self.render_cache.cache_valid = false;
+                        }
+                    }
+                }
+                StreamingUpdate::ToolUseDelta { message_id, tool_call_id, arguments } => {
+                    // Preview the arguments of a call that is still being generated
+                    if let Some(call) = self.messages.iter_mut().rev()
+                        .find(|m| m.id == message_id)
+                        .and_then(|m| m.tool_calls.iter_mut().find(|call| call.id == tool_call_id))
+                    {
+                        call.arguments = arguments;
+                        self.render_cache.cache_valid = false;
+                    }
+                }
+                StreamingUpdate::StreamFailed { message_id, error } => {
+                    self.set_streaming_state(&message_id, StreamingState::Failed(error));
+                }
+                StreamingUpdate::StreamCancelled { message_id } => {
+                    self.set_streaming_state(&message_id, StreamingState::Cancelled);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Update the live progress of a running tool call
+    pub fn update_tool_progress(&mut self, progress: ToolProgress) {
+        if let Some(message) = self.messages.iter_mut().rev()
+            .find(|m| m.tool_calls.iter().any(|call| call.id == progress.tool_call_id))
+        {
+            message.update_tool_progress(progress);
+            self.render_cache.cache_valid = false;
+        }
+    }
+
+    /// Append streamed text to a message
+    ///
+    /// Only the content changes here; the scroll position is reconciled with
+    /// the new height when the messages are next rendered.
+    pub fn append_to_message(&mut self, message_id: &str, delta: &str) {
+        if let Some(message) = self.messages.iter_mut().rev().find(|m| m.id == message_id) {
+            match message.content.last_mut() {
+                Some(ContentBlock::Text { text }) => text.push_str(delta),
+                _ => message.content.push(ContentBlock::Text { text: delta.to_string() }),
+            }
+            self.render_cache.cache_valid = false;
+            if message.role == MessageRole::Assistant {
+                self.read_aloud(delta, false);
+            }
+        }
+    }
+
+    /// Record the results in a tool message on the assistant message that called them
+    fn attach_tool_results(&mut self, message: &ChatMessage) {
+        // Tables and file changes are shown in place of the raw content
+        let metadata: serde_json::Map<String, serde_json::Value> = [TABLE_METADATA_KEY, FILE_CHANGE_KEY]
+            .into_iter()
+            .filter_map(|key| Some((key.to_string(), message.metadata.get(key)?.clone())))
+            .collect();
+        for block in &message.content {
+            let ContentBlock::ToolResult { tool_call_id, content } = block else {
+                continue;
+            };
+            let caller = self
+                .messages
+                .iter_mut()
+                .rev()
+                .find(|m| m.tool_calls.iter().any(|call| &call.id == tool_call_id));
+            if let Some(caller) = caller {
+                let mut result = ToolResult::new(tool_call_id.clone(), content.clone());
+                if !metadata.is_empty() {
+                    result = result.with_metadata(serde_json::Value::Object(metadata.clone()));
+                }
+                caller.add_tool_result(result);
+            }
+        }
+    }
+
+    /// Copy an issue drafted from the current session to the clipboard
+    async fn copy_issue_draft(&mut self) {
+        let (Some(manager), Some(session)) = (&self.session_manager, &self.current_session) else {
+            return;
+        };
+        let copied = manager
+            .issue_from_session(&session.id, None)
+            .await
+            .and_then(|draft| crate::tui::clipboard::copy(&draft.to_markdown()));
+        if let Err(e) = copied {
+            tracing::warn!("Failed to copy an issue draft: {}", e);
+        }
+    }
+
+    /// Give keyboard focus to the newest table shown in a tool result
+    fn focus_latest_table(&mut self) {
+        let latest = self
+            .messages
+            .iter()
+            .rev()
+            .flat_map(|message| message.tool_results.iter().rev())
+            .find(|result| self.message_renderer.has_table(&result.tool_call_id))
+            .map(|result| result.tool_call_id.clone());
+        if let Some(id) = latest {
+            self.message_renderer.focus_table(Some(&id));
+            self.render_cache.cache_valid = false;
+        }
+    }
+
+    /// Give keyboard focus to the newest diff shown in a tool result
+    fn focus_latest_diff(&mut self) {
+        let latest = self
+            .messages
+            .iter()
+            .rev()
+            .flat_map(|message| message.tool_results.iter().rev())
+            .find(|result| self.message_renderer.has_diff(&result.tool_call_id))
+            .map(|result| result.tool_call_id.clone());
+        if let Some(id) = latest {
+            self.message_renderer.focus_diff(Some(&id));
+            self.render_cache.cache_valid = false;
+        }
+    }
+
+    /// Whether responses in the current session are read aloud
+    pub fn is_reading_aloud(&self) -> bool {
+        self.speaker.is_some()
+            && self.read_aloud_overrides
+                .get(self.session_key())
+                .copied()
+                .unwrap_or(self.read_aloud_by_default)
+    }
+
+    /// Toggle read-aloud for the current session, returning the new state
+    pub fn toggle_read_aloud(&mut self) -> bool {
+        let enabled = !self.is_reading_aloud();
+        self.read_aloud_overrides.insert(self.session_key().to_string(), enabled);
+        if !enabled {
+            if let Some(speaker) = &self.speaker {
+                speaker.stop();
+            }
+            self.sentence_chunker = SentenceChunker::new();
+        }
+        enabled
+    }
+
+    /// Pause or resume speech, returning whether it is now paused
+    pub fn toggle_speech_pause(&mut self) -> bool {
+        self.speaker.as_ref().is_some_and(|speaker| speaker.toggle_pause())
+    }
+
+    /// Queue the sentences completed by new response text
+    fn read_aloud(&mut self, text: &str, finished: bool) {
+        if !self.is_reading_aloud() {
+            return;
+        }
+        let Some(speaker) = &self.speaker else {
+            return;
+        };
+        for sentence in self.sentence_chunker.push(text) {
+            speaker.say(sentence);
+        }
+        if finished {
+            if let Some(rest) = self.sentence_chunker.finish() {
+                speaker.say(rest);
+            }
+        }
+    }
+
+    fn session_key(&self) -> &str {
+        self.current_session.as_ref().map_or("", |session| session.id.as_str())
+    }
+
+    /// Update the streaming state of a message
+    fn set_streaming_state(&mut self, message_id: &str, state: StreamingState) {
+        if let Some(message) = self.messages.iter_mut().rev().find(|m| m.id == message_id) {
+            message.streaming_state = state;
+            self.render_cache.cache_valid = false;
+        }
+    }
+
+    /// Current draft in the editor
+    pub fn draft(&self) -> &str {
+        self.editor.get_content()
+    }
+
+    /// Replace the draft, e.g. with content saved in an external editor
+    pub fn set_draft(&mut self, content: String) {
+        self.editor.set_content(content);
+    }
+
+    /// Insert a bracketed paste into the editor
+    pub fn paste(&mut self, text: &str) {
+        self.editor.paste_text(text);
+        self.set_focus(FocusedComponent::Editor);
+    }
+
+    /// Check if the editor has text selected
+    pub fn has_selection(&self) -> bool {
+        self.editor.has_selection()
+    }
+
+    /// Scroll state of the message area
+    pub fn scroll(&self) -> &StickyScroll {
+        &self.scroll
+    }
+
     /// Set focused component
     pub fn set_focus(&mut self, component: FocusedComponent) {
         // Remove focus from current component
@@ -328,22 +792,77 @@ impl EnhancedChatInterface {
     /// Toggle sidebar visibility
     pub fn toggle_sidebar(&mut self) {
         self.layout_config.show_sidebar = !self.layout_config.show_sidebar;
-        self.sidebar.set_visible(self.layout_config.show_sidebar);
+        self.sidebar.set_visible(self.sidebar_shown());
     }
 
     /// Toggle header visibility
     pub fn toggle_header(&mut self) {
         self.layout_config.show_header = !self.layout_config.show_header;
-        self.header.set_visible(self.layout_config.show_header);
+        self.header.set_visible(self.header_shown());
     }
 
     /// Set display options
     pub fn set_display_options(&mut self, options: MessageDisplayOptions) {
-        self.display_options = options.clone();
+        self.display_options = options;
+        self.sync_display_options();
+    }
+
+    /// Current layout tier
+    pub fn layout_tier(&self) -> LayoutTier {
+        self.tier
+    }
+
+    /// Adapt the layout to the space available
+    ///
+    /// Below the full tier the sidebar collapses and the header and messages
+    /// turn compact; at the minimal tier the header goes too. The user's own
+    /// layout settings come back once the terminal grows again.
+    fn apply_tier(&mut self, area: Rect) {
+        let tier = LayoutTier::for_area(area);
+        if tier == self.tier {
+            return;
+        }
+        self.tier = tier;
+
+        self.sidebar.set_visible(self.state.is_visible && self.sidebar_shown());
+        self.header.set_visible(self.state.is_visible && self.header_shown());
+        self.header.set_compact_mode(self.layout_config.compact_mode || tier < LayoutTier::Full);
+        self.sync_display_options();
+
+        let focus_hidden = match self.focused_component {
+            FocusedComponent::Sidebar => !self.sidebar_shown(),
+            FocusedComponent::Header => !self.header_shown(),
+            _ => false,
+        };
+        if focus_hidden {
+            self.set_focus(FocusedComponent::Editor);
+        }
+    }
+
+    /// Pass the display options on, compacted when space is short
+    fn sync_display_options(&mut self) {
+        let mut options = self.display_options.clone();
+        options.compact_mode |= self.tier < LayoutTier::Full;
         self.message_renderer.set_display_options(options);
         self.render_cache.cache_valid = false;
     }
 
+    fn sidebar_shown(&self) -> bool {
+        self.layout_config.show_sidebar && self.tier == LayoutTier::Full
+    }
+
+    fn header_shown(&self) -> bool {
+        self.layout_config.show_header && self.tier > LayoutTier::Minimal
+    }
+
+    fn header_height(&self) -> u16 {
+        if self.tier == LayoutTier::Full {
+            self.layout_config.header_height
+        } else {
+            self.header.min_height()
+        }
+    }
+
     /// Get messages in current session
     pub fn get_messages(&self) -> &VecDeque<ChatMessage> {
         &self.messages
@@ -355,14 +874,85 @@ impl EnhancedChatInterface {
     }
 
     /// Load session messages
-    async fn load_session_messages(&mut self, _session: &Session) -> Result<()> {
-        // In a real implementation, this would load messages from the session manager
-        // For now, we'll just clear the current messages
+    async fn load_session_messages(&mut self, session: &Session) -> Result<()> {
         self.messages.clear();
+        if let Some(manager) = &self.session_manager {
+            for message in manager.get_messages(&session.id, Some(self.max_messages as u32)).await? {
+                let mut chat_message = ChatMessage::new(message.role, message.content);
+                // Keep the stored id so search hits can be found on screen
+                chat_message.id = message.id;
+                chat_message.timestamp = message.timestamp;
+                chat_message.metadata = message.metadata;
+                self.messages.push_back(chat_message);
+            }
+        }
         self.render_cache.cache_valid = false;
         Ok(())
     }
 
+    /// Open the search bar, or focus its query if it is open
+    fn open_search(&mut self) {
+        match &mut self.search {
+            Some(search) => {
+                search.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+            }
+            None => self.search = Some(MessageSearch::new()),
+        }
+    }
+
+    /// Handle a key while the search bar is open
+    async fn handle_search_key(&mut self, event: KeyEvent) -> Result<()> {
+        let Some(search) = &mut self.search else {
+            return Ok(());
+        };
+        match search.handle_key(event) {
+            SearchAction::Run => {
+                self.run_search().await;
+                let first = self.search.as_ref().and_then(|search| search.current_hit().cloned());
+                if let Some(hit) = first {
+                    self.show_hit(hit).await?;
+                }
+            }
+            SearchAction::Jump(hit) => self.show_hit(hit).await?,
+            SearchAction::Close => self.search = None,
+            SearchAction::None => {}
+        }
+        Ok(())
+    }
+
+    /// Look the search query up in its scope
+    async fn run_search(&mut self) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        let results = match (&self.session_manager, search.scope(), &self.current_session) {
+            (None, _, _) => Err(anyhow::anyhow!("No session store to search")),
+            (Some(manager), SearchScope::Session, Some(session)) => {
+                manager.search_messages(search.query(), Some(&session.id)).await
+            }
+            (Some(_), SearchScope::Session, None) => Ok(Vec::new()),
+            (Some(manager), SearchScope::AllSessions, _) => manager.search_messages(search.query(), None).await,
+        };
+        search.set_results(results);
+    }
+
+    /// Scroll to the message of a search hit, opening its session if needed
+    async fn show_hit(&mut self, hit: MessageHit) -> Result<()> {
+        let in_current = self.current_session.as_ref().is_some_and(|session| session.id == hit.session_id);
+        if !in_current {
+            let Some(manager) = self.session_manager.clone() else {
+                return Ok(());
+            };
+            match manager.get_session(&hit.session_id).await? {
+                Some(session) => self.set_session(session).await?,
+                None => return Ok(()),
+            }
+        }
+        self.scroll_target = Some(hit.message_id);
+        self.focused_component = FocusedComponent::Messages;
+        Ok(())
+    }
+
     /// Get all sessions
     async fn get_all_sessions(&self) -> Result<Option<Vec<Session>>> {
         // In a real implementation, this would fetch from the session manager
@@ -395,6 +985,25 @@ impl EnhancedChatInterface {
             ChatEvent::FocusChanged(component) => {
                 self.set_focus(component);
             }
+            ChatEvent::ToolCallProgress(progress) => {
+                self.update_tool_progress(progress);
+            }
+            ChatEvent::StreamingStarted { message_id } if !self.messages.iter().any(|m| m.id == message_id) => {
+                let mut message = ChatMessage::new_assistant_text(String::new());
+                message.id = message_id;
+                message.streaming_state = StreamingState::Streaming;
+                self.add_message(message).await?;
+            }
+            ChatEvent::StreamingUpdate { message_id, delta } => {
+                self.append_to_message(&message_id, &delta);
+            }
+            ChatEvent::StreamingCompleted { message_id } => {
+                self.set_streaming_state(&message_id, StreamingState::Complete);
+                self.read_aloud("", true);
+            }
+            ChatEvent::StreamingFailed { message_id, error } => {
+                self.set_streaming_state(&message_id, StreamingState::Failed(error));
+            }
             ChatEvent::ThemeChanged(_theme_name) => {
                 // Theme changes are handled through the theme manager in each component
                 // No direct action needed here as components get theme via render() calls
@@ -411,15 +1020,15 @@ impl EnhancedChatInterface {
         let mut constraints = Vec::new();
         
         // Header
-        if self.layout_config.show_header {
-            constraints.push(Constraint::Length(self.layout_config.header_height));
+        if self.header_shown() {
+            constraints.push(Constraint::Length(self.header_height()));
         }
         
         // Main content area
         constraints.push(Constraint::Min(1));
         
         // Editor area
-        let editor_height = if self.layout_config.compact_mode {
+        let editor_height = if self.layout_config.compact_mode || self.tier < LayoutTier::Full {
             self.layout_config.min_editor_height
         } else {
             self.layout_config.max_editor_height.min(area.height / 4)
@@ -431,7 +1040,7 @@ impl EnhancedChatInterface {
 
     /// Calculate main layout (with sidebar)
     fn calculate_main_layout(&self, area: Rect) -> (Option<Rect>, Rect) {
-        if self.layout_config.show_sidebar {
+        if self.sidebar_shown() {
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
@@ -456,12 +1065,32 @@ impl EnhancedChatInterface {
         let inner_area = block.inner(area);
         frame.render_widget(block, area);
 
-        // Render messages using the message renderer
+        // Lay messages out oldest first and reconcile the scroll position
+        // with whatever was appended since the last frame
+        let heights: Vec<usize> = self.messages
+            .iter()
+            .map(|message| self.message_renderer.calculate_message_height(message, inner_area.width) as usize)
+            .collect();
+        self.scroll.update(heights.iter().sum(), inner_area.height as usize);
+        if let Some(target) = self.scroll_target.take() {
+            if let Some(index) = self.messages.iter().position(|message| message.id == target) {
+                self.scroll.scroll_to_line(heights[..index].iter().sum());
+            }
+        }
+        let top_line = self.scroll.top_line();
+
+        let bottom = inner_area.y + inner_area.height;
+        let mut line = 0;
         let mut current_y = inner_area.y;
-        let available_height = inner_area.height;
         
-        for message in self.messages.iter().rev() {
-            if current_y >= inner_area.y + available_height {
+        for (message, height) in self.messages.iter().zip(&heights) {
+            let start = line;
+            line += height;
+            // Messages are drawn whole, so one cut off at the top is skipped
+            if start < top_line {
+                continue;
+            }
+            if current_y >= bottom {
                 break;
             }
             
@@ -469,12 +1098,43 @@ impl EnhancedChatInterface {
                 x: inner_area.x,
                 y: current_y,
                 width: inner_area.width,
-                height: available_height - (current_y - inner_area.y),
+                height: bottom - current_y,
             };
             
             let rendered = self.message_renderer.render_message(message, frame, message_area);
             current_y += rendered.total_height;
         }
+        
+        if let Some(search) = &self.search {
+            let style = theme.styles.warning.add_modifier(Modifier::REVERSED);
+            search::highlight_matches(frame.buffer_mut(), inner_area, &search.terms(), style);
+        }
+        
+        if self.scroll.unread_lines() > 0 {
+            self.render_unread_pill(frame, inner_area, theme);
+        }
+    }
+
+    /// Render the "N new lines" indicator at the bottom of the message area
+    fn render_unread_pill(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let unread = self.scroll.unread_lines();
+        let label = format!(" {} new line{} ↓ ", unread, if unread == 1 { "" } else { "s" });
+        let width = (label.chars().count() as u16).min(area.width);
+        if area.height == 0 || width == 0 {
+            return;
+        }
+        
+        let pill_area = Rect {
+            x: area.x + area.width - width,
+            y: area.y + area.height - 1,
+            width,
+            height: 1,
+        };
+        frame.render_widget(Clear, pill_area);
+        frame.render_widget(
+            Paragraph::new(label).style(theme.styles.selected_base.add_modifier(Modifier::BOLD)),
+            pill_area,
+        );
     }
 }
 
@@ -484,18 +1144,28 @@ impl Component for EnhancedChatInterface {
         // Process pending events first
         self.process_events().await?;
 
+        // The search bar takes keys while it is open
+        if self.search.is_some() {
+            return self.handle_search_key(event).await;
+        }
+        
+        // So does the offer to fence pasted code
+        if self.pending_send.is_some() {
+            return self.handle_pending_send_key(event).await;
+        }
+
         // Handle global shortcuts
         match (event.code, event.modifiers) {
             // Tab between components
             (KeyCode::Tab, KeyModifiers::NONE) => {
                 let next_component = match self.focused_component {
                     FocusedComponent::Editor => FocusedComponent::Messages,
-                    FocusedComponent::Messages => if self.layout_config.show_sidebar {
+                    FocusedComponent::Messages => if self.sidebar_shown() {
                         FocusedComponent::Sidebar
                     } else {
                         FocusedComponent::Editor
                     },
-                    FocusedComponent::Sidebar => if self.layout_config.show_header {
+                    FocusedComponent::Sidebar => if self.header_shown() {
                         FocusedComponent::Header
                     } else {
                         FocusedComponent::Editor
@@ -506,6 +1176,30 @@ impl Component for EnhancedChatInterface {
                 return Ok(());
             }
             
+            // Jump back to the newest message
+            (KeyCode::End, KeyModifiers::CONTROL) => {
+                self.scroll.scroll_to_bottom();
+                return Ok(());
+            }
+            
+            // Search messages
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+                self.open_search();
+                return Ok(());
+            }
+            
+            // Read responses aloud in this session
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.toggle_read_aloud();
+                return Ok(());
+            }
+            
+            // Play/pause speech
+            (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                self.toggle_speech_pause();
+                return Ok(());
+            }
+            
             // Toggle sidebar
             (KeyCode::F(9), KeyModifiers::NONE) => {
                 self.toggle_sidebar();
@@ -523,7 +1217,7 @@ impl Component for EnhancedChatInterface {
                 if !self.editor.get_content().trim().is_empty() {
                     let content = self.editor.get_content().to_string();
                     let attachments = self.editor.get_attachments().to_vec();
-                    self.send_message(content, attachments).await?;
+                    self.submit_draft(content, attachments).await?;
                 }
                 return Ok(());
             }
@@ -543,8 +1237,40 @@ impl Component for EnhancedChatInterface {
                 self.header.handle_key_event(event).await?;
             }
             FocusedComponent::Messages => {
-                // Handle message area navigation
-                // TODO: Implement message selection and scrolling
+                if let Some(table) = self.message_renderer.active_table_mut() {
+                    match table.handle_key(event) {
+                        TableAction::Copy(text) => {
+                            if let Err(e) = crate::tui::clipboard::copy(&text) {
+                                tracing::warn!("Failed to copy table contents: {}", e);
+                            }
+                        }
+                        TableAction::Exit => {
+                            self.message_renderer.focus_table(None);
+                        }
+                        TableAction::None => {}
+                    }
+                    self.render_cache.cache_valid = false;
+                    return Ok(());
+                }
+                if let Some(diff) = self.message_renderer.active_diff_mut() {
+                    if diff.handle_key(event) == DiffAction::Exit {
+                        self.message_renderer.focus_diff(None);
+                    }
+                    self.render_cache.cache_valid = false;
+                    return Ok(());
+                }
+                match event.code {
+                    KeyCode::Char('t') => self.focus_latest_table(),
+                    KeyCode::Char('d') => self.focus_latest_diff(),
+                    KeyCode::Char('i') => self.copy_issue_draft().await,
+                    KeyCode::Up | KeyCode::Char('k') => self.scroll.scroll_up(1),
+                    KeyCode::Down | KeyCode::Char('j') => self.scroll.scroll_down(1),
+                    KeyCode::PageUp => self.scroll.page_up(),
+                    KeyCode::PageDown => self.scroll.page_down(),
+                    KeyCode::Home | KeyCode::Char('g') => self.scroll.scroll_to_top(),
+                    KeyCode::End | KeyCode::Char('G') => self.scroll.scroll_to_bottom(),
+                    _ => {}
+                }
             }
         }
 
@@ -562,6 +1288,7 @@ impl Component for EnhancedChatInterface {
     async fn tick(&mut self) -> Result<()> {
         // Process events
         self.process_events().await?;
+        self.drain_streaming_updates();
         
         // Tick all components
         self.editor.tick().await?;
@@ -572,11 +1299,14 @@ impl Component for EnhancedChatInterface {
     }
 
     fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.apply_tier(area);
+        
         // Calculate main layout (sidebar + content)
         let (sidebar_area, content_area) = self.calculate_main_layout(area);
         
         // Render sidebar if visible
         if let Some(sidebar_area) = sidebar_area {
+            let _span = profiler::span("sidebar");
             self.sidebar.render(frame, sidebar_area, theme);
         }
         
@@ -590,17 +1320,32 @@ impl Component for EnhancedChatInterface {
         let mut chunk_index = 0;
         
         // Render header if visible
-        if self.layout_config.show_header {
+        if self.header_shown() {
+            let _span = profiler::span("header");
             self.header.render(frame, chunks[chunk_index], theme);
             chunk_index += 1;
         }
         
         // Render messages area
-        self.render_messages(frame, chunks[chunk_index], theme);
+        {
+            let _span = profiler::span("messages");
+            self.render_messages(frame, chunks[chunk_index], theme);
+        }
         chunk_index += 1;
         
-        // Render editor
-        self.editor.render(frame, chunks[chunk_index], theme);
+        // Render editor, with the search bar over its first line while searching
+        {
+            let _span = profiler::span("editor");
+            let editor_area = chunks[chunk_index];
+            self.editor.render(frame, editor_area, theme);
+            if let Some(search) = &self.search {
+                let bar = Rect { height: editor_area.height.min(1), ..editor_area };
+                frame.render_widget(Clear, bar);
+                search.render(frame, bar, theme);
+            }
+        }
+        
+        self.render_pending_send(frame, area, theme);
         
         // Update render timestamp
         self.last_render = Instant::now();
@@ -612,6 +1357,7 @@ impl Component for EnhancedChatInterface {
 
     fn set_size(&mut self, size: Rect) {
         self.state.size = size;
+        self.apply_tier(size);
         
         // Update component sizes based on layout
         let (sidebar_area, content_area) = self.calculate_main_layout(size);
@@ -628,7 +1374,7 @@ impl Component for EnhancedChatInterface {
         
         let mut chunk_index = 0;
         
-        if self.layout_config.show_header {
+        if self.header_shown() {
             self.header.set_size(chunks[chunk_index]);
             chunk_index += 1;
         }
@@ -668,8 +1414,8 @@ impl Component for EnhancedChatInterface {
     fn set_visible(&mut self, visible: bool) {
         self.state.is_visible = visible;
         self.editor.set_visible(visible);
-        self.sidebar.set_visible(visible && self.layout_config.show_sidebar);
-        self.header.set_visible(visible && self.layout_config.show_header);
+        self.sidebar.set_visible(visible && self.sidebar_shown());
+        self.header.set_visible(visible && self.header_shown());
     }
 }
 
@@ -745,6 +1491,25 @@ mod tests {
         assert_eq!(interface.messages.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_unfenced_code_is_offered_a_fence() {
+        let mut interface = EnhancedChatInterface::new();
+        let draft = "Why does this panic?\nlet v: Vec<i32> = Vec::new();\nlet first = v[0];\nprintln!(\"{}\", first);";
+        interface.set_draft(draft.to_string());
+        interface.submit_draft(draft.to_string(), Vec::new()).await.unwrap();
+        assert!(interface.pending_send.is_some());
+        assert!(interface.messages.is_empty());
+
+        interface.handle_key_event(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE)).await.unwrap();
+        assert!(interface.pending_send.is_none());
+        let sent = interface.messages.back().unwrap();
+        assert!(matches!(&sent.content[0], ContentBlock::Text { text } if text.contains("```rust\nlet v: Vec<i32>")));
+
+        interface.submit_draft("Thanks, that fixed it.".to_string(), Vec::new()).await.unwrap();
+        assert!(interface.pending_send.is_none());
+        assert_eq!(interface.messages.len(), 2);
+    }
+
     #[test]
     fn test_focus_management() {
         let mut interface = EnhancedChatInterface::new();
@@ -770,4 +1535,26 @@ mod tests {
         assert_eq!(interface.layout_config.header_height, 2);
         assert!(!interface.layout_config.show_sidebar);
     }
+
+    #[test]
+    fn test_layout_degrades_in_small_terminals() {
+        let mut interface = EnhancedChatInterface::new();
+        interface.set_focus(FocusedComponent::Sidebar);
+
+        interface.set_size(Rect::new(0, 0, 80, 20));
+        assert_eq!(interface.layout_tier(), LayoutTier::Compact);
+        assert!(!interface.sidebar_shown());
+        assert!(interface.header_shown());
+        assert_eq!(interface.header_height(), 1);
+        assert_eq!(interface.focused_component, FocusedComponent::Editor);
+
+        interface.set_size(Rect::new(0, 0, 40, 10));
+        assert_eq!(interface.layout_tier(), LayoutTier::Minimal);
+        assert!(!interface.header_shown());
+        assert_eq!(interface.editor.size().height, interface.layout_config.min_editor_height);
+
+        interface.set_size(Rect::new(0, 0, 120, 40));
+        assert!(interface.sidebar_shown());
+        assert!(interface.header_shown());
+    }
 }
\ No newline at end of file
@@ -383,7 +383,7 @@ impl MessageRenderer {
                 }
                 ContentBlock::ToolUse { name, input, .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("🔧 ", theme.styles.chat_tool),
+                        Span::styled(format!("{} ", theme.icons.tool), theme.styles.chat_tool),
                         Span::styled(format!("Tool: {}", name), theme.styles.chat_tool),
                     ]));
                     if let Ok(pretty_input) = serde_json::to_string_pretty(input) {
@@ -392,14 +392,14 @@ impl MessageRenderer {
                 }
                 ContentBlock::ToolResult { content, .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("📋 ", theme.styles.success),
+                        Span::styled(format!("{} ", theme.icons.copy), theme.styles.success),
                         Span::styled("Tool Result:", theme.styles.success),
                     ]));
                     lines.push(Line::from(content.clone()));
                 }
                 ContentBlock::Image { .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("🖼️ ", theme.styles.info),
+                        Span::styled(format!("{} ", theme.icons.image), theme.styles.info),
                         Span::styled("[Image]", theme.styles.info),
                     ]));
                 }
@@ -431,7 +431,7 @@ impl MessageRenderer {
                 if line.len() > 3 {
                     let lang = line.trim_start_matches("```");
                     lines.push(Line::from(Span::styled(
-                        format!("📄 {}", lang),
+                        format!("{} {}", theme.icons.file, lang),
                         theme.styles.subtle,
                     )));
                 }
@@ -6,10 +6,16 @@
 
 use super::message_types::{ChatMessage, MessageAttachment};
 use crate::tui::{
-    components::{Component, ComponentState, TextInput},
-    themes::{Theme, ThemeManager},
+    clipboard,
+    components::{
+        image::{ImageConfig, ImageWidget},
+        markdown::{MarkdownConfig, MarkdownWidget},
+        Component, ComponentState, TextInput,
+    },
+    themes::{IconSet, Theme, ThemeManager},
     Frame,
 };
+use crate::utils::width::{display_width, truncate_to_width};
 use anyhow::Result;
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
@@ -20,7 +26,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     path::Path,
     time::{Duration, Instant},
 };
@@ -28,9 +34,16 @@ use std::{
 /// Maximum number of attachments allowed
 const MAX_ATTACHMENTS: usize = 10;
 
+/// Size of the preview shown for attached images, in cells
+const THUMBNAIL_WIDTH: u16 = 16;
+const THUMBNAIL_HEIGHT: u16 = 6;
+
 /// Maximum attachment size (10MB)
 const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
 
+/// Pause in typing after which the markdown preview catches up
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(300);
+
 /// Enhanced chat editor component
 pub struct ChatEditor {
     state: ComponentState,
@@ -77,6 +90,22 @@ pub struct ChatEditor {
     
     // File operations
     last_file_drop: Option<Instant>,
+
+    /// Rendered previews of attached images, by attachment id
+    thumbnails: HashMap<String, Vec<Line<'static>>>,
+
+    /// Rendered markdown of the draft, shown beside it while toggled on
+    preview: Option<DraftPreview>,
+}
+
+/// Markdown preview of the draft
+///
+/// The widget only gets the draft once typing pauses, so a long message is
+/// not rendered again on every key.
+struct DraftPreview {
+    widget: MarkdownWidget,
+    /// Width the cached rendering was made for
+    width: u16,
 }
 
 /// Editor operation modes
@@ -166,6 +195,8 @@ impl ChatEditor {
             last_activity: Instant::now(),
             blink_state: false,
             last_file_drop: None,
+            thumbnails: HashMap::new(),
+            preview: None,
         }
     }
 
@@ -204,9 +235,70 @@ impl ChatEditor {
         }
         self.cursor_position = self.content.len();
         self.update_cursor_from_position();
+        self.clear_selection();
         self.invalidate_cache();
     }
 
+    /// Show or hide the markdown preview, returning whether it is shown
+    pub fn toggle_preview(&mut self) -> bool {
+        self.preview = match self.preview.take() {
+            Some(_) => None,
+            None => {
+                let config = MarkdownConfig {
+                    border: None,
+                    show_line_numbers: false,
+                    ..MarkdownConfig::default()
+                };
+                let mut widget = MarkdownWidget::with_config(config);
+                widget.set_content(self.content.clone());
+                Some(DraftPreview { widget, width: 0 })
+            }
+        };
+        self.preview.is_some()
+    }
+
+    /// Whether the markdown preview is shown
+    pub fn is_previewing(&self) -> bool {
+        self.preview.is_some()
+    }
+
+    /// Give the preview the current draft once typing has paused,
+    /// returning whether it changed
+    fn refresh_preview(&mut self) -> bool {
+        let Some(preview) = self.preview.as_mut() else {
+            return false;
+        };
+        if preview.widget.content() == self.content || self.last_activity.elapsed() < PREVIEW_DEBOUNCE {
+            return false;
+        }
+        preview.widget.set_content(self.content.clone());
+        true
+    }
+
+    /// Render the markdown preview into `area`
+    fn render_preview(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.refresh_preview();
+        let Some(preview) = self.preview.as_mut() else {
+            return;
+        };
+        let block = Block::default()
+            .title("Preview")
+            .borders(Borders::ALL)
+            .border_style(theme.styles.dialog_border);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if preview.width != inner.width {
+            preview.widget.set_theme(theme.clone());
+            preview.width = inner.width;
+        }
+        let text = preview
+            .widget
+            .render(inner)
+            .unwrap_or_else(|_| Text::raw(preview.widget.content().to_string()));
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+    }
+
     /// Clear all content
     pub fn clear(&mut self) {
         self.content.clear();
@@ -230,6 +322,9 @@ impl ChatEditor {
             return Err(anyhow::anyhow!("Attachment too large (max {} MB)", MAX_ATTACHMENT_SIZE / (1024 * 1024)));
         }
         
+        if let Some(thumbnail) = render_thumbnail(&attachment) {
+            self.thumbnails.insert(attachment.id.clone(), thumbnail);
+        }
         self.attachments.push(attachment);
         Ok(())
     }
@@ -237,7 +332,8 @@ impl ChatEditor {
     /// Remove an attachment by index
     pub fn remove_attachment(&mut self, index: usize) -> Result<()> {
         if index < self.attachments.len() {
-            self.attachments.remove(index);
+            let attachment = self.attachments.remove(index);
+            self.thumbnails.remove(&attachment.id);
             Ok(())
         } else {
             Err(anyhow::anyhow!("Invalid attachment index"))
@@ -330,6 +426,23 @@ impl ChatEditor {
 
     /// Move cursor
     pub fn move_cursor(&mut self, direction: CursorDirection) {
+        self.clear_selection();
+        self.step_cursor(direction);
+    }
+
+    /// Move cursor, extending the selection from where the cursor was
+    pub fn select_to(&mut self, direction: CursorDirection) {
+        let cursor = (self.cursor_line, self.cursor_column);
+        let anchor = match (self.selection_start, self.selection_end) {
+            (Some(start), Some(end)) if end == cursor => start,
+            _ => cursor,
+        };
+        self.step_cursor(direction);
+        self.selection_start = Some(anchor);
+        self.selection_end = Some((self.cursor_line, self.cursor_column));
+    }
+
+    fn step_cursor(&mut self, direction: CursorDirection) {
         match direction {
             CursorDirection::Left => self.move_cursor_left(),
             CursorDirection::Right => self.move_cursor_right(),
@@ -343,8 +456,9 @@ impl ChatEditor {
         self.update_position_from_cursor();
     }
 
-    /// Insert text at cursor
+    /// Insert text at cursor, replacing the selection
     pub fn insert_text(&mut self, text: &str) {
+        self.delete_selection();
         self.lines[self.cursor_line].insert_str(self.cursor_column, text);
         self.cursor_column += text.len();
         self.update_content_from_lines();
@@ -404,38 +518,61 @@ impl ChatEditor {
 
     /// Select all text
     pub fn select_all(&mut self) {
+        self.cursor_line = self.lines.len() - 1;
+        self.cursor_column = self.lines[self.cursor_line].len();
+        self.update_position_from_cursor();
         self.selection_start = Some((0, 0));
-        self.selection_end = Some((self.lines.len() - 1, self.lines.last().unwrap().len()));
+        self.selection_end = Some((self.cursor_line, self.cursor_column));
+    }
+
+    /// Clear the selection, leaving the text alone
+    pub fn clear_selection(&mut self) {
+        self.selection_start = None;
+        self.selection_end = None;
+    }
+
+    /// Check if any text is selected
+    pub fn has_selection(&self) -> bool {
+        self.selection_range().is_some()
+    }
+
+    /// Selected text as (line, column) from start to end, whichever way it was selected
+    fn selection_range(&self) -> Option<((usize, usize), (usize, usize))> {
+        let (start, end) = (self.selection_start?, self.selection_end?);
+        match start.cmp(&end) {
+            std::cmp::Ordering::Less => Some((start, end)),
+            std::cmp::Ordering::Greater => Some((end, start)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Columns of a line that are selected
+    fn selected_columns(&self, line_idx: usize) -> Option<(usize, usize)> {
+        let ((start_line, start_col), (end_line, end_col)) = self.selection_range()?;
+        if line_idx < start_line || line_idx > end_line {
+            return None;
+        }
+        let len = self.lines[line_idx].len();
+        let start = if line_idx == start_line { start_col.min(len) } else { 0 };
+        let end = if line_idx == end_line { end_col.min(len) } else { len };
+        Some((start, end))
     }
 
     /// Get selected text
     pub fn get_selected_text(&self) -> Option<String> {
-        if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
-            let (start_line, start_col) = start;
-            let (end_line, end_col) = end;
-            
-            if start_line == end_line {
-                let line = &self.lines[start_line];
-                return Some(line[start_col..end_col].to_string());
-            } else {
-                let mut result = String::new();
-                for line_idx in start_line..=end_line {
-                    let line = &self.lines[line_idx];
-                    if line_idx == start_line {
-                        result.push_str(&line[start_col..]);
-                    } else if line_idx == end_line {
-                        result.push_str(&line[..end_col]);
-                    } else {
-                        result.push_str(line);
-                    }
-                    if line_idx < end_line {
-                        result.push('\n');
-                    }
-                }
-                return Some(result);
-            }
+        let ((start_line, start_col), (end_line, end_col)) = self.selection_range()?;
+        if start_line == end_line {
+            return Some(self.lines[start_line][start_col..end_col].to_string());
         }
-        None
+
+        let mut result = self.lines[start_line][start_col..].to_string();
+        for line in &self.lines[start_line + 1..end_line] {
+            result.push('\n');
+            result.push_str(line);
+        }
+        result.push('\n');
+        result.push_str(&self.lines[end_line][..end_col]);
+        Some(result)
     }
 
     /// Copy selected text
@@ -445,86 +582,62 @@ impl ChatEditor {
 
     /// Cut selected text
     pub fn cut_selection(&mut self) -> Option<String> {
-        if let Some(text) = self.get_selected_text() {
-            self.delete_selection();
-            Some(text)
-        } else {
-            None
-        }
+        let text = self.get_selected_text()?;
+        self.delete_selection();
+        Some(text)
     }
 
     /// Delete selected text
     pub fn delete_selection(&mut self) {
-        if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
-            let (start_line, start_col) = start;
-            let (end_line, end_col) = end;
-            
-            if start_line == end_line {
-                let line = &mut self.lines[start_line];
-                line.replace_range(start_col..end_col, "");
-                self.cursor_line = start_line;
-                self.cursor_column = start_col;
-            } else {
-                // Remove complete lines in between
-                for _ in (start_line + 1)..end_line {
-                    self.lines.remove(start_line + 1);
-                }
-                
-                // Merge start and end lines
-                let end_part = self.lines[start_line + 1][end_col..].to_string();
-                self.lines[start_line].truncate(start_col);
-                self.lines[start_line].push_str(&end_part);
-                self.lines.remove(start_line + 1);
-                
-                self.cursor_line = start_line;
-                self.cursor_column = start_col;
-            }
-            
-            self.selection_start = None;
-            self.selection_end = None;
-            self.update_content_from_lines();
-            self.invalidate_cache();
-        }
+        let Some(((start_line, start_col), (end_line, end_col))) = self.selection_range() else {
+            self.clear_selection();
+            return;
+        };
+
+        let end_part = self.lines[end_line].split_off(end_col);
+        self.lines.drain(start_line + 1..=end_line);
+        self.lines[start_line].truncate(start_col);
+        self.lines[start_line].push_str(&end_part);
+        self.cursor_line = start_line;
+        self.cursor_column = start_col;
+
+        self.clear_selection();
+        self.update_content_from_lines();
+        self.invalidate_cache();
+        self.last_activity = Instant::now();
     }
 
-    /// Paste text at cursor
+    /// Paste text at cursor, replacing the selection
+    ///
+    /// Line endings are normalized and the lines are spliced in at once, so
+    /// large pasted blocks stay cheap.
     pub fn paste_text(&mut self, text: &str) {
-        if self.selection_start.is_some() {
-            self.delete_selection();
-        }
-        
-        let lines: Vec<&str> = text.lines().collect();
-        if lines.is_empty() {
+        self.delete_selection();
+
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if text.is_empty() {
             return;
         }
-        
-        if lines.len() == 1 {
-            // Single line paste
-            self.insert_text(text);
+
+        let mut pasted = text.split('\n');
+        let first = pasted.next().unwrap_or_default();
+        let inserted: Vec<String> = pasted.map(str::to_string).collect();
+        let line = self.cursor_line;
+        let after = self.lines[line].split_off(self.cursor_column);
+        self.lines[line].push_str(first);
+
+        if inserted.is_empty() {
+            self.cursor_column += first.len();
         } else {
-            // Multi-line paste
-            let current_line = &self.lines[self.cursor_line];
-            let (before, after) = current_line.split_at(self.cursor_column);
-            
-            // Replace current line with first part + first line of paste
-            self.lines[self.cursor_line] = format!("{}{}", before, lines[0]);
-            
-            // Insert middle lines
-            for (i, line) in lines.iter().enumerate().skip(1).take(lines.len() - 2) {
-                self.lines.insert(self.cursor_line + i, line.to_string());
-            }
-            
-            // Insert last line + remaining part of original line
-            if lines.len() > 1 {
-                let last_line = format!("{}{}", lines.last().unwrap(), after);
-                self.lines.insert(self.cursor_line + lines.len() - 1, last_line);
-                self.cursor_line += lines.len() - 1;
-                self.cursor_column = lines.last().unwrap().len();
-            }
-            
-            self.update_content_from_lines();
-            self.invalidate_cache();
+            self.cursor_line = line + inserted.len();
+            self.cursor_column = inserted.last().map_or(0, String::len);
+            self.lines.splice(line + 1..line + 1, inserted);
         }
+        self.lines[self.cursor_line].push_str(&after);
+
+        self.update_content_from_lines();
+        self.invalidate_cache();
+        self.last_activity = Instant::now();
     }
 
     // Helper methods
@@ -660,7 +773,13 @@ impl ChatEditor {
             let line_content = &self.lines[line_idx];
             let mut spans = Vec::new();
             
-            if self.syntax_highlighting && self.mode == EditorMode::Normal {
+            if let Some((start, end)) = self.selected_columns(line_idx) {
+                spans = vec![
+                    Span::styled(line_content[..start].to_string(), theme.styles.text),
+                    Span::styled(line_content[start..end].to_string(), theme.styles.selected_base),
+                    Span::styled(line_content[end..].to_string(), theme.styles.text),
+                ];
+            } else if self.syntax_highlighting && self.mode == EditorMode::Normal {
                 // Simple syntax highlighting for common patterns
                 spans = self.highlight_syntax(line_content);
             } else {
@@ -740,6 +859,16 @@ impl ChatEditor {
         spans
     }
 
+    /// Rows the attachment list needs, taller when there are image previews
+    fn attachments_height(&self) -> u16 {
+        let rows = if self.thumbnails.is_empty() {
+            1
+        } else {
+            THUMBNAIL_HEIGHT.max(self.attachments.len() as u16)
+        };
+        rows + 2
+    }
+
     fn render_attachments(&self, frame: &mut Frame, area: Rect) {
         if self.attachments.is_empty() {
             return;
@@ -750,11 +879,11 @@ impl ChatEditor {
 
         for (i, attachment) in self.attachments.iter().enumerate() {
             let icon = if attachment.is_image() {
-                "🖼️"
+                &theme.icons.image
             } else if attachment.is_text() {
-                "📄"
+                &theme.icons.file
             } else {
-                "📁"
+                &theme.icons.folder_closed
             };
 
             let item = ListItem::new(Line::from(vec![
@@ -772,16 +901,35 @@ impl ChatEditor {
             items.push(item);
         }
 
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title("Attachments")
-                    .borders(Borders::ALL)
-                    .border_style(theme.styles.dialog_border),
-            )
-            .style(theme.styles.base);
+        let block = Block::default()
+            .title("Attachments")
+            .borders(Borders::ALL)
+            .border_style(theme.styles.dialog_border);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        // Image previews go on the left, one after the other, the list takes the rest
+        let thumbnails: Vec<_> = self
+            .attachments
+            .iter()
+            .filter_map(|attachment| self.thumbnails.get(&attachment.id))
+            .collect();
+        let mut list_area = inner;
+        for thumbnail in thumbnails {
+            if list_area.width < THUMBNAIL_WIDTH * 2 {
+                break;
+            }
+            let thumbnail_area = Rect {
+                width: THUMBNAIL_WIDTH,
+                ..list_area
+            };
+            frame.render_widget(Paragraph::new(thumbnail.clone()), thumbnail_area);
+            list_area.x += THUMBNAIL_WIDTH + 1;
+            list_area.width -= THUMBNAIL_WIDTH + 1;
+        }
 
-        frame.render_widget(list, area);
+        let list = List::new(items).style(theme.styles.base);
+        frame.render_widget(list, list_area);
     }
 
     fn render_completion_popup(&self, frame: &mut Frame) {
@@ -793,11 +941,14 @@ impl ChatEditor {
             let theme = self.theme_manager.current_theme();
             let (x, y) = popup.position;
             
+            // Icon, label and detail inside the borders
             let popup_width = popup.items.iter()
-                .map(|item| item.label.len() + item.detail.as_ref().map_or(0, |d| d.len() + 3))
+                .map(|item| 3 + display_width(&item.label) + item.detail.as_ref().map_or(0, |d| display_width(d) + 3))
                 .max()
                 .unwrap_or(20)
-                .min(50) as u16;
+                .min(50) as u16
+                + 2;
+            let inner_width = popup_width as usize - 2;
             
             let popup_height = (popup.items.len() + 2).min(10) as u16;
             
@@ -819,16 +970,18 @@ impl ChatEditor {
                     theme.styles.base
                 };
 
+                let label = truncate_to_width(&item.label, inner_width.saturating_sub(3));
+                let detail_width = inner_width.saturating_sub(3 + display_width(&label) + 3);
                 let mut spans = vec![
-                    Span::styled(get_completion_icon(&item.kind), theme.styles.info),
+                    Span::styled(get_completion_icon(&theme.icons, &item.kind), theme.styles.info),
                     Span::raw(" "),
-                    Span::styled(&item.label, style),
+                    Span::styled(label, style),
                 ];
 
-                if let Some(detail) = &item.detail {
+                if let Some(detail) = item.detail.as_ref().filter(|_| detail_width > 0) {
                     spans.extend([
                         Span::raw(" - "),
-                        Span::styled(detail, theme.styles.muted),
+                        Span::styled(truncate_to_width(detail, detail_width), theme.styles.muted),
                     ]);
                 }
 
@@ -873,13 +1026,13 @@ fn is_keyword(word: &str) -> bool {
     )
 }
 
-fn get_completion_icon(kind: &CompletionKind) -> &'static str {
+fn get_completion_icon<'a>(icons: &'a IconSet, kind: &CompletionKind) -> &'a str {
     match kind {
-        CompletionKind::File => "📄",
-        CompletionKind::Command => "⚡",
-        CompletionKind::Snippet => "📝",
-        CompletionKind::Variable => "🔤",
-        CompletionKind::Function => "🔧",
+        CompletionKind::File => &icons.file,
+        CompletionKind::Command => &icons.arrow_right,
+        CompletionKind::Snippet => &icons.edit,
+        CompletionKind::Variable => &icons.bullet,
+        CompletionKind::Function => &icons.tool,
     }
 }
 
@@ -918,7 +1071,7 @@ impl Component for ChatEditor {
         } else {
             Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Length(3), Constraint::Min(1)])
+                .constraints([Constraint::Length(self.attachments_height()), Constraint::Min(1)])
                 .split(area)
         };
 
@@ -927,7 +1080,15 @@ impl Component for ChatEditor {
             self.render_attachments(frame, chunks[0]);
         }
 
-        let editor_area = if self.attachments.is_empty() { chunks[0] } else { chunks[1] };
+        let mut editor_area = if self.attachments.is_empty() { chunks[0] } else { chunks[1] };
+        if self.preview.is_some() {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(editor_area);
+            editor_area = halves[0];
+            self.render_preview(frame, halves[1], theme);
+        }
         
         // Render editor
         let border_style = if self.state.has_focus {
@@ -1026,6 +1187,7 @@ impl ChatEditor {
                     let attachments = self.attachments.clone();
                     self.clear();
                     self.attachments.clear();
+                    self.thumbnails.clear();
                     // In a real implementation, you'd emit an event here
                 }
             }
@@ -1050,6 +1212,19 @@ impl ChatEditor {
             (KeyCode::PageUp, KeyModifiers::NONE) => self.move_cursor(CursorDirection::PageUp),
             (KeyCode::PageDown, KeyModifiers::NONE) => self.move_cursor(CursorDirection::PageDown),
 
+            // Selection by moving with Shift held
+            (KeyCode::Left, KeyModifiers::SHIFT) => self.select_to(CursorDirection::Left),
+            (KeyCode::Right, KeyModifiers::SHIFT) => self.select_to(CursorDirection::Right),
+            (KeyCode::Up, KeyModifiers::SHIFT) => self.select_to(CursorDirection::Up),
+            (KeyCode::Down, KeyModifiers::SHIFT) => self.select_to(CursorDirection::Down),
+            (KeyCode::Home, KeyModifiers::SHIFT) => self.select_to(CursorDirection::Home),
+            (KeyCode::End, KeyModifiers::SHIFT) => self.select_to(CursorDirection::End),
+
+            // Markdown preview beside the draft
+            (KeyCode::Char('m'), KeyModifiers::ALT) => {
+                self.toggle_preview();
+            }
+
             // History navigation
             (KeyCode::Up, KeyModifiers::CONTROL) => {
                 self.history_previous();
@@ -1059,22 +1234,38 @@ impl ChatEditor {
             }
 
             // Editing
+            (KeyCode::Backspace, KeyModifiers::NONE) | (KeyCode::Delete, KeyModifiers::NONE)
+                if self.has_selection() =>
+            {
+                self.delete_selection()
+            }
             (KeyCode::Backspace, KeyModifiers::NONE) => self.delete_previous_char(),
             (KeyCode::Delete, KeyModifiers::NONE) => self.delete_char(),
 
             // Selection
             (KeyCode::Char('a'), KeyModifiers::CONTROL) => self.select_all(),
 
-            // Copy/Cut/Paste (simplified - would need clipboard integration)
+            // Copy/Cut/Paste
             (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                // TODO: Copy to clipboard
+                if let Some(text) = self.copy_selection() {
+                    if let Err(e) = clipboard::copy(&text) {
+                        tracing::warn!("Failed to copy selection: {}", e);
+                    }
+                }
             }
             (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
-                // TODO: Cut to clipboard
-            }
-            (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
-                // TODO: Paste from clipboard
+                // Only remove the text once it is safely on the clipboard
+                if let Some(text) = self.copy_selection() {
+                    match clipboard::copy(&text) {
+                        Ok(()) => self.delete_selection(),
+                        Err(e) => tracing::warn!("Failed to cut selection: {}", e),
+                    }
+                }
             }
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => match clipboard::paste() {
+                Ok(text) => self.paste_text(&text),
+                Err(e) => tracing::warn!("Failed to paste: {}", e),
+            },
 
             // Toggle modes
             (KeyCode::Char(':'), KeyModifiers::NONE) => {
@@ -1169,6 +1360,24 @@ impl Default for ChatEditor {
     }
 }
 
+/// Preview of an image attachment small enough to sit in the attachment list
+fn render_thumbnail(attachment: &MessageAttachment) -> Option<Vec<Line<'static>>> {
+    attachment.to_image()?;
+    let mut widget = ImageWidget::with_config(ImageConfig {
+        max_width: THUMBNAIL_WIDTH,
+        // Two pixels per row with half blocks
+        max_height: THUMBNAIL_HEIGHT * 2,
+        border: None,
+        ..Default::default()
+    });
+    widget.load_from_bytes(&attachment.data).ok()?;
+    let mut lines = widget
+        .render(Rect::new(0, 0, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT * 2))
+        .ok()?;
+    lines.truncate(THUMBNAIL_HEIGHT as usize);
+    Some(lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1181,6 +1390,24 @@ mod tests {
         assert_eq!(editor.lines.len(), 1);
     }
 
+    #[test]
+    fn test_preview_follows_the_draft_once_typing_pauses() {
+        let mut editor = ChatEditor::new();
+        editor.insert_text("| a | b |");
+        assert!(editor.toggle_preview());
+        assert_eq!(editor.preview.as_ref().unwrap().widget.content(), "| a | b |");
+
+        editor.insert_newline();
+        editor.insert_text("|---|---|");
+        assert!(!editor.refresh_preview());
+        editor.last_activity -= PREVIEW_DEBOUNCE;
+        assert!(editor.refresh_preview());
+        assert_eq!(editor.preview.as_ref().unwrap().widget.content(), "| a | b |\n|---|---|");
+
+        assert!(!editor.toggle_preview());
+        assert!(!editor.refresh_preview());
+    }
+
     #[test]
     fn test_text_insertion() {
         let mut editor = ChatEditor::new();
@@ -1217,6 +1444,37 @@ mod tests {
         assert_eq!(editor.cursor_line, 0);
     }
 
+    #[test]
+    fn test_paste_and_cut_multiline_text() {
+        let mut editor = ChatEditor::new();
+        editor.insert_text("ab");
+        editor.move_cursor(CursorDirection::Left);
+        editor.paste_text("one\r\ntwo\r\nthree");
+        assert_eq!(editor.get_content(), "aone\ntwo\nthreeb");
+        assert_eq!((editor.cursor_line, editor.cursor_column), (2, 5));
+
+        let block: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        editor.paste_text(&block);
+        assert_eq!(editor.lines.len(), 5003);
+        assert_eq!(editor.lines[5002], "b");
+
+        editor.clear();
+        editor.paste_text("first\nsecond");
+        editor.select_to(CursorDirection::Up);
+        editor.select_to(CursorDirection::Home);
+        assert_eq!(editor.copy_selection().as_deref(), Some("first\nsecond"));
+        editor.move_cursor(CursorDirection::End);
+        assert!(!editor.has_selection());
+
+        editor.select_to(CursorDirection::Left);
+        editor.select_to(CursorDirection::Left);
+        assert_eq!(editor.cut_selection().as_deref(), Some("st"));
+        assert_eq!(editor.get_content(), "fir\nsecond");
+        editor.select_all();
+        editor.insert_text("x");
+        assert_eq!(editor.get_content(), "x");
+    }
+
     #[test]
     fn test_history() {
         let mut editor = ChatEditor::new();
@@ -1245,4 +1503,23 @@ mod tests {
         assert!(editor.remove_attachment(0).is_ok());
         assert_eq!(editor.attachments.len(), 0);
     }
+
+    #[test]
+    fn test_image_attachments_get_a_thumbnail() {
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(40, 20, image::Rgba([0, 120, 200, 255])));
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+
+        let mut editor = ChatEditor::new();
+        editor
+            .add_attachment(MessageAttachment::new("shot.png".to_string(), "image/png".to_string(), png))
+            .unwrap();
+        let thumbnail = &editor.thumbnails[&editor.attachments[0].id];
+        assert!(!thumbnail.is_empty());
+        assert!(thumbnail.len() <= THUMBNAIL_HEIGHT as usize);
+        assert_eq!(editor.attachments_height(), THUMBNAIL_HEIGHT + 2);
+
+        editor.remove_attachment(0).unwrap();
+        assert!(editor.thumbnails.is_empty());
+    }
 }
\ No newline at end of file
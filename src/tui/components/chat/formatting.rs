@@ -4,7 +4,7 @@
 //! syntax highlighting, and creating consistent visual presentation.
 
 use crate::llm::types::{ContentBlock, MessageRole, ToolCall};
-use crate::tui::themes::{Theme, ThemeManager};
+use crate::tui::themes::{self, Theme, ThemeManager};
 use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
@@ -258,7 +258,7 @@ impl MessageFormatter {
         let theme = self.theme_manager.current_theme();
         
         let line = Line::from(vec![
-            Span::styled("🖼️ ", theme.styles.info),
+            Span::styled(format!("{} ", theme.icons.image), theme.styles.info),
             Span::styled("[Image]", theme.styles.info),
         ]);
         
@@ -283,7 +283,7 @@ impl MessageFormatter {
         
         // Tool header
         lines.push(Line::from(vec![
-            Span::styled("🔧 ", theme.styles.chat_tool),
+            Span::styled(format!("{} ", theme.icons.tool), theme.styles.chat_tool),
             Span::styled("Tool: ", theme.styles.chat_tool),
             Span::styled(name, theme.styles.chat_tool.add_modifier(Modifier::BOLD)),
         ]));
@@ -335,7 +335,7 @@ impl MessageFormatter {
         
         // Result header
         lines.push(Line::from(vec![
-            Span::styled("📋 ", theme.styles.success),
+            Span::styled(format!("{} ", theme.icons.copy), theme.styles.success),
             Span::styled("Tool Result:", theme.styles.success),
         ]));
         
@@ -574,7 +574,7 @@ impl MarkdownRenderer {
                     _code_language = if lang.is_empty() { None } else { Some(lang.to_string()) };
                     
                     lines.push(Line::from(vec![
-                        Span::styled("📄 ".to_string(), Style::default().fg(Color::Cyan)),
+                        Span::styled(format!("{} ", themes::current_theme().icons.file), Style::default().fg(Color::Cyan)),
                         Span::styled(lang.to_string(), self.code_style),
                     ]));
                     continue;
@@ -11,6 +11,7 @@ use crate::{
         themes::{Theme, ThemeManager},
         Frame,
     },
+    utils::width::truncate_to_width,
 };
 use anyhow::Result;
 use async_trait::async_trait;
@@ -217,11 +218,7 @@ impl ChatHeader {
         
         // Session title
         if let Some(ref session) = self.session {
-            let title = if session.title.len() > 25 {
-                format!("{}...", &session.title[..22])
-            } else {
-                session.title.clone()
-            };
+            let title = truncate_to_width(&session.title, 25);
             
             spans.push(Span::styled(title, theme.styles.title));
             spans.push(Span::raw(" • "));
@@ -266,9 +263,9 @@ impl ChatHeader {
         let theme = self.theme_manager.current_theme();
         
         let logo_text = if area.width > 15 {
-            "🤖 Goofy"
+            format!("{} Goofy", theme.icons.assistant)
         } else {
-            "🤖"
+            theme.icons.assistant.clone()
         };
         
         let logo = Paragraph::new(logo_text)
@@ -286,14 +283,10 @@ impl ChatHeader {
             let mut lines = Vec::new();
             
             // Session title
-            let title = if session.title.len() > area.width as usize - 4 {
-                format!("{}...", &session.title[..area.width as usize - 7])
-            } else {
-                session.title.clone()
-            };
+            let title = truncate_to_width(&session.title, (area.width as usize).saturating_sub(4));
             
             lines.push(Line::from(vec![
-                Span::styled("📝 ", theme.styles.info),
+                Span::styled(format!("{} ", theme.icons.session), theme.styles.info),
                 Span::styled(title, theme.styles.text),
             ]));
             
@@ -301,7 +294,7 @@ impl ChatHeader {
             if self.show_details {
                 lines.push(Line::from(vec![
                     Span::styled("ID: ", theme.styles.muted),
-                    Span::styled(&session.id[..8], theme.styles.muted),
+                    Span::styled(session.id.get(..8).unwrap_or(&session.id), theme.styles.muted),
                 ]));
                 
                 let created_at = session.created_at.format("%Y-%m-%d %H:%M").to_string();
@@ -335,7 +328,7 @@ impl ChatHeader {
         // Model information
         if self.show_model_info {
             lines.push(Line::from(vec![
-                Span::styled("🤖 ", theme.styles.info),
+                Span::styled(format!("{} ", theme.icons.assistant), theme.styles.info),
                 Span::styled(&self.cached_model_name, theme.styles.text),
                 if !self.cached_provider_name.is_empty() {
                     Span::styled(format!(" ({})", self.cached_provider_name), theme.styles.muted)
@@ -357,7 +350,7 @@ impl ChatHeader {
             };
             
             lines.push(Line::from(vec![
-                Span::styled("📊 ", theme.styles.info),
+                Span::styled(format!("{} ", theme.icons.info), theme.styles.info),
                 Span::styled(
                     format!("{:.0}% ", percentage),
                     style,
@@ -375,7 +368,6 @@ impl ChatHeader {
         // Cost information
         if self.show_session_stats && self.cached_cost > 0.0 {
             lines.push(Line::from(vec![
-                Span::styled("💰 ", theme.styles.info),
                 Span::styled(
                     format!("${:.4}", self.cached_cost),
                     theme.styles.text,
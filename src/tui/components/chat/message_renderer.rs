@@ -3,9 +3,18 @@
 //! This module provides sophisticated rendering of chat messages with support for
 //! markdown, syntax highlighting, tool calls, attachments, and streaming updates.
 
+use crate::tui::components::data_table::{DataTable, TableData};
+use crate::llm::changes::{FileChange, FILE_CHANGE_KEY};
+use crate::tui::components::files::diff_viewer::DiffViewer;
+use crate::tui::components::highlighting::{self, HighlightConfig};
+use crate::tui::components::postprocess::Postprocessor;
 use super::message_types::{ChatMessage, MessageDisplayOptions, ToolResult, MessageAttachment, CodeBlock};
+use crate::tui::components::animations::loading::{LoadingConfig, LoadingIndicator, LoadingMessage};
+use crate::llm::citations::{marker_regex, CITATIONS_KEY};
+use crate::llm::tools::ToolProgress;
 use crate::llm::types::{ContentBlock, MessageRole, ToolCall};
-use crate::tui::themes::{Theme, ThemeManager};
+use crate::tui::themes::{self, Theme, ThemeManager};
+use crate::tui::components::markdown::{close_partial_markdown, stable_markdown_prefix};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
@@ -13,6 +22,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Wrap},
     Frame,
 };
+use std::borrow::Cow;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 
@@ -23,6 +33,60 @@ pub struct MessageRenderer {
     markdown_parser: MarkdownParser,
     syntax_highlighter: SyntaxHighlighter,
     animation_state: AnimationState,
+    generating_indicator: LoadingIndicator,
+    streaming_cache: Option<StreamingRenderCache>,
+    /// View state of tables in tool results, by tool call id
+    tables: HashMap<String, DataTable>,
+    /// Tool call whose table has keyboard focus
+    active_table: Option<String>,
+    /// View state of diffs of the files tools changed, by tool call id
+    diffs: HashMap<String, DiffViewer>,
+    /// Tool call whose diff has keyboard focus
+    active_diff: Option<String>,
+    /// Highlighter for the code in diffs, loaded with the first diff
+    diff_highlighter: Option<highlighting::SyntaxHighlighter>,
+    /// Rewrites of finished assistant messages
+    postprocessor: Postprocessor,
+}
+
+/// Rendered lines of the settled part of a streaming message
+///
+/// While a response streams in, only the text after the last complete block
+/// can still change, so the part before it is parsed once and reused.
+struct StreamingRenderCache {
+    message_id: String,
+    stable_len: usize,
+    lines: Vec<Line<'static>>,
+}
+
+impl StreamingRenderCache {
+    /// Render markdown that is still streaming in
+    ///
+    /// The settled prefix comes from the cache; only the tail is re-parsed,
+    /// with any open code fence closed so it renders as code already.
+    fn render(
+        cache: &mut Option<Self>,
+        parser: &MarkdownParser,
+        message_id: &str,
+        text: &str,
+    ) -> Vec<Line<'static>> {
+        let stable_len = stable_markdown_prefix(text);
+        let stale = !matches!(
+            cache,
+            Some(cached) if cached.message_id == message_id && cached.stable_len == stable_len
+        );
+        if stale {
+            *cache = Some(Self {
+                message_id: message_id.to_string(),
+                stable_len,
+                lines: parser.parse_markdown(&text[..stable_len]),
+            });
+        }
+
+        let mut lines = cache.as_ref().map(|cached| cached.lines.clone()).unwrap_or_default();
+        lines.extend(parser.parse_markdown(&close_partial_markdown(&text[stable_len..])));
+        lines
+    }
 }
 
 /// Markdown parsing helper
@@ -81,6 +145,17 @@ impl MessageRenderer {
             markdown_parser: MarkdownParser::new(current_theme),
             syntax_highlighter: SyntaxHighlighter::new(current_theme),
             animation_state: AnimationState::new(),
+            generating_indicator: LoadingIndicator::new(
+                LoadingConfig::ai_thinking().align_left().hide_context(),
+                LoadingMessage::new("Generating".to_string()),
+            ),
+            streaming_cache: None,
+            tables: HashMap::new(),
+            active_table: None,
+            diffs: HashMap::new(),
+            active_diff: None,
+            diff_highlighter: None,
+            postprocessor: Postprocessor::default(),
         }
     }
 
@@ -89,6 +164,11 @@ impl MessageRenderer {
         self.display_options = options;
     }
 
+    /// Set the postprocessing applied to finished assistant messages
+    pub fn set_postprocessor(&mut self, postprocessor: Postprocessor) {
+        self.postprocessor = postprocessor;
+    }
+
     /// Set theme
     pub fn set_theme(&mut self, theme_name: &str) -> Result<(), String> {
         self.theme_manager.set_theme(theme_name)?;
@@ -98,6 +178,64 @@ impl MessageRenderer {
         Ok(())
     }
 
+    /// Give keyboard focus to the table of a tool call, or take it away
+    ///
+    /// Returns false if the tool call has no table.
+    pub fn focus_table(&mut self, tool_call_id: Option<&str>) -> bool {
+        match tool_call_id {
+            Some(id) if self.tables.contains_key(id) => {
+                self.active_table = Some(id.to_string());
+                self.active_diff = None;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.active_table = None;
+                true
+            }
+        }
+    }
+
+    /// Whether a tool call's result is shown as a table
+    pub fn has_table(&self, tool_call_id: &str) -> bool {
+        self.tables.contains_key(tool_call_id)
+    }
+
+    /// The table that has keyboard focus
+    pub fn active_table_mut(&mut self) -> Option<&mut DataTable> {
+        let id = self.active_table.as_ref()?;
+        self.tables.get_mut(id)
+    }
+
+    /// Give keyboard focus to the diff of a tool call, or take it away
+    ///
+    /// Returns false if the tool call has no diff.
+    pub fn focus_diff(&mut self, tool_call_id: Option<&str>) -> bool {
+        match tool_call_id {
+            Some(id) if self.diffs.contains_key(id) => {
+                self.active_diff = Some(id.to_string());
+                self.active_table = None;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.active_diff = None;
+                true
+            }
+        }
+    }
+
+    /// Whether a tool call's result is shown as a diff
+    pub fn has_diff(&self, tool_call_id: &str) -> bool {
+        self.diffs.contains_key(tool_call_id)
+    }
+
+    /// The diff that has keyboard focus
+    pub fn active_diff_mut(&mut self) -> Option<&mut DiffViewer> {
+        let id = self.active_diff.as_ref()?;
+        self.diffs.get_mut(id)
+    }
+
     /// Render a complete message
     pub fn render_message(
         &mut self,
@@ -179,6 +317,7 @@ impl MessageRenderer {
             let tool_calls_height = self.render_tool_calls(
                 &message.tool_calls,
                 &message.tool_results,
+                &message.tool_progress,
                 frame,
                 Rect {
                     x: area.x,
@@ -265,7 +404,7 @@ impl MessageRenderer {
         if let Some(thinking_content) = &message.thinking_content {
             let mut lines = vec![
                 Line::from(vec![
-                    Span::styled("🤔 ", theme.styles.info),
+                    Span::styled(format!("{} ", theme.icons.thinking), theme.styles.info),
                     if message.is_streaming() {
                         Span::styled(
                             format!("Thinking{}", self.animation_state.get_thinking_indicator()),
@@ -300,24 +439,39 @@ impl MessageRenderer {
         let theme = self.theme_manager.current_theme();
         let mut lines = Vec::new();
 
-        for block in &message.content {
+        let last_block = message.content.len().saturating_sub(1);
+        for (index, block) in message.content.iter().enumerate() {
             match block {
                 ContentBlock::Text { text } => {
-                    if self.display_options.markdown_rendering {
-                        lines.extend(self.markdown_parser.parse_markdown(text));
+                    // Streaming answers are shown as they arrive and rewritten once finished
+                    let text = if message.role == MessageRole::Assistant && !message.is_streaming() {
+                        self.postprocessor.apply(text)
                     } else {
-                        lines.extend(self.render_plain_text(text));
+                        Cow::Borrowed(text.as_str())
+                    };
+                    let text = text.as_ref();
+                    let rendered = if !self.display_options.markdown_rendering {
+                        self.render_plain_text(text)
+                    } else if message.is_streaming() && index == last_block {
+                        StreamingRenderCache::render(&mut self.streaming_cache, &self.markdown_parser, &message.id, text)
+                    } else {
+                        self.markdown_parser.parse_markdown(text)
+                    };
+                    if message.metadata.contains_key(CITATIONS_KEY) {
+                        lines.extend(rendered.into_iter().map(|line| highlight_citation_markers(line, theme.styles.link)));
+                    } else {
+                        lines.extend(rendered);
                     }
                 }
                 ContentBlock::Image { .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("🖼️ ", theme.styles.info),
+                        Span::styled(format!("{} ", theme.icons.image), theme.styles.info),
                         Span::styled("[Image]", theme.styles.info),
                     ]));
                 }
                 ContentBlock::ToolUse { name, input, .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("🔧 ", theme.styles.chat_tool),
+                        Span::styled(format!("{} ", theme.icons.tool), theme.styles.chat_tool),
                         Span::styled(format!("Using tool: {}", name), theme.styles.chat_tool),
                     ]));
                     if let Ok(formatted_input) = serde_json::to_string_pretty(input) {
@@ -326,7 +480,7 @@ impl MessageRenderer {
                 }
                 ContentBlock::ToolResult { content, .. } => {
                     lines.push(Line::from(vec![
-                        Span::styled("📋 ", theme.styles.success),
+                        Span::styled(format!("{} ", theme.icons.copy), theme.styles.success),
                         Span::styled("Tool Result:", theme.styles.success),
                     ]));
                     lines.extend(self.render_plain_text(content));
@@ -371,18 +525,18 @@ impl MessageRenderer {
 
         let mut lines = vec![
             Line::from(vec![
-                Span::styled("📎 ", theme.styles.info),
+                Span::styled(format!("{} ", theme.icons.attachment), theme.styles.info),
                 Span::styled("Attachments:", theme.styles.info),
             ]),
         ];
 
         for attachment in attachments {
             let icon = if attachment.is_image() {
-                "🖼️"
+                &theme.icons.image
             } else if attachment.is_text() {
-                "📄"
+                &theme.icons.file
             } else {
-                "📁"
+                &theme.icons.folder_closed
             };
 
             lines.push(Line::from(vec![
@@ -408,9 +562,10 @@ impl MessageRenderer {
 
     /// Render tool calls with their status and results
     fn render_tool_calls(
-        &self,
+        &mut self,
         tool_calls: &[ToolCall],
         tool_results: &[ToolResult],
+        tool_progress: &HashMap<String, ToolProgress>,
         frame: &mut Frame,
         area: Rect,
     ) -> u16 {
@@ -428,9 +583,9 @@ impl MessageRenderer {
             let result = tool_results.iter().find(|r| r.tool_call_id == tool_call.id);
             
             let status_icon = match result {
-                Some(r) if r.is_error() => "❌",
-                Some(_) => "✅",
-                None => "⏳",
+                Some(r) if r.is_error() => &theme.icons.error,
+                Some(_) => &theme.icons.success,
+                None => &theme.icons.loading,
             };
 
             let status_style = match result {
@@ -439,13 +594,27 @@ impl MessageRenderer {
                 None => theme.styles.warning,
             };
 
-            lines.push(Line::from(vec![
+            let mut header = vec![
                 Span::styled(status_icon, status_style),
                 Span::raw(" "),
                 Span::styled(format!("Tool: {}", tool_call.name), theme.styles.chat_tool),
-            ]));
+            ];
+            // Show what a pending call targets while its arguments stream in
+            if let Some(target) = result.is_none().then(|| tool_target(&tool_call.arguments)).flatten() {
+                header.push(Span::raw("  "));
+                header.push(Span::styled(target, theme.styles.muted));
+            }
+            lines.push(Line::from(header));
             total_height += 1;
 
+            // Render live progress while the tool is still running
+            if result.is_none() {
+                if let Some(progress) = tool_progress.get(&tool_call.id) {
+                    lines.push(self.render_progress_line(progress, area.width));
+                    total_height += 1;
+                }
+            }
+
             // Render arguments
             if let Ok(formatted_args) = serde_json::to_string_pretty(&tool_call.arguments) {
                 lines.push(Line::from(vec![
@@ -475,6 +644,27 @@ impl MessageRenderer {
                         ),
                     ]));
                     total_height += 1;
+                } else if let Some(table) = Self::table_for(&mut self.tables, result) {
+                    let focused = self.active_table.as_deref() == Some(tool_call.id.as_str());
+                    for line in table.render_lines(area.width.saturating_sub(5) as usize, theme, focused) {
+                        let mut spans = vec![Span::raw("    ")];
+                        spans.extend(line.spans);
+                        lines.push(Line::from(spans));
+                        total_height += 1;
+                    }
+                } else if let Some(diff) = Self::diff_for(&mut self.diffs, result) {
+                    let focused = self.active_diff.as_deref() == Some(tool_call.id.as_str());
+                    if self.diff_highlighter.is_none() {
+                        let config = HighlightConfig { show_line_numbers: false, ..HighlightConfig::default() };
+                        self.diff_highlighter = highlighting::SyntaxHighlighter::with_config(config).ok();
+                    }
+                    let width = area.width.saturating_sub(5) as usize;
+                    for line in diff.render_lines(width, theme, self.diff_highlighter.as_mut(), focused) {
+                        let mut spans = vec![Span::raw("    ")];
+                        spans.extend(line.spans);
+                        lines.push(Line::from(spans));
+                        total_height += 1;
+                    }
                 } else if !result.content.is_empty() {
                     lines.push(Line::from(vec![
                         Span::raw("  "),
@@ -505,25 +695,68 @@ impl MessageRenderer {
         total_height
     }
 
-    /// Render streaming indicator with animation
+    /// Table state for a result with tabular metadata, created on first sight
+    fn table_for<'a>(tables: &'a mut HashMap<String, DataTable>, result: &ToolResult) -> Option<&'a mut DataTable> {
+        if !tables.contains_key(&result.tool_call_id) {
+            let data = result.metadata.as_ref().and_then(TableData::from_metadata)?;
+            tables.insert(result.tool_call_id.clone(), DataTable::new(data));
+        }
+        tables.get_mut(&result.tool_call_id)
+    }
+
+    /// Diff state for a result that changed a file, created on first sight
+    fn diff_for<'a>(diffs: &'a mut HashMap<String, DiffViewer>, result: &ToolResult) -> Option<&'a mut DiffViewer> {
+        if !diffs.contains_key(&result.tool_call_id) {
+            let change = result.metadata.as_ref()?.get(FILE_CHANGE_KEY)?;
+            let change: FileChange = serde_json::from_value(change.clone()).ok()?;
+            diffs.insert(result.tool_call_id.clone(), DiffViewer::from_unified_diff(&change.path, &change.diff));
+        }
+        diffs.get_mut(&result.tool_call_id)
+    }
+
+    /// Render a single-line progress bar for a running tool
+    fn render_progress_line(&self, progress: &ToolProgress, width: u16) -> Line<'static> {
+        let theme = self.theme_manager.current_theme();
+        let label = progress.message.clone().unwrap_or_default();
+
+        match progress.percent() {
+            Some(percent) => {
+                let bar_width = (width as usize).saturating_sub(label.len() + 12).clamp(10, 40);
+                let filled = bar_width * percent as usize / 100;
+                Line::from(vec![
+                    Span::raw("  "),
+                    Span::styled("█".repeat(filled), theme.styles.info),
+                    Span::styled("░".repeat(bar_width - filled), theme.styles.muted),
+                    Span::styled(format!(" {:>3}%", percent), theme.styles.info),
+                    Span::raw(" "),
+                    Span::styled(label, theme.styles.muted),
+                ])
+            }
+            None => Line::from(vec![
+                Span::raw("  "),
+                Span::styled(self.animation_state.get_spinner(), theme.styles.info),
+                Span::raw(" "),
+                Span::styled(label, theme.styles.muted),
+            ]),
+        }
+    }
+
+    /// Render the "generating" indicator below a streaming message
     fn render_streaming_indicator(&mut self, frame: &mut Frame, area: Rect) {
         let theme = self.theme_manager.current_theme();
+        let _ = self.generating_indicator.update();
         
-        let indicator = Line::from(vec![
-            Span::styled(
-                self.animation_state.get_spinner(),
-                theme.styles.info,
-            ),
-            Span::raw(" "),
-            Span::styled("Streaming...", theme.styles.info),
-        ]);
+        let indicator = self.generating_indicator
+            .render(area)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
 
         let widget = Paragraph::new(indicator).style(theme.styles.base);
         frame.render_widget(widget, area);
-        
-        self.animation_state.update();
     }
 
+
     /// Render plain text without markdown processing
     fn render_plain_text(&self, text: &str) -> Vec<Line<'static>> {
         text.lines()
@@ -559,6 +792,20 @@ impl MessageRenderer {
         // Tool calls height (approximate)
         if message.has_tool_calls() {
             height += message.tool_calls.len() as u16 * 5; // Conservative estimate
+            height += message
+                .tool_results
+                .iter()
+                .filter_map(|result| self.tables.get(&result.tool_call_id))
+                .map(|table| table.height())
+                .sum::<u16>();
+            height += message
+                .tool_results
+                .iter()
+                .filter_map(|result| {
+                    let focused = self.active_diff.as_deref() == Some(result.tool_call_id.as_str());
+                    Some(self.diffs.get(&result.tool_call_id)?.height(focused))
+                })
+                .sum::<u16>();
         }
 
         // Streaming indicator
@@ -612,7 +859,7 @@ impl MarkdownParser {
                         Some(lang.to_string())
                     };
                     lines.push(Line::from(Span::styled(
-                        format!("📄 {}", lang),
+                        format!("{} {}", themes::current_theme().icons.file, lang),
                         self.code_block_style,
                     )));
                     continue;
@@ -670,6 +917,26 @@ impl MarkdownParser {
                     let code_text = self.consume_until(&mut chars, "`");
                     spans.push(Span::styled(code_text, self.inline_code_style));
                 }
+                '[' => {
+                    // Link [text](url), with the URL kept visible when it differs
+                    let mut lookahead = chars.clone();
+                    match Self::consume_link(&mut lookahead) {
+                        Some((text, url)) => {
+                            if !current_text.is_empty() {
+                                spans.push(Span::raw(current_text.clone()));
+                                current_text.clear();
+                            }
+                            chars = lookahead;
+                            if text != url {
+                                spans.push(Span::styled(text, self.link_style.add_modifier(Modifier::UNDERLINED)));
+                                spans.push(Span::styled(format!(" ({})", url), self.link_style.add_modifier(Modifier::DIM)));
+                            } else {
+                                spans.push(Span::styled(url, self.link_style.add_modifier(Modifier::UNDERLINED)));
+                            }
+                        }
+                        None => current_text.push(ch),
+                    }
+                }
                 _ => {
                     current_text.push(ch);
                 }
@@ -683,6 +950,23 @@ impl MarkdownParser {
         Line::from(spans)
     }
 
+    /// Text and URL of a link after its opening bracket
+    fn consume_link(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<(String, String)> {
+        let text: String = chars.by_ref().take_while(|&c| c != ']').collect();
+        if text.is_empty() || text.starts_with('^') || chars.next() != Some('(') {
+            return None;
+        }
+        let mut url = String::new();
+        for c in chars.by_ref() {
+            match c {
+                ')' => return (!url.is_empty()).then_some((text, url)),
+                c if c.is_whitespace() => return None,
+                c => url.push(c),
+            }
+        }
+        None
+    }
+
     fn consume_until(&self, chars: &mut std::iter::Peekable<std::str::Chars>, delimiter: &str) -> String {
         let mut text = String::new();
         let delimiter_chars: Vec<char> = delimiter.chars().collect();
@@ -820,6 +1104,40 @@ impl Default for MessageRenderer {
     }
 }
 
+/// Style citation markers such as `[2]` so they stand out from the text
+fn highlight_citation_markers(line: Line<'static>, style: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    for span in line.spans {
+        let content = span.content.to_string();
+        let mut last = 0;
+        for marker in marker_regex().find_iter(&content) {
+            if marker.start() > last {
+                spans.push(Span::styled(content[last..marker.start()].to_string(), span.style));
+            }
+            spans.push(Span::styled(marker.as_str().to_string(), span.style.patch(style)));
+            last = marker.end();
+        }
+        if last == 0 {
+            spans.push(span);
+        } else if last < content.len() {
+            spans.push(Span::styled(content[last..].to_string(), span.style));
+        }
+    }
+    Line::from(spans)
+}
+
+/// Arguments that say what a tool call acts on, most telling first
+const TARGET_ARGUMENTS: &[&str] = &["file_path", "path", "url", "command", "pattern", "query"];
+
+/// The file, URL or command a tool call targets, if its arguments name one
+fn tool_target(arguments: &serde_json::Value) -> Option<String> {
+    TARGET_ARGUMENTS
+        .iter()
+        .find_map(|key| arguments.get(key)?.as_str())
+        .filter(|target| !target.is_empty())
+        .map(|target| target.lines().next().unwrap_or_default().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -844,6 +1162,28 @@ mod tests {
         assert!(lines[0].spans.len() > 1);
     }
 
+    #[test]
+    fn test_links_are_parsed() {
+        let theme = ThemeManager::new();
+        let parser = MarkdownParser::new(theme.current_theme());
+
+        let lines = parser.parse_markdown("See [docs](https://docs.rs) or [https://x.io](https://x.io), [ ] [^1] [a](b c)");
+        let spans: Vec<&str> = lines[0].spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(spans, ["See ", "docs", " (https://docs.rs)", " or ", "https://x.io", ", [ ] [^1] [a](b c)"]);
+    }
+
+    #[test]
+    fn test_streaming_render_matches_full_render() {
+        let theme = ThemeManager::new();
+        let parser = MarkdownParser::new(theme.current_theme());
+        let mut cache = None;
+        let text = "First **bold** line.\n\nSecond paragraph with `code`\n\n```\nlet x = 1;\n```\nTail";
+
+        let streamed = StreamingRenderCache::render(&mut cache, &parser, "m1", text);
+        assert_eq!(streamed, parser.parse_markdown(text));
+        assert_eq!(cache.as_ref().unwrap().stable_len, text.len() - "Tail".len());
+    }
+
     #[test]
     fn test_height_calculation() {
         let renderer = MessageRenderer::new();
@@ -3,6 +3,8 @@
 //! This module defines comprehensive message types that support rich content,
 //! tool calls, attachments, and streaming updates.
 
+use crate::llm::images::ImageAttachment;
+use crate::llm::tools::ToolProgress;
 use crate::llm::types::{ContentBlock, MessageRole, ToolCall};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -20,6 +22,8 @@ pub struct ChatMessage {
     pub attachments: Vec<MessageAttachment>,
     pub tool_calls: Vec<ToolCall>,
     pub tool_results: Vec<ToolResult>,
+    #[serde(default)]
+    pub tool_progress: HashMap<String, ToolProgress>,
     pub streaming_state: StreamingState,
     pub finish_reason: Option<FinishReason>,
     pub thinking_content: Option<String>,
@@ -45,6 +49,9 @@ pub struct ToolResult {
     pub error: Option<String>,
     pub execution_time: Option<std::time::Duration>,
     pub artifacts: Vec<ToolArtifact>,
+    /// Structured output reported by the tool, such as a table
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// Artifact created by a tool
@@ -126,6 +133,7 @@ impl ChatMessage {
             attachments: Vec::new(),
             tool_calls: Vec::new(),
             tool_results: Vec::new(),
+            tool_progress: HashMap::new(),
             streaming_state: StreamingState::Complete,
             finish_reason: None,
             thinking_content: None,
@@ -181,9 +189,29 @@ impl ChatMessage {
 
     /// Add a tool result to the message
     pub fn add_tool_result(&mut self, tool_result: ToolResult) {
+        self.tool_progress.remove(&tool_result.tool_call_id);
         self.tool_results.push(tool_result);
     }
 
+    /// Record the latest progress update for a running tool call
+    ///
+    /// A line of command output becomes the label of the update, so the
+    /// latest line shows beside the spinner.
+    pub fn update_tool_progress(&mut self, mut progress: ToolProgress) {
+        if !self.tool_calls.iter().any(|call| call.id == progress.tool_call_id) {
+            return;
+        }
+        if let Some(line) = progress.output.take() {
+            progress.message = Some(line);
+        }
+        self.tool_progress.insert(progress.tool_call_id.clone(), progress);
+    }
+
+    /// Get the latest progress update for a tool call, if any
+    pub fn get_tool_progress(&self, tool_call_id: &str) -> Option<&ToolProgress> {
+        self.tool_progress.get(tool_call_id)
+    }
+
     /// Check if message is currently being streamed
     pub fn is_streaming(&self) -> bool {
         self.streaming_state == StreamingState::Streaming
@@ -369,6 +397,7 @@ impl MessageAttachment {
             Some("png") => "image/png",
             Some("jpg") | Some("jpeg") => "image/jpeg",
             Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
             Some("svg") => "image/svg+xml",
             Some("pdf") => "application/pdf",
             _ => "application/octet-stream",
@@ -382,6 +411,12 @@ impl MessageAttachment {
         self.content_type.starts_with("image/")
     }
 
+    /// The attachment as an image for the model, unless it is a vector image
+    pub fn to_image(&self) -> Option<ImageAttachment> {
+        (self.is_image() && self.content_type != "image/svg+xml")
+            .then(|| ImageAttachment::new(self.filename.clone(), self.data.clone()))
+    }
+
     /// Check if attachment is text
     pub fn is_text(&self) -> bool {
         self.content_type.starts_with("text/") || 
@@ -413,6 +448,7 @@ impl ToolResult {
             error: None,
             execution_time: None,
             artifacts: Vec::new(),
+            metadata: None,
         }
     }
 
@@ -424,9 +460,16 @@ impl ToolResult {
             error: Some(error),
             execution_time: None,
             artifacts: Vec::new(),
+            metadata: None,
         }
     }
 
+    /// Attach the tool's structured metadata
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     /// Check if the tool result represents an error
     pub fn is_error(&self) -> bool {
         self.error.is_some()
@@ -508,6 +551,10 @@ And some more text."#;
         assert_eq!(attachment.size, data.len() as u64);
         assert!(attachment.is_text());
         assert!(!attachment.is_image());
+        assert!(attachment.to_image().is_none());
+
+        let image = MessageAttachment::new("shot.png".to_string(), "image/png".to_string(), vec![1, 2, 3]);
+        assert_eq!(image.to_image().unwrap().data, vec![1, 2, 3]);
     }
 
     #[test]
@@ -527,4 +574,35 @@ And some more text."#;
         
         assert!(error_result.is_error());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tool_progress_cleared_by_result() {
+        let mut message = ChatMessage::new_assistant_text(String::new());
+        message.add_tool_call(ToolCall {
+            id: "call_1".to_string(),
+            name: "download".to_string(),
+            arguments: serde_json::json!({}),
+        });
+        
+        message.update_tool_progress(ToolProgress {
+            tool_call_id: "call_1".to_string(),
+            tool_name: "download".to_string(),
+            fraction: Some(0.25),
+            step: None,
+            total_steps: None,
+            message: None,
+            output: None,
+        });
+        assert_eq!(message.get_tool_progress("call_1").and_then(|p| p.percent()), Some(25));
+        
+        message.update_tool_progress(ToolProgress {
+            fraction: None,
+            output: Some("Compiling goofy".to_string()),
+            ..message.get_tool_progress("call_1").unwrap().clone()
+        });
+        assert_eq!(message.get_tool_progress("call_1").unwrap().message.as_deref(), Some("Compiling goofy"));
+        
+        message.add_tool_result(ToolResult::new("call_1".to_string(), "done".to_string()));
+        assert!(message.get_tool_progress("call_1").is_none());
+    }
+}
@@ -5,6 +5,9 @@
 
 use super::message_types::ChatMessage;
 use crate::{
+    git::{GitRepo, GitStatus},
+    utils::width::truncate_to_width,
+    llm::citations::{Citation, CitationSource},
     session::{Session, SessionManager},
     tui::{
         components::{Component, ComponentState, ListView, Scrollable},
@@ -52,6 +55,14 @@ pub struct ChatSidebar {
     // Tool status
     tool_statuses: HashMap<String, ToolStatus>,
     
+    // Sources cited by the current message
+    citations: Vec<Citation>,
+    citation_list_state: ListState,
+    
+    // Branch and changed files of the repository the file tree shows
+    git_status: Option<GitStatus>,
+    last_git_refresh: Option<Instant>,
+    
     // Search functionality
     search_mode: bool,
     search_query: String,
@@ -74,6 +85,8 @@ pub enum SidebarMode {
     Files,
     /// Show tool status
     Tools,
+    /// Show sources cited by the current message
+    Citations,
     /// Show both sessions and files
     Mixed,
 }
@@ -145,7 +158,7 @@ impl Default for SidebarConfig {
             max_files_displayed: 15,
             auto_refresh_interval: Duration::from_secs(30),
             show_recent_files: true,
-            show_git_status: false,
+            show_git_status: true,
             enable_file_preview: false,
         }
     }
@@ -175,6 +188,10 @@ impl ChatSidebar {
             file_tree: FileTree::new(PathBuf::from(".")),
             file_tree_expanded: false,
             tool_statuses: HashMap::new(),
+            citations: Vec::new(),
+            citation_list_state: ListState::default(),
+            git_status: None,
+            last_git_refresh: None,
             search_mode: false,
             search_query: String::new(),
             filtered_sessions: Vec::new(),
@@ -264,6 +281,32 @@ impl ChatSidebar {
         self.tool_statuses.insert(name, status);
     }
 
+    /// Show the sources cited by the current message
+    pub fn set_citations(&mut self, citations: Vec<Citation>) {
+        self.citation_list_state.select(if citations.is_empty() { None } else { Some(0) });
+        self.citations = citations;
+    }
+    
+    /// Sources cited by the current message
+    pub fn citations(&self) -> &[Citation] {
+        &self.citations
+    }
+    
+    /// Show the branch and changed files of a repository
+    pub fn set_git_status(&mut self, status: Option<GitStatus>) {
+        self.git_status = status;
+    }
+    
+    /// Read the git status of the directory the file tree shows
+    pub async fn refresh_git_status(&mut self) {
+        self.last_git_refresh = Some(Instant::now());
+        // Outside a repository there is simply nothing to show
+        self.git_status = match GitRepo::open(&self.file_tree.root).await {
+            Ok(repo) => repo.status().await.ok(),
+            Err(_) => None,
+        };
+    }
+    
     /// Start search mode
     pub fn start_search(&mut self) {
         self.search_mode = true;
@@ -300,6 +343,10 @@ impl ChatSidebar {
             SidebarMode::Tools => {
                 // TODO: Implement tool navigation
             }
+            SidebarMode::Citations => {
+                let selected = self.citation_list_state.selected().unwrap_or(0);
+                self.citation_list_state.select(Some(selected.saturating_sub(1)));
+            }
         }
     }
 
@@ -325,6 +372,12 @@ impl ChatSidebar {
             SidebarMode::Tools => {
                 // TODO: Implement tool navigation
             }
+            SidebarMode::Citations => {
+                let selected = self.citation_list_state.selected().unwrap_or(0);
+                if selected + 1 < self.citations.len() {
+                    self.citation_list_state.select(Some(selected + 1));
+                }
+            }
         }
     }
 
@@ -354,9 +407,26 @@ impl ChatSidebar {
                 // TODO: Implement tool activation
                 None
             }
+            SidebarMode::Citations => {
+                let citation = self.citations.get(self.citation_list_state.selected()?)?;
+                Some(match &citation.source {
+                    CitationSource::File { path, .. } => SidebarAction::FileSelected(PathBuf::from(path)),
+                    CitationSource::Url { url } => SidebarAction::UrlSelected(url.clone()),
+                })
+            }
         }
     }
 
+    /// Take the selected session off the list to be archived
+    pub fn archive_selected(&mut self) -> Option<SidebarAction> {
+        if !matches!(self.mode, SidebarMode::Sessions | SidebarMode::Mixed) {
+            return None;
+        }
+        let id = self.get_selected_session()?.id.clone();
+        self.remove_session(&id);
+        Some(SidebarAction::SessionArchived(id))
+    }
+
     /// Update filtered sessions based on search query
     fn update_filtered_sessions(&mut self) {
         if self.search_mode && !self.search_query.is_empty() {
@@ -440,15 +510,11 @@ impl ChatSidebar {
         let theme = self.theme_manager.current_theme();
         
         let mut spans = vec![
-            Span::styled("📝 ", theme.styles.info),
+            Span::styled(format!("{} ", theme.icons.session), theme.styles.info),
         ];
         
         // Session title
-        let title = if session.title.len() > 30 {
-            format!("{}...", &session.title[..27])
-        } else {
-            session.title.clone()
-        };
+        let title = truncate_to_width(&session.title, 30);
         
         let title_style = if Some(&session.id) == self.selected_session_id.as_ref() {
             theme.styles.text.add_modifier(Modifier::BOLD)
@@ -502,13 +568,8 @@ impl ChatSidebar {
         }
         
         // Icon and name
-        if node.is_directory {
-            let icon = if node.is_expanded { "📂" } else { "📁" };
-            spans.push(Span::styled(icon, theme.styles.info));
-        } else {
-            let icon = get_file_icon(&node.name);
-            spans.push(Span::styled(icon, theme.styles.info));
-        }
+        let icon = theme.icons.entry_icon(&node.name, node.is_directory, node.is_expanded);
+        spans.push(Span::styled(icon.to_string(), theme.styles.info));
         
         spans.push(Span::raw(" "));
         spans.push(Span::styled(&node.name, theme.styles.text));
@@ -550,10 +611,10 @@ impl ChatSidebar {
         let theme = self.theme_manager.current_theme();
         
         let (icon, style) = match &tool.status {
-            ToolState::Available => ("✅", theme.styles.success),
-            ToolState::Running => ("⏳", theme.styles.info),
-            ToolState::Error(_) => ("❌", theme.styles.error),
-            ToolState::Disabled => ("⚫", theme.styles.muted),
+            ToolState::Available => (&theme.icons.success, theme.styles.success),
+            ToolState::Running => (&theme.icons.loading, theme.styles.info),
+            ToolState::Error(_) => (&theme.icons.error, theme.styles.error),
+            ToolState::Disabled => (&theme.icons.bullet, theme.styles.muted),
         };
         
         let mut spans = vec![
@@ -573,11 +634,64 @@ impl ChatSidebar {
         ListItem::new(Line::from(spans))
     }
 
+    /// Render the sources cited by the current message
+    fn render_citations(&mut self, frame: &mut Frame, area: Rect) {
+        let theme = self.theme_manager.current_theme();
+        
+        let block = Block::default()
+            .title(format!("Citations ({})", self.citations.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.styles.dialog_border);
+
+        let items: Vec<ListItem> = if self.citations.is_empty() {
+            vec![ListItem::new(Span::styled("No sources cited", theme.styles.muted))]
+        } else {
+            self.citations
+                .iter()
+                .map(|citation| self.create_citation_list_item(citation))
+                .collect()
+        };
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(theme.styles.selected_base)
+            .highlight_symbol("▶ ");
+
+        frame.render_stateful_widget(list, area, &mut self.citation_list_state);
+    }
+
+    /// Create a list item for a citation
+    fn create_citation_list_item(&self, citation: &Citation) -> ListItem {
+        let theme = self.theme_manager.current_theme();
+        
+        let icon = match citation.source {
+            CitationSource::File { .. } => &theme.icons.file,
+            CitationSource::Url { .. } => &theme.icons.link,
+        };
+        
+        let mut spans = vec![
+            Span::styled(format!("[{}] ", citation.index), theme.styles.link),
+            Span::styled(icon, theme.styles.info),
+            Span::raw(" "),
+            Span::styled(citation.source.to_string(), theme.styles.text),
+        ];
+        
+        if !self.compact_mode {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(format!("({})", citation.tool), theme.styles.muted));
+        }
+        
+        ListItem::new(Line::from(spans))
+    }
+
     /// Render search box
     fn render_search_box(&self, frame: &mut Frame, area: Rect) {
         let theme = self.theme_manager.current_theme();
         
         // Position search box at the bottom of the area
+        if area.height < 3 || area.width < 3 {
+            return;
+        }
         let search_area = Rect {
             x: area.x + 1,
             y: area.y + area.height - 2,
@@ -596,6 +710,33 @@ impl ChatSidebar {
         frame.render_widget(search_widget, search_area);
     }
 
+    /// Render the branch, its distance to upstream and the changed file count
+    fn render_git_status(&self, frame: &mut Frame, area: Rect) {
+        let Some(status) = &self.git_status else {
+            return;
+        };
+        let theme = self.theme_manager.current_theme();
+        
+        let branch = status.branch.as_deref().unwrap_or("detached");
+        let mut spans = vec![
+            Span::styled(format!("{} ", theme.icons.branch), theme.styles.info),
+            Span::styled(truncate_to_width(branch, area.width.saturating_sub(12) as usize), theme.styles.text),
+        ];
+        if status.ahead > 0 {
+            spans.push(Span::styled(format!(" {}{}", theme.icons.arrow_up, status.ahead), theme.styles.muted));
+        }
+        if status.behind > 0 {
+            spans.push(Span::styled(format!(" {}{}", theme.icons.arrow_down, status.behind), theme.styles.muted));
+        }
+        if status.is_clean() {
+            spans.push(Span::styled(format!(" {}", theme.icons.checkmark), theme.styles.success));
+        } else {
+            spans.push(Span::styled(format!(" +{}", status.dirty_count()), theme.styles.warning));
+        }
+        
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
     /// Render mixed mode (sessions and files)
     fn render_mixed_mode(&mut self, frame: &mut Frame, area: Rect) {
         let chunks = Layout::default()
@@ -628,7 +769,9 @@ impl ChatSidebar {
 pub enum SidebarAction {
     SessionSelected(String),
     SessionDeleted(String),
+    SessionArchived(String),
     FileSelected(PathBuf),
+    UrlSelected(String),
     ToolActivated(String),
     SearchActivated,
     ModeChanged(SidebarMode),
@@ -668,6 +811,14 @@ impl Component for ChatSidebar {
             KeyCode::Char('m') => {
                 self.set_mode(SidebarMode::Mixed);
             }
+            KeyCode::Char('c') => {
+                self.set_mode(SidebarMode::Citations);
+            }
+            KeyCode::Char('a') => {
+                if let Some(_action) = self.archive_selected() {
+                    // TODO: Emit action event
+                }
+            }
             KeyCode::Delete => {
                 // TODO: Handle session/file deletion
             }
@@ -686,6 +837,14 @@ impl Component for ChatSidebar {
         // Update scroll animation
         self.scroll_animation.update();
         
+        let git_refresh_due = match self.last_git_refresh {
+            Some(refreshed) => refreshed.elapsed() >= self.config.auto_refresh_interval,
+            None => true,
+        };
+        if self.config.show_git_status && git_refresh_due {
+            self.refresh_git_status().await;
+        }
+        
         // Auto-refresh file tree if needed
         if self.last_update.elapsed() >= self.config.auto_refresh_interval {
             if matches!(self.mode, SidebarMode::Files | SidebarMode::Mixed) {
@@ -698,11 +857,22 @@ impl Component for ChatSidebar {
     }
 
     fn render(&mut self, frame: &mut Frame, area: Rect, _theme: &Theme) {
+        let area = if self.config.show_git_status && self.git_status.is_some() && area.height > 1 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+            self.render_git_status(frame, chunks[0]);
+            chunks[1]
+        } else {
+            area
+        };
         match self.mode {
             SidebarMode::Sessions => self.render_sessions_list(frame, area),
             SidebarMode::Files => self.render_file_tree(frame, area),
             SidebarMode::Tools => self.render_tool_status(frame, area),
             SidebarMode::Mixed => self.render_mixed_mode(frame, area),
+            SidebarMode::Citations => self.render_citations(frame, area),
         }
     }
 
@@ -906,28 +1076,10 @@ fn format_file_size(size: u64) -> String {
     }
 }
 
-fn get_file_icon(filename: &str) -> &'static str {
-    let extension = Path::new(filename).extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("");
-    
-    match extension {
-        "rs" => "🦀",
-        "py" => "🐍",
-        "js" | "ts" => "📜",
-        "json" => "📋",
-        "md" => "📝",
-        "txt" => "📄",
-        "png" | "jpg" | "jpeg" | "gif" => "🖼️",
-        "pdf" => "📕",
-        "zip" | "tar" | "gz" => "📦",
-        _ => "📄",
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tui::themes::IconSet;
 
     #[test]
     fn test_sidebar_creation() {
@@ -966,6 +1118,28 @@ mod tests {
         assert!(sidebar.search_mode);
     }
 
+    #[test]
+    fn test_citation_activation_links_to_source() {
+        let mut sidebar = ChatSidebar::new();
+        sidebar.set_mode(SidebarMode::Citations);
+        sidebar.set_citations(vec![
+            Citation {
+                index: 1,
+                source: CitationSource::File { path: "src/main.rs".to_string(), start_line: Some(1), end_line: Some(5) },
+                tool: "view".to_string(),
+            },
+            Citation {
+                index: 2,
+                source: CitationSource::Url { url: "https://example.com".to_string() },
+                tool: "fetch".to_string(),
+            },
+        ]);
+
+        assert!(matches!(sidebar.activate_selected(), Some(SidebarAction::FileSelected(path)) if path == PathBuf::from("src/main.rs")));
+        sidebar.navigate_down();
+        assert!(matches!(sidebar.activate_selected(), Some(SidebarAction::UrlSelected(url)) if url == "https://example.com"));
+    }
+
     #[test]
     fn test_file_size_formatting() {
         assert_eq!(format_file_size(512), "512B");
@@ -976,9 +1150,10 @@ mod tests {
 
     #[test]
     fn test_file_icons() {
-        assert_eq!(get_file_icon("test.rs"), "🦀");
-        assert_eq!(get_file_icon("script.py"), "🐍");
-        assert_eq!(get_file_icon("config.json"), "📋");
-        assert_eq!(get_file_icon("unknown.xyz"), "📄");
+        let icons = IconSet::emoji();
+        assert_eq!(icons.file_icon("test.rs"), "🦀");
+        assert_eq!(icons.file_icon("script.py"), "🐍");
+        assert_eq!(icons.file_icon("config.json"), "📋");
+        assert_eq!(icons.file_icon("unknown.xyz"), "📄");
     }
 }
\ No newline at end of file
@@ -4,6 +4,7 @@
 //! handling incremental updates, typing indicators, and streaming state management.
 
 use super::message_types::{ChatMessage, StreamingState, FinishReason};
+use crate::llm::partial_json::PartialJson;
 use crate::llm::types::{ProviderEvent, MessageRole, ContentBlock};
 use anyhow::Result;
 use std::{
@@ -46,6 +47,8 @@ struct StreamingMessage {
     thinking_buffer: Option<String>,
     error_count: usize,
     max_errors: usize,
+    /// Arguments of the tool call currently being streamed
+    tool_arguments: Option<(String, PartialJson)>,
 }
 
 /// Streaming update events
@@ -79,6 +82,14 @@ pub enum StreamingUpdate {
         tool_name: String,
     },
     
+    /// More of a pending tool call's arguments arrived
+    ToolUseDelta {
+        message_id: String,
+        tool_call_id: String,
+        /// Arguments received so far, with unfinished parts closed off
+        arguments: serde_json::Value,
+    },
+    
     /// Tool use completed
     ToolUseCompleted {
         message_id: String,
@@ -322,6 +333,7 @@ impl StreamingManager {
             thinking_buffer: None,
             error_count: 0,
             max_errors: 5,
+            tool_arguments: None,
         };
 
         streams_guard.insert(message_id.clone(), streaming_message);
@@ -361,7 +373,17 @@ impl StreamingManager {
                 }
                 
                 ProviderEvent::ToolUseStart { tool_call } => {
-                    stream.message.add_tool_call(tool_call.clone());
+                    // Providers may send the first fragment of the arguments along
+                    let mut arguments = PartialJson::new();
+                    if let Some(fragment) = tool_call.arguments.as_str() {
+                        arguments.push(fragment);
+                    }
+                    let mut call = tool_call.clone();
+                    if call.arguments.is_string() {
+                        call.arguments = arguments.value().unwrap_or(serde_json::Value::Null);
+                    }
+                    stream.tool_arguments = Some((tool_call.id.clone(), arguments));
+                    stream.message.add_tool_call(call);
                     
                     let _ = update_sender.send(StreamingUpdate::ToolUseStarted {
                         message_id: message_id.clone(),
@@ -370,6 +392,26 @@ impl StreamingManager {
                     });
                 }
                 
+                ProviderEvent::ToolUseDelta { delta } => {
+                    if let Some((tool_call_id, arguments)) = stream.tool_arguments.as_mut() {
+                        arguments.push(&delta);
+                        if let Some(value) = arguments.value() {
+                            if let Some(call) = stream.message.tool_calls.iter_mut().find(|c| c.id == *tool_call_id) {
+                                call.arguments = value.clone();
+                            }
+                            let _ = update_sender.send(StreamingUpdate::ToolUseDelta {
+                                message_id: message_id.clone(),
+                                tool_call_id: tool_call_id.clone(),
+                                arguments: value,
+                            });
+                        }
+                    }
+                }
+                
+                ProviderEvent::ToolUseStop => {
+                    stream.tool_arguments = None;
+                }
+                
                 ProviderEvent::Done { response } => {
                     // Finalize the message
                     stream.message.set_streaming_state(StreamingState::Complete);
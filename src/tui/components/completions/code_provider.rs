@@ -260,11 +260,16 @@ impl CodeProvider {
                                   "map()", "and_then()", "or_else()", "is_some()", "is_none()"];
                     for method in &methods {
                         if method.starts_with(&query) {
-                            items.push(
-                                CompletionItem::new(method, method, "method")
-                                    .with_description("Result/Option method".to_string())
-                                    .with_score(0.8)
-                            );
+                            let mut item = CompletionItem::new(method, method, "method")
+                                .with_description("Result/Option method".to_string())
+                                .with_score(0.8);
+                            if let Some((signature, documentation)) = rust_option_method_docs(method) {
+                                item = item.with_metadata(serde_json::json!({
+                                    "signature": signature,
+                                    "documentation": documentation,
+                                }));
+                            }
+                            items.push(item);
                         }
                     }
                 }
@@ -419,6 +424,22 @@ impl CompletionProvider for CodeProvider {
     }
 }
 
+/// Signature and summary of the common `Result`/`Option` methods
+fn rust_option_method_docs(method: &str) -> Option<(&'static str, &'static str)> {
+    Some(match method {
+        "unwrap()" => ("fn unwrap(self) -> T", "Returns the contained value, panicking on `None` or `Err`."),
+        "expect()" => ("fn expect(self, msg: &str) -> T", "Returns the contained value, panicking with `msg` on `None` or `Err`."),
+        "unwrap_or()" => ("fn unwrap_or(self, default: T) -> T", "Returns the contained value or `default`."),
+        "unwrap_or_else()" => ("fn unwrap_or_else<F: FnOnce() -> T>(self, f: F) -> T", "Returns the contained value or computes it from `f`."),
+        "map()" => ("fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Option<U>", "Maps the contained value with `f`, leaving `None`/`Err` untouched."),
+        "and_then()" => ("fn and_then<U, F: FnOnce(T) -> Option<U>>(self, f: F) -> Option<U>", "Chains a fallible computation on the contained value."),
+        "or_else()" => ("fn or_else<F: FnOnce() -> Option<T>>(self, f: F) -> Option<T>", "Returns `self` if it holds a value, otherwise calls `f`."),
+        "is_some()" => ("fn is_some(&self) -> bool", "Returns `true` if the option is a `Some` value."),
+        "is_none()" => ("fn is_none(&self) -> bool", "Returns `true` if the option is a `None` value."),
+        _ => return None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,7 +2,7 @@
 
 use super::{
     CompletionContext, CompletionEngine, CompletionEvent, CompletionItem, 
-    CompletionList, CompletionMessage, CompletionProvider, ProviderPriority,
+    CompletionList, CompletionMessage, CompletionPreview, CompletionProvider, ProviderPriority,
     FileProvider, CommandProvider, HistoryProvider, CodeProvider,
 };
 use crate::tui::{
@@ -31,6 +31,8 @@ pub struct CompletionInput {
     cursor_position: usize,
     completion_engine: Arc<RwLock<CompletionEngine>>,
     completion_list: CompletionList,
+    preview: CompletionPreview,
+    show_preview: bool,
     completion_enabled: bool,
     auto_complete: bool,
     completion_delay_ms: u64,
@@ -66,6 +68,8 @@ impl CompletionInput {
             cursor_position: 0,
             completion_engine: Arc::new(RwLock::new(engine)),
             completion_list,
+            preview: CompletionPreview::new(),
+            show_preview: true,
             completion_enabled: true,
             auto_complete: true,
             completion_delay_ms: 100,
@@ -88,6 +92,12 @@ impl CompletionInput {
         self
     }
 
+    /// Show or hide the preview pane beside the completion popup
+    pub fn with_preview(mut self, enabled: bool) -> Self {
+        self.show_preview = enabled;
+        self
+    }
+
     /// Enable or disable automatic completion triggers
     pub fn with_auto_complete(mut self, auto: bool) -> Self {
         self.auto_complete = auto;
@@ -499,6 +509,15 @@ impl Component for CompletionInput {
 
     async fn tick(&mut self) -> Result<()> {
         self.handle_completion_events().await;
+        
+        // Keep the preview in step with the highlighted item
+        let highlighted = if self.show_preview && self.completion_list.is_visible() {
+            self.completion_list.selected_item().cloned()
+        } else {
+            None
+        };
+        self.preview.request(highlighted);
+        self.preview.poll();
         Ok(())
     }
 
@@ -541,6 +560,13 @@ impl Component for CompletionInput {
         // Render completion list if visible
         if self.completion_list.is_visible() {
             self.completion_list.render(frame, area, theme);
+            
+            if self.show_preview && self.preview.has_content() {
+                let popup = self.completion_list.popup_area(area);
+                if let Some(preview_area) = preview_area(area, popup) {
+                    self.preview.render(frame, preview_area, theme);
+                }
+            }
         }
     }
 
@@ -625,4 +651,26 @@ impl TextInput for CompletionInput {
     fn set_cursor_position(&mut self, pos: usize) {
         self.cursor_position = pos.min(self.text.len());
     }
-}
\ No newline at end of file
+}
+
+/// Place the preview pane beside the popup, preferring the right-hand side
+fn preview_area(area: Rect, popup: Rect) -> Option<Rect> {
+    const PREVIEW_WIDTH: u16 = 50;
+    const MIN_WIDTH: u16 = 24;
+    const MIN_HEIGHT: u16 = 12;
+
+    let right_space = (area.x + area.width).saturating_sub(popup.x + popup.width);
+    let left_space = popup.x.saturating_sub(area.x);
+    let (x, width) = if right_space >= MIN_WIDTH {
+        (popup.x + popup.width, right_space.min(PREVIEW_WIDTH))
+    } else if left_space >= MIN_WIDTH {
+        let width = left_space.min(PREVIEW_WIDTH);
+        (popup.x - width, width)
+    } else {
+        return None;
+    };
+
+    let bottom = area.y + area.height;
+    let height = popup.height.max(MIN_HEIGHT).min(bottom.saturating_sub(popup.y));
+    Some(Rect { x, y: popup.y, width, height })
+}
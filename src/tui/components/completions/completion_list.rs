@@ -223,6 +223,11 @@ impl CompletionList {
         }
     }
 
+    /// Area the popup occupies when rendered into `area`
+    pub fn popup_area(&self, area: Rect) -> Rect {
+        self.calculate_display_area(area)
+    }
+
     /// Calculate the display area for the completion list
     fn calculate_display_area(&self, area: Rect) -> Rect {
         let items_count = min(self.items.len(), self.max_visible_items);
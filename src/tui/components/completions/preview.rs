@@ -1,4 +1,10 @@
 //! Preview pane for completion items with detailed information
+//!
+//! Shown beside the completion popup for the highlighted item: the head of a
+//! file, the `--help` output of a command, or the signature and docs of a
+//! symbol. Previews are built on a background task so slow sources (large
+//! files, commands) never block typing; a spinner is shown until the result
+//! arrives, and results for items that are no longer highlighted are dropped.
 
 use super::CompletionItem;
 use crate::tui::{
@@ -6,19 +12,66 @@ use crate::tui::{
     themes::Theme,
     Frame,
 };
+use crate::utils::width::truncate_with;
 use anyhow::Result;
 use async_trait::async_trait;
 use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::Rect,
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
-    Frame as RatatuiFrame,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
-use std::fs;
 use std::path::Path;
-use tracing::{debug, warn};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Bytes read from the start of a file for its preview
+const FILE_HEAD_BYTES: usize = 16 * 1024;
+
+/// How long a command gets to print its help
+const HELP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Spinner frames shown while a preview loads
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Kind of preview an item gets, derived from its provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewKind {
+    File,
+    Command,
+    Symbol,
+    History,
+    Other,
+}
+
+impl PreviewKind {
+    /// Classify an item by the provider that produced it
+    pub fn of(item: &CompletionItem) -> Self {
+        match item.provider.as_str() {
+            "file" => Self::File,
+            "command" | "shell" | "tool" | "system" | "git" | "cargo" | "npm" | "flag" => Self::Command,
+            "code" | "keyword" | "pattern" | "module" | "method" | "package" | "function" => Self::Symbol,
+            "history" => Self::History,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Settings that shape preview content
+#[derive(Debug, Clone)]
+struct PreviewOptions {
+    show_content_preview: bool,
+    max_preview_lines: usize,
+    show_metadata: bool,
+}
+
+/// A preview being built in the background
+struct PendingPreview {
+    rx: oneshot::Receiver<String>,
+}
 
 /// Preview component for displaying detailed completion information
 pub struct CompletionPreview {
@@ -26,9 +79,9 @@ pub struct CompletionPreview {
     current_item: Option<CompletionItem>,
     preview_content: String,
     preview_title: String,
-    show_content_preview: bool,
-    max_preview_lines: usize,
-    show_metadata: bool,
+    options: PreviewOptions,
+    pending: Option<PendingPreview>,
+    spinner_frame: usize,
 }
 
 impl CompletionPreview {
@@ -39,319 +92,440 @@ impl CompletionPreview {
             current_item: None,
             preview_content: String::new(),
             preview_title: String::new(),
-            show_content_preview: true,
-            max_preview_lines: 20,
-            show_metadata: true,
+            options: PreviewOptions {
+                show_content_preview: true,
+                max_preview_lines: 20,
+                show_metadata: true,
+            },
+            pending: None,
+            spinner_frame: 0,
         }
     }
 
     /// Enable or disable content preview for files
     pub fn with_content_preview(mut self, enabled: bool) -> Self {
-        self.show_content_preview = enabled;
+        self.options.show_content_preview = enabled;
         self
     }
 
     /// Set maximum number of preview lines
     pub fn with_max_preview_lines(mut self, max_lines: usize) -> Self {
-        self.max_preview_lines = max_lines;
+        self.options.max_preview_lines = max_lines;
         self
     }
 
     /// Enable or disable metadata display
     pub fn with_metadata(mut self, show: bool) -> Self {
-        self.show_metadata = show;
+        self.options.show_metadata = show;
         self
     }
 
-    /// Update preview with a new completion item
-    pub async fn update_preview(&mut self, item: Option<CompletionItem>) -> Result<()> {
-        self.current_item = item.clone();
-        
-        if let Some(ref item) = item {
-            debug!("Updating preview for item: {}", item.title);
-            self.preview_title = format!("{} [{}]", item.title, item.provider);
-            self.preview_content = self.generate_preview_content(item).await;
-        } else {
-            self.preview_title.clear();
-            self.preview_content.clear();
-        }
-
-        Ok(())
-    }
-
-    /// Generate preview content for the completion item
-    async fn generate_preview_content(&self, item: &CompletionItem) -> String {
-        let mut content = Vec::new();
-
-        // Add basic information
-        content.push(format!("Title: {}", item.title));
-        content.push(format!("Value: {}", item.value));
-        content.push(format!("Provider: {}", item.provider));
-        content.push(format!("Score: {:.2}", item.score));
-
-        if let Some(ref description) = item.description {
-            content.push(format!("Description: {}", description));
+    /// Start loading the preview for an item in the background
+    ///
+    /// Does nothing if the item is already shown or loading.
+    pub fn request(&mut self, item: Option<CompletionItem>) {
+        let unchanged = match (&self.current_item, &item) {
+            (Some(current), Some(new)) => {
+                current.value == new.value && current.provider == new.provider && current.title == new.title
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
         }
 
-        content.push(String::new()); // Empty line
-
-        // Add provider-specific content
-        match item.provider.as_str() {
-            "file" => {
-                content.extend(self.generate_file_preview(&item.value).await);
-            }
-            "command" => {
-                content.extend(self.generate_command_preview(&item.value).await);
-            }
-            "history" => {
-                content.extend(self.generate_history_preview(item).await);
-            }
-            "code" => {
-                content.extend(self.generate_code_preview(item).await);
-            }
-            _ => {
-                content.push("No additional information available.".to_string());
+        self.current_item = item.clone();
+        self.preview_content.clear();
+        // Dropping the old receiver discards a result that is still in flight
+        self.pending = None;
+
+        match item {
+            Some(item) => {
+                debug!("Loading preview for item: {}", item.title);
+                self.preview_title = format!("{} [{}]", item.title, item.provider);
+                let (tx, rx) = oneshot::channel();
+                let options = self.options.clone();
+                tokio::spawn(async move {
+                    let _ = tx.send(generate_preview_content(&item, &options).await);
+                });
+                self.pending = Some(PendingPreview { rx });
             }
+            None => self.preview_title.clear(),
         }
+    }
 
-        // Add metadata if available and enabled
-        if self.show_metadata {
-            if let Some(ref metadata) = item.metadata {
-                content.push(String::new());
-                content.push("Metadata:".to_string());
-                if let Ok(pretty_json) = serde_json::to_string_pretty(metadata) {
-                    content.push(pretty_json);
-                } else {
-                    content.push(metadata.to_string());
+    /// Pick up a finished background preview and advance the spinner
+    pub fn poll(&mut self) {
+        if let Some(pending) = self.pending.as_mut() {
+            match pending.rx.try_recv() {
+                Ok(content) => {
+                    self.preview_content = content;
+                    self.pending = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {
+                    self.spinner_frame = (self.spinner_frame + 1) % SPINNER.len();
+                }
+                Err(oneshot::error::TryRecvError::Closed) => {
+                    self.preview_content = "Preview unavailable".to_string();
+                    self.pending = None;
                 }
             }
         }
+    }
+
+    /// Update preview with a new completion item, waiting for it to load
+    pub async fn update_preview(&mut self, item: Option<CompletionItem>) -> Result<()> {
+        self.current_item = None;
+        self.request(item);
+        if let Some(pending) = self.pending.take() {
+            self.preview_content = pending.rx.await.unwrap_or_default();
+        }
+        Ok(())
+    }
 
-        content.join("\n")
+    /// Whether a preview is still being built
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
     }
 
     /// Generate preview content for file completions
     async fn generate_file_preview(&self, file_path: &str) -> Vec<String> {
-        let mut content = Vec::new();
-        let path = Path::new(file_path);
-
-        // File information
-        if path.exists() {
-            if let Ok(metadata) = fs::metadata(path) {
-                content.push(format!("Type: {}", if metadata.is_dir() { "Directory" } else { "File" }));
-                content.push(format!("Size: {} bytes", metadata.len()));
-                
-                if let Ok(modified) = metadata.modified() {
-                    if let Ok(duration) = modified.elapsed() {
-                        let seconds = duration.as_secs();
-                        let time_str = if seconds < 60 {
-                            format!("{} seconds ago", seconds)
-                        } else if seconds < 3600 {
-                            format!("{} minutes ago", seconds / 60)
-                        } else if seconds < 86400 {
-                            format!("{} hours ago", seconds / 3600)
-                        } else {
-                            format!("{} days ago", seconds / 86400)
-                        };
-                        content.push(format!("Modified: {}", time_str));
-                    }
-                }
-            }
-
-            // File content preview for text files
-            if self.show_content_preview && path.is_file() {
-                if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                    let is_text = matches!(ext.to_lowercase().as_str(), 
-                        "txt" | "md" | "rs" | "py" | "js" | "ts" | "html" | "css" | 
-                        "json" | "yaml" | "toml" | "xml" | "csv" | "log"
-                    );
-
-                    if is_text {
-                        content.push(String::new());
-                        content.push("Content Preview:".to_string());
-                        content.push("─".repeat(40));
-
-                        match fs::read_to_string(path) {
-                            Ok(file_content) => {
-                                let lines: Vec<&str> = file_content.lines().collect();
-                                let preview_lines = lines.iter()
-                                    .take(self.max_preview_lines)
-                                    .map(|&line| {
-                                        if line.len() > 80 {
-                                            format!("{}...", &line[..77])
-                                        } else {
-                                            line.to_string()
-                                        }
-                                    })
-                                    .collect::<Vec<_>>();
-
-                                content.extend(preview_lines);
-
-                                if lines.len() > self.max_preview_lines {
-                                    content.push(format!("... ({} more lines)", lines.len() - self.max_preview_lines));
-                                }
-                            }
-                            Err(e) => {
-                                content.push(format!("Error reading file: {}", e));
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            content.push("File does not exist".to_string());
-        }
-
-        content
+        file_preview(file_path, &self.options).await
     }
 
     /// Generate preview content for command completions
     async fn generate_command_preview(&self, command: &str) -> Vec<String> {
-        let mut content = Vec::new();
-
-        // Command information
-        content.push(format!("Command: {}", command));
-
-        // Add common command descriptions
-        let command_info = match command {
-            "ls" => "List directory contents",
-            "cd" => "Change directory",
-            "pwd" => "Print working directory",
-            "cat" => "Display file contents",
-            "grep" => "Search text patterns",
-            "find" => "Find files and directories",
-            "git" => "Version control system",
-            "cargo" => "Rust package manager",
-            "npm" => "Node package manager",
-            "docker" => "Container platform",
-            "curl" => "Transfer data from servers",
-            "vim" | "nvim" => "Text editor",
-            "code" => "VS Code editor",
-            "ssh" => "Secure shell connection",
-            "ps" => "List running processes",
-            "top" => "Display running processes",
-            "kill" => "Terminate processes",
-            _ => "System command",
-        };
+        command_preview(&CompletionItem::new(command, command, "command"), &self.options).await
+    }
 
-        content.push(format!("Type: {}", command_info));
+    /// Generate preview content for code completions
+    async fn generate_code_preview(&self, item: &CompletionItem) -> Vec<String> {
+        symbol_preview(item)
+    }
 
-        // Check if command exists in PATH
-        if let Ok(path_var) = std::env::var("PATH") {
-            let found = path_var.split(':').any(|dir| {
-                Path::new(dir).join(command).exists()
-            });
-            content.push(format!("Available: {}", if found { "Yes" } else { "No" }));
-        }
+    /// Check if preview has content
+    pub fn has_content(&self) -> bool {
+        !self.preview_content.is_empty() || self.is_loading()
+    }
 
-        // Add usage examples for common commands
-        let usage_example = match command {
-            "ls" => Some(vec!["ls -la", "ls -lh", "ls *.txt"]),
-            "cd" => Some(vec!["cd ~/Documents", "cd ..", "cd /path/to/dir"]),
-            "grep" => Some(vec!["grep 'pattern' file.txt", "grep -r 'pattern' .", "grep -i 'pattern' *.log"]),
-            "find" => Some(vec!["find . -name '*.rs'", "find /path -type f", "find . -mtime -7"]),
-            "git" => Some(vec!["git status", "git commit -m 'message'", "git push origin main"]),
-            "cargo" => Some(vec!["cargo build", "cargo test", "cargo run"]),
-            _ => None,
-        };
+    /// Get current preview title
+    pub fn title(&self) -> &str {
+        &self.preview_title
+    }
+}
 
-        if let Some(examples) = usage_example {
+/// Build the full preview text for an item
+async fn generate_preview_content(item: &CompletionItem, options: &PreviewOptions) -> String {
+    let mut content = Vec::new();
+
+    // Add basic information
+    content.push(format!("Title: {}", item.title));
+    content.push(format!("Value: {}", item.value));
+    content.push(format!("Provider: {}", item.provider));
+    content.push(format!("Score: {:.2}", item.score));
+
+    if let Some(ref description) = item.description {
+        content.push(format!("Description: {}", description));
+    }
+
+    content.push(String::new()); // Empty line
+
+    // Add provider-specific content
+    match PreviewKind::of(item) {
+        PreviewKind::File => content.extend(file_preview(&item.value, options).await),
+        PreviewKind::Command => content.extend(command_preview(item, options).await),
+        PreviewKind::Symbol => content.extend(symbol_preview(item)),
+        PreviewKind::History => content.extend(history_preview(item)),
+        PreviewKind::Other => content.push("No additional information available.".to_string()),
+    }
+
+    // Add metadata if available and enabled
+    if options.show_metadata {
+        if let Some(ref metadata) = item.metadata {
             content.push(String::new());
-            content.push("Usage Examples:".to_string());
-            for example in examples {
-                content.push(format!("  {}", example));
+            content.push("Metadata:".to_string());
+            if let Ok(pretty_json) = serde_json::to_string_pretty(metadata) {
+                content.push(pretty_json);
+            } else {
+                content.push(metadata.to_string());
             }
         }
-
-        content
     }
 
-    /// Generate preview content for history completions
-    async fn generate_history_preview(&self, item: &CompletionItem) -> Vec<String> {
-        let mut content = Vec::new();
+    content.join("\n")
+}
+
+/// File information and the first lines of the file
+async fn file_preview(file_path: &str, options: &PreviewOptions) -> Vec<String> {
+    let mut content = Vec::new();
+    let path = Path::new(file_path);
 
-        // Extract usage frequency from description if available
-        if let Some(ref description) = item.description {
-            content.push(format!("Usage: {}", description));
+    let metadata = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            content.push("File does not exist".to_string());
+            return content;
         }
-
-        // Analyze the completion type
-        let completion_type = if item.value.contains('/') || item.value.contains('\\') {
-            "File path"
-        } else if item.value.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
-            "Command or identifier"
+    };
+
+    content.push(format!("Type: {}", if metadata.is_dir() { "Directory" } else { "File" }));
+    content.push(format!("Size: {} bytes", metadata.len()));
+
+    if let Ok(duration) = metadata.modified().and_then(|m| m.elapsed().map_err(std::io::Error::other)) {
+        let seconds = duration.as_secs();
+        let time_str = if seconds < 60 {
+            format!("{} seconds ago", seconds)
+        } else if seconds < 3600 {
+            format!("{} minutes ago", seconds / 60)
+        } else if seconds < 86400 {
+            format!("{} hours ago", seconds / 3600)
         } else {
-            "Text phrase"
+            format!("{} days ago", seconds / 86400)
         };
+        content.push(format!("Modified: {}", time_str));
+    }
 
-        content.push(format!("Type: {}", completion_type));
+    if !options.show_content_preview {
+        return content;
+    }
 
-        // Add context-based suggestions
-        if completion_type == "Command or identifier" {
+    if metadata.is_dir() {
+        if let Ok(mut entries) = tokio::fs::read_dir(path).await {
+            let mut names = Vec::new();
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let mut name = entry.file_name().to_string_lossy().to_string();
+                if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+                    name.push('/');
+                }
+                names.push(name);
+            }
+            names.sort();
             content.push(String::new());
-            content.push("Similar patterns you've used:".to_string());
-            // This would be enhanced with actual historical data
-            content.push("  (Historical patterns would be shown here)".to_string());
+            content.push("Contents:".to_string());
+            content.extend(names.iter().take(options.max_preview_lines).map(|name| format!("  {}", name)));
+            if names.len() > options.max_preview_lines {
+                content.push(format!("  ... ({} more)", names.len() - options.max_preview_lines));
+            }
         }
+        return content;
+    }
 
-        content
+    // Only the head is read, so huge files preview as fast as small ones
+    let mut head = Vec::with_capacity(FILE_HEAD_BYTES);
+    let read = match tokio::fs::File::open(path).await {
+        Ok(file) => file.take(FILE_HEAD_BYTES as u64).read_to_end(&mut head).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = read {
+        content.push(format!("Error reading file: {}", e));
+        return content;
+    }
+    if head.contains(&0) {
+        content.push("Binary file".to_string());
+        return content;
     }
 
-    /// Generate preview content for code completions
-    async fn generate_code_preview(&self, item: &CompletionItem) -> Vec<String> {
-        let mut content = Vec::new();
-
-        // Determine completion type
-        let completion_type = if item.title.ends_with('(') || item.title.contains("()") {
-            "Function/Method"
-        } else if item.title.chars().all(|c| c.is_lowercase() || c == '_') {
-            "Keyword"
-        } else if item.title.starts_with(char::is_uppercase) {
-            "Type/Class"
-        } else {
-            "Identifier"
-        };
+    content.push(String::new());
+    content.push("Content Preview:".to_string());
+    content.push("─".repeat(40));
 
-        content.push(format!("Type: {}", completion_type));
+    let text = String::from_utf8_lossy(&head);
+    let lines: Vec<&str> = text.lines().collect();
+    content.extend(lines.iter().take(options.max_preview_lines).map(|&line| truncate_with(line, 80, "...")));
+    if lines.len() > options.max_preview_lines || metadata.len() > head.len() as u64 {
+        content.push("...".to_string());
+    }
 
-        // Add language-specific information
-        match completion_type {
-            "Function/Method" => {
-                content.push("Signature: (parameters would be shown here)".to_string());
-                content.push("Documentation: (doc comments would be shown here)".to_string());
-            }
-            "Keyword" => {
-                content.push("Language keyword".to_string());
-                content.push("Usage: Used for language syntax".to_string());
-            }
-            "Type/Class" => {
-                content.push("Definition: (type definition would be shown here)".to_string());
-                content.push("Members: (available methods/properties would be listed)".to_string());
-            }
-            _ => {
-                content.push("Code identifier".to_string());
-            }
-        }
+    content
+}
+
+/// Description, availability, examples and `--help` output of a command
+async fn command_preview(item: &CompletionItem, options: &PreviewOptions) -> Vec<String> {
+    let mut content = Vec::new();
+    let command = item.value.as_str();
+    let program = command.split_whitespace().next().unwrap_or(command);
+
+    content.push(format!("Command: {}", command));
+
+    // Add common command descriptions
+    let command_info = match program {
+        "ls" => "List directory contents",
+        "cd" => "Change directory",
+        "pwd" => "Print working directory",
+        "cat" => "Display file contents",
+        "grep" => "Search text patterns",
+        "find" => "Find files and directories",
+        "git" => "Version control system",
+        "cargo" => "Rust package manager",
+        "npm" => "Node package manager",
+        "docker" => "Container platform",
+        "curl" => "Transfer data from servers",
+        "vim" | "nvim" => "Text editor",
+        "code" => "VS Code editor",
+        "ssh" => "Secure shell connection",
+        "ps" => "List running processes",
+        "top" => "Display running processes",
+        "kill" => "Terminate processes",
+        _ => "System command",
+    };
+
+    content.push(format!("Type: {}", command_info));
+
+    // Check if command exists in PATH
+    if let Ok(path_var) = std::env::var("PATH") {
+        let found = std::env::split_paths(&path_var).any(|dir| dir.join(program).exists());
+        content.push(format!("Available: {}", if found { "Yes" } else { "No" }));
+    }
 
-        // Add example usage
+    // Add usage examples for common commands
+    let usage_example = match program {
+        "ls" => Some(vec!["ls -la", "ls -lh", "ls *.txt"]),
+        "cd" => Some(vec!["cd ~/Documents", "cd ..", "cd /path/to/dir"]),
+        "grep" => Some(vec!["grep 'pattern' file.txt", "grep -r 'pattern' .", "grep -i 'pattern' *.log"]),
+        "find" => Some(vec!["find . -name '*.rs'", "find /path -type f", "find . -mtime -7"]),
+        "git" => Some(vec!["git status", "git commit -m 'message'", "git push origin main"]),
+        "cargo" => Some(vec!["cargo build", "cargo test", "cargo run"]),
+        _ => None,
+    };
+
+    if let Some(examples) = usage_example {
         content.push(String::new());
-        content.push("Example:".to_string());
-        content.push(format!("  {}", item.value));
+        content.push("Usage Examples:".to_string());
+        for example in examples {
+            content.push(format!("  {}", example));
+        }
+    }
 
-        content
+    if let Some(args) = help_invocation(item) {
+        if let Some(help) = command_help(&args, options.max_preview_lines).await {
+            content.push(String::new());
+            content.push("Help:".to_string());
+            content.push("─".repeat(40));
+            content.extend(help);
+        }
     }
 
-    /// Check if preview has content
-    pub fn has_content(&self) -> bool {
-        !self.preview_content.is_empty()
+    content
+}
+
+/// Command line that prints help for a command item, if it has one
+///
+/// Shell builtins, Goofy tool commands and flags have no standalone help.
+fn help_invocation(item: &CompletionItem) -> Option<Vec<String>> {
+    let value = item.value.trim();
+    match item.provider.as_str() {
+        "git" => Some(vec!["git".into(), value.into(), "-h".into()]),
+        "cargo" | "npm" => Some(vec![item.provider.clone(), value.into(), "--help".into()]),
+        "system" | "command" => {
+            let mut args: Vec<String> = value.split_whitespace().map(String::from).collect();
+            args.push("--help".into());
+            Some(args)
+        }
+        _ => None,
     }
+}
 
-    /// Get current preview title
-    pub fn title(&self) -> &str {
-        &self.preview_title
+/// Run a help command and return the first lines of its output
+async fn command_help(args: &[String], max_lines: usize) -> Option<Vec<String>> {
+    let (program, rest) = args.split_first()?;
+    let output = tokio::time::timeout(
+        HELP_TIMEOUT,
+        tokio::process::Command::new(program)
+            .args(rest)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    // Many tools print usage to stderr, especially for `-h`
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    let text = String::from_utf8_lossy(&text);
+    let lines: Vec<String> = text.lines().take(max_lines).map(str::to_string).collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines)
     }
 }
 
+/// Signature and documentation of a symbol
+///
+/// Providers that know them (e.g. from a language server) pass `signature`
+/// and `documentation` in the item metadata.
+fn symbol_preview(item: &CompletionItem) -> Vec<String> {
+    let mut content = Vec::new();
+
+    // Determine completion type
+    let completion_type = match item.provider.as_str() {
+        "module" | "package" => "Module",
+        "keyword" => "Keyword",
+        "method" | "function" => "Function/Method",
+        _ if item.title.ends_with('(') || item.title.contains("()") => "Function/Method",
+        _ if item.title.chars().all(|c| c.is_lowercase() || c == '_') => "Keyword",
+        _ if item.title.starts_with(char::is_uppercase) => "Type/Class",
+        _ => "Identifier",
+    };
+
+    content.push(format!("Type: {}", completion_type));
+
+    let field = |key: &str| {
+        item.metadata
+            .as_ref()
+            .and_then(|m| m.get(key))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+
+    if let Some(signature) = field("signature") {
+        content.push(String::new());
+        content.push("Signature:".to_string());
+        content.push(format!("  {}", signature));
+    }
+
+    match field("documentation") {
+        Some(documentation) => {
+            content.push(String::new());
+            content.extend(documentation.lines().map(str::to_string));
+        }
+        None if completion_type == "Keyword" => {
+            content.push("Language keyword".to_string());
+        }
+        None => {}
+    }
+
+    // Add example usage
+    content.push(String::new());
+    content.push("Example:".to_string());
+    content.push(format!("  {}", item.value));
+
+    content
+}
+
+/// Usage summary of a history entry
+fn history_preview(item: &CompletionItem) -> Vec<String> {
+    let mut content = Vec::new();
+
+    // Extract usage frequency from description if available
+    if let Some(ref description) = item.description {
+        content.push(format!("Usage: {}", description));
+    }
+
+    let completion_type = if item.value.contains('/') || item.value.contains('\\') {
+        "File path"
+    } else if item.value.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        "Command or identifier"
+    } else {
+        "Text phrase"
+    };
+
+    content.push(format!("Type: {}", completion_type));
+    content
+}
+
 impl Default for CompletionPreview {
     fn default() -> Self {
         Self::new()
@@ -370,12 +544,29 @@ impl Component for CompletionPreview {
         Ok(())
     }
 
+    async fn tick(&mut self) -> Result<()> {
+        self.poll();
+        Ok(())
+    }
+
     fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
         if !self.has_content() {
             return;
         }
 
-        let preview_widget = Paragraph::new(self.preview_content.clone())
+        frame.render_widget(Clear, area);
+
+        let text = if self.is_loading() {
+            Line::from(vec![
+                Span::styled(SPINNER[self.spinner_frame].to_string(), Style::default().fg(theme.accent)),
+                Span::raw(" Loading preview..."),
+            ])
+            .into()
+        } else {
+            ratatui::text::Text::from(self.preview_content.clone())
+        };
+
+        let preview_widget = Paragraph::new(text)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
@@ -488,4 +679,48 @@ mod tests {
         assert_eq!(preview.max_preview_lines, 10);
         assert!(!preview.show_metadata);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_request_loads_in_background() {
+        let mut preview = CompletionPreview::new();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "first line").unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+
+        preview.request(Some(CompletionItem::new("file", path, "file")));
+        assert!(preview.is_loading());
+        assert!(preview.has_content());
+
+        for _ in 0..100 {
+            preview.poll();
+            if !preview.is_loading() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(preview.preview_content.contains("first line"));
+
+        preview.request(None);
+        assert!(!preview.has_content());
+    }
+
+    #[test]
+    fn test_symbol_preview_shows_signature_and_docs() {
+        let item = CompletionItem::new("unwrap_or()", "unwrap_or()", "method").with_metadata(serde_json::json!({
+            "signature": "fn unwrap_or(self, default: T) -> T",
+            "documentation": "Returns the contained value or `default`.",
+        }));
+
+        let content = symbol_preview(&item);
+        assert!(content.iter().any(|line| line.contains("fn unwrap_or(self, default: T) -> T")));
+        assert!(content.iter().any(|line| line.contains("contained value")));
+    }
+
+    #[test]
+    fn test_help_invocation_per_provider() {
+        let git = CompletionItem::new("status", "status", "git");
+        assert_eq!(help_invocation(&git), Some(vec!["git".into(), "status".into(), "-h".into()]));
+        assert_eq!(help_invocation(&CompletionItem::new("cd", "cd", "shell")), None);
+        assert_eq!(PreviewKind::of(&git), PreviewKind::Command);
+    }
+}
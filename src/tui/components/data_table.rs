@@ -0,0 +1,434 @@
+//! Interactive table for tabular tool results
+//!
+//! Tools that return rows (query results, data previews) put them in their
+//! metadata under `table` as `{"columns": [...], "rows": [[...], ...]}`; rows
+//! may also be objects keyed by column name. The table only keeps view state
+//! on top of the data (sort, hidden columns, selection and horizontal
+//! scroll), so the original row order can always be restored.
+
+use crate::tui::themes::Theme;
+pub use crate::llm::tools::TABLE_METADATA_KEY;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use serde_json::Value;
+use std::cmp::Ordering;
+use crate::utils::width::{display_width, pad_to_width};
+
+/// Widest a column is drawn before its cells are truncated
+const MAX_COLUMN_WIDTH: usize = 32;
+
+/// Rows shown at once unless the view has less room
+const VISIBLE_ROWS: usize = 10;
+
+/// Column names and rows of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl TableData {
+    /// Read a table from tool metadata, if it has one
+    pub fn from_metadata(metadata: &Value) -> Option<Self> {
+        Self::from_value(metadata.get(TABLE_METADATA_KEY)?)
+    }
+
+    /// Read a table from the value tools put under `table`
+    pub fn from_value(table: &Value) -> Option<Self> {
+        let columns: Vec<String> = table
+            .get("columns")?
+            .as_array()?
+            .iter()
+            .map(|column| column.as_str().map(str::to_string).unwrap_or_else(|| column.to_string()))
+            .collect();
+        if columns.is_empty() {
+            return None;
+        }
+
+        let rows = table
+            .get("rows")?
+            .as_array()?
+            .iter()
+            .map(|row| match row {
+                Value::Array(cells) => (0..columns.len())
+                    .map(|i| cells.get(i).cloned().unwrap_or(Value::Null))
+                    .collect(),
+                Value::Object(cells) => columns
+                    .iter()
+                    .map(|column| cells.get(column).cloned().unwrap_or(Value::Null))
+                    .collect(),
+                scalar => std::iter::once(scalar.clone())
+                    .chain(std::iter::repeat(Value::Null))
+                    .take(columns.len())
+                    .collect(),
+            })
+            .collect();
+
+        Some(Self { columns, rows })
+    }
+}
+
+/// Display text of a cell
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Order cells numerically when both are numbers, with empty cells last
+fn compare_cells(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        }
+        _ => cell_text(a).cmp(&cell_text(b)),
+    }
+}
+
+/// Direction of the active sort
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Result of a key press handled by the table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableAction {
+    None,
+    /// Text to put on the clipboard
+    Copy(String),
+    /// The table gave up focus
+    Exit,
+}
+
+/// View state of a table shown in a tool result
+#[derive(Debug, Clone)]
+pub struct DataTable {
+    data: TableData,
+    /// Row indices in display order
+    order: Vec<usize>,
+    sort: Option<(usize, SortDirection)>,
+    hidden: Vec<bool>,
+    /// Display position of the selected row
+    selected_row: usize,
+    /// Data index of the selected column, always a visible one
+    selected_column: usize,
+    /// First visible column drawn at the left edge
+    column_offset: usize,
+    row_offset: usize,
+    visible_rows: usize,
+}
+
+impl DataTable {
+    pub fn new(data: TableData) -> Self {
+        let columns = data.columns.len();
+        Self {
+            order: (0..data.rows.len()).collect(),
+            hidden: vec![false; columns],
+            data,
+            sort: None,
+            selected_row: 0,
+            selected_column: 0,
+            column_offset: 0,
+            row_offset: 0,
+            visible_rows: VISIBLE_ROWS,
+        }
+    }
+
+    pub fn data(&self) -> &TableData {
+        &self.data
+    }
+
+    pub fn sort(&self) -> Option<(usize, SortDirection)> {
+        self.sort
+    }
+
+    /// Data indices of the columns that are not hidden
+    pub fn visible_columns(&self) -> Vec<usize> {
+        (0..self.data.columns.len()).filter(|&i| !self.hidden[i]).collect()
+    }
+
+    /// Show at most `rows` rows at once, keeping the selection in view
+    pub fn set_visible_rows(&mut self, rows: usize) {
+        self.visible_rows = rows.clamp(1, VISIBLE_ROWS);
+        self.move_row(0);
+    }
+
+    /// Rows in display order
+    pub fn rows(&self) -> impl Iterator<Item = &Vec<Value>> {
+        self.order.iter().map(|&i| &self.data.rows[i])
+    }
+
+    /// Sort by a column, cycling ascending, descending and unsorted
+    pub fn sort_by(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((current, SortDirection::Ascending)) if current == column => {
+                Some((column, SortDirection::Descending))
+            }
+            Some((current, SortDirection::Descending)) if current == column => None,
+            _ => Some((column, SortDirection::Ascending)),
+        };
+
+        self.order = (0..self.data.rows.len()).collect();
+        if let Some((column, direction)) = self.sort {
+            let rows = &self.data.rows;
+            self.order.sort_by(|&a, &b| {
+                let ordering = compare_cells(&rows[a][column], &rows[b][column]);
+                match direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+            });
+        }
+    }
+
+    /// Hide the selected column, keeping at least one visible
+    pub fn hide_selected_column(&mut self) {
+        let visible = self.visible_columns();
+        if visible.len() <= 1 {
+            return;
+        }
+        let position = visible.iter().position(|&c| c == self.selected_column).unwrap_or(0);
+        self.hidden[self.selected_column] = true;
+        let next = visible.get(position + 1).or_else(|| visible.get(position.wrapping_sub(1)));
+        self.selected_column = next.copied().unwrap_or(0);
+        self.column_offset = self.column_offset.min(visible.len() - 2);
+    }
+
+    /// Show every hidden column again
+    pub fn show_all_columns(&mut self) {
+        self.hidden.fill(false);
+    }
+
+    /// Move the row selection, scrolling to keep it in view
+    pub fn move_row(&mut self, delta: isize) {
+        let last = self.order.len().saturating_sub(1) as isize;
+        self.selected_row = (self.selected_row as isize + delta).clamp(0, last.max(0)) as usize;
+        if self.selected_row < self.row_offset {
+            self.row_offset = self.selected_row;
+        } else if self.selected_row >= self.row_offset + self.visible_rows {
+            self.row_offset = self.selected_row + 1 - self.visible_rows;
+        }
+    }
+
+    /// Move the column selection among the visible columns
+    ///
+    /// The left edge follows the selection; the right edge is adjusted when
+    /// rendering, where the available width is known.
+    pub fn move_column(&mut self, delta: isize) {
+        let visible = self.visible_columns();
+        let position = visible.iter().position(|&c| c == self.selected_column).unwrap_or(0) as isize;
+        let position = (position + delta).clamp(0, visible.len() as isize - 1) as usize;
+        self.selected_column = visible[position];
+        self.column_offset = self.column_offset.min(position);
+    }
+
+    /// Text of the selected cell
+    pub fn selected_cell_text(&self) -> Option<String> {
+        let row = self.order.get(self.selected_row)?;
+        Some(cell_text(&self.data.rows[*row][self.selected_column]))
+    }
+
+    /// Visible cells of the selected row, tab separated
+    pub fn selected_row_text(&self) -> Option<String> {
+        let row = &self.data.rows[*self.order.get(self.selected_row)?];
+        Some(
+            self.visible_columns()
+                .into_iter()
+                .map(|c| cell_text(&row[c]))
+                .collect::<Vec<_>>()
+                .join("\t"),
+        )
+    }
+
+    /// Handle a key while the table has focus
+    pub fn handle_key(&mut self, event: KeyEvent) -> TableAction {
+        match (event.code, event.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => return TableAction::Exit,
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => self.move_row(-1),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => self.move_row(1),
+            (KeyCode::PageUp, _) => self.move_row(-(self.visible_rows as isize)),
+            (KeyCode::PageDown, _) => self.move_row(self.visible_rows as isize),
+            (KeyCode::Home, _) => self.move_row(isize::MIN / 2),
+            (KeyCode::End, _) => self.move_row(isize::MAX / 2),
+            (KeyCode::Left, _) | (KeyCode::Char('h'), KeyModifiers::NONE) => self.move_column(-1),
+            (KeyCode::Right, _) | (KeyCode::Char('l'), KeyModifiers::NONE) => self.move_column(1),
+            (KeyCode::Char('s'), KeyModifiers::NONE) => self.sort_by(self.selected_column),
+            (KeyCode::Char('x'), KeyModifiers::NONE) => self.hide_selected_column(),
+            (KeyCode::Char('a'), KeyModifiers::NONE) => self.show_all_columns(),
+            (KeyCode::Char('y'), KeyModifiers::NONE) => {
+                return self.selected_cell_text().map_or(TableAction::None, TableAction::Copy);
+            }
+            (KeyCode::Char('Y'), _) => {
+                return self.selected_row_text().map_or(TableAction::None, TableAction::Copy);
+            }
+            _ => {}
+        }
+        TableAction::None
+    }
+
+    /// Lines taken by the table when rendered
+    pub fn height(&self) -> u16 {
+        // Header, separator, rows and the status line
+        (self.order.len().min(self.visible_rows) + 3) as u16
+    }
+
+    /// Render the table into lines no wider than `width`
+    pub fn render_lines(&mut self, width: usize, theme: &Theme, focused: bool) -> Vec<Line<'static>> {
+        let visible = self.visible_columns();
+        let widths: Vec<usize> = visible
+            .iter()
+            .map(|&c| {
+                let header = display_width(&self.data.columns[c]) + 2;
+                let cells = self.data.rows.iter().map(|row| display_width(&cell_text(&row[c])));
+                cells.fold(header, usize::max).min(MAX_COLUMN_WIDTH)
+            })
+            .collect();
+
+        // Scroll right until the selected column fits
+        let selected = visible.iter().position(|&c| c == self.selected_column).unwrap_or(0);
+        while self.column_offset < selected
+            && widths[self.column_offset..=selected].iter().map(|w| w + 3).sum::<usize>() > width
+        {
+            self.column_offset += 1;
+        }
+        let mut shown = Vec::new();
+        let mut used = 0;
+        for (position, &column) in visible.iter().enumerate().skip(self.column_offset) {
+            if used + widths[position] > width && !shown.is_empty() {
+                break;
+            }
+            used += widths[position] + 3;
+            shown.push((column, widths[position].min(width)));
+        }
+
+        let mut lines = Vec::new();
+        let header_style = Style::default().fg(theme.secondary).add_modifier(Modifier::BOLD);
+        let muted = Style::default().fg(theme.fg_muted);
+        let mut header = Vec::new();
+        for &(column, column_width) in &shown {
+            let arrow = match self.sort {
+                Some((sorted, SortDirection::Ascending)) if sorted == column => " ↑",
+                Some((sorted, SortDirection::Descending)) if sorted == column => " ↓",
+                _ => "",
+            };
+            let label = format!("{}{}", self.data.columns[column], arrow);
+            header.push(Span::styled(fit(&label, column_width), header_style));
+            header.push(Span::styled(" │ ", muted));
+        }
+        header.pop();
+        lines.push(Line::from(header));
+        lines.push(Line::from(Span::styled(
+            "─".repeat(used.saturating_sub(3).min(width)),
+            muted,
+        )));
+
+        let end = (self.row_offset + self.visible_rows).min(self.order.len());
+        for position in self.row_offset..end {
+            let row = &self.data.rows[self.order[position]];
+            let row_selected = focused && position == self.selected_row;
+            let mut spans = Vec::new();
+            for &(column, column_width) in &shown {
+                let style = if row_selected && column == self.selected_column {
+                    Style::default().fg(theme.fg_base).bg(theme.primary).add_modifier(Modifier::BOLD)
+                } else if row_selected {
+                    Style::default().fg(theme.fg_selected).bg(theme.primary)
+                } else {
+                    Style::default().fg(theme.fg_base)
+                };
+                spans.push(Span::styled(fit(&cell_text(&row[column]), column_width), style));
+                spans.push(Span::styled(" │ ", muted));
+            }
+            spans.pop();
+            lines.push(Line::from(spans));
+        }
+
+        let mut status = if self.order.is_empty() {
+            "no rows".to_string()
+        } else {
+            format!("rows {}-{} of {}", self.row_offset + 1, end, self.order.len())
+        };
+        let hidden = self.hidden.iter().filter(|&&h| h).count();
+        if hidden > 0 {
+            status.push_str(&format!(" · {} hidden", hidden));
+        }
+        if shown.len() < visible.len() {
+            status.push_str(&format!(" · columns {}-{} of {}", self.column_offset + 1, self.column_offset + shown.len(), visible.len()));
+        }
+        if focused {
+            status.push_str(" · s sort · x hide · a show all · y/Y copy cell/row · esc");
+        }
+        lines.push(Line::from(Span::styled(status, muted)));
+
+        lines
+    }
+}
+
+/// Pad or truncate text to exactly `width` columns
+fn fit(text: &str, width: usize) -> String {
+    pad_to_width(&text.replace(['\n', '\t'], " "), width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn table() -> DataTable {
+        let metadata = json!({
+            "table": {
+                "columns": ["name", "age", "city"],
+                "rows": [
+                    ["carol", 41, "Oslo"],
+                    { "name": "alice", "age": 9, "city": "Lima" },
+                    ["bob", null, "Kyiv"]
+                ]
+            }
+        });
+        DataTable::new(TableData::from_metadata(&metadata).unwrap())
+    }
+
+    #[test]
+    fn test_sort_cycles_and_compares_numbers() {
+        let mut table = table();
+        let names = |t: &DataTable| t.rows().map(|r| cell_text(&r[0])).collect::<Vec<_>>();
+
+        table.sort_by(1);
+        assert_eq!(names(&table), vec!["alice", "carol", "bob"]);
+        table.sort_by(1);
+        assert_eq!(table.sort(), Some((1, SortDirection::Descending)));
+        assert_eq!(names(&table), vec!["bob", "carol", "alice"]);
+        table.sort_by(1);
+        assert_eq!(names(&table), vec!["carol", "alice", "bob"]);
+    }
+
+    #[test]
+    fn test_hidden_columns_are_skipped_when_copying() {
+        let mut table = table();
+        table.move_column(1);
+        table.hide_selected_column();
+        assert_eq!(table.visible_columns(), vec![0, 2]);
+        assert_eq!(table.handle_key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE)),
+            TableAction::Copy("Oslo".to_string()));
+        assert_eq!(table.selected_row_text().as_deref(), Some("carol\tOslo"));
+
+        table.show_all_columns();
+        assert_eq!(table.visible_columns().len(), 3);
+    }
+
+    #[test]
+    fn test_metadata_without_table_is_ignored() {
+        assert!(TableData::from_metadata(&json!({ "file_path": "a.rs" })).is_none());
+        assert_eq!(fit("abcdef", 4), "abc…");
+    }
+}
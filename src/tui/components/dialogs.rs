@@ -11,11 +11,17 @@ pub mod types;
 pub mod layer;
 pub mod navigation;
 pub mod quit;
+pub mod confirm;
 pub mod commands;
 pub mod sessions;
+pub mod semantic_search;
 pub mod models;
+pub mod permission;
+pub mod recent_files;
+pub mod session_diff;
 
 pub use manager::DialogManager;
 pub use types::*;
 pub use layer::DialogLayer;
-pub use navigation::DialogNavigation;
\ No newline at end of file
+pub use navigation::DialogNavigation;
+pub use confirm::{ConfirmButton, ConfirmDialog};
\ No newline at end of file
@@ -149,6 +149,13 @@ impl CommandsDialog {
                 "Session",
             ).with_shortcut("Ctrl+S"),
             
+            Command::new(
+                "search_sessions",
+                "Search Sessions",
+                "Find past conversations by meaning",
+                "Session",
+            ),
+            
             Command::new(
                 "delete_session",
                 "Delete Session",
@@ -194,6 +201,13 @@ impl CommandsDialog {
                 "File",
             ).with_shortcut("Ctrl+O"),
             
+            Command::new(
+                "screenshot",
+                "Attach Screenshot",
+                "Capture the screen and attach it to the message (/screenshot)",
+                "File",
+            ),
+            
             Command::new(
                 "save_conversation",
                 "Save Conversation",
@@ -329,7 +343,7 @@ impl CommandsDialog {
         
         if self.is_loading {
             let loading = Paragraph::new("Loading commands...")
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(loading, area);
             return;
@@ -343,7 +357,7 @@ impl CommandsDialog {
             };
             
             let empty = Paragraph::new(empty_msg)
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(empty, area);
             return;
@@ -364,7 +378,7 @@ impl CommandsDialog {
         for (category, commands) in categorized.iter() {
             // Add category header
             items.push(ListItem::new(format!("── {} ──", category))
-                .style(Style::default().fg(theme.text_muted()).add_modifier(Modifier::BOLD)));
+                .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::BOLD)));
             
             // Add commands in this category
             for command in commands {
@@ -379,9 +393,9 @@ impl CommandsDialog {
                 line = format!("{}\n    {}", line, command.description);
                 
                 let style = if command.enabled {
-                    Style::default().fg(theme.text)
+                    Style::default().fg(theme.fg_base)
                 } else {
-                    Style::default().fg(theme.text_muted())
+                    Style::default().fg(theme.fg_muted)
                 };
                 
                 items.push(ListItem::new(line).style(style));
@@ -391,7 +405,7 @@ impl CommandsDialog {
         
         let list = List::new(items)
             .block(Block::default())
-            .style(Style::default().fg(theme.text))
+            .style(Style::default().fg(theme.fg_base))
             .highlight_style(
                 Style::default()
                     .bg(theme.primary)
@@ -412,7 +426,7 @@ impl CommandsDialog {
         };
         
         let search_input = Paragraph::new(search_text)
-            .style(Style::default().bg(theme.surface()).fg(theme.text))
+            .style(Style::default().bg(theme.bg_overlay).fg(theme.fg_base))
             .block(Block::default().borders(Borders::ALL).title("Search"));
         
         frame.render_widget(search_input, area);
@@ -423,7 +437,7 @@ impl CommandsDialog {
         let help_text = "↑/↓: Navigate • Enter: Execute • Type: Search • Esc: Close";
         
         let help = Paragraph::new(help_text)
-            .style(Style::default().fg(theme.text_muted()).add_modifier(Modifier::DIM))
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
             .alignment(Alignment::Center);
         
         frame.render_widget(help, area);
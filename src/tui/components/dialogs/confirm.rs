@@ -0,0 +1,399 @@
+//! Confirmation dialog
+//!
+//! A question with a row of buttons, each answering with a value of the
+//! caller's type. The answer is sent on a oneshot channel, so the code asking
+//! can simply await it; dropping the dialog unanswered drops the sender and
+//! the receiver sees an error, which callers treat as "no". Buttons can have
+//! keyboard shortcuts, one of them has focus when the dialog opens, and
+//! Escape picks the cancel answer when there is one.
+
+use super::types::{Dialog, DialogConfig, DialogId, DialogPosition, DialogSize, dialog_ids};
+use crate::tui::{
+    components::{Component, ComponentState},
+    events::Event,
+    themes::Theme,
+    Frame,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// One answer of a confirmation dialog
+#[derive(Debug, Clone)]
+pub struct ConfirmButton<T> {
+    pub label: String,
+    /// Keys that pick this answer directly, matched case-insensitively
+    pub shortcuts: Vec<char>,
+    pub value: T,
+}
+
+impl<T> ConfirmButton<T> {
+    pub fn new(label: impl Into<String>, value: T) -> Self {
+        Self {
+            label: label.into(),
+            shortcuts: Vec::new(),
+            value,
+        }
+    }
+
+    pub fn with_shortcut(mut self, key: char) -> Self {
+        self.shortcuts.push(key.to_ascii_lowercase());
+        self
+    }
+}
+
+/// Dialog asking a question and answering with the chosen button's value
+pub struct ConfirmDialog<T> {
+    /// Component state
+    state: ComponentState,
+
+    /// Dialog configuration
+    config: DialogConfig,
+
+    /// Event sender for dialog events
+    event_sender: Option<mpsc::UnboundedSender<Event>>,
+
+    /// Question text to display
+    message: String,
+
+    buttons: Vec<ConfirmButton<T>>,
+
+    /// Index of the button Enter picks
+    focused: usize,
+
+    /// Answer given by Escape; Escape does nothing without one
+    cancel: Option<T>,
+
+    /// Where the answer goes; taken once answered
+    respond: Option<oneshot::Sender<T>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> ConfirmDialog<T> {
+    /// Create a dialog and the receiver its answer arrives on
+    pub fn new(title: impl Into<String>, message: impl Into<String>) -> (Self, oneshot::Receiver<T>) {
+        let (respond, response) = oneshot::channel();
+        (Self::answering(title, message, respond), response)
+    }
+
+    /// Create a dialog answering on an existing channel
+    pub fn answering(title: impl Into<String>, message: impl Into<String>, respond: oneshot::Sender<T>) -> Self {
+        let config = DialogConfig::new(dialog_ids::confirm())
+            .with_title(title.into())
+            .with_position(DialogPosition::Center)
+            .with_size(DialogSize::Fixed(50, 8))
+            .with_border(true)
+            .modal(true)
+            .closable(true);
+
+        Self {
+            state: ComponentState::new(),
+            config,
+            event_sender: None,
+            message: message.into(),
+            buttons: Vec::new(),
+            focused: 0,
+            cancel: None,
+            respond: Some(respond),
+        }
+    }
+
+    /// Add a button after the existing ones
+    pub fn with_button(mut self, button: ConfirmButton<T>) -> Self {
+        self.buttons.push(button);
+        self
+    }
+
+    /// Give the button at `index` focus when the dialog opens
+    pub fn with_default(mut self, index: usize) -> Self {
+        self.focused = index.min(self.buttons.len().saturating_sub(1));
+        self
+    }
+
+    /// Answer with `value` when Escape is pressed
+    pub fn with_cancel(mut self, value: T) -> Self {
+        self.cancel = Some(value);
+        self
+    }
+
+    /// Use a different dialog ID, for dialogs the manager tells apart
+    pub fn with_id(mut self, id: impl Into<DialogId>) -> Self {
+        self.config.id = id.into();
+        self
+    }
+
+    /// Set the event sender for this dialog
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<Event>) {
+        self.event_sender = Some(sender);
+    }
+
+    /// Whether an answer has been given
+    pub fn is_answered(&self) -> bool {
+        self.respond.is_none()
+    }
+
+    /// Index of the focused button
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Answer with `value` and close the dialog
+    pub fn answer(&mut self, value: T) {
+        if let Some(respond) = self.respond.take() {
+            let _ = respond.send(value);
+        }
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(Event::Custom(
+                "dialog_close_request".to_string(),
+                serde_json::json!({"dialog_id": self.config.id.as_str()}),
+            ));
+        }
+    }
+
+    /// Handle a key, returning whether the dialog used it
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let count = self.buttons.len().max(1);
+        match key.code {
+            KeyCode::Left | KeyCode::BackTab => self.focused = (self.focused + count - 1) % count,
+            KeyCode::Right | KeyCode::Tab => self.focused = (self.focused + 1) % count,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(button) = self.buttons.get(self.focused) {
+                    let value = button.value.clone();
+                    self.answer(value);
+                }
+            }
+            KeyCode::Esc => match self.cancel.clone() {
+                Some(value) => self.answer(value),
+                None => return false,
+            },
+            KeyCode::Char(c) => {
+                let c = c.to_ascii_lowercase();
+                let Some(button) = self.buttons.iter().find(|button| button.shortcuts.contains(&c)) else {
+                    return false;
+                };
+                let value = button.value.clone();
+                self.answer(value);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the dialog, for a help line
+    pub fn help_text(&self) -> String {
+        let mut parts: Vec<String> = self
+            .buttons
+            .iter()
+            .filter(|button| !button.shortcuts.is_empty())
+            .map(|button| {
+                let keys: Vec<String> = button.shortcuts.iter().map(char::to_string).collect();
+                format!("{}: {}", keys.join("/"), button.label)
+            })
+            .collect();
+        parts.push("←/→: Select • Enter: Confirm".to_string());
+        if self.cancel.is_some() {
+            parts.push("Esc: Cancel".to_string());
+        }
+        parts.join(" • ")
+    }
+
+    /// Render the row of buttons, the focused one highlighted
+    pub fn render_buttons(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        if self.buttons.is_empty() {
+            return;
+        }
+        let constraints = vec![Constraint::Ratio(1, self.buttons.len() as u32); self.buttons.len()];
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(area);
+
+        for (index, (button, cell)) in self.buttons.iter().zip(cells.iter()).enumerate() {
+            let style = if index == self.focused {
+                Style::default().bg(theme.primary).fg(Color::White).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().bg(theme.bg_overlay).fg(theme.fg_base)
+            };
+            let widget = Paragraph::new(format!(" {} ", button.label))
+                .style(style)
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL));
+            frame.render_widget(widget, *cell);
+        }
+    }
+
+    /// Render the help line
+    pub fn render_help(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let help = Paragraph::new(self.help_text())
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
+            .alignment(Alignment::Center);
+        frame.render_widget(help, area);
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send + Sync + 'static> Component for ConfirmDialog<T> {
+    async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        self.handle_key(event);
+        Ok(())
+    }
+
+    async fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.render_content(frame, area, theme);
+    }
+
+    fn size(&self) -> Rect {
+        self.state.size
+    }
+
+    fn set_size(&mut self, size: Rect) {
+        self.state.size = size;
+    }
+
+    fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.state.has_focus = focus;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.state.is_visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.state.is_visible = visible;
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send + Sync + 'static> Dialog for ConfirmDialog<T> {
+    fn config(&self) -> &DialogConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut DialogConfig {
+        &mut self.config
+    }
+
+    fn position(&self, available_area: Rect) -> (u16, u16) {
+        let (width, height) = self.dialog_size(available_area);
+        let x = available_area.x + (available_area.width.saturating_sub(width)) / 2;
+        let y = available_area.y + (available_area.height.saturating_sub(height)) / 2;
+        (x, y)
+    }
+
+    fn dialog_size(&self, available_area: Rect) -> (u16, u16) {
+        let width = (self.buttons.len() as u16 * 16).max(50).min(available_area.width);
+        (width, 8)
+    }
+
+    async fn handle_dialog_key(&mut self, key: KeyEvent) -> Result<bool> {
+        Ok(self.handle_key(key))
+    }
+
+    fn render_content(&mut self, frame: &mut Frame, content_area: Rect, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(1),     // Question
+                Constraint::Length(3),  // Buttons
+                Constraint::Length(1),  // Help text
+            ])
+            .split(content_area);
+
+        let question = Paragraph::new(self.message.clone())
+            .style(Style::default().fg(theme.fg_base))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(question, chunks[0]);
+        self.render_buttons(frame, chunks[1], theme);
+        self.render_help(frame, chunks[2], theme);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (30, 6)
+    }
+
+    fn preferred_size(&self) -> (u16, u16) {
+        (50, 8)
+    }
+}
+
+impl ConfirmDialog<bool> {
+    /// Yes/No question, answered No by Escape and focused on No
+    pub fn yes_no(title: impl Into<String>, message: impl Into<String>) -> (Self, oneshot::Receiver<bool>) {
+        let (dialog, response) = Self::new(title, message);
+        let dialog = dialog
+            .with_button(ConfirmButton::new("Yes", true).with_shortcut('y'))
+            .with_button(ConfirmButton::new("No", false).with_shortcut('n'))
+            .with_default(1)
+            .with_cancel(false);
+        (dialog, response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Choice {
+        Keep,
+        Delete,
+        Archive,
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[tokio::test]
+    async fn test_answers_arrive_typed() {
+        let (dialog, response) = ConfirmDialog::new("Delete session?", "This cannot be undone.");
+        let mut dialog = dialog
+            .with_button(ConfirmButton::new("Keep", Choice::Keep))
+            .with_button(ConfirmButton::new("Archive", Choice::Archive).with_shortcut('a'))
+            .with_button(ConfirmButton::new("Delete", Choice::Delete).with_shortcut('d'))
+            .with_cancel(Choice::Keep);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        dialog.set_event_sender(tx);
+
+        assert!(dialog.handle_key(key(KeyCode::Left)));
+        assert_eq!(dialog.focused(), 2);
+        assert!(!dialog.handle_key(key(KeyCode::Char('x'))));
+        assert!(dialog.handle_key(key(KeyCode::Char('A'))));
+        assert!(dialog.is_answered());
+        assert_eq!(response.await.unwrap(), Choice::Archive);
+        assert!(matches!(rx.try_recv(), Ok(Event::Custom(name, _)) if name == "dialog_close_request"));
+        assert_eq!(dialog.help_text(), "a: Archive • d: Delete • ←/→: Select • Enter: Confirm • Esc: Cancel");
+    }
+
+    #[tokio::test]
+    async fn test_yes_no_defaults_to_no() {
+        let (mut dialog, response) = ConfirmDialog::yes_no("Quit", "Are you sure you want to quit?");
+        dialog.handle_key(key(KeyCode::Enter));
+        assert!(!response.await.unwrap());
+
+        let (mut dialog, response) = ConfirmDialog::yes_no("Quit", "Are you sure you want to quit?");
+        dialog.handle_key(key(KeyCode::Tab));
+        dialog.handle_key(key(KeyCode::Enter));
+        assert!(response.await.unwrap());
+
+        let (dialog, response) = ConfirmDialog::yes_no("Quit", "Are you sure you want to quit?");
+        drop(dialog);
+        assert!(response.await.is_err());
+    }
+}
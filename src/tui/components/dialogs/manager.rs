@@ -264,7 +264,7 @@ impl DialogManager {
     /// Get the currently focused dialog (mutable)
     pub fn focused_dialog_mut(&mut self) -> Option<&mut dyn Dialog> {
         if let Some(idx) = self.focused_index {
-            self.dialogs.get_mut(idx).map(|dialog| &mut **dialog)
+            self.dialogs.get_mut(idx).map(|dialog| dialog.as_mut() as &mut dyn Dialog)
         } else {
             None
         }
@@ -280,7 +280,7 @@ impl DialogManager {
     /// Get dialog by ID (mutable)
     pub fn get_dialog_mut(&mut self, dialog_id: &DialogId) -> Option<&mut dyn Dialog> {
         if let Some(&idx) = self.id_map.get(dialog_id) {
-            self.dialogs.get_mut(idx).map(|dialog| &mut **dialog)
+            self.dialogs.get_mut(idx).map(|dialog| dialog.as_mut() as &mut dyn Dialog)
         } else {
             None
         }
@@ -570,7 +570,7 @@ impl DialogManager {
         // Render dimmed background
         let dim_style = Style::default()
             .bg(Color::Black)
-            .fg(theme.text)
+            .fg(theme.fg_base)
             .add_modifier(ratatui::style::Modifier::DIM);
         
         let dim_block = Block::default().style(dim_style);
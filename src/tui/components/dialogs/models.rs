@@ -1,11 +1,14 @@
 //! Model selection dialog
 //! 
 //! This dialog allows users to view and select available AI models
-//! for their conversations.
+//! for their conversations. Only models that can do what the session needs,
+//! calling tools by default, are offered; models listed by a server that the
+//! catalog doesn't know are offered as well.
 
 use super::types::{Dialog, DialogConfig, DialogId, DialogPosition, DialogSize, dialog_ids};
 use crate::{
     config::Config,
+    llm::models::{self, Capabilities, KnownModel},
     tui::{
         components::{Component, ComponentState},
         events::Event,
@@ -34,6 +37,8 @@ pub struct ModelInfo {
     pub context_length: Option<u32>,
     pub is_available: bool,
     pub requires_api_key: bool,
+    /// What the model can do, if known
+    pub capabilities: Option<Capabilities>,
 }
 
 impl ModelInfo {
@@ -50,6 +55,7 @@ impl ModelInfo {
             context_length: None,
             is_available: true,
             requires_api_key: false,
+            capabilities: None,
         }
     }
     
@@ -72,6 +78,30 @@ impl ModelInfo {
         self.requires_api_key = requires;
         self
     }
+    
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+    
+    /// Fill in what the catalog knows and the listing left out
+    fn with_catalog_entry(mut self) -> Self {
+        if let Some(known) = models::lookup(&self.id) {
+            self.context_length = self.context_length.or(Some(known.context_window));
+            self.capabilities = self.capabilities.or(Some(known.capabilities));
+        }
+        self
+    }
+}
+
+impl From<&KnownModel> for ModelInfo {
+    fn from(known: &KnownModel) -> Self {
+        ModelInfo::new(known.id, known.name, known.provider)
+            .with_description(known.description)
+            .with_context_length(known.context_window)
+            .with_capabilities(known.capabilities)
+            .requires_api_key(known.provider != "ollama")
+    }
 }
 
 /// Models dialog for selecting AI models
@@ -108,6 +138,9 @@ pub struct ModelsDialog {
     
     /// Error message if any
     error_message: Option<String>,
+    
+    /// What the offered models must be able to do
+    needs: Capabilities,
 }
 
 impl ModelsDialog {
@@ -136,6 +169,7 @@ impl ModelsDialog {
             in_search_mode: false,
             is_loading: false,
             error_message: None,
+            needs: Capabilities { tools: true, vision: false },
         }
     }
     
@@ -144,6 +178,12 @@ impl ModelsDialog {
         self.event_sender = Some(sender);
     }
     
+    /// Offer only models that can do what `needs` asks for
+    pub fn set_needs(&mut self, needs: Capabilities) {
+        self.needs = needs;
+        self.list_state.select(Some(0));
+    }
+    
     /// Set the current configuration
     pub fn set_config(&mut self, config: Config) {
         self.current_model = Some(config.model.clone());
@@ -155,57 +195,53 @@ impl ModelsDialog {
         self.is_loading = true;
         self.error_message = None;
         
-        // Create a basic set of known models
-        self.models = vec![
-            // OpenAI Models
-            ModelInfo::new("gpt-4", "GPT-4", "openai")
-                .with_description("Most capable GPT-4 model")
-                .with_context_length(8192)
-                .requires_api_key(true),
-                
-            ModelInfo::new("gpt-4-turbo", "GPT-4 Turbo", "openai")
-                .with_description("Latest GPT-4 model with improved capabilities")
-                .with_context_length(128000)
-                .requires_api_key(true),
-                
-            ModelInfo::new("gpt-3.5-turbo", "GPT-3.5 Turbo", "openai")
-                .with_description("Fast and efficient ChatGPT model")
-                .with_context_length(4096)
-                .requires_api_key(true),
-                
-            // Anthropic Models
-            ModelInfo::new("claude-3-opus-20240229", "Claude 3 Opus", "anthropic")
-                .with_description("Most powerful Claude model")
-                .with_context_length(200000)
-                .requires_api_key(true),
-                
-            ModelInfo::new("claude-3-sonnet-20240229", "Claude 3 Sonnet", "anthropic")
-                .with_description("Balanced Claude model")
-                .with_context_length(200000)
-                .requires_api_key(true),
-                
-            ModelInfo::new("claude-3-haiku-20240307", "Claude 3 Haiku", "anthropic")
-                .with_description("Fast Claude model")
-                .with_context_length(200000)
-                .requires_api_key(true),
-                
-            // Ollama Models (examples)
-            ModelInfo::new("llama3.2", "Llama 3.2", "ollama")
-                .with_description("Meta's Llama 3.2 model")
-                .with_context_length(8192),
-                
-            ModelInfo::new("codellama", "Code Llama", "ollama")
-                .with_description("Specialized coding model")
-                .with_context_length(16384),
-                
-            ModelInfo::new("mistral", "Mistral", "ollama")
-                .with_description("Mistral AI model")
-                .with_context_length(8192),
-        ];
+        self.models = models::catalog().iter().map(ModelInfo::from).collect();
+        
+        // OpenRouter lists the models it serves along with their prices
+        if let Some(config) = self.current_config.as_ref().filter(|c| c.provider == "openrouter") {
+            match crate::llm::openrouter::list_models(config.base_url.as_deref()).await {
+                Ok(listed) => {
+                    for model in listed {
+                        if let Some(pricing) = model.pricing() {
+                            crate::llm::models::register_pricing(&model.id, pricing);
+                        }
+                        let mut info = ModelInfo::new(model.id, model.name, "openrouter").requires_api_key(true);
+                        if let Some(description) = model.description {
+                            info = info.with_description(description);
+                        }
+                        if let Some(context_length) = model.context_length {
+                            info = info.with_context_length(context_length);
+                        }
+                        self.models.push(info.with_catalog_entry());
+                    }
+                }
+                Err(e) => self.error_message = Some(format!("Could not list OpenRouter models: {}", e)),
+            }
+        }
+        
+        // Any other compatible server lists what it serves below its base URL
+        if let Some(config) = self.current_config.as_ref().filter(|c| c.provider == "openai_compatible") {
+            let base_url = config.base_url.as_deref().unwrap_or_default();
+            match crate::llm::compatible::list_models(base_url, config.api_key.as_deref(), &config.extra_headers).await {
+                Ok(listed) => {
+                    for model in listed {
+                        let mut info = ModelInfo::new(model.id.clone(), model.id, "openai_compatible");
+                        if let Some(owner) = model.owned_by {
+                            info = info.with_description(format!("Served by {}", owner));
+                        }
+                        if let Some(context_length) = model.context_length {
+                            info = info.with_context_length(context_length);
+                        }
+                        self.models.push(info.with_catalog_entry());
+                    }
+                }
+                Err(e) => self.error_message = Some(format!("Could not list models at {}: {}", base_url, e)),
+            }
+        }
         
         // Set current selection to the current model if it exists
         if let Some(current) = &self.current_model {
-            if let Some(index) = self.models.iter().position(|m| &m.id == current) {
+            if let Some(index) = self.filtered_models().iter().position(|m| &m.id == current) {
                 self.list_state.select(Some(index));
             }
         }
@@ -233,13 +269,19 @@ impl ModelsDialog {
         Ok(())
     }
     
+    /// Models that can do what the session needs
+    fn compatible_models(&self) -> impl Iterator<Item = &ModelInfo> {
+        self.models
+            .iter()
+            .filter(|model| model.capabilities.map_or(true, |capabilities| capabilities.covers(self.needs)))
+    }
+    
     /// Get filtered models based on search text
     fn filtered_models(&self) -> Vec<&ModelInfo> {
         if self.filter_text.is_empty() {
-            self.models.iter().collect()
+            self.compatible_models().collect()
         } else {
-            self.models
-                .iter()
+            self.compatible_models()
                 .filter(|model| {
                     model.name.to_lowercase().contains(&self.filter_text.to_lowercase())
                         || model.id.to_lowercase().contains(&self.filter_text.to_lowercase())
@@ -302,7 +344,7 @@ impl ModelsDialog {
         
         if self.is_loading {
             let loading = Paragraph::new("Loading models...")
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(loading, area);
             return;
@@ -316,7 +358,7 @@ impl ModelsDialog {
             };
             
             let empty = Paragraph::new(empty_msg)
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(empty, area);
             return;
@@ -346,15 +388,20 @@ impl ModelsDialog {
                     line = format!("{}\n    {}", line, desc);
                 }
                 
-                // Add context length
+                // Add context length and capabilities
                 if let Some(context) = model.context_length {
                     line = format!("{}\n    Context: {} tokens", line, context);
                 }
+                if let Some(capabilities) = model.capabilities {
+                    let tools = if capabilities.tools { "tools" } else { "no tools" };
+                    let vision = if capabilities.vision { "vision" } else { "no vision" };
+                    line = format!("{} • {} • {}", line, tools, vision);
+                }
                 
                 let style = if model.is_available {
-                    Style::default().fg(theme.text)
+                    Style::default().fg(theme.fg_base)
                 } else {
-                    Style::default().fg(theme.text_muted())
+                    Style::default().fg(theme.fg_muted)
                 };
                 
                 ListItem::new(line).style(style)
@@ -363,7 +410,7 @@ impl ModelsDialog {
         
         let list = List::new(items)
             .block(Block::default())
-            .style(Style::default().fg(theme.text))
+            .style(Style::default().fg(theme.fg_base))
             .highlight_style(
                 Style::default()
                     .bg(theme.primary)
@@ -380,7 +427,7 @@ impl ModelsDialog {
         let search_style = if self.in_search_mode {
             Style::default().bg(theme.primary).fg(Color::White)
         } else {
-            Style::default().bg(theme.surface()).fg(theme.text)
+            Style::default().bg(theme.bg_overlay).fg(theme.fg_base)
         };
         
         let search_text = if self.filter_text.is_empty() && !self.in_search_mode {
@@ -403,9 +450,14 @@ impl ModelsDialog {
         } else {
             "↑/↓: Navigate • Enter: Select • /: Search • Esc: Close"
         };
+        let hidden = self.models.len() - self.compatible_models().count();
+        let help_text = match hidden {
+            0 => help_text.to_string(),
+            hidden => format!("{} • {} hidden without {}", help_text, hidden, describe_needs(self.needs)),
+        };
         
         let help = Paragraph::new(help_text)
-            .style(Style::default().fg(theme.text_muted()).add_modifier(Modifier::DIM))
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
             .alignment(Alignment::Center);
         
         frame.render_widget(help, area);
@@ -583,6 +635,16 @@ impl Dialog for ModelsDialog {
     }
 }
 
+/// The capabilities asked for, in words
+fn describe_needs(needs: Capabilities) -> &'static str {
+    match (needs.tools, needs.vision) {
+        (true, true) => "tools and vision",
+        (true, false) => "tools",
+        (false, true) => "vision",
+        (false, false) => "",
+    }
+}
+
 impl Default for ModelsDialog {
     fn default() -> Self {
         Self::new()
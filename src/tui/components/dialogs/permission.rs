@@ -0,0 +1,399 @@
+//! Tool call approval dialog
+//!
+//! Shown when the permission system wants the user to confirm a tool call.
+//! The agent waits until the dialog answers: allow once, allow the tool for
+//! the rest of the session, always allow it in this project, or deny. Edits
+//! are previewed as a diff of the replaced and inserted text, other calls show
+//! their arguments.
+
+use super::confirm::{ConfirmButton, ConfirmDialog};
+use super::types::{Dialog, DialogConfig, DialogPosition, DialogSize, dialog_ids};
+use crate::{
+    permission::{PermissionContext, PermissionDecision, PermissionLevel, PermissionRequest},
+    tui::{
+        components::{Component, ComponentState},
+        events::Event,
+        themes::Theme,
+        Frame,
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// Dialog asking whether a tool call may run
+pub struct PermissionDialog {
+    /// Component state
+    state: ComponentState,
+
+    /// Dialog configuration
+    config: DialogConfig,
+
+    /// Tool call being asked about
+    context: PermissionContext,
+
+    /// Question shown under the summary
+    message: String,
+
+    /// Answer buttons, which send the decision to the waiting tool call
+    choices: ConfirmDialog<PermissionDecision>,
+
+    /// First preview line shown
+    scroll: u16,
+}
+
+impl PermissionDialog {
+    /// Create a dialog for a pending request
+    pub fn new(request: PermissionRequest) -> Self {
+        let config = DialogConfig::new(dialog_ids::permissions())
+            .with_title("Allow Tool Call?".to_string())
+            .with_position(DialogPosition::Center)
+            .with_size(DialogSize::Percentage(70, 70))
+            .with_border(true)
+            .modal(true)
+            .closable(true);
+        let (context, message, respond) = request.into_parts();
+        let choices = ConfirmDialog::answering("Allow Tool Call?", message.clone(), respond)
+            .with_id(dialog_ids::permissions())
+            .with_button(ConfirmButton::new("Allow", PermissionDecision::Allow).with_shortcut('a').with_shortcut('y'))
+            .with_button(ConfirmButton::new("This session", PermissionDecision::AllowForSession).with_shortcut('s'))
+            .with_button(ConfirmButton::new("Always here", PermissionDecision::AlwaysAllow).with_shortcut('p'))
+            .with_button(ConfirmButton::new("Deny", PermissionDecision::Deny).with_shortcut('d').with_shortcut('n'))
+            .with_cancel(PermissionDecision::Deny);
+
+        Self {
+            state: ComponentState::new(),
+            config,
+            context,
+            message,
+            choices,
+            scroll: 0,
+        }
+    }
+
+    /// Set the event sender for this dialog
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<Event>) {
+        self.choices.set_event_sender(sender);
+    }
+
+    /// Whether the request has been answered
+    pub fn is_answered(&self) -> bool {
+        self.choices.is_answered()
+    }
+
+    /// Answer the request and close the dialog
+    pub async fn decide(&mut self, decision: PermissionDecision) -> Result<()> {
+        self.choices.answer(decision);
+        Ok(())
+    }
+
+    /// Lines previewing what the call will do
+    fn preview_lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let arguments = self.context.arguments.clone().unwrap_or(Value::Null);
+        let removed = Style::default().fg(theme.error);
+        let added = Style::default().fg(theme.success);
+
+        let mut lines = Vec::new();
+        match self.context.tool_name.as_str() {
+            "edit" => diff_lines(&arguments, removed, added, &mut lines),
+            "multiedit" => {
+                let edits = arguments.get("edits").and_then(Value::as_array).cloned().unwrap_or_default();
+                for (index, edit) in edits.iter().enumerate() {
+                    lines.push(Line::from(Span::styled(
+                        format!("@@ edit {} of {}", index + 1, edits.len()),
+                        Style::default().fg(theme.fg_muted),
+                    )));
+                    diff_lines(edit, removed, added, &mut lines);
+                }
+            }
+            "write" => {
+                let content = arguments.get("content").and_then(Value::as_str).unwrap_or_default();
+                lines.extend(content.lines().map(|line| Line::from(Span::styled(format!("+ {}", line), added))));
+            }
+            "git_commit" => {
+                let message = arguments.get("message").and_then(Value::as_str).unwrap_or_default();
+                lines.extend(message.lines().map(|line| Line::from(line.to_string())));
+                let paths = arguments.get("paths").and_then(Value::as_array).cloned().unwrap_or_default();
+                if !paths.is_empty() {
+                    lines.push(Line::from(""));
+                    for path in paths.iter().filter_map(Value::as_str) {
+                        lines.push(Line::from(Span::styled(format!("+ stage {}", path), added)));
+                    }
+                }
+            }
+            "bash" | "bash_background" => {
+                let command = self.context.command.clone().unwrap_or_default();
+                lines.extend(command.lines().map(|line| Line::from(format!("$ {}", line))));
+            }
+            _ => {
+                let pretty = serde_json::to_string_pretty(&arguments).unwrap_or_default();
+                lines.extend(pretty.lines().map(|line| Line::from(line.to_string())));
+            }
+        }
+        lines
+    }
+
+    /// Lines naming the tool, target and risk level, and asking
+    fn summary_lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let context = &self.context;
+        let label = Style::default().fg(theme.fg_muted);
+        let (risk, risk_color) = risk_label(&context.risk_level, theme);
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Tool: ", label),
+                Span::styled(context.tool_name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+            ]),
+            Line::from(vec![
+                Span::styled("Risk: ", label),
+                Span::styled(risk, Style::default().fg(risk_color).add_modifier(Modifier::BOLD)),
+            ]),
+        ];
+        if let Some(path) = &context.file_path {
+            lines.push(Line::from(vec![Span::styled("File: ", label), Span::raw(path.display().to_string())]));
+        }
+        for flag in &context.flags {
+            lines.push(Line::from(Span::styled(format!("⚠ The command {}", flag), Style::default().fg(theme.error))));
+        }
+        lines.push(Line::from(Span::styled(self.message.clone(), Style::default().fg(theme.fg_base))));
+        lines
+    }
+
+    /// Render the tool, target and risk level
+    fn render_summary(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        frame.render_widget(Paragraph::new(self.summary_lines(theme)).wrap(Wrap { trim: false }), area);
+    }
+
+    /// The summary and preview, for interfaces that print them above a
+    /// smaller prompt instead of drawing the whole dialog
+    pub fn transcript(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let mut lines = self.summary_lines(theme);
+        lines.extend(self.preview_lines(theme));
+        lines.push(Line::from(""));
+        lines
+    }
+
+    /// Render only the question and the answers, for a viewport a few lines
+    /// tall; the buttons are left out when they don't fit, as the help line
+    /// names their keys
+    pub fn render_compact(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let (risk, risk_color) = risk_label(&self.context.risk_level, theme);
+        let buttons = if area.height >= 5 { 3 } else { 0 };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),       // Question
+                Constraint::Length(buttons), // Buttons
+                Constraint::Min(0),
+                Constraint::Length(1),       // Help text
+            ])
+            .split(area);
+
+        let question = Line::from(vec![
+            Span::styled(format!("Allow {}? ", self.context.tool_name), Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD)),
+            Span::styled(risk, Style::default().fg(risk_color)),
+        ]);
+        frame.render_widget(Paragraph::new(question), chunks[0]);
+        if buttons > 0 {
+            self.choices.render_buttons(frame, chunks[1], theme);
+        }
+        let help = Paragraph::new(self.choices.help_text()).style(Style::default().fg(theme.fg_muted));
+        frame.render_widget(help, chunks[3]);
+    }
+
+    /// Handle a key, scrolling the preview or answering
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            _ => {
+                self.choices.handle_key(key);
+            }
+        }
+    }
+
+    /// Render the arguments or diff preview
+    fn render_preview(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let preview = Paragraph::new(self.preview_lines(theme))
+            .style(Style::default().fg(theme.fg_base))
+            .block(Block::default().borders(Borders::ALL).title("Preview"))
+            .scroll((self.scroll, 0));
+        frame.render_widget(preview, area);
+    }
+
+    /// Render help text
+    fn render_help(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let help = Paragraph::new(format!("{} • ↑/↓: Scroll", self.choices.help_text()))
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
+            .alignment(Alignment::Center);
+        frame.render_widget(help, area);
+    }
+}
+
+/// Diff preview of one `old_string`/`new_string` replacement
+fn diff_lines(edit: &Value, removed: Style, added: Style, lines: &mut Vec<Line<'static>>) {
+    let text = |key: &str| edit.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    lines.extend(text("old_string").lines().map(|line| Line::from(Span::styled(format!("- {}", line), removed))));
+    lines.extend(text("new_string").lines().map(|line| Line::from(Span::styled(format!("+ {}", line), added))));
+}
+
+/// Name and color of a risk level
+fn risk_label(level: &PermissionLevel, theme: &Theme) -> (&'static str, Color) {
+    match level {
+        PermissionLevel::Read => ("read", theme.success),
+        PermissionLevel::Write => ("modifies files", theme.warning),
+        PermissionLevel::Execute => ("runs commands", theme.warning),
+        PermissionLevel::Network => ("network access", theme.warning),
+        PermissionLevel::Dangerous => ("dangerous", theme.error),
+    }
+}
+
+#[async_trait]
+impl Component for PermissionDialog {
+    async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        self.handle_key(event);
+        Ok(())
+    }
+
+    async fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.render_content(frame, area, theme);
+    }
+
+    fn size(&self) -> Rect {
+        self.state.size
+    }
+
+    fn set_size(&mut self, size: Rect) {
+        self.state.size = size;
+    }
+
+    fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.state.has_focus = focus;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.state.is_visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.state.is_visible = visible;
+    }
+}
+
+#[async_trait]
+impl Dialog for PermissionDialog {
+    fn config(&self) -> &DialogConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut DialogConfig {
+        &mut self.config
+    }
+
+    fn position(&self, available_area: Rect) -> (u16, u16) {
+        let (width, height) = self.dialog_size(available_area);
+        let x = available_area.x + (available_area.width.saturating_sub(width)) / 2;
+        let y = available_area.y + (available_area.height.saturating_sub(height)) / 2;
+        (x, y)
+    }
+
+    fn dialog_size(&self, available_area: Rect) -> (u16, u16) {
+        let width = (available_area.width as f32 * 0.7) as u16;
+        let height = (available_area.height as f32 * 0.7) as u16;
+        (width.max(50), height.max(18))
+    }
+
+    fn render_content(&mut self, frame: &mut Frame, content_area: Rect, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(5),   // Summary
+                Constraint::Min(5),      // Preview
+                Constraint::Length(3),   // Buttons
+                Constraint::Length(1),   // Help text
+            ])
+            .split(content_area);
+
+        self.render_summary(frame, chunks[0], theme);
+        self.render_preview(frame, chunks[1], theme);
+        self.choices.render_buttons(frame, chunks[2], theme);
+        self.render_help(frame, chunks[3], theme);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (40, 18)
+    }
+
+    fn preferred_size(&self) -> (u16, u16) {
+        (70, 25)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permission::PermissionContext;
+    use crossterm::event::KeyModifiers;
+    use std::collections::HashMap;
+
+    fn edit_request() -> (PermissionRequest, tokio::sync::oneshot::Receiver<PermissionDecision>) {
+        let arguments = HashMap::from([
+            ("file_path".to_string(), Value::from("src/main.rs")),
+            ("old_string".to_string(), Value::from("let a = 1;")),
+            ("new_string".to_string(), Value::from("let a = 2;\nlet b = 3;")),
+        ]);
+        let context = PermissionContext::for_tool_call("edit", &arguments);
+        PermissionRequest::new(context, "Tool 'edit' wants to modify files. Allow operation?".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_keys_answer_the_request() {
+        let (request, response) = edit_request();
+        let mut dialog = PermissionDialog::new(request);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        dialog.set_event_sender(tx);
+
+        dialog.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)).await.unwrap();
+        assert!(dialog.is_answered());
+        assert_eq!(response.await.unwrap(), PermissionDecision::AllowForSession);
+        assert!(matches!(rx.try_recv(), Ok(Event::Custom(name, _)) if name == "dialog_close_request"));
+    }
+
+    #[tokio::test]
+    async fn test_closing_unanswered_denies() {
+        let (request, response) = edit_request();
+        drop(PermissionDialog::new(request));
+        assert!(response.await.is_err());
+    }
+
+    #[test]
+    fn test_edit_is_previewed_as_diff() {
+        let (request, _response) = edit_request();
+        let dialog = PermissionDialog::new(request);
+        let text: Vec<String> = dialog
+            .preview_lines(&crate::tui::themes::presets::goofy_dark())
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text, ["- let a = 1;", "+ let a = 2;", "+ let b = 3;"]);
+    }
+}
@@ -0,0 +1,398 @@
+//! Recent files switcher
+//!
+//! Lists the files the agent read or edited this session, most recently
+//! touched first, from the tool manager's file tracker rather than from the
+//! disk. Typing filters the list fuzzily; the chosen file is opened in the
+//! file viewer or attached to the next prompt.
+
+use super::types::{Dialog, DialogConfig, DialogPosition, DialogSize, dialog_ids};
+use crate::{
+    fswatch::TouchedFile,
+    tui::{
+        components::{completions::fuzzy_score, Component, ComponentState},
+        events::Event,
+        themes::Theme,
+        Frame,
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+/// What to do with the chosen file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecentFileAction {
+    /// Show the file in the file viewer
+    Open(PathBuf),
+    /// Attach the file to the next prompt
+    Attach(PathBuf),
+}
+
+/// Dialog for switching to a file the agent touched
+pub struct RecentFilesDialog {
+    /// Component state
+    state: ComponentState,
+
+    /// Dialog configuration
+    config: DialogConfig,
+
+    /// Event sender for dialog events
+    event_sender: Option<mpsc::UnboundedSender<Event>>,
+
+    /// Touched files, most recent first, with the path shown for each
+    files: Vec<(TouchedFile, String)>,
+
+    /// Filter being typed
+    query: String,
+
+    /// Indices into `files` matching the query, best match first
+    matches: Vec<usize>,
+
+    /// List state for navigation
+    list_state: ListState,
+
+    /// Chosen action, until the application takes it
+    action: Option<RecentFileAction>,
+
+    /// Whether the dialog was closed
+    closed: bool,
+}
+
+impl RecentFilesDialog {
+    /// Create a switcher over the given files, most recent first
+    ///
+    /// Paths below `root` are shown relative to it.
+    pub fn new(files: Vec<TouchedFile>, root: Option<&Path>) -> Self {
+        let config = DialogConfig::new(dialog_ids::recent_files())
+            .with_title("Recent Files".to_string())
+            .with_position(DialogPosition::Center)
+            .with_size(DialogSize::Percentage(60, 60))
+            .with_border(true)
+            .modal(true)
+            .closable(true);
+
+        let files = files
+            .into_iter()
+            .map(|file| {
+                let shown = root
+                    .and_then(|root| file.path.strip_prefix(root).ok())
+                    .unwrap_or(&file.path)
+                    .display()
+                    .to_string();
+                (file, shown)
+            })
+            .collect();
+
+        let mut dialog = Self {
+            state: ComponentState::new(),
+            config,
+            event_sender: None,
+            files,
+            query: String::new(),
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            action: None,
+            closed: false,
+        };
+        dialog.filter();
+        dialog
+    }
+
+    /// Set the event sender for this dialog
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<Event>) {
+        self.event_sender = Some(sender);
+    }
+
+    /// Whether the dialog was closed, with or without a choice
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Take the action chosen by the user
+    pub fn take_action(&mut self) -> Option<RecentFileAction> {
+        self.action.take()
+    }
+
+    /// Match the files against the query
+    ///
+    /// Equally good matches keep their recency order.
+    fn filter(&mut self) {
+        let query = self.query.trim();
+        let mut scored: Vec<(usize, f64)> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(index, (_, shown))| (index, fuzzy_score(shown, query)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        self.matches = scored.into_iter().map(|(index, _)| index).collect();
+        self.list_state.select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    /// The selected file
+    fn selected(&self) -> Option<&TouchedFile> {
+        let index = self.matches.get(self.list_state.selected()?)?;
+        self.files.get(*index).map(|(file, _)| file)
+    }
+
+    /// Move the selection by one file, wrapping around
+    fn move_selection(&mut self, down: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let count = self.matches.len();
+        let current = self.list_state.selected().unwrap_or(0);
+        let next = if down { (current + 1) % count } else { (current + count - 1) % count };
+        self.list_state.select(Some(next));
+    }
+
+    /// Choose what to do with the selected file and close
+    async fn choose(&mut self, attach: bool) -> Result<()> {
+        let Some(path) = self.selected().map(|file| file.path.clone()) else {
+            return Ok(());
+        };
+        self.action = Some(if attach { RecentFileAction::Attach(path) } else { RecentFileAction::Open(path) });
+        self.close_dialog().await
+    }
+
+    /// Close the dialog
+    async fn close_dialog(&mut self) -> Result<()> {
+        self.closed = true;
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(Event::Custom(
+                "dialog_close_request".to_string(),
+                serde_json::json!({"dialog_id": self.config.id.as_str()}),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render the filter input
+    fn render_query(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let (text, style) = if self.query.is_empty() {
+            ("Type to filter...", Style::default().fg(theme.fg_muted))
+        } else {
+            (self.query.as_str(), Style::default().fg(theme.fg_base))
+        };
+
+        let input = Paragraph::new(text)
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title("Filter"));
+        frame.render_widget(input, area);
+    }
+
+    /// Render the matching files
+    fn render_files(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        if self.matches.is_empty() {
+            let text = if self.files.is_empty() {
+                "The agent has not read or edited any files yet."
+            } else {
+                "No matching files."
+            };
+            let placeholder = Paragraph::new(text)
+                .style(Style::default().fg(theme.fg_muted))
+                .alignment(Alignment::Center);
+            frame.render_widget(placeholder, area);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&index| {
+                let (file, shown) = &self.files[index];
+                let (icon, label) = if file.edited {
+                    (theme.icons.edit.clone(), "edited")
+                } else {
+                    (theme.icons.file.clone(), "read")
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", icon)),
+                    Span::raw(shown.clone()),
+                    Span::styled(format!("  {}", label), Style::default().fg(theme.fg_muted)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .style(Style::default().fg(theme.fg_base))
+            .highlight_style(
+                Style::default()
+                    .bg(theme.primary)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            )
+            .highlight_symbol("► ");
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+    }
+
+    /// Render help text
+    fn render_help(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let help = Paragraph::new("Enter: Open • Tab: Attach to prompt • ↑/↓: Navigate • Esc: Close")
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
+            .alignment(Alignment::Center);
+        frame.render_widget(help, area);
+    }
+}
+
+#[async_trait]
+impl Component for RecentFilesDialog {
+    async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            KeyCode::Esc => self.close_dialog().await?,
+            KeyCode::Up => self.move_selection(false),
+            KeyCode::Down => self.move_selection(true),
+            KeyCode::Char('p') if ctrl => self.move_selection(false),
+            KeyCode::Char('n') if ctrl => self.move_selection(true),
+            KeyCode::Enter => self.choose(false).await?,
+            KeyCode::Tab => self.choose(true).await?,
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.render_content(frame, area, theme);
+    }
+
+    fn size(&self) -> Rect {
+        self.state.size
+    }
+
+    fn set_size(&mut self, size: Rect) {
+        self.state.size = size;
+    }
+
+    fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.state.has_focus = focus;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.state.is_visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.state.is_visible = visible;
+    }
+}
+
+#[async_trait]
+impl Dialog for RecentFilesDialog {
+    fn config(&self) -> &DialogConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut DialogConfig {
+        &mut self.config
+    }
+
+    fn position(&self, available_area: Rect) -> (u16, u16) {
+        let (width, height) = self.dialog_size(available_area);
+        let x = available_area.x + (available_area.width.saturating_sub(width)) / 2;
+        let y = available_area.y + (available_area.height.saturating_sub(height)) / 2;
+        (x, y)
+    }
+
+    fn dialog_size(&self, available_area: Rect) -> (u16, u16) {
+        let width = (available_area.width as f32 * 0.6) as u16;
+        let height = (available_area.height as f32 * 0.6) as u16;
+        (width.max(40), height.max(12))
+    }
+
+    fn render_content(&mut self, frame: &mut Frame, content_area: Rect, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),   // Filter
+                Constraint::Min(3),      // Files
+                Constraint::Length(1),   // Help text
+            ])
+            .split(content_area);
+
+        self.render_query(frame, chunks[0], theme);
+        self.render_files(frame, chunks[1], theme);
+        self.render_help(frame, chunks[2], theme);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (40, 12)
+    }
+
+    fn preferred_size(&self) -> (u16, u16) {
+        (60, 20)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fswatch::FileAccess;
+
+    fn touched(path: &str, edited: bool) -> TouchedFile {
+        TouchedFile {
+            path: PathBuf::from(path),
+            last_access: if edited { FileAccess::Edit } else { FileAccess::Read },
+            edited,
+        }
+    }
+
+    async fn type_keys(dialog: &mut RecentFilesDialog, keys: &[KeyCode]) {
+        for key in keys {
+            dialog.handle_key_event(KeyEvent::new(*key, KeyModifiers::NONE)).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_and_choose() {
+        let files = vec![
+            touched("/work/src/main.rs", true),
+            touched("/work/README.md", false),
+            touched("/work/src/tui/app.rs", false),
+        ];
+        let mut dialog = RecentFilesDialog::new(files, Some(Path::new("/work")));
+        assert_eq!(dialog.selected().unwrap().path, Path::new("/work/src/main.rs"));
+
+        type_keys(&mut dialog, &[KeyCode::Char('a'), KeyCode::Char('p'), KeyCode::Char('p')]).await;
+        assert_eq!(dialog.matches.len(), 1);
+        type_keys(&mut dialog, &[KeyCode::Tab]).await;
+        assert!(dialog.is_closed());
+        assert_eq!(dialog.take_action(), Some(RecentFileAction::Attach(PathBuf::from("/work/src/tui/app.rs"))));
+        assert_eq!(dialog.take_action(), None);
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_keeps_recency_order() {
+        let files = vec![touched("/b.rs", false), touched("/a.rs", false)];
+        let mut dialog = RecentFilesDialog::new(files, None);
+        type_keys(&mut dialog, &[KeyCode::Down, KeyCode::Enter]).await;
+        assert_eq!(dialog.take_action(), Some(RecentFileAction::Open(PathBuf::from("/a.rs"))));
+    }
+}
@@ -0,0 +1,374 @@
+//! Semantic session search dialog
+//!
+//! Finds past conversations by meaning rather than by title: the query is
+//! embedded and compared with the indexed messages of every session. Picking
+//! a result switches to its session and scrolls to the matching message,
+//! restoring the session first if it was archived.
+
+use super::types::{Dialog, DialogConfig, DialogPosition, DialogSize, dialog_ids};
+use crate::{
+    session::{SemanticMatch, SessionManager},
+    tui::{
+        components::{Component, ComponentState},
+        events::Event,
+        themes::Theme,
+        Frame,
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Results shown for a query
+const RESULT_LIMIT: usize = 20;
+
+/// Dialog for searching past sessions by meaning
+pub struct SemanticSearchDialog {
+    /// Component state
+    state: ComponentState,
+
+    /// Dialog configuration
+    config: DialogConfig,
+
+    /// Session manager that owns the embedding index
+    session_manager: Option<Arc<SessionManager>>,
+
+    /// Event sender for dialog events
+    event_sender: Option<mpsc::UnboundedSender<Event>>,
+
+    /// Query being typed
+    query: String,
+
+    /// Query the current results belong to
+    searched_query: Option<String>,
+
+    /// Matches of the last search
+    results: Vec<SemanticMatch>,
+
+    /// List state for navigation
+    list_state: ListState,
+
+    /// Loading state
+    is_searching: bool,
+
+    /// Error message if any
+    error_message: Option<String>,
+}
+
+impl SemanticSearchDialog {
+    /// Create a new semantic search dialog
+    pub fn new() -> Self {
+        let config = DialogConfig::new(dialog_ids::semantic_search())
+            .with_title("Search Sessions".to_string())
+            .with_position(DialogPosition::Center)
+            .with_size(DialogSize::Percentage(70, 80))
+            .with_border(true)
+            .modal(true)
+            .closable(true);
+
+        Self {
+            state: ComponentState::new(),
+            config,
+            session_manager: None,
+            event_sender: None,
+            query: String::new(),
+            searched_query: None,
+            results: Vec::new(),
+            list_state: ListState::default(),
+            is_searching: false,
+            error_message: None,
+        }
+    }
+
+    /// Set the session manager used for searching
+    pub fn with_session_manager(mut self, manager: Arc<SessionManager>) -> Self {
+        self.session_manager = Some(manager);
+        self
+    }
+
+    /// Set the event sender for this dialog
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<Event>) {
+        self.event_sender = Some(sender);
+    }
+
+    /// Run the search for the current query
+    pub async fn search(&mut self) -> Result<()> {
+        let query = self.query.trim().to_string();
+        if query.is_empty() {
+            return Ok(());
+        }
+        let Some(manager) = self.session_manager.clone() else {
+            self.error_message = Some("Semantic search is not available".to_string());
+            return Ok(());
+        };
+
+        self.is_searching = true;
+        self.error_message = None;
+        match manager.semantic_search(&query, RESULT_LIMIT).await {
+            Ok(results) => {
+                self.results = results;
+                self.list_state.select(if self.results.is_empty() { None } else { Some(0) });
+            }
+            Err(e) => {
+                self.results.clear();
+                self.list_state.select(None);
+                self.error_message = Some(e.to_string());
+            }
+        }
+        self.searched_query = Some(query);
+        self.is_searching = false;
+        Ok(())
+    }
+
+    /// Open the selected result's session
+    async fn open_selected(&self) -> Result<()> {
+        let Some(result) = self.list_state.selected().and_then(|i| self.results.get(i)) else {
+            return Ok(());
+        };
+        if result.archived {
+            if let Some(manager) = &self.session_manager {
+                manager.unarchive_session(&result.session_id).await?;
+            }
+        }
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(Event::Custom(
+                "session_selected".to_string(),
+                serde_json::json!({
+                    "session_id": result.session_id,
+                    "message_id": result.message_id,
+                }),
+            ));
+        }
+        self.close_dialog().await
+    }
+
+    /// Move the selection by one result, wrapping around
+    fn move_selection(&mut self, down: bool) {
+        if self.results.is_empty() {
+            return;
+        }
+        let count = self.results.len();
+        let current = self.list_state.selected().unwrap_or(0);
+        let next = if down { (current + 1) % count } else { (current + count - 1) % count };
+        self.list_state.select(Some(next));
+    }
+
+    /// Close the dialog
+    async fn close_dialog(&self) -> Result<()> {
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(Event::Custom(
+                "dialog_close_request".to_string(),
+                serde_json::json!({"dialog_id": self.config.id.as_str()}),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render the query input
+    fn render_query(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let (text, style) = if self.query.is_empty() {
+            ("Describe what you are looking for...", Style::default().fg(theme.fg_muted))
+        } else {
+            (self.query.as_str(), Style::default().fg(theme.fg_base))
+        };
+
+        let input = Paragraph::new(text)
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title("Query"));
+        frame.render_widget(input, area);
+    }
+
+    /// Render the matches
+    fn render_results(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let placeholder = if self.is_searching {
+            Some("Searching...".to_string())
+        } else if let Some(error) = &self.error_message {
+            Some(error.clone())
+        } else if self.searched_query.is_some() && self.results.is_empty() {
+            Some("No matching conversations.".to_string())
+        } else if self.results.is_empty() {
+            Some("Press Enter to search.".to_string())
+        } else {
+            None
+        };
+
+        if let Some(text) = placeholder {
+            let style = if self.error_message.is_some() {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default().fg(theme.fg_muted)
+            };
+            frame.render_widget(Paragraph::new(text).style(style).alignment(Alignment::Center), area);
+            return;
+        }
+
+        let snippet_width = area.width.saturating_sub(4) as usize;
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|result| {
+                let snippet: String = result
+                    .snippet
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .chars()
+                    .take(snippet_width)
+                    .collect();
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(result.session_title.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                        Span::styled(
+                            if result.archived { "  (archived)" } else { "" },
+                            Style::default().fg(theme.fg_muted),
+                        ),
+                        Span::styled(
+                            format!("  {:.0}%", result.score.max(0.0) * 100.0),
+                            Style::default().fg(theme.fg_muted),
+                        ),
+                    ]),
+                    Line::from(Span::styled(format!("  {}", snippet), Style::default().fg(theme.fg_muted))),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .style(Style::default().fg(theme.fg_base))
+            .highlight_style(
+                Style::default()
+                    .bg(theme.primary)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            )
+            .highlight_symbol("► ");
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+    }
+
+    /// Render help text
+    fn render_help(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let help = Paragraph::new("Type to edit • Enter: Search / Open • ↑/↓: Navigate • Esc: Close")
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
+            .alignment(Alignment::Center);
+        frame.render_widget(help, area);
+    }
+}
+
+#[async_trait]
+impl Component for SemanticSearchDialog {
+    async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        match event.code {
+            KeyCode::Esc => self.close_dialog().await?,
+            KeyCode::Up => self.move_selection(false),
+            KeyCode::Down => self.move_selection(true),
+            KeyCode::Enter => {
+                // A changed query searches again; otherwise open the selection
+                if self.searched_query.as_deref() == Some(self.query.trim()) && !self.results.is_empty() {
+                    self.open_selected().await?;
+                } else {
+                    self.search().await?;
+                }
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Char(c) => self.query.push(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.render_content(frame, area, theme);
+    }
+
+    fn size(&self) -> Rect {
+        self.state.size
+    }
+
+    fn set_size(&mut self, size: Rect) {
+        self.state.size = size;
+    }
+
+    fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.state.has_focus = focus;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.state.is_visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.state.is_visible = visible;
+    }
+}
+
+#[async_trait]
+impl Dialog for SemanticSearchDialog {
+    fn config(&self) -> &DialogConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut DialogConfig {
+        &mut self.config
+    }
+
+    fn position(&self, available_area: Rect) -> (u16, u16) {
+        let (width, height) = self.dialog_size(available_area);
+        let x = available_area.x + (available_area.width.saturating_sub(width)) / 2;
+        let y = available_area.y + (available_area.height.saturating_sub(height)) / 2;
+        (x, y)
+    }
+
+    fn dialog_size(&self, available_area: Rect) -> (u16, u16) {
+        let width = (available_area.width as f32 * 0.7) as u16;
+        let height = (available_area.height as f32 * 0.8) as u16;
+        (width.max(50), height.max(15))
+    }
+
+    fn render_content(&mut self, frame: &mut Frame, content_area: Rect, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),   // Query
+                Constraint::Min(5),      // Results
+                Constraint::Length(1),   // Help text
+            ])
+            .split(content_area);
+
+        self.render_query(frame, chunks[0], theme);
+        self.render_results(frame, chunks[1], theme);
+        self.render_help(frame, chunks[2], theme);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (40, 15)
+    }
+
+    fn preferred_size(&self) -> (u16, u16) {
+        (70, 25)
+    }
+}
+
+impl Default for SemanticSearchDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,514 @@
+//! Session comparison
+//!
+//! Two sessions are picked from the list, first the one to compare from and
+//! then the one to compare with. The application loads their comparison and
+//! the dialog shows it turn by turn, with the assistant's answers diffed word
+//! by word: removed words struck through, added words highlighted.
+
+use super::types::{Dialog, DialogConfig, DialogPosition, DialogSize, dialog_ids};
+use crate::{
+    session::{ComparedTurn, Session, SessionComparison, WordChange},
+    tui::{
+        components::{Component, ComponentState},
+        events::Event,
+        themes::Theme,
+        Frame,
+    },
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph, Wrap},
+};
+use tokio::sync::mpsc;
+
+/// Dialog for picking two sessions and reading their differences
+pub struct SessionDiffDialog {
+    /// Component state
+    state: ComponentState,
+
+    /// Dialog configuration
+    config: DialogConfig,
+
+    /// Event sender for dialog events
+    event_sender: Option<mpsc::UnboundedSender<Event>>,
+
+    /// Sessions to pick from, most recent first
+    sessions: Vec<Session>,
+
+    /// List state for navigation
+    list_state: ListState,
+
+    /// Session picked first, to compare from
+    first: Option<String>,
+
+    /// Pair of sessions picked, until the application takes it
+    request: Option<(String, String)>,
+
+    /// The comparison being shown, once loaded
+    comparison: Option<SessionComparison>,
+
+    /// Lines scrolled past in the comparison
+    scroll: u16,
+
+    /// Why the comparison could not be loaded
+    error: Option<String>,
+
+    /// Whether the dialog was closed
+    closed: bool,
+}
+
+impl SessionDiffDialog {
+    /// Create a picker over the given sessions, most recent first
+    pub fn new(sessions: Vec<Session>) -> Self {
+        let config = DialogConfig::new(dialog_ids::session_diff())
+            .with_title("Compare Sessions".to_string())
+            .with_position(DialogPosition::Center)
+            .with_size(DialogSize::Percentage(80, 80))
+            .with_border(true)
+            .modal(true)
+            .closable(true);
+
+        let mut list_state = ListState::default();
+        list_state.select(if sessions.is_empty() { None } else { Some(0) });
+
+        Self {
+            state: ComponentState::new(),
+            config,
+            event_sender: None,
+            sessions,
+            list_state,
+            first: None,
+            request: None,
+            comparison: None,
+            scroll: 0,
+            error: None,
+            closed: false,
+        }
+    }
+
+    /// Set the event sender for this dialog
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<Event>) {
+        self.event_sender = Some(sender);
+    }
+
+    /// Whether the dialog was closed
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Take the two sessions to compare, first and second
+    pub fn take_request(&mut self) -> Option<(String, String)> {
+        self.request.take()
+    }
+
+    /// Show the comparison of the picked sessions
+    pub fn show(&mut self, comparison: SessionComparison) {
+        self.comparison = Some(comparison);
+        self.scroll = 0;
+        self.error = None;
+    }
+
+    /// Tell why the picked sessions can't be compared, and pick again
+    pub fn show_error(&mut self, error: String) {
+        self.error = Some(error);
+        self.first = None;
+    }
+
+    /// Pick the selected session, asking for the comparison once two are picked
+    fn pick(&mut self) {
+        let Some(session) = self.list_state.selected().and_then(|index| self.sessions.get(index)) else {
+            return;
+        };
+        match self.first.take() {
+            // Picking the first session again unpicks it
+            Some(first) if first == session.id => {}
+            Some(first) => self.request = Some((first, session.id.clone())),
+            None => self.first = Some(session.id.clone()),
+        }
+        self.error = None;
+    }
+
+    /// Move the selection by one session, wrapping around
+    fn move_selection(&mut self, down: bool) {
+        if self.sessions.is_empty() {
+            return;
+        }
+        let count = self.sessions.len();
+        let current = self.list_state.selected().unwrap_or(0);
+        let next = if down { (current + 1) % count } else { (current + count - 1) % count };
+        self.list_state.select(Some(next));
+    }
+
+    /// Scroll to the next or previous turn that changed
+    fn jump_to_change(&mut self, forward: bool) {
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+        let starts = changed_turn_lines(comparison);
+        let scroll = self.scroll as usize;
+        let target = if forward {
+            starts.into_iter().find(|&line| line > scroll)
+        } else {
+            starts.into_iter().rev().find(|&line| line < scroll)
+        };
+        if let Some(line) = target {
+            self.scroll = line as u16;
+        }
+    }
+
+    /// Close the dialog
+    async fn close_dialog(&mut self) -> Result<()> {
+        self.closed = true;
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(Event::Custom(
+                "dialog_close_request".to_string(),
+                serde_json::json!({"dialog_id": self.config.id.as_str()}),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render the sessions to pick from
+    fn render_picker(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),   // What to pick
+                Constraint::Min(3),      // Sessions
+                Constraint::Length(1),   // Help text
+            ])
+            .split(area);
+
+        let prompt = match (&self.error, &self.first) {
+            (Some(error), _) => Line::from(Span::styled(error.clone(), Style::default().fg(theme.error))),
+            (None, Some(_)) => Line::from("Pick the session to compare with"),
+            (None, None) => Line::from("Pick the first session"),
+        };
+        frame.render_widget(Paragraph::new(prompt), chunks[0]);
+
+        if self.sessions.is_empty() {
+            let placeholder = Paragraph::new("No sessions to compare.")
+                .style(Style::default().fg(theme.fg_muted))
+                .alignment(Alignment::Center);
+            frame.render_widget(placeholder, chunks[1]);
+        } else {
+            let items: Vec<ListItem> = self
+                .sessions
+                .iter()
+                .map(|session| {
+                    let marker = if self.first.as_deref() == Some(session.id.as_str()) { "A " } else { "  " };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(marker, Style::default().fg(theme.info).add_modifier(Modifier::BOLD)),
+                        Span::raw(session.title.clone()),
+                        Span::styled(
+                            format!(
+                                "  {} · {} messages",
+                                session.updated_at.format("%Y-%m-%d %H:%M"),
+                                session.message_count
+                            ),
+                            Style::default().fg(theme.fg_muted),
+                        ),
+                    ]))
+                })
+                .collect();
+
+            let list = List::new(items)
+                .style(Style::default().fg(theme.fg_base))
+                .highlight_style(
+                    Style::default()
+                        .bg(theme.primary)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD)
+                )
+                .highlight_symbol("► ");
+            frame.render_stateful_widget(list, chunks[1], &mut self.list_state);
+        }
+
+        self.render_help(frame, chunks[2], theme, "Enter: Pick • ↑/↓: Navigate • Esc: Close");
+    }
+
+    /// Render the comparison of the picked sessions
+    fn render_comparison(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),      // Comparison
+                Constraint::Length(1),   // Help text
+            ])
+            .split(area);
+
+        let text = Paragraph::new(comparison_lines(comparison, theme))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(text, chunks[0]);
+
+        self.render_help(frame, chunks[1], theme, "↑/↓: Scroll • n/p: Next/previous change • Backspace: Pick again • Esc: Close");
+    }
+
+    /// Render help text
+    fn render_help(&self, frame: &mut Frame, area: Rect, theme: &Theme, text: &str) {
+        let help = Paragraph::new(text.to_string())
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
+            .alignment(Alignment::Center);
+        frame.render_widget(help, area);
+    }
+}
+
+/// The comparison as lines: a summary, then each turn with its word diff
+///
+/// Unchanged turns take a single line so the differences stand out.
+fn comparison_lines(comparison: &SessionComparison, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("A ", Style::default().fg(theme.info).add_modifier(Modifier::BOLD)),
+        Span::raw(comparison.first.title.clone()),
+        Span::styled("  B ", Style::default().fg(theme.info).add_modifier(Modifier::BOLD)),
+        Span::raw(comparison.second.title.clone()),
+        Span::styled(
+            format!("  {} of {} turns differ", comparison.changed_turns(), comparison.turns.len()),
+            Style::default().fg(theme.fg_muted),
+        ),
+    ])];
+
+    for (index, turn) in comparison.turns.iter().enumerate() {
+        lines.push(Line::from(""));
+        if turn.is_unchanged() {
+            lines.push(Line::styled(format!("Turn {} unchanged", index + 1), Style::default().fg(theme.fg_muted)));
+            continue;
+        }
+        lines.push(Line::styled(format!("Turn {}", index + 1), Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD)));
+        lines.extend(prompt_lines(turn, theme));
+
+        // Answers span several lines, and a change can start or end on any of them
+        let mut line = Vec::new();
+        for change in turn.answer_diff() {
+            let (text, style) = match change {
+                WordChange::Same(text) => (text, Style::default().fg(theme.fg_base)),
+                WordChange::Removed(text) => (text, Style::default().fg(theme.error).add_modifier(Modifier::CROSSED_OUT)),
+                WordChange::Added(text) => (text, Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
+            };
+            let mut parts = text.split('\n');
+            if let Some(part) = parts.next().filter(|part| !part.is_empty()) {
+                line.push(Span::styled(part.to_string(), style));
+            }
+            for part in parts {
+                lines.push(Line::from(std::mem::take(&mut line)));
+                if !part.is_empty() {
+                    line.push(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+        lines.push(Line::from(line));
+    }
+    lines
+}
+
+/// The prompt of a turn, or each session's prompt when they differ
+fn prompt_lines(turn: &ComparedTurn, theme: &Theme) -> Vec<Line<'static>> {
+    let prompt = |label: &'static str, text: Option<&str>| {
+        Line::from(vec![
+            Span::styled(label, Style::default().fg(theme.info).add_modifier(Modifier::BOLD)),
+            match text {
+                Some(text) => Span::styled(text.to_string(), Style::default().fg(theme.fg_base).add_modifier(Modifier::ITALIC)),
+                None => Span::styled("no such turn", Style::default().fg(theme.fg_muted)),
+            },
+        ])
+    };
+    match (&turn.first, &turn.second) {
+        (Some(first), _) if turn.same_prompt() => vec![prompt("> ", Some(&first.prompt))],
+        (first, second) => vec![
+            prompt("A> ", first.as_ref().map(|turn| turn.prompt.as_str())),
+            prompt("B> ", second.as_ref().map(|turn| turn.prompt.as_str())),
+        ],
+    }
+}
+
+/// Line where each changed turn starts in [`comparison_lines`]
+fn changed_turn_lines(comparison: &SessionComparison) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut line = 1;
+    for turn in &comparison.turns {
+        // Blank line, then the turn's heading
+        line += 1;
+        if turn.is_unchanged() {
+            line += 1;
+            continue;
+        }
+        starts.push(line);
+        let prompts = if turn.same_prompt() { 1 } else { 2 };
+        let answer = turn.answer_diff().iter().map(|change| match change {
+            WordChange::Same(text) | WordChange::Removed(text) | WordChange::Added(text) => text.matches('\n').count(),
+        }).sum::<usize>() + 1;
+        line += 1 + prompts + answer;
+    }
+    starts
+}
+
+#[async_trait]
+impl Component for SessionDiffDialog {
+    async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
+        if self.comparison.is_some() {
+            match event.code {
+                KeyCode::Esc => self.close_dialog().await?,
+                KeyCode::Backspace => self.comparison = None,
+                KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::Home => self.scroll = 0,
+                KeyCode::Char('n') => self.jump_to_change(true),
+                KeyCode::Char('p') => self.jump_to_change(false),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match event.code {
+            KeyCode::Esc => self.close_dialog().await?,
+            KeyCode::Up => self.move_selection(false),
+            KeyCode::Down => self.move_selection(true),
+            KeyCode::Char('p') if ctrl => self.move_selection(false),
+            KeyCode::Char('n') if ctrl => self.move_selection(true),
+            KeyCode::Enter => self.pick(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        let _ = event;
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.render_content(frame, area, theme);
+    }
+
+    fn size(&self) -> Rect {
+        self.state.size
+    }
+
+    fn set_size(&mut self, size: Rect) {
+        self.state.size = size;
+    }
+
+    fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.state.has_focus = focus;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.state.is_visible
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.state.is_visible = visible;
+    }
+}
+
+#[async_trait]
+impl Dialog for SessionDiffDialog {
+    fn config(&self) -> &DialogConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut DialogConfig {
+        &mut self.config
+    }
+
+    fn position(&self, available_area: Rect) -> (u16, u16) {
+        let (width, height) = self.dialog_size(available_area);
+        let x = available_area.x + (available_area.width.saturating_sub(width)) / 2;
+        let y = available_area.y + (available_area.height.saturating_sub(height)) / 2;
+        (x, y)
+    }
+
+    fn dialog_size(&self, available_area: Rect) -> (u16, u16) {
+        let width = (available_area.width as f32 * 0.8) as u16;
+        let height = (available_area.height as f32 * 0.8) as u16;
+        (width.max(50), height.max(15))
+    }
+
+    fn render_content(&mut self, frame: &mut Frame, content_area: Rect, theme: &Theme) {
+        if self.comparison.is_some() {
+            self.render_comparison(frame, content_area, theme);
+        } else {
+            self.render_picker(frame, content_area, theme);
+        }
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (50, 15)
+    }
+
+    fn preferred_size(&self) -> (u16, u16) {
+        (100, 40)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::TurnText;
+    use crate::tui::themes::ThemeManager;
+
+    async fn press(dialog: &mut SessionDiffDialog, keys: &[KeyCode]) {
+        for key in keys {
+            dialog.handle_key_event(KeyEvent::new(*key, KeyModifiers::NONE)).await.unwrap();
+        }
+    }
+
+    fn turn(prompt: &str, answer: &str) -> TurnText {
+        TurnText { prompt: prompt.to_string(), answer: answer.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_pick_two_sessions_and_jump_between_changes() {
+        let first = Session::new("Opus".to_string(), None);
+        let second = Session::new("Haiku".to_string(), None);
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        let mut dialog = SessionDiffDialog::new(vec![first.clone(), second.clone()]);
+
+        press(&mut dialog, &[KeyCode::Enter, KeyCode::Enter]).await;
+        assert_eq!(dialog.take_request(), None);
+        press(&mut dialog, &[KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+        assert_eq!(dialog.take_request(), Some((first_id, second_id)));
+
+        let comparison = SessionComparison {
+            first,
+            second,
+            turns: vec![
+                ComparedTurn { first: Some(turn("a", "one\ntwo")), second: Some(turn("a", "one\nthree")) },
+                ComparedTurn { first: Some(turn("b", "same")), second: Some(turn("b", "same")) },
+                ComparedTurn { first: Some(turn("c", "x")), second: None },
+            ],
+        };
+        let theme = ThemeManager::new();
+        let lines = comparison_lines(&comparison, theme.current_theme());
+        let text = |line: &Line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>();
+        let starts = changed_turn_lines(&comparison);
+        assert_eq!(starts, [2, 9]);
+        assert_eq!(text(&lines[2]), "Turn 1");
+        assert_eq!(text(&lines[5]), "twothree");
+        assert_eq!(text(&lines[9]), "Turn 3");
+
+        dialog.show(comparison);
+        press(&mut dialog, &[KeyCode::Char('n')]).await;
+        assert_eq!(dialog.scroll as usize, starts[0]);
+        press(&mut dialog, &[KeyCode::Char('n'), KeyCode::Char('n')]).await;
+        assert_eq!(dialog.scroll as usize, starts[1]);
+        press(&mut dialog, &[KeyCode::Backspace]).await;
+        assert!(dialog.comparison.is_none());
+    }
+}
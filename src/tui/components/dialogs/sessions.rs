@@ -252,7 +252,7 @@ impl SessionsDialog {
         
         if self.is_loading {
             let loading = Paragraph::new("Loading sessions...")
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(loading, area);
             return;
@@ -266,7 +266,7 @@ impl SessionsDialog {
             };
             
             let empty = Paragraph::new(empty_msg)
-                .style(Style::default().fg(theme.text_muted()))
+                .style(Style::default().fg(theme.fg_muted))
                 .alignment(Alignment::Center);
             frame.render_widget(empty, area);
             return;
@@ -287,7 +287,7 @@ impl SessionsDialog {
         
         let list = List::new(items)
             .block(Block::default())
-            .style(Style::default().fg(theme.text))
+            .style(Style::default().fg(theme.fg_base))
             .highlight_style(
                 Style::default()
                     .bg(theme.primary)
@@ -304,7 +304,7 @@ impl SessionsDialog {
         let search_style = if self.in_search_mode {
             Style::default().bg(theme.primary).fg(Color::White)
         } else {
-            Style::default().bg(theme.surface()).fg(theme.text)
+            Style::default().bg(theme.bg_overlay).fg(theme.fg_base)
         };
         
         let search_text = if self.filter_text.is_empty() && !self.in_search_mode {
@@ -329,7 +329,7 @@ impl SessionsDialog {
         };
         
         let help = Paragraph::new(help_text)
-            .style(Style::default().fg(theme.text_muted()).add_modifier(Modifier::DIM))
+            .style(Style::default().fg(theme.fg_muted).add_modifier(Modifier::DIM))
             .alignment(Alignment::Center);
         
         frame.render_widget(help, area);
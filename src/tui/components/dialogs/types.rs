@@ -577,19 +577,27 @@ pub mod dialog_ids {
     use super::DialogId;
     
     pub fn quit() -> DialogId { DialogId("quit".to_string()) }
+    pub fn confirm() -> DialogId { DialogId("confirm".to_string()) }
     pub fn commands() -> DialogId { DialogId("commands".to_string()) }
     pub fn sessions() -> DialogId { DialogId("sessions".to_string()) }
+    pub fn semantic_search() -> DialogId { DialogId("semantic_search".to_string()) }
     pub fn models() -> DialogId { DialogId("models".to_string()) }
     pub fn file_picker() -> DialogId { DialogId("file_picker".to_string()) }
+    pub fn recent_files() -> DialogId { DialogId("recent_files".to_string()) }
+    pub fn session_diff() -> DialogId { DialogId("session_diff".to_string()) }
     pub fn permissions() -> DialogId { DialogId("permissions".to_string()) }
     pub fn help() -> DialogId { DialogId("help".to_string()) }
     pub fn settings() -> DialogId { DialogId("settings".to_string()) }
     
     pub const QUIT: &str = "quit";
+    pub const CONFIRM: &str = "confirm";
     pub const COMMANDS: &str = "commands";
     pub const SESSIONS: &str = "sessions";
+    pub const SEMANTIC_SEARCH: &str = "semantic_search";
     pub const MODELS: &str = "models";
     pub const FILE_PICKER: &str = "file_picker";
+    pub const RECENT_FILES: &str = "recent_files";
+    pub const SESSION_DIFF: &str = "session_diff";
     pub const PERMISSIONS: &str = "permissions";
     pub const HELP: &str = "help";
     pub const SETTINGS: &str = "settings";
@@ -0,0 +1,245 @@
+//! Spotting code pasted without a fence
+//!
+//! Models read code more reliably, and answers render it better, when it sits
+//! in a fenced block tagged with its language. Before a draft is sent, the run
+//! of lines that looks like code and is not already inside a fence is found,
+//! and its language is guessed from the names of attached files first and
+//! from telltale patterns in the code second. The chat then offers to wrap
+//! the run in a fence.
+
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::sync::oneshot;
+
+use super::dialogs::{ConfirmButton, ConfirmDialog};
+
+/// Fewest code lines in a run worth fencing
+const MIN_CODE_LINES: usize = 3;
+
+/// Code pasted without a fence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unfenced {
+    /// Lines of the draft the code spans
+    pub lines: Range<usize>,
+    /// Language tag for the fence, empty when it could not be told
+    pub language: String,
+}
+
+/// What to do with a draft holding code without a fence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenceChoice {
+    Wrap,
+    SendAsIs,
+    KeepEditing,
+}
+
+impl Unfenced {
+    /// The question offering to fence the code
+    pub fn question(&self) -> String {
+        let what = match self.language.as_str() {
+            "" => "code".to_string(),
+            language => format!("{} code", language),
+        };
+        format!(
+            "Lines {}-{} look like {} without a fence. Wrap them in a ```{} block before sending?",
+            self.lines.start + 1,
+            self.lines.end,
+            what,
+            self.language
+        )
+    }
+
+    /// Dialog offering to fence the code, with the receiver the choice
+    /// arrives on; Escape keeps editing
+    pub fn offer(&self) -> (ConfirmDialog<FenceChoice>, oneshot::Receiver<FenceChoice>) {
+        let (dialog, response) = ConfirmDialog::new("Unfenced Code", self.question());
+        let dialog = dialog
+            .with_button(ConfirmButton::new("Wrap and send", FenceChoice::Wrap).with_shortcut('w'))
+            .with_button(ConfirmButton::new("Send as is", FenceChoice::SendAsIs).with_shortcut('s'))
+            .with_button(ConfirmButton::new("Keep editing", FenceChoice::KeepEditing).with_shortcut('e'))
+            .with_cancel(FenceChoice::KeepEditing);
+        (dialog, response)
+    }
+}
+
+/// Languages by file extension
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("mjs", "javascript"),
+    ("jsx", "jsx"),
+    ("ts", "typescript"),
+    ("tsx", "tsx"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("cs", "csharp"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("swift", "swift"),
+    ("sh", "bash"),
+    ("bash", "bash"),
+    ("sql", "sql"),
+    ("html", "html"),
+    ("css", "css"),
+    ("json", "json"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("toml", "toml"),
+    ("lua", "lua"),
+];
+
+/// Patterns telling languages apart, most specific first
+const SIGNS: &[(&str, &str)] = &[
+    ("rust", r"^\s*(pub(\(crate\))?\s+)?(fn|impl|struct|enum|trait|mod)\s+\w|\blet\s+mut\b|::\w+\(|\w+!\s*[\[(]|^\s*use\s+\w+(::\w+)+"),
+    ("go", r"^\s*func\s+(\(\w+\s+\*?\w+\)\s*)?\w+\(|^\s*package\s+\w+\s*$|:=\s"),
+    ("python", r"^\s*(def|class)\s+\w+.*:\s*$|^\s*(from\s+\S+\s+)?import\s+\w+\s*$|^\s*(if|elif|for|while|with)\b.*:\s*$|\bself\.\w+"),
+    ("typescript", r"^\s*(export\s+)?(interface|type)\s+\w+|:\s*(string|number|boolean)\b"),
+    ("javascript", r"^\s*(const|let|var)\s+\w+\s*=|=>\s*\{?|\bfunction\s*\w*\(|\brequire\(|console\.log\("),
+    ("java", r"^\s*(public|private|protected)\s+(static\s+)?(class|void|\w+)\s+\w+|System\.out\."),
+    ("c", r"^\s*#include\s*[<\x22]|\bprintf\(|\bint\s+main\s*\("),
+    ("sql", r"(?i)^\s*(select|insert\s+into|update|delete\s+from|create\s+table)\b"),
+    ("bash", r"^\s*(\$\s+)?(sudo|cd|ls|echo|export|cargo|npm|git|curl|docker)\s|^#!/bin/(ba)?sh"),
+    ("json", r#"^\s*[\[{]\s*$|^\s*"[^"]+"\s*:\s*"#),
+    ("html", r"^\s*</?(div|span|html|head|body|p|a|ul|li|script)\b"),
+];
+
+/// Language of a fence for `code`, from `filenames` or the code itself
+pub fn language_of(code: &str, filenames: &[&str]) -> Option<&'static str> {
+    for filename in filenames {
+        let extension = Path::new(filename).extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let extension = extension.to_ascii_lowercase();
+        if let Some((_, language)) = EXTENSIONS.iter().find(|(ext, _)| *ext == extension) {
+            return Some(language);
+        }
+    }
+
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        SIGNS.iter().map(|(_, pattern)| Regex::new(&format!("(?m){}", pattern)).unwrap()).collect()
+    });
+    SIGNS
+        .iter()
+        .zip(patterns)
+        .map(|((language, _), pattern)| (*language, pattern.find_iter(code).count()))
+        .filter(|(_, hits)| *hits > 0)
+        // On a tie the more specific language, listed first, wins
+        .fold(None, |best: Option<(&'static str, usize)>, (language, hits)| match best {
+            Some((_, most)) if most >= hits => best,
+            _ => Some((language, hits)),
+        })
+        .map(|(language, _)| language)
+}
+
+/// Whether a line looks like code rather than prose
+fn is_code_line(line: &str) -> bool {
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    let code = CODE.get_or_init(|| {
+        Regex::new(concat!(
+            r"[;{}]\s*$|^\s*[}\])]|^(\t| {2,})\S|=>|->|::|:=|==|!=|&&|\|\||",
+            r"^\s*(fn|def|class|import|from|use|let|const|var|func|return|if|for|while|#include|package)\b",
+            r"|^\s*(\$ |#!)|^\s*//\s|\w+\(.*\)\s*[;:{]?\s*$",
+        ))
+        .unwrap()
+    });
+    code.is_match(line)
+}
+
+/// Find the longest run of code lines outside any fence
+///
+/// Blank lines inside a run are part of it; a run needs at least
+/// [`MIN_CODE_LINES`] code lines.
+pub fn find_unfenced(draft: &str, filenames: &[&str]) -> Option<Unfenced> {
+    let lines: Vec<&str> = draft.lines().collect();
+    let mut in_fence = false;
+    let mut best: Option<(Range<usize>, usize)> = None;
+    let mut run: Option<(usize, usize, usize)> = None; // start, end, code lines
+
+    let close_run = |run: &mut Option<(usize, usize, usize)>, best: &mut Option<(Range<usize>, usize)>| {
+        if let Some((start, end, count)) = run.take() {
+            if count >= MIN_CODE_LINES && best.as_ref().map_or(true, |(_, most)| count > *most) {
+                *best = Some((start..end, count));
+            }
+        }
+    };
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            close_run(&mut run, &mut best);
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if is_code_line(line) {
+            run = Some(match run {
+                Some((start, _, count)) => (start, index + 1, count + 1),
+                None => (index, index + 1, 1),
+            });
+        } else if !line.trim().is_empty() {
+            close_run(&mut run, &mut best);
+        }
+    }
+    close_run(&mut run, &mut best);
+
+    let (range, _) = best?;
+    let code = lines[range.clone()].join("\n");
+    Some(Unfenced {
+        language: language_of(&code, filenames).unwrap_or_default().to_string(),
+        lines: range,
+    })
+}
+
+/// The draft with the code wrapped in a fence tagged with its language
+pub fn wrap(draft: &str, unfenced: &Unfenced) -> String {
+    let mut lines: Vec<String> = draft.lines().map(str::to_string).collect();
+    let end = unfenced.lines.end.min(lines.len());
+    lines.insert(end, "```".to_string());
+    lines.insert(unfenced.lines.start.min(end), format!("```{}", unfenced.language));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUESTION: &str = "Why does this not compile?\n\
+        fn main() {\n\
+        \x20   let v = vec![1, 2, 3];\n\
+        \n\
+        \x20   let first = &v[0];\n\
+        \x20   v.push(4);\n\
+        }\n\
+        Thanks in advance.";
+
+    #[test]
+    fn test_pasted_code_is_found_and_wrapped() {
+        let unfenced = find_unfenced(QUESTION, &[]).unwrap();
+        assert_eq!(unfenced, Unfenced { lines: 1..7, language: "rust".to_string() });
+        let wrapped = wrap(QUESTION, &unfenced);
+        assert!(wrapped.starts_with("Why does this not compile?\n```rust\nfn main() {"));
+        assert!(wrapped.ends_with("    v.push(4);\n}\n```\nThanks in advance."));
+
+        assert_eq!(find_unfenced(&wrapped, &[]), None);
+        assert_eq!(find_unfenced("Please review the attached file and tell me what you think.", &[]), None);
+    }
+
+    #[test]
+    fn test_language_hints() {
+        let code = "def load(path):\n    with open(path) as f:\n        return f.read()";
+        assert_eq!(language_of(code, &[]), Some("python"));
+        assert_eq!(language_of(code, &["notes.txt", "loader.RB"]), Some("ruby"));
+        assert_eq!(language_of("x := compute()\nfunc compute() int {\n\treturn 1\n}", &[]), Some("go"));
+        assert_eq!(language_of("SELECT id FROM users WHERE active = 1;", &[]), Some("sql"));
+        assert_eq!(language_of("just words", &[]), None);
+    }
+}
@@ -6,23 +6,39 @@
 //! - Line numbers and context display
 //! - Scrolling and navigation
 //! - Configurable styling and themes
+//!
+//! Besides the full-screen view, a diff can be rendered inline as lines of
+//! the chat transcript; that is how the changes of editing tools are shown.
+//! Tools only report the unified diff, so the file is read back from disk
+//! when more context is asked for.
 
 use crate::tui::{
-    components::Component,
+    components::{highlighting::SyntaxHighlighter, Component},
     themes::Theme,
     Frame,
 };
+use crate::config::paths::display_path_short;
+use crate::utils::width::{display_width, pad_to_width};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
-use std::collections::HashMap;
+use similar::{ChangeTag, TextDiff};
 use std::path::{Path, PathBuf};
-use std::io::Write;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Rows of an inline diff shown before it has focus
+const INLINE_ROWS: usize = 12;
+
+/// Rows of an inline diff shown at once while it has focus
+const FOCUSED_ROWS: usize = 30;
+
+/// Context lines added by each expansion
+const CONTEXT_STEP: usize = 5;
 
 /// Diff viewer component
 #[derive(Debug)]
@@ -54,11 +70,36 @@ pub struct DiffViewer {
     /// Whether component has focus
     has_focus: bool,
     
-    /// Syntax highlighting cache
-    syntax_cache: HashMap<String, Vec<Line<'static>>>,
+    /// Syntax highlighted content of each hunk's lines
+    syntax_cache: Vec<Vec<Line<'static>>>,
+    
+    /// Whether both versions of the file are known, not just the hunks
+    has_contents: bool,
     
     /// Error message
     error_message: Option<String>,
+    
+    /// Outcome of the last key press, shown in the status line
+    notice: Option<String>,
+}
+
+/// Result of a key press handled by an inline diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffAction {
+    None,
+    /// The diff gave up focus
+    Exit,
+}
+
+/// A row of the diff as displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffRow {
+    /// Header of a hunk
+    Header(usize),
+    /// Line of a hunk, in the unified layout
+    Line(usize, usize),
+    /// Lines of a hunk side by side, in the split layout
+    Pair(usize, Option<usize>, Option<usize>),
 }
 
 /// Diff layout modes
@@ -249,8 +290,106 @@ impl DiffViewer {
             horizontal_offset: 0,
             area: Rect::default(),
             has_focus: false,
-            syntax_cache: HashMap::new(),
+            syntax_cache: Vec::new(),
+            has_contents: false,
             error_message: None,
+            notice: None,
+        }
+    }
+    
+    /// Diff viewer over a unified diff, such as the one an editing tool reports
+    ///
+    /// Only the hunks are known; the file is read from disk when the context
+    /// is expanded.
+    pub fn from_unified_diff<P: AsRef<Path>>(path: P, diff: &str) -> Self {
+        let mut viewer = Self::new();
+        viewer.before_file = DiffFile::new(&path, String::new());
+        viewer.after_file = DiffFile::new(&path, String::new());
+        viewer.hunks = parse_unified_diff(diff);
+        viewer
+    }
+    
+    /// Current layout mode
+    pub fn layout(&self) -> DiffLayout {
+        self.layout
+    }
+    
+    /// Switch between the unified and split layouts
+    pub fn toggle_layout(&mut self) {
+        self.layout = match self.layout {
+            DiffLayout::Unified => DiffLayout::Split,
+            DiffLayout::Split => DiffLayout::Unified,
+        };
+    }
+    
+    /// Computed hunks
+    pub fn hunks(&self) -> &[DiffHunk] {
+        &self.hunks
+    }
+    
+    /// Load the file as it is after the change, recovering the version before
+    /// it from the hunks
+    ///
+    /// Fails when the file no longer matches the diff.
+    pub fn load_contents(&mut self, after: String) -> Result<()> {
+        let after_lines: Vec<&str> = after.lines().collect();
+        let mut before: Vec<&str> = Vec::new();
+        // Next line of `after` not yet accounted for
+        let mut next = 0;
+        
+        for line in self.hunks.iter().flat_map(|hunk| &hunk.lines) {
+            // Lines between hunks are the same in both versions
+            let gap = match (line.before_line, line.after_line) {
+                (Some(before_line), _) => (before_line - 1).checked_sub(before.len()),
+                (None, Some(after_line)) => (after_line - 1).checked_sub(next),
+                (None, None) => Some(0),
+            };
+            let unchanged = gap
+                .and_then(|gap| after_lines.get(next..next + gap))
+                .ok_or_else(|| anyhow::anyhow!("{} changed since the diff", self.after_file.path.display()))?;
+            before.extend(unchanged);
+            next += unchanged.len();
+            
+            if let Some(after_line) = line.after_line {
+                if after_line != next + 1 || after_lines.get(next) != Some(&line.content.as_str()) {
+                    anyhow::bail!("{} changed since the diff", self.after_file.path.display());
+                }
+                next += 1;
+            }
+            if line.before_line.is_some() {
+                before.push(&line.content);
+            }
+        }
+        before.extend(&after_lines[next..]);
+        
+        let mut before = before.join("\n");
+        if after.ends_with('\n') && !before.is_empty() {
+            before.push('\n');
+        }
+        self.before_file.content = before;
+        self.after_file.content = after;
+        self.has_contents = true;
+        Ok(())
+    }
+    
+    /// Show `lines` more lines of context around each change
+    pub fn expand_context(&mut self, lines: usize) -> Result<()> {
+        if !self.has_contents {
+            let after = std::fs::read_to_string(&self.after_file.path)?;
+            self.load_contents(after)?;
+        }
+        self.config.context_lines += lines;
+        self.syntax_cache.clear();
+        self.compute_diff();
+        Ok(())
+    }
+    
+    /// Go back to the default amount of context
+    pub fn reset_context(&mut self) {
+        self.config.context_lines = DiffConfig::default().context_lines;
+        if self.has_contents {
+            self.syntax_cache.clear();
+            self.compute_diff();
         }
     }
     
@@ -263,6 +402,7 @@ impl DiffViewer {
     /// Set the before file
     pub fn set_before_file(&mut self, file: DiffFile) -> &mut Self {
         self.before_file = file;
+        self.has_contents = true;
         self.syntax_cache.clear();
         self.compute_diff();
         self
@@ -271,6 +411,7 @@ impl DiffViewer {
     /// Set the after file
     pub fn set_after_file(&mut self, file: DiffFile) -> &mut Self {
         self.after_file = file;
+        self.has_contents = true;
         self.syntax_cache.clear();
         self.compute_diff();
         self
@@ -300,112 +441,40 @@ impl DiffViewer {
     
     /// Internal diff computation
     fn compute_diff_internal(&self) -> Result<Vec<DiffHunk>> {
-        let before_lines: Vec<&str> = self.before_file.content.lines().collect();
-        let after_lines: Vec<&str> = self.after_file.content.lines().collect();
-        
-        // Simple diff algorithm - in a real implementation you'd use a proper diff library
+        let diff = TextDiff::from_lines(&self.before_file.content, &self.after_file.content);
         let mut hunks = Vec::new();
-        let mut before_pos = 0;
-        let mut after_pos = 0;
-        
-        // Find differences
-        while before_pos < before_lines.len() || after_pos < after_lines.len() {
-            // Find next difference
-            let hunk_start_before = before_pos;
-            let hunk_start_after = after_pos;
-            
-            // Skip equal lines
-            while before_pos < before_lines.len() 
-                && after_pos < after_lines.len() 
-                && before_lines[before_pos] == after_lines[after_pos] {
-                before_pos += 1;
-                after_pos += 1;
-            }
-            
-            if before_pos >= before_lines.len() && after_pos >= after_lines.len() {
-                break;
-            }
-            
-            // Start a new hunk
-            let mut hunk_lines = Vec::new();
-            let context_start = hunk_start_before.saturating_sub(self.config.context_lines);
-            
-            // Add context before change
-            for i in context_start..hunk_start_before {
-                if i < before_lines.len() {
-                    hunk_lines.push(DiffLine {
-                        kind: DiffLineKind::Equal,
-                        content: before_lines[i].to_string(),
-                        before_line: Some(i + 1),
-                        after_line: Some(i + 1),
-                    });
-                }
-            }
-            
-            // Find end of difference
-            let diff_start_before = before_pos;
-            let diff_start_after = after_pos;
-            
-            // Simple approach: find next common line
-            let mut found_common = false;
-            while !found_common && (before_pos < before_lines.len() || after_pos < after_lines.len()) {
-                if before_pos < before_lines.len() && after_pos < after_lines.len() {
-                    if before_lines[before_pos] == after_lines[after_pos] {
-                        found_common = true;
-                        break;
-                    }
-                }
-                
-                // Add deleted lines
-                if before_pos < before_lines.len() && 
-                   (after_pos >= after_lines.len() || 
-                    before_lines[before_pos] != after_lines.get(after_pos).unwrap_or(&"")) {
-                    hunk_lines.push(DiffLine {
-                        kind: DiffLineKind::Delete,
-                        content: before_lines[before_pos].to_string(),
-                        before_line: Some(before_pos + 1),
-                        after_line: None,
-                    });
-                    before_pos += 1;
-                }
-                
-                // Add inserted lines
-                if after_pos < after_lines.len() && 
-                   (before_pos >= before_lines.len() || 
-                    after_lines[after_pos] != before_lines.get(before_pos).unwrap_or(&"")) {
-                    hunk_lines.push(DiffLine {
-                        kind: DiffLineKind::Insert,
-                        content: after_lines[after_pos].to_string(),
-                        before_line: None,
-                        after_line: Some(after_pos + 1),
-                    });
-                    after_pos += 1;
-                }
-            }
+        
+        for group in diff.grouped_ops(self.config.context_lines) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let before = first.old_range().start..last.old_range().end;
+            let after = first.new_range().start..last.new_range().end;
             
-            // Add context after change
-            let context_end = (before_pos + self.config.context_lines).min(before_lines.len());
-            for i in before_pos..context_end {
-                if i < before_lines.len() && i < after_lines.len() && before_lines[i] == after_lines[i] {
-                    hunk_lines.push(DiffLine {
-                        kind: DiffLineKind::Equal,
-                        content: before_lines[i].to_string(),
-                        before_line: Some(i + 1),
-                        after_line: Some(i + 1),
-                    });
-                }
-            }
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        ChangeTag::Equal => DiffLineKind::Equal,
+                        ChangeTag::Insert => DiffLineKind::Insert,
+                        ChangeTag::Delete => DiffLineKind::Delete,
+                    },
+                    content: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                    before_line: change.old_index().map(|i| i + 1),
+                    after_line: change.new_index().map(|i| i + 1),
+                })
+                .collect();
             
-            if !hunk_lines.is_empty() {
-                hunks.push(DiffHunk {
-                    before_start: diff_start_before + 1,
-                    before_count: before_pos - diff_start_before,
-                    after_start: diff_start_after + 1,
-                    after_count: after_pos - diff_start_after,
-                    lines: hunk_lines,
-                    context: None,
-                });
-            }
+            // Like `diff -u`, an empty range starts at the line before it
+            hunks.push(DiffHunk {
+                before_start: if before.is_empty() { before.start } else { before.start + 1 },
+                before_count: before.len(),
+                after_start: if after.is_empty() { after.start } else { after.start + 1 },
+                after_count: after.len(),
+                lines,
+                context: None,
+            });
         }
         
         Ok(hunks)
@@ -652,6 +721,268 @@ impl DiffViewer {
         (before_lines, after_lines)
     }
     
+    /// Handle a key while the diff has focus
+    pub fn handle_key(&mut self, event: KeyEvent) -> DiffAction {
+        let page = if self.area.height > 0 { self.area.height as usize } else { FOCUSED_ROWS };
+        self.notice = None;
+        match (event.code, event.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => return DiffAction::Exit,
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => self.scroll_down(1),
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => self.scroll_up(1),
+            (KeyCode::PageDown, _) => self.scroll_down(page),
+            (KeyCode::PageUp, _) => self.scroll_up(page),
+            (KeyCode::Home, _) => self.scroll_offset = 0,
+            (KeyCode::End, _) => self.scroll_offset = self.get_total_display_lines().saturating_sub(page),
+            (KeyCode::Left, _) | (KeyCode::Char('h'), KeyModifiers::NONE) => self.scroll_horizontal(-5),
+            (KeyCode::Right, _) | (KeyCode::Char('l'), KeyModifiers::NONE) => self.scroll_horizontal(5),
+            (KeyCode::Char('v'), KeyModifiers::NONE) => self.toggle_layout(),
+            (KeyCode::Char('u'), KeyModifiers::NONE) => self.layout = DiffLayout::Unified,
+            (KeyCode::Char('s'), KeyModifiers::NONE) => self.layout = DiffLayout::Split,
+            (KeyCode::Char('n'), KeyModifiers::NONE) => {
+                self.config.show_line_numbers = !self.config.show_line_numbers;
+            }
+            (KeyCode::Char('e'), KeyModifiers::NONE) => {
+                if let Err(e) = self.expand_context(CONTEXT_STEP) {
+                    self.notice = Some(format!("can't expand: {:#}", e));
+                }
+            }
+            (KeyCode::Char('E'), _) => self.reset_context(),
+            _ => {}
+        }
+        DiffAction::None
+    }
+    
+    /// Lines taken by the diff when rendered inline
+    pub fn height(&self, focused: bool) -> u16 {
+        let visible = if focused { FOCUSED_ROWS } else { INLINE_ROWS };
+        // Rows and the status line
+        (self.rows().len().min(visible) + 1) as u16
+    }
+    
+    /// Render the diff into lines no wider than `width`, for the chat transcript
+    ///
+    /// With a highlighter, the code is syntax highlighted on the first render.
+    pub fn render_lines(
+        &mut self,
+        width: usize,
+        theme: &Theme,
+        highlighter: Option<&mut SyntaxHighlighter>,
+        focused: bool,
+    ) -> Vec<Line<'static>> {
+        if let Some(error) = &self.error_message {
+            return vec![Line::from(Span::styled(error.clone(), Style::default().fg(theme.error)))];
+        }
+        if let Some(highlighter) = highlighter {
+            self.highlight(highlighter);
+        }
+        
+        let rows = self.rows();
+        let visible = if focused { FOCUSED_ROWS } else { INLINE_ROWS };
+        self.scroll_offset = self.scroll_offset.min(rows.len().saturating_sub(visible));
+        let end = (self.scroll_offset + visible).min(rows.len());
+        let mut lines: Vec<Line<'static>> = rows[self.scroll_offset..end]
+            .iter()
+            .map(|&row| self.render_row(row, width))
+            .collect();
+        
+        let (added, removed) = self.hunks.iter().flat_map(|hunk| &hunk.lines).fold((0, 0), |(added, removed), line| {
+            match line.kind {
+                DiffLineKind::Insert => (added + 1, removed),
+                DiffLineKind::Delete => (added, removed + 1),
+                _ => (added, removed),
+            }
+        });
+        let mut status = format!(
+            "{} · +{} -{} · {} · {} lines of context",
+            display_path_short(&self.after_file.path),
+            added,
+            removed,
+            match self.layout {
+                DiffLayout::Unified => "unified",
+                DiffLayout::Split => "split",
+            },
+            self.config.context_lines,
+        );
+        if end - self.scroll_offset < rows.len() {
+            status.push_str(&format!(" · rows {}-{} of {}", self.scroll_offset + 1, end, rows.len()));
+        }
+        if let Some(notice) = &self.notice {
+            status.push_str(&format!(" · {}", notice));
+        }
+        if focused {
+            status.push_str(" · v split/unified · e/E more/less context · n numbers · esc");
+        } else {
+            status.push_str(" · d to inspect");
+        }
+        lines.push(Line::from(Span::styled(status, Style::default().fg(theme.fg_muted))));
+        
+        lines
+    }
+    
+    /// Rows in display order for the current layout
+    fn rows(&self) -> Vec<DiffRow> {
+        let mut rows = Vec::new();
+        for (h, hunk) in self.hunks.iter().enumerate() {
+            rows.push(DiffRow::Header(h));
+            match self.layout {
+                DiffLayout::Unified => rows.extend((0..hunk.lines.len()).map(|i| DiffRow::Line(h, i))),
+                DiffLayout::Split => {
+                    // Removed lines face the lines that replaced them
+                    let (mut deleted, mut inserted) = (Vec::new(), Vec::new());
+                    for (i, line) in hunk.lines.iter().enumerate() {
+                        match line.kind {
+                            DiffLineKind::Delete if inserted.is_empty() => deleted.push(i),
+                            DiffLineKind::Insert => inserted.push(i),
+                            _ => {
+                                pair_up(&mut rows, h, &mut deleted, &mut inserted);
+                                match line.kind {
+                                    DiffLineKind::Delete => deleted.push(i),
+                                    _ => rows.push(DiffRow::Pair(h, Some(i), Some(i))),
+                                }
+                            }
+                        }
+                    }
+                    pair_up(&mut rows, h, &mut deleted, &mut inserted);
+                }
+            }
+        }
+        rows
+    }
+    
+    /// Render one row no wider than `width`
+    fn render_row(&self, row: DiffRow, width: usize) -> Line<'static> {
+        match row {
+            DiffRow::Header(h) => {
+                let hunk = &self.hunks[h];
+                let mut header = format!(
+                    "@@ -{},{} +{},{} @@",
+                    hunk.before_start, hunk.before_count, hunk.after_start, hunk.after_count
+                );
+                if let Some(context) = &hunk.context {
+                    header.push(' ');
+                    header.push_str(context);
+                }
+                Line::from(Span::styled(pad_to_width(&header, width), self.config.styling.hunk_header_style))
+            }
+            DiffRow::Line(h, i) => {
+                let line = &self.hunks[h].lines[i];
+                let mut spans = Vec::new();
+                if self.config.show_line_numbers {
+                    let number = |n: Option<usize>| n.map_or_else(|| "    ".to_string(), |n| format!("{:4}", n));
+                    spans.push(Span::styled(
+                        format!("{} {} ", number(line.before_line), number(line.after_line)),
+                        self.config.styling.line_number_style,
+                    ));
+                }
+                let used = spans.iter().map(|span| display_width(&span.content)).sum::<usize>();
+                spans.extend(self.cell(h, i, line.before_line.or(line.after_line), width.saturating_sub(used), false));
+                Line::from(spans)
+            }
+            DiffRow::Pair(h, before, after) => {
+                let half = width.saturating_sub(3) / 2;
+                let mut spans = match before {
+                    Some(i) => self.cell(h, i, self.hunks[h].lines[i].before_line, half, true),
+                    None => vec![Span::raw(" ".repeat(half))],
+                };
+                spans.push(Span::styled(" │ ", self.config.styling.line_number_style));
+                match after {
+                    Some(i) => spans.extend(self.cell(h, i, self.hunks[h].lines[i].after_line, half, false)),
+                    None => {}
+                }
+                Line::from(spans)
+            }
+        }
+    }
+    
+    /// A line's marker and content, clipped to `width` and padded to it when `pad`
+    ///
+    /// In the split layout each side carries its own line number.
+    fn cell(&self, h: usize, i: usize, number: Option<usize>, width: usize, pad: bool) -> Vec<Span<'static>> {
+        let line = &self.hunks[h].lines[i];
+        let style = match line.kind {
+            DiffLineKind::Equal => self.config.styling.equal_style,
+            DiffLineKind::Insert => self.config.styling.insert_style,
+            DiffLineKind::Delete => self.config.styling.delete_style,
+            DiffLineKind::Context => self.config.styling.context_style,
+        };
+        let marker = match line.kind {
+            DiffLineKind::Insert => "+",
+            DiffLineKind::Delete => "-",
+            _ => " ",
+        };
+        
+        let mut spans = Vec::new();
+        if self.config.show_line_numbers && self.layout == DiffLayout::Split {
+            let number = number.map_or_else(|| "     ".to_string(), |n| format!("{:4} ", n));
+            spans.push(Span::styled(number, self.config.styling.line_number_style));
+        }
+        spans.push(Span::styled(marker, style));
+        
+        // Highlighted code keeps its colors on the line's background
+        let code: Vec<Span<'static>> = match self.syntax_cache.get(h).and_then(|lines| lines.get(i)) {
+            Some(highlighted) => highlighted
+                .spans
+                .iter()
+                .map(|span| {
+                    let code_style = match style.bg {
+                        Some(bg) => span.style.bg(bg),
+                        None => span.style,
+                    };
+                    Span::styled(span.content.to_string(), code_style)
+                })
+                .collect(),
+            None => vec![Span::styled(line.content.clone(), style)],
+        };
+        let used = spans.iter().map(|span| display_width(&span.content)).sum::<usize>();
+        let available = width.saturating_sub(used);
+        let (code, code_width) = clip_spans(code, self.horizontal_offset, available, self.config.tab_width);
+        spans.extend(code);
+        if pad && code_width < available {
+            spans.push(Span::styled(" ".repeat(available - code_width), style));
+        }
+        spans
+    }
+    
+    /// Highlight the code of every hunk, each side on its own so the
+    /// highlighter sees code as it was written
+    fn highlight(&mut self, highlighter: &mut SyntaxHighlighter) {
+        if !self.config.enable_syntax_highlighting || !self.syntax_cache.is_empty() {
+            return;
+        }
+        let filename = self.after_file.path.to_string_lossy().to_string();
+        for hunk in &self.hunks {
+            let side = |changed: DiffLineKind| {
+                hunk.lines
+                    .iter()
+                    .filter(|line| line.kind == changed || line.kind == DiffLineKind::Equal)
+                    .map(|line| line.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let highlight = |highlighter: &mut SyntaxHighlighter, code: String| {
+                highlighter.highlight(&code, Some(&filename)).map(|content| content.lines).unwrap_or_default()
+            };
+            let mut before = highlight(highlighter, side(DiffLineKind::Delete)).into_iter();
+            let mut after = highlight(highlighter, side(DiffLineKind::Insert)).into_iter();
+            let lines = hunk
+                .lines
+                .iter()
+                .map(|line| {
+                    let highlighted = match line.kind {
+                        DiffLineKind::Insert => after.next(),
+                        DiffLineKind::Delete => before.next(),
+                        _ => {
+                            after.next();
+                            before.next()
+                        }
+                    };
+                    highlighted.unwrap_or_else(|| Line::from(line.content.clone()))
+                })
+                .collect();
+            self.syntax_cache.push(lines);
+        }
+    }
+    
     /// Create spans for a line with line number and content
     fn create_line_spans(&self, line_number: Option<usize>, prefix: &str, content: &str, style: Style) -> Vec<Span<'static>> {
         let mut spans = Vec::new();
@@ -677,50 +1008,9 @@ impl DiffViewer {
 
 impl Component for DiffViewer {
     async fn handle_key_event(&mut self, event: KeyEvent) -> Result<()> {
-        if !self.has_focus {
-            return Ok(());
-        }
-        
-        match event.code {
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.scroll_down(1);
-            }
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.scroll_up(1);
-            }
-            KeyCode::PageDown => {
-                let page_size = self.area.height as usize;
-                self.scroll_down(page_size);
-            }
-            KeyCode::PageUp => {
-                let page_size = self.area.height as usize;
-                self.scroll_up(page_size);
-            }
-            KeyCode::Home => {
-                self.scroll_offset = 0;
-            }
-            KeyCode::End => {
-                let total_lines = self.get_total_display_lines();
-                self.scroll_offset = total_lines.saturating_sub(self.area.height as usize);
-            }
-            KeyCode::Left | KeyCode::Char('h') => {
-                self.scroll_horizontal(-5);
-            }
-            KeyCode::Right | KeyCode::Char('l') => {
-                self.scroll_horizontal(5);
-            }
-            KeyCode::Char('u') => {
-                self.layout = DiffLayout::Unified;
-            }
-            KeyCode::Char('s') => {
-                self.layout = DiffLayout::Split;
-            }
-            KeyCode::Char('n') => {
-                self.config.show_line_numbers = !self.config.show_line_numbers;
-            }
-            _ => {}
+        if self.has_focus {
+            self.handle_key(event);
         }
-        
         Ok(())
     }
     
@@ -782,7 +1072,7 @@ impl Component for DiffViewer {
                 
                 // Before (left) side
                 let before_block = Block::default()
-                    .title(format!("Before: {}", self.before_file.path.display()))
+                    .title(format!("Before: {}", display_path_short(&self.before_file.path)))
                     .borders(Borders::RIGHT)
                     .border_style(Style::default().fg(theme.colors.border));
                 
@@ -795,7 +1085,7 @@ impl Component for DiffViewer {
                 
                 // After (right) side
                 let after_block = Block::default()
-                    .title(format!("After: {}", self.after_file.path.display()))
+                    .title(format!("After: {}", display_path_short(&self.after_file.path)))
                     .borders(Borders::NONE)
                     .border_style(Style::default().fg(theme.colors.border));
                 
@@ -818,7 +1108,7 @@ impl Component for DiffViewer {
             };
             
             let status_text = format!(
-                "Line {}/{} | {} hunks | {} (u)nified (s)plit (n)umbers ↑↓←→ scroll",
+                "Line {}/{} | {} hunks | {} (v) toggle (e)xpand (n)umbers ↑↓←→ scroll",
                 self.scroll_offset + 1,
                 self.get_total_display_lines(),
                 self.hunks.len(),
@@ -859,6 +1149,119 @@ impl Default for DiffViewer {
     }
 }
 
+/// Add rows facing removed lines with the lines that replaced them
+fn pair_up(rows: &mut Vec<DiffRow>, hunk: usize, deleted: &mut Vec<usize>, inserted: &mut Vec<usize>) {
+    for k in 0..deleted.len().max(inserted.len()) {
+        rows.push(DiffRow::Pair(hunk, deleted.get(k).copied(), inserted.get(k).copied()));
+    }
+    deleted.clear();
+    inserted.clear();
+}
+
+/// Cut `skip` columns off the start of the spans and keep at most `width`
+/// columns, returning the kept spans and the columns they take
+fn clip_spans(spans: Vec<Span<'static>>, skip: usize, width: usize, tab_width: usize) -> (Vec<Span<'static>>, usize) {
+    let tab = " ".repeat(tab_width);
+    let (mut column, mut used) = (0, 0);
+    let mut clipped = Vec::new();
+    'spans: for span in spans {
+        let text = span.content.replace('\t', &tab);
+        let mut kept = String::new();
+        for grapheme in text.graphemes(true) {
+            if grapheme.ends_with('\n') || grapheme == "\r" {
+                continue;
+            }
+            let grapheme_width = display_width(grapheme);
+            if column < skip {
+                column += grapheme_width;
+                continue;
+            }
+            if used + grapheme_width > width {
+                if !kept.is_empty() {
+                    clipped.push(Span::styled(kept, span.style));
+                }
+                break 'spans;
+            }
+            kept.push_str(grapheme);
+            column += grapheme_width;
+            used += grapheme_width;
+        }
+        if !kept.is_empty() {
+            clipped.push(Span::styled(kept, span.style));
+        }
+    }
+    (clipped, used)
+}
+
+/// Hunks of a unified diff; file headers and other lines outside hunks are skipped
+fn parse_unified_diff(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let (mut before, mut after) = (0, 0);
+    
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            if let Some(((before_start, before_count), (after_start, after_count), context)) = parse_hunk_header(header) {
+                before = before_start;
+                after = after_start;
+                hunks.push(DiffHunk {
+                    before_start,
+                    before_count,
+                    after_start,
+                    after_count,
+                    lines: Vec::new(),
+                    context,
+                });
+            }
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        
+        let mut chars = line.chars();
+        let kind = match chars.next() {
+            Some('+') => DiffLineKind::Insert,
+            Some('-') => DiffLineKind::Delete,
+            Some(' ') | None => DiffLineKind::Equal,
+            // "\ No newline at end of file"
+            _ => continue,
+        };
+        let (before_line, after_line) = match kind {
+            DiffLineKind::Insert => (None, Some(after)),
+            DiffLineKind::Delete => (Some(before), None),
+            _ => (Some(before), Some(after)),
+        };
+        before += before_line.is_some() as usize;
+        after += after_line.is_some() as usize;
+        hunk.lines.push(DiffLine {
+            kind,
+            content: chars.as_str().to_string(),
+            before_line,
+            after_line,
+        });
+    }
+    
+    hunks
+}
+
+/// Ranges and trailing context of a hunk header, without its leading `@@ `
+fn parse_hunk_header(header: &str) -> Option<((usize, usize), (usize, usize), Option<String>)> {
+    let (ranges, context) = header.split_once("@@")?;
+    let mut ranges = ranges.split_whitespace();
+    let before = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let after = parse_range(ranges.next()?.strip_prefix('+')?)?;
+    let context = Some(context.trim()).filter(|c| !c.is_empty()).map(str::to_string);
+    Some((before, after, context))
+}
+
+/// Start and length of a range such as `12,3`; the length defaults to one
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
 /// Detect programming language from file extension
 fn detect_language(path: &Path) -> Option<String> {
     match path.extension()?.to_str()? {
@@ -930,4 +1333,53 @@ mod tests {
         
         assert!(!viewer.hunks.is_empty());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_context_expands_from_a_tool_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        let before: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let after = before.replace("line 10\n", "line ten\nline 10.5\n");
+        std::fs::write(&path, &after).unwrap();
+        let diff = crate::llm::changes::unified_diff("lib.rs", &before, &after);
+        
+        let mut viewer = DiffViewer::from_unified_diff(&path, &diff);
+        assert_eq!(viewer.hunks().len(), 1);
+        assert_eq!(viewer.hunks()[0].lines.len(), 9);
+        
+        viewer.expand_context(CONTEXT_STEP).unwrap();
+        assert_eq!(viewer.before_file.content, before);
+        assert_eq!(viewer.hunks()[0].lines.len(), 19);
+        assert_eq!(viewer.hunks()[0].before_start, 2);
+        
+        // Changed lines face each other in the split layout
+        viewer.toggle_layout();
+        let rows = viewer.rows();
+        let changed: Vec<_> = rows
+            .iter()
+            .filter(|row| matches!(row, DiffRow::Pair(_, before, after) if before != after))
+            .collect();
+        assert_eq!(changed.len(), 2);
+        
+        std::fs::write(&path, "rewritten\n").unwrap();
+        let mut stale = DiffViewer::from_unified_diff(&path, &diff);
+        assert!(stale.expand_context(CONTEXT_STEP).is_err());
+    }
+    
+    #[test]
+    fn test_inline_lines_fit_the_width() {
+        let mut viewer = DiffViewer::new();
+        viewer.set_before_file(DiffFile::new("a.txt", "short\n".to_string()));
+        viewer.set_after_file(DiffFile::new("a.txt", format!("{}\n", "long ".repeat(40))));
+        let theme = crate::tui::themes::current_theme();
+        
+        for layout in [DiffLayout::Unified, DiffLayout::Split] {
+            viewer.set_layout(layout);
+            let lines = viewer.render_lines(60, &theme, None, false);
+            assert_eq!(lines.len() as u16, viewer.height(false));
+            for line in &lines[..lines.len() - 1] {
+                assert!(line.width() <= 60, "{:?} is too wide", line);
+            }
+        }
+    }
+}
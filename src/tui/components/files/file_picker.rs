@@ -6,6 +6,7 @@
 //! - Image preview for supported formats
 //! - Keyboard and mouse navigation
 //! - File size and permission validation
+//! - Switching between workspace roots
 
 use super::{FileEvent, FileItem, StandardFileItem, validate_file_path, is_file_too_large};
 use crate::tui::{
@@ -13,6 +14,8 @@ use crate::tui::{
     themes::Theme,
     Frame,
 };
+use crate::config::paths::display_path_short;
+use crate::utils::width::truncate_to_width;
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
@@ -36,6 +39,9 @@ pub struct FilePicker {
     /// Current directory
     current_directory: PathBuf,
     
+    /// Index of the active workspace root
+    active_root: usize,
+    
     /// Available files and directories
     items: Vec<StandardFileItem>,
     
@@ -102,6 +108,9 @@ pub struct FilePickerConfig {
     
     /// Preview panel width percentage (0-100)
     pub preview_width_percent: u16,
+    
+    /// Workspace roots to switch between, as (name, path)
+    pub roots: Vec<(String, PathBuf)>,
 }
 
 impl Default for FilePickerConfig {
@@ -117,6 +126,7 @@ impl Default for FilePickerConfig {
             allow_directory_selection: false,
             show_breadcrumbs: true,
             preview_width_percent: 40,
+            roots: Vec::new(),
         }
     }
 }
@@ -162,12 +172,14 @@ impl FilePicker {
     /// Create a new file picker with custom configuration
     pub fn with_config(config: FilePickerConfig) -> Self {
         let start_dir = config.start_directory.clone()
+            .or_else(|| config.roots.first().map(|(_, path)| path.clone()))
             .or_else(|| std::env::current_dir().ok())
             .or_else(|| dirs::home_dir())
             .unwrap_or_else(|| PathBuf::from("/"));
         
         let mut picker = Self {
             current_directory: start_dir,
+            active_root: 0,
             items: Vec::new(),
             selected_index: 0,
             virtual_list: VirtualList::default(),
@@ -357,11 +369,7 @@ impl FilePicker {
     /// Generate text preview
     fn generate_text_preview(&self, path: &Path) -> Result<PreviewContent> {
         let content = std::fs::read_to_string(path)?;
-        let preview = if content.len() > 1000 {
-            format!("{}...", &content[..1000])
-        } else {
-            content
-        };
+        let preview = truncate_to_width(&content, 1000);
         
         Ok(PreviewContent::Text { content: preview })
     }
@@ -420,6 +428,25 @@ impl FilePicker {
         }
     }
     
+    /// Switch to the next workspace root
+    fn next_root(&mut self) -> Result<()> {
+        if self.config.roots.len() < 2 {
+            return Ok(());
+        }
+        self.active_root = (self.active_root + 1) % self.config.roots.len();
+        let (_, path) = self.config.roots[self.active_root].clone();
+        self.selected_index = 0;
+        self.navigate_to(path)
+    }
+    
+    /// Name of the active workspace root, if the picker has several
+    pub fn active_root_name(&self) -> Option<&str> {
+        if self.config.roots.len() < 2 {
+            return None;
+        }
+        self.config.roots.get(self.active_root).map(|(name, _)| name.as_str())
+    }
+    
     /// Navigate to parent directory
     fn go_to_parent(&mut self) -> Result<()> {
         if let Some(parent) = self.current_directory.parent() {
@@ -441,24 +468,23 @@ impl FilePicker {
         let mut spans = Vec::new();
         
         // Home icon
-        spans.push(Span::styled("🏠 ", Style::default().fg(theme.colors.primary)));
+        spans.push(Span::styled(format!("{} ", theme.icons.home), Style::default().fg(theme.colors.primary)));
+        
+        // Active root in multi-root workspaces
+        if let Some(name) = self.active_root_name() {
+            spans.push(Span::styled(
+                format!("[{}] ", name),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            ));
+        }
         
-        // Path components
-        let components: Vec<_> = self.current_directory.components().collect();
-        for (i, component) in components.iter().enumerate() {
+        // Path components, as paths are shown everywhere
+        let shown = display_path_short(&self.current_directory);
+        for (i, name) in shown.split(std::path::MAIN_SEPARATOR).filter(|name| !name.is_empty()).enumerate() {
             if i > 0 {
                 spans.push(Span::styled(" / ", Style::default().fg(theme.colors.muted)));
             }
-            
-            let name = match component {
-                std::path::Component::RootDir => "".to_string(),
-                std::path::Component::Normal(name) => name.to_string_lossy().to_string(),
-                _ => component.as_os_str().to_string_lossy().to_string(),
-            };
-            
-            if !name.is_empty() {
-                spans.push(Span::styled(name, Style::default().fg(theme.colors.text)));
-            }
+            spans.push(Span::styled(name.to_string(), Style::default().fg(theme.colors.text)));
         }
         
         Paragraph::new(Line::from(spans))
@@ -544,6 +570,9 @@ impl Component for FilePicker {
                 self.config.show_hidden = !self.config.show_hidden;
                 self.load_directory();
             }
+            KeyCode::Tab => {
+                self.next_root()?;
+            }
             KeyCode::Home => {
                 self.selected_index = 0;
                 if !self.items.is_empty() {
@@ -750,4 +779,27 @@ mod tests {
         let _picker = FilePicker::with_config(config);
         // Test would require creating actual files to verify filtering
     }
+    
+    #[test]
+    fn test_root_switcher_cycles_roots() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let config = FilePickerConfig {
+            roots: vec![
+                ("app".to_string(), first.path().canonicalize().unwrap()),
+                ("lib".to_string(), second.path().canonicalize().unwrap()),
+            ],
+            ..Default::default()
+        };
+        
+        let mut picker = FilePicker::with_config(config);
+        assert_eq!(picker.active_root_name(), Some("app"));
+        
+        picker.next_root().unwrap();
+        assert_eq!(picker.active_root_name(), Some("lib"));
+        assert_eq!(picker.current_directory, second.path().canonicalize().unwrap());
+        
+        picker.next_root().unwrap();
+        assert_eq!(picker.active_root_name(), Some("app"));
+    }
 }
\ No newline at end of file
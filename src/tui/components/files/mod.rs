@@ -286,21 +286,7 @@ impl FileItem for StandardFileItem {
         let mut spans = Vec::new();
         
         // File type indicator
-        let type_indicator = if self.is_directory {
-            "📁"
-        } else {
-            match self.extension() {
-                Some("rs") => "🦀",
-                Some("go") => "🐹",
-                Some("py") => "🐍",
-                Some("js") => "🟨",
-                Some("html") => "🌐",
-                Some("md") => "📝",
-                Some("json") => "📋",
-                Some("jpg") | Some("jpeg") | Some("png") | Some("gif") => "🖼️",
-                _ => "📄",
-            }
-        };
+        let type_indicator = theme.icons.entry_icon(&self.path, self.is_directory, false);
         
         spans.push(Span::raw(format!("{} ", type_indicator)));
         
@@ -328,9 +314,9 @@ impl FileItem for StandardFileItem {
         // Permissions indicator
         if let Some(perms) = self.permissions {
             if !perms.readable {
-                spans.push(Span::styled(" 🚫", Style::default().fg(Color::Red)));
+                spans.push(Span::styled(format!(" {}", theme.icons.blocked), Style::default().fg(Color::Red)));
             } else if !perms.writable {
-                spans.push(Span::styled(" 🔒", Style::default().fg(Color::Yellow)));
+                spans.push(Span::styled(format!(" {}", theme.icons.locked), Style::default().fg(Color::Yellow)));
             }
         }
         
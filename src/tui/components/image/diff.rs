@@ -0,0 +1,384 @@
+//! Image diff view
+//!
+//! Compares two versions of an image, such as an asset before and after the
+//! agent edited it or a before/after pair from a screenshot tool. Both
+//! versions are shown side by side, or in the same place alternating
+//! ("blink compare") so small shifts stand out. The share of changed pixels
+//! is added to the metadata of the new version.
+
+use super::{ImageConfig, ImageWidget};
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use image::{DynamicImage, GenericImageView};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Key under which the pixel difference is stored in image metadata
+pub const PIXEL_DIFF_METADATA_KEY: &str = "Changed";
+
+/// Largest per-channel difference still treated as the same pixel
+///
+/// Absorbs noise from lossy re-encoding.
+pub const DEFAULT_TOLERANCE: u8 = 8;
+
+/// How long each version is shown in blink mode
+const DEFAULT_BLINK_INTERVAL: Duration = Duration::from_millis(700);
+
+/// Columns between the two images in side-by-side mode
+const SEPARATOR: &str = " │ ";
+
+/// Result of comparing two images pixel by pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiffStats {
+    /// Pixels that differ, including pixels only one image covers
+    pub changed_pixels: u64,
+
+    /// Pixels in the union of both images
+    pub total_pixels: u64,
+
+    /// Whether the images have different dimensions
+    pub size_changed: bool,
+}
+
+impl ImageDiffStats {
+    /// Share of changed pixels, from 0 to 100
+    pub fn percent(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+        }
+    }
+
+    /// Human-readable summary, e.g. "2.35%"
+    pub fn summary(&self) -> String {
+        if self.size_changed {
+            format!("{:.2}% (resized)", self.percent())
+        } else {
+            format!("{:.2}%", self.percent())
+        }
+    }
+}
+
+/// Compare two images pixel by pixel
+///
+/// A pixel counts as changed when any RGBA channel differs by more than
+/// `tolerance`. When the sizes differ, the images are aligned at the top-left
+/// corner and pixels covered by only one of them count as changed.
+pub fn compare_images(before: &DynamicImage, after: &DynamicImage, tolerance: u8) -> ImageDiffStats {
+    let (bw, bh) = before.dimensions();
+    let (aw, ah) = after.dimensions();
+    let (overlap_w, overlap_h) = (bw.min(aw), bh.min(ah));
+
+    let union = |w: u32, h: u32| w as u64 * h as u64;
+    let total_pixels = union(bw.max(aw), bh.max(ah));
+    // Everything outside the overlap exists in only one image (or neither)
+    let mut changed_pixels = total_pixels - union(overlap_w, overlap_h);
+
+    for y in 0..overlap_h {
+        for x in 0..overlap_w {
+            let a = before.get_pixel(x, y).0;
+            let b = after.get_pixel(x, y).0;
+            if a.iter().zip(b.iter()).any(|(p, q)| p.abs_diff(*q) > tolerance) {
+                changed_pixels += 1;
+            }
+        }
+    }
+
+    ImageDiffStats {
+        changed_pixels,
+        total_pixels,
+        size_changed: (bw, bh) != (aw, ah),
+    }
+}
+
+/// How the two versions are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageDiffMode {
+    /// Both versions next to each other
+    SideBySide,
+    /// One version at a time, alternating
+    Blink,
+}
+
+/// Before/after view of an image
+#[derive(Debug)]
+pub struct ImageDiffView {
+    before: ImageWidget,
+    after: ImageWidget,
+    mode: ImageDiffMode,
+    stats: Option<ImageDiffStats>,
+    /// Whether blink mode currently shows the new version
+    showing_after: bool,
+    /// Whether blink mode alternates on its own
+    auto_blink: bool,
+    blink_interval: Duration,
+    last_blink: Instant,
+}
+
+impl ImageDiffView {
+    /// Create an empty diff view
+    pub fn new(config: ImageConfig) -> Self {
+        Self {
+            before: ImageWidget::with_config(config.clone()),
+            after: ImageWidget::with_config(config),
+            mode: ImageDiffMode::SideBySide,
+            stats: None,
+            showing_after: true,
+            auto_blink: true,
+            blink_interval: DEFAULT_BLINK_INTERVAL,
+            last_blink: Instant::now(),
+        }
+    }
+
+    /// Load both versions from files
+    pub async fn load_from_paths<P: AsRef<Path>>(&mut self, before: P, after: P) -> Result<()> {
+        self.before.load_from_path(before).await?;
+        self.after.load_from_path(after).await?;
+        self.refresh_stats()
+    }
+
+    /// Load both versions from encoded bytes
+    pub fn load_from_bytes(&mut self, before: &[u8], after: &[u8]) -> Result<()> {
+        self.before.load_from_bytes(before)?;
+        self.after.load_from_bytes(after)?;
+        self.refresh_stats()
+    }
+
+    /// Set how long each version is shown in blink mode
+    pub fn with_blink_interval(mut self, interval: Duration) -> Self {
+        self.blink_interval = interval;
+        self
+    }
+
+    /// Current layout mode
+    pub fn mode(&self) -> ImageDiffMode {
+        self.mode
+    }
+
+    /// Switch between side-by-side and blink mode
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            ImageDiffMode::SideBySide => ImageDiffMode::Blink,
+            ImageDiffMode::Blink => ImageDiffMode::SideBySide,
+        };
+        self.showing_after = true;
+        self.last_blink = Instant::now();
+    }
+
+    /// Pixel difference between the versions, once both are loaded
+    pub fn stats(&self) -> Option<ImageDiffStats> {
+        self.stats
+    }
+
+    /// Whether blink mode currently shows the new version
+    pub fn showing_after(&self) -> bool {
+        self.showing_after
+    }
+
+    /// Show the other version in blink mode and stop alternating on its own
+    pub fn flip(&mut self) {
+        self.showing_after = !self.showing_after;
+        self.auto_blink = false;
+        self.last_blink = Instant::now();
+    }
+
+    /// Resume alternating automatically in blink mode
+    pub fn resume_blinking(&mut self) {
+        self.auto_blink = true;
+        self.last_blink = Instant::now();
+    }
+
+    /// Advance the blink animation; returns whether a redraw is needed
+    pub fn tick(&mut self) -> bool {
+        if self.mode != ImageDiffMode::Blink
+            || !self.auto_blink
+            || self.last_blink.elapsed() < self.blink_interval
+        {
+            return false;
+        }
+        self.showing_after = !self.showing_after;
+        self.last_blink = Instant::now();
+        true
+    }
+
+    /// Handle a key press; returns whether it was used
+    ///
+    /// `m` switches mode, space flips between versions in blink mode and `b`
+    /// resumes automatic blinking.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('m') => self.toggle_mode(),
+            KeyCode::Char(' ') if self.mode == ImageDiffMode::Blink => self.flip(),
+            KeyCode::Char('b') if self.mode == ImageDiffMode::Blink => self.resume_blinking(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Render the view into lines fitting the given area
+    pub fn render(&self, area: Rect) -> Result<Vec<Line<'static>>> {
+        let mut lines = vec![self.render_header()];
+        let body = Rect {
+            height: area.height.saturating_sub(1),
+            ..area
+        };
+
+        match self.mode {
+            ImageDiffMode::Blink => {
+                let widget = if self.showing_after { &self.after } else { &self.before };
+                lines.extend(widget.render(body)?);
+            }
+            ImageDiffMode::SideBySide => {
+                let half = body.width.saturating_sub(SEPARATOR.len() as u16) / 2;
+                let left_area = Rect { width: half, ..body };
+                let right_area = Rect {
+                    x: body.x + half + SEPARATOR.len() as u16,
+                    width: half,
+                    ..body
+                };
+                let left = self.before.render(left_area)?;
+                let right = self.after.render(right_area)?;
+
+                for i in 0..left.len().max(right.len()) {
+                    let mut spans: Vec<Span<'static>> = Vec::new();
+                    let left_line = left.get(i).cloned().unwrap_or_default();
+                    let padding = (half as usize).saturating_sub(left_line.width());
+                    spans.extend(left_line.spans);
+                    spans.push(Span::raw(" ".repeat(padding)));
+                    spans.push(Span::styled(SEPARATOR, Style::default().fg(Color::DarkGray)));
+                    if let Some(right_line) = right.get(i) {
+                        spans.extend(right_line.spans.clone());
+                    }
+                    lines.push(Line::from(spans));
+                }
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Title line naming the visible version(s) and the difference
+    fn render_header(&self) -> Line<'static> {
+        let label = Style::default().fg(Color::Gray);
+        let active = Style::default().add_modifier(Modifier::BOLD);
+        let mut spans = match self.mode {
+            ImageDiffMode::SideBySide => vec![
+                Span::styled("Before", active),
+                Span::styled(" │ ", label),
+                Span::styled("After", active),
+            ],
+            ImageDiffMode::Blink => vec![Span::styled(
+                if self.showing_after { "After" } else { "Before" },
+                active,
+            )],
+        };
+        if let Some(stats) = self.stats {
+            spans.push(Span::styled("  Changed: ", label));
+            spans.push(Span::raw(stats.summary()));
+        }
+        Line::from(spans)
+    }
+
+    /// Recompute the difference and record it on the new version's metadata
+    fn refresh_stats(&mut self) -> Result<()> {
+        let before = self.before.image().context("Original image is not loaded")?;
+        let after = self.after.image().context("Modified image is not loaded")?;
+        let stats = compare_images(before, after, DEFAULT_TOLERANCE);
+        if let Some(metadata) = self.after.metadata_mut() {
+            metadata
+                .extra_info
+                .insert(PIXEL_DIFF_METADATA_KEY.to_string(), stats.summary());
+        }
+        self.stats = Some(stats);
+        Ok(())
+    }
+}
+
+impl Default for ImageDiffView {
+    fn default() -> Self {
+        Self::new(ImageConfig {
+            border: None,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(color))
+    }
+
+    fn png(image: RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_compare_images() {
+        let before = solid(2, 2, [0, 0, 0, 255]);
+        let mut after = before.clone();
+        after.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        // Within tolerance
+        after.put_pixel(0, 0, Rgba([4, 4, 4, 255]));
+
+        let stats = compare_images(&before.into(), &after.into(), DEFAULT_TOLERANCE);
+        assert_eq!(stats.changed_pixels, 1);
+        assert_eq!(stats.percent(), 25.0);
+        assert!(!stats.size_changed);
+
+        // Pixels outside the overlap count as changed
+        let stats = compare_images(&solid(2, 2, [0; 4]).into(), &solid(4, 2, [0; 4]).into(), 0);
+        assert_eq!((stats.changed_pixels, stats.total_pixels), (4, 8));
+        assert_eq!(stats.summary(), "50.00% (resized)");
+    }
+
+    #[test]
+    fn test_diff_view_records_stats_in_metadata() {
+        let mut after = solid(4, 4, [255, 255, 255, 255]);
+        after.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+
+        let mut view = ImageDiffView::default();
+        view.load_from_bytes(&png(solid(4, 4, [255, 255, 255, 255])), &png(after))
+            .unwrap();
+
+        assert_eq!(view.stats().unwrap().changed_pixels, 1);
+        let metadata = view.after.metadata().unwrap();
+        assert_eq!(metadata.extra_info[PIXEL_DIFF_METADATA_KEY], "6.25%");
+
+        let lines = view.render(Rect::new(0, 0, 40, 10)).unwrap();
+        assert!(lines[0].spans.iter().any(|s| s.content == "6.25%"));
+    }
+
+    #[test]
+    fn test_blink_mode() {
+        let mut view = ImageDiffView::default().with_blink_interval(Duration::ZERO);
+        assert!(!view.tick(), "side-by-side mode does not blink");
+
+        view.toggle_mode();
+        assert_eq!(view.mode(), ImageDiffMode::Blink);
+        assert!(view.showing_after());
+        assert!(view.tick());
+        assert!(!view.showing_after());
+
+        // Flipping by hand pauses the animation
+        view.flip();
+        assert!(view.showing_after());
+        assert!(!view.tick());
+        view.resume_blinking();
+        assert!(view.tick());
+    }
+}
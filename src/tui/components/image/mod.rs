@@ -2,7 +2,8 @@
 //! 
 //! This module provides comprehensive image support for the TUI,
 //! including loading, resizing, and terminal-based rendering of
-//! various image formats including PNG, JPEG, GIF, and SVG.
+//! various image formats including PNG, JPEG, GIF, and SVG. Terminals with
+//! Sixel, Kitty or iTerm2 image support get the image at full resolution.
 
 use anyhow::Result;
 use image::{ImageFormat, DynamicImage, ImageReader};
@@ -15,12 +16,16 @@ use ratatui::{
 };
 use std::io::Cursor;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs;
 
 pub mod renderer;
 pub mod loader;
 pub mod formats;
+pub mod diff;
+pub mod protocol;
 
+pub use protocol::ImageProtocol;
 use renderer::ImageRenderer;
 use loader::ImageLoader;
 
@@ -41,8 +46,14 @@ pub struct ImageWidget {
     
     /// Image metadata
     metadata: Option<ImageMetadata>,
+    
+    /// Identifies the loaded image to the native image protocols
+    key: u64,
 }
 
+/// Keys of loaded images
+static NEXT_IMAGE_KEY: AtomicU64 = AtomicU64::new(1);
+
 /// Configuration for image display
 #[derive(Debug, Clone)]
 pub struct ImageConfig {
@@ -69,6 +80,9 @@ pub struct ImageConfig {
     
     /// Title display
     pub title: Option<String>,
+    
+    /// How the image is drawn when rendered as a widget
+    pub protocol: ImageProtocol,
 }
 
 /// Image rendering quality
@@ -144,6 +158,7 @@ impl Default for ImageConfig {
             show_metadata: false,
             border: Some(Borders::ALL),
             title: None,
+            protocol: ImageProtocol::Auto,
         }
     }
 }
@@ -157,6 +172,7 @@ impl ImageWidget {
             state: ImageState::Empty,
             error: None,
             metadata: None,
+            key: 0,
         }
     }
     
@@ -168,6 +184,7 @@ impl ImageWidget {
             state: ImageState::Empty,
             error: None,
             metadata: None,
+            key: 0,
         }
     }
     
@@ -180,6 +197,7 @@ impl ImageWidget {
             Ok((image, metadata)) => {
                 self.image = Some(image);
                 self.metadata = Some(metadata);
+                self.key = NEXT_IMAGE_KEY.fetch_add(1, Ordering::Relaxed);
                 self.state = ImageState::Ready;
                 Ok(())
             }
@@ -200,6 +218,7 @@ impl ImageWidget {
             Ok((image, metadata)) => {
                 self.image = Some(image);
                 self.metadata = Some(metadata);
+                self.key = NEXT_IMAGE_KEY.fetch_add(1, Ordering::Relaxed);
                 self.state = ImageState::Ready;
                 Ok(())
             }
@@ -220,6 +239,7 @@ impl ImageWidget {
             Ok((image, metadata)) => {
                 self.image = Some(image);
                 self.metadata = Some(metadata);
+                self.key = NEXT_IMAGE_KEY.fetch_add(1, Ordering::Relaxed);
                 self.state = ImageState::Ready;
                 Ok(())
             }
@@ -256,6 +276,16 @@ impl ImageWidget {
         self.metadata.as_ref()
     }
     
+    /// Get the loaded image if any
+    pub fn image(&self) -> Option<&DynamicImage> {
+        self.image.as_ref()
+    }
+    
+    /// Get mutable image metadata if available
+    pub fn metadata_mut(&mut self) -> Option<&mut ImageMetadata> {
+        self.metadata.as_mut()
+    }
+    
     /// Check if image is loaded
     pub fn is_loaded(&self) -> bool {
         self.state == ImageState::Ready && self.image.is_some()
@@ -269,6 +299,25 @@ impl ImageWidget {
         self.state = ImageState::Empty;
     }
     
+    /// Queue the image to be drawn natively inside `area`
+    ///
+    /// Returns the cells the image covers, or `None` when it is drawn as text
+    /// because the protocol is half blocks or no image is loaded.
+    pub fn place(&self, area: Rect) -> Option<Rect> {
+        let protocol = self.config.protocol.resolve();
+        if !protocol.is_native() || self.state != ImageState::Ready {
+            return None;
+        }
+        let image = self.image.as_ref()?;
+        match protocol::place(area, protocol, self.key, image) {
+            Ok(placed) => Some(placed),
+            Err(e) => {
+                tracing::debug!("Drawing the image with half blocks: {}", e);
+                None
+            }
+        }
+    }
+    
     /// Render the image widget
     pub fn render(&self, area: Rect) -> Result<Vec<Line<'static>>> {
         match self.state {
@@ -402,6 +451,14 @@ impl Widget for ImageWidget {
             area
         };
         
+        // Native images are written after the frame, over blank cells
+        if let Some(placed) = self.place(inner_area) {
+            for y in placed.top()..placed.bottom() {
+                buf.set_string(placed.x, y, " ".repeat(placed.width as usize), Style::default());
+            }
+            return;
+        }
+        
         // Render the image content
         if let Ok(lines) = self.render(inner_area) {
             for (i, line) in lines.iter().enumerate() {
@@ -0,0 +1,395 @@
+//! Native terminal image protocols
+//!
+//! Terminals that speak Sixel, the Kitty graphics protocol or iTerm2 inline
+//! images can show images at full resolution instead of half-block cells.
+//! Their escape sequences can't go through ratatui's cell buffer, so widgets
+//! reserve blank cells and queue a placement while rendering, and the main loop
+//! writes the queued images with [`flush`] once the frame is drawn. Placements
+//! are only written again when they change, since ratatui leaves cells it
+//! considers unchanged alone; overlays drawn above an image [`hide`] it.
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
+use ratatui::layout::Rect;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use crate::tui::{GraphicsSupport, TerminalCapabilities};
+
+/// Largest chunk of base64 data in one Kitty escape sequence
+const KITTY_CHUNK: usize = 4096;
+
+/// Cell size assumed when the terminal doesn't report its pixel size
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// How images are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ImageProtocol {
+    /// The best protocol the terminal supports
+    #[default]
+    Auto,
+    /// Two pixels per character cell with half blocks, works everywhere
+    HalfBlocks,
+    /// DEC Sixel graphics
+    Sixel,
+    /// Kitty graphics protocol
+    Kitty,
+    /// iTerm2 inline images
+    ITerm2,
+}
+
+impl ImageProtocol {
+    /// The protocol to use in this terminal, never `Auto`
+    pub fn resolve(self) -> Self {
+        static DETECTED: OnceLock<ImageProtocol> = OnceLock::new();
+        match self {
+            Self::Auto => *DETECTED.get_or_init(|| Self::for_terminal(&TerminalCapabilities::probe())),
+            protocol => protocol,
+        }
+    }
+
+    /// The best protocol a terminal supports
+    pub fn for_terminal(capabilities: &TerminalCapabilities) -> Self {
+        match capabilities.graphics {
+            GraphicsSupport::Sixel => Self::Sixel,
+            GraphicsSupport::Kitty => Self::Kitty,
+            GraphicsSupport::ITerm2 => Self::ITerm2,
+            GraphicsSupport::None => Self::HalfBlocks,
+        }
+    }
+
+    /// Whether the protocol draws pixels rather than characters
+    pub fn is_native(self) -> bool {
+        matches!(self.resolve(), Self::Sixel | Self::Kitty | Self::ITerm2)
+    }
+}
+
+/// An image queued to be drawn at a position
+#[derive(Debug, Clone)]
+struct Placement {
+    area: Rect,
+    protocol: ImageProtocol,
+    key: u64,
+    sequence: Rc<str>,
+}
+
+impl Placement {
+    fn same_as(&self, other: &Placement) -> bool {
+        self.area == other.area && self.protocol == other.protocol && self.key == other.key
+    }
+}
+
+#[derive(Default)]
+struct Graphics {
+    /// Placements queued while rendering the current frame
+    frame: Vec<Placement>,
+    /// Placements on screen after the last flush
+    shown: Vec<Placement>,
+    /// Set when the screen was redrawn and everything has to be placed again
+    invalidated: bool,
+    cell_size: Option<(u16, u16)>,
+}
+
+thread_local! {
+    static GRAPHICS: RefCell<Graphics> = RefCell::new(Graphics::default());
+}
+
+/// Queue an image to be drawn inside `area` and return the cells it covers
+///
+/// `key` identifies the image: an image placed with the same key, area and
+/// protocol as in the last frame is not encoded again.
+pub fn place(area: Rect, protocol: ImageProtocol, key: u64, image: &DynamicImage) -> Result<Rect> {
+    GRAPHICS.with(|graphics| {
+        let mut graphics = graphics.borrow_mut();
+        let cell_size = *graphics.cell_size.get_or_insert_with(cell_size);
+        let area = fit(image.dimensions(), area, cell_size);
+
+        let cached = graphics
+            .shown
+            .iter()
+            .find(|shown| shown.area == area && shown.protocol == protocol && shown.key == key)
+            .map(|shown| shown.sequence.clone());
+        let sequence = match cached {
+            Some(sequence) => sequence,
+            None => encode(protocol, image, area, cell_size, key)?.into(),
+        };
+        graphics.frame.push(Placement {
+            area,
+            protocol,
+            key,
+            sequence,
+        });
+        Ok(area)
+    })
+}
+
+/// Drop the queued images that overlap an area drawn above them
+pub fn hide(area: Rect) {
+    GRAPHICS.with(|graphics| graphics.borrow_mut().frame.retain(|placement| !placement.area.intersects(area)));
+}
+
+/// Place every image again on the next flush, after the screen was cleared or resized
+pub fn invalidate() {
+    GRAPHICS.with(|graphics| {
+        let mut graphics = graphics.borrow_mut();
+        graphics.invalidated = true;
+        graphics.cell_size = None;
+    });
+}
+
+/// Write the images of the frame just drawn, if they changed
+pub fn flush(out: &mut impl Write) -> std::io::Result<()> {
+    GRAPHICS.with(|graphics| {
+        let mut graphics = graphics.borrow_mut();
+        let frame = std::mem::take(&mut graphics.frame);
+        let unchanged = frame.len() == graphics.shown.len()
+            && frame.iter().zip(&graphics.shown).all(|(a, b)| a.same_as(b));
+        if unchanged && !graphics.invalidated {
+            return Ok(());
+        }
+
+        // Kitty images float above the text, so old ones have to be removed
+        if graphics.shown.iter().chain(&frame).any(|p| p.protocol == ImageProtocol::Kitty) {
+            out.write_all(b"\x1b_Ga=d,d=a,q=2\x1b\\")?;
+        }
+        out.write_all(b"\x1b7")?;
+        for placement in &frame {
+            write!(out, "\x1b[{};{}H", placement.area.y + 1, placement.area.x + 1)?;
+            out.write_all(placement.sequence.as_bytes())?;
+        }
+        out.write_all(b"\x1b8")?;
+        out.flush()?;
+
+        graphics.shown = frame;
+        graphics.invalidated = false;
+        Ok(())
+    })
+}
+
+/// Pixel size of a terminal cell
+fn cell_size() -> (u16, u16) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Cells an image covers when scaled down to fit `area`, keeping its aspect ratio
+fn fit((width, height): (u32, u32), area: Rect, (cell_width, cell_height): (u16, u16)) -> Rect {
+    let max_width = area.width as f64 * cell_width as f64;
+    let max_height = area.height as f64 * cell_height as f64;
+    let scale = (max_width / width.max(1) as f64)
+        .min(max_height / height.max(1) as f64)
+        .min(1.0);
+    let columns = (width as f64 * scale / cell_width as f64).ceil() as u16;
+    let rows = (height as f64 * scale / cell_height as f64).ceil() as u16;
+    Rect {
+        width: columns.clamp(1, area.width.max(1)),
+        height: rows.clamp(1, area.height.max(1)),
+        ..area
+    }
+}
+
+/// Escape sequence drawing an image over `area`
+fn encode(protocol: ImageProtocol, image: &DynamicImage, area: Rect, cell_size: (u16, u16), key: u64) -> Result<String> {
+    let pixel_width = area.width as u32 * cell_size.0 as u32;
+    let pixel_height = area.height as u32 * cell_size.1 as u32;
+    let scaled = if image.width() > pixel_width || image.height() > pixel_height {
+        image.resize(pixel_width, pixel_height, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+
+    match protocol.resolve() {
+        ImageProtocol::Sixel => Ok(encode_sixel(&scaled)),
+        ImageProtocol::Kitty => encode_kitty(&scaled, area, key),
+        ImageProtocol::ITerm2 => encode_iterm2(&scaled, area),
+        ImageProtocol::Auto | ImageProtocol::HalfBlocks => {
+            anyhow::bail!("Half blocks are drawn as text, not placed")
+        }
+    }
+}
+
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// Kitty graphics: PNG data sent in chunks and shown over the given cells
+fn encode_kitty(image: &DynamicImage, area: Rect, key: u64) -> Result<String> {
+    let data = BASE64.encode(png(image)?);
+    // Kitty image IDs are 32 bit and must not be zero
+    let id = (key % u32::MAX as u64) + 1;
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut sequence = String::with_capacity(data.len() + chunks.len() * 16 + 64);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk)?;
+        if i == 0 {
+            sequence.push_str(&format!(
+                "\x1b_Ga=T,f=100,i={},c={},r={},C=1,q=2,m={};{}\x1b\\",
+                id, area.width, area.height, more, chunk
+            ));
+        } else {
+            sequence.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    Ok(sequence)
+}
+
+/// iTerm2 inline image: the whole PNG in one sequence, scaled to the cells
+fn encode_iterm2(image: &DynamicImage, area: Rect) -> Result<String> {
+    let bytes = png(image)?;
+    Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        bytes.len(),
+        area.width,
+        area.height,
+        BASE64.encode(&bytes)
+    ))
+}
+
+/// Index of a color in a 6×6×6 color cube
+fn cube_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| (c as u16 * 5 + 127) / 255;
+    (level(r) * 36 + level(g) * 6 + level(b)) as u8
+}
+
+/// Sixel graphics with the image quantized to a 216 color cube
+///
+/// Pixels are written in bands of six rows: for each color of a band, one
+/// character per column says which of its six pixels have that color.
+/// Transparent pixels are left out.
+fn encode_sixel(image: &DynamicImage) -> String {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+    let mut sequence = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+
+    for index in 0..216u16 {
+        let percent = |level: u16| level * 100 / 5;
+        sequence.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / 36),
+            percent(index / 6 % 6),
+            percent(index % 6)
+        ));
+    }
+
+    for band in (0..height).step_by(6) {
+        let mut colors: HashMap<u8, Vec<u8>> = HashMap::new();
+        for row in 0..6.min(height - band) {
+            for x in 0..width {
+                let pixel = image.get_pixel(x, band + row);
+                if pixel[3] < 128 {
+                    continue;
+                }
+                let bits = colors
+                    .entry(cube_index(pixel[0], pixel[1], pixel[2]))
+                    .or_insert_with(|| vec![0; width as usize]);
+                bits[x as usize] |= 1 << row;
+            }
+        }
+
+        let mut colors: Vec<_> = colors.into_iter().collect();
+        colors.sort_by_key(|(index, _)| *index);
+        for (index, bits) in colors {
+            sequence.push_str(&format!("#{}", index));
+            push_sixel_run_lengths(&mut sequence, &bits);
+            sequence.push('$');
+        }
+        sequence.push('-');
+    }
+
+    sequence.push_str("\x1b\\");
+    sequence
+}
+
+/// Sixel characters for a row of a band, with runs compressed
+fn push_sixel_run_lengths(sequence: &mut String, bits: &[u8]) {
+    let mut i = 0;
+    while i < bits.len() {
+        let run = bits[i..].iter().take_while(|b| **b == bits[i]).count();
+        let ch = (63 + bits[i]) as char;
+        if run > 3 {
+            sequence.push_str(&format!("!{}{}", run, ch));
+        } else {
+            (0..run).for_each(|_| sequence.push(ch));
+        }
+        i += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn solid(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])))
+    }
+
+    #[test]
+    fn test_images_fit_the_area_keeping_their_aspect_ratio() {
+        let area = Rect::new(2, 3, 40, 10);
+        assert_eq!(fit((800, 400), area, (8, 16)), Rect::new(2, 3, 40, 10));
+        assert_eq!(fit((160, 160), area, (8, 16)), Rect::new(2, 3, 20, 10));
+        // Small images are not scaled up
+        assert_eq!(fit((16, 16), area, (8, 16)), Rect::new(2, 3, 2, 1));
+    }
+
+    #[test]
+    fn test_sixel_encodes_bands_with_run_lengths() {
+        let sequence = encode_sixel(&solid(10, 12));
+        assert!(sequence.starts_with("\x1bP0;1;0q\"1;1;10;12"));
+        assert!(sequence.ends_with("\x1b\\"));
+        // Pure red is the last red level of the cube, two full bands of it
+        let red = cube_index(255, 0, 0);
+        assert_eq!(red, 180);
+        assert_eq!(sequence.matches("#180!10~$-").count(), 2);
+    }
+
+    #[test]
+    fn test_kitty_and_iterm2_sequences() {
+        let area = Rect::new(0, 0, 4, 2);
+        let kitty = encode_kitty(&solid(32, 32), area, 7).unwrap();
+        assert!(kitty.starts_with("\x1b_Ga=T,f=100,i=8,c=4,r=2,C=1,q=2,m=0;"));
+
+        let iterm = encode_iterm2(&solid(32, 32), area).unwrap();
+        assert!(iterm.starts_with("\x1b]1337;File=inline=1;size="));
+        assert!(iterm.contains(";width=4;height=2;"));
+        assert!(iterm.ends_with('\x07'));
+    }
+
+    #[test]
+    fn test_unchanged_placements_are_not_written_again() {
+        let image = solid(16, 16);
+        let area = Rect::new(0, 0, 4, 4);
+        let mut out = Vec::new();
+
+        place(area, ImageProtocol::ITerm2, 1, &image).unwrap();
+        flush(&mut out).unwrap();
+        assert!(!out.is_empty());
+
+        out.clear();
+        place(area, ImageProtocol::ITerm2, 1, &image).unwrap();
+        flush(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        // Hidden under an overlay, then shown again
+        place(area, ImageProtocol::ITerm2, 1, &image).unwrap();
+        hide(Rect::new(0, 0, 10, 10));
+        flush(&mut out).unwrap();
+        out.clear();
+        place(area, ImageProtocol::ITerm2, 1, &image).unwrap();
+        flush(&mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("1337;File="));
+    }
+}
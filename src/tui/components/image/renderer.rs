@@ -228,7 +228,8 @@ impl ImageRenderer {
             RenderQuality::High => image::imageops::FilterType::Lanczos3,
         };
         
-        image.resize(width, height, filter)
+        // The display size already keeps the aspect ratio, and every pixel of it is read
+        image.resize_exact(width.max(1), height.max(1), filter)
     }
 }
 
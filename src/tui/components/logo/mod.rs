@@ -6,6 +6,7 @@
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span, Text};
 use crate::tui::themes::colors::{ColorPalette, manipulate};
+use crate::utils::width::{display_width, truncate_to_width};
 
 /// Options for rendering the Goofy logo
 #[derive(Debug, Clone)]
@@ -63,24 +64,16 @@ pub fn render_logo(version: &str, opts: LogoOpts) -> Text<'static> {
     let stretch_index = if opts.compact { None } else { Some(2) }; // Stretch second 'O'
     
     let logo_lines = render_word(&letters, spacing, stretch_index);
-    let logo_width = logo_lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    let logo_width = logo_lines.iter().map(|line| display_width(line)).max().unwrap_or(0);
     
     // Apply gradient to the logo
     let gradient_logo = apply_gradient_to_lines(&logo_lines, opts.gradient_start, opts.gradient_end);
     
     // Create meta row (brand + version)
-    let version_truncated = if version.len() + brand_text.len() + 1 > logo_width {
-        let max_version_len = logo_width.saturating_sub(brand_text.len() + 1);
-        if version.len() > max_version_len {
-            format!("{}…", &version[..max_version_len.saturating_sub(1)])
-        } else {
-            version.to_string()
-        }
-    } else {
-        version.to_string()
-    };
+    let max_version_len = logo_width.saturating_sub(display_width(brand_text) + 1);
+    let version_truncated = truncate_to_width(version, max_version_len);
     
-    let gap_size = logo_width.saturating_sub(brand_text.len() + version_truncated.len());
+    let gap_size = logo_width.saturating_sub(display_width(brand_text) + display_width(&version_truncated));
     let gap = " ".repeat(gap_size);
     
     let meta_line = Line::from(vec![
@@ -106,7 +99,7 @@ pub fn render_small_logo(width: usize, opts: LogoOpts) -> Line<'static> {
     let brand = "Goofy™";
     let brand_span = Span::styled(brand, Style::default().fg(opts.brand_color));
     
-    let remaining_width = width.saturating_sub(brand.len() + 1);
+    let remaining_width = width.saturating_sub(display_width(brand) + 1);
     let field_pattern = DIAG.repeat(remaining_width);
     let field_span = Span::styled(field_pattern, Style::default().fg(opts.field_color));
     
@@ -208,7 +201,7 @@ fn render_word(letters: &[LetterForm], spacing: usize, stretch_index: Option<usi
                 line.push_str(&letter[row]);
             } else {
                 // Pad with spaces if this letter is shorter
-                let width = letter.get(0).map_or(0, |s| s.len());
+                let width = letter.first().map_or(0, |s| display_width(s));
                 line.push_str(&" ".repeat(width));
             }
         }
@@ -12,6 +12,8 @@ use std::path::Path;
 
 use super::styles::MarkdownStyles;
 use crate::tui::components::image::{ImageWidget, ImageConfig};
+use crate::tui::themes::{self, IconStyle};
+use crate::utils::width::{middle_ellipsis, pad_to_width, truncate_with};
 
 /// Image placeholder configuration
 #[derive(Debug, Clone)]
@@ -99,7 +101,7 @@ impl ImageRenderer {
     fn render_simple_placeholder(&self, info: &ImageInfo) -> Result<Vec<Line<'static>>> {
         let mut lines = Vec::new();
         
-        let prefix = if info.is_url { "🌐" } else { "🖼" };
+        let prefix = image_icon(info);
         let main_text = if info.alt_text.is_empty() {
             format!("{} Image: {}", prefix, info.source)
         } else {
@@ -107,15 +109,7 @@ impl ImageRenderer {
         };
         
         // Truncate if too long
-        let truncated_text = if main_text.chars().count() > self.config.max_width as usize {
-            let mut truncated = main_text.chars()
-                .take(self.config.max_width as usize - 3)
-                .collect::<String>();
-            truncated.push_str("...");
-            truncated
-        } else {
-            main_text
-        };
+        let truncated_text = truncate_with(&main_text, self.config.max_width as usize, "...");
         
         let span = Span::styled(truncated_text, self.styles.image);
         lines.push(Line::from(span));
@@ -156,7 +150,7 @@ impl ImageRenderer {
         lines.push(Line::from(Span::styled(top_border, border_style)));
         
         // Content lines
-        let prefix = if info.is_url { "🌐" } else { "🖼" };
+        let prefix = image_icon(info);
         let content_lines = if info.alt_text.is_empty() {
             vec![
                 format!("{} Image", prefix),
@@ -170,22 +164,8 @@ impl ImageRenderer {
         };
         
         for content_line in content_lines {
-            let truncated = if content_line.chars().count() > box_width - 4 {
-                let mut truncated = content_line.chars()
-                    .take(box_width - 7)
-                    .collect::<String>();
-                truncated.push_str("...");
-                truncated
-            } else {
-                content_line
-            };
-            
-            let padding_needed = box_width - 4 - truncated.chars().count();
-            let padded_content = format!(
-                "│ {}{} │",
-                truncated,
-                " ".repeat(padding_needed)
-            );
+            let truncated = truncate_with(&content_line, box_width - 4, "...");
+            let padded_content = format!("│ {} │", pad_to_width(&truncated, box_width - 4));
             
             lines.push(Line::from(Span::styled(padded_content, border_style)));
         }
@@ -194,22 +174,8 @@ impl ImageRenderer {
         if let Some(title) = &info.title {
             if !title.is_empty() {
                 let title_line = format!("Title: {}", title);
-                let truncated = if title_line.chars().count() > box_width - 4 {
-                    let mut truncated = title_line.chars()
-                        .take(box_width - 7)
-                        .collect::<String>();
-                    truncated.push_str("...");
-                    truncated
-                } else {
-                    title_line
-                };
-                
-                let padding_needed = box_width - 4 - truncated.chars().count();
-                let padded_content = format!(
-                    "│ {}{} │",
-                    truncated,
-                    " ".repeat(padding_needed)
-                );
+                let truncated = truncate_with(&title_line, box_width - 4, "...");
+                let padded_content = format!("│ {} │", pad_to_width(&truncated, box_width - 4));
                 
                 lines.push(Line::from(Span::styled(padded_content, border_style.fg(Color::Gray))));
             }
@@ -234,18 +200,7 @@ impl ImageRenderer {
         lines.push(Line::from(Span::styled(top_decoration, self.styles.image)));
         
         // Main content with icon
-        let icon = if info.is_url {
-            "🌐"
-        } else {
-            match info.extension.as_deref() {
-                Some("png") | Some("PNG") => "🖼",
-                Some("jpg") | Some("jpeg") | Some("JPG") | Some("JPEG") => "📷",
-                Some("gif") | Some("GIF") => "🎞",
-                Some("svg") | Some("SVG") => "🎨",
-                Some("webp") | Some("WEBP") => "🖼",
-                _ => "🖼",
-            }
-        };
+        let icon = image_icon(info);
         
         let main_content = if info.alt_text.is_empty() {
             format!("{} Image", icon)
@@ -253,43 +208,19 @@ impl ImageRenderer {
             format!("{} {}", icon, info.alt_text)
         };
         
-        let content_padding = frame_width.saturating_sub(main_content.chars().count() + 4);
-        let content_line = format!(
-            "│ {}{} │",
-            main_content,
-            " ".repeat(content_padding)
-        );
+        let content_line = format!("│ {} │", pad_to_width(&main_content, frame_width - 4));
         lines.push(Line::from(Span::styled(content_line, self.styles.image)));
         
         // Source line
-        let source_display = if info.source.chars().count() > frame_width - 6 {
-            let mut truncated = info.source.chars()
-                .take(frame_width - 9)
-                .collect::<String>();
-            truncated.push_str("...");
-            truncated
-        } else {
-            info.source.clone()
-        };
-        
-        let source_padding = frame_width.saturating_sub(source_display.chars().count() + 4);
-        let source_line = format!(
-            "│ {}{} │",
-            source_display,
-            " ".repeat(source_padding)
-        );
+        let source_display = middle_ellipsis(&info.source, frame_width - 6);
+        let source_line = format!("│ {} │", pad_to_width(&source_display, frame_width - 4));
         lines.push(Line::from(Span::styled(source_line, self.styles.image.fg(Color::Gray))));
         
         // Format info if enabled
         if self.config.show_format {
             if let Some(extension) = &info.extension {
                 let format_text = format!("Format: {}", extension.to_uppercase());
-                let format_padding = frame_width.saturating_sub(format_text.chars().count() + 4);
-                let format_line = format!(
-                    "│ {}{} │",
-                    format_text,
-                    " ".repeat(format_padding)
-                );
+                let format_line = format!("│ {} │", pad_to_width(&format_text, frame_width - 4));
                 lines.push(Line::from(Span::styled(format_line, self.styles.image.fg(Color::DarkGray))));
             }
         }
@@ -302,6 +233,21 @@ impl ImageRenderer {
     }
 }
 
+/// Icon of an image in the current theme's icon set
+///
+/// Emoji sets tell image formats apart; the other sets have one image icon.
+fn image_icon(info: &ImageInfo) -> String {
+    let theme = themes::current_theme();
+    let icons = &theme.icons;
+    if info.is_url {
+        icons.link.clone()
+    } else if icons.style == IconStyle::Emoji {
+        utils::get_image_icon(info.extension.as_deref()).to_string()
+    } else {
+        icons.image.clone()
+    }
+}
+
 /// Utility functions for image processing
 pub mod utils {
     use super::*;
@@ -343,7 +289,7 @@ pub mod utils {
         }
     }
     
-    /// Get appropriate icon for image type
+    /// Get appropriate emoji for image type
     pub fn get_image_icon(extension: Option<&str>) -> &'static str {
         match extension {
             Some("png") | Some("PNG") => "🖼",
@@ -12,6 +12,7 @@ use ratatui::{
     text::{Line, Span, Text},
     widgets::{Block, Borders, Widget, Paragraph, Wrap},
 };
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::tui::{
@@ -237,6 +238,107 @@ impl MarkdownWidget {
     }
 }
 
+/// Markdown of an answer still streaming in, rendered as it grows
+///
+/// Only the text after the last complete block can still change, so the part
+/// before it is rendered once and reused. An open code fence in the rest is
+/// closed, so it renders as code already.
+#[derive(Debug, Default)]
+pub struct StreamingMarkdown {
+    text: String,
+    width: u16,
+    stable_len: usize,
+    stable_lines: Vec<Line<'static>>,
+    lines: Vec<Line<'static>>,
+}
+
+impl StreamingMarkdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Markdown received so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Rendered lines of what was received so far
+    pub fn lines(&self) -> &[Line<'static>] {
+        &self.lines
+    }
+
+    /// Add the next chunk and render again
+    pub fn push(&mut self, chunk: &str, theme: &Theme, width: u16) {
+        self.text.push_str(chunk);
+        self.layout(theme, width);
+    }
+
+    /// Render again for another width
+    pub fn layout(&mut self, theme: &Theme, width: u16) {
+        let config = MarkdownConfig::default();
+        let stable_len = stable_markdown_prefix(&self.text);
+        if stable_len != self.stable_len || width != self.width {
+            self.stable_lines = render_lines(&self.text[..stable_len], &config, theme, width);
+            self.stable_len = stable_len;
+            self.width = width;
+        }
+        let tail = close_partial_markdown(&self.text[stable_len..]);
+        self.lines = self.stable_lines.clone();
+        self.lines.extend(render_lines(&tail, &config, theme, width));
+    }
+}
+
+fn render_lines(markdown: &str, config: &MarkdownConfig, theme: &Theme, width: u16) -> Vec<Line<'static>> {
+    if markdown.is_empty() {
+        return Vec::new();
+    }
+    match MarkdownWidget::render_string(markdown, config, theme, width) {
+        Ok(text) => text.lines,
+        Err(_) => Text::raw(markdown.to_string()).lines,
+    }
+}
+
+/// Length of the part of streamed markdown whose rendering is final
+///
+/// That is everything up to the last blank line outside a code block, or the
+/// end of the last closed code block, whichever comes later.
+pub fn stable_markdown_prefix(text: &str) -> usize {
+    let mut in_code_block = false;
+    let mut stable = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+        if line.starts_with("```") {
+            in_code_block = !in_code_block;
+            if !in_code_block {
+                stable = offset;
+            }
+        } else if !in_code_block && line.trim().is_empty() {
+            stable = offset;
+        }
+    }
+
+    stable
+}
+
+/// Close a code fence left open by a partial stream
+pub fn close_partial_markdown(text: &str) -> Cow<'_, str> {
+    let fences = text.lines().filter(|line| line.starts_with("```")).count();
+    if fences % 2 == 1 {
+        Cow::Owned(format!("{}\n```", text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
 impl Default for MarkdownWidget {
     fn default() -> Self {
         Self::new()
@@ -449,6 +551,34 @@ mod tests {
         assert_eq!(blocks[1].1, "plain code\n");
     }
     
+    #[test]
+    fn test_stable_prefix_stops_before_open_blocks() {
+        let text = "Intro paragraph.\n\n```rust\nfn main() {\n";
+        assert_eq!(stable_markdown_prefix(text), "Intro paragraph.\n\n".len());
+
+        let closed = "Intro.\n\n```rust\nfn main() {}\n```\nMore";
+        assert_eq!(stable_markdown_prefix(closed), closed.len() - "More".len());
+        assert_eq!(close_partial_markdown("```rust\nfn main() {"), "```rust\nfn main() {\n```");
+        assert_eq!(close_partial_markdown("plain"), "plain");
+    }
+
+    #[test]
+    fn test_streaming_markdown_renders_open_code_blocks() {
+        let theme = ThemeManager::new().current_theme().clone();
+        let mut streamed = StreamingMarkdown::new();
+        streamed.push("Intro.\n\n```rust\nlet x", &theme, 80);
+        streamed.push(" = 1;\n", &theme, 80);
+
+        assert_eq!(streamed.text(), "Intro.\n\n```rust\nlet x = 1;\n");
+        let rendered: Vec<String> = streamed
+            .lines()
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+        assert!(rendered.iter().any(|line| line.contains("let x = 1;")));
+        assert!(!rendered.iter().any(|line| line.contains("```")));
+    }
+    
     #[test]
     fn test_content_detection() {
         assert!(utils::contains_tables("| a | b |\n|---|---|"));
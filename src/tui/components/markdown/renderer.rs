@@ -13,6 +13,7 @@ use std::collections::HashMap;
 
 use super::{MarkdownConfig, RenderContext, TableState, styles::MarkdownStyles};
 use crate::tui::components::highlighting::{SyntaxHighlighter, HighlightConfig};
+use crate::tui::themes;
 
 /// Core markdown renderer
 pub struct MarkdownRenderer {
@@ -421,10 +422,12 @@ impl MarkdownRenderer {
             return Ok(());
         }
         
+        let theme = themes::current_theme();
+        let icon = &theme.icons.image;
         let image_text = if title.is_empty() {
-            format!("🖼 Image: {}", dest_url)
+            format!("{} Image: {}", icon, dest_url)
         } else {
-            format!("🖼 {}: {}", title, dest_url)
+            format!("{} {}: {}", icon, title, dest_url)
         };
         
         let span = Span::styled(image_text, self.styles.image);
@@ -14,6 +14,7 @@ use ratatui::{
 use std::cmp::max;
 
 use super::styles::MarkdownStyles;
+use crate::utils::width::{display_width, truncate_with};
 
 /// Table configuration
 #[derive(Debug, Clone)]
@@ -152,7 +153,7 @@ impl TableRenderer {
         // Check header widths
         for (i, header) in data.headers.iter().enumerate() {
             if i < column_count {
-                content_widths[i] = max(content_widths[i], header.chars().count() as u16);
+                content_widths[i] = max(content_widths[i], display_width(header) as u16);
             }
         }
         
@@ -160,7 +161,7 @@ impl TableRenderer {
         for row in &data.rows {
             for (i, cell) in row.iter().enumerate() {
                 if i < column_count {
-                    content_widths[i] = max(content_widths[i], cell.chars().count() as u16);
+                    content_widths[i] = max(content_widths[i], display_width(cell) as u16);
                 }
             }
         }
@@ -296,21 +297,12 @@ impl TableRenderer {
     
     /// Format cell content with alignment and padding
     fn format_cell_content(&self, content: &str, width: u16, alignment: Alignment) -> String {
-        let content_width = content.chars().count();
         let available_width = width.saturating_sub(self.config.cell_padding * 2) as usize;
         
         // Truncate content if too long
-        let truncated_content = if content_width > available_width {
-            let mut truncated = content.chars().take(available_width.saturating_sub(3)).collect::<String>();
-            if available_width > 3 {
-                truncated.push_str("...");
-            }
-            truncated
-        } else {
-            content.to_string()
-        };
+        let truncated_content = truncate_with(content, available_width, "...");
         
-        let truncated_width = truncated_content.chars().count();
+        let truncated_width = display_width(&truncated_content);
         let padding_needed = available_width.saturating_sub(truncated_width);
         
         let formatted = match alignment {
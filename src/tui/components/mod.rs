@@ -1,20 +1,27 @@
 // TODO: Re-enable these modules after fixing theme compatibility
 // pub mod chat;
 // pub mod core;
-// pub mod dialogs;
+pub mod dialogs;
 // TODO: Re-enable these modules after fixing theme compatibility
 // pub mod list;
 // pub mod input;
 // pub mod logo;
-// pub mod splash;
+pub mod splash;
 // pub mod status;
 
 pub mod completions;
+pub mod data_table;
+pub mod fences;
 pub mod files;
 pub mod lists;
 pub mod highlighting;
 pub mod image;
 pub mod markdown;
+pub mod postprocess;
+pub mod scroll;
+pub mod search;
+pub mod status_bar;
+pub mod toast;
 
 use crate::tui::{events::Event, themes::Theme, Frame};
 use anyhow::Result;
@@ -0,0 +1,393 @@
+//! Postprocessing of assistant messages before they are rendered
+//!
+//! A finished answer passes through a chain of stages, each rewriting its
+//! markdown: links for bare URLs and issue numbers, footnotes gathered at the
+//! end, numbered headings. Stages leave code blocks and inline code alone, and
+//! each can be turned off in the `tui.postprocess` configuration.
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::PostprocessConfig;
+use crate::git::GitRepo;
+
+/// One rewrite of a message's markdown
+pub trait Stage: Send + Sync {
+    /// Name of the stage, for logs
+    fn name(&self) -> &'static str;
+
+    /// Rewrite the markdown
+    fn apply(&self, text: &str) -> String;
+}
+
+/// The chain of stages applied to assistant messages
+#[derive(Default)]
+pub struct Postprocessor {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Postprocessor {
+    /// Stages enabled in the configuration
+    ///
+    /// `repo_issue_url` is used for issue links when the configuration doesn't
+    /// set its own, usually from [`issue_url_for_remote`].
+    pub fn from_config(config: &PostprocessConfig, repo_issue_url: Option<String>) -> Self {
+        let mut postprocessor = Self::default();
+        let issue_url = if config.link_issues {
+            config.issue_url.clone().or(repo_issue_url)
+        } else {
+            None
+        };
+        if config.linkify_urls || issue_url.is_some() {
+            postprocessor = postprocessor.with_stage(Linkify {
+                urls: config.linkify_urls,
+                issue_url,
+            });
+        }
+        if config.footnotes {
+            postprocessor = postprocessor.with_stage(Footnotes);
+        }
+        if config.number_headings {
+            postprocessor = postprocessor.with_stage(HeadingNumbers);
+        }
+        postprocessor
+    }
+
+    /// Stages enabled in the configuration, linking issues of the repository
+    /// containing `dir` when no issue URL is configured
+    pub async fn for_directory(config: &PostprocessConfig, dir: &Path) -> Self {
+        let repo_issue_url = if config.link_issues && config.issue_url.is_none() {
+            match GitRepo::open(dir).await {
+                Ok(repo) => repo.remote_url("origin").await.ok().and_then(|url| issue_url_for_remote(&url)),
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+        Self::from_config(config, repo_issue_url)
+    }
+
+    /// Add a stage at the end of the chain
+    pub fn with_stage(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Check if there are no stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run the markdown through every stage in order
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for stage in &self.stages {
+            text = Cow::Owned(stage.apply(&text));
+        }
+        text
+    }
+}
+
+/// Issue URL template for a git remote on GitHub, GitLab or similar hosts
+///
+/// Both `git@host:owner/repo.git` and `https://host/owner/repo` remotes are
+/// understood. The template has `{number}` where the issue number goes.
+pub fn issue_url_for_remote(remote: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches('/').trim_end_matches(".git");
+    let (host, path) = if let Some(rest) = remote.strip_prefix("git@") {
+        rest.split_once(':')?
+    } else {
+        let rest = remote.split_once("://")?.1;
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+        rest.split_once('/')?
+    };
+    if path.split('/').filter(|part| !part.is_empty()).count() < 2 {
+        return None;
+    }
+    Some(format!("https://{}/{}/issues/{{number}}", host, path))
+}
+
+/// Bare URLs and issue references like `#123` become links
+pub struct Linkify {
+    /// Link bare URLs
+    pub urls: bool,
+    /// Template with `{number}` for issue links, none to leave issues alone
+    pub issue_url: Option<String>,
+}
+
+impl Stage for Linkify {
+    fn name(&self) -> &'static str {
+        "linkify"
+    }
+
+    fn apply(&self, text: &str) -> String {
+        static LINKABLE: OnceLock<Regex> = OnceLock::new();
+        let linkable = LINKABLE.get_or_init(|| {
+            Regex::new(r"(?P<url>https?://[^\s<>\[\]()]+)|#(?P<issue>\d+)\b").unwrap()
+        });
+
+        map_prose(text, protected_spans(), |prose| {
+            linkable
+                .replace_all(prose, |caps: &regex::Captures| {
+                    let whole = caps.get(0).unwrap();
+                    if let Some(url) = caps.name("url").filter(|_| self.urls) {
+                        // Sentence punctuation after a URL is not part of it
+                        let trimmed = url.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+                        let rest = &url.as_str()[trimmed.len()..];
+                        return format!("[{}]({}){}", trimmed, trimmed, rest);
+                    }
+                    if let (Some(number), Some(template)) = (caps.name("issue"), &self.issue_url) {
+                        let preceding = prose[..whole.start()].chars().next_back();
+                        if !preceding.is_some_and(|c| c.is_alphanumeric() || matches!(c, '&' | '/' | '#')) {
+                            let url = template.replace("{number}", number.as_str());
+                            return format!("[#{}]({})", number.as_str(), url);
+                        }
+                    }
+                    whole.as_str().to_string()
+                })
+                .into_owned()
+        })
+    }
+}
+
+/// Footnote references and link targets are numbered and listed at the end
+///
+/// `[^label]` references are renumbered in order of appearance and their
+/// `[^label]: ...` definitions move to the end. Links whose text isn't their
+/// URL keep only their text, followed by a footnote with the URL.
+pub struct Footnotes;
+
+impl Stage for Footnotes {
+    fn name(&self) -> &'static str {
+        "footnotes"
+    }
+
+    fn apply(&self, text: &str) -> String {
+        static DEFINITION: OnceLock<Regex> = OnceLock::new();
+        static REFERENCE: OnceLock<Regex> = OnceLock::new();
+        static INLINE_CODE: OnceLock<Regex> = OnceLock::new();
+        let definition = DEFINITION.get_or_init(|| Regex::new(r"^\[\^([^\]\s]+)\]:\s*(.*?)\s*$").unwrap());
+        let reference = REFERENCE.get_or_init(|| {
+            Regex::new(r"\[\^(?P<label>[^\]\s]+)\]|(?P<image>!?)\[(?P<text>[^\]]*)\]\((?P<url>[^)\s]+)\)").unwrap()
+        });
+        let inline_code = INLINE_CODE.get_or_init(|| Regex::new(r"`[^`]*`").unwrap());
+
+        // Pull out the definitions first so references before them resolve
+        let mut definitions = HashMap::new();
+        let body = map_lines(text, |line| match definition.captures(line.trim_end_matches(['\r', '\n'])) {
+            Some(caps) => {
+                definitions.insert(caps[1].to_string(), caps[2].to_string());
+                None
+            }
+            None => Some(line.to_string()),
+        });
+
+        let mut notes: Vec<String> = Vec::new();
+        let mut numbers: HashMap<String, usize> = HashMap::new();
+        let mut number_for = |key: String, note: String| {
+            *numbers.entry(key).or_insert_with(|| {
+                notes.push(note);
+                notes.len()
+            })
+        };
+        let body = map_prose(&body, inline_code, |prose| {
+            reference
+                .replace_all(prose, |caps: &regex::Captures| {
+                    if let Some(label) = caps.name("label") {
+                        return match definitions.get(label.as_str()) {
+                            Some(note) => format!("[^{}]", number_for(format!("^{}", label.as_str()), note.clone())),
+                            None => caps[0].to_string(),
+                        };
+                    }
+                    let (text, url) = (&caps["text"], &caps["url"]);
+                    if !caps["image"].is_empty() || text == url {
+                        return caps[0].to_string();
+                    }
+                    format!("{}[^{}]", text, number_for(url.to_string(), url.to_string()))
+                })
+                .into_owned()
+        });
+
+        if notes.is_empty() {
+            return body;
+        }
+        let mut result = body.trim_end().to_string();
+        result.push_str("\n\n---\n");
+        for (index, note) in notes.iter().enumerate() {
+            result.push_str(&format!("[^{}]: {}\n", index + 1, note));
+        }
+        result
+    }
+}
+
+/// Headings are numbered by section, like `1.`, `1.2.` and `1.2.1.`
+///
+/// Numbering starts at the highest level used in the message, and headings
+/// already starting with a number are left as they are.
+pub struct HeadingNumbers;
+
+impl Stage for HeadingNumbers {
+    fn name(&self) -> &'static str {
+        "heading numbers"
+    }
+
+    fn apply(&self, text: &str) -> String {
+        static HEADING: OnceLock<Regex> = OnceLock::new();
+        static NUMBERED: OnceLock<Regex> = OnceLock::new();
+        let heading = HEADING.get_or_init(|| Regex::new(r"^(#{1,6})[ \t]+(.*)$").unwrap());
+        let numbered = NUMBERED.get_or_init(|| Regex::new(r"^\d+(\.\d+)*\.?\s").unwrap());
+
+        let mut top = usize::MAX;
+        map_lines(text, |line| {
+            if let Some(caps) = heading.captures(line.trim_end_matches(['\r', '\n'])) {
+                top = top.min(caps[1].len());
+            }
+            Some(String::new())
+        });
+        if top == usize::MAX {
+            return text.to_string();
+        }
+
+        let mut counters = [0usize; 6];
+        map_lines(text, |line| {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let Some(caps) = heading.captures(content) else {
+                return Some(line.to_string());
+            };
+            if numbered.is_match(&caps[2]) {
+                return Some(line.to_string());
+            }
+            let depth = caps[1].len() - top;
+            counters[depth] += 1;
+            counters[depth + 1..].iter_mut().for_each(|counter| *counter = 0);
+            let number: String = counters[..=depth].iter().map(|counter| format!("{}.", counter)).collect();
+            Some(format!("{} {} {}{}", &caps[1], number, &caps[2], &line[content.len()..]))
+        })
+    }
+}
+
+/// Code spans and links whose text must not be linked again
+fn protected_spans() -> &'static Regex {
+    static PROTECTED: OnceLock<Regex> = OnceLock::new();
+    PROTECTED.get_or_init(|| Regex::new(r"`[^`]*`|!?\[[^\]]*\]\([^)]*\)|<https?://[^>]+>").unwrap())
+}
+
+/// Rewrite the lines outside fenced code blocks, dropping those mapped to `None`
+fn map_lines(text: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut fence: Option<&str> = None;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                result.push_str(line);
+            }
+            (Some(open), Some(marker)) if open == marker => {
+                fence = None;
+                result.push_str(line);
+            }
+            (Some(_), _) => result.push_str(line),
+            (None, None) => {
+                if let Some(line) = f(line) {
+                    result.push_str(&line);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Rewrite the prose outside code blocks and the spans matched by `protected`
+fn map_prose(text: &str, protected: &Regex, mut f: impl FnMut(&str) -> String) -> String {
+    map_lines(text, |line| {
+        let mut result = String::with_capacity(line.len());
+        let mut last = 0;
+        for span in protected.find_iter(line) {
+            result.push_str(&f(&line[last..span.start()]));
+            result.push_str(span.as_str());
+            last = span.end();
+        }
+        result.push_str(&f(&line[last..]));
+        Some(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUES: &str = "https://github.com/acme/app/issues/{number}";
+
+    #[test]
+    fn test_urls_and_issues_are_linked_outside_code() {
+        let linkify = Linkify { urls: true, issue_url: Some(ISSUES.to_string()) };
+        let text = "See https://docs.rs/tokio. Fixes #12, not a#3 or [#4](x).\n\
+                    `curl https://x.io #5`\n\
+                    ```\nhttps://in.code #6\n```\n";
+        assert_eq!(
+            linkify.apply(text),
+            "See [https://docs.rs/tokio](https://docs.rs/tokio). Fixes [#12](https://github.com/acme/app/issues/12), not a#3 or [#4](x).\n\
+             `curl https://x.io #5`\n\
+             ```\nhttps://in.code #6\n```\n"
+        );
+
+        let urls_only = Linkify { urls: true, issue_url: None };
+        assert_eq!(urls_only.apply("#7 at http://a.b/c?d=1"), "#7 at [http://a.b/c?d=1](http://a.b/c?d=1)");
+    }
+
+    #[test]
+    fn test_footnotes_are_numbered_and_collected() {
+        let text = "Uses serde[^serde] and [tokio](https://tokio.rs).\n\
+                    [^serde]: The serialization crate.\n\
+                    Again [tokio](https://tokio.rs), plain https://x.io, `[c](d)` and [^missing].\n";
+        assert_eq!(
+            Footnotes.apply(text),
+            "Uses serde[^1] and tokio[^2].\n\
+             Again tokio[^2], plain https://x.io, `[c](d)` and [^missing].\n\
+             \n---\n\
+             [^1]: The serialization crate.\n\
+             [^2]: https://tokio.rs\n"
+        );
+        assert_eq!(Footnotes.apply("nothing to note\n"), "nothing to note\n");
+    }
+
+    #[test]
+    fn test_headings_are_numbered_from_the_top_level() {
+        let text = "## Setup\n### Install\n### Configure\n```\n## not a heading\n```\n## 3. Done\n## Usage\n";
+        assert_eq!(
+            HeadingNumbers.apply(text),
+            "## 1. Setup\n### 1.1. Install\n### 1.2. Configure\n```\n## not a heading\n```\n## 3. Done\n## 2. Usage\n"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_follows_the_configuration() {
+        let config = PostprocessConfig {
+            footnotes: true,
+            number_headings: true,
+            ..Default::default()
+        };
+        let postprocessor = Postprocessor::from_config(&config, Some(ISSUES.to_string()));
+        assert_eq!(
+            postprocessor.apply("# Notes\nSee #1\n"),
+            "# 1. Notes\nSee #1[^1]\n\n---\n[^1]: https://github.com/acme/app/issues/1\n"
+        );
+
+        let off = PostprocessConfig {
+            linkify_urls: false,
+            link_issues: false,
+            ..Default::default()
+        };
+        assert!(Postprocessor::from_config(&off, Some(ISSUES.to_string())).is_empty());
+        assert!(matches!(Postprocessor::default().apply("same"), Cow::Borrowed("same")));
+
+        assert_eq!(issue_url_for_remote("git@github.com:acme/app.git").as_deref(), Some(ISSUES));
+        assert_eq!(issue_url_for_remote("https://token@github.com/acme/app\n").as_deref(), Some(ISSUES));
+        assert_eq!(issue_url_for_remote("/srv/git/app"), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! Sticky scroll state for views whose content grows at the bottom
+//!
+//! Scrolling is tracked as a distance from the bottom of the content, like
+//! the transcript or an answer being written, and kept apart from the content: appends only change the content height,
+//! which is reconciled on the next render. While following the bottom, new
+//! lines scroll into view; once the user scrolls up, the view stays anchored
+//! to what they were reading and the added lines are counted as unread.
+
+/// Scroll position of a view whose content grows at the bottom
+#[derive(Debug, Clone, Default)]
+pub struct StickyScroll {
+    /// Lines between the bottom of the viewport and the end of the content
+    offset_from_bottom: usize,
+    /// Lines added below the viewport since the user scrolled away
+    unread_lines: usize,
+    content_height: usize,
+    viewport_height: usize,
+}
+
+impl StickyScroll {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the view is pinned to the newest content
+    pub fn is_following(&self) -> bool {
+        self.offset_from_bottom == 0
+    }
+
+    /// Lines that arrived below the viewport while scrolled up
+    pub fn unread_lines(&self) -> usize {
+        self.unread_lines
+    }
+
+    /// Reconcile with the current content and viewport heights
+    pub fn update(&mut self, content_height: usize, viewport_height: usize) {
+        if content_height > self.content_height && !self.is_following() {
+            // Keep the same lines on screen by moving the anchor with the content
+            let added = content_height - self.content_height;
+            self.offset_from_bottom += added;
+            self.unread_lines += added;
+        }
+        self.content_height = content_height;
+        self.viewport_height = viewport_height;
+        self.offset_from_bottom = self.offset_from_bottom.min(self.max_offset());
+        if self.is_following() {
+            self.unread_lines = 0;
+        }
+    }
+
+    /// Scroll towards older content
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.offset_from_bottom = (self.offset_from_bottom + lines).min(self.max_offset());
+    }
+
+    /// Scroll towards newer content, resuming follow mode at the bottom
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.offset_from_bottom = self.offset_from_bottom.saturating_sub(lines);
+        self.unread_lines = self.unread_lines.min(self.offset_from_bottom);
+    }
+
+    /// Scroll one viewport up
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.viewport_height.saturating_sub(1).max(1));
+    }
+
+    /// Scroll one viewport down
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.viewport_height.saturating_sub(1).max(1));
+    }
+
+    /// Jump to the oldest content
+    pub fn scroll_to_top(&mut self) {
+        self.offset_from_bottom = self.max_offset();
+    }
+
+    /// Jump back to the newest content and follow it
+    pub fn scroll_to_bottom(&mut self) {
+        self.offset_from_bottom = 0;
+        self.unread_lines = 0;
+    }
+
+    /// Scroll so that `line` is at the top of the viewport, or as close as
+    /// the content allows
+    pub fn scroll_to_line(&mut self, line: usize) {
+        self.offset_from_bottom = self.content_height
+            .saturating_sub(self.viewport_height)
+            .saturating_sub(line)
+            .min(self.max_offset());
+        self.unread_lines = self.unread_lines.min(self.offset_from_bottom);
+    }
+
+    /// First content line shown at the top of the viewport
+    pub fn top_line(&self) -> usize {
+        self.content_height
+            .saturating_sub(self.viewport_height)
+            .saturating_sub(self.offset_from_bottom)
+    }
+
+    fn max_offset(&self) -> usize {
+        self.content_height.saturating_sub(self.viewport_height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follows_bottom_while_at_bottom() {
+        let mut scroll = StickyScroll::new();
+        scroll.update(50, 20);
+        assert_eq!(scroll.top_line(), 30);
+
+        scroll.update(60, 20);
+        assert!(scroll.is_following());
+        assert_eq!(scroll.top_line(), 40);
+        assert_eq!(scroll.unread_lines(), 0);
+    }
+
+    #[test]
+    fn test_keeps_position_and_counts_unread_when_scrolled_up() {
+        let mut scroll = StickyScroll::new();
+        scroll.update(50, 20);
+        scroll.scroll_up(10);
+        assert_eq!(scroll.top_line(), 20);
+
+        scroll.update(57, 20);
+        assert!(!scroll.is_following());
+        assert_eq!(scroll.top_line(), 20);
+        assert_eq!(scroll.unread_lines(), 7);
+
+        scroll.scroll_to_bottom();
+        assert!(scroll.is_following());
+        assert_eq!(scroll.unread_lines(), 0);
+        assert_eq!(scroll.top_line(), 37);
+    }
+
+    #[test]
+    fn test_scrolling_down_to_bottom_resumes_following() {
+        let mut scroll = StickyScroll::new();
+        scroll.update(50, 20);
+        scroll.scroll_up(5);
+        scroll.update(53, 20);
+        scroll.scroll_down(100);
+        assert!(scroll.is_following());
+        assert_eq!(scroll.unread_lines(), 0);
+    }
+}
@@ -0,0 +1,268 @@
+//! Search through the messages of a conversation
+//!
+//! Ctrl+F opens a search bar in the chat. The query is looked up with the
+//! session store's full-text search, in the current session or, after Tab, in
+//! all sessions. Matches are highlighted wherever they are on screen, and once
+//! the search has run `n` and `N` move between the messages that matched.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::session::{match_ranges, search_terms, MessageHit};
+use crate::tui::{themes::Theme, Frame};
+
+/// Sessions a search looks through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScope {
+    Session,
+    AllSessions,
+}
+
+/// What a key press in the search bar asks the chat page to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchAction {
+    None,
+    /// Look the query up in the scope
+    Run,
+    /// Show the message of the current hit
+    Jump(MessageHit),
+    Close,
+}
+
+/// State of the search bar
+#[derive(Debug, Clone)]
+pub struct MessageSearch {
+    query: String,
+    scope: SearchScope,
+    /// The query is being typed; otherwise keys move between hits
+    editing: bool,
+    hits: Vec<MessageHit>,
+    current: Option<usize>,
+    error: Option<String>,
+}
+
+impl Default for MessageSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageSearch {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            scope: SearchScope::Session,
+            editing: true,
+            hits: Vec::new(),
+            current: None,
+            error: None,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn scope(&self) -> SearchScope {
+        self.scope
+    }
+
+    /// Words of the query, as matches are highlighted
+    pub fn terms(&self) -> Vec<String> {
+        search_terms(&self.query)
+    }
+
+    pub fn hits(&self) -> &[MessageHit] {
+        &self.hits
+    }
+
+    pub fn current_hit(&self) -> Option<&MessageHit> {
+        self.hits.get(self.current?)
+    }
+
+    /// Take the results of running the query
+    pub fn set_results(&mut self, results: anyhow::Result<Vec<MessageHit>>) {
+        match results {
+            Ok(hits) => {
+                self.current = if hits.is_empty() { None } else { Some(0) };
+                self.hits = hits;
+                self.error = None;
+            }
+            Err(e) => {
+                self.hits.clear();
+                self.current = None;
+                self.error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> SearchAction {
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => return SearchAction::Close,
+            (KeyCode::Tab, _) => {
+                self.scope = match self.scope {
+                    SearchScope::Session => SearchScope::AllSessions,
+                    SearchScope::AllSessions => SearchScope::Session,
+                };
+                if !self.query.trim().is_empty() {
+                    return SearchAction::Run;
+                }
+            }
+            (KeyCode::Enter, _) if self.editing => {
+                if self.query.trim().is_empty() {
+                    return SearchAction::None;
+                }
+                self.editing = false;
+                return SearchAction::Run;
+            }
+            (KeyCode::Enter, _) => return self.jump(),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::Char('/'), KeyModifiers::NONE) if !self.editing => {
+                self.editing = true;
+            }
+            (KeyCode::Char('n'), KeyModifiers::NONE) if !self.editing => return self.step(1),
+            (KeyCode::Char('N'), _) if !self.editing => return self.step(self.hits.len().saturating_sub(1)),
+            (KeyCode::Backspace, _) if self.editing => {
+                self.query.pop();
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if self.editing => self.query.push(c),
+            _ => {}
+        }
+        SearchAction::None
+    }
+
+    /// Move `by` hits forward, wrapping around, and show the hit
+    fn step(&mut self, by: usize) -> SearchAction {
+        let Some(current) = self.current else {
+            return SearchAction::None;
+        };
+        self.current = Some((current + by) % self.hits.len());
+        self.jump()
+    }
+
+    fn jump(&self) -> SearchAction {
+        match self.current_hit() {
+            Some(hit) => SearchAction::Jump(hit.clone()),
+            None => SearchAction::None,
+        }
+    }
+
+    /// Render the search bar on one line
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let scope = match self.scope {
+            SearchScope::Session => "this session",
+            SearchScope::AllSessions => "all sessions",
+        };
+        let mut spans = vec![
+            Span::styled(format!("Search {}: ", scope), Style::default().fg(theme.fg_muted)),
+            Span::styled(self.query.clone(), Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD)),
+        ];
+        if self.editing {
+            spans.push(Span::styled("▏", Style::default().fg(theme.primary)));
+        }
+        let status = match (&self.error, self.current) {
+            (Some(error), _) => Span::styled(format!("  {}", error), Style::default().fg(theme.error)),
+            (None, Some(current)) => {
+                let mut status = format!("  {}/{}", current + 1, self.hits.len());
+                if let Some(hit) = self.current_hit() {
+                    status.push_str(&format!(" · {}", hit.snippet));
+                }
+                Span::styled(status, Style::default().fg(theme.info))
+            }
+            (None, None) if !self.editing => Span::styled("  no matches", Style::default().fg(theme.fg_muted)),
+            (None, None) => Span::raw(""),
+        };
+        spans.push(status);
+        let help = if self.editing { "  Enter search · Tab scope · Esc" } else { "  n/N next/previous · Enter open · / edit · Esc" };
+        spans.push(Span::styled(help, Style::default().fg(theme.fg_subtle)));
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
+
+/// Style matches of the terms already drawn in `area`
+///
+/// Works on what is on screen, so matches are found however a message was
+/// rendered.
+pub fn highlight_matches(buffer: &mut Buffer, area: Rect, terms: &[String], style: Style) {
+    if terms.is_empty() {
+        return;
+    }
+    let area = area.intersection(buffer.area);
+    for y in area.top()..area.bottom() {
+        // Row text with the column each byte of it came from
+        let mut text = String::new();
+        let mut columns = Vec::new();
+        for x in area.left()..area.right() {
+            let symbol = buffer.get(x, y).symbol();
+            columns.extend(std::iter::repeat(x).take(symbol.len()));
+            text.push_str(symbol);
+        }
+        for range in match_ranges(&text, terms) {
+            let (Some(&first), Some(&last)) = (columns.get(range.start), columns.get(range.end - 1)) else {
+                continue;
+            };
+            for x in first..=last {
+                buffer.get_mut(x, y).set_style(style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MessageRole;
+
+    fn hit(id: &str) -> MessageHit {
+        MessageHit {
+            session_id: "s1".to_string(),
+            message_id: id.to_string(),
+            role: MessageRole::User,
+            snippet: String::new(),
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_n_and_shift_n_move_between_hits() {
+        let mut search = MessageSearch::new();
+        for c in "borrow".chars() {
+            search.handle_key(key(KeyCode::Char(c)));
+        }
+        // Typing n edits the query until the search has run
+        search.handle_key(key(KeyCode::Char('n')));
+        search.handle_key(key(KeyCode::Backspace));
+        assert_eq!(search.handle_key(key(KeyCode::Enter)), SearchAction::Run);
+        search.set_results(Ok(vec![hit("a"), hit("b"), hit("c")]));
+
+        assert_eq!(search.handle_key(key(KeyCode::Char('n'))), SearchAction::Jump(hit("b")));
+        assert_eq!(search.handle_key(KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT)), SearchAction::Jump(hit("a")));
+        assert_eq!(search.handle_key(KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT)), SearchAction::Jump(hit("c")));
+        assert_eq!(search.handle_key(key(KeyCode::Tab)), SearchAction::Run);
+        assert_eq!(search.scope(), SearchScope::AllSessions);
+        assert_eq!(search.handle_key(key(KeyCode::Esc)), SearchAction::Close);
+    }
+
+    #[test]
+    fn test_matches_on_screen_are_highlighted() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 20, 2));
+        buffer.set_string(0, 0, "Über the Borrow", Style::default());
+        buffer.set_string(0, 1, "borrowed", Style::default());
+        let style = Style::default().add_modifier(Modifier::REVERSED);
+        highlight_matches(&mut buffer, buffer.area, &search_terms("borrow über"), style);
+
+        let reversed = |x, y| buffer.get(x, y).modifier.contains(Modifier::REVERSED);
+        assert!((0..4).all(|x| reversed(x, 0)));
+        assert!(!reversed(4, 0));
+        assert!((9..15).all(|x| reversed(x, 0)));
+        assert!((0..6).all(|x| reversed(x, 1)) && !reversed(6, 1));
+    }
+}
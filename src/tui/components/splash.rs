@@ -1,313 +1,409 @@
-//! Splash screen component for Goofy TUI
-//! 
-//! Displays the Goofy logo, version, and welcome information when the application starts.
+//! Splash screen with the first-run setup
+//!
+//! With no API key configured, goofy opens on a splash screen that sets up a
+//! provider: pick one, paste its key unless the environment has it, choose
+//! whether the key goes to the OS keychain or the config file, pick a default
+//! model, and wait while a short request checks that the provider answers
+//! before the chat starts. The wizard only keeps the state and draws it; the
+//! caller runs the check and saves what was chosen.
 
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Style},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
-    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::tui::{
-    components::{Component, ComponentState, logo::{render_logo, render_small_logo, LogoOpts}},
-    themes::Theme,
-    themes::colors::ColorPalette,
-};
-use async_trait::async_trait;
-use crossterm::event::{KeyEvent, MouseEvent};
-use anyhow::Result;
+use crate::config::credentials::KeyStorage;
+use crate::llm::models::{self, KnownModel};
+use crate::tui::{themes::Theme, Frame};
+
+/// Widest the setup box gets
+const MAX_WIDTH: u16 = 72;
+
+/// Frames of the spinner shown during the check
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Characters of a pasted key left readable at its end
+const KEY_VISIBLE_CHARS: usize = 4;
+
+/// A provider offered by the setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderChoice {
+    /// Name used in the configuration
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Environment variables the key is read from, none for local providers
+    pub key_env: &'static [&'static str],
+}
+
+/// Providers the setup offers; the others are configured in `goofy.json`
+pub const PROVIDERS: &[ProviderChoice] = &[
+    ProviderChoice { name: "anthropic", label: "Anthropic", key_env: &["ANTHROPIC_API_KEY"] },
+    ProviderChoice { name: "openai", label: "OpenAI", key_env: &["OPENAI_API_KEY"] },
+    ProviderChoice { name: "gemini", label: "Google Gemini", key_env: &["GEMINI_API_KEY", "GOOGLE_API_KEY"] },
+    ProviderChoice { name: "deepseek", label: "DeepSeek", key_env: &["DEEPSEEK_API_KEY"] },
+    ProviderChoice { name: "qwen", label: "Qwen", key_env: &["DASHSCOPE_API_KEY"] },
+    ProviderChoice { name: "ollama", label: "Ollama, running locally", key_env: &[] },
+];
+
+/// Where a key can be kept, in the order offered
+const STORAGES: &[KeyStorage] = &[KeyStorage::Keychain, KeyStorage::ConfigFile];
 
-/// Splash screen component showing Goofy branding and information
-pub struct SplashComponent {
-    state: ComponentState,
+/// Step of the setup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetupStep {
+    Provider,
+    Key,
+    Storage,
+    Model,
+    /// Waiting for the provider to answer
+    Checking,
+    /// The check or saving failed, with why
+    Failed(String),
+}
+
+/// What the setup chose
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    pub provider: String,
+    pub model: String,
+    /// Key pasted in the setup, none when the environment has it or the
+    /// provider needs none
+    pub api_key: Option<String>,
+    pub storage: KeyStorage,
+}
+
+/// What the caller does after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WizardAction {
+    None,
+    /// Check that the provider answers with the setup
+    Check(Setup),
+    /// Stop the check under way
+    CancelCheck,
+    Quit,
+}
+
+/// State of the first-run setup
+#[derive(Debug)]
+pub struct SetupWizard {
+    step: SetupStep,
     version: String,
-    show_info: bool,
-    compact_mode: bool,
+    /// Which of the providers have a key in the environment
+    env_keys: Vec<bool>,
+    /// Index into the providers
+    provider: usize,
+    key: String,
+    /// Index into the storages
+    storage: usize,
+    /// Models of the provider picked
+    models: Vec<&'static KnownModel>,
+    /// Index into the models
+    model: usize,
+    /// Where the config file storage writes, as shown
+    config_path: String,
+    spinner: usize,
 }
 
-impl SplashComponent {
-    /// Create a new splash component
-    pub fn new(version: String) -> Self {
+impl SetupWizard {
+    /// Start the setup, with `env_has` telling whether an environment
+    /// variable is set
+    pub fn new(version: impl Into<String>, config_path: impl Into<String>, env_has: impl Fn(&str) -> bool) -> Self {
+        let env_keys: Vec<bool> = PROVIDERS.iter().map(|choice| choice.key_env.iter().any(|name| env_has(name))).collect();
         Self {
-            state: ComponentState::new(),
-            version,
-            show_info: true,
-            compact_mode: false,
+            step: SetupStep::Provider,
+            version: version.into(),
+            // A key already in the environment is likely the one to use
+            provider: env_keys.iter().position(|has| *has).unwrap_or(0),
+            env_keys,
+            key: String::new(),
+            storage: 0,
+            models: Vec::new(),
+            model: 0,
+            config_path: config_path.into(),
+            spinner: 0,
         }
     }
-    
-    /// Set whether to show additional info below the logo
-    pub fn with_info(mut self, show_info: bool) -> Self {
-        self.show_info = show_info;
-        self
+
+    pub fn step(&self) -> &SetupStep {
+        &self.step
+    }
+
+    fn choice(&self) -> &'static ProviderChoice {
+        &PROVIDERS[self.provider]
     }
-    
-    /// Set compact mode for smaller screens
-    pub fn with_compact_mode(mut self, compact: bool) -> Self {
-        self.compact_mode = compact;
-        self
+
+    /// Whether the key of the provider picked has to be pasted
+    fn needs_key(&self) -> bool {
+        !self.choice().key_env.is_empty() && !self.env_keys[self.provider]
     }
-    
-    /// Render the logo section
-    fn render_logo(&self, area: Rect, theme: &Theme) -> Text<'static> {
-        let opts = LogoOpts {
-            gradient_start: ColorPalette::GOOFY_ORANGE,
-            gradient_end: ColorPalette::GOOFY_PURPLE,
-            field_color: ColorPalette::GOOFY_ORANGE,
-            brand_color: ColorPalette::GOOFY_PURPLE,
-            version_color: ColorPalette::GOOFY_ORANGE,
-            width: area.width as usize,
-            compact: self.compact_mode || area.width < 60 || area.height < 15,
-        };
-        
-        if opts.compact {
-            let small_logo = render_small_logo(area.width as usize, opts);
-            Text::from(vec![small_logo])
+
+    /// The step the setup goes back to when the check fails
+    fn retry_step(&self) -> SetupStep {
+        if self.needs_key() {
+            SetupStep::Key
         } else {
-            render_logo(&self.version, opts)
+            SetupStep::Model
         }
     }
-    
-    /// Create info section with project details
-    fn create_info_section(&self, theme: &Theme) -> Text<'static> {
-        let info_lines = vec![
-            Line::from(vec![
-                Span::styled("🚀 ", Style::default().fg(ColorPalette::GOOFY_ORANGE)),
-                Span::styled("Welcome to Goofy", Style::default().fg(theme.text)),
-                Span::styled(" - Your AI Coding Assistant", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("📚 ", Style::default().fg(ColorPalette::GOOFY_BLUE)),
-                Span::styled("Features:", Style::default().fg(theme.text)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • "),
-                Span::styled("Multi-provider LLM support", Style::default().fg(theme.text_dim)),
-                Span::styled(" (OpenAI, Anthropic, Ollama)", Style::default().fg(theme.placeholder)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • "),
-                Span::styled("Interactive Terminal UI", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • "),
-                Span::styled("Session management and persistence", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • "),
-                Span::styled("Comprehensive tool system", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("⚡ ", Style::default().fg(ColorPalette::GOOFY_YELLOW)),
-                Span::styled("Quick Start:", Style::default().fg(theme.text)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • Press "),
-                Span::styled("Ctrl+N", Style::default().fg(ColorPalette::GOOFY_ORANGE)),
-                Span::styled(" to create a new session", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • Press "),
-                Span::styled("Ctrl+P", Style::default().fg(ColorPalette::GOOFY_ORANGE)),
-                Span::styled(" to open command palette", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(vec![
-                Span::raw("  • Press "),
-                Span::styled("?", Style::default().fg(ColorPalette::GOOFY_ORANGE)),
-                Span::styled(" for help", Style::default().fg(theme.text_dim)),
-            ]),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("🔧 ", Style::default().fg(ColorPalette::GOOFY_GREEN)),
-                Span::styled("Status: ", Style::default().fg(theme.text)),
-                Span::styled("Ready", Style::default().fg(ColorPalette::SUCCESS_GREEN)),
-            ]),
-        ];
-        
-        Text::from(info_lines)
-    }
-    
-    /// Check if the screen is too small for full display
-    fn is_small_screen(&self) -> bool {
-        self.state.size.width < 60 || self.state.size.height < 20
-    }
-}
 
-#[async_trait]
-impl Component for SplashComponent {
-    async fn handle_key_event(&mut self, _event: KeyEvent) -> Result<()> {
-        // Splash screen is typically read-only, but could handle navigation
-        Ok(())
-    }
-    
-    async fn handle_mouse_event(&mut self, _event: MouseEvent) -> Result<()> {
-        Ok(())
-    }
-    
-    async fn tick(&mut self) -> Result<()> {
-        Ok(())
+    fn setup(&self) -> Setup {
+        Setup {
+            provider: self.choice().name.to_string(),
+            model: self.models[self.model].id.to_string(),
+            api_key: self.needs_key().then(|| self.key.trim().to_string()),
+            storage: STORAGES[self.storage],
+        }
     }
-    
-    fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
-        // Update our size
-        self.state.size = area;
-        
-        // Determine layout based on screen size and content
-        let is_small = self.is_small_screen();
-        
-        if is_small {
-            // Compact layout for small screens
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(3), // Logo
-                    Constraint::Min(1),    // Info (if shown)
-                ])
-                .split(area);
-            
-            // Render compact logo
-            let logo_text = self.render_logo(chunks[0], theme);
-            let logo_paragraph = Paragraph::new(logo_text)
-                .alignment(Alignment::Center)
-                .wrap(Wrap { trim: true });
-            frame.render_widget(logo_paragraph, chunks[0]);
-            
-            // Render minimal info if requested
-            if self.show_info {
-                let info_text = Text::from(vec![
-                    Line::from(vec![
-                        Span::styled("Goofy AI Assistant ", Style::default().fg(theme.text)),
-                        Span::styled(&self.version, Style::default().fg(theme.text_dim)),
-                    ]),
-                    Line::from(vec![
-                        Span::styled("Press ", Style::default().fg(theme.text_dim)),
-                        Span::styled("?", Style::default().fg(ColorPalette::GOOFY_ORANGE)),
-                        Span::styled(" for help", Style::default().fg(theme.text_dim)),
-                    ]),
-                ]);
-                
-                let info_paragraph = Paragraph::new(info_text)
-                    .alignment(Alignment::Center)
-                    .wrap(Wrap { trim: true });
-                frame.render_widget(info_paragraph, chunks[1]);
-            }
-        } else {
-            // Full layout for larger screens
-            let main_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(8),  // Logo area
-                    Constraint::Length(2),  // Spacing
-                    Constraint::Min(1),     // Info area
-                ])
-                .split(area);
-            
-            // Render full logo
-            let logo_text = self.render_logo(main_chunks[0], theme);
-            let logo_paragraph = Paragraph::new(logo_text)
-                .alignment(Alignment::Center);
-            frame.render_widget(logo_paragraph, main_chunks[0]);
-            
-            // Render info section if requested
-            if self.show_info {
-                let info_text = self.create_info_section(theme);
-                let info_paragraph = Paragraph::new(info_text)
-                    .alignment(Alignment::Left)
-                    .wrap(Wrap { trim: true })
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .border_style(Style::default().fg(ColorPalette::GOOFY_ORANGE))
-                            .title(" Welcome ")
-                            .title_style(Style::default().fg(ColorPalette::GOOFY_PURPLE))
-                    );
-                
-                // Center the info block
-                let info_area = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([
-                        Constraint::Percentage(10),
-                        Constraint::Percentage(80),
-                        Constraint::Percentage(10),
-                    ])
-                    .split(main_chunks[2])[1];
-                
-                frame.render_widget(info_paragraph, info_area);
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> WizardAction {
+        if key.kind == KeyEventKind::Release {
+            return WizardAction::None;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return WizardAction::Quit;
+        }
+        match self.step.clone() {
+            SetupStep::Provider => match key.code {
+                KeyCode::Up => self.provider = self.provider.saturating_sub(1),
+                KeyCode::Down => self.provider = (self.provider + 1).min(PROVIDERS.len() - 1),
+                KeyCode::Enter => {
+                    self.models = models::catalog().iter().filter(|model| model.provider == self.choice().name).collect();
+                    self.model = 0;
+                    self.step = if self.needs_key() { SetupStep::Key } else { SetupStep::Model };
+                }
+                KeyCode::Esc => return WizardAction::Quit,
+                _ => {}
+            },
+            SetupStep::Key => match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.key.push(c),
+                KeyCode::Backspace => {
+                    self.key.pop();
+                }
+                KeyCode::Enter if !self.key.trim().is_empty() => self.step = SetupStep::Storage,
+                KeyCode::Esc => self.step = SetupStep::Provider,
+                _ => {}
+            },
+            SetupStep::Storage => match key.code {
+                KeyCode::Up => self.storage = self.storage.saturating_sub(1),
+                KeyCode::Down => self.storage = (self.storage + 1).min(STORAGES.len() - 1),
+                KeyCode::Enter => self.step = SetupStep::Model,
+                KeyCode::Esc => self.step = SetupStep::Key,
+                _ => {}
+            },
+            SetupStep::Model => match key.code {
+                KeyCode::Up => self.model = self.model.saturating_sub(1),
+                KeyCode::Down => self.model = (self.model + 1).min(self.models.len().saturating_sub(1)),
+                KeyCode::Enter if !self.models.is_empty() => {
+                    self.step = SetupStep::Checking;
+                    return WizardAction::Check(self.setup());
+                }
+                KeyCode::Esc => self.step = if self.needs_key() { SetupStep::Storage } else { SetupStep::Provider },
+                _ => {}
+            },
+            SetupStep::Checking => {
+                if key.code == KeyCode::Esc {
+                    self.step = SetupStep::Model;
+                    return WizardAction::CancelCheck;
+                }
             }
+            SetupStep::Failed(_) => match key.code {
+                KeyCode::Enter => self.step = self.retry_step(),
+                KeyCode::Esc => self.step = SetupStep::Provider,
+                _ => {}
+            },
         }
+        WizardAction::None
     }
-    
-    fn size(&self) -> Rect {
-        self.state.size
+
+    /// Take a pasted key
+    pub fn paste(&mut self, text: &str) {
+        if self.step == SetupStep::Key {
+            self.key.push_str(text.trim());
+        }
     }
-    
-    fn set_size(&mut self, size: Rect) {
-        self.state.size = size;
+
+    /// The check or saving failed
+    pub fn fail(&mut self, message: impl Into<String>) {
+        self.step = SetupStep::Failed(message.into());
     }
-    
-    fn has_focus(&self) -> bool {
-        self.state.has_focus
+
+    /// Advance the spinner
+    pub fn tick(&mut self) {
+        self.spinner = (self.spinner + 1) % SPINNER.len();
     }
-    
-    fn set_focus(&mut self, focus: bool) {
-        self.state.has_focus = focus;
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let width = area.width.min(MAX_WIDTH);
+        let lines = self.lines(theme);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let setup_area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_focus))
+            .title(Span::styled(
+                format!(" Goofy {} ", self.version),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            ));
+        frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), setup_area);
     }
-    
-    fn is_visible(&self) -> bool {
-        self.state.is_visible
+
+    fn lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let text = Style::default().fg(theme.fg_base);
+        let muted = Style::default().fg(theme.fg_muted);
+        let heading = Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD);
+        let option = |selected: bool, label: String, note: String| {
+            let (marker, style) = if selected {
+                ("› ", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))
+            } else {
+                ("  ", text)
+            };
+            let mut spans = vec![Span::styled(marker, style), Span::styled(label, style)];
+            if !note.is_empty() {
+                spans.push(Span::styled(format!("  {}", note), muted));
+            }
+            Line::from(spans)
+        };
+
+        let mut lines = vec![
+            Line::from(Span::styled("Welcome! Let's set up a model provider to chat with.", text)),
+            Line::from(""),
+        ];
+        let hint = match &self.step {
+            SetupStep::Provider => {
+                lines.push(Line::from(Span::styled("Provider", heading)));
+                for (index, choice) in PROVIDERS.iter().enumerate() {
+                    let note = match choice.key_env.first() {
+                        Some(name) if self.env_keys[index] => format!("key found in ${}", name),
+                        Some(_) => String::new(),
+                        None => "no key needed".to_string(),
+                    };
+                    lines.push(option(index == self.provider, choice.label.to_string(), note));
+                }
+                "↑↓ move · Enter pick · Esc quit"
+            }
+            SetupStep::Key => {
+                let choice = self.choice();
+                lines.push(Line::from(Span::styled(format!("{} API key", choice.label), heading)));
+                lines.push(Line::from(vec![
+                    Span::styled("› ", Style::default().fg(theme.primary)),
+                    Span::styled(mask(&self.key), text),
+                    Span::styled("▏", Style::default().fg(theme.primary)),
+                ]));
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("Paste the key, or set ${} and start goofy again.", choice.key_env[0]),
+                    muted,
+                )));
+                "Enter continue · Esc back"
+            }
+            SetupStep::Storage => {
+                lines.push(Line::from(Span::styled("Keep the key in", heading)));
+                for (index, storage) in STORAGES.iter().enumerate() {
+                    let (label, note) = match storage {
+                        KeyStorage::Keychain => ("The OS keychain".to_string(), String::new()),
+                        KeyStorage::ConfigFile => (self.config_path.clone(), "readable only by you".to_string()),
+                    };
+                    lines.push(option(index == self.storage, label, note));
+                }
+                "↑↓ move · Enter pick · Esc back"
+            }
+            SetupStep::Model => {
+                lines.push(Line::from(Span::styled("Default model", heading)));
+                for (index, model) in self.models.iter().enumerate() {
+                    lines.push(option(index == self.model, model.name.to_string(), model.description.to_string()));
+                }
+                "↑↓ move · Enter check and start · Esc back"
+            }
+            SetupStep::Checking => {
+                let setup = self.setup();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{} ", SPINNER[self.spinner]), Style::default().fg(theme.primary)),
+                    Span::styled(format!("Asking {} {} for a short answer…", self.choice().label, setup.model), text),
+                ]));
+                "Esc cancel"
+            }
+            SetupStep::Failed(message) => {
+                lines.push(Line::from(Span::styled(
+                    format!("{} Setup did not finish", theme.icons.error),
+                    Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+                )));
+                lines.push(Line::from(Span::styled(message.clone(), Style::default().fg(theme.error))));
+                "Enter try again · Esc pick another provider"
+            }
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(hint, Style::default().fg(theme.fg_subtle))));
+        lines
     }
-    
-    fn set_visible(&mut self, visible: bool) {
-        self.state.is_visible = visible;
+}
+
+/// The key hidden but for its last characters
+fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let shown = chars.len().saturating_sub(KEY_VISIBLE_CHARS);
+    let masked: String = "•".repeat(shown) + &chars[shown..].iter().collect::<String>();
+    // Long keys would wrap; their start says nothing
+    let width = masked.width();
+    if width > MAX_WIDTH as usize / 2 {
+        masked.chars().skip(width - MAX_WIDTH as usize / 2).collect()
+    } else {
+        masked
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tui::themes::Theme;
-    use ratatui::{backend::TestBackend, Terminal};
 
-    #[test]
-    fn test_splash_component_creation() {
-        let splash = SplashComponent::new("v1.0.0".to_string());
-        assert_eq!(splash.version, "v1.0.0");
-        assert!(splash.show_info);
-        assert!(!splash.compact_mode);
+    fn press(wizard: &mut SetupWizard, code: KeyCode) -> WizardAction {
+        wizard.handle_key(KeyEvent::from(code))
     }
 
     #[test]
-    fn test_splash_component_with_options() {
-        let splash = SplashComponent::new("v1.0.0".to_string())
-            .with_info(false)
-            .with_compact_mode(true);
-        
-        assert!(!splash.show_info);
-        assert!(splash.compact_mode);
-    }
+    fn test_setup_asks_for_a_key_only_when_the_environment_has_none() {
+        let mut wizard = SetupWizard::new("1.0.0", "~/.config/goofy/goofy.json", |_| false);
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step(), &SetupStep::Key);
+        // An empty key goes nowhere
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step(), &SetupStep::Key);
+        wizard.paste(" sk-ant-secret\n");
+        assert_eq!(mask(&wizard.key), "•••••••••cret");
+        press(&mut wizard, KeyCode::Enter);
+        press(&mut wizard, KeyCode::Down);
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step(), &SetupStep::Model);
+        let WizardAction::Check(setup) = press(&mut wizard, KeyCode::Enter) else {
+            panic!("the check did not start");
+        };
+        assert_eq!(setup.provider, "anthropic");
+        assert_eq!(setup.api_key.as_deref(), Some("sk-ant-secret"));
+        assert_eq!(setup.storage, KeyStorage::ConfigFile);
+
+        wizard.fail("401 Unauthorized");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step(), &SetupStep::Key);
 
-    #[tokio::test]
-    async fn test_splash_component_events() {
-        let mut splash = SplashComponent::new("v1.0.0".to_string());
-        
-        // Test key event handling
-        let key_event = KeyEvent::from(crossterm::event::KeyCode::Enter);
-        assert!(splash.handle_key_event(key_event).await.is_ok());
-        
-        // Test mouse event handling
-        let mouse_event = MouseEvent {
-            kind: crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left),
-            column: 0,
-            row: 0,
-            modifiers: crossterm::event::KeyModifiers::empty(),
+        // With the key in the environment its provider is picked and the key skipped
+        let mut wizard = SetupWizard::new("1.0.0", "goofy.json", |name| name == "GOOGLE_API_KEY");
+        press(&mut wizard, KeyCode::Enter);
+        assert_eq!(wizard.step(), &SetupStep::Model);
+        let WizardAction::Check(setup) = press(&mut wizard, KeyCode::Enter) else {
+            panic!("the check did not start");
         };
-        assert!(splash.handle_mouse_event(mouse_event).await.is_ok());
-        
-        // Test tick
-        assert!(splash.tick().await.is_ok());
+        assert_eq!((setup.provider.as_str(), setup.api_key), ("gemini", None));
+        assert_eq!(press(&mut wizard, KeyCode::Esc), WizardAction::CancelCheck);
+        press(&mut wizard, KeyCode::Esc);
+        assert_eq!(press(&mut wizard, KeyCode::Esc), WizardAction::Quit);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,245 @@
+//! The status bar under the input
+//!
+//! One line telling what the session runs on and what it has cost: the model
+//! and its provider, how much of the context window the conversation takes,
+//! the session's cost against its budget, the git branch with how far it is
+//! ahead of or behind its upstream and how many files changed, and how tool
+//! calls are approved, with YOLO mode
+//! standing out. Costs follow the agent's events; the caller refreshes the git
+//! state when [`StatusBar::handle_event`] says tools may have changed it. When
+//! the line is too narrow the least useful parts go first.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::AppEvent;
+use crate::git::GitStatus;
+use crate::llm::models::MessageModel;
+use crate::permission::ApprovalMode;
+use crate::tui::themes::{IconSet, Theme};
+
+/// Between the parts of the bar
+const SEPARATOR: &str = " · ";
+
+/// Share of the context window or budget from which it is highlighted
+const WARN_PERCENT: f64 = 80.0;
+
+/// Share of the context window from which it is an error
+const FULL_PERCENT: f64 = 95.0;
+
+/// What a part of the bar looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tone {
+    Muted,
+    Text,
+    Warning,
+    Error,
+    /// Reversed, to be noticed
+    Alert,
+}
+
+/// A part of the bar, dropped before those of higher rank when space runs out
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    text: String,
+    tone: Tone,
+    rank: u8,
+}
+
+/// Branch of the working tree and its changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitState {
+    /// `None` on a detached head
+    pub branch: Option<String>,
+    /// Commits the branch is ahead of its upstream
+    pub ahead: u32,
+    /// Commits the branch is behind its upstream
+    pub behind: u32,
+    /// Changed and untracked files
+    pub changed: usize,
+}
+
+impl From<&GitStatus> for GitState {
+    fn from(status: &GitStatus) -> Self {
+        Self {
+            branch: status.branch.clone(),
+            ahead: status.ahead,
+            behind: status.behind,
+            changed: status.dirty_count(),
+        }
+    }
+}
+
+/// What the status bar shows
+#[derive(Debug, Clone, Default)]
+pub struct StatusBar {
+    pub model: Option<MessageModel>,
+    /// Cost of the session so far in US dollars
+    pub cost: Option<f64>,
+    /// Budget of the session in US dollars
+    pub max_cost: Option<f64>,
+    /// `None` outside a repository
+    pub git: Option<GitState>,
+    pub approval: ApprovalMode,
+}
+
+impl StatusBar {
+    /// Follow an event of the agent
+    ///
+    /// Returns whether the git state may have changed, after a tool ran.
+    pub fn handle_event(&mut self, event: &AppEvent) -> bool {
+        match event {
+            AppEvent::CostUpdated { total_cost, max_cost, .. } => {
+                self.cost = Some(*total_cost);
+                self.max_cost = *max_cost;
+                false
+            }
+            AppEvent::ToolCompleted { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The bar fitted into `width` columns, with the conversation taking
+    /// `context_percent` of the model's context window when known
+    pub fn line(&self, theme: &Theme, width: u16, context_percent: Option<f64>) -> Line<'static> {
+        let segments = fit(self.segments(&theme.icons, context_percent), width as usize);
+        let mut spans = Vec::new();
+        for (index, segment) in segments.into_iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::styled(SEPARATOR, Style::default().fg(theme.fg_subtle)));
+            }
+            let style = match segment.tone {
+                Tone::Muted => Style::default().fg(theme.fg_muted),
+                Tone::Text => Style::default().fg(theme.fg_base),
+                Tone::Warning => Style::default().fg(theme.warning),
+                Tone::Error => Style::default().fg(theme.error),
+                Tone::Alert => Style::default().fg(theme.bg_base).bg(theme.error).add_modifier(Modifier::BOLD),
+            };
+            spans.push(Span::styled(segment.text, style));
+        }
+        Line::from(spans)
+    }
+
+    fn segments(&self, icons: &IconSet, context_percent: Option<f64>) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        if let Some(model) = &self.model {
+            segments.push(Segment {
+                text: format!("{}/{}", model.provider, model.model),
+                tone: Tone::Text,
+                rank: 4,
+            });
+        }
+        if let Some(percent) = context_percent {
+            let tone = match percent {
+                p if p >= FULL_PERCENT => Tone::Error,
+                p if p >= WARN_PERCENT => Tone::Warning,
+                _ => Tone::Muted,
+            };
+            segments.push(Segment {
+                text: format!("{:.0}% context", percent),
+                tone,
+                rank: 3,
+            });
+        }
+        if let Some(cost) = self.cost {
+            let (text, tone) = match self.max_cost {
+                Some(max_cost) if max_cost > 0.0 => {
+                    let tone = if cost / max_cost * 100.0 >= WARN_PERCENT { Tone::Warning } else { Tone::Muted };
+                    (format!("${:.2} / ${:.2}", cost, max_cost), tone)
+                }
+                _ => (format!("${:.2}", cost), Tone::Muted),
+            };
+            segments.push(Segment { text, tone, rank: 2 });
+        }
+        if let Some(git) = &self.git {
+            let mut text = format!("{} {}", icons.branch, git.branch.as_deref().unwrap_or("detached"));
+            if git.ahead > 0 {
+                text.push_str(&format!(" {}{}", icons.arrow_up, git.ahead));
+            }
+            if git.behind > 0 {
+                text.push_str(&format!(" {}{}", icons.arrow_down, git.behind));
+            }
+            if git.changed > 0 {
+                text.push_str(&format!(" +{}", git.changed));
+            }
+            segments.push(Segment {
+                text,
+                tone: Tone::Muted,
+                rank: 1,
+            });
+        }
+        let (text, tone) = match self.approval {
+            ApprovalMode::Ask => ("ask", Tone::Muted),
+            ApprovalMode::ReadOnly => ("read-only", Tone::Text),
+            ApprovalMode::Yolo => (" YOLO ", Tone::Alert),
+        };
+        segments.push(Segment {
+            text: text.to_string(),
+            tone,
+            // YOLO mode is the last thing to hide
+            rank: if self.approval == ApprovalMode::Yolo { 5 } else { 0 },
+        });
+        segments
+    }
+}
+
+/// The segments that fit in `width` columns, dropping the lowest ranked first
+fn fit(mut segments: Vec<Segment>, width: usize) -> Vec<Segment> {
+    let total = |segments: &[Segment]| -> usize {
+        let separators = segments.len().saturating_sub(1) * SEPARATOR.width();
+        segments.iter().map(|segment| segment.text.width()).sum::<usize>() + separators
+    };
+    while total(&segments) > width {
+        let Some(lowest) = segments.iter().enumerate().min_by_key(|(_, segment)| segment.rank).map(|(index, _)| index)
+        else {
+            break;
+        };
+        segments.remove(lowest);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(bar: &StatusBar, width: usize, context: Option<f64>) -> Vec<String> {
+        fit(bar.segments(&IconSet::ascii(), context), width).into_iter().map(|segment| segment.text).collect()
+    }
+
+    #[test]
+    fn test_bar_follows_costs_and_drops_parts_when_narrow() {
+        let mut bar = StatusBar {
+            model: Some(MessageModel::new("anthropic", "claude-sonnet-4")),
+            git: Some(GitState { branch: Some("main".to_string()), ahead: 2, behind: 0, changed: 3 }),
+            approval: ApprovalMode::Yolo,
+            ..StatusBar::default()
+        };
+        let cost = AppEvent::CostUpdated {
+            session_id: "s1".to_string(),
+            total_cost: 4.5,
+            max_cost: Some(5.0),
+        };
+        assert!(!bar.handle_event(&cost));
+        let tool = AppEvent::ToolCompleted {
+            session_id: "s1".to_string(),
+            tool_id: "t1".to_string(),
+            result: String::new(),
+            success: true,
+            table: None,
+        };
+        assert!(bar.handle_event(&tool));
+
+        assert_eq!(
+            texts(&bar, 200, Some(42.4)),
+            ["anthropic/claude-sonnet-4", "42% context", "$4.50 / $5.00", "git main ^2 +3", " YOLO "]
+        );
+        assert_eq!(texts(&bar, 50, Some(42.4)), ["anthropic/claude-sonnet-4", "42% context", " YOLO "]);
+        assert_eq!(texts(&bar, 10, None), [" YOLO "]);
+
+        let segments = bar.segments(&IconSet::ascii(), Some(96.0));
+        assert_eq!(segments[1].tone, Tone::Error);
+        assert_eq!(segments[2].tone, Tone::Warning);
+    }
+}
@@ -0,0 +1,123 @@
+//! Transient status messages
+//!
+//! A toast says something worth a glance, like an answer that took a while
+//! being in, and goes away by itself. Toasts are shown one at a time in the
+//! order they were pushed, each for its own duration counted from when it
+//! first shows.
+
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::tui::themes::Theme;
+
+/// How long a toast shows unless told otherwise
+pub const DEFAULT_TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// What a toast reports, which picks its style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A message shown for a while
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub kind: ToastKind,
+    duration: Duration,
+    /// When it first showed; toasts waiting their turn haven't
+    shown_at: Option<Instant>,
+}
+
+impl Toast {
+    /// The toast as a status line
+    pub fn line(&self, theme: &Theme) -> Line<'static> {
+        let (icon, color) = match self.kind {
+            ToastKind::Info => (&theme.icons.info, theme.info),
+            ToastKind::Success => (&theme.icons.success, theme.success),
+            ToastKind::Warning => (&theme.icons.warning, theme.warning),
+            ToastKind::Error => (&theme.icons.error, theme.error),
+        };
+        Line::from(Span::styled(format!("{} {}", icon, self.message), Style::default().fg(color)))
+    }
+}
+
+/// Toasts waiting to show, the first showing
+#[derive(Debug, Clone, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Show `message` for the default duration, after the toasts before it
+    pub fn push(&mut self, message: impl Into<String>, kind: ToastKind) {
+        self.push_for(message, kind, DEFAULT_TOAST_DURATION);
+    }
+
+    pub fn push_for(&mut self, message: impl Into<String>, kind: ToastKind, duration: Duration) {
+        self.queue.push_back(Toast {
+            message: message.into(),
+            kind,
+            duration,
+            shown_at: None,
+        });
+    }
+
+    /// Drop the toasts shown long enough and start the clock of the next
+    ///
+    /// Returns whether the toast shown changed, for the screen to be drawn
+    /// again.
+    pub fn update(&mut self, now: Instant) -> bool {
+        let mut changed = false;
+        while let Some(toast) = self.queue.front_mut() {
+            match toast.shown_at {
+                Some(shown_at) if now.saturating_duration_since(shown_at) >= toast.duration => {
+                    self.queue.pop_front();
+                    changed = true;
+                }
+                Some(_) => break,
+                None => {
+                    toast.shown_at = Some(now);
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        changed
+    }
+
+    /// The toast showing, once [`update`](Self::update) started its clock
+    pub fn current(&self) -> Option<&Toast> {
+        self.queue.front().filter(|toast| toast.shown_at.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_show_in_turn_for_their_duration() {
+        let start = Instant::now();
+        let mut toasts = Toasts::default();
+        toasts.push_for("Answered in 42s", ToastKind::Success, Duration::from_secs(2));
+        toasts.push("Notification not sent", ToastKind::Warning);
+        assert!(toasts.current().is_none());
+
+        assert!(toasts.update(start));
+        assert_eq!(toasts.current().unwrap().message, "Answered in 42s");
+        assert!(!toasts.update(start + Duration::from_secs(1)));
+
+        // The second toast's time starts when it shows
+        assert!(toasts.update(start + Duration::from_secs(3)));
+        assert_eq!(toasts.current().unwrap().kind, ToastKind::Warning);
+        assert!(!toasts.update(start + Duration::from_secs(6)));
+        assert!(toasts.update(start + Duration::from_secs(7)));
+        assert!(toasts.current().is_none());
+    }
+}
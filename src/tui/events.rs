@@ -1,9 +1,33 @@
+//! Events of the TUI and the loop that orders them
+//!
+//! Input is never queued behind other work: every call to
+//! [`EventHandler::next`] reads pending keys, mouse events and pastes first,
+//! then internal events, then ticks. Ticks drive animations and are only
+//! worth having while they are current, so ticks missed while the loop was
+//! busy are dropped rather than caught up on, and low priority internal events
+//! are kept in a small queue that drops its oldest entries. While the app is
+//! suspended there are no ticks at all and internal events are looked at less
+//! often; input still arrives at once.
+
 use crossterm::event::{KeyEvent, MouseEvent, Event as CrosstermEvent};
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tokio::time::timeout;
 use anyhow::Result;
 
+use crate::app::Suspend;
+
+/// Low priority events kept while the loop is busy
+const LOW_QUEUE_CAPACITY: usize = 4;
+
+/// Longest wait for input before internal events are looked at again
+const INPUT_POLL: Duration = Duration::from_millis(10);
+
+/// Longest wait for input while ticks are paused
+const PAUSED_POLL: Duration = Duration::from_millis(500);
+
 /// Application events
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -32,6 +56,125 @@ pub enum Event {
     Custom(String, serde_json::Value),
 }
 
+impl Event {
+    /// Short name of the kind of event
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Key(_) => "key",
+            Event::Mouse(_) => "mouse",
+            Event::Resize(_, _) => "resize",
+            Event::Tick => "tick",
+            Event::PageChange(_) => "page change",
+            Event::StatusMessage(_) => "status message",
+            Event::ClearStatus => "clear status",
+            Event::Custom(_, _) => "custom",
+        }
+    }
+    
+    /// How soon the event has to be handled
+    pub fn priority(&self) -> Priority {
+        match self {
+            Event::Key(_) | Event::Mouse(_) | Event::Resize(_, _) => Priority::Input,
+            Event::Custom(kind, _) if kind == "paste" => Priority::Input,
+            Event::Tick => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// How soon an event has to be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Keys, mouse, pastes and resizes, handled before anything else
+    Input,
+    /// Internal events like status messages and page changes
+    Normal,
+    /// Ticks driving animations, dropped when the loop falls behind
+    Low,
+}
+
+/// Internal events and ticks waiting to be handled, most urgent first
+#[derive(Debug)]
+struct EventQueue {
+    normal: VecDeque<Event>,
+    low: VecDeque<Event>,
+    tick_interval: Duration,
+    next_tick: Instant,
+    /// Low priority events and ticks dropped so far
+    dropped: u64,
+}
+
+impl EventQueue {
+    fn new(tick_interval: Duration, now: Instant) -> Self {
+        Self {
+            normal: VecDeque::new(),
+            low: VecDeque::new(),
+            tick_interval,
+            next_tick: now + tick_interval,
+            dropped: 0,
+        }
+    }
+    
+    fn push(&mut self, event: Event) {
+        match event.priority() {
+            Priority::Input => self.normal.push_front(event),
+            Priority::Normal => self.normal.push_back(event),
+            Priority::Low => {
+                if self.low.len() == LOW_QUEUE_CAPACITY {
+                    self.low.pop_front();
+                    self.dropped += 1;
+                }
+                self.low.push_back(event);
+            }
+        }
+    }
+    
+    /// The most urgent event due at `now`, with or without ticks
+    fn pop(&mut self, now: Instant, ticking: bool) -> Option<Event> {
+        if let Some(event) = self.normal.pop_front() {
+            return Some(event);
+        }
+        if !ticking {
+            // Paused ticks are not missed ones
+            self.next_tick = now + self.tick_interval;
+        } else if now >= self.next_tick {
+            // Ticks missed while the loop was busy are dropped, not caught up on
+            let missed = (now - self.next_tick).as_nanos() / self.tick_interval.as_nanos().max(1);
+            self.dropped += missed as u64;
+            self.next_tick = now + self.tick_interval;
+            return Some(Event::Tick);
+        }
+        self.low.pop_front()
+    }
+    
+    /// Time left until the next tick
+    fn until_tick(&self, now: Instant) -> Duration {
+        self.next_tick.saturating_duration_since(now)
+    }
+}
+
+/// Switch pausing the ticks of an event handler while the app is suspended
+#[derive(Debug, Default)]
+pub struct Ticks {
+    paused: AtomicBool,
+}
+
+impl Ticks {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+impl Suspend for Ticks {
+    fn suspend(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
+
 /// Event handler for managing input events
 pub struct EventHandler {
     /// Event receiver channel
@@ -40,8 +183,14 @@ pub struct EventHandler {
     /// Event sender channel
     sender: mpsc::UnboundedSender<Event>,
     
-    /// Tick interval for periodic events
-    tick_interval: Duration,
+    /// Internal events and ticks, by priority
+    queue: EventQueue,
+    
+    /// When the input event returned last was read
+    last_input: Option<Instant>,
+    
+    /// Pauses ticks while the app is suspended
+    ticks: Arc<Ticks>,
 }
 
 impl EventHandler {
@@ -53,29 +202,72 @@ impl EventHandler {
         Self {
             receiver,
             sender,
-            tick_interval,
+            queue: EventQueue::new(tick_interval, Instant::now()),
+            last_input: None,
+            ticks: Arc::new(Ticks::default()),
         }
     }
     
-    /// Get the next event
+    /// Get the next event, waiting until one is due
     pub async fn next(&mut self) -> Option<Event> {
-        // Try to get crossterm events with timeout
-        if let Ok(Ok(crossterm_event)) = timeout(
-            Duration::from_millis(50),
-            tokio::task::spawn_blocking(|| crossterm::event::read())
-        ).await {
-            if let Ok(event) = crossterm_event {
-                return Some(self.convert_crossterm_event(event));
+        loop {
+            if let Some(event) = self.try_next() {
+                return Some(event);
+            }
+            
+            // Poll inside the blocking task and wait for it, so it never
+            // outlives this call; a leftover poll would steal input from an
+            // external editor. Reading happens in `try_next`.
+            let wait = if self.ticks.is_paused() {
+                PAUSED_POLL
+            } else {
+                self.queue.until_tick(Instant::now()).min(INPUT_POLL)
+            };
+            let poll = tokio::task::spawn_blocking(move || crossterm::event::poll(wait)).await;
+            if !matches!(poll, Ok(Ok(_))) {
+                tokio::time::sleep(wait).await;
             }
         }
-        
-        // Check for internal events
-        if let Ok(event) = self.receiver.try_recv() {
+    }
+    
+    /// The most urgent event that is ready, without waiting
+    pub fn try_next(&mut self) -> Option<Event> {
+        if let Some(event) = self.read_input() {
             return Some(event);
         }
-        
-        // Return tick event if no other events
-        Some(Event::Tick)
+        while let Ok(event) = self.receiver.try_recv() {
+            self.queue.push(event);
+        }
+        self.queue.pop(Instant::now(), !self.ticks.is_paused())
+    }
+    
+    /// The switch pausing ticks, to register with the activity tracker
+    pub fn ticks(&self) -> Arc<Ticks> {
+        self.ticks.clone()
+    }
+    
+    /// Check if input is waiting to be read
+    pub fn input_pending(&self) -> bool {
+        crossterm::event::poll(Duration::ZERO).unwrap_or(false)
+    }
+    
+    /// When the input event returned last was read, if it came from the terminal
+    pub fn take_last_input(&mut self) -> Option<Instant> {
+        self.last_input.take()
+    }
+    
+    /// Low priority events and ticks dropped since the last call
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.queue.dropped)
+    }
+    
+    fn read_input(&mut self) -> Option<Event> {
+        if !self.input_pending() {
+            return None;
+        }
+        let event = crossterm::event::read().ok()?;
+        self.last_input = Some(Instant::now());
+        Some(self.convert_crossterm_event(event))
     }
     
     /// Convert crossterm events to application events
@@ -106,4 +298,58 @@ impl Default for EventHandler {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_events_come_out_by_priority() {
+        let start = Instant::now();
+        let mut queue = EventQueue::new(TICK, start);
+        queue.push(Event::StatusMessage("saved".to_string()));
+        queue.push(Event::Custom("paste".to_string(), serde_json::Value::String("text".to_string())));
+        queue.push(Event::ClearStatus);
+
+        let due = start + TICK;
+        assert_eq!(queue.pop(due, true).map(|event| event.priority()), Some(Priority::Input));
+        assert!(matches!(queue.pop(due, true), Some(Event::StatusMessage(_))));
+        assert!(matches!(queue.pop(due, true), Some(Event::ClearStatus)));
+        assert!(matches!(queue.pop(due, true), Some(Event::Tick)));
+        assert!(queue.pop(due, true).is_none());
+    }
+
+    #[test]
+    fn test_late_ticks_and_old_low_events_are_dropped() {
+        let start = Instant::now();
+        let mut queue = EventQueue::new(TICK, start);
+        assert!(queue.pop(start, true).is_none());
+
+        // A loop busy for three and a half tick intervals gets one tick
+        let late = start + TICK * 7 / 2;
+        assert!(matches!(queue.pop(late, true), Some(Event::Tick)));
+        assert!(queue.pop(late, true).is_none());
+        assert_eq!(queue.dropped, 2);
+        assert_eq!(queue.until_tick(late), TICK);
+
+        for _ in 0..LOW_QUEUE_CAPACITY + 2 {
+            queue.push(Event::Tick);
+        }
+        assert_eq!(queue.low.len(), LOW_QUEUE_CAPACITY);
+        assert_eq!(queue.dropped, 4);
+    }
+
+    #[test]
+    fn test_paused_ticks_are_not_dropped() {
+        let start = Instant::now();
+        let mut queue = EventQueue::new(TICK, start);
+        let later = start + TICK * 30;
+        assert!(queue.pop(later, false).is_none());
+        assert_eq!(queue.dropped, 0);
+        assert!(queue.pop(later + TICK, true).is_some());
+        assert_eq!(queue.dropped, 0);
+    }
+}
@@ -0,0 +1,112 @@
+//! Editing the prompt draft in an external editor
+//!
+//! The draft is written to a temporary file and `$VISUAL` (or `$EDITOR`) is
+//! run on it with the TUI suspended. When the editor exits successfully the
+//! saved content replaces the draft; if it fails the draft is left untouched.
+
+use crate::tui::{init_terminal, restore_terminal, Backend};
+use anyhow::{Context, Result};
+use ratatui::Terminal;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+#[cfg(unix)]
+const DEFAULT_EDITOR: &str = "vi";
+#[cfg(not(unix))]
+const DEFAULT_EDITOR: &str = "notepad";
+
+/// The user's preferred editor command
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Open the draft in the external editor, suspending the TUI meanwhile
+///
+/// Returns the saved content, or `None` if the editor exited with an error.
+pub fn edit_draft(terminal: &mut Terminal<Backend>, draft: &str) -> Result<Option<String>> {
+    let mut file = tempfile::Builder::new()
+        .prefix("goofy-draft-")
+        .suffix(".md")
+        .tempfile()?;
+    file.write_all(draft.as_bytes())?;
+    file.flush()?;
+
+    let command = editor_command();
+    restore_terminal(terminal)?;
+    let status = run_editor(&command, file.path());
+    // Bring the TUI back even if the editor could not be started
+    *terminal = init_terminal()?;
+    terminal.clear()?;
+    crate::tui::components::image::protocol::invalidate();
+
+    if !status?.success() {
+        return Ok(None);
+    }
+    read_draft(file.path()).map(Some)
+}
+
+/// Run the editor command on a file and wait for it to exit
+///
+/// The command goes through the shell so values like `code --wait` work.
+fn run_editor(command: &str, path: &Path) -> Result<ExitStatus> {
+    #[cfg(unix)]
+    let mut process = {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(format!("{} \"$@\"", command)).arg(command).arg(path);
+        process
+    };
+    #[cfg(not(unix))]
+    let mut process = {
+        let mut parts = command.split_whitespace();
+        let mut process = Command::new(parts.next().unwrap_or(DEFAULT_EDITOR));
+        process.args(parts).arg(path);
+        process
+    };
+
+    process
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", command))
+}
+
+/// Read the edited draft, dropping the newline editors add at the end
+fn read_draft(path: &Path) -> Result<String> {
+    let mut content = std::fs::read_to_string(path)?;
+    if content.ends_with('\n') {
+        content.pop();
+        if content.ends_with('\r') {
+            content.pop();
+        }
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_editor_edits_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("draft.md");
+        std::fs::write(&path, "first draft\n").unwrap();
+
+        let status = run_editor("sed -i s/first/final/", &path).unwrap();
+        assert!(status.success());
+        assert_eq!(read_draft(&path).unwrap(), "final draft");
+    }
+
+    #[test]
+    fn test_read_draft_keeps_inner_newlines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("draft.md");
+        std::fs::write(&path, "line one\n\nline three\n").unwrap();
+        assert_eq!(read_draft(&path).unwrap(), "line one\n\nline three");
+    }
+}
@@ -0,0 +1,4113 @@
+//! Inline chat below the shell prompt
+//!
+//! Instead of taking over the alternate screen, inline mode draws a small
+//! viewport of a few lines under the prompt, like `crush --inline`. Finished
+//! questions and answers are printed above the viewport into the normal
+//! buffer, so they stay in the terminal's scrollback after goofy exits.
+//!
+//! With follow-up suggestions enabled, they are shown as chips on the status
+//! line once an answer is in, and Tab puts them into the input one by one.
+//!
+//! Prompts come from and go to the project's prompt history, which `goofy run`
+//! adds to as well: Up and Down walk through it, and Ctrl+R searches it.
+//!
+//! Commands the agent runs print their output above the viewport line by
+//! line as it arrives, and Ctrl+C cancels the command instead of quitting
+//! while one is running. When a request to the provider fails and is retried
+//! or goes to the fallback model, a warning says so above the viewport.
+//!
+//! The status line shows how many tokens the request would take with what is
+//! typed, out of the model's context window when it is known; counts marked
+//! `~` are estimates for models whose tokenizer isn't available.
+//!
+//! While the terminal is out of focus or nothing was typed for a while, and
+//! no answer is on its way, the spinner stops ticking and the workspace
+//! watcher is suspended until the next key.
+//!
+//! Prompts sent while an answer is on its way are queued, shown on the
+//! status line, and sent one at a time as the answers before them come in.
+//! Ctrl+X takes the latest queued prompt back into the input. When an answer
+//! fails, the queue waits until Enter is pressed.
+//!
+//! Alt+M shows the draft rendered as markdown beside the input, updated
+//! once typing pauses. A prompt holding code pasted without a fence is held
+//! back on Enter with an offer to wrap the code in a block tagged with its
+//! language, send it as is, or keep editing.
+//!
+//! `/edit` puts the conversation's latest prompt into the input, Up and Down
+//! move to earlier ones, and Enter sends the edited prompt in its place,
+//! dropping what followed it; with Ctrl+B the edit goes on in a new branch
+//! session instead. `/retry` regenerates the latest answer.
+//!
+//! An answer a crash cut off while it streamed is shown as interrupted when
+//! its session is continued, and `/continue` asks the model to finish it.
+//!
+//! `/model` switches the conversation to another model without leaving:
+//! `/model <model>` or `/model <provider> <model>` switches at once, and
+//! `/model` on its own lists the catalog's models to pick from.
+//!
+//! While a slash command is typed, the status line lists the built-in and
+//! custom commands starting with it, and Tab completes their names. Custom
+//! commands are sent as the prompts they stand for, and stay in the history
+//! and the scrollback as typed.
+//!
+//! Ctrl+S lists the recent sessions with their titles, when they were last
+//! used, their messages and cost, filtered by what is typed. Enter continues
+//! the session picked in place of this one, Ctrl+F continues a fork of it,
+//! Ctrl+D deletes it once confirmed, and Ctrl+A archives it.
+//! `/recall` lists past sessions by meaning instead, through the session
+//! manager's semantic search, with the message of each that matched;
+//! archived sessions are found too, and restored when picked.
+//! `/diff` picks two sessions, or `/diff <first> <second>` names them, and
+//! prints their answers compared turn by turn with word diffs.
+//!
+//! Ctrl+F searches the messages of the session, or of all sessions after Tab,
+//! with the session store's full-text search. The messages that matched are
+//! listed with the matches highlighted, `n` and `N` move between them, and
+//! Enter prints the message into the scrollback, switching to its session
+//! when it is another one.
+//!
+//! A PDF dropped on the terminal, which pastes its path, is attached to the
+//! next prompt as its text. A preview opens with exactly what the model will
+//! get, how many parts and tokens that is, and whether the document was cut at
+//! the size limit; Ctrl+O opens it again, and Ctrl+D in it removes the
+//! document.
+//!
+//! Ctrl+P lists the files the agent read or edited this session, most
+//! recent first and filtered by what is typed, from the tool calls rather
+//! than the disk. Enter attaches the file to the next prompt, opening the
+//! preview, and Tab puts its path into the input.
+//!
+//! `/screenshot` captures the screen, or `/screenshot <window>` a window, and
+//! attaches it to the next prompt as an image.
+//!
+//! Tool calls the permission system wants confirmed wait for an answer in
+//! the viewport: the tool, its risk and a preview of what it will do are
+//! printed above, and the keys of the approval dialog allow the call once,
+//! for the session or always in the project, or deny it.
+//!
+//! Tables tools return are printed above the viewport, and Ctrl+T opens the
+//! latest one in it to sort, hide columns and copy cells or rows.
+//!
+//! Answers go through the postprocessing stages of `tui.postprocess` before
+//! they are printed; the answer being written is shown as it arrives.
+//!
+//! With `"stream": true` in the configuration, answers show up between the
+//! status line and the input as they are written, and text the model writes
+//! before calling tools stays in the scrollback above its commands. PageUp
+//! and PageDown scroll through the answer; scrolled up, it stays in place as
+//! lines arrive, which a pill counts, until Ctrl+End or scrolling back down.
+//!
+//! Keyboard macros work as in the full-screen UI: `Alt+q` and a register
+//! record the keys that follow until `Alt+q` again, and `Alt+e` with an
+//! optional count and the register replays them. The status line shows the
+//! register while recording, and replays stop at a tool approval.
+//!
+//! The actions of `tui.keybindings` that inline mode has are bound as
+//! configured, and the help key, F1 unless rebound, lists them with their keys.
+//!
+//! Narrow terminals get a compact layout: below 100 columns the hints on the
+//! status line are shortened, and below 60 the status bar is left out so the
+//! input keeps its rows. A viewport too small for even that says so instead.
+//!
+//! In debug builds the profiler's key, F12 unless rebound, shows the render
+//! profiler in the viewport's top right corner.
+
+use anyhow::Result;
+use crossterm::{
+    event::{DisableFocusChange, EnableFocusChange, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+    Terminal, TerminalOptions, Viewport,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use unicode_width::UnicodeWidthChar;
+
+use super::{
+    clipboard,
+    components::{
+        data_table::{DataTable, TableAction, TableData},
+        fences::{self, FenceChoice, Unfenced},
+        dialogs::{confirm::ConfirmDialog, permission::PermissionDialog},
+        lists::{FilterableItem, FilterableList, ListItem, SimpleFilterableItem},
+        markdown::{MarkdownConfig, MarkdownWidget, StreamingMarkdown},
+        postprocess::Postprocessor,
+        scroll::StickyScroll,
+        search::{MessageSearch, SearchAction, SearchScope},
+        status_bar::{GitState, StatusBar},
+        toast::{ToastKind, Toasts},
+    },
+    events::{Event, EventHandler, Priority},
+    keys::KeyMap,
+    macros::{self, MacroOutcome, MacroRecorder},
+    profiler,
+    recording::{Recorded, Recorder},
+    responsive::{self, LayoutTier, MinSize},
+    themes::{self, Theme},
+    Frame,
+};
+use crate::app::{
+    commands::{CustomCommands, BUILT_IN_COMMANDS},
+    switcher::{model_command, ServedModel},
+    App, AppEvent, ModelSwitcher, Notifier,
+};
+use crate::fswatch::TouchedFile;
+use crate::git::GitRepo;
+use crate::permission::PermissionRequest;
+use crate::llm::{
+    documents::{self, DocumentAttachment, DocumentLimits},
+    images::ImageAttachment,
+    models::MessageModel,
+    tokenizer::{format_tokens, Tokenizer},
+    tools::{
+        screenshot::{is_ssh_session, take_screenshot, CaptureTarget},
+        session_env::env_command,
+    },
+    MessageRole, ProviderResponse,
+};
+use crate::session::prompt_history::{PromptHistory, PromptSource};
+use crate::session::{
+    journal, match_ranges, Conversation, MessageHit, Session, SessionComparison, TurnText, WordChange,
+};
+use crate::utils::text::format;
+
+/// A status line above at least one line of input, with the status bar under
+const MIN_HEIGHT: u16 = 3;
+
+/// Smallest viewport the chat is drawn in
+const MIN_SIZE: MinSize = MinSize::new(20, MIN_HEIGHT);
+
+type InlineTerminal = Terminal<CrosstermBackend<Recorded<io::Stdout>>>;
+
+/// Edit an earlier prompt of the conversation and send it again
+const EDIT_COMMAND: &str = "/edit";
+
+/// Regenerate the answer to the latest prompt
+const RETRY_COMMAND: &str = "/retry";
+
+/// Finish an answer a crash cut off
+const CONTINUE_COMMAND: &str = "/continue";
+
+/// Find past sessions by what they were about
+const RECALL_COMMAND: &str = "/recall";
+
+/// Most sessions `/recall` lists
+const SESSIONS_RECALLED: usize = 20;
+
+/// Compare the answers of two sessions
+const DIFF_COMMAND: &str = "/diff";
+
+/// Attach a screenshot of the screen, or of the window named, to the next prompt
+const SCREENSHOT_COMMAND: &str = "/screenshot";
+
+/// Frames of the spinner shown while waiting, one every 80 ms
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Most recent sessions listed by Ctrl+S
+const SESSIONS_LISTED: u32 = 100;
+
+/// Lines PageUp and PageDown scroll the document preview by
+const PREVIEW_PAGE: usize = 10;
+
+/// Pause in typing after which the markdown preview of the draft catches up
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Actions of the key map inline mode has, listed by the help key
+const INLINE_ACTIONS: &[&str] = &["quit", "help", "record_macro", "play_macro", "recent_files", "compare_sessions", "profiler"];
+
+const PROMPT: &str = "› ";
+const PROMPT_WIDTH: u16 = 2;
+
+/// What a key press asks the inline loop to do
+#[derive(Debug, PartialEq)]
+enum InlineAction {
+    None,
+    Submit(String),
+    /// Stop the command the agent is running
+    CancelCommand,
+    /// Switch to the model picked, as `/model` arguments
+    SwitchModel(String),
+    /// List the sessions to switch to
+    OpenSessions,
+    /// List the files the agent read or edited
+    OpenRecentFiles,
+    /// List the sessions to pick two of to compare
+    PickComparison,
+    /// Attach a file to the next prompt
+    AttachFile(PathBuf),
+    /// Continue the session with this ID instead
+    SwitchSession(String),
+    /// Continue a fork of the session with this ID
+    ForkSession(String),
+    DeleteSession(String),
+    /// Move the session with this ID to the compressed archive
+    ArchiveSession(String),
+    /// Compare the answers of the first session with the second's
+    CompareSessions(String, String),
+    /// Look the query of the message search up in its scope
+    SearchMessages(String, SearchScope),
+    /// Print the message a search hit is in, switching to its session
+    ShowHit(MessageHit),
+    /// Send an earlier prompt again, edited
+    Edit {
+        message_id: String,
+        prompt: String,
+        /// Go on in a new session instead of dropping what followed the prompt
+        branch: bool,
+    },
+    Quit,
+}
+
+/// State of the inline viewport
+#[derive(Default)]
+struct InlineChat {
+    input: String,
+    /// Byte offset of the cursor in the input
+    cursor: usize,
+    /// When the pending question was asked
+    waiting_since: Option<Instant>,
+    /// Prompts to send once the pending answer is in, oldest first
+    queued: VecDeque<String>,
+    /// Queued prompts wait for Enter, after an answer failed
+    queue_paused: bool,
+    error: Option<String>,
+    /// Follow-up prompts suggested for the last answer
+    suggestions: Vec<String>,
+    /// Suggestion last put into the input
+    selected: Option<usize>,
+    /// Earlier prompts of the project, oldest first
+    history: Vec<String>,
+    /// Prompt of the history shown in the input, with the input it replaced
+    browsing: Option<(usize, String)>,
+    /// Search through the history in progress
+    search: Option<HistorySearch>,
+    /// Background jobs of the agent still running
+    running_jobs: usize,
+    /// Command the agent is running, with when it started
+    running_command: Option<(String, Instant)>,
+    /// Models offered by `/model` without arguments
+    picker: Option<ModelPicker>,
+    /// Sessions listed by Ctrl+S
+    sessions: Option<SessionPicker>,
+    /// Files listed by Ctrl+P
+    recent_files: Option<FilePicker>,
+    /// Search through the messages, opened by Ctrl+F
+    message_search: Option<MessageSearch>,
+    /// What the conversation takes of the context, to count the input against
+    prompt_tokens: Option<PromptTokens>,
+    /// Earlier prompt being edited in the input
+    editing: Option<PromptEdit>,
+    /// Built-in and custom slash commands, by name
+    commands: Vec<SlashCommand>,
+    /// Command name typed before Tab completed it, with the command picked
+    completing: Option<(String, usize)>,
+    /// Documents sent along with the next prompt
+    documents: Vec<AttachedDocument>,
+    /// Screenshots sent along with the next prompt
+    images: Vec<ImageAttachment>,
+    /// What an attached document sends, while it is shown
+    preview: Option<DocumentPreview>,
+    /// The draft rendered as markdown, toggled by Alt+M
+    draft_preview: Option<DraftPreview>,
+    /// Transient messages shown in the status line
+    toasts: Toasts,
+    /// Model, cost and git state under the input
+    status_bar: StatusBar,
+    /// Tool call waiting for the user's answer
+    approval: Option<PermissionDialog>,
+    /// Tool calls to ask about after it, oldest first
+    approvals: VecDeque<PermissionRequest>,
+    /// Question to answer before an action is taken
+    confirm: Option<Confirmation>,
+    /// Offer to fence the code of the prompt being sent
+    fence_offer: Option<FenceOffer>,
+    /// Key bindings of the configuration file
+    key_map: KeyMap,
+    /// Keyboard macros
+    macros: MacroRecorder,
+    /// Configuration file recorded macros are saved to
+    macro_store: Option<PathBuf>,
+    /// Keys of a macro still to replay, oldest first
+    replaying: VecDeque<KeyEvent>,
+    /// Answer being written, shown above the input until it is printed
+    streamed: Option<StreamingMarkdown>,
+    /// Position in the answer being written
+    streamed_scroll: StickyScroll,
+    /// Newest table a tool returned, which Ctrl+T opens
+    table: Option<DataTable>,
+    /// The table is open in the viewport and takes the keys
+    table_open: bool,
+    /// Rewrites of answers before they are printed
+    postprocessor: Postprocessor,
+    /// First line of the key bindings shown, while they are
+    help: Option<usize>,
+}
+
+/// A yes/no question asked before an action is taken
+struct Confirmation {
+    message: String,
+    dialog: ConfirmDialog<bool>,
+    answer: oneshot::Receiver<bool>,
+    /// What Yes does
+    action: InlineAction,
+}
+
+impl Confirmation {
+    fn new(message: String, action: InlineAction) -> Self {
+        let (dialog, answer) = ConfirmDialog::yes_no("Confirm", message.clone());
+        Self { message, dialog, answer, action }
+    }
+
+    /// The action, once the dialog was answered Yes
+    fn into_action(mut self) -> InlineAction {
+        match self.answer.try_recv() {
+            Ok(true) => self.action,
+            _ => InlineAction::None,
+        }
+    }
+
+}
+
+/// Code of the draft sent without a fence, held back while the user decides
+/// whether to wrap it in one
+struct FenceOffer {
+    unfenced: Unfenced,
+    dialog: ConfirmDialog<FenceChoice>,
+    choice: oneshot::Receiver<FenceChoice>,
+}
+
+/// Draw a question with the answers of its dialog over the whole viewport;
+/// the buttons are left out when they don't fit, as the help line names
+/// their keys
+fn render_question<T: Clone + Send + Sync + 'static>(message: &str, dialog: &ConfirmDialog<T>, frame: &mut Frame, theme: &Theme) {
+    let area = frame.size();
+    let buttons = if area.height >= 5 { 3 } else { 0 };
+    let question = Paragraph::new(Span::styled(message.to_string(), Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(question, Rect::new(area.x, area.y, area.width, area.height - 1 - buttons));
+    if buttons > 0 {
+        dialog.render_buttons(frame, Rect::new(area.x, area.bottom() - 1 - buttons, area.width.min(60), buttons), theme);
+    }
+    let help = Paragraph::new(dialog.help_text()).style(Style::default().fg(theme.fg_muted));
+    frame.render_widget(help, Rect::new(area.x, area.bottom() - 1, area.width, 1));
+}
+
+/// A document attached to the next prompt
+struct AttachedDocument {
+    document: DocumentAttachment,
+    /// What the model gets
+    context: String,
+    tokens: usize,
+}
+
+/// Position in the preview of the attached documents
+#[derive(Debug, Default)]
+struct DocumentPreview {
+    /// Index into the attached documents
+    index: usize,
+    /// First line shown
+    scroll: usize,
+}
+
+/// The draft rendered as markdown beside the input
+///
+/// The draft is only rendered again once typing pauses, so a long prompt is
+/// not rendered on every key.
+#[derive(Debug, Default)]
+struct DraftPreview {
+    /// Draft and width the lines were rendered for
+    draft: String,
+    width: u16,
+    lines: Vec<Line<'static>>,
+    /// When the draft was first seen changed since it was rendered
+    changed: Option<Instant>,
+}
+
+impl DraftPreview {
+    /// Render the draft again once typing has paused, or at once for a new
+    /// width, returning whether it was
+    fn refresh(&mut self, draft: &str, width: u16, theme: &Theme, now: Instant) -> bool {
+        if draft == self.draft && width == self.width {
+            self.changed = None;
+            return false;
+        }
+        let changed = *self.changed.get_or_insert(now);
+        if width == self.width && now.duration_since(changed) < PREVIEW_DEBOUNCE {
+            return false;
+        }
+        let config = MarkdownConfig {
+            border: None,
+            show_line_numbers: false,
+            ..MarkdownConfig::default()
+        };
+        self.lines = match MarkdownWidget::render_string(draft, &config, theme, width) {
+            Ok(text) => text.lines,
+            Err(_) => Text::raw(draft.to_string()).lines,
+        };
+        self.draft = draft.to_string();
+        self.width = width;
+        self.changed = None;
+        true
+    }
+}
+
+/// A slash command offered while its name is typed
+#[derive(Debug, Clone)]
+struct SlashCommand {
+    name: String,
+    description: String,
+}
+
+impl SlashCommand {
+    /// Built-in commands followed by the custom ones
+    fn all(custom: &CustomCommands) -> Vec<Self> {
+        let built_in = BUILT_IN_COMMANDS.iter().map(|(name, description)| Self {
+            name: name.to_string(),
+            description: description.to_string(),
+        });
+        let custom = custom.iter().map(|command| {
+            let description = command.description.clone().unwrap_or_else(|| "Custom command".to_string());
+            Self {
+                name: command.name.clone(),
+                description: match &command.argument_hint {
+                    Some(hint) => format!("{} {}", description, hint),
+                    None => description,
+                },
+            }
+        });
+        let mut commands: Vec<Self> = built_in.chain(custom).collect();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        commands
+    }
+}
+
+/// An earlier prompt of the conversation being edited
+#[derive(Debug)]
+struct PromptEdit {
+    /// The conversation's prompts, oldest first, as message IDs with their text
+    prompts: Vec<(String, String)>,
+    /// Index of the prompt being edited
+    index: usize,
+    /// Go on in a new session instead of dropping what followed the prompt
+    branch: bool,
+}
+
+/// Size of the request the input would send
+struct PromptTokens {
+    tokenizer: Tokenizer,
+    /// Tokens of the messages so far, the system message and the tools
+    context: usize,
+    context_window: Option<u32>,
+}
+
+impl PromptTokens {
+    async fn of(conversation: &Conversation) -> Self {
+        Self {
+            tokenizer: conversation.tokenizer(),
+            context: conversation.context_tokens().await,
+            context_window: conversation.context_window(),
+        }
+    }
+
+    /// Tokens in the request with `input` as its prompt and `attached` tokens
+    /// of documents, marked when estimated
+    fn label(&self, input: &str, attached: usize) -> String {
+        let tokens = self.context + attached + self.tokenizer.count(input);
+        let estimate = if self.tokenizer.is_exact() { "" } else { "~" };
+        match self.context_window {
+            Some(window) => format!("{}{} / {} tokens", estimate, format_tokens(tokens), format_tokens(window as usize)),
+            None => format!("{}{} tokens", estimate, format_tokens(tokens)),
+        }
+    }
+
+    /// Share of the context window the conversation takes, in percent
+    fn context_percent(&self) -> Option<f64> {
+        let window = self.context_window.filter(|window| *window > 0)?;
+        Some(self.context as f64 * 100.0 / window as f64)
+    }
+}
+
+/// A model to pick in the `/model` picker
+#[derive(Debug, Clone, PartialEq)]
+struct ModelChoice {
+    model: MessageModel,
+    name: String,
+}
+
+/// Choice of a model to switch to, filtered by what is typed
+#[derive(Debug, Default)]
+struct ModelPicker {
+    choices: Vec<ModelChoice>,
+    /// Model answering now
+    current: Option<MessageModel>,
+    query: String,
+    /// Index into the matching choices
+    selected: usize,
+    /// How listing the server's models goes, while there is something to say
+    note: Option<String>,
+}
+
+impl ModelPicker {
+    /// Offer the switcher's models, the current one first
+    fn new(switcher: &ModelSwitcher) -> Self {
+        let current = switcher.current();
+        let mut choices: Vec<ModelChoice> = switcher
+            .choices()
+            .into_iter()
+            .map(|known| ModelChoice {
+                model: MessageModel::new(known.provider, known.id),
+                name: known.name.to_string(),
+            })
+            .collect();
+        match choices.iter().position(|choice| choice.model == current) {
+            Some(index) => {
+                let choice = choices.remove(index);
+                choices.insert(0, choice);
+            }
+            None => choices.insert(0, ModelChoice { model: current.clone(), name: current.model.clone() }),
+        }
+        Self {
+            choices,
+            current: Some(current),
+            ..Self::default()
+        }
+    }
+
+    /// Add the models the server lists after the catalog's
+    fn add_served(&mut self, served: Vec<ServedModel>) {
+        for served in served {
+            if !self.choices.iter().any(|choice| choice.model == served.model) {
+                self.choices.push(ModelChoice { model: served.model, name: served.name });
+            }
+        }
+        self.note = None;
+    }
+
+    /// Choices whose name, model or provider contain the query, ignoring case
+    fn matching(&self) -> Vec<&ModelChoice> {
+        let query = self.query.to_lowercase();
+        self.choices
+            .iter()
+            .filter(|choice| {
+                choice.name.to_lowercase().contains(&query)
+                    || choice.model.model.to_lowercase().contains(&query)
+                    || choice.model.provider.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Draw the model picker over the whole viewport
+    fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let mut status = vec![
+            Span::styled("Switch model: ", Style::default().fg(theme.fg_muted)),
+            Span::styled(self.query.clone(), Style::default().fg(theme.info)),
+            Span::styled(" · ↑/↓ to choose · Enter to switch · Esc to cancel", Style::default().fg(theme.fg_muted)),
+        ];
+        if let Some(note) = &self.note {
+            status.push(Span::styled(format!(" · {}", note), Style::default().fg(theme.fg_muted)));
+        }
+        frame.render_widget(Paragraph::new(Line::from(status)), Rect::new(area.x, area.y, area.width, 1));
+
+        // Scroll the list so the selected model stays in view
+        let rows = (area.height - 1) as usize;
+        let matching = self.matching();
+        let first = (self.selected + 1).saturating_sub(rows);
+        let lines: Vec<Line> = if matching.is_empty() {
+            vec![Line::from(Span::styled("  No model matches", Style::default().fg(theme.fg_muted)))]
+        } else {
+            matching
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(index, choice)| {
+                    let marker = if self.current.as_ref() == Some(&choice.model) { "● " } else { "  " };
+                    let style = if index == self.selected {
+                        Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default().fg(theme.fg_base)
+                    };
+                    Line::from(vec![
+                        Span::styled(marker, Style::default().fg(theme.info)),
+                        Span::styled(choice.name.clone(), style),
+                        Span::styled(format!("  {} · {}", choice.model.provider, choice.model.model), Style::default().fg(theme.fg_muted)),
+                    ])
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+}
+
+/// Choice of a session to continue, fork, delete or archive, filtered by what is typed
+#[derive(Debug, Default)]
+struct SessionPicker {
+    /// Most recent first
+    sessions: Vec<Session>,
+    /// Filters the sessions by title and ID
+    list: FilterableList<SimpleFilterableItem>,
+    /// Session of the conversation, which can't be deleted
+    current: String,
+    /// Index into the matching sessions
+    selected: usize,
+    notice: Option<String>,
+    /// Message that matched in each session `/recall` found, by session ID
+    snippets: HashMap<String, String>,
+    /// Sessions `/recall` found in the archive, restored when picked
+    archived: HashSet<String>,
+    /// Opened by `/diff` to pick two sessions to compare
+    comparing: bool,
+    /// Session picked first for `/diff`
+    compared: Option<String>,
+}
+
+impl SessionPicker {
+    fn new(sessions: Vec<Session>, current: &str) -> Self {
+        let mut picker = Self {
+            sessions,
+            current: current.to_string(),
+            ..Self::default()
+        };
+        picker.refilter();
+        picker
+    }
+
+    /// Sessions to pick two of for `/diff`
+    fn comparing(sessions: Vec<Session>, current: &str) -> Self {
+        let mut picker = Self::new(sessions, current);
+        picker.comparing = true;
+        picker
+    }
+
+    /// Sessions `/recall` found, best match first, with the message that
+    /// matched and whether the session is archived
+    fn recalled(found: Vec<(Session, String, bool)>, current: &str) -> Self {
+        let snippets = found.iter().map(|(session, snippet, _)| (session.id.clone(), snippet.clone())).collect();
+        let archived = found.iter().filter(|(_, _, archived)| *archived).map(|(session, _, _)| session.id.clone()).collect();
+        let mut picker = Self::new(found.into_iter().map(|(session, _, _)| session).collect(), current);
+        picker.snippets = snippets;
+        picker.archived = archived;
+        picker
+    }
+
+    /// Match the sessions against the query again
+    fn refilter(&mut self) {
+        let items = self
+            .sessions
+            .iter()
+            .map(|session| SimpleFilterableItem::from_text(session.id.clone(), format!("{} {}", session.title, session.id)))
+            .collect();
+        if let Err(e) = self.list.set_items(items) {
+            tracing::debug!("Sessions not filtered: {}", e);
+        }
+    }
+
+    fn query(&self) -> &str {
+        self.list.query()
+    }
+
+    fn set_query(&mut self, query: String) {
+        if let Err(e) = self.list.set_query(query) {
+            tracing::debug!("Sessions not filtered: {}", e);
+        }
+        self.selected = 0;
+    }
+
+    /// Sessions matching the query, best first, with the positions of the
+    /// characters matched
+    fn matching(&self) -> Vec<(&Session, &[usize])> {
+        self.list
+            .filtered_items()
+            .iter()
+            .filter_map(|item| {
+                let session = self.sessions.iter().find(|session| session.id == item.id())?;
+                Some((session, item.match_indices()))
+            })
+            .collect()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.matching().get(self.selected).map(|(session, _)| session.id.clone())
+    }
+
+    /// Drop a deleted or archived session from the list
+    fn remove(&mut self, session_id: &str, notice: &str) {
+        self.sessions.retain(|session| session.id != session_id);
+        self.refilter();
+        self.selected = self.selected.min(self.matching().len().saturating_sub(1));
+        self.notice = Some(notice.to_string());
+    }
+
+    /// Draw the sessions over the whole viewport
+    fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let label = match (self.comparing, self.snippets.is_empty()) {
+            (true, _) => "Compare sessions: ",
+            (false, true) => "Sessions: ",
+            (false, false) => "Recalled sessions: ",
+        };
+        let mut status = vec![
+            Span::styled(label, Style::default().fg(theme.fg_muted)),
+            Span::styled(self.query().to_string(), Style::default().fg(theme.info)),
+        ];
+        match &self.notice {
+            Some(notice) => status.push(Span::styled(format!(" · {}", notice), Style::default().fg(theme.fg_muted))),
+            None if self.comparing => {
+                let hint = match self.compared {
+                    Some(_) => " · Enter to compare with A · Esc to close",
+                    None => " · Enter to pick A · Esc to close",
+                };
+                status.push(Span::styled(hint, Style::default().fg(theme.fg_muted)));
+            }
+            None => status.push(Span::styled(
+                " · Enter to switch · Ctrl+F to fork · Ctrl+D to delete · Ctrl+A to archive · Esc to close",
+                Style::default().fg(theme.fg_muted),
+            )),
+        }
+        frame.render_widget(Paragraph::new(Line::from(status)), Rect::new(area.x, area.y, area.width, 1));
+
+        // Scroll the list so the selected session stays in view
+        let rows = (area.height - 1) as usize;
+        let matching = self.matching();
+        let first = (self.selected + 1).saturating_sub(rows);
+        let lines: Vec<Line> = if matching.is_empty() {
+            vec![Line::from(Span::styled("  No session matches", Style::default().fg(theme.fg_muted)))]
+        } else {
+            matching
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(index, (session, matched))| {
+                    let marker = if self.compared.as_ref() == Some(&session.id) {
+                        "A "
+                    } else if session.id == self.current {
+                        "● "
+                    } else {
+                        "  "
+                    };
+                    let style = if index == self.selected {
+                        Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default().fg(theme.fg_base)
+                    };
+                    let mut spans = vec![Span::styled(marker, Style::default().fg(theme.info))];
+                    for (position, c) in session.title.chars().enumerate() {
+                        let style = if matched.contains(&position) { style.add_modifier(Modifier::BOLD) } else { style };
+                        spans.push(Span::styled(c.to_string(), style));
+                    }
+                    let details = match self.snippets.get(&session.id) {
+                        Some(snippet) => format!("  {}", snippet.lines().next().unwrap_or_default()),
+                        None => format!(
+                            "  {} · {} messages · ${:.2}",
+                            session.updated_at.format("%Y-%m-%d %H:%M"),
+                            session.message_count,
+                            session.total_cost
+                        ),
+                    };
+                    if self.archived.contains(&session.id) {
+                        spans.push(Span::styled("  (archived)", Style::default().fg(theme.fg_muted)));
+                    }
+                    spans.push(Span::styled(details, Style::default().fg(theme.fg_muted)));
+                    Line::from(spans)
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+}
+
+/// Choice of a file the agent touched this session, filtered by what is typed
+#[derive(Debug, Default)]
+struct FilePicker {
+    /// Most recently touched first, with the path shown for each
+    files: Vec<(TouchedFile, String)>,
+    /// Filters the files by the path shown
+    list: FilterableList<SimpleFilterableItem>,
+    /// Index into the matching files
+    selected: usize,
+}
+
+impl FilePicker {
+    /// Paths below `root` are shown relative to it
+    fn new(files: Vec<TouchedFile>, root: Option<&std::path::Path>) -> Self {
+        let files: Vec<(TouchedFile, String)> = files
+            .into_iter()
+            .map(|file| {
+                let shown = root
+                    .and_then(|root| file.path.strip_prefix(root).ok())
+                    .unwrap_or(&file.path)
+                    .display()
+                    .to_string();
+                (file, shown)
+            })
+            .collect();
+        let mut picker = Self::default();
+        let items = files
+            .iter()
+            .map(|(_, shown)| SimpleFilterableItem::from_text(shown.clone(), shown.clone()))
+            .collect();
+        if let Err(e) = picker.list.set_items(items) {
+            tracing::debug!("Files not filtered: {}", e);
+        }
+        picker.files = files;
+        picker
+    }
+
+    fn query(&self) -> &str {
+        self.list.query()
+    }
+
+    fn set_query(&mut self, query: String) {
+        if let Err(e) = self.list.set_query(query) {
+            tracing::debug!("Files not filtered: {}", e);
+        }
+        self.selected = 0;
+    }
+
+    /// Files matching the query, best first, with the positions of the
+    /// characters matched
+    fn matching(&self) -> Vec<(&TouchedFile, &str, &[usize])> {
+        self.list
+            .filtered_items()
+            .iter()
+            .filter_map(|item| {
+                let (file, shown) = self.files.iter().find(|(_, shown)| *shown == item.id())?;
+                Some((file, shown.as_str(), item.match_indices()))
+            })
+            .collect()
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.matching().get(self.selected).map(|(file, _, _)| file.path.clone())
+    }
+
+    /// Draw the files over the whole viewport
+    fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let status = vec![
+            Span::styled("Recent files: ", Style::default().fg(theme.fg_muted)),
+            Span::styled(self.query().to_string(), Style::default().fg(theme.info)),
+            Span::styled(
+                " · Enter to attach · Tab to insert the path · Esc to close",
+                Style::default().fg(theme.fg_muted),
+            ),
+        ];
+        frame.render_widget(Paragraph::new(Line::from(status)), Rect::new(area.x, area.y, area.width, 1));
+
+        // Scroll the list so the selected file stays in view
+        let rows = (area.height - 1) as usize;
+        let matching = self.matching();
+        let first = (self.selected + 1).saturating_sub(rows);
+        let lines: Vec<Line> = if matching.is_empty() {
+            let text = if self.files.is_empty() {
+                "  The agent has not read or edited any files yet"
+            } else {
+                "  No file matches"
+            };
+            vec![Line::from(Span::styled(text, Style::default().fg(theme.fg_muted)))]
+        } else {
+            matching
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(rows)
+                .map(|(index, (file, shown, matched))| {
+                    let style = if index == self.selected {
+                        Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default().fg(theme.fg_base)
+                    };
+                    let icon = if file.edited { &theme.icons.edit } else { &theme.icons.file };
+                    let mut spans = vec![Span::styled(format!("{} ", icon), Style::default().fg(theme.info))];
+                    for (position, c) in shown.chars().enumerate() {
+                        let style = if matched.contains(&position) { style.add_modifier(Modifier::BOLD) } else { style };
+                        spans.push(Span::styled(c.to_string(), style));
+                    }
+                    let label = if file.edited { "  edited" } else { "  read" };
+                    spans.push(Span::styled(label, Style::default().fg(theme.fg_muted)));
+                    Line::from(spans)
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+}
+
+/// A Ctrl+R search through the prompt history
+#[derive(Debug, Default)]
+struct HistorySearch {
+    query: String,
+    /// Prompt found for the query
+    found: Option<usize>,
+    /// Input from before the search, put back when it is cancelled
+    draft: String,
+}
+
+impl InlineChat {
+    fn handle_key(&mut self, key: KeyEvent) -> InlineAction {
+        if key.kind != KeyEventKind::Press {
+            return InlineAction::None;
+        }
+        if self.key_map.should_toggle_profiler(&key) {
+            profiler::set_enabled(!profiler::is_enabled());
+            return InlineAction::None;
+        }
+        // Approvals are answered by hand, never by a macro
+        if self.approval.is_none() {
+            let record = self.key_map.should_record_macro(&key);
+            let play = self.key_map.should_play_macro(&key);
+            match self.macros.handle_key(key, record, play) {
+                MacroOutcome::Pass => {}
+                MacroOutcome::Consumed(message) => {
+                    if let Some(message) = message {
+                        self.toasts.push(message, ToastKind::Info);
+                    }
+                    return InlineAction::None;
+                }
+                MacroOutcome::Recorded(register, keys) => {
+                    self.save_macro(register, &keys);
+                    return InlineAction::None;
+                }
+                MacroOutcome::Replay(keys) => {
+                    self.replaying.extend(keys);
+                    return InlineAction::None;
+                }
+            }
+        }
+        self.handle_typed_key(key)
+    }
+
+    /// Keep a recorded macro for later sessions
+    fn save_macro(&mut self, register: char, keys: &[KeyEvent]) {
+        let saved = match &self.macro_store {
+            Some(path) => macros::save(path, register, keys).map_err(|e| e.to_string()),
+            None => Err("no configuration file".to_string()),
+        };
+        match saved {
+            Ok(()) => self.toasts.push(format!("Recorded macro {} ({} keys)", register, keys.len()), ToastKind::Success),
+            Err(e) => self.toasts.push(
+                format!("Recorded macro {} ({} keys) for this session only: {}", register, keys.len(), e),
+                ToastKind::Info,
+            ),
+        }
+    }
+
+    /// Next key of a macro being replayed; a tool approval ends the replay
+    fn next_replayed(&mut self) -> Option<KeyEvent> {
+        if self.approval.is_some() {
+            self.replaying.clear();
+        }
+        self.replaying.pop_front()
+    }
+
+    /// Handle a key typed or replayed from a macro
+    fn handle_typed_key(&mut self, key: KeyEvent) -> InlineAction {
+        // The agent is blocked until the tool call is answered
+        if let Some(approval) = self.approval.as_mut() {
+            approval.handle_key(key);
+            if approval.is_answered() {
+                self.approval = None;
+            }
+            return InlineAction::None;
+        }
+        if let Some(confirm) = self.confirm.as_mut() {
+            confirm.dialog.handle_key(key);
+            if confirm.dialog.is_answered() {
+                return self.confirm.take().map_or(InlineAction::None, Confirmation::into_action);
+            }
+            return InlineAction::None;
+        }
+        if self.fence_offer.is_some() {
+            return self.handle_fence_offer_key(key);
+        }
+        if self.search.is_some() {
+            self.handle_search_key(key);
+            return InlineAction::None;
+        }
+        if self.picker.is_some() {
+            return self.handle_picker_key(key);
+        }
+        if self.sessions.is_some() {
+            return self.handle_sessions_key(key);
+        }
+        if self.recent_files.is_some() {
+            return self.handle_recent_files_key(key);
+        }
+        if self.message_search.is_some() {
+            return self.handle_message_search_key(key);
+        }
+        if self.preview.is_some() {
+            self.handle_preview_key(key);
+            return InlineAction::None;
+        }
+        if self.table_open {
+            self.handle_table_key(key);
+            return InlineAction::None;
+        }
+        if self.help.is_some() {
+            self.handle_help_key(key);
+            return InlineAction::None;
+        }
+        if let Some(action) = self.handle_editing_key(key) {
+            return action;
+        }
+        if key.code != KeyCode::Tab {
+            self.completing = None;
+        }
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) if self.running_command.is_some() => {
+                return InlineAction::CancelCommand;
+            }
+            (KeyCode::Esc, _) => return InlineAction::Quit,
+            _ if self.key_map.should_quit(&key) => return InlineAction::Quit,
+            _ if self.key_map.should_show_help(&key) => self.help = Some(0),
+            _ if self.key_map.should_compare_sessions(&key) => return InlineAction::PickComparison,
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) if self.input.is_empty() => return InlineAction::Quit,
+            (KeyCode::Enter, KeyModifiers::SHIFT) | (KeyCode::Enter, KeyModifiers::ALT) => self.insert("\n"),
+            (KeyCode::Enter, _) => {
+                if self.offer_fence() {
+                    return InlineAction::None;
+                }
+                if let Some(question) = self.take_prompt() {
+                    return InlineAction::Submit(question);
+                }
+            }
+            (KeyCode::Tab, _) => {
+                if !self.complete_command() {
+                    self.next_suggestion();
+                }
+            }
+            (KeyCode::Up, KeyModifiers::NONE) if self.input.is_empty() || self.browsing.is_some() => self.history_previous(),
+            (KeyCode::Down, KeyModifiers::NONE) if self.browsing.is_some() => self.history_next(),
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.search = Some(HistorySearch {
+                    draft: self.input.clone(),
+                    ..Default::default()
+                });
+            }
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                self.input.clear();
+                self.cursor = 0;
+            }
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => return InlineAction::OpenSessions,
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => self.message_search = Some(MessageSearch::new()),
+            _ if self.key_map.should_open_recent_files(&key) => return InlineAction::OpenRecentFiles,
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => self.unqueue(),
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) if !self.documents.is_empty() => {
+                self.preview = Some(DocumentPreview::default());
+            }
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) if self.table.is_some() => self.table_open = true,
+            (KeyCode::Char('m'), KeyModifiers::ALT) => {
+                self.draft_preview = match self.draft_preview.take() {
+                    Some(_) => None,
+                    None => Some(DraftPreview::default()),
+                };
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                self.insert(c.encode_utf8(&mut [0; 4]));
+            }
+            (KeyCode::Backspace, _) => {
+                if let Some(c) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.input.remove(self.cursor);
+                }
+            }
+            (KeyCode::Delete, _) => {
+                if self.cursor < self.input.len() {
+                    self.input.remove(self.cursor);
+                }
+            }
+            (KeyCode::Left, _) => {
+                if let Some(c) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            (KeyCode::Right, _) => {
+                if let Some(c) = self.input[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            // While an answer is being written, these scroll through it
+            (KeyCode::PageUp, _) if self.streamed.is_some() => self.streamed_scroll.page_up(),
+            (KeyCode::PageDown, _) if self.streamed.is_some() => self.streamed_scroll.page_down(),
+            (KeyCode::End, KeyModifiers::CONTROL) if self.streamed.is_some() => self.streamed_scroll.scroll_to_bottom(),
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => self.cursor = 0,
+            (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => self.cursor = self.input.len(),
+            _ => {}
+        }
+        InlineAction::None
+    }
+
+    /// Take the input to send, unless it is empty; while an answer is on its
+    /// way, it is queued instead
+    fn take_prompt(&mut self) -> Option<String> {
+        self.queue_paused = false;
+        if self.input.trim().is_empty() {
+            return None;
+        }
+        if self.waiting_since.is_some() {
+            if self.editing.is_none() {
+                let prompt = std::mem::take(&mut self.input);
+                self.cursor = 0;
+                self.browsing = None;
+                self.remember(&prompt);
+                self.queued.push_back(prompt);
+            }
+            return None;
+        }
+        self.cursor = 0;
+        self.start_waiting();
+        self.browsing = None;
+        let prompt = std::mem::take(&mut self.input);
+        self.remember(&prompt);
+        Some(prompt)
+    }
+
+    /// Ask about the next tool call waiting, once no other is asked about,
+    /// returning its dialog to print the preview of
+    fn next_approval(&mut self) -> Option<&PermissionDialog> {
+        if self.approval.is_some() {
+            return None;
+        }
+        let request = self.approvals.pop_front()?;
+        self.approval = Some(PermissionDialog::new(request));
+        self.approval.as_ref()
+    }
+
+    fn start_waiting(&mut self) {
+        self.error = None;
+        self.suggestions.clear();
+        self.selected = None;
+        self.waiting_since = Some(Instant::now());
+    }
+
+    /// The queued prompt to send next, once no answer is on its way
+    fn next_queued(&mut self) -> Option<String> {
+        if self.waiting_since.is_some() || self.queue_paused {
+            return None;
+        }
+        let prompt = self.queued.pop_front()?;
+        self.start_waiting();
+        Some(prompt)
+    }
+
+    /// Take the latest queued prompt back into the input, after what is typed
+    fn unqueue(&mut self) {
+        let Some(prompt) = self.queued.pop_back() else {
+            return;
+        };
+        if !self.input.is_empty() {
+            self.input.push('\n');
+        }
+        self.input.push_str(&prompt);
+        self.cursor = self.input.len();
+    }
+
+    /// Attach a document to the next prompt and show what it sends
+    fn attach(&mut self, document: DocumentAttachment) {
+        let context = document.context();
+        let tokens = match &self.prompt_tokens {
+            Some(prompt_tokens) => prompt_tokens.tokenizer.count(&context),
+            None => context.len() / 4,
+        };
+        self.documents.push(AttachedDocument { document, context, tokens });
+        self.preview = Some(DocumentPreview {
+            index: self.documents.len() - 1,
+            scroll: 0,
+        });
+    }
+
+    /// The documents attached to the prompt being sent, which go with it only
+    fn take_documents(&mut self) -> Vec<DocumentAttachment> {
+        self.preview = None;
+        self.documents.drain(..).map(|attached| attached.document).collect()
+    }
+
+    /// The screenshots attached to the prompt being sent
+    fn take_images(&mut self) -> Vec<ImageAttachment> {
+        std::mem::take(&mut self.images)
+    }
+
+    fn handle_preview_key(&mut self, key: KeyEvent) {
+        let Some(preview) = self.preview.as_mut() else {
+            return;
+        };
+        let last_line = self
+            .documents
+            .get(preview.index)
+            .map_or(0, |attached| attached.context.lines().count().saturating_sub(1));
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _)
+            | (KeyCode::Enter, _)
+            | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+            | (KeyCode::Char('o'), KeyModifiers::CONTROL) => self.preview = None,
+            (KeyCode::Up, _) => preview.scroll = preview.scroll.saturating_sub(1),
+            (KeyCode::Down, _) => preview.scroll = (preview.scroll + 1).min(last_line),
+            (KeyCode::PageUp, _) => preview.scroll = preview.scroll.saturating_sub(PREVIEW_PAGE),
+            (KeyCode::PageDown, _) => preview.scroll = (preview.scroll + PREVIEW_PAGE).min(last_line),
+            (KeyCode::Tab, _) if !self.documents.is_empty() => {
+                preview.index = (preview.index + 1) % self.documents.len();
+                preview.scroll = 0;
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) | (KeyCode::Delete, _) => {
+                if preview.index < self.documents.len() {
+                    self.documents.remove(preview.index);
+                }
+                self.preview = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Draw what the previewed document sends over the whole viewport
+    fn render_preview(&self, preview: &DocumentPreview, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let Some(attached) = self.documents.get(preview.index) else {
+            return;
+        };
+        let document = &attached.document;
+        let parts = document.chunks.len();
+        let mut status = vec![
+            Span::styled(document.name.clone(), Style::default().fg(theme.info)),
+            Span::styled(
+                format!(
+                    " · {} part{} · {} tokens",
+                    parts,
+                    if parts == 1 { "" } else { "s" },
+                    format_tokens(attached.tokens)
+                ),
+                Style::default().fg(theme.fg_muted),
+            ),
+        ];
+        if document.truncated {
+            status.push(Span::styled(
+                format!(" · cut to {} of {} characters", document.sent_chars(), document.total_chars),
+                Style::default().fg(theme.warning),
+            ));
+        }
+        let tab = if self.documents.len() > 1 { " · Tab for the next" } else { "" };
+        status.push(Span::styled(
+            format!(" · ↑/↓ to scroll{} · Ctrl+D to remove · Esc to close", tab),
+            Style::default().fg(theme.fg_muted),
+        ));
+        frame.render_widget(Paragraph::new(Line::from(status)), Rect::new(area.x, area.y, area.width, 1));
+
+        let body = Paragraph::new(Text::styled(attached.context.as_str(), Style::default().fg(theme.fg_base)))
+            .scroll((preview.scroll.min(u16::MAX as usize) as u16, 0));
+        frame.render_widget(body, Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+
+    /// The key map's actions inline mode has, as their keys and descriptions
+    fn help_lines(&self) -> Vec<(String, &str)> {
+        self.key_map
+            .bindings()
+            .into_iter()
+            .filter(|(action, _)| INLINE_ACTIONS.contains(action))
+            .filter(|(action, _)| *action != "profiler" || cfg!(debug_assertions))
+            .map(|(_, binding)| (binding.key_label(), binding.description.as_str()))
+            .collect()
+    }
+
+    fn handle_help_key(&mut self, key: KeyEvent) {
+        let Some(first) = self.help else {
+            return;
+        };
+        let last_line = self.help_lines().len().saturating_sub(1);
+        self.help = match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Enter, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => None,
+            _ if self.key_map.should_show_help(&key) => None,
+            (KeyCode::Up, _) => Some(first.saturating_sub(1)),
+            (KeyCode::Down, _) => Some((first + 1).min(last_line)),
+            (KeyCode::PageUp, _) => Some(first.saturating_sub(PREVIEW_PAGE)),
+            (KeyCode::PageDown, _) => Some((first + PREVIEW_PAGE).min(last_line)),
+            _ => Some(first),
+        };
+    }
+
+    /// Draw the key bindings over the whole viewport
+    fn render_help(&self, first: usize, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        let status = Line::from(vec![
+            Span::styled("Keys", Style::default().fg(theme.info)),
+            Span::styled(" · ↑/↓ to scroll · Esc to close", Style::default().fg(theme.fg_muted)),
+        ]);
+        frame.render_widget(Paragraph::new(status), Rect::new(area.x, area.y, area.width, 1));
+
+        let bindings = self.help_lines();
+        let width = bindings.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        let lines: Vec<Line> = bindings
+            .into_iter()
+            .skip(first)
+            .map(|(key, description)| {
+                Line::from(vec![
+                    Span::styled(format!("  {:<width$}  ", key, width = width), Style::default().fg(theme.info)),
+                    Span::styled(description.to_string(), Style::default().fg(theme.fg_base)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+
+    fn handle_table_key(&mut self, key: KeyEvent) {
+        let Some(table) = self.table.as_mut() else {
+            self.table_open = false;
+            return;
+        };
+        if matches!((key.code, key.modifiers), (KeyCode::Char('c'), KeyModifiers::CONTROL) | (KeyCode::Char('t'), KeyModifiers::CONTROL)) {
+            self.table_open = false;
+            return;
+        }
+        match table.handle_key(key) {
+            TableAction::Copy(text) => match clipboard::copy(&text) {
+                Ok(()) => self.toasts.push("Copied".to_string(), ToastKind::Success),
+                Err(e) => self.error = Some(format!("Error: {}", e)),
+            },
+            TableAction::Exit => self.table_open = false,
+            TableAction::None => {}
+        }
+    }
+
+    /// Draw the open table over the whole viewport, its status line on top
+    fn render_table(table: &mut DataTable, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        // The status line, the header and the separator take a row each
+        table.set_visible_rows(area.height.saturating_sub(3) as usize);
+        let mut lines = table.render_lines(area.width as usize, theme, true);
+        let status = lines.pop().unwrap_or_default();
+        frame.render_widget(Paragraph::new(status), Rect::new(area.x, area.y, area.width, 1));
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+
+    /// Edit the conversation's latest prompt; Up and Down move to the others
+    fn start_editing(&mut self, prompts: Vec<(String, String)>) {
+        if prompts.is_empty() {
+            self.error = Some("Nothing to edit yet".to_string());
+            return;
+        }
+        let index = prompts.len() - 1;
+        self.editing = Some(PromptEdit {
+            prompts,
+            index,
+            branch: false,
+        });
+        self.show_edited(index);
+    }
+
+    fn show_edited(&mut self, index: usize) {
+        if let Some(editing) = self.editing.as_mut() {
+            editing.index = index;
+            self.input = editing.prompts[index].1.clone();
+            self.cursor = self.input.len();
+        }
+    }
+
+    /// Send the latest prompt again to regenerate its answer
+    fn retry(&mut self, mut prompts: Vec<(String, String)>) -> InlineAction {
+        match prompts.pop() {
+            Some((message_id, prompt)) => InlineAction::Edit {
+                message_id,
+                prompt,
+                branch: false,
+            },
+            None => {
+                self.waiting_since = None;
+                self.error = Some("Nothing to regenerate yet".to_string());
+                InlineAction::None
+            }
+        }
+    }
+
+    /// Keys choosing and sending the prompt being edited; `None` for those
+    /// editing the input as usual
+    fn handle_editing_key(&mut self, key: KeyEvent) -> Option<InlineAction> {
+        let editing = self.editing.as_mut()?;
+        let (index, last) = (editing.index, editing.prompts.len() - 1);
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                self.editing = None;
+                self.input.clear();
+                self.cursor = 0;
+            }
+            (KeyCode::Up, KeyModifiers::NONE) => self.show_edited(index.saturating_sub(1)),
+            (KeyCode::Down, KeyModifiers::NONE) => self.show_edited((index + 1).min(last)),
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => editing.branch = !editing.branch,
+            (KeyCode::Enter, KeyModifiers::NONE) => {
+                let prompt = self.take_prompt()?;
+                let editing = self.editing.take()?;
+                let (message_id, _) = editing.prompts.into_iter().nth(editing.index)?;
+                return Some(InlineAction::Edit {
+                    message_id,
+                    prompt,
+                    branch: editing.branch,
+                });
+            }
+            _ => return None,
+        }
+        Some(InlineAction::None)
+    }
+
+    /// Commands starting with the command name typed, if one is being typed
+    fn typed_commands(&self) -> Vec<&SlashCommand> {
+        let typed = self.completing.as_ref().map_or(self.input.as_str(), |(typed, _)| typed.as_str());
+        match typed.strip_prefix('/') {
+            Some(prefix) if !typed.contains(char::is_whitespace) && self.editing.is_none() => {
+                self.commands.iter().filter(|command| command.name.starts_with(prefix)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Complete the command name typed, or move on to the next command
+    /// matching it; false when no command name is typed
+    fn complete_command(&mut self) -> bool {
+        let matching: Vec<String> = self.typed_commands().iter().map(|command| command.name.clone()).collect();
+        if matching.is_empty() {
+            return false;
+        }
+        let (typed, index) = match self.completing.take() {
+            Some((typed, index)) => (typed, (index + 1) % matching.len()),
+            None => (self.input.clone(), 0),
+        };
+        self.input = format!("/{}", matching[index]);
+        self.cursor = self.input.len();
+        self.completing = Some((typed, index));
+        true
+    }
+
+    /// Put the next suggestion into the input, unless the user typed something else
+    fn next_suggestion(&mut self) {
+        if self.suggestions.is_empty() {
+            return;
+        }
+        let untouched = match self.selected {
+            Some(index) => self.input == self.suggestions[index],
+            None => self.input.is_empty(),
+        };
+        if !untouched {
+            return;
+        }
+        let index = self.selected.map_or(0, |index| (index + 1) % self.suggestions.len());
+        self.input = self.suggestions[index].clone();
+        self.cursor = self.input.len();
+        self.selected = Some(index);
+    }
+
+    /// Keep a prompt in the history, as its latest entry
+    fn remember(&mut self, prompt: &str) {
+        self.history.retain(|earlier| earlier != prompt);
+        self.history.push(prompt.to_string());
+    }
+
+    /// Show the prompt before the one shown, starting with the latest
+    fn history_previous(&mut self) {
+        let index = match &self.browsing {
+            Some((index, _)) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        if self.browsing.is_none() {
+            self.browsing = Some((index, std::mem::take(&mut self.input)));
+        } else if let Some((shown, _)) = &mut self.browsing {
+            *shown = index;
+        }
+        self.show_prompt(index);
+    }
+
+    /// Show the prompt after the one shown, or the input from before browsing
+    fn history_next(&mut self) {
+        let Some((index, draft)) = self.browsing.take() else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.browsing = Some((index + 1, draft));
+            self.show_prompt(index + 1);
+        } else {
+            self.input = draft;
+            self.cursor = self.input.len();
+        }
+    }
+
+    fn show_prompt(&mut self, index: usize) {
+        self.input = self.history[index].clone();
+        self.cursor = self.input.len();
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('g'), KeyModifiers::CONTROL) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                if let Some(search) = self.search.take() {
+                    self.input = search.draft;
+                    self.cursor = self.input.len();
+                }
+                return;
+            }
+            (KeyCode::Enter, _) | (KeyCode::Left, _) | (KeyCode::Right, _) | (KeyCode::Tab, _) => {
+                self.search = None;
+                self.browsing = None;
+                return;
+            }
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                let before = search.found.unwrap_or(self.history.len());
+                if let Some(found) = find_prompt(&self.history[..before], &search.query) {
+                    search.found = Some(found);
+                }
+            }
+            (KeyCode::Backspace, _) => {
+                search.query.pop();
+                search.found = find_prompt(&self.history, &search.query);
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                search.query.push(c);
+                search.found = find_prompt(&self.history, &search.query);
+            }
+            _ => return,
+        }
+        let shown = match search.found {
+            Some(index) => self.history[index].clone(),
+            None => search.draft.clone(),
+        };
+        self.input = shown;
+        self.cursor = self.input.len();
+    }
+
+    fn handle_picker_key(&mut self, key: KeyEvent) -> InlineAction {
+        let Some(picker) = self.picker.as_mut() else {
+            return InlineAction::None;
+        };
+        let matching = picker.matching().len();
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.picker = None,
+            (KeyCode::Enter, _) => {
+                let picked = picker.matching().get(picker.selected).map(|choice| choice.model.clone());
+                self.picker = None;
+                if let Some(model) = picked {
+                    return InlineAction::SwitchModel(format!("{} {}", model.provider, model.model));
+                }
+            }
+            (KeyCode::Up, _) if matching > 0 => picker.selected = (picker.selected + matching - 1) % matching,
+            (KeyCode::Down, _) | (KeyCode::Tab, _) if matching > 0 => picker.selected = (picker.selected + 1) % matching,
+            (KeyCode::Backspace, _) => {
+                picker.query.pop();
+                picker.selected = 0;
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                picker.query.push(c);
+                picker.selected = 0;
+            }
+            _ => {}
+        }
+        InlineAction::None
+    }
+
+    fn handle_sessions_key(&mut self, key: KeyEvent) -> InlineAction {
+        let Some(picker) = self.sessions.as_mut() else {
+            return InlineAction::None;
+        };
+        picker.notice = None;
+        let matching = picker.matching().len();
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.sessions = None,
+            (KeyCode::Enter, _) if picker.comparing => match (picker.compared.clone(), picker.selected_id()) {
+                (Some(first), Some(second)) if first != second => {
+                    self.sessions = None;
+                    return InlineAction::CompareSessions(first, second);
+                }
+                (Some(_), Some(_)) => picker.notice = Some("Pick another session to compare with".to_string()),
+                (None, picked) => picker.compared = picked,
+                (_, None) => {}
+            },
+            (KeyCode::Enter, _) => {
+                let picked = picker.selected_id();
+                self.sessions = None;
+                if let Some(session_id) = picked {
+                    return InlineAction::SwitchSession(session_id);
+                }
+            }
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+                let picked = picker.selected_id();
+                self.sessions = None;
+                if let Some(session_id) = picked {
+                    return InlineAction::ForkSession(session_id);
+                }
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => match picker.selected_id() {
+                Some(session_id) if session_id == picker.current => {
+                    picker.notice = Some("The session in use can't be deleted".to_string());
+                }
+                Some(session_id) => {
+                    let title = picker.matching().get(picker.selected).map(|(session, _)| session.title.clone()).unwrap_or_default();
+                    let message = format!("Delete session \"{}\"?", title);
+                    self.confirm = Some(Confirmation::new(message, InlineAction::DeleteSession(session_id)));
+                }
+                None => {}
+            },
+            (KeyCode::Char('a'), KeyModifiers::CONTROL) => match picker.selected_id() {
+                Some(session_id) if session_id == picker.current => {
+                    picker.notice = Some("The session in use can't be archived".to_string());
+                }
+                Some(session_id) if picker.archived.contains(&session_id) => {
+                    picker.notice = Some("The session is archived already".to_string());
+                }
+                Some(session_id) => return InlineAction::ArchiveSession(session_id),
+                None => {}
+            },
+            (KeyCode::Up, _) if matching > 0 => picker.selected = (picker.selected + matching - 1) % matching,
+            (KeyCode::Down, _) | (KeyCode::Tab, _) if matching > 0 => picker.selected = (picker.selected + 1) % matching,
+            (KeyCode::Backspace, _) => {
+                let mut query = picker.query().to_string();
+                query.pop();
+                picker.set_query(query);
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                let query = format!("{}{}", picker.query(), c);
+                picker.set_query(query);
+            }
+            _ => {}
+        }
+        InlineAction::None
+    }
+
+    fn handle_recent_files_key(&mut self, key: KeyEvent) -> InlineAction {
+        let Some(picker) = self.recent_files.as_mut() else {
+            return InlineAction::None;
+        };
+        let matching = picker.matching().len();
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.recent_files = None,
+            (KeyCode::Enter, _) => {
+                let picked = picker.selected_path();
+                self.recent_files = None;
+                if let Some(path) = picked {
+                    return InlineAction::AttachFile(path);
+                }
+            }
+            (KeyCode::Tab, _) => {
+                let picked = picker.selected_path();
+                self.recent_files = None;
+                if let Some(path) = picked {
+                    self.insert(&path.display().to_string());
+                }
+            }
+            (KeyCode::Up, _) if matching > 0 => picker.selected = (picker.selected + matching - 1) % matching,
+            (KeyCode::Down, _) if matching > 0 => picker.selected = (picker.selected + 1) % matching,
+            (KeyCode::Backspace, _) => {
+                let mut query = picker.query().to_string();
+                query.pop();
+                picker.set_query(query);
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                let query = format!("{}{}", picker.query(), c);
+                picker.set_query(query);
+            }
+            _ => {}
+        }
+        InlineAction::None
+    }
+
+    /// Hold the prompt back with an offer to fence code pasted without one,
+    /// returning whether it was; slash commands are sent as typed
+    fn offer_fence(&mut self) -> bool {
+        if self.input.trim_start().starts_with('/') {
+            return false;
+        }
+        let filenames: Vec<&str> = self.documents.iter().map(|attached| attached.document.name.as_str()).collect();
+        let Some(unfenced) = fences::find_unfenced(&self.input, &filenames) else {
+            return false;
+        };
+        let (dialog, choice) = unfenced.offer();
+        self.fence_offer = Some(FenceOffer { unfenced, dialog, choice });
+        true
+    }
+
+    fn handle_fence_offer_key(&mut self, key: KeyEvent) -> InlineAction {
+        let Some(offer) = self.fence_offer.as_mut() else {
+            return InlineAction::None;
+        };
+        offer.dialog.handle_key(key);
+        let Ok(choice) = offer.choice.try_recv() else {
+            return InlineAction::None;
+        };
+        let Some(offer) = self.fence_offer.take() else {
+            return InlineAction::None;
+        };
+        match choice {
+            FenceChoice::Wrap => {
+                self.input = fences::wrap(&self.input, &offer.unfenced);
+                self.cursor = self.input.len();
+            }
+            FenceChoice::SendAsIs => {}
+            FenceChoice::KeepEditing => return InlineAction::None,
+        }
+        self.take_prompt().map_or(InlineAction::None, InlineAction::Submit)
+    }
+
+    fn handle_message_search_key(&mut self, key: KeyEvent) -> InlineAction {
+        let Some(search) = self.message_search.as_mut() else {
+            return InlineAction::None;
+        };
+        if (key.code, key.modifiers) == (KeyCode::Char('c'), KeyModifiers::CONTROL) {
+            self.message_search = None;
+            return InlineAction::None;
+        }
+        match search.handle_key(key) {
+            SearchAction::Run => InlineAction::SearchMessages(search.query().to_string(), search.scope()),
+            // `n` and `N` only move between the hits; Enter prints the message
+            SearchAction::Jump(hit) if key.code == KeyCode::Enter => InlineAction::ShowHit(hit),
+            SearchAction::Jump(_) | SearchAction::None => InlineAction::None,
+            SearchAction::Close => {
+                self.message_search = None;
+                InlineAction::None
+            }
+        }
+    }
+
+    /// Draw the search bar with the hits under it over the whole viewport
+    fn render_message_search(search: &MessageSearch, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        search.render(frame, Rect::new(area.x, area.y, area.width, 1), theme);
+
+        // Scroll the hits so the current one stays in view
+        let rows = (area.height - 1) as usize;
+        let current = search.hits().iter().position(|hit| Some(hit) == search.current_hit()).unwrap_or(0);
+        let first = (current + 1).saturating_sub(rows);
+        let terms = search.terms();
+        let lines: Vec<Line> = search
+            .hits()
+            .iter()
+            .skip(first)
+            .take(rows)
+            .map(|hit| {
+                let style = if Some(hit) == search.current_hit() {
+                    Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(theme.fg_base)
+                };
+                let mut spans = Vec::new();
+                if search.scope() == SearchScope::AllSessions {
+                    let session: String = hit.session_id.chars().take(8).collect();
+                    spans.push(Span::styled(format!("{} ", session), Style::default().fg(theme.fg_muted)));
+                }
+                spans.push(Span::styled(format!("{:<7}", role_label(&hit.role)), Style::default().fg(theme.fg_muted)));
+                spans.extend(highlighted(&hit.snippet, &terms, style, theme));
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), Rect::new(area.x, area.y + 1, area.width, area.height - 1));
+    }
+
+    fn insert(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.input.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    fn render(&mut self, frame: &mut Frame, theme: &Theme) {
+        {
+            let _span = profiler::span("viewport");
+            self.render_viewport(frame, theme);
+        }
+        if profiler::is_enabled() {
+            render_profiler_overlay(frame, theme);
+        }
+    }
+
+    fn render_viewport(&mut self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.size();
+        if !MIN_SIZE.fits(area) {
+            responsive::render_too_small(frame, area, MIN_SIZE, theme);
+            return;
+        }
+        let tier = layout_tier(area.width);
+
+        if let Some(approval) = &self.approval {
+            approval.render_compact(frame, area, theme);
+            return;
+        }
+        if let Some(confirm) = &self.confirm {
+            render_question(&confirm.message, &confirm.dialog, frame, theme);
+            return;
+        }
+        if let Some(offer) = &self.fence_offer {
+            render_question(&offer.unfenced.question(), &offer.dialog, frame, theme);
+            return;
+        }
+        if let Some(picker) = &self.picker {
+            picker.render(frame, theme);
+            return;
+        }
+        if let Some(sessions) = &self.sessions {
+            sessions.render(frame, theme);
+            return;
+        }
+        if let Some(recent_files) = &self.recent_files {
+            recent_files.render(frame, theme);
+            return;
+        }
+        if let Some(search) = &self.message_search {
+            Self::render_message_search(search, frame, theme);
+            return;
+        }
+        if let Some(preview) = &self.preview {
+            self.render_preview(preview, frame, theme);
+            return;
+        }
+        if let Some(table) = self.table.as_mut().filter(|_| self.table_open) {
+            Self::render_table(table, frame, theme);
+            return;
+        }
+        if let Some(first) = self.help {
+            self.render_help(first, frame, theme);
+            return;
+        }
+
+        let status = if let Some(search) = &self.search {
+            let mut spans = vec![
+                Span::styled("Search history: ", Style::default().fg(theme.fg_muted)),
+                Span::styled(search.query.clone(), Style::default().fg(theme.info)),
+            ];
+            if search.found.is_none() && !search.query.is_empty() {
+                spans.push(Span::styled(" (no match)", Style::default().fg(theme.fg_muted)));
+            }
+            spans.push(Span::styled(" · Ctrl+R older · Enter to keep · Esc to cancel", Style::default().fg(theme.fg_muted)));
+            Line::from(spans)
+        } else if let Some((command, started)) = &self.running_command {
+            let tick = started.elapsed().as_millis() as usize / 80;
+            Line::from(vec![
+                Span::styled(format!("{} Running ", SPINNER[tick % SPINNER.len()]), Style::default().fg(theme.info)),
+                Span::styled(command.lines().next().unwrap_or_default().to_string(), Style::default().fg(theme.fg_base)),
+                Span::styled(format!(" {}s · Ctrl+C to cancel", started.elapsed().as_secs()), Style::default().fg(theme.fg_muted)),
+            ])
+        } else if let Some(started) = self.waiting_since {
+            let tick = started.elapsed().as_millis() as usize / 80;
+            let doing = if self.streamed.as_ref().is_some_and(|streamed| !streamed.is_empty()) { "Writing" } else { "Thinking" };
+            Line::from(Span::styled(
+                format!("{} {}… {}s", SPINNER[tick % SPINNER.len()], doing, started.elapsed().as_secs()),
+                Style::default().fg(theme.info),
+            ))
+        } else if let Some(editing) = &self.editing {
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        "Editing prompt {} of {}{}",
+                        editing.index + 1,
+                        editing.prompts.len(),
+                        if editing.branch { " in a new branch" } else { "" }
+                    ),
+                    Style::default().fg(theme.info),
+                ),
+                Span::styled(
+                    format!(
+                        " · ↑/↓ other prompts · Ctrl+B to {} · Enter to resend · Esc to cancel",
+                        if editing.branch { "stay" } else { "branch off" }
+                    ),
+                    Style::default().fg(theme.fg_muted),
+                ),
+            ])
+        } else if !self.typed_commands().is_empty() {
+            let commands = self.typed_commands();
+            let selected = self.completing.as_ref().map_or(0, |(_, index)| *index);
+            let mut spans = vec![Span::styled("Tab ", Style::default().fg(theme.fg_muted))];
+            for (index, command) in commands.iter().enumerate() {
+                let style = if self.completing.is_some() && index == selected {
+                    Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(theme.info)
+                };
+                spans.push(Span::styled(format!("/{}", command.name), style));
+                spans.push(Span::raw(" "));
+            }
+            if let Some(command) = commands.get(selected) {
+                spans.push(Span::styled(format!("· {}", command.description), Style::default().fg(theme.fg_muted)));
+            }
+            Line::from(spans)
+        } else if let Some(toast) = self.toasts.current() {
+            toast.line(theme)
+        } else if let Some(error) = &self.error {
+            Line::from(Span::styled(error.clone(), Style::default().fg(theme.error)))
+        } else if !self.suggestions.is_empty() {
+            let mut spans = vec![Span::styled("Tab ", Style::default().fg(theme.fg_muted))];
+            for (index, suggestion) in self.suggestions.iter().enumerate() {
+                let style = if self.selected == Some(index) {
+                    Style::default().fg(theme.info).add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(theme.info)
+                };
+                spans.push(Span::styled(format!(" {} ", suggestion), style));
+                spans.push(Span::raw(" "));
+            }
+            Line::from(spans)
+        } else {
+            let hint = match tier {
+                LayoutTier::Full => {
+                    "Enter to ask · Shift+Enter for a new line · Ctrl+R to search history · Ctrl+S for sessions · Esc to quit"
+                }
+                LayoutTier::Compact => "Enter to ask · Ctrl+R history · Ctrl+S sessions · Esc to quit",
+                LayoutTier::Minimal => "Enter to ask · Esc to quit",
+            };
+            Line::from(Span::styled(hint, Style::default().fg(theme.fg_muted)))
+        };
+        let status = match self.queued.front() {
+            Some(next) => {
+                let next: String = next.lines().next().unwrap_or_default().chars().take(30).collect();
+                let hint = if self.queue_paused { "Enter to send" } else { "Ctrl+X to take back" };
+                let mut spans = vec![Span::styled(
+                    format!("{} queued, next \"{}\" · {} · ", self.queued.len(), next, hint),
+                    Style::default().fg(theme.info),
+                )];
+                spans.extend(status.spans);
+                Line::from(spans)
+            }
+            None => status,
+        };
+        let status = if self.documents.is_empty() {
+            status
+        } else {
+            let names: Vec<&str> = self.documents.iter().map(|attached| attached.document.name.as_str()).collect();
+            let mut spans = vec![Span::styled(
+                format!("{} {} · Ctrl+O to preview · ", theme.icons.file, names.join(", ")),
+                Style::default().fg(theme.info),
+            )];
+            spans.extend(status.spans);
+            Line::from(spans)
+        };
+        let status = if self.images.is_empty() {
+            status
+        } else {
+            let names: Vec<&str> = self.images.iter().map(|image| image.name.as_str()).collect();
+            let mut spans = vec![Span::styled(
+                format!("{} {} · ", theme.icons.image, names.join(", ")),
+                Style::default().fg(theme.info),
+            )];
+            spans.extend(status.spans);
+            Line::from(spans)
+        };
+        let status = match self.macros.recording() {
+            Some(register) => {
+                let mut spans = vec![Span::styled(
+                    format!("● Recording macro {} · ", register),
+                    Style::default().fg(theme.error),
+                )];
+                spans.extend(status.spans);
+                Line::from(spans)
+            }
+            None => status,
+        };
+        let status = if self.running_jobs > 0 {
+            let label = format!(
+                "{} {} job{} running · ",
+                theme.icons.loading,
+                self.running_jobs,
+                if self.running_jobs == 1 { "" } else { "s" }
+            );
+            let mut spans = vec![Span::styled(label, Style::default().fg(theme.warning))];
+            spans.extend(status.spans);
+            Line::from(spans)
+        } else {
+            status
+        };
+        // The request's size goes on the right while nothing is under way
+        let mut status_width = area.width;
+        let idle = self.search.is_none() && self.running_command.is_none() && self.waiting_since.is_none();
+        if let Some(prompt_tokens) = self.prompt_tokens.as_ref().filter(|_| idle) {
+            let attached = self.documents.iter().map(|attached| attached.tokens).sum();
+            let label = prompt_tokens.label(&self.input, attached);
+            let width = label.len() as u16;
+            if width + 20 < area.width {
+                status_width = area.width - width - 1;
+                frame.render_widget(
+                    Paragraph::new(Span::styled(label, Style::default().fg(theme.fg_muted))),
+                    Rect::new(area.x + status_width + 1, area.y, width, 1),
+                );
+            }
+        }
+        frame.render_widget(Paragraph::new(status), Rect::new(area.x, area.y, status_width, 1));
+
+        // Narrow terminals leave the status bar out, for the input's sake
+        let bar_rows = if tier == LayoutTier::Minimal { 0 } else { 1 };
+        if bar_rows > 0 {
+            let context_percent = self.prompt_tokens.as_ref().and_then(PromptTokens::context_percent);
+            let _status_bar = profiler::span("status bar");
+            frame.render_widget(
+                Paragraph::new(self.status_bar.line(theme, area.width, context_percent)),
+                Rect::new(area.x, area.y + area.height - 1, area.width, 1),
+            );
+        }
+
+        // The draft's preview takes the right half of the input's rows, in
+        // terminals wide enough for two columns
+        let previewing = self.draft_preview.is_some() && tier != LayoutTier::Minimal;
+        let input_width = if previewing { area.width / 2 } else { area.width };
+
+        // An answer being written gets the rows the input leaves, following
+        // its latest lines unless scrolled up
+        let (rows, (cursor_row, cursor_col)) = wrap_input(&self.input, self.cursor, input_width);
+        let free = area.height - 1 - bar_rows;
+        let streamed_height = match &self.streamed {
+            Some(streamed) if !streamed.is_empty() => free.saturating_sub(rows.len() as u16),
+            _ => 0,
+        };
+        if let Some(streamed) = self.streamed.as_ref().filter(|_| streamed_height > 0) {
+            let _streamed = profiler::span("streamed answer");
+            let lines = streamed.lines();
+            self.streamed_scroll.update(lines.len(), streamed_height as usize);
+            let shown: Vec<Line> = lines.iter().skip(self.streamed_scroll.top_line()).take(streamed_height as usize).cloned().collect();
+            let streamed_area = Rect::new(area.x, area.y + 1, area.width, streamed_height);
+            frame.render_widget(Paragraph::new(shown), streamed_area);
+
+            let unread = self.streamed_scroll.unread_lines();
+            if unread > 0 {
+                let pill = format!(" {} new line{} ↓ Ctrl+End ", unread, if unread == 1 { "" } else { "s" });
+                let width = (pill.chars().count() as u16).min(area.width);
+                let pill_area = Rect::new(area.x + area.width - width, streamed_area.bottom() - 1, width, 1);
+                frame.render_widget(Clear, pill_area);
+                frame.render_widget(
+                    Paragraph::new(Span::styled(pill, Style::default().fg(theme.bg_base).bg(theme.info))),
+                    pill_area,
+                );
+            }
+        }
+
+        // Keep the cursor's row in view when the input outgrows the viewport
+        let _input = profiler::span("input");
+        let input_area = Rect::new(area.x, area.y + 1 + streamed_height, input_width, free - streamed_height);
+        if let Some(preview) = self.draft_preview.as_mut().filter(|_| previewing) {
+            let preview_area = Rect::new(input_area.right(), input_area.y, area.width - input_width, input_area.height);
+            let block = Block::default().borders(Borders::LEFT).border_style(Style::default().fg(theme.fg_subtle));
+            let inner = block.inner(preview_area);
+            frame.render_widget(block, preview_area);
+            preview.refresh(&self.input, inner.width, theme, Instant::now());
+            // The end of the draft, where it is being written, stays in view
+            let first = preview.lines.len().saturating_sub(inner.height as usize);
+            let lines: Vec<Line> = if self.input.is_empty() {
+                vec![Line::from(Span::styled("Markdown preview · Alt+M to hide", Style::default().fg(theme.fg_muted)))]
+            } else {
+                preview.lines[first..].to_vec()
+            };
+            frame.render_widget(Paragraph::new(lines), inner);
+        }
+        let first = (cursor_row + 1).saturating_sub(input_area.height as usize);
+        let lines: Vec<Line> = rows
+            .into_iter()
+            .enumerate()
+            .skip(first)
+            .map(|(row, text)| {
+                let prefix = if row == 0 { PROMPT } else { "  " };
+                Line::from(vec![
+                    Span::styled(prefix, Style::default().fg(theme.tertiary).add_modifier(Modifier::BOLD)),
+                    Span::styled(text, Style::default().fg(theme.fg_base)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), input_area);
+        if self.waiting_since.is_none() {
+            frame.set_cursor(
+                input_area.x + PROMPT_WIDTH + cursor_col,
+                input_area.y + (cursor_row - first) as u16,
+            );
+        }
+    }
+}
+
+/// How much of the chat fits; the viewport is as tall as asked for, so only
+/// the terminal's width decides
+fn layout_tier(width: u16) -> LayoutTier {
+    if width >= LayoutTier::FULL.width {
+        LayoutTier::Full
+    } else if width >= LayoutTier::COMPACT.width {
+        LayoutTier::Compact
+    } else {
+        LayoutTier::Minimal
+    }
+}
+
+/// The profiler's timings in the top right corner of the viewport, cut to
+/// its height
+fn render_profiler_overlay(frame: &mut Frame, theme: &Theme) {
+    let lines = profiler::overlay_lines(theme);
+    let width = lines.iter().map(|line| line.width() as u16).max().unwrap_or(0) + 2;
+    let screen = frame.size();
+    let area = Rect::new(
+        screen.x + screen.width.saturating_sub(width),
+        screen.y,
+        width.min(screen.width),
+        (lines.len() as u16 + 2).min(screen.height),
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Profiler (F12)")
+        .style(Style::default().fg(theme.fg_base));
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Latest prompt containing the query, ignoring case
+fn find_prompt(prompts: &[String], query: &str) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+    let query = query.to_lowercase();
+    prompts.iter().rposition(|prompt| prompt.to_lowercase().contains(&query))
+}
+
+/// Split the input into rows that fit after the prompt, and find the cursor in them
+fn wrap_input(input: &str, cursor: usize, width: u16) -> (Vec<String>, (usize, u16)) {
+    let width = width.saturating_sub(PROMPT_WIDTH).max(1) as usize;
+    let mut rows = vec![String::new()];
+    let mut row_width = 0;
+    let mut cursor_at = None;
+
+    for (offset, c) in input.char_indices() {
+        if offset == cursor {
+            cursor_at = Some((rows.len() - 1, row_width as u16));
+        }
+        if c == '\n' {
+            rows.push(String::new());
+            row_width = 0;
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width {
+            rows.push(String::new());
+            row_width = 0;
+            if offset == cursor {
+                cursor_at = Some((rows.len() - 1, 0));
+            }
+        }
+        if let Some(row) = rows.last_mut() {
+            row.push(c);
+        }
+        row_width += char_width;
+    }
+
+    let cursor_at = cursor_at.unwrap_or_else(|| {
+        if row_width >= width {
+            rows.push(String::new());
+            (rows.len() - 1, 0)
+        } else {
+            (rows.len() - 1, row_width as u16)
+        }
+    });
+    (rows, cursor_at)
+}
+
+/// Chat with the agent in a viewport of `height` lines below the prompt,
+/// recalling and recording prompts in `history`
+///
+/// `/env` commands change the session environment of the app's tools and
+/// are neither recorded nor sent. `agent_events` are the events of the
+/// conversation's agent, which carry the output of running commands. The
+/// app's activity tracker hears about input and focus, and suspends
+/// background work while the user is away. `/model` commands switch the
+/// models of the app's conversations, Ctrl+S continues its other sessions,
+/// and `commands` are expanded into the prompts they stand for. Tool calls
+/// arriving on `prompts` are asked about before they run. A `recorder` gets
+/// what is drawn and the agent's events.
+pub async fn run(
+    app: &App,
+    conversation: Arc<Conversation>,
+    height: u16,
+    history: PromptHistory,
+    agent_events: mpsc::UnboundedReceiver<AppEvent>,
+    prompts: mpsc::UnboundedReceiver<PermissionRequest>,
+    commands: CustomCommands,
+    recorder: Option<Recorder>,
+) -> Result<()> {
+    let height = height.max(MIN_HEIGHT);
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(Recorded::new(io::stdout(), recorder.clone())),
+        TerminalOptions {
+            viewport: Viewport::Inline(height),
+        },
+    )?;
+    enable_raw_mode()?;
+    // Terminals without focus reporting ignore this
+    let _ = execute!(io::stdout(), EnableFocusChange);
+
+    let result = run_loop(
+        &mut terminal,
+        app,
+        conversation,
+        height,
+        history,
+        agent_events,
+        prompts,
+        &app.model_switcher(),
+        &commands,
+        recorder.as_ref(),
+    )
+    .await;
+
+    // Leave the shell prompt right below the last answer
+    terminal.clear()?;
+    let _ = execute!(io::stdout(), DisableFocusChange);
+    disable_raw_mode()?;
+    terminal.show_cursor()?;
+    result
+}
+
+async fn run_loop(
+    terminal: &mut InlineTerminal,
+    app: &App,
+    mut conversation: Arc<Conversation>,
+    height: u16,
+    history: PromptHistory,
+    mut agent_events: mpsc::UnboundedReceiver<AppEvent>,
+    mut prompts: mpsc::UnboundedReceiver<PermissionRequest>,
+    switcher: &ModelSwitcher,
+    commands: &CustomCommands,
+    recorder: Option<&Recorder>,
+) -> Result<()> {
+    let (tools, activity) = (app.tool_manager(), app.activity());
+    let notifier = app.notifier();
+    let theme = themes::current_theme();
+    let mut chat = InlineChat {
+        commands: SlashCommand::all(commands),
+        ..Default::default()
+    };
+    chat.status_bar.approval = app.approval_mode();
+    if let Some(path) = macros::store_path() {
+        chat.key_map = KeyMap::load(&path)?;
+        match macros::load_stored(&path) {
+            Ok(stored) => {
+                for problem in chat.macros.load(&stored) {
+                    tracing::warn!("Ignoring {}", problem);
+                }
+            }
+            Err(e) => tracing::warn!("Could not load macros from {}: {}", path.display(), e),
+        }
+        chat.macro_store = Some(path);
+    }
+    match history.prompts() {
+        Ok(prompts) => chat.history = prompts,
+        Err(e) => tracing::warn!("Prompt history not loaded: {:#}", e),
+    }
+    let mut events = EventHandler::new();
+    activity.register(events.ticks());
+    let mut reply: Option<JoinHandle<Result<ProviderResponse>>> = None;
+    let mut suggestions: Option<JoinHandle<Result<Vec<String>>>> = None;
+    let mut git: Option<JoinHandle<Option<GitState>>> = Some(tokio::spawn(git_state()));
+    let mut listing: Option<JoinHandle<Result<Vec<ServedModel>>>> = None;
+    let mut recalling: Option<JoinHandle<Result<Vec<(Session, String, bool)>>>> = None;
+
+    chat.postprocessor = Postprocessor::for_directory(app.postprocess_config(), &std::env::current_dir()?).await;
+
+    // A resumed session shows what was said before
+    print_conversation(terminal, height, &conversation, &chat.postprocessor, &theme).await?;
+    chat.prompt_tokens = Some(PromptTokens::of(&conversation).await);
+
+    loop {
+        activity.set_busy(reply.is_some() || chat.running_command.is_some(), Instant::now());
+        chat.running_jobs = tools.jobs().running();
+        chat.toasts.update(Instant::now());
+        chat.status_bar.model = Some(conversation.model());
+        if let Some(approval) = chat.next_approval() {
+            print_above(terminal, height, approval.transcript(&theme))?;
+        }
+        profiler::begin_frame();
+        terminal.draw(|frame| chat.render(frame, &theme))?;
+        profiler::end_frame();
+        if let Some(recorder) = recorder {
+            let size = terminal.size()?;
+            recorder.resize(size.width, size.height);
+        }
+
+        if reply.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(handle) = reply.take() {
+                // What the agent did on the way goes above the answer
+                while let Ok(event) = agent_events.try_recv() {
+                    follow_agent_event(terminal, height, &mut chat, event, recorder, &mut git, &theme)?;
+                }
+                // The answer is printed in full instead
+                chat.streamed = None;
+                let elapsed = chat.waiting_since.take().map(|started| started.elapsed());
+                let answered = handle.await?;
+                if let Some(elapsed) = elapsed {
+                    announce(terminal, &notifier, &mut chat, answered.is_ok(), elapsed, activity.is_focused());
+                }
+                match answered {
+                    Ok(response) => {
+                        print_answer(terminal, height, &response.content, &chat.postprocessor, &theme)?;
+                        // Named once, after the first answer; later calls return early
+                        let titled = conversation.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = titled.generate_title().await {
+                                tracing::debug!("Session not named: {}", e);
+                            }
+                        });
+                        let conversation = conversation.clone();
+                        suggestions = Some(tokio::spawn(async move { conversation.suggest_follow_ups().await }));
+                        if git.is_none() {
+                            git = Some(tokio::spawn(git_state()));
+                        }
+                    }
+                    Err(e) => {
+                        chat.error = Some(format!("Error: {}", e));
+                        chat.queue_paused = !chat.queued.is_empty();
+                    }
+                }
+                chat.prompt_tokens = Some(PromptTokens::of(&conversation).await);
+                continue;
+            }
+        }
+
+        if suggestions.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(handle) = suggestions.take() {
+                match handle.await? {
+                    Ok(found) => chat.suggestions = found,
+                    Err(e) => tracing::debug!("No follow-up suggestions: {}", e),
+                }
+                continue;
+            }
+        }
+
+        if git.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(handle) = git.take() {
+                chat.status_bar.git = handle.await?;
+                continue;
+            }
+        }
+
+        if recalling.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(handle) = recalling.take() {
+                match handle.await? {
+                    Ok(found) if found.is_empty() => chat.error = Some("No past session matches".to_string()),
+                    Ok(found) => chat.sessions = Some(SessionPicker::recalled(found, &conversation.session_id)),
+                    Err(e) => chat.error = Some(format!("Error: {:#}", e)),
+                }
+                continue;
+            }
+        }
+
+        if listing.as_ref().is_some_and(JoinHandle::is_finished) {
+            if let Some(handle) = listing.take() {
+                let listed = handle.await?;
+                if let Some(picker) = chat.picker.as_mut() {
+                    match listed {
+                        Ok(served) => picker.add_served(served),
+                        Err(e) => picker.note = Some(format!("Models not listed: {}", e)),
+                    }
+                }
+                continue;
+            }
+        }
+
+        // Queued prompts go out one at a time, once the answer before them is in
+        let action = match chat.next_queued() {
+            Some(question) => InlineAction::Submit(question),
+            None if !chat.replaying.is_empty() => match chat.next_replayed() {
+                Some(key) => chat.handle_typed_key(key),
+                None => InlineAction::None,
+            },
+            None => {
+                let event = tokio::select! {
+                    event = events.next() => event,
+                    Some(event) = agent_events.recv() => {
+                        follow_agent_event(terminal, height, &mut chat, event, recorder, &mut git, &theme)?;
+                        continue;
+                    }
+                    Some(request) = prompts.recv() => {
+                        chat.approvals.push_back(request);
+                        continue;
+                    }
+                };
+                let now = Instant::now();
+                match &event {
+                    Some(Event::Custom(kind, _)) if kind == "focus_lost" => activity.set_focused(false, now),
+                    Some(Event::Custom(kind, _)) if kind == "focus_gained" => activity.set_focused(true, now),
+                    Some(event) if event.priority() == Priority::Input => {
+                        activity.record_input(now);
+                        profiler::record_input(now);
+                    }
+                    _ => {}
+                }
+                match event {
+                    Some(Event::Key(key)) => {
+                        let action = chat.handle_key(key);
+                        profiler::record_event("key", now);
+                        action
+                    }
+                    Some(Event::Custom(kind, serde_json::Value::String(text))) if kind == "paste" => {
+                        match dropped_document(&text) {
+                            Some(path) => match DocumentAttachment::from_path(&path, DocumentLimits::default()).await {
+                                Ok(document) => chat.attach(document),
+                                Err(e) => chat.error = Some(format!("Error: {:#}", e)),
+                            },
+                            None => chat.insert(&text),
+                        }
+                        continue;
+                    }
+                    Some(Event::Resize(width, _)) => {
+                        terminal.autoresize()?;
+                        if let Some(streamed) = chat.streamed.as_mut() {
+                            streamed.layout(&theme, width);
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                }
+            }
+        };
+        // `/retry` sends the latest prompt again
+        let action = match action {
+            InlineAction::Submit(question) if question.trim() == RETRY_COMMAND => chat.retry(conversation.prompts().await),
+            action => action,
+        };
+        match action {
+            InlineAction::Quit => break,
+            InlineAction::CancelCommand => tools.cancel_commands(),
+            InlineAction::OpenRecentFiles => {
+                let root = std::env::current_dir().ok();
+                chat.recent_files = Some(FilePicker::new(app.file_tracker().recent(), root.as_deref()));
+            }
+            InlineAction::AttachFile(path) => match tokio::fs::read_to_string(&path).await {
+                Ok(text) => {
+                    let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                    chat.attach(DocumentAttachment::from_text(name, &text, DocumentLimits::default()));
+                }
+                Err(e) => chat.error = Some(format!("Error: {} not attached: {}", path.display(), e)),
+            },
+            InlineAction::OpenSessions => match app.session_manager().list_sessions(Some(SESSIONS_LISTED)).await {
+                Ok(sessions) => chat.sessions = Some(SessionPicker::new(sessions, &conversation.session_id)),
+                Err(e) => chat.error = Some(format!("Error: {}", e)),
+            },
+            InlineAction::SearchMessages(query, scope) => {
+                let session_id = match scope {
+                    SearchScope::Session => Some(conversation.session_id.as_str()),
+                    SearchScope::AllSessions => None,
+                };
+                let results = app.session_manager().search_messages(&query, session_id).await;
+                if let Some(search) = chat.message_search.as_mut() {
+                    search.set_results(results);
+                }
+            }
+            InlineAction::ShowHit(hit) if hit.session_id == conversation.session_id => {
+                let terms = chat.message_search.as_ref().map(MessageSearch::terms).unwrap_or_default();
+                print_hit(terminal, height, &conversation, &hit, &terms, &theme).await?;
+            }
+            InlineAction::SwitchSession(_) | InlineAction::ForkSession(_) | InlineAction::ShowHit(_) if reply.is_some() => {
+                chat.error = Some("Wait for the answer before switching sessions".to_string());
+            }
+            InlineAction::SwitchSession(ref session_id)
+            | InlineAction::ForkSession(ref session_id)
+            | InlineAction::ShowHit(MessageHit { ref session_id, .. }) => {
+                let fork = matches!(action, InlineAction::ForkSession(_));
+                if let Some(handle) = suggestions.take() {
+                    handle.abort();
+                }
+                match open_session(terminal, height, app, switcher, session_id, fork, &chat.postprocessor, &theme).await {
+                    Ok((opened, events)) => {
+                        conversation = opened;
+                        agent_events = events;
+                        chat.suggestions.clear();
+                        chat.selected = None;
+                        chat.error = None;
+                        chat.prompt_tokens = Some(PromptTokens::of(&conversation).await);
+                        // A hit in another session is printed after what was said in it
+                        if let InlineAction::ShowHit(hit) = &action {
+                            let terms = chat.message_search.as_ref().map(MessageSearch::terms).unwrap_or_default();
+                            print_hit(terminal, height, &conversation, hit, &terms, &theme).await?;
+                        }
+                    }
+                    Err(e) => chat.error = Some(format!("Error: {}", e)),
+                }
+            }
+            InlineAction::ArchiveSession(session_id) => match app.session_manager().archive_session(&session_id).await {
+                Ok(_) => {
+                    if let Some(sessions) = chat.sessions.as_mut() {
+                        sessions.remove(&session_id, "Session archived");
+                    }
+                }
+                Err(e) => {
+                    chat.sessions = None;
+                    chat.error = Some(format!("Error: {}", e));
+                }
+            },
+            InlineAction::DeleteSession(session_id) => match app.session_manager().delete_session(&session_id).await {
+                Ok(()) => {
+                    if let Some(sessions) = chat.sessions.as_mut() {
+                        sessions.remove(&session_id, "Session deleted");
+                    }
+                }
+                Err(e) => {
+                    chat.sessions = None;
+                    chat.error = Some(format!("Error: {}", e));
+                }
+            },
+            // Values set here may be secrets, so they stay out of the
+            // history and the scrollback
+            InlineAction::Submit(question) if env_command(&question).is_some() => {
+                chat.waiting_since = None;
+                let args = env_command(&question).unwrap_or_default();
+                match tools.session_env().run_command(args) {
+                    Ok(output) => {
+                        let mut lines: Vec<Line<'static>> =
+                            output.lines().map(|line| Line::from(Span::styled(line.to_string(), Style::default().fg(theme.fg_muted)))).collect();
+                        lines.push(Line::from(""));
+                        print_above(terminal, height, lines)?;
+                    }
+                    Err(e) => chat.error = Some(format!("Error: {}", e)),
+                }
+            }
+            InlineAction::Submit(question) if command_args(&question, RECALL_COMMAND).is_some() => {
+                chat.waiting_since = None;
+                match command_args(&question, RECALL_COMMAND).unwrap_or_default() {
+                    "" => chat.error = Some("Usage: /recall <what the session was about>".to_string()),
+                    query => {
+                        let sessions = app.session_manager().clone();
+                        let query = query.to_string();
+                        chat.toasts.push("Searching past sessions…".to_string(), ToastKind::Info);
+                        recalling = Some(tokio::spawn(async move {
+                            let mut found = Vec::new();
+                            for matched in sessions.semantic_search(&query, SESSIONS_RECALLED).await? {
+                                if let Some(session) = sessions.get_session(&matched.session_id).await? {
+                                    found.push((session, matched.snippet, false));
+                                } else if let Some(archived) = sessions.get_archived_session(&matched.session_id).await? {
+                                    // Listed as it was when archived, until it is restored
+                                    let session = Session {
+                                        id: archived.id,
+                                        updated_at: archived.updated_at,
+                                        message_count: archived.message_count,
+                                        ..Session::new(archived.title, None)
+                                    };
+                                    found.push((session, matched.snippet, true));
+                                }
+                            }
+                            Ok(found)
+                        }));
+                    }
+                }
+            }
+            InlineAction::Submit(question) if command_args(&question, DIFF_COMMAND).is_some() => {
+                chat.waiting_since = None;
+                let ids: Vec<&str> = command_args(&question, DIFF_COMMAND).unwrap_or_default().split_whitespace().collect();
+                match ids.as_slice() {
+                    [] => match app.session_manager().list_sessions(Some(SESSIONS_LISTED)).await {
+                        Ok(sessions) => chat.sessions = Some(SessionPicker::comparing(sessions, &conversation.session_id)),
+                        Err(e) => chat.error = Some(format!("Error: {}", e)),
+                    },
+                    [first, second] => {
+                        if let Err(e) = print_comparison(terminal, height, app, first, second, &theme).await {
+                            chat.error = Some(format!("Error: {}", e));
+                        }
+                    }
+                    _ => chat.error = Some("Usage: /diff [<first session> <second session>]".to_string()),
+                }
+            }
+            InlineAction::PickComparison => match app.session_manager().list_sessions(Some(SESSIONS_LISTED)).await {
+                Ok(sessions) => chat.sessions = Some(SessionPicker::comparing(sessions, &conversation.session_id)),
+                Err(e) => chat.error = Some(format!("Error: {}", e)),
+            },
+            InlineAction::CompareSessions(first, second) => {
+                if let Err(e) = print_comparison(terminal, height, app, &first, &second, &theme).await {
+                    chat.error = Some(format!("Error: {}", e));
+                }
+            }
+            InlineAction::Submit(question) if command_args(&question, SCREENSHOT_COMMAND).is_some() => {
+                chat.waiting_since = None;
+                if is_ssh_session() && !app.screenshots_over_ssh() {
+                    chat.error = Some("Screenshots are disabled over SSH".to_string());
+                    continue;
+                }
+                let target = match command_args(&question, SCREENSHOT_COMMAND).unwrap_or_default() {
+                    "" => CaptureTarget::Screen { monitor: None },
+                    window => CaptureTarget::Window { title: window.to_string() },
+                };
+                match take_screenshot(target, &std::env::current_dir()?).await {
+                    Ok(screenshot) => {
+                        let name = screenshot
+                            .path
+                            .file_name()
+                            .map_or_else(|| "screenshot.png".to_string(), |name| name.to_string_lossy().into_owned());
+                        let note = format!(
+                            "{} {}x{} screenshot saved to {}, sent with the next prompt",
+                            theme.icons.image,
+                            screenshot.width,
+                            screenshot.height,
+                            screenshot.path.display()
+                        );
+                        print_above(terminal, height, vec![Line::from(Span::styled(note, Style::default().fg(theme.fg_muted)))])?;
+                        chat.images.push(ImageAttachment::new(name, screenshot.attachment));
+                    }
+                    Err(e) => chat.error = Some(format!("Screenshot failed: {:#}", e)),
+                }
+            }
+            InlineAction::Submit(question) if model_command(&question).is_some() => {
+                chat.waiting_since = None;
+                match model_command(&question).unwrap_or_default() {
+                    "" => {
+                        let mut picker = ModelPicker::new(switcher);
+                        // Aggregators list more models than the catalog knows
+                        if switcher.lists_models() {
+                            picker.note = Some("Listing the server's models…".to_string());
+                            if listing.is_none() {
+                                listing = Some(tokio::spawn(switcher.listing()));
+                            }
+                        }
+                        chat.picker = Some(picker);
+                    }
+                    args => {
+                        switch_model(terminal, height, &mut chat, switcher, &conversation, args, &theme)?;
+                        chat.prompt_tokens = Some(PromptTokens::of(&conversation).await);
+                    }
+                }
+            }
+            InlineAction::SwitchModel(args) => {
+                switch_model(terminal, height, &mut chat, switcher, &conversation, &args, &theme)?;
+                chat.prompt_tokens = Some(PromptTokens::of(&conversation).await);
+            }
+            InlineAction::Submit(question) if question.trim() == CONTINUE_COMMAND => {
+                if conversation.is_interrupted().await {
+                    if let Some(handle) = suggestions.take() {
+                        handle.abort();
+                    }
+                    print_question(terminal, height, &question, &theme)?;
+                    let conversation = conversation.clone();
+                    reply = Some(tokio::spawn(async move { conversation.continue_interrupted().await }));
+                } else {
+                    chat.waiting_since = None;
+                    chat.error = Some("No interrupted answer to continue".to_string());
+                }
+            }
+            InlineAction::Submit(question) if question.trim() == EDIT_COMMAND => {
+                chat.waiting_since = None;
+                chat.start_editing(conversation.prompts().await);
+            }
+            InlineAction::Edit { message_id, prompt, branch } => {
+                if let Some(handle) = suggestions.take() {
+                    handle.abort();
+                }
+                match rewind_to(terminal, height, &conversation, &message_id, branch, &theme).await {
+                    Ok(branched) => {
+                        if let Some((branch, events)) = branched {
+                            conversation = branch;
+                            agent_events = events;
+                        }
+                        if let Err(e) = history.record(&prompt, PromptSource::Tui) {
+                            tracing::warn!("Prompt not added to the history: {:#}", e);
+                        }
+                        print_question(terminal, height, &prompt, &theme)?;
+                        let conversation = conversation.clone();
+                        reply = Some(tokio::spawn(async move { conversation.send_message(prompt).await }));
+                    }
+                    Err(e) => {
+                        chat.waiting_since = None;
+                        chat.error = Some(format!("Error: {}", e));
+                    }
+                }
+            }
+            InlineAction::Submit(question) => {
+                if let Some(handle) = suggestions.take() {
+                    handle.abort();
+                }
+                if let Err(e) = history.record(&question, PromptSource::Tui) {
+                    tracing::warn!("Prompt not added to the history: {:#}", e);
+                }
+                print_question(terminal, height, &question, &theme)?;
+                let attached = chat.take_documents();
+                if !attached.is_empty() {
+                    let names: Vec<&str> = attached.iter().map(|document| document.name.as_str()).collect();
+                    let note = format!("{} {}", theme.icons.file, names.join(", "));
+                    print_above(terminal, height, vec![Line::from(Span::styled(note, Style::default().fg(theme.fg_muted)))])?;
+                }
+                let prompt = commands.expand(&question).unwrap_or(question);
+                let prompt = documents::with_documents(&prompt, &attached);
+                let images = chat.take_images();
+                let conversation = conversation.clone();
+                reply = Some(tokio::spawn(async move { conversation.send_message_with_images(prompt, images).await }));
+            }
+            InlineAction::None => {}
+        }
+    }
+
+    if let Some(handle) = reply {
+        handle.abort();
+    }
+    Ok(())
+}
+
+/// Drop what followed an earlier prompt, or branch off before it, and note
+/// it in the scrollback
+///
+/// Returns the branch and the events of its agent when one was started.
+async fn rewind_to(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    conversation: &Conversation,
+    message_id: &str,
+    branch: bool,
+    theme: &Theme,
+) -> Result<Option<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)>> {
+    let (note, branched) = if branch {
+        let (branch, events) = conversation.branch(message_id).await?;
+        (format!("Branched off into session {}", branch.session_id), Some((branch, events)))
+    } else {
+        let dropped = conversation.rewind(message_id).await?;
+        (format!("Dropped {} messages from the edited prompt on", dropped), None)
+    };
+    print_above(terminal, height, vec![Line::from(Span::styled(note, Style::default().fg(theme.fg_muted))), Line::from("")])?;
+    Ok(branched)
+}
+
+/// Continue another session, or a fork of it, in place of the conversation,
+/// printing what was said in it
+///
+/// The conversation stays with the model picked with `/model`.
+async fn open_session(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    app: &App,
+    switcher: &ModelSwitcher,
+    session_id: &str,
+    fork: bool,
+    postprocessor: &Postprocessor,
+    theme: &Theme,
+) -> Result<(Arc<Conversation>, mpsc::UnboundedReceiver<AppEvent>)> {
+    // Sessions `/recall` found in the archive come back before they are opened
+    if app.session_manager().get_archived_session(session_id).await?.is_some() {
+        let restored = app.session_manager().unarchive_session(session_id).await?;
+        let note = format!("Restored archived session {} ({})", restored.title, restored.id);
+        print_above(terminal, height, vec![Line::from(Span::styled(note, Style::default().fg(theme.fg_muted)))])?;
+    }
+    let session = match fork {
+        true => app.session_manager().fork_session(session_id).await?,
+        false => app.session_manager().resumable_session(session_id).await?,
+    };
+    let (conversation, events) = app.resume_session_conversation_with_events(&session.id).await?;
+    let model = switcher.current();
+    if conversation.model() != model {
+        if let Err(e) = switcher.switch(&conversation, &format!("{} {}", model.provider, model.model)) {
+            tracing::warn!("Session {} stays with {}: {}", session.id, conversation.model().model, e);
+        }
+    }
+
+    let note = format!("{} session {} ({})", if fork { "Forked into" } else { "Switched to" }, session.title, session.id);
+    print_above(terminal, height, vec![Line::from(Span::styled(note, Style::default().fg(theme.fg_muted))), Line::from("")])?;
+    print_conversation(terminal, height, &conversation, postprocessor, theme).await?;
+    Ok((conversation, events))
+}
+
+/// Print the answers of two sessions compared turn by turn into the scrollback
+async fn print_comparison(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    app: &App,
+    first: &str,
+    second: &str,
+    theme: &Theme,
+) -> Result<()> {
+    let comparison = app.session_manager().compare_sessions(first, second).await?;
+    print_above(terminal, height, comparison_lines(&comparison, theme))
+}
+
+/// A comparison with the words only one session's answer has marked
+fn comparison_lines(comparison: &SessionComparison, theme: &Theme) -> Vec<Line<'static>> {
+    let muted = Style::default().fg(theme.fg_muted);
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "A \"{}\" compared with B \"{}\": {} of {} turns differ",
+            comparison.first.title,
+            comparison.second.title,
+            comparison.changed_turns(),
+            comparison.turns.len()
+        ),
+        Style::default().fg(theme.info),
+    ))];
+    for (index, turn) in comparison.turns.iter().enumerate() {
+        lines.push(Line::from(""));
+        if turn.is_unchanged() {
+            lines.push(Line::from(Span::styled(format!("── Turn {}: unchanged ──", index + 1), muted)));
+            continue;
+        }
+        lines.push(Line::from(Span::styled(format!("── Turn {} ──", index + 1), muted)));
+        let prompt = |turn: Option<&TurnText>| turn.map_or("(no turn)".to_string(), |turn| turn.prompt.clone());
+        let prompts = match &turn.first {
+            Some(first) if turn.same_prompt() => vec![format!("› {}", first.prompt)],
+            _ => vec![format!("A› {}", prompt(turn.first.as_ref())), format!("B› {}", prompt(turn.second.as_ref()))],
+        };
+        for text in prompts {
+            lines.push(Line::from(Span::styled(text, Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD))));
+        }
+        // Changes can span lines, so each is cut at its line breaks
+        let mut spans = Vec::new();
+        for change in turn.answer_diff() {
+            let (text, style) = match change {
+                WordChange::Same(text) => (text, Style::default().fg(theme.fg_base)),
+                WordChange::Removed(text) => (text, Style::default().fg(theme.error).add_modifier(Modifier::CROSSED_OUT)),
+                WordChange::Added(text) => (text, Style::default().fg(theme.success)),
+            };
+            let mut parts = text.split('\n');
+            if let Some(part) = parts.next() {
+                spans.push(Span::styled(part.to_string(), style));
+            }
+            for part in parts {
+                lines.push(Line::from(std::mem::take(&mut spans)));
+                spans.push(Span::styled(part.to_string(), style));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+/// Print the message of a search hit into the scrollback, its matches highlighted
+///
+/// A message the conversation no longer has is shown as the hit's snippet.
+async fn print_hit(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    conversation: &Conversation,
+    hit: &MessageHit,
+    terms: &[String],
+    theme: &Theme,
+) -> Result<()> {
+    let text = conversation
+        .get_messages()
+        .await
+        .into_iter()
+        .find(|message| message.id == hit.message_id)
+        .and_then(|message| message.get_text_content())
+        .unwrap_or_else(|| hit.snippet.clone());
+    print_above(terminal, height, hit_lines(hit, &text, terms, theme))
+}
+
+/// The text of a hit's message under a note of what it is
+fn hit_lines(hit: &MessageHit, text: &str, terms: &[String], theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(Span::styled(
+        format!("── {} with a match ──", role_label(&hit.role)),
+        Style::default().fg(theme.fg_muted),
+    ))];
+    let style = Style::default().fg(theme.fg_base);
+    lines.extend(text.lines().map(|line| Line::from(highlighted(line, terms, style, theme))));
+    lines.push(Line::from(""));
+    lines
+}
+
+/// Spans of `text` with the matches of the terms marked
+fn highlighted(text: &str, terms: &[String], style: Style, theme: &Theme) -> Vec<Span<'static>> {
+    let matched = style.fg(theme.warning).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in match_ranges(text, terms) {
+        if range.start > end {
+            spans.push(Span::styled(text[end..range.start].to_string(), style));
+        }
+        spans.push(Span::styled(text[range.clone()].to_string(), matched));
+        end = range.end;
+    }
+    if end < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[end..].to_string(), style));
+    }
+    spans
+}
+
+/// Who wrote a message, as search hits list it
+fn role_label(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "Prompt",
+        MessageRole::Assistant => "Answer",
+        MessageRole::Tool => "Tool",
+        MessageRole::System => "System",
+    }
+}
+
+/// Print the questions and answers of a conversation into the scrollback
+async fn print_conversation(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    conversation: &Conversation,
+    postprocessor: &Postprocessor,
+    theme: &Theme,
+) -> Result<()> {
+    for message in conversation.get_messages().await {
+        let text = message.get_text_content().unwrap_or_default();
+        match message.role {
+            _ if text.trim().is_empty() => {}
+            MessageRole::User => print_question(terminal, height, &text, theme)?,
+            MessageRole::Assistant => print_answer(terminal, height, &text, postprocessor, theme)?,
+            _ => {}
+        }
+        if journal::is_interrupted(&message) {
+            let line = Line::from(vec![
+                Span::styled(format!("{} ", theme.icons.warning), Style::default().fg(theme.warning)),
+                Span::styled("Interrupted by a crash · /continue to finish it", Style::default().fg(theme.fg_muted)),
+            ]);
+            print_above(terminal, height, vec![line, Line::from("")])?;
+        }
+    }
+    Ok(())
+}
+
+/// Move the conversation to another model and note it in the scrollback
+fn switch_model(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    chat: &mut InlineChat,
+    switcher: &ModelSwitcher,
+    conversation: &Conversation,
+    args: &str,
+    theme: &Theme,
+) -> Result<()> {
+    match switcher.switch(conversation, args) {
+        Ok(model) => {
+            let line = Line::from(Span::styled(
+                format!("Switched to {} ({})", model.model, model.provider),
+                Style::default().fg(theme.fg_muted),
+            ));
+            print_above(terminal, height, vec![line, Line::from("")])?;
+        }
+        Err(e) => chat.error = Some(format!("Error: {}", e)),
+    }
+    Ok(())
+}
+
+/// Tell the user an answer is in, or failed, when they may have looked away
+fn announce(
+    terminal: &mut InlineTerminal,
+    notifier: &Notifier,
+    chat: &mut InlineChat,
+    answered: bool,
+    elapsed: Duration,
+    focused: bool,
+) {
+    if !notifier.should_notify(focused, elapsed) {
+        return;
+    }
+    let took = format::format_duration(elapsed);
+    let message = if answered {
+        format!("Answered in {}", took)
+    } else {
+        format!("The answer failed after {}", took)
+    };
+    if let Err(e) = notifier.notify(&message, terminal.backend_mut()) {
+        tracing::debug!("Answer not announced: {:#}", e);
+    }
+    // A failure shows its error instead
+    if answered {
+        chat.toasts.push(message, ToastKind::Success);
+    }
+}
+
+/// Branch and changes of the working directory's repository, if any
+async fn git_state() -> Option<GitState> {
+    let repo = GitRepo::open(std::env::current_dir().ok()?).await.ok()?;
+    match repo.status().await {
+        Ok(status) => Some(GitState::from(&status)),
+        Err(e) => {
+            tracing::debug!("No git status for the status bar: {}", e);
+            None
+        }
+    }
+}
+
+/// Record an event of the agent, update the status bar with it and show it
+fn follow_agent_event(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    chat: &mut InlineChat,
+    event: AppEvent,
+    recorder: Option<&Recorder>,
+    git: &mut Option<JoinHandle<Option<GitState>>>,
+    theme: &Theme,
+) -> Result<()> {
+    if let Some(recorder) = recorder {
+        recorder.event(&event);
+    }
+    if chat.status_bar.handle_event(&event) && git.is_none() {
+        *git = Some(tokio::spawn(git_state()));
+    }
+    show_agent_event(terminal, height, chat, event, theme)
+}
+
+/// Follow the commands the agent runs, printing their output as it arrives,
+/// and the answers it streams
+fn show_agent_event(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    chat: &mut InlineChat,
+    event: AppEvent,
+    theme: &Theme,
+) -> Result<()> {
+    // Text written before a tool call or another round stays in the scrollback
+    if matches!(event, AppEvent::ToolCalled { .. } | AppEvent::StreamStarted { .. }) {
+        if let Some(streamed) = chat.streamed.take().filter(|streamed| !streamed.is_empty()) {
+            print_answer(terminal, height, streamed.text(), &chat.postprocessor, theme)?;
+        }
+    }
+    match event {
+        AppEvent::StreamStarted { .. } => {
+            chat.streamed = Some(StreamingMarkdown::new());
+            chat.streamed_scroll = StickyScroll::new();
+        }
+        AppEvent::StreamChunk { chunk, .. } => {
+            if let Some(streamed) = chat.streamed.as_mut() {
+                streamed.push(&chunk, theme, terminal.size()?.width);
+            }
+        }
+        AppEvent::ToolCalled { tool_name, arguments, .. } if tool_name == "bash" => {
+            let command = arguments.get("command").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            let line = Line::from(vec![
+                Span::styled("$ ", Style::default().fg(theme.fg_muted)),
+                Span::styled(command.clone(), Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD)),
+            ]);
+            chat.running_command = Some((command, Instant::now()));
+            print_above(terminal, height, vec![line])?;
+        }
+        AppEvent::ToolProgress { progress, .. } => {
+            if let Some(output) = progress.output {
+                print_above(terminal, height, vec![Line::from(Span::styled(output, Style::default().fg(theme.fg_muted)))])?;
+            }
+        }
+        AppEvent::ToolCompleted { .. } if chat.running_command.is_some() => {
+            chat.running_command = None;
+            print_above(terminal, height, vec![Line::from("")])?;
+        }
+        AppEvent::ToolCompleted { table: Some(table), .. } => {
+            if let Some(data) = TableData::from_value(&table) {
+                let mut table = DataTable::new(data);
+                let mut lines = table.render_lines(terminal.size()?.width as usize, theme, false);
+                lines.push(Line::from(""));
+                print_above(terminal, height, lines)?;
+                chat.table = Some(table);
+                chat.toasts.push("Ctrl+T browses the table".to_string(), ToastKind::Info);
+            }
+        }
+        AppEvent::Error { error, recovery: Some(recovery) } => {
+            let line = Line::from(vec![
+                Span::styled(format!("{} ", theme.icons.warning), Style::default().fg(theme.warning)),
+                Span::styled(format!("{}; {}", error, recovery), Style::default().fg(theme.fg_muted)),
+            ]);
+            print_above(terminal, height, vec![line])?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Arguments of a prompt that runs `command`, if it does
+fn command_args<'a>(prompt: &'a str, command: &str) -> Option<&'a str> {
+    let rest = prompt.trim().strip_prefix(command)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+/// The PDF a pasted path names, as terminals paste the files dropped on them
+fn dropped_document(text: &str) -> Option<PathBuf> {
+    let text = text.trim();
+    if text.contains('\n') {
+        return None;
+    }
+    let quoted = text
+        .strip_prefix('\'')
+        .and_then(|text| text.strip_suffix('\''))
+        .or_else(|| text.strip_prefix('"').and_then(|text| text.strip_suffix('"')));
+    let path = match quoted {
+        Some(path) => path.to_string(),
+        None => text.replace("\\ ", " "),
+    };
+    let path = PathBuf::from(path.strip_prefix("file://").unwrap_or(&path));
+    (documents::is_document(&path) && path.is_file()).then_some(path)
+}
+
+fn print_question(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    question: &str,
+    theme: &Theme,
+) -> Result<()> {
+    let width = terminal.size()?.width;
+    let (rows, _) = wrap_input(question, 0, width);
+    let lines = rows
+        .into_iter()
+        .enumerate()
+        .map(|(row, text)| {
+            let prefix = if row == 0 { PROMPT } else { "  " };
+            Line::from(vec![
+                Span::styled(prefix, Style::default().fg(theme.tertiary).add_modifier(Modifier::BOLD)),
+                Span::styled(text, Style::default().fg(theme.fg_base).add_modifier(Modifier::BOLD)),
+            ])
+        })
+        .collect();
+    print_above(terminal, height, lines)
+}
+
+/// Print an answer as markdown, through the postprocessing stages
+fn print_answer(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    answer: &str,
+    postprocessor: &Postprocessor,
+    theme: &Theme,
+) -> Result<()> {
+    let answer = postprocessor.apply(answer);
+    let answer = answer.as_ref();
+    let width = terminal.size()?.width;
+    let mut lines = match MarkdownWidget::render_string(answer, &MarkdownConfig::default(), theme, width) {
+        Ok(text) => text.lines,
+        Err(_) => Text::raw(answer.to_string()).lines,
+    };
+    lines.push(Line::from(""));
+    print_above(terminal, height, lines)
+}
+
+/// Print lines into the scrollback above the viewport
+///
+/// Lines go in chunks no taller than the space above the viewport, which is
+/// all a single insert can scroll.
+fn print_above(
+    terminal: &mut InlineTerminal,
+    height: u16,
+    lines: Vec<Line<'static>>,
+) -> Result<()> {
+    let chunk = terminal.size()?.height.saturating_sub(height).max(1) as usize;
+    for chunk in lines.chunks(chunk) {
+        let text = chunk.to_vec();
+        terminal.insert_before(text.len() as u16, |buf| {
+            Paragraph::new(text).render(buf.area, buf);
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fswatch::FileAccess;
+    use crate::session::{search_terms, ComparedTurn};
+    use ratatui::backend::TestBackend;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_input_wraps_and_tracks_the_cursor() {
+        let (rows, cursor) = wrap_input("abcdefgh", 8, 6);
+        assert_eq!(rows, vec!["abcd", "efgh", ""]);
+        assert_eq!(cursor, (2, 0));
+
+        let (rows, cursor) = wrap_input("ab\ncd", 1, 20);
+        assert_eq!(rows, vec!["ab", "cd"]);
+        assert_eq!(cursor, (0, 1));
+
+        let (_, cursor) = wrap_input("日本語", 6, 6);
+        assert_eq!(cursor, (1, 2));
+    }
+
+    #[test]
+    fn test_questions_are_submitted_once() {
+        let mut chat = InlineChat::default();
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+
+        chat.insert("why\r\nnot");
+        chat.handle_key(key(KeyCode::Left));
+        chat.handle_key(key(KeyCode::Backspace));
+        assert_eq!(chat.input, "why\nnt");
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit("why\nnt".to_string()));
+        assert!(chat.input.is_empty());
+
+        chat.insert("again");
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        assert_eq!(chat.handle_key(key(KeyCode::Esc)), InlineAction::Quit);
+    }
+
+    #[test]
+    fn test_pasted_code_is_offered_a_fence_before_sending() {
+        let theme = themes::current_theme();
+        let draft = "Why does this panic?\nlet v: Vec<i32> = Vec::new();\nlet first = v[0];\nprintln!(\"{}\", first);";
+        let mut chat = InlineChat::default();
+        chat.insert(draft);
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        let mut terminal = Terminal::new(TestBackend::new(100, 8)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Lines 2-4 look like rust code without a fence."));
+        assert!(rows[7].starts_with("w: Wrap and send • s: Send as is • e: Keep editing"));
+
+        // Keeping on editing leaves the draft in the input
+        assert_eq!(chat.handle_key(key(KeyCode::Char('e'))), InlineAction::None);
+        assert!(chat.fence_offer.is_none());
+        assert_eq!(chat.input, draft);
+
+        chat.handle_key(key(KeyCode::Enter));
+        let InlineAction::Submit(sent) = chat.handle_key(key(KeyCode::Char('w'))) else {
+            panic!("the wrapped draft was not sent");
+        };
+        assert!(sent.starts_with("Why does this panic?\n```rust\nlet v"));
+        assert!(sent.ends_with("first);\n```"));
+
+        // The answer is in, so the next prompt is sent instead of queued
+        chat.waiting_since = None;
+        chat.insert(draft);
+        chat.handle_key(key(KeyCode::Enter));
+        assert_eq!(chat.handle_key(key(KeyCode::Char('s'))), InlineAction::Submit(draft.to_string()));
+
+        // Commands go as typed
+        chat.waiting_since = None;
+        chat.insert("/recall let mut\nfn main() {\nlet x = 1;\n}");
+        assert!(matches!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit(_)));
+    }
+
+    #[test]
+    fn test_ctrl_c_cancels_a_running_command_first() {
+        let mut chat = InlineChat::default();
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        chat.running_command = Some(("cargo test".to_string(), Instant::now()));
+        assert_eq!(chat.handle_key(ctrl_c), InlineAction::CancelCommand);
+
+        chat.running_command = None;
+        assert_eq!(chat.handle_key(ctrl_c), InlineAction::Quit);
+    }
+
+    #[test]
+    fn test_tab_cycles_through_suggestions() {
+        let mut chat = InlineChat {
+            suggestions: vec!["Add tests".to_string(), "Run clippy".to_string()],
+            ..InlineChat::default()
+        };
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "Add tests");
+        chat.handle_key(key(KeyCode::Tab));
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "Add tests");
+
+        // An edited suggestion is the user's own now
+        chat.insert(" too");
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "Add tests too");
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit("Add tests too".to_string()));
+        assert!(chat.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_tab_completes_slash_commands() {
+        let mut chat = InlineChat {
+            commands: SlashCommand::all(&CustomCommands::default()),
+            suggestions: vec!["Add tests".to_string()],
+            ..InlineChat::default()
+        };
+        chat.handle_key(key(KeyCode::Char('/')));
+        chat.handle_key(key(KeyCode::Char('e')));
+        assert_eq!(chat.typed_commands().len(), 2);
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "/edit");
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "/env");
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "/edit");
+
+        // Once arguments follow, Tab goes back to the suggestions
+        chat.handle_key(key(KeyCode::Char(' ')));
+        assert!(chat.typed_commands().is_empty());
+        chat.input.clear();
+        chat.cursor = 0;
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "Add tests");
+    }
+
+    #[test]
+    fn test_earlier_prompts_are_edited_and_resent() {
+        let mut chat = InlineChat::default();
+        chat.start_editing(Vec::new());
+        assert!(chat.editing.is_none() && chat.error.is_some());
+
+        let prompts = vec![("1".to_string(), "first".to_string()), ("2".to_string(), "second".to_string())];
+        chat.start_editing(prompts.clone());
+        assert_eq!(chat.input, "second");
+        chat.handle_key(key(KeyCode::Up));
+        chat.handle_key(key(KeyCode::Up));
+        assert_eq!(chat.input, "first");
+        chat.insert("!");
+        chat.handle_key(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL));
+        assert_eq!(
+            chat.handle_key(key(KeyCode::Enter)),
+            InlineAction::Edit {
+                message_id: "1".to_string(),
+                prompt: "first!".to_string(),
+                branch: true,
+            }
+        );
+        assert!(chat.editing.is_none());
+
+        // Esc leaves the prompt as it was instead of quitting
+        chat.waiting_since = None;
+        chat.start_editing(prompts.clone());
+        assert_eq!(chat.handle_key(key(KeyCode::Esc)), InlineAction::None);
+        assert!(chat.editing.is_none() && chat.input.is_empty());
+
+        assert_eq!(
+            chat.retry(prompts),
+            InlineAction::Edit {
+                message_id: "2".to_string(),
+                prompt: "second".to_string(),
+                branch: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_model_picker_filters_and_picks() {
+        let choice = |provider: &str, model: &str, name: &str| ModelChoice {
+            model: MessageModel::new(provider, model),
+            name: name.to_string(),
+        };
+        let mut chat = InlineChat {
+            picker: Some(ModelPicker {
+                choices: vec![
+                    choice("openai", "gpt-4o", "GPT-4o"),
+                    choice("anthropic", "claude-3-5-sonnet-latest", "Claude 3.5 Sonnet"),
+                    choice("anthropic", "claude-3-5-haiku-latest", "Claude 3.5 Haiku"),
+                ],
+                ..ModelPicker::default()
+            }),
+            ..InlineChat::default()
+        };
+        for c in "claude".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            chat.handle_key(key(KeyCode::Enter)),
+            InlineAction::SwitchModel("anthropic claude-3-5-haiku-latest".to_string())
+        );
+        assert!(chat.picker.is_none());
+        assert!(chat.input.is_empty());
+    }
+
+    #[test]
+    fn test_model_picker_offers_gemini() {
+        let switcher = ModelSwitcher::new(Config {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            ..Config::default()
+        });
+        let mut chat = InlineChat {
+            picker: Some(ModelPicker::new(&switcher)),
+            ..InlineChat::default()
+        };
+        for c in "2.5-flash".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            chat.handle_key(key(KeyCode::Enter)),
+            InlineAction::SwitchModel("gemini gemini-2.5-flash".to_string())
+        );
+    }
+
+    #[test]
+    fn test_model_picker_offers_deepseek_and_qwen() {
+        let switcher = ModelSwitcher::new(Config::default());
+        let picker = ModelPicker::new(&switcher);
+        let offered = |model: &str| picker.choices.iter().any(|choice| choice.model.model == model);
+        assert!(offered("deepseek-chat"));
+        assert!(offered("qwen3-coder-plus"));
+        // The agent needs tools, which deepseek-reasoner can't call
+        assert!(!offered("deepseek-reasoner"));
+
+        let mut chat = InlineChat {
+            picker: Some(picker),
+            ..InlineChat::default()
+        };
+        for c in "qwen-max".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::SwitchModel("qwen qwen-max".to_string()));
+    }
+
+    #[test]
+    fn test_listed_models_join_the_picker() {
+        let mut picker = ModelPicker::new(&ModelSwitcher::new(Config {
+            provider: "openrouter".to_string(),
+            model: "openai/gpt-4o".to_string(),
+            ..Config::default()
+        }));
+        picker.note = Some("Listing the server's models…".to_string());
+        let served = |model: &str, name: &str| ServedModel {
+            model: MessageModel::new("openrouter", model),
+            name: name.to_string(),
+        };
+        picker.add_served(vec![served("openai/gpt-4o", "OpenAI: GPT-4o"), served("mistralai/codestral", "Codestral")]);
+        assert!(picker.note.is_none());
+        let listed: Vec<&str> = picker
+            .choices
+            .iter()
+            .filter(|choice| choice.model.model.contains('/'))
+            .map(|choice| choice.model.model.as_str())
+            .collect();
+        assert_eq!(listed, vec!["openai/gpt-4o", "mistralai/codestral"]);
+    }
+
+    #[test]
+    fn test_session_picker_filters_switches_and_deletes() {
+        let sessions = vec![
+            Session::new("Fix the flaky login test".to_string(), None),
+            Session::new("Parser refactor".to_string(), None),
+            Session::new("Login page styles".to_string(), None),
+        ];
+        let ids: Vec<String> = sessions.iter().map(|session| session.id.clone()).collect();
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let mut chat = InlineChat {
+            sessions: Some(SessionPicker::new(sessions, &ids[0])),
+            ..InlineChat::default()
+        };
+        for c in "login".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        let picker = chat.sessions.as_ref().unwrap();
+        let matching: Vec<&str> = picker.matching().iter().map(|(session, _)| session.id.as_str()).collect();
+        assert_eq!(matching.len(), 2);
+        assert!(!matching.contains(&ids[1].as_str()));
+
+        // The session in use stays, others go once the deletion is confirmed
+        let current = matching.iter().position(|id| *id == ids[0]).unwrap();
+        chat.sessions.as_mut().unwrap().selected = current;
+        assert_eq!(chat.handle_key(ctrl('d')), InlineAction::None);
+        assert!(chat.sessions.as_ref().unwrap().notice.is_some());
+        assert!(chat.confirm.is_none());
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(chat.handle_key(ctrl('d')), InlineAction::None);
+        let mut terminal = Terminal::new(TestBackend::new(80, 5)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &themes::current_theme())).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Delete session \"Login page styles\"?"));
+        assert!(rows[4].starts_with("y: Yes • n: No"));
+        // Enter picks the focused No, and the picker is back
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        assert!(chat.confirm.is_none() && chat.sessions.is_some());
+        chat.handle_key(ctrl('d'));
+        assert_eq!(chat.handle_key(key(KeyCode::Char('y'))), InlineAction::DeleteSession(ids[2].clone()));
+        chat.sessions.as_mut().unwrap().remove(&ids[2], "Session deleted");
+        assert_eq!(chat.sessions.as_ref().unwrap().matching().len(), 1);
+
+        assert_eq!(chat.handle_key(ctrl('f')), InlineAction::ForkSession(ids[0].clone()));
+        assert!(chat.sessions.is_none());
+        assert_eq!(chat.handle_key(ctrl('s')), InlineAction::OpenSessions);
+    }
+
+    #[test]
+    fn test_recent_files_attach_or_insert_the_picked_file() {
+        let theme = themes::current_theme();
+        let touched = |path: &str, edited| TouchedFile {
+            path: PathBuf::from(path),
+            last_access: if edited { FileAccess::Edit } else { FileAccess::Read },
+            edited,
+        };
+        let files = vec![touched("/work/src/main.rs", true), touched("/work/README.md", false)];
+        let mut chat = InlineChat {
+            recent_files: Some(FilePicker::new(files.clone(), Some(std::path::Path::new("/work")))),
+            ..Default::default()
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Recent files: "));
+        assert!(rows[1].contains("src/main.rs  edited"));
+        assert!(rows[2].contains("README.md  read"));
+
+        for c in "read".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::AttachFile(PathBuf::from("/work/README.md")));
+        assert!(chat.recent_files.is_none());
+
+        chat.recent_files = Some(FilePicker::new(files, None));
+        chat.handle_key(key(KeyCode::Tab));
+        assert_eq!(chat.input, "/work/src/main.rs");
+        assert!(chat.recent_files.is_none());
+    }
+
+    #[test]
+    fn test_message_search_lists_hits_and_prints_the_one_picked() {
+        let theme = themes::current_theme();
+        let hit = |id: &str, role, snippet: &str| MessageHit {
+            session_id: "current".to_string(),
+            message_id: id.to_string(),
+            role,
+            snippet: snippet.to_string(),
+        };
+        let mut chat = InlineChat::default();
+        chat.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+        for c in "borrow".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            chat.handle_key(key(KeyCode::Enter)),
+            InlineAction::SearchMessages("borrow".to_string(), SearchScope::Session)
+        );
+        let hits = vec![
+            hit("a", MessageRole::User, "why does the borrow checker complain"),
+            hit("b", MessageRole::Assistant, "the Borrow ends too late"),
+        ];
+        chat.message_search.as_mut().unwrap().set_results(Ok(hits.clone()));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Search this session: borrow  1/2"));
+        assert!(rows[1].starts_with("Prompt why does the borrow checker complain"));
+        assert!(rows[2].starts_with("Answer the Borrow ends too late"));
+
+        // n moves to the next hit without printing it
+        assert_eq!(chat.handle_key(key(KeyCode::Char('n'))), InlineAction::None);
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::ShowHit(hits[1].clone()));
+        assert_eq!(
+            chat.handle_key(key(KeyCode::Tab)),
+            InlineAction::SearchMessages("borrow".to_string(), SearchScope::AllSessions)
+        );
+        chat.handle_key(key(KeyCode::Esc));
+        assert!(chat.message_search.is_none());
+
+        let lines = hit_lines(&hits[1], "Here the Borrow ends\ntoo late", &search_terms("borrow"), &theme);
+        let text: Vec<String> = lines.iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect();
+        assert_eq!(text, vec!["── Answer with a match ──", "Here the Borrow ends", "too late", ""]);
+        assert_eq!(lines[1].spans[1].content, "Borrow");
+        assert!(lines[1].spans[1].style.add_modifier.contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn test_draft_preview_renders_markdown_once_typing_pauses() {
+        let theme = themes::current_theme();
+        let mut chat = InlineChat {
+            input: "**Bold** move".to_string(),
+            cursor: 13,
+            ..Default::default()
+        };
+        chat.handle_key(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::ALT));
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[1].starts_with("› **Bold** move"));
+        let (_, preview) = rows[1].split_once('│').unwrap();
+        assert!(preview.contains("Bold move") && !preview.contains('*'));
+
+        let now = Instant::now();
+        let mut preview = DraftPreview::default();
+        assert!(preview.refresh("draft", 40, &theme, now));
+        assert!(!preview.refresh("draft!", 40, &theme, now));
+        assert!(!preview.refresh("draft!!", 40, &theme, now + PREVIEW_DEBOUNCE / 2));
+        assert!(preview.refresh("draft!!", 40, &theme, now + PREVIEW_DEBOUNCE));
+        // A new width can't wait
+        assert!(preview.refresh("draft!!", 30, &theme, now + PREVIEW_DEBOUNCE));
+
+        chat.handle_key(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::ALT));
+        assert!(chat.draft_preview.is_none());
+    }
+
+    #[test]
+    fn test_recalled_sessions_keep_their_order_and_show_what_matched() {
+        let theme = themes::current_theme();
+        let found = vec![
+            (Session::new("Parser refactor".to_string(), None), "Split the lexer from the parser".to_string(), false),
+            (Session::new("Fix the flaky login test".to_string(), None), "The tokenizer drops\nquotes".to_string(), true),
+        ];
+        let first = found[0].0.id.clone();
+        let mut chat = InlineChat {
+            sessions: Some(SessionPicker::recalled(found, "current")),
+            ..InlineChat::default()
+        };
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Recalled sessions: "));
+        assert!(rows[1].contains("Parser refactor  Split the lexer from the parser"));
+        assert!(rows[2].contains("(archived)  The tokenizer drops") && !rows[2].contains("quotes"));
+
+        let archive = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(chat.handle_key(archive), InlineAction::None);
+        assert!(chat.sessions.as_ref().unwrap().notice.is_some());
+        chat.handle_key(key(KeyCode::Up));
+        assert_eq!(chat.handle_key(archive), InlineAction::ArchiveSession(first.clone()));
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::SwitchSession(first));
+        assert_eq!(command_args("/recall  the parser ", RECALL_COMMAND), Some("the parser"));
+        assert_eq!(command_args("/recalled", RECALL_COMMAND), None);
+    }
+
+    #[test]
+    fn test_two_sessions_are_picked_and_compared() {
+        let sessions = vec![Session::new("Opus run".to_string(), None), Session::new("Haiku run".to_string(), None)];
+        let ids: Vec<String> = sessions.iter().map(|session| session.id.clone()).collect();
+        let mut chat = InlineChat {
+            sessions: Some(SessionPicker::comparing(sessions, &ids[0])),
+            ..Default::default()
+        };
+
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        assert_eq!(chat.sessions.as_ref().unwrap().compared.as_ref(), Some(&ids[0]));
+        // The same session twice is no comparison
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        assert!(chat.sessions.as_ref().unwrap().notice.is_some());
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::CompareSessions(ids[0].clone(), ids[1].clone()));
+        assert!(chat.sessions.is_none());
+
+        let theme = themes::current_theme();
+        let turn = |prompt: &str, answer: &str| TurnText { prompt: prompt.to_string(), answer: answer.to_string() };
+        let comparison = SessionComparison {
+            first: Session::new("Opus run".to_string(), None),
+            second: Session::new("Haiku run".to_string(), None),
+            turns: vec![
+                ComparedTurn { first: Some(turn("Plan", "Same")), second: Some(turn("Plan", "Same")) },
+                ComparedTurn { first: Some(turn("Fix it", "Use a vector\nthen test")), second: Some(turn("Fix it", "Use a map\nthen test")) },
+            ],
+        };
+        let lines: Vec<String> = comparison_lines(&comparison, &theme)
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+        assert_eq!(lines[0], "A \"Opus run\" compared with B \"Haiku run\": 1 of 2 turns differ");
+        assert_eq!(lines[2], "── Turn 1: unchanged ──");
+        assert_eq!(lines[5..8], ["› Fix it", "Use a vectormap", "then test"]);
+    }
+
+    #[test]
+    fn test_prompts_are_queued_while_waiting() {
+        let mut chat = InlineChat::default();
+        chat.insert("first");
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit("first".to_string()));
+        for prompt in ["second", "third"] {
+            chat.insert(prompt);
+            assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        }
+        assert_eq!(chat.queued, ["second", "third"]);
+        assert!(chat.input.is_empty());
+        assert_eq!(chat.next_queued(), None);
+
+        // Ctrl+X takes the latest back
+        chat.insert("draft");
+        chat.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert_eq!(chat.input, "draft\nthird");
+        chat.input.clear();
+
+        chat.waiting_since = None;
+        assert_eq!(chat.next_queued().as_deref(), Some("second"));
+        assert!(chat.waiting_since.is_some() && chat.queued.is_empty());
+
+        // After a failed answer the queue waits for Enter
+        chat.queued.push_back("fourth".to_string());
+        chat.waiting_since = None;
+        chat.queue_paused = true;
+        assert_eq!(chat.next_queued(), None);
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::None);
+        assert_eq!(chat.next_queued().as_deref(), Some("fourth"));
+    }
+
+    #[test]
+    fn test_dropped_pdfs_are_attached_and_previewed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Design notes.pdf");
+        std::fs::write(&path, b"%PDF-1.7").unwrap();
+        let shown = path.display().to_string();
+        assert_eq!(dropped_document(&shown.replace(' ', "\\ ")), Some(path.clone()));
+        assert_eq!(dropped_document(&format!("'{}'\n", shown)), Some(path.clone()));
+        assert_eq!(dropped_document(&format!("file://{}", shown)), Some(path.clone()));
+        assert_eq!(dropped_document(&dir.path().join("missing.pdf").display().to_string()), None);
+        assert_eq!(dropped_document(&format!("{}\nand more", shown)), None);
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let limits = DocumentLimits::default();
+        let mut chat = InlineChat::default();
+        chat.attach(DocumentAttachment::from_text("spec.pdf", "First.\n\nSecond.", limits));
+        chat.attach(DocumentAttachment::from_text("notes.pdf", "Third.", limits));
+        assert_eq!(chat.preview.as_ref().unwrap().index, 1);
+
+        // Keys scroll and switch documents instead of editing the input
+        chat.handle_key(key(KeyCode::Tab));
+        chat.handle_key(key(KeyCode::Down));
+        chat.handle_key(key(KeyCode::PageDown));
+        let preview = chat.preview.as_ref().unwrap();
+        assert_eq!((preview.index, preview.scroll), (0, 4));
+        assert!(chat.input.is_empty());
+        chat.handle_key(ctrl('d'));
+        assert!(chat.preview.is_none());
+        assert_eq!(chat.handle_key(ctrl('o')), InlineAction::None);
+        chat.handle_key(key(KeyCode::Esc));
+        assert!(chat.preview.is_none());
+
+        let attached = chat.take_documents();
+        assert_eq!(attached.len(), 1);
+        assert_eq!(attached[0].name, "notes.pdf");
+        assert!(chat.documents.is_empty());
+    }
+
+    #[test]
+    fn test_screenshots_wait_for_the_next_prompt() {
+        let theme = themes::current_theme();
+        let mut chat = InlineChat {
+            images: vec![ImageAttachment::new("screenshot-1.png", vec![1, 2, 3])],
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal)[0].contains("screenshot-1.png"));
+
+        chat.insert("what is wrong here?");
+        assert!(matches!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit(_)));
+        assert_eq!(chat.take_images().len(), 1);
+        assert!(chat.images.is_empty());
+    }
+
+    #[test]
+    fn test_history_is_walked_and_searched() {
+        let mut chat = InlineChat {
+            history: vec!["explain the build".to_string(), "add a test".to_string(), "fix the build".to_string()],
+            ..InlineChat::default()
+        };
+        chat.handle_key(key(KeyCode::Up));
+        chat.handle_key(key(KeyCode::Up));
+        assert_eq!(chat.input, "add a test");
+        chat.handle_key(key(KeyCode::Down));
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(chat.input, "");
+
+        chat.insert("draft");
+        chat.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        for c in "BUILD".chars() {
+            chat.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(chat.input, "fix the build");
+        chat.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert_eq!(chat.input, "explain the build");
+        chat.handle_key(key(KeyCode::Esc));
+        assert_eq!(chat.input, "draft");
+
+        chat.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        chat.handle_key(key(KeyCode::Char('t')));
+        chat.handle_key(key(KeyCode::Enter));
+        assert_eq!(chat.input, "fix the build");
+        assert_eq!(chat.handle_key(key(KeyCode::Enter)), InlineAction::Submit("fix the build".to_string()));
+        assert_eq!(chat.history.last().map(String::as_str), Some("fix the build"));
+    }
+
+    /// Text of the rows drawn on a test terminal
+    fn rows(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_streamed_answers_show_above_the_input() {
+        let theme = themes::current_theme();
+        let mut streamed = StreamingMarkdown::new();
+        streamed.push("First line\n\nSecond line\n\nThird", &theme, 40);
+        let mut chat = InlineChat {
+            waiting_since: Some(Instant::now()),
+            streamed: Some(streamed),
+            ..Default::default()
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 5)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].contains("Writing"));
+        // The two rows the input leaves show the latest lines
+        assert!(rows[1..3].iter().any(|row| row.contains("Third")));
+        assert!(!rows.iter().any(|row| row.contains("First")));
+        assert!(rows[3].starts_with(PROMPT));
+    }
+
+    #[test]
+    fn test_tables_open_sort_and_close() {
+        let theme = themes::current_theme();
+        let data = TableData::from_value(&serde_json::json!({
+            "columns": ["name", "age"],
+            "rows": [["carol", 41], ["alice", 9], ["bob", 23]]
+        }))
+        .unwrap();
+        let mut chat = InlineChat {
+            table: Some(DataTable::new(data)),
+            ..Default::default()
+        };
+
+        chat.handle_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert!(chat.table_open);
+        chat.handle_key(key(KeyCode::Char('s')));
+        let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        // Two rows fit under the status line, the header and the separator
+        assert!(rows[0].contains("rows 1-2 of 3"));
+        assert!(rows[1].contains("name ↑"));
+        assert!(rows[3].contains("alice"));
+        assert!(rows[4].contains("bob"));
+
+        chat.handle_key(key(KeyCode::Esc));
+        assert!(!chat.table_open);
+        assert!(chat.table.is_some());
+    }
+
+    #[test]
+    fn test_narrow_terminals_get_a_compact_layout() {
+        let theme = themes::current_theme();
+        let mut chat = InlineChat::default();
+        chat.insert("hello");
+
+        let mut terminal = Terminal::new(TestBackend::new(70, 3)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Enter to ask · Ctrl+R history"));
+        assert!(rows[1].starts_with(PROMPT));
+
+        // Without the status bar the input gets its row
+        let mut terminal = Terminal::new(TestBackend::new(40, 3)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert_eq!(rows[0].trim_end(), "Enter to ask · Esc to quit");
+        assert!(rows[1].starts_with(PROMPT));
+        assert!(rows[2].trim().is_empty());
+
+        let mut terminal = Terminal::new(TestBackend::new(12, 3)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal).iter().any(|row| row.contains("Terminal")));
+    }
+
+    #[test]
+    fn test_help_lists_the_bindings_in_effect() {
+        let theme = themes::current_theme();
+        let overrides: HashMap<String, String> = [("quit", "ctrl+q"), ("help", "f2")]
+            .iter()
+            .map(|(action, key)| (action.to_string(), key.to_string()))
+            .collect();
+        let mut chat = InlineChat {
+            key_map: KeyMap::with_overrides(&overrides).unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(chat.handle_key(key(KeyCode::F(1))), InlineAction::None);
+        assert!(chat.help.is_none());
+        chat.handle_key(key(KeyCode::F(2)));
+        let mut terminal = Terminal::new(TestBackend::new(80, 6)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows[0].starts_with("Keys"));
+        assert!(rows[1].trim_start().starts_with("ctrl+q") && rows[1].contains("Quit application"));
+        assert!(rows[2].trim_start().starts_with("f2"));
+        // Keys inline mode doesn't have are left out
+        assert!(!rows.iter().any(|row| row.contains("$EDITOR")));
+
+        chat.handle_key(key(KeyCode::Down));
+        assert_eq!(chat.help, Some(1));
+        chat.handle_key(key(KeyCode::F(2)));
+        assert!(chat.help.is_none());
+        assert_eq!(chat.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)), InlineAction::Quit);
+        assert_eq!(
+            chat.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::ALT)),
+            InlineAction::PickComparison
+        );
+    }
+
+    #[test]
+    fn test_macros_record_and_replay_into_the_input() {
+        let theme = themes::current_theme();
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("goofy.json");
+        let mut chat = InlineChat {
+            macro_store: Some(store.clone()),
+            ..Default::default()
+        };
+        let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+
+        chat.handle_key(alt('q'));
+        chat.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(chat.macros.recording(), Some('a'));
+        let mut terminal = Terminal::new(TestBackend::new(60, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal)[0].contains("Recording macro a"));
+
+        chat.handle_key(key(KeyCode::Char('h')));
+        chat.handle_key(key(KeyCode::Char('i')));
+        chat.handle_key(alt('q'));
+        assert_eq!(chat.macros.recording(), None);
+        assert_eq!(chat.input, "hi");
+        let stored = macros::load_stored(&store).unwrap();
+        assert_eq!(stored["a"], vec!["h", "i"]);
+
+        chat.handle_key(alt('e'));
+        chat.handle_key(key(KeyCode::Char('2')));
+        chat.handle_key(key(KeyCode::Char('a')));
+        while let Some(replayed) = chat.next_replayed() {
+            chat.handle_typed_key(replayed);
+        }
+        assert_eq!(chat.input, "hihihi");
+    }
+
+    #[test]
+    fn test_scrolled_up_answers_stay_and_count_new_lines() {
+        let theme = themes::current_theme();
+        let mut chat = InlineChat {
+            waiting_since: Some(Instant::now()),
+            streamed: Some(StreamingMarkdown::new()),
+            ..Default::default()
+        };
+        let text: String = (1..=6).map(|n| format!("Line {}\n\n", n)).collect();
+        chat.streamed.as_mut().unwrap().push(&text, &theme, 40);
+        let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+
+        // Scrolled up, the lines read stay as more arrive
+        chat.handle_key(key(KeyCode::PageUp));
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let before = rows(&terminal);
+        chat.streamed.as_mut().unwrap().push("Line 7\n\n", &theme, 40);
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let after = rows(&terminal);
+        assert_eq!(before[1], after[1]);
+        assert!(after[3].contains("new line"));
+        assert!(!after.iter().any(|row| row.contains("Line 7")));
+
+        // Ctrl+End follows the answer again
+        chat.handle_key(KeyEvent::new(KeyCode::End, KeyModifiers::CONTROL));
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        let rows = rows(&terminal);
+        assert!(rows.iter().any(|row| row.contains("Line 7")));
+        assert!(!rows.iter().any(|row| row.contains("new line")));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_wait_for_the_answer() {
+        use crate::permission::{PermissionConfig, PermissionContext, PermissionLevel, PermissionManager};
+
+        let (prompter, mut prompts) = mpsc::unbounded_channel();
+        let manager = Arc::new(PermissionManager::new(PermissionConfig::default()).with_prompter(prompter));
+        let edit = PermissionContext::new("edit".to_string(), "write".to_string())
+            .with_file_path(PathBuf::from("src/main.rs"))
+            .with_risk_level(PermissionLevel::Write);
+        let call = tokio::spawn(async move { manager.check_permission(edit).await });
+
+        let mut chat = InlineChat::default();
+        chat.approvals.push_back(prompts.recv().await.unwrap());
+        assert!(chat.next_approval().is_some());
+        assert!(chat.next_approval().is_none());
+
+        // Keys answer the prompt instead of going into the input
+        chat.handle_key(key(KeyCode::Char('x')));
+        tokio::task::yield_now().await;
+        assert!(!call.is_finished());
+        assert!(chat.input.is_empty());
+
+        chat.handle_key(key(KeyCode::Char('a')));
+        assert!(chat.approval.is_none());
+        assert!(call.await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_background_jobs_are_approved_and_counted_on_the_status_line() {
+        use crate::permission::{PermissionConfig, PermissionContext, PermissionManager};
+
+        let (prompter, mut prompts) = mpsc::unbounded_channel();
+        let manager = Arc::new(PermissionManager::new(PermissionConfig::default()).with_prompter(prompter));
+        let arguments = HashMap::from([("command".to_string(), serde_json::Value::from("npm run dev"))]);
+        let start = PermissionContext::for_tool_call("bash_background", &arguments);
+        let call = tokio::spawn(async move { manager.check_permission(start).await });
+
+        let theme = themes::current_theme();
+        let mut chat = InlineChat::default();
+        chat.approvals.push_back(prompts.recv().await.unwrap());
+        let transcript: Vec<String> = chat
+            .next_approval()
+            .unwrap()
+            .transcript(&theme)
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+        assert!(transcript.contains(&"$ npm run dev".to_string()));
+        let mut terminal = Terminal::new(TestBackend::new(100, 4)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal)[0].starts_with("Allow bash_background?"));
+        chat.handle_key(key(KeyCode::Char('a')));
+        assert!(call.await.unwrap().unwrap());
+
+        chat.running_jobs = 2;
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal)[0].contains("2 jobs running · "));
+        chat.running_jobs = 1;
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal)[0].contains("1 job running · "));
+    }
+
+    #[tokio::test]
+    async fn test_flagged_commands_are_asked_about_despite_a_session_grant() {
+        use crate::permission::{PermissionConfig, PermissionContext, PermissionManager};
+
+        let (prompter, mut prompts) = mpsc::unbounded_channel();
+        let manager = Arc::new(PermissionManager::new(PermissionConfig::default()).with_prompter(prompter));
+        manager.allow_for_session(vec!["bash".to_string()]).await;
+        let bash = |command: &str| {
+            let arguments = HashMap::from([("command".to_string(), serde_json::Value::from(command))]);
+            PermissionContext::for_tool_call("bash", &arguments)
+        };
+        assert!(manager.check_permission(bash("cargo build")).await.unwrap());
+        assert!(prompts.try_recv().is_err());
+
+        let theme = themes::current_theme();
+        let mut chat = InlineChat::default();
+        for (answer, granted) in [('s', true), ('d', false)] {
+            let call = tokio::spawn({
+                let manager = manager.clone();
+                async move { manager.check_permission(bash("sudo make install")).await }
+            });
+            chat.approvals.push_back(prompts.recv().await.unwrap());
+            let transcript = chat.next_approval().unwrap().transcript(&theme);
+            assert!(transcript.iter().any(|line| line.spans.iter().any(|span| span.content.starts_with("⚠ The command"))));
+            chat.handle_key(key(KeyCode::Char(answer)));
+            assert_eq!(call.await.unwrap().unwrap(), granted);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_always_allowed_tools_stay_allowed_in_the_project() {
+        use crate::permission::{GrantStore, PermissionConfig, PermissionContext, PermissionLevel, PermissionManager};
+
+        let project = tempfile::tempdir().unwrap();
+        let (prompter, mut prompts) = mpsc::unbounded_channel();
+        let manager = Arc::new(
+            PermissionManager::new(PermissionConfig::default())
+                .with_project_grants(GrantStore::load_for(project.path()).unwrap())
+                .with_prompter(prompter),
+        );
+        let edit = || {
+            PermissionContext::new("edit".to_string(), "write".to_string())
+                .with_file_path(PathBuf::from("src/main.rs"))
+                .with_risk_level(PermissionLevel::Write)
+        };
+        let asking = manager.clone();
+        let call = tokio::spawn(async move { asking.check_permission(edit()).await });
+
+        let mut chat = InlineChat::default();
+        chat.approvals.push_back(prompts.recv().await.unwrap());
+        chat.next_approval();
+        let theme = themes::current_theme();
+        let mut terminal = Terminal::new(TestBackend::new(120, 8)).unwrap();
+        terminal.draw(|frame| chat.render(frame, &theme)).unwrap();
+        assert!(rows(&terminal).iter().any(|row| row.contains("p: Always here")));
+
+        chat.handle_key(key(KeyCode::Char('p')));
+        assert!(call.await.unwrap().unwrap());
+        assert!(GrantStore::load_for(project.path()).unwrap().contains("edit"));
+
+        // The next call goes through without asking
+        assert!(manager.check_permission(edit()).await.unwrap());
+        assert!(prompts.try_recv().is_err());
+    }
+}
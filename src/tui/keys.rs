@@ -1,5 +1,17 @@
+//! Key bindings of the TUI actions
+//!
+//! Every action has a default key, and `tui.keybindings` in the configuration
+//! file rebinds actions by name, keys written like macro keys (`ctrl+q`,
+//! `alt+shift+d`, `f2`). The key map is checked when it is loaded, so two
+//! actions on the same key are reported instead of one silently shadowing the
+//! other.
+
+use anyhow::{anyhow, bail, Context, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::collections::HashMap;
+use std::path::Path;
+
+use super::macros;
 
 /// Key binding configuration
 #[derive(Debug, Clone)]
@@ -21,6 +33,11 @@ impl KeyBinding {
     pub fn matches(&self, event: &KeyEvent) -> bool {
         self.key == event.code && self.modifiers == event.modifiers
     }
+    
+    /// The key as written in the configuration, like `ctrl+c`
+    pub fn key_label(&self) -> String {
+        macros::key_to_string(&KeyEvent::new(self.key, self.modifiers))
+    }
 }
 
 /// Application key mappings
@@ -31,6 +48,27 @@ pub struct KeyMap {
     
     /// Show help
     pub help: KeyBinding,
+    
+    /// Edit the draft in $EDITOR
+    pub external_editor: KeyBinding,
+    
+    /// Start or stop recording a macro
+    pub record_macro: KeyBinding,
+    
+    /// Replay a macro
+    pub play_macro: KeyBinding,
+    
+    /// Switch to a file the agent touched this session
+    pub recent_files: KeyBinding,
+    
+    /// Undo the file changes of the last agent turn
+    pub undo_turn: KeyBinding,
+    
+    /// Compare the answers of two sessions
+    pub compare_sessions: KeyBinding,
+    
+    /// Show the render profiler overlay, in debug builds
+    pub profiler: KeyBinding,
 }
 
 impl Default for KeyMap {
@@ -42,15 +80,135 @@ impl Default for KeyMap {
                 "Quit application"
             ),
             help: KeyBinding::new(
+                KeyCode::F(1),
+                KeyModifiers::NONE,
+                "Show/hide help"
+            ),
+            external_editor: KeyBinding::new(
                 KeyCode::Char('g'),
                 KeyModifiers::CONTROL,
-                "Show/hide help"
+                "Edit draft in $EDITOR"
+            ),
+            record_macro: KeyBinding::new(
+                KeyCode::Char('q'),
+                KeyModifiers::ALT,
+                "Record macro into a register / stop recording"
+            ),
+            play_macro: KeyBinding::new(
+                KeyCode::Char('e'),
+                KeyModifiers::ALT,
+                "Replay macro: [count] register, @ for the last one"
+            ),
+            recent_files: KeyBinding::new(
+                KeyCode::Char('p'),
+                KeyModifiers::CONTROL,
+                "Recent files the agent read or edited"
+            ),
+            undo_turn: KeyBinding::new(
+                KeyCode::Char('u'),
+                KeyModifiers::ALT,
+                "Undo the file changes of the last agent turn"
+            ),
+            compare_sessions: KeyBinding::new(
+                KeyCode::Char('d'),
+                KeyModifiers::ALT,
+                "Compare the answers of two sessions"
+            ),
+            profiler: KeyBinding::new(
+                KeyCode::F(12),
+                KeyModifiers::NONE,
+                "Show/hide render times (debug builds)"
             ),
         }
     }
 }
 
 impl KeyMap {
+    /// The default key map with the actions in `overrides` rebound
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> Result<Self> {
+        let mut key_map = Self::default();
+        let mut actions: Vec<&String> = overrides.keys().collect();
+        actions.sort();
+        for action in actions {
+            let key = macros::parse_key(&overrides[action])
+                .with_context(|| format!("Invalid key for '{}'", action))?;
+            let binding = key_map.binding_mut(action).ok_or_else(|| {
+                let known: Vec<&str> = Self::default().bindings().iter().map(|(name, _)| *name).collect();
+                anyhow!("Unknown action '{}'; actions are {}", action, known.join(", "))
+            })?;
+            binding.key = key.code;
+            binding.modifiers = key.modifiers;
+        }
+        
+        let conflicts = key_map.conflicts();
+        if !conflicts.is_empty() {
+            bail!("Conflicting keybindings: {}", conflicts.join("; "));
+        }
+        Ok(key_map)
+    }
+    
+    /// Load the key map with the overrides under `tui.keybindings` in a configuration file
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let overrides = match config.pointer("/tui/keybindings") {
+            Some(overrides) => serde_json::from_value(overrides.clone())?,
+            None => HashMap::new(),
+        };
+        Self::with_overrides(&overrides)
+            .with_context(|| format!("Invalid tui.keybindings in {}", path.display()))
+    }
+    
+    /// Every action by name with its binding, in the order help lists them
+    pub fn bindings(&self) -> [(&'static str, &KeyBinding); 9] {
+        [
+            ("quit", &self.quit),
+            ("help", &self.help),
+            ("external_editor", &self.external_editor),
+            ("record_macro", &self.record_macro),
+            ("play_macro", &self.play_macro),
+            ("recent_files", &self.recent_files),
+            ("undo_turn", &self.undo_turn),
+            ("compare_sessions", &self.compare_sessions),
+            ("profiler", &self.profiler),
+        ]
+    }
+    
+    fn binding_mut(&mut self, action: &str) -> Option<&mut KeyBinding> {
+        match action {
+            "quit" => Some(&mut self.quit),
+            "help" => Some(&mut self.help),
+            "external_editor" => Some(&mut self.external_editor),
+            "record_macro" => Some(&mut self.record_macro),
+            "play_macro" => Some(&mut self.play_macro),
+            "recent_files" => Some(&mut self.recent_files),
+            "undo_turn" => Some(&mut self.undo_turn),
+            "compare_sessions" => Some(&mut self.compare_sessions),
+            "profiler" => Some(&mut self.profiler),
+            _ => None,
+        }
+    }
+    
+    /// Keys bound to more than one action, each described like
+    /// `ctrl+p: recent_files, undo_turn`
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut by_key: Vec<(String, Vec<&str>)> = Vec::new();
+        for (action, binding) in self.bindings() {
+            let key = binding.key_label();
+            match by_key.iter_mut().find(|(bound, _)| *bound == key) {
+                Some((_, actions)) => actions.push(action),
+                None => by_key.push((key, vec![action])),
+            }
+        }
+        by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(key, actions)| format!("{}: {}", key, actions.join(", ")))
+            .collect()
+    }
+    
     /// Check if the event should quit the application
     pub fn should_quit(&self, event: &KeyEvent) -> bool {
         self.quit.matches(event)
@@ -61,8 +219,86 @@ impl KeyMap {
         self.help.matches(event)
     }
     
-    /// Get help text for all key bindings
+    /// Check if the event should open the draft in an external editor
+    pub fn should_open_external_editor(&self, event: &KeyEvent) -> bool {
+        self.external_editor.matches(event)
+    }
+    
+    /// Check if the event should start or stop recording a macro
+    pub fn should_record_macro(&self, event: &KeyEvent) -> bool {
+        self.record_macro.matches(event)
+    }
+    
+    /// Check if the event should replay a macro
+    pub fn should_play_macro(&self, event: &KeyEvent) -> bool {
+        self.play_macro.matches(event)
+    }
+    
+    /// Check if the event should open the recent files switcher
+    pub fn should_open_recent_files(&self, event: &KeyEvent) -> bool {
+        self.recent_files.matches(event)
+    }
+    
+    /// Check if the event should undo the last agent turn's file changes
+    pub fn should_undo_turn(&self, event: &KeyEvent) -> bool {
+        self.undo_turn.matches(event)
+    }
+    
+    /// Check if the event should open the session comparison
+    pub fn should_compare_sessions(&self, event: &KeyEvent) -> bool {
+        self.compare_sessions.matches(event)
+    }
+    
+    /// Check if the event should toggle the profiler overlay
+    pub fn should_toggle_profiler(&self, event: &KeyEvent) -> bool {
+        cfg!(debug_assertions) && self.profiler.matches(event)
+    }
+    
+    /// Get help text for all key bindings, with the keys in effect
     pub fn help_text(&self) -> String {
-        format!("{}\n{}", self.quit.description, self.help.description)
+        let bindings: Vec<&KeyBinding> = self
+            .bindings()
+            .into_iter()
+            .filter(|(action, _)| *action != "profiler" || cfg!(debug_assertions))
+            .map(|(_, binding)| binding)
+            .collect();
+        let width = bindings.iter().map(|binding| binding.key_label().len()).max().unwrap_or(0);
+        bindings
+            .iter()
+            .map(|binding| format!("{:<width$}  {}", binding.key_label(), binding.description, width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(action, key)| (action.to_string(), key.to_string())).collect()
+    }
+
+    #[test]
+    fn test_overrides_rebind_actions() {
+        let key_map = KeyMap::with_overrides(&overrides(&[("quit", "ctrl+q"), ("help", "f2")])).unwrap();
+        assert!(key_map.should_quit(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+        assert!(!key_map.should_quit(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert!(key_map.help_text().lines().any(|line| line.starts_with("f2 ") && line.ends_with("Show/hide help")));
+
+        let error = KeyMap::with_overrides(&overrides(&[("jump", "ctrl+j")])).unwrap_err().to_string();
+        assert!(error.contains("Unknown action 'jump'") && error.contains("compare_sessions"), "{}", error);
+        assert!(KeyMap::with_overrides(&overrides(&[("quit", "hyper+q")])).is_err());
+    }
+
+    #[test]
+    fn test_conflicts_are_reported() {
+        assert!(KeyMap::default().conflicts().is_empty());
+        let error = KeyMap::with_overrides(&overrides(&[("undo_turn", "ctrl+p")])).unwrap_err().to_string();
+        assert_eq!(error, "Conflicting keybindings: ctrl+p: recent_files, undo_turn");
+
+        // Swapping two keys is not a conflict
+        let swapped = overrides(&[("recent_files", "alt+u"), ("undo_turn", "ctrl+p")]);
+        assert!(KeyMap::with_overrides(&swapped).is_ok());
     }
 }
\ No newline at end of file
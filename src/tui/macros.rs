@@ -0,0 +1,337 @@
+//! Keyboard macros
+//!
+//! Macros work like Vim's registers: `Alt+q` and a register character start
+//! recording the keys that follow, `Alt+q` again stops, and `Alt+e`, an
+//! optional count and the register replay them. `@` as register replays the
+//! macro played last. Recorded macros are saved under `tui.macros` in the
+//! configuration file, keys written like `ctrl+s` or `enter`, so they are
+//! there in the next session.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Most times a macro is replayed in one go
+const MAX_COUNT: u32 = 100;
+
+/// Write a key the way macros are stored, like `ctrl+s` or `shift+tab`
+pub fn key_to_string(key: &KeyEvent) -> String {
+    let mut parts = Vec::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        parts.push("ctrl".to_string());
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        parts.push("alt".to_string());
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        parts.push("shift".to_string());
+    }
+    parts.push(match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char('+') => "plus".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        other => format!("{:?}", other).to_lowercase(),
+    });
+    parts.join("+")
+}
+
+/// Read a key written by [`key_to_string`]
+pub fn parse_key(text: &str) -> Result<KeyEvent> {
+    let mut parts: Vec<&str> = text.split('+').collect();
+    let key = parts.pop().filter(|key| !key.is_empty())
+        .ok_or_else(|| anyhow!("Empty key: '{}'", text))?;
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in parts {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(anyhow!("Unknown modifier '{}' in key '{}'", modifier, text)),
+        };
+    }
+
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "plus" => KeyCode::Char('+'),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) => KeyCode::F(n),
+                None => return Err(anyhow!("Unknown key '{}'", text)),
+            },
+        },
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// What became of a key given to the recorder
+#[derive(Debug, PartialEq)]
+pub enum MacroOutcome {
+    /// Not a macro key; handle it as usual
+    Pass,
+    /// Taken by the recorder, with a message for the status bar
+    Consumed(Option<String>),
+    /// Recording stopped; the macro should be saved
+    Recorded(char, Vec<KeyEvent>),
+    /// Keys to handle as if typed
+    Replay(Vec<KeyEvent>),
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Idle,
+    /// `Alt+q` was pressed; the next key names the register
+    AwaitingRecordRegister,
+    /// `Alt+e` was pressed; digits make the count and then a key names the register
+    AwaitingReplayRegister { count: u32 },
+}
+
+/// Records and replays keyboard macros
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    macros: HashMap<char, Vec<KeyEvent>>,
+    state: State,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    last_played: Option<char>,
+}
+
+impl MacroRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load macros stored as key names, returning those that could not be read
+    pub fn load(&mut self, stored: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let mut invalid = Vec::new();
+        for (name, keys) in stored {
+            let mut chars = name.chars();
+            let (Some(register), None) = (chars.next(), chars.next()) else {
+                invalid.push(format!("macro '{}' must be named by a single character", name));
+                continue;
+            };
+            match keys.iter().map(|key| parse_key(key)).collect::<Result<Vec<_>>>() {
+                Ok(keys) => {
+                    self.macros.insert(register, keys);
+                }
+                Err(e) => invalid.push(format!("macro '{}': {}", name, e)),
+            }
+        }
+        invalid
+    }
+
+    /// Register being recorded into, if recording
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// Keys of the macro in a register
+    pub fn get(&self, register: char) -> Option<&[KeyEvent]> {
+        self.macros.get(&register).map(Vec::as_slice)
+    }
+
+    /// Handle a key, where `record` and `replay` tell whether it is one of the macro keys
+    pub fn handle_key(&mut self, key: KeyEvent, record: bool, replay: bool) -> MacroOutcome {
+        match std::mem::take(&mut self.state) {
+            State::AwaitingRecordRegister => match key.code {
+                KeyCode::Char(register) if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() => {
+                    self.recording = Some((register, Vec::new()));
+                    MacroOutcome::Consumed(Some(format!("Recording macro {}", register)))
+                }
+                _ => MacroOutcome::Consumed(Some("Macro recording cancelled".to_string())),
+            },
+            State::AwaitingReplayRegister { count } => match key.code {
+                KeyCode::Char(digit @ '0'..='9') if key.modifiers.is_empty() && (count > 0 || digit != '0') => {
+                    let count = (count * 10 + digit.to_digit(10).unwrap_or(0)).min(MAX_COUNT);
+                    self.state = State::AwaitingReplayRegister { count };
+                    MacroOutcome::Consumed(None)
+                }
+                KeyCode::Char(register) if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() => {
+                    self.replay(register, count.max(1))
+                }
+                _ => MacroOutcome::Consumed(None),
+            },
+            State::Idle if record => match self.recording.take() {
+                Some((register, keys)) => {
+                    self.macros.insert(register, keys.clone());
+                    MacroOutcome::Recorded(register, keys)
+                }
+                None => {
+                    self.state = State::AwaitingRecordRegister;
+                    MacroOutcome::Consumed(Some("Record macro into register...".to_string()))
+                }
+            },
+            State::Idle if replay => {
+                self.state = State::AwaitingReplayRegister { count: 0 };
+                MacroOutcome::Consumed(None)
+            }
+            State::Idle => {
+                if let Some((_, keys)) = self.recording.as_mut() {
+                    keys.push(key);
+                }
+                MacroOutcome::Pass
+            }
+        }
+    }
+
+    fn replay(&mut self, register: char, count: u32) -> MacroOutcome {
+        let Some(register) = (if register == '@' { self.last_played } else { Some(register) }) else {
+            return MacroOutcome::Consumed(Some("No macro played yet".to_string()));
+        };
+        // Replaying the macro being recorded would record it into itself
+        if self.recording() == Some(register) {
+            return MacroOutcome::Consumed(Some(format!("Macro {} is being recorded", register)));
+        }
+        let Some(keys) = self.macros.get(&register) else {
+            return MacroOutcome::Consumed(Some(format!("No macro in register {}", register)));
+        };
+        self.last_played = Some(register);
+        let keys: Vec<KeyEvent> = std::iter::repeat(keys).take(count as usize).flatten().copied().collect();
+        // Replayed keys become part of a macro being recorded
+        if let Some((_, recorded)) = self.recording.as_mut() {
+            recorded.extend(keys.iter().copied());
+        }
+        MacroOutcome::Replay(keys)
+    }
+}
+
+/// File macros are saved to: the configuration file in use, or the user's one
+pub fn store_path() -> Option<PathBuf> {
+    crate::config::Config::config_paths()
+        .into_iter()
+        .find(|path| path.exists())
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("goofy").join("goofy.json")))
+}
+
+/// Read the macros saved in a configuration file
+pub fn load_stored(path: &Path) -> Result<HashMap<String, Vec<String>>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    match config.pointer("/tui/macros") {
+        Some(macros) => Ok(serde_json::from_value(macros.clone())?),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Save a macro in a configuration file, keeping everything else in it
+pub fn save(path: &Path, register: char, keys: &[KeyEvent]) -> Result<()> {
+    let mut config: serde_json::Value = if path.exists() {
+        serde_json::from_str(&std::fs::read_to_string(path)?)?
+    } else {
+        serde_json::json!({})
+    };
+    let root = config.as_object_mut().ok_or_else(|| anyhow!("{} is not a JSON object", path.display()))?;
+    let tui = root.entry("tui").or_insert_with(|| serde_json::json!({}));
+    let macros = tui.as_object_mut()
+        .ok_or_else(|| anyhow!("tui in {} is not an object", path.display()))?
+        .entry("macros")
+        .or_insert_with(|| serde_json::json!({}));
+    let macros = macros.as_object_mut()
+        .ok_or_else(|| anyhow!("tui.macros in {} is not an object", path.display()))?;
+    let keys: Vec<String> = keys.iter().map(key_to_string).collect();
+    macros.insert(register.to_string(), serde_json::json!(keys));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&config)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        for text in ["a", "ctrl+s", "shift+tab", "alt+shift+A", "space", "plus", "f5", "pagedown", "enter"] {
+            assert_eq!(key_to_string(&parse_key(text).unwrap()), text);
+        }
+        assert!(parse_key("hyper+x").is_err());
+        assert!(parse_key("ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_record_and_replay_with_count() {
+        let mut recorder = MacroRecorder::new();
+        let alt_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::ALT);
+        let alt_e = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::ALT);
+
+        recorder.handle_key(alt_q, true, false);
+        recorder.handle_key(key(KeyCode::Char('a')), false, false);
+        assert_eq!(recorder.recording(), Some('a'));
+        assert_eq!(recorder.handle_key(key(KeyCode::Down), false, false), MacroOutcome::Pass);
+        assert_eq!(recorder.handle_key(key(KeyCode::Enter), false, false), MacroOutcome::Pass);
+        let recorded = vec![key(KeyCode::Down), key(KeyCode::Enter)];
+        assert_eq!(recorder.handle_key(alt_q, true, false), MacroOutcome::Recorded('a', recorded.clone()));
+
+        recorder.handle_key(alt_e, false, true);
+        recorder.handle_key(key(KeyCode::Char('3')), false, false);
+        let MacroOutcome::Replay(keys) = recorder.handle_key(key(KeyCode::Char('a')), false, false) else {
+            panic!("expected a replay");
+        };
+        assert_eq!(keys.len(), 6);
+
+        recorder.handle_key(alt_e, false, true);
+        assert_eq!(recorder.handle_key(key(KeyCode::Char('@')), false, false), MacroOutcome::Replay(recorded));
+        recorder.handle_key(alt_e, false, true);
+        assert!(matches!(recorder.handle_key(key(KeyCode::Char('b')), false, false), MacroOutcome::Consumed(Some(_))));
+    }
+
+    #[test]
+    fn test_saved_macros_load_back() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("goofy.json");
+        std::fs::write(&path, r#"{ "provider": "openai", "tui": { "icons": "ascii" } }"#).unwrap();
+
+        save(&path, 'a', &[KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL), key(KeyCode::Enter)]).unwrap();
+        let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config["provider"], "openai");
+        assert_eq!(config["tui"]["icons"], "ascii");
+
+        let mut recorder = MacroRecorder::new();
+        let invalid = recorder.load(&load_stored(&path).unwrap());
+        assert!(invalid.is_empty());
+        assert_eq!(recorder.get('a').unwrap().len(), 2);
+    }
+}
@@ -2,21 +2,31 @@
 //! This is the equivalent of the Bubble Tea TUI in the Go version
 
 mod app;
+mod capabilities;
+pub mod clipboard;
 mod components;
 mod events;
+mod external_editor;
+pub mod inline;
 mod keys;
+mod macros;
 mod pages;
 mod polish;
+pub mod profiler;
+pub mod recording;
+mod responsive;
+pub mod setup;
 mod styles;
 mod themes;
 mod utils;
 
 pub use app::App;
-pub use events::{Event, EventHandler};
+pub use capabilities::{GraphicsSupport, TerminalCapabilities};
+pub use events::{Event, EventHandler, Priority};
 pub use keys::KeyMap;
 
 use anyhow::Result;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture};
 use crossterm::execute;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
@@ -30,19 +40,27 @@ pub type Frame<'a> = ratatui::Frame<'a>;
 pub fn init_terminal() -> Result<Terminal<Backend>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
 }
 
+/// Names of the themes that come with goofy, sorted
+pub fn preset_theme_names() -> Vec<String> {
+    let mut names: Vec<String> = themes::ThemeManager::new().list_themes().into_iter().map(String::from).collect();
+    names.sort();
+    names
+}
+
 /// Restore the terminal to normal mode
 pub fn restore_terminal(terminal: &mut Terminal<Backend>) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     Ok(())
@@ -50,9 +68,27 @@ pub fn restore_terminal(terminal: &mut Terminal<Backend>) -> Result<()> {
 
 /// Main TUI entry point
 pub async fn run() -> Result<()> {
+    let config_path = macros::store_path();
+    // Fail on a bad key map before the terminal is taken over
+    let key_map = match &config_path {
+        Some(path) => KeyMap::load(path)?,
+        None => KeyMap::default(),
+    };
+    
     let mut terminal = init_terminal()?;
     let mut app = App::new().await?;
     let mut event_handler = EventHandler::new();
+    app.key_map = key_map;
+    let _theme_watcher = themes::user::themes_dir()
+        .and_then(|dir| app.load_user_themes(&dir, event_handler.sender()));
+    if let Some(path) = config_path {
+        if let Some(theme) = configured_theme(&path) {
+            if let Err(e) = app.set_theme(&theme) {
+                app.status_message = Some(e.to_string());
+            }
+        }
+        app.set_macro_store(path);
+    }
     
     let result = run_app(&mut terminal, &mut app, &mut event_handler).await;
     
@@ -60,19 +96,59 @@ pub async fn run() -> Result<()> {
     result
 }
 
+/// Theme chosen under `tui.theme` in a configuration file
+fn configured_theme(path: &std::path::Path) -> Option<String> {
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    config.pointer("/tui/theme")?.as_str().map(str::to_string)
+}
+
+/// Most input events handled in a row without drawing
+const MAX_INPUT_RUN: usize = 16;
+
 /// Main application loop
 async fn run_app(
     terminal: &mut Terminal<Backend>,
     app: &mut App,
     event_handler: &mut EventHandler,
 ) -> Result<()> {
+    let mut draw = true;
+    let mut input_run = 0;
     loop {
-        terminal.draw(|frame| app.render(frame))?;
+        if draw {
+            profiler::begin_frame();
+            terminal.draw(|frame| app.render(frame))?;
+            components::image::protocol::flush(terminal.backend_mut())?;
+            profiler::end_frame();
+            input_run = 0;
+        }
         
         if let Some(event) = event_handler.next().await {
-            if app.handle_event(event).await? {
+            let kind = event.kind();
+            let is_input = event.priority() == Priority::Input;
+            let started = std::time::Instant::now();
+            let quit = app.handle_event(event).await?;
+            profiler::record_event(kind, started);
+            profiler::record_dropped(event_handler.take_dropped());
+            if quit {
                 break; // Exit requested
             }
+            
+            // Keys typed faster than frames are drawn are handled before the
+            // next frame, though never so many that the screen stops updating
+            if is_input {
+                profiler::record_input(event_handler.take_last_input().unwrap_or(started));
+                input_run += 1;
+            }
+            draw = !(is_input && input_run < MAX_INPUT_RUN && event_handler.input_pending());
+        }
+        
+        if app.take_external_edit_request() {
+            let draft = app.draft().unwrap_or_default();
+            match external_editor::edit_draft(terminal, &draft) {
+                Ok(Some(content)) => app.set_draft(content),
+                Ok(None) => app.status_message = Some("Editor exited with an error; draft unchanged".to_string()),
+                Err(e) => app.status_message = Some(format!("External editor failed: {}", e)),
+            }
         }
     }
     Ok(())
@@ -7,6 +7,7 @@ use crate::{
             // chat::{ChatInput, ChatMessage, ChatMessageList},
             Component,
         },
+        responsive::MinSize,
         themes::Theme,
         Frame,
     },
@@ -114,6 +115,11 @@ impl Page for ChatPage {
         Ok(())
     }
     
+    fn min_size(&self) -> MinSize {
+        // A line of messages above the input box
+        MinSize::new(24, 4)
+    }
+    
     fn help_text(&self) -> Vec<(&str, &str)> {
         vec![
             ("Tab", "Switch focus"),
@@ -4,12 +4,13 @@
 // pub mod home;
 // pub mod settings;
 
-use crate::tui::{components::Component, themes::Theme, Frame};
+use crate::tui::{components::Component, responsive::MinSize, themes::Theme, Frame};
 use anyhow::Result;
 use async_trait::async_trait;
 use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::layout::Rect;
 use std::collections::HashMap;
+use std::path::Path;
 
 /// Page identifier type
 pub type PageId = String;
@@ -55,6 +56,37 @@ pub trait Page: Send + Sync {
         true
     }
     
+    /// Smallest area the page can be drawn in
+    fn min_size(&self) -> MinSize {
+        MinSize::DEFAULT
+    }
+    
+    /// Current input draft, for pages that take input
+    fn draft(&self) -> Option<String> {
+        None
+    }
+    
+    /// Replace the input draft, e.g. after editing it externally
+    fn set_draft(&mut self, _content: String) {}
+    
+    /// Insert text pasted into the terminal, for pages that take input
+    fn paste(&mut self, _text: &str) {}
+    
+    /// Check if the page has text selected, which Ctrl+C then copies
+    fn has_selection(&self) -> bool {
+        false
+    }
+    
+    /// Show a file, for pages with a file viewer
+    fn open_file(&mut self, path: &Path) -> Result<()> {
+        anyhow::bail!("{} cannot be shown on this page", path.display())
+    }
+    
+    /// Attach a file to the next prompt, for pages that take input
+    fn attach_file(&mut self, path: &Path) -> Result<()> {
+        anyhow::bail!("{} cannot be attached on this page", path.display())
+    }
+    
     /// Get page-specific help text
     fn help_text(&self) -> Vec<(&str, &str)> {
         vec![]
@@ -0,0 +1,442 @@
+//! Render profiler for finding TUI hot spots
+//!
+//! Components wrap their work in [`span`] and the profiler records how long
+//! each span took and how many allocations it made. Spans nest, and a span's
+//! time includes the spans inside it. The overlay shows the last finished frame
+//! and, for each kind of event, how long the latest one took to handle and to
+//! reach the screen. For input it also keeps the time from reading a key to
+//! drawing the frame that shows it over the last keys, and counts the ticks
+//! the event loop dropped while it was busy.
+//!
+//! Nothing is recorded while the overlay is hidden, so a span then costs a
+//! thread-local lookup. Allocations are counted by [`CountingAllocator`], which
+//! the binary installs as its global allocator when built with the
+//! `alloc-profiler` feature; they are process-wide, so work on other threads
+//! during a span is counted too.
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span as TextSpan},
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::themes::Theme;
+
+/// Spans slower than this are highlighted, a frame at 60 FPS
+const SLOW_SPAN: Duration = Duration::from_micros(16_667);
+
+/// Input events whose latency is kept
+const INPUT_SAMPLES: usize = 128;
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+/// Timing of one span in a frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanStats {
+    pub name: &'static str,
+    /// Number of spans this one is nested in
+    pub depth: usize,
+    pub duration: Duration,
+    /// Allocations made during the span, with the `alloc-profiler` feature
+    pub allocations: Option<u64>,
+}
+
+/// Timings of a whole frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameStats {
+    pub spans: Vec<SpanStats>,
+    pub duration: Duration,
+    pub allocations: Option<u64>,
+}
+
+/// Latency of the latest event of a kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventStats {
+    /// Time spent in the event handler
+    pub handling: Duration,
+    /// Time from the start of handling until the next frame was drawn
+    pub to_frame: Option<Duration>,
+}
+
+/// Time from reading input to drawing it, over the latest input events
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputLatency {
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// Number of input events measured
+    pub samples: usize,
+}
+
+#[derive(Default)]
+struct Profiler {
+    enabled: bool,
+    depth: usize,
+    frame_start: Option<(Instant, Option<u64>)>,
+    current: Vec<SpanStats>,
+    last_frame: FrameStats,
+    events: BTreeMap<&'static str, EventStats>,
+    /// Events handled since the last frame, with when handling started
+    pending_events: Vec<(&'static str, Instant)>,
+    /// When the input handled since the last frame was read
+    pending_input: Vec<Instant>,
+    input_latencies: VecDeque<Duration>,
+    dropped_events: u64,
+}
+
+/// Guard timing a span until it is dropped
+#[must_use = "the span ends when the guard is dropped"]
+pub struct Span {
+    started: Option<(usize, Instant, Option<u64>)>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some((index, started, allocations)) = self.started.take() else {
+            return;
+        };
+        let duration = started.elapsed();
+        let allocations = allocations.zip(allocation_count()).map(|(start, end)| end - start);
+        PROFILER.with(|profiler| {
+            let mut profiler = profiler.borrow_mut();
+            profiler.depth = profiler.depth.saturating_sub(1);
+            if let Some(stats) = profiler.current.get_mut(index) {
+                stats.duration = duration;
+                stats.allocations = allocations;
+            }
+        });
+    }
+}
+
+/// Time the work done until the returned guard is dropped
+pub fn span(name: &'static str) -> Span {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        if !profiler.enabled {
+            return Span { started: None };
+        }
+        let index = profiler.current.len();
+        let depth = profiler.depth;
+        profiler.current.push(SpanStats {
+            name,
+            depth,
+            duration: Duration::ZERO,
+            allocations: None,
+        });
+        profiler.depth += 1;
+        Span {
+            started: Some((index, Instant::now(), allocation_count())),
+        }
+    })
+}
+
+/// Turn recording on or off
+pub fn set_enabled(enabled: bool) {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        *profiler = Profiler {
+            enabled,
+            ..Profiler::default()
+        };
+    });
+}
+
+/// Whether the profiler is recording
+pub fn is_enabled() -> bool {
+    PROFILER.with(|profiler| profiler.borrow().enabled)
+}
+
+/// Start recording a frame
+pub fn begin_frame() {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        if profiler.enabled {
+            profiler.current.clear();
+            profiler.depth = 0;
+            profiler.frame_start = Some((Instant::now(), allocation_count()));
+        }
+    });
+}
+
+/// Finish the frame, which becomes the one shown in the overlay
+pub fn end_frame() {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        let Some((started, allocations)) = profiler.frame_start.take() else {
+            return;
+        };
+        let now = Instant::now();
+        profiler.last_frame = FrameStats {
+            spans: std::mem::take(&mut profiler.current),
+            duration: now - started,
+            allocations: allocations.zip(allocation_count()).map(|(start, end)| end - start),
+        };
+        for (kind, handled_at) in std::mem::take(&mut profiler.pending_events) {
+            if let Some(stats) = profiler.events.get_mut(kind) {
+                stats.to_frame = Some(now - handled_at);
+            }
+        }
+        for received_at in std::mem::take(&mut profiler.pending_input) {
+            if profiler.input_latencies.len() == INPUT_SAMPLES {
+                profiler.input_latencies.pop_front();
+            }
+            profiler.input_latencies.push_back(now - received_at);
+        }
+    });
+}
+
+/// Record an input event read at `received_at`, measured until it is drawn
+pub fn record_input(received_at: Instant) {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        if profiler.enabled {
+            profiler.pending_input.push(received_at);
+        }
+    });
+}
+
+/// Record events the event loop dropped
+pub fn record_dropped(count: u64) {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        if profiler.enabled {
+            profiler.dropped_events += count;
+        }
+    });
+}
+
+/// Latency of the latest input events, if any were measured
+pub fn input_latency() -> Option<InputLatency> {
+    PROFILER.with(|profiler| {
+        let mut latencies: Vec<Duration> = profiler.borrow().input_latencies.iter().copied().collect();
+        latencies.sort();
+        let at = |fraction: f64| latencies[((latencies.len() - 1) as f64 * fraction).round() as usize];
+        (!latencies.is_empty()).then(|| InputLatency {
+            median: at(0.5),
+            p95: at(0.95),
+            max: latencies[latencies.len() - 1],
+            samples: latencies.len(),
+        })
+    })
+}
+
+/// Events the event loop dropped since recording started
+pub fn dropped_events() -> u64 {
+    PROFILER.with(|profiler| profiler.borrow().dropped_events)
+}
+
+/// Record how long handling an event took
+pub fn record_event(kind: &'static str, started: Instant) {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        if !profiler.enabled {
+            return;
+        }
+        profiler.events.insert(
+            kind,
+            EventStats {
+                handling: started.elapsed(),
+                to_frame: None,
+            },
+        );
+        profiler.pending_events.push((kind, started));
+    });
+}
+
+/// The last finished frame
+pub fn last_frame() -> FrameStats {
+    PROFILER.with(|profiler| profiler.borrow().last_frame.clone())
+}
+
+/// Latest latency of each kind of event
+pub fn event_latencies() -> BTreeMap<&'static str, EventStats> {
+    PROFILER.with(|profiler| profiler.borrow().events.clone())
+}
+
+/// Lines of the overlay
+pub fn overlay_lines(theme: &Theme) -> Vec<Line<'static>> {
+    let frame = last_frame();
+    let mut lines = vec![Line::from(TextSpan::styled(
+        format!("Frame {}{}", format_duration(frame.duration), format_allocations(frame.allocations)),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+
+    for stats in &frame.spans {
+        let style = if stats.duration >= SLOW_SPAN {
+            Style::default().fg(theme.warning)
+        } else {
+            Style::default().fg(theme.fg_base)
+        };
+        lines.push(Line::from(TextSpan::styled(
+            format!(
+                "{}{:<24} {:>9}{}",
+                "  ".repeat(stats.depth + 1),
+                stats.name,
+                format_duration(stats.duration),
+                format_allocations(stats.allocations)
+            ),
+            style,
+        )));
+    }
+
+    if let Some(latency) = input_latency() {
+        let style = if latency.p95 >= SLOW_SPAN {
+            Style::default().fg(theme.warning)
+        } else {
+            Style::default().fg(theme.fg_base)
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(TextSpan::styled(
+            format!(
+                "Input to screen: {} median, {} p95, {} max ({} keys)",
+                format_duration(latency.median),
+                format_duration(latency.p95),
+                format_duration(latency.max),
+                latency.samples
+            ),
+            style,
+        )));
+        lines.push(Line::from(TextSpan::styled(
+            format!("Dropped ticks: {}", dropped_events()),
+            Style::default().fg(theme.fg_base),
+        )));
+    }
+
+    let events = event_latencies();
+    if !events.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(TextSpan::styled(
+            "Events: handled / drawn",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        for (kind, stats) in events {
+            lines.push(Line::from(TextSpan::styled(
+                format!(
+                    "  {:<24} {:>9} / {}",
+                    kind,
+                    format_duration(stats.handling),
+                    stats.to_frame.map_or("-".to_string(), format_duration)
+                ),
+                Style::default().fg(theme.fg_base),
+            )));
+        }
+    }
+    lines
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration >= Duration::from_millis(1) {
+        format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{} µs", duration.as_micros())
+    }
+}
+
+fn format_allocations(allocations: Option<u64>) -> String {
+    allocations.map_or(String::new(), |count| format!(", {} allocs", count))
+}
+
+#[cfg(feature = "alloc-profiler")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+    /// System allocator that counts allocations
+    pub struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+#[cfg(feature = "alloc-profiler")]
+pub use counting::CountingAllocator;
+
+/// Allocations made by the process so far
+#[cfg(feature = "alloc-profiler")]
+fn allocation_count() -> Option<u64> {
+    Some(counting::ALLOCATIONS.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+/// Allocations are not counted without the `alloc-profiler` feature
+#[cfg(not(feature = "alloc-profiler"))]
+fn allocation_count() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_are_recorded_per_frame_when_enabled() {
+        set_enabled(false);
+        begin_frame();
+        drop(span("hidden"));
+        end_frame();
+        assert!(last_frame().spans.is_empty());
+
+        set_enabled(true);
+        begin_frame();
+        {
+            let _page = span("page");
+            let _list = span("list");
+            let data: Vec<u64> = (0..100).collect();
+            assert_eq!(data.len(), 100);
+        }
+        let started = Instant::now();
+        record_event("key", started);
+        end_frame();
+
+        let frame = last_frame();
+        let names: Vec<_> = frame.spans.iter().map(|s| (s.name, s.depth)).collect();
+        assert_eq!(names, vec![("page", 0), ("list", 1)]);
+        assert!(frame.spans[0].duration >= frame.spans[1].duration);
+        if cfg!(feature = "alloc-profiler") {
+            assert!(frame.spans[1].allocations.unwrap() >= 1);
+        }
+        assert!(event_latencies()["key"].to_frame.is_some());
+        set_enabled(false);
+    }
+
+    #[test]
+    fn test_input_latency_is_summarized() {
+        set_enabled(true);
+        assert!(input_latency().is_none());
+        let now = Instant::now();
+        for millis in [1, 2, 3, 4, 40] {
+            record_input(now - Duration::from_millis(millis));
+        }
+        record_dropped(3);
+        end_frame();
+        // Input is measured at the end of a frame that was begun
+        assert!(input_latency().is_none());
+
+        begin_frame();
+        end_frame();
+        let latency = input_latency().unwrap();
+        assert_eq!(latency.samples, 5);
+        assert!(latency.median >= Duration::from_millis(3) && latency.median < Duration::from_millis(40));
+        assert!(latency.max >= Duration::from_millis(40));
+        assert_eq!(dropped_events(), 3);
+        set_enabled(false);
+    }
+}
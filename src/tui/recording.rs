@@ -0,0 +1,325 @@
+//! Recording terminal sessions as asciicast files
+//!
+//! A recording is an asciicast v2 file, which asciinema and its players read:
+//! a JSON header line, then a JSON array per event holding the seconds since
+//! the start, the event type and its data. Everything written to the terminal
+//! is an `o` event, one per rendered frame, a change of the terminal size an
+//! `r` event, and each app event an `m` marker holding the event as JSON.
+//! Players skip markers; `goofy replay --events` lists them.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+use crate::app::AppEvent;
+
+/// Environment variables kept in the header, for players picking a shell
+/// and colors
+const HEADER_ENV: &[&str] = &["SHELL", "TERM"];
+
+/// First line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    /// Seconds since the epoch at the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+/// An event of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "(f64, String, String)", into = "(f64, String, String)")]
+pub struct CastEvent {
+    /// Seconds since the start
+    pub time: f64,
+    /// `o` for output, `r` for a resize to `WIDTHxHEIGHT`, `m` for a marker
+    pub kind: String,
+    pub data: String,
+}
+
+impl From<(f64, String, String)> for CastEvent {
+    fn from((time, kind, data): (f64, String, String)) -> Self {
+        Self { time, kind, data }
+    }
+}
+
+impl From<CastEvent> for (f64, String, String) {
+    fn from(event: CastEvent) -> Self {
+        (event.time, event.kind, event.data)
+    }
+}
+
+/// A recording read back
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub header: CastHeader,
+    pub events: Vec<CastEvent>,
+}
+
+impl Cast {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("{} is not an asciicast v2 recording", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, first)) = lines.next() else {
+            bail!("The recording is empty");
+        };
+        let header: CastHeader = serde_json::from_str(first).context("Invalid header")?;
+        if header.version != 2 {
+            bail!("Unsupported asciicast version {}", header.version);
+        }
+        let events = lines
+            .map(|(index, line)| serde_json::from_str(line).with_context(|| format!("Invalid event on line {}", index + 1)))
+            .collect::<Result<Vec<CastEvent>>>()?;
+        Ok(Self { header, events })
+    }
+
+    /// Seconds to wait before each event, pauses longer than `idle_limit`
+    /// shortened to it
+    pub fn gaps(&self, idle_limit: Option<f64>) -> Vec<f64> {
+        let mut previous = 0.0;
+        self.events
+            .iter()
+            .map(|event| {
+                let gap = (event.time - previous).max(0.0);
+                previous = event.time.max(previous);
+                idle_limit.map_or(gap, |limit| gap.min(limit))
+            })
+            .collect()
+    }
+
+    /// The recorded app events with their time
+    pub fn markers(&self) -> impl Iterator<Item = &CastEvent> {
+        self.events.iter().filter(|event| event.kind == "m")
+    }
+}
+
+struct RecorderState {
+    out: Box<dyn Write + Send>,
+    started: Instant,
+    size: (u16, u16),
+    /// Output since the last frame, ending with at most an incomplete
+    /// UTF-8 sequence once a frame is written
+    pending: Vec<u8>,
+    failed: bool,
+}
+
+impl RecorderState {
+    fn write_event(&mut self, kind: &str, data: String) {
+        if self.failed {
+            return;
+        }
+        let event = CastEvent {
+            time: (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6,
+            kind: kind.to_string(),
+            data,
+        };
+        let written = serde_json::to_string(&event)
+            .map_err(io::Error::from)
+            .and_then(|line| writeln!(self.out, "{}", line))
+            .and_then(|_| self.out.flush());
+        // A full disk ends the recording, not the session
+        if let Err(e) = written {
+            debug!("Recording stopped: {}", e);
+            self.failed = true;
+        }
+    }
+}
+
+/// Writes a recording as the session goes
+#[derive(Clone)]
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl Recorder {
+    /// Start a recording of a terminal of `width` by `height` in a new file
+    pub fn create(path: &Path, width: u16, height: u16, title: Option<String>) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("Could not create {}", path.display()))?;
+        Self::new(BufWriter::new(file), width, height, title)
+    }
+
+    pub fn new(mut out: impl Write + Send + 'static, width: u16, height: u16, title: Option<String>) -> Result<Self> {
+        let header = CastHeader {
+            version: 2,
+            width,
+            height,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|since| since.as_secs()),
+            title,
+            env: HEADER_ENV
+                .iter()
+                .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+                .collect(),
+        };
+        writeln!(out, "{}", serde_json::to_string(&header)?)?;
+        out.flush()?;
+        Ok(Self {
+            state: Arc::new(Mutex::new(RecorderState {
+                out: Box::new(out),
+                started: Instant::now(),
+                size: (width, height),
+                pending: Vec::new(),
+                failed: false,
+            })),
+        })
+    }
+
+    /// Bytes written to the terminal
+    pub fn output(&self, bytes: &[u8]) {
+        if let Ok(mut state) = self.state.lock() {
+            state.pending.extend_from_slice(bytes);
+        }
+    }
+
+    /// Record the output since the last frame as one event
+    pub fn frame(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let complete = match std::str::from_utf8(&state.pending) {
+            Ok(_) => state.pending.len(),
+            // A character split across writes waits for its other bytes
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => state.pending.len(),
+        };
+        if complete == 0 {
+            return;
+        }
+        let rest = state.pending.split_off(complete);
+        let data = String::from_utf8_lossy(&std::mem::replace(&mut state.pending, rest)).into_owned();
+        state.write_event("o", data);
+    }
+
+    /// Record the terminal's size when it changed
+    pub fn resize(&self, width: u16, height: u16) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.size != (width, height) {
+            state.size = (width, height);
+            state.write_event("r", format!("{}x{}", width, height));
+        }
+    }
+
+    /// Record an app event as a marker
+    pub fn event(&self, event: &AppEvent) {
+        let Ok(data) = serde_json::to_string(event) else {
+            return;
+        };
+        if let Ok(mut state) = self.state.lock() {
+            state.write_event("m", data);
+        }
+    }
+}
+
+/// A terminal writer copying what is written to a recorder, if any
+pub struct Recorded<W> {
+    inner: W,
+    recorder: Option<Recorder>,
+}
+
+impl<W> Recorded<W> {
+    pub fn new(inner: W, recorder: Option<Recorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl<W: Write> Write for Recorded<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.output(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    /// Terminal backends flush once per frame, which makes it an event
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(recorder) = &self.recorder {
+            recorder.frame();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file shared with the recorder writing it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_recordings_read_back() {
+        let file = Shared::default();
+        let recorder = Recorder::new(file.clone(), 80, 24, Some("Inline session".to_string())).unwrap();
+        let mut terminal = Recorded::new(Vec::new(), Some(recorder.clone()));
+
+        let snowman = "☃".as_bytes();
+        terminal.write_all(b"\x1b[1mHello ").unwrap();
+        terminal.write_all(&snowman[..1]).unwrap();
+        terminal.flush().unwrap();
+        terminal.write_all(&snowman[1..]).unwrap();
+        terminal.flush().unwrap();
+        terminal.flush().unwrap();
+        recorder.resize(80, 24);
+        recorder.resize(100, 30);
+        recorder.event(&AppEvent::SessionCreated { session_id: "s1".to_string() });
+
+        let content = String::from_utf8(file.0.lock().unwrap().clone()).unwrap();
+        let cast = Cast::parse(&content).unwrap();
+        assert_eq!((cast.header.width, cast.header.height), (80, 24));
+        assert_eq!(cast.header.title.as_deref(), Some("Inline session"));
+        let events: Vec<(&str, &str)> = cast.events.iter().map(|event| (event.kind.as_str(), event.data.as_str())).collect();
+        assert_eq!(
+            events,
+            [
+                ("o", "\x1b[1mHello "),
+                ("o", "☃"),
+                ("r", "100x30"),
+                ("m", r#"{"type":"session_created","session_id":"s1"}"#),
+            ]
+        );
+        assert_eq!(terminal.inner, "\x1b[1mHello ☃".as_bytes());
+    }
+
+    #[test]
+    fn test_long_pauses_are_shortened() {
+        let content = "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"o\", \"a\"]\n\n[10.5, \"o\", \"b\"]\n[11.0, \"m\", \"{}\"]\n";
+        let cast = Cast::parse(content).unwrap();
+        assert_eq!(cast.gaps(None), [0.5, 10.0, 0.5]);
+        assert_eq!(cast.gaps(Some(2.0)), [0.5, 2.0, 0.5]);
+        assert_eq!(cast.markers().count(), 1);
+
+        assert!(Cast::parse("{\"version\": 1, \"width\": 80, \"height\": 24}").is_err());
+        assert!(Cast::parse("{\"version\": 2, \"width\": 80, \"height\": 24}\n[1.0, \"o\"]").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! Layouts for small terminals
+//!
+//! Pages pick a [`LayoutTier`] from the area they are given. At the full tier
+//! everything is shown, the compact tier collapses side panels and renders
+//! messages densely, and the minimal tier keeps only what is needed to read and
+//! type. Each page also declares the smallest size it can work in at all; below
+//! that the app shows a "terminal too small" notice instead of drawing garbage.
+
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Style,
+    widgets::{Paragraph, Wrap},
+};
+
+use super::{themes::Theme, Frame};
+
+/// How much of a page fits in the terminal, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LayoutTier {
+    /// Only the essentials, without side panels or headers
+    Minimal,
+    /// Side panels collapsed and dense rendering
+    Compact,
+    /// Everything shown
+    Full,
+}
+
+impl LayoutTier {
+    /// Smallest size that gets the full layout
+    pub const FULL: MinSize = MinSize::new(100, 24);
+    /// Smallest size that gets the compact layout
+    pub const COMPACT: MinSize = MinSize::new(60, 15);
+
+    /// The tier for an area
+    pub fn for_area(area: Rect) -> Self {
+        if Self::FULL.fits(area) {
+            LayoutTier::Full
+        } else if Self::COMPACT.fits(area) {
+            LayoutTier::Compact
+        } else {
+            LayoutTier::Minimal
+        }
+    }
+}
+
+/// Smallest size a page can be drawn at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl MinSize {
+    /// Fallback for pages that don't declare their own
+    pub const DEFAULT: MinSize = MinSize::new(20, 5);
+
+    pub const fn new(width: u16, height: u16) -> Self {
+        Self { width, height }
+    }
+
+    /// Check if an area is at least this size
+    pub fn fits(&self, area: Rect) -> bool {
+        area.width >= self.width && area.height >= self.height
+    }
+}
+
+/// Tell the user the terminal needs to grow, in whatever space there is
+pub fn render_too_small(frame: &mut Frame, area: Rect, needed: MinSize, theme: &Theme) {
+    let text = format!(
+        "Terminal too small\n{}x{}, need {}x{}",
+        area.width, area.height, needed.width, needed.height
+    );
+    let top = area.height.saturating_sub(2) / 2;
+    let message_area = Rect::new(area.x, area.y + top, area.width, area.height - top);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(theme.warning)),
+        message_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiers_shrink_with_the_terminal() {
+        assert_eq!(LayoutTier::for_area(Rect::new(0, 0, 120, 40)), LayoutTier::Full);
+        assert_eq!(LayoutTier::for_area(Rect::new(0, 0, 80, 24)), LayoutTier::Compact);
+        assert_eq!(LayoutTier::for_area(Rect::new(0, 0, 120, 20)), LayoutTier::Compact);
+        assert_eq!(LayoutTier::for_area(Rect::new(0, 0, 40, 10)), LayoutTier::Minimal);
+        assert!(LayoutTier::Minimal < LayoutTier::Full);
+
+        assert!(MinSize::DEFAULT.fits(Rect::new(0, 0, 40, 10)));
+        assert!(!MinSize::DEFAULT.fits(Rect::new(0, 0, 19, 10)));
+    }
+}
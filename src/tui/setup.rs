@@ -0,0 +1,127 @@
+//! Running the first-run setup before the chat starts
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::components::splash::{Setup, SetupWizard, WizardAction};
+use super::{themes, Backend, Event, EventHandler};
+use crate::app::App;
+use crate::config::credentials::{self, KeyStorage};
+use crate::config::{paths::display_path, Config};
+use crate::llm::{types::ChatRequest, Message};
+use ratatui::Terminal;
+
+/// How long the provider has to answer the check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Set up a provider on the splash screen
+///
+/// Returns the configuration with the provider, model and key chosen, once
+/// the provider answered and the choice was saved, or `None` when the user
+/// quit.
+pub async fn run(config: &Config) -> Result<Option<Config>> {
+    let mut terminal = super::init_terminal()?;
+    let result = run_wizard(&mut terminal, config).await;
+    super::restore_terminal(&mut terminal)?;
+    result
+}
+
+async fn run_wizard(terminal: &mut Terminal<Backend>, config: &Config) -> Result<Option<Config>> {
+    let theme = themes::current_theme();
+    let config_path = credentials::user_config_path().context("No configuration directory to save the setup in")?;
+    let mut wizard = SetupWizard::new(env!("CARGO_PKG_VERSION"), display_path(&config_path), |name| {
+        std::env::var(name).is_ok_and(|value| !value.is_empty())
+    });
+    let mut events = EventHandler::new();
+    let mut check: Option<(Setup, Config, JoinHandle<Result<()>>)> = None;
+
+    loop {
+        terminal.draw(|frame| wizard.render(frame, &theme))?;
+
+        if check.as_ref().is_some_and(|(_, _, handle)| handle.is_finished()) {
+            if let Some((setup, configured, handle)) = check.take() {
+                let saved = match handle.await? {
+                    Ok(()) => save(&setup),
+                    Err(e) => Err(e),
+                };
+                match saved {
+                    Ok(()) => return Ok(Some(configured)),
+                    Err(e) => wizard.fail(format!("{:#}", e)),
+                }
+                continue;
+            }
+        }
+
+        let action = match events.next().await {
+            Some(Event::Key(key)) => wizard.handle_key(key),
+            Some(Event::Custom(kind, serde_json::Value::String(text))) if kind == "paste" => {
+                wizard.paste(&text);
+                continue;
+            }
+            Some(Event::Resize(_, _)) => {
+                terminal.autoresize()?;
+                continue;
+            }
+            Some(Event::Tick) => {
+                wizard.tick();
+                continue;
+            }
+            _ => continue,
+        };
+        match action {
+            WizardAction::None => {}
+            WizardAction::Quit => return Ok(None),
+            WizardAction::CancelCheck => {
+                if let Some((_, _, handle)) = check.take() {
+                    handle.abort();
+                }
+            }
+            WizardAction::Check(setup) => {
+                let mut configured = config.clone();
+                configured.switch_model(&setup.provider, &setup.model);
+                if let Some(key) = &setup.api_key {
+                    configured.api_key = Some(key.clone());
+                }
+                let checked = configured.clone();
+                check = Some((setup, configured, tokio::spawn(async move { check_provider(&checked).await })));
+            }
+        }
+    }
+}
+
+/// Send the provider a short request, to find a wrong key or model before
+/// the chat does
+async fn check_provider(config: &Config) -> Result<()> {
+    let provider = App::create_provider(config)?;
+    let request = ChatRequest {
+        messages: vec![Message::new_user("Reply with OK.".to_string())],
+        tools: Vec::new(),
+        system_message: None,
+        max_tokens: Some(16),
+        temperature: None,
+        top_p: None,
+        stream: false,
+        metadata: HashMap::new(),
+    };
+    tokio::time::timeout(CHECK_TIMEOUT, provider.chat_completion(request))
+        .await
+        .map_err(|_| anyhow!("No answer within {} seconds", CHECK_TIMEOUT.as_secs()))??;
+    Ok(())
+}
+
+/// Keep the key where the user asked and make the provider and model the
+/// default
+fn save(setup: &Setup) -> Result<()> {
+    let path = credentials::user_config_path().context("No configuration directory to save the setup in")?;
+    let file_key = match (&setup.api_key, setup.storage) {
+        (Some(key), KeyStorage::Keychain) => {
+            credentials::save_to_keychain(&setup.provider, key)?;
+            None
+        }
+        (Some(key), KeyStorage::ConfigFile) => Some(key.as_str()),
+        (None, _) => None,
+    };
+    credentials::save_setup(&path, &setup.provider, &setup.model, file_key)
+}
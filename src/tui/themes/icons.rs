@@ -0,0 +1,219 @@
+//! Icon sets for Goofy TUI
+//!
+//! Icons come in three flavours: color emoji, Nerd Font glyphs and plain
+//! ASCII. Emoji and Nerd Font glyphs render as empty boxes on terminals or
+//! fonts without them, so components take their icons from the theme's
+//! `IconSet` instead of hardcoding glyphs, and the set is picked per terminal.
+
+use std::path::Path;
+
+/// Kind of glyphs an icon set is made of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IconStyle {
+    #[default]
+    Emoji,
+    NerdFont,
+    Ascii,
+}
+
+/// Icon set for different UI elements
+#[derive(Debug, Clone)]
+pub struct IconSet {
+    pub style: IconStyle,
+
+    // Navigation icons
+    pub folder_open: String,
+    pub folder_closed: String,
+    pub file: String,
+    pub session: String,
+    pub home: String,
+    pub branch: String,
+
+    // Chat icons
+    pub user: String,
+    pub assistant: String,
+    pub system: String,
+    pub tool: String,
+    pub attachment: String,
+    pub image: String,
+    pub link: String,
+    pub thinking: String,
+
+    // Status icons
+    pub success: String,
+    pub error: String,
+    pub warning: String,
+    pub info: String,
+    pub loading: String,
+    pub locked: String,
+    pub blocked: String,
+
+    // Action icons
+    pub copy: String,
+    pub edit: String,
+    pub delete: String,
+    pub search: String,
+    pub settings: String,
+    pub help: String,
+
+    // Arrows and indicators
+    pub arrow_right: String,
+    pub arrow_down: String,
+    pub arrow_up: String,
+    pub arrow_left: String,
+    pub bullet: String,
+    pub checkmark: String,
+}
+
+impl IconSet {
+    /// Icon set of the given style
+    pub fn for_style(style: IconStyle) -> Self {
+        match style {
+            IconStyle::Emoji => Self::emoji(),
+            IconStyle::NerdFont => Self::nerd_font(),
+            IconStyle::Ascii => Self::ascii(),
+        }
+    }
+
+    /// Color emoji, as most modern terminals draw them
+    pub fn emoji() -> Self {
+        Self::from_glyphs(
+            IconStyle::Emoji,
+            [
+                "📂", "📁", "📄", "💬", "🏠", "🌿",
+                "👤", "🤖", "⚙️", "🔧", "📎", "🖼️", "🌐", "🤔",
+                "✅", "❌", "⚠️", "ℹ️", "⏳", "🔒", "🚫",
+                "📋", "✏️", "🗑️", "🔍", "⚙️", "❓",
+                "→", "↓", "↑", "←", "•", "✓",
+            ],
+        )
+    }
+
+    /// Glyphs of a patched Nerd Font
+    pub fn nerd_font() -> Self {
+        Self::from_glyphs(
+            IconStyle::NerdFont,
+            [
+                "\u{f07c}", "\u{f07b}", "\u{f15b}", "\u{f086}", "\u{f015}", "\u{e725}",
+                "\u{f007}", "\u{f2db}", "\u{f013}", "\u{f0ad}", "\u{f0c6}", "\u{f03e}", "\u{f0ac}", "\u{f0eb}",
+                "\u{f00c}", "\u{f00d}", "\u{f071}", "\u{f05a}", "\u{f110}", "\u{f023}", "\u{f05e}",
+                "\u{f0c5}", "\u{f044}", "\u{f1f8}", "\u{f002}", "\u{f013}", "\u{f059}",
+                "\u{f061}", "\u{f063}", "\u{f062}", "\u{f060}", "\u{f111}", "\u{f00c}",
+            ],
+        )
+    }
+
+    /// Plain ASCII, readable on any terminal
+    pub fn ascii() -> Self {
+        Self::from_glyphs(
+            IconStyle::Ascii,
+            [
+                "v", ">", "-", "#", "~", "git",
+                ">", "*", "#", "$", "@", "[img]", "[url]", "...",
+                "+", "x", "!", "i", "~", "[ro]", "[!]",
+                "c", "e", "d", "/", "=", "?",
+                "->", "v", "^", "<-", "*", "x",
+            ],
+        )
+    }
+
+    fn from_glyphs(style: IconStyle, glyphs: [&str; 33]) -> Self {
+        let [folder_open, folder_closed, file, session, home, branch,
+             user, assistant, system, tool, attachment, image, link, thinking,
+             success, error, warning, info, loading, locked, blocked,
+             copy, edit, delete, search, settings, help,
+             arrow_right, arrow_down, arrow_up, arrow_left, bullet, checkmark] = glyphs.map(str::to_string);
+        Self {
+            style,
+            folder_open, folder_closed, file, session, home, branch,
+            user, assistant, system, tool, attachment, image, link, thinking,
+            success, error, warning, info, loading, locked, blocked,
+            copy, edit, delete, search, settings, help,
+            arrow_right, arrow_down, arrow_up, arrow_left, bullet, checkmark,
+        }
+    }
+
+    /// Icon of a file, by its extension
+    pub fn file_icon(&self, path: impl AsRef<Path>) -> &str {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let glyph = match (self.style, extension.as_deref()) {
+            (IconStyle::Ascii, _) => None,
+            (IconStyle::Emoji, Some(extension)) => match extension {
+                "rs" => Some("🦀"),
+                "go" => Some("🐹"),
+                "py" => Some("🐍"),
+                "js" => Some("🟨"),
+                "ts" => Some("📜"),
+                "html" => Some("🌐"),
+                "md" => Some("📝"),
+                "json" => Some("📋"),
+                "pdf" => Some("📕"),
+                "zip" | "tar" | "gz" => Some("📦"),
+                _ => None,
+            },
+            (IconStyle::NerdFont, Some(extension)) => match extension {
+                "rs" => Some("\u{e7a8}"),
+                "go" => Some("\u{e626}"),
+                "py" => Some("\u{e73c}"),
+                "js" => Some("\u{e74e}"),
+                "ts" => Some("\u{e628}"),
+                "html" => Some("\u{e736}"),
+                "md" => Some("\u{e73e}"),
+                "json" => Some("\u{e60b}"),
+                "pdf" => Some("\u{f1c1}"),
+                "zip" | "tar" | "gz" => Some("\u{f1c6}"),
+                _ => None,
+            },
+            (_, None) => None,
+        };
+        if let Some(glyph) = glyph {
+            return glyph;
+        }
+        match extension.as_deref() {
+            Some("png" | "jpg" | "jpeg" | "gif" | "svg" | "webp") => &self.image,
+            _ => &self.file,
+        }
+    }
+
+    /// Icon of a directory entry
+    pub fn entry_icon(&self, path: impl AsRef<Path>, is_directory: bool, is_expanded: bool) -> &str {
+        match (is_directory, is_expanded) {
+            (true, true) => &self.folder_open,
+            (true, false) => &self.folder_closed,
+            (false, _) => self.file_icon(path),
+        }
+    }
+}
+
+impl Default for IconSet {
+    fn default() -> Self {
+        Self::emoji()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_set_is_plain_ascii() {
+        let icons = IconSet::ascii();
+        assert_eq!(icons.file_icon("main.rs"), icons.file);
+        assert_eq!(icons.file_icon("logo.PNG"), "[img]");
+        assert_eq!(icons.entry_icon("src", true, false), ">");
+        let debug = format!("{:?}", icons);
+        assert!(debug.is_ascii());
+    }
+
+    #[test]
+    fn test_file_icons_by_style() {
+        assert_eq!(IconSet::emoji().file_icon("main.rs"), "🦀");
+        assert_eq!(IconSet::emoji().file_icon("notes.xyz"), "📄");
+        assert_eq!(IconSet::nerd_font().file_icon("lib.RS"), "\u{e7a8}");
+        assert_eq!(IconSet::for_style(IconStyle::NerdFont).style, IconStyle::NerdFont);
+    }
+}
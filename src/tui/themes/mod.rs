@@ -6,8 +6,16 @@
 //! Based on the Charmbracelet Crush theme architecture, this module
 //! provides a complete theming solution with semantic color definitions,
 //! pre-built component styles, and theme management.
+//!
+//! The themes are shared through [`SharedThemes`], a handle to one
+//! [`ThemeManager`] behind a lock that components clone. Reading the current
+//! theme hands out a snapshot that stays valid while it is used, and every
+//! change bumps a generation that components subscribe to, so a component
+//! caching anything built from the theme knows when to rebuild it.
 
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard};
+use tokio::sync::watch;
 use ratatui::style::{Color, Style, Modifier};
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
@@ -15,6 +23,10 @@ use anyhow::Result;
 pub mod colors;
 pub mod styles;
 pub mod presets;
+pub mod icons;
+pub mod user;
+
+pub use icons::{IconSet, IconStyle};
 
 /// Theme represents a complete visual style configuration
 /// 
@@ -67,6 +79,9 @@ pub struct Theme {
     pub red_light: Color,
     pub cherry: Color,
     
+    // Glyphs for icons, chosen for the terminal rather than the palette
+    pub icons: IconSet,
+    
     // Cached styles - built lazily
     styles: Option<Styles>,
 }
@@ -188,46 +203,6 @@ pub struct MarkdownStyles {
     pub table_cell: Style,
 }
 
-/// Icon set for different UI elements
-#[derive(Debug, Clone)]
-pub struct IconSet {
-    // Navigation icons
-    pub folder_open: String,
-    pub folder_closed: String,
-    pub file: String,
-    pub session: String,
-    
-    // Chat icons
-    pub user: String,
-    pub assistant: String,
-    pub system: String,
-    pub tool: String,
-    pub attachment: String,
-    
-    // Status icons
-    pub success: String,
-    pub error: String,
-    pub warning: String,
-    pub info: String,
-    pub loading: String,
-    
-    // Action icons
-    pub copy: String,
-    pub edit: String,
-    pub delete: String,
-    pub search: String,
-    pub settings: String,
-    pub help: String,
-    
-    // Arrows and indicators
-    pub arrow_right: String,
-    pub arrow_down: String,
-    pub arrow_up: String,
-    pub arrow_left: String,
-    pub bullet: String,
-    pub checkmark: String,
-}
-
 /// Animation configuration
 #[derive(Debug, Clone)]
 pub struct AnimationConfig {
@@ -406,6 +381,13 @@ impl ThemeManager {
     pub fn get_theme(&self, name: &str) -> Option<&Theme> {
         self.themes.get(name)
     }
+    
+    /// Use the given icon glyphs in every theme
+    pub fn set_icon_style(&mut self, style: IconStyle) {
+        for theme in self.themes.values_mut() {
+            theme.icons = IconSet::for_style(style);
+        }
+    }
 }
 
 impl Default for ThemeManager {
@@ -414,41 +396,108 @@ impl Default for ThemeManager {
     }
 }
 
-// Global theme manager instance
-static mut GLOBAL_THEME_MANAGER: Option<ThemeManager> = None;
-static INIT: std::sync::Once = std::sync::Once::new();
+/// Shared access to the themes, safe to use from any thread
+///
+/// Clones share the same themes. Changes go through [`SharedThemes::update`],
+/// which takes a new snapshot of the current theme and notifies subscribers.
+#[derive(Clone)]
+pub struct SharedThemes {
+    manager: Arc<RwLock<ThemeManager>>,
+    /// The current theme with its styles built, replaced on every change
+    current: Arc<RwLock<Arc<Theme>>>,
+    /// Generation of the themes, bumped on every change
+    changes: Arc<watch::Sender<u64>>,
+}
 
-/// Get the global theme manager
-pub fn theme_manager() -> &'static mut ThemeManager {
-    unsafe {
-        INIT.call_once(|| {
-            GLOBAL_THEME_MANAGER = Some(ThemeManager::new());
-        });
-        GLOBAL_THEME_MANAGER.as_mut().unwrap()
+impl SharedThemes {
+    /// Share the themes of a manager
+    pub fn new(manager: ThemeManager) -> Self {
+        let current = Arc::new(RwLock::new(Self::snapshot(&manager)));
+        let (changes, _) = watch::channel(0);
+        Self {
+            manager: Arc::new(RwLock::new(manager)),
+            current,
+            changes: Arc::new(changes),
+        }
+    }
+    
+    fn snapshot(manager: &ThemeManager) -> Arc<Theme> {
+        let mut theme = manager.current_theme().clone();
+        theme.styles();
+        Arc::new(theme)
+    }
+    
+    /// The current theme
+    pub fn current(&self) -> Arc<Theme> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// Read the themes
+    pub fn read(&self) -> RwLockReadGuard<'_, ThemeManager> {
+        self.manager.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Change the themes and tell subscribers about it
+    pub fn update<R>(&self, change: impl FnOnce(&mut ThemeManager) -> R) -> R {
+        let mut manager = self.manager.write().unwrap_or_else(|e| e.into_inner());
+        let result = change(&mut manager);
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Self::snapshot(&manager);
+        drop(manager);
+        self.changes.send_modify(|generation| *generation += 1);
+        result
+    }
+    
+    /// Switch to a theme by name
+    pub fn set_theme(&self, name: &str) -> Result<()> {
+        self.update(|manager| manager.set_theme(name)).map_err(|e| anyhow::anyhow!(e))
+    }
+    
+    /// Get notified of changes to the themes
+    ///
+    /// The receiver sees the generation of the themes; `has_changed` tells a
+    /// component to rebuild what it cached from the theme.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+    
+    /// Generation of the themes, bumped on every change
+    pub fn generation(&self) -> u64 {
+        *self.changes.borrow()
     }
 }
 
-/// Get the current theme (mutable reference for style building)
-pub fn current_theme_mut() -> &'static mut Theme {
-    theme_manager().current_theme_mut()
+impl Default for SharedThemes {
+    fn default() -> Self {
+        shared().clone()
+    }
 }
 
-/// Get the current theme (immutable reference)
-pub fn current_theme() -> &'static Theme {
-    theme_manager().current_theme()
+impl std::fmt::Debug for SharedThemes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedThemes")
+            .field("current", &self.current().name)
+            .field("generation", &self.generation())
+            .finish()
+    }
 }
 
-/// Set the current theme
-pub fn set_current_theme(name: &str) -> Result<()> {
-    theme_manager().set_theme(name)
-        .map_err(|e| anyhow::anyhow!(e))
+/// Themes of the application
+static SHARED_THEMES: OnceLock<SharedThemes> = OnceLock::new();
+
+/// The application's themes, for components not handed a [`SharedThemes`]
+pub fn shared() -> &'static SharedThemes {
+    SHARED_THEMES.get_or_init(|| SharedThemes::new(ThemeManager::new()))
 }
 
-/// Get styles for the current theme (builds them if necessary)
-pub fn current_styles() -> &'static Styles {
-    current_theme_mut().styles()
+/// Get the current theme
+pub fn current_theme() -> Arc<Theme> {
+    shared().current()
 }
 
+/// Set the current theme
+pub fn set_current_theme(name: &str) -> Result<()> {
+    shared().set_theme(name)
+}
 
 /// Utility functions for theme operations
 pub mod utils {
@@ -551,4 +600,23 @@ mod tests {
             panic!("Expected RGB color");
         }
     }
+    
+    #[test]
+    fn test_shared_themes_notify_changes() {
+        let themes = SharedThemes::new(ThemeManager::new());
+        let mut changes = themes.subscribe();
+        let before = themes.current();
+        assert_eq!(before.name, "goofy_dark");
+        
+        let clone = themes.clone();
+        std::thread::spawn(move || clone.set_theme("goofy_light").unwrap()).join().unwrap();
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(*changes.borrow_and_update(), 1);
+        assert_eq!(themes.current().name, "goofy_light");
+        // Snapshots taken before the change stay as they were
+        assert_eq!(before.name, "goofy_dark");
+        
+        assert!(themes.set_theme("nonexistent").is_err());
+        assert_eq!(themes.current().name, "goofy_light");
+    }
 }
\ No newline at end of file
@@ -3,7 +3,7 @@
 //! This module provides pre-built themes including the default "Goofy" theme
 //! based on the Charmbracelet color palette, as well as classic light and dark themes.
 
-use super::Theme;
+use super::{IconSet, Theme};
 use ratatui::style::Color;
 
 /// Create the default Goofy dark theme
@@ -58,6 +58,7 @@ pub fn goofy_dark() -> Theme {
         red_light: Color::Rgb(0xFF, 0xAB, 0x91), // Salmon - light red
         cherry: Color::Rgb(0xE9, 0x1E, 0x63),    // Cherry - accent red
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
@@ -110,6 +111,7 @@ pub fn goofy_light() -> Theme {
         red_light: Color::Rgb(0xFF, 0xCF, 0xD1), // Light red
         cherry: Color::Rgb(0xC2, 0x18, 0x5B),    // Dark cherry
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
@@ -162,6 +164,7 @@ pub fn classic_dark() -> Theme {
         red_light: Color::LightRed,
         cherry: Color::Magenta,
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
@@ -214,6 +217,7 @@ pub fn classic_light() -> Theme {
         red_light: Color::LightRed,
         cherry: Color::Rgb(0x80, 0x00, 0x80),
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
@@ -266,6 +270,7 @@ pub fn high_contrast() -> Theme {
         red_light: Color::LightRed,
         cherry: Color::LightMagenta,
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
@@ -318,6 +323,7 @@ pub fn monochrome() -> Theme {
         red_light: Color::LightGray,
         cherry: Color::Gray,
         
+        icons: IconSet::default(),
         styles: None, // Built lazily
     }
 }
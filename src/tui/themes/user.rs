@@ -0,0 +1,272 @@
+//! Themes written by the user
+//!
+//! Every `.toml` or `.json` file in the themes directory
+//! (`~/.config/goofy/themes` on Linux) defines a theme. A file names the theme,
+//! says whether it is dark and sets colors by the names of the [`Theme`]
+//! fields, as hex values:
+//!
+//! ```toml
+//! name = "dusk"
+//! dark = true
+//! extends = "goofy_dark"
+//!
+//! [colors]
+//! primary = "#c678dd"
+//! bg_base = "#1e2127"
+//! ```
+//!
+//! The colors in [`REQUIRED_COLORS`] have to be set; the rest come from the
+//! theme the file extends, `goofy_dark` or `goofy_light` by default. Theme
+//! files are watched while the interface runs, and a saved change is applied
+//! right away, so a theme can be tuned while looking at it.
+
+use anyhow::{anyhow, bail, Context, Result};
+use notify::{recommended_watcher, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::{colors::{accessibility, convert}, Theme, ThemeManager};
+use crate::tui::events::Event;
+
+/// Custom event sent with the path of a theme file that changed
+pub const THEME_CHANGED_EVENT: &str = "theme_changed";
+
+/// Colors every theme file has to set
+pub const REQUIRED_COLORS: &[&str] = &[
+    "primary", "secondary", "accent", "bg_base", "fg_base", "fg_muted",
+    "border", "success", "error", "warning", "info",
+];
+
+/// A theme as written in a file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    /// Theme name, the file name without extension if unset
+    name: Option<String>,
+    /// Whether the theme is dark, guessed from `bg_base` if unset
+    dark: Option<bool>,
+    /// Theme the unset colors come from
+    extends: Option<String>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+/// Directory user themes are loaded from
+pub fn themes_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("goofy").join("themes"))
+}
+
+/// Check if a path looks like a theme file
+pub fn is_theme_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some("toml" | "json"))
+}
+
+/// Theme files in a directory, by name
+pub fn theme_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_theme_file(path))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Read the theme in a file, taking unset colors from `base`
+fn parse_theme(path: &Path, base: impl Fn(&str) -> Option<Theme>) -> Result<Theme> {
+    let text = std::fs::read_to_string(path)?;
+    let file: ThemeFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&text)?,
+        _ => toml::from_str(&text)?,
+    };
+
+    let missing: Vec<&str> = REQUIRED_COLORS
+        .iter()
+        .copied()
+        .filter(|name| !file.colors.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        bail!("Missing colors: {}", missing.join(", "));
+    }
+    let mut colors = BTreeMap::new();
+    for (name, value) in &file.colors {
+        let color = convert::hex_to_color(value).map_err(|e| anyhow!("Color {} = '{}': {}", name, value, e))?;
+        colors.insert(name.as_str(), color);
+    }
+
+    let dark = file
+        .dark
+        .unwrap_or_else(|| accessibility::luminance(colors["bg_base"]) < 0.5);
+    let extends = file
+        .extends
+        .as_deref()
+        .unwrap_or(if dark { "goofy_dark" } else { "goofy_light" });
+    let mut theme = base(extends).ok_or_else(|| anyhow!("Theme '{}' to extend not found", extends))?;
+
+    theme.name = match file.name {
+        Some(name) => name,
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("Theme file has no name"))?,
+    };
+    theme.is_dark = dark;
+    for (name, color) in colors {
+        let field = color_field(&mut theme, name).ok_or_else(|| anyhow!("Unknown color '{}'", name))?;
+        *field = color;
+    }
+    theme.styles = None;
+    Ok(theme)
+}
+
+/// The color of a theme with the given field name
+fn color_field<'a>(theme: &'a mut Theme, name: &str) -> Option<&'a mut Color> {
+    Some(match name {
+        "primary" => &mut theme.primary,
+        "secondary" => &mut theme.secondary,
+        "tertiary" => &mut theme.tertiary,
+        "accent" => &mut theme.accent,
+        "bg_base" => &mut theme.bg_base,
+        "bg_base_lighter" => &mut theme.bg_base_lighter,
+        "bg_subtle" => &mut theme.bg_subtle,
+        "bg_overlay" => &mut theme.bg_overlay,
+        "fg_base" => &mut theme.fg_base,
+        "fg_muted" => &mut theme.fg_muted,
+        "fg_half_muted" => &mut theme.fg_half_muted,
+        "fg_subtle" => &mut theme.fg_subtle,
+        "fg_selected" => &mut theme.fg_selected,
+        "border" => &mut theme.border,
+        "border_focus" => &mut theme.border_focus,
+        "success" => &mut theme.success,
+        "error" => &mut theme.error,
+        "warning" => &mut theme.warning,
+        "info" => &mut theme.info,
+        "white" => &mut theme.white,
+        "blue_light" => &mut theme.blue_light,
+        "blue" => &mut theme.blue,
+        "yellow" => &mut theme.yellow,
+        "green" => &mut theme.green,
+        "green_dark" => &mut theme.green_dark,
+        "green_light" => &mut theme.green_light,
+        "red" => &mut theme.red,
+        "red_dark" => &mut theme.red_dark,
+        "red_light" => &mut theme.red_light,
+        "cherry" => &mut theme.cherry,
+        _ => return None,
+    })
+}
+
+impl ThemeManager {
+    /// Register the theme in a file, replacing an earlier version of it
+    ///
+    /// Returns the theme's name.
+    pub fn load_theme_file(&mut self, path: &Path) -> Result<String> {
+        let theme = parse_theme(path, |name| self.get_theme(name).cloned())
+            .with_context(|| format!("Invalid theme file {}", path.display()))?;
+        let name = theme.name.clone();
+        self.register_theme(theme);
+        Ok(name)
+    }
+
+    /// Register every theme in a directory, returning the problems with files
+    /// that could not be loaded
+    pub fn load_user_themes(&mut self, dir: &Path) -> Vec<String> {
+        theme_files(dir)
+            .iter()
+            .filter_map(|path| self.load_theme_file(path).err())
+            .map(|e| format!("{:#}", e))
+            .collect()
+    }
+}
+
+/// Watches the themes directory and reports changed theme files as events
+///
+/// Watching stops when the watcher is dropped.
+pub struct ThemeWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ThemeWatcher {
+    /// Watch `dir`, sending a [`THEME_CHANGED_EVENT`] to `sender` for each
+    /// theme file written there
+    pub fn new(dir: &Path, sender: mpsc::UnboundedSender<Event>) -> Result<Self> {
+        let mut watcher = recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in event.paths.iter().filter(|path| is_theme_file(path)) {
+                        let _ = sender.send(Event::Custom(
+                            THEME_CHANGED_EVENT.to_string(),
+                            serde_json::Value::String(path.to_string_lossy().into_owned()),
+                        ));
+                    }
+                }
+            }
+            Err(e) => debug!("Theme watcher error: {}", e),
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: &str = r##"
+[colors]
+primary = "#c678dd"
+secondary = "#e5c07b"
+accent = "#d19a66"
+bg_base = "#1e2127"
+fg_base = "#abb2bf"
+fg_muted = "#7f848e"
+border = "#3e4451"
+success = "#98c379"
+error = "#e06c75"
+warning = "#d19a66"
+info = "#61afef"
+"##;
+
+    #[test]
+    fn test_theme_files_are_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("dusk.toml"), COLORS).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a theme").unwrap();
+
+        let mut manager = ThemeManager::new();
+        assert!(manager.load_user_themes(dir.path()).is_empty());
+        let theme = manager.get_theme("dusk").unwrap();
+        assert!(theme.is_dark);
+        assert_eq!(theme.primary, Color::Rgb(0xc6, 0x78, 0xdd));
+        // Unset colors come from the dark preset
+        assert_eq!(theme.cherry, manager.get_theme("goofy_dark").unwrap().cherry);
+
+        // A saved change replaces the theme
+        let light = COLORS.replace("#1e2127", "#fafafa").replace("[colors]", "name = \"dusk\"\n[colors]");
+        std::fs::write(dir.path().join("dusk.toml"), light).unwrap();
+        assert_eq!(manager.load_theme_file(&dir.path().join("dusk.toml")).unwrap(), "dusk");
+        assert!(!manager.get_theme("dusk").unwrap().is_dark);
+    }
+
+    #[test]
+    fn test_invalid_theme_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("partial.json"), r##"{ "colors": { "primary": "#ffffff" } }"##).unwrap();
+        std::fs::write(dir.path().join("typo.toml"), format!("{}prmary = \"#000000\"\n", COLORS)).unwrap();
+        std::fs::write(dir.path().join("bad.toml"), COLORS.replace("#c678dd", "purple")).unwrap();
+
+        let mut manager = ThemeManager::new();
+        let problems = manager.load_user_themes(dir.path());
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("primary = 'purple'"), "{}", problems[0]);
+        assert!(problems[1].contains("Missing colors: secondary"), "{}", problems[1]);
+        assert!(problems[2].contains("Unknown color 'prmary'"), "{}", problems[2]);
+        assert!(manager.get_theme("partial").is_none());
+    }
+}
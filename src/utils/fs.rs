@@ -174,7 +174,7 @@ fn should_ignore_entry(
 }
 
 /// Simple glob pattern matching
-fn matches_glob_pattern(text: &str, pattern: &str) -> bool {
+pub fn matches_glob_pattern(text: &str, pattern: &str) -> bool {
     if pattern == "*" {
         return true;
     }
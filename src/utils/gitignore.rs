@@ -0,0 +1,258 @@
+//! Matching paths against `.gitignore` files
+//!
+//! [`GitIgnore`] reads the ignore files of a repository the way git does:
+//! `.git/info/exclude`, then the `.gitignore` of every directory from the
+//! repository root down, later rules overriding earlier ones. Patterns follow
+//! the gitignore syntax: `*`, `?`, `[...]` and `**`, a leading `/` or a `/`
+//! in the middle anchoring the pattern to its file's directory, a trailing `/`
+//! matching directories only, and `!` re-including what an earlier rule
+//! ignored. Outside a repository, the `.gitignore` files from the directory
+//! walked down apply.
+//!
+//! Walkers add the `.gitignore` of each directory they enter with
+//! [`GitIgnore::enter`] and skip ignored directories as a whole, since git
+//! doesn't look inside them either. [`glob_regex`] translates the same
+//! wildcards for tools matching paths against a pattern.
+
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// One line of an ignore file
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Directory of the file the rule is from
+    base: PathBuf,
+    regex: Regex,
+    /// Matched against the path below `base` instead of the name only
+    anchored: bool,
+    /// Matches directories only
+    dir_only: bool,
+    /// Re-includes what earlier rules ignored
+    negated: bool,
+}
+
+/// The ignore rules applying in a directory
+#[derive(Debug, Clone, Default)]
+pub struct GitIgnore {
+    rules: Vec<Rule>,
+}
+
+impl GitIgnore {
+    /// Rules applying in `dir`: those of its repository from the root down to
+    /// it, or only its own outside a repository
+    pub fn for_dir(dir: &Path) -> Self {
+        let mut ignore = Self::default();
+        let Some(root) = dir.ancestors().find(|ancestor| ancestor.join(".git").exists()) else {
+            ignore.add_file(dir, &dir.join(".gitignore"));
+            return ignore;
+        };
+        ignore.add_file(root, &root.join(".git").join("info").join("exclude"));
+        let below: Vec<&Path> = dir.ancestors().take_while(|ancestor| *ancestor != root).collect();
+        ignore.add_file(root, &root.join(".gitignore"));
+        for ancestor in below.into_iter().rev() {
+            ignore.add_file(ancestor, &ancestor.join(".gitignore"));
+        }
+        ignore
+    }
+
+    /// Rules applying in a subdirectory, adding its `.gitignore`
+    pub fn enter(&self, dir: &Path) -> Self {
+        let mut ignore = self.clone();
+        ignore.add_file(dir, &dir.join(".gitignore"));
+        ignore
+    }
+
+    /// Add the rules of an ignore file, if there is one
+    pub fn add_file(&mut self, base: &Path, file: &Path) {
+        if let Ok(content) = fs::read_to_string(file) {
+            self.add_patterns(base, &content);
+        }
+    }
+
+    /// Add rules relative to `base`, one pattern per line
+    pub fn add_patterns(&mut self, base: &Path, patterns: &str) {
+        for line in patterns.lines() {
+            match parse_rule(base, line) {
+                Some(Ok(rule)) => self.rules.push(rule),
+                Some(Err(e)) => debug!("Ignoring pattern {:?} in {}: {}", line, base.display(), e),
+                None => {}
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether a path is ignored, itself or through a directory above it
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let mut ancestors = path.ancestors().skip(1).filter(|ancestor| self.applies_to(ancestor));
+        ancestors.any(|ancestor| self.matches(ancestor, true)) || self.matches(path, is_dir)
+    }
+
+    /// Whether the last rule matching the path itself ignores it
+    pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if relative.is_empty() {
+                continue;
+            }
+            let subject = if rule.anchored {
+                relative.as_str()
+            } else {
+                relative.rsplit('/').next().unwrap_or_default()
+            };
+            if rule.regex.is_match(subject) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+
+    /// Whether any rule is about paths inside `dir`
+    fn applies_to(&self, dir: &Path) -> bool {
+        self.rules.iter().any(|rule| dir.starts_with(&rule.base) && dir != rule.base)
+    }
+}
+
+/// The rule of an ignore file line; `None` for blank lines and comments
+fn parse_rule(base: &Path, line: &str) -> Option<Result<Rule, regex::Error>> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let line = match line.strip_suffix("\\ ") {
+        Some(line) => format!("{} ", line.trim_end()),
+        None => line.trim_end().to_string(),
+    };
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        // `\#` and `\!` start patterns with those characters
+        None => (false, line.strip_prefix('\\').filter(|rest| rest.starts_with(['#', '!'])).unwrap_or(&line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(glob_regex(pattern).map(|regex| Rule {
+        base: base.to_path_buf(),
+        regex,
+        anchored,
+        dir_only,
+        negated,
+    }))
+}
+
+/// Regex matching a whole path against a gitignore-style glob
+///
+/// `*` and `?` stop at `/`, `**/` spans any number of directories, and
+/// `[...]` classes take `!` for negation.
+pub fn glob_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let mut regex = String::from("^");
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_start = i == 0 || chars[i - 1] == '/';
+                match chars.get(i + 2) {
+                    Some('/') if at_start => {
+                        regex.push_str("(?:.*/)?");
+                        i += 3;
+                        continue;
+                    }
+                    None if at_start => regex.push_str(".*"),
+                    _ => regex.push_str("[^/]*"),
+                }
+                i += 2;
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match chars[i + 1..].iter().position(|c| *c == ']') {
+                Some(end) if end > 0 => {
+                    let class: String = chars[i + 1..i + 1 + end].iter().collect();
+                    let class = class.strip_prefix('!').map_or(class.clone(), |rest| format!("^{}", rest));
+                    regex.push('[');
+                    regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                    regex.push(']');
+                    i += end + 2;
+                    continue;
+                }
+                _ => regex.push_str("\\["),
+            },
+            '\\' if i + 1 < chars.len() => {
+                regex.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 2;
+                continue;
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    regex.push('$');
+    Regex::new(&regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_globs_follow_gitignore_wildcards() {
+        let matches = |pattern: &str, path: &str| glob_regex(pattern).unwrap().is_match(path);
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("**/*.rs", "src/tui/main.rs"));
+        assert!(matches("**/*.rs", "main.rs"));
+        assert!(matches("src/**", "src/a/b.txt"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("file?.[ch]", "file1.c"));
+        assert!(!matches("file?.[!ch]", "file1.c"));
+        assert!(matches("\\*literal", "*literal"));
+    }
+
+    #[test]
+    fn test_nested_rules_negation_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::create_dir_all(root.join("web/dist")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "scratch.txt\n").unwrap();
+        fs::write(root.join(".gitignore"), "# build output\n/target\n*.log\n!keep.log\nbuild/\n").unwrap();
+        fs::write(root.join("web/.gitignore"), "dist\n").unwrap();
+
+        let ignore = GitIgnore::for_dir(&root.join("web"));
+        assert!(ignore.is_ignored(&root.join("target"), true));
+        assert!(!ignore.is_ignored(&root.join("web/target"), true));
+        assert!(ignore.is_ignored(&root.join("web/debug.log"), false));
+        assert!(!ignore.is_ignored(&root.join("web/keep.log"), false));
+        assert!(ignore.is_ignored(&root.join("web/dist/app.js"), false));
+        assert!(ignore.is_ignored(&root.join("scratch.txt"), false));
+        assert!(ignore.is_ignored(&root.join("web/build"), true));
+        assert!(!ignore.is_ignored(&root.join("web/build"), false));
+
+        // Rules of a subdirectory only apply below it
+        let top = GitIgnore::for_dir(root);
+        assert!(!top.is_ignored(&root.join("dist"), true));
+        assert!(top.enter(&root.join("web")).is_ignored(&root.join("web/dist"), true));
+    }
+}
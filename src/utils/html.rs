@@ -0,0 +1,235 @@
+//! Readable content of web pages
+//!
+//! Pages are mostly menus, scripts and footers around what a reader came
+//! for. [`readable`] keeps the main content: the `<main>` or `<article>`
+//! element when the page has one, and the body without its header and footer
+//! otherwise. Scripts, styles, navigation, forms and hidden elements are
+//! dropped and links are made absolute, and what is left converts to
+//! Markdown or plain text.
+
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements dropped with everything in them
+const DROPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object", "embed", "nav", "aside", "form",
+    "button", "input", "select", "textarea", "dialog", "menu", "link", "meta", "head",
+];
+
+/// Elements dropped when the body is kept as a whole
+const PAGE_CHROME: &[&str] = &["header", "footer"];
+
+/// Elements without content or closing tag
+const VOID: &[&str] = &["br", "hr", "img", "wbr"];
+
+/// Elements separated from what is around them by a blank line in text
+const PARAGRAPHS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "table", "ul", "ol", "dl", "figure", "hr",
+];
+
+/// Elements starting a line of their own in text
+const LINES: &[&str] = &[
+    "div", "section", "article", "main", "header", "footer", "li", "tr", "br", "dt", "dd", "figcaption", "details",
+    "summary",
+];
+
+/// Attributes kept; the others only matter to browsers
+const KEPT_ATTRIBUTES: &[&str] = &["href", "src", "alt", "title", "colspan", "rowspan"];
+
+/// The main content of a page
+#[derive(Debug, Clone, Default)]
+pub struct ReadablePage {
+    pub title: Option<String>,
+    html: String,
+    text: String,
+}
+
+impl ReadablePage {
+    /// The content as HTML, without what was dropped
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    /// The content as Markdown
+    pub fn markdown(&self) -> String {
+        tidy(&html2md::parse_html(&self.html))
+    }
+
+    /// The content as plain text, a paragraph per block
+    pub fn text(&self) -> String {
+        tidy(&self.text)
+    }
+
+    fn add_children(&mut self, element: ElementRef, base: Option<&Url>, drop_chrome: bool, preformatted: bool) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => {
+                    let text: &str = &text.text;
+                    self.html.push_str(&escape(text, false));
+                    if preformatted {
+                        self.text.push_str(text);
+                    } else {
+                        push_collapsed(&mut self.text, text);
+                    }
+                }
+                Node::Element(value) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    let name = value.name();
+                    let hidden = value.attr("hidden").is_some()
+                        || value.attr("aria-hidden") == Some("true")
+                        || matches!(value.attr("role"), Some("navigation" | "banner" | "contentinfo" | "search"));
+                    if hidden || DROPPED.contains(&name) || (drop_chrome && PAGE_CHROME.contains(&name)) {
+                        continue;
+                    }
+                    let separator = if PARAGRAPHS.contains(&name) {
+                        "\n\n"
+                    } else if LINES.contains(&name) {
+                        "\n"
+                    } else {
+                        ""
+                    };
+                    push_break(&mut self.text, separator);
+                    self.html.push('<');
+                    self.html.push_str(name);
+                    for (attribute, value) in value.attrs() {
+                        if !KEPT_ATTRIBUTES.contains(&attribute) {
+                            continue;
+                        }
+                        let value = match (attribute, base) {
+                            ("href" | "src", Some(base)) => base.join(value).map_or(value.to_string(), String::from),
+                            _ => value.to_string(),
+                        };
+                        self.html.push_str(&format!(" {}=\"{}\"", attribute, escape(&value, true)));
+                    }
+                    self.html.push('>');
+                    if VOID.contains(&name) {
+                        continue;
+                    }
+                    self.add_children(child, base, drop_chrome, preformatted || name == "pre");
+                    self.html.push_str(&format!("</{}>", name));
+                    push_break(&mut self.text, separator);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The main content of an HTML page, with links resolved against `base`
+pub fn readable(html: &str, base: Option<&Url>) -> ReadablePage {
+    let document = Html::parse_document(html);
+    let first = |selector: &str| {
+        let selector = Selector::parse(selector).ok()?;
+        document.select(&selector).next()
+    };
+    let title = first("title")
+        .map(|title| title.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty());
+    let main = ["main", "article", "[role=main]"].into_iter().find_map(&first);
+    let (root, drop_chrome) = match main {
+        Some(main) => (main, false),
+        None => (first("body").unwrap_or_else(|| document.root_element()), true),
+    };
+    let mut page = ReadablePage {
+        title,
+        ..ReadablePage::default()
+    };
+    page.add_children(root, base, drop_chrome, false);
+    page
+}
+
+/// End the line, or the paragraph, unless it already is
+fn push_break(out: &mut String, separator: &str) {
+    let ended = out.len() - out.trim_end_matches('\n').len();
+    for _ in ended..separator.len() {
+        out.push('\n');
+    }
+}
+
+/// Text with its runs of whitespace made single spaces, as browsers show it
+fn push_collapsed(out: &mut String, text: &str) {
+    for (index, word) in text.split_whitespace().enumerate() {
+        let after_space = out.is_empty() || out.ends_with([' ', '\n']);
+        if (index > 0 || text.starts_with(char::is_whitespace)) && !after_space {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+        out.push(' ');
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let escaped = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    if attribute {
+        escaped.replace('"', "&quot;")
+    } else {
+        escaped
+    }
+}
+
+/// Lines without trailing spaces, and no more than one blank line in a row
+fn tidy(text: &str) -> String {
+    let mut tidied = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank = !tidied.is_empty();
+            continue;
+        }
+        if !tidied.is_empty() {
+            tidied.push_str(if blank { "\n\n" } else { "\n" });
+        }
+        tidied.push_str(line);
+        blank = false;
+    }
+    tidied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!doctype html>
+<html><head><title> Release
+  notes </title><style>body { color: red }</style></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/docs">Docs</a></nav>
+  <header>Site banner</header>
+  <main>
+    <h1>Version 2.0</h1>
+    <p>Adds <strong>streaming</strong> and
+       a <a href="guide/streaming.html">guide</a>.</p>
+    <script>track();</script>
+    <div hidden>Cookie settings</div>
+    <ul><li>Faster</li><li>Smaller</li></ul>
+  </main>
+  <footer>Copyright</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_main_content_is_kept_and_chrome_dropped() {
+        let base = Url::parse("https://example.com/releases/").unwrap();
+        let page = readable(PAGE, Some(&base));
+        assert_eq!(page.title.as_deref(), Some("Release notes"));
+
+        let text = page.text();
+        assert_eq!(text, "Version 2.0\n\nAdds streaming and a guide.\n\nFaster\nSmaller");
+
+        let markdown = page.markdown();
+        assert!(markdown.contains("**streaming**"));
+        assert!(markdown.contains("(https://example.com/releases/guide/streaming.html)"));
+        for dropped in ["Home", "banner", "track()", "Cookie", "Copyright", "color: red"] {
+            assert!(!markdown.contains(dropped), "{} kept", dropped);
+        }
+    }
+
+    #[test]
+    fn test_pages_without_main_drop_their_header_and_footer() {
+        let page = readable("<body><header>Menu</header><p>Body text</p><footer>Legal</footer></body>", None);
+        assert_eq!(page.text(), "Body text");
+    }
+}
@@ -0,0 +1,359 @@
+//! Where diagnostics go
+//!
+//! Standard output carries only what a command produces, such as the answer
+//! of `goofy run` or the JSON of `--json` modes, so scripts can consume it
+//! byte for byte. Tracing output, warnings and progress go to standard error,
+//! or to the file given with `--log-file`.
+//!
+//! Once the configuration is loaded, events are also written as JSON lines to
+//! `goofy.jsonl` in the `logs` directory of the data directory, one object per
+//! event with its time, level, target, message and fields, the fields of the
+//! spans it happened in included. The file is rotated to `goofy.1.jsonl`,
+//! `goofy.2.jsonl` and so on when it grows past `logging.max_file_bytes`, and
+//! `goofy logs` reads them back.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::LoggingConfig;
+
+/// Name of the log file being written; rotated files are numbered before
+/// the extension
+pub const LOG_FILE: &str = "goofy.jsonl";
+
+/// Directory of the log files in the data directory
+pub fn log_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("logs")
+}
+
+/// The log files in a directory, oldest first and the current one last
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut rotated: Vec<(usize, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix("goofy.")?.strip_suffix(".jsonl")?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    rotated.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+    let mut files: Vec<PathBuf> = rotated.into_iter().map(|(_, path)| path).collect();
+    let current = dir.join(LOG_FILE);
+    if current.exists() {
+        files.push(current);
+    }
+    files
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("goofy.{}.jsonl", index))
+}
+
+/// A log file that moves aside once it grows past a size, keeping a number
+/// of the files moved aside
+pub struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(LOG_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let current = self.dir.join(LOG_FILE);
+        if self.max_files == 0 {
+            fs::remove_file(&current)?;
+        } else {
+            let oldest = rotated_path(&self.dir, self.max_files);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.dir, index);
+                if from.exists() {
+                    fs::rename(from, rotated_path(&self.dir, index + 1))?;
+                }
+            }
+            fs::rename(&current, rotated_path(&self.dir, 1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(current)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Where JSON lines go and from which level on
+struct JsonSink {
+    out: Box<dyn Write + Send>,
+    level: LevelFilter,
+}
+
+type SharedSink = Arc<Mutex<Option<JsonSink>>>;
+
+/// The sink of the installed subscriber, empty until [`log_to_files`]
+fn files() -> &'static SharedSink {
+    static FILES: OnceLock<SharedSink> = OnceLock::new();
+    FILES.get_or_init(SharedSink::default)
+}
+
+/// Start writing the JSON log files configured in `config`
+pub fn log_to_files(data_dir: &Path, config: &LoggingConfig) -> Result<()> {
+    if !config.files {
+        return Ok(());
+    }
+    let level = LevelFilter::from_str(&config.level)
+        .map_err(|_| anyhow::anyhow!("Invalid logging.level {:?}", config.level))?;
+    let file = RotatingFile::open(&log_dir(data_dir), config.max_file_bytes, config.max_files)?;
+    if let Ok(mut sink) = files().lock() {
+        *sink = Some(JsonSink {
+            out: Box::new(file),
+            level,
+        });
+    }
+    Ok(())
+}
+
+/// Field values of an event or span, as JSON
+#[derive(Debug, Clone, Default)]
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+}
+
+/// Writes each event as a line of JSON
+struct JsonLayer {
+    sink: SharedSink,
+}
+
+impl<S> Layer<S> for JsonLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: LayerContext<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        let Ok(mut sink) = self.sink.lock() else {
+            return;
+        };
+        let Some(sink) = sink.as_mut().filter(|sink| *event.metadata().level() <= sink.level) else {
+            return;
+        };
+
+        // Fields of inner spans win over those of outer ones, and the event's
+        // over both
+        let mut fields = Fields::default();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<Fields>() {
+                    fields.0.extend(span_fields.0.clone());
+                }
+            }
+        }
+        event.record(&mut fields);
+        let message = fields.0.remove("message").unwrap_or_else(|| Value::from(""));
+
+        let mut record = Map::new();
+        record.insert(
+            "time".to_string(),
+            Value::from(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        );
+        record.insert("level".to_string(), Value::from(event.metadata().level().as_str()));
+        record.insert("target".to_string(), Value::from(event.metadata().target()));
+        record.insert("msg".to_string(), message);
+        if !fields.0.is_empty() {
+            record.insert("fields".to_string(), Value::Object(fields.0));
+        }
+        if let Ok(mut line) = serde_json::to_vec(&record) {
+            line.push(b'\n');
+            // Logging failures are nowhere to be logged
+            let _ = sink.out.write_all(&line);
+        }
+    }
+}
+
+/// Writer for log output: standard error, or `log_file` appended to
+pub fn writer(log_file: Option<&Path>) -> Result<BoxMakeWriter> {
+    let Some(path) = log_file else {
+        return Ok(BoxMakeWriter::new(std::io::stderr));
+    };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    Ok(BoxMakeWriter::new(Mutex::new(file)))
+}
+
+/// Install the global tracing subscriber, its readable output filtered by
+/// `RUST_LOG`
+///
+/// The JSON log files, filtered by their own level, start with
+/// [`log_to_files`].
+pub fn init(log_file: Option<&Path>) -> Result<()> {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| "goofy=info".into());
+    tracing_subscriber::registry()
+        .with(JsonLayer { sink: files().clone() })
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(writer(log_file)?)
+                .with_ansi(log_file.is_none())
+                .with_filter(env_filter),
+        )
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("Failed to initialize tracing: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logs_are_appended_to_the_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/goofy.log");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "earlier\n").unwrap();
+
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(writer(Some(&path)).unwrap())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || tracing::warn!("disk almost full"));
+
+        let logged = std::fs::read_to_string(&path).unwrap();
+        assert!(logged.starts_with("earlier\n"));
+        assert!(logged.contains("WARN"));
+        assert!(logged.contains("disk almost full"));
+    }
+
+    #[test]
+    fn test_events_are_written_as_json_lines_with_span_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = SharedSink::default();
+        *sink.lock().unwrap() = Some(JsonSink {
+            out: Box::new(RotatingFile::open(dir.path(), 1 << 20, 2).unwrap()),
+            level: LevelFilter::INFO,
+        });
+        let subscriber = tracing_subscriber::registry().with(JsonLayer { sink });
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("turn", session_id = "s1", attempt = 1);
+            let _entered = span.enter();
+            tracing::warn!(tool = "bash", elapsed_ms = 12u64, "Tool {} timed out", "bash");
+            tracing::debug!("not written");
+        });
+
+        let content = fs::read_to_string(dir.path().join(LOG_FILE)).unwrap();
+        let lines: Vec<Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["msg"], "Tool bash timed out");
+        assert_eq!(
+            lines[0]["fields"],
+            serde_json::json!({"session_id": "s1", "attempt": 1, "tool": "bash", "elapsed_ms": 12})
+        );
+        assert!(lines[0]["time"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_log_files_rotate_past_their_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        let files = log_files(dir.path());
+        let names: Vec<_> = files.iter().map(|path| path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["goofy.2.jsonl", "goofy.1.jsonl", "goofy.jsonl"]);
+        let contents: Vec<String> = files.iter().map(|path| fs::read_to_string(path).unwrap()).collect();
+        assert_eq!(contents, ["second\n", "third\n", "fourth\n"]);
+    }
+}
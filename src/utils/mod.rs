@@ -1,6 +1,11 @@
 // Utility functions and helpers
 
 pub mod fs;
+pub mod gitignore;
+pub mod html;
+pub mod logging;
+pub mod stats;
 pub mod text;
+pub mod width;
 
 // Common utility functions can go here
\ No newline at end of file
@@ -0,0 +1,388 @@
+// Workspace statistics
+//
+// Lines of code per language, the largest files, how much of the code is
+// tests and which files changed most in recent git history. Lines are
+// classified the way tokei does it, but only line comments are recognised;
+// block comments count as code.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::fs::{walk_directory, WalkConfig};
+
+/// Files larger than this are not read
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+/// A language recognised by file extension
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language { name: "Rust", extensions: &["rs"], line_comments: &["//"] },
+    Language { name: "Go", extensions: &["go"], line_comments: &["//"] },
+    Language { name: "Python", extensions: &["py", "pyi"], line_comments: &["#"] },
+    Language { name: "JavaScript", extensions: &["js", "jsx", "mjs", "cjs"], line_comments: &["//"] },
+    Language { name: "TypeScript", extensions: &["ts", "tsx", "mts", "cts"], line_comments: &["//"] },
+    Language { name: "C", extensions: &["c", "h"], line_comments: &["//"] },
+    Language { name: "C++", extensions: &["cpp", "cc", "cxx", "hpp", "hxx"], line_comments: &["//"] },
+    Language { name: "Java", extensions: &["java"], line_comments: &["//"] },
+    Language { name: "Kotlin", extensions: &["kt", "kts"], line_comments: &["//"] },
+    Language { name: "Swift", extensions: &["swift"], line_comments: &["//"] },
+    Language { name: "C#", extensions: &["cs"], line_comments: &["//"] },
+    Language { name: "Ruby", extensions: &["rb"], line_comments: &["#"] },
+    Language { name: "PHP", extensions: &["php"], line_comments: &["//", "#"] },
+    Language { name: "Lua", extensions: &["lua"], line_comments: &["--"] },
+    Language { name: "Shell", extensions: &["sh", "bash", "zsh", "fish"], line_comments: &["#"] },
+    Language { name: "SQL", extensions: &["sql"], line_comments: &["--"] },
+    Language { name: "HTML", extensions: &["html", "htm"], line_comments: &[] },
+    Language { name: "CSS", extensions: &["css", "scss", "sass", "less"], line_comments: &[] },
+    Language { name: "Markdown", extensions: &["md", "markdown"], line_comments: &[] },
+    Language { name: "JSON", extensions: &["json"], line_comments: &[] },
+    Language { name: "YAML", extensions: &["yaml", "yml"], line_comments: &["#"] },
+    Language { name: "TOML", extensions: &["toml"], line_comments: &["#"] },
+];
+
+fn language_for(path: &Path) -> Option<&'static Language> {
+    let extension = super::fs::get_file_extension(path)?;
+    LANGUAGES.iter().find(|language| language.extensions.contains(&extension.as_str()))
+}
+
+/// Whether a path looks like test code
+///
+/// Files under a `tests`, `test`, `__tests__` or `spec` directory count, as do
+/// files named like `test_x.py`, `x_test.go`, `x.test.ts` or `x.spec.js`.
+/// Tests inline in other files, like Rust's `#[cfg(test)]` modules, do not.
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.parent().map_or(false, |parent| {
+        parent.components().any(|component| {
+            matches!(component.as_os_str().to_str(), Some("tests" | "test" | "__tests__" | "spec"))
+        })
+    });
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with(".test")
+        || stem.ends_with("_spec")
+        || stem.ends_with(".spec")
+}
+
+/// Line counts of one language
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LanguageStats {
+    pub name: String,
+    pub files: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+/// One file and its size
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStats {
+    pub path: String,
+    pub language: String,
+    pub lines: usize,
+}
+
+/// Lines changed in one file over the churn window
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileChurn {
+    pub path: String,
+    pub commits: usize,
+    pub additions: usize,
+    pub removals: usize,
+}
+
+/// Recent git history of the workspace
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GitChurn {
+    pub days: u32,
+    pub commits: usize,
+    pub files: Vec<FileChurn>,
+}
+
+/// What to collect
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// Number of largest and most churned files to report
+    pub top: usize,
+    /// Days of git history to look at for churn
+    pub churn_days: u32,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self { top: 10, churn_days: 30 }
+    }
+}
+
+/// Statistics of a workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceStats {
+    pub root: PathBuf,
+    /// Languages, most code first
+    pub languages: Vec<LanguageStats>,
+    pub largest_files: Vec<FileStats>,
+    /// Code lines in test files
+    pub test_code: usize,
+    /// Code lines in all other files
+    pub other_code: usize,
+    /// `None` if the workspace is not a git repository
+    pub churn: Option<GitChurn>,
+}
+
+impl WorkspaceStats {
+    /// Collect statistics of the directory tree at `root`
+    pub async fn collect(root: &Path, options: &StatsOptions) -> Result<Self> {
+        let walk_root = root.to_path_buf();
+        let top = options.top;
+        let mut stats = tokio::task::spawn_blocking(move || Self::count_lines(&walk_root, top)).await??;
+        stats.churn = git_churn(root, options.churn_days, options.top).await;
+        Ok(stats)
+    }
+
+    fn count_lines(root: &Path, top: usize) -> Result<Self> {
+        let config = WalkConfig { max_depth: None, ..WalkConfig::default() };
+        let mut languages: HashMap<&'static str, LanguageStats> = HashMap::new();
+        let mut files = Vec::new();
+        let mut test_code = 0;
+        let mut other_code = 0;
+
+        for file in walk_directory(root, Some(config))? {
+            if file.is_dir || file.size > MAX_FILE_SIZE {
+                continue;
+            }
+            let Some(language) = language_for(&file.path) else {
+                continue;
+            };
+            // Skip files that are not text
+            let Ok(content) = std::fs::read_to_string(&file.path) else {
+                continue;
+            };
+
+            let entry = languages.entry(language.name).or_insert_with(|| LanguageStats {
+                name: language.name.to_string(),
+                ..LanguageStats::default()
+            });
+            entry.files += 1;
+            let mut code = 0;
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() {
+                    entry.blanks += 1;
+                } else if language.line_comments.iter().any(|prefix| line.starts_with(prefix)) {
+                    entry.comments += 1;
+                } else {
+                    code += 1;
+                }
+            }
+            entry.code += code;
+
+            if is_test_path(&file.relative_path) {
+                test_code += code;
+            } else {
+                other_code += code;
+            }
+            files.push(FileStats {
+                path: file.relative_path.display().to_string(),
+                language: language.name.to_string(),
+                lines: content.lines().count(),
+            });
+        }
+
+        let mut languages: Vec<LanguageStats> = languages.into_values().collect();
+        languages.sort_by(|a, b| b.code.cmp(&a.code).then_with(|| a.name.cmp(&b.name)));
+        files.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+        files.truncate(top);
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            languages,
+            largest_files: files,
+            test_code,
+            other_code,
+            churn: None,
+        })
+    }
+
+    /// Code lines of tests per code line of everything else
+    pub fn test_ratio(&self) -> Option<f64> {
+        (self.other_code > 0).then(|| self.test_code as f64 / self.other_code as f64)
+    }
+
+    /// Plain-text tables of all statistics
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Workspace: {}", self.root.display());
+
+        let _ = writeln!(out, "\nLanguages");
+        let _ = writeln!(out, "  {:<12} {:>7} {:>9} {:>9} {:>9}", "Language", "Files", "Code", "Comments", "Blanks");
+        for language in &self.languages {
+            let _ = writeln!(
+                out,
+                "  {:<12} {:>7} {:>9} {:>9} {:>9}",
+                language.name, language.files, language.code, language.comments, language.blanks
+            );
+        }
+        let _ = writeln!(
+            out,
+            "  {:<12} {:>7} {:>9} {:>9} {:>9}",
+            "Total",
+            self.languages.iter().map(|l| l.files).sum::<usize>(),
+            self.languages.iter().map(|l| l.code).sum::<usize>(),
+            self.languages.iter().map(|l| l.comments).sum::<usize>(),
+            self.languages.iter().map(|l| l.blanks).sum::<usize>(),
+        );
+
+        let _ = writeln!(out, "\nLargest files");
+        for file in &self.largest_files {
+            let _ = writeln!(out, "  {:>7}  {}", file.lines, file.path);
+        }
+
+        let _ = writeln!(out, "\nTests");
+        match self.test_ratio() {
+            Some(ratio) => {
+                let _ = writeln!(
+                    out,
+                    "  {} test code lines for {} other code lines (ratio {:.2})",
+                    self.test_code, self.other_code, ratio
+                );
+            }
+            None => {
+                let _ = writeln!(out, "  No code outside of tests");
+            }
+        }
+
+        match &self.churn {
+            Some(churn) => {
+                let _ = writeln!(out, "\nChurn (last {} days, {} commits)", churn.days, churn.commits);
+                for file in &churn.files {
+                    let _ = writeln!(
+                        out,
+                        "  {:>6} {:>6} {:>4} commits  {}",
+                        format!("+{}", file.additions),
+                        format!("-{}", file.removals),
+                        file.commits,
+                        file.path
+                    );
+                }
+            }
+            None => {
+                let _ = writeln!(out, "\nChurn\n  Not a git repository");
+            }
+        }
+
+        out
+    }
+}
+
+/// Files changed most in the last `days` days, by lines added and removed
+async fn git_churn(root: &Path, days: u32, top: usize) -> Option<GitChurn> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "--numstat", "--no-renames", "--format=commit"])
+        .arg(format!("--since={}.days.ago", days))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut churn = parse_numstat(&String::from_utf8_lossy(&output.stdout), top);
+    churn.days = days;
+    Some(churn)
+}
+
+/// Parse `git log --numstat --format=commit` output
+fn parse_numstat(log: &str, top: usize) -> GitChurn {
+    let mut commits = 0;
+    let mut files: HashMap<&str, FileChurn> = HashMap::new();
+    for line in log.lines() {
+        if line == "commit" {
+            commits += 1;
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let file = files.entry(path).or_insert_with(|| FileChurn { path: path.to_string(), ..FileChurn::default() });
+        file.commits += 1;
+        // Binary files report "-" for both counts
+        file.additions += added.parse::<usize>().unwrap_or(0);
+        file.removals += removed.parse::<usize>().unwrap_or(0);
+    }
+
+    let mut files: Vec<FileChurn> = files.into_values().collect();
+    files.sort_by(|a, b| {
+        (b.additions + b.removals)
+            .cmp(&(a.additions + a.removals))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    files.truncate(top);
+    GitChurn { days: 0, commits, files }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_counts_lines_per_language() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "// entry\nfn main() {\n\n    run();\n}\n").unwrap();
+        std::fs::write(root.join("tests/cli.rs"), "#[test]\nfn runs() {}\n").unwrap();
+        std::fs::write(root.join("setup.py"), "# setup\nimport os\n").unwrap();
+        std::fs::write(root.join("logo.bin"), [0u8, 1, 2]).unwrap();
+
+        let stats = WorkspaceStats::collect(root, &StatsOptions::default()).await.unwrap();
+        assert_eq!(
+            stats.languages[0],
+            LanguageStats { name: "Rust".to_string(), files: 2, code: 5, comments: 1, blanks: 1 }
+        );
+        assert_eq!(stats.languages[1].name, "Python");
+        assert_eq!(stats.languages.len(), 2);
+        assert_eq!(stats.largest_files[0].path, format!("src{}main.rs", std::path::MAIN_SEPARATOR));
+        assert_eq!((stats.test_code, stats.other_code), (2, 4));
+        assert_eq!(stats.test_ratio(), Some(0.5));
+        assert!(stats.render().contains("Rust"));
+    }
+
+    #[test]
+    fn test_test_paths() {
+        assert!(is_test_path(Path::new("tests/cli.rs")));
+        assert!(is_test_path(Path::new("pkg/server_test.go")));
+        assert!(is_test_path(Path::new("web/app.spec.ts")));
+        assert!(is_test_path(Path::new("test_utils.py")));
+        assert!(!is_test_path(Path::new("src/testing.rs")));
+        assert!(!is_test_path(Path::new("src/contest.rs")));
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let log = "commit\n\n3\t1\tsrc/main.rs\n-\t-\tlogo.png\ncommit\n\n10\t0\tsrc/main.rs\n1\t1\tREADME.md\n";
+        let churn = parse_numstat(log, 2);
+        assert_eq!(churn.commits, 2);
+        assert_eq!(
+            churn.files,
+            vec![
+                FileChurn { path: "src/main.rs".to_string(), commits: 2, additions: 13, removals: 1 },
+                FileChurn { path: "README.md".to_string(), commits: 1, additions: 1, removals: 1 },
+            ]
+        );
+    }
+}
@@ -189,15 +189,9 @@ pub mod syntax {
 pub mod string {
     use super::*;
     
-    /// Truncate text to a specified length with ellipsis
+    /// Truncate text to a specified display width with ellipsis
     pub fn truncate(text: &str, max_length: usize) -> String {
-        if text.len() <= max_length {
-            text.to_string()
-        } else if max_length <= 3 {
-            "...".to_string()
-        } else {
-            format!("{}...", &text[..max_length - 3])
-        }
+        crate::utils::width::truncate_with(text, max_length, "...")
     }
     
     /// Word wrap text to specified width
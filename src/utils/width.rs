@@ -0,0 +1,115 @@
+// Display width utilities
+//
+// Terminal columns are not bytes or chars: CJK characters and emoji take two
+// columns, combining marks none. These helpers measure and cut text by
+// grapheme cluster, so they never split a character or leave half of a
+// wide one on screen.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Ellipsis marking text that was cut short
+pub const ELLIPSIS: &str = "…";
+
+/// Columns a grapheme cluster takes
+///
+/// Emoji sequences (ZWJ families, flags) are drawn as one wide glyph even
+/// though they are made of several wide characters.
+fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+/// Columns the text takes on a terminal
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Longest prefix of the text that fits in `max_width` columns
+fn prefix_within(text: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        width += grapheme_width(grapheme);
+        if width > max_width {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// Longest suffix of the text that fits in `max_width` columns
+fn suffix_within(text: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (index, grapheme) in text.grapheme_indices(true).rev() {
+        width += grapheme_width(grapheme);
+        if width > max_width {
+            return &text[index + grapheme.len()..];
+        }
+    }
+    text
+}
+
+/// Cut the text to `max_width` columns, ending in `ellipsis` if it was cut
+pub fn truncate_with(text: &str, max_width: usize, ellipsis: &str) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    let ellipsis_width = display_width(ellipsis);
+    if ellipsis_width > max_width {
+        return prefix_within(ellipsis, max_width).to_string();
+    }
+    format!("{}{}", prefix_within(text, max_width - ellipsis_width), ellipsis)
+}
+
+/// Cut the text to `max_width` columns, ending in an ellipsis if it was cut
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    truncate_with(text, max_width, ELLIPSIS)
+}
+
+/// Fill the text with spaces to exactly `width` columns, cutting it if wider
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let text = truncate_to_width(text, width);
+    let padding = width.saturating_sub(display_width(&text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Cut the middle out of the text to fit `max_width` columns
+///
+/// Keeps both ends, which tell paths and identifiers apart better than the
+/// start alone: `/home/user/…/src/main.rs`.
+pub fn middle_ellipsis(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    let available = max_width.saturating_sub(display_width(ELLIPSIS));
+    if available == 0 {
+        return truncate_to_width(text, max_width);
+    }
+    let tail = suffix_within(text, available / 2);
+    let head = prefix_within(text, available - display_width(tail));
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncation_never_splits_characters() {
+        assert_eq!(truncate_to_width("héllo wörld", 6), "héllo…");
+        assert_eq!(truncate_to_width("日本語のテキスト", 7), "日本語…");
+        assert_eq!(truncate_to_width("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(truncate_to_width("👨‍👩‍👧 family", 4), "👨‍👩‍👧 …");
+        assert_eq!(truncate_to_width("short", 10), "short");
+        assert_eq!(truncate_with("version", 2, "..."), "..");
+        assert_eq!(display_width("日本"), 4);
+    }
+
+    #[test]
+    fn test_padding_and_middle_ellipsis() {
+        assert_eq!(pad_to_width("日本", 5), "日本 ");
+        assert_eq!(pad_to_width("abcdef", 4), "abc…");
+        assert_eq!(middle_ellipsis("/home/user/project/src/main.rs", 17), "/home/us…/main.rs");
+        assert_eq!(display_width(&middle_ellipsis("ñandú-ñandú-ñandú", 9)), 9);
+        assert_eq!(middle_ellipsis("abc", 3), "abc");
+    }
+}